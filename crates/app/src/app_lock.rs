@@ -0,0 +1,147 @@
+//! Locking the app behind its passphrase.
+//!
+//! When a passphrase is set the app starts locked. While locked, every
+//! command except the lock commands themselves is refused with a `LOCKED`
+//! error, so the frontend can only show its unlock screen. Any other command
+//! counts as activity; after the idle timeout passes without any, the app
+//! locks again and `app:locked` is emitted.
+
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+
+use aequi_storage::app_lock::AppLockSettings;
+use tauri::ipc::Invoke;
+use tauri::{Emitter, Manager, Runtime};
+
+use crate::commands::CommandError;
+
+/// How often the idle timeout is checked.
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+pub const EVENT_LOCKED: &str = "app:locked";
+
+/// Commands that work while the app is locked. They don't count as
+/// activity, so an unlock screen polling its status doesn't keep the app
+/// open.
+const ALLOWED_WHILE_LOCKED: &[&str] = &["get_app_lock_status", "unlock_app", "lock_app"];
+
+/// Whether the app is locked, and when it was last used.
+pub struct AppLock {
+    enabled: AtomicBool,
+    locked: AtomicBool,
+    idle_timeout_minutes: AtomicU32,
+    last_activity: std::sync::Mutex<Instant>,
+}
+
+impl AppLock {
+    /// Locked from the start if a passphrase is set.
+    pub fn new(settings: &AppLockSettings) -> Self {
+        AppLock {
+            enabled: AtomicBool::new(settings.enabled()),
+            locked: AtomicBool::new(settings.enabled()),
+            idle_timeout_minutes: AtomicU32::new(settings.idle_timeout_minutes),
+            last_activity: std::sync::Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::SeqCst)
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked.load(Ordering::SeqCst)
+    }
+
+    pub fn idle_timeout_minutes(&self) -> u32 {
+        self.idle_timeout_minutes.load(Ordering::SeqCst)
+    }
+
+    /// Take new settings after the passphrase or timeout changed. Turning
+    /// the lock off also unlocks.
+    pub fn configure(&self, settings: &AppLockSettings) {
+        self.enabled.store(settings.enabled(), Ordering::SeqCst);
+        self.idle_timeout_minutes
+            .store(settings.idle_timeout_minutes, Ordering::SeqCst);
+        if !settings.enabled() {
+            self.locked.store(false, Ordering::SeqCst);
+        }
+        self.touch();
+    }
+
+    pub fn lock(&self) {
+        self.locked.store(true, Ordering::SeqCst);
+    }
+
+    pub fn unlock(&self) {
+        self.locked.store(false, Ordering::SeqCst);
+        self.touch();
+    }
+
+    pub fn touch(&self) {
+        if let Ok(mut last) = self.last_activity.lock() {
+            *last = Instant::now();
+        }
+    }
+
+    /// Lock if the idle timeout has passed. Returns true if this call
+    /// locked the app.
+    pub fn lock_if_idle(&self) -> bool {
+        let minutes = self.idle_timeout_minutes();
+        if !self.is_enabled() || self.is_locked() || minutes == 0 {
+            return false;
+        }
+        let idle = match self.last_activity.lock() {
+            Ok(last) => last.elapsed(),
+            Err(_) => return false,
+        };
+        if idle < Duration::from_secs(u64::from(minutes) * 60) {
+            return false;
+        }
+        self.lock();
+        true
+    }
+}
+
+/// Wrap the command handler so commands are refused while the app is
+/// locked, and otherwise count as activity.
+pub fn guard<R: Runtime>(
+    handler: impl Fn(Invoke<R>) -> bool + Send + Sync + 'static,
+) -> impl Fn(Invoke<R>) -> bool + Send + Sync + 'static {
+    move |invoke: Invoke<R>| {
+        let allowed = ALLOWED_WHILE_LOCKED.contains(&invoke.message.command());
+        let webview = invoke.message.webview();
+        if let Some(lock) = webview.try_state::<AppLock>() {
+            if !allowed {
+                if lock.is_locked() {
+                    invoke.resolver.reject(CommandError {
+                        code: "LOCKED".into(),
+                        message: "aequi is locked".into(),
+                    });
+                    return true;
+                }
+                lock.touch();
+            }
+        }
+        handler(invoke)
+    }
+}
+
+/// Lock the app now and tell the frontend.
+pub fn lock_and_notify<R: Runtime>(app: &tauri::AppHandle<R>, lock: &AppLock) {
+    lock.lock();
+    let _ = app.emit(EVENT_LOCKED, ());
+}
+
+/// Lock the app if it has been idle too long, telling the frontend.
+pub fn check_idle<R: Runtime>(app: &tauri::AppHandle<R>) {
+    let Some(lock) = app.try_state::<AppLock>() else {
+        return;
+    };
+    if lock.lock_if_idle() {
+        tracing::info!(
+            "Locked after {} idle minute(s)",
+            lock.idle_timeout_minutes()
+        );
+        let _ = app.emit(EVENT_LOCKED, ());
+    }
+}
@@ -0,0 +1,248 @@
+//! Auto-approval of high-confidence receipts.
+//!
+//! With `receipt_auto_approve` turned on, a receipt read with at least the
+//! configured confidence that also matches a bank transaction skips the
+//! review queue. A match already in the ledger gets the receipt linked; a
+//! categorized bank row gets a transaction posted from the receipt. Each one
+//! is logged so it shows up in the daily digest and can be undone.
+
+use std::time::Duration;
+
+use aequi_core::{
+    Deductibility, Money, TransactionLine, UnvalidatedTransaction, ValidatedTransaction,
+};
+use aequi_import::{AutoMatchEngine, ReceiptLinkTarget};
+use aequi_storage::hooks::TransactionSource;
+use aequi_storage::{AutoApproveSettings, DbPool, NewAutoApproval, ReceiptRecord};
+use chrono::NaiveDate;
+use tauri_plugin_notification::NotificationExt;
+
+use crate::commands::{account_id_for_code, CommandError};
+
+/// How often pending receipts are re-checked for bank matches that arrived
+/// after them, and whether the digest is due.
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Setting holding when the last digest went out.
+const DIGEST_SENT_SETTING: &str = "receipt_auto_approve_digest_at";
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Try to auto-approve one receipt. Returns the approval id if it was
+/// approved; receipts that don't qualify are left for review.
+pub async fn try_auto_approve(
+    db: &DbPool,
+    settings: &AutoApproveSettings,
+    receipt_id: i64,
+) -> Result<Option<i64>, CommandError> {
+    if !settings.enabled {
+        return Ok(None);
+    }
+    let Some(receipt) = aequi_storage::get_receipt_by_id(db, receipt_id).await? else {
+        return Ok(None);
+    };
+    if receipt.status != "pending_review" || receipt.confidence < settings.min_confidence {
+        return Ok(None);
+    }
+    let engine = AutoMatchEngine::new(3, 0.5, 0);
+    let Some(found) = aequi_storage::find_receipt_match(db, &engine, &receipt).await? else {
+        return Ok(None);
+    };
+    if found.confidence < settings.min_match_confidence {
+        return Ok(None);
+    }
+
+    let mut sql_tx = db.begin().await?;
+    let approval = match found.target {
+        ReceiptLinkTarget::Ledger(transaction_id) => NewAutoApproval {
+            receipt_id,
+            transaction_id,
+            imported_transaction_id: None,
+            posted: false,
+            receipt_confidence: receipt.confidence,
+            match_confidence: found.confidence,
+        },
+        ReceiptLinkTarget::Imported(imported_id) => {
+            // Only categorized rows say which account to book to.
+            let Some(validated) = transaction_for_bank_row(db, &receipt, imported_id).await? else {
+                return Ok(None);
+            };
+            let deductibility = Deductibility {
+                is_personal: receipt.is_personal,
+                deductible_percent: receipt.deductible_percent.clamp(0, 100) as u8,
+            };
+            let output =
+                aequi_storage::insert_transaction(&mut sql_tx, &validated, deductibility).await?;
+            NewAutoApproval {
+                receipt_id,
+                transaction_id: output.id,
+                imported_transaction_id: Some(imported_id),
+                posted: true,
+                receipt_confidence: receipt.confidence,
+                match_confidence: found.confidence,
+            }
+        }
+    };
+    let id = aequi_storage::record_auto_approval(&mut sql_tx, &approval).await?;
+    if id.is_some() {
+        sql_tx.commit().await?;
+        if approval.posted {
+            crate::hooks::transaction_posted(
+                db,
+                approval.transaction_id,
+                TransactionSource::AutoApproval,
+            );
+        }
+        crate::hooks::receipt_approved(db, receipt_id, true);
+    }
+    Ok(id)
+}
+
+/// The transaction to post for a receipt matched to a categorized bank row:
+/// the row's account against the account the receipt's payment method maps
+/// to. `None` if the row isn't categorized.
+async fn transaction_for_bank_row(
+    db: &DbPool,
+    receipt: &ReceiptRecord,
+    imported_id: i64,
+) -> Result<Option<ValidatedTransaction>, CommandError> {
+    let row = aequi_storage::get_import_review_row(db, imported_id)
+        .await?
+        .filter(|r| r.status == "categorized")
+        .and_then(|r| Some((r.account_id?, r.description)));
+    let (Some((expense_account, bank_description)), Some(total_cents), Some(date)) = (
+        row,
+        receipt.total_cents.filter(|t| *t != 0),
+        receipt
+            .receipt_date
+            .as_deref()
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok()),
+    ) else {
+        return Ok(None);
+    };
+    let Some(credit_code) = aequi_storage::get_payment_account_map(db)
+        .await?
+        .account_for(receipt.payment_method.as_deref())
+        .map(str::to_string)
+    else {
+        return Ok(None);
+    };
+
+    let amount = Money::from_cents(total_cents.abs());
+    let refund = total_cents < 0;
+    let (debit, credit) = if refund {
+        (Money::zero(), amount)
+    } else {
+        (amount, Money::zero())
+    };
+    let lines = vec![
+        TransactionLine {
+            account_id: aequi_core::AccountId(expense_account),
+            debit,
+            credit,
+            memo: None,
+        },
+        TransactionLine {
+            account_id: account_id_for_code(db, &credit_code).await?,
+            debit: credit,
+            credit: debit,
+            memo: receipt.payment_method.clone(),
+        },
+    ];
+    let description = receipt
+        .vendor
+        .clone()
+        .filter(|v| !v.trim().is_empty())
+        .unwrap_or(bank_description);
+    Ok(Some(ValidatedTransaction::validate(
+        UnvalidatedTransaction {
+            date,
+            description,
+            lines,
+            memo: receipt.reference.clone(),
+        },
+    )?))
+}
+
+/// Called after a receipt is stored. Failures are logged; the receipt
+/// stays in the review queue.
+pub async fn after_ingest(db: &DbPool, receipt_id: i64) {
+    let settings = match aequi_storage::get_auto_approve_settings(db).await {
+        Ok(settings) => settings,
+        Err(e) => {
+            tracing::warn!("Failed to load auto-approve settings: {e}");
+            return;
+        }
+    };
+    match try_auto_approve(db, &settings, receipt_id).await {
+        Ok(Some(_)) => tracing::info!("Auto-approved receipt {receipt_id}"),
+        Ok(None) => {}
+        Err(e) => tracing::warn!(
+            "Auto-approval of receipt {receipt_id} failed: {}",
+            e.message
+        ),
+    }
+}
+
+/// Try every receipt still pending review. Returns how many were approved.
+pub async fn run(db: &DbPool) -> Result<usize, CommandError> {
+    let settings = aequi_storage::get_auto_approve_settings(db).await?;
+    if !settings.enabled {
+        return Ok(0);
+    }
+    let mut approved = 0;
+    for receipt in aequi_storage::get_receipts_pending_review(db).await? {
+        match try_auto_approve(db, &settings, receipt.id).await {
+            Ok(Some(_)) => approved += 1,
+            Ok(None) => {}
+            Err(e) => tracing::warn!(
+                "Auto-approval of receipt {} failed: {}",
+                receipt.id,
+                e.message
+            ),
+        }
+    }
+    Ok(approved)
+}
+
+/// Send the digest of the last day's auto-approvals, at most once a day.
+/// Nothing is sent for a day with none.
+pub async fn send_digest(app: &tauri::AppHandle, db: &DbPool) -> Result<(), CommandError> {
+    let now = chrono::Utc::now().naive_utc();
+    let last_sent = aequi_storage::get_setting(db, DIGEST_SENT_SETTING)
+        .await?
+        .and_then(|s| chrono::NaiveDateTime::parse_from_str(&s, TIMESTAMP_FORMAT).ok());
+    let since = match last_sent {
+        Some(last) if now - last < chrono::Duration::days(1) => return Ok(()),
+        Some(last) => last,
+        None => now - chrono::Duration::days(1),
+    };
+
+    let booked: Vec<_> =
+        aequi_storage::get_auto_approvals(db, &since.format(TIMESTAMP_FORMAT).to_string())
+            .await?
+            .into_iter()
+            .filter(|a| a.undone_at.is_none())
+            .collect();
+    if !booked.is_empty() {
+        let total: i64 = booked.iter().filter_map(|a| a.total_cents).sum();
+        let body = format!(
+            "{} receipt(s) totalling {} were booked automatically. Review them to undo any.",
+            booked.len(),
+            Money::from_cents(total)
+        );
+        let _ = app
+            .notification()
+            .builder()
+            .title("Auto-approved Receipts")
+            .body(&body)
+            .show();
+    }
+    aequi_storage::set_setting(
+        db,
+        DIGEST_SENT_SETTING,
+        &now.format(TIMESTAMP_FORMAT).to_string(),
+    )
+    .await?;
+    Ok(())
+}
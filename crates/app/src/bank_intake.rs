@@ -0,0 +1,223 @@
+//! Watch-folder import for bank statements.
+//!
+//! CSV, OFX/QFX and QIF files dropped into the bank intake folder are parsed
+//! (CSV files with the matching saved profile), queued as pending
+//! `imported_transactions` for review, and moved to `processed/`.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use aequi_import::bank_intake::{move_to_subfolder, parse_bank_file, PROCESSED_DIR};
+use aequi_import::{
+    AutoMatchEngine, BankFileKind, CategorizableTransaction, CategoryRuleEngine, CsvImportProfile,
+    IntakeTransaction, MatchableTransaction,
+};
+use aequi_storage::hooks::ImportCompleted;
+
+/// How many days either side of a bank row a ledger entry may be dated and
+/// still be matched to it.
+const MATCH_WINDOW_DAYS: i64 = 3;
+
+/// What became of the rows of one imported file.
+#[derive(Debug, Default, Clone)]
+pub struct QueueOutcome {
+    pub queued: usize,
+    /// Rows skipped because their provider id was already imported.
+    pub duplicates: usize,
+    pub categorized: usize,
+    /// Rows with a ledger transaction suggested as their match.
+    pub matched: usize,
+}
+
+/// Saved CSV profiles, skipping any that can no longer be read.
+pub async fn load_csv_profiles(
+    db: &aequi_storage::DbPool,
+) -> Result<Vec<CsvImportProfile>, sqlx::Error> {
+    Ok(aequi_storage::get_csv_import_profiles(db)
+        .await?
+        .into_iter()
+        .filter_map(|p| match p {
+            Ok(p) => Some(p),
+            Err(e) => {
+                tracing::warn!("Skipping unusable import profile: {e}");
+                None
+            }
+        })
+        .collect())
+}
+
+/// Queue parsed bank rows under `batch_id`. Each new row is run through the
+/// categorization rules, falling back to the account the source suggested,
+/// and an unmatched ledger transaction of the same amount is suggested as
+/// its match for review.
+pub async fn queue_transactions(
+    db: &aequi_storage::DbPool,
+    kind: BankFileKind,
+    batch_id: &str,
+    transactions: Vec<IntakeTransaction>,
+) -> Result<QueueOutcome, sqlx::Error> {
+    let mut outcome = QueueOutcome::default();
+    let (rule_ids, rules): (Vec<i64>, Vec<_>) = aequi_storage::get_category_rules(db)
+        .await?
+        .into_iter()
+        .unzip();
+    let rules = CategoryRuleEngine::new(rules);
+
+    let window = chrono::Duration::days(MATCH_WINDOW_DAYS);
+    let mut ledger = match (
+        transactions.iter().map(|t| t.date).min(),
+        transactions.iter().map(|t| t.date).max(),
+    ) {
+        (Some(first), Some(last)) => {
+            aequi_storage::get_unmatched_ledger_transactions(
+                db,
+                &(first - window).to_string(),
+                &(last + window).to_string(),
+            )
+            .await?
+        }
+        _ => Vec::new(),
+    };
+    let matcher = AutoMatchEngine::new(MATCH_WINDOW_DAYS as i32, 0.5, 0);
+    let mut suggested_ids: HashMap<String, Option<i64>> = HashMap::new();
+
+    for t in transactions {
+        if let Some(source_id) = &t.source_id {
+            if aequi_storage::imported_transaction_exists(db, kind.source_type(), source_id).await?
+            {
+                outcome.duplicates += 1;
+                continue;
+            }
+        }
+        let row = aequi_storage::ImportedTransaction {
+            id: 0,
+            source_type: kind.source_type().to_string(),
+            source_id: t.source_id.clone(),
+            import_batch_id: batch_id.to_string(),
+            date: t.date.to_string(),
+            description: t.description.clone(),
+            amount_cents: t.amount_cents,
+            debit_cents: t.debit_cents,
+            credit_cents: t.credit_cents,
+            memo: t.memo.clone(),
+            matched_transaction_id: None,
+            category_rule_id: None,
+            status: "pending".to_string(),
+            created_at: String::new(),
+        };
+        let id = aequi_storage::insert_imported_transaction(db, &row).await?;
+        outcome.queued += 1;
+
+        let categorizable = CategorizableTransaction {
+            date: t.date,
+            description: t.description.clone(),
+            amount_cents: t.amount_cents,
+            memo: t.memo,
+        };
+        if let Some(idx) = rules.find_matching_index(&categorizable) {
+            aequi_storage::mark_imported_transaction_categorized(db, id, rule_ids[idx]).await?;
+            outcome.categorized += 1;
+        } else if let Some(code) = t.suggested_account {
+            let account_id = match suggested_ids.get(&code) {
+                Some(account_id) => *account_id,
+                None => {
+                    let account_id = aequi_storage::get_account_by_code(db, &code)
+                        .await?
+                        .filter(|a| !a.is_archived)
+                        .and_then(|a| a.id)
+                        .map(|id| id.0);
+                    suggested_ids.insert(code, account_id);
+                    account_id
+                }
+            };
+            if let Some(account_id) = account_id {
+                aequi_storage::set_imported_transaction_account(db, id, account_id).await?;
+                outcome.categorized += 1;
+            }
+        }
+
+        // Ledger totals are unsigned, so compare on magnitude.
+        let imported = MatchableTransaction {
+            id,
+            date: t.date,
+            description: t.description,
+            amount_cents: t.amount_cents.abs(),
+        };
+        let matched = matcher
+            .find_matches(std::slice::from_ref(&imported), &ledger)
+            .into_iter()
+            .next()
+            .and_then(|m| m.matched_tx_id);
+        if let Some(tx_id) = matched {
+            aequi_storage::suggest_imported_match(db, id, tx_id).await?;
+            ledger.retain(|l| l.id != tx_id);
+            outcome.matched += 1;
+        }
+    }
+
+    Ok(outcome)
+}
+
+/// Import one dropped bank file and move it to `processed/`. Returns the
+/// number of transactions queued.
+///
+/// Files that fail to parse are left in place so the user can fix the profile
+/// and drop them again.
+pub async fn process_bank_file(db: &aequi_storage::DbPool, path: &Path) -> Result<usize, String> {
+    if BankFileKind::from_path(path).is_none() {
+        return Ok(0);
+    }
+    let queued = import_bank_file(db, path).await?;
+    move_to_subfolder(path, PROCESSED_DIR)
+        .map_err(|e| format!("imported but failed to move {}: {e}", path.display()))?;
+    Ok(queued)
+}
+
+/// Parse a bank file and queue its rows for review, leaving the file where
+/// it is. Returns the number of rows queued.
+pub async fn import_bank_file(db: &aequi_storage::DbPool, path: &Path) -> Result<usize, String> {
+    let kind = BankFileKind::from_path(path)
+        .ok_or_else(|| format!("not a bank statement: {}", path.display()))?;
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| format!("invalid file name: {}", path.display()))?;
+
+    let data = tokio::fs::read(path)
+        .await
+        .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+
+    let profiles = load_csv_profiles(db).await.map_err(|e| e.to_string())?;
+    let parsed = parse_bank_file(file_name, &data, &profiles).map_err(|e| e.to_string())?;
+
+    let batch_id = batch_id("intake", path);
+    let outcome = queue_transactions(db, kind, &batch_id, parsed.transactions)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    tracing::info!(
+        "Queued {} transactions from {file_name} (batch {batch_id}, profile {:?})",
+        outcome.queued,
+        parsed.profile_name
+    );
+    crate::hooks::import_completed(
+        db,
+        ImportCompleted {
+            source: kind.source_type().to_string(),
+            file_name: file_name.to_string(),
+            batch_ids: vec![batch_id],
+            imported: outcome.queued,
+            duplicates: outcome.duplicates,
+        },
+    );
+    Ok(outcome.queued)
+}
+
+/// A new batch id for a file, e.g. `intake-20260315093000-chase_march`.
+pub fn batch_id(prefix: &str, path: &Path) -> String {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("file");
+    format!(
+        "{prefix}-{}-{stem}",
+        chrono::Utc::now().format("%Y%m%d%H%M%S")
+    )
+}
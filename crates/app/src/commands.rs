@@ -1,16 +1,33 @@
 use aequi_core::{
-    Account, ContactId, Discount, FiscalYear, InvoiceId, InvoiceLine, Money, Quarter, TaxLine,
-    TransactionLine, UnvalidatedTransaction, ValidatedTransaction,
+    Account, ContactId, Deductibility, FiscalYear, Money, MoneyFormat, Quarter, TransactionLine,
+    UnvalidatedTransaction, ValidatedTransaction,
 };
-use aequi_ocr::{MockRecognizer, ReceiptPipeline};
+use aequi_ocr::{
+    ExtractOptions, LlmExtractor, LlmExtractorConfig, OcrBackendKind, OcrConfig, OcrHealth,
+    VendorDictionary, VendorProfile,
+};
+use aequi_storage::aging::{self, AgingReport};
+use aequi_storage::attachment_store::resolve_path;
+use aequi_storage::attachments::{self, Attachment, AttachmentEntity};
+use aequi_storage::balance_assertions::{self, AssertionCheck};
+use aequi_storage::documents::{self, Document, DocumentSearch, DocumentType, YearArchiveSummary};
+use aequi_storage::fixed_assets::{self, CapitalizeInput, FixedAsset};
+use aequi_storage::home_office::{self, HomeOfficeWorksheet};
+use aequi_storage::hooks::{ImportCompleted, TransactionSource};
+use aequi_storage::payables::{self, Bill, BillPayment, NewBill};
+use aequi_storage::receipt_status::ReceiptStatus;
+use aequi_storage::receivables::{self, InvoiceBalance, NewPayment, RecordedPayment};
+use aequi_storage::saved_reports::{self, ReportDefinition, SavedReport, SavedReportOutput};
+use aequi_storage::time_tracking::{self, TimeEntry, TimeEntryInput};
 use chrono::{Datelike, NaiveDate};
-use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use sqlx::Row;
-use std::path::PathBuf;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
 use std::sync::Arc;
 use tauri::State;
 use tokio::sync::Mutex;
+use tracing::Instrument;
 
 use tauri_plugin_notification::NotificationExt;
 use tauri_plugin_updater::UpdaterExt;
@@ -55,9 +72,38 @@ impl CommandError {
 
 impl From<sqlx::Error> for CommandError {
     fn from(e: sqlx::Error) -> Self {
+        aequi_storage::StorageError::from(e).into()
+    }
+}
+
+impl From<aequi_storage::StorageError> for CommandError {
+    fn from(e: aequi_storage::StorageError) -> Self {
+        use aequi_storage::StorageError;
+        let (code, message) = match e {
+            StorageError::NotFound => {
+                return CommandError::not_found("That record no longer exists")
+            }
+            StorageError::Conflict(detail) => (
+                "CONFLICT",
+                format!("This clashes with something already saved ({detail})"),
+            ),
+            StorageError::Busy => (
+                "BUSY",
+                "The database is busy with another change. Try again in a moment.".to_string(),
+            ),
+            StorageError::Corruption(detail) => (
+                "CORRUPTION",
+                format!("The database file is damaged ({detail}). Restore it from a backup."),
+            ),
+            StorageError::InvalidData(detail) => (
+                "INVALID_DATA",
+                format!("Saved data can't be read ({detail})"),
+            ),
+            StorageError::Database(e) => ("DATABASE", e.to_string()),
+        };
         CommandError {
-            code: "DATABASE".into(),
-            message: e.to_string(),
+            code: code.into(),
+            message,
         }
     }
 }
@@ -80,12 +126,146 @@ impl From<aequi_ocr::PipelineError> for CommandError {
     }
 }
 
+impl From<aequi_storage::cloud_backup::CloudBackupError> for CommandError {
+    fn from(e: aequi_storage::cloud_backup::CloudBackupError) -> Self {
+        use aequi_storage::cloud_backup::CloudBackupError;
+        match e {
+            CloudBackupError::NotConfigured => CommandError::config(e.to_string()),
+            CloudBackupError::Invalid(_) | CloudBackupError::Crypto(_) => {
+                CommandError::validation(e.to_string())
+            }
+            _ => CommandError::internal(e.to_string()),
+        }
+    }
+}
+
+impl From<aequi_storage::sync::SyncError> for CommandError {
+    fn from(e: aequi_storage::sync::SyncError) -> Self {
+        match e {
+            aequi_storage::sync::SyncError::Database(e) => e.into(),
+            aequi_storage::sync::SyncError::Invalid(message) => CommandError::validation(message),
+        }
+    }
+}
+
+impl From<aequi_storage::onboarding::OnboardingError> for CommandError {
+    fn from(e: aequi_storage::onboarding::OnboardingError) -> Self {
+        match e {
+            aequi_storage::onboarding::OnboardingError::Database(e) => e.into(),
+            aequi_storage::onboarding::OnboardingError::Invalid(message) => {
+                CommandError::validation(message)
+            }
+        }
+    }
+}
+
+impl From<aequi_storage::gnucash::GnucashImportError> for CommandError {
+    fn from(e: aequi_storage::gnucash::GnucashImportError) -> Self {
+        match e {
+            aequi_storage::gnucash::GnucashImportError::Database(e) => e.into(),
+            aequi_storage::gnucash::GnucashImportError::Gnucash(e) => {
+                CommandError::validation(e.to_string())
+            }
+        }
+    }
+}
+
+impl From<aequi_storage::attachment_store::StoreMoveError> for CommandError {
+    fn from(e: aequi_storage::attachment_store::StoreMoveError) -> Self {
+        use aequi_storage::attachment_store::StoreMoveError;
+        match e {
+            StoreMoveError::Database(e) => e.into(),
+            StoreMoveError::Invalid(message) => CommandError::validation(message),
+            StoreMoveError::Io(_) => CommandError::internal(e.to_string()),
+        }
+    }
+}
+
+impl From<aequi_storage::receipt_status::ReceiptStatusError> for CommandError {
+    fn from(e: aequi_storage::receipt_status::ReceiptStatusError) -> Self {
+        use aequi_storage::receipt_status::ReceiptStatusError;
+        match e {
+            ReceiptStatusError::Database(e) => e.into(),
+            ReceiptStatusError::NotFound(_) => CommandError::not_found(e.to_string()),
+            ReceiptStatusError::Unknown(_) => CommandError::internal(e.to_string()),
+            ReceiptStatusError::Invalid { .. } | ReceiptStatusError::Linked => {
+                CommandError::validation(e.to_string())
+            }
+        }
+    }
+}
+
+impl From<aequi_storage::receivables::PaymentError> for CommandError {
+    fn from(e: aequi_storage::receivables::PaymentError) -> Self {
+        match e {
+            aequi_storage::receivables::PaymentError::Database(e) => e.into(),
+            aequi_storage::receivables::PaymentError::Storage(e) => e.into(),
+            aequi_storage::receivables::PaymentError::NotFound(message) => {
+                CommandError::not_found(message)
+            }
+            aequi_storage::receivables::PaymentError::Invalid(message) => {
+                CommandError::validation(message)
+            }
+        }
+    }
+}
+
+impl From<fixed_assets::FixedAssetError> for CommandError {
+    fn from(e: fixed_assets::FixedAssetError) -> Self {
+        match e {
+            fixed_assets::FixedAssetError::Database(e) => e.into(),
+            fixed_assets::FixedAssetError::NotFound(message) => CommandError::not_found(message),
+            fixed_assets::FixedAssetError::Invalid(message) => CommandError::validation(message),
+        }
+    }
+}
+
+impl From<saved_reports::SavedReportError> for CommandError {
+    fn from(e: saved_reports::SavedReportError) -> Self {
+        match e {
+            saved_reports::SavedReportError::Database(e) => e.into(),
+            saved_reports::SavedReportError::NotFound(message) => CommandError::not_found(message),
+            saved_reports::SavedReportError::Invalid(message) => CommandError::validation(message),
+        }
+    }
+}
+
+impl From<home_office::HomeOfficeError> for CommandError {
+    fn from(e: home_office::HomeOfficeError) -> Self {
+        match e {
+            home_office::HomeOfficeError::Database(e) => e.into(),
+            home_office::HomeOfficeError::NotFound(message) => CommandError::not_found(message),
+            home_office::HomeOfficeError::Invalid(message) => CommandError::validation(message),
+        }
+    }
+}
+
+impl From<aequi_storage::app_lock::AppLockError> for CommandError {
+    fn from(e: aequi_storage::app_lock::AppLockError) -> Self {
+        use aequi_storage::app_lock::AppLockError;
+        match e {
+            AppLockError::Database(e) => e.into(),
+            AppLockError::Invalid(message) => CommandError::validation(message),
+            AppLockError::WrongPassphrase { .. } | AppLockError::TooManyAttempts { .. } => {
+                CommandError {
+                    code: "UNAUTHORIZED".into(),
+                    message: e.to_string(),
+                }
+            }
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct TransactionInput {
     pub date: String,
     pub description: String,
     pub lines: Vec<TransactionLineInput>,
     pub memo: Option<String>,
+    /// `is_personal` and `deductible_percent`; a fully deductible business
+    /// transaction when omitted.
+    #[serde(flatten)]
+    pub deductibility: Deductibility,
 }
 
 #[derive(Debug, Deserialize)]
@@ -101,18 +281,32 @@ pub struct TransactionOutput {
     pub id: i64,
     pub date: String,
     pub description: String,
+    pub balanced_total_cents: i64,
+    /// `balanced_total_cents` as the user's locale writes it.
     pub balanced_total: String,
     pub memo: Option<String>,
     pub created_at: String,
+    pub is_personal: bool,
+    pub deductible_percent: u8,
+    /// Set when the transaction just posted booked at least the capitalize
+    /// threshold to Equipment, to offer [`capitalize_equipment`] instead.
+    pub capitalize_prompt: bool,
 }
 
 #[derive(Debug, Serialize)]
 pub struct ProfitLossEntry {
     pub account_code: String,
     pub account_name: String,
+    pub total_cents: i64,
+    /// `total_cents` as the user's locale writes it.
     pub total: String,
 }
 
+/// How amounts are shown in command output, from the saved preferences.
+pub(crate) async fn money_format(db: &aequi_storage::DbPool) -> Result<MoneyFormat, CommandError> {
+    Ok(aequi_storage::get_preferences(db).await?.money_format())
+}
+
 #[tauri::command]
 pub async fn get_accounts(
     state: State<'_, Arc<Mutex<AppState>>>,
@@ -134,8 +328,14 @@ pub async fn create_transaction(
         let s = state.lock().await;
         s.db.clone()
     };
-    let db = &db;
+    post_transaction(&db, input).await
+}
 
+/// Validate and record a transaction entered by hand or sent to the local API.
+pub(crate) async fn post_transaction(
+    db: &aequi_storage::DbPool,
+    input: TransactionInput,
+) -> Result<TransactionOutput, CommandError> {
     let description = input.description.trim().to_string();
     if description.is_empty() {
         return Err(CommandError::validation(
@@ -150,6 +350,11 @@ pub async fn create_transaction(
 
     let date = NaiveDate::parse_from_str(&input.date, "%Y-%m-%d")
         .map_err(|_| CommandError::validation("Invalid date format (expected YYYY-MM-DD)"))?;
+    if !input.deductibility.is_valid() {
+        return Err(CommandError::validation(
+            "Deductible percentage must be between 0 and 100",
+        ));
+    }
 
     let mut lines = Vec::new();
     for line in input.lines {
@@ -187,47 +392,39 @@ pub async fn create_transaction(
 
     let validated = ValidatedTransaction::validate(tx)?;
 
-    // Use a SQL transaction for atomicity
-    let mut sql_tx = db.begin().await?;
-
-    let result = sqlx::query(
-        "INSERT INTO transactions (date, description, memo, balanced_total_cents) VALUES (?, ?, ?, ?) RETURNING id, date, description, memo, balanced_total_cents, created_at"
-    )
-    .bind(validated.date.to_string())
-    .bind(&validated.description)
-    .bind(&validated.memo)
-    .bind(validated.balanced_total.to_cents())
-    .fetch_one(&mut *sql_tx)
+    // Use a SQL transaction for atomicity, run again if another writer got
+    // in the way.
+    let validated = &validated;
+    let record = aequi_storage::with_retry(&Default::default(), move || async move {
+        let mut sql_tx = db.begin().await?;
+        let record =
+            aequi_storage::insert_transaction(&mut sql_tx, validated, input.deductibility).await?;
+        sql_tx.commit().await?;
+        Ok(record)
+    })
     .await?;
+    let mut output = TransactionOutput::new(record, &money_format(db).await?);
+    output.capitalize_prompt = fixed_assets::should_offer_capitalize(db, output.id).await?;
 
-    let id: i64 = result.get("id");
-    let balanced_cents: i64 = result.get("balanced_total_cents");
+    crate::hooks::transaction_posted(db, output.id, TransactionSource::Manual);
+    Ok(output)
+}
 
-    for line in &validated.lines {
-        sqlx::query(
-            "INSERT INTO transaction_lines (transaction_id, account_id, debit_cents, credit_cents, memo) VALUES (?, ?, ?, ?, ?)"
-        )
-        .bind(id)
-        .bind(line.account_id.0)
-        .bind(line.debit.to_cents())
-        .bind(line.credit.to_cents())
-        .bind(&line.memo)
-        .execute(&mut *sql_tx)
-        .await?;
+impl TransactionOutput {
+    pub(crate) fn new(r: aequi_storage::TransactionRecord, money: &MoneyFormat) -> Self {
+        TransactionOutput {
+            id: r.id,
+            date: r.date,
+            description: r.description,
+            balanced_total_cents: r.balanced_total_cents,
+            balanced_total: money.format_cents(r.balanced_total_cents),
+            memo: r.memo,
+            created_at: r.created_at,
+            is_personal: r.is_personal,
+            deductible_percent: r.deductible_percent,
+            capitalize_prompt: false,
+        }
     }
-
-    sql_tx.commit().await?;
-
-    let created_at: String = result.get("created_at");
-
-    Ok(TransactionOutput {
-        id,
-        date: validated.date.to_string(),
-        description: validated.description,
-        balanced_total: Money::from_cents(balanced_cents).to_string(),
-        memo: validated.memo,
-        created_at,
-    })
 }
 
 #[tauri::command]
@@ -242,930 +439,6164 @@ pub async fn get_transactions(
     };
     let db = &db;
 
-    let query = match (start_date, end_date) {
-        (Some(start), Some(end)) => {
-            sqlx::query_as::<_, (i64, String, String, Option<String>, i64, String)>(
-                "SELECT id, date, description, memo, balanced_total_cents, created_at FROM transactions WHERE date >= ? AND date <= ? ORDER BY date DESC, id DESC"
-            )
-            .bind(start)
-            .bind(end)
-            .fetch_all(db)
-            .await?
-        },
-        _ => {
-            sqlx::query_as::<_, (i64, String, String, Option<String>, i64, String)>(
-                "SELECT id, date, description, memo, balanced_total_cents, created_at FROM transactions ORDER BY date DESC, id DESC"
-            )
-            .fetch_all(db)
-            .await?
-        }
+    let range = match (start_date, end_date) {
+        (Some(start), Some(end)) => Some(aequi_core::DateRange::new(
+            parse_date(&start)?,
+            parse_date(&end)?,
+        )),
+        _ => None,
     };
 
-    Ok(query
+    let money = money_format(db).await?;
+    Ok(aequi_storage::get_transactions(db, range, None)
+        .await?
         .into_iter()
-        .map(|r| TransactionOutput {
-            id: r.0,
-            date: r.1,
-            description: r.2,
-            memo: r.3,
-            balanced_total: Money::from_cents(r.4).to_string(),
-            created_at: r.5,
-        })
+        .map(|r| TransactionOutput::new(r, &money))
         .collect())
 }
 
+/// Set how much of an account's spending is deductible, e.g. 50 for an
+/// account only half of which counts for tax.
 #[tauri::command]
-pub async fn get_profit_loss(
+pub async fn set_account_deductible_pct(
     state: State<'_, Arc<Mutex<AppState>>>,
-    start_date: Option<String>,
-    end_date: Option<String>,
-) -> Result<Vec<ProfitLossEntry>, CommandError> {
+    account_code: String,
+    deductible_pct: u8,
+) -> Result<(), CommandError> {
+    if deductible_pct > 100 {
+        return Err(CommandError::validation(
+            "Deductible percentage must be between 0 and 100",
+        ));
+    }
     let db = {
         let s = state.lock().await;
         s.db.clone()
     };
-    let db = &db;
-
-    let (start, end) = match (start_date, end_date) {
-        (Some(s), Some(e)) => (s, e),
-        _ => {
-            let now = chrono::Utc::now().date_naive();
-            let start = NaiveDate::from_ymd_opt(now.year(), 1, 1).unwrap();
-            let end = now;
-            (start.to_string(), end.to_string())
-        }
-    };
-
-    let rows = sqlx::query(
-        r#"
-        SELECT a.code, a.name, 
-            COALESCE(SUM(tl.credit_cents - tl.debit_cents), 0) as total_cents
-        FROM accounts a
-        LEFT JOIN transaction_lines tl ON a.id = tl.account_id
-        LEFT JOIN transactions t ON tl.transaction_id = t.id 
-            AND t.date >= ? AND t.date <= ?
-        WHERE a.account_type IN ('Income', 'Expense')
-        GROUP BY a.id, a.code, a.name
-        ORDER BY a.account_type, a.code
-        "#,
-    )
-    .bind(&start)
-    .bind(&end)
-    .fetch_all(db)
-    .await?;
-
-    Ok(rows
-        .into_iter()
-        .map(|r| {
-            let total_cents: i64 = r.get("total_cents");
-            ProfitLossEntry {
-                account_code: r.get("code"),
-                account_name: r.get("name"),
-                total: Money::from_cents(total_cents).to_string(),
-            }
-        })
-        .collect())
-}
-
-// ── Receipt commands ──────────────────────────────────────────────────────────
-
-#[derive(Debug, Serialize)]
-pub struct ReceiptOutput {
-    pub id: i64,
-    pub file_hash: String,
-    pub vendor: Option<String>,
-    pub receipt_date: Option<String>,
-    pub total_cents: Option<i64>,
-    pub subtotal_cents: Option<i64>,
-    pub tax_cents: Option<i64>,
-    pub payment_method: Option<String>,
-    pub confidence: f64,
-    pub status: String,
-    pub transaction_id: Option<i64>,
-    pub attachment_path: String,
-    pub needs_review: bool,
-    pub created_at: String,
-}
-
-impl From<aequi_storage::ReceiptRecord> for ReceiptOutput {
-    fn from(r: aequi_storage::ReceiptRecord) -> Self {
-        let needs_review = r.confidence < 0.7;
-        ReceiptOutput {
-            id: r.id,
-            file_hash: r.file_hash,
-            vendor: r.vendor,
-            receipt_date: r.receipt_date,
-            total_cents: r.total_cents,
-            subtotal_cents: r.subtotal_cents,
-            tax_cents: r.tax_cents,
-            payment_method: r.payment_method,
-            confidence: r.confidence,
-            status: r.status,
-            transaction_id: r.transaction_id,
-            attachment_path: r.attachment_path,
-            needs_review,
-            created_at: r.created_at,
-        }
+    if !aequi_storage::set_account_deductible_pct(&db, &account_code, deductible_pct).await? {
+        return Err(CommandError::not_found(format!(
+            "Account not found: {account_code}"
+        )));
     }
+    Ok(())
 }
 
-/// Ingest a receipt from a file path on disk.
-/// Processes the image through the OCR pipeline and stores the result.
+/// Mark a transaction personal, or set how much of it is deductible.
 #[tauri::command]
-pub async fn ingest_receipt(
+pub async fn set_transaction_deductibility(
     state: State<'_, Arc<Mutex<AppState>>>,
-    file_path: String,
-) -> Result<ReceiptOutput, CommandError> {
-    let path = PathBuf::from(&file_path);
-
-    // Validate file size before processing (50 MB limit)
-    const MAX_RECEIPT_SIZE: u64 = 50 * 1024 * 1024;
-    let meta = tokio::fs::metadata(&path)
-        .await
-        .map_err(|e| CommandError::validation(format!("Cannot read file: {e}")))?;
-    if meta.len() > MAX_RECEIPT_SIZE {
-        return Err(CommandError::validation(format!(
-            "File too large ({:.1} MB, max 50 MB)",
-            meta.len() as f64 / 1_048_576.0
-        )));
+    transaction_id: i64,
+    deductibility: Deductibility,
+) -> Result<(), CommandError> {
+    if !deductibility.is_valid() {
+        return Err(CommandError::validation(
+            "Deductible percentage must be between 0 and 100",
+        ));
     }
-
-    let (db, attachments_dir) = {
+    let db = {
         let s = state.lock().await;
-        (s.db.clone(), s.attachments_dir.clone())
+        s.db.clone()
     };
-
-    // Use MockRecognizer by default; swap for TesseractRecognizer when the
-    // `tesseract` feature is enabled and Tesseract data is available.
-    let pipeline = ReceiptPipeline::new(MockRecognizer::new(""), attachments_dir);
-    let result = pipeline.process_file(&path).await?;
-
-    let e = &result.extracted;
-    let id = aequi_storage::insert_receipt(
-        &db,
-        &result.hash_hex,
-        path.extension().and_then(|x| x.to_str()).unwrap_or("bin"),
-        result.attachment_path.to_str().unwrap_or(""),
-        Some(&result.ocr_text),
-        e.vendor.as_ref().map(|f| f.value.as_str()),
-        e.date.as_ref().map(|f| f.value.to_string()).as_deref(),
-        e.total_cents.as_ref().map(|f| f.value),
-        e.subtotal_cents.as_ref().map(|f| f.value),
-        e.tax_cents.as_ref().map(|f| f.value),
-        e.payment_method
-            .as_ref()
-            .map(|f| f.value.to_string())
-            .as_deref(),
-        e.confidence as f64,
-    )
-    .await
-    .map_err(|e| CommandError::internal(e.to_string()))?;
-
-    let record = aequi_storage::get_receipt_by_id(&db, id)
-        .await
-        .map_err(|e| CommandError::internal(e.to_string()))?
-        .ok_or(CommandError::internal("Receipt not found after insert"))?;
-
-    Ok(record.into())
+    if !aequi_storage::set_transaction_deductibility(&db, transaction_id, deductibility).await? {
+        return Err(CommandError::not_found("Transaction not found"));
+    }
+    Ok(())
 }
 
-/// Return all receipts currently awaiting review.
 #[tauri::command]
-pub async fn get_pending_receipts(
+pub async fn get_transaction_tags(
     state: State<'_, Arc<Mutex<AppState>>>,
-) -> Result<Vec<ReceiptOutput>, CommandError> {
+    transaction_id: i64,
+) -> Result<Vec<String>, CommandError> {
     let db = {
         let s = state.lock().await;
         s.db.clone()
     };
-    let records = aequi_storage::get_receipts_pending_review(&db)
-        .await
-        .map_err(|e| CommandError::internal(e.to_string()))?;
-    Ok(records.into_iter().map(ReceiptOutput::from).collect())
+    Ok(aequi_storage::get_transaction_tags(&db, transaction_id).await?)
 }
 
-/// Approve a receipt, optionally linking it to an existing transaction.
+/// Replace a transaction's tags, such as the client or project it was for.
 #[tauri::command]
-pub async fn approve_receipt(
+pub async fn set_transaction_tags(
     state: State<'_, Arc<Mutex<AppState>>>,
-    receipt_id: i64,
-    transaction_id: Option<i64>,
+    transaction_id: i64,
+    tags: Vec<String>,
 ) -> Result<(), CommandError> {
     let db = {
         let s = state.lock().await;
         s.db.clone()
     };
-
-    // Validate receipt exists and is still pending
-    let receipt = aequi_storage::get_receipt_by_id(&db, receipt_id)
-        .await
-        .map_err(|e| CommandError::internal(e.to_string()))?
-        .ok_or_else(|| CommandError::not_found("Receipt not found"))?;
-    if receipt.status != "pending_review" {
-        return Err(CommandError::validation(format!(
-            "Receipt is already {} — cannot approve",
-            receipt.status
-        )));
-    }
-
-    if let Some(tx_id) = transaction_id {
-        // Validate transaction exists
-        let exists: Option<(i64,)> = sqlx::query_as("SELECT id FROM transactions WHERE id = ?")
-            .bind(tx_id)
-            .fetch_optional(&db)
-            .await
-            .map_err(|e| CommandError::internal(e.to_string()))?;
-        if exists.is_none() {
-            return Err(CommandError::not_found("Transaction not found"));
-        }
-
-        aequi_storage::link_receipt_to_transaction(&db, receipt_id, tx_id)
-            .await
-            .map_err(|e| CommandError::internal(e.to_string()))?;
-    } else {
-        aequi_storage::update_receipt_status(&db, receipt_id, "approved")
-            .await
-            .map_err(|e| CommandError::internal(e.to_string()))?;
+    if !aequi_storage::set_transaction_tags(&db, transaction_id, &tags).await? {
+        return Err(CommandError::not_found("Transaction not found"));
     }
     Ok(())
 }
 
-/// Reject a receipt (marks it as not usable / duplicate).
+/// Every tag in use, for picking from.
 #[tauri::command]
-pub async fn reject_receipt(
-    state: State<'_, Arc<Mutex<AppState>>>,
-    receipt_id: i64,
-) -> Result<(), CommandError> {
+pub async fn get_tags(state: State<'_, Arc<Mutex<AppState>>>) -> Result<Vec<String>, CommandError> {
     let db = {
         let s = state.lock().await;
         s.db.clone()
     };
-    aequi_storage::update_receipt_status(&db, receipt_id, "rejected")
-        .await
-        .map_err(|e| CommandError::internal(e.to_string()))?;
-    Ok(())
+    Ok(aequi_storage::get_all_tags(&db).await?)
 }
 
-// ── Tax commands ─────────────────────────────────────────────────────────────
+/// Capitalize what a transaction booked to Equipment as a fixed asset,
+/// moving it to the fixed asset account.
+#[tauri::command]
+pub async fn capitalize_equipment(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    transaction_id: i64,
+    input: Option<CapitalizeInput>,
+) -> Result<FixedAsset, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    Ok(fixed_assets::capitalize_equipment(&db, transaction_id, &input.unwrap_or_default()).await?)
+}
 
-#[derive(Debug, Serialize)]
-pub struct QuarterlyEstimateOutput {
-    pub year: u16,
-    pub quarter: String,
-    pub ytd_gross_income_cents: i64,
-    pub ytd_total_expenses_cents: i64,
-    pub ytd_net_profit_cents: i64,
-    pub se_tax_cents: i64,
-    pub se_tax_deduction_cents: i64,
-    pub income_tax_cents: i64,
-    pub total_tax_cents: i64,
-    pub safe_harbor_cents: i64,
-    pub quarterly_payment_cents: i64,
-    pub payment_due_date: String,
-    pub schedule_c_lines: Vec<ScheduleCLineOutput>,
-}
-
-#[derive(Debug, Serialize)]
-pub struct ScheduleCLineOutput {
-    pub line: String,
-    pub label: String,
-    pub amount_cents: i64,
-    pub is_income: bool,
+/// Capitalized equipment with each asset's depreciation schedule.
+#[tauri::command]
+pub async fn get_fixed_assets(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Vec<FixedAsset>, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    Ok(fixed_assets::get_fixed_assets(&db).await?)
 }
 
-/// Compute a quarterly tax estimate for the given year and quarter.
 #[tauri::command]
-pub async fn estimate_quarterly_tax(
+pub async fn get_profit_loss(
     state: State<'_, Arc<Mutex<AppState>>>,
-    year: Option<u16>,
-    quarter: Option<u8>,
-) -> Result<QuarterlyEstimateOutput, CommandError> {
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<Vec<ProfitLossEntry>, CommandError> {
     let db = {
         let s = state.lock().await;
         s.db.clone()
     };
     let db = &db;
 
-    let now = chrono::Utc::now().date_naive();
-    let yr = year.unwrap_or(now.year() as u16);
-    let qtr = quarter
-        .and_then(Quarter::new)
-        .unwrap_or_else(|| Quarter::new(((now.month0() / 3) + 1) as u8).unwrap_or(Quarter::Q1));
-
-    let rules = load_tax_rules(yr)?;
-    let fy = FiscalYear::new(yr);
-
-    let prior_year_cents = aequi_storage::get_prior_year_total_tax(db, yr)
-        .await
-        .map_err(|e| CommandError::internal(e.to_string()))?;
-    let prior_year_tax = prior_year_cents.map(Money::from_cents);
-
-    let snapshot = aequi_storage::build_ledger_snapshot(db, fy, prior_year_tax)
-        .await
-        .map_err(|e| CommandError::internal(e.to_string()))?;
-
-    let est = aequi_core::compute_quarterly_estimate(&rules, &snapshot, qtr);
+    let period = match (start_date, end_date) {
+        (Some(s), Some(e)) => aequi_core::DateRange::new(parse_date(&s)?, parse_date(&e)?),
+        _ => {
+            let now = chrono::Utc::now().date_naive();
+            aequi_core::DateRange::new(NaiveDate::from_ymd_opt(now.year(), 1, 1).unwrap(), now)
+        }
+    };
 
-    // Persist the estimate
-    aequi_storage::upsert_tax_period(
-        db,
-        yr,
-        quarter_to_u8(qtr),
-        est.total_tax_estimate.to_cents(),
-        est.se_tax_amount.to_cents(),
-        est.estimated_income_tax.to_cents(),
-        est.ytd_net_profit.to_cents(),
-        &est.payment_due_date.to_string(),
-        rules.year.value,
+    let money = money_format(db).await?;
+    Ok(
+        aequi_storage::account_balances::income_expense_totals(db, period)
+            .await?
+            .into_iter()
+            .map(|n| ProfitLossEntry {
+                account_code: n.code,
+                account_name: n.name,
+                total_cents: -n.net_cents,
+                total: money.format_cents(-n.net_cents),
+            })
+            .collect(),
     )
-    .await
-    .map_err(|e| CommandError::internal(e.to_string()))?;
+}
 
-    let schedule_c_lines: Vec<ScheduleCLineOutput> = est
-        .schedule_c_lines
-        .iter()
-        .map(|(line, amount)| ScheduleCLineOutput {
-            line: format!("{line:?}"),
-            label: line.label().to_string(),
-            amount_cents: amount.to_cents(),
-            is_income: line.is_income(),
-        })
-        .collect();
+/// Number of vendors the vendor spending report lists unless told otherwise.
+const VENDOR_SPENDING_LIMIT: usize = 25;
 
-    Ok(QuarterlyEstimateOutput {
-        year: est.year,
-        quarter: est.quarter.to_string(),
-        ytd_gross_income_cents: est.ytd_gross_income.to_cents(),
-        ytd_total_expenses_cents: est.ytd_total_expenses.to_cents(),
-        ytd_net_profit_cents: est.ytd_net_profit.to_cents(),
-        se_tax_cents: est.se_tax_amount.to_cents(),
-        se_tax_deduction_cents: est.se_tax_deduction.to_cents(),
-        income_tax_cents: est.estimated_income_tax.to_cents(),
-        total_tax_cents: est.total_tax_estimate.to_cents(),
-        safe_harbor_cents: est.safe_harbor_amount.to_cents(),
-        quarterly_payment_cents: est.quarterly_payment.to_cents(),
-        payment_due_date: est.payment_due_date.to_string(),
-        schedule_c_lines,
-    })
+/// Business expenses by vendor over the period, month by month.
+#[tauri::command]
+pub async fn get_vendor_spending(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    period: aequi_core::DateRange,
+    limit: Option<usize>,
+) -> Result<aequi_storage::reports::VendorSpendingReport, CommandError> {
+    if period.start > period.end {
+        return Err(CommandError::validation("Start date is after end date"));
+    }
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    Ok(aequi_storage::reports::vendor_spending(
+        &db,
+        period,
+        limit.unwrap_or(VENDOR_SPENDING_LIMIT).max(1),
+    )
+    .await?)
 }
 
-/// Get the Schedule C preview for a given year.
+/// Owner contributions, draws and profit over the period, month by month
+/// unless another interval is given, rolled forward to ending equity.
 #[tauri::command]
-pub async fn get_schedule_c_preview(
+pub async fn get_owners_equity(
     state: State<'_, Arc<Mutex<AppState>>>,
-    year: Option<u16>,
-) -> Result<ScheduleCPreviewOutput, CommandError> {
+    period: aequi_core::DateRange,
+    interval: Option<aequi_storage::reports::ReportInterval>,
+) -> Result<aequi_storage::reports::OwnersEquityReport, CommandError> {
+    if period.start > period.end {
+        return Err(CommandError::validation("Start date is after end date"));
+    }
     let db = {
         let s = state.lock().await;
         s.db.clone()
     };
-    let db = &db;
-
-    let yr = year.unwrap_or(chrono::Utc::now().date_naive().year() as u16);
-    let rules = load_tax_rules(yr)?;
-    let fy = FiscalYear::new(yr);
-
-    let snapshot = aequi_storage::build_ledger_snapshot(db, fy, None)
-        .await
-        .map_err(|e| CommandError::internal(e.to_string()))?;
-
-    let preview = aequi_core::tax::engine::schedule_c_preview(&rules, &snapshot);
-
-    let lines: Vec<ScheduleCLineOutput> = preview
-        .lines
-        .iter()
-        .map(|(line, amount)| ScheduleCLineOutput {
-            line: format!("{line:?}"),
-            label: line.label().to_string(),
-            amount_cents: amount.to_cents(),
-            is_income: line.is_income(),
-        })
-        .collect();
-
-    Ok(ScheduleCPreviewOutput {
-        year: preview.year,
-        gross_income_cents: preview.gross_income.to_cents(),
-        total_expenses_cents: preview.total_expenses.to_cents(),
-        net_profit_cents: preview.net_profit.to_cents(),
-        lines,
-    })
+    Ok(aequi_storage::reports::owners_equity(
+        &db,
+        period,
+        interval.unwrap_or(aequi_storage::reports::ReportInterval::Month),
+    )
+    .await?)
 }
 
-#[derive(Debug, Serialize)]
-pub struct ScheduleCPreviewOutput {
-    pub year: u16,
-    pub gross_income_cents: i64,
-    pub total_expenses_cents: i64,
-    pub net_profit_cents: i64,
-    pub lines: Vec<ScheduleCLineOutput>,
+#[tauri::command]
+pub async fn get_saved_reports(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Vec<SavedReport>, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    Ok(saved_reports::get_saved_reports(&db).await?)
 }
 
-/// Load tax rules for a given year from the bundled rules directory.
-fn load_tax_rules(year: u16) -> Result<aequi_core::TaxRules, CommandError> {
-    // Use the bundled rules file. In production this would resolve from
-    // the app's resource directory; for now we embed the 2026 rules.
-    let toml_str = include_str!("../../../rules/tax/us/2026.toml");
-
-    let rules = aequi_core::TaxRules::from_toml(toml_str)
-        .map_err(|e| CommandError::internal(e.to_string()))?;
-
-    if rules.year.value != year {
-        return Err(CommandError::not_found(format!(
-            "Tax rules for year {year} not available (have {})",
-            rules.year.value
-        )));
-    }
-
-    Ok(rules)
+/// Save a report definition under `name`, replacing report `id` when given.
+#[tauri::command]
+pub async fn save_saved_report(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    id: Option<i64>,
+    name: String,
+    definition: ReportDefinition,
+) -> Result<SavedReport, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let id = saved_reports::save_saved_report(&db, id, &name, &definition).await?;
+    saved_reports::get_saved_report(&db, id)
+        .await?
+        .ok_or_else(|| CommandError::internal("Saved report not found after saving"))
 }
 
-fn quarter_to_u8(q: Quarter) -> u8 {
-    match q {
-        Quarter::Q1 => 1,
-        Quarter::Q2 => 2,
-        Quarter::Q3 => 3,
-        Quarter::Q4 => 4,
+#[tauri::command]
+pub async fn delete_saved_report(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+) -> Result<(), CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    if !saved_reports::delete_saved_report(&db, id).await? {
+        return Err(CommandError::not_found("Saved report not found"));
     }
+    Ok(())
 }
 
-// ── Contact commands ─────────────────────────────────────────────────────────
-
-#[derive(Debug, Deserialize)]
-pub struct ContactInput {
-    pub name: String,
-    pub email: Option<String>,
-    pub phone: Option<String>,
-    pub address: Option<String>,
-    pub contact_type: String,
-    pub is_contractor: bool,
-    pub tax_id: Option<String>,
-    pub notes: Option<String>,
+/// Run a saved report for its period as of today.
+#[tauri::command]
+pub async fn run_saved_report(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+) -> Result<SavedReportOutput, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let today = chrono::Local::now().date_naive();
+    Ok(saved_reports::run_saved_report(&db, id, today).await?)
 }
 
+/// Run a definition without saving it, to preview it in the report builder.
 #[tauri::command]
-pub async fn get_contacts(
+pub async fn preview_report_definition(
     state: State<'_, Arc<Mutex<AppState>>>,
-) -> Result<Vec<aequi_storage::ContactRecord>, CommandError> {
+    definition: ReportDefinition,
+) -> Result<SavedReportOutput, CommandError> {
     let db = {
         let s = state.lock().await;
         s.db.clone()
     };
-    let contacts = aequi_storage::get_all_contacts(&db)
-        .await
-        .map_err(|e| CommandError::internal(e.to_string()))?;
-    Ok(contacts)
+    let today = chrono::Local::now().date_naive();
+    Ok(saved_reports::run_report_definition(&db, "Custom report", &definition, today).await?)
 }
 
+// ── Quick entry commands ────────────────────────────────────────────────────
+
+/// Payees matching what has been typed so far, most used first.
 #[tauri::command]
-pub async fn create_contact(
+pub async fn suggest_payees(
     state: State<'_, Arc<Mutex<AppState>>>,
-    input: ContactInput,
-) -> Result<aequi_storage::ContactRecord, CommandError> {
-    // Input validation
-    let name = input.name.trim().to_string();
-    if name.is_empty() {
-        return Err(CommandError::validation("Contact name is required"));
-    }
-    if let Some(ref email) = input.email {
-        let email = email.trim();
-        if !email.is_empty() && (!email.contains('@') || !email.contains('.')) {
-            return Err(CommandError::validation("Invalid email address format"));
-        }
-    }
-    if !["Client", "Vendor", "Contractor"].contains(&input.contact_type.as_str()) {
-        return Err(CommandError::validation(
-            "Contact type must be Client, Vendor, or Contractor",
-        ));
+    query: String,
+    limit: Option<i64>,
+) -> Result<Vec<aequi_storage::PayeeSuggestion>, CommandError> {
+    if query.trim().is_empty() {
+        return Ok(Vec::new());
     }
-
     let db = {
         let s = state.lock().await;
         s.db.clone()
     };
-    let id = aequi_storage::insert_contact(
-        &db,
-        &name,
-        input.email.as_deref(),
-        input.phone.as_deref(),
-        input.address.as_deref(),
-        &input.contact_type,
-        input.is_contractor,
-        input.tax_id.as_deref(),
-        input.notes.as_deref(),
+    Ok(
+        aequi_storage::get_payee_suggestions(&db, &query, limit.unwrap_or(10).clamp(1, 100))
+            .await?,
     )
-    .await
-    .map_err(|e| CommandError {
-        code: "DATABASE".into(),
-        message: e.to_string(),
-    })?;
-
-    aequi_storage::get_contact_by_id(&db, id)
-        .await
-        .map_err(|e| CommandError {
-            code: "DATABASE".into(),
-            message: e.to_string(),
-        })?
-        .ok_or(CommandError::not_found("Contact not found after insert"))
 }
 
-// ── Invoice commands ─────────────────────────────────────────────────────────
-
+#[tauri::command]
+pub async fn get_payee_accounts(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    payee: String,
+    limit: Option<i64>,
+) -> Result<Vec<aequi_storage::PayeeAccountUsage>, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    Ok(aequi_storage::get_payee_accounts(&db, &payee, limit.unwrap_or(5).clamp(1, 100)).await?)
+}
+
+#[tauri::command]
+pub async fn get_payee_amounts(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    payee: String,
+    limit: Option<i64>,
+) -> Result<Vec<aequi_storage::PayeeAmount>, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    Ok(aequi_storage::get_payee_amounts(&db, &payee, limit.unwrap_or(5).clamp(1, 100)).await?)
+}
+
+/// What quick entry fills in once a known payee is chosen.
+#[derive(Debug, Serialize)]
+pub struct QuickEntryPrefill {
+    pub payee: String,
+    /// The income or expense account most often used for the payee.
+    pub category_account: Option<aequi_storage::PayeeAccountUsage>,
+    /// The bank or card account most often used to pay them.
+    pub payment_account: Option<aequi_storage::PayeeAccountUsage>,
+    /// The amount of the latest transaction with the payee.
+    pub amount_cents: Option<i64>,
+    /// The contact the payee is, by name or alias.
+    pub contact_id: Option<i64>,
+}
+
+#[tauri::command]
+pub async fn get_quick_entry_prefill(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    payee: String,
+) -> Result<QuickEntryPrefill, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let accounts = aequi_storage::get_payee_accounts(&db, &payee, 20).await?;
+    let first_of = |types: &[&str]| {
+        accounts
+            .iter()
+            .find(|a| types.contains(&a.account_type.as_str()))
+            .cloned()
+    };
+    let amount_cents = aequi_storage::get_payee_amounts(&db, &payee, 1)
+        .await?
+        .first()
+        .map(|a| a.amount_cents);
+    let contact = aequi_storage::find_contact_by_payee(&db, &payee).await?;
+
+    // Without any history, fall back to the contact's default account.
+    let mut category_account = first_of(&["Expense", "Income"]);
+    if let (None, Some(account_id)) = (
+        &category_account,
+        contact.as_ref().and_then(|c| c.default_account_id),
+    ) {
+        category_account = aequi_storage::get_all_accounts(&db)
+            .await?
+            .into_iter()
+            .find(|a| a.id.map(|id| id.0) == Some(account_id) && !a.is_archived)
+            .map(|a| aequi_storage::PayeeAccountUsage {
+                account_id,
+                account_code: a.code,
+                account_name: a.name,
+                account_type: a.account_type.as_str().to_string(),
+                uses: 0,
+                last_used: String::new(),
+            });
+    }
+
+    Ok(QuickEntryPrefill {
+        category_account,
+        payment_account: first_of(&["Asset", "Liability"]),
+        amount_cents,
+        contact_id: contact.as_ref().map(|c| c.id),
+        payee: contact.map_or_else(|| payee.trim().to_string(), |c| c.name),
+    })
+}
+
+// ── Receipt commands ──────────────────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct ReceiptOutput {
+    pub id: i64,
+    pub file_hash: String,
+    pub vendor: Option<String>,
+    pub receipt_date: Option<String>,
+    pub total_cents: Option<i64>,
+    pub subtotal_cents: Option<i64>,
+    pub tax_cents: Option<i64>,
+    pub payment_method: Option<String>,
+    pub reference: Option<String>,
+    pub confidence: f64,
+    pub status: String,
+    pub transaction_id: Option<i64>,
+    pub attachment_path: String,
+    pub needs_review: bool,
+    pub created_at: String,
+    pub quality_score: Option<f64>,
+    pub quality_issues: Vec<String>,
+    /// Set when the photo should be retaken, e.g. "Photo too blurry — …".
+    pub retake_message: Option<String>,
+    /// Account the receipt's payment method maps to, suggested as the
+    /// account to credit.
+    pub payment_account_code: Option<String>,
+    /// MIME type of the full-size image `get_receipt_image` returns.
+    pub content_type: String,
+    pub is_personal: bool,
+    pub deductible_percent: i64,
+    /// For a `duplicate`, the receipt it looks like a second copy of.
+    pub duplicate_of: Option<i64>,
+    /// Set by ingest when the same file had been stored before and this is
+    /// that receipt, not a new one.
+    pub already_stored: bool,
+    /// The photo this receipt was cut out of, when it held several.
+    pub source_path: Option<String>,
+    /// Set by ingest when the photo held several receipts: all of them, in
+    /// order, this one first.
+    pub split_into: Vec<i64>,
+}
+
+impl From<aequi_storage::ReceiptRecord> for ReceiptOutput {
+    fn from(r: aequi_storage::ReceiptRecord) -> Self {
+        let needs_review = r.confidence < aequi_storage::DEFAULT_REVIEW_THRESHOLD;
+        let quality_issues: Vec<String> = r
+            .quality_issues
+            .as_deref()
+            .map(|s| s.split(',').map(str::to_string).collect())
+            .unwrap_or_default();
+        let retake_message = quality_issues
+            .iter()
+            .find_map(|code| aequi_ocr::QualityIssue::parse(code))
+            .map(|issue| issue.message().to_string());
+        ReceiptOutput {
+            id: r.id,
+            file_hash: r.file_hash,
+            vendor: r.vendor,
+            receipt_date: r.receipt_date,
+            total_cents: r.total_cents,
+            subtotal_cents: r.subtotal_cents,
+            tax_cents: r.tax_cents,
+            payment_method: r.payment_method,
+            reference: r.reference,
+            confidence: r.confidence,
+            status: r.status,
+            transaction_id: r.transaction_id,
+            attachment_path: r.attachment_path,
+            needs_review,
+            created_at: r.created_at,
+            quality_score: r.quality_score,
+            quality_issues,
+            retake_message,
+            payment_account_code: None,
+            content_type: served_content_type(&r.file_ext).to_string(),
+            is_personal: r.is_personal,
+            deductible_percent: r.deductible_percent,
+            duplicate_of: r.duplicate_of,
+            already_stored: false,
+            source_path: r.source_path,
+            split_into: Vec::new(),
+        }
+    }
+}
+
+/// Formats the webview can't show itself and are converted to JPEG.
+fn needs_conversion(ext: &str) -> bool {
+    matches!(
+        ext.to_ascii_lowercase().as_str(),
+        "heic" | "heif" | "tif" | "tiff"
+    )
+}
+
+fn served_content_type(ext: &str) -> &'static str {
+    if needs_conversion(ext) {
+        "image/jpeg"
+    } else {
+        aequi_ocr::content_type(ext)
+    }
+}
+
+/// The stored receipt file, for display. With `thumbnail` set this is a
+/// small JPEG preview; otherwise the original, converted to JPEG if it is
+/// HEIC or TIFF. Bytes are returned raw rather than as a path so the
+/// webview never needs filesystem access.
+#[tauri::command]
+pub async fn get_receipt_image(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    receipt_id: i64,
+    thumbnail: Option<bool>,
+) -> Result<tauri::ipc::Response, CommandError> {
+    let (db, pipeline) = {
+        let s = state.lock().await;
+        (s.db.clone(), s.pipeline.clone())
+    };
+    let receipt = aequi_storage::get_receipt_by_id(&db, receipt_id)
+        .await?
+        .ok_or_else(|| CommandError::not_found("Receipt not found"))?;
+
+    // Only serve files from the attachment store, whatever the row says.
+    let root = pipeline.attachments_dir();
+    let path = tokio::fs::canonicalize(resolve_path(&root, &receipt.attachment_path))
+        .await
+        .map_err(|_| CommandError::not_found("Receipt file is missing"))?;
+    let root = tokio::fs::canonicalize(&root)
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))?;
+    if !path.starts_with(&root) {
+        return Err(CommandError::validation(
+            "Receipt file is outside the attachment store",
+        ));
+    }
+    let data = tokio::fs::read(&path)
+        .await
+        .map_err(|e| CommandError::internal(format!("Cannot read receipt file: {e}")))?;
+
+    let max_side = if thumbnail.unwrap_or(false) {
+        Some(aequi_ocr::THUMBNAIL_SIZE)
+    } else if needs_conversion(&receipt.file_ext) {
+        None
+    } else {
+        return Ok(tauri::ipc::Response::new(data));
+    };
+    let jpeg = tokio::task::spawn_blocking(move || aequi_ocr::preview_jpeg(&data, max_side))
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))?
+        .map_err(|e| CommandError::validation(format!("No preview available: {e}")))?;
+    Ok(tauri::ipc::Response::new(jpeg))
+}
+
+/// Largest receipt file accepted for ingestion.
+pub(crate) const MAX_RECEIPT_SIZE: u64 = 50 * 1024 * 1024;
+
+fn receipt_too_large(len: u64) -> CommandError {
+    CommandError::validation(format!(
+        "File too large ({:.1} MB, max 50 MB)",
+        len as f64 / 1_048_576.0
+    ))
+}
+
+/// Ingest a receipt from a file path on disk.
+/// Processes the image through the OCR pipeline and stores the result.
+#[tauri::command]
+pub async fn ingest_receipt(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    file_path: String,
+) -> Result<ReceiptOutput, CommandError> {
+    let path = PathBuf::from(&file_path);
+
+    // Validate file size before processing
+    let meta = tokio::fs::metadata(&path)
+        .await
+        .map_err(|e| CommandError::validation(format!("Cannot read file: {e}")))?;
+    if meta.len() > MAX_RECEIPT_SIZE {
+        return Err(receipt_too_large(meta.len()));
+    }
+
+    let (db, pipeline) = {
+        let s = state.lock().await;
+        (s.db.clone(), s.pipeline.clone())
+    };
+
+    async {
+        let outcome = pipeline.process_file(&path).await?;
+        let ext = path.extension().and_then(|x| x.to_str()).unwrap_or("bin");
+        store_ingested(&db, outcome, ext).await
+    }
+    .instrument(crate::logging::command_span("ingest_receipt"))
+    .await
+}
+
+/// Ingest a receipt handed over as bytes, e.g. a camera capture on mobile
+/// where the webview has no file path to give. `ext` is the extension or
+/// MIME type the client reports; the bytes themselves must agree with it.
+#[tauri::command]
+pub async fn ingest_receipt_bytes(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    data: Vec<u8>,
+    ext: String,
+) -> Result<ReceiptOutput, CommandError> {
+    let (db, pipeline) = {
+        let s = state.lock().await;
+        (s.db.clone(), s.pipeline.clone())
+    };
+    ingest_receipt_data(&db, &pipeline, &data, &ext)
+        .instrument(crate::logging::command_span("ingest_receipt_bytes"))
+        .await
+}
+
+/// Queue a photo or PDF shared to the app, e.g. opened with it or dropped
+/// on the window. It goes through the intake
+/// queue rather than being processed here, so progress comes as the
+/// `receipt:*` events; returns the path they report it under.
+#[tauri::command]
+pub async fn ingest_shared_file(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<Mutex<AppState>>>,
+    path: String,
+) -> Result<String, CommandError> {
+    let path = PathBuf::from(&path);
+    let meta = tokio::fs::metadata(&path)
+        .await
+        .map_err(|e| CommandError::validation(format!("Cannot read file: {e}")))?;
+    if meta.len() > MAX_RECEIPT_SIZE {
+        return Err(receipt_too_large(meta.len()));
+    }
+    if !crate::receipt_intake::is_shareable(&path) {
+        return Err(CommandError::validation(
+            "Unsupported file type (expected an image or PDF)",
+        ));
+    }
+    let (db, wake, shared_dir) = {
+        let s = state.lock().await;
+        (s.db.clone(), s.intake_wake.clone(), s.shared_dir.clone())
+    };
+    let queued = crate::receipt_intake::ingest_shared(&app, &db, &wake, &shared_dir, &path)
+        .await
+        .map_err(CommandError::internal)?;
+    Ok(queued.display().to_string())
+}
+
+/// Check and process receipt bytes from the webview or the local API.
+pub(crate) async fn ingest_receipt_data(
+    db: &aequi_storage::DbPool,
+    pipeline: &aequi_ocr::ReceiptPipeline,
+    data: &[u8],
+    ext: &str,
+) -> Result<ReceiptOutput, CommandError> {
+    if data.is_empty() {
+        return Err(CommandError::validation("Receipt file is empty"));
+    }
+    if data.len() as u64 > MAX_RECEIPT_SIZE {
+        return Err(receipt_too_large(data.len() as u64));
+    }
+    let sniffed = aequi_ocr::sniff_extension(data).ok_or_else(|| {
+        CommandError::validation("Unsupported file type (expected an image or PDF)")
+    })?;
+    match aequi_ocr::canonical_extension(ext) {
+        Some(claimed) if claimed == sniffed => {}
+        _ => {
+            return Err(CommandError::validation(format!(
+                "File content is {sniffed}, not {ext}"
+            )))
+        }
+    }
+
+    let outcome = pipeline.process_bytes(data, sniffed).await?;
+    store_ingested(db, outcome, sniffed).await
+}
+
+/// Store a freshly processed receipt, or look up the one it duplicates.
+/// A photo of several receipts stores each of them and returns the first.
+async fn store_ingested(
+    db: &aequi_storage::DbPool,
+    outcome: aequi_ocr::ProcessOutcome,
+    ext: &str,
+) -> Result<ReceiptOutput, CommandError> {
+    let mut stored = Vec::new();
+    match outcome {
+        aequi_ocr::ProcessOutcome::Duplicate { receipt_id, .. } => {
+            stored.push(aequi_storage::ReceiptInsert::AlreadyStored { id: receipt_id });
+        }
+        outcome => {
+            for result in outcome.into_results() {
+                if let Some(err) = &result.llm_error {
+                    tracing::warn!("LLM receipt extraction failed, used regex extractor: {err}");
+                }
+                let inserted = store_ocr_result(db, &result, ext)
+                    .await
+                    .map_err(|e| CommandError::internal(e.to_string()))?;
+                stored.push(inserted);
+            }
+        }
+    }
+    let inserted = *stored
+        .first()
+        .ok_or(CommandError::internal("Pipeline produced no receipt"))?;
+
+    let record = aequi_storage::get_receipt_by_id(db, inserted.id())
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))?
+        .ok_or(CommandError::internal("Receipt not found after insert"))?;
+
+    let mut out = ReceiptOutput::from(record);
+    out.already_stored = !inserted.is_new();
+    if stored.len() > 1 {
+        out.split_into = stored.iter().map(|i| i.id()).collect();
+    }
+    Ok(out)
+}
+
+/// Set the IMAP folder receipts are forwarded to, or `None` to stop
+/// polling. The folder is checked right away, so bad credentials show up
+/// here; returns the number of receipts imported.
+#[tauri::command]
+pub async fn configure_receipt_inbox(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    config: Option<aequi_email::ImapConfig>,
+) -> Result<usize, CommandError> {
+    if let Some(c) = &config {
+        if c.host.trim().is_empty() || c.username.trim().is_empty() {
+            return Err(CommandError::validation(
+                "IMAP host and username are required",
+            ));
+        }
+    }
+    let value = match &config {
+        Some(c) => serde_json::to_string(c).map_err(|e| CommandError::internal(e.to_string()))?,
+        None => String::new(),
+    };
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    aequi_storage::set_setting(&db, "receipt_inbox_config", &value).await?;
+    poll_receipt_inbox(state).await
+}
+
+/// Check the receipt inbox now instead of waiting for the next poll.
+#[tauri::command]
+pub async fn poll_receipt_inbox(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<usize, CommandError> {
+    let (db, pipeline) = {
+        let s = state.lock().await;
+        (s.db.clone(), s.pipeline.clone())
+    };
+    crate::email_intake::poll_inbox(&db, &pipeline)
+        .instrument(crate::logging::command_span("poll_receipt_inbox"))
+        .await
+        .map_err(CommandError::config)
+}
+
+/// Lets the pipeline skip files whose hash is already in the receipts table.
+pub(crate) struct StoredReceipts(pub aequi_storage::DbPool);
+
+impl aequi_ocr::DedupCheck for StoredReceipts {
+    fn existing_receipt<'a>(
+        &'a self,
+        hash_hex: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Option<i64>> + Send + 'a>> {
+        Box::pin(async move {
+            // A failed lookup just means the file is processed again; the
+            // insert still dedups on the hash.
+            aequi_storage::check_receipt_duplicate(&self.0, hash_hex)
+                .await
+                .unwrap_or_else(|e| {
+                    tracing::warn!("Receipt duplicate check failed: {e}");
+                    None
+                })
+        })
+    }
+}
+
+/// Save a pipeline result as a receipt pending review, or marked
+/// `duplicate` when it looks like one already on file, with its photo
+/// quality. A file stored before is left as it was. A receipt cut out of a
+/// photo of several records that photo, and is never a duplicate of the
+/// others from it.
+pub(crate) async fn store_ocr_result(
+    db: &aequi_storage::DbPool,
+    result: &aequi_ocr::OcrResult,
+    ext: &str,
+) -> Result<aequi_storage::ReceiptInsert, sqlx::Error> {
+    let e = &result.extracted;
+    let ext = match result.source {
+        Some(_) => aequi_ocr::split::CUT_OUT_EXTENSION,
+        None => ext,
+    };
+    let mut inserted = aequi_storage::insert_receipt(
+        db,
+        &result.hash_hex,
+        ext,
+        &result.stored_path,
+        Some(&result.ocr_text),
+        e.vendor.as_ref().map(|f| f.value.as_str()),
+        e.date.as_ref().map(|f| f.value.to_string()).as_deref(),
+        e.total_cents.as_ref().map(|f| f.value),
+        e.subtotal_cents.as_ref().map(|f| f.value),
+        e.tax_cents.as_ref().map(|f| f.value),
+        e.payment_method
+            .as_ref()
+            .map(|f| f.value.to_string())
+            .as_deref(),
+        e.reference.as_ref().map(|f| f.value.as_str()),
+        e.confidence as f64,
+    )
+    .await?;
+    if !inserted.is_new() {
+        return Ok(inserted);
+    }
+    let id = inserted.id();
+    if let Some(source) = &result.source {
+        inserted =
+            match aequi_storage::set_receipt_source(db, id, &source.hash_hex, &source.stored_path)
+                .await?
+            {
+                Some(duplicate_of) => {
+                    aequi_storage::ReceiptInsert::PossibleDuplicate { id, duplicate_of }
+                }
+                None => aequi_storage::ReceiptInsert::Inserted { id },
+            };
+    }
+    if let Some(quality) = &result.quality {
+        store_receipt_quality(db, id, quality).await?;
+    }
+    for item in &e.line_items {
+        aequi_storage::insert_receipt_line_item(
+            db,
+            id,
+            &item.description,
+            item.amount_cents,
+            item.quantity.map(f64::from),
+        )
+        .await?;
+    }
+    crate::auto_approve::after_ingest(db, id).await;
+    Ok(inserted)
+}
+
+pub(crate) async fn store_receipt_quality(
+    db: &aequi_storage::DbPool,
+    receipt_id: i64,
+    quality: &aequi_ocr::ImageQuality,
+) -> Result<(), sqlx::Error> {
+    let issues: Vec<&str> = quality.issues.iter().map(|i| i.as_str()).collect();
+    aequi_storage::set_receipt_quality(db, receipt_id, quality.score as f64, &issues).await
+}
+
+/// Intake folder files in the queue: those with `status`, or all that
+/// aren't done.
+#[tauri::command]
+pub async fn get_receipt_jobs(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    status: Option<aequi_storage::receipt_jobs::ReceiptJobStatus>,
+) -> Result<Vec<aequi_storage::receipt_jobs::ReceiptJob>, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    Ok(aequi_storage::receipt_jobs::get_receipt_jobs(&db, status).await?)
+}
+
+/// Queue a file that failed every attempt to be tried again now.
+#[tauri::command]
+pub async fn retry_receipt_job(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+) -> Result<(), CommandError> {
+    let (db, wake) = {
+        let s = state.lock().await;
+        (s.db.clone(), s.intake_wake.clone())
+    };
+    if !aequi_storage::receipt_jobs::retry_receipt_job(&db, id).await? {
+        return Err(CommandError::not_found(format!(
+            "No failed receipt job {id} to retry"
+        )));
+    }
+    wake.notify_one();
+    Ok(())
+}
+
+/// Return all receipts currently awaiting review.
+#[tauri::command]
+pub async fn get_pending_receipts(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Vec<ReceiptOutput>, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let records = aequi_storage::get_receipts_pending_review(&db)
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))?;
+    let payment_accounts = aequi_storage::get_payment_account_map(&db).await?;
+    let review_threshold = aequi_storage::get_preferences(&db).await?.review_threshold;
+    Ok(records
+        .into_iter()
+        .map(|r| {
+            let mut out = ReceiptOutput::from(r);
+            out.needs_review = out.confidence < review_threshold;
+            out.payment_account_code = payment_accounts
+                .account_for(out.payment_method.as_deref())
+                .map(str::to_string);
+            out
+        })
+        .collect())
+}
+
+/// Receipts held back as possible copies of ones already on file, each with
+/// `duplicate_of` to compare against. Reject one to drop it, or clear it to
+/// review it as a purchase of its own.
+#[tauri::command]
+pub async fn get_duplicate_receipts(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Vec<ReceiptOutput>, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let records = aequi_storage::get_duplicate_receipts(&db).await?;
+    Ok(records.into_iter().map(ReceiptOutput::from).collect())
+}
+
+/// Put a receipt marked `duplicate` back in the review queue.
+#[tauri::command]
+pub async fn clear_receipt_duplicate(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    receipt_id: i64,
+) -> Result<(), CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    if !aequi_storage::clear_receipt_duplicate(&db, receipt_id).await? {
+        return Err(CommandError::not_found(format!(
+            "Receipt {receipt_id} isn't marked as a duplicate"
+        )));
+    }
+    Ok(())
+}
+
+/// Every receipt cut out of the same photo as `receipt_id`, itself
+/// included; just the receipt when its photo held only it.
+#[tauri::command]
+pub async fn get_receipts_from_same_photo(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    receipt_id: i64,
+) -> Result<Vec<ReceiptOutput>, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let receipt = aequi_storage::get_receipt_by_id(&db, receipt_id)
+        .await?
+        .ok_or_else(|| CommandError::not_found("Receipt not found"))?;
+    let records = match &receipt.source_hash {
+        Some(source_hash) => aequi_storage::get_receipts_from_source(&db, source_hash).await?,
+        None => vec![receipt],
+    };
+    Ok(records.into_iter().map(ReceiptOutput::from).collect())
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReceiptPageOutput {
+    pub receipts: Vec<ReceiptOutput>,
+    pub total: i64,
+}
+
+/// Search all receipts, whatever their status, one page at a time.
+#[tauri::command]
+pub async fn query_receipts(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    filter: aequi_storage::ReceiptQuery,
+) -> Result<ReceiptPageOutput, CommandError> {
+    for date in [&filter.date_from, &filter.date_to].into_iter().flatten() {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| CommandError::validation(format!("Invalid date: {date}")))?;
+    }
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let page = aequi_storage::query_receipts(&db, &filter).await?;
+    let review_threshold = aequi_storage::get_preferences(&db).await?.review_threshold;
+    Ok(ReceiptPageOutput {
+        receipts: page
+            .receipts
+            .into_iter()
+            .map(|r| {
+                let mut out = ReceiptOutput::from(r);
+                out.needs_review = out.confidence < review_threshold;
+                out
+            })
+            .collect(),
+        total: page.total,
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct DocumentationGapsOutput {
+    pub threshold_cents: i64,
+    pub unreceipted_transactions: Vec<aequi_storage::UnreceiptedExpense>,
+    pub unlinked_receipts: Vec<ReceiptOutput>,
+}
+
+/// Expense transactions at or above the receipt threshold with no receipt,
+/// and approved receipts not tied to any transaction. Defaults to this year
+/// so far and the threshold saved under `receipt_required_threshold_cents`.
+#[tauri::command]
+pub async fn get_documentation_gaps(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    threshold_cents: Option<i64>,
+) -> Result<DocumentationGapsOutput, CommandError> {
+    let now = chrono::Utc::now().date_naive();
+    let start = start_date.unwrap_or_else(|| format!("{}-01-01", now.year()));
+    let end = end_date.unwrap_or_else(|| now.to_string());
+    for date in [&start, &end] {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| CommandError::validation(format!("Invalid date: {date}")))?;
+    }
+    if threshold_cents.is_some_and(|c| c < 0) {
+        return Err(CommandError::validation("Threshold cannot be negative"));
+    }
+
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let threshold_cents = match threshold_cents {
+        Some(cents) => cents,
+        None => aequi_storage::get_receipt_threshold_cents(&db).await?,
+    };
+    let unreceipted_transactions =
+        aequi_storage::get_unreceipted_expenses(&db, &start, &end, threshold_cents).await?;
+    let unlinked_receipts = aequi_storage::get_unlinked_approved_receipts(&db, &start, &end)
+        .await?
+        .into_iter()
+        .map(ReceiptOutput::from)
+        .collect();
+    Ok(DocumentationGapsOutput {
+        threshold_cents,
+        unreceipted_transactions,
+        unlinked_receipts,
+    })
+}
+
+/// Approve a receipt, optionally linking it to an existing transaction.
+#[tauri::command]
+pub async fn approve_receipt(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    receipt_id: i64,
+    transaction_id: Option<i64>,
+) -> Result<(), CommandError> {
+    let (db, pipeline) = {
+        let s = state.lock().await;
+        (s.db.clone(), s.pipeline.clone())
+    };
+
+    // Validate receipt exists and is still pending
+    let receipt = aequi_storage::get_receipt_by_id(&db, receipt_id)
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))?
+        .ok_or_else(|| CommandError::not_found("Receipt not found"))?;
+    if receipt.status != "pending_review" {
+        return Err(CommandError::validation(format!(
+            "Receipt is already {} — cannot approve",
+            receipt.status
+        )));
+    }
+
+    if let Some(tx_id) = transaction_id {
+        // Validate transaction exists
+        if !aequi_storage::transaction_exists(&db, tx_id).await? {
+            return Err(CommandError::not_found("Transaction not found"));
+        }
+
+        aequi_storage::link_receipt_to_transaction(&db, receipt_id, tx_id).await?;
+    } else {
+        aequi_storage::update_receipt_status(&db, receipt_id, ReceiptStatus::Approved).await?;
+    }
+
+    learn_vendor(&db, &pipeline, &receipt).await;
+    crate::hooks::receipt_approved(&db, receipt_id, false);
+    Ok(())
+}
+
+/// Teach the extractor about an approved receipt's vendor. Best effort: the
+/// approval itself has already succeeded.
+async fn learn_vendor(
+    db: &aequi_storage::DbPool,
+    pipeline: &aequi_ocr::ReceiptPipeline,
+    receipt: &aequi_storage::ReceiptRecord,
+) {
+    if record_vendor_profile(db, receipt).await {
+        pipeline.set_vendor_dictionary(load_vendor_dictionary(db).await);
+    }
+}
+
+/// Record an approved receipt's vendor profile. Returns whether the learned
+/// vendors changed.
+async fn record_vendor_profile(
+    db: &aequi_storage::DbPool,
+    receipt: &aequi_storage::ReceiptRecord,
+) -> bool {
+    let Some(vendor) = receipt.vendor.as_deref() else {
+        return false;
+    };
+    let profile = VendorProfile::from_approved_receipt(
+        vendor,
+        receipt.ocr_text.as_deref(),
+        receipt.subtotal_cents,
+        receipt.tax_cents,
+        receipt.total_cents,
+    );
+    match aequi_storage::record_vendor_approval(
+        db,
+        &profile.name,
+        profile.tax_rate,
+        profile.total_label.as_deref(),
+    )
+    .await
+    {
+        Ok(()) => true,
+        Err(e) => {
+            tracing::warn!("Failed to record vendor profile: {e}");
+            false
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct BulkReviewOutput {
+    pub updated: Vec<i64>,
+    /// Receipts that were missing or no longer awaiting review.
+    pub skipped: Vec<i64>,
+}
+
+impl BulkReviewOutput {
+    fn new(ids: &[i64], updated: Vec<i64>) -> Self {
+        let skipped = ids
+            .iter()
+            .copied()
+            .filter(|id| !updated.contains(id))
+            .collect();
+        BulkReviewOutput { updated, skipped }
+    }
+}
+
+/// Approve many pending receipts at once, without linking transactions.
+#[tauri::command]
+pub async fn bulk_approve_receipts(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    ids: Vec<i64>,
+) -> Result<BulkReviewOutput, CommandError> {
+    let (db, pipeline) = {
+        let s = state.lock().await;
+        (s.db.clone(), s.pipeline.clone())
+    };
+    let updated =
+        aequi_storage::set_pending_receipts_status(&db, &ids, ReceiptStatus::Approved).await?;
+
+    // The vendor dictionary is rebuilt once for the whole batch.
+    let mut learned = false;
+    for &id in &updated {
+        if let Some(receipt) = aequi_storage::get_receipt_by_id(&db, id).await? {
+            learned |= record_vendor_profile(&db, &receipt).await;
+        }
+        crate::hooks::receipt_approved(&db, id, false);
+    }
+    if learned {
+        pipeline.set_vendor_dictionary(load_vendor_dictionary(&db).await);
+    }
+    Ok(BulkReviewOutput::new(&ids, updated))
+}
+
+/// Reject many pending receipts at once.
+#[tauri::command]
+pub async fn bulk_reject_receipts(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    ids: Vec<i64>,
+) -> Result<BulkReviewOutput, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let updated =
+        aequi_storage::set_pending_receipts_status(&db, &ids, ReceiptStatus::Rejected).await?;
+    Ok(BulkReviewOutput::new(&ids, updated))
+}
+
+/// A receipt line item booked to a different expense account than the rest
+/// of the receipt.
+#[derive(Debug, Deserialize)]
+pub struct ReceiptSplitInput {
+    pub line_item_id: i64,
+    pub account_code: String,
+}
+
+pub(crate) async fn account_id_for_code(
+    db: &aequi_storage::DbPool,
+    code: &str,
+) -> Result<aequi_core::AccountId, CommandError> {
+    aequi_storage::get_account_by_code(db, code)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("Account not found: {code}")))?
+        .id
+        .ok_or_else(|| CommandError::internal("Account missing ID"))
+}
+
+/// Line items posted on their own: items with an amount, the account each
+/// goes to, and the item description as memo. Whatever the items don't
+/// cover (tax, fees, rounding) stays on `account_code`.
+async fn receipt_split_lines(
+    db: &aequi_storage::DbPool,
+    receipt_id: i64,
+    account_code: &str,
+    total_cents: i64,
+    splits: &[ReceiptSplitInput],
+) -> Result<Vec<(String, i64, Option<String>)>, CommandError> {
+    let items = aequi_storage::get_receipt_line_items(db, receipt_id).await?;
+    if let Some(split) = splits
+        .iter()
+        .find(|s| !items.iter().any(|i| i.id == s.line_item_id))
+    {
+        return Err(CommandError::not_found(format!(
+            "Line item {} is not on this receipt",
+            split.line_item_id
+        )));
+    }
+
+    let mut lines = Vec::new();
+    for item in items {
+        let Some(amount) = item.amount_cents.filter(|a| *a > 0) else {
+            continue;
+        };
+        let code = splits
+            .iter()
+            .find(|s| s.line_item_id == item.id)
+            .map_or(account_code, |s| s.account_code.as_str());
+        lines.push((code.to_string(), amount, Some(item.description)));
+    }
+    if lines.is_empty() {
+        return Err(CommandError::validation(
+            "Receipt has no line items with amounts to split by",
+        ));
+    }
+
+    let itemized: i64 = lines.iter().map(|(_, cents, _)| cents).sum();
+    if itemized > total_cents {
+        let money = money_format(db).await?;
+        return Err(CommandError::validation(format!(
+            "Line items add up to {}, more than the receipt total of {}",
+            money.format_cents(itemized),
+            money.format_cents(total_cents)
+        )));
+    }
+    if itemized < total_cents {
+        lines.push((
+            account_code.to_string(),
+            total_cents - itemized,
+            Some("Tax and other charges".to_string()),
+        ));
+    }
+    Ok(lines)
+}
+
+/// Post an expense transaction for a receipt and link the two. The receipt
+/// total is debited to `account_code` and credited to the account it was
+/// paid from: `credit_account_code` if given, otherwise the account its
+/// payment method is mapped to. With
+/// `split_line_items`, each extracted line item becomes its own debit line,
+/// booked to the account given in `splits` if any. Refunds post the other
+/// way round.
+#[tauri::command]
+pub async fn create_transaction_from_receipt(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    receipt_id: i64,
+    account_code: String,
+    credit_account_code: Option<String>,
+    split_line_items: Option<bool>,
+    splits: Option<Vec<ReceiptSplitInput>>,
+) -> Result<TransactionOutput, CommandError> {
+    let (db, pipeline) = {
+        let s = state.lock().await;
+        (s.db.clone(), s.pipeline.clone())
+    };
+
+    let receipt = aequi_storage::get_receipt_by_id(&db, receipt_id)
+        .await?
+        .ok_or_else(|| CommandError::not_found("Receipt not found"))?;
+    if receipt.transaction_id.is_some() {
+        return Err(CommandError::validation(
+            "Receipt is already linked to a transaction",
+        ));
+    }
+    if !matches!(receipt.status.as_str(), "pending_review" | "approved") {
+        return Err(CommandError::validation(format!(
+            "Receipt is {} — cannot create a transaction from it",
+            receipt.status
+        )));
+    }
+
+    let total_cents = receipt
+        .total_cents
+        .filter(|t| *t != 0)
+        .ok_or_else(|| CommandError::validation("Receipt has no total"))?;
+    let date = receipt
+        .receipt_date
+        .as_deref()
+        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+        .ok_or_else(|| CommandError::validation("Receipt has no date"))?;
+    let description = receipt
+        .vendor
+        .clone()
+        .filter(|v| !v.trim().is_empty())
+        .unwrap_or_else(|| format!("Receipt #{receipt_id}"));
+
+    let amount = total_cents.abs();
+    let refund = total_cents < 0;
+    let splits = splits.unwrap_or_default();
+    let debits = if split_line_items.unwrap_or(false) || !splits.is_empty() {
+        receipt_split_lines(&db, receipt_id, &account_code, amount, &splits).await?
+    } else {
+        vec![(account_code.clone(), amount, None)]
+    };
+    let credit_code = match credit_account_code {
+        Some(code) => code,
+        None => aequi_storage::get_payment_account_map(&db)
+            .await?
+            .account_for(receipt.payment_method.as_deref())
+            .map(str::to_string)
+            .ok_or_else(|| {
+                CommandError::validation(format!(
+                    "No account is mapped to payment method {} — choose the account it was paid from",
+                    receipt.payment_method.as_deref().unwrap_or("(none)")
+                ))
+            })?,
+    };
+
+    let mut lines = Vec::new();
+    for (code, cents, memo) in debits {
+        let (debit, credit) = if refund { (0, cents) } else { (cents, 0) };
+        lines.push(TransactionLine {
+            account_id: account_id_for_code(&db, &code).await?,
+            debit: Money::from_cents(debit),
+            credit: Money::from_cents(credit),
+            memo,
+        });
+    }
+    let (debit, credit) = if refund { (amount, 0) } else { (0, amount) };
+    lines.push(TransactionLine {
+        account_id: account_id_for_code(&db, &credit_code).await?,
+        debit: Money::from_cents(debit),
+        credit: Money::from_cents(credit),
+        memo: receipt.payment_method.clone(),
+    });
+
+    let validated = ValidatedTransaction::validate(UnvalidatedTransaction {
+        date,
+        description,
+        lines,
+        memo: receipt.reference.clone(),
+    })?;
+
+    // A personal or partly deductible receipt makes the same transaction.
+    let deductibility = Deductibility {
+        is_personal: receipt.is_personal,
+        deductible_percent: receipt.deductible_percent.clamp(0, 100) as u8,
+    };
+    let (db_ref, validated) = (&db, &validated);
+    let record = aequi_storage::with_retry(&Default::default(), move || async move {
+        let mut sql_tx = db_ref.begin().await?;
+        let record =
+            aequi_storage::insert_transaction(&mut sql_tx, validated, deductibility).await?;
+        aequi_storage::mark_receipt_posted(&mut sql_tx, receipt_id, record.id).await?;
+        sql_tx.commit().await?;
+        Ok(record)
+    })
+    .await?;
+    let mut output = TransactionOutput::new(record, &money_format(&db).await?);
+    output.capitalize_prompt = fixed_assets::should_offer_capitalize(&db, output.id).await?;
+
+    crate::hooks::transaction_posted(&db, output.id, TransactionSource::Receipt);
+    if receipt.status == "pending_review" {
+        learn_vendor(&db, &pipeline, &receipt).await;
+        crate::hooks::receipt_approved(&db, receipt_id, false);
+    }
+    Ok(output)
+}
+
+/// The line items extracted from a receipt.
+#[tauri::command]
+pub async fn get_receipt_line_items(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    receipt_id: i64,
+) -> Result<Vec<aequi_storage::ReceiptLineItemRecord>, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    Ok(aequi_storage::get_receipt_line_items(&db, receipt_id).await?)
+}
+
+/// Reject a receipt (marks it as not usable / duplicate). A receipt linked
+/// to a transaction has to be unlinked first.
+#[tauri::command]
+pub async fn reject_receipt(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    receipt_id: i64,
+) -> Result<(), CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    aequi_storage::update_receipt_status(&db, receipt_id, ReceiptStatus::Rejected).await?;
+    Ok(())
+}
+
+/// Take an approved receipt off the transaction or bank row it is linked
+/// to. The transaction itself is kept.
+#[tauri::command]
+pub async fn unlink_receipt(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    receipt_id: i64,
+) -> Result<(), CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    aequi_storage::receipt_status::unlink_receipt(&db, receipt_id).await?;
+    Ok(())
+}
+
+/// Save the user's corrections to a receipt's vendor, date (YYYY-MM-DD) or
+/// total. Each field passed counts as reviewed, even when unchanged, so
+/// confirming a value records that extraction got it right.
+#[tauri::command]
+pub async fn correct_receipt_fields(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    receipt_id: i64,
+    vendor: Option<String>,
+    receipt_date: Option<String>,
+    total_cents: Option<i64>,
+) -> Result<ReceiptOutput, CommandError> {
+    let vendor = vendor.map(|v| v.trim().to_string());
+    if vendor.as_deref() == Some("") {
+        return Err(CommandError::validation("Vendor cannot be empty"));
+    }
+    if let Some(date) = &receipt_date {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| CommandError::validation(format!("Invalid date: {date}")))?;
+    }
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+
+    let found = aequi_storage::correct_receipt_fields(
+        &db,
+        receipt_id,
+        vendor.as_deref(),
+        receipt_date.as_deref(),
+        total_cents,
+    )
+    .await?;
+    if !found {
+        return Err(CommandError::not_found("Receipt not found"));
+    }
+    let record = aequi_storage::get_receipt_by_id(&db, receipt_id)
+        .await?
+        .ok_or_else(|| CommandError::not_found("Receipt not found"))?;
+    Ok(record.into())
+}
+
+#[derive(Debug, Serialize)]
+pub struct UpdatedReceiptOutput {
+    pub receipt: ReceiptOutput,
+    /// Other receipts with the same vendor, date and total after the edit.
+    pub possible_duplicates: Vec<i64>,
+}
+
+/// Edit a receipt that is still awaiting review: vendor, date (YYYY-MM-DD),
+/// amounts, payment method, reference and line items. Transactions created
+/// from the receipt afterwards use the edited values.
+#[tauri::command]
+pub async fn update_receipt(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    receipt_id: i64,
+    mut fields: aequi_storage::ReceiptUpdate,
+) -> Result<UpdatedReceiptOutput, CommandError> {
+    fields.vendor = fields.vendor.map(|v| v.trim().to_string());
+    if fields.vendor.as_deref() == Some("") {
+        return Err(CommandError::validation("Vendor cannot be empty"));
+    }
+    if let Some(date) = &fields.receipt_date {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| CommandError::validation(format!("Invalid date: {date}")))?;
+    }
+    if fields.deductible_percent.is_some_and(|p| p > 100) {
+        return Err(CommandError::validation(
+            "Deductible percentage must be between 0 and 100",
+        ));
+    }
+    fields.payment_method = fields.payment_method.map(|m| m.trim().to_string());
+    fields.reference = fields.reference.map(|r| r.trim().to_string());
+    if let Some(items) = &mut fields.line_items {
+        for item in items.iter_mut() {
+            item.description = item.description.trim().to_string();
+            if item.description.is_empty() {
+                return Err(CommandError::validation(
+                    "Line item description is required",
+                ));
+            }
+        }
+    }
+
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let receipt = aequi_storage::get_receipt_by_id(&db, receipt_id)
+        .await?
+        .ok_or_else(|| CommandError::not_found("Receipt not found"))?;
+    if receipt.status != "pending_review" {
+        return Err(CommandError::validation(format!(
+            "Receipt is already {} — cannot edit",
+            receipt.status
+        )));
+    }
+
+    aequi_storage::update_receipt(&db, receipt_id, &fields).await?;
+    let record = aequi_storage::get_receipt_by_id(&db, receipt_id)
+        .await?
+        .ok_or_else(|| CommandError::not_found("Receipt not found"))?;
+    let possible_duplicates =
+        aequi_storage::find_possible_duplicate_receipts(&db, receipt_id).await?;
+    Ok(UpdatedReceiptOutput {
+        receipt: record.into(),
+        possible_duplicates,
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct FieldAccuracyOutput {
+    pub field: String,
+    pub reviewed: i64,
+    /// Reviews where the original extraction was already right.
+    pub correct: i64,
+    /// How many of the same reviews the current extractor gets right.
+    pub current_correct: i64,
+}
+
+/// Extraction accuracy per field over every user review, both as extracted
+/// at the time and as the current extractor would do on the same receipts.
+#[tauri::command]
+pub async fn get_extraction_accuracy(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Vec<FieldAccuracyOutput>, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let stored = aequi_storage::get_extraction_accuracy(&db).await?;
+    let corrections = aequi_storage::get_receipt_corrections(&db).await?;
+
+    let mut cases: std::collections::BTreeMap<i64, aequi_ocr::CorrectionCase> =
+        std::collections::BTreeMap::new();
+    for c in corrections {
+        let (Some(ocr_text), Some(value)) = (c.ocr_text, c.corrected_value) else {
+            continue;
+        };
+        let case = cases
+            .entry(c.receipt_id)
+            .or_insert_with(|| aequi_ocr::CorrectionCase {
+                ocr_text,
+                ..Default::default()
+            });
+        match c.field.as_str() {
+            "vendor" => case.vendor = Some(value),
+            "date" => case.date = NaiveDate::parse_from_str(&value, "%Y-%m-%d").ok(),
+            "total" => case.total_cents = value.parse().ok(),
+            _ => {}
+        }
+    }
+    let cases: Vec<_> = cases.into_values().collect();
+    let current = aequi_ocr::evaluate::evaluate(
+        &cases,
+        &load_extract_options(&db).await,
+        &load_vendor_dictionary(&db).await,
+    );
+
+    // Only the fields the extractor is scored on; payment method and the
+    // like are corrected too but not evaluated.
+    Ok(stored
+        .into_iter()
+        .filter_map(|s| {
+            let current = current.iter().find(|c| c.field == s.field)?;
+            Some(FieldAccuracyOutput {
+                current_correct: current.correct as i64,
+                field: s.field,
+                reviewed: s.reviewed,
+                correct: s.correct,
+            })
+        })
+        .collect())
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ReprocessSummary {
+    pub processed: usize,
+    pub updated: usize,
+    /// Reviewed receipts whose changes are waiting in the diff queue.
+    pub queued: usize,
+    pub unchanged: usize,
+    pub failed: usize,
+}
+
+/// Re-run OCR and extraction over stored receipts, e.g. after changing the
+/// OCR backend or extractor. Receipts still pending review take the new
+/// fields; reviewed ones keep theirs and queue the differences for review.
+#[tauri::command]
+pub async fn reprocess_receipts(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    filter: Option<aequi_storage::ReprocessFilter>,
+) -> Result<ReprocessSummary, CommandError> {
+    let (db, pipeline) = {
+        let s = state.lock().await;
+        (s.db.clone(), s.pipeline.clone())
+    };
+    async {
+        let receipts =
+            aequi_storage::get_receipts_for_reprocess(&db, &filter.unwrap_or_default()).await?;
+
+        let mut summary = ReprocessSummary::default();
+        let root = pipeline.attachments_dir();
+        for receipt in receipts {
+            summary.processed += 1;
+            let result = match pipeline
+                .reprocess_file(&resolve_path(&root, &receipt.attachment_path))
+                .await
+            {
+                Ok(result) => result,
+                Err(e) => {
+                    tracing::warn!("Re-processing receipt {} failed: {e}", receipt.id);
+                    summary.failed += 1;
+                    continue;
+                }
+            };
+            let e = &result.extracted;
+            let reextracted = aequi_storage::ReextractedReceipt {
+                ocr_text: result.ocr_text.clone(),
+                vendor: e.vendor.as_ref().map(|f| f.value.clone()),
+                receipt_date: e.date.as_ref().map(|f| f.value.to_string()),
+                total_cents: e.total_cents.as_ref().map(|f| f.value),
+                subtotal_cents: e.subtotal_cents.as_ref().map(|f| f.value),
+                tax_cents: e.tax_cents.as_ref().map(|f| f.value),
+                payment_method: e.payment_method.as_ref().map(|f| f.value.to_string()),
+                reference: e.reference.as_ref().map(|f| f.value.clone()),
+                confidence: e.confidence as f64,
+            };
+            match aequi_storage::apply_reprocessed_receipt(&db, receipt.id, &reextracted).await? {
+                aequi_storage::ReprocessOutcome::Updated => summary.updated += 1,
+                aequi_storage::ReprocessOutcome::Queued => summary.queued += 1,
+                aequi_storage::ReprocessOutcome::Unchanged => summary.unchanged += 1,
+            }
+        }
+        Ok::<_, CommandError>(summary)
+    }
+    .instrument(crate::logging::command_span("reprocess_receipts"))
+    .await
+}
+
+/// Field changes from re-processing that wait on the user because the
+/// receipt had already been reviewed.
+#[tauri::command]
+pub async fn get_reprocess_diffs(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Vec<aequi_storage::ReceiptReprocessDiff>, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    Ok(aequi_storage::get_pending_reprocess_diffs(&db).await?)
+}
+
+/// Accept a queued re-processing change into its receipt, or dismiss it.
+#[tauri::command]
+pub async fn resolve_reprocess_diff(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    diff_id: i64,
+    accept: bool,
+) -> Result<(), CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    if !aequi_storage::resolve_reprocess_diff(&db, diff_id, accept).await? {
+        return Err(CommandError::not_found("Pending change not found"));
+    }
+    Ok(())
+}
+
+/// Suggest links between approved receipts and ledger or imported bank
+/// transactions.
+#[tauri::command]
+pub async fn suggest_receipt_matches(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    date_window_days: Option<i32>,
+    tolerance_cents: Option<i64>,
+) -> Result<Vec<aequi_import::ReceiptMatchSuggestion>, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let engine = aequi_import::AutoMatchEngine::new(
+        date_window_days.unwrap_or(3).clamp(0, 30),
+        0.5,
+        tolerance_cents.unwrap_or(0).max(0),
+    );
+    let suggestions = aequi_storage::find_receipt_match_suggestions(&db, &engine).await?;
+    Ok(suggestions)
+}
+
+/// Confirm a suggested link, attaching the receipt to the transaction.
+#[tauri::command]
+pub async fn confirm_receipt_match(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    receipt_id: i64,
+    target: aequi_import::ReceiptLinkTarget,
+) -> Result<(), CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+
+    let receipt = aequi_storage::get_receipt_by_id(&db, receipt_id)
+        .await?
+        .ok_or_else(|| CommandError::not_found("Receipt not found"))?;
+    if receipt.status == "rejected" {
+        return Err(CommandError::validation(
+            "Rejected receipts cannot be linked",
+        ));
+    }
+
+    if !aequi_storage::receipt_link_target_exists(&db, target).await? {
+        return Err(CommandError::not_found("Transaction not found"));
+    }
+
+    aequi_storage::confirm_receipt_match(&db, receipt_id, target).await?;
+    Ok(())
+}
+
+/// The auto-approval settings.
+#[tauri::command]
+pub async fn get_auto_approve_settings(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<aequi_storage::AutoApproveSettings, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    Ok(aequi_storage::get_auto_approve_settings(&db).await?)
+}
+
+/// Save the auto-approval settings. Turning it on also runs it over the
+/// receipts already waiting for review; returns how many were approved.
+#[tauri::command]
+pub async fn set_auto_approve_settings(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    settings: aequi_storage::AutoApproveSettings,
+) -> Result<usize, CommandError> {
+    if !(0.0..=1.0).contains(&settings.min_confidence)
+        || !(0.0..=1.0).contains(&settings.min_match_confidence)
+    {
+        return Err(CommandError::validation(
+            "Confidence thresholds must be between 0 and 1",
+        ));
+    }
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    aequi_storage::set_auto_approve_settings(&db, &settings).await?;
+    crate::auto_approve::run(&db).await
+}
+
+/// Receipts approved automatically in the last `hours` (default 24), newest
+/// first, including ones since undone.
+#[tauri::command]
+pub async fn get_auto_approvals(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    hours: Option<i64>,
+) -> Result<Vec<aequi_storage::AutoApprovalRecord>, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let since = chrono::Utc::now() - chrono::Duration::hours(hours.unwrap_or(24).max(1));
+    Ok(
+        aequi_storage::get_auto_approvals(&db, &since.format("%Y-%m-%d %H:%M:%S").to_string())
+            .await?,
+    )
+}
+
+/// Undo an auto-approval: the receipt goes back to review, and a
+/// transaction posted from it is deleted.
+#[tauri::command]
+pub async fn undo_auto_approval(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+) -> Result<(), CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    if !aequi_storage::undo_auto_approval(&db, id).await? {
+        return Err(CommandError::not_found(
+            "Auto-approval not found or already undone",
+        ));
+    }
+    Ok(())
+}
+
+// ── Attachment commands ─────────────────────────────────────────────────────
+
+/// A document's bytes after checking and storing them.
+struct StoredDocument {
+    file_hash: String,
+    ext: &'static str,
+    size_bytes: i64,
+    path: String,
+}
+
+/// Check a document's bytes and put them in the content-addressed store,
+/// where identical files share one copy.
+async fn store_document(
+    pipeline: &aequi_ocr::ReceiptPipeline,
+    data: Vec<u8>,
+) -> Result<StoredDocument, CommandError> {
+    if data.is_empty() {
+        return Err(CommandError::validation("Document is empty"));
+    }
+    if data.len() as u64 > MAX_RECEIPT_SIZE {
+        return Err(receipt_too_large(data.len() as u64));
+    }
+    let ext = aequi_ocr::sniff_extension(&data).ok_or_else(|| {
+        CommandError::validation("Unsupported file type (expected a PDF or image)")
+    })?;
+    let size_bytes = data.len() as i64;
+    let dir = pipeline.attachments_dir();
+    let (file_hash, _) =
+        tokio::task::spawn_blocking(move || aequi_ocr::store_attachment(&dir, &data, ext))
+            .await
+            .map_err(|e| CommandError::internal(e.to_string()))?
+            .map_err(|e| CommandError::internal(format!("Cannot store document: {e}")))?;
+    Ok(StoredDocument {
+        file_hash,
+        ext,
+        size_bytes,
+        path: aequi_ocr::hash::relative_path(&file_hash, ext),
+    })
+}
+
+/// Read a stored file, refusing anything outside the attachment store
+/// whatever the row says.
+async fn read_stored_file(
+    pipeline: &aequi_ocr::ReceiptPipeline,
+    attachment_path: &str,
+) -> Result<Vec<u8>, CommandError> {
+    let root = pipeline.attachments_dir();
+    let path = tokio::fs::canonicalize(resolve_path(&root, attachment_path))
+        .await
+        .map_err(|_| CommandError::not_found("Document file is missing"))?;
+    let root = tokio::fs::canonicalize(&root)
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))?;
+    if !path.starts_with(&root) {
+        return Err(CommandError::validation(
+            "Document file is outside the attachment store",
+        ));
+    }
+    tokio::fs::read(&path)
+        .await
+        .map_err(|e| CommandError::internal(format!("Cannot read document file: {e}")))
+}
+
+/// Delete a stored file nothing refers to any more.
+async fn remove_stored_file(pipeline: &aequi_ocr::ReceiptPipeline, attachment_path: &str) {
+    let root = pipeline.attachments_dir();
+    let path = resolve_path(&root, attachment_path);
+    if path.starts_with(&root) {
+        if let Err(e) = tokio::fs::remove_file(&path).await {
+            tracing::warn!("Could not remove document file {}: {e}", path.display());
+        }
+    }
+}
+
+fn optional_text(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+async fn check_entity_exists(
+    db: &aequi_storage::DbPool,
+    entity: AttachmentEntity,
+) -> Result<(), CommandError> {
+    if entity.exists(db).await? {
+        Ok(())
+    } else {
+        Err(CommandError::not_found(format!(
+            "No {} with id {}",
+            entity.entity_type(),
+            entity.id()
+        )))
+    }
+}
+
+/// Attach a document (a contract, warranty or statement) to a transaction
+/// or account. The file goes into the same content-addressed store as
+/// receipts, so attaching identical bytes twice keeps one copy.
+#[tauri::command]
+pub async fn attach_document(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    entity: AttachmentEntity,
+    data: Vec<u8>,
+    file_name: Option<String>,
+) -> Result<Attachment, CommandError> {
+    let (db, pipeline) = {
+        let s = state.lock().await;
+        (s.db.clone(), s.pipeline.clone())
+    };
+    check_entity_exists(&db, entity).await?;
+    let stored = store_document(&pipeline, data).await?;
+    let file_name = optional_text(file_name);
+    let id = attachments::insert_attachment(
+        &db,
+        &attachments::NewAttachment {
+            entity,
+            file_hash: &stored.file_hash,
+            file_ext: stored.ext,
+            file_name: file_name.as_deref(),
+            size_bytes: stored.size_bytes,
+            attachment_path: &stored.path,
+        },
+    )
+    .await?;
+    attachments::get_attachment(&db, id)
+        .await?
+        .ok_or_else(|| CommandError::internal("Attachment not found after insert"))
+}
+
+#[tauri::command]
+pub async fn get_attachments(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    entity: AttachmentEntity,
+) -> Result<Vec<Attachment>, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    Ok(attachments::get_attachments(&db, entity).await?)
+}
+
+/// The attached file's bytes, served only from the attachment store.
+#[tauri::command]
+pub async fn get_attachment_file(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+) -> Result<tauri::ipc::Response, CommandError> {
+    let (db, pipeline) = {
+        let s = state.lock().await;
+        (s.db.clone(), s.pipeline.clone())
+    };
+    let attachment = attachments::get_attachment(&db, id)
+        .await?
+        .ok_or_else(|| CommandError::not_found("Attachment not found"))?;
+    let data = read_stored_file(&pipeline, &attachment.attachment_path).await?;
+    Ok(tauri::ipc::Response::new(data))
+}
+
+/// Remove an attachment. Its file is deleted too, unless something else
+/// stored has the same content.
+#[tauri::command]
+pub async fn delete_attachment(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+) -> Result<(), CommandError> {
+    let (db, pipeline) = {
+        let s = state.lock().await;
+        (s.db.clone(), s.pipeline.clone())
+    };
+    let (attachment, in_use) = attachments::delete_attachment(&db, id)
+        .await?
+        .ok_or_else(|| CommandError::not_found("Attachment not found"))?;
+    if !in_use {
+        remove_stored_file(&pipeline, &attachment.attachment_path).await;
+    }
+    Ok(())
+}
+
+/// Where attachments are stored and how photos are shrunk on the way in.
+#[derive(Debug, Serialize)]
+pub struct AttachmentStoreSettings {
+    pub dir: String,
+    pub max_side: Option<u32>,
+    pub jpeg_quality: u8,
+}
+
+#[tauri::command]
+pub async fn get_attachment_store_settings(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<AttachmentStoreSettings, CommandError> {
+    let (db, pipeline) = {
+        let s = state.lock().await;
+        (s.db.clone(), s.pipeline.clone())
+    };
+    let limits = load_image_limits(&db).await;
+    Ok(AttachmentStoreSettings {
+        dir: pipeline.attachments_dir().to_string_lossy().into_owned(),
+        max_side: limits.map(|l| l.max_side),
+        jpeg_quality: limits.map_or(DEFAULT_JPEG_QUALITY, |l| l.jpeg_quality),
+    })
+}
+
+/// Set how far photos are scaled down before they're stored. `None` for
+/// `max_side` keeps originals as they came. Applies to receipts stored
+/// from now on.
+#[tauri::command]
+pub async fn set_attachment_image_limits(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    max_side: Option<u32>,
+    jpeg_quality: Option<u8>,
+) -> Result<(), CommandError> {
+    use aequi_storage::attachment_store::{JPEG_QUALITY_SETTING, MAX_SIDE_SETTING};
+    let (db, pipeline) = {
+        let s = state.lock().await;
+        (s.db.clone(), s.pipeline.clone())
+    };
+    if max_side.is_some_and(|side| side < MIN_STORED_SIDE) {
+        return Err(CommandError::validation(format!(
+            "Photos can't be scaled below {MIN_STORED_SIDE} pixels; receipts would become unreadable"
+        )));
+    }
+    if let Some(quality) = jpeg_quality {
+        if !JPEG_QUALITY_RANGE.contains(&quality) {
+            return Err(CommandError::validation(format!(
+                "JPEG quality must be between {} and {}",
+                JPEG_QUALITY_RANGE.start(),
+                JPEG_QUALITY_RANGE.end()
+            )));
+        }
+        aequi_storage::set_setting(&db, JPEG_QUALITY_SETTING, &quality.to_string()).await?;
+    }
+    let max_side = max_side.map(|side| side.to_string()).unwrap_or_default();
+    aequi_storage::set_setting(&db, MAX_SIDE_SETTING, &max_side).await?;
+    pipeline.set_image_limits(load_image_limits(&db).await);
+    Ok(())
+}
+
+/// Only one move of the attachment store at a time.
+static STORE_MOVE: Mutex<()> = Mutex::const_new(());
+
+/// Move the attachment store to `dir`, such as a folder on an external
+/// drive or NAS. Files are copied and the ledger repointed before anything
+/// is deleted; the old copies are removed last, and only those that made
+/// it across.
+#[tauri::command]
+pub async fn move_attachment_store(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    dir: String,
+) -> Result<aequi_storage::attachment_store::StoreMove, CommandError> {
+    use aequi_storage::attachment_store;
+    let _moving = STORE_MOVE
+        .try_lock()
+        .map_err(|_| CommandError::validation("The attachment store is already being moved"))?;
+    let (db, pipeline) = {
+        let s = state.lock().await;
+        (s.db.clone(), s.pipeline.clone())
+    };
+    let (from, to) = (pipeline.attachments_dir(), PathBuf::from(dir.trim()));
+
+    let mut moved = attachment_store::move_attachment_store(&db, &from, &to).await?;
+    pipeline.set_attachments_dir(to.clone());
+    state.lock().await.attachments_dir = to.clone();
+    // Receipts stored under the old folder while the first pass ran.
+    let late = attachment_store::move_attachment_store(&db, &from, &to).await?;
+    moved.files_copied += late.files_copied;
+    moved.bytes_copied += late.bytes_copied;
+    moved.rows_updated += late.rows_updated;
+
+    let (old, new) = (from.clone(), to.clone());
+    match tokio::task::spawn_blocking(move || attachment_store::remove_moved_files(&old, &new))
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))?
+    {
+        Ok(removed) => tracing::info!(
+            "Moved the attachment store to {}; removed {removed} old files",
+            to.display()
+        ),
+        Err(e) => tracing::warn!(
+            "Moved the attachment store, but could not clear {}: {e}",
+            from.display()
+        ),
+    }
+    Ok(moved)
+}
+
+// ── Document commands ───────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct DocumentInput {
+    pub doc_type: DocumentType,
+    pub title: String,
+    pub period_start: Option<NaiveDate>,
+    pub period_end: Option<NaiveDate>,
+    pub contact_id: Option<i64>,
+    pub entity: Option<AttachmentEntity>,
+    pub notes: Option<String>,
+    pub file_name: Option<String>,
+}
+
+/// File a document with its type, period, counterparty and, optionally,
+/// the transaction or account it belongs to.
+#[tauri::command]
+pub async fn file_document(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    input: DocumentInput,
+    data: Vec<u8>,
+) -> Result<Document, CommandError> {
+    let title = input.title.trim();
+    if title.is_empty() {
+        return Err(CommandError::validation("Document title is required"));
+    }
+    if let (Some(start), Some(end)) = (input.period_start, input.period_end) {
+        if start > end {
+            return Err(CommandError::validation("Period start is after its end"));
+        }
+    }
+    let (db, pipeline) = {
+        let s = state.lock().await;
+        (s.db.clone(), s.pipeline.clone())
+    };
+    if let Some(contact_id) = input.contact_id {
+        aequi_storage::get_contact_by_id(&db, contact_id)
+            .await?
+            .ok_or_else(|| CommandError::not_found("Contact not found"))?;
+    }
+    if let Some(entity) = input.entity {
+        check_entity_exists(&db, entity).await?;
+    }
+    let stored = store_document(&pipeline, data).await?;
+    let notes = optional_text(input.notes);
+    let file_name = optional_text(input.file_name);
+    let id = documents::insert_document(
+        &db,
+        &documents::NewDocument {
+            doc_type: input.doc_type,
+            title,
+            period_start: input.period_start,
+            period_end: input.period_end,
+            contact_id: input.contact_id,
+            entity: input.entity,
+            notes: notes.as_deref(),
+            file_hash: &stored.file_hash,
+            file_ext: stored.ext,
+            file_name: file_name.as_deref(),
+            size_bytes: stored.size_bytes,
+            attachment_path: &stored.path,
+        },
+    )
+    .await?;
+    documents::get_document(&db, id)
+        .await?
+        .ok_or_else(|| CommandError::internal("Document not found after insert"))
+}
+
+#[tauri::command]
+pub async fn search_documents(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    query: DocumentSearch,
+) -> Result<Vec<Document>, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    Ok(documents::search_documents(&db, &query).await?)
+}
+
+#[tauri::command]
+pub async fn get_document_file(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+) -> Result<tauri::ipc::Response, CommandError> {
+    let (db, pipeline) = {
+        let s = state.lock().await;
+        (s.db.clone(), s.pipeline.clone())
+    };
+    let document = documents::get_document(&db, id)
+        .await?
+        .ok_or_else(|| CommandError::not_found("Document not found"))?;
+    let data = read_stored_file(&pipeline, &document.attachment_path).await?;
+    Ok(tauri::ipc::Response::new(data))
+}
+
+#[tauri::command]
+pub async fn delete_document(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+) -> Result<(), CommandError> {
+    let (db, pipeline) = {
+        let s = state.lock().await;
+        (s.db.clone(), s.pipeline.clone())
+    };
+    let (document, in_use) = documents::delete_document(&db, id)
+        .await?
+        .ok_or_else(|| CommandError::not_found("Document not found"))?;
+    if !in_use {
+        remove_stored_file(&pipeline, &document.attachment_path).await;
+    }
+    Ok(())
+}
+
+/// The fiscal year named `year`: twelve months from the configured start
+/// month of that calendar year.
+fn fiscal_year_period(year: i32, start_month: u32) -> Option<aequi_core::DateRange> {
+    let start = NaiveDate::from_ymd_opt(year, start_month, 1)?;
+    let end = start
+        .checked_add_months(chrono::Months::new(12))?
+        .pred_opt()?;
+    Some(aequi_core::DateRange::new(start, end))
+}
+
+/// Bundle a finished fiscal year into one zip: its documents, the files
+/// attached to its transactions, its receipts, and year-end reports as CSV,
+/// JSON and PDF, with Schedule C when tax rules for the year are available.
+#[tauri::command]
+pub async fn archive_fiscal_year(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    year: u16,
+    output_path: String,
+) -> Result<YearArchiveSummary, CommandError> {
+    let (db, attachments_dir) = {
+        let s = state.lock().await;
+        (s.db.clone(), s.attachments_dir.clone())
+    };
+    let prefs = aequi_storage::get_preferences(&db).await?;
+    let period = fiscal_year_period(year as i32, prefs.fiscal_year_start_month)
+        .ok_or_else(|| CommandError::validation(format!("Invalid fiscal year: {year}")))?;
+    if period.end >= chrono::Local::now().date_naive() {
+        return Err(CommandError::validation(format!(
+            "Fiscal year {year} runs until {} and is not closed yet",
+            period.end
+        )));
+    }
+
+    let mut tables = vec![
+        (
+            "profit_and_loss",
+            aequi_storage::reports::profit_loss(&db, period)
+                .await?
+                .table(),
+        ),
+        (
+            "balance_sheet",
+            aequi_storage::reports::balance_sheet(&db, period.end)
+                .await?
+                .table(),
+        ),
+    ];
+    let tax_year = period.end.year() as u16;
+    if let Ok(rules) = load_tax_rules(tax_year) {
+        let snapshot = aequi_storage::build_ledger_snapshot(&db, FiscalYear::new(tax_year), None)
+            .await
+            .map_err(|e| CommandError::internal(e.to_string()))?;
+        let preview = aequi_core::tax::engine::schedule_c_preview(&rules, &snapshot);
+        tables.push((
+            "schedule_c",
+            aequi_storage::reports::schedule_c_table(&preview),
+        ));
+    }
+    let money = prefs.money_format();
+    let extra_reports = tokio::task::spawn_blocking(move || {
+        let mut files = Vec::new();
+        for (name, table) in tables {
+            if name == "schedule_c" {
+                files.push((format!("{name}.csv"), table.to_csv().into_bytes()));
+            }
+            files.push((
+                format!("{name}.pdf"),
+                aequi_pdf::render_report_pdf(&table, &money)?,
+            ));
+        }
+        Ok::<_, String>(files)
+    })
+    .await
+    .map_err(|e| CommandError::internal(e.to_string()))?
+    .map_err(CommandError::internal)?;
+
+    documents::archive_fiscal_year(
+        &db,
+        &attachments_dir,
+        period,
+        extra_reports,
+        Path::new(&output_path),
+    )
+    .await
+    .map_err(|e| CommandError::internal(e.to_string()))
+}
+
+// ── OCR backend ─────────────────────────────────────────────────────────────
+
+/// Read the OCR settings (`ocr_backend`, `ocr_tessdata_dir`, `ocr_language`,
+/// `ocr_page_segmentation`, `ocr_char_whitelist`).
+///
+/// Without an explicit `ocr_tessdata_dir`, the app's own tessdata folder is
+/// used once a language has been downloaded into it; until then Tesseract
+/// falls back to `TESSDATA_PREFIX` or the system install.
+pub(crate) async fn load_ocr_config(
+    db: &aequi_storage::DbPool,
+    default_tessdata: &Path,
+) -> OcrConfig {
+    let setting = |key: &'static str| async move {
+        aequi_storage::get_setting(db, key)
+            .await
+            .ok()
+            .flatten()
+            .filter(|v| !v.trim().is_empty())
+    };
+
+    let mut config = OcrConfig::default();
+    if let Some(backend) = setting(aequi_storage::OCR_BACKEND_SETTING).await {
+        match OcrBackendKind::parse(&backend) {
+            Some(kind) => config.backend = kind,
+            None => tracing::warn!("Unknown ocr_backend setting {backend:?}, using default"),
+        }
+    }
+    if let Some(language) = setting("ocr_language").await {
+        config.language = language.trim().to_string();
+    } else if let Some(locale) = setting("receipt_locale").await {
+        config.language = aequi_ocr::locale::tesseract_languages(&locale);
+    }
+    config.tessdata_dir = match setting("ocr_tessdata_dir").await {
+        Some(dir) => Some(PathBuf::from(dir.trim())),
+        None => (!aequi_ocr::tessdata::installed_languages(default_tessdata).is_empty())
+            .then(|| default_tessdata.to_path_buf()),
+    };
+    if let Some(psm) = setting("ocr_page_segmentation").await {
+        match psm.trim().parse::<u8>() {
+            Ok(psm) if psm <= aequi_ocr::backend::MAX_PAGE_SEGMENTATION => {
+                config.page_segmentation = psm
+            }
+            _ => tracing::warn!("Invalid ocr_page_segmentation setting {psm:?}, using default"),
+        }
+    }
+    // "none" turns the whitelist off; unset picks one for the language.
+    config.char_whitelist = match setting("ocr_char_whitelist").await {
+        Some(chars) if chars.trim() == "none" => None,
+        Some(chars) => Some(chars),
+        None => OcrConfig::default_whitelist(&config.language),
+    };
+    config
+}
+
+/// Build the LLM receipt extractor from `receipt_llm_endpoint`,
+/// `receipt_llm_model`, `receipt_llm_api_key` and `receipt_llm_send_image`.
+/// `None` (regex extraction only) unless both endpoint and model are set.
+pub(crate) async fn load_llm_extractor(db: &aequi_storage::DbPool) -> Option<LlmExtractor> {
+    let setting = |key: &'static str| async move {
+        aequi_storage::get_setting(db, key)
+            .await
+            .ok()
+            .flatten()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+
+    let config = LlmExtractorConfig {
+        endpoint: setting("receipt_llm_endpoint").await?,
+        model: setting("receipt_llm_model").await?,
+        api_key: setting("receipt_llm_api_key").await,
+        send_image: setting("receipt_llm_send_image").await.as_deref() == Some("true"),
+        timeout_secs: 60,
+    };
+    match LlmExtractor::new(config) {
+        Ok(extractor) => Some(extractor),
+        Err(e) => {
+            tracing::warn!("LLM receipt extractor disabled: {e}");
+            None
+        }
+    }
+}
+
+/// Extraction options for the `receipt_locale` setting; US conventions when
+/// it is unset.
+pub(crate) async fn load_extract_options(db: &aequi_storage::DbPool) -> ExtractOptions {
+    match aequi_storage::get_setting(db, "receipt_locale").await {
+        Ok(Some(locale)) if !locale.trim().is_empty() => ExtractOptions::for_locale(&locale),
+        Ok(_) => ExtractOptions::default(),
+        Err(e) => {
+            tracing::warn!("Failed to load receipt_locale: {e}");
+            ExtractOptions::default()
+        }
+    }
+}
+
+/// How many intake receipts are OCR'd at once (`receipt_ocr_workers`).
+pub(crate) async fn load_intake_workers(db: &aequi_storage::DbPool) -> usize {
+    match aequi_storage::get_setting(db, "receipt_ocr_workers").await {
+        Ok(Some(n)) => match n.trim().parse::<usize>() {
+            Ok(n) if n > 0 => n,
+            _ => {
+                tracing::warn!("Ignoring invalid receipt_ocr_workers: {n}");
+                aequi_ocr::pipeline::default_intake_workers()
+            }
+        },
+        Ok(None) => aequi_ocr::pipeline::default_intake_workers(),
+        Err(e) => {
+            tracing::warn!("Failed to load receipt_ocr_workers: {e}");
+            aequi_ocr::pipeline::default_intake_workers()
+        }
+    }
+}
+
+/// Whether subfolders of the receipt intake folder are watched too
+/// (`receipt_intake_recursive`).
+pub(crate) async fn load_intake_recursive(db: &aequi_storage::DbPool) -> bool {
+    match aequi_storage::get_setting(db, "receipt_intake_recursive").await {
+        Ok(value) => value.is_some_and(|v| v.trim() == "true"),
+        Err(e) => {
+            tracing::warn!("Failed to load receipt_intake_recursive: {e}");
+            false
+        }
+    }
+}
+
+/// The receipt intake folder chosen during setup
+/// (`receipt_intake_folder`), or `default` if none was chosen or it can't
+/// be created.
+pub(crate) async fn load_intake_dir(db: &aequi_storage::DbPool, default: &Path) -> PathBuf {
+    let chosen = match aequi_storage::get_setting(
+        db,
+        aequi_storage::onboarding::INTAKE_FOLDER_SETTING,
+    )
+    .await
+    {
+        Ok(value) => value.filter(|v| !v.trim().is_empty()),
+        Err(e) => {
+            tracing::warn!("Failed to load receipt_intake_folder: {e}");
+            None
+        }
+    };
+    match chosen.map(PathBuf::from) {
+        Some(dir) => match std::fs::create_dir_all(&dir) {
+            Ok(()) => dir,
+            Err(e) => {
+                tracing::warn!(
+                    "Intake folder {} unavailable, using the default: {e}",
+                    dir.display()
+                );
+                default.to_path_buf()
+            }
+        },
+        None => default.to_path_buf(),
+    }
+}
+
+/// The attachment store moved with `move_attachment_store`
+/// (`attachments_dir`), or `default`. A configured folder that's missing,
+/// such as on a drive that isn't mounted, is still used but not created:
+/// receipts fail and wait to be retried rather than land on the local disk.
+pub(crate) async fn load_attachments_dir(db: &aequi_storage::DbPool, default: &Path) -> PathBuf {
+    let dir = match aequi_storage::get_setting(
+        db,
+        aequi_storage::attachment_store::ATTACHMENTS_DIR_SETTING,
+    )
+    .await
+    {
+        Ok(value) => value.filter(|v| !v.trim().is_empty()).map(PathBuf::from),
+        Err(e) => {
+            tracing::warn!("Failed to load attachments_dir: {e}");
+            None
+        }
+    };
+    match dir {
+        Some(dir) => {
+            if !dir.is_dir() {
+                tracing::error!("Attachment store {} is unavailable", dir.display());
+            }
+            dir
+        }
+        None => default.to_path_buf(),
+    }
+}
+
+/// Smallest longest side photos may be scaled down to before they're
+/// stored.
+const MIN_STORED_SIDE: u32 = 1000;
+const DEFAULT_JPEG_QUALITY: u8 = 85;
+const JPEG_QUALITY_RANGE: std::ops::RangeInclusive<u8> = 40..=95;
+
+/// How far photos are shrunk before they're stored (`attachment_max_side`,
+/// `attachment_jpeg_quality`); `None`, keeping originals, when no size is
+/// set.
+pub(crate) async fn load_image_limits(
+    db: &aequi_storage::DbPool,
+) -> Option<aequi_ocr::ImageLimits> {
+    use aequi_storage::attachment_store::{JPEG_QUALITY_SETTING, MAX_SIDE_SETTING};
+    let setting = |key: &'static str| async move {
+        aequi_storage::get_setting(db, key)
+            .await
+            .ok()
+            .flatten()
+            .filter(|v| !v.trim().is_empty())
+    };
+    let max_side = setting(MAX_SIDE_SETTING).await?;
+    let Ok(max_side) = max_side.trim().parse::<u32>() else {
+        tracing::warn!("Ignoring invalid {MAX_SIDE_SETTING}: {max_side}");
+        return None;
+    };
+    let jpeg_quality = match setting(JPEG_QUALITY_SETTING).await {
+        Some(q) => match q.trim().parse::<u8>() {
+            Ok(q) if JPEG_QUALITY_RANGE.contains(&q) => q,
+            _ => {
+                tracing::warn!("Ignoring invalid {JPEG_QUALITY_SETTING}: {q}");
+                DEFAULT_JPEG_QUALITY
+            }
+        },
+        None => DEFAULT_JPEG_QUALITY,
+    };
+    Some(aequi_ocr::ImageLimits {
+        max_side: max_side.max(MIN_STORED_SIDE),
+        jpeg_quality,
+    })
+}
+
+/// The built-in vendor list plus vendors learned from approved receipts.
+pub(crate) async fn load_vendor_dictionary(db: &aequi_storage::DbPool) -> VendorDictionary {
+    let learned = match aequi_storage::get_vendor_profiles(db).await {
+        Ok(rows) => rows,
+        Err(e) => {
+            tracing::warn!("Failed to load vendor profiles: {e}");
+            Vec::new()
+        }
+    };
+    VendorDictionary::with_learned(learned.into_iter().map(|r| VendorProfile {
+        name: r.name,
+        aliases: Vec::new(),
+        tax_rate: r.tax_rate,
+        total_label: r.total_label,
+    }))
+}
+
+/// Rebuild the recognizer and LLM extractor from the current settings and
+/// swap them into the shared pipeline used by both the intake folder and
+/// `ingest_receipt`.
+async fn reload_ocr_backend(state: &Mutex<AppState>) -> Result<OcrHealth, CommandError> {
+    let (db, tessdata_dir, pipeline) = {
+        let s = state.lock().await;
+        (s.db.clone(), s.tessdata_dir.clone(), s.pipeline.clone())
+    };
+    let config = load_ocr_config(&db, &tessdata_dir).await;
+    // Starting Tesseract loads the language model, so keep it off the async
+    // runtime.
+    let (recognizer, health) =
+        tokio::task::spawn_blocking(move || aequi_ocr::build_recognizer(&config))
+            .await
+            .map_err(|e| CommandError::internal(e.to_string()))?;
+    pipeline.set_recognizer(recognizer);
+    pipeline.set_llm_extractor(load_llm_extractor(&db).await);
+    pipeline.set_extract_options(load_extract_options(&db).await);
+    if let Some(detail) = &health.detail {
+        tracing::warn!("OCR backend unavailable, using mock: {detail}");
+    }
+
+    state.lock().await.ocr_health = health.clone();
+    Ok(health)
+}
+
+/// Report which OCR backend receipts are processed with and, if it is not
+/// the one configured, why.
+#[tauri::command]
+pub async fn get_ocr_health(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<OcrHealth, CommandError> {
+    Ok(state.lock().await.ocr_health.clone())
+}
+
+/// Save the OCR settings and switch the running pipeline over to them.
+/// `None` leaves a setting unchanged; an empty string clears it. A
+/// `char_whitelist` of `"none"` lets Tesseract read any character.
+#[tauri::command]
+pub async fn configure_ocr(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    backend: String,
+    language: Option<String>,
+    tessdata_dir: Option<String>,
+    page_segmentation: Option<u8>,
+    char_whitelist: Option<String>,
+) -> Result<OcrHealth, CommandError> {
+    let kind = OcrBackendKind::parse(&backend)
+        .ok_or_else(|| CommandError::validation(format!("Unknown OCR backend: {backend}")))?;
+    if page_segmentation.is_some_and(|psm| psm > aequi_ocr::backend::MAX_PAGE_SEGMENTATION) {
+        return Err(CommandError::validation(format!(
+            "Page segmentation mode must be 0 to {}",
+            aequi_ocr::backend::MAX_PAGE_SEGMENTATION
+        )));
+    }
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+
+    aequi_storage::set_setting(&db, aequi_storage::OCR_BACKEND_SETTING, kind.as_str()).await?;
+    if let Some(language) = language {
+        aequi_storage::set_setting(&db, "ocr_language", language.trim()).await?;
+    }
+    if let Some(dir) = tessdata_dir {
+        aequi_storage::set_setting(&db, "ocr_tessdata_dir", dir.trim()).await?;
+    }
+    if let Some(psm) = page_segmentation {
+        aequi_storage::set_setting(&db, "ocr_page_segmentation", &psm.to_string()).await?;
+    }
+    if let Some(chars) = char_whitelist {
+        aequi_storage::set_setting(&db, "ocr_char_whitelist", &chars).await?;
+    }
+
+    reload_ocr_backend(&state).await
+}
+
+/// Set the locale receipts are read in (`en-US`, `fr-FR`, `de-DE`, ...).
+/// This picks the numeric date order and, unless `ocr_language` is set
+/// explicitly, the Tesseract languages. An empty string restores the US
+/// default.
+#[tauri::command]
+pub async fn configure_receipt_locale(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    locale: String,
+) -> Result<OcrHealth, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    aequi_storage::set_setting(&db, "receipt_locale", locale.trim()).await?;
+    reload_ocr_backend(&state).await
+}
+
+/// Which account each receipt payment method is paid from.
+#[tauri::command]
+pub async fn get_payment_accounts(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<aequi_core::PaymentAccountMap, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    Ok(aequi_storage::get_payment_account_map(&db).await?)
+}
+
+/// Save the payment method to account mapping. Every account must be an
+/// asset or liability account, since that is where the money came from.
+#[tauri::command]
+pub async fn set_payment_accounts(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    map: aequi_core::PaymentAccountMap,
+) -> Result<(), CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    if map.methods.keys().any(|m| m.trim().is_empty()) {
+        return Err(CommandError::validation("Payment method name is required"));
+    }
+    for code in map.account_codes() {
+        let account = aequi_storage::get_account_by_code(&db, code)
+            .await?
+            .ok_or_else(|| CommandError::not_found(format!("Account not found: {code}")))?;
+        if !matches!(
+            account.account_type,
+            aequi_core::AccountType::Asset | aequi_core::AccountType::Liability
+        ) {
+            return Err(CommandError::validation(format!(
+                "{code} {} is not an asset or liability account",
+                account.name
+            )));
+        }
+    }
+    aequi_storage::set_payment_account_map(&db, &map).await?;
+    Ok(())
+}
+
+/// Save the LLM extraction settings and apply them to the pipeline. An empty
+/// endpoint turns LLM extraction off. Returns whether it is now enabled.
+#[tauri::command]
+pub async fn configure_receipt_llm(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    endpoint: String,
+    model: String,
+    api_key: Option<String>,
+    send_image: bool,
+) -> Result<bool, CommandError> {
+    let (db, pipeline) = {
+        let s = state.lock().await;
+        (s.db.clone(), s.pipeline.clone())
+    };
+
+    aequi_storage::set_setting(&db, "receipt_llm_endpoint", endpoint.trim()).await?;
+    aequi_storage::set_setting(&db, "receipt_llm_model", model.trim()).await?;
+    if let Some(key) = api_key {
+        aequi_storage::set_setting(&db, "receipt_llm_api_key", key.trim()).await?;
+    }
+    aequi_storage::set_setting(
+        &db,
+        "receipt_llm_send_image",
+        if send_image { "true" } else { "false" },
+    )
+    .await?;
+
+    let extractor = load_llm_extractor(&db).await;
+    let enabled = extractor.is_some();
+    pipeline.set_llm_extractor(extractor);
+    Ok(enabled)
+}
+
+/// Download Tesseract language data (default: the configured language) into
+/// the tessdata folder, then reload the backend so it is picked up.
+#[tauri::command]
+pub async fn download_ocr_language(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    language: Option<String>,
+) -> Result<OcrHealth, CommandError> {
+    let (db, default_tessdata) = {
+        let s = state.lock().await;
+        (s.db.clone(), s.tessdata_dir.clone())
+    };
+    let config = load_ocr_config(&db, &default_tessdata).await;
+    let language = language.unwrap_or_else(|| config.language.clone());
+    let dir = config
+        .tessdata_dir
+        .clone()
+        .unwrap_or_else(|| default_tessdata.clone());
+
+    let paths = aequi_ocr::tessdata::download_languages(&dir, &language)
+        .await
+        .map_err(|e| match e {
+            aequi_ocr::tessdata::TessdataError::InvalidLanguage(_) => {
+                CommandError::validation(e.to_string())
+            }
+            e => CommandError::internal(e.to_string()),
+        })?;
+    tracing::info!(
+        "OCR language data for {language} ready ({} files)",
+        paths.len()
+    );
+
+    reload_ocr_backend(&state).await
+}
+
+// ── Import commands ──────────────────────────────────────────────────────────
+
+/// Largest bank statement accepted for import.
+const MAX_IMPORT_SIZE: u64 = 20 * 1024 * 1024;
+
+async fn read_import_file(path: &Path) -> Result<(String, Vec<u8>), CommandError> {
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| CommandError::validation("Invalid file name"))?
+        .to_string();
+    let meta = tokio::fs::metadata(path)
+        .await
+        .map_err(|e| CommandError::validation(format!("Cannot read file: {e}")))?;
+    if meta.len() > MAX_IMPORT_SIZE {
+        return Err(CommandError::validation(format!(
+            "File is too large to import ({} MB, limit {} MB)",
+            meta.len() / (1024 * 1024),
+            MAX_IMPORT_SIZE / (1024 * 1024)
+        )));
+    }
+    let data = tokio::fs::read(path)
+        .await
+        .map_err(|e| CommandError::validation(format!("Cannot read file: {e}")))?;
+    Ok((file_name, data))
+}
+
+/// First step of the import wizard: the file's columns and opening rows, and
+/// the CSV profile that would read it (a saved one if any matches).
+#[tauri::command]
+pub async fn preview_import_file(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    file_path: String,
+) -> Result<aequi_import::ImportPreview, CommandError> {
+    let (file_name, data) = read_import_file(Path::new(&file_path)).await?;
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let profiles = crate::bank_intake::load_csv_profiles(&db).await?;
+    aequi_import::preview::preview_bank_file(&file_name, &data, &profiles)
+        .map_err(|e| CommandError::validation(e.to_string()))
+}
+
+#[derive(Debug, Serialize)]
+pub struct CommitImportOutput {
+    pub batch_id: String,
+    /// Rows skipped because they were imported before.
+    pub duplicates: usize,
+    pub summary: aequi_storage::ImportBatchSummary,
+    /// The statement's closing balance checked against the ledger, when the
+    /// file has one and the account was given.
+    pub balance_assertion: Option<AssertionCheck>,
+}
+
+/// Import a previewed file as a new batch, reading CSVs with `profile` when
+/// given. New rows are categorized by rule and given suggested ledger matches.
+/// `save_profile` stores a new `profile` for next time. With `account_code`,
+/// the ledger account the statement is for, an OFX closing balance is
+/// recorded as a balance assertion.
+#[tauri::command]
+pub async fn commit_import(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    file_path: String,
+    profile: Option<aequi_import::CsvImportProfile>,
+    save_profile: Option<bool>,
+    account_code: Option<String>,
+) -> Result<CommitImportOutput, CommandError> {
+    let path = PathBuf::from(&file_path);
+    let (file_name, data) = read_import_file(&path).await?;
+    let kind = aequi_import::BankFileKind::from_path(&path)
+        .ok_or_else(|| CommandError::validation(format!("Unsupported bank file: {file_name}")))?;
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let account = match &account_code {
+        Some(code) => Some(
+            aequi_storage::get_account_by_code(&db, code)
+                .await?
+                .ok_or_else(|| CommandError::not_found(format!("Account not found: {code}")))?,
+        ),
+        None => None,
+    };
+
+    let parsed = match &profile {
+        Some(p) => aequi_import::bank_intake::parse_bank_file_with_profile(&file_name, &data, p),
+        None => {
+            let profiles = crate::bank_intake::load_csv_profiles(&db).await?;
+            aequi_import::bank_intake::parse_bank_file(&file_name, &data, &profiles)
+        }
+    }
+    .map_err(|e| CommandError::validation(e.to_string()))?;
+
+    if let Some(p) = profile.filter(|p| p.id.is_none() && save_profile.unwrap_or(false)) {
+        p.validate().map_err(CommandError::validation)?;
+        aequi_storage::save_csv_import_profile(&db, &p).await?;
+    }
+
+    let batch_id = crate::bank_intake::batch_id("import", &path);
+    let outcome = crate::bank_intake::queue_transactions(&db, kind, &batch_id, parsed.transactions)
+        .instrument(crate::logging::command_span("commit_import"))
+        .await?;
+
+    let balance_assertion = match (account, parsed.ledger_balance) {
+        (Some(account), Some((date, balance))) => {
+            let account_id = account
+                .id
+                .ok_or_else(|| CommandError::internal("Account missing ID"))?
+                .0;
+            // Banks report what a card owes as negative.
+            let balance = match account.account_type {
+                aequi_core::AccountType::Liability => -balance,
+                _ => balance,
+            };
+            let id = balance_assertions::record_balance_assertion(
+                &db,
+                account_id,
+                date,
+                balance,
+                balance_assertions::SOURCE_OFX,
+            )
+            .await?;
+            balance_assertions::check_balance_assertions(&db, Some(account_id))
+                .await?
+                .into_iter()
+                .find(|c| c.assertion.id == id)
+        }
+        _ => None,
+    };
+    let summary = aequi_storage::get_import_batch_summary(&db, &batch_id)
+        .await?
+        .unwrap_or_else(|| aequi_storage::ImportBatchSummary {
+            batch_id: batch_id.clone(),
+            ..Default::default()
+        });
+
+    tracing::info!(
+        "Imported {} transactions from {file_name} (batch {batch_id}, {} with suggested matches, {} categorized)",
+        outcome.queued,
+        outcome.matched,
+        outcome.categorized
+    );
+    crate::hooks::import_completed(
+        &db,
+        ImportCompleted {
+            source: kind.source_type().to_string(),
+            file_name,
+            batch_ids: vec![batch_id.clone()],
+            imported: outcome.queued,
+            duplicates: outcome.duplicates,
+        },
+    );
+    Ok(CommitImportOutput {
+        batch_id,
+        duplicates: outcome.duplicates,
+        summary,
+        balance_assertion,
+    })
+}
+
+/// Rows a streamed CSV import queues at a time; progress is reported after
+/// each chunk.
+const IMPORT_CHUNK_ROWS: usize = 500;
+
+pub const EVENT_IMPORT_PROGRESS: &str = "import:progress";
+
+/// Payload of `import:progress` events.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportProgress {
+    pub job_id: String,
+    pub rows: u64,
+    pub bytes: u64,
+    pub total_bytes: u64,
+}
+
+/// Import a CSV as a new batch without reading it all into memory, for
+/// exports too large for `commit_import`. Progress is emitted as
+/// `import:progress` events, and `cancel_import` with the same `job_id`
+/// stops the import and discards the rows queued so far.
+#[tauri::command]
+pub async fn import_csv_streaming(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<Mutex<AppState>>>,
+    file_path: String,
+    profile: aequi_import::CsvImportProfile,
+    job_id: String,
+) -> Result<CommitImportOutput, CommandError> {
+    use std::ops::ControlFlow;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use tauri::Emitter;
+
+    profile.validate().map_err(CommandError::validation)?;
+    let path = PathBuf::from(&file_path);
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| CommandError::validation("Invalid file name"))?
+        .to_string();
+    let file = std::fs::File::open(&path)
+        .map_err(|e| CommandError::validation(format!("Cannot read file: {e}")))?;
+    let total_bytes = file
+        .metadata()
+        .map_err(|e| CommandError::validation(format!("Cannot read file: {e}")))?
+        .len();
+
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let db = {
+        let mut s = state.lock().await;
+        if s.import_jobs.contains_key(&job_id) {
+            return Err(CommandError::validation(format!(
+                "Import {job_id} is already running"
+            )));
+        }
+        s.import_jobs.insert(job_id.clone(), cancelled.clone());
+        s.db.clone()
+    };
+    let batch_id = crate::bank_intake::batch_id("import", &path);
+
+    let result = async {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(2);
+        let reader = tokio::task::spawn_blocking(move || {
+            aequi_import::csv::import_csv_chunked(
+                std::io::BufReader::new(file),
+                &profile,
+                IMPORT_CHUNK_ROWS,
+                |chunk, progress| match tx.blocking_send((chunk, progress)) {
+                    Ok(()) => ControlFlow::Continue(()),
+                    Err(_) => ControlFlow::Break(()),
+                },
+            )
+        });
+
+        let mut outcome = crate::bank_intake::QueueOutcome::default();
+        while let Some((chunk, progress)) = rx.recv().await {
+            if cancelled.load(Ordering::Relaxed) {
+                break;
+            }
+            let rows = chunk
+                .into_iter()
+                .map(aequi_import::IntakeTransaction::from)
+                .collect();
+            let queued = crate::bank_intake::queue_transactions(
+                &db,
+                aequi_import::BankFileKind::Csv,
+                &batch_id,
+                rows,
+            )
+            .await?;
+            outcome.queued += queued.queued;
+            outcome.duplicates += queued.duplicates;
+            outcome.categorized += queued.categorized;
+            outcome.matched += queued.matched;
+            let payload = ImportProgress {
+                job_id: job_id.clone(),
+                rows: progress.rows,
+                bytes: progress.bytes,
+                total_bytes,
+            };
+            if let Err(e) = app.emit(EVENT_IMPORT_PROGRESS, payload) {
+                tracing::warn!("Failed to emit {EVENT_IMPORT_PROGRESS}: {e}");
+            }
+        }
+        drop(rx);
+        reader
+            .await
+            .map_err(|e| CommandError::internal(e.to_string()))?
+            .map_err(|e| match e {
+                aequi_import::csv::CsvError::Cancelled => {
+                    CommandError::validation("Import cancelled")
+                }
+                e => CommandError::validation(e.to_string()),
+            })?;
+        // The file may have been read to the end before the last chunks
+        // were skipped.
+        if cancelled.load(Ordering::Relaxed) {
+            return Err(CommandError::validation("Import cancelled"));
+        }
+        Ok::<_, CommandError>(outcome)
+    }
+    .instrument(crate::logging::command_span("import_csv_streaming"))
+    .await;
+
+    state.lock().await.import_jobs.remove(&job_id);
+    let outcome = match result {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            let discarded = aequi_storage::delete_import_batch(&db, &batch_id).await?;
+            tracing::info!("Import of {file_name} stopped, {discarded} queued rows discarded");
+            return Err(e);
+        }
+    };
+
+    let summary = aequi_storage::get_import_batch_summary(&db, &batch_id)
+        .await?
+        .unwrap_or_else(|| aequi_storage::ImportBatchSummary {
+            batch_id: batch_id.clone(),
+            ..Default::default()
+        });
+    tracing::info!(
+        "Imported {} transactions from {file_name} (batch {batch_id}, {} with suggested matches, {} categorized)",
+        outcome.queued,
+        outcome.matched,
+        outcome.categorized
+    );
+    crate::hooks::import_completed(
+        &db,
+        ImportCompleted {
+            source: aequi_import::BankFileKind::Csv.source_type().to_string(),
+            file_name,
+            batch_ids: vec![batch_id.clone()],
+            imported: outcome.queued,
+            duplicates: outcome.duplicates,
+        },
+    );
+    Ok(CommitImportOutput {
+        batch_id,
+        duplicates: outcome.duplicates,
+        summary,
+        balance_assertion: None,
+    })
+}
+
+/// Stop a streamed import started with `job_id`.
+#[tauri::command]
+pub async fn cancel_import(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    job_id: String,
+) -> Result<(), CommandError> {
+    let s = state.lock().await;
+    let cancelled = s
+        .import_jobs
+        .get(&job_id)
+        .ok_or_else(|| CommandError::not_found(format!("No import {job_id} is running")))?;
+    cancelled.store(true, std::sync::atomic::Ordering::Relaxed);
+    Ok(())
+}
+
+/// Row counts by review status for an import batch.
+#[tauri::command]
+pub async fn get_import_summary(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    batch_id: String,
+) -> Result<aequi_storage::ImportBatchSummary, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    aequi_storage::get_import_batch_summary(&db, &batch_id)
+        .await?
+        .ok_or_else(|| CommandError::not_found("Import batch not found"))
+}
+
+/// Every row of an import batch with its account and suggested match.
+#[tauri::command]
+pub async fn get_import_review(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    batch_id: String,
+) -> Result<Vec<aequi_storage::ImportReviewRow>, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    Ok(aequi_storage::get_import_review(&db, &batch_id).await?)
+}
+
+/// Choose the account an imported row is booked to, overriding any rule.
+#[tauri::command]
+pub async fn set_imported_category(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+    account_code: String,
+) -> Result<(), CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let account_id = account_id_for_code(&db, &account_code).await?;
+    if !aequi_storage::set_imported_transaction_account(&db, id, account_id.0).await? {
+        return Err(CommandError::not_found(
+            "Imported transaction not found or already settled",
+        ));
+    }
+    Ok(())
+}
+
+/// Most rows returned by the uncategorized queue when no limit is given.
+const UNCATEGORIZED_QUEUE_LIMIT: i64 = 200;
+
+/// Imported rows from every batch that still need an account, largest
+/// first: the daily triage list.
+#[tauri::command]
+pub async fn get_uncategorized_queue(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    limit: Option<i64>,
+) -> Result<Vec<aequi_storage::ImportReviewRow>, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let limit = limit.unwrap_or(UNCATEGORIZED_QUEUE_LIMIT).max(1);
+    Ok(aequi_storage::get_uncategorized_imports(&db, limit).await?)
+}
+
+/// Book imported rows to an account from the triage list. With `similar`,
+/// other uncategorized rows with the same description are booked too.
+/// Returns the ids of the rows booked.
+#[tauri::command]
+pub async fn quick_categorize(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    ids: Vec<i64>,
+    account_code: String,
+    similar: Option<bool>,
+) -> Result<Vec<i64>, CommandError> {
+    if ids.is_empty() {
+        return Err(CommandError::validation("No transactions selected"));
+    }
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let account_id = account_id_for_code(&db, &account_code).await?;
+    let booked = aequi_storage::categorize_imported_transactions(
+        &db,
+        &ids,
+        account_id.0,
+        similar.unwrap_or(false),
+    )
+    .await?;
+    if booked.is_empty() {
+        return Err(CommandError::not_found(
+            "Imported transactions not found or already settled",
+        ));
+    }
+    Ok(booked)
+}
+
+/// Accept the ledger transaction suggested for an imported row.
+#[tauri::command]
+pub async fn accept_match(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+) -> Result<(), CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    if !aequi_storage::accept_imported_match(&db, id).await? {
+        return Err(CommandError::not_found(
+            "No suggested match to accept for this transaction",
+        ));
+    }
+    Ok(())
+}
+
+/// Reject the ledger transaction suggested for an imported row; the row can
+/// then be categorized and posted instead.
+#[tauri::command]
+pub async fn reject_match(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+) -> Result<(), CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    if !aequi_storage::reject_imported_match(&db, id).await? {
+        return Err(CommandError::not_found(
+            "No suggested match to reject for this transaction",
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+pub struct PostBatchOutput {
+    pub transaction_ids: Vec<i64>,
+    /// Rows still open because they have no account or an undecided match.
+    pub left_for_review: i64,
+}
+
+/// Post every categorized row of a batch to the ledger against
+/// `bank_account_code`, the account the statement belongs to. Money out is
+/// debited to the row's account, money in credited to it. All rows are
+/// posted in one database transaction.
+#[tauri::command]
+pub async fn post_batch(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    batch_id: String,
+    bank_account_code: String,
+) -> Result<PostBatchOutput, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let bank_account = account_id_for_code(&db, &bank_account_code).await?;
+    let rows = aequi_storage::get_postable_imported_transactions(&db, &batch_id).await?;
+
+    let mut row_ids = Vec::new();
+    let mut validated = Vec::new();
+    for row in rows {
+        let Some(account_id) = row.account_id.filter(|_| row.amount_cents != 0) else {
+            continue;
+        };
+        let date = NaiveDate::parse_from_str(&row.date, "%Y-%m-%d")
+            .map_err(|_| CommandError::internal(format!("Invalid date: {}", row.date)))?;
+        let amount = Money::from_cents(row.amount_cents.abs());
+        let (debit, credit) = if row.amount_cents < 0 {
+            (amount, Money::zero())
+        } else {
+            (Money::zero(), amount)
+        };
+        let lines = vec![
+            TransactionLine {
+                account_id: aequi_core::AccountId(account_id),
+                debit,
+                credit,
+                memo: None,
+            },
+            TransactionLine {
+                account_id: bank_account,
+                debit: credit,
+                credit: debit,
+                memo: None,
+            },
+        ];
+        let description = if row.description.trim().is_empty() {
+            "Imported transaction".to_string()
+        } else {
+            row.description.trim().to_string()
+        };
+        validated.push(ValidatedTransaction::validate(UnvalidatedTransaction {
+            date,
+            description,
+            lines,
+            memo: row.memo,
+        })?);
+        row_ids.push(row.id);
+    }
+
+    let (db_ref, validated, row_ids) = (&db, &validated, &row_ids);
+    let transaction_ids = aequi_storage::with_retry(&Default::default(), move || async move {
+        let mut sql_tx = db_ref.begin().await?;
+        let ids = aequi_storage::insert_transactions_bulk_tx(&mut sql_tx, validated).await?;
+        for (&row_id, &transaction_id) in row_ids.iter().zip(&ids) {
+            aequi_storage::settle_imported_transaction(&mut sql_tx, row_id, transaction_id).await?;
+        }
+        sql_tx.commit().await?;
+        Ok(ids)
+    })
+    .await?;
+    for &id in &transaction_ids {
+        crate::hooks::transaction_posted(&db, id, TransactionSource::Import);
+    }
+
+    let summary = aequi_storage::get_import_batch_summary(&db, &batch_id)
+        .await?
+        .unwrap_or_default();
+    Ok(PostBatchOutput {
+        transaction_ids,
+        left_for_review: summary.pending + summary.categorized,
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct BudgetAppBatch {
+    /// The app's name for the account the batch came from.
+    pub account: String,
+    pub batch_id: String,
+    /// Rows skipped because they were imported before.
+    pub duplicates: usize,
+    pub summary: aequi_storage::ImportBatchSummary,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BudgetAppImportOutput {
+    pub app: aequi_import::budget_apps::BudgetApp,
+    pub batches: Vec<BudgetAppBatch>,
+}
+
+/// Import a Mint, YNAB or Quicken transaction export, detecting which
+/// unless `app` is given. Each of the app's accounts becomes its own batch
+/// for review, with rows its rules don't categorize booked to the account
+/// suggested by their category in the app.
+#[tauri::command]
+pub async fn import_budget_app(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    file_path: String,
+    app: Option<aequi_import::budget_apps::BudgetApp>,
+) -> Result<BudgetAppImportOutput, CommandError> {
+    let path = PathBuf::from(&file_path);
+    let (file_name, data) = read_import_file(&path).await?;
+    let export = aequi_import::budget_apps::parse_export(&data, app)
+        .map_err(|e| CommandError::validation(e.to_string()))?;
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+
+    let prefix = crate::bank_intake::batch_id("import", &path);
+    let mut batches = Vec::new();
+    let (mut queued, mut duplicates) = (0, 0);
+    for (i, account) in export.accounts().into_iter().enumerate() {
+        let batch_id = format!("{prefix}-{}", i + 1);
+        let outcome = crate::bank_intake::queue_transactions(
+            &db,
+            aequi_import::BankFileKind::Csv,
+            &batch_id,
+            export.intake_transactions(account),
+        )
+        .instrument(crate::logging::command_span("import_budget_app"))
+        .await?;
+        let summary = aequi_storage::get_import_batch_summary(&db, &batch_id)
+            .await?
+            .unwrap_or_else(|| aequi_storage::ImportBatchSummary {
+                batch_id: batch_id.clone(),
+                ..Default::default()
+            });
+        queued += outcome.queued;
+        duplicates += outcome.duplicates;
+        batches.push(BudgetAppBatch {
+            account: account.to_string(),
+            batch_id,
+            duplicates: outcome.duplicates,
+            summary,
+        });
+    }
+
+    tracing::info!(
+        "Imported {} {} transactions from {file_name} in {} batches",
+        export.transactions.len(),
+        export.app.name(),
+        batches.len()
+    );
+    crate::hooks::import_completed(
+        &db,
+        ImportCompleted {
+            source: export.app.name().to_lowercase(),
+            file_name,
+            batch_ids: batches.iter().map(|b| b.batch_id.clone()).collect(),
+            imported: queued,
+            duplicates,
+        },
+    );
+    Ok(BudgetAppImportOutput {
+        app: export.app,
+        batches,
+    })
+}
+
+/// Bring in a GnuCash book, saved as XML (compressed or not) or SQLite.
+/// Importing the same book again only adds what is new.
+#[tauri::command]
+pub async fn import_gnucash(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    path: String,
+) -> Result<aequi_storage::gnucash::GnucashImportSummary, CommandError> {
+    let data = tokio::fs::read(&path)
+        .await
+        .map_err(|e| CommandError::validation(format!("Cannot read file: {e}")))?;
+    let book = if aequi_import::gnucash::is_sqlite(&data) {
+        aequi_storage::gnucash::read_sqlite_book(Path::new(&path))
+            .await
+            .map_err(|e| CommandError::validation(format!("Cannot read GnuCash database: {e}")))?
+    } else {
+        aequi_import::gnucash::parse_xml(&data)
+            .map_err(|e| CommandError::validation(e.to_string()))?
+    };
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let base_currency = aequi_storage::get_preferences(&db).await?.default_currency;
+    let summary = aequi_storage::gnucash::import_book(&db, &book, &base_currency)
+        .instrument(crate::logging::command_span("import_gnucash"))
+        .await?;
+    crate::hooks::import_completed(
+        &db,
+        ImportCompleted {
+            source: "gnucash".to_string(),
+            file_name: Path::new(&path)
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            batch_ids: Vec::new(),
+            imported: summary.transactions_imported,
+            duplicates: summary.already_imported,
+        },
+    );
+    Ok(summary)
+}
+
+// ── Balance assertion commands ──────────────────────────────────────────────
+
+/// Record an account's balance at the end of `date` as shown on a
+/// statement, in the account's normal sign, and check it against the ledger.
+#[tauri::command]
+pub async fn add_balance_assertion(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    account_code: String,
+    date: String,
+    balance_cents: i64,
+) -> Result<AssertionCheck, CommandError> {
+    let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+        .map_err(|_| CommandError::validation("Invalid date format (expected YYYY-MM-DD)"))?;
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let account = aequi_storage::get_account_by_code(&db, &account_code)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("Account not found: {account_code}")))?;
+    if !matches!(
+        account.account_type,
+        aequi_core::AccountType::Asset | aequi_core::AccountType::Liability
+    ) {
+        return Err(CommandError::validation(format!(
+            "{account_code} {} is not an asset or liability account",
+            account.name
+        )));
+    }
+    let account_id = account
+        .id
+        .ok_or_else(|| CommandError::internal("Account missing ID"))?
+        .0;
+    let id = balance_assertions::record_balance_assertion(
+        &db,
+        account_id,
+        date,
+        balance_cents,
+        balance_assertions::SOURCE_STATEMENT,
+    )
+    .await?;
+    balance_assertions::check_balance_assertions(&db, Some(account_id))
+        .await?
+        .into_iter()
+        .find(|c| c.assertion.id == id)
+        .ok_or_else(|| CommandError::internal("Balance assertion missing after insert"))
+}
+
+/// Balance assertions checked against the ledger, for one account or all.
+#[tauri::command]
+pub async fn get_balance_assertions(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    account_code: Option<String>,
+) -> Result<Vec<AssertionCheck>, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let account_id = match &account_code {
+        Some(code) => Some(
+            aequi_storage::get_account_by_code(&db, code)
+                .await?
+                .and_then(|a| a.id)
+                .ok_or_else(|| CommandError::not_found(format!("Account not found: {code}")))?
+                .0,
+        ),
+        None => None,
+    };
+    Ok(balance_assertions::check_balance_assertions(&db, account_id).await?)
+}
+
+#[tauri::command]
+pub async fn delete_balance_assertion(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+) -> Result<(), CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    if !balance_assertions::delete_balance_assertion(&db, id).await? {
+        return Err(CommandError::not_found("Balance assertion not found"));
+    }
+    Ok(())
+}
+
+// ── Categorization rule commands ────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct CategorizationRuleOutput {
+    pub id: i64,
+    #[serde(flatten)]
+    pub rule: aequi_import::CategoryRule,
+}
+
+/// The stored row for `rule`, once it has been checked and its account found.
+async fn categorization_rule_row(
+    db: &aequi_storage::DbPool,
+    id: i64,
+    rule: &aequi_import::CategoryRule,
+) -> Result<aequi_storage::CategorizationRule, CommandError> {
+    rule.validate().map_err(CommandError::validation)?;
+    let account_id = account_id_for_code(db, &rule.account_code).await?;
+    Ok(aequi_storage::CategorizationRule::from_category_rule(
+        id,
+        rule,
+        account_id.0,
+    ))
+}
+
+async fn ensure_categorization_rule_exists(
+    db: &aequi_storage::DbPool,
+    id: i64,
+) -> Result<(), CommandError> {
+    if aequi_storage::get_categorization_rules(db)
+        .await?
+        .iter()
+        .any(|r| r.id == id)
+    {
+        Ok(())
+    } else {
+        Err(CommandError::not_found("Categorization rule not found"))
+    }
+}
+
+/// Rules in the order they are tried, highest priority first.
+#[tauri::command]
+pub async fn get_categorization_rules(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Vec<CategorizationRuleOutput>, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    Ok(aequi_storage::get_category_rules(&db)
+        .await?
+        .into_iter()
+        .map(|(id, rule)| CategorizationRuleOutput { id, rule })
+        .collect())
+}
+
+#[tauri::command]
+pub async fn create_categorization_rule(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    rule: aequi_import::CategoryRule,
+) -> Result<i64, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let row = categorization_rule_row(&db, 0, &rule).await?;
+    Ok(aequi_storage::save_categorization_rule(&db, &row).await?)
+}
+
+#[tauri::command]
+pub async fn update_categorization_rule(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+    rule: aequi_import::CategoryRule,
+) -> Result<(), CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    ensure_categorization_rule_exists(&db, id).await?;
+    let row = categorization_rule_row(&db, id, &rule).await?;
+    aequi_storage::update_categorization_rule(&db, &row).await?;
+    Ok(())
+}
+
+/// Delete a rule. Open imported rows it categorized go back to pending
+/// unless an account was chosen for them by hand.
+#[tauri::command]
+pub async fn delete_categorization_rule(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+) -> Result<(), CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    ensure_categorization_rule_exists(&db, id).await?;
+    aequi_storage::delete_categorization_rule(&db, id).await?;
+    Ok(())
+}
+
+/// Set the order rules are tried in, first id first. Priorities are
+/// reassigned to match.
+#[tauri::command]
+pub async fn reorder_categorization_rules(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    ids: Vec<i64>,
+) -> Result<(), CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let stored: std::collections::HashSet<i64> = aequi_storage::get_categorization_rules(&db)
+        .await?
+        .into_iter()
+        .map(|r| r.id)
+        .collect();
+    if let Some(unknown) = ids.iter().find(|id| !stored.contains(id)) {
+        return Err(CommandError::not_found(format!(
+            "Categorization rule not found: {unknown}"
+        )));
+    }
+    aequi_storage::reorder_categorization_rules(&db, &ids).await?;
+    Ok(())
+}
+
+// ── Import profile commands ─────────────────────────────────────────────────
+
+/// Saved CSV import profiles, by name.
+#[tauri::command]
+pub async fn get_import_profiles(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Vec<aequi_import::CsvImportProfile>, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    Ok(crate::bank_intake::load_csv_profiles(&db).await?)
+}
+
+#[tauri::command]
+pub async fn save_import_profile(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    profile: aequi_import::CsvImportProfile,
+) -> Result<i64, CommandError> {
+    profile.validate().map_err(CommandError::validation)?;
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    Ok(aequi_storage::save_csv_import_profile(&db, &profile).await?)
+}
+
+#[tauri::command]
+pub async fn update_import_profile(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+    profile: aequi_import::CsvImportProfile,
+) -> Result<(), CommandError> {
+    profile.validate().map_err(CommandError::validation)?;
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let mut row = aequi_storage::ImportProfile::from(&profile);
+    row.id = id;
+    if !aequi_storage::update_import_profile(&db, &row).await? {
+        return Err(CommandError::not_found("Import profile not found"));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn delete_import_profile(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+) -> Result<(), CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    aequi_storage::delete_import_profile(&db, id).await?;
+    Ok(())
+}
+
+/// Read a sample file with an unsaved profile, so the mapping can be checked
+/// before it is saved. Parse failures come back in the preview's
+/// `parse_error` rather than as an error.
+#[tauri::command]
+pub async fn test_import_profile(
+    file_path: String,
+    profile: aequi_import::CsvImportProfile,
+) -> Result<aequi_import::ImportPreview, CommandError> {
+    let (file_name, data) = read_import_file(Path::new(&file_path)).await?;
+    aequi_import::preview::preview_with_profile(&file_name, &data, &profile)
+        .map_err(|e| CommandError::validation(e.to_string()))
+}
+
+// ── Tax commands ─────────────────────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct QuarterlyEstimateOutput {
+    pub year: u16,
+    pub quarter: String,
+    pub ytd_gross_income_cents: i64,
+    pub ytd_total_expenses_cents: i64,
+    pub ytd_net_profit_cents: i64,
+    pub se_tax_cents: i64,
+    pub se_tax_deduction_cents: i64,
+    pub income_tax_cents: i64,
+    pub total_tax_cents: i64,
+    pub safe_harbor_cents: i64,
+    pub quarterly_payment_cents: i64,
+    pub payment_due_date: String,
+    pub schedule_c_lines: Vec<ScheduleCLineOutput>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScheduleCLineOutput {
+    pub line: String,
+    pub label: String,
+    /// Deductible amount, after transaction and account shares and caps.
+    pub amount_cents: i64,
+    /// Amount as booked.
+    pub book_amount_cents: i64,
+    pub is_income: bool,
+}
+
+/// Schedule C lines with their deductible and booked amounts. Lines that
+/// were booked but deduct nothing are kept, at zero.
+fn schedule_c_line_outputs(
+    lines: &std::collections::BTreeMap<aequi_core::ScheduleCLine, Money>,
+    book_lines: &std::collections::BTreeMap<aequi_core::ScheduleCLine, Money>,
+) -> Vec<ScheduleCLineOutput> {
+    let all: std::collections::BTreeSet<_> = lines.keys().chain(book_lines.keys()).collect();
+    all.into_iter()
+        .map(|line| ScheduleCLineOutput {
+            line: format!("{line:?}"),
+            label: line.label().to_string(),
+            amount_cents: lines.get(line).map_or(0, |m| m.to_cents()),
+            book_amount_cents: book_lines.get(line).map_or(0, |m| m.to_cents()),
+            is_income: line.is_income(),
+        })
+        .collect()
+}
+
+/// Compute a quarterly tax estimate for the given year and quarter.
+#[tauri::command]
+pub async fn estimate_quarterly_tax(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    year: Option<u16>,
+    quarter: Option<u8>,
+) -> Result<QuarterlyEstimateOutput, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let db = &db;
+
+    let now = chrono::Utc::now().date_naive();
+    let yr = year.unwrap_or(now.year() as u16);
+    let qtr = quarter
+        .and_then(Quarter::new)
+        .unwrap_or_else(|| Quarter::new(((now.month0() / 3) + 1) as u8).unwrap_or(Quarter::Q1));
+
+    let rules = load_tax_rules(yr)?;
+    let fy = FiscalYear::new(yr);
+
+    let prior_year_cents = aequi_storage::get_prior_year_total_tax(db, yr)
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))?;
+    let prior_year_tax = prior_year_cents.map(Money::from_cents);
+
+    let snapshot = aequi_storage::build_ledger_snapshot(db, fy, prior_year_tax)
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))?;
+
+    let est = aequi_core::compute_quarterly_estimate(&rules, &snapshot, qtr);
+
+    // Persist the estimate
+    aequi_storage::upsert_tax_period(
+        db,
+        yr,
+        quarter_to_u8(qtr),
+        est.total_tax_estimate.to_cents(),
+        est.se_tax_amount.to_cents(),
+        est.estimated_income_tax.to_cents(),
+        est.ytd_net_profit.to_cents(),
+        &est.payment_due_date.to_string(),
+        rules.year.value,
+    )
+    .await
+    .map_err(|e| CommandError::internal(e.to_string()))?;
+
+    let schedule_c_lines =
+        schedule_c_line_outputs(&est.schedule_c_lines, &est.schedule_c_book_lines);
+
+    Ok(QuarterlyEstimateOutput {
+        year: est.year,
+        quarter: est.quarter.to_string(),
+        ytd_gross_income_cents: est.ytd_gross_income.to_cents(),
+        ytd_total_expenses_cents: est.ytd_total_expenses.to_cents(),
+        ytd_net_profit_cents: est.ytd_net_profit.to_cents(),
+        se_tax_cents: est.se_tax_amount.to_cents(),
+        se_tax_deduction_cents: est.se_tax_deduction.to_cents(),
+        income_tax_cents: est.estimated_income_tax.to_cents(),
+        total_tax_cents: est.total_tax_estimate.to_cents(),
+        safe_harbor_cents: est.safe_harbor_amount.to_cents(),
+        quarterly_payment_cents: est.quarterly_payment.to_cents(),
+        payment_due_date: est.payment_due_date.to_string(),
+        schedule_c_lines,
+    })
+}
+
+/// Get the Schedule C preview for a given year.
+#[tauri::command]
+pub async fn get_schedule_c_preview(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    year: Option<u16>,
+) -> Result<ScheduleCPreviewOutput, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let db = &db;
+
+    let yr = year.unwrap_or(chrono::Utc::now().date_naive().year() as u16);
+    let rules = load_tax_rules(yr)?;
+    let fy = FiscalYear::new(yr);
+
+    let snapshot = aequi_storage::build_ledger_snapshot(db, fy, None)
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))?;
+
+    let preview = aequi_core::tax::engine::schedule_c_preview(&rules, &snapshot);
+
+    let lines = schedule_c_line_outputs(&preview.lines, &preview.book_lines);
+
+    Ok(ScheduleCPreviewOutput {
+        year: preview.year,
+        gross_income_cents: preview.gross_income.to_cents(),
+        total_expenses_cents: preview.total_expenses.to_cents(),
+        book_total_expenses_cents: preview.book_total_expenses.to_cents(),
+        net_profit_cents: preview.net_profit.to_cents(),
+        lines,
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScheduleCPreviewOutput {
+    pub year: u16,
+    pub gross_income_cents: i64,
+    pub total_expenses_cents: i64,
+    /// Expenses as booked, before deductible shares and caps.
+    pub book_total_expenses_cents: i64,
+    pub net_profit_cents: i64,
+    pub lines: Vec<ScheduleCLineOutput>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HomeOfficeWorksheetOutput {
+    pub year: u16,
+    /// Inputs to fill the worksheet in from, with account-backed home costs
+    /// pulled from the ledger.
+    pub draft: aequi_core::tax::HomeOfficeInput,
+    pub saved: Option<HomeOfficeWorksheet>,
+    pub simplified_rate_per_sqft: rust_decimal::Decimal,
+    pub simplified_max_sqft: u32,
+}
+
+/// The year's home office worksheet, saved or not, with what to start it from.
+#[tauri::command]
+pub async fn get_home_office_worksheet(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    year: Option<u16>,
+) -> Result<HomeOfficeWorksheetOutput, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let yr = year.unwrap_or(chrono::Utc::now().date_naive().year() as u16);
+    let rules = load_tax_rules(yr)?;
+    let draft = home_office::draft_home_office_input(&db, yr).await?;
+    let saved = home_office::get_home_office_worksheet(&db, yr).await?;
+    Ok(HomeOfficeWorksheetOutput {
+        year: yr,
+        draft,
+        saved,
+        simplified_rate_per_sqft: rules.home_office_simplified.rate_per_sqft,
+        simplified_max_sqft: rules.home_office_simplified.max_sqft,
+    })
+}
+
+/// Work out the year's line 30 figure and keep the worksheet.
+#[tauri::command]
+pub async fn save_home_office_worksheet(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    year: u16,
+    input: aequi_core::tax::HomeOfficeInput,
+) -> Result<HomeOfficeWorksheet, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let rules = load_tax_rules(year)?;
+    Ok(home_office::save_home_office_worksheet(&db, year, input, &rules).await?)
+}
+
+/// Post the saved worksheet's deduction to the line 30 account, crediting
+/// owner's equity unless another account is given.
+#[tauri::command]
+pub async fn post_home_office_entry(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    year: u16,
+    credit_account_code: Option<String>,
+) -> Result<HomeOfficeWorksheet, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let transaction_id =
+        home_office::post_home_office_entry(&db, year, credit_account_code.as_deref()).await?;
+    crate::hooks::transaction_posted(&db, transaction_id, TransactionSource::Manual);
+    home_office::get_home_office_worksheet(&db, year)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("No {year} worksheet")))
+}
+
+/// Load tax rules for a given year from the bundled rules directory.
+pub(crate) fn load_tax_rules(year: u16) -> Result<aequi_core::TaxRules, CommandError> {
+    // Use the bundled rules file. In production this would resolve from
+    // the app's resource directory; for now we embed the 2026 rules.
+    let toml_str = include_str!("../../../rules/tax/us/2026.toml");
+
+    let rules = aequi_core::TaxRules::from_toml(toml_str)
+        .map_err(|e| CommandError::internal(e.to_string()))?;
+
+    if rules.year.value != year {
+        return Err(CommandError::not_found(format!(
+            "Tax rules for year {year} not available (have {})",
+            rules.year.value
+        )));
+    }
+
+    Ok(rules)
+}
+
+fn quarter_to_u8(q: Quarter) -> u8 {
+    match q {
+        Quarter::Q1 => 1,
+        Quarter::Q2 => 2,
+        Quarter::Q3 => 3,
+        Quarter::Q4 => 4,
+    }
+}
+
+// ── Contact commands ─────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct ContactInput {
+    pub name: String,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub address: Option<String>,
+    pub contact_type: String,
+    pub is_contractor: bool,
+    pub tax_id: Option<String>,
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub default_account_code: Option<String>,
+    #[serde(default)]
+    pub payment_terms_days: Option<i64>,
+    /// Other names the contact appears under on statements and receipts.
+    /// Left unchanged when absent.
+    #[serde(default)]
+    pub aliases: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ContactDetail {
+    #[serde(flatten)]
+    pub contact: aequi_storage::ContactRecord,
+    pub default_account_code: Option<String>,
+    pub aliases: Vec<String>,
+}
+
+/// Check and store the details beyond the contact's card: its default
+/// account, payment terms and aliases.
+async fn save_contact_details(
+    db: &aequi_storage::DbPool,
+    id: i64,
+    default_account_code: Option<&str>,
+    payment_terms_days: Option<i64>,
+    aliases: Option<&[String]>,
+) -> Result<(), CommandError> {
+    let default_account_id = match default_account_code.map(str::trim) {
+        Some(code) if !code.is_empty() => {
+            let account = aequi_storage::get_account_by_code(db, code)
+                .await?
+                .ok_or_else(|| CommandError::not_found(format!("Account {code} not found")))?;
+            if account.is_archived {
+                return Err(CommandError::validation(format!(
+                    "Account {code} is archived"
+                )));
+            }
+            account.id.map(|id| id.0)
+        }
+        _ => None,
+    };
+    if payment_terms_days.is_some_and(|d| !(0..=365).contains(&d)) {
+        return Err(CommandError::validation(
+            "Payment terms must be between 0 and 365 days",
+        ));
+    }
+    if let Some(aliases) = aliases {
+        for alias in aliases {
+            match aequi_storage::get_alias_contact(db, alias).await? {
+                Some(other) if other != id => {
+                    return Err(CommandError::validation(format!(
+                        "Alias \"{}\" already belongs to another contact",
+                        alias.trim()
+                    )));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    aequi_storage::set_contact_defaults(db, id, default_account_id, payment_terms_days).await?;
+    if let Some(aliases) = aliases {
+        aequi_storage::set_contact_aliases(db, id, aliases).await?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_contacts(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    include_archived: Option<bool>,
+) -> Result<Vec<aequi_storage::ContactRecord>, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let mut contacts = aequi_storage::get_all_contacts(&db)
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))?;
+    if !include_archived.unwrap_or(false) {
+        contacts.retain(|c| !c.is_archived);
+    }
+    Ok(contacts)
+}
+
+#[tauri::command]
+pub async fn get_contact(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+) -> Result<ContactDetail, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let contact = aequi_storage::get_contact_by_id(&db, id)
+        .await?
+        .ok_or_else(|| CommandError::not_found("Contact not found"))?;
+    let default_account_code = match contact.default_account_id {
+        Some(account_id) => aequi_storage::get_all_accounts(&db)
+            .await?
+            .into_iter()
+            .find(|a| a.id.map(|id| id.0) == Some(account_id))
+            .map(|a| a.code),
+        None => None,
+    };
+    let aliases = aequi_storage::get_contact_aliases(&db, id).await?;
+    Ok(ContactDetail {
+        contact,
+        default_account_code,
+        aliases,
+    })
+}
+
+#[tauri::command]
+pub async fn archive_contact(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+    archived: bool,
+) -> Result<(), CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    if !aequi_storage::set_contact_archived(&db, id, archived).await? {
+        return Err(CommandError::not_found("Contact not found"));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn delete_contact(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+) -> Result<(), CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    aequi_storage::get_contact_by_id(&db, id)
+        .await?
+        .ok_or_else(|| CommandError::not_found("Contact not found"))?;
+    if !aequi_storage::delete_contact(&db, id).await? {
+        return Err(CommandError::validation(
+            "Contact has invoices, bills or time entries; archive it instead",
+        ));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn create_contact(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    input: ContactInput,
+) -> Result<aequi_storage::ContactRecord, CommandError> {
+    // Input validation
+    let name = input.name.trim().to_string();
+    if name.is_empty() {
+        return Err(CommandError::validation("Contact name is required"));
+    }
+    if let Some(ref email) = input.email {
+        let email = email.trim();
+        if !email.is_empty() && (!email.contains('@') || !email.contains('.')) {
+            return Err(CommandError::validation("Invalid email address format"));
+        }
+    }
+    if !["Client", "Vendor", "Contractor"].contains(&input.contact_type.as_str()) {
+        return Err(CommandError::validation(
+            "Contact type must be Client, Vendor, or Contractor",
+        ));
+    }
+
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let id = aequi_storage::insert_contact(
+        &db,
+        &name,
+        input.email.as_deref(),
+        input.phone.as_deref(),
+        input.address.as_deref(),
+        &input.contact_type,
+        input.is_contractor,
+        input.tax_id.as_deref(),
+        input.notes.as_deref(),
+    )
+    .await
+    .map_err(|e| CommandError {
+        code: "DATABASE".into(),
+        message: e.to_string(),
+    })?;
+    if let Err(e) = save_contact_details(
+        &db,
+        id,
+        input.default_account_code.as_deref(),
+        input.payment_terms_days,
+        input.aliases.as_deref(),
+    )
+    .await
+    {
+        // Don't leave a half-made contact behind.
+        let _ = aequi_storage::delete_contact(&db, id).await;
+        return Err(e);
+    }
+
+    aequi_storage::get_contact_by_id(&db, id)
+        .await
+        .map_err(|e| CommandError {
+            code: "DATABASE".into(),
+            message: e.to_string(),
+        })?
+        .ok_or(CommandError::not_found("Contact not found after insert"))
+}
+
+// ── Invoice commands ─────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct InvoiceInput {
+    pub invoice_number: String,
+    pub contact_id: i64,
+    pub issue_date: String,
+    pub due_date: String,
+    pub notes: Option<String>,
+    pub terms: Option<String>,
+}
+
+#[tauri::command]
+pub async fn get_invoices(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Vec<aequi_storage::InvoiceRecord>, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    aequi_storage::get_all_invoices(&db)
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))
+}
+
+#[tauri::command]
+pub async fn create_invoice(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    input: InvoiceInput,
+) -> Result<aequi_storage::InvoiceRecord, CommandError> {
+    // Input validation
+    let invoice_number = input.invoice_number.trim().to_string();
+    if invoice_number.is_empty() {
+        return Err(CommandError::validation("Invoice number is required"));
+    }
+    let issue_date = NaiveDate::parse_from_str(&input.issue_date, "%Y-%m-%d")
+        .map_err(|_| CommandError::validation("Invalid issue date format (expected YYYY-MM-DD)"))?;
+    let due_date = NaiveDate::parse_from_str(&input.due_date, "%Y-%m-%d")
+        .map_err(|_| CommandError::validation("Invalid due date format (expected YYYY-MM-DD)"))?;
+    if due_date < issue_date {
+        return Err(CommandError::validation(
+            "Due date must be on or after issue date",
+        ));
+    }
+
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+
+    // Verify contact exists
+    aequi_storage::get_contact_by_id(&db, input.contact_id)
+        .await
+        .map_err(|e| CommandError {
+            code: "DATABASE".into(),
+            message: e.to_string(),
+        })?
+        .ok_or(CommandError::not_found("Contact not found"))?;
+
+    let id = aequi_storage::insert_invoice(
+        &db,
+        &invoice_number,
+        input.contact_id,
+        "Draft",
+        None,
+        &input.issue_date,
+        &input.due_date,
+        None,
+        None,
+        input.notes.as_deref(),
+        input.terms.as_deref(),
+    )
+    .await
+    .map_err(|e| CommandError {
+        code: "DATABASE".into(),
+        message: e.to_string(),
+    })?;
+
+    aequi_storage::get_invoice_by_id(&db, id)
+        .await
+        .map_err(|e| CommandError {
+            code: "DATABASE".into(),
+            message: e.to_string(),
+        })?
+        .ok_or(CommandError::not_found("Invoice not found after insert"))
+}
+
+#[tauri::command]
+pub async fn get_invoice_aging(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Vec<aequi_storage::InvoiceRecord>, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    aequi_storage::get_invoice_aging(&db)
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))
+}
+
+/// Parse an optional `as_of` date, today when absent.
+fn as_of_date(as_of: Option<&str>) -> Result<NaiveDate, CommandError> {
+    match as_of {
+        Some(d) => NaiveDate::parse_from_str(d, "%Y-%m-%d")
+            .map_err(|_| CommandError::validation("Invalid date format (expected YYYY-MM-DD)")),
+        None => Ok(chrono::Utc::now().date_naive()),
+    }
+}
+
+/// What customers owe on sent invoices, bucketed by days past due.
+#[tauri::command]
+pub async fn get_ar_aging(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    as_of: Option<String>,
+) -> Result<AgingReport, CommandError> {
+    let as_of = as_of_date(as_of.as_deref())?;
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    Ok(aging::ar_aging(&db, as_of).await?)
+}
+
+/// What is owed to vendors on unpaid bills, bucketed by days past due.
+#[tauri::command]
+pub async fn get_ap_aging(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    as_of: Option<String>,
+) -> Result<AgingReport, CommandError> {
+    let as_of = as_of_date(as_of.as_deref())?;
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    Ok(aging::ap_aging(&db, as_of).await?)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PaymentInput {
+    pub invoice_id: i64,
+    pub amount_cents: i64,
+    pub date: String,
+    pub method: Option<String>,
+    /// The bank or cash account the payment was deposited to; Checking when
+    /// omitted.
+    #[serde(default)]
+    pub account_code: Option<String>,
+}
+
+/// Account payments are deposited to unless another is chosen.
+const DEFAULT_DEPOSIT_ACCOUNT_CODE: &str = "1000";
+
+/// Record a payment against an invoice, post it as revenue and mark the
+/// invoice partially or fully paid. Overpayments become credit for the
+/// customer.
+#[tauri::command]
+pub async fn record_invoice_payment(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    input: PaymentInput,
+) -> Result<RecordedPayment, CommandError> {
+    let date = NaiveDate::parse_from_str(&input.date, "%Y-%m-%d")
+        .map_err(|_| CommandError::validation("Invalid date format (expected YYYY-MM-DD)"))?;
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let method = input
+        .method
+        .as_deref()
+        .map(str::trim)
+        .filter(|m| !m.is_empty());
+    let recorded = receivables::record_invoice_payment(
+        &db,
+        &NewPayment {
+            invoice_id: input.invoice_id,
+            amount_cents: input.amount_cents,
+            date,
+            deposit_account_code: input
+                .account_code
+                .as_deref()
+                .unwrap_or(DEFAULT_DEPOSIT_ACCOUNT_CODE),
+            method,
+        },
+    )
+    .await?;
+    crate::hooks::transaction_posted(&db, recorded.transaction_id, TransactionSource::Manual);
+    Ok(recorded)
+}
+
+/// Pay an invoice from the customer's credit, as far as it goes.
+#[tauri::command]
+pub async fn apply_customer_credit(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    invoice_id: i64,
+    date: String,
+) -> Result<RecordedPayment, CommandError> {
+    let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+        .map_err(|_| CommandError::validation("Invalid date format (expected YYYY-MM-DD)"))?;
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let recorded = receivables::apply_customer_credit(&db, invoice_id, date).await?;
+    crate::hooks::transaction_posted(&db, recorded.transaction_id, TransactionSource::Manual);
+    Ok(recorded)
+}
+
+#[derive(Debug, Serialize)]
+pub struct InvoicePayments {
+    pub balance: InvoiceBalance,
+    pub payments: Vec<aequi_storage::PaymentRecord>,
+    /// The customer's unspent credit.
+    pub customer_credit_cents: i64,
+}
+
+#[tauri::command]
+pub async fn get_invoice_payments(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    invoice_id: i64,
+) -> Result<InvoicePayments, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let balance = receivables::invoice_balance(&db, invoice_id)
+        .await?
+        .ok_or_else(|| CommandError::not_found("Invoice not found"))?;
+    let customer_credit_cents = receivables::customer_credit_cents(&db, balance.contact_id).await?;
+    Ok(InvoicePayments {
+        payments: aequi_storage::get_payments_for_invoice(&db, invoice_id).await?,
+        customer_credit_cents,
+        balance,
+    })
+}
+
+// ── Bill commands ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct BillInput {
+    pub contact_id: i64,
+    pub bill_number: Option<String>,
+    pub bill_date: String,
+    pub due_date: String,
+    pub amount_cents: i64,
+    /// The expense account the bill is booked to when paid.
+    pub account_code: String,
+    pub notes: Option<String>,
+}
+
+#[tauri::command]
+pub async fn create_bill(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    input: BillInput,
+) -> Result<Bill, CommandError> {
+    let bill_date = NaiveDate::parse_from_str(&input.bill_date, "%Y-%m-%d")
+        .map_err(|_| CommandError::validation("Invalid bill date format (expected YYYY-MM-DD)"))?;
+    let due_date = NaiveDate::parse_from_str(&input.due_date, "%Y-%m-%d")
+        .map_err(|_| CommandError::validation("Invalid due date format (expected YYYY-MM-DD)"))?;
+    if due_date < bill_date {
+        return Err(CommandError::validation(
+            "Due date must be on or after bill date",
+        ));
+    }
+    if input.amount_cents <= 0 {
+        return Err(CommandError::validation("Bill amount must be positive"));
+    }
+
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    aequi_storage::get_contact_by_id(&db, input.contact_id)
+        .await?
+        .ok_or_else(|| CommandError::not_found("Contact not found"))?;
+    let account = aequi_storage::get_account_by_code(&db, &input.account_code)
+        .await?
+        .ok_or_else(|| {
+            CommandError::not_found(format!("Account not found: {}", input.account_code))
+        })?;
+    if account.account_type != aequi_core::AccountType::Expense || account.is_archived {
+        return Err(CommandError::validation(
+            "Bills must be booked to an active expense account",
+        ));
+    }
+
+    let id = payables::insert_bill(
+        &db,
+        &NewBill {
+            contact_id: input.contact_id,
+            bill_number: input
+                .bill_number
+                .map(|n| n.trim().to_string())
+                .filter(|n| !n.is_empty()),
+            bill_date,
+            due_date,
+            amount_cents: input.amount_cents,
+            account_id: account
+                .id
+                .ok_or_else(|| CommandError::internal("Account missing ID"))?
+                .0,
+            notes: input.notes,
+        },
+    )
+    .await?;
+    payables::get_bill(&db, id)
+        .await?
+        .ok_or_else(|| CommandError::not_found("Bill not found after insert"))
+}
+
+#[tauri::command]
+pub async fn get_bills(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    open_only: Option<bool>,
+) -> Result<Vec<Bill>, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    Ok(payables::get_bills(&db, open_only.unwrap_or(false)).await?)
+}
+
+#[tauri::command]
+pub async fn void_bill(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+) -> Result<(), CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    payables::get_bill(&db, id)
+        .await?
+        .ok_or_else(|| CommandError::not_found("Bill not found"))?;
+    if !payables::void_bill(&db, id).await? {
+        return Err(CommandError::validation(
+            "Bill has payments recorded and can't be voided",
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BillPaymentInput {
+    pub bill_id: i64,
+    pub amount_cents: i64,
+    pub date: String,
+    /// The bank, cash or card account the bill was paid from.
+    pub account_code: String,
+}
+
+/// Pay some or all of a bill, posting the expense.
+#[tauri::command]
+pub async fn record_bill_payment(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    input: BillPaymentInput,
+) -> Result<BillPayment, CommandError> {
+    let date = NaiveDate::parse_from_str(&input.date, "%Y-%m-%d")
+        .map_err(|_| CommandError::validation("Invalid date format (expected YYYY-MM-DD)"))?;
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let paid = payables::record_bill_payment(
+        &db,
+        input.bill_id,
+        input.amount_cents,
+        date,
+        &input.account_code,
+    )
+    .await?;
+    crate::hooks::transaction_posted(&db, paid.transaction_id, TransactionSource::Manual);
+    Ok(paid)
+}
+
+#[derive(Debug, Serialize)]
+pub struct NecSummaryEntry {
+    pub contact_id: i64,
+    pub contact_name: String,
+    pub ytd_cents: i64,
+    pub over_threshold: bool,
+}
+
+#[tauri::command]
+pub async fn get_1099_summary(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    year: Option<u16>,
+) -> Result<Vec<NecSummaryEntry>, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let yr = year.unwrap_or(chrono::Utc::now().date_naive().year() as u16);
+
+    // Single JOIN query instead of N+1
+    let rows = aequi_storage::get_contractor_ytd_payments(&db, yr)
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))?;
+
+    let entries = rows
+        .into_iter()
+        .map(|(id, name, ytd)| NecSummaryEntry {
+            contact_id: id,
+            contact_name: name,
+            ytd_cents: ytd,
+            over_threshold: aequi_core::check_1099_threshold(Money::from_cents(ytd)),
+        })
+        .collect();
+    Ok(entries)
+}
+
+// ── Time tracking commands ──────────────────────────────────────────────────
+
+async fn validate_time_entry(
+    db: &aequi_storage::DbPool,
+    entry: &TimeEntryInput,
+) -> Result<(), CommandError> {
+    if entry.hourly_rate_cents < 0 {
+        return Err(CommandError::validation("Hourly rate cannot be negative"));
+    }
+    aequi_storage::get_contact_by_id(db, entry.contact_id)
+        .await?
+        .ok_or_else(|| CommandError::not_found("Contact not found"))?;
+    Ok(())
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate, CommandError> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| CommandError::validation("Invalid date format (expected YYYY-MM-DD)"))
+}
+
+/// Time entries newest first, for one client if given.
+#[tauri::command]
+pub async fn get_time_entries(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    contact_id: Option<i64>,
+    unbilled_only: Option<bool>,
+) -> Result<Vec<TimeEntry>, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    Ok(time_tracking::get_time_entries(&db, contact_id, unbilled_only.unwrap_or(false)).await?)
+}
+
+/// Record `minutes` worked on `date` without the timer.
+#[tauri::command]
+pub async fn create_time_entry(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    entry: TimeEntryInput,
+    date: String,
+    minutes: i64,
+) -> Result<TimeEntry, CommandError> {
+    let date = parse_date(&date)?;
+    if minutes <= 0 {
+        return Err(CommandError::validation("Time worked must be positive"));
+    }
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    validate_time_entry(&db, &entry).await?;
+    let id = time_tracking::insert_time_entry(&db, &entry, date, minutes).await?;
+    time_tracking::get_time_entry(&db, id)
+        .await?
+        .ok_or_else(|| CommandError::internal("Time entry missing after insert"))
+}
+
+/// Change an entry. Billed and running entries can't be changed.
+#[tauri::command]
+pub async fn update_time_entry(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+    entry: TimeEntryInput,
+    date: String,
+    minutes: i64,
+) -> Result<TimeEntry, CommandError> {
+    let date = parse_date(&date)?;
+    if minutes <= 0 {
+        return Err(CommandError::validation("Time worked must be positive"));
+    }
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    validate_time_entry(&db, &entry).await?;
+    if !time_tracking::update_time_entry(&db, id, &entry, date, minutes).await? {
+        return Err(match time_tracking::get_time_entry(&db, id).await? {
+            None => CommandError::not_found("Time entry not found"),
+            Some(e) if e.is_running() => {
+                CommandError::validation("Stop the timer before editing this entry")
+            }
+            Some(_) => CommandError::validation("Time already invoiced cannot be changed"),
+        });
+    }
+    time_tracking::get_time_entry(&db, id)
+        .await?
+        .ok_or_else(|| CommandError::not_found("Time entry not found"))
+}
+
+#[tauri::command]
+pub async fn delete_time_entry(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+) -> Result<(), CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    if !time_tracking::delete_time_entry(&db, id).await? {
+        return Err(match time_tracking::get_time_entry(&db, id).await? {
+            None => CommandError::not_found("Time entry not found"),
+            Some(_) => CommandError::validation("Time already invoiced cannot be deleted"),
+        });
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_running_timer(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Option<TimeEntry>, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    Ok(time_tracking::running_timer(&db).await?)
+}
+
+/// Start timing work for a client now.
+#[tauri::command]
+pub async fn start_timer(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    entry: TimeEntryInput,
+) -> Result<TimeEntry, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    validate_time_entry(&db, &entry).await?;
+    let today = chrono::Local::now().date_naive();
+    let id = time_tracking::start_timer(&db, &entry, today, chrono::Utc::now().naive_utc())
+        .await?
+        .ok_or_else(|| CommandError::validation("A timer is already running"))?;
+    time_tracking::get_time_entry(&db, id)
+        .await?
+        .ok_or_else(|| CommandError::internal("Time entry missing after insert"))
+}
+
+/// Stop the running timer, recording the time worked.
+#[tauri::command]
+pub async fn stop_timer(state: State<'_, Arc<Mutex<AppState>>>) -> Result<TimeEntry, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    time_tracking::stop_timer(&db, chrono::Utc::now().naive_utc())
+        .await?
+        .ok_or_else(|| CommandError::not_found("No timer is running"))
+}
+
+/// Bill a client's unbilled time on a new draft invoice, one line per
+/// project and rate. Only time worked up to `through_date` (by default the
+/// issue date) is included.
+#[tauri::command]
+pub async fn invoice_unbilled_time(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    input: InvoiceInput,
+    through_date: Option<String>,
+) -> Result<aequi_storage::InvoiceRecord, CommandError> {
+    let invoice_number = input.invoice_number.trim().to_string();
+    if invoice_number.is_empty() {
+        return Err(CommandError::validation("Invoice number is required"));
+    }
+    let issue_date = NaiveDate::parse_from_str(&input.issue_date, "%Y-%m-%d")
+        .map_err(|_| CommandError::validation("Invalid issue date format (expected YYYY-MM-DD)"))?;
+    let due_date = NaiveDate::parse_from_str(&input.due_date, "%Y-%m-%d")
+        .map_err(|_| CommandError::validation("Invalid due date format (expected YYYY-MM-DD)"))?;
+    if due_date < issue_date {
+        return Err(CommandError::validation(
+            "Due date must be on or after issue date",
+        ));
+    }
+    let through = match through_date {
+        Some(d) => parse_date(&d)?,
+        None => issue_date,
+    };
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    aequi_storage::get_contact_by_id(&db, input.contact_id)
+        .await?
+        .ok_or_else(|| CommandError::not_found("Contact not found"))?;
+
+    let draft = time_tracking::DraftInvoice {
+        invoice_number,
+        issue_date,
+        due_date,
+        notes: input.notes,
+        terms: input.terms,
+    };
+    let id = time_tracking::invoice_unbilled_time(&db, input.contact_id, through, &draft)
+        .await?
+        .ok_or_else(|| CommandError::validation("No unbilled time to invoice"))?;
+    aequi_storage::get_invoice_by_id(&db, id)
+        .await?
+        .ok_or_else(|| CommandError::not_found("Invoice not found after insert"))
+}
+
+// ── Email delivery ──────────────────────────────────────────────────────────
+
 #[derive(Debug, Deserialize)]
-pub struct InvoiceInput {
-    pub invoice_number: String,
-    pub contact_id: i64,
-    pub issue_date: String,
-    pub due_date: String,
-    pub notes: Option<String>,
-    pub terms: Option<String>,
+pub struct SendInvoiceInput {
+    pub invoice_id: i64,
+    pub subject: Option<String>,
+}
+
+fn record_to_contact(rec: &aequi_storage::ContactRecord) -> aequi_core::Contact {
+    let contact_type = match rec.contact_type.as_str() {
+        "Vendor" => aequi_core::ContactType::Vendor,
+        "Contractor" => aequi_core::ContactType::Contractor,
+        _ => aequi_core::ContactType::Client,
+    };
+
+    aequi_core::Contact {
+        id: Some(ContactId(rec.id)),
+        name: rec.name.clone(),
+        email: rec.email.clone(),
+        phone: rec.phone.clone(),
+        address: rec.address.clone(),
+        contact_type,
+        is_contractor: rec.is_contractor,
+        tax_id: rec.tax_id.clone(),
+        notes: rec.notes.clone(),
+    }
+}
+
+/// Save how invoices are emailed (SMTP or Resend), or `None` to stop
+/// sending. The config holds credentials, so it can't be read back.
+#[tauri::command]
+pub async fn configure_email(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    config: Option<serde_json::Value>,
+) -> Result<(), CommandError> {
+    let value = match config {
+        Some(config) => {
+            let parsed: aequi_email::EmailConfig = serde_json::from_value(config.clone())
+                .map_err(|e| CommandError::validation(format!("Invalid email config: {e}")))?;
+            if parsed.from_address().1.trim().is_empty() {
+                return Err(CommandError::validation("A from address is required"));
+            }
+            config.to_string()
+        }
+        None => String::new(),
+    };
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    aequi_storage::set_setting(&db, "email_config", &value).await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn send_invoice(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    input: SendInvoiceInput,
+) -> Result<aequi_email::DeliveryResult, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+
+    // Load email config from settings
+    let config_json = aequi_storage::get_setting(&db, "email_config")
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))?
+        .filter(|c| !c.is_empty())
+        .ok_or(CommandError::config(
+            "Email not configured — set up invoice email in Settings",
+        ))?;
+
+    let config: aequi_email::EmailConfig = serde_json::from_str(&config_json)
+        .map_err(|e| CommandError::internal(format!("Invalid email config: {e}")))?;
+
+    let rec = aequi_storage::get_invoice_by_id(&db, input.invoice_id)
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))?
+        .ok_or(CommandError::internal("Invoice not found"))?;
+
+    let lines = aequi_storage::get_invoice_lines(&db, input.invoice_id)
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))?;
+    let tax_lines = aequi_storage::get_invoice_tax_lines(&db, input.invoice_id)
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))?;
+    let invoice = aequi_storage::receivables::records_to_invoice(&rec, &lines, &tax_lines)?;
+
+    let contact_rec = aequi_storage::get_contact_by_id(&db, rec.contact_id)
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))?
+        .ok_or(CommandError::internal("Contact not found"))?;
+    let contact = record_to_contact(&contact_rec);
+
+    let result = aequi_email::send_invoice(&config, &invoice, &contact, input.subject.as_deref())
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))?;
+
+    // Update invoice status to Sent
+    let sent_data = serde_json::json!({ "sent_at": chrono::Utc::now().to_rfc3339() }).to_string();
+    let _ =
+        aequi_storage::update_invoice_status(&db, input.invoice_id, "Sent", Some(&sent_data)).await;
+
+    Ok(result)
+}
+
+// ── Export commands ──────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub async fn export_beancount(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<String, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let accounts = aequi_storage::get_all_accounts(&db)
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))?;
+
+    // For now export with empty transactions — full transaction fetch to be added
+    Ok(aequi_core::export::beancount::export_beancount(
+        &accounts,
+        &[],
+    ))
+}
+
+#[tauri::command]
+pub async fn export_qif(_state: State<'_, Arc<Mutex<AppState>>>) -> Result<String, CommandError> {
+    Ok(aequi_core::export::qif::export_qif(
+        &[],
+        aequi_core::AccountType::Asset,
+    ))
+}
+
+/// A report [`export_report`] can write.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReportKind {
+    ProfitLoss,
+    /// As of the end of the period.
+    BalanceSheet,
+    /// For the tax year the period ends in.
+    ScheduleC,
+    Register {
+        account_code: String,
+    },
+    /// Covers the session's own dates; the period is not used.
+    Reconciliation {
+        session_id: i64,
+    },
+    VendorSpending {
+        #[serde(default)]
+        limit: Option<usize>,
+    },
+    /// Monthly unless another interval is given.
+    OwnersEquity {
+        #[serde(default)]
+        interval: Option<aequi_storage::reports::ReportInterval>,
+    },
+    /// Covers the period in its definition, as of today; the period is not
+    /// used.
+    Saved {
+        id: i64,
+    },
+}
+
+/// Write a report for `period` to `path` as CSV, JSON or PDF.
+#[tauri::command]
+pub async fn export_report(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    kind: ReportKind,
+    period: aequi_core::DateRange,
+    format: aequi_core::export::ReportFormat,
+    path: String,
+) -> Result<(), CommandError> {
+    use aequi_core::export::ReportFormat;
+    use aequi_storage::reports;
+
+    if period.start > period.end {
+        return Err(CommandError::validation("Start date is after end date"));
+    }
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let (json, table) = match kind {
+        ReportKind::ProfitLoss => {
+            let report = reports::profit_loss(&db, period).await?;
+            (serde_json::to_vec_pretty(&report), report.table())
+        }
+        ReportKind::BalanceSheet => {
+            let report = reports::balance_sheet(&db, period.end).await?;
+            (serde_json::to_vec_pretty(&report), report.table())
+        }
+        ReportKind::ScheduleC => {
+            let year = period.end.year() as u16;
+            let rules = load_tax_rules(year)?;
+            let snapshot = aequi_storage::build_ledger_snapshot(&db, FiscalYear::new(year), None)
+                .await
+                .map_err(|e| CommandError::internal(e.to_string()))?;
+            let preview = aequi_core::tax::engine::schedule_c_preview(&rules, &snapshot);
+            (
+                serde_json::to_vec_pretty(&preview),
+                reports::schedule_c_table(&preview),
+            )
+        }
+        ReportKind::Register { account_code } => {
+            let report = reports::account_register(&db, &account_code, period)
+                .await?
+                .ok_or_else(|| CommandError::not_found(format!("No account {account_code}")))?;
+            (serde_json::to_vec_pretty(&report), report.table())
+        }
+        ReportKind::Reconciliation { session_id } => {
+            let report = reports::reconciliation(&db, session_id)
+                .await?
+                .ok_or_else(|| CommandError::not_found("Reconciliation session not found"))?;
+            (serde_json::to_vec_pretty(&report), report.table())
+        }
+        ReportKind::VendorSpending { limit } => {
+            let limit = limit.unwrap_or(VENDOR_SPENDING_LIMIT).max(1);
+            let report = reports::vendor_spending(&db, period, limit).await?;
+            (serde_json::to_vec_pretty(&report), report.table())
+        }
+        ReportKind::OwnersEquity { interval } => {
+            let interval = interval.unwrap_or(reports::ReportInterval::Month);
+            let report = reports::owners_equity(&db, period, interval).await?;
+            (serde_json::to_vec_pretty(&report), report.table())
+        }
+        ReportKind::Saved { id } => {
+            let today = chrono::Local::now().date_naive();
+            let report = saved_reports::run_saved_report(&db, id, today).await?;
+            (serde_json::to_vec_pretty(&report), report.table())
+        }
+    };
+
+    let bytes = match format {
+        ReportFormat::Csv => table.to_csv().into_bytes(),
+        ReportFormat::Json => json.map_err(|e| CommandError::internal(e.to_string()))?,
+        ReportFormat::Pdf => {
+            let money = money_format(&db).await?;
+            tokio::task::spawn_blocking(move || aequi_pdf::render_report_pdf(&table, &money))
+                .await
+                .map_err(|e| CommandError::internal(e.to_string()))?
+                .map_err(CommandError::internal)?
+        }
+    };
+    tokio::fs::write(&path, bytes)
+        .await
+        .map_err(|e| CommandError::internal(format!("Failed to write {path}: {e}")))
+}
+
+/// Write the books as a ledger-cli/hledger journal. With a period, only its
+/// transactions are written, after an opening balances entry carrying in
+/// what came before; without one, everything is.
+#[tauri::command]
+pub async fn export_ledger_format(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    path: String,
+    period: Option<aequi_core::DateRange>,
+) -> Result<usize, CommandError> {
+    if period.is_some_and(|p| p.start > p.end) {
+        return Err(CommandError::validation("Start date is after end date"));
+    }
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let accounts = aequi_storage::get_all_accounts(&db).await?;
+    let (range, opening) = match period {
+        Some(p) => (
+            p,
+            Some(aequi_storage::reports::opening_balances(&db, p.start).await?),
+        ),
+        // Dates are stored as text, so the bounds must compare as text too.
+        None => (
+            aequi_core::DateRange::new(
+                NaiveDate::from_ymd_opt(1, 1, 1).unwrap(),
+                NaiveDate::from_ymd_opt(9999, 12, 31).unwrap(),
+            ),
+            None,
+        ),
+    };
+    let transactions = aequi_storage::reports::period_transactions(&db, range).await?;
+    let journal = aequi_core::export::export_ledger(&accounts, opening.as_ref(), &transactions);
+    tokio::fs::write(&path, journal)
+        .await
+        .map_err(|e| CommandError::internal(format!("Failed to write {path}: {e}")))?;
+    Ok(transactions.len())
+}
+
+/// Write the transactions dated within `period` for QuickBooks to import,
+/// as IIF for QuickBooks Desktop or journal entry CSV for QuickBooks
+/// Online. Returns the number of transactions written.
+#[tauri::command]
+pub async fn export_quickbooks(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    path: String,
+    period: aequi_core::DateRange,
+    format: aequi_core::export::QuickbooksFormat,
+) -> Result<usize, CommandError> {
+    if period.start > period.end {
+        return Err(CommandError::validation("Start date is after end date"));
+    }
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let accounts = aequi_storage::get_all_accounts(&db).await?;
+    let transactions = aequi_storage::reports::period_transactions(&db, period).await?;
+    let out = aequi_core::export::export_quickbooks(&accounts, &transactions, format);
+    tokio::fs::write(&path, out)
+        .await
+        .map_err(|e| CommandError::internal(format!("Failed to write {path}: {e}")))?;
+    Ok(transactions.len())
+}
+
+/// Write an asset or liability account's activity within `period` as an
+/// OFX bank or credit card statement. Returns the number of transactions
+/// written.
+#[tauri::command]
+pub async fn export_ofx(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    path: String,
+    account_code: String,
+    period: aequi_core::DateRange,
+) -> Result<usize, CommandError> {
+    if period.start > period.end {
+        return Err(CommandError::validation("Start date is after end date"));
+    }
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let account = aequi_storage::get_account_by_code(&db, &account_code)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("Account not found: {account_code}")))?;
+    let name = account.name.to_lowercase();
+    let account_type = match account.account_type {
+        aequi_core::AccountType::Asset if name.contains("savings") => "SAVINGS",
+        aequi_core::AccountType::Asset => "CHECKING",
+        aequi_core::AccountType::Liability if name.contains("card") => "CREDITCARD",
+        aequi_core::AccountType::Liability => "CREDITLINE",
+        _ => {
+            return Err(CommandError::validation(format!(
+                "{account_code} {} is not an asset or liability account",
+                account.name
+            )))
+        }
+    };
+    let register = aequi_storage::reports::account_register(&db, &account_code, period)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("Account not found: {account_code}")))?;
+    let currency = aequi_storage::get_preferences(&db).await?.default_currency;
+
+    // A transaction touching the account twice still needs unique FITIDs.
+    let mut seen = std::collections::HashMap::<i64, usize>::new();
+    let transactions: Vec<aequi_import::OfxTransaction> = register
+        .entries
+        .iter()
+        .filter_map(|entry| {
+            let date = NaiveDate::parse_from_str(&entry.date, "%Y-%m-%d").ok()?;
+            let n = seen.entry(entry.transaction_id).or_default();
+            *n += 1;
+            let fit_id = if *n == 1 {
+                entry.transaction_id.to_string()
+            } else {
+                format!("{}-{n}", entry.transaction_id)
+            };
+            Some(aequi_import::OfxTransaction {
+                fit_id,
+                date,
+                amount: entry.debit_cents - entry.credit_cents,
+                memo: entry.memo.clone(),
+                name: Some(entry.description.clone()),
+                check_number: None,
+            })
+        })
+        .collect();
+    let closing = register.closing_balance_cents;
+    let statement = aequi_import::OfxStatement {
+        account: aequi_import::OfxAccount {
+            account_id: account_code,
+            bank_id: None,
+            account_type: Some(account_type.to_string()),
+        },
+        start_date: register.start_date,
+        end_date: register.end_date,
+        transactions,
+        currency: Some(currency),
+        ledger_balance: Some(match account.account_type {
+            aequi_core::AccountType::Liability => -closing,
+            _ => closing,
+        }),
+    };
+    tokio::fs::write(&path, aequi_import::ofx::write(&statement))
+        .await
+        .map_err(|e| CommandError::internal(format!("Failed to write {path}: {e}")))?;
+    Ok(statement.transactions.len())
+}
+
+/// Write the whole ledger as a GnuCash XML book in the base currency.
+/// Returns the number of transactions written.
+#[tauri::command]
+pub async fn export_gnucash(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    path: String,
+) -> Result<usize, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let accounts = aequi_storage::get_all_accounts(&db).await?;
+    let currency = aequi_storage::get_preferences(&db).await?.default_currency;
+    let everything = aequi_core::DateRange::new(
+        NaiveDate::from_ymd_opt(1, 1, 1).unwrap(),
+        NaiveDate::from_ymd_opt(9999, 12, 31).unwrap(),
+    );
+    let transactions = aequi_storage::reports::period_transactions(&db, everything).await?;
+    let xml = aequi_import::gnucash::write_xml(&accounts, &transactions, &currency);
+    tokio::fs::write(&path, xml)
+        .await
+        .map_err(|e| CommandError::internal(format!("Failed to write {path}: {e}")))?;
+    Ok(transactions.len())
+}
+
+/// Write upcoming financial deadlines (estimated tax payments, invoice due
+/// dates, recurring transactions) as an iCalendar file. Returns the number
+/// of events written.
+#[tauri::command]
+pub async fn export_calendar(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    path: String,
+) -> Result<usize, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let (ics, count) = crate::reminders::deadline_calendar(&db).await?;
+    tokio::fs::write(&path, ics)
+        .await
+        .map_err(|e| CommandError::internal(format!("Failed to write {path}: {e}")))?;
+    Ok(count)
+}
+
+/// Zip the receipts dated within a range, renamed by date, vendor and total,
+/// with a CSV index linking each to its transaction.
+#[tauri::command]
+pub async fn export_receipts(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    start_date: String,
+    end_date: String,
+    output_path: String,
+) -> Result<aequi_storage::receipt_export::ReceiptExportSummary, CommandError> {
+    for date in [&start_date, &end_date] {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| CommandError::validation(format!("Invalid date: {date}")))?;
+    }
+    if start_date > end_date {
+        return Err(CommandError::validation("Start date is after end date"));
+    }
+    let (db, attachments_dir) = {
+        let s = state.lock().await;
+        (s.db.clone(), s.attachments_dir.clone())
+    };
+    aequi_storage::receipt_export::export_receipts(
+        &db,
+        &attachments_dir,
+        &start_date,
+        &end_date,
+        Path::new(&output_path),
+    )
+    .await
+    .map_err(|e| CommandError::internal(e.to_string()))
+}
+
+// ── Setup commands ──────────────────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct ChartTemplateOption {
+    pub template: aequi_core::ChartTemplate,
+    pub accounts: Vec<TemplateAccount>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TemplateAccount {
+    pub code: String,
+    pub name: String,
+    pub account_type: aequi_core::AccountType,
+}
+
+/// What the setup wizard has saved so far, and whether it has finished.
+#[tauri::command]
+pub async fn get_onboarding_status(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<aequi_storage::onboarding::OnboardingStatus, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    Ok(aequi_storage::onboarding::get_onboarding_status(&db).await?)
+}
+
+/// The starting charts of accounts to choose from.
+#[tauri::command]
+pub fn get_chart_templates() -> Vec<ChartTemplateOption> {
+    aequi_core::ChartTemplate::ALL
+        .into_iter()
+        .map(|template| ChartTemplateOption {
+            template,
+            accounts: template
+                .accounts()
+                .map(|(code, name, account_type, _)| TemplateAccount {
+                    code: code.to_string(),
+                    name: name.to_string(),
+                    account_type: *account_type,
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub async fn save_business_profile(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    profile: aequi_storage::onboarding::BusinessProfile,
+) -> Result<(), CommandError> {
+    let name = profile.name.trim().to_string();
+    if name.is_empty() {
+        return Err(CommandError::validation("Business name is required"));
+    }
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    aequi_storage::onboarding::set_business_profile(
+        &db,
+        &aequi_storage::onboarding::BusinessProfile { name, ..profile },
+    )
+    .await?;
+    Ok(())
+}
+
+/// Save the fiscal year start month (1-12) and the base currency code.
+#[tauri::command]
+pub async fn save_fiscal_settings(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    fiscal_year_start_month: u32,
+    base_currency: String,
+) -> Result<(), CommandError> {
+    let mut prefs = aequi_storage::Preferences::default();
+    prefs
+        .set(
+            aequi_storage::FISCAL_YEAR_START_SETTING,
+            &fiscal_year_start_month.to_string(),
+        )
+        .map_err(CommandError::validation)?;
+    prefs
+        .set(aequi_storage::DEFAULT_CURRENCY_SETTING, &base_currency)
+        .map_err(CommandError::validation)?;
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    aequi_storage::set_setting(
+        &db,
+        aequi_storage::FISCAL_YEAR_START_SETTING,
+        &prefs.fiscal_year_start_month.to_string(),
+    )
+    .await?;
+    aequi_storage::set_setting(
+        &db,
+        aequi_storage::DEFAULT_CURRENCY_SETTING,
+        &prefs.default_currency,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Switch the chart of accounts to a template. Default accounts outside it
+/// are archived unless they have transactions.
+#[tauri::command]
+pub async fn apply_chart_template(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    template: aequi_core::ChartTemplate,
+) -> Result<aequi_storage::onboarding::ChartTemplateOutcome, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    Ok(aequi_storage::onboarding::apply_chart_template(&db, template).await?)
+}
+
+/// Record starting balances as of `as_of`, replacing any entered before.
+/// Returns the opening balance transaction, or `None` if all were zero.
+#[tauri::command]
+pub async fn set_opening_balances(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    as_of: String,
+    balances: Vec<aequi_storage::onboarding::OpeningBalance>,
+) -> Result<Option<i64>, CommandError> {
+    let as_of = NaiveDate::parse_from_str(&as_of, "%Y-%m-%d")
+        .map_err(|_| CommandError::validation("Invalid date format (expected YYYY-MM-DD)"))?;
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    Ok(aequi_storage::onboarding::set_opening_balances(&db, as_of, &balances).await?)
+}
+
+/// Choose the folder watched for receipts. It is created if missing, and
+/// watched from now on.
+#[tauri::command]
+pub async fn set_intake_folder(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    path: String,
+) -> Result<(), CommandError> {
+    let dir = PathBuf::from(path.trim());
+    if !dir.is_absolute() {
+        return Err(CommandError::validation(
+            "Intake folder must be an absolute path",
+        ));
+    }
+    std::fs::create_dir_all(&dir).map_err(|e| {
+        CommandError::validation(format!(
+            "Cannot use {} as the intake folder: {e}",
+            dir.display()
+        ))
+    })?;
+
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    aequi_storage::set_setting(
+        &db,
+        aequi_storage::onboarding::INTAKE_FOLDER_SETTING,
+        &dir.to_string_lossy(),
+    )
+    .await?;
+    #[cfg(desktop)]
+    {
+        let recursive = load_intake_recursive(&db).await;
+        let mut s = state.lock().await;
+        let watcher = crate::receipt_intake::spawn_watcher(&dir, s.receipt_tx.clone(), recursive)
+            .map_err(|e| {
+            CommandError::internal(format!("Failed to watch {}: {e}", dir.display()))
+        })?;
+        s._intake_watcher = Some(watcher);
+    }
+    Ok(())
+}
+
+/// Mark setup finished so the wizard isn't shown again.
+#[tauri::command]
+pub async fn complete_onboarding(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<aequi_storage::onboarding::OnboardingStatus, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    aequi_storage::onboarding::complete_onboarding(&db).await?;
+    Ok(aequi_storage::onboarding::get_onboarding_status(&db).await?)
+}
+
+// ── Settings commands ───────────────────────────────────────────────────────
+
+/// The typed preferences, with defaults for any not yet set.
+#[tauri::command]
+pub async fn get_settings(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<aequi_storage::Preferences, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let mut prefs = aequi_storage::get_preferences(&db).await?;
+    if aequi_storage::get_setting(&db, aequi_storage::OCR_BACKEND_SETTING)
+        .await?
+        .is_none()
+    {
+        prefs.ocr_backend = OcrBackendKind::default().as_str().to_string();
+    }
+    Ok(prefs)
+}
+
+/// Settings the webview may read and write by key, besides the typed
+/// preferences. The rest have commands of their own that check what is
+/// written, and some hold secrets, such as the app lock's passphrase hash
+/// and backup and API credentials, that must never be read back.
+const GENERAL_SETTINGS: [&str; 9] = [
+    "business_name",
+    "business_ein",
+    "mcp_enabled",
+    "mcp_read_only",
+    aequi_storage::RECEIPT_THRESHOLD_SETTING,
+    fixed_assets::CAPITALIZE_THRESHOLD_SETTING,
+    "receipt_ocr_workers",
+    "receipt_intake_recursive",
+    "receipt_intake_move_files",
+];
+
+fn check_setting_key(key: &str) -> Result<(), CommandError> {
+    if aequi_storage::Preferences::KEYS.contains(&key) || GENERAL_SETTINGS.contains(&key) {
+        Ok(())
+    } else {
+        Err(CommandError::validation(format!(
+            "Setting {key} can't be read or changed directly"
+        )))
+    }
+}
+
+/// The value to store for one of the [`GENERAL_SETTINGS`], if it is one
+/// that setting can take. The intake settings are read when the app starts.
+fn check_general_value(key: &str, value: &str) -> Result<String, CommandError> {
+    let value = value.trim();
+    match key {
+        "receipt_ocr_workers" => match value.parse::<usize>() {
+            Ok(n) if n > 0 => Ok(n.to_string()),
+            _ => Err(CommandError::validation(
+                "OCR workers must be a whole number of at least 1",
+            )),
+        },
+        "receipt_intake_recursive" | "receipt_intake_move_files" => match value {
+            "true" | "false" => Ok(value.to_string()),
+            _ => Err(CommandError::validation(format!(
+                "{key} must be true or false"
+            ))),
+        },
+        _ => Ok(value.to_string()),
+    }
+}
+
+/// One of the preferences or [`GENERAL_SETTINGS`].
+#[tauri::command]
+pub async fn get_setting(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    key: String,
+) -> Result<Option<String>, CommandError> {
+    check_setting_key(&key)?;
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    aequi_storage::get_setting(&db, &key)
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))
+}
+
+/// Save one of the preferences or [`GENERAL_SETTINGS`]. Values are checked
+/// first, and a new OCR backend takes effect straight away.
+#[tauri::command]
+pub async fn set_setting(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    key: String,
+    value: String,
+) -> Result<(), CommandError> {
+    check_setting_key(&key)?;
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let mut prefs = aequi_storage::Preferences::default();
+    if !prefs.set(&key, &value).map_err(CommandError::validation)? {
+        let value = check_general_value(&key, &value)?;
+        return aequi_storage::set_setting(&db, &key, &value)
+            .await
+            .map_err(|e| CommandError::internal(e.to_string()));
+    }
+
+    if key == aequi_storage::OCR_BACKEND_SETTING {
+        let kind = OcrBackendKind::parse(&value)
+            .ok_or_else(|| CommandError::validation(format!("Unknown OCR backend: {value}")))?;
+        aequi_storage::set_setting(&db, &key, kind.as_str()).await?;
+        reload_ocr_backend(&state).await?;
+        return Ok(());
+    }
+    let stored = match key.as_str() {
+        aequi_storage::DEFAULT_CURRENCY_SETTING => prefs.default_currency,
+        aequi_storage::NUMBER_LOCALE_SETTING => prefs.number_locale,
+        _ => value.trim().to_string(),
+    };
+    aequi_storage::set_setting(&db, &key, &stored).await?;
+    Ok(())
+}
+
+// ── Local API commands ──────────────────────────────────────────────────────
+
+#[tauri::command]
+pub async fn get_local_api_status(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<crate::local_api::LocalApiStatus, CommandError> {
+    let (db, running) = {
+        let s = state.lock().await;
+        (s.db.clone(), s.local_api.is_some())
+    };
+    let settings = aequi_storage::get_local_api_settings(&db).await?;
+    Ok(crate::local_api::LocalApiStatus::new(&settings, running))
+}
+
+/// Turn the local HTTP API on or off, optionally moving it to another port
+/// or replacing its token, and restart it with the new settings.
+#[tauri::command]
+pub async fn configure_local_api(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<Mutex<AppState>>>,
+    enabled: bool,
+    port: Option<u16>,
+    regenerate_token: Option<bool>,
+) -> Result<crate::local_api::LocalApiStatus, CommandError> {
+    if enabled && !crate::local_api::AVAILABLE {
+        return Err(crate::local_api::unavailable());
+    }
+    if port.is_some_and(|p| p < 1024) {
+        return Err(CommandError::validation("Port must be 1024 or above"));
+    }
+    let (db, pipeline) = {
+        let s = state.lock().await;
+        (s.db.clone(), s.pipeline.clone())
+    };
+
+    let mut settings = aequi_storage::get_local_api_settings(&db).await?;
+    settings.enabled = enabled;
+    if let Some(port) = port {
+        settings.port = port;
+    }
+    if enabled && (settings.token.is_none() || regenerate_token.unwrap_or(false)) {
+        settings.token = Some(crate::local_api::generate_token()?);
+    }
+    aequi_storage::set_local_api_settings(&db, &settings).await?;
+
+    if let Some(old) = state.lock().await.local_api.take() {
+        old.abort();
+    }
+    let handle = crate::local_api::start(&app, &db, &pipeline).await?;
+    let running = handle.is_some();
+    state.lock().await.local_api = handle;
+    Ok(crate::local_api::LocalApiStatus::new(&settings, running))
+}
+
+// ── App lock commands ───────────────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct AppLockStatus {
+    pub enabled: bool,
+    pub locked: bool,
+    pub idle_timeout_minutes: u32,
+    pub failed_attempts: u32,
+    /// Seconds until another unlock attempt is allowed, during backoff.
+    pub retry_after_secs: Option<i64>,
+}
+
+async fn app_lock_status(
+    db: &aequi_storage::DbPool,
+    lock: &crate::app_lock::AppLock,
+) -> Result<AppLockStatus, CommandError> {
+    let settings = aequi_storage::app_lock::get_app_lock_settings(db).await?;
+    Ok(AppLockStatus {
+        enabled: settings.enabled(),
+        locked: lock.is_locked(),
+        idle_timeout_minutes: settings.idle_timeout_minutes,
+        failed_attempts: settings.failed_attempts,
+        retry_after_secs: settings.retry_after_secs(chrono::Utc::now().naive_utc()),
+    })
+}
+
+#[tauri::command]
+pub async fn get_app_lock_status(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    lock: State<'_, crate::app_lock::AppLock>,
+) -> Result<AppLockStatus, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    app_lock_status(&db, &lock).await
+}
+
+#[tauri::command]
+pub async fn lock_app(
+    app: tauri::AppHandle,
+    lock: State<'_, crate::app_lock::AppLock>,
+) -> Result<(), CommandError> {
+    if !lock.is_enabled() {
+        return Err(CommandError::config("Set a passphrase before locking"));
+    }
+    crate::app_lock::lock_and_notify(&app, &lock);
+    Ok(())
 }
 
+/// Unlock with the passphrase. Wrong ones are refused with `UNAUTHORIZED`,
+/// and after a few each further one shuts out unlocking for longer.
 #[tauri::command]
-pub async fn get_invoices(
+pub async fn unlock_app(
     state: State<'_, Arc<Mutex<AppState>>>,
-) -> Result<Vec<aequi_storage::InvoiceRecord>, CommandError> {
+    lock: State<'_, crate::app_lock::AppLock>,
+    passphrase: String,
+) -> Result<AppLockStatus, CommandError> {
     let db = {
         let s = state.lock().await;
         s.db.clone()
     };
-    aequi_storage::get_all_invoices(&db)
-        .await
-        .map_err(|e| CommandError::internal(e.to_string()))
+    aequi_storage::app_lock::verify_passphrase(&db, &passphrase, chrono::Utc::now().naive_utc())
+        .await?;
+    lock.unlock();
+    app_lock_status(&db, &lock).await
 }
 
+/// Set a passphrase or PIN, turning the lock on. Changing an existing one
+/// needs `current_passphrase`.
 #[tauri::command]
-pub async fn create_invoice(
+pub async fn set_app_lock_passphrase(
     state: State<'_, Arc<Mutex<AppState>>>,
-    input: InvoiceInput,
-) -> Result<aequi_storage::InvoiceRecord, CommandError> {
-    // Input validation
-    let invoice_number = input.invoice_number.trim().to_string();
-    if invoice_number.is_empty() {
-        return Err(CommandError::validation("Invoice number is required"));
-    }
-    let issue_date = NaiveDate::parse_from_str(&input.issue_date, "%Y-%m-%d")
-        .map_err(|_| CommandError::validation("Invalid issue date format (expected YYYY-MM-DD)"))?;
-    let due_date = NaiveDate::parse_from_str(&input.due_date, "%Y-%m-%d")
-        .map_err(|_| CommandError::validation("Invalid due date format (expected YYYY-MM-DD)"))?;
-    if due_date < issue_date {
-        return Err(CommandError::validation(
-            "Due date must be on or after issue date",
-        ));
-    }
-
+    lock: State<'_, crate::app_lock::AppLock>,
+    passphrase: String,
+    current_passphrase: Option<String>,
+) -> Result<AppLockStatus, CommandError> {
     let db = {
         let s = state.lock().await;
         s.db.clone()
     };
-
-    // Verify contact exists
-    aequi_storage::get_contact_by_id(&db, input.contact_id)
-        .await
-        .map_err(|e| CommandError {
-            code: "DATABASE".into(),
-            message: e.to_string(),
-        })?
-        .ok_or(CommandError::not_found("Contact not found"))?;
-
-    let id = aequi_storage::insert_invoice(
+    aequi_storage::app_lock::set_passphrase(
         &db,
-        &invoice_number,
-        input.contact_id,
-        "Draft",
-        None,
-        &input.issue_date,
-        &input.due_date,
-        None,
-        None,
-        input.notes.as_deref(),
-        input.terms.as_deref(),
+        &passphrase,
+        current_passphrase.as_deref(),
+        chrono::Utc::now().naive_utc(),
     )
-    .await
-    .map_err(|e| CommandError {
-        code: "DATABASE".into(),
-        message: e.to_string(),
-    })?;
-
-    aequi_storage::get_invoice_by_id(&db, id)
-        .await
-        .map_err(|e| CommandError {
-            code: "DATABASE".into(),
-            message: e.to_string(),
-        })?
-        .ok_or(CommandError::not_found("Invoice not found after insert"))
+    .await?;
+    lock.configure(&aequi_storage::app_lock::get_app_lock_settings(&db).await?);
+    app_lock_status(&db, &lock).await
 }
 
 #[tauri::command]
-pub async fn get_invoice_aging(
+pub async fn remove_app_lock(
     state: State<'_, Arc<Mutex<AppState>>>,
-) -> Result<Vec<aequi_storage::InvoiceRecord>, CommandError> {
+    lock: State<'_, crate::app_lock::AppLock>,
+    current_passphrase: String,
+) -> Result<AppLockStatus, CommandError> {
     let db = {
         let s = state.lock().await;
         s.db.clone()
     };
-    aequi_storage::get_invoice_aging(&db)
-        .await
-        .map_err(|e| CommandError::internal(e.to_string()))
-}
-
-#[derive(Debug, Deserialize)]
-pub struct PaymentInput {
-    pub invoice_id: i64,
-    pub amount_cents: i64,
-    pub date: String,
-    pub method: Option<String>,
+    aequi_storage::app_lock::remove_passphrase(
+        &db,
+        &current_passphrase,
+        chrono::Utc::now().naive_utc(),
+    )
+    .await?;
+    lock.configure(&aequi_storage::app_lock::get_app_lock_settings(&db).await?);
+    app_lock_status(&db, &lock).await
 }
 
+/// Minutes without activity before the app locks; 0 turns the idle lock
+/// off.
 #[tauri::command]
-pub async fn record_invoice_payment(
+pub async fn set_app_lock_timeout(
     state: State<'_, Arc<Mutex<AppState>>>,
-    input: PaymentInput,
-) -> Result<i64, CommandError> {
+    lock: State<'_, crate::app_lock::AppLock>,
+    minutes: u32,
+) -> Result<AppLockStatus, CommandError> {
+    if minutes > 24 * 60 {
+        return Err(CommandError::validation(
+            "Idle timeout can be at most a day",
+        ));
+    }
     let db = {
         let s = state.lock().await;
         s.db.clone()
     };
-    aequi_storage::insert_payment(
-        &db,
-        input.invoice_id,
-        input.amount_cents,
-        &input.date,
-        input.method.as_deref(),
-        None,
-    )
-    .await
-    .map_err(|e| CommandError::internal(e.to_string()))
+    aequi_storage::app_lock::set_idle_timeout(&db, minutes).await?;
+    lock.configure(&aequi_storage::app_lock::get_app_lock_settings(&db).await?);
+    app_lock_status(&db, &lock).await
 }
 
+// ── Sync commands ───────────────────────────────────────────────────────────
+
 #[derive(Debug, Serialize)]
-pub struct NecSummaryEntry {
-    pub contact_id: i64,
-    pub contact_name: String,
-    pub ytd_cents: i64,
-    pub over_threshold: bool,
+pub struct SyncStatus {
+    pub device_id: String,
+    pub peers: Vec<aequi_storage::sync::SyncPeer>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncExportSummary {
+    pub device_id: String,
+    pub changes: usize,
+    pub up_to_seq: i64,
 }
 
 #[tauri::command]
-pub async fn get_1099_summary(
+pub async fn get_sync_status(
     state: State<'_, Arc<Mutex<AppState>>>,
-    year: Option<u16>,
-) -> Result<Vec<NecSummaryEntry>, CommandError> {
+) -> Result<SyncStatus, CommandError> {
     let db = {
         let s = state.lock().await;
         s.db.clone()
     };
-    let yr = year.unwrap_or(chrono::Utc::now().date_naive().year() as u16);
-
-    // Single JOIN query instead of N+1
-    let rows = aequi_storage::get_contractor_ytd_payments(&db, yr)
-        .await
-        .map_err(|e| CommandError::internal(e.to_string()))?;
-
-    let entries = rows
-        .into_iter()
-        .map(|(id, name, ytd)| NecSummaryEntry {
-            contact_id: id,
-            contact_name: name,
-            ytd_cents: ytd,
-            over_threshold: aequi_core::check_1099_threshold(Money::from_cents(ytd)),
-        })
-        .collect();
-    Ok(entries)
-}
-
-// ── Email delivery ──────────────────────────────────────────────────────────
-
-#[derive(Debug, Deserialize)]
-pub struct SendInvoiceInput {
-    pub invoice_id: i64,
-    pub subject: Option<String>,
-}
-
-/// Reconstruct a domain Invoice from storage records.
-fn records_to_invoice(
-    rec: &aequi_storage::InvoiceRecord,
-    lines: &[aequi_storage::InvoiceLineRecord],
-    tax_lines: &[aequi_storage::InvoiceTaxLineRecord],
-) -> aequi_core::Invoice {
-    let discount = match (rec.discount_type.as_deref(), rec.discount_value) {
-        (Some("Percentage"), Some(bps)) => Some(Discount::Percentage(Decimal::new(bps, 2))),
-        (Some("Flat"), Some(cents)) => Some(Discount::Flat(Money::from_cents(cents))),
-        _ => None,
-    };
-
-    aequi_core::Invoice {
-        id: Some(InvoiceId(rec.id)),
-        invoice_number: rec.invoice_number.clone(),
-        contact_id: ContactId(rec.contact_id),
-        status: aequi_core::InvoiceStatus::Draft,
-        issue_date: NaiveDate::parse_from_str(&rec.issue_date, "%Y-%m-%d")
-            .unwrap_or_else(|_| chrono::Utc::now().date_naive()),
-        due_date: NaiveDate::parse_from_str(&rec.due_date, "%Y-%m-%d")
-            .unwrap_or_else(|_| chrono::Utc::now().date_naive()),
-        lines: lines
-            .iter()
-            .map(|l| InvoiceLine {
-                description: l.description.clone(),
-                quantity: Decimal::new(l.quantity_hundredths, 2),
-                unit_rate: Money::from_cents(l.unit_rate_cents),
-                taxable: l.taxable,
-            })
-            .collect(),
-        discount,
-        tax_lines: tax_lines
-            .iter()
-            .map(|t| TaxLine {
-                label: t.label.clone(),
-                rate: Decimal::new(t.rate_bps, 4),
-            })
-            .collect(),
-        notes: rec.notes.clone(),
-        terms: rec.terms.clone(),
-    }
+    Ok(SyncStatus {
+        device_id: aequi_storage::sync::get_device_id(&db).await?,
+        peers: aequi_storage::sync::get_sync_peers(&db).await?,
+    })
 }
 
-fn record_to_contact(rec: &aequi_storage::ContactRecord) -> aequi_core::Contact {
-    let contact_type = match rec.contact_type.as_str() {
-        "Vendor" => aequi_core::ContactType::Vendor,
-        "Contractor" => aequi_core::ContactType::Contractor,
-        _ => aequi_core::ContactType::Client,
+/// Write this device's changes to a file for another device to import.
+/// Without a peer, or for a device not synced with before, the file holds
+/// every change.
+#[tauri::command]
+pub async fn export_sync_changes(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    output_path: String,
+    peer_device_id: Option<String>,
+) -> Result<SyncExportSummary, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
     };
-
-    aequi_core::Contact {
-        id: Some(ContactId(rec.id)),
-        name: rec.name.clone(),
-        email: rec.email.clone(),
-        phone: rec.phone.clone(),
-        address: rec.address.clone(),
-        contact_type,
-        is_contractor: rec.is_contractor,
-        tax_id: rec.tax_id.clone(),
-        notes: rec.notes.clone(),
-    }
+    let set = aequi_storage::sync::export_changes(&db, peer_device_id.as_deref()).await?;
+    let json =
+        serde_json::to_vec_pretty(&set).map_err(|e| CommandError::internal(e.to_string()))?;
+    tokio::fs::write(&output_path, json)
+        .await
+        .map_err(|e| CommandError::internal(format!("Failed to write {output_path}: {e}")))?;
+    Ok(SyncExportSummary {
+        device_id: set.device_id,
+        changes: set.changes.len(),
+        up_to_seq: set.up_to_seq,
+    })
 }
 
+/// Merge a change set file exported on another device.
 #[tauri::command]
-pub async fn send_invoice(
+pub async fn import_sync_changes(
     state: State<'_, Arc<Mutex<AppState>>>,
-    input: SendInvoiceInput,
-) -> Result<aequi_email::DeliveryResult, CommandError> {
+    input_path: String,
+) -> Result<aequi_storage::sync::SyncReport, CommandError> {
     let db = {
         let s = state.lock().await;
         s.db.clone()
     };
-
-    // Load email config from settings
-    let config_json = aequi_storage::get_setting(&db, "email_config")
-        .await
-        .map_err(|e| CommandError::internal(e.to_string()))?
-        .ok_or(CommandError::internal(
-            "Email not configured — set email_config in Settings",
-        ))?;
-
-    let config: aequi_email::EmailConfig = serde_json::from_str(&config_json)
-        .map_err(|e| CommandError::internal(format!("Invalid email config: {e}")))?;
-
-    let rec = aequi_storage::get_invoice_by_id(&db, input.invoice_id)
-        .await
-        .map_err(|e| CommandError::internal(e.to_string()))?
-        .ok_or(CommandError::internal("Invoice not found"))?;
-
-    let lines = aequi_storage::get_invoice_lines(&db, input.invoice_id)
-        .await
-        .map_err(|e| CommandError::internal(e.to_string()))?;
-    let tax_lines = aequi_storage::get_invoice_tax_lines(&db, input.invoice_id)
-        .await
-        .map_err(|e| CommandError::internal(e.to_string()))?;
-    let invoice = records_to_invoice(&rec, &lines, &tax_lines);
-
-    let contact_rec = aequi_storage::get_contact_by_id(&db, rec.contact_id)
-        .await
-        .map_err(|e| CommandError::internal(e.to_string()))?
-        .ok_or(CommandError::internal("Contact not found"))?;
-    let contact = record_to_contact(&contact_rec);
-
-    let result = aequi_email::send_invoice(&config, &invoice, &contact, input.subject.as_deref())
+    let data = tokio::fs::read(&input_path)
         .await
-        .map_err(|e| CommandError::internal(e.to_string()))?;
-
-    // Update invoice status to Sent
-    let sent_data = serde_json::json!({ "sent_at": chrono::Utc::now().to_rfc3339() }).to_string();
-    let _ =
-        aequi_storage::update_invoice_status(&db, input.invoice_id, "Sent", Some(&sent_data)).await;
-
-    Ok(result)
+        .map_err(|e| CommandError::validation(format!("Failed to read {input_path}: {e}")))?;
+    let set: aequi_storage::sync::ChangeSet = serde_json::from_slice(&data)
+        .map_err(|e| CommandError::validation(format!("Not a sync change set: {e}")))?;
+    let report = aequi_storage::sync::apply_changes(&db, &set).await?;
+    tracing::info!(
+        "Merged {} change(s) from device {} ({} conflict(s))",
+        report.applied,
+        report.device_id,
+        report.conflicts.len()
+    );
+    Ok(report)
 }
 
-// ── Export commands ──────────────────────────────────────────────────────────
+// ── Audit log command ───────────────────────────────────────────────────────
 
 #[tauri::command]
-pub async fn export_beancount(
+pub async fn get_audit_log(
     state: State<'_, Arc<Mutex<AppState>>>,
-) -> Result<String, CommandError> {
+    limit: Option<i64>,
+) -> Result<Vec<aequi_storage::AuditLogRecord>, CommandError> {
     let db = {
         let s = state.lock().await;
         s.db.clone()
     };
-    let accounts = aequi_storage::get_all_accounts(&db)
+    aequi_storage::get_audit_log(&db, limit.unwrap_or(100))
         .await
-        .map_err(|e| CommandError::internal(e.to_string()))?;
+        .map_err(|e| CommandError::internal(e.to_string()))
+}
 
-    // For now export with empty transactions — full transaction fetch to be added
-    Ok(aequi_core::export::beancount::export_beancount(
-        &accounts,
-        &[],
-    ))
+#[tauri::command]
+pub async fn create_backup(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    output_path: String,
+) -> Result<aequi_storage::backup::BackupManifest, CommandError> {
+    let (db, db_path, attachments_dir) = {
+        let s = state.lock().await;
+        (s.db.clone(), s.db_path.clone(), s.attachments_dir.clone())
+    };
+    let output = std::path::PathBuf::from(&output_path);
+    aequi_storage::backup::create_backup(
+        &db,
+        &db_path,
+        &attachments_dir,
+        &output,
+        env!("CARGO_PKG_VERSION"),
+    )
+    .await
+    .map_err(|e| CommandError::internal(e.to_string()))
+}
+
+#[tauri::command]
+pub async fn restore_backup(
+    archive_path: String,
+    target_dir: String,
+) -> Result<String, CommandError> {
+    let result = aequi_storage::backup::restore_backup(
+        std::path::Path::new(&archive_path),
+        std::path::Path::new(&target_dir),
+    )
+    .map_err(|e| CommandError::internal(e.to_string()))?;
+    Ok(result.db_path.to_string_lossy().to_string())
+}
+
+// ── Diagnostics commands ────────────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct Diagnostics {
+    pub app_version: String,
+    pub os: String,
+    pub arch: String,
+    pub storage: aequi_storage::diagnostics::StorageDiagnostics,
+    pub ocr: OcrHealth,
+    pub local_api_running: bool,
 }
 
+/// Sizes, row counts, backup and migration state and OCR availability, for
+/// support requests and health checks.
 #[tauri::command]
-pub async fn export_qif(_state: State<'_, Arc<Mutex<AppState>>>) -> Result<String, CommandError> {
-    Ok(aequi_core::export::qif::export_qif(
-        &[],
-        aequi_core::AccountType::Asset,
-    ))
+pub async fn get_diagnostics(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Diagnostics, CommandError> {
+    let (db, db_path, attachments_dir, ocr, local_api_running) = {
+        let s = state.lock().await;
+        (
+            s.db.clone(),
+            s.db_path.clone(),
+            s.attachments_dir.clone(),
+            s.ocr_health.clone(),
+            s.local_api.is_some(),
+        )
+    };
+    let storage = aequi_storage::diagnostics::collect(&db, &db_path, &attachments_dir).await?;
+    Ok(Diagnostics {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        storage,
+        ocr,
+        local_api_running,
+    })
 }
 
-// ── Settings commands ───────────────────────────────────────────────────────
+/// Recent lines from the log files for the in-app viewer, oldest first.
+/// `level` is the least severe level shown (default `info`); giving a
+/// `correlation_id` shows only what one command or intake file logged.
+#[tauri::command]
+pub async fn get_recent_logs(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    level: Option<String>,
+    limit: Option<usize>,
+    correlation_id: Option<String>,
+) -> Result<Vec<crate::logging::LogEntry>, CommandError> {
+    let level = level.unwrap_or_else(|| "info".into());
+    if !["error", "warn", "info", "debug", "trace"].contains(&level.to_ascii_lowercase().as_str()) {
+        return Err(CommandError::validation(format!(
+            "Unknown log level: {level}"
+        )));
+    }
+    let limit = limit.unwrap_or(crate::logging::DEFAULT_RECENT_LIMIT);
+    let log_dir = {
+        let s = state.lock().await;
+        s.log_dir.clone()
+    };
+    tokio::task::spawn_blocking(move || {
+        crate::logging::read_recent(&log_dir, &level, correlation_id.as_deref(), limit)
+    })
+    .await
+    .map_err(|e| CommandError::internal(e.to_string()))?
+    .map_err(|e| CommandError::internal(format!("Failed to read logs: {e}")))
+}
 
+/// Recompute the per-month account balance rollup from the ledger lines.
+/// Returns how many account-months it holds.
 #[tauri::command]
-pub async fn get_setting(
+pub async fn rebuild_account_balances(
     state: State<'_, Arc<Mutex<AppState>>>,
-    key: String,
-) -> Result<Option<String>, CommandError> {
+) -> Result<u64, CommandError> {
     let db = {
         let s = state.lock().await;
         s.db.clone()
     };
-    aequi_storage::get_setting(&db, &key)
-        .await
-        .map_err(|e| CommandError::internal(e.to_string()))
+    Ok(aequi_storage::account_balances::rebuild_account_balances(&db).await?)
 }
 
+// ── Cloud backup commands ───────────────────────────────────────────────────
+
 #[tauri::command]
-pub async fn set_setting(
+pub async fn get_cloud_backup_settings(
     state: State<'_, Arc<Mutex<AppState>>>,
-    key: String,
-    value: String,
-) -> Result<(), CommandError> {
+) -> Result<aequi_storage::cloud_backup::CloudBackupSettings, CommandError> {
     let db = {
         let s = state.lock().await;
         s.db.clone()
     };
-    aequi_storage::set_setting(&db, &key, &value)
-        .await
-        .map_err(|e| CommandError::internal(e.to_string()))
+    Ok(aequi_storage::cloud_backup::get_cloud_backup_settings(&db).await?)
 }
 
-// ── Audit log command ───────────────────────────────────────────────────────
-
 #[tauri::command]
-pub async fn get_audit_log(
+pub async fn save_cloud_backup_settings(
     state: State<'_, Arc<Mutex<AppState>>>,
-    limit: Option<i64>,
-) -> Result<Vec<aequi_storage::AuditLogRecord>, CommandError> {
+    settings: aequi_storage::cloud_backup::CloudBackupSettings,
+) -> Result<(), CommandError> {
+    if let Some(target) = &settings.target {
+        target.validate().map_err(CommandError::validation)?;
+    }
     let db = {
         let s = state.lock().await;
         s.db.clone()
     };
-    aequi_storage::get_audit_log(&db, limit.unwrap_or(100))
-        .await
-        .map_err(|e| CommandError::internal(e.to_string()))
+    aequi_storage::cloud_backup::set_cloud_backup_settings(&db, &settings).await?;
+    Ok(())
 }
 
+/// Back up, encrypt with `passphrase` and upload to the configured target,
+/// then prune old snapshots under the retention policy.
 #[tauri::command]
-pub async fn create_backup(
+pub async fn run_cloud_backup(
     state: State<'_, Arc<Mutex<AppState>>>,
-    output_path: String,
-) -> Result<aequi_storage::backup::BackupManifest, CommandError> {
+    passphrase: String,
+) -> Result<aequi_storage::cloud_backup::CloudBackupOutcome, CommandError> {
     let (db, db_path, attachments_dir) = {
         let s = state.lock().await;
         (s.db.clone(), s.db_path.clone(), s.attachments_dir.clone())
     };
-    let output = std::path::PathBuf::from(&output_path);
-    aequi_storage::backup::create_backup(
+    let settings = aequi_storage::cloud_backup::get_cloud_backup_settings(&db).await?;
+    let outcome = aequi_storage::cloud_backup::upload_backup(
         &db,
         &db_path,
         &attachments_dir,
-        &output,
+        &settings,
+        &passphrase,
         env!("CARGO_PKG_VERSION"),
     )
-    .await
-    .map_err(|e| CommandError::internal(e.to_string()))
+    .await?;
+    tracing::info!(
+        "Uploaded backup {} ({} old snapshot(s) pruned)",
+        outcome.snapshot.name,
+        outcome.pruned.len()
+    );
+    Ok(outcome)
 }
 
 #[tauri::command]
-pub async fn restore_backup(
-    archive_path: String,
+pub async fn list_cloud_backups(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Vec<aequi_storage::cloud_backup::RemoteSnapshot>, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let settings = aequi_storage::cloud_backup::get_cloud_backup_settings(&db).await?;
+    let target = settings
+        .target
+        .ok_or(aequi_storage::cloud_backup::CloudBackupError::NotConfigured)?;
+    Ok(aequi_storage::cloud_backup::list_snapshots(&target).await?)
+}
+
+/// Download and decrypt a snapshot into `target_dir`. Returns the restored
+/// database path, as `restore_backup` does.
+#[tauri::command]
+pub async fn restore_cloud_backup(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    name: String,
+    passphrase: String,
     target_dir: String,
 ) -> Result<String, CommandError> {
-    let result = aequi_storage::backup::restore_backup(
-        std::path::Path::new(&archive_path),
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let settings = aequi_storage::cloud_backup::get_cloud_backup_settings(&db).await?;
+    let target = settings
+        .target
+        .ok_or(aequi_storage::cloud_backup::CloudBackupError::NotConfigured)?;
+    let result = aequi_storage::cloud_backup::restore_snapshot(
+        &target,
+        &name,
+        &passphrase,
         std::path::Path::new(&target_dir),
     )
-    .map_err(|e| CommandError::internal(e.to_string()))?;
+    .await?;
     Ok(result.db_path.to_string_lossy().to_string())
 }
 
+/// What the checks at launch made of the ledger, and the snapshot a restore
+/// would use.
+#[tauri::command]
+pub async fn get_database_health(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<crate::db_health::DatabaseHealth, CommandError> {
+    let s = state.lock().await;
+    Ok(crate::db_health::DatabaseHealth {
+        latest_snapshot: crate::db_health::latest_snapshot(&s.snapshots_dir),
+        ..s.db_health.clone()
+    })
+}
+
+/// Put the latest daily snapshot in place of a ledger that failed its checks
+/// at launch, then restart the app on it. The damaged file is kept beside it.
+#[tauri::command]
+pub async fn restore_database_snapshot(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<(), CommandError> {
+    let (recovery_mode, db_path, snapshots_dir) = {
+        let s = state.lock().await;
+        (
+            s.db_health.recovery_mode,
+            s.db_path.clone(),
+            s.snapshots_dir.clone(),
+        )
+    };
+    if !recovery_mode {
+        return Err(CommandError::validation(
+            "The ledger passed its checks; restore a backup instead",
+        ));
+    }
+    let snapshot = crate::db_health::latest_snapshot(&snapshots_dir)
+        .ok_or_else(|| CommandError::not_found("No snapshot of the ledger to restore"))?;
+    let damaged = aequi_storage::health::restore_snapshot(&snapshot.path, &db_path)
+        .map_err(|e| CommandError::internal(format!("Failed to restore snapshot: {e}")))?;
+    tracing::warn!(
+        "Restored the ledger from {}; the damaged file is at {}",
+        snapshot.path.display(),
+        damaged.display()
+    );
+    app.restart()
+}
+
 #[tauri::command]
 pub async fn get_schema_versions(
     state: State<'_, Arc<Mutex<AppState>>>,
@@ -1264,6 +6695,231 @@ pub async fn check_overdue_invoices(
     Ok(count)
 }
 
+#[tauri::command]
+pub async fn get_notifications(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    include_dismissed: Option<bool>,
+) -> Result<Vec<aequi_storage::reminders::NotificationRecord>, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    Ok(
+        aequi_storage::reminders::get_notifications(&db, include_dismissed.unwrap_or(false))
+            .await?,
+    )
+}
+
+/// Re-evaluate the reminder rules now. Returns the notifications raised.
+#[tauri::command]
+pub async fn refresh_notifications(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Vec<aequi_storage::reminders::NotificationRecord>, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    crate::reminders::refresh(&db).await
+}
+
+#[tauri::command]
+pub async fn mark_notification_read(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+) -> Result<(), CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    if !aequi_storage::reminders::mark_notification_read(&db, id).await? {
+        return Err(CommandError::not_found(format!(
+            "Notification {id} not found"
+        )));
+    }
+    Ok(())
+}
+
+/// Hide a notification until its condition clears and comes back.
+#[tauri::command]
+pub async fn dismiss_notification(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+) -> Result<(), CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    if !aequi_storage::reminders::dismiss_notification(&db, id).await? {
+        return Err(CommandError::not_found(format!(
+            "Notification {id} not found"
+        )));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_reminder_settings(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<aequi_storage::reminders::ReminderSettings, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    Ok(aequi_storage::reminders::get_reminder_settings(&db).await?)
+}
+
+#[tauri::command]
+pub async fn set_reminder_settings(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    settings: aequi_storage::reminders::ReminderSettings,
+) -> Result<(), CommandError> {
+    if settings.tax_deadline_lead_days < 0 {
+        return Err(CommandError::validation(
+            "Tax deadline lead time cannot be negative",
+        ));
+    }
+    if settings.reconciliation_interval_days < 1 {
+        return Err(CommandError::validation(
+            "Reconciliation interval must be at least one day",
+        ));
+    }
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    for code in settings
+        .reconciliation_accounts
+        .iter()
+        .chain(std::iter::once(&settings.low_balance_account))
+    {
+        if aequi_storage::get_account_by_code(&db, code)
+            .await?
+            .is_none()
+        {
+            return Err(CommandError::validation(format!("Unknown account {code}")));
+        }
+    }
+    if settings.budget_alert_percent == Some(0) {
+        return Err(CommandError::validation(
+            "Budget alert percentage must be at least 1",
+        ));
+    }
+    if let Some(path) = &settings.calendar_path {
+        let parent = Path::new(path)
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty());
+        if path.trim().is_empty() || parent.is_some_and(|p| !p.is_dir()) {
+            return Err(CommandError::validation(format!(
+                "Cannot write the calendar to {path}"
+            )));
+        }
+    }
+    aequi_storage::reminders::set_reminder_settings(&db, &settings).await?;
+    Ok(())
+}
+
+// ── Budget commands ─────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub async fn get_budgets(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Vec<aequi_storage::budgets::Budget>, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    Ok(aequi_storage::budgets::get_budgets(&db).await?)
+}
+
+/// Set an expense account's monthly budget; `None` removes it.
+#[tauri::command]
+pub async fn set_budget(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    account_code: String,
+    monthly_cents: Option<i64>,
+) -> Result<(), CommandError> {
+    if monthly_cents.is_some_and(|c| c <= 0) {
+        return Err(CommandError::validation("Budget must be positive"));
+    }
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let account = aequi_storage::get_account_by_code(&db, &account_code)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("Account not found: {account_code}")))?;
+    if account.account_type != aequi_core::AccountType::Expense {
+        return Err(CommandError::validation(format!(
+            "Budgets are set on expense accounts, not {} {}",
+            account.code, account.name
+        )));
+    }
+    let account_id = account
+        .id
+        .ok_or_else(|| CommandError::internal("Account missing ID"))?
+        .0;
+    aequi_storage::budgets::set_budget(&db, account_id, monthly_cents).await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_budget_variance(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    as_of: Option<String>,
+) -> Result<Vec<aequi_storage::budgets::BudgetVariance>, CommandError> {
+    let as_of = as_of_date(as_of.as_deref())?;
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    Ok(aequi_storage::budgets::month_to_date_variance(&db, as_of).await?)
+}
+
+// ── Hook commands ───────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub async fn get_hook_settings(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> Result<aequi_storage::hooks::HookSettings, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    Ok(aequi_storage::hooks::get_hook_settings(&db).await?)
+}
+
+#[tauri::command]
+pub async fn set_hook_settings(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    settings: aequi_storage::hooks::HookSettings,
+) -> Result<(), CommandError> {
+    for hook in &settings.hooks {
+        hook.validate().map_err(CommandError::validation)?;
+    }
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    aequi_storage::hooks::set_hook_settings(&db, &settings).await?;
+    Ok(())
+}
+
+/// Send a hook a sample `event` (its first event if not given) and report
+/// whether it was delivered.
+#[tauri::command]
+pub async fn test_hook(
+    hook: aequi_storage::hooks::Hook,
+    event: Option<aequi_storage::hooks::HookEventKind>,
+) -> Result<(), CommandError> {
+    hook.validate().map_err(CommandError::validation)?;
+    let kind = event.unwrap_or(hook.events[0]);
+    let payload =
+        aequi_storage::hooks::HookPayload::now(aequi_storage::hooks::HookEvent::sample(kind));
+    crate::hooks::deliver(&hook, &payload)
+        .await
+        .map_err(|e| CommandError::validation(format!("Hook {} failed: {e}", hook.name)))
+}
+
 // ── Dashboard commands ──────────────────────────────────────────────────────
 
 #[derive(Debug, Serialize)]
@@ -1279,6 +6935,8 @@ pub struct DashboardSummary {
     pub recent_transactions: Vec<TransactionOutput>,
     pub quarterly_tax_due_cents: Option<i64>,
     pub next_tax_due_date: Option<String>,
+    /// This month's spending against each budget, most over budget first.
+    pub budget_variances: Vec<aequi_storage::budgets::BudgetVariance>,
 }
 
 #[tauri::command]
@@ -1292,28 +6950,13 @@ pub async fn get_dashboard_summary(
     let db = &db;
 
     let now = chrono::Utc::now().date_naive();
-    let year_start = NaiveDate::from_ymd_opt(now.year(), 1, 1)
-        .unwrap()
-        .to_string();
-    let today = now.to_string();
+    let year_start = NaiveDate::from_ymd_opt(now.year(), 1, 1).unwrap();
 
     // YTD P&L
-    let pl_row = sqlx::query_as::<_, (i64, i64)>(
-        r#"SELECT
-            COALESCE(SUM(CASE WHEN a.account_type = 'Income' THEN tl.credit_cents - tl.debit_cents ELSE 0 END), 0),
-            COALESCE(SUM(CASE WHEN a.account_type = 'Expense' THEN tl.debit_cents - tl.credit_cents ELSE 0 END), 0)
-        FROM transaction_lines tl
-        JOIN accounts a ON a.id = tl.account_id
-        JOIN transactions t ON t.id = tl.transaction_id
-        WHERE t.date >= ? AND t.date <= ?"#,
-    )
-    .bind(&year_start)
-    .bind(&today)
-    .fetch_one(db)
-    .await?;
-
-    let ytd_income = pl_row.0;
-    let ytd_expenses = pl_row.1;
+    let ytd = aequi_storage::reports::profit_loss(db, aequi_core::DateRange::new(year_start, now))
+        .await?;
+    let ytd_income = ytd.total_income_cents;
+    let ytd_expenses = ytd.total_expenses_cents;
 
     // Outstanding + overdue invoices
     let aging = aequi_storage::get_invoice_aging(db).await?;
@@ -1332,31 +6975,17 @@ pub async fn get_dashboard_summary(
     let pending_receipts = receipts.len() as u32;
 
     // Counts
-    let accounts: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM accounts WHERE is_archived = 0")
-        .fetch_one(db)
-        .await?;
-    let tx_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM transactions")
-        .fetch_one(db)
-        .await?;
+    let total_accounts = aequi_storage::count_active_accounts(db).await?;
+    let total_transactions = aequi_storage::count_transactions(db).await?;
 
     // Recent transactions (last 5)
-    let recent = sqlx::query_as::<_, (i64, String, String, Option<String>, i64, String)>(
-        "SELECT id, date, description, memo, balanced_total_cents, created_at FROM transactions ORDER BY date DESC, id DESC LIMIT 5"
-    )
-    .fetch_all(db)
-    .await?;
-
-    let recent_transactions: Vec<TransactionOutput> = recent
-        .into_iter()
-        .map(|r| TransactionOutput {
-            id: r.0,
-            date: r.1,
-            description: r.2,
-            memo: r.3,
-            balanced_total: Money::from_cents(r.4).to_string(),
-            created_at: r.5,
-        })
-        .collect();
+    let money = money_format(db).await?;
+    let recent_transactions: Vec<TransactionOutput> =
+        aequi_storage::get_transactions(db, None, Some(5))
+            .await?
+            .into_iter()
+            .map(|r| TransactionOutput::new(r, &money))
+            .collect();
 
     // Current quarter tax estimate
     let current_quarter = ((now.month0() / 3) + 1) as u8;
@@ -1366,6 +6995,8 @@ pub async fn get_dashboard_summary(
         .iter()
         .find(|p| p.quarter == current_quarter as i64);
 
+    let budget_variances = aequi_storage::budgets::month_to_date_variance(db, now).await?;
+
     Ok(DashboardSummary {
         ytd_income_cents: ytd_income,
         ytd_expenses_cents: ytd_expenses,
@@ -1373,14 +7004,35 @@ pub async fn get_dashboard_summary(
         outstanding_invoices: outstanding,
         overdue_invoices: overdue,
         pending_receipts,
-        total_accounts: accounts.0 as u32,
-        total_transactions: tx_count.0 as u32,
+        total_accounts: total_accounts as u32,
+        total_transactions: total_transactions as u32,
         recent_transactions,
         quarterly_tax_due_cents: current_period.map(|p| p.estimated_tax_cents),
         next_tax_due_date: current_period.map(|p| p.due_date.clone()),
+        budget_variances,
     })
 }
 
+/// The combined to-do list: overdue invoices, open reconciliation
+/// differences, unsettled bank rows and receipts to review, most pressing
+/// first. `limit` caps the items; the counts always cover everything.
+#[tauri::command]
+pub async fn get_attention_feed(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    limit: Option<usize>,
+) -> Result<aequi_storage::attention::AttentionFeed, CommandError> {
+    let db = {
+        let s = state.lock().await;
+        s.db.clone()
+    };
+    let today = chrono::Utc::now().date_naive();
+    let mut feed = aequi_storage::attention::needs_attention(&db, today).await?;
+    if let Some(limit) = limit {
+        feed.items.truncate(limit);
+    }
+    Ok(feed)
+}
+
 // ── Update contact command ──────────────────────────────────────────────────
 
 #[derive(Debug, Deserialize)]
@@ -1394,6 +7046,14 @@ pub struct UpdateContactInput {
     pub is_contractor: bool,
     pub tax_id: Option<String>,
     pub notes: Option<String>,
+    #[serde(default)]
+    pub default_account_code: Option<String>,
+    #[serde(default)]
+    pub payment_terms_days: Option<i64>,
+    /// Other names the contact appears under on statements and receipts.
+    /// Left unchanged when absent.
+    #[serde(default)]
+    pub aliases: Option<Vec<String>>,
 }
 
 #[tauri::command]
@@ -1416,6 +7076,17 @@ pub async fn update_contact(
         let s = state.lock().await;
         s.db.clone()
     };
+    aequi_storage::get_contact_by_id(&db, input.id)
+        .await?
+        .ok_or_else(|| CommandError::not_found("Contact not found"))?;
+    save_contact_details(
+        &db,
+        input.id,
+        input.default_account_code.as_deref(),
+        input.payment_terms_days,
+        input.aliases.as_deref(),
+    )
+    .await?;
     aequi_storage::update_contact(
         &db,
         input.id,
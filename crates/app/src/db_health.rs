@@ -0,0 +1,105 @@
+//! Opening the ledger at launch.
+//!
+//! The file is checked before it is opened. When it passes, it's migrated
+//! and a daily snapshot is taken. When it doesn't — it's damaged, comes from
+//! a newer version, or won't open — the app runs on an empty read-only
+//! stand-in instead of failing to start, so the window can say what went
+//! wrong and offer to put the latest snapshot back.
+
+use std::path::Path;
+
+use aequi_storage::health::{DbHealth, Snapshot};
+use aequi_storage::DbPool;
+use serde::Serialize;
+use sqlx::sqlite::SqlitePoolOptions;
+
+/// Directory under the app data directory holding the daily snapshots.
+pub const SNAPSHOT_DIR: &str = "snapshots";
+/// Daily snapshots kept.
+const KEEP_SNAPSHOTS: usize = 3;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DatabaseHealth {
+    #[serde(flatten)]
+    pub health: DbHealth,
+    /// Why the ledger couldn't be opened when the file checked out, or the
+    /// checks themselves failed.
+    pub error: Option<String>,
+    /// Whether the app is running on the stand-in rather than the ledger.
+    pub recovery_mode: bool,
+    /// The snapshot a restore would put back.
+    pub latest_snapshot: Option<Snapshot>,
+}
+
+/// Check and open the ledger at `db_path`, falling back to the stand-in when
+/// it can't be used. Fails only if the stand-in can't be made either.
+pub async fn open_ledger(
+    db_path: &Path,
+    snapshots_dir: &Path,
+) -> Result<(DbPool, DatabaseHealth), sqlx::Error> {
+    let (health, error) = match aequi_storage::health::check_database(db_path).await {
+        Ok(DbHealth::Healthy) => match open_checked(db_path, snapshots_dir).await {
+            Ok(db) => {
+                let health = DatabaseHealth {
+                    health: DbHealth::Healthy,
+                    error: None,
+                    recovery_mode: false,
+                    latest_snapshot: latest_snapshot(snapshots_dir),
+                };
+                return Ok((db, health));
+            }
+            Err(e) => (DbHealth::Healthy, Some(e.to_string())),
+        },
+        Ok(health) => (health, None),
+        Err(e) => (DbHealth::Healthy, Some(e.to_string())),
+    };
+
+    tracing::error!(
+        ?health,
+        error = error.as_deref().unwrap_or_default(),
+        "Ledger failed its startup checks; running in recovery mode"
+    );
+    let db = stand_in().await?;
+    let health = DatabaseHealth {
+        health,
+        error,
+        recovery_mode: true,
+        latest_snapshot: latest_snapshot(snapshots_dir),
+    };
+    Ok((db, health))
+}
+
+pub fn latest_snapshot(snapshots_dir: &Path) -> Option<Snapshot> {
+    aequi_storage::health::list_snapshots(snapshots_dir)
+        .into_iter()
+        .next()
+}
+
+async fn open_checked(db_path: &Path, snapshots_dir: &Path) -> Result<DbPool, sqlx::Error> {
+    let db = aequi_storage::create_db(db_path).await?;
+    aequi_storage::seed_default_accounts(&db).await?;
+    // A missed snapshot shouldn't keep the app from starting.
+    if let Err(e) =
+        aequi_storage::health::take_daily_snapshot(&db, snapshots_dir, KEEP_SNAPSHOTS).await
+    {
+        tracing::warn!("Failed to snapshot the ledger: {e}");
+    }
+    Ok(db)
+}
+
+/// An in-memory ledger with the default accounts that refuses writes, so
+/// nothing entered in recovery mode looks saved when it isn't.
+async fn stand_in() -> Result<DbPool, sqlx::Error> {
+    // One connection that never closes: each new in-memory connection
+    // would be a different, empty database.
+    let db = SqlitePoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect("sqlite::memory:")
+        .await?;
+    aequi_storage::migrate::run_migrations(&db).await?;
+    aequi_storage::seed_default_accounts(&db).await?;
+    sqlx::query("PRAGMA query_only = ON").execute(&db).await?;
+    Ok(db)
+}
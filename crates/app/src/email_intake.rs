@@ -0,0 +1,94 @@
+//! Email intake for receipts.
+//!
+//! `.eml` files dropped into the receipt intake folder, and unseen messages
+//! in the IMAP folder configured under `receipt_inbox_config`, are split
+//! into their receipt documents (attachments, or the HTML body) and run
+//! through the shared OCR pipeline like any other receipt.
+
+use std::path::Path;
+use std::time::Duration;
+
+use aequi_ocr::{ProcessOutcome, ReceiptPipeline};
+use aequi_storage::DbPool;
+
+/// How often the IMAP folder is checked.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Run every receipt document in a raw message through the pipeline.
+/// Returns the ids of the receipts stored; documents that fail are logged
+/// and skipped.
+pub async fn process_email(db: &DbPool, pipeline: &ReceiptPipeline, raw: &[u8]) -> Vec<i64> {
+    let mut stored = Vec::new();
+    for doc in aequi_email::receipt_documents(raw) {
+        let results = match pipeline.process_bytes(&doc.data, &doc.ext).await {
+            Ok(ProcessOutcome::Duplicate { .. }) => {
+                tracing::info!("Email receipt {} already stored", doc.name);
+                continue;
+            }
+            Ok(outcome) => outcome.into_results(),
+            Err(e) => {
+                tracing::warn!("Email receipt {} failed: {e}", doc.name);
+                continue;
+            }
+        };
+        // A photographed pair of receipts is stored as both.
+        for result in results {
+            if let Some(err) = &result.llm_error {
+                tracing::warn!("LLM receipt extraction failed: {err}");
+            }
+            match crate::commands::store_ocr_result(db, &result, &doc.ext).await {
+                Ok(aequi_storage::ReceiptInsert::PossibleDuplicate { id, duplicate_of }) => {
+                    tracing::info!(
+                        "Email receipt {} looks like a copy of receipt {duplicate_of}",
+                        doc.name
+                    );
+                    stored.push(id);
+                }
+                Ok(inserted) => stored.push(inserted.id()),
+                Err(e) => tracing::warn!("Failed to store email receipt {}: {e}", doc.name),
+            }
+        }
+    }
+    stored
+}
+
+/// Import one `.eml` file from the intake folder.
+pub async fn process_eml_file(
+    db: &DbPool,
+    pipeline: &ReceiptPipeline,
+    path: &Path,
+) -> Result<Vec<i64>, String> {
+    let raw = tokio::fs::read(path)
+        .await
+        .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+    Ok(process_email(db, pipeline, &raw).await)
+}
+
+/// Fetch and import unseen messages from the configured IMAP folder.
+/// Does nothing if no inbox is configured.
+pub async fn poll_inbox(db: &DbPool, pipeline: &ReceiptPipeline) -> Result<usize, String> {
+    let Some(config_json) = aequi_storage::get_setting(db, "receipt_inbox_config")
+        .await
+        .map_err(|e| e.to_string())?
+        .filter(|c| !c.trim().is_empty())
+    else {
+        return Ok(0);
+    };
+    let config: aequi_email::ImapConfig = serde_json::from_str(&config_json)
+        .map_err(|e| format!("Invalid receipt inbox config: {e}"))?;
+
+    let messages = aequi_email::fetch_unseen(&config)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut stored = 0;
+    for raw in &messages {
+        stored += process_email(db, pipeline, raw).await.len();
+    }
+    Ok(stored)
+}
+
+pub fn is_eml(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("eml"))
+}
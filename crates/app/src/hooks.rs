@@ -0,0 +1,134 @@
+//! Delivery of hook events to the scripts and HTTP endpoints the user has
+//! configured (see [`aequi_storage::hooks`]).
+//!
+//! Events are delivered in the background once whatever raised them has been
+//! committed, so a slow or failing hook never holds up the books. Failures are
+//! logged and not retried.
+
+use std::future::Future;
+use std::process::Stdio;
+use std::time::Duration;
+
+use aequi_storage::hooks::{
+    self, Hook, HookEvent, HookEventKind, HookPayload, HookTarget, ImportCompleted,
+    TransactionSource,
+};
+use aequi_storage::DbPool;
+use tokio::io::AsyncWriteExt;
+
+/// How long an HTTP hook may take to respond.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a script hook may run before it is killed.
+const SCRIPT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Deliver a payload to one hook.
+pub async fn deliver(hook: &Hook, payload: &HookPayload) -> Result<(), String> {
+    let body = serde_json::to_vec(payload).map_err(|e| e.to_string())?;
+    match &hook.target {
+        HookTarget::Http { url, headers } => {
+            let client = reqwest::Client::builder()
+                .timeout(HTTP_TIMEOUT)
+                .build()
+                .map_err(|e| e.to_string())?;
+            let mut request = client
+                .post(url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header("X-Aequi-Event", payload.event.kind().as_str())
+                .body(body);
+            for (name, value) in headers {
+                request = request.header(name.as_str(), value.as_str());
+            }
+            request
+                .send()
+                .await
+                .and_then(|r| r.error_for_status())
+                .map_err(|e| e.to_string())?;
+            Ok(())
+        }
+        HookTarget::Script { path, args } => {
+            let mut child = tokio::process::Command::new(path)
+                .args(args)
+                .env("AEQUI_EVENT", payload.event.kind().as_str())
+                .stdin(Stdio::piped())
+                .stdout(Stdio::null())
+                .stderr(Stdio::piped())
+                .kill_on_drop(true)
+                .spawn()
+                .map_err(|e| format!("cannot run {path}: {e}"))?;
+            if let Some(mut stdin) = child.stdin.take() {
+                // A script that ignores its input may exit before reading it.
+                let _ = stdin.write_all(&body).await;
+            }
+            let output = tokio::time::timeout(SCRIPT_TIMEOUT, child.wait_with_output())
+                .await
+                .map_err(|_| format!("{path} did not finish within {SCRIPT_TIMEOUT:?}"))?
+                .map_err(|e| e.to_string())?;
+            if output.status.success() {
+                Ok(())
+            } else {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                Err(format!(
+                    "{path} exited with {}: {}",
+                    output.status,
+                    stderr.trim()
+                ))
+            }
+        }
+    }
+}
+
+/// Build the event and deliver it to each hook subscribed to `kind`, in the
+/// background. The event is only built if some hook wants it.
+fn dispatch<F>(db: &DbPool, kind: HookEventKind, event: F)
+where
+    F: Future<Output = Result<Option<HookEvent>, sqlx::Error>> + Send + 'static,
+{
+    let db = db.clone();
+    tauri::async_runtime::spawn(async move {
+        let settings = match hooks::get_hook_settings(&db).await {
+            Ok(settings) => settings,
+            Err(e) => {
+                tracing::warn!("Failed to load hook settings: {e}");
+                return;
+            }
+        };
+        let subscribed = settings.subscribed(kind);
+        if subscribed.is_empty() {
+            return;
+        }
+        let payload = match event.await {
+            Ok(Some(event)) => HookPayload::now(event),
+            Ok(None) => return,
+            Err(e) => {
+                tracing::warn!("Failed to build {} hook event: {e}", kind.as_str());
+                return;
+            }
+        };
+        for hook in subscribed {
+            if let Err(e) = deliver(hook, &payload).await {
+                tracing::warn!("Hook {} failed on {}: {e}", hook.name, kind.as_str());
+            }
+        }
+    });
+}
+
+pub fn receipt_approved(db: &DbPool, receipt_id: i64, automatic: bool) {
+    let pool = db.clone();
+    dispatch(db, HookEventKind::ReceiptApproved, async move {
+        hooks::receipt_approved(&pool, receipt_id, automatic).await
+    });
+}
+
+pub fn transaction_posted(db: &DbPool, transaction_id: i64, source: TransactionSource) {
+    let pool = db.clone();
+    dispatch(db, HookEventKind::TransactionPosted, async move {
+        hooks::transaction_posted(&pool, transaction_id, source).await
+    });
+}
+
+pub fn import_completed(db: &DbPool, completed: ImportCompleted) {
+    dispatch(db, HookEventKind::ImportCompleted, async move {
+        Ok(Some(HookEvent::ImportCompleted(completed)))
+    });
+}
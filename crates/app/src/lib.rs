@@ -1,18 +1,51 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use tauri::Manager;
-use tokio::sync::{mpsc, Mutex};
+use tokio::sync::{mpsc, Mutex, Notify};
+use tracing::Instrument;
 
+pub mod app_lock;
+pub mod auto_approve;
+pub mod bank_intake;
 pub mod commands;
+pub mod db_health;
+pub mod email_intake;
+pub mod hooks;
+pub mod local_api;
+pub mod logging;
+pub mod receipt_intake;
+pub mod reminders;
 
 pub struct AppState {
     pub db: aequi_storage::DbPool,
     pub db_path: PathBuf,
+    /// What the launch checks found; in recovery mode `db` is a stand-in.
+    pub db_health: db_health::DatabaseHealth,
+    pub snapshots_dir: PathBuf,
     pub attachments_dir: PathBuf,
+    /// Where the rotating log files are written.
+    pub log_dir: PathBuf,
+    /// Where downloaded Tesseract language data is kept.
+    pub tessdata_dir: PathBuf,
+    pub pipeline: Arc<aequi_ocr::ReceiptPipeline>,
+    /// Outcome of the last OCR backend selection.
+    pub ocr_health: aequi_ocr::OcrHealth,
     pub receipt_tx: mpsc::Sender<PathBuf>,
+    /// Tells the receipt job dispatcher there is something new in the queue.
+    pub intake_wake: Arc<Notify>,
+    /// Where files shared to the app are copied before they're queued.
+    pub shared_dir: PathBuf,
+    /// The local HTTP API server, while it is running.
+    pub local_api: Option<tauri::async_runtime::JoinHandle<()>>,
+    /// Cancel flags of streamed imports in progress, by job id.
+    pub import_jobs: HashMap<String, Arc<AtomicBool>>,
     /// Kept alive for the app's lifetime; dropping it stops the watcher.
     #[cfg(desktop)]
     pub _intake_watcher: Option<Box<dyn std::any::Any + Send>>,
+    #[cfg(desktop)]
+    pub _bank_intake_watcher: Option<Box<dyn std::any::Any + Send>>,
 }
 
 /// Spawn the MCP server as a sidecar process (desktop only).
@@ -65,6 +98,51 @@ fn spawn_mcp_sidecar(app: &tauri::App, db_path: &std::path::Path) {
     }
 }
 
+/// Queue the photos and PDFs among `paths` as receipts shared to the app;
+/// anything else is skipped.
+fn ingest_shared_files(app: &tauri::AppHandle, paths: Vec<PathBuf>) {
+    let paths: Vec<PathBuf> = paths
+        .into_iter()
+        .filter(|p| p.is_file() && receipt_intake::is_shareable(p))
+        .collect();
+    if paths.is_empty() {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let Some(state) = app.try_state::<Arc<Mutex<AppState>>>() else {
+            return;
+        };
+        let (db, wake, shared_dir) = {
+            let s = state.lock().await;
+            (s.db.clone(), s.intake_wake.clone(), s.shared_dir.clone())
+        };
+        for path in paths {
+            if let Err(e) =
+                receipt_intake::ingest_shared(&app, &db, &wake, &shared_dir, &path).await
+            {
+                tracing::warn!("Failed to queue shared receipt: {e}");
+            }
+        }
+    });
+}
+
+/// Handle app-level events. On macOS the files the app is opened with from
+/// Finder, and on iOS documents opened in it, arrive here rather than on
+/// the command line.
+pub fn handle_run_event(app: &tauri::AppHandle, event: tauri::RunEvent) {
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    if let tauri::RunEvent::Opened { urls } = event {
+        let paths = urls
+            .into_iter()
+            .filter_map(|url| url.to_file_path().ok())
+            .collect();
+        ingest_shared_files(app, paths);
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "ios")))]
+    let _ = (app, event);
+}
+
 /// Build the shared Tauri app (used by both desktop main.rs and mobile lib entry).
 pub fn build_app() -> tauri::Builder<tauri::Wry> {
     tauri::Builder::default()
@@ -77,142 +155,491 @@ pub fn build_app() -> tauri::Builder<tauri::Wry> {
             let data_dir = app.path().app_data_dir()?;
             std::fs::create_dir_all(&data_dir)
                 .map_err(|e| format!("Failed to create data directory: {e}"))?;
+            let log_dir = data_dir.join(logging::LOG_DIR);
+            logging::init(&log_dir);
 
             let db_path = data_dir.join("ledger.db");
-            let attachments_dir = data_dir.join("attachments");
-            let intake_dir = data_dir.join("intake");
-            std::fs::create_dir_all(&attachments_dir)
+            let default_attachments_dir = data_dir.join("attachments");
+            let default_intake_dir = data_dir.join("intake");
+            let bank_intake_dir = data_dir.join("bank-intake");
+            let shared_dir = data_dir.join(receipt_intake::SHARED_DIR);
+            let tessdata_dir = data_dir.join("tessdata");
+            std::fs::create_dir_all(&default_attachments_dir)
                 .map_err(|e| format!("Failed to create attachments directory: {e}"))?;
-            std::fs::create_dir_all(&intake_dir)
+            std::fs::create_dir_all(&default_intake_dir)
                 .map_err(|e| format!("Failed to create intake directory: {e}"))?;
+            std::fs::create_dir_all(&bank_intake_dir)
+                .map_err(|e| format!("Failed to create bank intake directory: {e}"))?;
+            std::fs::create_dir_all(&shared_dir)
+                .map_err(|e| format!("Failed to create shared files directory: {e}"))?;
 
             let rt = tauri::async_runtime::handle();
 
-            let db = rt
-                .block_on(aequi_storage::create_db(&db_path))
-                .map_err(|e| format!("Failed to create database: {e}"))?;
+            // A ledger that fails its checks leaves the app in recovery mode
+            // rather than keeping it from starting.
+            let snapshots_dir = data_dir.join(db_health::SNAPSHOT_DIR);
+            let (db, db_health) = rt
+                .block_on(db_health::open_ledger(&db_path, &snapshots_dir))
+                .map_err(|e| format!("Failed to open database: {e}"))?;
+
+            // Start locked if a passphrase is set, and lock again when idle
+            let lock_settings = rt
+                .block_on(aequi_storage::app_lock::get_app_lock_settings(&db))
+                .unwrap_or_else(|e| {
+                    tracing::warn!("Failed to load app lock settings: {e}");
+                    Default::default()
+                });
+            app.manage(app_lock::AppLock::new(&lock_settings));
+            let app_for_lock = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let mut interval = tokio::time::interval(app_lock::CHECK_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    app_lock::check_idle(&app_for_lock);
+                }
+            });
 
-            rt.block_on(aequi_storage::seed_default_accounts(&db))
-                .map_err(|e| format!("Failed to seed default accounts: {e}"))?;
+            // The attachment store, wherever it was moved to
+            let attachments_dir = rt.block_on(commands::load_attachments_dir(
+                &db,
+                &default_attachments_dir,
+            ));
+
+            // The intake folder picked during setup, if any
+            let intake_dir = rt.block_on(commands::load_intake_dir(&db, &default_intake_dir));
 
             // Receipt intake pipeline
             let (receipt_tx, mut receipt_rx) = mpsc::channel::<PathBuf>(64);
 
+            // One OCR pipeline shared by the intake folder, the receipt
+            // inbox and the `ingest_receipt` command; `configure_ocr` swaps
+            // its backend.
+            let ocr_config = rt.block_on(commands::load_ocr_config(&db, &tessdata_dir));
+            let (recognizer, ocr_health) = aequi_ocr::build_recognizer(&ocr_config);
+            match &ocr_health.detail {
+                Some(detail) => tracing::warn!("OCR backend unavailable, using mock: {detail}"),
+                None => tracing::info!("OCR backend: {}", ocr_health.active.as_str()),
+            }
+            let pipeline = Arc::new(aequi_ocr::ReceiptPipeline::new(
+                recognizer,
+                attachments_dir.clone(),
+            ));
+            pipeline.set_llm_extractor(rt.block_on(commands::load_llm_extractor(&db)));
+            pipeline.set_vendor_dictionary(rt.block_on(commands::load_vendor_dictionary(&db)));
+            pipeline.set_extract_options(rt.block_on(commands::load_extract_options(&db)));
+            pipeline.set_image_limits(rt.block_on(commands::load_image_limits(&db)));
+            pipeline.set_dedup_check(Some(Arc::new(commands::StoredReceipts(db.clone()))));
+
             let db_for_pipeline = db.clone();
-            let attachments_for_pipeline = attachments_dir.clone();
+            let pipeline_for_intake = pipeline.clone();
 
+            // Each file is queued in the database as it arrives, and the
+            // frontend told, before it waits for a worker. Files left in
+            // progress by the last run go back in the queue first.
+            let requeued =
+                rt.block_on(aequi_storage::receipt_jobs::requeue_interrupted_receipt_jobs(&db));
+            match requeued {
+                Ok(0) => {}
+                Ok(n) => tracing::info!("Requeued {n} receipt(s) interrupted by the last exit"),
+                Err(e) => tracing::warn!("Failed to requeue interrupted receipts: {e}"),
+            }
+            let intake_wake = Arc::new(Notify::new());
+            let (queue_tx, queue_rx) = mpsc::channel::<PathBuf>(64);
+            let app_for_queue = app.handle().clone();
+            let db_for_queue = db.clone();
+            let wake_for_queue = intake_wake.clone();
             tauri::async_runtime::spawn(async move {
-                use aequi_ocr::{MockRecognizer, ReceiptPipeline};
+                while let Some(path) = receipt_rx.recv().await {
+                    receipt_intake::enqueue(
+                        &app_for_queue,
+                        &db_for_queue,
+                        &wake_for_queue,
+                        &path,
+                        aequi_storage::receipt_jobs::SOURCE_WATCHER,
+                    )
+                    .await;
+                }
+            });
+            tauri::async_runtime::spawn(receipt_intake::dispatch_jobs(
+                db.clone(),
+                queue_tx,
+                intake_wake.clone(),
+            ));
 
-                let pipeline =
-                    ReceiptPipeline::new(MockRecognizer::new(""), attachments_for_pipeline);
+            // Receipts are OCR'd on a bounded pool of workers; the count is
+            // read once at startup.
+            let workers = rt.block_on(commands::load_intake_workers(&db));
+            let app_for_intake = app.handle().clone();
+            tauri::async_runtime::spawn(aequi_ocr::pipeline::run_intake_workers(
+                queue_rx,
+                workers,
+                move |path| {
+                    let app = app_for_intake.clone();
+                    let db = db_for_pipeline.clone();
+                    let pipeline = pipeline_for_intake.clone();
+                    async move {
+                        receipt_intake::process_intake_file(&app, &db, &pipeline, &path).await
+                    }
+                },
+            ));
 
-                while let Some(path) = receipt_rx.recv().await {
-                    tracing::info!("Processing receipt: {}", path.display());
-                    match pipeline.process_file(&path).await {
-                        Ok(result) => {
-                            let e = &result.extracted;
-                            let ext = path.extension().and_then(|x| x.to_str()).unwrap_or("bin");
-                            let _ = aequi_storage::insert_receipt(
-                                &db_for_pipeline,
-                                &result.hash_hex,
-                                ext,
-                                result.attachment_path.to_str().unwrap_or(""),
-                                Some(&result.ocr_text),
-                                e.vendor.as_ref().map(|f| f.value.as_str()),
-                                e.date.as_ref().map(|f| f.value.to_string()).as_deref(),
-                                e.total_cents.as_ref().map(|f| f.value),
-                                e.subtotal_cents.as_ref().map(|f| f.value),
-                                e.tax_cents.as_ref().map(|f| f.value),
-                                e.payment_method
-                                    .as_ref()
-                                    .map(|f| f.value.to_string())
-                                    .as_deref(),
-                                e.confidence as f64,
-                            )
-                            .await;
-                            tracing::info!("Receipt stored: {}", result.hash_hex);
-                        }
-                        Err(e) => {
-                            tracing::warn!("Receipt pipeline error: {e}");
-                        }
+            // Receipts forwarded to an IMAP folder
+            let db_for_inbox = db.clone();
+            let pipeline_for_inbox = pipeline.clone();
+            tauri::async_runtime::spawn(async move {
+                let mut interval = tokio::time::interval(email_intake::POLL_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    match email_intake::poll_inbox(&db_for_inbox, &pipeline_for_inbox).await {
+                        Ok(0) => {}
+                        Ok(n) => tracing::info!("Stored {n} receipt(s) from the inbox"),
+                        Err(e) => tracing::warn!("Receipt inbox poll failed: {e}"),
+                    }
+                }
+            });
+
+            // Receipts whose bank transaction arrived after them, and the
+            // daily digest of what was booked automatically
+            let db_for_auto = db.clone();
+            let app_for_auto = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let mut interval = tokio::time::interval(auto_approve::CHECK_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    match auto_approve::run(&db_for_auto).await {
+                        Ok(0) => {}
+                        Ok(n) => tracing::info!("Auto-approved {n} receipt(s)"),
+                        Err(e) => tracing::warn!("Receipt auto-approval failed: {}", e.message),
+                    }
+                    if let Err(e) = auto_approve::send_digest(&app_for_auto, &db_for_auto).await {
+                        tracing::warn!("Auto-approval digest failed: {}", e.message);
+                    }
+                }
+            });
+
+            // Tax deadlines, overdue invoices, reconciliation and low
+            // balance reminders, checked at startup and then daily
+            let db_for_reminders = db.clone();
+            let app_for_reminders = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let mut interval = tokio::time::interval(reminders::CHECK_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    match reminders::run(&app_for_reminders, &db_for_reminders).await {
+                        Ok(0) => {}
+                        Ok(n) => tracing::info!("Raised {n} reminder(s)"),
+                        Err(e) => tracing::warn!("Reminder check failed: {}", e.message),
                     }
                 }
             });
 
             // Watch folder (desktop only — on mobile, files come via camera capture)
             #[cfg(desktop)]
-            let intake_watcher = {
-                let receipt_tx_for_watcher = receipt_tx.clone();
-                match aequi_ocr::pipeline::spawn_intake_watcher(&intake_dir, receipt_tx_for_watcher)
-                {
+            let intake_watcher = match receipt_intake::spawn_watcher(
+                &intake_dir,
+                receipt_tx.clone(),
+                rt.block_on(commands::load_intake_recursive(&db)),
+            ) {
+                Ok(watcher) => {
+                    tracing::info!("Watching intake folder: {}", intake_dir.display());
+                    Some(watcher)
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to start intake folder watcher: {e}");
+                    None
+                }
+            };
+
+            // Bank statement watch folder (desktop only)
+            #[cfg(desktop)]
+            let bank_intake_watcher = {
+                let (bank_tx, mut bank_rx) = mpsc::channel::<PathBuf>(64);
+                let db_for_bank = db.clone();
+                tauri::async_runtime::spawn(async move {
+                    while let Some(path) = bank_rx.recv().await {
+                        let span = logging::intake_span(&path);
+                        async {
+                            if let Err(e) = bank_intake::process_bank_file(&db_for_bank, &path).await
+                            {
+                                tracing::warn!("Bank file import failed for {}: {e}", path.display());
+                            }
+                        }
+                        .instrument(span)
+                        .await;
+                    }
+                });
+                match aequi_ocr::pipeline::spawn_intake_watcher(&bank_intake_dir, bank_tx) {
                     Ok(watcher) => {
-                        tracing::info!("Watching intake folder: {}", intake_dir.display());
+                        tracing::info!(
+                            "Watching bank intake folder: {}",
+                            bank_intake_dir.display()
+                        );
                         Some(Box::new(watcher) as Box<dyn std::any::Any + Send>)
                     }
                     Err(e) => {
-                        tracing::warn!("Failed to start intake folder watcher: {e}");
+                        tracing::warn!("Failed to start bank intake folder watcher: {e}");
                         None
                     }
                 }
             };
 
-            // Spawn MCP sidecar (desktop only)
+            let local_api = match rt.block_on(local_api::start(app.handle(), &db, &pipeline)) {
+                Ok(handle) => handle,
+                Err(e) => {
+                    tracing::warn!("Failed to start local API: {}", e.message);
+                    None
+                }
+            };
+
+            // Spawn MCP sidecar (desktop only). It would open the same
+            // ledger the checks turned down.
             #[cfg(desktop)]
-            spawn_mcp_sidecar(app, &db_path);
+            if !db_health.recovery_mode {
+                spawn_mcp_sidecar(app, &db_path);
+            }
 
             let state = AppState {
                 db,
                 db_path,
+                db_health,
+                snapshots_dir,
                 attachments_dir,
+                log_dir,
+                tessdata_dir,
+                pipeline,
+                ocr_health,
                 receipt_tx,
+                intake_wake,
+                shared_dir,
+                local_api,
+                import_jobs: HashMap::new(),
                 #[cfg(desktop)]
                 _intake_watcher: intake_watcher,
+                #[cfg(desktop)]
+                _bank_intake_watcher: bank_intake_watcher,
             };
             app.manage(Arc::new(Mutex::new(state)));
 
+            // Files the app was opened with ("Open with" on Windows and
+            // Linux passes them on the command line).
+            #[cfg(desktop)]
+            ingest_shared_files(
+                app.handle(),
+                std::env::args_os().skip(1).map(PathBuf::from).collect(),
+            );
+
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, .. }) = event {
+                ingest_shared_files(window.app_handle(), paths.clone());
+            }
+        })
+        .invoke_handler(app_lock::guard(tauri::generate_handler![
             commands::get_accounts,
             commands::create_transaction,
             commands::get_transactions,
+            commands::set_account_deductible_pct,
+            commands::set_transaction_deductibility,
+            commands::get_transaction_tags,
+            commands::set_transaction_tags,
+            commands::get_tags,
+            commands::capitalize_equipment,
+            commands::get_fixed_assets,
             commands::get_profit_loss,
+            commands::get_vendor_spending,
+            commands::get_owners_equity,
+            commands::get_saved_reports,
+            commands::save_saved_report,
+            commands::delete_saved_report,
+            commands::run_saved_report,
+            commands::preview_report_definition,
+            commands::suggest_payees,
+            commands::get_payee_accounts,
+            commands::get_payee_amounts,
+            commands::get_quick_entry_prefill,
             commands::ingest_receipt,
+            commands::ingest_receipt_bytes,
+            commands::ingest_shared_file,
+            commands::get_receipt_image,
             commands::get_pending_receipts,
+            commands::get_duplicate_receipts,
+            commands::clear_receipt_duplicate,
+            commands::get_receipts_from_same_photo,
+            commands::get_receipt_jobs,
+            commands::retry_receipt_job,
+            commands::query_receipts,
+            commands::get_documentation_gaps,
             commands::approve_receipt,
+            commands::create_transaction_from_receipt,
+            commands::get_receipt_line_items,
             commands::reject_receipt,
+            commands::unlink_receipt,
+            commands::bulk_approve_receipts,
+            commands::bulk_reject_receipts,
+            commands::correct_receipt_fields,
+            commands::update_receipt,
+            commands::get_extraction_accuracy,
+            commands::configure_receipt_inbox,
+            commands::poll_receipt_inbox,
+            commands::reprocess_receipts,
+            commands::get_reprocess_diffs,
+            commands::resolve_reprocess_diff,
+            commands::suggest_receipt_matches,
+            commands::confirm_receipt_match,
+            commands::get_auto_approve_settings,
+            commands::set_auto_approve_settings,
+            commands::get_auto_approvals,
+            commands::undo_auto_approval,
+            commands::attach_document,
+            commands::get_attachments,
+            commands::get_attachment_file,
+            commands::delete_attachment,
+            commands::get_attachment_store_settings,
+            commands::set_attachment_image_limits,
+            commands::move_attachment_store,
+            commands::file_document,
+            commands::search_documents,
+            commands::get_document_file,
+            commands::delete_document,
+            commands::archive_fiscal_year,
+            commands::get_ocr_health,
+            commands::configure_ocr,
+            commands::get_payment_accounts,
+            commands::set_payment_accounts,
+            commands::configure_receipt_llm,
+            commands::configure_receipt_locale,
+            commands::download_ocr_language,
+            commands::preview_import_file,
+            commands::commit_import,
+            commands::import_csv_streaming,
+            commands::cancel_import,
+            commands::get_import_summary,
+            commands::get_import_review,
+            commands::set_imported_category,
+            commands::get_uncategorized_queue,
+            commands::quick_categorize,
+            commands::accept_match,
+            commands::reject_match,
+            commands::post_batch,
+            commands::import_budget_app,
+            commands::import_gnucash,
+            commands::add_balance_assertion,
+            commands::get_balance_assertions,
+            commands::delete_balance_assertion,
+            commands::get_categorization_rules,
+            commands::create_categorization_rule,
+            commands::update_categorization_rule,
+            commands::delete_categorization_rule,
+            commands::reorder_categorization_rules,
+            commands::get_import_profiles,
+            commands::save_import_profile,
+            commands::update_import_profile,
+            commands::delete_import_profile,
+            commands::test_import_profile,
             commands::estimate_quarterly_tax,
             commands::get_schedule_c_preview,
+            commands::get_home_office_worksheet,
+            commands::save_home_office_worksheet,
+            commands::post_home_office_entry,
             commands::get_contacts,
             commands::create_contact,
+            commands::get_contact,
+            commands::archive_contact,
+            commands::delete_contact,
             commands::get_invoices,
             commands::create_invoice,
             commands::get_invoice_aging,
+            commands::get_ar_aging,
+            commands::get_ap_aging,
+            commands::create_bill,
+            commands::get_bills,
+            commands::void_bill,
+            commands::record_bill_payment,
             commands::record_invoice_payment,
+            commands::apply_customer_credit,
+            commands::get_invoice_payments,
             commands::get_1099_summary,
+            commands::get_time_entries,
+            commands::create_time_entry,
+            commands::update_time_entry,
+            commands::delete_time_entry,
+            commands::get_running_timer,
+            commands::start_timer,
+            commands::stop_timer,
+            commands::invoice_unbilled_time,
+            commands::configure_email,
             commands::send_invoice,
             commands::export_beancount,
             commands::export_qif,
+            commands::export_receipts,
+            commands::export_report,
+            commands::export_ledger_format,
+            commands::export_quickbooks,
+            commands::export_ofx,
+            commands::export_gnucash,
+            commands::export_calendar,
+            commands::get_onboarding_status,
+            commands::get_chart_templates,
+            commands::save_business_profile,
+            commands::save_fiscal_settings,
+            commands::apply_chart_template,
+            commands::set_opening_balances,
+            commands::set_intake_folder,
+            commands::complete_onboarding,
+            commands::get_settings,
             commands::get_setting,
             commands::set_setting,
+            commands::get_local_api_status,
+            commands::configure_local_api,
+            commands::get_sync_status,
+            commands::export_sync_changes,
+            commands::import_sync_changes,
             commands::get_audit_log,
             commands::get_schema_versions,
+            commands::get_diagnostics,
+            commands::get_recent_logs,
+            commands::rebuild_account_balances,
             commands::create_backup,
             commands::restore_backup,
+            commands::get_cloud_backup_settings,
+            commands::save_cloud_backup_settings,
+            commands::run_cloud_backup,
+            commands::list_cloud_backups,
+            commands::restore_cloud_backup,
+            commands::get_database_health,
+            commands::restore_database_snapshot,
             commands::check_for_updates,
             commands::check_overdue_invoices,
+            commands::get_notifications,
+            commands::refresh_notifications,
+            commands::mark_notification_read,
+            commands::dismiss_notification,
+            commands::get_reminder_settings,
+            commands::set_reminder_settings,
+            commands::get_budgets,
+            commands::set_budget,
+            commands::get_budget_variance,
+            commands::get_hook_settings,
+            commands::set_hook_settings,
+            commands::test_hook,
             commands::get_dashboard_summary,
+            commands::get_attention_feed,
             commands::update_contact,
-        ])
+            commands::get_app_lock_status,
+            commands::lock_app,
+            commands::unlock_app,
+            commands::set_app_lock_passphrase,
+            commands::remove_app_lock,
+            commands::set_app_lock_timeout,
+        ]))
 }
 
 #[cfg(mobile)]
 #[tauri::mobile_entry_point]
 fn main() {
-    tracing_subscriber::fmt::init();
-
     build_app()
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(handle_run_event);
 }
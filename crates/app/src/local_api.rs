@@ -0,0 +1,291 @@
+//! Localhost HTTP API for automation.
+//!
+//! With the `local-api` feature built in and the API turned on in settings,
+//! scripts on this machine (a scanner's "send to" hook, a Shortcuts action)
+//! can list accounts, post transactions and push receipts over plain
+//! HTTP/JSON. The server only listens on 127.0.0.1 and every request must
+//! carry the generated token as `Authorization: Bearer <token>`.
+//!
+//! | Method | Path                | Body                          |
+//! |--------|---------------------|-------------------------------|
+//! | GET    | `/api/accounts`     |                               |
+//! | POST   | `/api/transactions` | `TransactionInput` JSON       |
+//! | POST   | `/api/receipts`     | image or PDF bytes; type from `?ext=` or `Content-Type` |
+//!
+//! Calendar apps can't send headers, so the deadline calendar is served at
+//! `GET /calendar.ics?token=<token>` for them to subscribe to.
+//!
+//! While the app is locked every request, the calendar included, is refused
+//! with `423 Locked`: the token doesn't get around the passphrase.
+
+use std::sync::Arc;
+
+use aequi_storage::{DbPool, LocalApiSettings};
+use serde::Serialize;
+
+use crate::commands::CommandError;
+
+/// Whether this build includes the local API.
+pub const AVAILABLE: bool = cfg!(feature = "local-api");
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalApiStatus {
+    /// False when the app was built without the `local-api` feature.
+    pub available: bool,
+    pub enabled: bool,
+    pub running: bool,
+    pub port: u16,
+    pub token: Option<String>,
+    pub url: Option<String>,
+}
+
+impl LocalApiStatus {
+    pub fn new(settings: &LocalApiSettings, running: bool) -> Self {
+        LocalApiStatus {
+            available: AVAILABLE,
+            enabled: settings.enabled,
+            running,
+            port: settings.port,
+            token: settings.token.clone(),
+            url: running.then(|| format!("http://127.0.0.1:{}/api", settings.port)),
+        }
+    }
+}
+
+/// A fresh bearer token.
+pub fn generate_token() -> Result<String, CommandError> {
+    #[cfg(feature = "local-api")]
+    {
+        Ok(uuid::Uuid::new_v4().simple().to_string())
+    }
+    #[cfg(not(feature = "local-api"))]
+    {
+        Err(unavailable())
+    }
+}
+
+pub fn unavailable() -> CommandError {
+    CommandError::config("This build of aequi does not include the local API")
+}
+
+/// Start the API if it is turned on. Returns the server task, which stops
+/// the API when aborted, or `None` if it is off.
+pub async fn start(
+    app: &tauri::AppHandle,
+    db: &DbPool,
+    pipeline: &Arc<aequi_ocr::ReceiptPipeline>,
+) -> Result<Option<tauri::async_runtime::JoinHandle<()>>, CommandError> {
+    let settings = aequi_storage::get_local_api_settings(db).await?;
+    let Some(token) = settings.token.clone().filter(|_| settings.enabled) else {
+        return Ok(None);
+    };
+    #[cfg(feature = "local-api")]
+    {
+        server::spawn(
+            app.clone(),
+            db.clone(),
+            pipeline.clone(),
+            token,
+            settings.port,
+        )
+        .await
+        .map(Some)
+    }
+    #[cfg(not(feature = "local-api"))]
+    {
+        let _ = (app, pipeline, token);
+        tracing::warn!("Local API is enabled but this build does not include it");
+        Ok(None)
+    }
+}
+
+#[cfg(feature = "local-api")]
+mod server {
+    use std::sync::Arc;
+
+    use aequi_core::Account;
+    use axum::extract::{DefaultBodyLimit, Query, Request, State};
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::middleware::{self, Next};
+    use axum::response::{IntoResponse, Response};
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+    use serde::Deserialize;
+    use tauri::Manager;
+    use tracing::Instrument;
+
+    use crate::app_lock::AppLock;
+    use crate::commands::{self, CommandError, ReceiptOutput, TransactionInput, TransactionOutput};
+
+    struct ApiState {
+        app: tauri::AppHandle,
+        db: aequi_storage::DbPool,
+        pipeline: Arc<aequi_ocr::ReceiptPipeline>,
+        token: String,
+    }
+
+    impl IntoResponse for CommandError {
+        fn into_response(self) -> Response {
+            let status = match self.code.as_str() {
+                "VALIDATION" | "LEDGER" | "OCR" => StatusCode::BAD_REQUEST,
+                "NOT_FOUND" => StatusCode::NOT_FOUND,
+                "UNAUTHORIZED" => StatusCode::UNAUTHORIZED,
+                "LOCKED" => StatusCode::LOCKED,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (status, Json(self)).into_response()
+        }
+    }
+
+    /// Constant-time string comparison so the token can't be guessed by timing.
+    fn constant_time_eq(a: &str, b: &str) -> bool {
+        if a.len() != b.len() {
+            return false;
+        }
+        a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+            == 0
+    }
+
+    async fn require_token(
+        State(state): State<Arc<ApiState>>,
+        headers: HeaderMap,
+        request: Request,
+        next: Next,
+    ) -> Result<Response, CommandError> {
+        let token = headers
+            .get("authorization")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "));
+        match token {
+            Some(t) if constant_time_eq(t, &state.token) => Ok(next.run(request).await),
+            _ => Err(CommandError {
+                code: "UNAUTHORIZED".into(),
+                message: "Missing or invalid API token".into(),
+            }),
+        }
+    }
+
+    /// Refuse everything while the app is locked. API calls don't count as
+    /// activity, so a script can't keep the app from locking.
+    async fn require_unlocked(
+        State(state): State<Arc<ApiState>>,
+        request: Request,
+        next: Next,
+    ) -> Result<Response, CommandError> {
+        if state
+            .app
+            .try_state::<AppLock>()
+            .is_some_and(|lock| lock.is_locked())
+        {
+            return Err(CommandError {
+                code: "LOCKED".into(),
+                message: "aequi is locked".into(),
+            });
+        }
+        Ok(next.run(request).await)
+    }
+
+    async fn list_accounts(
+        State(state): State<Arc<ApiState>>,
+    ) -> Result<Json<Vec<Account>>, CommandError> {
+        Ok(Json(aequi_storage::get_all_accounts(&state.db).await?))
+    }
+
+    async fn create_transaction(
+        State(state): State<Arc<ApiState>>,
+        Json(input): Json<TransactionInput>,
+    ) -> Result<(StatusCode, Json<TransactionOutput>), CommandError> {
+        let output = commands::post_transaction(&state.db, input).await?;
+        Ok((StatusCode::CREATED, Json(output)))
+    }
+
+    #[derive(Deserialize)]
+    struct ReceiptQuery {
+        ext: Option<String>,
+    }
+
+    async fn ingest_receipt(
+        State(state): State<Arc<ApiState>>,
+        Query(q): Query<ReceiptQuery>,
+        headers: HeaderMap,
+        body: axum::body::Bytes,
+    ) -> Result<(StatusCode, Json<ReceiptOutput>), CommandError> {
+        let ext = q
+            .ext
+            .or_else(|| {
+                headers
+                    .get("content-type")
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string)
+            })
+            .ok_or_else(|| {
+                CommandError::validation("Give the file type as ?ext= or a Content-Type header")
+            })?;
+        let receipt = commands::ingest_receipt_data(&state.db, &state.pipeline, &body, &ext)
+            .instrument(crate::logging::command_span("api:ingest_receipt"))
+            .await?;
+        Ok((StatusCode::CREATED, Json(receipt)))
+    }
+
+    #[derive(Deserialize)]
+    struct CalendarQuery {
+        token: Option<String>,
+    }
+
+    async fn deadline_calendar(
+        State(state): State<Arc<ApiState>>,
+        Query(q): Query<CalendarQuery>,
+    ) -> Result<Response, CommandError> {
+        if !q.token.is_some_and(|t| constant_time_eq(&t, &state.token)) {
+            return Err(CommandError {
+                code: "UNAUTHORIZED".into(),
+                message: "Missing or invalid API token".into(),
+            });
+        }
+        let (ics, _) = crate::reminders::deadline_calendar(&state.db).await?;
+        Ok(([("content-type", "text/calendar; charset=utf-8")], ics).into_response())
+    }
+
+    fn router(state: Arc<ApiState>) -> Router {
+        Router::new()
+            .route("/api/accounts", get(list_accounts))
+            .route("/api/transactions", post(create_transaction))
+            .route("/api/receipts", post(ingest_receipt))
+            .route_layer(middleware::from_fn_with_state(state.clone(), require_token))
+            .route("/calendar.ics", get(deadline_calendar))
+            .layer(middleware::from_fn_with_state(
+                state.clone(),
+                require_unlocked,
+            ))
+            .layer(DefaultBodyLimit::max(commands::MAX_RECEIPT_SIZE as usize))
+            .with_state(state)
+    }
+
+    /// Bind to localhost and serve in the background. Binding happens before
+    /// returning so a port already in use is reported to the caller.
+    pub(super) async fn spawn(
+        app: tauri::AppHandle,
+        db: aequi_storage::DbPool,
+        pipeline: Arc<aequi_ocr::ReceiptPipeline>,
+        token: String,
+        port: u16,
+    ) -> Result<tauri::async_runtime::JoinHandle<()>, CommandError> {
+        let listener = tokio::net::TcpListener::bind(("127.0.0.1", port))
+            .await
+            .map_err(|e| CommandError::config(format!("Cannot listen on port {port}: {e}")))?;
+        let router = router(Arc::new(ApiState {
+            app,
+            db,
+            pipeline,
+            token,
+        }));
+        tracing::info!("Local API listening on http://127.0.0.1:{port}/api");
+        Ok(tauri::async_runtime::spawn(async move {
+            if let Err(e) = axum::serve(listener, router).await {
+                tracing::warn!("Local API stopped: {e}");
+            }
+        }))
+    }
+}
@@ -0,0 +1,246 @@
+//! Logging to rotating files under the data directory.
+//!
+//! Everything logged goes to stderr as before and, as JSON lines, to
+//! `logs/aequi.log`. When that file passes [`MAX_FILE_BYTES`] it is moved
+//! to `aequi.log.1` (shifting older ones up) and only [`KEEP_FILES`] are
+//! kept. [`read_recent`] reads them back for the in-app log viewer.
+//!
+//! Work started by a command or an intake file runs in a span carrying a
+//! correlation id (`cid`), so its log lines, including those from deep in
+//! the receipt pipeline, can be picked out together.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use serde::Serialize;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+/// Subdirectory of the data directory holding the log files.
+pub const LOG_DIR: &str = "logs";
+
+const LOG_FILE: &str = "aequi.log";
+const MAX_FILE_BYTES: u64 = 5 * 1024 * 1024;
+/// The current file plus this many minus one rotated ones.
+const KEEP_FILES: usize = 5;
+
+/// Entries returned by [`read_recent`] unless asked for another number.
+pub const DEFAULT_RECENT_LIMIT: usize = 500;
+
+/// A log file that starts a new one when it gets too big.
+struct RotatingFile {
+    dir: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn open(dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(LOG_FILE))?;
+        let size = file.metadata()?.len();
+        Ok(RotatingFile {
+            dir: dir.to_path_buf(),
+            file,
+            size,
+        })
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        for n in (1..KEEP_FILES).rev() {
+            let from = log_file(&self.dir, n - 1);
+            if from.exists() {
+                fs::rename(&from, log_file(&self.dir, n))?;
+            }
+        }
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.dir.join(LOG_FILE))?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > MAX_FILE_BYTES {
+            self.rotate()?;
+        }
+        let n = self.file.write(buf)?;
+        self.size += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// `aequi.log` for 0, `aequi.log.<n>` after that.
+fn log_file(dir: &Path, n: usize) -> PathBuf {
+    match n {
+        0 => dir.join(LOG_FILE),
+        n => dir.join(format!("{LOG_FILE}.{n}")),
+    }
+}
+
+/// Set up logging to stderr and, if the directory is usable, to rotating
+/// files in `log_dir`. `RUST_LOG` picks the level; the default is `info`.
+/// Only the first call has any effect.
+pub fn init(log_dir: &Path) {
+    let filter = || EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let stderr = tracing_subscriber::fmt::layer().with_writer(io::stderr);
+    match RotatingFile::open(log_dir) {
+        Ok(file) => {
+            let json = tracing_subscriber::fmt::layer()
+                .json()
+                .with_writer(Mutex::new(file));
+            let _ = tracing_subscriber::registry()
+                .with(filter())
+                .with(stderr)
+                .with(json)
+                .try_init();
+        }
+        Err(e) => {
+            let _ = tracing_subscriber::registry()
+                .with(filter())
+                .with(stderr)
+                .try_init();
+            tracing::warn!(
+                "Logging to stderr only; cannot open {}: {e}",
+                log_dir.display()
+            );
+        }
+    }
+}
+
+/// A new correlation id, unique within this run and unlikely to repeat
+/// across runs.
+pub fn correlation_id() -> String {
+    static RUN: OnceLock<u64> = OnceLock::new();
+    static NEXT: AtomicU64 = AtomicU64::new(1);
+    let run = RUN.get_or_init(|| {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() & 0xff_ffff)
+            .unwrap_or(0)
+    });
+    format!("{run:06x}-{}", NEXT.fetch_add(1, Ordering::Relaxed))
+}
+
+/// Span for one command invocation. Instrument the command's future with
+/// it so everything it logs carries the same `cid`.
+pub fn command_span(command: &str) -> tracing::Span {
+    tracing::info_span!("command", name = command, cid = %correlation_id())
+}
+
+/// Span for one file picked up from an intake folder.
+pub fn intake_span(path: &Path) -> tracing::Span {
+    tracing::info_span!("intake", file = %path.display(), cid = %correlation_id())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LogEntry {
+    pub timestamp: String,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+    /// From the innermost span that has one.
+    pub correlation_id: Option<String>,
+    /// Other fields logged with the message.
+    pub fields: serde_json::Map<String, serde_json::Value>,
+}
+
+fn severity(level: &str) -> u8 {
+    match level.to_ascii_uppercase().as_str() {
+        "ERROR" => 5,
+        "WARN" => 4,
+        "INFO" => 3,
+        "DEBUG" => 2,
+        _ => 1,
+    }
+}
+
+/// The newest `limit` entries at `min_level` or above, oldest first,
+/// optionally only those with correlation id `cid`.
+pub fn read_recent(
+    log_dir: &Path,
+    min_level: &str,
+    cid: Option<&str>,
+    limit: usize,
+) -> io::Result<Vec<LogEntry>> {
+    let min = severity(min_level);
+    let mut newest_first = Vec::new();
+    for n in 0..KEEP_FILES {
+        let path = log_file(log_dir, n);
+        let file = match File::open(&path) {
+            Ok(f) => f,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        let mut entries: Vec<LogEntry> = BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| parse_line(&line))
+            .filter(|e| severity(&e.level) >= min)
+            .filter(|e| cid.is_none() || e.correlation_id.as_deref() == cid)
+            .collect();
+        entries.reverse();
+        newest_first.extend(entries);
+        if newest_first.len() >= limit {
+            break;
+        }
+    }
+    newest_first.truncate(limit);
+    newest_first.reverse();
+    Ok(newest_first)
+}
+
+/// One JSON line as written by the file layer. Lines that don't parse (a
+/// partial last line, say) are skipped.
+fn parse_line(line: &str) -> Option<LogEntry> {
+    let mut value: serde_json::Value = serde_json::from_str(line).ok()?;
+    let mut fields = match value.get_mut("fields").map(serde_json::Value::take) {
+        Some(serde_json::Value::Object(map)) => map,
+        _ => serde_json::Map::new(),
+    };
+    let message = match fields.remove("message") {
+        Some(serde_json::Value::String(s)) => s,
+        Some(other) => other.to_string(),
+        None => String::new(),
+    };
+    let text = |key: &str| {
+        value
+            .get(key)
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string()
+    };
+    let correlation_id = value
+        .get("spans")
+        .and_then(|s| s.as_array())
+        .and_then(|spans| {
+            spans
+                .iter()
+                .rev()
+                .find_map(|span| span.get("cid").and_then(|c| c.as_str()))
+        })
+        .map(str::to_string);
+    Some(LogEntry {
+        timestamp: text("timestamp"),
+        level: text("level"),
+        target: text("target"),
+        message,
+        correlation_id,
+        fields,
+    })
+}
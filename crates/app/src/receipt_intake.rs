@@ -0,0 +1,399 @@
+//! Watch-folder intake for receipts.
+//!
+//! Each file dropped into the intake folder is sent down an [`IntakeRoute`]
+//! by its type: photos and PDFs are run through the OCR pipeline and stored
+//! for review, `.eml` files are split into their receipts, and bank
+//! statements are queued for import review like those dropped into the bank
+//! intake folder. The [`ROUTES_SETTING`] can send an extension elsewhere.
+//! Files of any other type fail straight away rather than being retried.
+//! Afterwards the file is moved to `processed/`, or to `failed/` next to a
+//! `.error.txt` note saying what went wrong, unless
+//! `receipt_intake_move_files` is `false`.
+//!
+//! Photos and PDFs opened with the app or dropped on its window are copied into [`SHARED_DIR`] by
+//! [`ingest_shared`] and go the same way, ending up in its `processed/` or
+//! `failed/`.
+//!
+//! Files go through the `receipt_jobs` queue (see
+//! [`aequi_storage::receipt_jobs`]) so none are lost when the app stops
+//! part way: [`enqueue`] records each one, [`dispatch_jobs`] hands them to
+//! the workers, and a failed file stays where it is to be tried again until
+//! its job is given up on.
+//!
+//! Each file's progress is emitted to the frontend as `receipt:queued`,
+//! `receipt:processing`, then `receipt:done`, `receipt:retrying` or
+//! `receipt:failed`.
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use aequi_import::bank_intake::move_to_subfolder;
+use aequi_import::BankFileKind;
+use aequi_ocr::{ProcessOutcome, ReceiptPipeline};
+use aequi_storage::receipt_jobs::{self, ReceiptJobStatus};
+use aequi_storage::DbPool;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::{mpsc, Notify};
+use tracing::Instrument;
+
+pub use aequi_import::bank_intake::PROCESSED_DIR;
+
+/// Where files the pipeline couldn't handle are moved.
+pub const FAILED_DIR: &str = "failed";
+
+/// Folder under the app's data directory that shared files are copied into.
+pub const SHARED_DIR: &str = "shared";
+
+pub const EVENT_QUEUED: &str = "receipt:queued";
+pub const EVENT_PROCESSING: &str = "receipt:processing";
+pub const EVENT_DONE: &str = "receipt:done";
+/// Failed, and queued to be tried again later.
+pub const EVENT_RETRYING: &str = "receipt:retrying";
+pub const EVENT_FAILED: &str = "receipt:failed";
+
+/// How often the queue is checked for jobs whose retry has come due.
+const RETRY_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Setting sending files with a given extension down another route than
+/// their type would, as a JSON object such as `{"csv": "reject"}`.
+pub const ROUTES_SETTING: &str = "receipt_intake_routes";
+
+/// Where an intake file is sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IntakeRoute {
+    /// A photo or scan, OCR'd as a receipt.
+    Image,
+    /// A PDF receipt, read from its text layer or, failing that, its
+    /// rendered pages.
+    Pdf,
+    /// An email, split into the receipts it carries.
+    Email,
+    /// A CSV, OFX/QFX or QIF statement, queued for import review.
+    BankStatement,
+    /// Not handled; moved to `failed/` without being tried again.
+    Reject,
+}
+
+impl IntakeRoute {
+    /// The route for `path` going by its extension.
+    pub fn for_path(path: &Path) -> Self {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default();
+        match aequi_ocr::canonical_extension(ext) {
+            Some("pdf") => IntakeRoute::Pdf,
+            Some(_) => IntakeRoute::Image,
+            None if crate::email_intake::is_eml(path) => IntakeRoute::Email,
+            None if BankFileKind::from_path(path).is_some() => IntakeRoute::BankStatement,
+            None => IntakeRoute::Reject,
+        }
+    }
+}
+
+/// Payload of the `receipt:*` events.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReceiptProgress {
+    /// The intake file, as it was named when it arrived.
+    pub path: String,
+    /// Receipts stored from the file (several for an email); on `done` only.
+    pub receipt_ids: Vec<i64>,
+    /// On `retrying` and `failed` only.
+    pub error: Option<String>,
+}
+
+/// Watch `dir` for receipts, sending each new file to `tx`. Dropping the
+/// returned watcher stops it.
+#[cfg(desktop)]
+pub fn spawn_watcher(
+    dir: &Path,
+    tx: tokio::sync::mpsc::Sender<std::path::PathBuf>,
+    recursive: bool,
+) -> notify::Result<Box<dyn std::any::Any + Send>> {
+    let options = aequi_ocr::pipeline::WatchOptions {
+        recursive,
+        exclude: vec![PROCESSED_DIR.to_string(), FAILED_DIR.to_string()],
+        ..Default::default()
+    };
+    let watcher = aequi_ocr::pipeline::spawn_intake_watcher_with(dir, tx, options)?;
+    Ok(Box::new(watcher))
+}
+
+pub fn emit_progress(app: &AppHandle, event: &str, path: &Path, progress: Result<&[i64], &str>) {
+    let payload = ReceiptProgress {
+        path: path.display().to_string(),
+        receipt_ids: progress.map(<[i64]>::to_vec).unwrap_or_default(),
+        error: progress.err().map(str::to_string),
+    };
+    if let Err(e) = app.emit(event, payload) {
+        tracing::warn!("Failed to emit {event}: {e}");
+    }
+}
+
+/// Queue a file from `source` and wake the dispatcher. A file already
+/// waiting or in progress isn't queued again.
+pub async fn enqueue(app: &AppHandle, db: &DbPool, wake: &Notify, path: &Path, source: &str) {
+    let queued = receipt_jobs::enqueue_receipt_job(db, &path.to_string_lossy(), source).await;
+    match queued {
+        Ok(Some(_)) => {
+            emit_progress(app, EVENT_QUEUED, path, Ok(&[]));
+            wake.notify_one();
+        }
+        Ok(None) => tracing::debug!("Receipt already queued: {}", path.display()),
+        Err(e) => tracing::warn!("Failed to queue receipt {}: {e}", path.display()),
+    }
+}
+
+/// Whether `path` is a photo or PDF, the files taken when shared.
+pub fn is_shareable(path: &Path) -> bool {
+    matches!(
+        IntakeRoute::for_path(path),
+        IntakeRoute::Image | IntakeRoute::Pdf
+    )
+}
+
+/// Queue a photo or PDF shared to the app. It is copied into `shared_dir`
+/// first, so the original stays where it was and a share's temporary file
+/// may go away before it's processed. Returns the copy's path, which is
+/// what the `receipt:*` events report.
+pub async fn ingest_shared(
+    app: &AppHandle,
+    db: &DbPool,
+    wake: &Notify,
+    shared_dir: &Path,
+    path: &Path,
+) -> Result<PathBuf, String> {
+    if !is_shareable(path) {
+        return Err(format!("{} is not a photo or PDF", path.display()));
+    }
+    let name = path
+        .file_name()
+        .ok_or_else(|| format!("{} is not a file", path.display()))?;
+    // Stamped, so sharing two files with the same name keeps both.
+    let mut copy_name = OsString::from(Utc::now().format("%Y%m%d-%H%M%S%.3f-").to_string());
+    copy_name.push(name);
+    let copy = shared_dir.join(copy_name);
+    tokio::fs::copy(path, &copy)
+        .await
+        .map_err(|e| format!("failed to copy {}: {e}", path.display()))?;
+    enqueue(app, db, wake, &copy, receipt_jobs::SOURCE_SHARED).await;
+    Ok(copy)
+}
+
+/// Hand queued files to the intake workers, oldest first, as they have
+/// room. Runs until the workers' channel closes.
+pub async fn dispatch_jobs(db: DbPool, tx: mpsc::Sender<PathBuf>, wake: Arc<Notify>) {
+    loop {
+        let Ok(permit) = tx.reserve().await else {
+            return;
+        };
+        match receipt_jobs::claim_next_receipt_job(&db).await {
+            Ok(Some(job)) => {
+                permit.send(PathBuf::from(job.path));
+                continue;
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Failed to take a receipt job: {e}"),
+        }
+        drop(permit);
+        tokio::select! {
+            _ = wake.notified() => {}
+            _ = tokio::time::sleep(RETRY_POLL_INTERVAL) => {}
+        }
+    }
+}
+
+/// OCR and store one file from the receipt intake folder, then move it out
+/// of the way. Everything logged along the way shares one correlation id.
+pub async fn process_intake_file(
+    app: &AppHandle,
+    db: &DbPool,
+    pipeline: &ReceiptPipeline,
+    path: &Path,
+) {
+    process(app, db, pipeline, path)
+        .instrument(crate::logging::intake_span(path))
+        .await
+}
+
+async fn process(app: &AppHandle, db: &DbPool, pipeline: &ReceiptPipeline, path: &Path) {
+    tracing::info!("Processing receipt: {}", path.display());
+    emit_progress(app, EVENT_PROCESSING, path, Ok(&[]));
+    let route = intake_route(db, path).await;
+    let outcome = ingest(db, pipeline, path, route).await;
+    let job = path.to_string_lossy();
+    let status = match (&outcome, route) {
+        (Err(e), IntakeRoute::Reject) => receipt_jobs::fail_receipt_job(db, &job, e)
+            .await
+            .map(|failed| failed.then_some(ReceiptJobStatus::Dead)),
+        _ => {
+            receipt_jobs::finish_receipt_job(
+                db,
+                &job,
+                outcome.as_ref().map(|_| ()).map_err(String::as_str),
+            )
+            .await
+        }
+    }
+    .unwrap_or_else(|e| {
+        tracing::warn!("Failed to record receipt job for {}: {e}", path.display());
+        None
+    });
+    match &outcome {
+        Ok(ids) => emit_progress(app, EVENT_DONE, path, Ok(ids)),
+        Err(e) if status == Some(ReceiptJobStatus::Queued) => {
+            tracing::warn!(
+                "Receipt intake failed for {}, will retry: {e}",
+                path.display()
+            );
+            emit_progress(app, EVENT_RETRYING, path, Err(e));
+            // Left in place for the next attempt.
+            return;
+        }
+        Err(e) => {
+            tracing::warn!("Receipt intake failed for {}: {e}", path.display());
+            emit_progress(app, EVENT_FAILED, path, Err(e));
+        }
+    }
+    if !move_files(db).await {
+        return;
+    }
+    let moved = match &outcome {
+        Ok(_) => move_to_subfolder(path, PROCESSED_DIR),
+        Err(e) => move_to_subfolder(path, FAILED_DIR).and_then(|dest| {
+            let mut note = dest.into_os_string();
+            note.push(".error.txt");
+            std::fs::write(&note, format!("{e}\n")).map(|_| note.into())
+        }),
+    };
+    if let Err(e) = moved {
+        tracing::warn!("Failed to move {} after intake: {e}", path.display());
+    }
+}
+
+/// The route configured for `path`'s extension in [`ROUTES_SETTING`], or
+/// the one its type takes.
+async fn intake_route(db: &DbPool, path: &Path) -> IntakeRoute {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    load_route_overrides(db)
+        .await
+        .remove(&ext)
+        .unwrap_or_else(|| IntakeRoute::for_path(path))
+}
+
+/// [`ROUTES_SETTING`], keyed by lowercase extension without the dot.
+async fn load_route_overrides(db: &DbPool) -> HashMap<String, IntakeRoute> {
+    let json = match aequi_storage::get_setting(db, ROUTES_SETTING).await {
+        Ok(Some(json)) if !json.trim().is_empty() => json,
+        Ok(_) => return HashMap::new(),
+        Err(e) => {
+            tracing::warn!("Failed to load {ROUTES_SETTING}: {e}");
+            return HashMap::new();
+        }
+    };
+    match serde_json::from_str::<HashMap<String, IntakeRoute>>(&json) {
+        Ok(routes) => routes
+            .into_iter()
+            .map(|(ext, route)| (ext.trim_start_matches('.').to_ascii_lowercase(), route))
+            .collect(),
+        Err(e) => {
+            tracing::warn!("Ignoring invalid {ROUTES_SETTING}: {e}");
+            HashMap::new()
+        }
+    }
+}
+
+/// The ids of the receipts stored (or already stored) from the file; none
+/// for a bank statement.
+async fn ingest(
+    db: &DbPool,
+    pipeline: &ReceiptPipeline,
+    path: &Path,
+    route: IntakeRoute,
+) -> Result<Vec<i64>, String> {
+    match route {
+        IntakeRoute::Image | IntakeRoute::Pdf => {}
+        IntakeRoute::Email => {
+            let ids = crate::email_intake::process_eml_file(db, pipeline, path).await?;
+            tracing::info!("Stored {} receipt(s) from email", ids.len());
+            return Ok(ids);
+        }
+        IntakeRoute::BankStatement => {
+            crate::bank_intake::import_bank_file(db, path).await?;
+            return Ok(Vec::new());
+        }
+        IntakeRoute::Reject => {
+            let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("none");
+            return Err(format!("Unsupported file type (.{ext})"));
+        }
+    }
+
+    let data = tokio::fs::read(path)
+        .await
+        .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+    let ext = aequi_ocr::sniff_extension(&data)
+        .ok_or_else(|| "File content is not an image or PDF".to_string())?;
+    let wants_pdf = route == IntakeRoute::Pdf;
+    if (ext == "pdf") != wants_pdf {
+        let expected = if wants_pdf { "a PDF" } else { "an image" };
+        return Err(format!("File content is {ext}, not {expected}"));
+    }
+    let results = match pipeline
+        .process_bytes(&data, ext)
+        .await
+        .map_err(|e| e.to_string())?
+    {
+        ProcessOutcome::Duplicate {
+            hash_hex,
+            receipt_id,
+        } => {
+            tracing::info!("Receipt already stored: {hash_hex}");
+            return Ok(vec![receipt_id]);
+        }
+        outcome => outcome.into_results(),
+    };
+    let mut ids = Vec::with_capacity(results.len());
+    for result in &results {
+        if let Some(err) = &result.llm_error {
+            tracing::warn!("LLM receipt extraction failed: {err}");
+        }
+        let inserted = crate::commands::store_ocr_result(db, result, ext)
+            .await
+            .map_err(|e| format!("failed to store receipt: {e}"))?;
+        match inserted {
+            aequi_storage::ReceiptInsert::PossibleDuplicate { duplicate_of, .. } => {
+                tracing::info!(
+                    "Receipt stored as a possible copy of receipt {duplicate_of}: {}",
+                    result.hash_hex
+                )
+            }
+            _ => tracing::info!("Receipt stored: {}", result.hash_hex),
+        }
+        ids.push(inserted.id());
+    }
+    if let Some(source) = results.first().and_then(|r| r.source.as_ref()) {
+        tracing::info!("Photo {} held {} receipts", source.hash_hex, ids.len());
+    }
+    Ok(ids)
+}
+
+/// `receipt_intake_move_files`; on unless set to `false`.
+async fn move_files(db: &DbPool) -> bool {
+    match aequi_storage::get_setting(db, "receipt_intake_move_files").await {
+        Ok(value) => value.is_none_or(|v| v.trim() != "false"),
+        Err(e) => {
+            tracing::warn!("Failed to load receipt_intake_move_files: {e}");
+            false
+        }
+    }
+}
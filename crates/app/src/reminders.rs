@@ -0,0 +1,100 @@
+//! Reminders for things that need attention.
+//!
+//! Once at startup and then daily, the reminder rules are evaluated against
+//! the ledger: estimated tax payments coming due, invoices past due, accounts
+//! not reconciled lately, a low checking balance and spending past its
+//! budget. Each one becomes a stored notification, and the ones raised this
+//! run are also shown as a system notification. The deadline calendar, when
+//! turned on, is rewritten on the same schedule.
+
+use std::time::Duration;
+
+use aequi_core::Quarter;
+use aequi_storage::calendar;
+use aequi_storage::reminders::{self, NotificationRecord, TaxDeadline};
+use aequi_storage::DbPool;
+use chrono::Datelike;
+use tauri_plugin_notification::NotificationExt;
+
+use crate::commands::{load_tax_rules, CommandError};
+
+/// How often the reminder rules are evaluated.
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// More newly raised notifications than this are shown as one summary.
+const MAX_INDIVIDUAL: usize = 3;
+
+/// Estimated payment due dates for this year and last year (the Q4 payment
+/// falls in January). Years without bundled tax rules are skipped.
+fn tax_deadlines(today: chrono::NaiveDate) -> Vec<TaxDeadline> {
+    let year = today.year() as u16;
+    let mut deadlines = Vec::new();
+    for year in [year - 1, year] {
+        let Ok(rules) = load_tax_rules(year) else {
+            continue;
+        };
+        for (n, quarter) in [Quarter::Q1, Quarter::Q2, Quarter::Q3, Quarter::Q4]
+            .into_iter()
+            .enumerate()
+        {
+            deadlines.push(TaxDeadline {
+                year,
+                quarter: n as u8 + 1,
+                due_date: rules.quarterly_due_dates.due_date(quarter),
+            });
+        }
+    }
+    deadlines
+}
+
+/// Evaluate the reminder rules and update the stored notifications, and the
+/// deadline calendar if one is kept. Returns the notifications raised by
+/// this run.
+pub async fn refresh(db: &DbPool) -> Result<Vec<NotificationRecord>, CommandError> {
+    let settings = reminders::get_reminder_settings(db).await?;
+    let today = chrono::Utc::now().date_naive();
+    let active = reminders::evaluate_reminders(db, &settings, &tax_deadlines(today), today).await?;
+    let raised = reminders::refresh_notifications(db, &active).await?;
+    if let Some(path) = &settings.calendar_path {
+        let (ics, _) = deadline_calendar(db).await?;
+        if let Err(e) = tokio::fs::write(path, ics).await {
+            tracing::warn!("Failed to write deadline calendar {path}: {e}");
+        }
+    }
+    Ok(raised)
+}
+
+/// Upcoming tax deadlines, invoice due dates and recurring transactions as
+/// an iCalendar file, with the number of events in it.
+pub async fn deadline_calendar(db: &DbPool) -> Result<(String, usize), CommandError> {
+    let now = chrono::Utc::now();
+    let today = now.date_naive();
+    let events = calendar::deadline_events(db, &tax_deadlines(today), today).await?;
+    Ok((calendar::write_ics(&events, now), events.len()))
+}
+
+/// Refresh, then show what was newly raised as system notifications.
+pub async fn run(app: &tauri::AppHandle, db: &DbPool) -> Result<usize, CommandError> {
+    let raised = refresh(db).await?;
+    if raised.len() > MAX_INDIVIDUAL {
+        let _ = app
+            .notification()
+            .builder()
+            .title("Reminders")
+            .body(format!(
+                "{} things need your attention. Open aequi to review them.",
+                raised.len()
+            ))
+            .show();
+    } else {
+        for notification in &raised {
+            let _ = app
+                .notification()
+                .builder()
+                .title(&notification.title)
+                .body(&notification.body)
+                .show();
+        }
+    }
+    Ok(raised.len())
+}
@@ -41,6 +41,15 @@ pub struct Account {
     pub is_archetype: bool,
     pub is_archived: bool,
     pub schedule_c_line: Option<String>,
+    /// Share of the account's spending that is deductible, 0–100, on top
+    /// of each transaction's own share. The meals cap from the tax rules
+    /// already covers line 24b, so meals accounts stay at 100.
+    #[serde(default = "fully_deductible")]
+    pub deductible_pct: u8,
+}
+
+fn fully_deductible() -> u8 {
+    100
 }
 
 impl Account {
@@ -53,6 +62,7 @@ impl Account {
             is_archetype: false,
             is_archived: false,
             schedule_c_line: None,
+            deductible_pct: 100,
         }
     }
 }
@@ -126,6 +136,78 @@ pub const DEFAULT_ACCOUNTS: &[(&str, &str, AccountType, &str)] = &[
     ("5900", "Miscellaneous", AccountType::Expense, "line_27"),
 ];
 
+/// Account used to balance opening balances.
+pub const OPENING_BALANCE_EQUITY_CODE: &str = "3000";
+
+/// Equity account owner withdrawals are booked to.
+pub const OWNER_DRAW_CODE: &str = "3100";
+
+/// Expense account equipment purchases are booked to.
+pub const EQUIPMENT_EXPENSE_CODE: &str = "5040";
+
+/// Starting charts of accounts offered during setup. Each is a subset of
+/// [`DEFAULT_ACCOUNTS`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChartTemplate {
+    /// Every default account.
+    Standard,
+    /// For freelancers and consultants: no product sales.
+    Services,
+    /// Bank, card, equity and a handful of common categories.
+    Minimal,
+}
+
+impl ChartTemplate {
+    pub const ALL: [ChartTemplate; 3] = [
+        ChartTemplate::Standard,
+        ChartTemplate::Services,
+        ChartTemplate::Minimal,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ChartTemplate::Standard => "standard",
+            ChartTemplate::Services => "services",
+            ChartTemplate::Minimal => "minimal",
+        }
+    }
+
+    pub fn includes(self, code: &str) -> bool {
+        match self {
+            ChartTemplate::Standard => true,
+            ChartTemplate::Services => code != "4010",
+            ChartTemplate::Minimal => matches!(
+                code,
+                "1000"
+                    | "1010"
+                    | "1020"
+                    | "2000"
+                    | "2010"
+                    | "3000"
+                    | "3100"
+                    | "4000"
+                    | "4020"
+                    | "5010"
+                    | "5020"
+                    | "5100"
+                    | "5110"
+                    | "5900"
+            ),
+        }
+    }
+
+    /// The template's accounts, in [`DEFAULT_ACCOUNTS`] order.
+    pub fn accounts(
+        self,
+    ) -> impl Iterator<Item = &'static (&'static str, &'static str, AccountType, &'static str)>
+    {
+        DEFAULT_ACCOUNTS
+            .iter()
+            .filter(move |(code, _, _, _)| self.includes(code))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -140,6 +222,7 @@ mod tests {
         assert!(!a.is_archived);
         assert!(a.id.is_none());
         assert!(a.schedule_c_line.is_none());
+        assert_eq!(a.deductible_pct, 100);
     }
 
     #[test]
@@ -175,4 +258,24 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn chart_templates_keep_required_accounts() {
+        for template in ChartTemplate::ALL {
+            let codes: Vec<_> = template.accounts().map(|(code, _, _, _)| *code).collect();
+            for required in ["1000", "2000", OPENING_BALANCE_EQUITY_CODE, "4000"] {
+                assert!(
+                    codes.contains(&required),
+                    "{} template missing {required}",
+                    template.as_str()
+                );
+            }
+        }
+        assert_eq!(
+            ChartTemplate::Standard.accounts().count(),
+            DEFAULT_ACCOUNTS.len()
+        );
+        assert!(!ChartTemplate::Services.includes("4010"));
+        assert!(ChartTemplate::Minimal.accounts().count() < DEFAULT_ACCOUNTS.len());
+    }
 }
@@ -0,0 +1,87 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::money::Money;
+
+/// How much of a transaction counts toward the business.
+///
+/// Personal spending on a shared card is kept in the books so the card
+/// balance reconciles, but is left out of profit and loss and tax. A mixed
+/// expense (a phone used 60% for work) is reported at its business share.
+/// The meals cap from the tax rules applies on top of this percentage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Deductibility {
+    pub is_personal: bool,
+    /// Business share of the amount, 0–100.
+    pub deductible_percent: u8,
+}
+
+impl Default for Deductibility {
+    fn default() -> Self {
+        Deductibility {
+            is_personal: false,
+            deductible_percent: 100,
+        }
+    }
+}
+
+impl Deductibility {
+    pub fn personal() -> Self {
+        Deductibility {
+            is_personal: true,
+            ..Default::default()
+        }
+    }
+
+    /// A business expense deductible at `percent`; `None` above 100.
+    pub fn partial(percent: u8) -> Option<Self> {
+        (percent <= 100).then_some(Deductibility {
+            is_personal: false,
+            deductible_percent: percent,
+        })
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.deductible_percent <= 100
+    }
+
+    /// The part of an expense that can be deducted: nothing for a personal
+    /// transaction, otherwise the business share.
+    pub fn deductible(&self, amount: Money) -> Money {
+        if self.is_personal {
+            return Money::zero();
+        }
+        amount * (Decimal::from(self.deductible_percent) / Decimal::from(100))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_is_fully_deductible() {
+        let d = Deductibility::default();
+        assert_eq!(d.deductible(Money::from_cents(12_345)).to_cents(), 12_345);
+    }
+
+    #[test]
+    fn partial_and_personal() {
+        let half = Deductibility::partial(50).unwrap();
+        assert_eq!(half.deductible(Money::from_cents(4_001)).to_cents(), 2_000);
+        assert_eq!(
+            Deductibility::personal()
+                .deductible(Money::from_cents(4_000))
+                .to_cents(),
+            0
+        );
+        assert!(Deductibility::partial(101).is_none());
+    }
+
+    #[test]
+    fn missing_fields_default() {
+        let d: Deductibility = serde_json::from_str(r#"{"deductible_percent": 60}"#).unwrap();
+        assert_eq!(d, Deductibility::partial(60).unwrap());
+    }
+}
@@ -106,6 +106,7 @@ mod tests {
                 is_archetype: false,
                 is_archived: false,
                 schedule_c_line: None,
+                deductible_pct: 100,
             },
             Account {
                 id: Some(AccountId(2)),
@@ -115,6 +116,7 @@ mod tests {
                 is_archetype: false,
                 is_archived: false,
                 schedule_c_line: None,
+                deductible_pct: 100,
             },
         ]
     }
@@ -0,0 +1,242 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::NaiveDate;
+
+use crate::{Account, AccountId, AccountType, Money, ValidatedTransaction};
+
+/// Posting that balances the opening balances transaction.
+pub const OPENING_BALANCES_ACCOUNT: &str = "Equity:Opening Balances";
+
+/// Balances carried into the exported period: each balance sheet account's
+/// net debit (debits less credits) before `date`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LedgerOpening {
+    pub date: NaiveDate,
+    pub balances: Vec<(AccountId, Money)>,
+}
+
+/// Top-level account for each account type.
+fn ledger_type(account_type: AccountType) -> &'static str {
+    match account_type {
+        AccountType::Asset => "Assets",
+        AccountType::Liability => "Liabilities",
+        AccountType::Equity => "Equity",
+        AccountType::Income => "Income",
+        AccountType::Expense => "Expenses",
+    }
+}
+
+/// hledger's account type tag.
+fn hledger_type(account_type: AccountType) -> &'static str {
+    match account_type {
+        AccountType::Asset => "A",
+        AccountType::Liability => "L",
+        AccountType::Equity => "E",
+        AccountType::Income => "R",
+        AccountType::Expense => "X",
+    }
+}
+
+/// Make an account name safe as one level of the hierarchy: no colons
+/// (which would add a level), no semicolons (which start a comment) and no
+/// runs of spaces (which end the name).
+fn sanitize_name(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| match c {
+            ':' | ';' => '-',
+            c if c.is_whitespace() => ' ',
+            c => c,
+        })
+        .collect();
+    let words: Vec<&str> = cleaned.split(' ').filter(|w| !w.is_empty()).collect();
+    let joined = words.join(" ");
+    let trimmed = joined.trim_start_matches(['(', '[']);
+    if trimmed.is_empty() {
+        "Unnamed".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+/// Colon-separated name for every account, e.g. `Expenses:Bank Fees`. Two
+/// accounts that would get the same name are told apart by their codes.
+fn account_names(accounts: &[Account]) -> HashMap<i64, String> {
+    let full = |a: &Account| format!("{}:{}", ledger_type(a.account_type), sanitize_name(&a.name));
+    let mut seen = HashSet::new();
+    let duplicated: HashSet<String> = accounts
+        .iter()
+        .map(full)
+        .filter(|name| !seen.insert(name.clone()))
+        .collect();
+    accounts
+        .iter()
+        .filter_map(|a| {
+            let name = full(a);
+            let name = if duplicated.contains(&name) {
+                format!("{name} {}", a.code)
+            } else {
+                name
+            };
+            a.id.map(|id| (id.0, name))
+        })
+        .collect()
+}
+
+/// `$1234.56` or `$-1234.56`, the form both ledger and hledger print.
+fn amount(m: Money) -> String {
+    format!("${:.2}", m.as_decimal())
+}
+
+fn one_line(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Export accounts and transactions as a journal readable by ledger-cli and
+/// hledger. With `opening`, balances from before the exported period are
+/// brought in by a first transaction against [`OPENING_BALANCES_ACCOUNT`].
+pub fn export_ledger(
+    accounts: &[Account],
+    opening: Option<&LedgerOpening>,
+    transactions: &[ValidatedTransaction],
+) -> String {
+    let names = account_names(accounts);
+    let name_of = |id: AccountId| {
+        names
+            .get(&id.0)
+            .cloned()
+            .unwrap_or_else(|| format!("Unknown:Account {}", id.0))
+    };
+    let mut out = String::new();
+
+    out.push_str("; Exported from Aequi\n");
+    out.push_str("commodity $1000.00\n\n");
+
+    for acct in accounts {
+        let Some(id) = acct.id else { continue };
+        out.push_str(&format!(
+            "account {}  ; type:{}, code:{}\n",
+            name_of(id),
+            hledger_type(acct.account_type),
+            acct.code
+        ));
+    }
+    if opening.is_some() {
+        out.push_str(&format!("account {OPENING_BALANCES_ACCOUNT}  ; type:E\n"));
+    }
+
+    if let Some(opening) = opening.filter(|o| !o.balances.is_empty()) {
+        out.push_str(&format!("\n{} * Opening balances\n", opening.date));
+        for (id, balance) in &opening.balances {
+            out.push_str(&format!("    {}  {}\n", name_of(*id), amount(*balance)));
+        }
+        out.push_str(&format!("    {OPENING_BALANCES_ACCOUNT}\n"));
+    }
+
+    for tx in transactions {
+        out.push('\n');
+        let code = tx.id.map(|id| format!(" ({id})")).unwrap_or_default();
+        out.push_str(&format!(
+            "{} *{code} {}\n",
+            tx.date,
+            one_line(&tx.description)
+        ));
+        if let Some(memo) = tx.memo.as_deref().filter(|m| !m.trim().is_empty()) {
+            out.push_str(&format!("    ; {}\n", one_line(memo)));
+        }
+        for line in &tx.lines {
+            let net = line.debit - line.credit;
+            out.push_str(&format!(
+                "    {}  {}",
+                name_of(line.account_id),
+                amount(net)
+            ));
+            if let Some(memo) = line.memo.as_deref().filter(|m| !m.trim().is_empty()) {
+                out.push_str(&format!("  ; {}", one_line(memo)));
+            }
+            out.push('\n');
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TransactionLine, UnvalidatedTransaction};
+
+    fn account(id: i64, code: &str, name: &str, account_type: AccountType) -> Account {
+        Account {
+            id: Some(AccountId(id)),
+            code: code.to_string(),
+            name: name.to_string(),
+            account_type,
+            is_archetype: false,
+            is_archived: false,
+            schedule_c_line: None,
+            deductible_pct: 100,
+        }
+    }
+
+    fn test_accounts() -> Vec<Account> {
+        vec![
+            account(1, "1000", "Checking", AccountType::Asset),
+            account(
+                2,
+                "5020",
+                "Business Meals (50% deductible)",
+                AccountType::Expense,
+            ),
+            account(3, "5090", "Meals: Travel;  Local", AccountType::Expense),
+            account(4, "2000", "Credit Card", AccountType::Liability),
+            account(5, "2001", "Credit Card", AccountType::Liability),
+        ]
+    }
+
+    #[test]
+    fn names_form_a_hierarchy() {
+        let names = account_names(&test_accounts());
+        assert_eq!(names[&1], "Assets:Checking");
+        assert_eq!(names[&2], "Expenses:Business Meals (50% deductible)");
+        assert_eq!(names[&3], "Expenses:Meals- Travel- Local");
+        assert_eq!(names[&4], "Liabilities:Credit Card 2000");
+        assert_eq!(names[&5], "Liabilities:Credit Card 2001");
+    }
+
+    #[test]
+    fn export_with_opening_and_transaction() {
+        let accounts = test_accounts();
+        let mut tx = ValidatedTransaction::validate(UnvalidatedTransaction {
+            date: NaiveDate::from_ymd_opt(2026, 3, 1).unwrap(),
+            description: "Lunch with\nclient".to_string(),
+            lines: vec![
+                TransactionLine::debit(AccountId(2), Money::from_cents(4250), None),
+                TransactionLine::credit(AccountId(1), Money::from_cents(4250), None),
+            ],
+            memo: Some("Project kickoff".to_string()),
+        })
+        .unwrap();
+        tx.id = Some(7);
+        let opening = LedgerOpening {
+            date: NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(),
+            balances: vec![(AccountId(1), Money::from_cents(100_000))],
+        };
+
+        let out = export_ledger(&accounts, Some(&opening), &[tx]);
+        assert!(out.contains("account Assets:Checking  ; type:A, code:1000\n"));
+        assert!(out.contains(
+            "2026-01-01 * Opening balances\n    Assets:Checking  $1000.00\n    Equity:Opening Balances\n"
+        ));
+        assert!(out.contains(
+            "2026-03-01 * (7) Lunch with client\n    ; Project kickoff\n    Expenses:Business Meals (50% deductible)  $42.50\n    Assets:Checking  $-42.50\n"
+        ));
+    }
+
+    #[test]
+    fn export_without_opening() {
+        let out = export_ledger(&test_accounts(), None, &[]);
+        assert!(!out.contains("Opening"));
+        assert!(out.starts_with("; Exported from Aequi\n"));
+    }
+}
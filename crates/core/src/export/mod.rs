@@ -1,5 +1,11 @@
 pub mod beancount;
+pub mod ledger;
 pub mod qif;
+pub mod quickbooks;
+pub mod report;
 
 pub use beancount::export_beancount;
+pub use ledger::{export_ledger, LedgerOpening};
 pub use qif::export_qif;
+pub use quickbooks::{export_quickbooks, QuickbooksFormat};
+pub use report::{ReportCell, ReportColumn, ReportFormat, ReportRow, ReportTable, RowStyle};
@@ -0,0 +1,268 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::export::report::csv_field;
+use crate::{Account, AccountId, AccountType, Money, ValidatedTransaction};
+
+/// File QuickBooks can import journal entries from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuickbooksFormat {
+    /// Intuit Interchange Format, read by QuickBooks Desktop.
+    Iif,
+    /// The journal entry CSV QuickBooks Online imports.
+    JournalCsv,
+}
+
+impl QuickbooksFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            QuickbooksFormat::Iif => "iif",
+            QuickbooksFormat::JournalCsv => "csv",
+        }
+    }
+}
+
+/// QuickBooks account type, from the account type and, for the balance
+/// sheet accounts QuickBooks treats specially, the name.
+fn iif_type(account: &Account) -> &'static str {
+    let name = account.name.to_lowercase();
+    match account.account_type {
+        AccountType::Asset if name.contains("receivable") => "AR",
+        AccountType::Asset
+            if ["checking", "savings", "bank", "cash"]
+                .iter()
+                .any(|w| name.contains(w)) =>
+        {
+            "BANK"
+        }
+        AccountType::Asset => "OCASSET",
+        AccountType::Liability if name.contains("accounts payable") => "AP",
+        AccountType::Liability if name.contains("credit card") => "CCARD",
+        AccountType::Liability => "OCLIAB",
+        AccountType::Equity => "EQUITY",
+        AccountType::Income => "INC",
+        AccountType::Expense => "EXP",
+    }
+}
+
+/// One line of text: no tabs or line breaks, which end IIF fields and
+/// rows, and no double quotes, which QuickBooks reads as field quoting.
+fn field(s: &str) -> String {
+    s.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace('"', "'")
+}
+
+/// Account names as QuickBooks will show them. A colon would make a
+/// subaccount, and two accounts with the same name are told apart by code.
+fn account_names(accounts: &[Account]) -> HashMap<i64, String> {
+    let clean = |a: &Account| field(&a.name).replace(':', "-");
+    let mut seen = HashSet::new();
+    let duplicated: HashSet<String> = accounts
+        .iter()
+        .map(|a| clean(a).to_lowercase())
+        .filter(|name| !seen.insert(name.clone()))
+        .collect();
+    accounts
+        .iter()
+        .filter_map(|a| {
+            let name = clean(a);
+            let name = if duplicated.contains(&name.to_lowercase()) {
+                format!("{name} {}", a.code)
+            } else {
+                name
+            };
+            a.id.map(|id| (id.0, name))
+        })
+        .collect()
+}
+
+fn amount(m: Money) -> String {
+    format!("{:.2}", m.as_decimal())
+}
+
+/// Export accounts and transactions in `format`.
+pub fn export_quickbooks(
+    accounts: &[Account],
+    transactions: &[ValidatedTransaction],
+    format: QuickbooksFormat,
+) -> String {
+    match format {
+        QuickbooksFormat::Iif => export_iif(accounts, transactions),
+        QuickbooksFormat::JournalCsv => export_journal_csv(accounts, transactions),
+    }
+}
+
+/// Export as an IIF file: the chart of accounts, so QuickBooks creates any
+/// it lacks, then each transaction as a general journal entry.
+pub fn export_iif(accounts: &[Account], transactions: &[ValidatedTransaction]) -> String {
+    let names = account_names(accounts);
+    let name_of = |id: AccountId| {
+        names
+            .get(&id.0)
+            .cloned()
+            .unwrap_or_else(|| format!("Account {}", id.0))
+    };
+    let mut out = String::new();
+
+    out.push_str("!ACCNT\tNAME\tACCNTTYPE\tACCNUM\tDESC\n");
+    for account in accounts {
+        let Some(id) = account.id else { continue };
+        out.push_str(&format!(
+            "ACCNT\t{}\t{}\t{}\t\n",
+            name_of(id),
+            iif_type(account),
+            field(&account.code)
+        ));
+    }
+
+    out.push_str("!TRNS\tTRNSID\tTRNSTYPE\tDATE\tACCNT\tAMOUNT\tDOCNUM\tMEMO\n");
+    out.push_str("!SPL\tSPLID\tTRNSTYPE\tDATE\tACCNT\tAMOUNT\tDOCNUM\tMEMO\n");
+    out.push_str("!ENDTRNS\n");
+    for tx in transactions {
+        let date = tx.date.format("%m/%d/%Y");
+        let docnum = tx.id.map(|id| id.to_string()).unwrap_or_default();
+        for (i, line) in tx.lines.iter().enumerate() {
+            let memo = line
+                .memo
+                .as_deref()
+                .filter(|m| !m.trim().is_empty())
+                .unwrap_or(&tx.description);
+            out.push_str(&format!(
+                "{}\t\tGENERAL JOURNAL\t{date}\t{}\t{}\t{docnum}\t{}\n",
+                if i == 0 { "TRNS" } else { "SPL" },
+                name_of(line.account_id),
+                amount(line.debit - line.credit),
+                field(memo)
+            ));
+        }
+        out.push_str("ENDTRNS\n");
+    }
+
+    out
+}
+
+/// Export as QuickBooks Online's journal entry CSV: one row per line, the
+/// rows of an entry sharing its journal number.
+pub fn export_journal_csv(accounts: &[Account], transactions: &[ValidatedTransaction]) -> String {
+    let names = account_names(accounts);
+    let mut out = String::from("Journal No,Journal Date,Account,Debits,Credits,Description,Memo\n");
+    for (i, tx) in transactions.iter().enumerate() {
+        let number = tx.id.unwrap_or(i as i64 + 1).to_string();
+        let date = tx.date.format("%m/%d/%Y").to_string();
+        let memo = tx.memo.as_deref().map(field).unwrap_or_default();
+        for line in &tx.lines {
+            let account = names
+                .get(&line.account_id.0)
+                .cloned()
+                .unwrap_or_else(|| format!("Account {}", line.account_id.0));
+            let description = line
+                .memo
+                .as_deref()
+                .filter(|m| !m.trim().is_empty())
+                .unwrap_or(&tx.description);
+            let money = |m: Money| {
+                if m.is_zero() {
+                    String::new()
+                } else {
+                    amount(m)
+                }
+            };
+            let fields = [
+                number.clone(),
+                date.clone(),
+                account,
+                money(line.debit),
+                money(line.credit),
+                field(description),
+                memo.clone(),
+            ];
+            let fields: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+            out.push_str(&fields.join(","));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TransactionLine, UnvalidatedTransaction};
+    use chrono::NaiveDate;
+
+    fn account(id: i64, code: &str, name: &str, account_type: AccountType) -> Account {
+        Account {
+            id: Some(AccountId(id)),
+            code: code.to_string(),
+            name: name.to_string(),
+            account_type,
+            is_archetype: false,
+            is_archived: false,
+            schedule_c_line: None,
+            deductible_pct: 100,
+        }
+    }
+
+    fn sample() -> (Vec<Account>, Vec<ValidatedTransaction>) {
+        let accounts = vec![
+            account(1, "1000", "Checking", AccountType::Asset),
+            account(2, "1020", "Accounts Receivable", AccountType::Asset),
+            account(3, "2000", "Credit Card", AccountType::Liability),
+            account(4, "5020", "Meals: \"Client\"", AccountType::Expense),
+        ];
+        let mut tx = ValidatedTransaction::validate(UnvalidatedTransaction {
+            date: NaiveDate::from_ymd_opt(2026, 3, 1).unwrap(),
+            description: "Lunch,\twith client".to_string(),
+            lines: vec![
+                TransactionLine::debit(AccountId(4), Money::from_cents(4250), None),
+                TransactionLine::credit(
+                    AccountId(3),
+                    Money::from_cents(4250),
+                    Some("Visa".to_string()),
+                ),
+            ],
+            memo: Some("Kickoff".to_string()),
+        })
+        .unwrap();
+        tx.id = Some(7);
+        (accounts, vec![tx])
+    }
+
+    #[test]
+    fn iif_lists_accounts_and_journal_entries() {
+        let (accounts, transactions) = sample();
+        let out = export_iif(&accounts, &transactions);
+
+        assert!(out.contains("ACCNT\tChecking\tBANK\t1000\t\n"));
+        assert!(out.contains("ACCNT\tAccounts Receivable\tAR\t1020\t\n"));
+        assert!(out.contains("ACCNT\tCredit Card\tCCARD\t2000\t\n"));
+        assert!(out.contains("ACCNT\tMeals- 'Client'\tEXP\t5020\t\n"));
+        assert!(out.contains(
+            "TRNS\t\tGENERAL JOURNAL\t03/01/2026\tMeals- 'Client'\t42.50\t7\tLunch, with client\n\
+             SPL\t\tGENERAL JOURNAL\t03/01/2026\tCredit Card\t-42.50\t7\tVisa\n\
+             ENDTRNS\n"
+        ));
+    }
+
+    #[test]
+    fn journal_csv_has_a_row_per_line() {
+        let (accounts, transactions) = sample();
+        let out = export_journal_csv(&accounts, &transactions);
+        let rows: Vec<&str> = out.lines().collect();
+
+        assert_eq!(
+            rows[0],
+            "Journal No,Journal Date,Account,Debits,Credits,Description,Memo"
+        );
+        assert_eq!(
+            rows[1],
+            "7,03/01/2026,Meals- 'Client',42.50,,\"Lunch, with client\",Kickoff"
+        );
+        assert_eq!(rows[2], "7,03/01/2026,Credit Card,,42.50,Visa,Kickoff");
+        assert_eq!(rows.len(), 3);
+    }
+}
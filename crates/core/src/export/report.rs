@@ -0,0 +1,209 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Money;
+
+/// File format a report can be exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportFormat {
+    Csv,
+    Json,
+    Pdf,
+}
+
+impl ReportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ReportFormat::Csv => "csv",
+            ReportFormat::Json => "json",
+            ReportFormat::Pdf => "pdf",
+        }
+    }
+}
+
+/// How a row is set apart when the report is printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowStyle {
+    /// A section title such as "Income"; only the first cell is used.
+    Heading,
+    Detail,
+    Subtotal,
+    Total,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReportCell {
+    Text(String),
+    Amount(Money),
+    Empty,
+}
+
+impl ReportCell {
+    pub fn text(s: impl Into<String>) -> Self {
+        ReportCell::Text(s.into())
+    }
+
+    /// The cell as written to a CSV: amounts as plain decimals, without a
+    /// currency sign, so spreadsheets read them as numbers.
+    pub fn plain(&self) -> String {
+        match self {
+            ReportCell::Text(s) => s.clone(),
+            ReportCell::Amount(m) => format!("{:.2}", m.as_decimal()),
+            ReportCell::Empty => String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportRow {
+    pub style: RowStyle,
+    pub cells: Vec<ReportCell>,
+}
+
+impl ReportRow {
+    pub fn heading(title: impl Into<String>) -> Self {
+        ReportRow {
+            style: RowStyle::Heading,
+            cells: vec![ReportCell::text(title)],
+        }
+    }
+
+    pub fn detail(cells: Vec<ReportCell>) -> Self {
+        ReportRow {
+            style: RowStyle::Detail,
+            cells,
+        }
+    }
+
+    pub fn subtotal(cells: Vec<ReportCell>) -> Self {
+        ReportRow {
+            style: RowStyle::Subtotal,
+            cells,
+        }
+    }
+
+    pub fn total(cells: Vec<ReportCell>) -> Self {
+        ReportRow {
+            style: RowStyle::Total,
+            cells,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportColumn {
+    pub label: String,
+    /// Right-aligned when printed.
+    pub numeric: bool,
+}
+
+impl ReportColumn {
+    pub fn text(label: impl Into<String>) -> Self {
+        ReportColumn {
+            label: label.into(),
+            numeric: false,
+        }
+    }
+
+    pub fn amount(label: impl Into<String>) -> Self {
+        ReportColumn {
+            label: label.into(),
+            numeric: true,
+        }
+    }
+}
+
+/// A report laid out as rows and columns, ready to write as CSV or print.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportTable {
+    pub title: String,
+    /// The period or date the report covers, and anything else worth
+    /// printing under the title.
+    pub subtitle: String,
+    pub columns: Vec<ReportColumn>,
+    pub rows: Vec<ReportRow>,
+}
+
+impl ReportTable {
+    pub fn new(title: impl Into<String>, subtitle: impl Into<String>) -> Self {
+        ReportTable {
+            title: title.into(),
+            subtitle: subtitle.into(),
+            columns: Vec::new(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn columns(mut self, columns: Vec<ReportColumn>) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    pub fn push(&mut self, row: ReportRow) {
+        self.rows.push(row);
+    }
+
+    /// A header line with the column labels, then one line per row. Short
+    /// rows are padded so every line has the same number of fields.
+    pub fn to_csv(&self) -> String {
+        let width = self.columns.len();
+        let mut out = String::new();
+        let header: Vec<String> = self.columns.iter().map(|c| csv_field(&c.label)).collect();
+        out.push_str(&header.join(","));
+        out.push('\n');
+        for row in &self.rows {
+            let mut fields: Vec<String> = row.cells.iter().map(|c| csv_field(&c.plain())).collect();
+            if fields.len() < width {
+                fields.resize(width, String::new());
+            }
+            out.push_str(&fields.join(","));
+            out.push('\n');
+        }
+        out
+    }
+}
+
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> ReportTable {
+        let mut table =
+            ReportTable::new("Profit and Loss", "2026-01-01 to 2026-03-31").columns(vec![
+                ReportColumn::text("Account"),
+                ReportColumn::amount("Amount"),
+            ]);
+        table.push(ReportRow::heading("Income"));
+        table.push(ReportRow::detail(vec![
+            ReportCell::text("4000 Services, consulting"),
+            ReportCell::Amount(Money::from_cents(123_456)),
+        ]));
+        table.push(ReportRow::total(vec![
+            ReportCell::text("Net income"),
+            ReportCell::Amount(Money::from_cents(-5)),
+        ]));
+        table
+    }
+
+    #[test]
+    fn csv_pads_rows_and_quotes_fields() {
+        assert_eq!(
+            sample().to_csv(),
+            "Account,Amount\nIncome,\n\"4000 Services, consulting\",1234.56\nNet income,-0.05\n"
+        );
+    }
+
+    #[test]
+    fn empty_cells_are_blank() {
+        assert_eq!(ReportCell::Empty.plain(), "");
+        assert_eq!(ReportCell::Amount(Money::zero()).plain(), "0.00");
+    }
+}
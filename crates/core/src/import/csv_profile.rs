@@ -0,0 +1,80 @@
+//! How a bank's CSV export is laid out, saved as a named profile.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsvColumnMapping {
+    pub date_column: Option<usize>,
+    pub description_column: Option<usize>,
+    pub amount_column: Option<usize>,
+    pub debit_column: Option<usize>,
+    pub credit_column: Option<usize>,
+    pub memo_column: Option<usize>,
+    pub date_format: String,
+}
+
+impl Default for CsvColumnMapping {
+    fn default() -> Self {
+        Self {
+            date_column: None,
+            description_column: None,
+            amount_column: None,
+            debit_column: None,
+            credit_column: None,
+            memo_column: None,
+            date_format: "%Y-%m-%d".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsvImportProfile {
+    pub id: Option<i64>,
+    pub name: String,
+    pub mapping: CsvColumnMapping,
+    pub has_header: bool,
+    pub delimiter: String,
+}
+
+impl Default for CsvImportProfile {
+    fn default() -> Self {
+        Self {
+            id: None,
+            name: "Unnamed Profile".to_string(),
+            mapping: CsvColumnMapping::default(),
+            has_header: true,
+            delimiter: ",".to_string(),
+        }
+    }
+}
+
+impl CsvImportProfile {
+    /// Check a profile can read a file before it is saved: it needs a name,
+    /// a one-byte delimiter, a date column and format, and either an amount
+    /// column or both debit and credit columns.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("profile name is required".into());
+        }
+        if self.delimiter.len() != 1 {
+            return Err(format!(
+                "profile '{}' needs a single-character delimiter",
+                self.name
+            ));
+        }
+        let m = &self.mapping;
+        if m.date_column.is_none() {
+            return Err(format!("profile '{}' has no date column", self.name));
+        }
+        if m.date_format.trim().is_empty() {
+            return Err(format!("profile '{}' has no date format", self.name));
+        }
+        if m.amount_column.is_none() && (m.debit_column.is_none() || m.credit_column.is_none()) {
+            return Err(format!(
+                "profile '{}' needs an amount column or both debit and credit columns",
+                self.name
+            ));
+        }
+        Ok(())
+    }
+}
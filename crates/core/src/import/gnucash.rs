@@ -0,0 +1,286 @@
+//! The parts of a GnuCash book aequi reads, whichever form it was stored
+//! in. The XML form is parsed by the import crate and the SQLite form by
+//! the storage crate, both into these types.
+
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+
+use crate::AccountType;
+
+#[derive(Debug, thiserror::Error)]
+pub enum GnucashError {
+    #[error("Failed to read GnuCash file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid GnuCash XML: {0}")]
+    Xml(String),
+    #[error("Missing required field: {0}")]
+    MissingField(String),
+    #[error("Invalid date: {0}")]
+    InvalidDate(String),
+    #[error("Invalid amount: {0}")]
+    InvalidAmount(String),
+}
+
+/// A GnuCash commodity: a currency (`CURRENCY` / `USD`) or a security.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Commodity {
+    pub space: String,
+    pub id: String,
+}
+
+impl Commodity {
+    /// Whether this is the currency with ISO code `code`. Older books use
+    /// the `ISO4217` namespace for currencies.
+    pub fn is_currency(&self, code: &str) -> bool {
+        (self.space == "CURRENCY" || self.space == "ISO4217") && self.id.eq_ignore_ascii_case(code)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GnucashAccount {
+    pub guid: String,
+    pub name: String,
+    /// GnuCash account type, e.g. `BANK`, `EXPENSE`, `ROOT`.
+    pub account_type: String,
+    pub parent: Option<String>,
+    pub commodity: Commodity,
+    pub code: Option<String>,
+    pub description: Option<String>,
+    pub placeholder: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct GnucashSplit {
+    pub account: String,
+    /// Amount in the transaction's currency, in cents. Positive is a debit.
+    pub value_cents: i64,
+    /// Amount in the account's commodity, in hundredths.
+    pub quantity_cents: i64,
+    pub memo: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GnucashTransaction {
+    pub guid: String,
+    pub date: NaiveDate,
+    pub description: String,
+    pub num: Option<String>,
+    pub notes: Option<String>,
+    pub currency: Commodity,
+    pub splits: Vec<GnucashSplit>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GnucashBook {
+    pub accounts: Vec<GnucashAccount>,
+    pub transactions: Vec<GnucashTransaction>,
+}
+
+impl GnucashBook {
+    pub fn account(&self, guid: &str) -> Option<&GnucashAccount> {
+        self.accounts.iter().find(|a| a.guid == guid)
+    }
+
+    /// Colon-separated path below the root, e.g. `Expenses:Auto:Fuel`.
+    pub fn account_path(&self, guid: &str) -> String {
+        let by_guid: HashMap<&str, &GnucashAccount> =
+            self.accounts.iter().map(|a| (a.guid.as_str(), a)).collect();
+        let mut parts = Vec::new();
+        let mut current = by_guid.get(guid).copied();
+        while let Some(account) = current {
+            if account.account_type == "ROOT" || parts.len() > by_guid.len() {
+                break;
+            }
+            parts.push(account.name.as_str());
+            current = account
+                .parent
+                .as_deref()
+                .and_then(|p| by_guid.get(p).copied());
+        }
+        parts.reverse();
+        parts.join(":")
+    }
+
+    /// Each split's amount in the base currency, in cents, or why the
+    /// transaction can't be brought into it.
+    ///
+    /// A transaction in the base currency uses split values as they are. A
+    /// transaction in another currency is converted at the rate implied by
+    /// its splits to base-currency accounts, whose quantities are already
+    /// in the base currency.
+    pub fn base_amounts(
+        &self,
+        tx: &GnucashTransaction,
+        base_currency: &str,
+    ) -> Result<Vec<i64>, String> {
+        let mut amounts: Vec<i64> = tx.splits.iter().map(|s| s.value_cents).collect();
+        if !tx.currency.is_currency(base_currency) {
+            let in_base: Vec<bool> = tx
+                .splits
+                .iter()
+                .map(|s| {
+                    self.account(&s.account)
+                        .is_some_and(|a| a.commodity.is_currency(base_currency))
+                })
+                .collect();
+            let (base_total, value_total) = tx
+                .splits
+                .iter()
+                .zip(&in_base)
+                .filter(|(_, base)| **base)
+                .fold((0i128, 0i128), |(q, v), (s, _)| {
+                    (q + s.quantity_cents as i128, v + s.value_cents as i128)
+                });
+            if value_total == 0 {
+                return Err(format!(
+                    "{} ({}): {} amounts can't be converted to {base_currency}",
+                    tx.description, tx.date, tx.currency.id
+                ));
+            }
+            for (i, split) in tx.splits.iter().enumerate() {
+                amounts[i] = if in_base[i] {
+                    split.quantity_cents
+                } else {
+                    round_div(split.value_cents as i128 * base_total, value_total) as i64
+                };
+            }
+            // Rounding can leave a cent over; it goes on the largest
+            // converted split.
+            let residual: i64 = amounts.iter().sum();
+            if let Some(i) = (0..amounts.len())
+                .filter(|i| !in_base[*i])
+                .max_by_key(|i| amounts[*i].abs())
+            {
+                if residual.abs() <= tx.splits.len() as i64 {
+                    amounts[i] -= residual;
+                }
+            }
+        }
+        if amounts.iter().sum::<i64>() != 0 {
+            return Err(format!(
+                "{} ({}): doesn't balance in {base_currency}",
+                tx.description, tx.date
+            ));
+        }
+        Ok(amounts)
+    }
+}
+
+/// aequi account type for a GnuCash account type. `None` for the root and
+/// for types aequi has no equivalent of.
+pub fn account_type(gnucash_type: &str) -> Option<AccountType> {
+    match gnucash_type {
+        "BANK" | "CASH" | "ASSET" | "STOCK" | "MUTUAL" | "RECEIVABLE" => Some(AccountType::Asset),
+        "CREDIT" | "LIABILITY" | "PAYABLE" => Some(AccountType::Liability),
+        "EQUITY" | "TRADING" => Some(AccountType::Equity),
+        "INCOME" => Some(AccountType::Income),
+        "EXPENSE" => Some(AccountType::Expense),
+        _ => None,
+    }
+}
+
+/// Date part of a GnuCash timestamp: `2026-03-01 10:59:00 +0000` in XML,
+/// `2026-03-01 10:59:00` or `20260301105900` in SQLite.
+pub fn parse_date(s: &str) -> Result<NaiveDate, GnucashError> {
+    let s = s.trim();
+    let parsed = match s.get(..10) {
+        Some(day) if day.contains('-') => NaiveDate::parse_from_str(day, "%Y-%m-%d"),
+        _ => NaiveDate::parse_from_str(s.get(..8).unwrap_or(s), "%Y%m%d"),
+    };
+    parsed.map_err(|_| GnucashError::InvalidDate(s.to_string()))
+}
+
+/// Cents in a GnuCash rational such as `-4250/100` or `17/1`, rounded half
+/// away from zero.
+pub fn rational_cents(s: &str) -> Result<i64, GnucashError> {
+    let invalid = || GnucashError::InvalidAmount(s.to_string());
+    let (num, denom) = s.trim().split_once('/').unwrap_or((s.trim(), "1"));
+    let num: i128 = num.trim().parse().map_err(|_| invalid())?;
+    let denom: i128 = denom.trim().parse().map_err(|_| invalid())?;
+    if denom <= 0 {
+        return Err(invalid());
+    }
+    i64::try_from(round_div(num * 100, denom)).map_err(|_| invalid())
+}
+
+fn round_div(num: i128, denom: i128) -> i128 {
+    let (num, denom) = if denom < 0 {
+        (-num, -denom)
+    } else {
+        (num, denom)
+    };
+    let q = num / denom;
+    let r = num % denom;
+    if 2 * r.abs() >= denom {
+        q + num.signum()
+    } else {
+        q
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rationals_and_dates() {
+        assert_eq!(rational_cents("-4250/100").unwrap(), -4250);
+        assert_eq!(rational_cents("17/1").unwrap(), 1700);
+        assert_eq!(rational_cents("12345/1000").unwrap(), 1235);
+        assert_eq!(rational_cents("-12345/1000").unwrap(), -1235);
+        assert!(rational_cents("1/0").is_err());
+        assert_eq!(
+            parse_date("20260301105900").unwrap(),
+            NaiveDate::from_ymd_opt(2026, 3, 1).unwrap()
+        );
+        assert_eq!(account_type("CREDIT"), Some(AccountType::Liability));
+        assert_eq!(account_type("ROOT"), None);
+    }
+
+    #[test]
+    fn converts_foreign_currency_through_base_split() {
+        let usd = Commodity {
+            space: "CURRENCY".to_string(),
+            id: "USD".to_string(),
+        };
+        let eur = Commodity {
+            space: "CURRENCY".to_string(),
+            id: "EUR".to_string(),
+        };
+        let account = |guid: &str, commodity: &Commodity| GnucashAccount {
+            guid: guid.to_string(),
+            name: guid.to_string(),
+            account_type: "EXPENSE".to_string(),
+            parent: None,
+            commodity: commodity.clone(),
+            code: None,
+            description: None,
+            placeholder: false,
+        };
+        let split = |account: &str, value: i64, quantity: i64| GnucashSplit {
+            account: account.to_string(),
+            value_cents: value,
+            quantity_cents: quantity,
+            memo: None,
+        };
+        let book = GnucashBook {
+            accounts: vec![account("hotel", &eur), account("card", &usd)],
+            transactions: vec![],
+        };
+        // €100 hotel paid with a card billed $108.33.
+        let mut tx = GnucashTransaction {
+            guid: "t".to_string(),
+            date: NaiveDate::from_ymd_opt(2026, 5, 1).unwrap(),
+            description: "Hotel".to_string(),
+            num: None,
+            notes: None,
+            currency: eur.clone(),
+            splits: vec![split("hotel", 10000, 10000), split("card", -10000, -10833)],
+        };
+        assert_eq!(book.base_amounts(&tx, "USD").unwrap(), vec![10833, -10833]);
+
+        tx.splits[1].account = "hotel".to_string();
+        assert!(book.base_amounts(&tx, "USD").is_err());
+    }
+}
@@ -1,6 +1,6 @@
 use chrono::NaiveDate;
 
-use crate::util::levenshtein_distance;
+use super::util::levenshtein_distance;
 
 #[derive(Debug, Clone)]
 pub struct MatchableTransaction {
@@ -0,0 +1,20 @@
+//! What importing shares with storage: CSV import profiles, categorization
+//! rules, transaction and receipt matching, and the GnuCash book model.
+//! Reading files in each format stays in the import crate.
+
+pub mod csv_profile;
+pub mod gnucash;
+pub mod match_engine;
+pub mod receipt_match;
+pub mod rules;
+mod util;
+
+pub use csv_profile::{CsvColumnMapping, CsvImportProfile};
+pub use match_engine::{AutoMatchEngine, MatchResult, MatchType, MatchableTransaction};
+pub use receipt_match::{
+    suggest_receipt_matches, MatchableReceipt, ReceiptLinkTarget, ReceiptMatchCandidate,
+    ReceiptMatchSuggestion,
+};
+pub use rules::{
+    CategorizableTransaction, CategoryRule, CategoryRuleEngine, MatchType as RuleMatchType,
+};
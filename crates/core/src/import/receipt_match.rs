@@ -0,0 +1,336 @@
+use std::collections::HashSet;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use super::match_engine::{AutoMatchEngine, MatchableTransaction};
+
+/// What a receipt can be linked to: a posted ledger transaction, or a bank
+/// row still waiting in the import review queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "id", rename_all = "snake_case")]
+pub enum ReceiptLinkTarget {
+    Ledger(i64),
+    Imported(i64),
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchableReceipt {
+    pub id: i64,
+    pub date: NaiveDate,
+    pub vendor: String,
+    pub total_cents: i64,
+    /// Invoice or order number printed on the receipt.
+    pub reference: Option<String>,
+    /// Account code the receipt's payment method maps to, when the receipt
+    /// says how it was paid.
+    pub payment_account: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReceiptMatchCandidate {
+    pub target: ReceiptLinkTarget,
+    pub date: NaiveDate,
+    pub description: String,
+    /// Signed as stored; receipts are matched on the absolute value since
+    /// bank rows record purchases as outflows.
+    pub amount_cents: i64,
+    /// Codes of the accounts the transaction posts to; empty when not known
+    /// yet, as for imported bank rows.
+    pub account_codes: Vec<String>,
+}
+
+impl ReceiptMatchCandidate {
+    /// Whether the receipt could have been paid through this transaction: a
+    /// ledger entry for a card purchase can't be the one that paid a cash
+    /// receipt.
+    fn pays_from(&self, receipt: &MatchableReceipt) -> bool {
+        match &receipt.payment_account {
+            Some(code) if !self.account_codes.is_empty() => self.account_codes.contains(code),
+            _ => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReceiptMatchSuggestion {
+    pub receipt_id: i64,
+    pub target: ReceiptLinkTarget,
+    pub confidence: f32,
+    pub difference_cents: i64,
+}
+
+/// Confidence for a transaction whose description quotes the receipt's
+/// reference number. Split shipments and currency conversion mean the amount
+/// may differ, so this doesn't depend on the engine's tolerances.
+const REFERENCE_MATCH_CONFIDENCE: f32 = 0.99;
+
+/// References shorter than this are too likely to appear by chance in a bank
+/// description (store numbers, dates).
+const MIN_REFERENCE_LEN: usize = 5;
+
+/// Pair receipts with the transactions they most likely paid for, scored by
+/// `engine` on total, date and vendor/description similarity. A description
+/// containing the receipt's reference number is a match on its own. Ledger
+/// transactions that don't touch the account the receipt's payment method
+/// maps to are never suggested.
+///
+/// Each target is suggested for at most one receipt; when two receipts want
+/// the same transaction the more confident pairing wins.
+pub fn suggest_receipt_matches(
+    engine: &AutoMatchEngine,
+    receipts: &[MatchableReceipt],
+    candidates: &[ReceiptMatchCandidate],
+) -> Vec<ReceiptMatchSuggestion> {
+    let mut suggestions: Vec<ReceiptMatchSuggestion> = Vec::new();
+    let mut by_reference = HashSet::new();
+    for r in receipts {
+        let Some(reference) = r.reference.as_deref().map(alphanumeric) else {
+            continue;
+        };
+        if reference.len() < MIN_REFERENCE_LEN {
+            continue;
+        }
+        if let Some(c) = candidates
+            .iter()
+            .find(|c| c.pays_from(r) && alphanumeric(&c.description).contains(&reference))
+        {
+            by_reference.insert(r.id);
+            suggestions.push(ReceiptMatchSuggestion {
+                receipt_id: r.id,
+                target: c.target,
+                confidence: REFERENCE_MATCH_CONFIDENCE,
+                difference_cents: (r.total_cents.abs() - c.amount_cents.abs()).abs(),
+            });
+        }
+    }
+
+    // Candidates are addressed by index so ledger and imported ids cannot
+    // collide inside the engine.
+    let candidate_txs: Vec<MatchableTransaction> = candidates
+        .iter()
+        .enumerate()
+        .map(|(idx, c)| MatchableTransaction {
+            id: idx as i64,
+            date: c.date,
+            description: c.description.clone(),
+            amount_cents: c.amount_cents.abs(),
+        })
+        .collect();
+
+    for r in receipts.iter().filter(|r| !by_reference.contains(&r.id)) {
+        let receipt_tx = MatchableTransaction {
+            id: r.id,
+            date: r.date,
+            description: r.vendor.clone(),
+            amount_cents: r.total_cents.abs(),
+        };
+        let eligible: Vec<MatchableTransaction> = candidate_txs
+            .iter()
+            .filter(|tx| candidates[tx.id as usize].pays_from(r))
+            .cloned()
+            .collect();
+        suggestions.extend(
+            engine
+                .find_matches(&[receipt_tx], &eligible)
+                .into_iter()
+                .filter_map(|m| {
+                    let idx = m.matched_tx_id? as usize;
+                    Some(ReceiptMatchSuggestion {
+                        receipt_id: m.imported_tx_id,
+                        target: candidates[idx].target,
+                        confidence: m.confidence,
+                        difference_cents: m.difference_cents,
+                    })
+                }),
+        );
+    }
+
+    suggestions.sort_by(|a, b| {
+        b.confidence
+            .total_cmp(&a.confidence)
+            .then_with(|| a.receipt_id.cmp(&b.receipt_id))
+    });
+    let mut taken = HashSet::new();
+    suggestions.retain(|s| taken.insert(s.target));
+    suggestions
+}
+
+/// Uppercase letters and digits only, so "112-1234567" matches "1121234567".
+fn alphanumeric(s: &str) -> String {
+    s.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, d).unwrap()
+    }
+
+    fn receipt(id: i64, d: u32, vendor: &str, total: i64) -> MatchableReceipt {
+        MatchableReceipt {
+            id,
+            date: date(d),
+            vendor: vendor.to_string(),
+            total_cents: total,
+            reference: None,
+            payment_account: None,
+        }
+    }
+
+    fn candidate(
+        target: ReceiptLinkTarget,
+        d: u32,
+        desc: &str,
+        amount: i64,
+    ) -> ReceiptMatchCandidate {
+        ReceiptMatchCandidate {
+            target,
+            date: date(d),
+            description: desc.to_string(),
+            amount_cents: amount,
+            account_codes: Vec::new(),
+        }
+    }
+
+    fn engine() -> AutoMatchEngine {
+        AutoMatchEngine::new(3, 0.5, 0)
+    }
+
+    #[test]
+    fn matches_bank_outflow_by_absolute_amount() {
+        let receipts = [receipt(1, 5, "Office Depot", 4599)];
+        let candidates = [
+            candidate(
+                ReceiptLinkTarget::Imported(10),
+                5,
+                "OFFICE DEPOT #123",
+                -4599,
+            ),
+            candidate(ReceiptLinkTarget::Imported(11), 5, "COFFEE", -450),
+        ];
+        let out = suggest_receipt_matches(&engine(), &receipts, &candidates);
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].receipt_id, 1);
+        assert_eq!(out[0].target, ReceiptLinkTarget::Imported(10));
+        assert_eq!(out[0].confidence, 1.0);
+    }
+
+    #[test]
+    fn ledger_and_imported_ids_do_not_collide() {
+        let receipts = [receipt(1, 5, "Hardware Store", 2500)];
+        let candidates = [
+            candidate(ReceiptLinkTarget::Imported(7), 1, "UNRELATED", -9999),
+            candidate(ReceiptLinkTarget::Ledger(7), 6, "Hardware Store", 2500),
+        ];
+        let out = suggest_receipt_matches(&engine(), &receipts, &candidates);
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].target, ReceiptLinkTarget::Ledger(7));
+        assert!(out[0].confidence < 1.0);
+    }
+
+    #[test]
+    fn amount_outside_tolerance_is_not_suggested() {
+        let receipts = [receipt(1, 5, "Restaurant", 3000)];
+        // Bank charge includes a tip the receipt total does not.
+        let candidates = [candidate(
+            ReceiptLinkTarget::Imported(1),
+            6,
+            "RESTAURANT",
+            -3600,
+        )];
+        assert!(suggest_receipt_matches(&engine(), &receipts, &candidates).is_empty());
+
+        let lenient = AutoMatchEngine::new(3, 0.5, 1000);
+        let out = suggest_receipt_matches(&lenient, &receipts, &candidates);
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].difference_cents, 600);
+    }
+
+    #[test]
+    fn each_target_is_suggested_once() {
+        let receipts = [
+            receipt(1, 4, "Gas Station", 4000),
+            receipt(2, 5, "Gas Station", 4000),
+        ];
+        let candidates = [candidate(
+            ReceiptLinkTarget::Ledger(3),
+            5,
+            "GAS STATION",
+            4000,
+        )];
+        let out = suggest_receipt_matches(&engine(), &receipts, &candidates);
+        assert_eq!(out.len(), 1);
+        assert_eq!(
+            out[0].receipt_id, 2,
+            "same-day receipt is the better pairing"
+        );
+    }
+
+    #[test]
+    fn reference_in_description_matches_despite_amount() {
+        let mut order = receipt(1, 2, "Amazon.com", 3499);
+        order.reference = Some("112-1234567-1234567".to_string());
+        let candidates = [
+            candidate(ReceiptLinkTarget::Imported(4), 2, "AMAZON MKTPL", -3499),
+            // Second shipment of the same order, charged later.
+            candidate(
+                ReceiptLinkTarget::Imported(5),
+                9,
+                "AMZN Mktp US*112-1234567-1234567",
+                -1850,
+            ),
+        ];
+        let out = suggest_receipt_matches(&engine(), &[order], &candidates);
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].target, ReceiptLinkTarget::Imported(5));
+        assert_eq!(out[0].difference_cents, 1649);
+    }
+
+    #[test]
+    fn short_reference_is_ignored() {
+        let mut r = receipt(1, 5, "Cafe", 450);
+        r.reference = Some("12".to_string());
+        let candidates = [candidate(
+            ReceiptLinkTarget::Imported(1),
+            20,
+            "STORE 12 PURCHASE",
+            -9900,
+        )];
+        assert!(suggest_receipt_matches(&engine(), &[r], &candidates).is_empty());
+    }
+
+    #[test]
+    fn ledger_entry_from_another_account_is_skipped() {
+        let mut r = receipt(1, 5, "Corner Store", 1200);
+        r.payment_account = Some("1000".to_string());
+        let mut card = candidate(ReceiptLinkTarget::Ledger(1), 5, "Corner Store", 1200);
+        card.account_codes = vec!["5000".to_string(), "2000".to_string()];
+        let mut cash = candidate(ReceiptLinkTarget::Ledger(2), 6, "Corner Store", 1200);
+        cash.account_codes = vec!["5000".to_string(), "1000".to_string()];
+
+        let out = suggest_receipt_matches(&engine(), &[r.clone()], &[card.clone()]);
+        assert!(out.is_empty());
+        let out = suggest_receipt_matches(&engine(), &[r.clone()], &[card.clone(), cash]);
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].target, ReceiptLinkTarget::Ledger(2));
+
+        // Without a known payment method any entry may have paid for it.
+        r.payment_account = None;
+        let out = suggest_receipt_matches(&engine(), &[r], &[card]);
+        assert_eq!(out[0].target, ReceiptLinkTarget::Ledger(1));
+    }
+
+    #[test]
+    fn target_serializes_with_kind() {
+        let json = serde_json::to_string(&ReceiptLinkTarget::Imported(5)).unwrap();
+        assert_eq!(json, r#"{"kind":"imported","id":5}"#);
+        let back: ReceiptLinkTarget = serde_json::from_str(r#"{"kind":"ledger","id":9}"#).unwrap();
+        assert_eq!(back, ReceiptLinkTarget::Ledger(9));
+    }
+}
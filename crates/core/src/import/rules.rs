@@ -1,7 +1,7 @@
 use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 
-use crate::util::levenshtein_distance;
+use super::util::levenshtein_distance;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CategoryRule {
@@ -14,6 +14,45 @@ pub struct CategoryRule {
     pub amount_max_cents: Option<i64>,
 }
 
+impl CategoryRule {
+    /// Check a rule is usable before it is saved or imported: it needs a
+    /// name, pattern and account, a regex that compiles, a fuzzy threshold
+    /// within 0.0-1.0 and an amount range that isn't inverted.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("rule name is required".into());
+        }
+        if self.pattern.is_empty() {
+            return Err(format!("rule '{}' has an empty pattern", self.name));
+        }
+        if self.account_code.trim().is_empty() {
+            return Err(format!("rule '{}' has no account code", self.name));
+        }
+        match &self.match_type {
+            MatchType::Regex => {
+                regex::Regex::new(&self.pattern)
+                    .map_err(|e| format!("rule '{}' has an invalid regex: {e}", self.name))?;
+            }
+            MatchType::Fuzzy { threshold } if !(0.0..=1.0).contains(threshold) => {
+                return Err(format!(
+                    "rule '{}' has a fuzzy threshold outside 0.0-1.0",
+                    self.name
+                ));
+            }
+            _ => {}
+        }
+        if let (Some(min), Some(max)) = (self.amount_min_cents, self.amount_max_cents) {
+            if min > max {
+                return Err(format!(
+                    "rule '{}' has amount_min_cents greater than amount_max_cents",
+                    self.name
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 pub enum MatchType {
     #[default]
@@ -44,6 +83,18 @@ impl std::str::FromStr for MatchType {
     }
 }
 
+/// Inverse of `FromStr`; this is the form stored in the `categorization_rules` table.
+impl std::fmt::Display for MatchType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MatchType::Contains => write!(f, "contains"),
+            MatchType::Exact => write!(f, "exact"),
+            MatchType::Regex => write!(f, "regex"),
+            MatchType::Fuzzy { threshold } => write!(f, "fuzzy:{threshold}"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct CategorizableTransaction {
     pub date: NaiveDate,
@@ -54,6 +105,8 @@ pub struct CategorizableTransaction {
 
 /// Internal pairing of a rule with its precompiled regex (if applicable).
 struct CompiledRule {
+    /// Position in the rules the engine was built from.
+    index: usize,
     rule: CategoryRule,
     compiled_regex: Option<regex::Regex>,
 }
@@ -66,29 +119,25 @@ impl CategoryRuleEngine {
     pub fn new(rules: Vec<CategoryRule>) -> Self {
         let mut compiled: Vec<CompiledRule> = rules
             .into_iter()
-            .map(|rule| {
+            .enumerate()
+            .map(|(index, rule)| {
                 let compiled_regex = if let MatchType::Regex = &rule.match_type {
                     regex::Regex::new(&rule.pattern).ok()
                 } else {
                     None
                 };
                 CompiledRule {
+                    index,
                     rule,
                     compiled_regex,
                 }
             })
             .collect();
         // Highest priority first.
-        compiled.sort_by(|a, b| b.rule.priority.cmp(&a.rule.priority));
+        compiled.sort_by_key(|cr| std::cmp::Reverse(cr.rule.priority));
         Self { rules: compiled }
     }
 
-    pub fn from_toml(toml_content: &str) -> Result<Self, String> {
-        let rules: Vec<CategoryRule> =
-            toml::from_str(toml_content).map_err(|e| format!("Failed to parse TOML: {e}"))?;
-        Ok(Self::new(rules))
-    }
-
     pub fn find_matching_rule(&self, tx: &CategorizableTransaction) -> Option<&CategoryRule> {
         self.rules
             .iter()
@@ -96,6 +145,16 @@ impl CategoryRuleEngine {
             .map(|cr| &cr.rule)
     }
 
+    /// Like [`Self::find_matching_rule`], but returns the rule's position in
+    /// the list the engine was built from, so callers can map it back to a
+    /// stored row.
+    pub fn find_matching_index(&self, tx: &CategorizableTransaction) -> Option<usize> {
+        self.rules
+            .iter()
+            .find(|cr| self.rule_matches(cr, tx))
+            .map(|cr| cr.index)
+    }
+
     /// Returns indices + matched rules for all transactions, in order.
     pub fn apply_rules<'a>(
         &'a self,
@@ -176,6 +235,23 @@ mod tests {
         }
     }
 
+    #[test]
+    fn matching_index_refers_to_input_order() {
+        let engine = CategoryRuleEngine::new(vec![
+            make_rule("coffee", MatchType::Contains, "5000", 1),
+            make_rule("starbucks", MatchType::Contains, "5010", 9),
+        ]);
+        assert_eq!(
+            engine.find_matching_index(&make_tx("STARBUCKS COFFEE", -450)),
+            Some(1)
+        );
+        assert_eq!(
+            engine.find_matching_index(&make_tx("COFFEE BEAN", -450)),
+            Some(0)
+        );
+        assert_eq!(engine.find_matching_index(&make_tx("RENT", -450)), None);
+    }
+
     #[test]
     fn contains_match_case_insensitive() {
         let engine = CategoryRuleEngine::new(vec![make_rule(
@@ -314,6 +390,37 @@ mod tests {
         assert_eq!(results[1].0, 2);
     }
 
+    #[test]
+    fn match_type_display_roundtrips_through_from_str() {
+        for mt in [
+            MatchType::Contains,
+            MatchType::Exact,
+            MatchType::Regex,
+            MatchType::Fuzzy { threshold: 0.75 },
+        ] {
+            assert_eq!(mt.to_string().parse::<MatchType>().unwrap(), mt);
+        }
+    }
+
+    #[test]
+    fn validate_rejects_unusable_rules() {
+        assert!(make_rule("coffee", MatchType::Contains, "5020", 0)
+            .validate()
+            .is_ok());
+        let err = make_rule("(unclosed", MatchType::Regex, "5020", 0)
+            .validate()
+            .unwrap_err();
+        assert!(err.contains("invalid regex"));
+        assert!(
+            make_rule("x", MatchType::Fuzzy { threshold: 1.5 }, "5020", 0)
+                .validate()
+                .is_err()
+        );
+        assert!(make_rule("x", MatchType::Contains, " ", 0)
+            .validate()
+            .is_err());
+    }
+
     #[test]
     fn fuzzy_score_identical_is_one() {
         assert_eq!(fuzzy_score("starbucks", "starbucks"), 1.0);
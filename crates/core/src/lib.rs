@@ -1,17 +1,27 @@
 pub mod account;
+pub mod deductibility;
 pub mod export;
+pub mod import;
 pub mod invoice;
+pub mod locale;
 pub mod money;
+pub mod payment_accounts;
 pub mod period;
 pub mod tax;
 pub mod transaction;
 
-pub use account::{Account, AccountId, AccountType, LedgerError, DEFAULT_ACCOUNTS};
+pub use account::{
+    Account, AccountId, AccountType, ChartTemplate, LedgerError, DEFAULT_ACCOUNTS,
+    EQUIPMENT_EXPENSE_CODE, OPENING_BALANCE_EQUITY_CODE, OWNER_DRAW_CODE,
+};
+pub use deductibility::Deductibility;
 pub use invoice::{
     check_1099_threshold, compute_ytd_payments, Contact, ContactId, ContactType, Discount, Invoice,
     InvoiceError, InvoiceId, InvoiceLine, InvoiceStatus, Payment, TaxLine,
 };
+pub use locale::{Locale, MoneyFormat};
 pub use money::Money;
+pub use payment_accounts::PaymentAccountMap;
 pub use period::{DateRange, FiscalYear, Quarter};
 pub use tax::{
     compute_quarterly_estimate, LedgerSnapshot, QuarterlyEstimate, ScheduleCLine, ScheduleCPreview,
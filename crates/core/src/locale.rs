@@ -0,0 +1,295 @@
+//! Showing amounts the way the user's locale writes them.
+//!
+//! Amounts travel as cents everywhere; this is only for the text shown next
+//! to them. `Money`'s `Display` stays `$1234.56` for logs and exports that
+//! need a fixed form.
+
+use crate::money::Money;
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A BCP 47 language tag cut down to what number formatting needs:
+/// language and optional region, e.g. `de-DE`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Locale {
+    language: String,
+    region: Option<String>,
+}
+
+impl Locale {
+    /// Parse `ll` or `ll-RR` (an underscore works too). `None` for
+    /// anything else.
+    pub fn parse(tag: &str) -> Option<Self> {
+        let mut parts = tag.trim().split(['-', '_']);
+        let language = parts.next()?;
+        let region = parts.next();
+        if parts.next().is_some()
+            || !(2..=3).contains(&language.len())
+            || !language.chars().all(|c| c.is_ascii_alphabetic())
+        {
+            return None;
+        }
+        if let Some(r) = region {
+            if r.len() != 2 || !r.chars().all(|c| c.is_ascii_alphabetic()) {
+                return None;
+            }
+        }
+        Some(Locale {
+            language: language.to_ascii_lowercase(),
+            region: region.map(|r| r.to_ascii_uppercase()),
+        })
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    pub fn region(&self) -> Option<&str> {
+        self.region.as_deref()
+    }
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Locale {
+            language: "en".to_string(),
+            region: Some("US".to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.region {
+            Some(region) => write!(f, "{}-{}", self.language, region),
+            None => f.write_str(&self.language),
+        }
+    }
+}
+
+/// Where the currency symbol goes relative to the number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SymbolPosition {
+    /// `$1,234.56`
+    Before,
+    /// `€ 1.234,56`
+    BeforeSpaced,
+    /// `1.234,56 €`
+    After,
+}
+
+/// Formats amounts in one currency for one locale.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoneyFormat {
+    locale: Locale,
+    currency: String,
+}
+
+impl MoneyFormat {
+    /// `currency` is an ISO 4217 code, e.g. `EUR`.
+    pub fn new(locale: Locale, currency: &str) -> Self {
+        MoneyFormat {
+            locale,
+            currency: currency.to_ascii_uppercase(),
+        }
+    }
+
+    pub fn locale(&self) -> &Locale {
+        &self.locale
+    }
+
+    pub fn currency(&self) -> &str {
+        &self.currency
+    }
+
+    /// `amount` with the currency symbol, e.g. `-1.234,56 €` for de-DE.
+    pub fn format(&self, amount: Money) -> String {
+        let number = self.number(amount.as_decimal().abs(), self.currency_decimals());
+        let symbol = self.symbol();
+        let sign = sign(amount.as_decimal(), &number);
+        match self.symbol_position() {
+            SymbolPosition::Before => format!("{sign}{symbol}{number}"),
+            SymbolPosition::BeforeSpaced => format!("{symbol}\u{a0}{sign}{number}"),
+            SymbolPosition::After => format!("{sign}{number}\u{a0}{symbol}"),
+        }
+    }
+
+    /// [`format`](Self::format) for an amount in cents.
+    pub fn format_cents(&self, cents: i64) -> String {
+        self.format(Money::from_cents(cents))
+    }
+
+    /// `value` with the locale's separators and `decimals` places, no
+    /// currency symbol, e.g. `1.234,5` for de-DE.
+    pub fn format_number(&self, value: Decimal, decimals: u32) -> String {
+        let number = self.number(value.abs(), decimals);
+        format!("{}{number}", sign(value, &number))
+    }
+
+    /// An unsigned number, rounded half away from zero.
+    fn number(&self, value: Decimal, decimals: u32) -> String {
+        let (group, decimal) = self.separators();
+        let rounded =
+            value.round_dp_with_strategy(decimals, RoundingStrategy::MidpointAwayFromZero);
+        let digits = format!("{:.*}", decimals as usize, rounded);
+        let (whole, fraction) = digits.split_once('.').unwrap_or((&digits, ""));
+
+        let mut out = String::new();
+        for (i, c) in whole.chars().enumerate() {
+            if i > 0 && (whole.len() - i) % 3 == 0 {
+                out.push_str(group);
+            }
+            out.push(c);
+        }
+        if !fraction.is_empty() {
+            out.push(decimal);
+            out.push_str(fraction);
+        }
+        out
+    }
+
+    /// Grouping and decimal separators.
+    fn separators(&self) -> (&'static str, char) {
+        if self.locale.region() == Some("CH") && self.locale.language() != "en" {
+            return ("\u{2019}", '.');
+        }
+        match self.locale.language() {
+            "de" | "es" | "it" | "nl" | "pt" | "da" | "id" | "tr" | "el" | "ro" | "hr" | "sl"
+            | "sr" => (".", ','),
+            "fr" => ("\u{202f}", ','),
+            "sv" | "nb" | "nn" | "no" | "fi" | "pl" | "cs" | "sk" | "ru" | "uk" | "hu" | "lt"
+            | "lv" | "et" | "bg" => ("\u{a0}", ','),
+            _ => (",", '.'),
+        }
+    }
+
+    fn symbol_position(&self) -> SymbolPosition {
+        if self.locale.region() == Some("CH") {
+            return SymbolPosition::BeforeSpaced;
+        }
+        match self.locale.language() {
+            "nl" => SymbolPosition::BeforeSpaced,
+            "de" | "es" | "it" | "pt" | "da" | "fr" | "sv" | "nb" | "nn" | "no" | "fi" | "pl"
+            | "cs" | "sk" | "ru" | "uk" | "hu" | "lt" | "lv" | "et" | "bg" | "el" | "ro" | "hr"
+            | "sl" | "sr" | "tr" => SymbolPosition::After,
+            _ => SymbolPosition::Before,
+        }
+    }
+
+    /// The currency's symbol; dollars are qualified away from home so
+    /// `$` stays unambiguous.
+    fn symbol(&self) -> String {
+        let region = self.locale.region();
+        let symbol = match self.currency.as_str() {
+            "USD" if matches!(region, None | Some("US")) => "$",
+            "USD" => "US$",
+            "CAD" if region == Some("CA") => "$",
+            "CAD" => "CA$",
+            "AUD" if region == Some("AU") => "$",
+            "AUD" => "A$",
+            "NZD" if region == Some("NZ") => "$",
+            "NZD" => "NZ$",
+            "EUR" => "€",
+            "GBP" => "£",
+            "JPY" => "¥",
+            "INR" => "₹",
+            "KRW" => "₩",
+            other => other,
+        };
+        symbol.to_string()
+    }
+
+    /// Minor units the currency is shown with.
+    fn currency_decimals(&self) -> u32 {
+        match self.currency.as_str() {
+            "JPY" | "KRW" => 0,
+            _ => 2,
+        }
+    }
+}
+
+/// A minus sign for negative values that don't round to zero.
+fn sign(value: Decimal, rounded: &str) -> &'static str {
+    if value.is_sign_negative() && rounded.chars().any(|c| c.is_ascii_digit() && c != '0') {
+        "-"
+    } else {
+        ""
+    }
+}
+
+impl Default for MoneyFormat {
+    fn default() -> Self {
+        MoneyFormat::new(Locale::default(), "USD")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(locale: &str, currency: &str) -> MoneyFormat {
+        MoneyFormat::new(Locale::parse(locale).unwrap(), currency)
+    }
+
+    #[test]
+    fn parse_locale() {
+        assert_eq!(Locale::parse("de_de").unwrap().to_string(), "de-DE");
+        assert_eq!(Locale::parse("fr").unwrap().to_string(), "fr");
+        assert!(Locale::parse("").is_none());
+        assert!(Locale::parse("english").is_none());
+        assert!(Locale::parse("en-US-x").is_none());
+        assert!(Locale::parse("en-1").is_none());
+    }
+
+    #[test]
+    fn us_dollars() {
+        let fmt = MoneyFormat::default();
+        assert_eq!(fmt.format_cents(123_456), "$1,234.56");
+        assert_eq!(fmt.format_cents(-500), "-$5.00");
+        assert_eq!(fmt.format_cents(0), "$0.00");
+        assert_eq!(fmt.format_cents(12_345_678_900), "$123,456,789.00");
+    }
+
+    #[test]
+    fn european_locales() {
+        assert_eq!(
+            format("de-DE", "EUR").format_cents(123_456),
+            "1.234,56\u{a0}€"
+        );
+        assert_eq!(
+            format("de-DE", "EUR").format_cents(-123_456),
+            "-1.234,56\u{a0}€"
+        );
+        assert_eq!(
+            format("nl-NL", "EUR").format_cents(-123_456),
+            "€\u{a0}-1.234,56"
+        );
+        assert_eq!(
+            format("fr-FR", "EUR").format_cents(123_456),
+            "1\u{202f}234,56\u{a0}€"
+        );
+        assert_eq!(format("en-IE", "EUR").format_cents(123_456), "€1,234.56");
+        assert_eq!(
+            format("de-CH", "CHF").format_cents(123_456),
+            "CHF\u{a0}1\u{2019}234.56"
+        );
+    }
+
+    #[test]
+    fn symbols_away_from_home() {
+        assert_eq!(format("en-CA", "CAD").format_cents(1000), "$10.00");
+        assert_eq!(format("en-CA", "USD").format_cents(1000), "US$10.00");
+        assert_eq!(format("en-GB", "GBP").format_cents(1000), "£10.00");
+        assert_eq!(format("en-US", "SEK").format_cents(1000), "SEK10.00");
+        assert_eq!(format("ja-JP", "JPY").format_cents(123_456), "¥1,235");
+    }
+
+    #[test]
+    fn format_number() {
+        let fmt = format("de-DE", "EUR");
+        assert_eq!(fmt.format_number(Decimal::new(12345, 1), 1), "1.234,5");
+        assert_eq!(fmt.format_number(Decimal::new(-1234, 0), 0), "-1.234");
+        assert_eq!(fmt.format_number(Decimal::new(-1, 3), 2), "0,00");
+    }
+}
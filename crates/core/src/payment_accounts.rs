@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The account a receipt was paid from, by the payment method printed on it
+/// (`Visa`, `Amex`, `Cash`, ...). Card payments land on the credit card
+/// account and everything else on checking unless the user maps them
+/// elsewhere, e.g. cash to a petty cash account.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaymentAccountMap {
+    /// Payment method name to account code. Names compare case-insensitively.
+    pub methods: BTreeMap<String, String>,
+    /// Account for methods not listed and for receipts that don't say how
+    /// they were paid.
+    pub default_account: Option<String>,
+}
+
+impl Default for PaymentAccountMap {
+    fn default() -> Self {
+        let methods = [
+            ("Visa", "2000"),
+            ("Mastercard", "2000"),
+            ("Amex", "2000"),
+            ("Discover", "2000"),
+            ("Debit", "1000"),
+            ("Check", "1000"),
+            ("Cash", "1000"),
+        ]
+        .into_iter()
+        .map(|(method, code)| (method.to_string(), code.to_string()))
+        .collect();
+        PaymentAccountMap {
+            methods,
+            default_account: Some("1000".to_string()),
+        }
+    }
+}
+
+impl PaymentAccountMap {
+    /// The account mapped to `method` itself, ignoring the default.
+    pub fn mapped(&self, method: &str) -> Option<&str> {
+        let method = method.trim();
+        self.methods
+            .iter()
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case(method))
+            .map(|(_, code)| code.as_str())
+    }
+
+    /// The account to credit for a receipt paid with `method`, falling back
+    /// to the default account.
+    pub fn account_for(&self, method: Option<&str>) -> Option<&str> {
+        method
+            .and_then(|m| self.mapped(m))
+            .or(self.default_account.as_deref())
+    }
+
+    /// Every account code the map refers to.
+    pub fn account_codes(&self) -> impl Iterator<Item = &str> {
+        self.methods
+            .values()
+            .chain(self.default_account.as_ref())
+            .map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cards_go_to_credit_card_and_cash_to_checking() {
+        let map = PaymentAccountMap::default();
+        assert_eq!(map.account_for(Some("Visa")), Some("2000"));
+        assert_eq!(map.account_for(Some("AMEX")), Some("2000"));
+        assert_eq!(map.account_for(Some("Cash")), Some("1000"));
+    }
+
+    #[test]
+    fn unlisted_method_falls_back_to_default() {
+        let mut map = PaymentAccountMap::default();
+        map.methods.insert("Cash".into(), "1050".into());
+        assert_eq!(map.account_for(Some("cash")), Some("1050"));
+        assert_eq!(map.mapped("Apple Pay"), None);
+        assert_eq!(map.account_for(Some("Apple Pay")), Some("1000"));
+        assert_eq!(map.account_for(None), Some("1000"));
+
+        map.default_account = None;
+        assert_eq!(map.account_for(None), None);
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let map = PaymentAccountMap::default();
+        let json = serde_json::to_string(&map).unwrap();
+        let back: PaymentAccountMap = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, map);
+    }
+}
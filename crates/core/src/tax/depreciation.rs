@@ -0,0 +1,69 @@
+//! Depreciation of capitalized equipment.
+//!
+//! Straight line over the recovery period with the half-year convention:
+//! half a year's depreciation in the year the asset is placed in service,
+//! a full year's in each year after, and the rest in the year after the
+//! period ends. Rounding is settled in the last year so the schedule adds
+//! up to the cost.
+
+use serde::{Deserialize, Serialize};
+
+/// One year's depreciation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DepreciationYear {
+    pub year: u16,
+    pub amount_cents: i64,
+}
+
+/// Depreciation by year of `cost_cents` placed in service in `first_year`
+/// and recovered over `recovery_years`.
+pub fn straight_line_schedule(
+    cost_cents: i64,
+    recovery_years: u8,
+    first_year: u16,
+) -> Vec<DepreciationYear> {
+    if cost_cents <= 0 || recovery_years == 0 {
+        return Vec::new();
+    }
+    let annual = cost_cents / recovery_years as i64;
+    let mut schedule = Vec::with_capacity(recovery_years as usize + 1);
+    let mut remaining = cost_cents;
+    for i in 0..=recovery_years as u16 {
+        let amount = match i {
+            0 => annual / 2,
+            _ if i == recovery_years as u16 => remaining,
+            _ => annual,
+        };
+        remaining -= amount;
+        schedule.push(DepreciationYear {
+            year: first_year + i,
+            amount_cents: amount,
+        });
+    }
+    schedule
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn half_year_convention() {
+        let schedule = straight_line_schedule(300_000, 5, 2026);
+        let amounts: Vec<i64> = schedule.iter().map(|y| y.amount_cents).collect();
+        assert_eq!(amounts, [30_000, 60_000, 60_000, 60_000, 60_000, 30_000]);
+        assert_eq!(schedule[0].year, 2026);
+        assert_eq!(schedule[5].year, 2031);
+    }
+
+    #[test]
+    fn rounding_lands_in_the_last_year() {
+        let schedule = straight_line_schedule(100_001, 3, 2026);
+        assert_eq!(
+            schedule.iter().map(|y| y.amount_cents).sum::<i64>(),
+            100_001
+        );
+        assert_eq!(schedule[3].amount_cents, 16_669);
+        assert!(straight_line_schedule(0, 5, 2026).is_empty());
+    }
+}
@@ -18,6 +18,10 @@ pub struct LedgerSnapshot {
     /// Income lines have positive values; expense lines have positive values
     /// (representing the amount spent).
     pub line_totals: BTreeMap<ScheduleCLine, Money>,
+    /// The same totals as booked, before transaction and account deductible
+    /// shares. Shown next to the deductible figures; not used in the tax.
+    #[serde(default)]
+    pub book_totals: BTreeMap<ScheduleCLine, Money>,
     /// Prior year total tax liability (for safe harbor calculation).
     /// None if this is the first year using the app.
     pub prior_year_tax: Option<Money>,
@@ -53,6 +57,26 @@ impl LedgerSnapshot {
     pub fn net_profit(&self, rules: &TaxRules) -> Money {
         self.gross_income() - self.total_expenses(rules)
     }
+
+    /// Schedule C line 29: net profit before the home office deduction on
+    /// line 30, which is limited to it.
+    pub fn tentative_profit(&self, rules: &TaxRules) -> Money {
+        let home_office = self
+            .line_totals
+            .get(&ScheduleCLine::Line30)
+            .copied()
+            .unwrap_or_else(Money::zero);
+        self.net_profit(rules) + home_office
+    }
+
+    /// Expenses as booked, before deductible shares and caps.
+    pub fn book_expenses(&self) -> Money {
+        self.book_totals
+            .iter()
+            .filter(|(line, _)| !line.is_income())
+            .map(|(_, amount)| *amount)
+            .fold(Money::zero(), |a, b| a + b)
+    }
 }
 
 /// Result of computing a quarterly tax estimate.
@@ -83,6 +107,8 @@ pub struct QuarterlyEstimate {
     pub payment_due_date: NaiveDate,
     /// Schedule C line totals (for preview)
     pub schedule_c_lines: BTreeMap<ScheduleCLine, Money>,
+    /// The same lines as booked, before deductible shares and caps.
+    pub schedule_c_book_lines: BTreeMap<ScheduleCLine, Money>,
 }
 
 /// Full Schedule C preview with deduction-adjusted totals.
@@ -94,6 +120,10 @@ pub struct ScheduleCPreview {
     pub net_profit: Money,
     /// Line-by-line breakdown with deduction caps applied.
     pub lines: BTreeMap<ScheduleCLine, Money>,
+    /// Expenses as booked, before deductible shares and caps.
+    pub book_total_expenses: Money,
+    /// Line-by-line amounts as booked.
+    pub book_lines: BTreeMap<ScheduleCLine, Money>,
 }
 
 /// Compute a quarterly tax estimate. This is a pure function — no I/O.
@@ -173,6 +203,7 @@ pub fn compute_quarterly_estimate(
         quarterly_payment,
         payment_due_date,
         schedule_c_lines,
+        schedule_c_book_lines: snapshot.book_totals.clone(),
     }
 }
 
@@ -217,6 +248,8 @@ pub fn schedule_c_preview(rules: &TaxRules, snapshot: &LedgerSnapshot) -> Schedu
         total_expenses,
         net_profit,
         lines,
+        book_total_expenses: snapshot.book_expenses(),
+        book_lines: snapshot.book_totals.clone(),
     }
 }
 
@@ -244,6 +277,7 @@ mod tests {
 
         LedgerSnapshot {
             year: FiscalYear::new(2026),
+            book_totals: line_totals.clone(),
             line_totals,
             prior_year_tax: None,
         }
@@ -271,6 +305,17 @@ mod tests {
         assert_eq!(snap.net_profit(&rules).to_cents(), 9_050_000);
     }
 
+    #[test]
+    fn tentative_profit_adds_back_home_office() {
+        let rules = load_rules();
+        let mut snap = sample_snapshot();
+        assert_eq!(snap.tentative_profit(&rules).to_cents(), 9_050_000);
+        snap.line_totals
+            .insert(ScheduleCLine::Line30, Money::from_cents(150_000));
+        assert_eq!(snap.net_profit(&rules).to_cents(), 8_900_000);
+        assert_eq!(snap.tentative_profit(&rules).to_cents(), 9_050_000);
+    }
+
     #[test]
     fn quarterly_estimate_basic() {
         let rules = load_rules();
@@ -313,6 +358,7 @@ mod tests {
         let snap = LedgerSnapshot {
             year: FiscalYear::new(2026),
             line_totals: BTreeMap::new(),
+            book_totals: BTreeMap::new(),
             prior_year_tax: None,
         };
         let est = compute_quarterly_estimate(&rules, &snap, Quarter::Q2);
@@ -331,6 +377,7 @@ mod tests {
 
         let snap = LedgerSnapshot {
             year: FiscalYear::new(2026),
+            book_totals: BTreeMap::new(),
             line_totals,
             prior_year_tax: None,
         };
@@ -370,6 +417,7 @@ mod tests {
         line_totals.insert(ScheduleCLine::Line1, Money::from_cents(50_000_000));
         let snap = LedgerSnapshot {
             year: FiscalYear::new(2026),
+            book_totals: BTreeMap::new(),
             line_totals,
             prior_year_tax: None,
         };
@@ -407,6 +455,12 @@ mod tests {
                 .to_cents(),
             150_000
         );
+        // As booked, before the cap.
+        assert_eq!(
+            preview.book_lines[&ScheduleCLine::Line24b].to_cents(),
+            300_000
+        );
+        assert_eq!(preview.book_total_expenses.to_cents(), 1_100_000);
     }
 
     #[test]
@@ -0,0 +1,269 @@
+//! The home office deduction (Schedule C line 30).
+//!
+//! The simplified method allows a flat rate per square foot of office, up
+//! to a cap. The regular method (Form 8829) allows the office's share of
+//! the home's costs: rent or mortgage interest, utilities, insurance,
+//! repairs and the like, by floor area. Either way the deduction can't take
+//! the business below zero; under the regular method what it can't use
+//! carries over to next year.
+
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+
+use super::rules::HomeOfficeSimplified;
+use crate::money::Money;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HomeOfficeMethod {
+    Simplified,
+    Regular,
+}
+
+impl HomeOfficeMethod {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HomeOfficeMethod::Simplified => "simplified",
+            HomeOfficeMethod::Regular => "regular",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "simplified" => Some(HomeOfficeMethod::Simplified),
+            "regular" => Some(HomeOfficeMethod::Regular),
+            _ => None,
+        }
+    }
+}
+
+/// A cost of the whole home for the year.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HomeExpense {
+    pub label: String,
+    /// Account the amount is pulled from; `None` when entered by hand.
+    #[serde(default)]
+    pub account_code: Option<String>,
+    pub amount_cents: i64,
+}
+
+/// What the deduction is worked out from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HomeOfficeInput {
+    pub method: HomeOfficeMethod,
+    /// Area used regularly and only for the business.
+    pub office_sqft: u32,
+    /// Area of the whole home; only the regular method uses it.
+    pub home_sqft: u32,
+    /// Only the regular method uses these.
+    #[serde(default)]
+    pub expenses: Vec<HomeExpense>,
+    /// Regular-method deduction disallowed last year, carried into this one.
+    #[serde(default)]
+    pub prior_carryover_cents: i64,
+}
+
+impl HomeOfficeInput {
+    /// Office area as a share of the home, 0–100, to two places; zero under
+    /// the simplified method.
+    pub fn business_percent(&self) -> Decimal {
+        match self.share() {
+            Some(share) => (share * Decimal::from(100))
+                .round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero),
+            None => Decimal::ZERO,
+        }
+    }
+
+    /// The home's costs added up; zero under the simplified method.
+    pub fn expenses_cents(&self) -> i64 {
+        match self.method {
+            HomeOfficeMethod::Simplified => 0,
+            HomeOfficeMethod::Regular => self.expenses.iter().map(|e| e.amount_cents).sum(),
+        }
+    }
+
+    fn share(&self) -> Option<Decimal> {
+        match self.method {
+            HomeOfficeMethod::Regular if self.home_sqft > 0 => {
+                Some(Decimal::from(self.office_sqft) / Decimal::from(self.home_sqft))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HomeOfficeDeduction {
+    /// Office area as a share of the home, 0–100, to two places.
+    pub business_percent: Decimal,
+    /// The home's costs added up.
+    pub expenses_cents: i64,
+    /// The deduction before the limit on profit.
+    pub allowable_cents: i64,
+    /// Schedule C line 30.
+    pub deduction_cents: i64,
+    /// Allowed but over the limit, for next year's worksheet.
+    pub carryover_cents: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HomeOfficeError {
+    NoOffice,
+    OfficeLargerThanHome,
+    NegativeExpense(String),
+}
+
+impl std::fmt::Display for HomeOfficeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HomeOfficeError::NoOffice => write!(f, "Office area must be more than zero"),
+            HomeOfficeError::OfficeLargerThanHome => {
+                write!(f, "Office area can't be more than the home's")
+            }
+            HomeOfficeError::NegativeExpense(label) => {
+                write!(f, "Home expense {label} can't be negative")
+            }
+        }
+    }
+}
+
+impl std::error::Error for HomeOfficeError {}
+
+/// Work out the deduction. `tentative_profit` is Schedule C line 29, the
+/// profit before the home office.
+pub fn compute_home_office(
+    rules: &HomeOfficeSimplified,
+    input: &HomeOfficeInput,
+    tentative_profit: Money,
+) -> Result<HomeOfficeDeduction, HomeOfficeError> {
+    if input.office_sqft == 0 {
+        return Err(HomeOfficeError::NoOffice);
+    }
+    let limit = tentative_profit.to_cents().max(0);
+
+    match input.method {
+        HomeOfficeMethod::Simplified => {
+            let sqft = input.office_sqft.min(rules.max_sqft);
+            let allowable =
+                Money::from_decimal(rules.rate_per_sqft * Decimal::from(sqft)).to_cents();
+            Ok(HomeOfficeDeduction {
+                business_percent: Decimal::ZERO,
+                expenses_cents: 0,
+                allowable_cents: allowable,
+                deduction_cents: allowable.min(limit),
+                // Nothing carries over under the simplified method.
+                carryover_cents: 0,
+            })
+        }
+        HomeOfficeMethod::Regular => {
+            if input.office_sqft > input.home_sqft {
+                return Err(HomeOfficeError::OfficeLargerThanHome);
+            }
+            if let Some(e) = input.expenses.iter().find(|e| e.amount_cents < 0) {
+                return Err(HomeOfficeError::NegativeExpense(e.label.clone()));
+            }
+            let share = input.share().unwrap_or(Decimal::ZERO);
+            let expenses = input.expenses_cents();
+            let allowable = Money::from_decimal(Money::from_cents(expenses).as_decimal() * share)
+                .to_cents()
+                + input.prior_carryover_cents.max(0);
+            let deduction = allowable.min(limit);
+            Ok(HomeOfficeDeduction {
+                business_percent: input.business_percent(),
+                expenses_cents: expenses,
+                allowable_cents: allowable,
+                deduction_cents: deduction,
+                carryover_cents: allowable - deduction,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules() -> HomeOfficeSimplified {
+        HomeOfficeSimplified {
+            rate_per_sqft: Decimal::new(500, 2),
+            max_sqft: 300,
+        }
+    }
+
+    fn regular(expenses: &[i64]) -> HomeOfficeInput {
+        HomeOfficeInput {
+            method: HomeOfficeMethod::Regular,
+            office_sqft: 150,
+            home_sqft: 1_200,
+            expenses: expenses
+                .iter()
+                .map(|&cents| HomeExpense {
+                    label: "Rent".to_string(),
+                    account_code: None,
+                    amount_cents: cents,
+                })
+                .collect(),
+            prior_carryover_cents: 0,
+        }
+    }
+
+    #[test]
+    fn simplified_is_capped_by_area() {
+        let input = HomeOfficeInput {
+            method: HomeOfficeMethod::Simplified,
+            office_sqft: 400,
+            home_sqft: 0,
+            expenses: Vec::new(),
+            prior_carryover_cents: 0,
+        };
+        let d = compute_home_office(&rules(), &input, Money::from_cents(10_000_000)).unwrap();
+        assert_eq!(d.deduction_cents, 150_000);
+        assert_eq!(d.carryover_cents, 0);
+    }
+
+    #[test]
+    fn regular_takes_the_office_share() {
+        // $24,000 rent and $2,400 utilities; 150 of 1,200 sq ft is 12.5%.
+        let d = compute_home_office(
+            &rules(),
+            &regular(&[2_400_000, 240_000]),
+            Money::from_cents(10_000_000),
+        )
+        .unwrap();
+        assert_eq!(d.business_percent, Decimal::new(1250, 2));
+        assert_eq!(d.expenses_cents, 2_640_000);
+        assert_eq!(d.deduction_cents, 330_000);
+    }
+
+    #[test]
+    fn regular_over_profit_carries_over() {
+        let mut input = regular(&[2_400_000]);
+        input.prior_carryover_cents = 10_000;
+        let d = compute_home_office(&rules(), &input, Money::from_cents(200_000)).unwrap();
+        assert_eq!(d.allowable_cents, 310_000);
+        assert_eq!(d.deduction_cents, 200_000);
+        assert_eq!(d.carryover_cents, 110_000);
+
+        let d = compute_home_office(&rules(), &input, Money::from_cents(-5_000)).unwrap();
+        assert_eq!(d.deduction_cents, 0);
+    }
+
+    #[test]
+    fn invalid_inputs() {
+        let mut input = regular(&[-1]);
+        assert_eq!(
+            compute_home_office(&rules(), &input, Money::zero()),
+            Err(HomeOfficeError::NegativeExpense("Rent".to_string()))
+        );
+        input.office_sqft = 2_000;
+        assert_eq!(
+            compute_home_office(&rules(), &input, Money::zero()),
+            Err(HomeOfficeError::OfficeLargerThanHome)
+        );
+        input.office_sqft = 0;
+        assert_eq!(
+            compute_home_office(&rules(), &input, Money::zero()),
+            Err(HomeOfficeError::NoOffice)
+        );
+    }
+}
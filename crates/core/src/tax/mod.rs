@@ -1,8 +1,15 @@
 pub mod community;
+pub mod depreciation;
 pub mod engine;
+pub mod home_office;
 pub mod rules;
 pub mod schedule_c;
 
+pub use depreciation::{straight_line_schedule, DepreciationYear};
 pub use engine::{compute_quarterly_estimate, LedgerSnapshot, QuarterlyEstimate, ScheduleCPreview};
+pub use home_office::{
+    compute_home_office, HomeExpense, HomeOfficeDeduction, HomeOfficeError, HomeOfficeInput,
+    HomeOfficeMethod,
+};
 pub use rules::{TaxRules, TaxRulesError};
 pub use schedule_c::ScheduleCLine;
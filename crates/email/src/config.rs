@@ -30,6 +30,41 @@ impl fmt::Debug for SmtpConfig {
     }
 }
 
+/// IMAP mailbox that receipts are forwarded to. Only implicit TLS (port 993)
+/// is supported.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ImapConfig {
+    pub host: String,
+    #[serde(default = "default_imap_port")]
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    /// Folder to poll; a dedicated one (e.g. `Receipts`) keeps personal mail
+    /// out of the receipt queue.
+    #[serde(default = "default_folder")]
+    pub folder: String,
+}
+
+fn default_imap_port() -> u16 {
+    993
+}
+
+fn default_folder() -> String {
+    "INBOX".to_string()
+}
+
+impl fmt::Debug for ImapConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ImapConfig")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("username", &self.username)
+            .field("password", &"[REDACTED]")
+            .field("folder", &self.folder)
+            .finish()
+    }
+}
+
 /// Email delivery configuration — either SMTP or Resend API.
 #[derive(Clone, Deserialize)]
 #[serde(tag = "backend")]
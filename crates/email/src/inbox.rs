@@ -0,0 +1,287 @@
+//! Receipts that arrive by email.
+//!
+//! Messages come either from an IMAP folder or as `.eml` files saved into
+//! the receipt intake folder. Each message yields the documents worth
+//! running through the receipt pipeline: PDF and photo attachments, or,
+//! when there are none, the HTML (or plain-text) body that online shops
+//! send as the receipt itself.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::TryStreamExt;
+use mail_parser::{MessageParser, MessagePart, MimeHeaders};
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::pki_types::ServerName;
+use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+use tokio_rustls::TlsConnector;
+
+use crate::config::ImapConfig;
+
+/// Messages fetched per poll; the rest stay unseen for the next one.
+pub const MAX_MESSAGES_PER_POLL: usize = 50;
+
+/// Give up on a poll that takes longer than this (slow server, huge
+/// attachments). Messages are only marked seen once all of them have been
+/// fetched, so a poll that times out loses nothing.
+const POLL_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Debug, thiserror::Error)]
+pub enum InboxError {
+    #[error("failed to connect to IMAP server: {0}")]
+    Connect(String),
+    #[error("IMAP login failed: {0}")]
+    Login(String),
+    #[error("IMAP error: {0}")]
+    Imap(String),
+    #[error("IMAP poll timed out")]
+    Timeout,
+}
+
+impl From<async_imap::error::Error> for InboxError {
+    fn from(e: async_imap::error::Error) -> Self {
+        InboxError::Imap(e.to_string())
+    }
+}
+
+/// One file to run through the receipt pipeline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReceiptDocument {
+    /// Attachment file name, or the subject for a body.
+    pub name: String,
+    /// Lowercase extension the pipeline dispatches on (`pdf`, `jpg`,
+    /// `html`, `txt`, ...).
+    pub ext: String,
+    pub data: Vec<u8>,
+}
+
+/// The receipt documents in a raw RFC 822 message.
+///
+/// PDFs and images sent as attachments are returned as they are. Images
+/// embedded in the HTML (logos, referenced by Content-ID) are skipped. A
+/// message without any such attachment returns its body instead.
+pub fn receipt_documents(raw: &[u8]) -> Vec<ReceiptDocument> {
+    let Some(message) = MessageParser::default().parse(raw) else {
+        return Vec::new();
+    };
+    let subject = message.subject().unwrap_or("email receipt").to_string();
+
+    let attachments: Vec<_> = message
+        .attachments()
+        .filter_map(|part| {
+            let ext = document_extension(part)?;
+            Some(ReceiptDocument {
+                name: part
+                    .attachment_name()
+                    .map_or_else(|| format!("{subject}.{ext}"), str::to_string),
+                ext: ext.to_string(),
+                data: part.contents().to_vec(),
+            })
+        })
+        .collect();
+    if !attachments.is_empty() {
+        return attachments;
+    }
+
+    // mail-parser converts a text-only message into an HTML body as well;
+    // only a real text/html part is treated as HTML.
+    let has_html = message
+        .html_part(0)
+        .and_then(|p| p.content_type())
+        .is_some_and(|ct| ct.subtype().is_some_and(|s| s.eq_ignore_ascii_case("html")));
+    let body = if has_html {
+        message.body_html(0).map(|b| ("html", b))
+    } else {
+        message.body_text(0).map(|b| ("txt", b))
+    };
+    match body {
+        Some((ext, body)) if !body.trim().is_empty() => vec![ReceiptDocument {
+            name: subject,
+            ext: ext.to_string(),
+            data: body.into_owned().into_bytes(),
+        }],
+        _ => Vec::new(),
+    }
+}
+
+/// Pipeline extension for an attachment, if it looks like a receipt.
+fn document_extension(part: &MessagePart<'_>) -> Option<&'static str> {
+    let by_type = part.content_type().and_then(|ct| {
+        match (
+            ct.ctype().to_ascii_lowercase().as_str(),
+            ct.subtype()?.to_ascii_lowercase().as_str(),
+        ) {
+            ("application", "pdf") => Some("pdf"),
+            ("image", "jpeg" | "jpg" | "pjpeg") => Some("jpg"),
+            ("image", "png") => Some("png"),
+            ("image", "heic") => Some("heic"),
+            ("image", "heif") => Some("heif"),
+            ("image", "webp") => Some("webp"),
+            ("image", "tiff") => Some("tiff"),
+            _ => None,
+        }
+    });
+    // Clients often send everything as application/octet-stream.
+    let ext = by_type.or_else(|| {
+        let name = part.attachment_name()?.to_ascii_lowercase();
+        let ext = name.rsplit_once('.')?.1;
+        ["pdf", "jpg", "jpeg", "png", "heic", "heif", "webp", "tiff"]
+            .into_iter()
+            .find(|e| *e == ext)
+            .map(|e| if e == "jpeg" { "jpg" } else { e })
+    })?;
+
+    let attached = part
+        .content_disposition()
+        .is_some_and(|d| d.is_attachment());
+    if ext != "pdf" && !attached && part.content_id().is_some() {
+        return None;
+    }
+    Some(ext)
+}
+
+/// Fetch the unseen messages in the configured folder, oldest first.
+///
+/// The returned messages are marked seen, so each is returned by one poll
+/// only.
+pub async fn fetch_unseen(config: &ImapConfig) -> Result<Vec<Vec<u8>>, InboxError> {
+    tokio::time::timeout(POLL_TIMEOUT, fetch_unseen_inner(config))
+        .await
+        .map_err(|_| InboxError::Timeout)?
+}
+
+async fn fetch_unseen_inner(config: &ImapConfig) -> Result<Vec<Vec<u8>>, InboxError> {
+    let roots = RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    let provider = Arc::new(tokio_rustls::rustls::crypto::ring::default_provider());
+    let tls = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| InboxError::Connect(e.to_string()))?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let server_name = ServerName::try_from(config.host.clone())
+        .map_err(|e| InboxError::Connect(format!("invalid host {}: {e}", config.host)))?;
+
+    let tcp = TcpStream::connect((config.host.as_str(), config.port))
+        .await
+        .map_err(|e| InboxError::Connect(e.to_string()))?;
+    let stream = TlsConnector::from(Arc::new(tls))
+        .connect(server_name, tcp)
+        .await
+        .map_err(|e| InboxError::Connect(e.to_string()))?;
+
+    let mut client = async_imap::Client::new(stream);
+    client
+        .read_response()
+        .await
+        .map_err(|e| InboxError::Connect(e.to_string()))?
+        .ok_or_else(|| InboxError::Connect("server closed the connection".to_string()))?;
+    let mut session = client
+        .login(&config.username, &config.password)
+        .await
+        .map_err(|(e, _)| InboxError::Login(e.to_string()))?;
+
+    session.select(&config.folder).await?;
+    let mut unseen: Vec<u32> = session.search("UNSEEN").await?.into_iter().collect();
+    unseen.sort_unstable();
+    unseen.truncate(MAX_MESSAGES_PER_POLL);
+
+    let mut messages = Vec::new();
+    if !unseen.is_empty() {
+        let set = unseen
+            .iter()
+            .map(u32::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        let mut fetches = session.fetch(&set, "BODY.PEEK[]").await?;
+        while let Some(fetch) = fetches.try_next().await? {
+            if let Some(body) = fetch.body() {
+                messages.push(body.to_vec());
+            }
+        }
+        drop(fetches);
+        session
+            .store(&set, "+FLAGS.SILENT (\\Seen)")
+            .await?
+            .try_collect::<Vec<_>>()
+            .await?;
+    }
+    session.logout().await?;
+    Ok(messages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PDF_EMAIL: &str = "From: billing@acme.example\r\n\
+Subject: Your invoice\r\n\
+MIME-Version: 1.0\r\n\
+Content-Type: multipart/mixed; boundary=\"b1\"\r\n\
+\r\n\
+--b1\r\n\
+Content-Type: text/html; charset=utf-8\r\n\
+\r\n\
+<p>Please find your invoice attached.</p><img src=\"cid:logo\">\r\n\
+--b1\r\n\
+Content-Type: image/png\r\n\
+Content-ID: <logo>\r\n\
+Content-Disposition: inline\r\n\
+Content-Transfer-Encoding: base64\r\n\
+\r\n\
+iVBORw0KGgo=\r\n\
+--b1\r\n\
+Content-Type: application/octet-stream; name=\"INV-1042.pdf\"\r\n\
+Content-Disposition: attachment; filename=\"INV-1042.pdf\"\r\n\
+Content-Transfer-Encoding: base64\r\n\
+\r\n\
+JVBERi0xLjcK\r\n\
+--b1--\r\n";
+
+    #[test]
+    fn pdf_attachment_without_inline_logo() {
+        let docs = receipt_documents(PDF_EMAIL.as_bytes());
+        assert_eq!(docs.len(), 1);
+        assert_eq!(docs[0].name, "INV-1042.pdf");
+        assert_eq!(docs[0].ext, "pdf");
+        assert_eq!(docs[0].data, b"%PDF-1.7\n");
+    }
+
+    #[test]
+    fn html_body_when_nothing_attached() {
+        let raw = "From: receipts@rides.example\r\n\
+Subject: Your Tuesday trip\r\n\
+MIME-Version: 1.0\r\n\
+Content-Type: multipart/alternative; boundary=\"b2\"\r\n\
+\r\n\
+--b2\r\n\
+Content-Type: text/plain\r\n\
+\r\n\
+Total $18.40\r\n\
+--b2\r\n\
+Content-Type: text/html\r\n\
+\r\n\
+<table><tr><td>Total</td><td>$18.40</td></tr></table>\r\n\
+--b2--\r\n";
+        let docs = receipt_documents(raw.as_bytes());
+        assert_eq!(docs.len(), 1);
+        assert_eq!(docs[0].name, "Your Tuesday trip");
+        assert_eq!(docs[0].ext, "html");
+        assert!(String::from_utf8_lossy(&docs[0].data).contains("<td>$18.40</td>"));
+    }
+
+    #[test]
+    fn plain_text_message() {
+        let raw = "Subject: Parking\r\n\r\nCity Parking\r\nTotal 6.00\r\n";
+        let docs = receipt_documents(raw.as_bytes());
+        assert_eq!(docs.len(), 1);
+        assert_eq!(docs[0].ext, "txt");
+        assert!(String::from_utf8_lossy(&docs[0].data).contains("Total 6.00"));
+    }
+
+    #[test]
+    fn empty_message_has_no_documents() {
+        assert!(receipt_documents(b"Subject: hi\r\n\r\n").is_empty());
+    }
+}
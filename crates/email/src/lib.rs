@@ -1,5 +1,7 @@
 mod config;
 mod deliver;
+mod inbox;
 
-pub use config::{EmailConfig, SmtpConfig};
+pub use config::{EmailConfig, ImapConfig, SmtpConfig};
 pub use deliver::{send_invoice, DeliveryError, DeliveryResult};
+pub use inbox::{fetch_unseen, receipt_documents, InboxError, ReceiptDocument};
@@ -0,0 +1,506 @@
+//! Amazon order history import.
+//!
+//! Amazon bills one card charge per shipment (occasionally per order), so the
+//! bank feed only ever shows an opaque "AMZN Mktp US*..." total. The order
+//! items report supplies the detail to split that charge into per-item lines.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use chrono::NaiveDate;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::str::FromStr;
+use thiserror::Error;
+
+use crate::match_engine::MatchableTransaction;
+use crate::rules::{CategorizableTransaction, CategoryRuleEngine};
+
+#[derive(Error, Debug)]
+pub enum AmazonError {
+    #[error("CSV error: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("Missing column: {0}")]
+    MissingColumn(&'static str),
+    #[error("Row {row}: invalid date {value:?}")]
+    InvalidDate { row: usize, value: String },
+    #[error("Row {row}: invalid amount {value:?}")]
+    InvalidAmount { row: usize, value: String },
+    #[error("No items found")]
+    NoItems,
+}
+
+/// One line of the order items report.
+#[derive(Debug, Clone, Serialize)]
+pub struct AmazonItem {
+    pub order_id: String,
+    pub order_date: NaiveDate,
+    pub ship_date: Option<NaiveDate>,
+    pub title: String,
+    pub category: Option<String>,
+    pub quantity: u32,
+    /// Item total including tax, in cents.
+    pub total_cents: i64,
+}
+
+/// The items billed together in one card charge.
+#[derive(Debug, Clone, Serialize)]
+pub struct AmazonCharge {
+    pub order_id: String,
+    /// Ship date when known (Amazon charges on shipment), else order date.
+    pub date: NaiveDate,
+    pub items: Vec<AmazonItem>,
+    pub total_cents: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AmazonSplitLine {
+    pub title: String,
+    pub account_code: String,
+    pub amount_cents: i64,
+}
+
+// Column names in the legacy "Items" report and in the newer
+// Retail.OrderHistory export from Amazon's data request.
+const ORDER_ID: &[&str] = &["order id"];
+const ORDER_DATE: &[&str] = &["order date"];
+const SHIP_DATE: &[&str] = &["shipment date", "ship date"];
+const TITLE: &[&str] = &["title", "product name"];
+const CATEGORY: &[&str] = &["category"];
+const QUANTITY: &[&str] = &["quantity"];
+const TOTAL: &[&str] = &["item total", "total owed"];
+const STATUS: &[&str] = &["order status"];
+
+/// Parse an Amazon order items report. Cancelled and zero-total lines are
+/// dropped since they never reach the card.
+pub fn parse_items_report<R: std::io::Read>(reader: R) -> Result<Vec<AmazonItem>, AmazonError> {
+    let mut rdr = csv::ReaderBuilder::new().flexible(true).from_reader(reader);
+    let headers: Vec<String> = rdr
+        .headers()?
+        .iter()
+        .map(|h| h.trim().trim_start_matches('\u{feff}').to_lowercase())
+        .collect();
+    let find = |aliases: &[&str]| headers.iter().position(|h| aliases.contains(&h.as_str()));
+
+    let order_id_col = find(ORDER_ID).ok_or(AmazonError::MissingColumn("Order ID"))?;
+    let order_date_col = find(ORDER_DATE).ok_or(AmazonError::MissingColumn("Order Date"))?;
+    let title_col = find(TITLE).ok_or(AmazonError::MissingColumn("Title"))?;
+    let total_col = find(TOTAL).ok_or(AmazonError::MissingColumn("Item Total"))?;
+    let ship_date_col = find(SHIP_DATE);
+    let category_col = find(CATEGORY);
+    let quantity_col = find(QUANTITY);
+    let status_col = find(STATUS);
+
+    let mut items = Vec::new();
+    for (idx, record) in rdr.records().enumerate() {
+        let record = record?;
+        let row = idx + 2;
+        let field = |col: Option<usize>| {
+            col.and_then(|c| record.get(c))
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+        };
+
+        if field(status_col).is_some_and(|s| s.eq_ignore_ascii_case("cancelled")) {
+            continue;
+        }
+        let total_raw = field(Some(total_col)).unwrap_or("0");
+        let total_cents = parse_amount(total_raw).ok_or_else(|| AmazonError::InvalidAmount {
+            row,
+            value: total_raw.to_string(),
+        })?;
+        if total_cents == 0 {
+            continue;
+        }
+
+        let order_date_raw = field(Some(order_date_col)).unwrap_or_default();
+        let order_date = parse_date(order_date_raw).ok_or_else(|| AmazonError::InvalidDate {
+            row,
+            value: order_date_raw.to_string(),
+        })?;
+
+        items.push(AmazonItem {
+            order_id: field(Some(order_id_col)).unwrap_or_default().to_string(),
+            order_date,
+            // "Not Available" for undelivered items; treat as unknown.
+            ship_date: field(ship_date_col).and_then(parse_date),
+            title: field(Some(title_col)).unwrap_or_default().to_string(),
+            category: field(category_col).map(str::to_string),
+            quantity: field(quantity_col)
+                .and_then(|q| q.parse().ok())
+                .unwrap_or(1),
+            total_cents,
+        });
+    }
+
+    if items.is_empty() {
+        return Err(AmazonError::NoItems);
+    }
+    Ok(items)
+}
+
+/// Group items into the charges Amazon made: one per order and ship date.
+pub fn group_charges(items: &[AmazonItem]) -> Vec<AmazonCharge> {
+    let mut groups: BTreeMap<(String, NaiveDate), Vec<AmazonItem>> = BTreeMap::new();
+    for item in items {
+        let date = item.ship_date.unwrap_or(item.order_date);
+        groups
+            .entry((item.order_id.clone(), date))
+            .or_default()
+            .push(item.clone());
+    }
+    let mut charges: Vec<AmazonCharge> = groups
+        .into_iter()
+        .map(|((order_id, date), items)| AmazonCharge {
+            total_cents: items.iter().map(|i| i.total_cents).sum(),
+            order_id,
+            date,
+            items,
+        })
+        .collect();
+    charges.sort_by(|a, b| {
+        a.date
+            .cmp(&b.date)
+            .then_with(|| a.order_id.cmp(&b.order_id))
+    });
+    charges
+}
+
+/// Maps Amazon's product categories onto chart-of-accounts codes.
+#[derive(Debug, Clone)]
+pub struct AmazonCategoryMap {
+    categories: HashMap<String, String>,
+    default_account: String,
+}
+
+impl Default for AmazonCategoryMap {
+    fn default() -> Self {
+        Self::standard("5900")
+    }
+}
+
+impl AmazonCategoryMap {
+    pub fn new(default_account: impl Into<String>) -> Self {
+        Self {
+            categories: HashMap::new(),
+            default_account: default_account.into(),
+        }
+    }
+
+    /// Mappings tuned to the standard chart of accounts, falling back to
+    /// `default_account` for anything else.
+    pub fn standard(default_account: impl Into<String>) -> Self {
+        let mut map = Self::new(default_account);
+        for (category, account) in [
+            ("office product", "5100"),
+            ("office products", "5100"),
+            ("paper", "5100"),
+            ("electronics", "5040"),
+            ("personal computer", "5040"),
+            ("pc", "5040"),
+            ("camera", "5040"),
+            ("book", "5030"),
+            ("books", "5030"),
+            ("abis_book", "5030"),
+            ("digital ebook purchase", "5030"),
+            ("software", "5110"),
+            ("digital software", "5110"),
+            ("wireless", "5070"),
+        ] {
+            map.categories
+                .insert(category.to_string(), account.to_string());
+        }
+        map
+    }
+
+    pub fn with(mut self, category: &str, account_code: impl Into<String>) -> Self {
+        self.categories
+            .insert(category.trim().to_lowercase(), account_code.into());
+        self
+    }
+
+    /// Account for one item: a user rule on the item title wins, then the
+    /// Amazon category, then the default account.
+    pub fn account_for(
+        &self,
+        item: &AmazonItem,
+        date: NaiveDate,
+        rules: &CategoryRuleEngine,
+    ) -> String {
+        let tx = CategorizableTransaction {
+            date,
+            description: item.title.clone(),
+            amount_cents: item.total_cents,
+            memo: item.category.clone(),
+        };
+        if let Some(rule) = rules.find_matching_rule(&tx) {
+            return rule.account_code.clone();
+        }
+        item.category
+            .as_deref()
+            .and_then(|c| self.categories.get(&c.trim().to_lowercase()))
+            .unwrap_or(&self.default_account)
+            .clone()
+    }
+}
+
+/// Split a charge into one line per item, each with its own account.
+pub fn split_charge(
+    charge: &AmazonCharge,
+    categories: &AmazonCategoryMap,
+    rules: &CategoryRuleEngine,
+) -> Vec<AmazonSplitLine> {
+    charge
+        .items
+        .iter()
+        .map(|item| AmazonSplitLine {
+            title: item.title.clone(),
+            account_code: categories.account_for(item, charge.date, rules),
+            amount_cents: item.total_cents,
+        })
+        .collect()
+}
+
+/// True for the descriptions Amazon charges show up under on statements
+/// ("AMZN Mktp US*2K4...", "Amazon.com*MK1...", "AMAZON DIGITAL SVCS").
+pub fn is_amazon_description(description: &str) -> bool {
+    let d = description.to_lowercase();
+    d.contains("amzn") || d.contains("amazon")
+}
+
+/// Find the bank charge behind each Amazon charge. Bank rows must look like
+/// Amazon, carry the same absolute amount, and fall within `window_days` of
+/// the charge date; the closest date wins and each bank row is used once.
+///
+/// When an order's shipments were billed together, the individual shipment
+/// charges will not match on their own, so unmatched charges of one order are
+/// retried against their combined total.
+///
+/// Returns the matched bank transaction id for each charge, by index.
+pub fn match_charges(
+    charges: &[AmazonCharge],
+    bank: &[MatchableTransaction],
+    window_days: i64,
+) -> Vec<Option<i64>> {
+    let amazon_rows: Vec<&MatchableTransaction> = bank
+        .iter()
+        .filter(|t| is_amazon_description(&t.description))
+        .collect();
+    let mut used = HashSet::new();
+    let find = |amount: i64, date: NaiveDate, used: &mut HashSet<i64>| {
+        let best = amazon_rows
+            .iter()
+            .filter(|t| !used.contains(&t.id) && t.amount_cents.abs() == amount)
+            .map(|t| (t.id, (t.date - date).num_days().abs()))
+            .filter(|(_, diff)| *diff <= window_days)
+            .min_by_key(|(id, diff)| (*diff, *id))
+            .map(|(id, _)| id);
+        if let Some(id) = best {
+            used.insert(id);
+        }
+        best
+    };
+
+    let mut matched: Vec<Option<i64>> = charges
+        .iter()
+        .map(|c| find(c.total_cents.abs(), c.date, &mut used))
+        .collect();
+
+    let mut unmatched_by_order: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (idx, charge) in charges.iter().enumerate() {
+        if matched[idx].is_none() {
+            unmatched_by_order
+                .entry(charge.order_id.as_str())
+                .or_default()
+                .push(idx);
+        }
+    }
+    for indices in unmatched_by_order.values().filter(|v| v.len() > 1) {
+        let total: i64 = indices.iter().map(|&i| charges[i].total_cents).sum();
+        let date = indices.iter().map(|&i| charges[i].date).min().unwrap();
+        if let Some(id) = find(total.abs(), date, &mut used) {
+            for &i in indices {
+                matched[i] = Some(id);
+            }
+        }
+    }
+
+    matched
+}
+
+fn parse_date(s: &str) -> Option<NaiveDate> {
+    let s = s.trim();
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(s) {
+        return Some(dt.date_naive());
+    }
+    for fmt in ["%m/%d/%y", "%m/%d/%Y", "%Y-%m-%d"] {
+        if let Ok(d) = NaiveDate::parse_from_str(s, fmt) {
+            return Some(d);
+        }
+    }
+    None
+}
+
+fn parse_amount(s: &str) -> Option<i64> {
+    let cleaned = s.replace([',', '$', ' ', '\''], "");
+    Decimal::from_str(&cleaned)
+        .ok()
+        .and_then(|d| (d * Decimal::from(100)).round().to_i64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::{CategoryRule, MatchType};
+
+    const LEGACY_REPORT: &str = "\
+Order Date,Order ID,Title,Category,Quantity,Shipment Date,Item Total
+01/15/24,111-1,Printer Paper 500 Sheets,Office Product,1,01/16/24,$12.99
+01/15/24,111-1,USB-C Cable,Electronics,2,01/16/24,$18.50
+01/15/24,111-1,Rust Programming Book,Book,1,01/20/24,$39.99
+01/20/24,222-2,Cancelled Thing,Toy,1,Not Available,$0.00
+";
+
+    const RETAIL_HISTORY: &str = "\
+Website,Order ID,Order Date,Currency,Total Owed,Quantity,Order Status,Ship Date,Product Name
+Amazon.com,333-3,2024-02-01T10:15:00Z,USD,25.00,1,Closed,2024-02-02T08:00:00Z,Desk Lamp
+Amazon.com,444-4,2024-02-03T10:15:00Z,USD,9.99,1,Cancelled,Not Available,Mouse Pad
+";
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn bank(id: i64, d: NaiveDate, desc: &str, amount: i64) -> MatchableTransaction {
+        MatchableTransaction {
+            id,
+            date: d,
+            description: desc.to_string(),
+            amount_cents: amount,
+        }
+    }
+
+    // ── parsing ──────────────────────────────────────────────────────────────
+
+    #[test]
+    fn parses_legacy_items_report() {
+        let items = parse_items_report(LEGACY_REPORT.as_bytes()).unwrap();
+        assert_eq!(items.len(), 3, "zero-total line dropped");
+        assert_eq!(items[0].order_date, date(2024, 1, 15));
+        assert_eq!(items[0].ship_date, Some(date(2024, 1, 16)));
+        assert_eq!(items[0].category.as_deref(), Some("Office Product"));
+        assert_eq!(items[1].quantity, 2);
+        assert_eq!(items[1].total_cents, 1850);
+    }
+
+    #[test]
+    fn parses_retail_order_history() {
+        let items = parse_items_report(RETAIL_HISTORY.as_bytes()).unwrap();
+        assert_eq!(items.len(), 1, "cancelled order dropped");
+        assert_eq!(items[0].title, "Desk Lamp");
+        assert_eq!(items[0].order_date, date(2024, 2, 1));
+        assert_eq!(items[0].ship_date, Some(date(2024, 2, 2)));
+        assert!(items[0].category.is_none());
+    }
+
+    #[test]
+    fn four_digit_years_accepted() {
+        assert_eq!(parse_date("01/15/2024"), Some(date(2024, 1, 15)));
+        assert_eq!(parse_date("1/5/24"), Some(date(2024, 1, 5)));
+    }
+
+    #[test]
+    fn missing_total_column_is_an_error() {
+        let data = "Order Date,Order ID,Title\n01/15/24,1,Thing\n";
+        assert!(matches!(
+            parse_items_report(data.as_bytes()),
+            Err(AmazonError::MissingColumn("Item Total"))
+        ));
+    }
+
+    #[test]
+    fn bad_date_reports_row() {
+        let data = "Order Date,Order ID,Title,Item Total\nyesterday,1,Thing,$1.00\n";
+        assert!(matches!(
+            parse_items_report(data.as_bytes()),
+            Err(AmazonError::InvalidDate { row: 2, .. })
+        ));
+    }
+
+    // ── grouping & splitting ─────────────────────────────────────────────────
+
+    #[test]
+    fn charges_grouped_by_shipment() {
+        let charges = group_charges(&parse_items_report(LEGACY_REPORT.as_bytes()).unwrap());
+        assert_eq!(charges.len(), 2);
+        assert_eq!(charges[0].date, date(2024, 1, 16));
+        assert_eq!(charges[0].items.len(), 2);
+        assert_eq!(charges[0].total_cents, 3149);
+        assert_eq!(charges[1].total_cents, 3999);
+    }
+
+    #[test]
+    fn split_uses_rules_then_categories_then_default() {
+        let charges = group_charges(&parse_items_report(LEGACY_REPORT.as_bytes()).unwrap());
+        let rules = CategoryRuleEngine::new(vec![CategoryRule {
+            name: "Cables are equipment".to_string(),
+            priority: 1,
+            pattern: "usb".to_string(),
+            match_type: MatchType::Contains,
+            account_code: "5041".to_string(),
+            amount_min_cents: None,
+            amount_max_cents: None,
+        }]);
+        let lines = split_charge(&charges[0], &AmazonCategoryMap::default(), &rules);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].account_code, "5100");
+        assert_eq!(lines[1].account_code, "5041");
+        assert_eq!(lines.iter().map(|l| l.amount_cents).sum::<i64>(), 3149);
+
+        let custom = AmazonCategoryMap::new("5999").with("Book", "5031");
+        let lines = split_charge(&charges[1], &custom, &CategoryRuleEngine::new(vec![]));
+        assert_eq!(lines[0].account_code, "5031");
+        let lines = split_charge(&charges[0], &custom, &CategoryRuleEngine::new(vec![]));
+        assert_eq!(lines[0].account_code, "5999");
+    }
+
+    // ── matching ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn matches_shipments_to_amazon_bank_rows() {
+        let charges = group_charges(&parse_items_report(LEGACY_REPORT.as_bytes()).unwrap());
+        let bank_rows = [
+            bank(1, date(2024, 1, 17), "AMZN Mktp US*2K4AB1", -3149),
+            bank(2, date(2024, 1, 17), "GROCERY STORE", -3999),
+            bank(3, date(2024, 1, 21), "Amazon.com*MK1", -3999),
+        ];
+        assert_eq!(
+            match_charges(&charges, &bank_rows, 5),
+            vec![Some(1), Some(3)]
+        );
+    }
+
+    #[test]
+    fn outside_window_is_unmatched() {
+        let charges = group_charges(&parse_items_report(LEGACY_REPORT.as_bytes()).unwrap());
+        let bank_rows = [bank(1, date(2024, 2, 20), "AMZN Mktp US", -3149)];
+        assert_eq!(match_charges(&charges, &bank_rows, 5), vec![None, None]);
+    }
+
+    #[test]
+    fn order_billed_once_matches_combined_total() {
+        let charges = group_charges(&parse_items_report(LEGACY_REPORT.as_bytes()).unwrap());
+        let bank_rows = [bank(9, date(2024, 1, 16), "AMZN Mktp US", -7148)];
+        assert_eq!(
+            match_charges(&charges, &bank_rows, 5),
+            vec![Some(9), Some(9)]
+        );
+    }
+
+    #[test]
+    fn amazon_descriptions() {
+        assert!(is_amazon_description("AMZN Mktp US*2K4AB1"));
+        assert!(is_amazon_description("Amazon Digital Svcs"));
+        assert!(!is_amazon_description("AMAZING CAFE"));
+    }
+}
@@ -0,0 +1,480 @@
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::csv::{CsvError, CsvImportProfile, CsvTransaction};
+use crate::ofx::OfxError;
+use crate::qif::QifError;
+
+/// Subfolder of the intake directory that successfully imported files are moved into.
+pub const PROCESSED_DIR: &str = "processed";
+
+/// Number of leading lines used to fingerprint a CSV against saved profiles.
+const FINGERPRINT_LINES: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BankFileKind {
+    Csv,
+    Ofx,
+    Qif,
+}
+
+impl BankFileKind {
+    /// Classify by extension; `.qfx` is Quicken's branded OFX.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "csv" => Some(BankFileKind::Csv),
+            "ofx" | "qfx" => Some(BankFileKind::Ofx),
+            "qif" => Some(BankFileKind::Qif),
+            _ => None,
+        }
+    }
+
+    /// Value stored in `imported_transactions.source_type`.
+    pub fn source_type(&self) -> &'static str {
+        match self {
+            BankFileKind::Csv => "csv",
+            BankFileKind::Ofx => "ofx",
+            BankFileKind::Qif => "qif",
+        }
+    }
+}
+
+/// A transaction read from a dropped bank file, normalized across formats.
+#[derive(Debug, Clone)]
+pub struct IntakeTransaction {
+    pub date: NaiveDate,
+    pub description: String,
+    pub amount_cents: i64,
+    pub debit_cents: Option<i64>,
+    pub credit_cents: Option<i64>,
+    pub memo: Option<String>,
+    /// Provider id (OFX FITID) when the format carries one.
+    pub source_id: Option<String>,
+    /// Code of the account the source suggests booking the row to, used
+    /// when no categorization rule matches.
+    pub suggested_account: Option<String>,
+}
+
+impl From<CsvTransaction> for IntakeTransaction {
+    fn from(t: CsvTransaction) -> Self {
+        IntakeTransaction {
+            date: t.date,
+            description: t.description,
+            amount_cents: t.amount,
+            debit_cents: t.debit,
+            credit_cents: t.credit,
+            memo: t.memo,
+            source_id: None,
+            suggested_account: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BankFileImport {
+    pub kind: BankFileKind,
+    /// Saved CSV profile used to read the file, if any.
+    pub profile_name: Option<String>,
+    pub transactions: Vec<IntakeTransaction>,
+    /// Closing balance of an OFX bank statement, in the bank's sign, and
+    /// the statement's end date.
+    pub ledger_balance: Option<(NaiveDate, i64)>,
+}
+
+#[derive(Debug, Error)]
+pub enum BankIntakeError {
+    #[error("unsupported bank file: {0}")]
+    UnsupportedFile(String),
+    #[error("no saved import profile matches {0}")]
+    NoMatchingProfile(String),
+    #[error("file is not valid UTF-8")]
+    Encoding,
+    #[error(transparent)]
+    Csv(#[from] CsvError),
+    #[error(transparent)]
+    Ofx(#[from] OfxError),
+    #[error(transparent)]
+    Qif(#[from] QifError),
+}
+
+/// Pick the saved CSV profile for a dropped file.
+///
+/// A profile whose name appears in the file name wins (`"Chase Checking"`
+/// matches `chase_checking_2026-03.csv`). Otherwise the first profile that can
+/// read the opening rows with its own date format is used.
+pub fn select_csv_profile<'a>(
+    file_name: &str,
+    data: &str,
+    profiles: &'a [CsvImportProfile],
+) -> Option<&'a CsvImportProfile> {
+    let file_slug = slug(file_name);
+    profiles
+        .iter()
+        .find(|p| {
+            let name = slug(&p.name);
+            !name.is_empty() && file_slug.contains(&name)
+        })
+        .or_else(|| profiles.iter().find(|p| fingerprint_matches(p, data)))
+}
+
+/// Lowercase alphanumerics only, so separators and case never matter.
+fn slug(s: &str) -> String {
+    s.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn fingerprint_matches(profile: &CsvImportProfile, data: &str) -> bool {
+    let sample: String = data
+        .lines()
+        .take(FINGERPRINT_LINES)
+        .flat_map(|l| [l, "\n"])
+        .collect();
+
+    let Some(date_col) = profile.mapping.date_column else {
+        return false;
+    };
+    let delimiter = profile
+        .delimiter
+        .as_bytes()
+        .first()
+        .copied()
+        .unwrap_or(b',');
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(profile.has_header)
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(sample.as_bytes());
+    let first_date = reader
+        .records()
+        .next()
+        .and_then(Result::ok)
+        .and_then(|r| r.get(date_col).map(|s| s.trim().to_string()));
+    let strict_date = first_date
+        .is_some_and(|d| NaiveDate::parse_from_str(&d, &profile.mapping.date_format).is_ok());
+
+    strict_date && crate::csv::import_csv(sample.as_bytes(), profile).is_ok()
+}
+
+/// Parse a dropped bank file into normalized transactions.
+pub fn parse_bank_file(
+    file_name: &str,
+    data: &[u8],
+    profiles: &[CsvImportProfile],
+) -> Result<BankFileImport, BankIntakeError> {
+    let kind = BankFileKind::from_path(Path::new(file_name))
+        .ok_or_else(|| BankIntakeError::UnsupportedFile(file_name.to_string()))?;
+
+    parse_with(kind, data, |text| {
+        select_csv_profile(file_name, text, profiles)
+            .ok_or_else(|| BankIntakeError::NoMatchingProfile(file_name.to_string()))
+    })
+}
+
+/// Parse a bank file, reading CSVs with `profile` instead of a saved one.
+/// Used once the user has confirmed the column mapping.
+pub fn parse_bank_file_with_profile(
+    file_name: &str,
+    data: &[u8],
+    profile: &CsvImportProfile,
+) -> Result<BankFileImport, BankIntakeError> {
+    let kind = BankFileKind::from_path(Path::new(file_name))
+        .ok_or_else(|| BankIntakeError::UnsupportedFile(file_name.to_string()))?;
+    parse_with(kind, data, |_| Ok(profile))
+}
+
+fn parse_with<'a>(
+    kind: BankFileKind,
+    data: &[u8],
+    csv_profile: impl FnOnce(&str) -> Result<&'a CsvImportProfile, BankIntakeError>,
+) -> Result<BankFileImport, BankIntakeError> {
+    match kind {
+        BankFileKind::Csv => {
+            let text = std::str::from_utf8(data).map_err(|_| BankIntakeError::Encoding)?;
+            let profile = csv_profile(text)?;
+            let transactions = crate::csv::import_csv(data, profile)?
+                .into_iter()
+                .map(IntakeTransaction::from)
+                .collect();
+            Ok(BankFileImport {
+                kind,
+                profile_name: Some(profile.name.clone()),
+                transactions,
+                ledger_balance: None,
+            })
+        }
+        BankFileKind::Ofx => {
+            // Brokerage statements are queued by their cash movements so the
+            // account can still be reconciled.
+            let (statement_rows, ledger_balance) = if crate::ofx::is_investment_statement(data) {
+                (crate::ofx::parse_investment(data)?.cash_flows(), None)
+            } else {
+                let statement = crate::ofx::parse(data)?;
+                let balance = statement.ledger_balance.map(|b| (statement.end_date, b));
+                (statement.transactions, balance)
+            };
+            let transactions = statement_rows
+                .into_iter()
+                .map(|t| IntakeTransaction {
+                    date: t.date,
+                    description: t
+                        .name
+                        .clone()
+                        .or_else(|| t.memo.clone())
+                        .unwrap_or_default(),
+                    amount_cents: t.amount,
+                    debit_cents: None,
+                    credit_cents: None,
+                    memo: t.memo,
+                    source_id: Some(t.fit_id),
+                    suggested_account: None,
+                })
+                .collect();
+            Ok(BankFileImport {
+                kind,
+                profile_name: None,
+                transactions,
+                ledger_balance,
+            })
+        }
+        BankFileKind::Qif => {
+            let text = String::from_utf8_lossy(data);
+            let transactions = crate::qif::parse(&text)?
+                .into_iter()
+                .map(|t| IntakeTransaction {
+                    date: t.date,
+                    description: t
+                        .payee
+                        .clone()
+                        .or_else(|| t.memo.clone())
+                        .unwrap_or_default(),
+                    amount_cents: t.amount,
+                    debit_cents: None,
+                    credit_cents: None,
+                    memo: t.memo,
+                    source_id: None,
+                    suggested_account: None,
+                })
+                .collect();
+            Ok(BankFileImport {
+                kind,
+                profile_name: None,
+                transactions,
+                ledger_balance: None,
+            })
+        }
+    }
+}
+
+/// Move `path` into `<intake_dir>/<subdir>/`, adding a numeric suffix if a
+/// file of the same name was already filed there. Returns the new location.
+pub fn move_to_subfolder(path: &Path, subdir: &str) -> std::io::Result<PathBuf> {
+    let parent = path.parent().unwrap_or_else(|| Path::new("."));
+    let dest_dir = parent.join(subdir);
+    std::fs::create_dir_all(&dest_dir)?;
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "no file name"))?;
+    let mut dest = dest_dir.join(file_name);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("file");
+    let ext = path.extension().and_then(|s| s.to_str());
+    let mut n = 1;
+    while dest.exists() {
+        let name = match ext {
+            Some(ext) => format!("{stem}-{n}.{ext}"),
+            None => format!("{stem}-{n}"),
+        };
+        dest = dest_dir.join(name);
+        n += 1;
+    }
+    std::fs::rename(path, &dest)?;
+    Ok(dest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csv::CsvColumnMapping;
+
+    fn profile(name: &str, date_format: &str, delimiter: &str) -> CsvImportProfile {
+        CsvImportProfile {
+            id: None,
+            name: name.to_string(),
+            mapping: CsvColumnMapping {
+                date_column: Some(0),
+                description_column: Some(1),
+                amount_column: Some(2),
+                debit_column: None,
+                credit_column: None,
+                memo_column: None,
+                date_format: date_format.to_string(),
+            },
+            has_header: true,
+            delimiter: delimiter.to_string(),
+        }
+    }
+
+    const US_CSV: &str =
+        "Date,Description,Amount\n01/15/2026,COFFEE,-4.50\n01/16/2026,CLIENT,100.00\n";
+
+    // ── BankFileKind ─────────────────────────────────────────────────────────
+
+    #[test]
+    fn kind_from_extension() {
+        assert_eq!(
+            BankFileKind::from_path(Path::new("a.CSV")),
+            Some(BankFileKind::Csv)
+        );
+        assert_eq!(
+            BankFileKind::from_path(Path::new("a.qfx")),
+            Some(BankFileKind::Ofx)
+        );
+        assert_eq!(
+            BankFileKind::from_path(Path::new("a.qif")),
+            Some(BankFileKind::Qif)
+        );
+        assert_eq!(BankFileKind::from_path(Path::new("a.jpg")), None);
+        assert_eq!(BankFileKind::from_path(Path::new("noext")), None);
+    }
+
+    // ── profile selection ────────────────────────────────────────────────────
+
+    #[test]
+    fn filename_match_wins() {
+        let profiles = vec![
+            profile("Amex Gold", "%m/%d/%Y", ","),
+            profile("Chase Checking", "%m/%d/%Y", ","),
+        ];
+        let p = select_csv_profile("chase_checking_2026-01.csv", US_CSV, &profiles).unwrap();
+        assert_eq!(p.name, "Chase Checking");
+    }
+
+    #[test]
+    fn fingerprint_uses_strict_date_format() {
+        let profiles = vec![
+            profile("ISO Bank", "%Y-%m-%d", ","),
+            profile("US Bank", "%m/%d/%Y", ","),
+        ];
+        let p = select_csv_profile("export.csv", US_CSV, &profiles).unwrap();
+        assert_eq!(p.name, "US Bank");
+    }
+
+    #[test]
+    fn fingerprint_respects_delimiter() {
+        let profiles = vec![profile("Semicolon", "%m/%d/%Y", ";")];
+        assert!(select_csv_profile("export.csv", US_CSV, &profiles).is_none());
+    }
+
+    // ── parse_bank_file ──────────────────────────────────────────────────────
+
+    #[test]
+    fn csv_file_parsed_with_selected_profile() {
+        let profiles = vec![profile("US Bank", "%m/%d/%Y", ",")];
+        let out = parse_bank_file("export.csv", US_CSV.as_bytes(), &profiles).unwrap();
+        assert_eq!(out.kind, BankFileKind::Csv);
+        assert_eq!(out.profile_name.as_deref(), Some("US Bank"));
+        assert_eq!(out.transactions.len(), 2);
+        assert_eq!(out.transactions[0].amount_cents, -450);
+    }
+
+    #[test]
+    fn csv_without_matching_profile_is_an_error() {
+        let out = parse_bank_file("export.csv", US_CSV.as_bytes(), &[]);
+        assert!(matches!(out, Err(BankIntakeError::NoMatchingProfile(_))));
+    }
+
+    #[test]
+    fn explicit_profile_overrides_selection() {
+        let data = "Posted;Payee;Value\n2026-01-15;COFFEE;-4.50\n";
+        let mut explicit = profile("Mapped", "%Y-%m-%d", ";");
+        explicit.name = "Unrelated".to_string();
+        let out = parse_bank_file_with_profile("export.csv", data.as_bytes(), &explicit).unwrap();
+        assert_eq!(out.profile_name.as_deref(), Some("Unrelated"));
+        assert_eq!(out.transactions[0].amount_cents, -450);
+    }
+
+    #[test]
+    fn qif_file_parsed() {
+        let data = b"!Type:Bank\nD01/15/2026\nT-12.00\nPLUNCH\n^\n";
+        let out = parse_bank_file("stmt.qif", data, &[]).unwrap();
+        assert_eq!(out.kind, BankFileKind::Qif);
+        assert_eq!(out.transactions[0].description, "LUNCH");
+        assert_eq!(out.transactions[0].amount_cents, -1200);
+    }
+
+    #[test]
+    fn bank_ofx_keeps_closing_balance() {
+        let data = b"<OFX>
+<STMTRS>
+<BANKACCTFROM>
+<ACCTID>123
+</BANKACCTFROM>
+<BANKTRANLIST>
+<DTSTART>20260101
+<DTEND>20260131
+<STMTTRN>
+<TRNTYPE>DEBIT
+<DTPOSTED>20260115
+<TRNAMT>-4.50
+<FITID>T1
+<NAME>COFFEE
+</STMTTRN>
+</BANKTRANLIST>
+<LEDGERBAL>
+<BALAMT>1234.56
+<DTASOF>20260131
+</LEDGERBAL>
+</STMTRS>
+</OFX>";
+        let out = parse_bank_file("stmt.ofx", data, &[]).unwrap();
+        assert_eq!(out.transactions.len(), 1);
+        assert_eq!(
+            out.ledger_balance,
+            Some((NaiveDate::from_ymd_opt(2026, 1, 31).unwrap(), 123_456))
+        );
+    }
+
+    #[test]
+    fn brokerage_ofx_queues_cash_flows() {
+        let data = b"<OFX><INVSTMTRS><DTASOF>20260131<INVACCTFROM><ACCTID>Z1</INVACCTFROM>\
+            <INVTRANLIST><INCOME><INVTRAN><FITID>D1<DTTRADE>20260115</INVTRAN>\
+            <SECID><UNIQUEID>037833100</SECID><INCOMETYPE>DIV<TOTAL>2.40</INCOME>\
+            </INVTRANLIST></INVSTMTRS></OFX>";
+        let out = parse_bank_file("brokerage.qfx", data, &[]).unwrap();
+        assert_eq!(out.transactions.len(), 1);
+        assert_eq!(out.transactions[0].description, "DIV 037833100");
+        assert_eq!(out.transactions[0].amount_cents, 240);
+        assert_eq!(out.transactions[0].source_id.as_deref(), Some("D1"));
+    }
+
+    #[test]
+    fn unsupported_extension_rejected() {
+        assert!(matches!(
+            parse_bank_file("photo.png", b"", &[]),
+            Err(BankIntakeError::UnsupportedFile(_))
+        ));
+    }
+
+    // ── move_to_subfolder ────────────────────────────────────────────────────
+
+    #[test]
+    fn move_avoids_overwriting() {
+        let dir = tempfile::tempdir().unwrap();
+        for _ in 0..2 {
+            let src = dir.path().join("stmt.csv");
+            std::fs::write(&src, "x").unwrap();
+            move_to_subfolder(&src, PROCESSED_DIR).unwrap();
+        }
+        let processed = dir.path().join(PROCESSED_DIR);
+        assert!(processed.join("stmt.csv").exists());
+        assert!(processed.join("stmt-1.csv").exists());
+        assert!(!dir.path().join("stmt.csv").exists());
+    }
+}
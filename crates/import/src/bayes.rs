@@ -0,0 +1,244 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::rule_suggest::CategorizedExample;
+use crate::rules::{CategorizableTransaction, CategoryRuleEngine};
+use crate::util::description_tokens;
+
+/// A probabilistic account proposal for a transaction no rule matched.
+#[derive(Debug, Clone, Serialize)]
+pub struct BayesPrediction {
+    pub account_code: String,
+    /// Posterior probability of `account_code` among all trained accounts.
+    pub confidence: f64,
+}
+
+#[derive(Debug, Default)]
+struct ClassStats {
+    docs: usize,
+    word_total: usize,
+    words: HashMap<String, usize>,
+    amount_buckets: HashMap<u32, usize>,
+}
+
+/// Multinomial naive Bayes over description words, with a coarse amount
+/// bucket as a separate categorical feature, trained on the user's own
+/// categorization history.
+///
+/// Predictions are meant for the review queue; callers should never post them
+/// without confirmation.
+#[derive(Debug, Default)]
+pub struct NaiveBayesCategorizer {
+    classes: HashMap<String, ClassStats>,
+    vocabulary: HashSet<String>,
+    buckets_seen: HashSet<u32>,
+    total_docs: usize,
+}
+
+impl NaiveBayesCategorizer {
+    pub fn train(examples: &[CategorizedExample]) -> Self {
+        let mut model = Self::default();
+        for ex in examples {
+            let words = description_tokens(&ex.description);
+            if words.is_empty() {
+                continue;
+            }
+            let bucket = amount_bucket(ex.amount_cents);
+            let class = model.classes.entry(ex.account_code.clone()).or_default();
+            class.docs += 1;
+            *class.amount_buckets.entry(bucket).or_default() += 1;
+            for w in words {
+                class.word_total += 1;
+                *class.words.entry(w.clone()).or_default() += 1;
+                model.vocabulary.insert(w);
+            }
+            model.buckets_seen.insert(bucket);
+            model.total_docs += 1;
+        }
+        model
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total_docs == 0
+    }
+
+    /// Most probable account for `tx`, or `None` when the model has never seen
+    /// any of its words (the amount alone is not enough evidence).
+    pub fn predict(&self, tx: &CategorizableTransaction) -> Option<BayesPrediction> {
+        if self.is_empty() {
+            return None;
+        }
+        let words = description_tokens(&tx.description);
+        if !words.iter().any(|w| self.vocabulary.contains(w)) {
+            return None;
+        }
+        let bucket = amount_bucket(tx.amount_cents);
+
+        let vocab = self.vocabulary.len() as f64;
+        let buckets = self.buckets_seen.len() as f64;
+        let scores: Vec<(&str, f64)> = self
+            .classes
+            .iter()
+            .map(|(code, class)| {
+                let prior = (class.docs as f64 / self.total_docs as f64).ln();
+                let denom = class.word_total as f64 + vocab;
+                let words_ll: f64 = words
+                    .iter()
+                    .map(|w| {
+                        let count = class.words.get(w).copied().unwrap_or(0) as f64;
+                        ((count + 1.0) / denom).ln()
+                    })
+                    .sum();
+                let in_bucket = class.amount_buckets.get(&bucket).copied().unwrap_or(0) as f64;
+                let amount_ll = ((in_bucket + 1.0) / (class.docs as f64 + buckets)).ln();
+                (code.as_str(), prior + words_ll + amount_ll)
+            })
+            .collect();
+
+        // Normalise log scores into posteriors (log-sum-exp for stability).
+        let max = scores
+            .iter()
+            .map(|(_, s)| *s)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let norm: f64 = scores.iter().map(|(_, s)| (s - max).exp()).sum();
+        let (code, best) = scores
+            .iter()
+            .max_by(|a, b| a.1.total_cmp(&b.1).then_with(|| b.0.cmp(a.0)))?;
+
+        Some(BayesPrediction {
+            account_code: code.to_string(),
+            confidence: (best - max).exp() / norm,
+        })
+    }
+
+    /// Predict accounts for the transactions `engine` leaves uncategorized,
+    /// keeping only proposals at or above `min_confidence`.
+    pub fn suggest_unmatched(
+        &self,
+        engine: &CategoryRuleEngine,
+        transactions: &[CategorizableTransaction],
+        min_confidence: f64,
+    ) -> Vec<(usize, BayesPrediction)> {
+        transactions
+            .iter()
+            .enumerate()
+            .filter(|(_, tx)| engine.find_matching_rule(tx).is_none())
+            .filter_map(|(idx, tx)| self.predict(tx).map(|p| (idx, p)))
+            .filter(|(_, p)| p.confidence >= min_confidence)
+            .collect()
+    }
+}
+
+/// Order of magnitude of the dollar amount, so a $4 and a $400 charge at the
+/// same merchant can still lean towards different accounts.
+fn amount_bucket(amount_cents: i64) -> u32 {
+    let dollars = amount_cents.unsigned_abs() / 100;
+    dollars.checked_ilog10().map_or(0, |d| d + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::{CategoryRule, MatchType};
+
+    fn ex(desc: &str, amount_cents: i64, account: &str) -> CategorizedExample {
+        CategorizedExample {
+            description: desc.to_string(),
+            amount_cents,
+            account_code: account.to_string(),
+        }
+    }
+
+    fn tx(desc: &str, amount_cents: i64) -> CategorizableTransaction {
+        CategorizableTransaction {
+            date: chrono::NaiveDate::from_ymd_opt(2024, 1, 15).unwrap(),
+            description: desc.to_string(),
+            amount_cents,
+            memo: None,
+        }
+    }
+
+    fn history() -> Vec<CategorizedExample> {
+        vec![
+            ex("STARBUCKS STORE 1001", 650, "5020"),
+            ex("BLUE BOTTLE COFFEE", 550, "5020"),
+            ex("PEETS COFFEE", 475, "5020"),
+            ex("SHELL OIL 5551", 4500, "5060"),
+            ex("CHEVRON GAS", 5200, "5060"),
+            ex("EXXON GAS STATION", 3900, "5060"),
+            ex("GITHUB SUBSCRIPTION", 2100, "5100"),
+        ]
+    }
+
+    // ── training ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn empty_model_predicts_nothing() {
+        let model = NaiveBayesCategorizer::train(&[]);
+        assert!(model.is_empty());
+        assert!(model.predict(&tx("STARBUCKS", 500)).is_none());
+    }
+
+    #[test]
+    fn descriptions_without_words_are_skipped() {
+        let model = NaiveBayesCategorizer::train(&[ex("#12345", 100, "5000")]);
+        assert!(model.is_empty());
+    }
+
+    // ── prediction ───────────────────────────────────────────────────────────
+
+    #[test]
+    fn predicts_account_from_shared_words() {
+        let model = NaiveBayesCategorizer::train(&history());
+        let p = model.predict(&tx("LOCAL COFFEE ROASTERS", 600)).unwrap();
+        assert_eq!(p.account_code, "5020");
+        assert!(p.confidence > 0.5 && p.confidence <= 1.0);
+
+        let p = model.predict(&tx("ARCO GAS #88", 4000)).unwrap();
+        assert_eq!(p.account_code, "5060");
+    }
+
+    #[test]
+    fn unknown_words_yield_no_prediction() {
+        let model = NaiveBayesCategorizer::train(&history());
+        assert!(model.predict(&tx("ZZZ UNSEEN MERCHANT", 600)).is_none());
+    }
+
+    #[test]
+    fn confidences_form_a_distribution() {
+        let model = NaiveBayesCategorizer::train(&history());
+        let p = model.predict(&tx("GITHUB SUBSCRIPTION", 2100)).unwrap();
+        assert_eq!(p.account_code, "5100");
+        assert!(p.confidence < 1.0);
+    }
+
+    // ── suggest_unmatched ────────────────────────────────────────────────────
+
+    #[test]
+    fn rule_matches_take_precedence() {
+        let model = NaiveBayesCategorizer::train(&history());
+        let engine = CategoryRuleEngine::new(vec![CategoryRule {
+            name: "Coffee".to_string(),
+            priority: 1,
+            pattern: "coffee".to_string(),
+            match_type: MatchType::Contains,
+            account_code: "5020".to_string(),
+            amount_min_cents: None,
+            amount_max_cents: None,
+        }]);
+        let txs = vec![tx("CORNER COFFEE", 500), tx("CHEVRON GAS 12", 4800)];
+        let out = model.suggest_unmatched(&engine, &txs, 0.0);
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].0, 1);
+        assert_eq!(out[0].1.account_code, "5060");
+    }
+
+    #[test]
+    fn low_confidence_predictions_are_filtered() {
+        let model = NaiveBayesCategorizer::train(&history());
+        let engine = CategoryRuleEngine::new(vec![]);
+        let out = model.suggest_unmatched(&engine, &[tx("GAS COFFEE", 1000)], 0.99);
+        assert!(out.is_empty());
+    }
+}
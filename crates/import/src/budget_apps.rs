@@ -0,0 +1,483 @@
+//! Import transaction history exported from Mint, YNAB and Quicken.
+//!
+//! Each app exports every account in one CSV, with the category the user
+//! gave each transaction. Rows are read into [`BudgetAppTransaction`]s,
+//! grouped by the app's account so each can be queued as its own bank
+//! import batch, and the app's category is turned into a suggested aequi
+//! account where an obvious one exists.
+
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::bank_intake::IntakeTransaction;
+use crate::csv::{parse_amount, parse_date, CsvError};
+
+/// Rows searched for the header; Quicken puts a report title above it.
+const HEADER_SEARCH_LINES: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetApp {
+    Mint,
+    Ynab,
+    Quicken,
+}
+
+impl BudgetApp {
+    pub fn name(self) -> &'static str {
+        match self {
+            BudgetApp::Mint => "Mint",
+            BudgetApp::Ynab => "YNAB",
+            BudgetApp::Quicken => "Quicken",
+        }
+    }
+
+    /// Recognize an export by its header row.
+    pub fn detect(headers: &[String]) -> Option<Self> {
+        let has = |name: &str| headers.iter().any(|h| h.eq_ignore_ascii_case(name));
+        if has("Transaction Type") && has("Account Name") && has("Original Description") {
+            Some(BudgetApp::Mint)
+        } else if has("Outflow") && has("Inflow") && has("Payee") {
+            Some(BudgetApp::Ynab)
+        } else if has("Date")
+            && has("Account")
+            && has("Category")
+            && has("Amount")
+            && (has("Payee") || has("Description"))
+        {
+            Some(BudgetApp::Quicken)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum BudgetAppError {
+    #[error("CSV error: {0}")]
+    Csv(#[from] csv::Error),
+    #[error(transparent)]
+    Value(#[from] CsvError),
+    #[error("Not a Mint, YNAB or Quicken export")]
+    UnknownFormat,
+    #[error("Missing column: {0}")]
+    MissingColumn(String),
+    #[error("File is not valid UTF-8")]
+    Encoding,
+}
+
+/// One row of an export.
+#[derive(Debug, Clone, Serialize)]
+pub struct BudgetAppTransaction {
+    pub date: NaiveDate,
+    /// The app's name for the account the row belongs to.
+    pub account: String,
+    pub payee: String,
+    pub category: Option<String>,
+    /// Positive for money in, negative for money out.
+    pub amount_cents: i64,
+    pub memo: Option<String>,
+    /// A move between two of the app's accounts. It shows up in both, so it
+    /// is given no suggested account.
+    pub transfer: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct BudgetAppExport {
+    pub app: BudgetApp,
+    pub transactions: Vec<BudgetAppTransaction>,
+}
+
+impl BudgetAppExport {
+    /// The app's accounts, in the order they first appear.
+    pub fn accounts(&self) -> Vec<&str> {
+        let mut accounts: Vec<&str> = Vec::new();
+        for tx in &self.transactions {
+            if !accounts.contains(&tx.account.as_str()) {
+                accounts.push(&tx.account);
+            }
+        }
+        accounts
+    }
+
+    /// Rows of `account` ready to queue as a bank import, each with a
+    /// source id built from its contents so importing an overlapping export
+    /// again skips rows already brought in.
+    pub fn intake_transactions(&self, account: &str) -> Vec<IntakeTransaction> {
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        self.transactions
+            .iter()
+            .filter(|t| t.account == account)
+            .map(|t| {
+                let key = format!(
+                    "{}:{}|{}|{}|{}",
+                    self.app.name().to_lowercase(),
+                    t.account,
+                    t.date,
+                    t.amount_cents,
+                    t.payee
+                );
+                let n = seen.entry(key.clone()).or_default();
+                *n += 1;
+                let memo = match (&t.memo, &t.category) {
+                    // Keep a category that couldn't be mapped where the
+                    // reviewer will see it.
+                    (memo, Some(category))
+                        if !t.transfer && suggest_account(category).is_none() =>
+                    {
+                        Some(match memo {
+                            Some(memo) => format!("{category}: {memo}"),
+                            None => category.clone(),
+                        })
+                    }
+                    (memo, _) => memo.clone(),
+                };
+                IntakeTransaction {
+                    date: t.date,
+                    description: t.payee.clone(),
+                    amount_cents: t.amount_cents,
+                    debit_cents: None,
+                    credit_cents: None,
+                    memo,
+                    source_id: Some(format!("{key}#{n}")),
+                    suggested_account: t
+                        .category
+                        .as_deref()
+                        .filter(|_| !t.transfer)
+                        .and_then(suggest_account)
+                        .map(str::to_string),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Categories that point at one of the default accounts, checked in order
+/// against the lowercased category, so more specific phrases come first.
+const CATEGORY_ACCOUNTS: &[(&str, &str)] = &[
+    ("rental car", "5120"),
+    ("gas & electric", "5130"),
+    ("utilities", "5130"),
+    ("electric", "5130"),
+    ("water", "5130"),
+    ("gas & fuel", "5140"),
+    ("fuel", "5140"),
+    ("parking", "5140"),
+    ("auto", "5140"),
+    ("vehicle", "5140"),
+    ("car ", "5140"),
+    ("bank fee", "5010"),
+    ("atm fee", "5010"),
+    ("service fee", "5010"),
+    ("finance charge", "5010"),
+    ("fees & charges", "5010"),
+    ("restaurant", "5020"),
+    ("dining", "5020"),
+    ("meals", "5020"),
+    ("coffee", "5020"),
+    ("advertising", "5000"),
+    ("marketing", "5000"),
+    ("education", "5030"),
+    ("tuition", "5030"),
+    ("training", "5030"),
+    ("books", "5030"),
+    ("computer", "5040"),
+    ("electronics", "5040"),
+    ("equipment", "5040"),
+    ("insurance", "5060"),
+    ("internet", "5070"),
+    ("phone", "5070"),
+    ("legal", "5080"),
+    ("accounting", "5080"),
+    ("tax preparation", "5080"),
+    ("professional", "5080"),
+    ("office", "5100"),
+    ("postage", "5100"),
+    ("shipping", "5100"),
+    ("software", "5110"),
+    ("subscription", "5110"),
+    ("travel", "5120"),
+    ("hotel", "5120"),
+    ("airfare", "5120"),
+    ("air travel", "5120"),
+    ("consulting", "4000"),
+    ("freelance", "4000"),
+    ("business income", "4000"),
+    ("sales", "4010"),
+    ("interest income", "4020"),
+    ("dividend", "4020"),
+    ("paycheck", "4020"),
+    ("income", "4020"),
+    ("ready to assign", "4020"),
+    ("to be budgeted", "4020"),
+];
+
+/// Default account code suggested for an app category, if any fits.
+pub fn suggest_account(category: &str) -> Option<&'static str> {
+    // Padded so "car " matches a final word without matching "card".
+    let category = format!("{} ", category.to_lowercase());
+    CATEGORY_ACCOUNTS
+        .iter()
+        .find(|(phrase, _)| category.contains(phrase))
+        .map(|(_, code)| *code)
+}
+
+/// Read an export. `app` picks the format; without it the header decides.
+pub fn parse_export(
+    data: &[u8],
+    app: Option<BudgetApp>,
+) -> Result<BudgetAppExport, BudgetAppError> {
+    let text = std::str::from_utf8(data).map_err(|_| BudgetAppError::Encoding)?;
+    let text = text.trim_start_matches('\u{feff}');
+
+    // The header is the first line that names a known format.
+    let lines: Vec<&str> = text.lines().take(HEADER_SEARCH_LINES).collect();
+    let (skip, detected) = lines
+        .iter()
+        .enumerate()
+        .find_map(|(i, line)| {
+            let headers = header_fields(line);
+            BudgetApp::detect(&headers).map(|found| (i, found))
+        })
+        .ok_or(BudgetAppError::UnknownFormat)?;
+    let app = app.unwrap_or(detected);
+    let body: String = text.lines().skip(skip).flat_map(|l| [l, "\n"]).collect();
+
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(true)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(body.as_bytes());
+    let headers: Vec<String> = reader.headers()?.iter().map(str::to_string).collect();
+    let col = |name: &str| headers.iter().position(|h| h.eq_ignore_ascii_case(name));
+    let need =
+        |name: &str| col(name).ok_or_else(|| BudgetAppError::MissingColumn(name.to_string()));
+
+    let mut transactions = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let get = |c: Option<usize>| {
+            c.and_then(|c| record.get(c))
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+        };
+        let tx = match app {
+            BudgetApp::Mint => {
+                let Some(date) = get(Some(need("Date")?)) else {
+                    continue;
+                };
+                let amount = parse_amount(get(Some(need("Amount")?)).unwrap_or("0"))?.abs();
+                let debit = get(Some(need("Transaction Type")?))
+                    .is_some_and(|t| t.eq_ignore_ascii_case("debit"));
+                let category = get(col("Category")).map(str::to_string);
+                BudgetAppTransaction {
+                    date: parse_date(date, "%m/%d/%Y")?,
+                    account: get(Some(need("Account Name")?))
+                        .unwrap_or_default()
+                        .to_string(),
+                    payee: get(col("Description"))
+                        .or(get(col("Original Description")))
+                        .unwrap_or_default()
+                        .to_string(),
+                    transfer: category.as_deref().is_some_and(|c| {
+                        let c = c.to_lowercase();
+                        c.starts_with("transfer") || c == "credit card payment"
+                    }),
+                    category,
+                    amount_cents: if debit { -amount } else { amount },
+                    memo: get(col("Notes")).map(str::to_string),
+                }
+            }
+            BudgetApp::Ynab => {
+                let Some(date) = get(Some(need("Date")?)) else {
+                    continue;
+                };
+                let outflow = get(Some(need("Outflow")?)).map(parse_amount).transpose()?;
+                let inflow = get(Some(need("Inflow")?)).map(parse_amount).transpose()?;
+                let payee = get(Some(need("Payee")?)).unwrap_or_default().to_string();
+                BudgetAppTransaction {
+                    date: parse_date(date, "%m/%d/%Y")?,
+                    account: get(Some(need("Account")?)).unwrap_or_default().to_string(),
+                    transfer: payee.to_lowercase().starts_with("transfer :"),
+                    category: get(col("Category Group/Category"))
+                        .or(get(col("Category")))
+                        .map(str::to_string),
+                    amount_cents: inflow.unwrap_or(0) - outflow.unwrap_or(0),
+                    memo: get(col("Memo")).map(str::to_string),
+                    payee,
+                }
+            }
+            BudgetApp::Quicken => {
+                let amount = get(Some(need("Amount")?));
+                let (Some(date), Some(amount)) = (get(Some(need("Date")?)), amount) else {
+                    // Blank lines and report subtotals.
+                    continue;
+                };
+                let category = get(Some(need("Category")?)).map(str::to_string);
+                BudgetAppTransaction {
+                    date: parse_quicken_date(date)?,
+                    account: get(Some(need("Account")?)).unwrap_or_default().to_string(),
+                    payee: get(col("Payee"))
+                        .or(get(col("Description")))
+                        .unwrap_or_default()
+                        .to_string(),
+                    transfer: category.as_deref().is_some_and(|c| {
+                        (c.starts_with('[') && c.ends_with(']'))
+                            || c.to_lowercase().starts_with("transfer")
+                    }),
+                    category,
+                    amount_cents: parse_amount(amount)?,
+                    memo: get(col("Memo")).or(get(col("Notes"))).map(str::to_string),
+                }
+            }
+        };
+        transactions.push(tx);
+    }
+
+    Ok(BudgetAppExport { app, transactions })
+}
+
+fn header_fields(line: &str) -> Vec<String> {
+    csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(line.as_bytes())
+        .records()
+        .next()
+        .and_then(Result::ok)
+        .map(|r| r.iter().map(|f| f.trim().to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// Quicken writes two-digit years in some reports.
+fn parse_quicken_date(s: &str) -> Result<NaiveDate, CsvError> {
+    let two_digit_year = s.rsplit('/').next().is_some_and(|y| y.len() == 2);
+    parse_date(
+        s,
+        if two_digit_year {
+            "%m/%d/%y"
+        } else {
+            "%m/%d/%Y"
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINT: &str = "\"Date\",\"Description\",\"Original Description\",\"Amount\",\"Transaction Type\",\"Category\",\"Account Name\",\"Labels\",\"Notes\"
+\"1/15/2024\",\"Shell\",\"SHELL OIL 5744\",\"42.10\",\"debit\",\"Gas & Fuel\",\"Chase Checking\",\"\",\"\"
+\"1/16/2024\",\"Acme Corp\",\"ACME PAYROLL\",\"2,500.00\",\"credit\",\"Paycheck\",\"Chase Checking\",\"\",\"January\"
+\"1/17/2024\",\"Transfer\",\"ONLINE TRANSFER\",\"500.00\",\"debit\",\"Transfer\",\"Chase Checking\",\"\",\"\"
+\"1/18/2024\",\"Etsy\",\"ETSY.COM\",\"18.00\",\"debit\",\"Hobbies\",\"Visa\",\"\",\"\"
+";
+
+    const YNAB: &str = "\u{feff}\"Account\",\"Flag\",\"Date\",\"Payee\",\"Category Group/Category\",\"Category Group\",\"Category\",\"Memo\",\"Outflow\",\"Inflow\",\"Cleared\"
+\"Checking\",\"\",\"03/02/2025\",\"Comcast\",\"Monthly Bills: Internet\",\"Monthly Bills\",\"Internet\",\"\",\"$79.99\",\"$0.00\",\"Cleared\"
+\"Checking\",\"\",\"03/03/2025\",\"Transfer : Savings\",\"\",\"\",\"\",\"\",\"$100.00\",\"$0.00\",\"Cleared\"
+\"Savings\",\"\",\"03/03/2025\",\"Transfer : Checking\",\"\",\"\",\"\",\"\",\"$0.00\",\"$100.00\",\"Cleared\"
+";
+
+    const QUICKEN: &str = "Transaction Report
+1/1/2023 through 12/31/2023
+
+Date,Account,Num,Payee,Memo,Category,Amount
+1/5/23,Checking,101,Staples,toner,Office Supplies,(64.20)
+1/9/23,Checking,,Transfer to Savings,,[Savings],-200.00
+,,,,,,
+,,,,Total,,(264.20)
+";
+
+    #[test]
+    fn reads_mint_exports() {
+        let export = parse_export(MINT.as_bytes(), None).unwrap();
+        assert_eq!(export.app, BudgetApp::Mint);
+        assert_eq!(export.accounts(), vec!["Chase Checking", "Visa"]);
+
+        let rows = export.intake_transactions("Chase Checking");
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].amount_cents, -4210);
+        assert_eq!(rows[0].suggested_account.as_deref(), Some("5140"));
+        assert_eq!(rows[1].amount_cents, 250_000);
+        assert_eq!(rows[1].suggested_account.as_deref(), Some("4020"));
+        assert_eq!(rows[1].memo.as_deref(), Some("January"));
+        assert_eq!(rows[2].suggested_account, None);
+        assert_eq!(rows[2].memo, None);
+
+        // Unmapped categories are kept in the memo.
+        let visa = export.intake_transactions("Visa");
+        assert_eq!(visa[0].suggested_account, None);
+        assert_eq!(visa[0].memo.as_deref(), Some("Hobbies"));
+    }
+
+    #[test]
+    fn reads_ynab_exports() {
+        let export = parse_export(YNAB.as_bytes(), None).unwrap();
+        assert_eq!(export.app, BudgetApp::Ynab);
+        let rows = export.intake_transactions("Checking");
+        assert_eq!(rows[0].amount_cents, -7999);
+        assert_eq!(rows[0].suggested_account.as_deref(), Some("5070"));
+        assert!(export.transactions[1].transfer);
+        assert_eq!(
+            export.intake_transactions("Savings")[0].amount_cents,
+            10_000
+        );
+    }
+
+    #[test]
+    fn reads_quicken_reports() {
+        let export = parse_export(QUICKEN.as_bytes(), None).unwrap();
+        assert_eq!(export.app, BudgetApp::Quicken);
+        assert_eq!(export.transactions.len(), 2);
+        let tx = &export.transactions[0];
+        assert_eq!(tx.date, NaiveDate::from_ymd_opt(2023, 1, 5).unwrap());
+        assert_eq!(tx.amount_cents, -6420);
+        assert_eq!(tx.memo.as_deref(), Some("toner"));
+        assert!(export.transactions[1].transfer);
+        assert_eq!(
+            export.intake_transactions("Checking")[0]
+                .suggested_account
+                .as_deref(),
+            Some("5100")
+        );
+    }
+
+    #[test]
+    fn identical_rows_get_distinct_source_ids() {
+        let csv = format!(
+            "{}\"1/18/2024\",\"Etsy\",\"ETSY.COM\",\"18.00\",\"debit\",\"Hobbies\",\"Visa\",\"\",\"\"\n",
+            MINT
+        );
+        let export = parse_export(csv.as_bytes(), None).unwrap();
+        let visa = export.intake_transactions("Visa");
+        assert_eq!(visa.len(), 2);
+        assert_ne!(visa[0].source_id, visa[1].source_id);
+        // The same export read again produces the same ids.
+        let again = parse_export(csv.as_bytes(), None).unwrap();
+        assert_eq!(
+            again.intake_transactions("Visa")[1].source_id,
+            visa[1].source_id
+        );
+    }
+
+    #[test]
+    fn suggestions_prefer_specific_phrases() {
+        assert_eq!(suggest_account("Utilities:Gas & Electric"), Some("5130"));
+        assert_eq!(suggest_account("Auto & Transport:Gas & Fuel"), Some("5140"));
+        assert_eq!(suggest_account("Travel:Rental Car & Taxi"), Some("5120"));
+        assert_eq!(suggest_account("Credit Card Payment"), None);
+        assert_eq!(suggest_account("Groceries"), None);
+    }
+
+    #[test]
+    fn unknown_files_are_rejected() {
+        assert!(matches!(
+            parse_export(b"Date,Amount\n1/1/2024,5.00\n", None),
+            Err(BudgetAppError::UnknownFormat)
+        ));
+    }
+}
@@ -1,56 +1,13 @@
 use chrono::NaiveDate;
 use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
 use std::io::Read;
+use std::ops::ControlFlow;
 use std::str::FromStr;
 use thiserror::Error;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CsvColumnMapping {
-    pub date_column: Option<usize>,
-    pub description_column: Option<usize>,
-    pub amount_column: Option<usize>,
-    pub debit_column: Option<usize>,
-    pub credit_column: Option<usize>,
-    pub memo_column: Option<usize>,
-    pub date_format: String,
-}
-
-impl Default for CsvColumnMapping {
-    fn default() -> Self {
-        Self {
-            date_column: None,
-            description_column: None,
-            amount_column: None,
-            debit_column: None,
-            credit_column: None,
-            memo_column: None,
-            date_format: "%Y-%m-%d".to_string(),
-        }
-    }
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CsvImportProfile {
-    pub id: Option<i64>,
-    pub name: String,
-    pub mapping: CsvColumnMapping,
-    pub has_header: bool,
-    pub delimiter: String,
-}
-
-impl Default for CsvImportProfile {
-    fn default() -> Self {
-        Self {
-            id: None,
-            name: "Unnamed Profile".to_string(),
-            mapping: CsvColumnMapping::default(),
-            has_header: true,
-            delimiter: ",".to_string(),
-        }
-    }
-}
+pub use aequi_core::import::csv_profile::{CsvColumnMapping, CsvImportProfile};
 
 #[derive(Debug, Clone)]
 pub struct CsvTransaction {
@@ -76,6 +33,8 @@ pub enum CsvError {
     InvalidAmount(String),
     #[error("No data rows")]
     NoDataRows,
+    #[error("Import cancelled")]
+    Cancelled,
 }
 
 pub struct CsvImporter;
@@ -87,75 +46,14 @@ impl CsvImporter {
     ) -> Result<Vec<CsvTransaction>, CsvError> {
         let mut transactions = Vec::new();
         const MAX_CSV_RECORDS: usize = 100_000;
-        let mapping = &profile.mapping;
 
         for result in reader.records() {
             if transactions.len() >= MAX_CSV_RECORDS {
                 break; // Safety limit
             }
-
-            let record = result?;
-
-            if record.is_empty() {
-                continue;
+            if let Some(t) = parse_record(&result?, &profile.mapping)? {
+                transactions.push(t);
             }
-
-            let date = if let Some(col) = mapping.date_column {
-                let field = record
-                    .get(col)
-                    .ok_or_else(|| CsvError::MissingColumn(format!("date_column {}", col)))?;
-                parse_date(field, &mapping.date_format)?
-            } else {
-                continue;
-            };
-
-            let description = if let Some(col) = mapping.description_column {
-                record.get(col).unwrap_or_default().to_string()
-            } else {
-                String::new()
-            };
-
-            let (amount, debit, credit) = if let Some(col) = mapping.amount_column {
-                let field = record.get(col).unwrap_or_default();
-                let amt = parse_amount(field)?;
-                (amt, None, None)
-            } else if let (Some(d_col), Some(c_col)) = (mapping.debit_column, mapping.credit_column)
-            {
-                let d = record
-                    .get(d_col)
-                    .filter(|s| !s.trim().is_empty())
-                    .map(parse_amount)
-                    .transpose()?;
-                let c = record
-                    .get(c_col)
-                    .filter(|s| !s.trim().is_empty())
-                    .map(parse_amount)
-                    .transpose()?;
-                let amt = match (d, c) {
-                    (Some(d), None) => d,
-                    (None, Some(c)) => -c,
-                    (None, None) => 0,
-                    _ => 0,
-                };
-                (amt, d, c)
-            } else {
-                continue;
-            };
-
-            let memo = mapping
-                .memo_column
-                .and_then(|col| record.get(col))
-                .filter(|s| !s.is_empty())
-                .map(|s| s.to_string());
-
-            transactions.push(CsvTransaction {
-                date,
-                description,
-                amount,
-                memo,
-                debit,
-                credit,
-            });
         }
 
         if transactions.is_empty() {
@@ -177,7 +75,74 @@ impl CsvImporter {
     }
 }
 
-fn parse_date(s: &str, format: &str) -> Result<NaiveDate, CsvError> {
+/// One row as a transaction, or `None` for a blank row or a mapping with
+/// no date or amount.
+fn parse_record(
+    record: &csv::StringRecord,
+    mapping: &CsvColumnMapping,
+) -> Result<Option<CsvTransaction>, CsvError> {
+    if record.is_empty() {
+        return Ok(None);
+    }
+
+    let date = if let Some(col) = mapping.date_column {
+        let field = record
+            .get(col)
+            .ok_or_else(|| CsvError::MissingColumn(format!("date_column {}", col)))?;
+        parse_date(field, &mapping.date_format)?
+    } else {
+        return Ok(None);
+    };
+
+    let description = if let Some(col) = mapping.description_column {
+        record.get(col).unwrap_or_default().to_string()
+    } else {
+        String::new()
+    };
+
+    let (amount, debit, credit) = if let Some(col) = mapping.amount_column {
+        let field = record.get(col).unwrap_or_default();
+        let amt = parse_amount(field)?;
+        (amt, None, None)
+    } else if let (Some(d_col), Some(c_col)) = (mapping.debit_column, mapping.credit_column) {
+        let d = record
+            .get(d_col)
+            .filter(|s| !s.trim().is_empty())
+            .map(parse_amount)
+            .transpose()?;
+        let c = record
+            .get(c_col)
+            .filter(|s| !s.trim().is_empty())
+            .map(parse_amount)
+            .transpose()?;
+        let amt = match (d, c) {
+            (Some(d), None) => d,
+            (None, Some(c)) => -c,
+            (None, None) => 0,
+            _ => 0,
+        };
+        (amt, d, c)
+    } else {
+        return Ok(None);
+    };
+
+    let memo = mapping
+        .memo_column
+        .and_then(|col| record.get(col))
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string());
+
+    Ok(Some(CsvTransaction {
+        date,
+        description,
+        amount,
+        memo,
+        debit,
+        credit,
+    }))
+}
+
+pub(crate) fn parse_date(s: &str, format: &str) -> Result<NaiveDate, CsvError> {
     let s = s.trim();
 
     if let Ok(date) = NaiveDate::parse_from_str(s, format) {
@@ -195,7 +160,7 @@ fn parse_date(s: &str, format: &str) -> Result<NaiveDate, CsvError> {
     Err(CsvError::InvalidDate(s.to_string()))
 }
 
-fn parse_amount(s: &str) -> Result<i64, CsvError> {
+pub(crate) fn parse_amount(s: &str) -> Result<i64, CsvError> {
     let s = s.trim();
     let (negative, s) = if s.starts_with('(') && s.ends_with(')') {
         (true, &s[1..s.len() - 1])
@@ -239,6 +204,109 @@ pub fn import_csv<R: Read>(
     parse(&mut reader, profile)
 }
 
+/// How far a streamed import has got.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct CsvProgress {
+    /// Transactions parsed so far.
+    pub rows: u64,
+    /// Bytes of the file read so far.
+    pub bytes: u64,
+}
+
+/// Reads a CSV a chunk of transactions at a time, for files too large to
+/// hold in memory at once. Unlike [`import_csv`] there is no row limit.
+pub struct CsvChunks<R> {
+    reader: csv::Reader<R>,
+    mapping: CsvColumnMapping,
+    chunk_size: usize,
+    record: csv::StringRecord,
+    progress: CsvProgress,
+    done: bool,
+}
+
+impl<R: Read> CsvChunks<R> {
+    pub fn new(data: R, profile: &CsvImportProfile, chunk_size: usize) -> Self {
+        let delimiter = profile
+            .delimiter
+            .as_bytes()
+            .first()
+            .copied()
+            .unwrap_or(b',');
+        let reader = csv::ReaderBuilder::new()
+            .has_headers(profile.has_header)
+            .delimiter(delimiter)
+            .from_reader(data);
+        Self {
+            reader,
+            mapping: profile.mapping.clone(),
+            chunk_size: chunk_size.max(1),
+            record: csv::StringRecord::new(),
+            progress: CsvProgress::default(),
+            done: false,
+        }
+    }
+
+    pub fn progress(&self) -> CsvProgress {
+        self.progress
+    }
+}
+
+impl<R: Read> Iterator for CsvChunks<R> {
+    type Item = Result<Vec<CsvTransaction>, CsvError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let mut chunk = Vec::with_capacity(self.chunk_size);
+        while chunk.len() < self.chunk_size {
+            let parsed = match self.reader.read_record(&mut self.record) {
+                Ok(true) => parse_record(&self.record, &self.mapping),
+                Ok(false) => {
+                    self.done = true;
+                    break;
+                }
+                Err(e) => Err(e.into()),
+            };
+            self.progress.bytes = self.reader.position().byte();
+            match parsed {
+                Ok(Some(t)) => {
+                    chunk.push(t);
+                    self.progress.rows += 1;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        (!chunk.is_empty()).then_some(Ok(chunk))
+    }
+}
+
+/// Stream a CSV through `on_chunk`, `chunk_size` transactions at a time,
+/// with the progress after each chunk. Returning `ControlFlow::Break` stops
+/// the import with [`CsvError::Cancelled`].
+pub fn import_csv_chunked<R: Read>(
+    data: R,
+    profile: &CsvImportProfile,
+    chunk_size: usize,
+    mut on_chunk: impl FnMut(Vec<CsvTransaction>, CsvProgress) -> ControlFlow<()>,
+) -> Result<CsvProgress, CsvError> {
+    let mut chunks = CsvChunks::new(data, profile, chunk_size);
+    while let Some(chunk) = chunks.next() {
+        if on_chunk(chunk?, chunks.progress()).is_break() {
+            return Err(CsvError::Cancelled);
+        }
+    }
+    let progress = chunks.progress();
+    if progress.rows == 0 {
+        return Err(CsvError::NoDataRows);
+    }
+    Ok(progress)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -372,4 +440,60 @@ mod tests {
         let result = import_csv(data.as_ref(), &default_profile());
         assert!(matches!(result, Err(CsvError::NoDataRows)));
     }
+
+    // ── streaming ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn chunks_report_rows_and_bytes() {
+        let mut data = String::from("date,description,amount\n");
+        for day in 1..=25 {
+            data.push_str(&format!("2024-01-{day:02},Row {day},-1.00\n"));
+        }
+        let mut seen = Vec::new();
+        let progress = import_csv_chunked(data.as_bytes(), &default_profile(), 10, |chunk, p| {
+            seen.push((chunk.len(), p.rows));
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+        assert_eq!(seen, [(10, 10), (10, 20), (5, 25)]);
+        assert_eq!(progress.rows, 25);
+        assert_eq!(progress.bytes, data.len() as u64);
+
+        let mut chunks = 0;
+        let cancelled = import_csv_chunked(data.as_bytes(), &default_profile(), 10, |_, _| {
+            chunks += 1;
+            ControlFlow::Break(())
+        });
+        assert!(matches!(cancelled, Err(CsvError::Cancelled)));
+        assert_eq!(chunks, 1);
+    }
+
+    #[test]
+    fn chunks_stop_at_a_bad_row() {
+        let data = b"date,description,amount\n2024-01-01,Ok,1.00\n2024-01-02,Bad,abc\n";
+        let mut chunks = CsvChunks::new(data.as_ref(), &default_profile(), 10);
+        assert!(matches!(
+            chunks.next(),
+            Some(Err(CsvError::InvalidAmount(_)))
+        ));
+        assert!(chunks.next().is_none());
+        assert_eq!(chunks.progress().rows, 1);
+    }
+
+    // ── validate ──────────────────────────────────────────────────────────────
+
+    #[test]
+    fn validate_requires_date_and_amount_columns() {
+        let mut profile = default_profile();
+        profile.name = "Chase".to_string();
+        assert!(profile.validate().is_ok());
+
+        profile.mapping.amount_column = None;
+        profile.mapping.debit_column = Some(2);
+        assert!(profile.validate().unwrap_err().contains("debit and credit"));
+
+        profile.mapping.credit_column = Some(3);
+        profile.delimiter = ";;".to_string();
+        assert!(profile.validate().unwrap_err().contains("delimiter"));
+    }
 }
@@ -0,0 +1,474 @@
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::ofx::{OfxError, OfxParser, OfxStatement};
+
+/// Keychain service name under which institution passwords are stored. The
+/// entry user is `"{fid}:{user_id}"` so one login per institution is kept.
+pub const KEYCHAIN_SERVICE: &str = "aequi-ofx";
+
+// ---------------------------------------------------------------------------
+// Configuration
+// ---------------------------------------------------------------------------
+
+/// An institution's DirectConnect endpoint, as published on ofxhome.com or by
+/// the bank itself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OfxInstitution {
+    pub org: String,
+    pub fid: String,
+    pub url: String,
+    /// Routing number; required for bank (not credit card) accounts.
+    pub bank_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OfxAccountKind {
+    Checking,
+    Savings,
+    MoneyMarket,
+    CreditLine,
+    CreditCard,
+}
+
+impl OfxAccountKind {
+    fn acct_type(&self) -> &'static str {
+        match self {
+            OfxAccountKind::Checking => "CHECKING",
+            OfxAccountKind::Savings => "SAVINGS",
+            OfxAccountKind::MoneyMarket => "MONEYMRKT",
+            OfxAccountKind::CreditLine => "CREDITLINE",
+            OfxAccountKind::CreditCard => "CREDITCARD",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct OfxCredentials {
+    pub user_id: String,
+    pub password: String,
+}
+
+// Redact password in Debug output
+impl std::fmt::Debug for OfxCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OfxCredentials")
+            .field("user_id", &self.user_id)
+            .field("password", &"[REDACTED]")
+            .finish()
+    }
+}
+
+impl OfxCredentials {
+    fn keychain_entry(
+        institution: &OfxInstitution,
+        user_id: &str,
+    ) -> keyring::Result<keyring::Entry> {
+        keyring::Entry::new(KEYCHAIN_SERVICE, &format!("{}:{user_id}", institution.fid))
+    }
+
+    /// Load the password for `user_id` at `institution` from the OS keychain.
+    pub fn from_keychain(
+        institution: &OfxInstitution,
+        user_id: &str,
+    ) -> Result<Self, DirectConnectError> {
+        let password = Self::keychain_entry(institution, user_id)
+            .and_then(|e| e.get_password())
+            .map_err(|e| DirectConnectError::Keychain(e.to_string()))?;
+        Ok(Self {
+            user_id: user_id.to_string(),
+            password,
+        })
+    }
+
+    pub fn store_in_keychain(
+        &self,
+        institution: &OfxInstitution,
+    ) -> Result<(), DirectConnectError> {
+        Self::keychain_entry(institution, &self.user_id)
+            .and_then(|e| e.set_password(&self.password))
+            .map_err(|e| DirectConnectError::Keychain(e.to_string()))
+    }
+
+    pub fn delete_from_keychain(
+        institution: &OfxInstitution,
+        user_id: &str,
+    ) -> Result<(), DirectConnectError> {
+        Self::keychain_entry(institution, user_id)
+            .and_then(|e| e.delete_credential())
+            .map_err(|e| DirectConnectError::Keychain(e.to_string()))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Error
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Error)]
+pub enum DirectConnectError {
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("OFX server returned HTTP {0}")]
+    HttpStatus(u16),
+
+    #[error("Keychain error: {0}")]
+    Keychain(String),
+
+    #[error("OFX server error {code}: {message}")]
+    Server { code: String, message: String },
+
+    #[error(transparent)]
+    Ofx(#[from] OfxError),
+}
+
+// ---------------------------------------------------------------------------
+// Request / response
+// ---------------------------------------------------------------------------
+
+/// Identity presented to the server. Many banks only answer requests that
+/// claim to come from Quicken.
+const APP_ID: &str = "QWIN";
+const APP_VER: &str = "2700";
+
+/// Build an OFX 1.02 SGML statement request for one account.
+pub fn statement_request(
+    institution: &OfxInstitution,
+    credentials: &OfxCredentials,
+    account_id: &str,
+    kind: OfxAccountKind,
+    start: NaiveDate,
+    end: NaiveDate,
+    now: NaiveDateTime,
+) -> String {
+    let dt_client = now.format("%Y%m%d%H%M%S").to_string();
+    let trn_uid = now.and_utc().timestamp_micros().to_string();
+
+    let mut lines = vec![
+        "OFXHEADER:100".to_string(),
+        "DATA:OFXSGML".to_string(),
+        "VERSION:102".to_string(),
+        "SECURITY:NONE".to_string(),
+        "ENCODING:USASCII".to_string(),
+        "CHARSET:1252".to_string(),
+        "COMPRESSION:NONE".to_string(),
+        "OLDFILEUID:NONE".to_string(),
+        "NEWFILEUID:NONE".to_string(),
+        String::new(),
+        "<OFX>".to_string(),
+        "<SIGNONMSGSRQV1>".to_string(),
+        "<SONRQ>".to_string(),
+        format!("<DTCLIENT>{dt_client}"),
+        format!("<USERID>{}", escape(&credentials.user_id)),
+        format!("<USERPASS>{}", escape(&credentials.password)),
+        "<LANGUAGE>ENG".to_string(),
+        "<FI>".to_string(),
+        format!("<ORG>{}", escape(&institution.org)),
+        format!("<FID>{}", escape(&institution.fid)),
+        "</FI>".to_string(),
+        format!("<APPID>{APP_ID}"),
+        format!("<APPVER>{APP_VER}"),
+        "</SONRQ>".to_string(),
+        "</SIGNONMSGSRQV1>".to_string(),
+    ];
+
+    let (msgset, trnrq, stmtrq, acctfrom) = if kind == OfxAccountKind::CreditCard {
+        (
+            "CREDITCARDMSGSRQV1",
+            "CCSTMTTRNRQ",
+            "CCSTMTRQ",
+            "CCACCTFROM",
+        )
+    } else {
+        ("BANKMSGSRQV1", "STMTTRNRQ", "STMTRQ", "BANKACCTFROM")
+    };
+    lines.push(format!("<{msgset}>"));
+    lines.push(format!("<{trnrq}>"));
+    lines.push(format!("<TRNUID>{trn_uid}"));
+    lines.push(format!("<{stmtrq}>"));
+    lines.push(format!("<{acctfrom}>"));
+    if kind != OfxAccountKind::CreditCard {
+        lines.push(format!(
+            "<BANKID>{}",
+            escape(institution.bank_id.as_deref().unwrap_or_default())
+        ));
+    }
+    lines.push(format!("<ACCTID>{}", escape(account_id)));
+    if kind != OfxAccountKind::CreditCard {
+        lines.push(format!("<ACCTTYPE>{}", kind.acct_type()));
+    }
+    lines.push(format!("</{acctfrom}>"));
+    lines.push("<INCTRAN>".to_string());
+    lines.push(format!("<DTSTART>{}", start.format("%Y%m%d")));
+    lines.push(format!("<DTEND>{}", end.format("%Y%m%d")));
+    lines.push("<INCLUDE>Y".to_string());
+    lines.push("</INCTRAN>".to_string());
+    lines.push(format!("</{stmtrq}>"));
+    lines.push(format!("</{trnrq}>"));
+    lines.push(format!("</{msgset}>"));
+    lines.push("</OFX>".to_string());
+
+    let mut body = lines.join("\r\n");
+    body.push_str("\r\n");
+    body
+}
+
+/// Parse a DirectConnect response. Server-side failures (bad password,
+/// unknown account, ...) are reported through `<STATUS>` aggregates rather
+/// than HTTP codes, so those are checked before the statement is parsed.
+pub fn parse_response(body: &str) -> Result<OfxStatement, DirectConnectError> {
+    // Servers often send the whole SGML document on one line, while the
+    // statement parser expects one tag per line.
+    let normalized = body.replace('<', "\n<");
+
+    let mut in_status = false;
+    let mut code = String::new();
+    let mut severity = String::new();
+    let mut message = String::new();
+    for line in normalized.lines() {
+        let Some(tag) = line.trim().strip_prefix('<') else {
+            continue;
+        };
+        let (name, value) = tag.split_once('>').unwrap_or((tag, ""));
+        match name.to_ascii_uppercase().as_str() {
+            "STATUS" => {
+                in_status = true;
+                code.clear();
+                severity.clear();
+                message.clear();
+            }
+            "/STATUS" => {
+                in_status = false;
+                if severity.eq_ignore_ascii_case("ERROR") {
+                    return Err(DirectConnectError::Server {
+                        code: code.clone(),
+                        message: message.clone(),
+                    });
+                }
+            }
+            "CODE" if in_status => code = value.trim().to_string(),
+            "SEVERITY" if in_status => severity = value.trim().to_string(),
+            "MESSAGE" if in_status => message = value.trim().to_string(),
+            _ => {}
+        }
+    }
+
+    Ok(OfxParser::parse(&normalized)?)
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+// ---------------------------------------------------------------------------
+// Client
+// ---------------------------------------------------------------------------
+
+pub struct DirectConnectClient {
+    http: reqwest::Client,
+}
+
+impl Default for DirectConnectClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DirectConnectClient {
+    pub fn new() -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(60))
+            .https_only(true)
+            .build()
+            .expect("failed to build HTTP client");
+        Self { http }
+    }
+
+    /// Download the statement for `account_id` covering `start..=end`.
+    pub async fn download_statement(
+        &self,
+        institution: &OfxInstitution,
+        credentials: &OfxCredentials,
+        account_id: &str,
+        kind: OfxAccountKind,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<OfxStatement, DirectConnectError> {
+        let body = statement_request(
+            institution,
+            credentials,
+            account_id,
+            kind,
+            start,
+            end,
+            Utc::now().naive_utc(),
+        );
+
+        let resp = self
+            .http
+            .post(&institution.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/x-ofx")
+            .header(reqwest::header::ACCEPT, "*/*, application/x-ofx")
+            .body(body)
+            .send()
+            .await?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            return Err(DirectConnectError::HttpStatus(status.as_u16()));
+        }
+        let bytes = resp.bytes().await?;
+        parse_response(&String::from_utf8_lossy(&bytes))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn institution() -> OfxInstitution {
+        OfxInstitution {
+            org: "MYBANK".to_string(),
+            fid: "1234".to_string(),
+            url: "https://ofx.mybank.example/ofx".to_string(),
+            bank_id: Some("021000021".to_string()),
+        }
+    }
+
+    fn credentials() -> OfxCredentials {
+        OfxCredentials {
+            user_id: "alice".to_string(),
+            password: "p<ss&word".to_string(),
+        }
+    }
+
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 3, 1)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+    }
+
+    fn range() -> (NaiveDate, NaiveDate) {
+        (
+            NaiveDate::from_ymd_opt(2024, 2, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 2, 29).unwrap(),
+        )
+    }
+
+    // ── request ──────────────────────────────────────────────────────────────
+
+    #[test]
+    fn bank_statement_request() {
+        let (start, end) = range();
+        let req = statement_request(
+            &institution(),
+            &credentials(),
+            "9876",
+            OfxAccountKind::Checking,
+            start,
+            end,
+            now(),
+        );
+        assert!(req.starts_with("OFXHEADER:100\r\n"));
+        assert!(req.contains("<DTCLIENT>20240301120000\r\n"));
+        assert!(req.contains("<ORG>MYBANK\r\n<FID>1234\r\n"));
+        assert!(req.contains("<BANKMSGSRQV1>\r\n<STMTTRNRQ>"));
+        assert!(req.contains("<BANKID>021000021\r\n<ACCTID>9876\r\n<ACCTTYPE>CHECKING"));
+        assert!(req.contains("<DTSTART>20240201\r\n<DTEND>20240229"));
+        assert!(req.ends_with("</OFX>\r\n"));
+    }
+
+    #[test]
+    fn credit_card_request_uses_cc_aggregates() {
+        let (start, end) = range();
+        let req = statement_request(
+            &institution(),
+            &credentials(),
+            "4111",
+            OfxAccountKind::CreditCard,
+            start,
+            end,
+            now(),
+        );
+        assert!(req.contains("<CREDITCARDMSGSRQV1>\r\n<CCSTMTTRNRQ>"));
+        assert!(req.contains("<CCACCTFROM>\r\n<ACCTID>4111\r\n</CCACCTFROM>"));
+        assert!(!req.contains("<BANKID>"));
+        assert!(!req.contains("<ACCTTYPE>"));
+    }
+
+    #[test]
+    fn request_values_are_escaped() {
+        let (start, end) = range();
+        let req = statement_request(
+            &institution(),
+            &credentials(),
+            "1",
+            OfxAccountKind::Savings,
+            start,
+            end,
+            now(),
+        );
+        assert!(req.contains("<USERPASS>p&lt;ss&amp;word\r\n"));
+    }
+
+    #[test]
+    fn credentials_debug_redacts_password() {
+        let debug = format!("{:?}", credentials());
+        assert!(!debug.contains("p<ss&word"));
+        assert!(debug.contains("[REDACTED]"));
+    }
+
+    // ── response ─────────────────────────────────────────────────────────────
+
+    const SINGLE_LINE_RESPONSE: &str = "OFXHEADER:100\r\nDATA:OFXSGML\r\nVERSION:102\r\n\r\n\
+<OFX><SIGNONMSGSRSV1><SONRS><STATUS><CODE>0<SEVERITY>INFO</STATUS><DTSERVER>20240301120000\
+<LANGUAGE>ENG</SONRS></SIGNONMSGSRSV1><BANKMSGSRSV1><STMTTRNRS><TRNUID>1\
+<STATUS><CODE>0<SEVERITY>INFO</STATUS><STMTRS><CURDEF>USD<BANKACCTFROM><BANKID>021000021\
+<ACCTID>9876<ACCTTYPE>CHECKING</BANKACCTFROM><BANKTRANLIST><DTSTART>20240201<DTEND>20240229\
+<STMTTRN><TRNTYPE>DEBIT<DTPOSTED>20240205<TRNAMT>-25.00<FITID>F1<NAME>UTILITY CO</STMTTRN>\
+<STMTTRN><TRNTYPE>CREDIT<DTPOSTED>20240210<TRNAMT>1000.00<FITID>F2<NAME>DEPOSIT</STMTTRN>\
+</BANKTRANLIST></STMTRS></STMTTRNRS></BANKMSGSRSV1></OFX>";
+
+    #[test]
+    fn single_line_response_parses() {
+        let stmt = parse_response(SINGLE_LINE_RESPONSE).unwrap();
+        assert_eq!(stmt.account.account_id, "9876");
+        assert_eq!(stmt.currency.as_deref(), Some("USD"));
+        assert_eq!(stmt.transactions.len(), 2);
+        assert_eq!(stmt.transactions[0].fit_id, "F1");
+        assert_eq!(stmt.transactions[0].amount, -2500);
+        assert_eq!(stmt.transactions[1].amount, 100000);
+    }
+
+    #[test]
+    fn signon_error_is_reported() {
+        let body = "<OFX><SIGNONMSGSRSV1><SONRS><STATUS><CODE>15500<SEVERITY>ERROR\
+<MESSAGE>Invalid user ID or password</STATUS></SONRS></SIGNONMSGSRSV1></OFX>";
+        match parse_response(body) {
+            Err(DirectConnectError::Server { code, message }) => {
+                assert_eq!(code, "15500");
+                assert_eq!(message, "Invalid user ID or password");
+            }
+            other => panic!("expected server error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn warning_status_does_not_fail() {
+        let body = SINGLE_LINE_RESPONSE.replacen(
+            "<CODE>0<SEVERITY>INFO",
+            "<CODE>2000<SEVERITY>WARN<MESSAGE>Partial",
+            1,
+        );
+        assert!(parse_response(&body).is_ok());
+    }
+}
@@ -0,0 +1,709 @@
+//! GnuCash interop.
+//!
+//! GnuCash keeps a book either as (usually gzipped) XML or as an SQLite
+//! database. This module reads the XML form into a [`GnucashBook`] and
+//! writes aequi's books back out as GnuCash XML; the SQLite form is read by
+//! the storage crate into the same types, which live in aequi-core.
+//!
+//! GnuCash books can hold several commodities. Amounts are brought into the
+//! base currency when the transaction is in it, or through a split posted to
+//! a base-currency account; anything else is reported rather than guessed.
+
+use std::io::Read;
+
+pub use aequi_core::import::gnucash::{
+    account_type, parse_date, rational_cents, Commodity, GnucashAccount, GnucashBook, GnucashError,
+    GnucashSplit, GnucashTransaction,
+};
+use aequi_core::{Account, AccountType, ValidatedTransaction};
+use flate2::read::GzDecoder;
+
+/// Whether `data` is an SQLite database rather than XML.
+pub fn is_sqlite(data: &[u8]) -> bool {
+    data.starts_with(b"SQLite format 3\0")
+}
+
+/// Parse a GnuCash XML book, gzipped or not.
+pub fn parse_xml(data: &[u8]) -> Result<GnucashBook, GnucashError> {
+    let text = if data.starts_with(&[0x1f, 0x8b]) {
+        let mut text = String::new();
+        GzDecoder::new(data).read_to_string(&mut text)?;
+        text
+    } else {
+        String::from_utf8(data.to_vec())
+            .map_err(|_| GnucashError::Xml("file is not UTF-8".to_string()))?
+    };
+    let root = parse_document(&text)?;
+    if root.name != "gnc-v2" {
+        return Err(GnucashError::Xml(format!(
+            "expected <gnc-v2>, found <{}>",
+            root.name
+        )));
+    }
+    let book = root
+        .child("gnc:book")
+        .ok_or_else(|| GnucashError::MissingField("gnc:book".to_string()))?;
+
+    let mut parsed = GnucashBook::default();
+    // Only direct children: scheduled transaction templates are nested in
+    // <gnc:template-transactions> and aren't part of the books.
+    for element in &book.children {
+        match element.name.as_str() {
+            "gnc:account" => parsed.accounts.push(parse_account(element)?),
+            "gnc:transaction" => parsed.transactions.push(parse_transaction(element)?),
+            _ => {}
+        }
+    }
+    Ok(parsed)
+}
+
+fn parse_account(el: &Element) -> Result<GnucashAccount, GnucashError> {
+    Ok(GnucashAccount {
+        guid: required(el, "act:id")?,
+        name: required(el, "act:name")?,
+        account_type: required(el, "act:type")?,
+        parent: el.text_of("act:parent"),
+        commodity: el
+            .child("act:commodity")
+            .map(parse_commodity)
+            .unwrap_or_else(|| Commodity {
+                space: "CURRENCY".to_string(),
+                id: String::new(),
+            }),
+        code: el.text_of("act:code"),
+        description: el.text_of("act:description"),
+        placeholder: slot(el.child("act:slots"), "placeholder").as_deref() == Some("true"),
+    })
+}
+
+fn parse_transaction(el: &Element) -> Result<GnucashTransaction, GnucashError> {
+    let guid = required(el, "trn:id")?;
+    let posted = el
+        .child("trn:date-posted")
+        .and_then(|d| d.text_of("ts:date"))
+        .ok_or_else(|| GnucashError::MissingField(format!("trn:date-posted in {guid}")))?;
+    let currency = el
+        .child("trn:currency")
+        .map(parse_commodity)
+        .ok_or_else(|| GnucashError::MissingField(format!("trn:currency in {guid}")))?;
+    let splits = el
+        .child("trn:splits")
+        .map(|s| s.children_named("trn:split").map(parse_split).collect())
+        .transpose()?
+        .unwrap_or_default();
+    Ok(GnucashTransaction {
+        date: parse_date(&posted)?,
+        description: el.text_of("trn:description").unwrap_or_default(),
+        num: el.text_of("trn:num"),
+        notes: slot(el.child("trn:slots"), "notes"),
+        currency,
+        splits,
+        guid,
+    })
+}
+
+fn parse_split(el: &Element) -> Result<GnucashSplit, GnucashError> {
+    let value = required(el, "split:value")?;
+    let quantity = el
+        .text_of("split:quantity")
+        .unwrap_or_else(|| value.clone());
+    Ok(GnucashSplit {
+        account: required(el, "split:account")?,
+        value_cents: rational_cents(&value)?,
+        quantity_cents: rational_cents(&quantity)?,
+        memo: el.text_of("split:memo"),
+    })
+}
+
+fn parse_commodity(el: &Element) -> Commodity {
+    Commodity {
+        space: el.text_of("cmdty:space").unwrap_or_default(),
+        id: el.text_of("cmdty:id").unwrap_or_default(),
+    }
+}
+
+fn required(el: &Element, name: &str) -> Result<String, GnucashError> {
+    el.text_of(name)
+        .ok_or_else(|| GnucashError::MissingField(format!("{name} in <{}>", el.name)))
+}
+
+/// Value of the string slot `key` in a `<*:slots>` element.
+fn slot(slots: Option<&Element>, key: &str) -> Option<String> {
+    slots?
+        .children_named("slot")
+        .find(|s| s.text_of("slot:key").as_deref() == Some(key))
+        .and_then(|s| s.text_of("slot:value"))
+}
+
+// ── Export ─────────────────────────────────────────────────────────────────
+
+/// Guid for an exported object. GnuCash only needs them unique within the
+/// book; deriving them from ids keeps repeated exports identical.
+fn guid(kind: u32, id: i64) -> String {
+    format!("{kind:08x}{:024x}", id as u64)
+}
+
+const ROOT_GUID_KIND: u32 = 0;
+const ACCOUNT_GUID_KIND: u32 = 1;
+const TRANSACTION_GUID_KIND: u32 = 2;
+const SPLIT_GUID_KIND: u32 = 3;
+const TOP_LEVEL_GUID_KIND: u32 = 4;
+const BOOK_GUID_KIND: u32 = 5;
+
+fn top_level(account_type: AccountType) -> (&'static str, &'static str, i64) {
+    match account_type {
+        AccountType::Asset => ("Assets", "ASSET", 1),
+        AccountType::Liability => ("Liabilities", "LIABILITY", 2),
+        AccountType::Equity => ("Equity", "EQUITY", 3),
+        AccountType::Income => ("Income", "INCOME", 4),
+        AccountType::Expense => ("Expenses", "EXPENSE", 5),
+    }
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn write_commodity(out: &mut String, tag: &str, currency: &str) {
+    out.push_str(&format!(
+        "  <{tag}>\n    <cmdty:space>CURRENCY</cmdty:space>\n    <cmdty:id>{}</cmdty:id>\n  </{tag}>\n",
+        xml_escape(currency)
+    ));
+}
+
+fn write_slot(out: &mut String, tag: &str, key: &str, value: &str) {
+    out.push_str(&format!(
+        "  <{tag}>\n    <slot>\n      <slot:key>{key}</slot:key>\n      <slot:value type=\"string\">{}</slot:value>\n    </slot>\n  </{tag}>\n",
+        xml_escape(value)
+    ));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn write_account(
+    out: &mut String,
+    guid: &str,
+    name: &str,
+    account_type: &str,
+    currency: &str,
+    parent: Option<&str>,
+    code: Option<&str>,
+    placeholder: bool,
+) {
+    out.push_str("<gnc:account version=\"2.0.0\">\n");
+    out.push_str(&format!("  <act:name>{}</act:name>\n", xml_escape(name)));
+    out.push_str(&format!("  <act:id type=\"guid\">{guid}</act:id>\n"));
+    out.push_str(&format!("  <act:type>{account_type}</act:type>\n"));
+    write_commodity(out, "act:commodity", currency);
+    out.push_str("  <act:commodity-scu>100</act:commodity-scu>\n");
+    if let Some(code) = code {
+        out.push_str(&format!("  <act:code>{}</act:code>\n", xml_escape(code)));
+    }
+    if placeholder {
+        write_slot(out, "act:slots", "placeholder", "true");
+    }
+    if let Some(parent) = parent {
+        out.push_str(&format!(
+            "  <act:parent type=\"guid\">{parent}</act:parent>\n"
+        ));
+    }
+    out.push_str("</gnc:account>\n");
+}
+
+/// Write accounts and transactions as an uncompressed GnuCash XML book in
+/// `currency`. Accounts sit under a placeholder per account type (Assets,
+/// Liabilities, ...), named as in aequi with their codes kept.
+pub fn write_xml(
+    accounts: &[Account],
+    transactions: &[ValidatedTransaction],
+    currency: &str,
+) -> String {
+    let types = [
+        AccountType::Asset,
+        AccountType::Liability,
+        AccountType::Equity,
+        AccountType::Income,
+        AccountType::Expense,
+    ];
+    let accounts: Vec<&Account> = accounts.iter().filter(|a| a.id.is_some()).collect();
+    let root = guid(ROOT_GUID_KIND, 0);
+    let mut out = String::new();
+
+    out.push_str("<?xml version=\"1.0\" encoding=\"utf-8\" ?>\n");
+    out.push_str(
+        "<gnc-v2\n     xmlns:gnc=\"http://www.gnucash.org/XML/gnc\"\n     xmlns:act=\"http://www.gnucash.org/XML/act\"\n     xmlns:book=\"http://www.gnucash.org/XML/book\"\n     xmlns:cd=\"http://www.gnucash.org/XML/cd\"\n     xmlns:cmdty=\"http://www.gnucash.org/XML/cmdty\"\n     xmlns:slot=\"http://www.gnucash.org/XML/slot\"\n     xmlns:split=\"http://www.gnucash.org/XML/split\"\n     xmlns:trn=\"http://www.gnucash.org/XML/trn\"\n     xmlns:ts=\"http://www.gnucash.org/XML/ts\">\n",
+    );
+    out.push_str("<gnc:count-data cd:type=\"book\">1</gnc:count-data>\n");
+    out.push_str("<gnc:book version=\"2.0.0\">\n");
+    out.push_str(&format!(
+        "<book:id type=\"guid\">{}</book:id>\n",
+        guid(BOOK_GUID_KIND, 0)
+    ));
+    out.push_str("<gnc:count-data cd:type=\"commodity\">1</gnc:count-data>\n");
+    out.push_str(&format!(
+        "<gnc:count-data cd:type=\"account\">{}</gnc:count-data>\n",
+        accounts.len() + types.len() + 1
+    ));
+    out.push_str(&format!(
+        "<gnc:count-data cd:type=\"transaction\">{}</gnc:count-data>\n",
+        transactions.len()
+    ));
+    out.push_str("<gnc:commodity version=\"2.0.0\">\n");
+    out.push_str(&format!(
+        "  <cmdty:space>CURRENCY</cmdty:space>\n  <cmdty:id>{}</cmdty:id>\n",
+        xml_escape(currency)
+    ));
+    out.push_str("</gnc:commodity>\n");
+
+    write_account(
+        &mut out,
+        &root,
+        "Root Account",
+        "ROOT",
+        currency,
+        None,
+        None,
+        false,
+    );
+    for account_type in types {
+        let (name, gnucash_type, index) = top_level(account_type);
+        write_account(
+            &mut out,
+            &guid(TOP_LEVEL_GUID_KIND, index),
+            name,
+            gnucash_type,
+            currency,
+            Some(&root),
+            None,
+            true,
+        );
+    }
+    for account in &accounts {
+        let (_, gnucash_type, index) = top_level(account.account_type);
+        let id = account.id.map(|id| id.0).unwrap_or_default();
+        write_account(
+            &mut out,
+            &guid(ACCOUNT_GUID_KIND, id),
+            // A colon would read as a level of the hierarchy.
+            &account.name.replace(':', "-"),
+            gnucash_type,
+            currency,
+            Some(&guid(TOP_LEVEL_GUID_KIND, index)),
+            Some(&account.code),
+            false,
+        );
+    }
+
+    for (i, tx) in transactions.iter().enumerate() {
+        let id = tx.id.unwrap_or(i as i64);
+        let posted = format!("{} 10:59:00 +0000", tx.date);
+        let entered = tx
+            .created_at
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S +0000").to_string())
+            .unwrap_or_else(|| posted.clone());
+        out.push_str("<gnc:transaction version=\"2.0.0\">\n");
+        out.push_str(&format!(
+            "  <trn:id type=\"guid\">{}</trn:id>\n",
+            guid(TRANSACTION_GUID_KIND, id)
+        ));
+        write_commodity(&mut out, "trn:currency", currency);
+        out.push_str(&format!(
+            "  <trn:date-posted>\n    <ts:date>{posted}</ts:date>\n  </trn:date-posted>\n"
+        ));
+        out.push_str(&format!(
+            "  <trn:date-entered>\n    <ts:date>{entered}</ts:date>\n  </trn:date-entered>\n"
+        ));
+        out.push_str(&format!(
+            "  <trn:description>{}</trn:description>\n",
+            xml_escape(&tx.description)
+        ));
+        if let Some(memo) = tx.memo.as_deref().filter(|m| !m.trim().is_empty()) {
+            write_slot(&mut out, "trn:slots", "notes", memo);
+        }
+        out.push_str("  <trn:splits>\n");
+        for (n, line) in tx.lines.iter().enumerate() {
+            let cents = (line.debit - line.credit).to_cents();
+            out.push_str("    <trn:split>\n");
+            out.push_str(&format!(
+                "      <split:id type=\"guid\">{:08x}{:016x}{:08x}</split:id>\n",
+                SPLIT_GUID_KIND, id as u64, n
+            ));
+            if let Some(memo) = line.memo.as_deref().filter(|m| !m.trim().is_empty()) {
+                out.push_str(&format!(
+                    "      <split:memo>{}</split:memo>\n",
+                    xml_escape(memo)
+                ));
+            }
+            out.push_str("      <split:reconciled-state>n</split:reconciled-state>\n");
+            out.push_str(&format!("      <split:value>{cents}/100</split:value>\n"));
+            out.push_str(&format!(
+                "      <split:quantity>{cents}/100</split:quantity>\n"
+            ));
+            out.push_str(&format!(
+                "      <split:account type=\"guid\">{}</split:account>\n",
+                guid(ACCOUNT_GUID_KIND, line.account_id.0)
+            ));
+            out.push_str("    </trn:split>\n");
+        }
+        out.push_str("  </trn:splits>\n");
+        out.push_str("</gnc:transaction>\n");
+    }
+
+    out.push_str("</gnc:book>\n");
+    out.push_str("</gnc-v2>\n");
+    out
+}
+
+// ── Minimal XML reader ─────────────────────────────────────────────────────
+//
+// GnuCash XML is plain: elements, attributes and text, with no mixed
+// content or DTD tricks. This reads just enough of it into a tree.
+
+#[derive(Debug, Default)]
+struct Element {
+    name: String,
+    children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    /// Trimmed text of the child `name`, if present and not empty.
+    fn text_of(&self, name: &str) -> Option<String> {
+        self.child(name)
+            .map(|c| c.text.trim().to_string())
+            .filter(|t| !t.is_empty())
+    }
+}
+
+fn parse_document(src: &str) -> Result<Element, GnucashError> {
+    let err = |msg: &str| GnucashError::Xml(msg.to_string());
+    let mut stack = vec![Element::default()];
+    let mut pos = 0;
+
+    while pos < src.len() {
+        let rest = &src[pos..];
+        if !rest.starts_with('<') {
+            let end = rest.find('<').unwrap_or(rest.len());
+            let top = stack.last_mut().ok_or_else(|| err("unbalanced tags"))?;
+            top.text.push_str(&decode_entities(&rest[..end]));
+            pos += end;
+            continue;
+        }
+        if let Some(body) = rest.strip_prefix("<![CDATA[") {
+            let end = body.find("]]>").ok_or_else(|| err("unterminated CDATA"))?;
+            let top = stack.last_mut().ok_or_else(|| err("unbalanced tags"))?;
+            top.text.push_str(&body[..end]);
+            pos += "<![CDATA[".len() + end + "]]>".len();
+            continue;
+        }
+        let (open, close) = if rest.starts_with("<!--") {
+            ("<!--", "-->")
+        } else if rest.starts_with("<?") {
+            ("<?", "?>")
+        } else if rest.starts_with("<!") {
+            ("<!", ">")
+        } else {
+            ("", "")
+        };
+        if !open.is_empty() {
+            let end = rest[open.len()..]
+                .find(close)
+                .ok_or_else(|| err("unterminated markup"))?;
+            pos += open.len() + end + close.len();
+            continue;
+        }
+
+        let end = tag_end(rest).ok_or_else(|| err("unterminated tag"))?;
+        let tag = &rest[1..end];
+        pos += end + 1;
+
+        if let Some(name) = tag.strip_prefix('/') {
+            let element = stack.pop().ok_or_else(|| err("unbalanced tags"))?;
+            if element.name != name.trim() {
+                return Err(GnucashError::Xml(format!(
+                    "</{}> closes <{}>",
+                    name.trim(),
+                    element.name
+                )));
+            }
+            stack
+                .last_mut()
+                .ok_or_else(|| err("unbalanced tags"))?
+                .children
+                .push(element);
+            continue;
+        }
+
+        let self_closing = tag.ends_with('/');
+        let tag = tag.trim_end_matches('/');
+        let name = tag
+            .split(|c: char| c.is_whitespace())
+            .next()
+            .unwrap_or_default();
+        if name.is_empty() {
+            return Err(err("empty tag name"));
+        }
+        let element = Element {
+            name: name.to_string(),
+            ..Element::default()
+        };
+        if self_closing {
+            stack
+                .last_mut()
+                .ok_or_else(|| err("unbalanced tags"))?
+                .children
+                .push(element);
+        } else {
+            stack.push(element);
+        }
+    }
+
+    if stack.len() != 1 {
+        return Err(err("unclosed elements at end of file"));
+    }
+    stack
+        .pop()
+        .and_then(|doc| doc.children.into_iter().next())
+        .ok_or_else(|| err("no root element"))
+}
+
+/// Index of the `>` closing the tag at the start of `s`, skipping any in
+/// quoted attribute values.
+fn tag_end(s: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in s.char_indices().skip(1) {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, '>') => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn decode_entities(s: &str) -> String {
+    if !s.contains('&') {
+        return s.to_string();
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';').filter(|e| *e <= 10) else {
+            out.push('&');
+            rest = &rest[1..];
+            continue;
+        };
+        let entity = &rest[1..end];
+        let decoded = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .map(|hex| u32::from_str_radix(hex, 16).ok())
+                .unwrap_or_else(|| entity.strip_prefix('#').and_then(|d| d.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aequi_core::{AccountId, Money, TransactionLine, UnvalidatedTransaction};
+    use chrono::NaiveDate;
+
+    const SAMPLE: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
+<gnc-v2 xmlns:gnc="http://www.gnucash.org/XML/gnc">
+<gnc:count-data cd:type="book">1</gnc:count-data>
+<gnc:book version="2.0.0">
+<gnc:account version="2.0.0">
+  <act:name>Root Account</act:name>
+  <act:id type="guid">root</act:id>
+  <act:type>ROOT</act:type>
+</gnc:account>
+<gnc:account version="2.0.0">
+  <act:name>Assets</act:name>
+  <act:id type="guid">assets</act:id>
+  <act:type>ASSET</act:type>
+  <act:commodity><cmdty:space>CURRENCY</cmdty:space><cmdty:id>USD</cmdty:id></act:commodity>
+  <act:slots>
+    <slot><slot:key>placeholder</slot:key><slot:value type="string">true</slot:value></slot>
+  </act:slots>
+  <act:parent type="guid">root</act:parent>
+</gnc:account>
+<gnc:account version="2.0.0">
+  <act:name>Checking</act:name>
+  <act:id type="guid">checking</act:id>
+  <act:type>BANK</act:type>
+  <act:commodity><cmdty:space>CURRENCY</cmdty:space><cmdty:id>USD</cmdty:id></act:commodity>
+  <act:code>1000</act:code>
+  <act:parent type="guid">assets</act:parent>
+</gnc:account>
+<gnc:account version="2.0.0">
+  <act:name>Meals &amp; Entertainment</act:name>
+  <act:id type="guid">meals</act:id>
+  <act:type>EXPENSE</act:type>
+  <act:commodity><cmdty:space>CURRENCY</cmdty:space><cmdty:id>USD</cmdty:id></act:commodity>
+  <act:parent type="guid">root</act:parent>
+</gnc:account>
+<gnc:transaction version="2.0.0">
+  <trn:id type="guid">tx1</trn:id>
+  <trn:currency><cmdty:space>CURRENCY</cmdty:space><cmdty:id>USD</cmdty:id></trn:currency>
+  <trn:date-posted><ts:date>2026-03-01 10:59:00 +0000</ts:date></trn:date-posted>
+  <trn:description>Lunch &lt;client&gt;</trn:description>
+  <trn:slots>
+    <slot><slot:key>notes</slot:key><slot:value type="string">Kickoff</slot:value></slot>
+  </trn:slots>
+  <trn:splits>
+    <trn:split>
+      <split:memo>Tip included</split:memo>
+      <split:value>4250/100</split:value>
+      <split:quantity>4250/100</split:quantity>
+      <split:account type="guid">meals</split:account>
+    </trn:split>
+    <trn:split>
+      <split:value>-4250/100</split:value>
+      <split:quantity>-4250/100</split:quantity>
+      <split:account type="guid">checking</split:account>
+    </trn:split>
+  </trn:splits>
+</gnc:transaction>
+<gnc:template-transactions>
+  <gnc:transaction version="2.0.0">
+    <trn:id type="guid">template</trn:id>
+  </gnc:transaction>
+</gnc:template-transactions>
+</gnc:book>
+</gnc-v2>
+"#;
+
+    #[test]
+    fn parses_accounts_and_transactions() {
+        let book = parse_xml(SAMPLE.as_bytes()).unwrap();
+        assert_eq!(book.accounts.len(), 4);
+        let checking = book.account("checking").unwrap();
+        assert_eq!(checking.code.as_deref(), Some("1000"));
+        assert!(checking.commodity.is_currency("usd"));
+        assert!(book.account("assets").unwrap().placeholder);
+        assert_eq!(book.account_path("checking"), "Assets:Checking");
+        assert_eq!(book.account("meals").unwrap().name, "Meals & Entertainment");
+
+        assert_eq!(book.transactions.len(), 1);
+        let tx = &book.transactions[0];
+        assert_eq!(tx.date, NaiveDate::from_ymd_opt(2026, 3, 1).unwrap());
+        assert_eq!(tx.description, "Lunch <client>");
+        assert_eq!(tx.notes.as_deref(), Some("Kickoff"));
+        assert_eq!(tx.splits[0].value_cents, 4250);
+        assert_eq!(tx.splits[0].memo.as_deref(), Some("Tip included"));
+        assert_eq!(book.base_amounts(tx, "USD").unwrap(), vec![4250, -4250]);
+    }
+
+    #[test]
+    fn parses_gzipped_books() {
+        use flate2::write::GzEncoder;
+        use std::io::Write;
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(SAMPLE.as_bytes()).unwrap();
+        let book = parse_xml(&encoder.finish().unwrap()).unwrap();
+        assert_eq!(book.transactions.len(), 1);
+    }
+
+    #[test]
+    fn rejects_malformed_xml() {
+        assert!(matches!(
+            parse_xml(b"<gnc-v2><gnc:book></gnc-v2>"),
+            Err(GnucashError::Xml(_))
+        ));
+        assert!(matches!(
+            parse_xml(b"<gnc-v2></gnc-v2>"),
+            Err(GnucashError::MissingField(_))
+        ));
+    }
+
+    #[test]
+    fn written_books_read_back() {
+        let accounts = vec![
+            Account {
+                id: Some(AccountId(1)),
+                code: "1000".to_string(),
+                name: "Checking".to_string(),
+                account_type: AccountType::Asset,
+                is_archetype: true,
+                is_archived: false,
+                schedule_c_line: None,
+                deductible_pct: 100,
+            },
+            Account {
+                id: Some(AccountId(2)),
+                code: "5020".to_string(),
+                name: "Meals: Travel & Local".to_string(),
+                account_type: AccountType::Expense,
+                is_archetype: true,
+                is_archived: false,
+                schedule_c_line: None,
+                deductible_pct: 100,
+            },
+        ];
+        let mut tx = ValidatedTransaction::validate(UnvalidatedTransaction {
+            date: NaiveDate::from_ymd_opt(2026, 3, 1).unwrap(),
+            description: "Lunch <client>".to_string(),
+            lines: vec![
+                TransactionLine::debit(AccountId(2), Money::from_cents(4250), None),
+                TransactionLine::credit(AccountId(1), Money::from_cents(4250), None),
+            ],
+            memo: Some("Kickoff".to_string()),
+        })
+        .unwrap();
+        tx.id = Some(7);
+
+        let xml = write_xml(&accounts, &[tx], "USD");
+        let book = parse_xml(xml.as_bytes()).unwrap();
+        assert_eq!(book.accounts.len(), 8);
+        let meals = book
+            .accounts
+            .iter()
+            .find(|a| a.code.as_deref() == Some("5020"))
+            .unwrap();
+        assert_eq!(
+            book.account_path(&meals.guid),
+            "Expenses:Meals- Travel & Local"
+        );
+        assert_eq!(meals.account_type, "EXPENSE");
+
+        let tx = &book.transactions[0];
+        assert_eq!(tx.description, "Lunch <client>");
+        assert_eq!(tx.notes.as_deref(), Some("Kickoff"));
+        assert_eq!(tx.splits.len(), 2);
+        assert_eq!(tx.splits[0].account, meals.guid);
+        assert_eq!(book.base_amounts(tx, "USD").unwrap(), vec![4250, -4250]);
+    }
+}
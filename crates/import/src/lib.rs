@@ -1,21 +1,46 @@
 pub mod actual;
 pub mod ai_categorize;
+pub mod amazon;
+pub mod bank_intake;
+pub mod bayes;
+pub mod budget_apps;
 pub mod csv;
-pub mod match_engine;
+pub mod directconnect;
+pub mod gnucash;
 pub mod ofx;
 pub mod plaid;
+pub mod preview;
 pub mod profile_sharing;
-pub mod rules;
+pub mod qif;
+pub mod rule_sharing;
+pub mod rule_suggest;
+#[cfg(feature = "sync")]
+pub mod simplefin;
+#[cfg(feature = "sync")]
+pub mod sync;
 pub(crate) mod util;
 pub mod wave;
 pub mod work_items;
 
-pub use csv::{CsvImportProfile, CsvTransaction};
+pub use aequi_core::import::{match_engine, receipt_match, rules};
+
+pub use bank_intake::{BankFileImport, BankFileKind, IntakeTransaction};
+pub use bayes::{BayesPrediction, NaiveBayesCategorizer};
+pub use csv::{CsvChunks, CsvImportProfile, CsvProgress, CsvTransaction};
 pub use match_engine::{AutoMatchEngine, MatchResult, MatchType, MatchableTransaction};
-pub use ofx::{OfxStatement, OfxTransaction};
+pub use ofx::{OfxAccount, OfxInvestmentStatement, OfxStatement, OfxTransaction};
+pub use preview::ImportPreview;
+pub use receipt_match::{
+    suggest_receipt_matches, MatchableReceipt, ReceiptLinkTarget, ReceiptMatchCandidate,
+    ReceiptMatchSuggestion,
+};
+pub use rule_sharing::{ConflictPolicy, RuleImportPlan, RuleSet, RuleSetFormat};
+pub use rule_suggest::{suggest_rules, CategorizedExample, RuleSuggestion, SuggestOptions};
 pub use rules::{
     CategorizableTransaction, CategoryRule, CategoryRuleEngine, MatchType as RuleMatchType,
 };
+#[cfg(feature = "sync")]
+pub use sync::{BankAggregator, SyncBatch, SyncProvider};
 
 pub mod import {
     use crate::*;
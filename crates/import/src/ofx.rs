@@ -1,6 +1,7 @@
 use chrono::NaiveDate;
 use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
+use std::collections::HashMap;
 use std::str::FromStr;
 use thiserror::Error;
 
@@ -28,6 +29,8 @@ pub struct OfxStatement {
     pub end_date: NaiveDate,
     pub transactions: Vec<OfxTransaction>,
     pub currency: Option<String>,
+    /// Closing balance from `<LEDGERBAL>`, in cents.
+    pub ledger_balance: Option<i64>,
 }
 
 #[derive(Error, Debug)]
@@ -56,8 +59,10 @@ impl OfxParser {
         let mut end_date = None;
         let mut transactions = Vec::new();
         let mut currency = None;
+        let mut ledger_balance = None;
 
         let mut in_stmttrn = false;
+        let mut in_ledgerbal = false;
         let mut current_trx: Option<BuildingTrx> = None;
 
         for line in data.lines() {
@@ -104,6 +109,11 @@ impl OfxParser {
                             currency = Some(v);
                         }
                     }
+                    "LEDGERBAL" => in_ledgerbal = true,
+                    "/LEDGERBAL" => in_ledgerbal = false,
+                    "BALAMT" if in_ledgerbal => {
+                        ledger_balance = value.as_deref().and_then(parse_ofx_amount);
+                    }
                     "STMTTRN" => {
                         in_stmttrn = true;
                         current_trx = Some(BuildingTrx::default());
@@ -179,6 +189,7 @@ impl OfxParser {
             end_date,
             transactions,
             currency,
+            ledger_balance,
         })
     }
 }
@@ -212,6 +223,10 @@ fn parse_ofx_date(s: &str) -> Option<NaiveDate> {
     None
 }
 
+fn parse_decimal(s: &str) -> Option<Decimal> {
+    Decimal::from_str(&s.trim().replace(',', "")).ok()
+}
+
 fn parse_ofx_amount(s: &str) -> Option<i64> {
     let s = s.trim();
     let s = s.replace(',', "");
@@ -219,7 +234,7 @@ fn parse_ofx_amount(s: &str) -> Option<i64> {
     (dec * Decimal::from(100)).round().to_i64()
 }
 
-pub fn parse(data: &[u8]) -> Result<OfxStatement, OfxError> {
+fn decode(data: &[u8]) -> Result<std::borrow::Cow<'_, str>, OfxError> {
     // Guard against pathologically large input
     const MAX_OFX_SIZE: usize = 50 * 1024 * 1024; // 50MB
     if data.len() > MAX_OFX_SIZE {
@@ -230,10 +245,537 @@ pub fn parse(data: &[u8]) -> Result<OfxStatement, OfxError> {
         )));
     }
 
-    let content = String::from_utf8_lossy(data);
+    Ok(String::from_utf8_lossy(data))
+}
+
+pub fn parse(data: &[u8]) -> Result<OfxStatement, OfxError> {
+    let content = decode(data)?;
     OfxParser::parse(&content)
 }
 
+// ---------------------------------------------------------------------------
+// Export
+// ---------------------------------------------------------------------------
+
+/// OFX 1.x element values: no markup characters or line breaks.
+fn ofx_text(s: &str) -> String {
+    s.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn ofx_amount(cents: i64) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    format!(
+        "{sign}{}.{:02}",
+        cents.unsigned_abs() / 100,
+        cents.unsigned_abs() % 100
+    )
+}
+
+/// Write a statement as an OFX 1.0.2 file, the form banks serve and
+/// Quicken, GnuCash and most accounting software import. A `CREDITCARD`
+/// account is written as a credit card statement, anything else as a bank
+/// statement.
+pub fn write(statement: &OfxStatement) -> String {
+    let credit_card = statement.account.account_type.as_deref() == Some("CREDITCARD");
+    let (messages, response, statement_tag, account_tag) = if credit_card {
+        (
+            "CREDITCARDMSGSRSV1",
+            "CCSTMTTRNRS",
+            "CCSTMTRS",
+            "CCACCTFROM",
+        )
+    } else {
+        ("BANKMSGSRSV1", "STMTTRNRS", "STMTRS", "BANKACCTFROM")
+    };
+    let date = |d: NaiveDate| d.format("%Y%m%d").to_string();
+    let mut out = String::new();
+    let mut line = |s: String| {
+        out.push_str(&s);
+        out.push('\n');
+    };
+
+    for header in [
+        "OFXHEADER:100",
+        "DATA:OFXSGML",
+        "VERSION:102",
+        "SECURITY:NONE",
+        "ENCODING:USASCII",
+        "CHARSET:1252",
+        "COMPRESSION:NONE",
+        "OLDFILEUID:NONE",
+        "NEWFILEUID:NONE",
+        "",
+    ] {
+        line(header.to_string());
+    }
+    line("<OFX>".into());
+    line("<SIGNONMSGSRSV1>".into());
+    line("<SONRS>".into());
+    line("<STATUS>".into());
+    line("<CODE>0".into());
+    line("<SEVERITY>INFO".into());
+    line("</STATUS>".into());
+    line(format!(
+        "<DTSERVER>{}",
+        chrono::Utc::now().format("%Y%m%d%H%M%S")
+    ));
+    line("<LANGUAGE>ENG".into());
+    line("</SONRS>".into());
+    line("</SIGNONMSGSRSV1>".into());
+    line(format!("<{messages}>"));
+    line(format!("<{response}>"));
+    line("<TRNUID>1".into());
+    line("<STATUS>".into());
+    line("<CODE>0".into());
+    line("<SEVERITY>INFO".into());
+    line("</STATUS>".into());
+    line(format!("<{statement_tag}>"));
+    line(format!(
+        "<CURDEF>{}",
+        ofx_text(statement.currency.as_deref().unwrap_or("USD"))
+    ));
+    line(format!("<{account_tag}>"));
+    if !credit_card {
+        if let Some(bank_id) = &statement.account.bank_id {
+            line(format!("<BANKID>{}", ofx_text(bank_id)));
+        }
+    }
+    line(format!(
+        "<ACCTID>{}",
+        ofx_text(&statement.account.account_id)
+    ));
+    if !credit_card {
+        line(format!(
+            "<ACCTTYPE>{}",
+            statement
+                .account
+                .account_type
+                .as_deref()
+                .unwrap_or("CHECKING")
+        ));
+    }
+    line(format!("</{account_tag}>"));
+    line("<BANKTRANLIST>".into());
+    line(format!("<DTSTART>{}", date(statement.start_date)));
+    line(format!("<DTEND>{}", date(statement.end_date)));
+    for tx in &statement.transactions {
+        let trntype = match (&tx.check_number, tx.amount) {
+            (Some(_), _) => "CHECK",
+            (None, amount) if amount < 0 => "DEBIT",
+            _ => "CREDIT",
+        };
+        line("<STMTTRN>".into());
+        line(format!("<TRNTYPE>{trntype}"));
+        line(format!("<DTPOSTED>{}", date(tx.date)));
+        line(format!("<TRNAMT>{}", ofx_amount(tx.amount)));
+        line(format!("<FITID>{}", ofx_text(&tx.fit_id)));
+        if let Some(check) = &tx.check_number {
+            line(format!("<CHECKNUM>{}", ofx_text(check)));
+        }
+        if let Some(name) = tx.name.as_deref().map(ofx_text).filter(|n| !n.is_empty()) {
+            // OFX 1.x caps NAME at 32 characters.
+            let name: String = name.chars().take(32).collect();
+            line(format!("<NAME>{}", name.trim_end()));
+        }
+        if let Some(memo) = tx.memo.as_deref().map(ofx_text).filter(|m| !m.is_empty()) {
+            line(format!("<MEMO>{memo}"));
+        }
+        line("</STMTTRN>".into());
+    }
+    line("</BANKTRANLIST>".into());
+    if let Some(balance) = statement.ledger_balance {
+        line("<LEDGERBAL>".into());
+        line(format!("<BALAMT>{}", ofx_amount(balance)));
+        line(format!("<DTASOF>{}", date(statement.end_date)));
+        line("</LEDGERBAL>".into());
+    }
+    line(format!("</{statement_tag}>"));
+    line(format!("</{response}>"));
+    line(format!("</{messages}>"));
+    line("</OFX>".into());
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Investment statements
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvestmentAction {
+    Buy,
+    Sell,
+    Income,
+    Reinvest,
+}
+
+impl InvestmentAction {
+    fn from_tag(tag: &str) -> Option<Self> {
+        match tag {
+            "BUYSTOCK" | "BUYMF" => Some(Self::Buy),
+            "SELLSTOCK" | "SELLMF" => Some(Self::Sell),
+            "INCOME" => Some(Self::Income),
+            "REINVEST" => Some(Self::Reinvest),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Buy => "BUY",
+            Self::Sell => "SELL",
+            Self::Income => "INCOME",
+            Self::Reinvest => "REINVEST",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OfxSecurity {
+    /// CUSIP or other identifier from `<UNIQUEID>`.
+    pub id: String,
+    pub name: Option<String>,
+    pub ticker: Option<String>,
+}
+
+impl OfxSecurity {
+    /// Ticker if known, then name, then the raw identifier.
+    pub fn label(&self) -> &str {
+        self.ticker
+            .as_deref()
+            .or(self.name.as_deref())
+            .unwrap_or(&self.id)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OfxInvestmentTransaction {
+    pub fit_id: String,
+    pub action: InvestmentAction,
+    pub trade_date: NaiveDate,
+    pub settle_date: Option<NaiveDate>,
+    pub security: Option<OfxSecurity>,
+    pub units: Option<Decimal>,
+    pub unit_price: Option<Decimal>,
+    /// `<COMMISSION>` plus `<FEES>`, in cents.
+    pub fees: i64,
+    /// Net cash effect in cents: negative for purchases, positive for sales
+    /// and income.
+    pub total: i64,
+    /// `DIV`, `INTEREST`, `CGLONG`, `CGSHORT` or `MISC` for income and
+    /// reinvestments.
+    pub income_type: Option<String>,
+    pub memo: Option<String>,
+}
+
+impl OfxInvestmentTransaction {
+    pub fn description(&self) -> String {
+        let action = match self.action {
+            InvestmentAction::Income => self.income_type.as_deref().unwrap_or("INCOME"),
+            other => other.as_str(),
+        };
+        match &self.security {
+            Some(sec) => format!("{action} {}", sec.label()),
+            None => action.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OfxPosition {
+    pub security: OfxSecurity,
+    /// `LONG` or `SHORT`.
+    pub position_type: Option<String>,
+    pub units: Decimal,
+    pub unit_price: Option<Decimal>,
+    pub market_value: i64,
+    pub price_as_of: Option<NaiveDate>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OfxInvestmentStatement {
+    /// `bank_id` carries the `<BROKERID>`.
+    pub account: OfxAccount,
+    pub as_of: NaiveDate,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub currency: Option<String>,
+    pub transactions: Vec<OfxInvestmentTransaction>,
+    /// Cash-only activity from `<INVBANKTRAN>` (deposits, withdrawals,
+    /// sweep interest).
+    pub cash_transactions: Vec<OfxTransaction>,
+    pub positions: Vec<OfxPosition>,
+}
+
+impl OfxInvestmentStatement {
+    /// Every movement of cash in the account as plain statement rows, so a
+    /// brokerage statement can go through the same review queue as a bank
+    /// statement. Reinvestments are left out since no cash changes hands.
+    pub fn cash_flows(&self) -> Vec<OfxTransaction> {
+        let mut flows: Vec<OfxTransaction> = self
+            .transactions
+            .iter()
+            .filter(|t| t.action != InvestmentAction::Reinvest)
+            .map(|t| OfxTransaction {
+                fit_id: t.fit_id.clone(),
+                date: t.trade_date,
+                amount: t.total,
+                memo: t.memo.clone(),
+                name: Some(t.description()),
+                check_number: None,
+            })
+            .chain(self.cash_transactions.iter().cloned())
+            .collect();
+        flows.sort_by_key(|t| t.date);
+        flows
+    }
+}
+
+/// Whether the document carries an investment statement rather than a bank
+/// or credit card one.
+pub fn is_investment_statement(data: &[u8]) -> bool {
+    data.windows(b"<INVSTMTRS>".len())
+        .any(|w| w.eq_ignore_ascii_case(b"<INVSTMTRS>"))
+}
+
+#[derive(Default)]
+struct BuildingInvTrx {
+    fit_id: Option<String>,
+    trade_date: Option<NaiveDate>,
+    settle_date: Option<NaiveDate>,
+    security_id: Option<String>,
+    units: Option<Decimal>,
+    unit_price: Option<Decimal>,
+    fees: i64,
+    total: Option<i64>,
+    income_type: Option<String>,
+    memo: Option<String>,
+}
+
+#[derive(Default)]
+struct BuildingPosition {
+    security_id: Option<String>,
+    position_type: Option<String>,
+    units: Option<Decimal>,
+    unit_price: Option<Decimal>,
+    market_value: Option<i64>,
+    price_as_of: Option<NaiveDate>,
+}
+
+enum InvSection {
+    None,
+    Trade(InvestmentAction, BuildingInvTrx),
+    Cash(BuildingTrx),
+    Position(BuildingPosition),
+    Security(OfxSecurity),
+}
+
+const POSITION_TAGS: [&str; 5] = ["POSSTOCK", "POSMF", "POSDEBT", "POSOPT", "POSOTHER"];
+const SECURITY_TAGS: [&str; 5] = ["STOCKINFO", "MFINFO", "DEBTINFO", "OPTINFO", "OTHERINFO"];
+
+/// Parse an `<INVSTMTRS>` investment statement: trades, income and
+/// reinvestments, cash activity, and end-of-period positions. Securities are
+/// resolved against `<SECLIST>` so each row carries a ticker and name.
+pub fn parse_investment(data: &[u8]) -> Result<OfxInvestmentStatement, OfxError> {
+    let content = decode(data)?;
+    // Brokerage exports frequently put several tags on one line.
+    let normalized = content.replace('<', "\n<");
+
+    let mut account = OfxAccount {
+        account_id: String::new(),
+        bank_id: None,
+        account_type: Some("INVESTMENT".to_string()),
+    };
+    let mut as_of = None;
+    let mut start_date = None;
+    let mut end_date = None;
+    let mut currency = None;
+    let mut trades: Vec<(InvestmentAction, BuildingInvTrx)> = Vec::new();
+    let mut cash_transactions = Vec::new();
+    let mut positions: Vec<BuildingPosition> = Vec::new();
+    let mut securities: HashMap<String, OfxSecurity> = HashMap::new();
+    let mut section = InvSection::None;
+
+    for line in normalized.lines() {
+        let Some(tag) = line.trim().strip_prefix('<') else {
+            continue;
+        };
+        let (name, value) = match tag.split_once('>') {
+            Some((name, val)) => (name.trim().to_uppercase(), val.trim().to_string()),
+            None => (tag.trim().to_uppercase(), String::new()),
+        };
+        let value = (!value.is_empty()).then_some(value);
+
+        if let Some(action) = InvestmentAction::from_tag(&name) {
+            section = InvSection::Trade(action, BuildingInvTrx::default());
+            continue;
+        }
+        if POSITION_TAGS.contains(&name.as_str()) {
+            section = InvSection::Position(BuildingPosition::default());
+            continue;
+        }
+        if SECURITY_TAGS.contains(&name.as_str()) {
+            section = InvSection::Security(OfxSecurity {
+                id: String::new(),
+                name: None,
+                ticker: None,
+            });
+            continue;
+        }
+        if name == "STMTTRN" {
+            section = InvSection::Cash(BuildingTrx::default());
+            continue;
+        }
+        if let Some(closing) = name.strip_prefix('/') {
+            let closes_section = InvestmentAction::from_tag(closing).is_some()
+                || POSITION_TAGS.contains(&closing)
+                || SECURITY_TAGS.contains(&closing)
+                || closing == "STMTTRN";
+            if closes_section {
+                match std::mem::replace(&mut section, InvSection::None) {
+                    InvSection::Trade(action, trx) => trades.push((action, trx)),
+                    InvSection::Cash(trx) => {
+                        if let Some(date) = trx.date {
+                            cash_transactions.push(OfxTransaction {
+                                fit_id: trx.fit_id.unwrap_or_default(),
+                                date,
+                                amount: trx.amount.unwrap_or(0),
+                                memo: trx.memo,
+                                name: trx.name,
+                                check_number: trx.check_number,
+                            });
+                        }
+                    }
+                    InvSection::Position(pos) => positions.push(pos),
+                    InvSection::Security(sec) => {
+                        if !sec.id.is_empty() {
+                            securities.insert(sec.id.clone(), sec);
+                        }
+                    }
+                    InvSection::None => {}
+                }
+            }
+            continue;
+        }
+        let Some(v) = value else {
+            continue;
+        };
+
+        match (&mut section, name.as_str()) {
+            (InvSection::Trade(_, trx), tag) => match tag {
+                "FITID" => trx.fit_id = Some(v),
+                "DTTRADE" => trx.trade_date = parse_ofx_date(&v),
+                "DTSETTLE" => trx.settle_date = parse_ofx_date(&v),
+                "UNIQUEID" => trx.security_id = Some(v),
+                "UNITS" => trx.units = parse_decimal(&v),
+                "UNITPRICE" => trx.unit_price = parse_decimal(&v),
+                "COMMISSION" | "FEES" => trx.fees += parse_ofx_amount(&v).unwrap_or(0),
+                "TOTAL" => trx.total = parse_ofx_amount(&v),
+                "INCOMETYPE" => trx.income_type = Some(v),
+                "MEMO" => trx.memo = Some(v),
+                _ => {}
+            },
+            (InvSection::Cash(trx), tag) => match tag {
+                "FITID" => trx.fit_id = Some(v),
+                "DTPOSTED" => trx.date = parse_ofx_date(&v),
+                "TRNAMT" => trx.amount = parse_ofx_amount(&v),
+                "MEMO" => trx.memo = Some(v),
+                "NAME" => trx.name = Some(v),
+                "CHECKNUM" => trx.check_number = Some(v),
+                _ => {}
+            },
+            (InvSection::Position(pos), tag) => match tag {
+                "UNIQUEID" => pos.security_id = Some(v),
+                "POSTYPE" => pos.position_type = Some(v),
+                "UNITS" => pos.units = parse_decimal(&v),
+                "UNITPRICE" => pos.unit_price = parse_decimal(&v),
+                "MKTVAL" => pos.market_value = parse_ofx_amount(&v),
+                "DTPRICEASOF" => pos.price_as_of = parse_ofx_date(&v),
+                _ => {}
+            },
+            (InvSection::Security(sec), tag) => match tag {
+                "UNIQUEID" => sec.id = v,
+                "SECNAME" => sec.name = Some(v),
+                "TICKER" => sec.ticker = Some(v),
+                _ => {}
+            },
+            (InvSection::None, tag) => match tag {
+                "ACCTID" => account.account_id = v,
+                "BROKERID" => account.bank_id = Some(v),
+                // Balance aggregates repeat DTASOF; the statement's own comes first.
+                "DTASOF" => as_of = as_of.or_else(|| parse_ofx_date(&v)),
+                "DTSTART" => start_date = parse_ofx_date(&v),
+                "DTEND" => end_date = parse_ofx_date(&v),
+                "CURDEF" => currency = Some(v),
+                _ => {}
+            },
+        }
+    }
+
+    if account.account_id.is_empty() {
+        return Err(OfxError::MissingField("ACCTID".to_string()));
+    }
+    let as_of = as_of
+        .or(end_date)
+        .ok_or(OfxError::MissingField("DTASOF".to_string()))?;
+
+    let security = |id: Option<String>| -> Option<OfxSecurity> {
+        let id = id?;
+        Some(securities.get(&id).cloned().unwrap_or(OfxSecurity {
+            id,
+            name: None,
+            ticker: None,
+        }))
+    };
+
+    let transactions = trades
+        .into_iter()
+        .filter_map(|(action, trx)| {
+            Some(OfxInvestmentTransaction {
+                fit_id: trx.fit_id.unwrap_or_default(),
+                action,
+                trade_date: trx.trade_date?,
+                settle_date: trx.settle_date,
+                security: security(trx.security_id),
+                units: trx.units,
+                unit_price: trx.unit_price,
+                fees: trx.fees,
+                total: trx.total.unwrap_or(0),
+                income_type: trx.income_type,
+                memo: trx.memo,
+            })
+        })
+        .collect();
+    let positions = positions
+        .into_iter()
+        .filter_map(|pos| {
+            Some(OfxPosition {
+                security: security(pos.security_id)?,
+                position_type: pos.position_type,
+                units: pos.units.unwrap_or_default(),
+                unit_price: pos.unit_price,
+                market_value: pos.market_value.unwrap_or(0),
+                price_as_of: pos.price_as_of,
+            })
+        })
+        .collect();
+
+    Ok(OfxInvestmentStatement {
+        account,
+        as_of,
+        start_date,
+        end_date,
+        currency,
+        transactions,
+        cash_transactions,
+        positions,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -369,6 +911,49 @@ VERSION:102
         assert!(t1.memo.is_none());
     }
 
+    #[test]
+    fn written_statement_parses_back() {
+        let mut stmt = parse(SAMPLE_OFX.as_bytes()).unwrap();
+        stmt.transactions[0].check_number = Some("1042".into());
+        stmt.transactions[1].name = Some("DIRECT DEPOSIT FROM A VERY LONG PAYER NAME".into());
+        stmt.ledger_balance = Some(-12_345);
+
+        let out = write(&stmt);
+        assert!(out.starts_with("OFXHEADER:100\n"));
+        assert!(out.contains("<TRNTYPE>CHECK\n"));
+        assert!(out.contains("<TRNTYPE>CREDIT\n"));
+
+        let back = parse(out.as_bytes()).unwrap();
+        assert_eq!(back.account.account_id, stmt.account.account_id);
+        assert_eq!(back.account.bank_id, stmt.account.bank_id);
+        assert_eq!(back.account.account_type, stmt.account.account_type);
+        assert_eq!(back.start_date, stmt.start_date);
+        assert_eq!(back.end_date, stmt.end_date);
+        assert_eq!(back.currency, stmt.currency);
+        assert_eq!(back.ledger_balance, Some(-12_345));
+        let t0 = &back.transactions[0];
+        assert_eq!(t0.fit_id, "TXN001");
+        assert_eq!(t0.amount, -4999);
+        assert_eq!(t0.check_number.as_deref(), Some("1042"));
+        assert_eq!(t0.memo.as_deref(), Some("Online purchase"));
+        assert_eq!(
+            back.transactions[1].name.as_deref(),
+            Some("DIRECT DEPOSIT FROM A VERY LONG")
+        );
+        assert_eq!(back.transactions[1].amount, 150000);
+    }
+
+    #[test]
+    fn credit_card_statement_is_written_as_one() {
+        let mut stmt = parse(SAMPLE_OFX.as_bytes()).unwrap();
+        stmt.account.account_type = Some("CREDITCARD".into());
+        let out = write(&stmt);
+        assert!(out.contains("<CREDITCARDMSGSRSV1>"));
+        assert!(out.contains("<CCACCTFROM>\n<ACCTID>000112345\n</CCACCTFROM>"));
+        assert!(!out.contains("<BANKID>"));
+        assert_eq!(parse(out.as_bytes()).unwrap().transactions.len(), 2);
+    }
+
     #[test]
     fn parse_ofx_missing_account_id_errors() {
         let bad = r#"
@@ -401,4 +986,194 @@ VERSION:102
 "#;
         assert!(parse(bad.as_bytes()).is_err());
     }
+
+    // ── investment statements ─────────────────────────────────────────────────
+
+    const SAMPLE_INVESTMENT: &str = r#"
+OFXHEADER:100
+DATA:OFXSGML
+
+<OFX>
+<INVSTMTMSGSRSV1><INVSTMTTRNRS><TRNUID>1
+<INVSTMTRS>
+<DTASOF>20240131120000
+<CURDEF>USD
+<INVACCTFROM><BROKERID>example.com<ACCTID>Z123</INVACCTFROM>
+<INVTRANLIST>
+<DTSTART>20240101
+<DTEND>20240131
+<BUYSTOCK>
+<INVBUY>
+<INVTRAN><FITID>B1<DTTRADE>20240105<DTSETTLE>20240108<MEMO>Market order</INVTRAN>
+<SECID><UNIQUEID>037833100<UNIQUEIDTYPE>CUSIP</SECID>
+<UNITS>10.5<UNITPRICE>185.1234<COMMISSION>1.00<FEES>0.05<TOTAL>-1944.85
+<SUBACCTSEC>CASH<SUBACCTFUND>CASH
+</INVBUY>
+<BUYTYPE>BUY
+</BUYSTOCK>
+<SELLSTOCK>
+<INVSELL>
+<INVTRAN><FITID>S1<DTTRADE>20240120</INVTRAN>
+<SECID><UNIQUEID>594918104<UNIQUEIDTYPE>CUSIP</SECID>
+<UNITS>-5<UNITPRICE>400.00<COMMISSION>0<TOTAL>2000.00
+</INVSELL>
+<SELLTYPE>SELL
+</SELLSTOCK>
+<INCOME>
+<INVTRAN><FITID>I1<DTTRADE>20240115</INVTRAN>
+<SECID><UNIQUEID>037833100<UNIQUEIDTYPE>CUSIP</SECID>
+<INCOMETYPE>DIV<TOTAL>2.40
+</INCOME>
+<REINVEST>
+<INVTRAN><FITID>R1<DTTRADE>20240116</INVTRAN>
+<SECID><UNIQUEID>922908363<UNIQUEIDTYPE>CUSIP</SECID>
+<INCOMETYPE>DIV<TOTAL>-12.00<UNITS>0.025<UNITPRICE>480.00
+</REINVEST>
+<INVBANKTRAN>
+<STMTTRN><TRNTYPE>CREDIT<DTPOSTED>20240102<TRNAMT>5000.00<FITID>C1<NAME>ACH DEPOSIT</STMTTRN>
+<SUBACCTFUND>CASH
+</INVBANKTRAN>
+</INVTRANLIST>
+<INVPOSLIST>
+<POSSTOCK><INVPOS>
+<SECID><UNIQUEID>037833100<UNIQUEIDTYPE>CUSIP</SECID>
+<HELDINACCT>CASH<POSTYPE>LONG<UNITS>10.5<UNITPRICE>188.00<MKTVAL>1974.00<DTPRICEASOF>20240131
+</INVPOS></POSSTOCK>
+<POSMF><INVPOS>
+<SECID><UNIQUEID>922908363<UNIQUEIDTYPE>CUSIP</SECID>
+<HELDINACCT>CASH<POSTYPE>LONG<UNITS>3.025<UNITPRICE>482.10<MKTVAL>1458.35
+</INVPOS></POSMF>
+</INVPOSLIST>
+<INVBAL><AVAILCASH>3057.55<MARGINBALANCE>0<SHORTBALANCE>0
+<BALLIST><BAL><NAME>Cash<BALTYPE>DOLLAR<VALUE>3057.55<DTASOF>20231231</BAL></BALLIST>
+</INVBAL>
+</INVSTMTRS>
+</INVSTMTTRNRS></INVSTMTMSGSRSV1>
+<SECLISTMSGSRSV1><SECLIST>
+<STOCKINFO><SECINFO><SECID><UNIQUEID>037833100<UNIQUEIDTYPE>CUSIP</SECID><SECNAME>Apple Inc.<TICKER>AAPL</SECINFO></STOCKINFO>
+<STOCKINFO><SECINFO><SECID><UNIQUEID>594918104<UNIQUEIDTYPE>CUSIP</SECID><SECNAME>Microsoft Corp.</SECINFO></STOCKINFO>
+<MFINFO><SECINFO><SECID><UNIQUEID>922908363<UNIQUEIDTYPE>CUSIP</SECID><SECNAME>Vanguard 500 Index<TICKER>VFIAX</SECINFO></MFINFO>
+</SECLIST></SECLISTMSGSRSV1>
+</OFX>
+"#;
+
+    #[test]
+    fn detects_investment_statement() {
+        assert!(is_investment_statement(SAMPLE_INVESTMENT.as_bytes()));
+        assert!(!is_investment_statement(SAMPLE_OFX.as_bytes()));
+        assert!(is_investment_statement(
+            b"<ofx><invstmtrs></invstmtrs></ofx>"
+        ));
+    }
+
+    #[test]
+    fn parse_investment_header() {
+        let stmt = parse_investment(SAMPLE_INVESTMENT.as_bytes()).unwrap();
+        assert_eq!(stmt.account.account_id, "Z123");
+        assert_eq!(stmt.account.bank_id.as_deref(), Some("example.com"));
+        assert_eq!(stmt.account.account_type.as_deref(), Some("INVESTMENT"));
+        assert_eq!(stmt.as_of, NaiveDate::from_ymd_opt(2024, 1, 31).unwrap());
+        assert_eq!(
+            stmt.start_date,
+            Some(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap())
+        );
+        assert_eq!(stmt.currency.as_deref(), Some("USD"));
+        assert_eq!(stmt.transactions.len(), 4);
+        assert_eq!(stmt.cash_transactions.len(), 1);
+        assert_eq!(stmt.positions.len(), 2);
+    }
+
+    #[test]
+    fn parse_investment_buy() {
+        let stmt = parse_investment(SAMPLE_INVESTMENT.as_bytes()).unwrap();
+        let buy = &stmt.transactions[0];
+        assert_eq!(buy.action, InvestmentAction::Buy);
+        assert_eq!(buy.fit_id, "B1");
+        assert_eq!(buy.trade_date, NaiveDate::from_ymd_opt(2024, 1, 5).unwrap());
+        assert_eq!(
+            buy.settle_date,
+            Some(NaiveDate::from_ymd_opt(2024, 1, 8).unwrap())
+        );
+        assert_eq!(buy.units, Some(Decimal::from_str("10.5").unwrap()));
+        assert_eq!(buy.unit_price, Some(Decimal::from_str("185.1234").unwrap()));
+        assert_eq!(buy.fees, 105);
+        assert_eq!(buy.total, -194485);
+        assert_eq!(buy.memo.as_deref(), Some("Market order"));
+        let sec = buy.security.as_ref().unwrap();
+        assert_eq!(sec.id, "037833100");
+        assert_eq!(sec.ticker.as_deref(), Some("AAPL"));
+        assert_eq!(buy.description(), "BUY AAPL");
+    }
+
+    #[test]
+    fn parse_investment_sell_income_reinvest() {
+        let stmt = parse_investment(SAMPLE_INVESTMENT.as_bytes()).unwrap();
+        let sell = &stmt.transactions[1];
+        assert_eq!(sell.action, InvestmentAction::Sell);
+        assert_eq!(sell.total, 200000);
+        // No ticker in the security list, so the name is used.
+        assert_eq!(sell.description(), "SELL Microsoft Corp.");
+
+        let income = &stmt.transactions[2];
+        assert_eq!(income.action, InvestmentAction::Income);
+        assert_eq!(income.income_type.as_deref(), Some("DIV"));
+        assert_eq!(income.total, 240);
+        assert_eq!(income.description(), "DIV AAPL");
+
+        let reinvest = &stmt.transactions[3];
+        assert_eq!(reinvest.action, InvestmentAction::Reinvest);
+        assert_eq!(reinvest.units, Some(Decimal::from_str("0.025").unwrap()));
+        assert_eq!(reinvest.total, -1200);
+    }
+
+    #[test]
+    fn parse_investment_positions() {
+        let stmt = parse_investment(SAMPLE_INVESTMENT.as_bytes()).unwrap();
+        let aapl = &stmt.positions[0];
+        assert_eq!(aapl.security.label(), "AAPL");
+        assert_eq!(aapl.position_type.as_deref(), Some("LONG"));
+        assert_eq!(aapl.units, Decimal::from_str("10.5").unwrap());
+        assert_eq!(aapl.market_value, 197400);
+        assert_eq!(
+            aapl.price_as_of,
+            Some(NaiveDate::from_ymd_opt(2024, 1, 31).unwrap())
+        );
+        let fund = &stmt.positions[1];
+        assert_eq!(fund.security.name.as_deref(), Some("Vanguard 500 Index"));
+        assert_eq!(fund.market_value, 145835);
+    }
+
+    #[test]
+    fn investment_cash_flows_skip_reinvestments() {
+        let stmt = parse_investment(SAMPLE_INVESTMENT.as_bytes()).unwrap();
+        let flows = stmt.cash_flows();
+        let ids: Vec<&str> = flows.iter().map(|t| t.fit_id.as_str()).collect();
+        assert_eq!(ids, ["C1", "B1", "I1", "S1"]);
+        assert_eq!(flows[0].name.as_deref(), Some("ACH DEPOSIT"));
+        assert_eq!(flows[1].amount, -194485);
+        assert_eq!(flows[1].name.as_deref(), Some("BUY AAPL"));
+        let net: i64 = flows.iter().map(|t| t.amount).sum();
+        assert_eq!(net, 500000 - 194485 + 240 + 200000);
+    }
+
+    #[test]
+    fn parse_investment_unknown_security_keeps_id() {
+        let data = "<OFX><INVSTMTRS><DTASOF>20240131<INVACCTFROM><ACCTID>1</INVACCTFROM>\
+            <INVTRANLIST><INCOME><INVTRAN><FITID>X<DTTRADE>20240110</INVTRAN>\
+            <SECID><UNIQUEID>999<UNIQUEIDTYPE>CUSIP</SECID><INCOMETYPE>INTEREST<TOTAL>1.00\
+            </INCOME></INVTRANLIST></INVSTMTRS></OFX>";
+        let stmt = parse_investment(data.as_bytes()).unwrap();
+        assert_eq!(stmt.transactions[0].description(), "INTEREST 999");
+        assert!(stmt.start_date.is_none());
+        assert!(stmt.positions.is_empty());
+    }
+
+    #[test]
+    fn parse_investment_missing_account_errors() {
+        let data = "<OFX><INVSTMTRS><DTASOF>20240131</INVSTMTRS></OFX>";
+        assert!(matches!(
+            parse_investment(data.as_bytes()),
+            Err(OfxError::MissingField(f)) if f == "ACCTID"
+        ));
+    }
 }
@@ -0,0 +1,364 @@
+//! First look at a bank file before it is imported: the columns and opening
+//! rows, and the CSV profile that would be used to read it.
+
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::bank_intake::{
+    parse_bank_file, parse_bank_file_with_profile, select_csv_profile, BankFileImport,
+    BankFileKind, BankIntakeError,
+};
+use crate::csv::{parse_amount, CsvColumnMapping, CsvImportProfile};
+
+/// Rows returned in a preview.
+pub const SAMPLE_ROWS: usize = 10;
+
+/// Date formats tried, in order, when guessing a CSV's date column.
+const DATE_FORMATS: &[&str] = &[
+    "%Y-%m-%d", "%m/%d/%Y", "%d/%m/%Y", "%m/%d/%y", "%Y/%m/%d", "%d.%m.%Y", "%m-%d-%Y", "%d-%m-%Y",
+];
+
+const DELIMITERS: &[u8] = b",;\t|";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportPreview {
+    pub kind: BankFileKind,
+    /// Header names, or `Column N` when the file has no header row.
+    pub columns: Vec<String>,
+    pub sample_rows: Vec<Vec<String>>,
+    /// Profile to read the file with: the one being tested, the matching
+    /// saved profile, or a mapping guessed from the headers. `None` for OFX
+    /// and QIF files.
+    pub suggested_profile: Option<CsvImportProfile>,
+    /// True when `suggested_profile` is a saved profile.
+    pub profile_matched: bool,
+    /// Transactions the suggested profile reads from the whole file.
+    pub transaction_count: usize,
+    /// Why the suggested profile could not read the file, if it couldn't.
+    pub parse_error: Option<String>,
+}
+
+/// Preview a bank file. OFX and QIF files are shown as parsed transactions;
+/// CSVs as their raw columns alongside the profile that would read them.
+pub fn preview_bank_file(
+    file_name: &str,
+    data: &[u8],
+    profiles: &[CsvImportProfile],
+) -> Result<ImportPreview, BankIntakeError> {
+    let kind = BankFileKind::from_path(Path::new(file_name))
+        .ok_or_else(|| BankIntakeError::UnsupportedFile(file_name.to_string()))?;
+
+    if kind != BankFileKind::Csv {
+        let parsed = parse_bank_file(file_name, data, profiles)?;
+        return Ok(statement_preview(parsed));
+    }
+
+    let text = std::str::from_utf8(data).map_err(|_| BankIntakeError::Encoding)?;
+    let (profile, profile_matched) = match select_csv_profile(file_name, text, profiles) {
+        Some(p) => (p.clone(), true),
+        None => (guess_csv_profile(text), false),
+    };
+    Ok(csv_preview(file_name, data, text, profile, profile_matched))
+}
+
+/// Preview a CSV as `profile` would read it, to check a mapping before it is
+/// saved. Other formats don't use profiles and preview as usual.
+pub fn preview_with_profile(
+    file_name: &str,
+    data: &[u8],
+    profile: &CsvImportProfile,
+) -> Result<ImportPreview, BankIntakeError> {
+    let kind = BankFileKind::from_path(Path::new(file_name))
+        .ok_or_else(|| BankIntakeError::UnsupportedFile(file_name.to_string()))?;
+    if kind != BankFileKind::Csv {
+        return preview_bank_file(file_name, data, &[]);
+    }
+    let text = std::str::from_utf8(data).map_err(|_| BankIntakeError::Encoding)?;
+    Ok(csv_preview(file_name, data, text, profile.clone(), false))
+}
+
+fn csv_preview(
+    file_name: &str,
+    data: &[u8],
+    text: &str,
+    profile: CsvImportProfile,
+    profile_matched: bool,
+) -> ImportPreview {
+    let rows = read_rows(text, delimiter_byte(&profile.delimiter));
+    let (columns, sample_rows) = if profile.has_header && !rows.is_empty() {
+        (rows[0].clone(), rows[1..].to_vec())
+    } else {
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        ((1..=width).map(|n| format!("Column {n}")).collect(), rows)
+    };
+
+    let (transaction_count, parse_error) =
+        match parse_bank_file_with_profile(file_name, data, &profile) {
+            Ok(parsed) => (parsed.transactions.len(), None),
+            Err(e) => (0, Some(e.to_string())),
+        };
+
+    ImportPreview {
+        kind: BankFileKind::Csv,
+        columns,
+        sample_rows: sample_rows.into_iter().take(SAMPLE_ROWS).collect(),
+        suggested_profile: Some(profile),
+        profile_matched,
+        transaction_count,
+        parse_error,
+    }
+}
+
+fn statement_preview(parsed: BankFileImport) -> ImportPreview {
+    let sample_rows = parsed
+        .transactions
+        .iter()
+        .take(SAMPLE_ROWS)
+        .map(|t| {
+            vec![
+                t.date.to_string(),
+                t.description.clone(),
+                format!("{:.2}", t.amount_cents as f64 / 100.0),
+                t.memo.clone().unwrap_or_default(),
+            ]
+        })
+        .collect();
+    ImportPreview {
+        kind: parsed.kind,
+        columns: ["Date", "Description", "Amount", "Memo"]
+            .map(str::to_string)
+            .to_vec(),
+        sample_rows,
+        suggested_profile: None,
+        profile_matched: false,
+        transaction_count: parsed.transactions.len(),
+        parse_error: None,
+    }
+}
+
+/// Guess a profile for a CSV no saved profile matches, from its delimiter,
+/// header names and the shape of its opening rows.
+pub fn guess_csv_profile(text: &str) -> CsvImportProfile {
+    let delimiter = detect_delimiter(text);
+    let rows = read_rows(text, delimiter);
+    let first = rows.first().cloned().unwrap_or_default();
+    let has_header = !first.is_empty()
+        && first.iter().all(|f| {
+            date_format_for(std::slice::from_ref(f)).is_none() && parse_amount(f).is_err()
+        });
+    let data_rows = if has_header { &rows[1..] } else { &rows[..] };
+
+    let mut mapping = CsvColumnMapping::default();
+    if has_header {
+        map_by_header(&first, &mut mapping);
+    }
+
+    let column = |i: usize| -> Vec<String> {
+        data_rows
+            .iter()
+            .filter_map(|r| r.get(i).cloned())
+            .filter(|v| !v.trim().is_empty())
+            .collect()
+    };
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+
+    if mapping.date_column.is_none() {
+        mapping.date_column = (0..width).find(|&i| date_format_for(&column(i)).is_some());
+    }
+    if let Some(fmt) = mapping
+        .date_column
+        .and_then(|i| date_format_for(&column(i)))
+    {
+        mapping.date_format = fmt.to_string();
+    }
+
+    let taken = |m: &CsvColumnMapping, i: usize| {
+        [
+            m.date_column,
+            m.description_column,
+            m.amount_column,
+            m.debit_column,
+            m.credit_column,
+            m.memo_column,
+        ]
+        .contains(&Some(i))
+    };
+    let numeric =
+        |values: &[String]| !values.is_empty() && values.iter().all(|v| parse_amount(v).is_ok());
+    if mapping.amount_column.is_none() && mapping.debit_column.is_none() {
+        mapping.amount_column = (0..width).find(|&i| !taken(&mapping, i) && numeric(&column(i)));
+    }
+    if mapping.description_column.is_none() {
+        // The widest remaining text column is usually the payee.
+        mapping.description_column = (0..width)
+            .filter(|&i| !taken(&mapping, i) && !numeric(&column(i)))
+            .max_by_key(|&i| column(i).iter().map(String::len).sum::<usize>());
+    }
+
+    CsvImportProfile {
+        id: None,
+        name: "New Profile".to_string(),
+        mapping,
+        has_header,
+        delimiter: (delimiter as char).to_string(),
+    }
+}
+
+fn map_by_header(headers: &[String], mapping: &mut CsvColumnMapping) {
+    let find = |keys: &[&str]| {
+        headers.iter().position(|h| {
+            let h = h.trim().to_lowercase();
+            keys.iter().any(|k| h.contains(k))
+        })
+    };
+    mapping.date_column = find(&["date", "posted"]);
+    mapping.description_column = find(&["description", "payee", "merchant", "name", "details"]);
+    mapping.debit_column = find(&["debit", "withdrawal"]);
+    mapping.credit_column = find(&["credit", "deposit"]);
+    if mapping.debit_column.is_none() || mapping.credit_column.is_none() {
+        mapping.debit_column = None;
+        mapping.credit_column = None;
+        mapping.amount_column = find(&["amount", "value"]);
+    }
+    mapping.memo_column = find(&["memo", "note", "reference"]);
+}
+
+/// First format that reads every value, if any do.
+fn date_format_for(values: &[String]) -> Option<&'static str> {
+    if values.is_empty() {
+        return None;
+    }
+    DATE_FORMATS.iter().copied().find(|fmt| {
+        values
+            .iter()
+            .all(|v| chrono::NaiveDate::parse_from_str(v.trim(), fmt).is_ok())
+    })
+}
+
+/// The candidate delimiter splitting the first line into the most fields.
+fn detect_delimiter(text: &str) -> u8 {
+    let line = text.lines().next().unwrap_or_default();
+    DELIMITERS
+        .iter()
+        .copied()
+        .max_by_key(|&d| line.bytes().filter(|&b| b == d).count())
+        .filter(|&d| line.as_bytes().contains(&d))
+        .unwrap_or(b',')
+}
+
+fn delimiter_byte(delimiter: &str) -> u8 {
+    delimiter.as_bytes().first().copied().unwrap_or(b',')
+}
+
+/// The first rows of `text`, header included.
+fn read_rows(text: &str, delimiter: u8) -> Vec<Vec<String>> {
+    csv::ReaderBuilder::new()
+        .has_headers(false)
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(text.as_bytes())
+        .records()
+        .filter_map(Result::ok)
+        .take(SAMPLE_ROWS + 1)
+        .map(|r| r.iter().map(str::to_string).collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guesses_mapping_from_headers() {
+        let text = "Transaction Date;Payee;Memo;Amount\n15.01.2026;COFFEE;card;-4.50\n";
+        let p = guess_csv_profile(text);
+        assert_eq!(p.delimiter, ";");
+        assert!(p.has_header);
+        assert_eq!(p.mapping.date_column, Some(0));
+        assert_eq!(p.mapping.description_column, Some(1));
+        assert_eq!(p.mapping.memo_column, Some(2));
+        assert_eq!(p.mapping.amount_column, Some(3));
+        assert_eq!(p.mapping.date_format, "%d.%m.%Y");
+    }
+
+    #[test]
+    fn guesses_debit_and_credit_columns() {
+        let text = "Date,Description,Debit,Credit\n2026-01-15,COFFEE,4.50,\n";
+        let p = guess_csv_profile(text);
+        assert_eq!(p.mapping.amount_column, None);
+        assert_eq!(p.mapping.debit_column, Some(2));
+        assert_eq!(p.mapping.credit_column, Some(3));
+    }
+
+    #[test]
+    fn guesses_headerless_columns_from_values() {
+        let text = "01/15/2026,-4.50,WHOLE FOODS MARKET\n01/16/2026,100.00,CLIENT PAYMENT\n";
+        let p = guess_csv_profile(text);
+        assert!(!p.has_header);
+        assert_eq!(p.mapping.date_column, Some(0));
+        assert_eq!(p.mapping.date_format, "%m/%d/%Y");
+        assert_eq!(p.mapping.amount_column, Some(1));
+        assert_eq!(p.mapping.description_column, Some(2));
+    }
+
+    #[test]
+    fn csv_preview_reads_with_guessed_profile() {
+        let data = "Date,Description,Amount\n01/15/2026,COFFEE,-4.50\n01/16/2026,CLIENT,100.00\n";
+        let out = preview_bank_file("export.csv", data.as_bytes(), &[]).unwrap();
+        assert_eq!(out.columns, vec!["Date", "Description", "Amount"]);
+        assert_eq!(out.sample_rows.len(), 2);
+        assert!(!out.profile_matched);
+        assert_eq!(out.transaction_count, 2);
+        assert!(out.parse_error.is_none());
+    }
+
+    #[test]
+    fn csv_preview_prefers_saved_profile() {
+        let saved = CsvImportProfile {
+            name: "Chase".to_string(),
+            mapping: CsvColumnMapping {
+                date_column: Some(0),
+                description_column: Some(1),
+                amount_column: Some(2),
+                date_format: "%m/%d/%Y".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let data = "Date,Description,Amount\n01/15/2026,COFFEE,-4.50\n";
+        let out = preview_bank_file("chase-jan.csv", data.as_bytes(), &[saved]).unwrap();
+        assert!(out.profile_matched);
+        assert_eq!(out.suggested_profile.unwrap().name, "Chase");
+    }
+
+    #[test]
+    fn preview_with_profile_reports_parse_errors() {
+        let profile = CsvImportProfile {
+            name: "Wrong".to_string(),
+            mapping: CsvColumnMapping {
+                date_column: Some(0),
+                description_column: Some(2),
+                amount_column: Some(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let data = "Date,Description,Amount\n01/15/2026,COFFEE,-4.50\n";
+        let out = preview_with_profile("sample.csv", data.as_bytes(), &profile).unwrap();
+        assert_eq!(out.columns, vec!["Date", "Description", "Amount"]);
+        assert_eq!(out.suggested_profile.unwrap().name, "Wrong");
+        assert_eq!(out.transaction_count, 0);
+        assert!(out.parse_error.is_some());
+    }
+
+    #[test]
+    fn qif_preview_lists_transactions() {
+        let data = b"!Type:Bank\nD01/15/2026\nT-12.00\nPLUNCH\n^\n";
+        let out = preview_bank_file("stmt.qif", data, &[]).unwrap();
+        assert_eq!(out.kind, BankFileKind::Qif);
+        assert!(out.suggested_profile.is_none());
+        assert_eq!(out.sample_rows[0][1], "LUNCH");
+        assert_eq!(out.sample_rows[0][2], "-12.00");
+    }
+}
@@ -0,0 +1,199 @@
+use chrono::NaiveDate;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// One `^`-terminated record from a QIF bank register.
+#[derive(Debug, Clone)]
+pub struct QifTransaction {
+    pub date: NaiveDate,
+    pub amount: i64,
+    pub payee: Option<String>,
+    pub memo: Option<String>,
+    pub check_number: Option<String>,
+    pub category: Option<String>,
+}
+
+#[derive(Error, Debug)]
+pub enum QifError {
+    #[error("Invalid date format: {0}")]
+    InvalidDate(String),
+    #[error("Invalid amount: {0}")]
+    InvalidAmount(String),
+    #[error("Record {0} is missing a date or amount")]
+    IncompleteRecord(usize),
+    #[error("No transactions found")]
+    NoTransactions,
+}
+
+/// Parse the transactions out of a QIF file. Only bank/cash/credit card
+/// registers are meaningful here; investment and memorized-list sections
+/// (`!Type:Invst`, `!Type:Memorized`, ...) are skipped.
+pub fn parse(data: &str) -> Result<Vec<QifTransaction>, QifError> {
+    let mut transactions = Vec::new();
+    let mut in_register = true;
+
+    let mut date = None;
+    let mut amount = None;
+    let mut payee = None;
+    let mut memo = None;
+    let mut check_number = None;
+    let mut category = None;
+    let mut has_fields = false;
+
+    for line in data.lines() {
+        let line = line.trim_end_matches('\r');
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(header) = line.strip_prefix('!') {
+            let header = header.to_ascii_lowercase();
+            if let Some(kind) = header.strip_prefix("type:") {
+                in_register = matches!(kind.trim(), "bank" | "cash" | "ccard" | "oth a" | "oth l");
+            }
+            continue;
+        }
+        if !in_register {
+            continue;
+        }
+
+        let mut chars = line.chars();
+        let code = chars.next();
+        let value = chars.as_str().trim();
+        match code {
+            Some('D') => date = Some(parse_date(value)?),
+            Some('T' | 'U') => amount = Some(parse_amount(value)?),
+            Some('P') => payee = non_empty(value),
+            Some('M') => memo = non_empty(value),
+            Some('N') => check_number = non_empty(value),
+            Some('L') => category = non_empty(value),
+            Some('^') => {
+                if has_fields {
+                    let (Some(date), Some(amount)) = (date.take(), amount.take()) else {
+                        return Err(QifError::IncompleteRecord(transactions.len() + 1));
+                    };
+                    transactions.push(QifTransaction {
+                        date,
+                        amount,
+                        payee: payee.take(),
+                        memo: memo.take(),
+                        check_number: check_number.take(),
+                        category: category.take(),
+                    });
+                }
+                has_fields = false;
+                continue;
+            }
+            _ => {}
+        }
+        has_fields = true;
+    }
+
+    if transactions.is_empty() {
+        return Err(QifError::NoTransactions);
+    }
+    Ok(transactions)
+}
+
+fn non_empty(s: &str) -> Option<String> {
+    (!s.is_empty()).then(|| s.to_string())
+}
+
+/// QIF dates come in several US-centric shapes: `01/15/2024`, `1/15/24`,
+/// `1/15'24` (Quicken's post-2000 form) and occasionally ISO.
+fn parse_date(s: &str) -> Result<NaiveDate, QifError> {
+    let normalized = s.replace('\'', "/").replace(' ', "");
+    // chrono's %Y happily reads "24" as year 24, so pick the two-digit form
+    // explicitly when that is what the file contains.
+    let two_digit_year = normalized
+        .rsplit('/')
+        .next()
+        .is_some_and(|y| y.len() <= 2 && normalized.contains('/'));
+    let slash_fmt = if two_digit_year {
+        "%m/%d/%y"
+    } else {
+        "%m/%d/%Y"
+    };
+    for fmt in [slash_fmt, "%Y-%m-%d", "%m-%d-%Y", "%d.%m.%Y"] {
+        if let Ok(d) = NaiveDate::parse_from_str(&normalized, fmt) {
+            return Ok(d);
+        }
+    }
+    Err(QifError::InvalidDate(s.to_string()))
+}
+
+fn parse_amount(s: &str) -> Result<i64, QifError> {
+    let cleaned = s.replace([',', '$', ' '], "");
+    Decimal::from_str(&cleaned)
+        .ok()
+        .and_then(|d| (d * Decimal::from(100)).round().to_i64())
+        .ok_or_else(|| QifError::InvalidAmount(s.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "!Type:Bank\n\
+D01/15/2024\n\
+T-45.67\n\
+PWHOLE FOODS\n\
+MGroceries run\n\
+LGroceries\n\
+^\n\
+D1/20'24\n\
+T1,250.00\n\
+PACME CORP\n\
+N1042\n\
+^\n";
+
+    #[test]
+    fn parses_bank_register() {
+        let txs = parse(SAMPLE).unwrap();
+        assert_eq!(txs.len(), 2);
+        assert_eq!(txs[0].date, NaiveDate::from_ymd_opt(2024, 1, 15).unwrap());
+        assert_eq!(txs[0].amount, -4567);
+        assert_eq!(txs[0].payee.as_deref(), Some("WHOLE FOODS"));
+        assert_eq!(txs[0].memo.as_deref(), Some("Groceries run"));
+        assert_eq!(txs[0].category.as_deref(), Some("Groceries"));
+        assert_eq!(txs[1].date, NaiveDate::from_ymd_opt(2024, 1, 20).unwrap());
+        assert_eq!(txs[1].amount, 125000);
+        assert_eq!(txs[1].check_number.as_deref(), Some("1042"));
+    }
+
+    #[test]
+    fn crlf_line_endings() {
+        let crlf = SAMPLE.replace('\n', "\r\n");
+        assert_eq!(parse(&crlf).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn skips_non_register_sections() {
+        let data =
+            "!Type:Memorized\nT-10.00\nPNETFLIX\n^\n!Type:Bank\nD2024-02-01\nT-5.00\nPCOFFEE\n^\n";
+        let txs = parse(data).unwrap();
+        assert_eq!(txs.len(), 1);
+        assert_eq!(txs[0].payee.as_deref(), Some("COFFEE"));
+    }
+
+    #[test]
+    fn record_without_amount_is_an_error() {
+        let data = "!Type:Bank\nD01/15/2024\nPNO AMOUNT\n^\n";
+        assert!(matches!(parse(data), Err(QifError::IncompleteRecord(1))));
+    }
+
+    #[test]
+    fn empty_file_has_no_transactions() {
+        assert!(matches!(
+            parse("!Type:Bank\n"),
+            Err(QifError::NoTransactions)
+        ));
+    }
+
+    #[test]
+    fn invalid_date_rejected() {
+        let data = "!Type:Bank\nDnot a date\nT1.00\n^\n";
+        assert!(matches!(parse(data), Err(QifError::InvalidDate(_))));
+    }
+}
@@ -0,0 +1,346 @@
+use serde::{Deserialize, Serialize};
+
+use crate::rules::{CategoryRule, CategoryRuleEngine};
+
+/// Current version of the rule set file format. Bump when the layout changes
+/// in a way older builds cannot read.
+pub const RULE_SET_FORMAT_VERSION: u32 = 1;
+
+/// Maximum size of a serialized rule set (1 MB).
+const MAX_RULE_SET_SIZE: usize = 1_048_576;
+
+/// A versioned, shareable collection of categorization rules.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleSet {
+    pub format_version: u32,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub exported_at: Option<String>,
+    #[serde(default)]
+    pub rules: Vec<CategoryRule>,
+}
+
+impl RuleSet {
+    pub fn new(rules: Vec<CategoryRule>) -> Self {
+        Self {
+            format_version: RULE_SET_FORMAT_VERSION,
+            name: None,
+            exported_at: Some(chrono::Utc::now().to_rfc3339()),
+            rules,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleSetFormat {
+    Toml,
+    Json,
+}
+
+impl std::str::FromStr for RuleSetFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "toml" => Ok(RuleSetFormat::Toml),
+            "json" => Ok(RuleSetFormat::Json),
+            other => Err(format!("Unknown rule set format: '{other}'")),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RuleSharingError {
+    #[error("failed to serialize rule set: {0}")]
+    Serialize(String),
+    #[error("failed to parse rule set: {0}")]
+    Parse(String),
+    #[error("unsupported rule set version {found} (this build reads up to {supported})")]
+    UnsupportedVersion { found: u32, supported: u32 },
+    #[error("validation error: {0}")]
+    Validation(String),
+}
+
+/// How to treat an incoming rule whose pattern and match type already exist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// Keep the existing rule and drop the incoming one.
+    #[default]
+    Skip,
+    /// Replace the existing rule with the incoming one.
+    Replace,
+    /// Import the incoming rule alongside the existing one.
+    KeepBoth,
+}
+
+/// Outcome of merging an incoming rule set into an existing one.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RuleImportPlan {
+    /// Rules with no counterpart in the existing set.
+    pub to_insert: Vec<CategoryRule>,
+    /// `(index into existing, incoming rule)` pairs to overwrite.
+    pub to_replace: Vec<(usize, CategoryRule)>,
+    /// Incoming rules already present verbatim.
+    pub unchanged: Vec<CategoryRule>,
+    /// Incoming rules that conflict with an existing rule and were skipped.
+    pub skipped: Vec<CategoryRule>,
+}
+
+/// Serialize rules to a versioned TOML or JSON document.
+pub fn export_rule_set(set: &RuleSet, format: RuleSetFormat) -> Result<String, RuleSharingError> {
+    match format {
+        RuleSetFormat::Toml => {
+            toml::to_string_pretty(set).map_err(|e| RuleSharingError::Serialize(e.to_string()))
+        }
+        RuleSetFormat::Json => serde_json::to_string_pretty(set)
+            .map_err(|e| RuleSharingError::Serialize(e.to_string())),
+    }
+}
+
+/// Parse and validate a rule set document.
+pub fn import_rule_set(data: &str, format: RuleSetFormat) -> Result<RuleSet, RuleSharingError> {
+    if data.len() > MAX_RULE_SET_SIZE {
+        return Err(RuleSharingError::Validation(format!(
+            "rule set too large ({} bytes, max {})",
+            data.len(),
+            MAX_RULE_SET_SIZE
+        )));
+    }
+
+    let set: RuleSet = match format {
+        RuleSetFormat::Toml => {
+            toml::from_str(data).map_err(|e| RuleSharingError::Parse(e.to_string()))?
+        }
+        RuleSetFormat::Json => {
+            serde_json::from_str(data).map_err(|e| RuleSharingError::Parse(e.to_string()))?
+        }
+    };
+
+    if set.format_version == 0 || set.format_version > RULE_SET_FORMAT_VERSION {
+        return Err(RuleSharingError::UnsupportedVersion {
+            found: set.format_version,
+            supported: RULE_SET_FORMAT_VERSION,
+        });
+    }
+    for rule in &set.rules {
+        rule.validate().map_err(RuleSharingError::Validation)?;
+    }
+    Ok(set)
+}
+
+/// Two rules collide when they look for the same text the same way.
+fn same_matcher(a: &CategoryRule, b: &CategoryRule) -> bool {
+    a.match_type == b.match_type && a.pattern.to_lowercase() == b.pattern.to_lowercase()
+}
+
+fn identical(a: &CategoryRule, b: &CategoryRule) -> bool {
+    same_matcher(a, b)
+        && a.account_code == b.account_code
+        && a.priority == b.priority
+        && a.amount_min_cents == b.amount_min_cents
+        && a.amount_max_cents == b.amount_max_cents
+}
+
+/// Build a categorization engine from a versioned TOML rule set.
+pub fn rule_engine_from_toml(toml_content: &str) -> Result<CategoryRuleEngine, String> {
+    let set = import_rule_set(toml_content, RuleSetFormat::Toml).map_err(|e| e.to_string())?;
+    Ok(CategoryRuleEngine::new(set.rules))
+}
+
+/// Decide what to do with each incoming rule given the rules already stored.
+pub fn plan_rule_import(
+    existing: &[CategoryRule],
+    incoming: Vec<CategoryRule>,
+    policy: ConflictPolicy,
+) -> RuleImportPlan {
+    let mut plan = RuleImportPlan::default();
+    for rule in incoming {
+        if existing.iter().any(|e| identical(e, &rule)) {
+            plan.unchanged.push(rule);
+            continue;
+        }
+        match existing.iter().position(|e| same_matcher(e, &rule)) {
+            None => plan.to_insert.push(rule),
+            Some(idx) => match policy {
+                ConflictPolicy::Skip => plan.skipped.push(rule),
+                ConflictPolicy::Replace => plan.to_replace.push((idx, rule)),
+                ConflictPolicy::KeepBoth => plan.to_insert.push(rule),
+            },
+        }
+    }
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::{CategorizableTransaction, MatchType};
+
+    fn rule(name: &str, pattern: &str, match_type: MatchType, account: &str) -> CategoryRule {
+        CategoryRule {
+            name: name.to_string(),
+            priority: 10,
+            pattern: pattern.to_string(),
+            match_type,
+            account_code: account.to_string(),
+            amount_min_cents: None,
+            amount_max_cents: None,
+        }
+    }
+
+    fn sample_set() -> RuleSet {
+        RuleSet::new(vec![
+            rule("Coffee", "starbucks", MatchType::Contains, "5020"),
+            rule("SaaS", "(?i)github|gitlab", MatchType::Regex, "5100"),
+            rule(
+                "Fuel",
+                "shell oil",
+                MatchType::Fuzzy { threshold: 0.8 },
+                "5060",
+            ),
+        ])
+    }
+
+    // ── export / import ──────────────────────────────────────────────────────
+
+    #[test]
+    fn toml_roundtrip() {
+        let set = sample_set();
+        let text = export_rule_set(&set, RuleSetFormat::Toml).unwrap();
+        assert!(text.contains("format_version = 1"));
+        let restored = import_rule_set(&text, RuleSetFormat::Toml).unwrap();
+        assert_eq!(restored.rules.len(), 3);
+        assert_eq!(
+            restored.rules[2].match_type,
+            MatchType::Fuzzy { threshold: 0.8 }
+        );
+    }
+
+    #[test]
+    fn json_roundtrip() {
+        let set = sample_set();
+        let text = export_rule_set(&set, RuleSetFormat::Json).unwrap();
+        let restored = import_rule_set(&text, RuleSetFormat::Json).unwrap();
+        assert_eq!(restored.format_version, RULE_SET_FORMAT_VERSION);
+        assert_eq!(restored.rules[1].pattern, "(?i)github|gitlab");
+    }
+
+    #[test]
+    fn newer_version_rejected() {
+        let mut set = sample_set();
+        set.format_version = RULE_SET_FORMAT_VERSION + 1;
+        let text = export_rule_set(&set, RuleSetFormat::Toml).unwrap();
+        assert!(matches!(
+            import_rule_set(&text, RuleSetFormat::Toml),
+            Err(RuleSharingError::UnsupportedVersion { .. })
+        ));
+    }
+
+    #[test]
+    fn missing_version_rejected() {
+        assert!(matches!(
+            import_rule_set("[[rules]]\nname = \"x\"", RuleSetFormat::Toml),
+            Err(RuleSharingError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn invalid_regex_rejected() {
+        let set = RuleSet::new(vec![rule("Bad", "(unclosed", MatchType::Regex, "5000")]);
+        let text = export_rule_set(&set, RuleSetFormat::Json).unwrap();
+        assert!(matches!(
+            import_rule_set(&text, RuleSetFormat::Json),
+            Err(RuleSharingError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn inverted_amount_range_rejected() {
+        let mut r = rule("Range", "x", MatchType::Contains, "5000");
+        r.amount_min_cents = Some(500);
+        r.amount_max_cents = Some(100);
+        let text = export_rule_set(&RuleSet::new(vec![r]), RuleSetFormat::Json).unwrap();
+        assert!(import_rule_set(&text, RuleSetFormat::Json).is_err());
+    }
+
+    #[test]
+    fn format_parses_case_insensitively() {
+        assert_eq!(
+            "TOML".parse::<RuleSetFormat>().unwrap(),
+            RuleSetFormat::Toml
+        );
+        assert!("yaml".parse::<RuleSetFormat>().is_err());
+    }
+
+    // ── conflict handling ────────────────────────────────────────────────────
+
+    #[test]
+    fn identical_rules_are_unchanged() {
+        let existing = vec![rule("Coffee", "starbucks", MatchType::Contains, "5020")];
+        let incoming = vec![rule(
+            "Coffee copy",
+            "STARBUCKS",
+            MatchType::Contains,
+            "5020",
+        )];
+        let plan = plan_rule_import(&existing, incoming, ConflictPolicy::Replace);
+        assert_eq!(plan.unchanged.len(), 1);
+        assert!(plan.to_insert.is_empty() && plan.to_replace.is_empty());
+    }
+
+    #[test]
+    fn conflict_policies() {
+        let existing = vec![rule("Coffee", "starbucks", MatchType::Contains, "5020")];
+        let incoming = || {
+            vec![
+                rule("Coffee", "starbucks", MatchType::Contains, "5000"),
+                rule("Fuel", "shell", MatchType::Contains, "5060"),
+            ]
+        };
+
+        let skip = plan_rule_import(&existing, incoming(), ConflictPolicy::Skip);
+        assert_eq!(skip.to_insert.len(), 1);
+        assert_eq!(skip.skipped.len(), 1);
+
+        let replace = plan_rule_import(&existing, incoming(), ConflictPolicy::Replace);
+        assert_eq!(replace.to_replace.len(), 1);
+        assert_eq!(replace.to_replace[0].0, 0);
+        assert_eq!(replace.to_replace[0].1.account_code, "5000");
+
+        let both = plan_rule_import(&existing, incoming(), ConflictPolicy::KeepBoth);
+        assert_eq!(both.to_insert.len(), 2);
+    }
+
+    #[test]
+    fn different_match_type_is_not_a_conflict() {
+        let existing = vec![rule("Coffee", "starbucks", MatchType::Contains, "5020")];
+        let incoming = vec![rule("Coffee", "starbucks", MatchType::Exact, "5000")];
+        let plan = plan_rule_import(&existing, incoming, ConflictPolicy::Skip);
+        assert_eq!(plan.to_insert.len(), 1);
+    }
+
+    #[test]
+    fn rule_engine_from_toml_reads_versioned_rule_set() {
+        let toml = r#"
+format_version = 1
+
+[[rules]]
+name = "Coffee"
+priority = 5
+pattern = "starbucks"
+match_type = "Contains"
+account_code = "5020"
+"#;
+        let engine = rule_engine_from_toml(toml).unwrap();
+        let tx = CategorizableTransaction {
+            date: chrono::NaiveDate::from_ymd_opt(2024, 1, 15).unwrap(),
+            description: "STARBUCKS #12".to_string(),
+            amount_cents: 500,
+            memo: None,
+        };
+        assert_eq!(engine.find_matching_rule(&tx).unwrap().account_code, "5020");
+    }
+}
@@ -0,0 +1,314 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::rules::{CategorizableTransaction, CategoryRule, CategoryRuleEngine, MatchType};
+use crate::util::description_tokens;
+
+/// A transaction the user has already assigned to an account.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategorizedExample {
+    pub description: String,
+    pub amount_cents: i64,
+    pub account_code: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct SuggestOptions {
+    /// Minimum number of examples that must agree before a rule is proposed.
+    pub min_support: usize,
+    /// Share of a merchant's examples that must land in the same account.
+    pub min_consistency: f32,
+    /// Maximum number of merchant tokens kept in the generated pattern.
+    pub max_pattern_tokens: usize,
+    /// Priority assigned to suggested rules.
+    pub priority: i32,
+}
+
+impl Default for SuggestOptions {
+    fn default() -> Self {
+        Self {
+            min_support: 3,
+            min_consistency: 0.8,
+            max_pattern_tokens: 2,
+            priority: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleSuggestion {
+    pub rule: CategoryRule,
+    /// Number of examples that agree with the suggested account.
+    pub support: usize,
+    /// `support` divided by every example sharing the merchant pattern.
+    pub consistency: f32,
+    /// Name of an existing rule that currently sends these transactions elsewhere.
+    pub overrides_rule: Option<String>,
+    pub sample_descriptions: Vec<String>,
+}
+
+/// Reduce a raw bank description to a short lowercase merchant key, e.g.
+/// `"POS PURCHASE WHOLE FOODS MKT #10234"` becomes `"whole foods"`.
+pub fn merchant_key(description: &str, max_tokens: usize) -> Option<String> {
+    let mut tokens = description_tokens(description);
+    tokens.truncate(max_tokens.max(1));
+    let key = tokens.join(" ");
+    (key.len() >= 3).then_some(key)
+}
+
+/// Mine past categorizations for merchants that are consistently filed under
+/// the same account and propose a `Contains` rule for each.
+///
+/// Merchants already routed to the proposed account by `existing` are skipped.
+/// Suggestions are ordered by support, strongest first.
+pub fn suggest_rules(
+    history: &[CategorizedExample],
+    existing: &CategoryRuleEngine,
+    options: &SuggestOptions,
+) -> Vec<RuleSuggestion> {
+    let mut groups: BTreeMap<String, Vec<&CategorizedExample>> = BTreeMap::new();
+    for example in history {
+        if let Some(key) = merchant_key(&example.description, options.max_pattern_tokens) {
+            groups.entry(key).or_default().push(example);
+        }
+    }
+
+    let mut suggestions = Vec::new();
+    for (key, examples) in groups {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for ex in &examples {
+            *counts.entry(ex.account_code.as_str()).or_default() += 1;
+        }
+        let Some((account_code, support)) = counts
+            .into_iter()
+            .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+        else {
+            continue;
+        };
+
+        let consistency = support as f32 / examples.len() as f32;
+        if support < options.min_support || consistency < options.min_consistency {
+            continue;
+        }
+
+        let agreeing: Vec<&CategorizedExample> = examples
+            .iter()
+            .copied()
+            .filter(|ex| ex.account_code == account_code)
+            .collect();
+
+        let Some(pattern) = contained_pattern(&key, &agreeing) else {
+            continue;
+        };
+
+        // Skip merchants the current rule set already handles correctly, and
+        // remember which rule is sending them somewhere else otherwise.
+        let mut overrides_rule = None;
+        let mut already_covered = true;
+        for ex in &agreeing {
+            let tx = CategorizableTransaction {
+                date: chrono::NaiveDate::default(),
+                description: ex.description.clone(),
+                amount_cents: ex.amount_cents,
+                memo: None,
+            };
+            match existing.find_matching_rule(&tx) {
+                Some(rule) if rule.account_code == account_code => {}
+                Some(rule) => {
+                    already_covered = false;
+                    overrides_rule.get_or_insert_with(|| rule.name.clone());
+                }
+                None => already_covered = false,
+            }
+        }
+        if already_covered {
+            continue;
+        }
+
+        let mut sample_descriptions: Vec<String> =
+            agreeing.iter().map(|ex| ex.description.clone()).collect();
+        sample_descriptions.dedup();
+        sample_descriptions.truncate(3);
+
+        suggestions.push(RuleSuggestion {
+            rule: CategoryRule {
+                name: format!("Suggested: {pattern}"),
+                priority: options.priority,
+                pattern,
+                match_type: MatchType::Contains,
+                account_code: account_code.to_string(),
+                amount_min_cents: None,
+                amount_max_cents: None,
+            },
+            support,
+            consistency,
+            overrides_rule,
+            sample_descriptions,
+        });
+    }
+
+    suggestions.sort_by(|a, b| {
+        b.support
+            .cmp(&a.support)
+            .then_with(|| a.rule.pattern.cmp(&b.rule.pattern))
+    });
+    suggestions
+}
+
+/// `Contains` matches against the raw lowercased description, so the key is
+/// only usable if it appears verbatim in every example. Fall back to the
+/// leading token when punctuation splits the merchant name.
+fn contained_pattern(key: &str, examples: &[&CategorizedExample]) -> Option<String> {
+    let appears_in_all = |pattern: &str| {
+        examples
+            .iter()
+            .all(|ex| ex.description.to_lowercase().contains(pattern))
+    };
+    if appears_in_all(key) {
+        return Some(key.to_string());
+    }
+    let first = key.split(' ').next()?;
+    (first.len() >= 3 && appears_in_all(first)).then(|| first.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ex(desc: &str, account: &str) -> CategorizedExample {
+        CategorizedExample {
+            description: desc.to_string(),
+            amount_cents: 1500,
+            account_code: account.to_string(),
+        }
+    }
+
+    fn empty_engine() -> CategoryRuleEngine {
+        CategoryRuleEngine::new(vec![])
+    }
+
+    // ── merchant_key ─────────────────────────────────────────────────────────
+
+    #[test]
+    fn merchant_key_strips_noise_and_numbers() {
+        assert_eq!(
+            merchant_key("POS PURCHASE WHOLE FOODS MKT #10234", 2).as_deref(),
+            Some("whole foods")
+        );
+        assert_eq!(
+            merchant_key("AMAZON.COM*AB12CD", 2).as_deref(),
+            Some("amazon")
+        );
+    }
+
+    #[test]
+    fn merchant_key_rejects_pure_noise() {
+        assert_eq!(merchant_key("POS 12345 #99", 2), None);
+        assert_eq!(merchant_key("", 2), None);
+    }
+
+    // ── suggest_rules ────────────────────────────────────────────────────────
+
+    #[test]
+    fn suggests_contains_rule_for_repeated_merchant() {
+        let history = vec![
+            ex("STARBUCKS STORE 1001", "5020"),
+            ex("STARBUCKS STORE 2044", "5020"),
+            ex("STARBUCKS STORE 0007", "5020"),
+        ];
+        let out = suggest_rules(&history, &empty_engine(), &SuggestOptions::default());
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].rule.pattern, "starbucks store");
+        assert_eq!(out[0].rule.account_code, "5020");
+        assert_eq!(out[0].rule.match_type, MatchType::Contains);
+        assert_eq!(out[0].support, 3);
+        assert!(out[0].overrides_rule.is_none());
+    }
+
+    #[test]
+    fn below_min_support_is_ignored() {
+        let history = vec![ex("ADOBE CREATIVE", "5100"), ex("ADOBE CREATIVE", "5100")];
+        let out = suggest_rules(&history, &empty_engine(), &SuggestOptions::default());
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn inconsistent_categorization_is_ignored() {
+        let history = vec![
+            ex("COSTCO WHOLESALE", "5080"),
+            ex("COSTCO WHOLESALE", "5080"),
+            ex("COSTCO WHOLESALE", "5040"),
+            ex("COSTCO WHOLESALE", "3100"),
+        ];
+        let out = suggest_rules(&history, &empty_engine(), &SuggestOptions::default());
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn already_covered_merchant_is_skipped() {
+        let engine = CategoryRuleEngine::new(vec![CategoryRule {
+            name: "Coffee".to_string(),
+            priority: 1,
+            pattern: "starbucks".to_string(),
+            match_type: MatchType::Contains,
+            account_code: "5020".to_string(),
+            amount_min_cents: None,
+            amount_max_cents: None,
+        }]);
+        let history = vec![
+            ex("STARBUCKS 1", "5020"),
+            ex("STARBUCKS 2", "5020"),
+            ex("STARBUCKS 3", "5020"),
+        ];
+        let out = suggest_rules(&history, &engine, &SuggestOptions::default());
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn correction_against_existing_rule_reports_override() {
+        let engine = CategoryRuleEngine::new(vec![CategoryRule {
+            name: "Groceries".to_string(),
+            priority: 1,
+            pattern: "target".to_string(),
+            match_type: MatchType::Contains,
+            account_code: "3100".to_string(),
+            amount_min_cents: None,
+            amount_max_cents: None,
+        }]);
+        let history = vec![
+            ex("TARGET 0001", "5080"),
+            ex("TARGET 0002", "5080"),
+            ex("TARGET 0003", "5080"),
+        ];
+        let out = suggest_rules(&history, &engine, &SuggestOptions::default());
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].overrides_rule.as_deref(), Some("Groceries"));
+    }
+
+    #[test]
+    fn pattern_falls_back_to_first_token_when_split_by_punctuation() {
+        let history = vec![
+            ex("BLUE-BOTTLE COFFEE", "5020"),
+            ex("BLUE-BOTTLE OAKLAND", "5020"),
+            ex("BLUE-BOTTLE SF", "5020"),
+        ];
+        let opts = SuggestOptions {
+            max_pattern_tokens: 2,
+            ..SuggestOptions::default()
+        };
+        let out = suggest_rules(&history, &empty_engine(), &opts);
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].rule.pattern, "blue");
+    }
+
+    #[test]
+    fn suggestions_ordered_by_support() {
+        let mut history = vec![ex("GITHUB", "5100"); 3];
+        history.extend(vec![ex("SHELL OIL", "5060"); 5]);
+        let out = suggest_rules(&history, &empty_engine(), &SuggestOptions::default());
+        assert_eq!(out.len(), 2);
+        assert_eq!(out[0].rule.pattern, "shell oil");
+        assert_eq!(out[1].rule.pattern, "github");
+    }
+}
@@ -0,0 +1,332 @@
+//! Scheduled pulls from bank aggregation APIs (Plaid, SimpleFIN), normalised
+//! into one shape so callers can queue them as `imported_transactions` keyed
+//! by the provider's transaction id.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::str::FromStr;
+use thiserror::Error;
+
+use crate::plaid::{PlaidClient, PlaidError, TransactionsGetResponse};
+use crate::simplefin::{SimpleFinAccountSet, SimpleFinClient, SimpleFinError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncProvider {
+    Plaid,
+    SimpleFin,
+}
+
+impl SyncProvider {
+    /// Value stored in `imported_transactions.source_type`.
+    pub fn source_type(&self) -> &'static str {
+        match self {
+            SyncProvider::Plaid => "plaid",
+            SyncProvider::SimpleFin => "simplefin",
+        }
+    }
+}
+
+/// A posted transaction. `amount_cents` follows the statement convention
+/// used by the OFX/CSV importers: negative for money leaving the account.
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncedTransaction {
+    pub provider_id: String,
+    pub account_id: String,
+    pub date: NaiveDate,
+    pub description: String,
+    pub amount_cents: i64,
+    pub memo: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncedBalance {
+    pub account_id: String,
+    pub name: String,
+    pub currency: Option<String>,
+    pub current_cents: Option<i64>,
+    pub available_cents: Option<i64>,
+    pub as_of: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncBatch {
+    pub provider: SyncProvider,
+    pub transactions: Vec<SyncedTransaction>,
+    pub balances: Vec<SyncedBalance>,
+}
+
+#[derive(Debug, Error)]
+pub enum SyncError {
+    #[error(transparent)]
+    Plaid(#[from] PlaidError),
+    #[error(transparent)]
+    SimpleFin(#[from] SimpleFinError),
+    #[error("Invalid date from provider: {0}")]
+    InvalidDate(String),
+    #[error("Invalid amount from provider: {0}")]
+    InvalidAmount(String),
+}
+
+/// A configured aggregator connection.
+pub enum BankAggregator {
+    Plaid {
+        client: PlaidClient,
+        access_token: String,
+    },
+    SimpleFin(SimpleFinClient),
+}
+
+impl BankAggregator {
+    pub fn provider(&self) -> SyncProvider {
+        match self {
+            BankAggregator::Plaid { .. } => SyncProvider::Plaid,
+            BankAggregator::SimpleFin(_) => SyncProvider::SimpleFin,
+        }
+    }
+
+    /// Pull balances and posted transactions dated `start..=end`.
+    pub async fn fetch(&self, start: NaiveDate, end: NaiveDate) -> Result<SyncBatch, SyncError> {
+        match self {
+            BankAggregator::Plaid {
+                client,
+                access_token,
+            } => {
+                let resp = client
+                    .get_transactions(access_token, &start.to_string(), &end.to_string())
+                    .await?;
+                normalize_plaid(&resp, Utc::now())
+            }
+            BankAggregator::SimpleFin(client) => {
+                // SimpleFIN's end-date is exclusive.
+                let start_ts = start.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp();
+                let end_ts = end
+                    .succ_opt()
+                    .unwrap_or(end)
+                    .and_hms_opt(0, 0, 0)
+                    .unwrap()
+                    .and_utc()
+                    .timestamp();
+                let set = client.get_accounts(start_ts, end_ts).await?;
+                normalize_simplefin(&set)
+            }
+        }
+    }
+}
+
+/// Plaid reports outflows as positive amounts, so the sign is flipped.
+/// Pending transactions are dropped; Plaid issues a new id once they post.
+pub fn normalize_plaid(
+    resp: &TransactionsGetResponse,
+    as_of: DateTime<Utc>,
+) -> Result<SyncBatch, SyncError> {
+    let mut transactions = Vec::new();
+    for t in resp.transactions.iter().filter(|t| !t.pending) {
+        let date = NaiveDate::parse_from_str(&t.date, "%Y-%m-%d")
+            .map_err(|_| SyncError::InvalidDate(t.date.clone()))?;
+        transactions.push(SyncedTransaction {
+            provider_id: t.transaction_id.clone(),
+            account_id: t.account_id.clone(),
+            date,
+            description: t.name.clone(),
+            amount_cents: -t.amount_cents(),
+            memo: t.merchant_name.clone(),
+        });
+    }
+
+    let balances = resp
+        .accounts
+        .iter()
+        .map(|a| SyncedBalance {
+            account_id: a.account_id.clone(),
+            name: a.name.clone(),
+            currency: a.balances.iso_currency_code.clone(),
+            current_cents: a.balances.current.map(dollars_to_cents),
+            available_cents: a.balances.available.map(dollars_to_cents),
+            as_of,
+        })
+        .collect();
+
+    Ok(SyncBatch {
+        provider: SyncProvider::Plaid,
+        transactions,
+        balances,
+    })
+}
+
+/// SimpleFIN amounts already use the statement sign convention.
+pub fn normalize_simplefin(set: &SimpleFinAccountSet) -> Result<SyncBatch, SyncError> {
+    let mut transactions = Vec::new();
+    let mut balances = Vec::new();
+
+    for account in &set.accounts {
+        for t in &account.transactions {
+            if t.pending || t.posted == 0 {
+                continue;
+            }
+            let date = DateTime::from_timestamp(t.posted, 0)
+                .ok_or_else(|| SyncError::InvalidDate(t.posted.to_string()))?
+                .date_naive();
+            let description = if t.description.trim().is_empty() {
+                t.payee.clone().unwrap_or_default()
+            } else {
+                t.description.clone()
+            };
+            transactions.push(SyncedTransaction {
+                provider_id: t.id.clone(),
+                account_id: account.id.clone(),
+                date,
+                description,
+                amount_cents: decimal_to_cents(&t.amount)?,
+                memo: t.memo.clone().filter(|m| !m.trim().is_empty()),
+            });
+        }
+
+        balances.push(SyncedBalance {
+            account_id: account.id.clone(),
+            name: account.name.clone(),
+            currency: Some(account.currency.clone()),
+            current_cents: Some(decimal_to_cents(&account.balance)?),
+            available_cents: account
+                .available_balance
+                .as_deref()
+                .map(decimal_to_cents)
+                .transpose()?,
+            as_of: DateTime::from_timestamp(account.balance_date, 0)
+                .ok_or_else(|| SyncError::InvalidDate(account.balance_date.to_string()))?,
+        });
+    }
+
+    Ok(SyncBatch {
+        provider: SyncProvider::SimpleFin,
+        transactions,
+        balances,
+    })
+}
+
+fn dollars_to_cents(amount: f64) -> i64 {
+    (amount * 100.0).round() as i64
+}
+
+fn decimal_to_cents(s: &str) -> Result<i64, SyncError> {
+    Decimal::from_str(s.trim())
+        .ok()
+        .and_then(|d| (d * Decimal::from(100)).round().to_i64())
+        .ok_or_else(|| SyncError::InvalidAmount(s.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plaid_response() -> TransactionsGetResponse {
+        serde_json::from_str(
+            r#"{
+            "accounts": [{
+                "account_id": "acc_1",
+                "name": "Checking",
+                "type": "depository",
+                "subtype": "checking",
+                "balances": { "available": 950.5, "current": 1000.0, "limit": null, "iso_currency_code": "USD" }
+            }],
+            "transactions": [
+                {"transaction_id": "txn_1", "name": "COFFEE SHOP", "amount": 4.75, "date": "2026-03-10",
+                 "category": null, "account_id": "acc_1", "pending": false, "iso_currency_code": "USD",
+                 "merchant_name": "Coffee Shop"},
+                {"transaction_id": "txn_2", "name": "PAYROLL", "amount": -2500.00, "date": "2026-03-11",
+                 "category": null, "account_id": "acc_1", "pending": false, "iso_currency_code": "USD",
+                 "merchant_name": null},
+                {"transaction_id": "txn_3", "name": "GAS", "amount": 30.00, "date": "2026-03-12",
+                 "category": null, "account_id": "acc_1", "pending": true, "iso_currency_code": "USD",
+                 "merchant_name": null}
+            ],
+            "total_transactions": 3,
+            "request_id": "req"
+        }"#,
+        )
+        .unwrap()
+    }
+
+    fn simplefin_set() -> SimpleFinAccountSet {
+        serde_json::from_str(
+            r#"{"errors": [], "accounts": [{
+                "id": "ACT-1", "name": "Business Checking", "currency": "USD",
+                "balance": "2045.10", "available-balance": "2000.00", "balance-date": 1709251200,
+                "transactions": [
+                    {"id": "T1", "posted": 1709164800, "amount": "-12.34", "description": "OFFICE DEPOT", "memo": ""},
+                    {"id": "T2", "posted": 0, "amount": "-5.00", "description": "PENDING", "pending": true},
+                    {"id": "T3", "posted": 1709078400, "amount": "800.00", "description": " ", "payee": "Client Co"}
+                ]
+            }]}"#,
+        )
+        .unwrap()
+    }
+
+    // ── Plaid ────────────────────────────────────────────────────────────────
+
+    #[test]
+    fn plaid_signs_are_flipped_and_pending_dropped() {
+        let batch = normalize_plaid(&plaid_response(), Utc::now()).unwrap();
+        assert_eq!(batch.provider.source_type(), "plaid");
+        assert_eq!(batch.transactions.len(), 2);
+        assert_eq!(batch.transactions[0].provider_id, "txn_1");
+        assert_eq!(batch.transactions[0].amount_cents, -475);
+        assert_eq!(batch.transactions[0].memo.as_deref(), Some("Coffee Shop"));
+        assert_eq!(batch.transactions[1].amount_cents, 250_000);
+    }
+
+    #[test]
+    fn plaid_balances_in_cents() {
+        let batch = normalize_plaid(&plaid_response(), Utc::now()).unwrap();
+        assert_eq!(batch.balances.len(), 1);
+        assert_eq!(batch.balances[0].current_cents, Some(100_000));
+        assert_eq!(batch.balances[0].available_cents, Some(95_050));
+        assert_eq!(batch.balances[0].currency.as_deref(), Some("USD"));
+    }
+
+    #[test]
+    fn plaid_bad_date_is_an_error() {
+        let mut resp = plaid_response();
+        resp.transactions[0].date = "03/10/2026".to_string();
+        assert!(matches!(
+            normalize_plaid(&resp, Utc::now()),
+            Err(SyncError::InvalidDate(_))
+        ));
+    }
+
+    // ── SimpleFIN ────────────────────────────────────────────────────────────
+
+    #[test]
+    fn simplefin_posted_transactions_only() {
+        let batch = normalize_simplefin(&simplefin_set()).unwrap();
+        assert_eq!(batch.provider.source_type(), "simplefin");
+        assert_eq!(batch.transactions.len(), 2);
+        let t1 = &batch.transactions[0];
+        assert_eq!(t1.provider_id, "T1");
+        assert_eq!(t1.amount_cents, -1234);
+        assert_eq!(t1.date, NaiveDate::from_ymd_opt(2024, 2, 29).unwrap());
+        assert!(t1.memo.is_none());
+        assert_eq!(batch.transactions[1].description, "Client Co");
+    }
+
+    #[test]
+    fn simplefin_balances_in_cents() {
+        let batch = normalize_simplefin(&simplefin_set()).unwrap();
+        let b = &batch.balances[0];
+        assert_eq!(b.current_cents, Some(204_510));
+        assert_eq!(b.available_cents, Some(200_000));
+        assert_eq!(b.as_of.timestamp(), 1709251200);
+    }
+
+    #[test]
+    fn simplefin_bad_amount_is_an_error() {
+        let mut set = simplefin_set();
+        set.accounts[0].transactions[0].amount = "twelve".to_string();
+        assert!(matches!(
+            normalize_simplefin(&set),
+            Err(SyncError::InvalidAmount(_))
+        ));
+    }
+}
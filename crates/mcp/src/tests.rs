@@ -1001,7 +1001,7 @@ async fn ingest_receipt_supported_extensions_pass_validation() {
 
     // These should pass validation but fail on "Cannot read file" since files don't exist
     for ext in &[
-        "jpg", "jpeg", "png", "gif", "webp", "tiff", "tif", "bmp", "pdf",
+        "jpg", "jpeg", "png", "gif", "webp", "tiff", "tif", "bmp", "heic", "heif", "pdf",
     ] {
         let result = registry
             .call(
@@ -1054,10 +1054,12 @@ async fn approve_receipt_without_transaction() {
         None,
         None,
         None,
+        None,
         0.0,
     )
     .await
-    .unwrap();
+    .unwrap()
+    .id();
 
     let result = registry
         .call(
@@ -1089,10 +1091,12 @@ async fn reject_receipt() {
         None,
         None,
         None,
+        None,
         0.0,
     )
     .await
-    .unwrap();
+    .unwrap()
+    .id();
 
     let result = registry
         .call(
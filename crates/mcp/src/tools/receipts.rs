@@ -16,7 +16,8 @@ pub fn register(registry: &mut ToolRegistry) {
                     "file_path": { "type": "string", "description": "Absolute path to the receipt image file" },
                     "vendor": { "type": "string", "description": "Vendor name (if known)" },
                     "date": { "type": "string", "description": "Receipt date YYYY-MM-DD (if known)" },
-                    "total_cents": { "type": "integer", "description": "Total amount in cents (if known)" }
+                    "total_cents": { "type": "integer", "description": "Total amount in cents (if known)" },
+                    "reference": { "type": "string", "description": "Invoice or order number (if known)" }
                 },
                 "required": ["file_path"]
             }),
@@ -39,7 +40,7 @@ pub fn register(registry: &mut ToolRegistry) {
             }
             // Validate file extension is an image type
             let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
-            let allowed_exts = ["jpg", "jpeg", "png", "gif", "webp", "tiff", "tif", "bmp", "pdf"];
+            let allowed_exts = ["jpg", "jpeg", "png", "gif", "webp", "tiff", "tif", "bmp", "heic", "heif", "pdf"];
             if !allowed_exts.contains(&ext.to_lowercase().as_str()) {
                 return ToolResult::error(format!("Unsupported file type: .{ext}"));
             }
@@ -47,6 +48,7 @@ pub fn register(registry: &mut ToolRegistry) {
             let vendor = params.get("vendor").and_then(|v| v.as_str());
             let date = params.get("date").and_then(|v| v.as_str());
             let total_cents = params.get("total_cents").and_then(|v| v.as_i64());
+            let reference = params.get("reference").and_then(|v| v.as_str());
 
             let data = match tokio::fs::read(file_path).await {
                 Ok(d) => d,
@@ -68,13 +70,26 @@ pub fn register(registry: &mut ToolRegistry) {
 
             match aequi_storage::insert_receipt(
                 &db, &hash, ext, file_path, None, vendor, date,
-                total_cents, None, None, None, 0.0,
+                total_cents, None, None, None, reference, 0.0,
             ).await {
-                Ok(id) => ToolResult::text(json!({
+                Ok(aequi_storage::ReceiptInsert::Inserted { id }) => ToolResult::text(json!({
                     "receipt_id": id,
                     "file_hash": hash,
                     "status": "pending_review"
                 }).to_string()),
+                Ok(aequi_storage::ReceiptInsert::PossibleDuplicate { id, duplicate_of }) => {
+                    ToolResult::text(json!({
+                        "receipt_id": id,
+                        "file_hash": hash,
+                        "status": "duplicate",
+                        "duplicate_of": duplicate_of
+                    }).to_string())
+                }
+                Ok(aequi_storage::ReceiptInsert::AlreadyStored { id }) => ToolResult::text(json!({
+                    "receipt_id": id,
+                    "file_hash": hash,
+                    "already_stored": true
+                }).to_string()),
                 Err(e) => ToolResult::error(e.to_string()),
             }
         },
@@ -119,7 +134,12 @@ pub fn register(registry: &mut ToolRegistry) {
             let result = if let Some(tx_id) = transaction_id {
                 aequi_storage::link_receipt_to_transaction(&db, receipt_id, tx_id).await
             } else {
-                aequi_storage::update_receipt_status(&db, receipt_id, "approved").await
+                aequi_storage::update_receipt_status(
+                    &db,
+                    receipt_id,
+                    aequi_storage::receipt_status::ReceiptStatus::Approved,
+                )
+                .await
             };
 
             match result {
@@ -145,7 +165,13 @@ pub fn register(registry: &mut ToolRegistry) {
                 .get("receipt_id")
                 .and_then(|v| v.as_i64())
                 .unwrap_or(0);
-            match aequi_storage::update_receipt_status(&db, receipt_id, "rejected").await {
+            match aequi_storage::update_receipt_status(
+                &db,
+                receipt_id,
+                aequi_storage::receipt_status::ReceiptStatus::Rejected,
+            )
+            .await
+            {
                 Ok(()) => ToolResult::text("Receipt rejected".to_string()),
                 Err(e) => ToolResult::error(e.to_string()),
             }
@@ -44,6 +44,8 @@ pub fn register(registry: &mut ToolRegistry) {
                 match_pattern: params.get("match_pattern").and_then(|v| v.as_str()).unwrap_or("").to_string(),
                 match_type: params.get("match_type").and_then(|v| v.as_str()).unwrap_or("contains").to_string(),
                 account_id: params.get("account_id").and_then(|v| v.as_i64()).unwrap_or(0),
+                amount_min_cents: None,
+                amount_max_cents: None,
                 created_at: String::new(),
             };
 
@@ -1,6 +1,9 @@
 use serde_json::json;
 
-use aequi_core::{Money, TransactionLine, UnvalidatedTransaction, ValidatedTransaction};
+use aequi_core::{
+    DateRange, Deductibility, Money, TransactionLine, UnvalidatedTransaction, ValidatedTransaction,
+};
+use chrono::Datelike;
 
 use crate::protocol::{ToolDefinition, ToolResult};
 use crate::tools::ToolRegistry;
@@ -22,19 +25,28 @@ pub fn register(registry: &mut ToolRegistry) {
         false,
         |db, params| async move {
             let limit = params.get("limit").and_then(|v| v.as_i64()).unwrap_or(50);
-            let rows = sqlx::query_as::<_, (i64, String, String, Option<String>, i64)>(
-                "SELECT id, date, description, memo, balanced_total_cents FROM transactions ORDER BY date DESC LIMIT ?"
-            )
-            .bind(limit)
-            .fetch_all(&db)
-            .await;
+            let date = |name: &str| {
+                params
+                    .get(name)
+                    .and_then(|v| v.as_str())
+                    .and_then(|s| chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").ok())
+            };
+            let range = date("start_date")
+                .zip(date("end_date"))
+                .map(|(s, e)| DateRange::new(s, e));
+            let rows = aequi_storage::get_transactions(&db, range, Some(limit)).await;
 
             match rows {
                 Ok(rows) => {
-                    let txs: Vec<_> = rows.iter().map(|r| json!({
-                        "id": r.0, "date": r.1, "description": r.2,
-                        "memo": r.3, "balanced_total_cents": r.4
-                    })).collect();
+                    let txs: Vec<_> = rows
+                        .iter()
+                        .map(|r| {
+                            json!({
+                                "id": r.id, "date": r.date, "description": r.description,
+                                "memo": r.memo, "balanced_total_cents": r.balanced_total_cents
+                            })
+                        })
+                        .collect();
                     ToolResult::text(serde_json::to_string_pretty(&txs).unwrap())
                 }
                 Err(e) => ToolResult::error(e.to_string()),
@@ -75,7 +87,11 @@ pub fn register(registry: &mut ToolRegistry) {
                 Err(e) => return ToolResult::error(format!("Invalid date: {e}")),
             };
 
-            let description = params.get("description").and_then(|v| v.as_str()).unwrap_or("").to_string();
+            let description = params
+                .get("description")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string();
             let lines_val = match params.get("lines").and_then(|v| v.as_array()) {
                 Some(l) => l,
                 None => return ToolResult::error("lines is required".to_string()),
@@ -83,14 +99,23 @@ pub fn register(registry: &mut ToolRegistry) {
 
             let mut lines = Vec::new();
             for line in lines_val {
-                let code = line.get("account_code").and_then(|v| v.as_str()).unwrap_or("");
+                let code = line
+                    .get("account_code")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("");
                 let account = match aequi_storage::get_account_by_code(&db, code).await {
                     Ok(Some(a)) => a,
                     Ok(None) => return ToolResult::error(format!("Account {code} not found")),
                     Err(e) => return ToolResult::error(e.to_string()),
                 };
-                let debit = line.get("debit_cents").and_then(|v| v.as_i64()).unwrap_or(0);
-                let credit = line.get("credit_cents").and_then(|v| v.as_i64()).unwrap_or(0);
+                let debit = line
+                    .get("debit_cents")
+                    .and_then(|v| v.as_i64())
+                    .unwrap_or(0);
+                let credit = line
+                    .get("credit_cents")
+                    .and_then(|v| v.as_i64())
+                    .unwrap_or(0);
                 lines.push(TransactionLine {
                     account_id: account.id.unwrap(),
                     debit: Money::from_cents(debit),
@@ -99,40 +124,33 @@ pub fn register(registry: &mut ToolRegistry) {
                 });
             }
 
-            let tx = UnvalidatedTransaction { date, description, lines, memo: None };
+            let tx = UnvalidatedTransaction {
+                date,
+                description,
+                lines,
+                memo: None,
+            };
             let validated = match ValidatedTransaction::validate(tx) {
                 Ok(v) => v,
                 Err(e) => return ToolResult::error(e.to_string()),
             };
 
-            let row = sqlx::query_as::<_, (i64,)>(
-                "INSERT INTO transactions (date, description, memo, balanced_total_cents) VALUES (?, ?, ?, ?) RETURNING id"
-            )
-            .bind(validated.date.to_string())
-            .bind(&validated.description)
-            .bind(&validated.memo)
-            .bind(validated.balanced_total.to_cents())
-            .fetch_one(&db)
-            .await;
-
-            let id = match row {
-                Ok(r) => r.0,
+            let inserted = async {
+                let mut sql_tx = db.begin().await?;
+                let record = aequi_storage::insert_transaction(
+                    &mut sql_tx,
+                    &validated,
+                    Deductibility::default(),
+                )
+                .await?;
+                sql_tx.commit().await?;
+                Ok::<_, sqlx::Error>(record.id)
+            };
+            let id = match inserted.await {
+                Ok(id) => id,
                 Err(e) => return ToolResult::error(e.to_string()),
             };
 
-            for line in &validated.lines {
-                let _ = sqlx::query(
-                    "INSERT INTO transaction_lines (transaction_id, account_id, debit_cents, credit_cents, memo) VALUES (?, ?, ?, ?, ?)"
-                )
-                .bind(id)
-                .bind(line.account_id.0)
-                .bind(line.debit.to_cents())
-                .bind(line.credit.to_cents())
-                .bind(&line.memo)
-                .execute(&db)
-                .await;
-            }
-
             ToolResult::text(json!({ "id": id, "status": "created" }).to_string())
         },
     );
@@ -152,47 +170,33 @@ pub fn register(registry: &mut ToolRegistry) {
         false,
         |db, params| async move {
             let today = chrono::Utc::now().date_naive();
-            let default_start = format!("{}-01-01", today.format("%Y"));
-            let start = params.get("start_date").and_then(|v| v.as_str()).unwrap_or(&default_start);
-            let today_str = today.to_string();
-            let end = params.get("end_date").and_then(|v| v.as_str()).unwrap_or(&today_str);
-
-            let income_rows = sqlx::query_as::<_, (String, String, i64)>(
-                r#"SELECT a.code, a.name, COALESCE(SUM(tl.credit_cents - tl.debit_cents), 0) AS net
-                   FROM accounts a
-                   JOIN transaction_lines tl ON tl.account_id = a.id
-                   JOIN transactions t ON t.id = tl.transaction_id
-                   WHERE a.account_type = 'Income' AND t.date >= ? AND t.date <= ?
-                   GROUP BY a.id ORDER BY a.code"#
-            )
-            .bind(start).bind(end)
-            .fetch_all(&db).await;
-
-            let expense_rows = sqlx::query_as::<_, (String, String, i64)>(
-                r#"SELECT a.code, a.name, COALESCE(SUM(tl.debit_cents - tl.credit_cents), 0) AS net
-                   FROM accounts a
-                   JOIN transaction_lines tl ON tl.account_id = a.id
-                   JOIN transactions t ON t.id = tl.transaction_id
-                   WHERE a.account_type = 'Expense' AND t.date >= ? AND t.date <= ?
-                   GROUP BY a.id ORDER BY a.code"#
-            )
-            .bind(start).bind(end)
-            .fetch_all(&db).await;
+            let date_param = |name: &str, default: chrono::NaiveDate| {
+                match params.get(name).and_then(|v| v.as_str()) {
+                    Some(s) => chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                        .map_err(|e| format!("Invalid {name}: {e}")),
+                    None => Ok(default),
+                }
+            };
+            let year_start = chrono::NaiveDate::from_ymd_opt(today.year(), 1, 1).unwrap();
+            let (start, end) = match (date_param("start_date", year_start), date_param("end_date", today)) {
+                (Ok(s), Ok(e)) => (s, e),
+                (Err(e), _) | (_, Err(e)) => return ToolResult::error(e),
+            };
 
-            let (income, expenses) = match (income_rows, expense_rows) {
-                (Ok(i), Ok(e)) => (i, e),
-                (Err(e), _) | (_, Err(e)) => return ToolResult::error(e.to_string()),
+            let report = match aequi_storage::reports::profit_loss(&db, DateRange::new(start, end)).await {
+                Ok(r) => r,
+                Err(e) => return ToolResult::error(e.to_string()),
             };
 
-            let total_income: i64 = income.iter().map(|r| r.2).sum();
-            let total_expenses: i64 = expenses.iter().map(|r| r.2).sum();
-            let net_profit = total_income - total_expenses;
+            let total_income = report.total_income_cents;
+            let total_expenses = report.total_expenses_cents;
+            let net_profit = report.net_income_cents;
 
-            let income_lines: Vec<_> = income.iter().map(|r| json!({
-                "code": r.0, "name": r.1, "amount_cents": r.2
+            let income_lines: Vec<_> = report.income.iter().map(|r| json!({
+                "code": r.account_code, "name": r.account_name, "amount_cents": r.amount_cents
             })).collect();
-            let expense_lines: Vec<_> = expenses.iter().map(|r| json!({
-                "code": r.0, "name": r.1, "amount_cents": r.2
+            let expense_lines: Vec<_> = report.expenses.iter().map(|r| json!({
+                "code": r.account_code, "name": r.account_name, "amount_cents": r.amount_cents
             })).collect();
 
             ToolResult::text(serde_json::to_string_pretty(&json!({
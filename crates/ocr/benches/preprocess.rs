@@ -0,0 +1,60 @@
+//! Preparing 12 MP phone photos for Tesseract: grayscale, down-scale, and
+//! contrast stretch, with Sauvola binarization on top for the shadowed one.
+//!
+//! Run with `cargo bench -p aequi-ocr --bench preprocess`. Set
+//! `RAYON_NUM_THREADS=1` to compare against a single thread.
+
+use aequi_ocr::binarize::sauvola;
+use aequi_ocr::preprocess::normalize;
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use image::{DynamicImage, GrayImage, Luma, Rgb, RgbImage};
+
+const WIDTH: u32 = 4000;
+const HEIGHT: u32 = 3000;
+
+/// Dark strokes on paper, with the right half in shadow when `shadowed`.
+fn receipt_photo(shadowed: bool) -> RgbImage {
+    RgbImage::from_fn(WIDTH, HEIGHT, |x, y| {
+        let ink = x % 48 < 30 && y % 36 < 9;
+        let shade = shadowed && x >= WIDTH / 2;
+        let v = match (shade, ink) {
+            (false, false) => 230,
+            (false, true) => 40,
+            (true, false) => 110,
+            (true, true) => 20,
+        };
+        // A slight warm cast, as phone photos of paper have.
+        Rgb([v, v.saturating_sub(4), v.saturating_sub(12)])
+    })
+}
+
+fn bench_normalize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("normalize_12mp");
+    group.sample_size(10);
+    for (name, shadowed) in [("even", false), ("shadowed", true)] {
+        let photo = DynamicImage::ImageRgb8(receipt_photo(shadowed));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &photo, |b, photo| {
+            b.iter_batched(|| photo.clone(), normalize, BatchSize::LargeInput);
+        });
+    }
+    group.finish();
+}
+
+fn bench_sauvola(c: &mut Criterion) {
+    let gray = GrayImage::from_fn(WIDTH, HEIGHT, |x, y| {
+        let ink = x % 48 < 30 && y % 36 < 9;
+        Luma([match (x >= WIDTH / 2, ink) {
+            (false, false) => 230,
+            (false, true) => 40,
+            (true, false) => 110,
+            (true, true) => 20,
+        }])
+    });
+    let mut group = c.benchmark_group("sauvola_12mp");
+    group.sample_size(10);
+    group.bench_function("shadowed", |b| b.iter(|| sauvola(&gray)));
+    group.finish();
+}
+
+criterion_group!(benches, bench_normalize, bench_sauvola);
+criterion_main!(benches);
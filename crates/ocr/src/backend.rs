@@ -0,0 +1,231 @@
+//! Runtime selection of the OCR engine from user settings.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::recognizer::{MockRecognizer, OcrBackend};
+use crate::tessdata;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OcrBackendKind {
+    Mock,
+    Tesseract,
+}
+
+impl OcrBackendKind {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "mock" | "none" => Some(Self::Mock),
+            "tesseract" => Some(Self::Tesseract),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Mock => "mock",
+            Self::Tesseract => "tesseract",
+        }
+    }
+}
+
+impl Default for OcrBackendKind {
+    /// Tesseract when it was compiled in, otherwise the mock.
+    fn default() -> Self {
+        if cfg!(feature = "tesseract") {
+            Self::Tesseract
+        } else {
+            Self::Mock
+        }
+    }
+}
+
+/// Tesseract's "single column of text of variable sizes": a receipt's
+/// layout, without the column detection of the fully automatic mode.
+pub const RECEIPT_PAGE_SEGMENTATION: u8 = 4;
+
+/// Highest valid Tesseract page segmentation mode.
+pub const MAX_PAGE_SEGMENTATION: u8 = 13;
+
+/// Characters found on English-language receipts. Restricting Tesseract to
+/// them stops smudges being read as stray symbols.
+pub const RECEIPT_CHARACTERS: &str =
+    "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789 $€£¢%.,:;/-#&@*()'\"+=!?";
+
+#[derive(Debug, Clone)]
+pub struct OcrConfig {
+    pub backend: OcrBackendKind,
+    /// Directory holding `<lang>.traineddata`. `None` lets Tesseract use
+    /// `TESSDATA_PREFIX` or its compiled-in default.
+    pub tessdata_dir: Option<PathBuf>,
+    pub language: String,
+    /// Tesseract `--psm` value, 0 to [`MAX_PAGE_SEGMENTATION`].
+    pub page_segmentation: u8,
+    /// Characters Tesseract may recognise; `None` allows all of the
+    /// language's.
+    pub char_whitelist: Option<String>,
+}
+
+impl OcrConfig {
+    /// The whitelist to use when none is configured: [`RECEIPT_CHARACTERS`]
+    /// for English alone, nothing otherwise, since it would drop accented
+    /// letters.
+    pub fn default_whitelist(language: &str) -> Option<String> {
+        (language == "eng").then(|| RECEIPT_CHARACTERS.to_string())
+    }
+}
+
+impl Default for OcrConfig {
+    fn default() -> Self {
+        Self {
+            backend: OcrBackendKind::default(),
+            tessdata_dir: None,
+            language: "eng".to_string(),
+            page_segmentation: RECEIPT_PAGE_SEGMENTATION,
+            char_whitelist: Self::default_whitelist("eng"),
+        }
+    }
+}
+
+/// What [`build_recognizer`] ended up with, for display in settings.
+#[derive(Debug, Clone, Serialize)]
+pub struct OcrHealth {
+    pub requested: OcrBackendKind,
+    pub active: OcrBackendKind,
+    /// `false` while the mock is in use: receipts get no OCR text.
+    pub real_ocr: bool,
+    pub tesseract_compiled: bool,
+    pub tessdata_dir: Option<String>,
+    pub language: String,
+    /// Only known when `tessdata_dir` is set.
+    pub language_installed: Option<bool>,
+    pub page_segmentation: u8,
+    pub char_whitelist: Option<String>,
+    /// Why the requested backend is not active, if it is not.
+    pub detail: Option<String>,
+}
+
+/// Build the configured recognizer, falling back to the mock (and saying
+/// why in the returned health) when Tesseract cannot start.
+pub fn build_recognizer(config: &OcrConfig) -> (Box<dyn OcrBackend>, OcrHealth) {
+    let mut health = OcrHealth {
+        requested: config.backend,
+        active: OcrBackendKind::Mock,
+        real_ocr: false,
+        tesseract_compiled: cfg!(feature = "tesseract"),
+        tessdata_dir: config
+            .tessdata_dir
+            .as_ref()
+            .map(|d| d.display().to_string()),
+        language: config.language.clone(),
+        language_installed: config
+            .tessdata_dir
+            .as_ref()
+            .map(|d| tessdata::is_language_installed(d, &config.language)),
+        page_segmentation: config.page_segmentation,
+        char_whitelist: config.char_whitelist.clone(),
+        detail: None,
+    };
+
+    match config.backend {
+        OcrBackendKind::Mock => {}
+        OcrBackendKind::Tesseract => match tesseract(config) {
+            Ok(recognizer) => {
+                health.active = OcrBackendKind::Tesseract;
+                health.real_ocr = true;
+                return (recognizer, health);
+            }
+            Err(detail) => health.detail = Some(detail),
+        },
+    }
+
+    (Box::new(MockRecognizer::new("")), health)
+}
+
+#[cfg(feature = "tesseract")]
+fn tesseract(config: &OcrConfig) -> Result<Box<dyn OcrBackend>, String> {
+    use crate::recognizer::tesseract_backend::TesseractRecognizer;
+
+    let data_path = config
+        .tessdata_dir
+        .as_ref()
+        .map(|d| d.to_string_lossy().into_owned());
+    let recognizer = TesseractRecognizer::new(
+        data_path,
+        &config.language,
+        config.page_segmentation,
+        config.char_whitelist.clone(),
+    );
+    recognizer
+        .check()
+        .map_err(|e| format!("Tesseract failed to start for '{}': {e}", config.language))?;
+    Ok(Box::new(recognizer))
+}
+
+#[cfg(not(feature = "tesseract"))]
+fn tesseract(_config: &OcrConfig) -> Result<Box<dyn OcrBackend>, String> {
+    Err(crate::recognizer::OcrError::NotAvailable.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backend_kind_parses_setting_values() {
+        assert_eq!(
+            OcrBackendKind::parse("Tesseract"),
+            Some(OcrBackendKind::Tesseract)
+        );
+        assert_eq!(OcrBackendKind::parse("mock"), Some(OcrBackendKind::Mock));
+        assert_eq!(OcrBackendKind::parse("cloud"), None);
+    }
+
+    #[test]
+    fn mock_backend_reports_no_real_ocr() {
+        let config = OcrConfig {
+            backend: OcrBackendKind::Mock,
+            ..OcrConfig::default()
+        };
+        let (recognizer, health) = build_recognizer(&config);
+        assert_eq!(recognizer.recognize(b"img").unwrap(), "");
+        assert_eq!(health.active, OcrBackendKind::Mock);
+        assert!(!health.real_ocr);
+        assert!(health.detail.is_none());
+        assert!(health.language_installed.is_none());
+    }
+
+    #[test]
+    fn language_installed_checked_in_tessdata_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("eng.traineddata"), b"x").unwrap();
+        let config = OcrConfig {
+            backend: OcrBackendKind::Mock,
+            tessdata_dir: Some(dir.path().to_path_buf()),
+            ..OcrConfig::default()
+        };
+        let (_, health) = build_recognizer(&config);
+        assert_eq!(health.language_installed, Some(true));
+    }
+
+    #[test]
+    fn receipt_whitelist_only_for_english() {
+        assert!(OcrConfig::default_whitelist("eng").is_some_and(|w| w.contains('$')));
+        assert_eq!(OcrConfig::default_whitelist("deu+eng"), None);
+    }
+
+    #[cfg(not(feature = "tesseract"))]
+    #[test]
+    fn tesseract_request_falls_back_without_feature() {
+        let config = OcrConfig {
+            backend: OcrBackendKind::Tesseract,
+            ..OcrConfig::default()
+        };
+        let (_, health) = build_recognizer(&config);
+        assert_eq!(health.requested, OcrBackendKind::Tesseract);
+        assert_eq!(health.active, OcrBackendKind::Mock);
+        assert!(!health.tesseract_compiled);
+        assert!(health.detail.unwrap().contains("tesseract"));
+    }
+}
@@ -0,0 +1,193 @@
+//! Adaptive binarization for unevenly lit receipts.
+//!
+//! A contrast stretch uses one global range, so a shadow across half of a
+//! photographed receipt leaves that half too dark to read once Tesseract
+//! applies its own global threshold. Sauvola thresholding picks a threshold
+//! per pixel from the mean and spread of its neighbourhood, which keeps the
+//! paper white on both sides of the shadow edge.
+//!
+//! Both passes split the image into rows across rayon's thread pool; a
+//! 12 MP phone photo otherwise spends most of its preprocessing time here.
+
+use image::GrayImage;
+use rayon::prelude::*;
+
+/// Images smaller than this on either side are left alone; there is not
+/// enough paper to judge the lighting.
+const MIN_DIMENSION: u32 = 64;
+
+/// Grid used by [`illumination_spread`] to sample paper brightness.
+const GRID: u32 = 8;
+
+/// Paper brightness differing by more than this between the brightest and
+/// darkest grid cells is treated as a shadow or crease.
+const UNEVEN_LIGHTING_SPREAD: u8 = 60;
+
+/// Sauvola sensitivity; higher values push more pixels to black.
+const SAUVOLA_K: f64 = 0.2;
+
+/// Dynamic range of the standard deviation for 8-bit images.
+const SAUVOLA_R: f64 = 128.0;
+
+/// Whether `gray` is lit unevenly enough to need adaptive thresholding.
+pub fn needs_adaptive(gray: &GrayImage) -> bool {
+    illumination_spread(gray).is_some_and(|spread| spread > UNEVEN_LIGHTING_SPREAD)
+}
+
+/// Difference between the brightest and darkest paper estimate across an
+/// 8×8 grid. Each cell's paper level is its 90th-percentile brightness, so
+/// printed text inside the cell doesn't drag the estimate down.
+pub fn illumination_spread(gray: &GrayImage) -> Option<u8> {
+    let (width, height) = gray.dimensions();
+    if width < MIN_DIMENSION || height < MIN_DIMENSION {
+        return None;
+    }
+
+    let cell_w = width / GRID;
+    let cell_h = height / GRID;
+    let levels: Vec<u8> = (0..GRID * GRID)
+        .into_par_iter()
+        .map(|cell| {
+            let (gx, gy) = (cell % GRID, cell / GRID);
+            let mut histogram = [0u32; 256];
+            for y in gy * cell_h..(gy + 1) * cell_h {
+                for x in gx * cell_w..(gx + 1) * cell_w {
+                    histogram[gray.get_pixel(x, y)[0] as usize] += 1;
+                }
+            }
+            percentile(&histogram, cell_w * cell_h, 0.9)
+        })
+        .collect();
+
+    let min = levels.iter().copied().min()?;
+    let max = levels.iter().copied().max()?;
+    Some(max - min)
+}
+
+fn percentile(histogram: &[u32; 256], total: u32, fraction: f64) -> u8 {
+    let target = (total as f64 * fraction).ceil() as u32;
+    let mut seen = 0;
+    for (value, count) in histogram.iter().enumerate() {
+        seen += count;
+        if seen >= target {
+            return value as u8;
+        }
+    }
+    255
+}
+
+/// Sauvola thresholding with a window scaled to the image size.
+pub fn sauvola(gray: &GrayImage) -> GrayImage {
+    let window = (gray.width().min(gray.height()) / 20).max(15) | 1;
+    sauvola_with(gray, window, SAUVOLA_K)
+}
+
+/// Sauvola thresholding: each pixel becomes white when brighter than
+/// `mean * (1 + k * (stddev / R - 1))` over the `window`×`window`
+/// neighbourhood, black otherwise. Integral images keep it O(pixels).
+pub fn sauvola_with(gray: &GrayImage, window: u32, k: f64) -> GrayImage {
+    let (width, height) = gray.dimensions();
+    let (w, h) = (width as usize, height as usize);
+
+    // Summed-area tables with a zero row and column in front.
+    let stride = w + 1;
+    let mut sum = vec![0u64; stride * (h + 1)];
+    let mut sum_sq = vec![0u64; stride * (h + 1)];
+    for (y, pixels) in gray.chunks_exact(w.max(1)).enumerate() {
+        let mut row = 0u64;
+        let mut row_sq = 0u64;
+        for (x, &v) in pixels.iter().enumerate() {
+            let v = v as u64;
+            row += v;
+            row_sq += v * v;
+            sum[(y + 1) * stride + x + 1] = sum[y * stride + x + 1] + row;
+            sum_sq[(y + 1) * stride + x + 1] = sum_sq[y * stride + x + 1] + row_sq;
+        }
+    }
+
+    let half = (window / 2) as usize;
+    let mut out = GrayImage::new(width, height);
+    out.par_chunks_mut(w.max(1))
+        .zip(gray.par_chunks(w.max(1)))
+        .enumerate()
+        .for_each(|(y, (out_row, row))| {
+            let y0 = y.saturating_sub(half);
+            let y1 = (y + half + 1).min(h);
+            for (x, (out_px, &px)) in out_row.iter_mut().zip(row).enumerate() {
+                let x0 = x.saturating_sub(half);
+                let x1 = (x + half + 1).min(w);
+                let area = ((x1 - x0) * (y1 - y0)) as f64;
+                let region = |table: &[u64]| {
+                    (table[y1 * stride + x1] + table[y0 * stride + x0]
+                        - table[y0 * stride + x1]
+                        - table[y1 * stride + x0]) as f64
+                };
+
+                let mean = region(&sum) / area;
+                let variance = (region(&sum_sq) / area - mean * mean).max(0.0);
+                let threshold = mean * (1.0 + k * (variance.sqrt() / SAUVOLA_R - 1.0));
+                *out_px = if px as f64 > threshold { 255 } else { 0 };
+            }
+        });
+    out
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use image::Luma;
+
+    /// A page of short dark strokes ("text") with the right half in shadow:
+    /// paper 230 / ink 120 on the left, paper 110 / ink 20 on the right.
+    pub(crate) fn shadowed_receipt(width: u32, height: u32) -> GrayImage {
+        GrayImage::from_fn(width, height, |x, y| {
+            let ink = x % 16 < 10 && y % 12 < 3;
+            let shadow = x >= width / 2;
+            Luma([match (shadow, ink) {
+                (false, false) => 230,
+                (false, true) => 120,
+                (true, false) => 110,
+                (true, true) => 20,
+            }])
+        })
+    }
+
+    fn evenly_lit_receipt(width: u32, height: u32) -> GrayImage {
+        GrayImage::from_fn(width, height, |x, y| {
+            Luma([if x % 16 < 10 && y % 12 < 3 { 30 } else { 225 }])
+        })
+    }
+
+    #[test]
+    fn shadow_is_detected() {
+        assert!(needs_adaptive(&shadowed_receipt(320, 240)));
+    }
+
+    #[test]
+    fn even_lighting_is_not_flagged() {
+        assert!(!needs_adaptive(&evenly_lit_receipt(320, 240)));
+    }
+
+    #[test]
+    fn tiny_images_are_not_judged() {
+        assert_eq!(illumination_spread(&shadowed_receipt(40, 40)), None);
+    }
+
+    #[test]
+    fn sauvola_whitens_paper_on_both_sides_of_shadow() {
+        let img = shadowed_receipt(320, 240);
+        let out = sauvola(&img);
+        // Paper pixels well inside each half, away from strokes.
+        assert_eq!(out.get_pixel(60 + 12, 120 + 6)[0], 255);
+        assert_eq!(out.get_pixel(220 + 12, 120 + 6)[0], 255);
+        // Ink inside each half stays black.
+        assert_eq!(out.get_pixel(64, 120)[0], 0);
+        assert_eq!(out.get_pixel(224, 120)[0], 0);
+    }
+
+    #[test]
+    fn sauvola_output_is_binary() {
+        let out = sauvola(&shadowed_receipt(128, 96));
+        assert!(out.pixels().all(|p| p[0] == 0 || p[0] == 255));
+    }
+}
@@ -0,0 +1,120 @@
+//! Score the extractor against receipts the user has corrected.
+//!
+//! Each reviewed receipt is a labelled example: its OCR text and the values
+//! the user confirmed. Re-running extraction over them shows whether a
+//! change to the extractor helps or hurts on real receipts.
+
+use chrono::NaiveDate;
+use serde::Serialize;
+
+use crate::extract::Extractor;
+use crate::locale::ExtractOptions;
+use crate::vendors::VendorDictionary;
+
+/// A receipt's OCR text and the values the user settled on. Fields the user
+/// didn't review are `None` and aren't scored.
+#[derive(Debug, Clone, Default)]
+pub struct CorrectionCase {
+    pub ocr_text: String,
+    pub vendor: Option<String>,
+    pub date: Option<NaiveDate>,
+    pub total_cents: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldScore {
+    pub field: &'static str,
+    pub reviewed: usize,
+    pub correct: usize,
+}
+
+impl FieldScore {
+    pub fn accuracy(&self) -> Option<f32> {
+        (self.reviewed > 0).then(|| self.correct as f32 / self.reviewed as f32)
+    }
+}
+
+/// Run the regex extractor and vendor dictionary over every case, as the
+/// pipeline would without an LLM, and count per field how often they produce
+/// the user's value. Fields come back as `vendor`, `date`, `total`, matching
+/// the stored correction records.
+pub fn evaluate(
+    cases: &[CorrectionCase],
+    options: &ExtractOptions,
+    vendors: &VendorDictionary,
+) -> Vec<FieldScore> {
+    let mut scores = ["vendor", "date", "total"].map(|field| FieldScore {
+        field,
+        reviewed: 0,
+        correct: 0,
+    });
+    let mut score = |idx: usize, hit: Option<bool>| {
+        if let Some(hit) = hit {
+            scores[idx].reviewed += 1;
+            scores[idx].correct += usize::from(hit);
+        }
+    };
+
+    for case in cases {
+        let mut extracted = Extractor::extract_with(&case.ocr_text, options);
+        vendors.apply(&mut extracted, &case.ocr_text);
+        score(
+            0,
+            case.vendor
+                .as_ref()
+                .map(|want| extracted.vendor.as_ref().map(|f| &f.value) == Some(want)),
+        );
+        score(
+            1,
+            case.date
+                .map(|want| extracted.date.as_ref().map(|f| f.value) == Some(want)),
+        );
+        score(
+            2,
+            case.total_cents
+                .map(|want| extracted.total_cents.as_ref().map(|f| f.value) == Some(want)),
+        );
+    }
+    scores.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_only_reviewed_fields() {
+        let cases = [
+            CorrectionCase {
+                ocr_text: "STARBUCKS COFFEE #1123\n2024-01-15\nTotal $5.50".to_string(),
+                vendor: Some("Starbucks".to_string()),
+                total_cents: Some(550),
+                ..Default::default()
+            },
+            CorrectionCase {
+                ocr_text: "SHELL\n01/15/2024\nTotal $40.00".to_string(),
+                // The user fixed a misread total.
+                total_cents: Some(4500),
+                date: NaiveDate::from_ymd_opt(2024, 1, 15),
+                ..Default::default()
+            },
+        ];
+        let scores = evaluate(
+            &cases,
+            &ExtractOptions::default(),
+            &VendorDictionary::seeded(),
+        );
+        let summary: Vec<_> = scores
+            .iter()
+            .map(|s| (s.field, s.reviewed, s.correct))
+            .collect();
+        assert_eq!(summary, [("vendor", 1, 1), ("date", 1, 1), ("total", 2, 1)]);
+        assert_eq!(scores[2].accuracy(), Some(0.5));
+    }
+
+    #[test]
+    fn no_cases_no_accuracy() {
+        let scores = evaluate(&[], &ExtractOptions::default(), &VendorDictionary::seeded());
+        assert!(scores.iter().all(|s| s.accuracy().is_none()));
+    }
+}
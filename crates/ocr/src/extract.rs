@@ -6,7 +6,10 @@ use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
 use std::str::FromStr;
 
-use crate::types::{ExtractedField, ExtractedReceipt, PaymentMethod};
+use crate::layout::{Layout, Row};
+use crate::locale::{DateOrder, ExtractOptions};
+use crate::recognizer::{OcrPage, OcrWord};
+use crate::types::{ExtractedField, ExtractedReceipt, LineItem, PaymentMethod};
 
 // ── Compiled regex cache ─────────────────────────────────────────────────────
 
@@ -19,37 +22,121 @@ macro_rules! re {
     };
 }
 
+/// The digits of a printed amount, with a decimal point or (European) a
+/// decimal comma: `1,234.56`, `23.45`, `1.234,56`, `23,45`.
+macro_rules! number {
+    () => {
+        r"(?:\d{1,3}(?:\.\d{3})+,\d{2}|[\d,]+\.\d{2}|\d+,\d{2})\b"
+    };
+}
+
+/// A printed amount, optionally signed: `23.45`, `$23.45`, `-$5.00`,
+/// `$-5.00`, `5.00-`, `($5.00)` or `€23,45`. The capture is parsed by
+/// [`parse_amount_str`].
+macro_rules! amount {
+    () => {
+        concat!(
+            r"(\(\s*[\$€£]?\s*",
+            number!(),
+            r"\s*\)|-?[\$€£]?\s*-?",
+            number!(),
+            r"-?)"
+        )
+    };
+}
+
+// Labels in English, French, Spanish and German.
 re!(
     re_amount_label,
-    r"(?i)\b(?:total|grand\s+total|amount\s+due|balance\s+due|total\s+due)\s*[:\$]?\s*\$?\s*([\d,]+\.\d{2})\b"
+    concat!(
+        r"(?i)\b(?:total|grand\s+total|amount\s+due|balance\s+due|total\s+due|",
+        r"refund\s+total|total\s+refund|refund\s+due|amount\s+refunded|",
+        r"total\s+ttc|montant\s+(?:total|ttc|d[ûu])|net\s+[àa]\s+payer|",
+        r"importe(?:\s+total)?|total\s+a\s+pagar|",
+        r"gesamt(?:betrag|summe)?|summe|zu\s+zahlen|betrag)\s*[:\$€£]?\s*",
+        amount!()
+    )
 );
 re!(
     re_subtotal,
-    r"(?i)\bsubtotal\b\s*[:\$]?\s*\$?\s*([\d,]+\.\d{2})\b"
+    concat!(
+        r"(?i)\b(?:subtotal|sub-total|sous-total|total\s+ht|zwischensumme|netto)\b\s*[:\$€£]?\s*",
+        amount!()
+    )
 );
+// Rates often sit between label and amount: "TVA 20% 4,00", "MwSt. 19%".
 re!(
     re_tax,
-    r"(?i)\b(?:tax|hst|gst|pst|vat|sales\s*tax)\b\s*[:\$]?\s*\$?\s*([\d,]+\.\d{2})\b"
+    concat!(
+        r"(?i)\b(?:tax|hst|gst|pst|vat|sales\s*tax|tva|taxe|iva|impuesto|mwst|ust|mehrwertsteuer)\b",
+        r"\.?\s*(?:\d{1,2}(?:[.,]\d+)?\s*%\s*)?[:\$€£]?\s*",
+        amount!()
+    )
+);
+re!(
+    re_tip,
+    concat!(
+        r"(?i)\b(?:tip|gratuity|pourboire|propina|trinkgeld)\b\s*[:\$€£]?\s*",
+        amount!()
+    )
+);
+re!(
+    re_currency,
+    concat!(
+        r"(\(\s*[\$€£]\s*",
+        number!(),
+        r"\s*\)|-?[\$€£]\s*-?",
+        number!(),
+        r"-?|-?",
+        number!(),
+        r"\s*(?:€|EUR\b|£))"
+    )
+);
+re!(re_any_amount, amount!());
+// A word that is nothing but an amount, as in an amounts column.
+re!(re_amount_word, concat!(r"^", amount!(), r"$"));
+// A leading item count: "2", "2x", "3@".
+re!(re_quantity, r"^(\d{1,3})[xX@]?$");
+re!(
+    re_refund,
+    r"(?i)\b(?:refund(?:ed)?|return(?:ed)?|credit\s+memo)\b"
+);
+re!(
+    re_policy,
+    r"(?i)\b(?:policy|within|days|receipt\s+required)\b"
 );
-re!(re_currency, r"\$\s*([\d,]+\.\d{2})");
 
 re!(
     re_date_month_name,
     r"(?i)\b(january|february|march|april|may|june|july|august|september|october|november|december)\s+(\d{1,2}),?\s+(\d{4})\b"
 );
+// "15 Jan 2024", "15 mars 2024", "15. März 2024", "15 de marzo de 2024".
 re!(
-    re_date_abbr_month,
-    r"(?i)\b(\d{1,2})\s+(jan|feb|mar|apr|may|jun|jul|aug|sep|oct|nov|dec)\.?\s+(\d{4})\b"
+    re_date_day_month,
+    r"(?i)\b(\d{1,2})\.?\s+(?:de\s+)?(\p{L}{3,10})\.?\s+(?:de\s+)?(\d{4})\b"
 );
 re!(re_date_iso, r"\b(\d{4})-(\d{2})-(\d{2})\b");
 re!(re_date_slash, r"\b(\d{1,2})/(\d{1,2})/(\d{2,4})\b");
 re!(re_date_dash, r"\b(\d{1,2})-(\d{1,2})-(\d{2,4})\b");
+re!(re_date_dot, r"\b(\d{1,2})\.(\d{1,2})\.(\d{2,4})\b");
 
 re!(
     re_payment,
     r"(?i)\b(visa|mastercard|master\s*card|amex|american\s+express|discover|cash|debit|check|cheque)\b"
 );
 
+// "Invoice No. INV-2024-0042", "Order #112-1234567-1234567", "Facture n° 881".
+// The number must contain a digit, so "Order Total" and "Invoice Date" don't
+// match.
+re!(
+    re_reference,
+    concat!(
+        r"(?i)\b(invoice|inv|facture|factura|rechnung|order|receipt|rcpt|ticket|beleg|folio|",
+        r"reference|ref|transaction|trans|txn)\b\.?\s*(?:number|num|no|nr|n°|#|id)?\.?\s*[:#]?\s*",
+        r"([A-Z0-9][A-Z0-9\-/]*\d[A-Z0-9\-/]*)"
+    )
+);
+
 re!(re_phone, r"\(?\d{3}\)?[\s\-]\d{3}[\s\-]\d{4}");
 re!(re_url, r"(?i)(https?://|www\.)\S+");
 
@@ -58,34 +145,76 @@ re!(re_url, r"(?i)(https?://|www\.)\S+");
 pub struct Extractor;
 
 impl Extractor {
-    /// Extract structured fields from raw OCR text.
+    /// Extract structured fields from raw OCR text, reading numeric dates
+    /// month-first.
     pub fn extract(ocr_text: &str) -> ExtractedReceipt {
+        Self::extract_with(ocr_text, &ExtractOptions::default())
+    }
+
+    /// [`extract`](Self::extract) with locale-dependent options.
+    pub fn extract_with(ocr_text: &str, options: &ExtractOptions) -> ExtractedReceipt {
+        let mut receipt = Self::read_fields(ocr_text, options);
+        check_arithmetic(&mut receipt, ocr_text);
+        receipt.confidence = aggregate_confidence(&receipt);
+        receipt
+    }
+
+    /// Extract using where the words sit on the page as well as the text:
+    /// the vendor is the largest print near the top, an unlabeled total
+    /// comes from the amounts column in the lower half, and line items are
+    /// the rows above the totals with an amount in that column. Fields are
+    /// then weighted by how clearly their words were read. Without word
+    /// positions this is [`extract_with`](Self::extract_with).
+    pub fn extract_page(page: &OcrPage, options: &ExtractOptions) -> ExtractedReceipt {
+        if page.words.is_empty() {
+            return Self::extract_with(&page.text, options);
+        }
+        let layout = Layout::new(&page.words);
+        let text = layout.text();
+        let mut receipt = Self::read_fields(&text, options);
+
+        let title = Self::layout_vendor(&layout);
+        if let Some((_, vendor)) = &title {
+            receipt.vendor = Some(vendor.clone());
+        }
+        let amounts = layout.right_column(|w| re_amount_word().is_match(&w.text));
+        if let Some(in_column) = &amounts {
+            if !re_amount_label().is_match(&text) {
+                if let Some(total) = Self::column_total(&layout, in_column) {
+                    let cents = if is_refund(&text) { -total } else { total };
+                    receipt.total_cents = Some(ExtractedField::new(cents, 0.65));
+                }
+            }
+            let body_starts = title.map_or(0, |(row, _)| row + 1);
+            receipt.line_items = Self::layout_line_items(&layout, in_column, body_starts);
+        }
+
+        check_arithmetic(&mut receipt, &text);
+        Self::weight_by_ocr(&mut receipt, &page.words);
+        receipt
+    }
+
+    /// Fields read from the text alone, before the amounts are checked
+    /// against each other.
+    fn read_fields(ocr_text: &str, options: &ExtractOptions) -> ExtractedReceipt {
         let vendor = Self::extract_vendor(ocr_text);
-        let date = Self::extract_date(ocr_text);
-        let total_cents = Self::extract_total(ocr_text);
-        let subtotal_cents = Self::extract_subtotal(ocr_text);
-        let tax_cents = Self::extract_tax(ocr_text);
-        let payment_method = Self::extract_payment_method(ocr_text);
+        let date = Self::extract_date(ocr_text, options.date_order);
+        let tip = Self::extract_tip(ocr_text);
+        let mut total_cents = Self::extract_total(ocr_text, tip.as_ref());
+        let mut subtotal_cents = Self::extract_subtotal(ocr_text);
+        let mut tax_cents = Self::extract_tax(ocr_text);
 
-        // Aggregate confidence: weighted sum of key fields.
-        let confidence = {
-            let weighted = [
-                (vendor.as_ref().map(|f| f.confidence), 0.25f32),
-                (date.as_ref().map(|f| f.confidence), 0.30),
-                (total_cents.as_ref().map(|f| f.confidence), 0.35),
-                (payment_method.as_ref().map(|f| f.confidence), 0.10),
-            ];
-            let (score, weight) = weighted
-                .iter()
-                .fold((0.0f32, 0.0f32), |(s, w), (conf, fw)| {
-                    (s + conf.unwrap_or(0.0) * fw, w + fw)
-                });
-            if weight > 0.0 {
-                score / weight
-            } else {
-                0.0
+        // Refund slips often print the amounts unsigned.
+        if is_refund(ocr_text) {
+            for field in [&mut total_cents, &mut subtotal_cents, &mut tax_cents]
+                .into_iter()
+                .flatten()
+            {
+                field.value = -field.value.abs();
             }
-        };
+        }
+        let reference = Self::extract_reference(ocr_text);
+        let payment_method = Self::extract_payment_method(ocr_text);
 
         ExtractedReceipt {
             vendor,
@@ -93,12 +222,78 @@ impl Extractor {
             subtotal_cents,
             tax_cents,
             total_cents,
+            tip_cents: tip.map(|(_, tip)| tip),
+            reference,
             payment_method,
             line_items: vec![],
-            confidence,
+            confidence: 0.0,
         }
     }
 
+    /// Lower each field's confidence when the words it was read from were
+    /// read poorly by OCR. A value whose words can't all be found, such as
+    /// a total corrected from the other amounts, is left as it is.
+    pub fn weight_by_ocr(receipt: &mut ExtractedReceipt, words: &[OcrWord]) {
+        if words.is_empty() {
+            return;
+        }
+        let weigh = |confidence: &mut f32, read: Option<f32>| {
+            if let Some(read) = read {
+                *confidence *= (read / CLEAR_READ).min(1.0);
+            }
+        };
+
+        if let Some(vendor) = &mut receipt.vendor {
+            let parts: Vec<WordMatch> = vendor
+                .value
+                .split_whitespace()
+                .map(bare)
+                .filter(|token| !token.is_empty())
+                .map(|token| Box::new(move |w: &str| bare(w) == token) as WordMatch)
+                .collect();
+            weigh(&mut vendor.confidence, read_confidence(words, &parts));
+        }
+        if let Some(date) = &mut receipt.date {
+            let read = read_confidence(words, &date_parts(date.value))
+                .or_else(|| read_confidence(words, &spelled_date_parts(date.value)));
+            weigh(&mut date.confidence, read);
+        }
+        for field in [
+            &mut receipt.subtotal_cents,
+            &mut receipt.tax_cents,
+            &mut receipt.total_cents,
+            &mut receipt.tip_cents,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            let cents = field.value.abs();
+            let part: WordMatch = Box::new(move |w: &str| {
+                re_any_amount()
+                    .captures(w)
+                    .and_then(|c| parse_amount_str(c.get(1)?.as_str()))
+                    .map(i64::abs)
+                    == Some(cents)
+            });
+            weigh(&mut field.confidence, read_confidence(words, &[part]));
+        }
+        if let Some(reference) = &mut receipt.reference {
+            let value = bare(&reference.value);
+            let part: WordMatch = Box::new(move |w: &str| bare(w).contains(&value));
+            weigh(&mut reference.confidence, read_confidence(words, &[part]));
+        }
+        if let Some(method) = &mut receipt.payment_method {
+            let value = method.value.clone();
+            let part: WordMatch = Box::new(move |w: &str| {
+                re_payment()
+                    .captures(w)
+                    .is_some_and(|c| parse_payment_method(&c[1]) == value)
+            });
+            weigh(&mut method.confidence, read_confidence(words, &[part]));
+        }
+        receipt.confidence = aggregate_confidence(receipt);
+    }
+
     // ── Vendor ────────────────────────────────────────────────────────────────
 
     fn extract_vendor(text: &str) -> Option<ExtractedField<String>> {
@@ -109,6 +304,7 @@ impl Extractor {
             .filter(|l| !l.is_empty())
             .filter(|l| !re_phone().is_match(l))
             .filter(|l| !re_url().is_match(l))
+            .filter(|l| !re_reference().is_match(l))
             .filter(|l| !re_date_slash().is_match(l) && !re_date_iso().is_match(l))
             .filter(|l| l.len() >= 3 && l.len() <= 50)
             // Skip lines that start with a digit (likely address or amount)
@@ -126,21 +322,25 @@ impl Extractor {
 
     // ── Date ─────────────────────────────────────────────────────────────────
 
-    fn extract_date(text: &str) -> Option<ExtractedField<NaiveDate>> {
+    fn extract_date(text: &str, order: DateOrder) -> Option<ExtractedField<NaiveDate>> {
         // Try patterns from most to least specific.
         if let Some(d) = try_date_month_name(text) {
             return Some(ExtractedField::new(d, 0.90));
         }
-        if let Some(d) = try_date_abbr_month(text) {
+        if let Some(d) = try_date_day_month(text) {
             return Some(ExtractedField::new(d, 0.90));
         }
         if let Some(d) = try_date_iso(text) {
             return Some(ExtractedField::new(d, 0.95));
         }
-        if let Some(d) = try_date_slash(text) {
+        // Dotted dates are day-first wherever they're used.
+        if let Some(d) = try_date_numeric(re_date_dot(), text, DateOrder::DayFirst) {
+            return Some(ExtractedField::new(d, 0.80));
+        }
+        if let Some(d) = try_date_numeric(re_date_slash(), text, order) {
             return Some(ExtractedField::new(d, 0.75));
         }
-        if let Some(d) = try_date_dash(text) {
+        if let Some(d) = try_date_numeric(re_date_dash(), text, order) {
             return Some(ExtractedField::new(d, 0.70));
         }
         None
@@ -148,21 +348,70 @@ impl Extractor {
 
     // ── Amounts ───────────────────────────────────────────────────────────────
 
-    fn extract_total(text: &str) -> Option<ExtractedField<i64>> {
-        // Prefer a labeled total over any raw dollar amount.
-        if let Some(c) = re_amount_label().captures(text) {
-            if let Some(cents) = parse_amount_str(c.get(1)?.as_str()) {
-                return Some(ExtractedField::new(cents, 0.92));
+    /// The total that was charged. On restaurant receipts that is not the
+    /// printed total but the one after the tip line, written in or, failing
+    /// that, the printed total plus the tip.
+    fn extract_total(
+        text: &str,
+        tip: Option<&(usize, ExtractedField<i64>)>,
+    ) -> Option<ExtractedField<i64>> {
+        let labeled: Vec<(usize, i64)> = re_amount_label()
+            .captures_iter(text)
+            .filter_map(|c| {
+                let m = c.get(1)?;
+                Some((m.start(), parse_amount_str(m.as_str())?))
+            })
+            .collect();
+
+        if let Some((tip_at, tip)) = tip {
+            if let Some(&(_, cents)) = labeled.iter().find(|(at, _)| at > tip_at) {
+                return Some(ExtractedField::new(cents, 0.90));
+            }
+            if let Some(&(_, printed)) = labeled.first() {
+                return Some(ExtractedField::new(printed + tip.value, 0.80));
             }
         }
-        // Fall back to the largest dollar value on the page.
-        re_currency()
+
+        // Prefer a labeled total over any raw dollar amount.
+        if let Some(&(_, cents)) = labeled.first() {
+            return Some(ExtractedField::new(cents, 0.92));
+        }
+        // Fall back to the largest dollar value on the page. Discounts are
+        // negative and never the total, unless nothing else is.
+        let amounts: Vec<i64> = re_currency()
             .captures_iter(text)
             .filter_map(|c| parse_amount_str(c.get(1)?.as_str()))
+            .collect();
+        amounts
+            .iter()
+            .copied()
+            .filter(|c| *c > 0)
             .max()
+            .or_else(|| amounts.iter().copied().min())
             .map(|cents| ExtractedField::new(cents, 0.55))
     }
 
+    /// A filled-in tip line, with its position in the text. Tip guides
+    /// ("18% tip = $7.83") are suggestions, not what was paid, and skipped.
+    fn extract_tip(text: &str) -> Option<(usize, ExtractedField<i64>)> {
+        let mut offset = 0;
+        for line in text.split_inclusive('\n') {
+            let start = offset;
+            offset += line.len();
+            let lower = line.to_lowercase();
+            if line.contains('%') || lower.contains("suggest") {
+                continue;
+            }
+            if let Some(m) = re_tip().captures(line).and_then(|c| c.get(1)) {
+                let cents = parse_amount_str(m.as_str())?;
+                if cents > 0 {
+                    return Some((start + m.start(), ExtractedField::new(cents, 0.85)));
+                }
+            }
+        }
+        None
+    }
+
     fn extract_subtotal(text: &str) -> Option<ExtractedField<i64>> {
         let c = re_subtotal().captures(text)?;
         let cents = parse_amount_str(c.get(1)?.as_str())?;
@@ -175,40 +424,355 @@ impl Extractor {
         Some(ExtractedField::new(cents, 0.88))
     }
 
+    // ── Reference ─────────────────────────────────────────────────────────────
+
+    /// The invoice or order number, preferring those over receipt and
+    /// terminal transaction numbers when several are printed.
+    fn extract_reference(text: &str) -> Option<ExtractedField<String>> {
+        re_reference()
+            .captures_iter(text)
+            .filter_map(|c| {
+                let rank = match c.get(1)?.as_str().to_lowercase().as_str() {
+                    "invoice" | "inv" | "facture" | "factura" | "rechnung" => 0,
+                    "order" => 1,
+                    "receipt" | "rcpt" | "ticket" | "beleg" | "folio" => 2,
+                    "reference" | "ref" => 3,
+                    _ => 4,
+                };
+                let value = c.get(2)?.as_str().trim_end_matches(['-', '/']);
+                // "Receipt 01/15/2024" is a date, not a number.
+                let is_date = re_date_slash().is_match(value)
+                    || re_date_dash().is_match(value)
+                    || re_date_iso().is_match(value);
+                (value.len() >= 3 && !is_date).then_some((rank, value))
+            })
+            .min_by_key(|(rank, _)| *rank)
+            .map(|(rank, value)| {
+                ExtractedField::new(value.to_string(), if rank <= 1 { 0.85 } else { 0.75 })
+            })
+    }
+
     // ── Payment method ────────────────────────────────────────────────────────
 
     fn extract_payment_method(text: &str) -> Option<ExtractedField<PaymentMethod>> {
         let c = re_payment().captures(text)?;
-        let method = match c.get(1)?.as_str().to_lowercase().replace(' ', "").as_str() {
-            "visa" => PaymentMethod::Visa,
-            "mastercard" | "mc" => PaymentMethod::Mastercard,
-            "amex" | "americanexpress" => PaymentMethod::Amex,
-            "discover" => PaymentMethod::Discover,
-            "cash" => PaymentMethod::Cash,
-            "debit" => PaymentMethod::Debit,
-            "check" | "cheque" => PaymentMethod::Check,
-            other => PaymentMethod::Other(other.to_string()),
-        };
+        let method = parse_payment_method(c.get(1)?.as_str());
         Some(ExtractedField::new(method, 0.90))
     }
+
+    // ── Layout ────────────────────────────────────────────────────────────────
+
+    /// The row printed noticeably larger than the rest in the top quarter
+    /// of the page, with its index. Logos and shop names are; when nothing
+    /// stands out the text heuristics pick the vendor instead.
+    fn layout_vendor(layout: &Layout) -> Option<(usize, ExtractedField<String>)> {
+        let body = layout.text_height();
+        let (index, row) = layout
+            .top_rows(0.25)
+            .filter(|(_, row)| row.text_height() * 5 >= body * 6)
+            .filter(|(_, row)| Self::extract_vendor(&row.text()).is_some())
+            .fold(None, |best: Option<(usize, &Row)>, (i, row)| match best {
+                Some((_, b)) if b.text_height() >= row.text_height() => best,
+                _ => Some((i, row)),
+            })?;
+        Some((index, ExtractedField::new(row.text(), 0.75)))
+    }
+
+    /// The largest positive amount in the amounts column in the lower half
+    /// of the page, for receipts whose total has no label.
+    fn column_total(layout: &Layout, in_column: &dyn Fn(&OcrWord) -> bool) -> Option<i64> {
+        layout
+            .rows
+            .iter()
+            .filter(|row| layout.is_below(row, 0.5))
+            .flat_map(|row| row.words.iter().filter(|w| in_column(w)))
+            .filter_map(|w| parse_amount_str(&w.text))
+            .filter(|cents| *cents > 0)
+            .max()
+    }
+
+    /// Rows from `first_row` up to the subtotal or total that end in an
+    /// amount in the amounts column and describe something. A leading
+    /// count (`2`, `2x`, `2 @`) is the quantity; unit prices are dropped
+    /// from the description.
+    fn layout_line_items(
+        layout: &Layout,
+        in_column: &dyn Fn(&OcrWord) -> bool,
+        first_row: usize,
+    ) -> Vec<LineItem> {
+        let mut items = Vec::new();
+        for row in layout.rows.iter().skip(first_row) {
+            let text = row.text();
+            if re_subtotal().is_match(&text) || re_amount_label().is_match(&text) {
+                break;
+            }
+            let Some((last, rest)) = row.words.split_last() else {
+                continue;
+            };
+            if !in_column(last)
+                || re_tax().is_match(&text)
+                || re_tip().is_match(&text)
+                || re_payment().is_match(&text)
+                || re_phone().is_match(&text)
+                || re_reference().is_match(&text)
+                || re_date_slash().is_match(&text)
+                || re_date_iso().is_match(&text)
+            {
+                continue;
+            }
+            let mut words: Vec<&str> = rest
+                .iter()
+                .map(|w| w.text.as_str())
+                .filter(|w| !re_amount_word().is_match(w) && !matches!(*w, "$" | "€" | "£"))
+                .collect();
+            let quantity = match words.as_slice() {
+                [count, ..] if words.len() > 1 => re_quantity()
+                    .captures(count)
+                    .and_then(|c| c[1].parse::<f32>().ok()),
+                _ => None,
+            };
+            if quantity.is_some() {
+                words.remove(0);
+                if matches!(words.first(), Some(&("x" | "X" | "@"))) {
+                    words.remove(0);
+                }
+            }
+            let description = words.join(" ");
+            if description.chars().filter(|c| c.is_alphabetic()).count() < 2 {
+                continue;
+            }
+            items.push(LineItem {
+                description,
+                amount_cents: parse_amount_str(&last.text),
+                quantity,
+            });
+        }
+        items
+    }
+}
+
+/// A refund or return slip: a refund/return marker on a line that carries an
+/// amount. Return-policy boilerplate, which every receipt has, doesn't count.
+fn is_refund(text: &str) -> bool {
+    text.lines().any(|line| {
+        re_refund().is_match(line)
+            && !re_policy().is_match(line)
+            && line.chars().any(|c| c.is_ascii_digit())
+    })
+}
+
+/// The amount printed after `label` (case-insensitive), e.g. a vendor's own
+/// wording for the charged total.
+pub(crate) fn amount_after_label(text: &str, label: &str) -> Option<i64> {
+    let pattern = format!(
+        r"(?i)(?:^|\s){}\s*[:\$]?\s*{}",
+        regex::escape(label),
+        amount!()
+    );
+    let c = Regex::new(&pattern).ok()?.captures(text)?;
+    parse_amount_str(c.get(1)?.as_str())
+}
+
+/// The words in front of `cents` on the line that prints it — how a vendor
+/// labels an amount, learned from a receipt the user approved. Labels the
+/// extractor already understands ("Total", "Amount due") aren't returned.
+pub(crate) fn label_before_amount(text: &str, cents: i64) -> Option<String> {
+    text.lines().find_map(|line| {
+        let m = re_any_amount()
+            .captures_iter(line)
+            .filter_map(|c| c.get(1))
+            .find(|m| parse_amount_str(m.as_str()).map(i64::abs) == Some(cents.abs()))?;
+        let label = line[..m.start()]
+            .trim_end_matches(|c: char| c.is_whitespace() || c == ':' || c == '$')
+            .trim();
+        let is_label = !label.is_empty()
+            && label.chars().all(|c| c.is_alphabetic() || c == ' ')
+            && !re_amount_label().is_match(&format!("{label} 1.00"));
+        is_label.then(|| label.to_string())
+    })
+}
+
+/// Off-by-this-much still counts as adding up (per-line tax rounding).
+const ARITHMETIC_TOLERANCE_CENTS: i64 = 2;
+/// Amounts that add up are very unlikely to all be misread.
+const CONSISTENT_CONFIDENCE: f32 = 0.95;
+/// An amount re-read from the text because it makes the receipt add up.
+const CORRECTED_CONFIDENCE: f32 = 0.85;
+/// Applied to all three amounts when nothing reconciles them.
+const MISMATCH_PENALTY: f32 = 0.6;
+
+/// Check that subtotal + tax (+ tip) ≈ total. When it holds, all three
+/// amounts gain confidence. When it doesn't, look through every amount
+/// printed on the receipt for a total, then a tax, then a subtotal that
+/// would make it add up; if none does, down-weight all three so the
+/// receipt goes to review.
+///
+/// A missing tax line is treated as zero tax, but only ever filled in,
+/// never penalised, since tax-free receipts often omit it.
+pub(crate) fn check_arithmetic(receipt: &mut ExtractedReceipt, text: &str) {
+    let (Some(subtotal), Some(total)) = (&receipt.subtotal_cents, &receipt.total_cents) else {
+        return;
+    };
+    // Refunds are negative throughout; reconcile magnitudes.
+    let sign = if total.value < 0 { -1 } else { 1 };
+    let sub = subtotal.value.abs();
+    let tot = total.value.abs();
+    let tax = receipt.tax_cents.as_ref().map(|f| f.value.abs());
+    let tip = receipt.tip_cents.as_ref().map_or(0, |f| f.value);
+    let near = |a: i64, b: i64| (a - b).abs() <= ARITHMETIC_TOLERANCE_CENTS;
+
+    if near(sub + tax.unwrap_or(0) + tip, tot) {
+        for field in [
+            &mut receipt.subtotal_cents,
+            &mut receipt.tax_cents,
+            &mut receipt.total_cents,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            field.confidence = field.confidence.max(CONSISTENT_CONFIDENCE);
+        }
+        return;
+    }
+
+    let printed: Vec<i64> = re_any_amount()
+        .captures_iter(text)
+        .filter_map(|c| parse_amount_str(c.get(1)?.as_str()))
+        .map(i64::abs)
+        .collect();
+    let find = |want: i64| printed.iter().copied().find(|&c| c > 0 && near(c, want));
+
+    let Some(tax) = tax else {
+        if let Some(found) = find(tot - sub - tip) {
+            receipt.tax_cents = Some(ExtractedField::new(sign * found, CORRECTED_CONFIDENCE));
+        }
+        return;
+    };
+
+    if let Some(found) = find(sub + tax + tip) {
+        receipt.total_cents = Some(ExtractedField::new(sign * found, CORRECTED_CONFIDENCE));
+    } else if let Some(found) = find(tot - sub - tip) {
+        receipt.tax_cents = Some(ExtractedField::new(sign * found, CORRECTED_CONFIDENCE));
+    } else if let Some(found) = find(tot - tax - tip) {
+        receipt.subtotal_cents = Some(ExtractedField::new(sign * found, CORRECTED_CONFIDENCE));
+    } else {
+        for field in [
+            &mut receipt.subtotal_cents,
+            &mut receipt.tax_cents,
+            &mut receipt.total_cents,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            field.confidence *= MISMATCH_PENALTY;
+        }
+    }
+}
+
+/// Weighted confidence across the fields that matter for creating a
+/// transaction; the total counts most.
+pub(crate) fn aggregate_confidence(receipt: &ExtractedReceipt) -> f32 {
+    let weighted = [
+        (receipt.vendor.as_ref().map(|f| f.confidence), 0.25f32),
+        (receipt.date.as_ref().map(|f| f.confidence), 0.30),
+        (receipt.total_cents.as_ref().map(|f| f.confidence), 0.35),
+        (receipt.payment_method.as_ref().map(|f| f.confidence), 0.10),
+    ];
+    let (score, weight) = weighted
+        .iter()
+        .fold((0.0f32, 0.0f32), |(s, w), (conf, fw)| {
+            (s + conf.unwrap_or(0.0) * fw, w + fw)
+        });
+    if weight > 0.0 {
+        score / weight
+    } else {
+        0.0
+    }
+}
+
+// ── OCR word confidence ───────────────────────────────────────────────────────
+
+/// Words read at least this confidently don't lower a field's confidence.
+const CLEAR_READ: f32 = 0.85;
+
+/// Whether an OCR word is (part of) a field's value.
+type WordMatch = Box<dyn Fn(&str) -> bool>;
+
+/// How clearly OCR read a value made of `parts`: each part counts the
+/// best-read word it matches, and the value its worst-read part. `None`
+/// when a part matches no word.
+fn read_confidence(words: &[OcrWord], parts: &[WordMatch]) -> Option<f32> {
+    if parts.is_empty() {
+        return None;
+    }
+    parts.iter().try_fold(1.0f32, |worst, part| {
+        let best = words
+            .iter()
+            .filter(|w| part(&w.text))
+            .map(|w| w.confidence)
+            .reduce(f32::max)?;
+        Some(worst.min(best))
+    })
+}
+
+/// Lowercase letters and digits only, so "Coffee," matches "COFFEE".
+fn bare(s: &str) -> String {
+    s.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// A date printed as one word: `2024-03-15`, `15/03/2024`, `15.03.24`.
+fn date_parts(date: NaiveDate) -> Vec<WordMatch> {
+    vec![Box::new(move |w: &str| {
+        [DateOrder::MonthFirst, DateOrder::DayFirst]
+            .into_iter()
+            .any(|order| Extractor::extract_date(w, order).is_some_and(|d| d.value == date))
+    })]
+}
+
+/// A date spelled out over several words: `March 15, 2024`, `15. März 2024`.
+fn spelled_date_parts(date: NaiveDate) -> Vec<WordMatch> {
+    use chrono::Datelike;
+    let (day, month, year) = (date.day(), date.month(), date.year());
+    vec![
+        Box::new(move |w: &str| month_to_num(&bare(w)) == Some(month)),
+        Box::new(move |w: &str| bare(w).parse() == Ok(day)),
+        Box::new(move |w: &str| bare(w).parse() == Ok(year)),
+    ]
+}
+
+pub(crate) fn parse_payment_method(s: &str) -> PaymentMethod {
+    match s.to_lowercase().replace(' ', "").as_str() {
+        "visa" => PaymentMethod::Visa,
+        "mastercard" | "mc" => PaymentMethod::Mastercard,
+        "amex" | "americanexpress" => PaymentMethod::Amex,
+        "discover" => PaymentMethod::Discover,
+        "cash" => PaymentMethod::Cash,
+        "debit" => PaymentMethod::Debit,
+        "check" | "cheque" => PaymentMethod::Check,
+        other => PaymentMethod::Other(other.to_string()),
+    }
 }
 
 // ── Date helpers ──────────────────────────────────────────────────────────────
 
 fn try_date_month_name(text: &str) -> Option<NaiveDate> {
     let c = re_date_month_name().captures(text)?;
-    let month = month_name_to_num(c.get(1)?.as_str())?;
+    let month = month_to_num(c.get(1)?.as_str())?;
     let day: u32 = c.get(2)?.as_str().parse().ok()?;
     let year: i32 = c.get(3)?.as_str().parse().ok()?;
     NaiveDate::from_ymd_opt(year, month, day)
 }
 
-fn try_date_abbr_month(text: &str) -> Option<NaiveDate> {
-    let c = re_date_abbr_month().captures(text)?;
-    let day: u32 = c.get(1)?.as_str().parse().ok()?;
-    let month = abbr_month_to_num(c.get(2)?.as_str())?;
-    let year: i32 = c.get(3)?.as_str().parse().ok()?;
-    NaiveDate::from_ymd_opt(year, month, day)
+fn try_date_day_month(text: &str) -> Option<NaiveDate> {
+    re_date_day_month().captures_iter(text).find_map(|c| {
+        let day: u32 = c.get(1)?.as_str().parse().ok()?;
+        let month = month_to_num(c.get(2)?.as_str())?;
+        let year: i32 = c.get(3)?.as_str().parse().ok()?;
+        NaiveDate::from_ymd_opt(year, month, day)
+    })
 }
 
 fn try_date_iso(text: &str) -> Option<NaiveDate> {
@@ -219,23 +783,18 @@ fn try_date_iso(text: &str) -> Option<NaiveDate> {
     NaiveDate::from_ymd_opt(y, m, d)
 }
 
-fn try_date_slash(text: &str) -> Option<NaiveDate> {
-    let c = re_date_slash().captures(text)?;
+/// `a/b/year`-style dates, with `a` and `b` read in `order`.
+fn try_date_numeric(re: &Regex, text: &str, order: DateOrder) -> Option<NaiveDate> {
+    let c = re.captures(text)?;
     let p1: u32 = c.get(1)?.as_str().parse().ok()?;
     let p2: u32 = c.get(2)?.as_str().parse().ok()?;
-    let p3_str = c.get(3)?.as_str();
-    let year: i32 = expand_year(p3_str.parse().ok()?);
-    // Assume MM/DD/YYYY (US format)
-    NaiveDate::from_ymd_opt(year, p1, p2)
-}
-
-fn try_date_dash(text: &str) -> Option<NaiveDate> {
-    let c = re_date_dash().captures(text)?;
-    let p1: u32 = c.get(1)?.as_str().parse().ok()?;
-    let p2: u32 = c.get(2)?.as_str().parse().ok()?;
-    let p3_str = c.get(3)?.as_str();
-    let year: i32 = expand_year(p3_str.parse().ok()?);
-    NaiveDate::from_ymd_opt(year, p1, p2)
+    let year: i32 = expand_year(c.get(3)?.as_str().parse().ok()?);
+    let (month, day) = match order {
+        DateOrder::MonthFirst => (p1, p2),
+        DateOrder::DayFirst => (p2, p1),
+    };
+    // A date like 25/12 only reads one way, whatever the locale says.
+    NaiveDate::from_ymd_opt(year, month, day).or_else(|| NaiveDate::from_ymd_opt(year, day, month))
 }
 
 fn expand_year(y: i32) -> i32 {
@@ -246,48 +805,47 @@ fn expand_year(y: i32) -> i32 {
     }
 }
 
-fn month_name_to_num(name: &str) -> Option<u32> {
-    match name.to_lowercase().as_str() {
-        "january" => Some(1),
-        "february" => Some(2),
-        "march" => Some(3),
-        "april" => Some(4),
-        "may" => Some(5),
-        "june" => Some(6),
-        "july" => Some(7),
-        "august" => Some(8),
-        "september" => Some(9),
-        "october" => Some(10),
-        "november" => Some(11),
-        "december" => Some(12),
-        _ => None,
-    }
-}
-
-fn abbr_month_to_num(name: &str) -> Option<u32> {
-    match name.to_lowercase().as_str() {
-        "jan" => Some(1),
-        "feb" => Some(2),
-        "mar" => Some(3),
-        "apr" => Some(4),
-        "may" => Some(5),
-        "jun" => Some(6),
-        "jul" => Some(7),
-        "aug" => Some(8),
-        "sep" => Some(9),
-        "oct" => Some(10),
-        "nov" => Some(11),
-        "dec" => Some(12),
-        _ => None,
-    }
+/// Full and abbreviated month names in English, French, Spanish and German.
+fn month_to_num(name: &str) -> Option<u32> {
+    let month = match name.to_lowercase().as_str() {
+        "january" | "jan" | "janvier" | "janv" | "enero" | "ene" | "januar" | "jänner" => 1,
+        "february" | "feb" | "février" | "fevrier" | "févr" | "fevr" | "febrero" | "februar" => 2,
+        "march" | "mar" | "mars" | "marzo" | "märz" | "maerz" | "mär" => 3,
+        "april" | "apr" | "avril" | "avr" | "abril" | "abr" => 4,
+        "may" | "mai" | "mayo" => 5,
+        "june" | "jun" | "juin" | "junio" | "juni" => 6,
+        "july" | "jul" | "juillet" | "juil" | "julio" | "juli" => 7,
+        "august" | "aug" | "août" | "aout" | "agosto" | "ago" => 8,
+        "september" | "sep" | "sept" | "septembre" | "septiembre" | "setiembre" => 9,
+        "october" | "oct" | "octobre" | "octubre" | "oktober" | "okt" => 10,
+        "november" | "nov" | "novembre" | "noviembre" => 11,
+        "december" | "dec" | "décembre" | "decembre" | "déc" | "diciembre" | "dic" | "dezember"
+        | "dez" => 12,
+        _ => return None,
+    };
+    Some(month)
 }
 
 // ── Amount parsing ────────────────────────────────────────────────────────────
 
+/// Parse an amount capture to cents. A minus sign anywhere (`-5.00`,
+/// `$-5.00`, `5.00-`) or accounting parentheses make it negative. The
+/// decimal separator is whichever of `.` or `,` is followed by exactly two
+/// trailing digits; any other separators group thousands.
 fn parse_amount_str(s: &str) -> Option<i64> {
-    let clean = s.replace(',', "");
-    let dec = Decimal::from_str(&clean).ok()?;
-    (dec * Decimal::from(100)).round().to_i64()
+    let negative = s.contains('-') || s.starts_with('(');
+    let body: String = s
+        .chars()
+        .filter(|c| c.is_ascii_digit() || *c == '.' || *c == ',')
+        .collect();
+    let (whole, fraction) = match body.rfind(['.', ',']) {
+        Some(i) if body.len() - i == 3 => (&body[..i], &body[i + 1..]),
+        _ => (body.as_str(), "00"),
+    };
+    let whole: String = whole.chars().filter(char::is_ascii_digit).collect();
+    let dec = Decimal::from_str(&format!("{whole}.{fraction}")).ok()?;
+    let cents = (dec * Decimal::from(100)).round().to_i64()?;
+    Some(if negative { -cents } else { cents })
 }
 
 // ── Tests ─────────────────────────────────────────────────────────────────────
@@ -361,6 +919,80 @@ mod tests {
         );
     }
 
+    #[test]
+    fn numeric_date_day_first_by_locale() {
+        let text = "TESCO\n05/03/2024\nTotal £12.40";
+        let day_first = ExtractOptions {
+            date_order: DateOrder::DayFirst,
+        };
+        let r = Extractor::extract_with(text, &day_first);
+        assert_eq!(
+            r.date.unwrap().value,
+            NaiveDate::from_ymd_opt(2024, 3, 5).unwrap()
+        );
+        let r = Extractor::extract(text);
+        assert_eq!(
+            r.date.unwrap().value,
+            NaiveDate::from_ymd_opt(2024, 5, 3).unwrap()
+        );
+    }
+
+    #[test]
+    fn day_first_only_date_read_regardless_of_locale() {
+        let r = Extractor::extract("STORE\n25/12/2024\nTotal $5.00");
+        assert_eq!(
+            r.date.unwrap().value,
+            NaiveDate::from_ymd_opt(2024, 12, 25).unwrap()
+        );
+    }
+
+    // ── Non-English receipts ──────────────────────────────────────────────────
+
+    #[test]
+    fn german_receipt() {
+        let text = "REWE MARKT\n15.03.2024\nZwischensumme 19,70\nMwSt 19% 3,75\nGesamt 23,45 €\nKartenzahlung";
+        let r = Extractor::extract(text);
+        assert_eq!(
+            r.date.unwrap().value,
+            NaiveDate::from_ymd_opt(2024, 3, 15).unwrap()
+        );
+        assert_eq!(r.subtotal_cents.unwrap().value, 1970);
+        assert_eq!(r.tax_cents.unwrap().value, 375);
+        assert_eq!(r.total_cents.unwrap().value, 2345);
+    }
+
+    #[test]
+    fn french_receipt() {
+        let text = "CARREFOUR\n15 mars 2024\nTotal HT 10,42\nTVA 20% 2,08\nTOTAL TTC 12,50 €";
+        let r = Extractor::extract(text);
+        assert_eq!(
+            r.date.unwrap().value,
+            NaiveDate::from_ymd_opt(2024, 3, 15).unwrap()
+        );
+        assert_eq!(r.subtotal_cents.unwrap().value, 1042);
+        assert_eq!(r.tax_cents.unwrap().value, 208);
+        assert_eq!(r.total_cents.unwrap().value, 1250);
+    }
+
+    #[test]
+    fn spanish_receipt() {
+        let text =
+            "MERCADONA\n15 de marzo de 2024\nBase imponible 8,26\nIVA 21% 1,74\nTOTAL 10,00 €";
+        let r = Extractor::extract(text);
+        assert_eq!(
+            r.date.unwrap().value,
+            NaiveDate::from_ymd_opt(2024, 3, 15).unwrap()
+        );
+        assert_eq!(r.tax_cents.unwrap().value, 174);
+        assert_eq!(r.total_cents.unwrap().value, 1000);
+    }
+
+    #[test]
+    fn european_thousands_separator() {
+        let r = Extractor::extract("MEDIAMARKT\nSumme 1.234,56 €");
+        assert_eq!(r.total_cents.unwrap().value, 123456);
+    }
+
     // ── Amounts ───────────────────────────────────────────────────────────────
 
     #[test]
@@ -387,6 +1019,34 @@ mod tests {
         assert_eq!(r.total_cents.unwrap().value, 4860);
     }
 
+    #[test]
+    fn restaurant_total_after_tip_line() {
+        let text = "THE DINER\nSubtotal $40.00\nTax $3.50\nTotal $43.50\n\
+                    Tip 8.00\nTotal 51.50\nVISA";
+        let r = Extractor::extract(text);
+        assert_eq!(r.tip_cents.unwrap().value, 800);
+        assert_eq!(r.total_cents.unwrap().value, 5150);
+    }
+
+    #[test]
+    fn restaurant_total_adds_tip_when_final_total_missing() {
+        let text = "THE DINER\nTotal $43.50\nGratuity: $8.70\nSignature ________";
+        let r = Extractor::extract(text);
+        assert_eq!(r.tip_cents.unwrap().value, 870);
+        let total = r.total_cents.unwrap();
+        assert_eq!(total.value, 5220);
+        assert!(total.confidence < 0.9);
+    }
+
+    #[test]
+    fn tip_guide_is_not_a_tip() {
+        let text = "THE DINER\nTotal $43.50\n\
+                    Suggested tip:\n18% Tip $7.83\n20% Tip $8.70\nTip ______";
+        let r = Extractor::extract(text);
+        assert!(r.tip_cents.is_none());
+        assert_eq!(r.total_cents.unwrap().value, 4350);
+    }
+
     #[test]
     fn extract_total_falls_back_to_largest_amount() {
         let text = "STORE\n$5.00\n$3.00\n$8.00";
@@ -401,6 +1061,141 @@ mod tests {
         assert_eq!(r.total_cents.unwrap().value, 123456);
     }
 
+    #[test]
+    fn extract_total_without_currency_symbol() {
+        let text = "CORNER MART\nMILK 3.49\nTOTAL 23.45\nCASH";
+        let r = Extractor::extract(text);
+        assert_eq!(r.total_cents.unwrap().value, 2345);
+    }
+
+    #[test]
+    fn discount_is_never_the_fallback_total() {
+        let text = "STORE\nWidget $30.00\n-$50.00 OFF COUPON\n$25.00";
+        let r = Extractor::extract(text);
+        assert_eq!(r.total_cents.unwrap().value, 3000);
+    }
+
+    #[test]
+    fn discounts_do_not_disturb_labeled_amounts() {
+        let text = "STORE\nWidget 30.00\nCOUPON (5.00)\nDISCOUNT 2.00-\n\
+                    Subtotal $23.00\nTax $1.84\nTotal $24.84\n\
+                    Returns accepted within 30 days with receipt";
+        let r = Extractor::extract(text);
+        assert_eq!(r.subtotal_cents.unwrap().value, 2300);
+        assert_eq!(r.total_cents.unwrap().value, 2484);
+    }
+
+    #[test]
+    fn refund_receipt_has_negative_total() {
+        let text = "HOME DEPOT\nREFUND\nDrill -$89.00\nSubtotal 89.00\n\
+                    Tax 7.12\nRefund Total $96.12";
+        let r = Extractor::extract(text);
+        assert_eq!(r.total_cents.unwrap().value, -9612);
+        assert_eq!(r.subtotal_cents.unwrap().value, -8900);
+        assert_eq!(r.tax_cents.unwrap().value, -712);
+    }
+
+    #[test]
+    fn signed_total_kept_negative() {
+        let text = "STORE\nRETURNED ITEM 12.00\nTOTAL ($12.00)";
+        let r = Extractor::extract(text);
+        assert_eq!(r.total_cents.unwrap().value, -1200);
+    }
+
+    #[test]
+    fn return_policy_does_not_make_a_refund() {
+        let text = "STORE\nTotal $10.00\nReturn policy: 30 days\nNo refunds on sale items";
+        let r = Extractor::extract(text);
+        assert_eq!(r.total_cents.unwrap().value, 1000);
+    }
+
+    // ── Arithmetic check ──────────────────────────────────────────────────────
+
+    #[test]
+    fn consistent_amounts_gain_confidence() {
+        let text = "STORE\nSubtotal $45.00\nTax $3.60\nTotal $48.60";
+        let r = Extractor::extract(text);
+        assert_eq!(r.total_cents.unwrap().confidence, CONSISTENT_CONFIDENCE);
+        assert_eq!(r.tax_cents.unwrap().confidence, CONSISTENT_CONFIDENCE);
+    }
+
+    #[test]
+    fn misread_total_replaced_by_printed_amount_that_adds_up() {
+        // OCR misread the 8 in the total as a 3; the card line has it right.
+        let text = "STORE\nSubtotal $45.00\nTax $3.60\nTotal $43.60\nVISA 48.60";
+        let r = Extractor::extract(text);
+        let total = r.total_cents.unwrap();
+        assert_eq!(total.value, 4860);
+        assert_eq!(total.confidence, CORRECTED_CONFIDENCE);
+    }
+
+    #[test]
+    fn missing_tax_filled_from_difference() {
+        let text = "STORE\nSubtotal $45.00\nHST 13% 5.85\nTotal $50.85";
+        let r = Extractor::extract(text);
+        assert_eq!(r.tax_cents.unwrap().value, 585);
+    }
+
+    #[test]
+    fn irreconcilable_amounts_are_down_weighted() {
+        let text = "STORE\nSubtotal $45.00\nTax $3.60\nTotal $99.99";
+        let r = Extractor::extract(text);
+        let total = r.total_cents.as_ref().unwrap();
+        assert_eq!(total.value, 9999);
+        assert!(
+            total.confidence < 0.6,
+            "confidence was {}",
+            total.confidence
+        );
+        assert!(r.needs_review());
+    }
+
+    #[test]
+    fn tip_counts_toward_total() {
+        let text = "DINER\nSubtotal $40.00\nTax $3.50\nTotal $43.50\nTip 8.00\nTotal 51.50";
+        let r = Extractor::extract(text);
+        assert_eq!(r.total_cents.unwrap().confidence, CONSISTENT_CONFIDENCE);
+    }
+
+    // ── Reference ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn extract_invoice_number() {
+        let text =
+            "ACME SUPPLY CO\nInvoice No. INV-2024-0042\nInvoice Date: 01/15/2024\nTotal $108.25";
+        let r = Extractor::extract(text);
+        let reference = r.reference.unwrap();
+        assert_eq!(reference.value, "INV-2024-0042");
+        assert!(reference.confidence >= 0.85);
+        assert_eq!(r.vendor.unwrap().value, "ACME SUPPLY CO");
+    }
+
+    #[test]
+    fn extract_amazon_order_number() {
+        let text = "amazon.com\nOrder #112-1234567-1234567\nOrder Total: $34.99";
+        let r = Extractor::extract(text);
+        assert_eq!(r.reference.unwrap().value, "112-1234567-1234567");
+    }
+
+    #[test]
+    fn order_number_preferred_over_transaction_number() {
+        let text = "STORE\nTrans 88231 Register 4\nOrder 123-456\nTotal $5.00";
+        let r = Extractor::extract(text);
+        assert_eq!(r.reference.unwrap().value, "123-456");
+    }
+
+    #[test]
+    fn receipt_date_is_not_a_reference() {
+        let text = "STORE\nReceipt 01/15/2024\nOrder Total $5.00";
+        assert!(Extractor::extract(text).reference.is_none());
+    }
+
+    #[test]
+    fn french_invoice_number() {
+        let r = Extractor::extract("FNAC\nFacture n° 881204\nTOTAL TTC 12,50 €");
+        assert_eq!(r.reference.unwrap().value, "881204");
+    }
+
     // ── Payment method ────────────────────────────────────────────────────────
 
     #[test]
@@ -444,6 +1239,164 @@ mod tests {
         let _ = Extractor::extract("!@#$%^&*()\n\0\x01\x02");
     }
 
+    /// Words of `text` as OCR would report them, each read at `confidence`
+    /// unless listed in `shaky`.
+    fn words(text: &str, confidence: f32, shaky: &[(&str, f32)]) -> Vec<OcrWord> {
+        text.lines()
+            .enumerate()
+            .flat_map(|(line, l)| l.split_whitespace().map(move |w| (line, w)))
+            .map(|(line, w)| OcrWord {
+                text: w.to_string(),
+                confidence: shaky
+                    .iter()
+                    .find(|(s, _)| *s == w)
+                    .map_or(confidence, |(_, c)| *c),
+                bbox: crate::recognizer::BoundingBox {
+                    left: 0,
+                    top: 0,
+                    width: 0,
+                    height: 0,
+                },
+                line,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn clear_ocr_leaves_confidence_alone() {
+        let text = "STARBUCKS COFFEE\nMarch 15, 2024\nTotal $5.25\nVISA";
+        let mut r = Extractor::extract(text);
+        let before = r.clone();
+        Extractor::weight_by_ocr(&mut r, &words(text, 0.93, &[]));
+        assert_eq!(r.vendor, before.vendor);
+        assert_eq!(r.date, before.date);
+        assert_eq!(r.total_cents, before.total_cents);
+        assert_eq!(r.confidence, before.confidence);
+    }
+
+    #[test]
+    fn poorly_read_words_lower_their_fields() {
+        let text = "STARBUCKS COFFEE\n2024-01-15\nTotal $5.25\nVISA";
+        let mut r = Extractor::extract(text);
+        let before = r.clone();
+        let read = words(text, 0.95, &[("$5.25", 0.425), ("COFFEE", 0.68)]);
+        Extractor::weight_by_ocr(&mut r, &read);
+
+        let total = r.total_cents.unwrap().confidence;
+        assert!((total - before.total_cents.unwrap().confidence * 0.5).abs() < 1e-4);
+        let vendor = r.vendor.unwrap().confidence;
+        assert!((vendor - before.vendor.unwrap().confidence * 0.8).abs() < 1e-4);
+        assert_eq!(r.date, before.date);
+        assert_eq!(r.payment_method, before.payment_method);
+        assert!(r.confidence < before.confidence);
+    }
+
+    #[test]
+    fn spelled_out_date_weighted_by_its_words() {
+        let text = "REWE MARKT\n15. März 2024\nGesamt 23,45 €";
+        let mut r = Extractor::extract(text);
+        let before = r.date.clone().unwrap().confidence;
+        Extractor::weight_by_ocr(&mut r, &words(text, 0.9, &[("März", 0.425)]));
+        assert!((r.date.unwrap().confidence - before * 0.5).abs() < 1e-4);
+    }
+
+    // ── Layout ────────────────────────────────────────────────────────────────
+
+    /// A wide receipt as Tesseract segments it: the description column
+    /// first, then the amounts column, so no text line holds a label and
+    /// its amount together.
+    fn wide_receipt() -> OcrPage {
+        use crate::layout::tests::word;
+        let words = vec![
+            word("Welcome", 20, 0, 20),
+            word("HARBOR", 20, 40, 48),
+            word("HARDWARE", 100, 40, 48),
+            word("2026-02-03", 20, 110, 20),
+            word("2", 20, 160, 20),
+            word("x", 40, 160, 20),
+            word("Hinge", 60, 160, 20),
+            word("4.25", 400, 160, 20),
+            word("Wood", 20, 200, 20),
+            word("glue", 70, 200, 20),
+            word("6.49", 400, 200, 20),
+            word("Subtotal", 20, 260, 20),
+            word("Tax", 20, 300, 20),
+            word("Total", 20, 340, 20),
+            word("$8.50", 600, 160, 20),
+            word("$6.49", 600, 200, 20),
+            word("$14.99", 590, 260, 20),
+            word("$1.20", 600, 300, 20),
+            word("$16.19", 590, 340, 20),
+            word("VISA", 20, 380, 20),
+        ];
+        let text = words
+            .iter()
+            .map(|w| w.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        OcrPage { text, words }
+    }
+
+    #[test]
+    fn layout_puts_wide_rows_back_together() {
+        let page = wide_receipt();
+        // From the text alone the labels and amounts don't pair up.
+        let flat = Extractor::extract(&page.text);
+        assert_eq!(flat.tax_cents, None);
+
+        let r = Extractor::extract_page(&page, &ExtractOptions::default());
+        assert_eq!(r.vendor.unwrap().value, "HARBOR HARDWARE");
+        assert_eq!(r.subtotal_cents.unwrap().value, 1499);
+        assert_eq!(r.tax_cents.unwrap().value, 120);
+        let total = r.total_cents.unwrap();
+        assert_eq!(total.value, 1619);
+        assert_eq!(total.confidence, CONSISTENT_CONFIDENCE);
+
+        let items: Vec<(&str, Option<i64>, Option<f32>)> = r
+            .line_items
+            .iter()
+            .map(|i| (i.description.as_str(), i.amount_cents, i.quantity))
+            .collect();
+        assert_eq!(
+            items,
+            [
+                ("Hinge", Some(850), Some(2.0)),
+                ("Wood glue", Some(649), None)
+            ]
+        );
+    }
+
+    #[test]
+    fn unlabeled_total_taken_from_amounts_column() {
+        use crate::layout::tests::word;
+        let words = vec![
+            word("CORNER", 20, 0, 40),
+            word("CAFE", 110, 0, 40),
+            word("Call", 20, 60, 20),
+            word("555-0100", 70, 60, 20),
+            word("Latte", 20, 120, 20),
+            word("$4.75", 300, 120, 20),
+            word("Muffin", 20, 160, 20),
+            word("$3.25", 300, 160, 20),
+            word("$8.00", 300, 220, 20),
+            word("Ref", 20, 260, 20),
+            word("$99.00", 80, 260, 20),
+        ];
+        let page = OcrPage {
+            text: String::new(),
+            words,
+        };
+        let r = Extractor::extract_page(&page, &ExtractOptions::default());
+        // $99.00 is larger but sits outside the column.
+        assert_eq!(r.total_cents.unwrap().value, 800);
+        let items: Vec<&str> = r
+            .line_items
+            .iter()
+            .map(|i| i.description.as_str())
+            .collect();
+        assert_eq!(items, ["Latte", "Muffin"]);
+    }
+
     // ── amount parsing ────────────────────────────────────────────────────────
 
     #[test]
@@ -452,4 +1405,20 @@ mod tests {
         assert_eq!(parse_amount_str("0.01"), Some(1));
         assert_eq!(parse_amount_str("1,234.56"), Some(123456));
     }
+
+    #[test]
+    fn parse_amount_str_decimal_comma() {
+        assert_eq!(parse_amount_str("1.234,56"), Some(123456));
+        assert_eq!(parse_amount_str("23,45"), Some(2345));
+        assert_eq!(parse_amount_str("1,234"), Some(123400));
+    }
+
+    #[test]
+    fn parse_amount_str_signed() {
+        assert_eq!(parse_amount_str("-$5.00"), Some(-500));
+        assert_eq!(parse_amount_str("$-5.00"), Some(-500));
+        assert_eq!(parse_amount_str("5.00-"), Some(-500));
+        assert_eq!(parse_amount_str("($1,005.25)"), Some(-100525));
+        assert_eq!(parse_amount_str("$ 12.00"), Some(1200));
+    }
 }
@@ -37,6 +37,38 @@ pub fn attachment_path(attachments_dir: &Path, hash_hex: &str, ext: &str) -> std
         .join(format!("{hash_hex}.{ext}"))
 }
 
+/// The same location relative to the store, with `/` separators whatever
+/// the platform: how the ledger records it, so the store can move.
+pub fn relative_path(hash_hex: &str, ext: &str) -> String {
+    format!("{}/{hash_hex}.{ext}", &hash_hex[..2])
+}
+
+/// Put `data` in the content-addressed store, unless the same content is
+/// already there. Returns its hex hash and stored path.
+pub fn store_bytes(
+    attachments_dir: &Path,
+    data: &[u8],
+    ext: &str,
+) -> io::Result<(String, std::path::PathBuf)> {
+    let hash_hex = to_hex(&sha256_bytes(data));
+    let dest = attachment_path(attachments_dir, &hash_hex, ext);
+    if !dest.exists() {
+        create_shard_dir(&dest)?;
+        std::fs::write(&dest, data)?;
+    }
+    Ok((hash_hex, dest))
+}
+
+/// Create the folder `dest` goes in, but not the store itself: a store on
+/// a drive that isn't mounted must fail rather than be recreated on the
+/// local disk.
+pub(crate) fn create_shard_dir(dest: &Path) -> io::Result<()> {
+    match dest.parent().map(std::fs::create_dir) {
+        Some(Err(e)) if e.kind() != io::ErrorKind::AlreadyExists => Err(e),
+        _ => Ok(()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -64,12 +96,24 @@ mod tests {
         assert_eq!(to_hex(&hash).len(), 64);
     }
 
+    #[test]
+    fn store_bytes_dedups_by_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let (hash, path) = store_bytes(dir.path(), b"%PDF-1.4 contract", "pdf").unwrap();
+        let (again, same_path) = store_bytes(dir.path(), b"%PDF-1.4 contract", "pdf").unwrap();
+        assert_eq!(hash, again);
+        assert_eq!(path, same_path);
+        assert_eq!(std::fs::read(&path).unwrap(), b"%PDF-1.4 contract");
+        assert!(path.starts_with(dir.path().join(&hash[..2])));
+    }
+
     #[test]
     fn attachment_path_layout() {
         let base = std::path::PathBuf::from("/data/attachments");
         let hash = "abcdef1234567890abcdef1234567890abcdef1234567890abcdef1234567890ab";
         let path = attachment_path(&base, hash, "jpg");
         assert_eq!(path, std::path::PathBuf::from("/data/attachments/ab/abcdef1234567890abcdef1234567890abcdef1234567890abcdef1234567890ab.jpg"));
+        assert_eq!(base.join(relative_path(hash, "jpg")), path);
     }
 
     #[test]
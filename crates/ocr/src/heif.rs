@@ -0,0 +1,147 @@
+//! HEIC/HEIF decoding for iPhone camera captures, which the `image` crate
+//! cannot read.
+//!
+//! With the `heif` feature, libheif decodes in-process. Otherwise the file is
+//! converted with whichever system tool is available: `heif-convert`
+//! (libheif-examples), `sips` (macOS) or ImageMagick.
+
+use image::DynamicImage;
+
+/// ISO-BMFF brands used by HEIF stills. AVIF shares the container but is a
+/// different codec, so it is not included.
+const HEIF_BRANDS: [&[u8; 4]; 8] = [
+    b"heic", b"heix", b"hevc", b"hevx", b"heim", b"heis", b"mif1", b"msf1",
+];
+
+pub fn is_heif(data: &[u8]) -> bool {
+    data.len() >= 12 && &data[4..8] == b"ftyp" && HEIF_BRANDS.iter().any(|b| &data[8..12] == *b)
+}
+
+/// Decode the primary image of a HEIF file.
+pub fn decode(data: &[u8]) -> Result<DynamicImage, String> {
+    #[cfg(feature = "heif")]
+    {
+        decode_libheif(data)
+    }
+    #[cfg(not(feature = "heif"))]
+    {
+        let png = convert_with_system_tool(data)?;
+        image::load_from_memory(&png).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(feature = "heif")]
+fn decode_libheif(data: &[u8]) -> Result<DynamicImage, String> {
+    use image::RgbImage;
+    use libheif_rs::{ColorSpace, HeifContext, LibHeif, RgbChroma};
+
+    let lib_heif = LibHeif::new();
+    let ctx = HeifContext::read_from_bytes(data).map_err(|e| e.to_string())?;
+    let handle = ctx.primary_image_handle().map_err(|e| e.to_string())?;
+    let decoded = lib_heif
+        .decode(&handle, ColorSpace::Rgb(RgbChroma::Rgb), None)
+        .map_err(|e| e.to_string())?;
+    let plane = decoded
+        .planes()
+        .interleaved
+        .ok_or_else(|| "decoded HEIF has no interleaved plane".to_string())?;
+
+    // Rows may be padded past width * 3 bytes.
+    let row_len = plane.width as usize * 3;
+    let mut pixels = Vec::with_capacity(row_len * plane.height as usize);
+    for row in plane.data.chunks(plane.stride).take(plane.height as usize) {
+        pixels.extend_from_slice(&row[..row_len]);
+    }
+    RgbImage::from_raw(plane.width, plane.height, pixels)
+        .map(DynamicImage::ImageRgb8)
+        .ok_or_else(|| "HEIF pixel buffer has unexpected size".to_string())
+}
+
+#[cfg(not(feature = "heif"))]
+fn convert_with_system_tool(data: &[u8]) -> Result<Vec<u8>, String> {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let dir = std::env::temp_dir().join(format!(
+        "aequi-heif-{}-{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let result = run_converters(&dir, data);
+    let _ = std::fs::remove_dir_all(&dir);
+    result
+}
+
+#[cfg(not(feature = "heif"))]
+fn run_converters(dir: &std::path::Path, data: &[u8]) -> Result<Vec<u8>, String> {
+    use std::ffi::OsStr;
+    use std::process::{Command, Stdio};
+
+    let input = dir.join("in.heic");
+    let output = dir.join("out.png");
+    std::fs::write(&input, data).map_err(|e| e.to_string())?;
+
+    let (i, o) = (input.as_os_str(), output.as_os_str());
+    let tools: [(&str, Vec<&OsStr>); 3] = [
+        ("heif-convert", vec![i, o]),
+        (
+            "sips",
+            vec![
+                OsStr::new("-s"),
+                OsStr::new("format"),
+                OsStr::new("png"),
+                i,
+                OsStr::new("--out"),
+                o,
+            ],
+        ),
+        ("magick", vec![i, o]),
+    ];
+    for (tool, args) in tools {
+        let converted = Command::new(tool)
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|s| s.success());
+        if converted {
+            if let Ok(png) = std::fs::read(&output) {
+                return Ok(png);
+            }
+        }
+    }
+    Err(
+        "no HEIC converter available — install libheif (heif-convert) or ImageMagick, \
+         or build with `heif` feature"
+            .to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ftyp(brand: &[u8; 4]) -> Vec<u8> {
+        let mut data = vec![0, 0, 0, 24];
+        data.extend_from_slice(b"ftyp");
+        data.extend_from_slice(brand);
+        data.extend_from_slice(&[0; 12]);
+        data
+    }
+
+    #[test]
+    fn detects_heif_brands() {
+        assert!(is_heif(&ftyp(b"heic")));
+        assert!(is_heif(&ftyp(b"mif1")));
+        assert!(!is_heif(&ftyp(b"avif")));
+        assert!(!is_heif(&ftyp(b"isom")));
+        assert!(!is_heif(b"\x89PNG\r\n\x1a\n"));
+        assert!(!is_heif(b""));
+    }
+
+    #[test]
+    fn corrupt_heif_is_an_error() {
+        assert!(decode(&ftyp(b"heic")).is_err());
+    }
+}
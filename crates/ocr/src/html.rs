@@ -0,0 +1,125 @@
+//! HTML receipts.
+//!
+//! Online shops and ride-hailing apps usually email the receipt itself as
+//! an HTML body rather than attaching a file. Those are read as text, one
+//! line per block or table row, so the regular extractor can find the
+//! totals without any OCR.
+
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+fn hidden() -> &'static Regex {
+    static R: OnceLock<Regex> = OnceLock::new();
+    R.get_or_init(|| {
+        Regex::new(
+            r"(?is)<!--.*?-->|<(?:script|style|head|title)\b.*?</(?:script|style|head|title)\s*>",
+        )
+        .expect("invalid regex")
+    })
+}
+
+fn line_break() -> &'static Regex {
+    static R: OnceLock<Regex> = OnceLock::new();
+    R.get_or_init(|| {
+        Regex::new(r"(?i)<\s*/?\s*(?:br|p|div|tr|li|h[1-6]|table|tbody|thead|ul|ol|section|header|footer)\b[^>]*>")
+            .expect("invalid regex")
+    })
+}
+
+fn tag() -> &'static Regex {
+    static R: OnceLock<Regex> = OnceLock::new();
+    R.get_or_init(|| Regex::new(r"<[^>]*>").expect("invalid regex"))
+}
+
+fn entity() -> &'static Regex {
+    static R: OnceLock<Regex> = OnceLock::new();
+    R.get_or_init(|| Regex::new(r"&(#[0-9]+|#[xX][0-9a-fA-F]+|[a-zA-Z]+);").expect("invalid regex"))
+}
+
+/// Whether a stored receipt of this extension is read as text rather than
+/// passed to the OCR backend.
+pub fn is_text_document(ext: &str) -> bool {
+    matches!(ext.to_ascii_lowercase().as_str(), "html" | "htm" | "txt")
+}
+
+/// Plain text of an HTML document. Table cells on a row are joined with
+/// spaces so a label and its amount stay on the same line.
+pub fn html_to_text(html: &str) -> String {
+    let text = hidden().replace_all(html, "");
+    let text = line_break().replace_all(&text, "\n");
+    let text = tag().replace_all(&text, " ");
+    let text = entity().replace_all(&text, |caps: &regex::Captures| decode_entity(&caps[1]));
+
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn decode_entity(name: &str) -> String {
+    let code = if let Some(hex) = name.strip_prefix("#x").or(name.strip_prefix("#X")) {
+        u32::from_str_radix(hex, 16).ok()
+    } else if let Some(dec) = name.strip_prefix('#') {
+        dec.parse().ok()
+    } else {
+        None
+    };
+    if let Some(c) = code.and_then(char::from_u32) {
+        return c.to_string();
+    }
+    match name {
+        "amp" => "&",
+        "lt" => "<",
+        "gt" => ">",
+        "quot" => "\"",
+        "apos" => "'",
+        "nbsp" => " ",
+        "euro" => "€",
+        "pound" => "£",
+        "yen" => "¥",
+        "cent" => "¢",
+        _ => return format!("&{name};"),
+    }
+    .to_string()
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_rows_become_lines() {
+        let html = r#"<html><head><title>Your receipt</title>
+            <style>td { color: red; }</style></head>
+            <body><h1>Blue Bottle Coffee</h1>
+            <p>March 5, 2026</p>
+            <table>
+              <tr><td>Latte</td><td>$5.25</td></tr>
+              <tr><td><b>Total</b></td><td>$5.25</td></tr>
+            </table>
+            <!-- tracking pixel --><img src="x.gif"></body></html>"#;
+        assert_eq!(
+            html_to_text(html),
+            "Blue Bottle Coffee\nMarch 5, 2026\nLatte $5.25\nTotal $5.25"
+        );
+    }
+
+    #[test]
+    fn entities_decoded() {
+        assert_eq!(
+            html_to_text("Caf&eacute; &amp; Bar&nbsp;&#8364;4,50 &#x24;1 &euro;"),
+            "Caf&eacute; & Bar €4,50 $1 €"
+        );
+    }
+
+    #[test]
+    fn text_document_extensions() {
+        assert!(is_text_document("HTML"));
+        assert!(is_text_document("txt"));
+        assert!(!is_text_document("pdf"));
+    }
+}
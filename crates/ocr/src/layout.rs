@@ -0,0 +1,198 @@
+//! Where OCR'd words sit on the page.
+//!
+//! Tesseract reports text in the order it segmented the page, so on a wide
+//! receipt or invoice an item's description and its amount can come out as
+//! separate lines far apart in the text. Grouping words by their vertical
+//! position puts each printed row back together, left to right, and gives
+//! extraction the geometry to tell a heading from the body and the amounts
+//! column from the rest.
+
+use crate::recognizer::OcrWord;
+
+/// A printed row: words whose vertical centres line up, left to right.
+#[derive(Debug, Clone)]
+pub struct Row<'a> {
+    pub words: Vec<&'a OcrWord>,
+}
+
+impl Row<'_> {
+    pub fn text(&self) -> String {
+        self.words
+            .iter()
+            .map(|w| w.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    pub fn top(&self) -> u32 {
+        self.words.iter().map(|w| w.bbox.top).min().unwrap_or(0)
+    }
+
+    /// Median word height, a stand-in for the size of the print.
+    pub fn text_height(&self) -> u32 {
+        median(self.words.iter().map(|w| w.bbox.height))
+    }
+}
+
+/// The rows of a page, top to bottom.
+#[derive(Debug, Clone)]
+pub struct Layout<'a> {
+    pub rows: Vec<Row<'a>>,
+}
+
+impl<'a> Layout<'a> {
+    /// Group `words` into rows. A word joins a row when its vertical centre
+    /// is within half the row's first word's height of that word's centre.
+    pub fn new(words: &'a [OcrWord]) -> Self {
+        let centre = |w: &OcrWord| w.bbox.top + w.bbox.height / 2;
+        let mut sorted: Vec<&OcrWord> = words.iter().collect();
+        sorted.sort_by_key(|w| (centre(w), w.bbox.left));
+
+        let mut rows: Vec<Row> = Vec::new();
+        for word in sorted {
+            match rows.last_mut() {
+                Some(row)
+                    if centre(word).abs_diff(centre(row.words[0]))
+                        <= row.words[0].bbox.height.max(1) / 2 =>
+                {
+                    row.words.push(word)
+                }
+                _ => rows.push(Row { words: vec![word] }),
+            }
+        }
+        for row in &mut rows {
+            row.words.sort_by_key(|w| w.bbox.left);
+        }
+        Self { rows }
+    }
+
+    /// The rows as text, one per line.
+    pub fn text(&self) -> String {
+        self.rows
+            .iter()
+            .map(Row::text)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Rows starting in the top `fraction` of the printed area.
+    pub fn top_rows(&self, fraction: f32) -> impl Iterator<Item = (usize, &Row<'a>)> {
+        let cutoff = self.cutoff(fraction);
+        self.rows
+            .iter()
+            .enumerate()
+            .take_while(move |(_, row)| row.top() <= cutoff)
+    }
+
+    /// Whether `row` starts below the top `fraction` of the printed area.
+    pub fn is_below(&self, row: &Row, fraction: f32) -> bool {
+        row.top() > self.cutoff(fraction)
+    }
+
+    /// Median text height across all rows.
+    pub fn text_height(&self) -> u32 {
+        median(self.rows.iter().map(Row::text_height))
+    }
+
+    /// A test for membership of the right-most column formed by the words
+    /// `in_column` accepts: right edges within one and a half text heights
+    /// of the furthest right among them. `None` when no word is accepted.
+    pub fn right_column(
+        &self,
+        in_column: impl Fn(&OcrWord) -> bool,
+    ) -> Option<impl Fn(&OcrWord) -> bool> {
+        let words: Vec<&OcrWord> = self
+            .rows
+            .iter()
+            .flat_map(|row| row.words.iter().copied())
+            .filter(|w| in_column(w))
+            .collect();
+        let edge = words.iter().map(|w| right(w)).max()?;
+        let slack = median(words.iter().map(|w| w.bbox.height)) * 3 / 2;
+        Some(move |w: &OcrWord| in_column(w) && right(w) + slack >= edge)
+    }
+
+    fn cutoff(&self, fraction: f32) -> u32 {
+        let top = self.rows.first().map_or(0, Row::top);
+        let bottom = self
+            .rows
+            .iter()
+            .flat_map(|row| &row.words)
+            .map(|w| w.bbox.top + w.bbox.height)
+            .max()
+            .unwrap_or(0);
+        top + (bottom.saturating_sub(top) as f32 * fraction) as u32
+    }
+}
+
+fn right(word: &OcrWord) -> u32 {
+    word.bbox.left + word.bbox.width
+}
+
+fn median(values: impl Iterator<Item = u32>) -> u32 {
+    let mut values: Vec<u32> = values.collect();
+    values.sort_unstable();
+    values.get(values.len() / 2).copied().unwrap_or(0)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::recognizer::BoundingBox;
+
+    /// A word `height` pixels tall whose box starts at (`left`, `top`),
+    /// ten pixels wide per character.
+    pub(crate) fn word(text: &str, left: u32, top: u32, height: u32) -> OcrWord {
+        OcrWord {
+            text: text.to_string(),
+            confidence: 0.95,
+            bbox: BoundingBox {
+                left,
+                top,
+                width: 10 * text.chars().count() as u32,
+                height,
+            },
+            line: 0,
+        }
+    }
+
+    #[test]
+    fn words_at_the_same_height_form_a_row() {
+        // Tesseract read the amounts as a block after the labels.
+        let words = [
+            word("Coffee", 20, 100, 20),
+            word("Total", 20, 140, 20),
+            word("4.50", 600, 102, 20),
+            word("4.50", 600, 141, 20),
+        ];
+        let layout = Layout::new(&words);
+        assert_eq!(layout.text(), "Coffee 4.50\nTotal 4.50");
+        assert_eq!(layout.rows[1].top(), 140);
+    }
+
+    #[test]
+    fn top_rows_and_right_column() {
+        let words = [
+            word("ACME", 20, 0, 40),
+            word("Tea", 20, 100, 20),
+            word("3.00", 200, 100, 20),
+            word("2", 20, 300, 20),
+            word("@", 40, 300, 20),
+            word("1.50", 60, 300, 20),
+            word("3.00", 200, 300, 20),
+        ];
+        let layout = Layout::new(&words);
+        let top: Vec<String> = layout.top_rows(0.25).map(|(_, r)| r.text()).collect();
+        assert_eq!(top, ["ACME"]);
+        assert!(layout.is_below(&layout.rows[2], 0.5));
+
+        let is_number = |w: &OcrWord| w.text.contains('.');
+        let column = layout.right_column(is_number).unwrap();
+        let in_column: Vec<u32> = words
+            .iter()
+            .filter(|w| column(w))
+            .map(|w| w.bbox.left)
+            .collect();
+        assert_eq!(in_column, [200, 200]);
+    }
+}
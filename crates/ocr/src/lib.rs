@@ -1,13 +1,40 @@
+pub mod backend;
+pub mod binarize;
+pub mod evaluate;
 pub mod extract;
 pub mod hash;
+pub mod heif;
+pub mod html;
+pub mod layout;
+pub mod llm_extract;
+pub mod locale;
+pub mod pdf;
 pub mod pipeline;
 pub mod preprocess;
+pub mod qr;
+pub mod quality;
 pub mod recognizer;
+pub mod sniff;
+pub mod split;
+pub mod tessdata;
 pub mod types;
+pub mod vendors;
 
+pub use backend::{build_recognizer, OcrBackendKind, OcrConfig, OcrHealth};
+pub use evaluate::{CorrectionCase, FieldScore};
 pub use extract::Extractor;
-pub use hash::{sha256_bytes, sha256_file, to_hex};
-pub use pipeline::{OcrResult, PipelineError, ReceiptPipeline};
-pub use preprocess::{prepare_for_ocr, PreprocessError};
-pub use recognizer::{MockRecognizer, OcrBackend, OcrError};
+pub use hash::{sha256_bytes, sha256_file, store_bytes as store_attachment, to_hex};
+pub use llm_extract::{LlmExtractError, LlmExtractor, LlmExtractorConfig};
+pub use locale::{DateOrder, ExtractOptions};
+pub use pipeline::{
+    DedupCheck, OcrResult, PipelineError, ProcessOutcome, ReceiptPipeline, SourcePhoto,
+};
+pub use preprocess::{
+    prepare_for_ocr, preview_jpeg, recompress_jpeg, ImageLimits, PreprocessError, THUMBNAIL_SIZE,
+};
+pub use qr::{FiscalFormat, FiscalReceipt};
+pub use quality::{ImageQuality, QualityIssue};
+pub use recognizer::{BoundingBox, MockRecognizer, OcrBackend, OcrError, OcrPage, OcrWord};
+pub use sniff::{canonical_extension, content_type, sniff_extension};
 pub use types::{ExtractedField, ExtractedReceipt, LineItem, PaymentMethod, ReceiptStatus};
+pub use vendors::{VendorDictionary, VendorProfile};
@@ -0,0 +1,415 @@
+//! Structured receipt extraction through an LLM.
+//!
+//! The OCR text (and optionally the preprocessed image) is sent to an
+//! OpenAI-compatible chat completions endpoint — OpenAI, Ollama, LM Studio,
+//! vLLM and most gateways speak it — with a JSON schema the reply must match.
+//! Whatever the model leaves out, or any failure to reach it, falls back to
+//! the regex [`Extractor`].
+
+use std::time::Duration;
+
+use chrono::NaiveDate;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use thiserror::Error;
+
+use crate::extract::{aggregate_confidence, check_arithmetic, parse_payment_method, Extractor};
+use crate::locale::{DateOrder, ExtractOptions};
+use crate::types::{ExtractedField, ExtractedReceipt, LineItem};
+
+/// Confidence given to a field the model returned. Fields the regex extractor
+/// independently agrees with are raised to [`AGREED_CONFIDENCE`].
+const LLM_FIELD_CONFIDENCE: f32 = 0.85;
+const AGREED_CONFIDENCE: f32 = 0.97;
+
+const SYSTEM_PROMPT: &str = "You extract data from store receipts and invoices. \
+The OCR text may contain recognition errors; correct obvious ones. \
+Reply with JSON only. Use null for anything not present on the receipt. \
+Amounts are decimal numbers in the receipt's currency, without symbols. \
+Dates are YYYY-MM-DD. \
+If a tip or gratuity was added, total is the final amount charged including it. \
+The reference is the invoice, receipt or order number exactly as printed.";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmExtractorConfig {
+    /// Base URL of the API, e.g. `https://api.openai.com/v1` or
+    /// `http://localhost:11434/v1`.
+    pub endpoint: String,
+    pub api_key: Option<String>,
+    pub model: String,
+    /// Attach the receipt image for vision-capable models.
+    #[serde(default)]
+    pub send_image: bool,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_timeout_secs() -> u64 {
+    60
+}
+
+#[derive(Debug, Error)]
+pub enum LlmExtractError {
+    #[error("LLM request failed: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("LLM endpoint returned {status}: {body}")]
+    Status { status: u16, body: String },
+    #[error("LLM response invalid: {0}")]
+    InvalidResponse(String),
+}
+
+/// The JSON shape the model is asked to produce.
+#[derive(Debug, Default, Deserialize)]
+struct LlmReceipt {
+    vendor: Option<String>,
+    date: Option<String>,
+    subtotal: Option<Decimal>,
+    tax: Option<Decimal>,
+    total: Option<Decimal>,
+    tip: Option<Decimal>,
+    reference: Option<String>,
+    payment_method: Option<String>,
+    #[serde(default)]
+    line_items: Vec<LlmLineItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LlmLineItem {
+    description: String,
+    amount: Option<Decimal>,
+    quantity: Option<f32>,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<ChatChoice>,
+}
+
+#[derive(Deserialize)]
+struct ChatChoice {
+    message: ChatMessage,
+}
+
+#[derive(Deserialize)]
+struct ChatMessage {
+    content: Option<String>,
+}
+
+pub struct LlmExtractor {
+    config: LlmExtractorConfig,
+    client: reqwest::Client,
+}
+
+impl LlmExtractor {
+    pub fn new(config: LlmExtractorConfig) -> Result<Self, LlmExtractError> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()?;
+        Ok(Self { config, client })
+    }
+
+    /// Ask the model for the receipt fields. `image_png` is only sent when
+    /// `send_image` is enabled.
+    pub async fn extract(
+        &self,
+        ocr_text: &str,
+        image_png: Option<&[u8]>,
+        options: &ExtractOptions,
+    ) -> Result<ExtractedReceipt, LlmExtractError> {
+        let date_hint = match options.date_order {
+            DateOrder::MonthFirst => "",
+            DateOrder::DayFirst => "Numeric dates on this receipt are day-first (DD/MM/YYYY).\n\n",
+        };
+        let mut content = vec![json!({
+            "type": "text",
+            "text": format!("{date_hint}Receipt OCR text:\n\n{ocr_text}"),
+        })];
+        if let (true, Some(png)) = (self.config.send_image, image_png) {
+            content.push(json!({
+                "type": "image_url",
+                "image_url": { "url": format!("data:image/png;base64,{}", base64_encode(png)) },
+            }));
+        }
+        let body = json!({
+            "model": self.config.model,
+            "temperature": 0,
+            "messages": [
+                { "role": "system", "content": SYSTEM_PROMPT },
+                { "role": "user", "content": content },
+            ],
+            "response_format": {
+                "type": "json_schema",
+                "json_schema": { "name": "receipt", "strict": true, "schema": response_schema() },
+            },
+        });
+
+        let mut req = self
+            .client
+            .post(format!(
+                "{}/chat/completions",
+                self.config.endpoint.trim_end_matches('/')
+            ))
+            .json(&body);
+        if let Some(ref key) = self.config.api_key {
+            req = req.bearer_auth(key);
+        }
+
+        let resp = req.send().await?;
+        if !resp.status().is_success() {
+            let status = resp.status().as_u16();
+            let mut body = resp.text().await.unwrap_or_default();
+            body.truncate(500);
+            return Err(LlmExtractError::Status { status, body });
+        }
+        let chat: ChatResponse = resp
+            .json()
+            .await
+            .map_err(|e| LlmExtractError::InvalidResponse(e.to_string()))?;
+        let reply = chat
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|c| c.message.content)
+            .ok_or_else(|| LlmExtractError::InvalidResponse("empty reply".to_string()))?;
+
+        let parsed = parse_reply(&reply)?;
+        Ok(merge(
+            parsed,
+            Extractor::extract_with(ocr_text, options),
+            ocr_text,
+        ))
+    }
+
+    /// [`extract`](Self::extract), falling back to the regex extractor when
+    /// the endpoint is unreachable or replies with something unusable.
+    pub async fn extract_or_fallback(
+        &self,
+        ocr_text: &str,
+        image_png: Option<&[u8]>,
+        options: &ExtractOptions,
+    ) -> (ExtractedReceipt, Option<LlmExtractError>) {
+        match self.extract(ocr_text, image_png, options).await {
+            Ok(receipt) => (receipt, None),
+            Err(e) => (Extractor::extract_with(ocr_text, options), Some(e)),
+        }
+    }
+}
+
+fn response_schema() -> serde_json::Value {
+    let amount = json!({ "type": ["number", "null"] });
+    let text = json!({ "type": ["string", "null"] });
+    json!({
+        "type": "object",
+        "additionalProperties": false,
+        "required": ["vendor", "date", "subtotal", "tax", "total", "tip", "reference", "payment_method", "line_items"],
+        "properties": {
+            "vendor": text,
+            "date": text,
+            "subtotal": amount,
+            "tax": amount,
+            "total": amount,
+            "tip": amount,
+            "reference": text,
+            "payment_method": text,
+            "line_items": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "additionalProperties": false,
+                    "required": ["description", "amount", "quantity"],
+                    "properties": {
+                        "description": { "type": "string" },
+                        "amount": amount,
+                        "quantity": amount,
+                    },
+                },
+            },
+        },
+    })
+}
+
+/// Models that ignore `response_format` tend to wrap the JSON in a Markdown
+/// code fence; accept that too.
+fn parse_reply(reply: &str) -> Result<LlmReceipt, LlmExtractError> {
+    let trimmed = reply.trim();
+    let json = trimmed
+        .strip_prefix("```json")
+        .or_else(|| trimmed.strip_prefix("```"))
+        .and_then(|s| s.strip_suffix("```"))
+        .unwrap_or(trimmed);
+    serde_json::from_str(json).map_err(|e| LlmExtractError::InvalidResponse(e.to_string()))
+}
+
+fn to_cents(amount: Decimal) -> Option<i64> {
+    (amount * Decimal::from(100)).round().to_i64()
+}
+
+/// Take each field from the model when it has one, otherwise from the regex
+/// result. Values both agree on get a confidence boost.
+fn merge(llm: LlmReceipt, regex: ExtractedReceipt, ocr_text: &str) -> ExtractedReceipt {
+    fn pick<T: PartialEq>(
+        llm: Option<T>,
+        regex: Option<ExtractedField<T>>,
+    ) -> Option<ExtractedField<T>> {
+        match (llm, regex) {
+            (Some(v), Some(r)) if r.value == v => {
+                Some(ExtractedField::new(v, r.confidence.max(AGREED_CONFIDENCE)))
+            }
+            (Some(v), _) => Some(ExtractedField::new(v, LLM_FIELD_CONFIDENCE)),
+            (None, r) => r,
+        }
+    }
+
+    let vendor = llm
+        .vendor
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty());
+    let date = llm
+        .date
+        .as_deref()
+        .and_then(|d| NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d").ok());
+    let reference = llm
+        .reference
+        .map(|r| r.trim().to_string())
+        .filter(|r| !r.is_empty());
+    let payment_method = llm
+        .payment_method
+        .as_deref()
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(parse_payment_method);
+    let line_items = llm
+        .line_items
+        .into_iter()
+        .map(|item| LineItem {
+            description: item.description,
+            amount_cents: item.amount.and_then(to_cents),
+            quantity: item.quantity,
+        })
+        .collect();
+
+    let mut receipt = ExtractedReceipt {
+        vendor: pick(vendor, regex.vendor),
+        date: pick(date, regex.date),
+        subtotal_cents: pick(llm.subtotal.and_then(to_cents), regex.subtotal_cents),
+        tax_cents: pick(llm.tax.and_then(to_cents), regex.tax_cents),
+        total_cents: pick(llm.total.and_then(to_cents), regex.total_cents),
+        tip_cents: pick(llm.tip.and_then(to_cents), regex.tip_cents),
+        reference: pick(reference, regex.reference),
+        payment_method: pick(payment_method, regex.payment_method),
+        line_items,
+        confidence: 0.0,
+    };
+    check_arithmetic(&mut receipt, ocr_text);
+    receipt.confidence = aggregate_confidence(&receipt);
+    receipt
+}
+
+fn base64_encode(data: &[u8]) -> String {
+    const CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let triple = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(CHARS[(triple >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PaymentMethod;
+
+    const OCR: &str = "TRADER JOE'S\n2024-03-05\nSUBTOTAL $18.20\nTAX $1.45\nTOTAL $19.65\nVISA";
+
+    #[test]
+    fn reply_parsed_with_or_without_fence() {
+        let plain = r#"{"vendor":"A","total":1.5,"line_items":[]}"#;
+        assert_eq!(parse_reply(plain).unwrap().total, Some(Decimal::new(15, 1)));
+        let fenced = format!("```json\n{plain}\n```");
+        assert_eq!(parse_reply(&fenced).unwrap().vendor.as_deref(), Some("A"));
+        assert!(parse_reply("Sorry, I can't read that").is_err());
+    }
+
+    #[test]
+    fn string_amounts_accepted() {
+        let r = parse_reply(r#"{"total":"19.65"}"#).unwrap();
+        assert_eq!(r.total.and_then(to_cents), Some(1965));
+    }
+
+    #[test]
+    fn merge_prefers_llm_and_boosts_agreement() {
+        let reply = parse_reply(
+            r#"{"vendor":"Trader Joe's","date":"2024-03-05","subtotal":18.2,"tax":1.45,
+                "total":19.65,"payment_method":"Visa",
+                "line_items":[{"description":"Bananas","amount":0.29,"quantity":4}]}"#,
+        )
+        .unwrap();
+        let out = merge(reply, Extractor::extract(OCR), OCR);
+
+        let vendor = out.vendor.as_ref().unwrap();
+        assert_eq!(vendor.value, "Trader Joe's");
+        assert_eq!(vendor.confidence, LLM_FIELD_CONFIDENCE);
+
+        let total = out.total_cents.as_ref().unwrap();
+        assert_eq!(total.value, 1965);
+        assert_eq!(total.confidence, AGREED_CONFIDENCE);
+        assert_eq!(
+            out.payment_method.as_ref().unwrap().value,
+            PaymentMethod::Visa
+        );
+        assert_eq!(out.line_items.len(), 1);
+        assert_eq!(out.line_items[0].amount_cents, Some(29));
+        assert!(!out.needs_review());
+    }
+
+    #[test]
+    fn merge_fills_gaps_from_regex() {
+        let reply = parse_reply(r#"{"vendor":"Trader Joe's","date":"03/05/2024"}"#).unwrap();
+        let regex = Extractor::extract(OCR);
+        let out = merge(reply, regex.clone(), OCR);
+
+        // Unparseable date and missing total come from the regex pass.
+        assert_eq!(out.date, regex.date);
+        assert_eq!(out.total_cents, regex.total_cents);
+    }
+
+    #[tokio::test]
+    async fn unreachable_endpoint_falls_back_to_regex() {
+        let extractor = LlmExtractor::new(LlmExtractorConfig {
+            endpoint: "http://127.0.0.1:9".to_string(),
+            api_key: None,
+            model: "test".to_string(),
+            send_image: false,
+            timeout_secs: 2,
+        })
+        .unwrap();
+        let (receipt, err) = extractor
+            .extract_or_fallback(OCR, None, &ExtractOptions::default())
+            .await;
+        assert!(err.is_some());
+        assert_eq!(receipt.total_cents.unwrap().value, 1965);
+    }
+
+    #[test]
+    fn base64_matches_reference() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+    }
+}
@@ -0,0 +1,105 @@
+//! Receipt locale: how numeric dates are ordered and which Tesseract
+//! language packs to load.
+//!
+//! Locales are BCP 47 tags as the user sets them (`en-US`, `fr-FR`, `de`).
+//! Only the language and region matter here.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DateOrder {
+    /// `03/15/2024` — the US convention.
+    #[default]
+    MonthFirst,
+    /// `15/03/2024` — nearly everywhere else.
+    DayFirst,
+}
+
+/// Settings that change how receipt text is read.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtractOptions {
+    pub date_order: DateOrder,
+}
+
+impl ExtractOptions {
+    pub fn for_locale(locale: &str) -> Self {
+        let (language, region) = split_locale(locale);
+        // Month-first is a US (and Philippine) habit; a bare "en" is assumed
+        // to be US English, which is what the extractor has always done.
+        let month_first = matches!(region.as_deref(), Some("US" | "PH"))
+            || (language == "en" && region.is_none());
+        Self {
+            date_order: if month_first {
+                DateOrder::MonthFirst
+            } else {
+                DateOrder::DayFirst
+            },
+        }
+    }
+}
+
+/// Tesseract language spec for a locale: the local language plus English,
+/// since most receipts mix in English brand names and card-terminal text.
+pub fn tesseract_languages(locale: &str) -> String {
+    let (language, _) = split_locale(locale);
+    let tess = match language.as_str() {
+        "fr" => "fra",
+        "es" => "spa",
+        "de" => "deu",
+        "it" => "ita",
+        "pt" => "por",
+        "nl" => "nld",
+        _ => return "eng".to_string(),
+    };
+    format!("{tess}+eng")
+}
+
+fn split_locale(locale: &str) -> (String, Option<String>) {
+    let mut parts = locale.trim().split(['-', '_']);
+    let language = parts.next().unwrap_or_default().to_ascii_lowercase();
+    let region = parts.find(|p| p.len() == 2).map(|p| p.to_ascii_uppercase());
+    (language, region)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn date_order_by_region() {
+        assert_eq!(
+            ExtractOptions::for_locale("en-US").date_order,
+            DateOrder::MonthFirst
+        );
+        assert_eq!(
+            ExtractOptions::for_locale("en").date_order,
+            DateOrder::MonthFirst
+        );
+        assert_eq!(
+            ExtractOptions::for_locale("en-GB").date_order,
+            DateOrder::DayFirst
+        );
+        assert_eq!(
+            ExtractOptions::for_locale("fr_FR").date_order,
+            DateOrder::DayFirst
+        );
+        assert_eq!(
+            ExtractOptions::for_locale("es-US").date_order,
+            DateOrder::MonthFirst
+        );
+        assert_eq!(
+            ExtractOptions::for_locale("de").date_order,
+            DateOrder::DayFirst
+        );
+    }
+
+    #[test]
+    fn tesseract_languages_include_english() {
+        assert_eq!(tesseract_languages("de-DE"), "deu+eng");
+        assert_eq!(tesseract_languages("fr-CA"), "fra+eng");
+        assert_eq!(tesseract_languages("es"), "spa+eng");
+        assert_eq!(tesseract_languages("en-GB"), "eng");
+        assert_eq!(tesseract_languages(""), "eng");
+    }
+}
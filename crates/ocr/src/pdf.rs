@@ -0,0 +1,183 @@
+//! PDF receipts and invoices.
+//!
+//! Most emailed invoices are generated PDFs with a text layer, which is read
+//! directly and never goes near OCR. Scanned PDFs have no text, so their
+//! pages are rendered to images for the OCR backend; that needs the
+//! `pdfium` feature and the pdfium library at runtime.
+
+use thiserror::Error;
+
+/// Rendering stops after this many pages; receipts and invoices are short
+/// and anything longer is more likely a statement sent to the wrong place.
+pub const MAX_PDF_PAGES: usize = 10;
+
+/// Fewer alphanumeric characters than this and the text layer is treated as
+/// absent (scanners often embed a page number or a blank layer).
+const MIN_TEXT_LAYER_CHARS: usize = 16;
+
+#[derive(Debug, Error)]
+pub enum PdfError {
+    #[error("Failed to read PDF text: {0}")]
+    Text(String),
+    #[error("Failed to render PDF: {0}")]
+    Render(String),
+    #[error("PDF has no text layer and rendering is not available — build with `pdfium` feature")]
+    RenderUnavailable,
+}
+
+pub fn is_pdf(data: &[u8]) -> bool {
+    let start = data
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(data.len());
+    data[start..].starts_with(b"%PDF-")
+}
+
+/// Text of every page, in order.
+pub fn extract_text(data: &[u8]) -> Result<String, PdfError> {
+    // pdf-extract panics on some malformed documents rather than erroring.
+    std::panic::catch_unwind(|| pdf_extract::extract_text_from_mem(data))
+        .map_err(|_| PdfError::Text("parser panicked".to_string()))?
+        .map_err(|e| PdfError::Text(e.to_string()))
+}
+
+pub fn has_text_layer(text: &str) -> bool {
+    text.chars().filter(|c| c.is_alphanumeric()).count() >= MIN_TEXT_LAYER_CHARS
+}
+
+/// Render up to `max_pages` pages to PNG images for OCR.
+#[cfg(feature = "pdfium")]
+pub fn render_pages(data: &[u8], max_pages: usize) -> Result<Vec<Vec<u8>>, PdfError> {
+    use pdfium_render::prelude::*;
+    use std::io::Cursor;
+
+    let render = |e: PdfiumError| PdfError::Render(e.to_string());
+    let bindings = Pdfium::bind_to_system_library()
+        .or_else(|_| Pdfium::bind_to_library(Pdfium::pdfium_platform_library_name_at_path("./")))
+        .map_err(render)?;
+    let pdfium = Pdfium::new(bindings);
+    let document = pdfium
+        .load_pdf_from_byte_slice(data, None)
+        .map_err(render)?;
+
+    // Roughly 200 DPI for a letter-width page, enough for small receipt print.
+    let config = PdfRenderConfig::new()
+        .set_target_width(1700)
+        .set_maximum_height(4400);
+    document
+        .pages()
+        .iter()
+        .take(max_pages)
+        .map(|page| {
+            let image = page.render_with_config(&config).map_err(render)?.as_image();
+            let mut png = Vec::new();
+            image
+                .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+                .map_err(|e| PdfError::Render(e.to_string()))?;
+            Ok(png)
+        })
+        .collect()
+}
+
+#[cfg(not(feature = "pdfium"))]
+pub fn render_pages(_data: &[u8], _max_pages: usize) -> Result<Vec<Vec<u8>>, PdfError> {
+    Err(PdfError::RenderUnavailable)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A minimal single-font PDF with one page per entry in `pages`, each
+    /// drawing its lines of text top to bottom.
+    pub(crate) fn text_pdf(pages: &[&[&str]]) -> Vec<u8> {
+        let mut objects: Vec<String> = Vec::new();
+        let page_count = pages.len();
+        // 1: catalog, 2: pages, 3: font, then (page, content) pairs.
+        let kids: Vec<String> = (0..page_count)
+            .map(|i| format!("{} 0 R", 4 + i * 2))
+            .collect();
+        objects.push("<< /Type /Catalog /Pages 2 0 R >>".to_string());
+        objects.push(format!(
+            "<< /Type /Pages /Kids [{}] /Count {page_count} >>",
+            kids.join(" ")
+        ));
+        objects.push("<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_string());
+        for (i, lines) in pages.iter().enumerate() {
+            let mut stream = String::from("BT /F1 12 Tf 72 720 Td 14 TL\n");
+            for line in lines.iter() {
+                stream.push_str(&format!("({line}) Tj T*\n"));
+            }
+            stream.push_str("ET");
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] \
+                 /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+                5 + i * 2
+            ));
+            objects.push(format!(
+                "<< /Length {} >>\nstream\n{stream}\nendstream",
+                stream.len()
+            ));
+        }
+
+        let mut out = String::from("%PDF-1.4\n");
+        let mut offsets = Vec::new();
+        for (i, obj) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            out.push_str(&format!("{} 0 obj\n{obj}\nendobj\n", i + 1));
+        }
+        let xref = out.len();
+        out.push_str(&format!(
+            "xref\n0 {}\n0000000000 65535 f \n",
+            objects.len() + 1
+        ));
+        for off in offsets {
+            out.push_str(&format!("{off:010} 00000 n \n"));
+        }
+        out.push_str(&format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+            objects.len() + 1
+        ));
+        out.into_bytes()
+    }
+
+    #[test]
+    fn detects_pdf_magic() {
+        assert!(is_pdf(b"%PDF-1.7\n..."));
+        assert!(is_pdf(b"\r\n%PDF-1.4"));
+        assert!(!is_pdf(b"\x89PNG\r\n"));
+        assert!(!is_pdf(b""));
+    }
+
+    #[test]
+    fn text_layer_extracted_across_pages() {
+        let pdf = text_pdf(&[
+            &["ACME SUPPLY CO", "Invoice 1042"],
+            &["Subtotal $100.00", "Total $108.25"],
+        ]);
+        let text = extract_text(&pdf).unwrap();
+        assert!(text.contains("ACME SUPPLY CO"));
+        assert!(text.contains("Total $108.25"));
+        assert!(has_text_layer(&text));
+    }
+
+    #[test]
+    fn near_empty_text_is_not_a_text_layer() {
+        assert!(!has_text_layer("  1  \n\n"));
+        assert!(!has_text_layer(""));
+    }
+
+    #[test]
+    fn garbage_is_an_error_not_a_panic() {
+        assert!(extract_text(b"%PDF-1.4\nnot really a pdf").is_err());
+    }
+
+    #[cfg(not(feature = "pdfium"))]
+    #[test]
+    fn rendering_unavailable_without_feature() {
+        assert!(matches!(
+            render_pages(&text_pdf(&[&["x"]]), 1),
+            Err(PdfError::RenderUnavailable)
+        ));
+    }
+}
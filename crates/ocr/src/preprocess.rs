@@ -1,56 +1,176 @@
-use image::{DynamicImage, GrayImage, ImageBuffer, Luma};
+use crate::qr;
+use crate::quality::{self, ImageQuality};
+use image::{DynamicImage, GrayImage, ImageDecoder};
+use rayon::prelude::*;
 use std::io::Cursor;
 use std::path::Path;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum PreprocessError {
+    #[error("Failed to read image file: {0}")]
+    Io(#[from] std::io::Error),
     #[error("Failed to load image: {0}")]
     Load(#[from] image::ImageError),
+    #[error("Failed to decode HEIC image: {0}")]
+    Heif(String),
     #[error("Failed to encode processed image: {0}")]
     Encode(String),
+    #[error("Failed to render PDF page: {0}")]
+    Pdf(String),
 }
 
+/// Longest side of a receipt thumbnail, in pixels.
+pub const THUMBNAIL_SIZE: u32 = 320;
+
 /// Load an image file, apply normalization, and return PNG bytes ready for OCR.
 pub fn prepare_for_ocr(path: &Path) -> Result<Vec<u8>, PreprocessError> {
-    let img = image::open(path)?;
-    encode_as_png(normalize(img))
+    prepare_for_ocr_from_bytes(&std::fs::read(path)?)
 }
 
-/// Process raw image bytes (JPEG / PNG / WEBP / …) and return normalized PNG bytes.
+/// Process raw image bytes (JPEG / PNG / WEBP / HEIC / …) and return normalized PNG bytes.
 pub fn prepare_for_ocr_from_bytes(data: &[u8]) -> Result<Vec<u8>, PreprocessError> {
-    let img = image::load_from_memory(data)?;
-    encode_as_png(normalize(img))
+    encode_as_png(normalize(load_image(data)?))
 }
 
-/// Grayscale + contrast stretch.
-fn normalize(img: DynamicImage) -> DynamicImage {
-    // Down-scale if the image is very large (Tesseract works best at 300 DPI / ~2000 px).
-    let img = if img.width() > 2800 || img.height() > 2800 {
-        img.resize(2800, 2800, image::imageops::FilterType::Lanczos3)
+/// A normalized image plus the quality score of the photo it came from.
+#[derive(Debug)]
+pub struct PreparedImage {
+    pub png: Vec<u8>,
+    pub quality: ImageQuality,
+    /// Contents of any QR codes in the photo, read before binarization.
+    pub qr_codes: Vec<String>,
+}
+
+/// Like [`prepare_for_ocr_from_bytes`], also scoring the original photo.
+pub fn prepare_with_quality(data: &[u8]) -> Result<PreparedImage, PreprocessError> {
+    let img = load_image(data)?;
+    let quality = quality::assess(&img);
+    let qr_codes = qr::decode(&img);
+    Ok(PreparedImage {
+        png: encode_as_png(normalize(img))?,
+        quality,
+        qr_codes,
+    })
+}
+
+/// A JPEG of the receipt for display, scaled down to fit in `max_side`
+/// pixels if given. A PDF is shown by its first page, which needs the
+/// `pdfium` feature.
+pub fn preview_jpeg(data: &[u8], max_side: Option<u32>) -> Result<Vec<u8>, PreprocessError> {
+    let img = if crate::pdf::is_pdf(data) {
+        let page = crate::pdf::render_pages(data, 1)
+            .map_err(|e| PreprocessError::Pdf(e.to_string()))?
+            .into_iter()
+            .next()
+            .ok_or_else(|| PreprocessError::Pdf("document has no pages".to_string()))?;
+        image::load_from_memory(&page)?
     } else {
-        img
+        load_image(data)?
+    };
+    let img = match max_side {
+        Some(side) if img.width() > side || img.height() > side => img.thumbnail(side, side),
+        _ => img,
     };
+    // JPEG has no alpha channel.
+    let mut buf = Vec::new();
+    DynamicImage::ImageRgb8(img.to_rgb8())
+        .write_to(&mut Cursor::new(&mut buf), image::ImageFormat::Jpeg)
+        .map_err(|e| PreprocessError::Encode(e.to_string()))?;
+    Ok(buf)
+}
+
+/// How far stored photos are scaled down and recompressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageLimits {
+    /// Longest side in pixels.
+    pub max_side: u32,
+    /// JPEG quality, 1–100.
+    pub jpeg_quality: u8,
+}
+
+/// Re-encode a JPEG photo within `limits`. The EXIF orientation is applied
+/// to the pixels, since the re-encoded file carries no EXIF. Returns `None`
+/// when `data` isn't a JPEG or the result wouldn't be smaller.
+pub fn recompress_jpeg(
+    data: &[u8],
+    limits: &ImageLimits,
+) -> Result<Option<Vec<u8>>, PreprocessError> {
+    let reader = image::ImageReader::new(Cursor::new(data)).with_guessed_format()?;
+    if reader.format() != Some(image::ImageFormat::Jpeg) {
+        return Ok(None);
+    }
+    let mut decoder = reader.into_decoder()?;
+    let orientation = decoder.orientation()?;
+    let mut img = DynamicImage::from_decoder(decoder)?;
+    img.apply_orientation(orientation);
+
+    let side = limits.max_side;
+    if img.width() > side || img.height() > side {
+        img = img.resize(side, side, image::imageops::FilterType::Lanczos3);
+    }
+    let mut buf = Vec::new();
+    let encoder =
+        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buf, limits.jpeg_quality.max(1));
+    DynamicImage::ImageRgb8(img.to_rgb8())
+        .write_with_encoder(encoder)
+        .map_err(|e| PreprocessError::Encode(e.to_string()))?;
+    Ok((buf.len() < data.len()).then_some(buf))
+}
+
+/// Sniff the format from the content rather than trusting the extension;
+/// phones happily save HEIC photos as `.jpg`.
+pub(crate) fn load_image(data: &[u8]) -> Result<DynamicImage, PreprocessError> {
+    if crate::heif::is_heif(data) {
+        return crate::heif::decode(data).map_err(PreprocessError::Heif);
+    }
+    Ok(image::load_from_memory(data)?)
+}
 
-    let gray: GrayImage = img.to_luma8();
+/// Grayscale + contrast stretch, then adaptive binarization when the
+/// lighting across the page is uneven (shadows, creases). Public for the
+/// preprocessing benchmark.
+pub fn normalize(img: DynamicImage) -> DynamicImage {
+    // Grayscale first so the resize filters one channel rather than three.
+    let mut gray: GrayImage = img.to_luma8();
+
+    // Down-scale if the image is very large (Tesseract works best at 300 DPI / ~2000 px).
+    if gray.width() > 2800 || gray.height() > 2800 {
+        gray = DynamicImage::ImageLuma8(gray)
+            .resize(2800, 2800, image::imageops::FilterType::Lanczos3)
+            .into_luma8();
+    }
+    let row = (gray.width() as usize).max(1);
 
     // Compute min and max pixel values for contrast stretching.
     let (min_px, max_px) = gray
-        .pixels()
-        .fold((255u8, 0u8), |(mn, mx), p| (mn.min(p[0]), mx.max(p[0])));
+        .par_chunks(row)
+        .map(|pixels| {
+            pixels
+                .iter()
+                .fold((255u8, 0u8), |(mn, mx), &p| (mn.min(p), mx.max(p)))
+        })
+        .reduce(|| (255, 0), |(a, b), (c, d)| (a.min(c), b.max(d)));
 
-    if max_px == min_px {
+    if max_px <= min_px {
         // Uniform image — return grayscale as-is.
         return DynamicImage::ImageLuma8(gray);
     }
 
     let range = (max_px - min_px) as u32;
-    let stretched: GrayImage = ImageBuffer::from_fn(gray.width(), gray.height(), |x, y| {
-        let p = gray.get_pixel(x, y)[0];
-        let v = ((p - min_px) as u32 * 255 / range) as u8;
-        Luma([v])
+    let stretch: [u8; 256] = std::array::from_fn(|p| {
+        ((p as u32).saturating_sub(min_px as u32) * 255 / range).min(255) as u8
+    });
+    let mut stretched = gray;
+    stretched.par_chunks_mut(row).for_each(|pixels| {
+        for p in pixels {
+            *p = stretch[*p as usize];
+        }
     });
 
+    if crate::binarize::needs_adaptive(&stretched) {
+        return DynamicImage::ImageLuma8(crate::binarize::sauvola(&stretched));
+    }
     DynamicImage::ImageLuma8(stretched)
 }
 
@@ -98,6 +218,51 @@ mod tests {
         assert_eq!(max, 255);
     }
 
+    #[test]
+    fn preview_scales_to_fit() {
+        let mut png = Vec::new();
+        gradient_gray(1200, 600)
+            .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        let thumb = preview_jpeg(&png, Some(THUMBNAIL_SIZE)).unwrap();
+        assert!(thumb.starts_with(&[0xFF, 0xD8, 0xFF]));
+        let img = image::load_from_memory(&thumb).unwrap();
+        assert_eq!(
+            (img.width(), img.height()),
+            (THUMBNAIL_SIZE, THUMBNAIL_SIZE / 2)
+        );
+
+        let full = image::load_from_memory(&preview_jpeg(&png, None).unwrap()).unwrap();
+        assert_eq!((full.width(), full.height()), (1200, 600));
+    }
+
+    #[test]
+    fn recompress_scales_large_jpegs_only() {
+        let limits = ImageLimits {
+            max_side: 400,
+            jpeg_quality: 70,
+        };
+        let mut jpeg = Vec::new();
+        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, 100)
+            .encode_image(&gradient_gray(1200, 600))
+            .unwrap();
+        let smaller = recompress_jpeg(&jpeg, &limits).unwrap().unwrap();
+        let img = image::load_from_memory(&smaller).unwrap();
+        assert_eq!((img.width(), img.height()), (400, 200));
+
+        let mut png = Vec::new();
+        gradient_gray(1200, 600)
+            .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        assert!(recompress_jpeg(&png, &limits).unwrap().is_none());
+    }
+
+    #[test]
+    fn preview_rejects_non_images() {
+        assert!(preview_jpeg(b"<html>receipt</html>", Some(THUMBNAIL_SIZE)).is_err());
+    }
+
     #[test]
     fn prepare_from_bytes_produces_png_header() {
         // Create a tiny PNG in memory and round-trip it.
@@ -113,6 +278,36 @@ mod tests {
         assert_eq!(&result[..4], b"\x89PNG");
     }
 
+    #[test]
+    fn shadowed_image_is_binarized() {
+        let img = crate::binarize::tests::shadowed_receipt(320, 240);
+        let gray = normalize(DynamicImage::ImageLuma8(img)).to_luma8();
+        assert!(gray.pixels().all(|p| p[0] == 0 || p[0] == 255));
+    }
+
+    #[test]
+    fn evenly_lit_image_keeps_grayscale() {
+        let img: GrayImage = ImageBuffer::from_fn(320, 240, |x, _| {
+            Luma([if x % 16 < 10 { 40 } else { 200 + (x % 7) as u8 }])
+        });
+        let gray = normalize(DynamicImage::ImageLuma8(img)).to_luma8();
+        assert!(gray.pixels().any(|p| p[0] != 0 && p[0] != 255));
+    }
+
+    #[test]
+    fn prepare_with_quality_flags_small_photo() {
+        let img = solid_gray(4, 4, 100);
+        let mut png_bytes = Vec::new();
+        img.write_to(
+            &mut std::io::Cursor::new(&mut png_bytes),
+            image::ImageFormat::Png,
+        )
+        .unwrap();
+        let prepared = prepare_with_quality(&png_bytes).unwrap();
+        assert_eq!(&prepared.png[..4], b"\x89PNG");
+        assert!(prepared.quality.retake_recommended());
+    }
+
     #[test]
     fn large_image_is_resized() {
         // A 3000×3000 image should be scaled down.
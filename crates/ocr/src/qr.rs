@@ -0,0 +1,422 @@
+//! QR codes printed on receipts.
+//!
+//! Several countries require registers to print a signed QR code carrying
+//! the receipt's date, total and tax breakdown, and Swiss invoices carry a
+//! QR-bill with the creditor and amount. When one of these decodes, its
+//! values are exact and replace whatever the OCR text suggested.
+
+use chrono::NaiveDate;
+use image::DynamicImage;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::extract::aggregate_confidence;
+use crate::types::{ExtractedField, ExtractedReceipt};
+
+/// Photos are scanned at up to this size; QR modules on a receipt survive
+/// the downscale and very large frames make detection slow.
+const MAX_SCAN_SIZE: u32 = 3000;
+
+/// Confidence for values read from a fiscal code.
+const QR_CONFIDENCE: f32 = 0.99;
+
+/// A creditor name is the legal entity, which may differ from the brand on
+/// the receipt header.
+const QR_VENDOR_CONFIDENCE: f32 = 0.90;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FiscalFormat {
+    /// Germany: KassenSichV / TSE receipt code (`V0;...`).
+    GermanTse,
+    /// Austria: Registrierkassensicherheitsverordnung (`_R1-AT...`).
+    AustrianRksv,
+    /// Portugal: ATCUD invoice code (`A:...*B:...`).
+    PortugueseAtcud,
+    /// Russia: FNS cash receipt code (`t=...&s=...&fn=...`).
+    RussianFns,
+    /// Switzerland: QR-bill payment part (`SPC`).
+    SwissQrBill,
+}
+
+/// Fields decoded from a receipt QR code. Anything the format doesn't carry
+/// is `None`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FiscalReceipt {
+    pub format: FiscalFormat,
+    pub vendor: Option<String>,
+    pub date: Option<NaiveDate>,
+    pub total_cents: Option<i64>,
+    pub tax_cents: Option<i64>,
+    pub reference: Option<String>,
+}
+
+impl FiscalReceipt {
+    /// Overwrite the extracted fields with the decoded ones. With both total
+    /// and tax known the subtotal follows exactly.
+    pub fn apply(&self, receipt: &mut ExtractedReceipt) {
+        if let Some(vendor) = &self.vendor {
+            receipt.vendor = Some(ExtractedField::new(vendor.clone(), QR_VENDOR_CONFIDENCE));
+        }
+        if let Some(date) = self.date {
+            receipt.date = exact(date);
+        }
+        if let Some(total) = self.total_cents {
+            // Registers sign the pre-tip amount; a tip written in afterwards
+            // is still on top of it.
+            let tip = receipt.tip_cents.as_ref().map_or(0, |f| f.value);
+            let with_tip = receipt
+                .total_cents
+                .as_ref()
+                .is_some_and(|f| tip != 0 && f.value == total + tip);
+            receipt.total_cents = exact(if with_tip { total + tip } else { total });
+
+            if let Some(tax) = self.tax_cents {
+                receipt.tax_cents = exact(tax);
+                receipt.subtotal_cents = exact(total - tax);
+            }
+        }
+        if let Some(reference) = &self.reference {
+            receipt.reference = exact(reference.clone());
+        }
+        receipt.confidence = aggregate_confidence(receipt);
+    }
+}
+
+fn exact<T>(value: T) -> Option<ExtractedField<T>> {
+    Some(ExtractedField::new(value, QR_CONFIDENCE))
+}
+
+/// Text of every QR code found in the image.
+pub fn decode(img: &DynamicImage) -> Vec<String> {
+    let img = if img.width() > MAX_SCAN_SIZE || img.height() > MAX_SCAN_SIZE {
+        img.resize(
+            MAX_SCAN_SIZE,
+            MAX_SCAN_SIZE,
+            image::imageops::FilterType::Triangle,
+        )
+    } else {
+        img.clone()
+    };
+    let gray = img.to_luma8();
+    let mut prepared = rqrr::PreparedImage::prepare_from_greyscale(
+        gray.width() as usize,
+        gray.height() as usize,
+        |x, y| gray.get_pixel(x as u32, y as u32)[0],
+    );
+    prepared
+        .detect_grids()
+        .into_iter()
+        .filter_map(|grid| grid.decode().ok().map(|(_, content)| content))
+        .collect()
+}
+
+/// The first code in `codes` that is a recognised receipt format.
+pub fn find_fiscal(codes: &[String]) -> Option<FiscalReceipt> {
+    codes.iter().find_map(|code| parse(code))
+}
+
+/// Parse a QR payload as one of the supported receipt formats.
+pub fn parse(payload: &str) -> Option<FiscalReceipt> {
+    let payload = payload.trim();
+    if payload.starts_with("V0;") {
+        parse_german_tse(payload)
+    } else if payload.starts_with("_R1-AT") {
+        parse_austrian_rksv(payload)
+    } else if payload.starts_with("SPC") {
+        parse_swiss_qr_bill(payload)
+    } else if payload.starts_with("A:") && payload.contains("*B:") {
+        parse_portuguese_atcud(payload)
+    } else if payload.contains("fn=") && payload.contains("s=") {
+        parse_russian_fns(payload)
+    } else {
+        None
+    }
+}
+
+/// VAT contained in gross amounts, each paired with its rate in tenths of a
+/// percent.
+fn tax_from_gross(gross: &[(i64, i64)]) -> i64 {
+    gross
+        .iter()
+        .map(|&(amount, rate)| Decimal::from(amount * rate) / Decimal::from(1000 + rate))
+        .map(|tax| tax.round().to_i64().unwrap_or(0))
+        .sum()
+}
+
+fn cents(s: &str) -> Option<i64> {
+    let value = Decimal::from_str(&s.trim().replace(',', ".")).ok()?;
+    (value * Decimal::from(100)).round().to_i64()
+}
+
+/// `V0;<serial>;Kassenbeleg-V1;Beleg^<gross per rate>^<payments>;<txn>;
+/// <counter>;<start>;<end>;...` — gross amounts for 19 %, 7 %, 10.7 %,
+/// 5.5 % and 0 %, separated by `_`.
+fn parse_german_tse(payload: &str) -> Option<FiscalReceipt> {
+    const RATES: [i64; 5] = [190, 70, 107, 55, 0];
+
+    let fields: Vec<&str> = payload.split(';').collect();
+    if fields.get(2).copied() != Some("Kassenbeleg-V1") {
+        return None;
+    }
+    let mut process = fields.get(3)?.split('^');
+    if process.next() != Some("Beleg") {
+        return None;
+    }
+    let gross: Vec<(i64, i64)> = process
+        .next()?
+        .split('_')
+        .zip(RATES)
+        .map(|(amount, rate)| Some((cents(amount)?, rate)))
+        .collect::<Option<_>>()?;
+
+    Some(FiscalReceipt {
+        format: FiscalFormat::GermanTse,
+        vendor: None,
+        date: fields.get(6).and_then(|t| iso_date(t)),
+        total_cents: Some(gross.iter().map(|(amount, _)| amount).sum()),
+        tax_cents: Some(tax_from_gross(&gross)),
+        reference: fields.get(4).map(|s| s.to_string()),
+    })
+}
+
+/// `_R1-AT1_<register>_<receipt no>_<timestamp>_<20 %>_<10 %>_<13 %>_<0 %>_
+/// <19 %>_...` with decimal commas.
+fn parse_austrian_rksv(payload: &str) -> Option<FiscalReceipt> {
+    const RATES: [i64; 5] = [200, 100, 130, 0, 190];
+
+    let fields: Vec<&str> = payload.trim_start_matches('_').split('_').collect();
+    let gross: Vec<(i64, i64)> = fields
+        .get(4..9)?
+        .iter()
+        .zip(RATES)
+        .map(|(amount, rate)| Some((cents(amount)?, rate)))
+        .collect::<Option<_>>()?;
+
+    Some(FiscalReceipt {
+        format: FiscalFormat::AustrianRksv,
+        vendor: None,
+        date: fields.get(3).and_then(|t| iso_date(t)),
+        total_cents: Some(gross.iter().map(|(amount, _)| amount).sum()),
+        tax_cents: Some(tax_from_gross(&gross)),
+        reference: fields.get(2).map(|s| s.to_string()),
+    })
+}
+
+/// `A:<issuer NIF>*B:<buyer NIF>*...*F:<YYYYMMDD>*G:<document id>*...*
+/// N:<total tax>*O:<total>*...`
+fn parse_portuguese_atcud(payload: &str) -> Option<FiscalReceipt> {
+    let field = |key: &str| {
+        payload
+            .split('*')
+            .find_map(|kv| kv.strip_prefix(key)?.strip_prefix(':'))
+    };
+    Some(FiscalReceipt {
+        format: FiscalFormat::PortugueseAtcud,
+        vendor: None,
+        date: field("F").and_then(|d| NaiveDate::parse_from_str(d, "%Y%m%d").ok()),
+        total_cents: Some(cents(field("O")?)?),
+        tax_cents: field("N").and_then(cents),
+        reference: field("G").map(str::to_string),
+    })
+}
+
+/// `t=<YYYYMMDDTHHMM>&s=<total>&fn=<drive>&i=<document>&fp=<sign>&n=<type>`;
+/// type 2 is a refund.
+fn parse_russian_fns(payload: &str) -> Option<FiscalReceipt> {
+    let field = |key: &str| {
+        payload
+            .split('&')
+            .find_map(|kv| kv.strip_prefix(key)?.strip_prefix('='))
+    };
+    let total = cents(field("s")?)?;
+    let sign = if field("n") == Some("2") { -1 } else { 1 };
+    Some(FiscalReceipt {
+        format: FiscalFormat::RussianFns,
+        vendor: None,
+        date: field("t")
+            .and_then(|t| t.get(..8))
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y%m%d").ok()),
+        total_cents: Some(sign * total),
+        tax_cents: None,
+        reference: field("i").map(str::to_string),
+    })
+}
+
+/// Line-based: creditor name on line 6, amount on line 19 and the payment
+/// reference on line 29 (1-based). Bills without an amount leave it blank.
+fn parse_swiss_qr_bill(payload: &str) -> Option<FiscalReceipt> {
+    let lines: Vec<&str> = payload.lines().map(str::trim).collect();
+    if lines.first() != Some(&"SPC") {
+        return None;
+    }
+    let line = |n: usize| lines.get(n).copied().filter(|s| !s.is_empty());
+    Some(FiscalReceipt {
+        format: FiscalFormat::SwissQrBill,
+        vendor: line(5).map(str::to_string),
+        date: None,
+        total_cents: line(18).and_then(cents),
+        tax_cents: None,
+        reference: line(28).map(str::to_string),
+    })
+}
+
+fn iso_date(timestamp: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(timestamp.get(..10)?, "%Y-%m-%d").ok()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::extract::Extractor;
+    use image::{GrayImage, Luma};
+
+    const TSE: &str = "V0;955002-00;Kassenbeleg-V1;Beleg^23.80_10.70_0.00_0.00_0.00^34.50:Bar;\
+                       1204;2480;2024-03-15T12:30:05.000Z;2024-03-15T12:30:09.000Z;\
+                       ecdsa-plain-SHA384;unixTime;MEUCIQ==;BHhW";
+
+    /// A QR code rendered with 8-pixel modules and a quiet zone.
+    pub(crate) fn qr_image(payload: &str) -> DynamicImage {
+        let code = qrcode::QrCode::new(payload.as_bytes()).unwrap();
+        let width = code.width() as u32;
+        let colors = code.to_colors();
+        let scale = 8;
+        let quiet = 4 * scale;
+        let size = width * scale + 2 * quiet;
+        DynamicImage::ImageLuma8(GrayImage::from_fn(size, size, |x, y| {
+            let (mx, my) = (x.wrapping_sub(quiet) / scale, y.wrapping_sub(quiet) / scale);
+            let dark = mx < width
+                && my < width
+                && colors[(my * width + mx) as usize] == qrcode::Color::Dark;
+            Luma([if dark { 0 } else { 255 }])
+        }))
+    }
+
+    #[test]
+    fn decodes_qr_from_image() {
+        assert_eq!(decode(&qr_image(TSE)), [TSE]);
+    }
+
+    #[test]
+    fn image_without_qr_decodes_nothing() {
+        let blank = DynamicImage::ImageLuma8(GrayImage::from_pixel(200, 200, Luma([255])));
+        assert!(decode(&blank).is_empty());
+    }
+
+    #[test]
+    fn german_tse() {
+        let r = parse(TSE).unwrap();
+        assert_eq!(r.format, FiscalFormat::GermanTse);
+        assert_eq!(r.total_cents, Some(3450));
+        // 23.80 × 19/119 + 10.70 × 7/107
+        assert_eq!(r.tax_cents, Some(380 + 70));
+        assert_eq!(r.date, NaiveDate::from_ymd_opt(2024, 3, 15));
+        assert_eq!(r.reference.as_deref(), Some("1204"));
+    }
+
+    #[test]
+    fn austrian_rksv() {
+        let payload = "_R1-AT1_KASSE-01_83469_2024-03-15T19:20:11_12,00_5,50_0,00_0,00_0,00_\
+                       AAAA_5A3F_0F2C8_QUFB";
+        let r = parse(payload).unwrap();
+        assert_eq!(r.format, FiscalFormat::AustrianRksv);
+        assert_eq!(r.total_cents, Some(1750));
+        assert_eq!(r.tax_cents, Some(200 + 50));
+        assert_eq!(r.reference.as_deref(), Some("83469"));
+        assert_eq!(r.date, NaiveDate::from_ymd_opt(2024, 3, 15));
+    }
+
+    #[test]
+    fn portuguese_atcud() {
+        let payload = "A:123456789*B:999999990*C:PT*D:FS*E:N*F:20240315*G:FS A/1234*\
+                       H:JFJ7KN2X-1234*I1:PT*I7:10.16*I8:2.34*N:2.34*O:12.50*Q:abcd*R:9999";
+        let r = parse(payload).unwrap();
+        assert_eq!(r.total_cents, Some(1250));
+        assert_eq!(r.tax_cents, Some(234));
+        assert_eq!(r.reference.as_deref(), Some("FS A/1234"));
+        assert_eq!(r.date, NaiveDate::from_ymd_opt(2024, 3, 15));
+    }
+
+    #[test]
+    fn russian_fns_refund_is_negative() {
+        let r = parse("t=20240315T1230&s=1234.50&fn=9289000100012345&i=4512&fp=2819037689&n=2")
+            .unwrap();
+        assert_eq!(r.total_cents, Some(-123450));
+        assert_eq!(r.reference.as_deref(), Some("4512"));
+        assert_eq!(r.date, NaiveDate::from_ymd_opt(2024, 3, 15));
+    }
+
+    #[test]
+    fn swiss_qr_bill() {
+        let payload = [
+            "SPC",
+            "0200",
+            "1",
+            "CH4431999123000889012",
+            "S",
+            "Muster Krankenkasse",
+            "Musterstrasse",
+            "12",
+            "8000",
+            "Zürich",
+            "CH",
+            "",
+            "",
+            "",
+            "",
+            "",
+            "",
+            "",
+            "211.00",
+            "CHF",
+            "S",
+            "Pia Rutschmann",
+            "Marktgasse",
+            "28",
+            "9400",
+            "Rorschach",
+            "CH",
+            "QRR",
+            "210000000003139471430009017",
+            "Prämie März",
+            "EPD",
+        ]
+        .join("\n");
+        let r = parse(&payload).unwrap();
+        assert_eq!(r.vendor.as_deref(), Some("Muster Krankenkasse"));
+        assert_eq!(r.total_cents, Some(21100));
+        assert_eq!(r.reference.as_deref(), Some("210000000003139471430009017"));
+    }
+
+    #[test]
+    fn other_payloads_are_not_receipts() {
+        assert!(parse("https://example.com/menu").is_none());
+        assert!(parse("WIFI:S:Guest;T:WPA;P:secret;;").is_none());
+        assert!(parse(
+            "V0;serial;SonstigerVorgang;AVBelegabbruch^0.00_0.00_0.00_0.00_0.00^;1;2;x;y"
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn fiscal_code_overrides_misread_text() {
+        let text = "BÄCKEREI SCHMIDT\n15.03.2024\nSUMME 84,50\nMwSt 19% 3,80";
+        let mut receipt = Extractor::extract(text);
+        parse(TSE).unwrap().apply(&mut receipt);
+        assert_eq!(receipt.total_cents.as_ref().unwrap().value, 3450);
+        assert_eq!(receipt.tax_cents.as_ref().unwrap().value, 450);
+        assert_eq!(receipt.subtotal_cents.as_ref().unwrap().value, 3000);
+        assert_eq!(receipt.reference.as_ref().unwrap().value, "1204");
+        assert!(receipt.total_cents.unwrap().confidence >= QR_CONFIDENCE);
+    }
+
+    #[test]
+    fn handwritten_tip_stays_on_top_of_register_total() {
+        let text = "TRATTORIA\nSumme 34,50\nTrinkgeld 4,00\nGesamt 38,50";
+        let mut receipt = Extractor::extract(text);
+        parse(TSE).unwrap().apply(&mut receipt);
+        assert_eq!(receipt.total_cents.unwrap().value, 3850);
+    }
+}
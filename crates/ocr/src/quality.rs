@@ -0,0 +1,248 @@
+//! Photo quality scoring, so a bad capture can be retaken on the spot rather
+//! than producing an extraction nobody can trust.
+//!
+//! Three independent checks, each scored 0.0–1.0:
+//! - sharpness: variance of the Laplacian, which collapses when edges blur
+//! - exposure: mean brightness and how much of the frame is clipped
+//! - resolution: pixels along the short side
+
+use image::{DynamicImage, GrayImage};
+use serde::{Deserialize, Serialize};
+
+/// Sharpness is measured at this size so the score doesn't depend on the
+/// camera's megapixels.
+const ANALYSIS_SIZE: u32 = 1200;
+
+/// Laplacian variance at which a receipt photo counts as fully sharp.
+const SHARP_LAPLACIAN_VARIANCE: f32 = 400.0;
+
+/// Short side (px) at which receipt print is comfortably legible.
+const GOOD_SHORT_SIDE: u32 = 1000;
+
+/// Component scores below this raise the matching issue.
+const ISSUE_THRESHOLD: f32 = 0.35;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QualityIssue {
+    Blurry,
+    TooDark,
+    Overexposed,
+    LowResolution,
+}
+
+impl QualityIssue {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            QualityIssue::Blurry => "blurry",
+            QualityIssue::TooDark => "too_dark",
+            QualityIssue::Overexposed => "overexposed",
+            QualityIssue::LowResolution => "low_resolution",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "blurry" => Some(QualityIssue::Blurry),
+            "too_dark" => Some(QualityIssue::TooDark),
+            "overexposed" => Some(QualityIssue::Overexposed),
+            "low_resolution" => Some(QualityIssue::LowResolution),
+            _ => None,
+        }
+    }
+
+    /// Short prompt for the capture screen.
+    pub fn message(self) -> &'static str {
+        match self {
+            QualityIssue::Blurry => "Photo too blurry — hold steady and retake",
+            QualityIssue::TooDark => "Photo too dark — add light and retake",
+            QualityIssue::Overexposed => "Photo washed out — avoid glare and retake",
+            QualityIssue::LowResolution => "Photo too small — move closer and retake",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImageQuality {
+    pub sharpness: f32,
+    pub exposure: f32,
+    pub resolution: f32,
+    /// Overall score: the weakest of the three, since any one of them is
+    /// enough to ruin OCR.
+    pub score: f32,
+    pub issues: Vec<QualityIssue>,
+}
+
+impl ImageQuality {
+    pub fn retake_recommended(&self) -> bool {
+        !self.issues.is_empty()
+    }
+}
+
+/// Score a decoded photo. Call before any contrast stretching, which would
+/// hide exposure problems.
+pub fn assess(img: &DynamicImage) -> ImageQuality {
+    let resolution = (img.width().min(img.height()) as f32 / GOOD_SHORT_SIDE as f32).min(1.0);
+
+    let analysis = if img.width() > ANALYSIS_SIZE || img.height() > ANALYSIS_SIZE {
+        img.resize(
+            ANALYSIS_SIZE,
+            ANALYSIS_SIZE,
+            image::imageops::FilterType::Triangle,
+        )
+    } else {
+        img.clone()
+    };
+    let gray = analysis.to_luma8();
+
+    let sharpness = (laplacian_variance(&gray) / SHARP_LAPLACIAN_VARIANCE).min(1.0);
+    let (exposure, exposure_issue) = exposure_score(&gray);
+
+    let mut issues = Vec::new();
+    if sharpness < ISSUE_THRESHOLD {
+        issues.push(QualityIssue::Blurry);
+    }
+    if exposure < ISSUE_THRESHOLD {
+        issues.push(exposure_issue);
+    }
+    if resolution < ISSUE_THRESHOLD {
+        issues.push(QualityIssue::LowResolution);
+    }
+
+    ImageQuality {
+        sharpness,
+        exposure,
+        resolution,
+        score: sharpness.min(exposure).min(resolution),
+        issues,
+    }
+}
+
+/// Variance of the 4-neighbour Laplacian over the interior pixels.
+fn laplacian_variance(gray: &GrayImage) -> f32 {
+    let (width, height) = gray.dimensions();
+    if width < 3 || height < 3 {
+        return 0.0;
+    }
+
+    let px = |x: u32, y: u32| gray.get_pixel(x, y)[0] as f64;
+    let mut sum = 0.0;
+    let mut sum_sq = 0.0;
+    let mut n = 0.0;
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            let lap = px(x - 1, y) + px(x + 1, y) + px(x, y - 1) + px(x, y + 1) - 4.0 * px(x, y);
+            sum += lap;
+            sum_sq += lap * lap;
+            n += 1.0;
+        }
+    }
+    let mean = sum / n;
+    (sum_sq / n - mean * mean) as f32
+}
+
+/// Penalises a dark or bright mean and large clipped areas; returns which
+/// way the exposure is off.
+fn exposure_score(gray: &GrayImage) -> (f32, QualityIssue) {
+    let total = (gray.width() * gray.height()).max(1) as f32;
+    let mut sum = 0u64;
+    let mut dark = 0u32;
+    let mut bright = 0u32;
+    for p in gray.pixels() {
+        let v = p[0];
+        sum += v as u64;
+        if v <= 10 {
+            dark += 1;
+        } else if v >= 250 {
+            bright += 1;
+        }
+    }
+    let mean = sum as f32 / total;
+
+    // Receipts are mostly white paper, so a well-exposed photo sits bright.
+    let mean_score = if mean < 90.0 {
+        mean / 90.0
+    } else if mean > 235.0 {
+        (255.0 - mean) / 20.0
+    } else {
+        1.0
+    };
+    let dark_frac = dark as f32 / total;
+    let bright_frac = bright as f32 / total;
+    let clip_score = 1.0 - ((dark_frac.max(bright_frac) - 0.25).max(0.0) / 0.5).min(1.0);
+
+    let issue = if mean < 128.0 {
+        QualityIssue::TooDark
+    } else {
+        QualityIssue::Overexposed
+    };
+    (mean_score.min(clip_score).clamp(0.0, 1.0), issue)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Luma;
+
+    /// Crisp black strokes on white paper.
+    fn sharp_receipt(width: u32, height: u32) -> GrayImage {
+        GrayImage::from_fn(width, height, |x, y| {
+            Luma([if x % 16 < 8 && y % 20 < 4 { 20 } else { 225 }])
+        })
+    }
+
+    #[test]
+    fn sharp_well_lit_photo_has_no_issues() {
+        let q = assess(&DynamicImage::ImageLuma8(sharp_receipt(1200, 1600)));
+        assert!(q.issues.is_empty(), "{q:?}");
+        assert!(!q.retake_recommended());
+        assert!(q.score > 0.9);
+    }
+
+    #[test]
+    fn blurred_photo_is_flagged() {
+        let img = DynamicImage::ImageLuma8(sharp_receipt(1200, 1600)).blur(6.0);
+        let q = assess(&img);
+        assert!(q.issues.contains(&QualityIssue::Blurry), "{q:?}");
+        assert!(q.retake_recommended());
+    }
+
+    #[test]
+    fn dark_photo_is_flagged() {
+        let img = GrayImage::from_fn(1200, 1600, |x, y| {
+            Luma([if x % 16 < 8 && y % 20 < 4 { 2 } else { 30 }])
+        });
+        let q = assess(&DynamicImage::ImageLuma8(img));
+        assert!(q.issues.contains(&QualityIssue::TooDark), "{q:?}");
+        assert!(!q.issues.contains(&QualityIssue::Overexposed));
+    }
+
+    #[test]
+    fn washed_out_photo_is_flagged() {
+        let img = GrayImage::from_fn(1200, 1600, |x, y| {
+            Luma([if x % 16 < 8 && y % 20 < 4 { 200 } else { 255 }])
+        });
+        let q = assess(&DynamicImage::ImageLuma8(img));
+        assert!(q.issues.contains(&QualityIssue::Overexposed), "{q:?}");
+    }
+
+    #[test]
+    fn thumbnail_is_low_resolution() {
+        let q = assess(&DynamicImage::ImageLuma8(sharp_receipt(200, 300)));
+        assert!(q.issues.contains(&QualityIssue::LowResolution), "{q:?}");
+        assert!(q.score <= q.resolution);
+    }
+
+    #[test]
+    fn issue_codes_round_trip() {
+        for issue in [
+            QualityIssue::Blurry,
+            QualityIssue::TooDark,
+            QualityIssue::Overexposed,
+            QualityIssue::LowResolution,
+        ] {
+            assert_eq!(QualityIssue::parse(issue.as_str()), Some(issue));
+        }
+        assert_eq!(QualityIssue::parse("grainy"), None);
+    }
+}
@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -10,10 +11,99 @@ pub enum OcrError {
     NotAvailable,
 }
 
+/// Pixel rectangle of a word in the image it was read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoundingBox {
+    pub left: u32,
+    pub top: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A recognized word and how sure the engine was of it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OcrWord {
+    pub text: String,
+    /// 0.0–1.0.
+    pub confidence: f32,
+    pub bbox: BoundingBox,
+    /// Index of the text line the word is on, counted from the top of the
+    /// page.
+    pub line: usize,
+}
+
+/// Recognized text together with the words it was made from. `words` is
+/// empty for backends that can't report them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OcrPage {
+    pub text: String,
+    pub words: Vec<OcrWord>,
+}
+
 /// Abstraction over an OCR backend.
 /// Implementations accept raw PNG/JPEG image bytes and return the recognized text.
 pub trait OcrBackend: Send + Sync {
     fn recognize(&self, image_bytes: &[u8]) -> Result<String, OcrError>;
+
+    /// The text with per-word confidences and positions. Backends without
+    /// word detail return the text alone.
+    fn recognize_detailed(&self, image_bytes: &[u8]) -> Result<OcrPage, OcrError> {
+        Ok(OcrPage {
+            text: self.recognize(image_bytes)?,
+            words: Vec::new(),
+        })
+    }
+}
+
+/// Words from Tesseract's TSV output (`level page block par line word left
+/// top width height conf text`). Rows that aren't words, and words
+/// Tesseract gave no confidence, are skipped.
+pub fn words_from_tsv(tsv: &str) -> Vec<OcrWord> {
+    let mut words = Vec::new();
+    let mut lines: Vec<(&str, &str, &str, &str)> = Vec::new();
+    for row in tsv.lines() {
+        let cols: Vec<&str> = row.splitn(12, '\t').collect();
+        let [level, page, block, par, line, _word, left, top, width, height, conf, text] = cols[..]
+        else {
+            continue;
+        };
+        let text = text.trim();
+        if level != "5" || text.is_empty() {
+            continue;
+        }
+        let (Ok(conf), Ok(left), Ok(top), Ok(width), Ok(height)) = (
+            conf.parse::<f32>(),
+            left.parse(),
+            top.parse(),
+            width.parse(),
+            height.parse(),
+        ) else {
+            continue;
+        };
+        if conf < 0.0 {
+            continue;
+        }
+        let key = (page, block, par, line);
+        let line = match lines.iter().position(|k| *k == key) {
+            Some(i) => i,
+            None => {
+                lines.push(key);
+                lines.len() - 1
+            }
+        };
+        words.push(OcrWord {
+            text: text.to_string(),
+            confidence: (conf / 100.0).clamp(0.0, 1.0),
+            bbox: BoundingBox {
+                left,
+                top,
+                width,
+                height,
+            },
+            line,
+        });
+    }
+    words
 }
 
 // ── Mock backend (always available, used for tests) ───────────────────────────
@@ -22,11 +112,23 @@ pub trait OcrBackend: Send + Sync {
 /// without requiring Tesseract to be installed.
 pub struct MockRecognizer {
     pub text: String,
+    pub words: Vec<OcrWord>,
 }
 
 impl MockRecognizer {
     pub fn new(text: impl Into<String>) -> Self {
-        Self { text: text.into() }
+        Self {
+            text: text.into(),
+            words: Vec::new(),
+        }
+    }
+
+    /// Also report `words` from [`OcrBackend::recognize_detailed`].
+    pub fn with_words(text: impl Into<String>, words: Vec<OcrWord>) -> Self {
+        Self {
+            text: text.into(),
+            words,
+        }
     }
 }
 
@@ -34,37 +136,141 @@ impl OcrBackend for MockRecognizer {
     fn recognize(&self, _image_bytes: &[u8]) -> Result<String, OcrError> {
         Ok(self.text.clone())
     }
+
+    fn recognize_detailed(&self, _image_bytes: &[u8]) -> Result<OcrPage, OcrError> {
+        Ok(OcrPage {
+            text: self.text.clone(),
+            words: self.words.clone(),
+        })
+    }
 }
 
 // ── Tesseract backend (optional, gated behind `tesseract` feature) ─────────────
 
 #[cfg(feature = "tesseract")]
 pub mod tesseract_backend {
-    use super::{OcrBackend, OcrError};
-    use leptess::LepTess;
+    use super::{OcrBackend, OcrError, OcrPage};
+    use leptess::{LepTess, Variable};
+    use std::cell::RefCell;
 
-    pub struct TesseractRecognizer {
+    /// Settings an engine is initialised with. A warm engine is only reused
+    /// by a recognizer with the same ones.
+    #[derive(Debug, Clone, PartialEq)]
+    struct EngineKey {
         data_path: Option<String>,
         lang: String,
+        page_segmentation: u8,
+        char_whitelist: Option<String>,
+    }
+
+    impl EngineKey {
+        fn init(&self) -> Result<LepTess, OcrError> {
+            let mut lt = LepTess::new(self.data_path.as_deref(), &self.lang)
+                .map_err(|e| OcrError::Engine(e.to_string()))?;
+            lt.set_variable(
+                Variable::TesseditPagesegMode,
+                &self.page_segmentation.to_string(),
+            )
+            .map_err(|e| OcrError::Engine(e.to_string()))?;
+            if let Some(chars) = &self.char_whitelist {
+                lt.set_variable(Variable::TesseditCharWhitelist, chars)
+                    .map_err(|e| OcrError::Engine(e.to_string()))?;
+            }
+            Ok(lt)
+        }
+    }
+
+    thread_local! {
+        /// One warm engine per thread. Loading the language model is most
+        /// of the cost of a recognition, and the blocking threads OCR runs
+        /// on are reused from one receipt to the next.
+        static ENGINE: RefCell<Option<(EngineKey, LepTess)>> = const { RefCell::new(None) };
+    }
+
+    pub struct TesseractRecognizer {
+        key: EngineKey,
     }
 
     impl TesseractRecognizer {
-        pub fn new(data_path: Option<String>, lang: &str) -> Self {
+        /// `page_segmentation` is Tesseract's `--psm`; `char_whitelist`
+        /// limits recognition to those characters.
+        pub fn new(
+            data_path: Option<String>,
+            lang: &str,
+            page_segmentation: u8,
+            char_whitelist: Option<String>,
+        ) -> Self {
             Self {
-                data_path,
-                lang: lang.to_string(),
+                key: EngineKey {
+                    data_path,
+                    lang: lang.to_string(),
+                    page_segmentation,
+                    char_whitelist,
+                },
             }
         }
+
+        /// Initialise the engine once without an image, surfacing a missing
+        /// library or language file before any receipt is processed.
+        pub fn check(&self) -> Result<(), OcrError> {
+            self.key.init().map(|_| ())
+        }
+    }
+
+    impl TesseractRecognizer {
+        /// Run `read` on this thread's warm engine once it has been given
+        /// the image, initialising the engine first if there is none with
+        /// these settings.
+        fn with_engine<T>(
+            &self,
+            image_bytes: &[u8],
+            read: impl FnOnce(&mut LepTess) -> Result<T, OcrError>,
+        ) -> Result<T, OcrError> {
+            ENGINE.with(|cell| {
+                let mut slot = cell.borrow_mut();
+                let mut lt = match slot.take() {
+                    Some((key, lt)) if key == self.key => lt,
+                    stale => {
+                        // Free the old model before loading another.
+                        drop(stale);
+                        self.key.init()?
+                    }
+                };
+                let result = lt
+                    .set_image_from_mem(image_bytes)
+                    .map_err(|e| OcrError::ImageDecode(e.to_string()))
+                    .and_then(|()| read(&mut lt));
+                // Don't hand an engine that failed to the next receipt.
+                if !matches!(result, Err(OcrError::Engine(_))) {
+                    *slot = Some((self.key.clone(), lt));
+                }
+                result
+            })
+        }
     }
 
     impl OcrBackend for TesseractRecognizer {
         fn recognize(&self, image_bytes: &[u8]) -> Result<String, OcrError> {
-            let mut lt = LepTess::new(self.data_path.as_deref(), &self.lang)
-                .map_err(|e| OcrError::Engine(e.to_string()))?;
-            lt.set_image_from_mem(image_bytes)
-                .map_err(|e| OcrError::ImageDecode(e.to_string()))?;
-            lt.get_utf8_text()
-                .map_err(|e| OcrError::Engine(e.to_string()))
+            self.with_engine(image_bytes, |lt| {
+                lt.get_utf8_text()
+                    .map_err(|e| OcrError::Engine(e.to_string()))
+            })
+        }
+
+        fn recognize_detailed(&self, image_bytes: &[u8]) -> Result<OcrPage, OcrError> {
+            self.with_engine(image_bytes, |lt| {
+                // Recognition runs once; the TSV reuses its result.
+                let text = lt
+                    .get_utf8_text()
+                    .map_err(|e| OcrError::Engine(e.to_string()))?;
+                let tsv = lt
+                    .get_tsv_text(0)
+                    .map_err(|e| OcrError::Engine(e.to_string()))?;
+                Ok(OcrPage {
+                    text,
+                    words: super::words_from_tsv(&tsv),
+                })
+            })
         }
     }
 }
@@ -88,4 +294,41 @@ mod tests {
         assert_eq!(r.recognize(b"anything").unwrap(), "hello");
         assert_eq!(r.recognize(b"").unwrap(), "hello");
     }
+
+    #[test]
+    fn words_read_from_tsv() {
+        let tsv = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext
+1\t1\t0\t0\t0\t0\t0\t0\t800\t1200\t-1\t
+4\t1\t1\t1\t1\t0\t40\t30\t300\t40\t-1\t
+5\t1\t1\t1\t1\t1\t40\t30\t180\t40\t96.5\tSTARBUCKS
+5\t1\t1\t1\t1\t2\t230\t30\t110\t40\t91\tCOFFEE
+5\t1\t1\t1\t2\t1\t40\t90\t90\t30\t42.25\t$5.50
+5\t1\t1\t1\t2\t2\t140\t90\t10\t30\t0\t ";
+        let words = words_from_tsv(tsv);
+        let read: Vec<(&str, usize)> = words.iter().map(|w| (w.text.as_str(), w.line)).collect();
+        assert_eq!(read, [("STARBUCKS", 0), ("COFFEE", 0), ("$5.50", 1)]);
+        assert!((words[2].confidence - 0.4225).abs() < 1e-6);
+        assert_eq!(
+            words[0].bbox,
+            BoundingBox {
+                left: 40,
+                top: 30,
+                width: 180,
+                height: 40
+            }
+        );
+    }
+
+    #[test]
+    fn recognize_detailed_defaults_to_text_alone() {
+        struct TextOnly;
+        impl OcrBackend for TextOnly {
+            fn recognize(&self, _image_bytes: &[u8]) -> Result<String, OcrError> {
+                Ok("TOTAL 5.00".to_string())
+            }
+        }
+        let page = TextOnly.recognize_detailed(b"img").unwrap();
+        assert_eq!(page.text, "TOTAL 5.00");
+        assert!(page.words.is_empty());
+    }
 }
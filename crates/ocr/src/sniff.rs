@@ -0,0 +1,107 @@
+//! File type detection for receipts that arrive as bytes.
+//!
+//! Camera captures and uploads come with a name or MIME type chosen by the
+//! client; the leading bytes say what the file really is.
+
+use crate::heif::is_heif;
+use crate::pdf::is_pdf;
+
+/// Extension for the receipt formats the pipeline reads, from the file's
+/// magic bytes. `None` for anything else.
+pub fn sniff_extension(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("jpg")
+    } else if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("png")
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        Some("gif")
+    } else if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        Some("webp")
+    } else if data.starts_with(b"II*\0") || data.starts_with(b"MM\0*") {
+        Some("tiff")
+    } else if data.starts_with(b"BM") {
+        Some("bmp")
+    } else if is_heif(data) {
+        Some("heic")
+    } else if is_pdf(data) {
+        Some("pdf")
+    } else {
+        None
+    }
+}
+
+/// Normalise an extension or MIME type (`image/jpeg`, `JPEG`, `.tif`) to
+/// the extension [`sniff_extension`] returns for that format.
+pub fn canonical_extension(ext_or_mime: &str) -> Option<&'static str> {
+    let s = ext_or_mime
+        .trim()
+        .trim_start_matches('.')
+        .to_ascii_lowercase();
+    let s = s.rsplit('/').next().unwrap_or_default();
+    Some(match s {
+        "jpg" | "jpeg" | "pjpeg" => "jpg",
+        "png" => "png",
+        "gif" => "gif",
+        "webp" => "webp",
+        "tif" | "tiff" => "tiff",
+        "bmp" => "bmp",
+        "heic" | "heif" | "heic-sequence" | "heif-sequence" => "heic",
+        "pdf" => "pdf",
+        _ => return None,
+    })
+}
+
+/// MIME type of a stored receipt, by its extension.
+pub fn content_type(ext: &str) -> &'static str {
+    match ext.trim_start_matches('.').to_ascii_lowercase().as_str() {
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "tif" | "tiff" => "image/tiff",
+        "bmp" => "image/bmp",
+        "heic" => "image/heic",
+        "heif" => "image/heif",
+        "pdf" => "application/pdf",
+        "html" | "htm" => "text/html",
+        "txt" => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniffs_common_formats() {
+        assert_eq!(
+            sniff_extension(&[0xFF, 0xD8, 0xFF, 0xE0, 0, 0x10]),
+            Some("jpg")
+        );
+        assert_eq!(sniff_extension(b"\x89PNG\r\n\x1a\n\0\0"), Some("png"));
+        assert_eq!(sniff_extension(b"RIFF\x10\0\0\0WEBPVP8 "), Some("webp"));
+        assert_eq!(sniff_extension(b"\0\0\0\x18ftypheic\0\0\0\0"), Some("heic"));
+        assert_eq!(sniff_extension(b"%PDF-1.7\n"), Some("pdf"));
+        assert_eq!(sniff_extension(b"<html><body>"), None);
+        assert_eq!(sniff_extension(b""), None);
+    }
+
+    #[test]
+    fn canonical_names() {
+        assert_eq!(canonical_extension("image/jpeg"), Some("jpg"));
+        assert_eq!(canonical_extension(".JPEG"), Some("jpg"));
+        assert_eq!(canonical_extension("tif"), Some("tiff"));
+        assert_eq!(canonical_extension("application/pdf"), Some("pdf"));
+        assert_eq!(canonical_extension("image/heif"), Some("heic"));
+        assert_eq!(canonical_extension("exe"), None);
+    }
+
+    #[test]
+    fn content_types() {
+        assert_eq!(content_type("JPG"), "image/jpeg");
+        assert_eq!(content_type(".pdf"), "application/pdf");
+        assert_eq!(content_type("heic"), "image/heic");
+        assert_eq!(content_type("eml"), "application/octet-stream");
+    }
+}
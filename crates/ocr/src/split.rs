@@ -0,0 +1,276 @@
+//! Finding more than one receipt in a single photo.
+//!
+//! People lay two stubs side by side, or one above the other, and take one
+//! picture. Paper is brighter than whatever it lies on, so at a small scale
+//! each receipt shows up as a run of columns (or rows) with paper in them,
+//! separated from the next by a band with none. Each run is cut out and
+//! goes through the pipeline as a receipt of its own.
+
+use std::ops::Range;
+
+use image::{DynamicImage, GrayImage};
+
+use crate::preprocess::{self, PreprocessError};
+use crate::recognizer::BoundingBox;
+
+/// Regions are found at this size; receipts are large features.
+const ANALYSIS_SIZE: u32 = 400;
+
+/// Paper and background must differ by at least this much in mean
+/// brightness, or the photo is taken to be all paper (or all table).
+const MIN_CONTRAST: f32 = 40.0;
+
+/// A column or row with less paper than this is background.
+const BACKGROUND_FRACTION: f32 = 0.05;
+
+/// A receipt spans at least this share of the photo's width or height.
+const MIN_RECEIPT_FRACTION: f32 = 0.10;
+
+/// Gaps narrower than this are within a receipt (a fold, a dark logo).
+const MIN_GAP_FRACTION: f32 = 0.02;
+
+/// Margin kept around each cut-out, as a share of the photo's size.
+const PADDING_FRACTION: f32 = 0.01;
+
+/// JPEG quality of the cut-outs; they go on to OCR, so keep them crisp.
+const CUT_OUT_QUALITY: u8 = 90;
+
+/// File extension the cut-outs are stored under.
+pub const CUT_OUT_EXTENSION: &str = "jpg";
+
+/// Where the receipts in `img` are, left to right or top to bottom. Empty
+/// unless there are at least two.
+pub fn receipt_regions(img: &DynamicImage) -> Vec<BoundingBox> {
+    let (width, height) = (img.width(), img.height());
+    if width == 0 || height == 0 {
+        return Vec::new();
+    }
+    let small = img.thumbnail(ANALYSIS_SIZE, ANALYSIS_SIZE).to_luma8();
+    let Some(threshold) = paper_threshold(&small) else {
+        return Vec::new();
+    };
+    let paper = |x: u32, y: u32| small.get_pixel(x, y)[0] > threshold;
+    let (sw, sh) = small.dimensions();
+
+    // Side by side first, since that's how stubs usually get laid out.
+    let columns: Vec<f32> = (0..sw)
+        .map(|x| (0..sh).filter(|&y| paper(x, y)).count() as f32 / sh as f32)
+        .collect();
+    let mut regions: Vec<(Range<u32>, Range<u32>)> = runs(&columns)
+        .into_iter()
+        .filter_map(|xs| {
+            let rows: Vec<f32> = (0..sh)
+                .map(|y| xs.clone().filter(|&x| paper(x, y)).count() as f32 / xs.len() as f32)
+                .collect();
+            Some((xs, extent(&rows)?))
+        })
+        .collect();
+    if regions.len() < 2 {
+        let rows: Vec<f32> = (0..sh)
+            .map(|y| (0..sw).filter(|&x| paper(x, y)).count() as f32 / sw as f32)
+            .collect();
+        regions = runs(&rows)
+            .into_iter()
+            .filter_map(|ys| {
+                let columns: Vec<f32> = (0..sw)
+                    .map(|x| ys.clone().filter(|&y| paper(x, y)).count() as f32 / ys.len() as f32)
+                    .collect();
+                Some((extent(&columns)?, ys))
+            })
+            .collect();
+    }
+    if regions.len() < 2 {
+        return Vec::new();
+    }
+
+    let (scale_x, scale_y) = (width as f32 / sw as f32, height as f32 / sh as f32);
+    let (pad_x, pad_y) = (
+        (width as f32 * PADDING_FRACTION) as u32,
+        (height as f32 * PADDING_FRACTION) as u32,
+    );
+    regions
+        .into_iter()
+        .map(|(xs, ys)| {
+            let left = ((xs.start as f32 * scale_x) as u32).saturating_sub(pad_x);
+            let top = ((ys.start as f32 * scale_y) as u32).saturating_sub(pad_y);
+            let right = ((xs.end as f32 * scale_x).ceil() as u32 + pad_x).min(width);
+            let bottom = ((ys.end as f32 * scale_y).ceil() as u32 + pad_y).min(height);
+            BoundingBox {
+                left,
+                top,
+                width: right - left,
+                height: bottom - top,
+            }
+        })
+        .collect()
+}
+
+/// Each receipt in a photo of several, cut out as a JPEG. Empty when the
+/// photo holds only one.
+pub fn split_receipts(data: &[u8]) -> Result<Vec<Vec<u8>>, PreprocessError> {
+    let img = preprocess::load_image(data)?;
+    receipt_regions(&img)
+        .into_iter()
+        .map(|r| {
+            let part = img.crop_imm(r.left, r.top, r.width, r.height).to_rgb8();
+            let mut buf = Vec::new();
+            let encoder =
+                image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buf, CUT_OUT_QUALITY);
+            DynamicImage::ImageRgb8(part)
+                .write_with_encoder(encoder)
+                .map_err(|e| PreprocessError::Encode(e.to_string()))?;
+            Ok(buf)
+        })
+        .collect()
+}
+
+/// Otsu's threshold between background and paper, or `None` when the two
+/// classes are too close in brightness to tell apart.
+fn paper_threshold(gray: &GrayImage) -> Option<u8> {
+    let mut histogram = [0u64; 256];
+    for p in gray.pixels() {
+        histogram[p[0] as usize] += 1;
+    }
+    let total: u64 = histogram.iter().sum();
+    let sum: f64 = histogram
+        .iter()
+        .enumerate()
+        .map(|(v, &n)| v as f64 * n as f64)
+        .sum();
+
+    let (mut best, mut best_variance, mut best_means) = (None, 0.0, (0.0, 0.0));
+    let (mut dark, mut dark_sum) = (0u64, 0.0);
+    for (v, &n) in histogram.iter().enumerate() {
+        dark += n;
+        dark_sum += v as f64 * n as f64;
+        let light = total - dark;
+        if dark == 0 || light == 0 {
+            continue;
+        }
+        let dark_mean = dark_sum / dark as f64;
+        let light_mean = (sum - dark_sum) / light as f64;
+        let variance = dark as f64 * light as f64 * (dark_mean - light_mean).powi(2);
+        if variance > best_variance {
+            (best, best_variance, best_means) = (Some(v as u8), variance, (dark_mean, light_mean));
+        }
+    }
+    best.filter(|_| (best_means.1 - best_means.0) as f32 >= MIN_CONTRAST)
+}
+
+/// Runs of entries with paper in them, wide enough to be a receipt, split
+/// by gaps wide enough to be table.
+fn runs(profile: &[f32]) -> Vec<Range<u32>> {
+    let len = profile.len() as f32;
+    let min_gap = ((len * MIN_GAP_FRACTION) as usize).max(1);
+    let min_run = (len * MIN_RECEIPT_FRACTION) as u32;
+
+    let mut runs: Vec<Range<u32>> = Vec::new();
+    let mut start = None;
+    for (i, &fraction) in profile.iter().enumerate() {
+        match (fraction >= BACKGROUND_FRACTION, start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                runs.push(s as u32..i as u32);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        runs.push(s as u32..profile.len() as u32);
+    }
+
+    let mut merged: Vec<Range<u32>> = Vec::new();
+    for run in runs {
+        match merged.last_mut() {
+            Some(last) if ((run.start - last.end) as usize) < min_gap => last.end = run.end,
+            _ => merged.push(run),
+        }
+    }
+    merged.retain(|run| run.len() as u32 >= min_run);
+    merged
+}
+
+/// First to last entry with paper in it.
+fn extent(profile: &[f32]) -> Option<Range<u32>> {
+    let first = profile.iter().position(|&f| f >= BACKGROUND_FRACTION)?;
+    let last = profile.iter().rposition(|&f| f >= BACKGROUND_FRACTION)?;
+    Some(first as u32..last as u32 + 1)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use image::{Luma, Rgb, RgbImage};
+    use std::io::Cursor;
+
+    /// A dark table with a printed receipt at each `(left, top, width,
+    /// height)`.
+    pub(crate) fn photo(width: u32, height: u32, receipts: &[(u32, u32, u32, u32)]) -> RgbImage {
+        RgbImage::from_fn(width, height, |x, y| {
+            let on_paper = receipts
+                .iter()
+                .any(|&(l, t, w, h)| (l..l + w).contains(&x) && (t..t + h).contains(&y));
+            let ink = x % 12 < 8 && y % 10 < 3;
+            match (on_paper, ink) {
+                (false, _) => Rgb([50, 45, 40]),
+                (true, false) => Rgb([235, 232, 225]),
+                (true, true) => Rgb([30, 30, 30]),
+            }
+        })
+    }
+
+    #[test]
+    fn two_stubs_side_by_side() {
+        let img =
+            DynamicImage::ImageRgb8(photo(800, 600, &[(40, 50, 300, 500), (460, 80, 280, 440)]));
+        let regions = receipt_regions(&img);
+        assert_eq!(regions.len(), 2);
+        let (a, b) = (&regions[0], &regions[1]);
+        // Within the padding and the down-scale's rounding of each stub.
+        assert!(a.left.abs_diff(40) <= 12 && (a.left + a.width).abs_diff(340) <= 12);
+        assert!(a.top.abs_diff(50) <= 12 && (a.top + a.height).abs_diff(550) <= 12);
+        assert!(b.left.abs_diff(460) <= 12 && (b.left + b.width).abs_diff(740) <= 12);
+        assert!(b.top.abs_diff(80) <= 12 && (b.top + b.height).abs_diff(520) <= 12);
+    }
+
+    #[test]
+    fn stubs_one_above_the_other() {
+        let img = DynamicImage::ImageRgb8(photo(
+            600,
+            800,
+            &[(100, 30, 400, 300), (120, 420, 360, 340)],
+        ));
+        let regions = receipt_regions(&img);
+        assert_eq!(regions.len(), 2);
+        assert!(regions[0].top < regions[1].top);
+        assert!(regions[0].top + regions[0].height < regions[1].top);
+    }
+
+    #[test]
+    fn one_receipt_or_no_contrast_is_left_whole() {
+        let one = DynamicImage::ImageRgb8(photo(800, 600, &[(200, 40, 400, 520)]));
+        assert!(receipt_regions(&one).is_empty());
+
+        let flat = DynamicImage::ImageLuma8(GrayImage::from_pixel(400, 300, Luma([128])));
+        assert!(receipt_regions(&flat).is_empty());
+
+        // All paper, as when the receipt fills the frame.
+        let filled = DynamicImage::ImageRgb8(photo(800, 600, &[(0, 0, 800, 600)]));
+        assert!(receipt_regions(&filled).is_empty());
+    }
+
+    #[test]
+    fn split_receipts_cuts_out_each_stub() {
+        let img =
+            DynamicImage::ImageRgb8(photo(800, 600, &[(40, 50, 300, 500), (460, 80, 280, 440)]));
+        let mut png = Vec::new();
+        img.write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        let parts = split_receipts(&png).unwrap();
+        assert_eq!(parts.len(), 2);
+        let first = image::load_from_memory(&parts[0]).unwrap();
+        assert!(first.width().abs_diff(300) <= 24 && first.height().abs_diff(500) <= 24);
+    }
+}
@@ -0,0 +1,161 @@
+//! Tesseract language data: where it lives and how to fetch it.
+
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// The "fast" models are a fraction of the size of the "best" ones and are
+/// accurate enough for printed receipts.
+pub const TESSDATA_BASE_URL: &str = "https://github.com/tesseract-ocr/tessdata_fast/raw/main";
+
+#[derive(Debug, Error)]
+pub enum TessdataError {
+    #[error("Invalid language code: {0:?}")]
+    InvalidLanguage(String),
+    #[error("Download failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("Download failed with HTTP status {0}")]
+    HttpStatus(u16),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Tesseract language codes are short ASCII identifiers such as `eng` or
+/// `chi_sim`; anything else is rejected so it cannot escape `dir`.
+fn validate_language(lang: &str) -> Result<(), TessdataError> {
+    let valid = !lang.is_empty()
+        && lang.len() <= 32
+        && lang
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(TessdataError::InvalidLanguage(lang.to_string()))
+    }
+}
+
+pub fn traineddata_path(dir: &Path, lang: &str) -> PathBuf {
+    dir.join(format!("{lang}.traineddata"))
+}
+
+/// Components of a Tesseract language spec; `deu+eng` loads both models.
+pub fn spec_languages(spec: &str) -> impl Iterator<Item = &str> {
+    spec.split('+').map(str::trim)
+}
+
+/// Whether every language in `spec` has its model in `dir`.
+pub fn is_language_installed(dir: &Path, spec: &str) -> bool {
+    spec_languages(spec)
+        .all(|lang| validate_language(lang).is_ok() && traineddata_path(dir, lang).is_file())
+}
+
+/// Language codes with a `.traineddata` file in `dir`, sorted.
+pub fn installed_languages(dir: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut langs: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter_map(|e| {
+            e.file_name()
+                .to_str()?
+                .strip_suffix(".traineddata")
+                .map(str::to_string)
+        })
+        .collect();
+    langs.sort();
+    langs
+}
+
+/// Download `<lang>.traineddata` into `dir`, replacing any existing copy.
+///
+/// The file is written under a temporary name and renamed into place, so an
+/// interrupted download never leaves a truncated model for Tesseract to load.
+pub async fn download_language(dir: &Path, lang: &str) -> Result<PathBuf, TessdataError> {
+    validate_language(lang)?;
+    tokio::fs::create_dir_all(dir).await?;
+
+    let resp = reqwest::get(format!("{TESSDATA_BASE_URL}/{lang}.traineddata")).await?;
+    if !resp.status().is_success() {
+        return Err(TessdataError::HttpStatus(resp.status().as_u16()));
+    }
+    let bytes = resp.bytes().await?;
+
+    let dest = traineddata_path(dir, lang);
+    let partial = dir.join(format!("{lang}.traineddata.part"));
+    tokio::fs::write(&partial, &bytes).await?;
+    tokio::fs::rename(&partial, &dest).await?;
+    Ok(dest)
+}
+
+/// Download every language in `spec` (e.g. `fra+eng`) that isn't already
+/// in `dir`. All codes are validated before anything is fetched.
+pub async fn download_languages(dir: &Path, spec: &str) -> Result<Vec<PathBuf>, TessdataError> {
+    let langs: Vec<&str> = spec_languages(spec).collect();
+    for lang in &langs {
+        validate_language(lang)?;
+    }
+    let mut paths = Vec::with_capacity(langs.len());
+    for lang in langs {
+        let path = traineddata_path(dir, lang);
+        if path.is_file() {
+            paths.push(path);
+        } else {
+            paths.push(download_language(dir, lang).await?);
+        }
+    }
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn language_codes_validated() {
+        assert!(validate_language("eng").is_ok());
+        assert!(validate_language("chi_sim").is_ok());
+        assert!(validate_language("").is_err());
+        assert!(validate_language("../eng").is_err());
+        assert!(validate_language("ENG").is_err());
+    }
+
+    #[test]
+    fn installed_languages_listed() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("fra.traineddata"), b"x").unwrap();
+        std::fs::write(dir.path().join("eng.traineddata"), b"x").unwrap();
+        std::fs::write(dir.path().join("eng.traineddata.part"), b"x").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), b"x").unwrap();
+
+        assert_eq!(installed_languages(dir.path()), ["eng", "fra"]);
+        assert!(is_language_installed(dir.path(), "eng"));
+        assert!(!is_language_installed(dir.path(), "deu"));
+        assert!(is_language_installed(dir.path(), "fra+eng"));
+        assert!(!is_language_installed(dir.path(), "deu+eng"));
+    }
+
+    #[tokio::test]
+    async fn combined_spec_validated_before_download() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(matches!(
+            download_languages(dir.path(), "eng+../x").await,
+            Err(TessdataError::InvalidLanguage(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn installed_components_are_not_downloaded_again() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("fra.traineddata"), b"x").unwrap();
+        std::fs::write(dir.path().join("eng.traineddata"), b"x").unwrap();
+        let paths = download_languages(dir.path(), "fra+eng").await.unwrap();
+        assert_eq!(paths.len(), 2);
+    }
+
+    #[test]
+    fn missing_dir_has_no_languages() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(installed_languages(&dir.path().join("missing")).is_empty());
+    }
+}
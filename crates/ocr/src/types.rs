@@ -96,6 +96,13 @@ pub struct ExtractedReceipt {
     pub tax_cents: Option<ExtractedField<i64>>,
     /// Grand total (cents) — the primary field for transaction creation.
     pub total_cents: Option<ExtractedField<i64>>,
+    /// Tip or gratuity added on top of the printed total; already included
+    /// in `total_cents`.
+    #[serde(default)]
+    pub tip_cents: Option<ExtractedField<i64>>,
+    /// Invoice, receipt or order number, as printed.
+    #[serde(default)]
+    pub reference: Option<ExtractedField<String>>,
     pub payment_method: Option<ExtractedField<PaymentMethod>>,
     pub line_items: Vec<LineItem>,
     /// Aggregate confidence across all extracted fields (0.0–1.0).
@@ -177,6 +184,8 @@ mod tests {
             subtotal_cents: Some(ExtractedField::new(1000, 0.8)),
             tax_cents: Some(ExtractedField::new(100, 0.8)),
             total_cents: Some(ExtractedField::new(1100, 0.85)),
+            tip_cents: None,
+            reference: None,
             payment_method: Some(ExtractedField::new(PaymentMethod::Visa, 0.7)),
             line_items: vec![LineItem {
                 description: "Widget".into(),
@@ -198,6 +207,8 @@ mod tests {
             subtotal_cents: None,
             tax_cents: None,
             total_cents: None,
+            tip_cents: None,
+            reference: None,
             payment_method: None,
             line_items: vec![],
             confidence: 0.5,
@@ -0,0 +1,367 @@
+//! Known vendors, consulted after extraction.
+//!
+//! The header of a receipt is the hardest part to OCR (logos, stylised type)
+//! and the vendor line heuristic only guesses. When a line fuzzy-matches a
+//! known vendor, the canonical name is used instead, and whatever is known
+//! about that vendor's receipts — its usual tax rate, how it labels the
+//! charged total — is applied too.
+//!
+//! The dictionary starts from a list of common chains and grows from receipts
+//! the user approves (see `aequi_storage::record_vendor_approval`).
+
+use serde::{Deserialize, Serialize};
+
+use crate::extract::{
+    aggregate_confidence, amount_after_label, check_arithmetic, label_before_amount,
+};
+use crate::types::{ExtractedField, ExtractedReceipt};
+
+/// Lines scanned for a vendor name; it is always in the header.
+const HEADER_LINES: usize = 12;
+
+/// Minimum similarity (0.0–1.0) for a fuzzy match.
+const MATCH_THRESHOLD: f32 = 0.8;
+
+const KNOWN_VENDOR_CONFIDENCE: f32 = 0.95;
+const HINTED_TOTAL_CONFIDENCE: f32 = 0.93;
+const ESTIMATED_TAX_CONFIDENCE: f32 = 0.7;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VendorProfile {
+    /// Canonical display name.
+    pub name: String,
+    /// Other spellings seen on receipts (`WAL-MART`, `WALMART SUPERCENTER`).
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Typical tax as a fraction of the subtotal, e.g. `0.0825`.
+    pub tax_rate: Option<f64>,
+    /// The label this vendor prints in front of the charged total.
+    pub total_label: Option<String>,
+}
+
+impl VendorProfile {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            aliases: Vec::new(),
+            tax_rate: None,
+            total_label: None,
+        }
+    }
+
+    /// What an approved receipt says about its vendor: the tax rate it
+    /// charged and, if unusual, how it labelled the total.
+    pub fn from_approved_receipt(
+        name: impl Into<String>,
+        ocr_text: Option<&str>,
+        subtotal_cents: Option<i64>,
+        tax_cents: Option<i64>,
+        total_cents: Option<i64>,
+    ) -> Self {
+        let mut profile = Self::new(name);
+        if let (Some(subtotal), Some(tax)) = (subtotal_cents, tax_cents) {
+            if subtotal != 0 {
+                profile.tax_rate = Some(tax as f64 / subtotal as f64);
+            }
+        }
+        if let (Some(text), Some(total)) = (ocr_text, total_cents) {
+            profile.total_label = label_before_amount(text, total);
+        }
+        profile
+    }
+
+    fn with_aliases(mut self, aliases: &[&str]) -> Self {
+        self.aliases = aliases.iter().map(|a| a.to_string()).collect();
+        self
+    }
+
+    fn spellings(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.name.as_str()).chain(self.aliases.iter().map(String::as_str))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct VendorDictionary {
+    profiles: Vec<VendorProfile>,
+}
+
+impl VendorDictionary {
+    /// Common chains, with the spellings their receipts actually use.
+    pub fn seeded() -> Self {
+        let seeds = [
+            (
+                "Walmart",
+                &["WAL-MART", "WAL*MART", "WALMART SUPERCENTER"][..],
+            ),
+            ("Target", &[]),
+            ("Costco", &["COSTCO WHOLESALE"]),
+            ("The Home Depot", &["HOME DEPOT"]),
+            ("Lowe's", &["LOWES", "LOWES HOME CENTERS"]),
+            ("Staples", &[]),
+            ("Office Depot", &["OFFICE DEPOT OFFICEMAX", "OFFICEMAX"]),
+            ("Best Buy", &["BESTBUY"]),
+            ("Apple Store", &["APPLE STORE"]),
+            ("Amazon", &["AMAZON.COM", "AMZN MKTP"]),
+            ("Starbucks", &["STARBUCKS COFFEE"]),
+            ("McDonald's", &["MCDONALDS"]),
+            ("Whole Foods Market", &["WHOLE FOODS", "WFM"]),
+            ("Trader Joe's", &["TRADER JOES"]),
+            ("Kroger", &[]),
+            ("Safeway", &[]),
+            ("Walgreens", &[]),
+            ("CVS Pharmacy", &["CVS/PHARMACY", "CVS"]),
+            ("Shell", &[]),
+            ("Chevron", &[]),
+            ("ExxonMobil", &["EXXON", "MOBIL"]),
+            ("FedEx Office", &["FEDEX"]),
+            ("The UPS Store", &["UPS STORE"]),
+            (
+                "USPS",
+                &["UNITED STATES POSTAL SERVICE", "US POSTAL SERVICE"],
+            ),
+        ];
+        Self {
+            profiles: seeds
+                .iter()
+                .map(|(name, aliases)| VendorProfile::new(*name).with_aliases(aliases))
+                .collect(),
+        }
+    }
+
+    /// The seeded chains plus `learned` profiles. A learned profile replaces
+    /// the seed with the same name, keeping the seed's aliases.
+    pub fn with_learned(learned: impl IntoIterator<Item = VendorProfile>) -> Self {
+        let mut dict = Self::seeded();
+        for mut profile in learned {
+            match dict
+                .profiles
+                .iter_mut()
+                .find(|p| normalize(&p.name) == normalize(&profile.name))
+            {
+                Some(existing) => {
+                    for alias in std::mem::take(&mut existing.aliases) {
+                        if !profile.aliases.contains(&alias) {
+                            profile.aliases.push(alias);
+                        }
+                    }
+                    *existing = profile;
+                }
+                None => dict.profiles.push(profile),
+            }
+        }
+        dict
+    }
+
+    pub fn profiles(&self) -> &[VendorProfile] {
+        &self.profiles
+    }
+
+    /// The best-matching known vendor among the header lines, if any is
+    /// similar enough.
+    pub fn find(&self, text: &str) -> Option<&VendorProfile> {
+        let lines: Vec<String> = text
+            .lines()
+            .take(HEADER_LINES)
+            .map(normalize)
+            .filter(|l| !l.is_empty())
+            .collect();
+
+        let mut best: Option<(&VendorProfile, f32)> = None;
+        for profile in &self.profiles {
+            for spelling in profile.spellings() {
+                let spelling = normalize(spelling);
+                for line in &lines {
+                    let score = match_score(line, &spelling);
+                    if score >= MATCH_THRESHOLD && best.is_none_or(|(_, s)| score > s) {
+                        best = Some((profile, score));
+                    }
+                }
+            }
+        }
+        best.map(|(profile, _)| profile)
+    }
+
+    /// Apply what is known about the receipt's vendor. Returns whether a
+    /// known vendor was found.
+    pub fn apply(&self, receipt: &mut ExtractedReceipt, text: &str) -> bool {
+        let Some(profile) = self.find(text) else {
+            return false;
+        };
+
+        receipt.vendor = Some(ExtractedField::new(
+            profile.name.clone(),
+            KNOWN_VENDOR_CONFIDENCE,
+        ));
+
+        if let Some(label) = &profile.total_label {
+            if let Some(cents) = amount_after_label(text, label) {
+                let sign = if receipt.total_cents.as_ref().is_some_and(|t| t.value < 0) {
+                    -1
+                } else {
+                    1
+                };
+                receipt.total_cents = Some(ExtractedField::new(
+                    sign * cents.abs(),
+                    HINTED_TOTAL_CONFIDENCE,
+                ));
+            }
+        }
+
+        if let (Some(rate), Some(subtotal), None) = (
+            profile.tax_rate,
+            &receipt.subtotal_cents,
+            &receipt.tax_cents,
+        ) {
+            let tax = (subtotal.value as f64 * rate).round() as i64;
+            receipt.tax_cents = Some(ExtractedField::new(tax, ESTIMATED_TAX_CONFIDENCE));
+        }
+
+        check_arithmetic(receipt, text);
+        receipt.confidence = aggregate_confidence(receipt);
+        true
+    }
+}
+
+/// Uppercase alphanumerics with single spaces; punctuation and OCR noise
+/// like `*` and `'` dropped.
+fn normalize(s: &str) -> String {
+    s.chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 1.0 when the vendor's spelling appears as whole words in the line,
+/// otherwise edit-distance similarity of the whole line (catches OCR slips
+/// like `STARBUCKS C0FFEE`).
+fn match_score(line: &str, spelling: &str) -> f32 {
+    if spelling.is_empty() {
+        return 0.0;
+    }
+    if format!(" {line} ").contains(&format!(" {spelling} ")) {
+        return 1.0;
+    }
+    let longest = line.chars().count().max(spelling.chars().count());
+    1.0 - levenshtein(line, spelling) as f32 / longest as f32
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::Extractor;
+
+    #[test]
+    fn exact_alias_gives_canonical_name() {
+        let text = "WAL*MART SUPERCENTER #1234\n123 Main St\nTOTAL 23.45";
+        let mut r = Extractor::extract(text);
+        assert!(VendorDictionary::seeded().apply(&mut r, text));
+        let vendor = r.vendor.unwrap();
+        assert_eq!(vendor.value, "Walmart");
+        assert_eq!(vendor.confidence, KNOWN_VENDOR_CONFIDENCE);
+    }
+
+    #[test]
+    fn ocr_slip_still_matches() {
+        let text = "5TARBUCKS C0FFEE\n2024-01-15\nTotal $5.50";
+        let dict = VendorDictionary::seeded();
+        assert_eq!(dict.find(text).unwrap().name, "Starbucks");
+    }
+
+    #[test]
+    fn unknown_vendor_is_left_alone() {
+        let text = "JOE'S HARDWARE\nTotal $12.00";
+        let mut r = Extractor::extract(text);
+        let before = r.vendor.clone();
+        assert!(!VendorDictionary::seeded().apply(&mut r, text));
+        assert_eq!(r.vendor, before);
+    }
+
+    #[test]
+    fn short_alias_needs_whole_word() {
+        // "CVS" must not match inside another word.
+        let dict = VendorDictionary::seeded();
+        assert!(dict.find("CVSTORE OUTLET\nTotal $1.00").is_none());
+    }
+
+    #[test]
+    fn learned_total_label_and_tax_rate_applied() {
+        let mut profile = VendorProfile::new("Joe's Hardware");
+        profile.tax_rate = Some(0.08);
+        profile.total_label = Some("AMOUNT TENDERED".to_string());
+        let dict = VendorDictionary::with_learned([profile]);
+
+        let text = "JOE'S HARDWARE\nSubtotal $50.00\nPoints balance $312.00\n\
+                    AMOUNT TENDERED $54.00";
+        let mut r = Extractor::extract(text);
+        assert!(dict.apply(&mut r, text));
+        assert_eq!(r.vendor.unwrap().value, "Joe's Hardware");
+        assert_eq!(r.total_cents.unwrap().value, 5400);
+        assert_eq!(r.tax_cents.unwrap().value, 400);
+    }
+
+    #[test]
+    fn learned_profile_replaces_seed_and_keeps_aliases() {
+        let mut profile = VendorProfile::new("walmart");
+        profile.tax_rate = Some(0.0725);
+        let dict = VendorDictionary::with_learned([profile]);
+        let walmart: Vec<_> = dict
+            .profiles()
+            .iter()
+            .filter(|p| normalize(&p.name) == "WALMART")
+            .collect();
+        assert_eq!(walmart.len(), 1);
+        assert_eq!(walmart[0].tax_rate, Some(0.0725));
+        assert!(walmart[0].aliases.iter().any(|a| a == "WAL-MART"));
+    }
+
+    #[test]
+    fn profile_learned_from_approved_receipt() {
+        let text = "JOE'S HARDWARE\nSubtotal $50.00\nTax $4.00\nAMOUNT TENDERED $54.00";
+        let p = VendorProfile::from_approved_receipt(
+            "Joe's Hardware",
+            Some(text),
+            Some(5000),
+            Some(400),
+            Some(5400),
+        );
+        assert_eq!(p.tax_rate, Some(0.08));
+        assert_eq!(p.total_label.as_deref(), Some("AMOUNT TENDERED"));
+    }
+
+    #[test]
+    fn standard_total_label_is_not_learned() {
+        let text = "JOE'S HARDWARE\nTotal: $54.00";
+        let p = VendorProfile::from_approved_receipt("Joe's", Some(text), None, None, Some(5400));
+        assert_eq!(p.total_label, None);
+        assert_eq!(p.tax_rate, None);
+    }
+
+    #[test]
+    fn levenshtein_distances() {
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("same", "same"), 0);
+    }
+}
@@ -1,5 +1,7 @@
 pub mod invoice_pdf;
+pub mod report_pdf;
 pub mod typst_pdf;
 
 pub use invoice_pdf::render_invoice_text;
+pub use report_pdf::render_report_pdf;
 pub use typst_pdf::render_invoice_pdf;
@@ -0,0 +1,163 @@
+use aequi_core::export::{ReportCell, ReportTable, RowStyle};
+use aequi_core::{Money, MoneyFormat};
+
+use crate::typst_pdf::{compile_pdf, escape};
+
+/// Generate Typst markup for a report table, with amounts in `money`.
+fn report_to_typst(report: &ReportTable, money: &MoneyFormat) -> String {
+    let mut typ = String::new();
+
+    typ.push_str("#set page(margin: (x: 2cm, y: 2cm), numbering: \"1\")\n");
+    typ.push_str("#set text(size: 9pt)\n\n");
+
+    typ.push_str(&format!(
+        "#text(size: 16pt, weight: \"bold\")[{}]\\\n",
+        escape(&report.title)
+    ));
+    if !report.subtitle.is_empty() {
+        typ.push_str(&format!(
+            "#text(fill: gray)[{}]\n",
+            escape(&report.subtitle)
+        ));
+    }
+    typ.push_str("\n#v(1em)\n");
+
+    // The last text column takes the spare width; the rest fit their content.
+    let wide = report.columns.iter().rposition(|c| !c.numeric);
+    let widths: Vec<&str> = (0..report.columns.len())
+        .map(|i| if Some(i) == wide { "1fr" } else { "auto" })
+        .collect();
+    let aligns: Vec<&str> = report
+        .columns
+        .iter()
+        .map(|c| if c.numeric { "right" } else { "left" })
+        .collect();
+
+    typ.push_str("#table(\n");
+    typ.push_str(&format!("  columns: ({},),\n", widths.join(", ")));
+    typ.push_str(&format!("  align: ({},),\n", aligns.join(", ")));
+    typ.push_str("  stroke: none,\n");
+    typ.push_str("  table.header(\n");
+    let header: Vec<String> = report
+        .columns
+        .iter()
+        .map(|c| format!("[*{}*]", escape(&c.label)))
+        .collect();
+    typ.push_str(&format!("    {},\n", header.join(", ")));
+    typ.push_str("  ),\n");
+    typ.push_str("  table.hline(),\n");
+
+    let width = report.columns.len().max(1);
+    for row in &report.rows {
+        match row.style {
+            RowStyle::Heading => {
+                let title = row
+                    .cells
+                    .first()
+                    .map(|c| cell(c, money))
+                    .unwrap_or_default();
+                typ.push_str(&format!(
+                    "  table.cell(colspan: {width})[#v(0.4em)#strong[{title}]],\n"
+                ));
+                continue;
+            }
+            RowStyle::Subtotal => typ.push_str("  table.hline(stroke: 0.5pt),\n"),
+            RowStyle::Total => typ.push_str("  table.hline(),\n"),
+            RowStyle::Detail => {}
+        }
+        let bold = row.style != RowStyle::Detail;
+        let cells: Vec<String> = (0..width)
+            .map(|i| {
+                let text = row.cells.get(i).map(|c| cell(c, money)).unwrap_or_default();
+                if bold && !text.is_empty() {
+                    format!("[#strong[{text}]]")
+                } else {
+                    format!("[{text}]")
+                }
+            })
+            .collect();
+        typ.push_str(&format!("  {},\n", cells.join(", ")));
+    }
+
+    typ.push_str(")\n");
+    typ
+}
+
+fn cell(c: &ReportCell, money: &MoneyFormat) -> String {
+    match c {
+        ReportCell::Text(s) => escape(s),
+        ReportCell::Amount(m) => amount(*m, money),
+        ReportCell::Empty => String::new(),
+    }
+}
+
+/// Negative amounts in parentheses, as accountants print them.
+fn amount(m: Money, money: &MoneyFormat) -> String {
+    if m.to_cents() < 0 {
+        escape(&format!("({})", money.format_cents(-m.to_cents())))
+    } else {
+        escape(&money.format(m))
+    }
+}
+
+/// Render a report as a PDF byte vector using Typst, with amounts written
+/// the way `money` formats them.
+pub fn render_report_pdf(report: &ReportTable, money: &MoneyFormat) -> Result<Vec<u8>, String> {
+    compile_pdf(report_to_typst(report, money))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aequi_core::export::{ReportColumn, ReportRow};
+
+    fn sample_report() -> ReportTable {
+        let mut report =
+            ReportTable::new("Profit and Loss", "2026-01-01 to 2026-03-31").columns(vec![
+                ReportColumn::text("Account"),
+                ReportColumn::amount("Amount"),
+            ]);
+        report.push(ReportRow::heading("Income"));
+        report.push(ReportRow::detail(vec![
+            ReportCell::text("4000 Services [consulting]"),
+            ReportCell::Amount(Money::from_cents(250_000)),
+        ]));
+        report.push(ReportRow::subtotal(vec![
+            ReportCell::text("Total income"),
+            ReportCell::Amount(Money::from_cents(250_000)),
+        ]));
+        report.push(ReportRow::total(vec![
+            ReportCell::text("Net income"),
+            ReportCell::Amount(Money::from_cents(-1_250)),
+        ]));
+        report
+    }
+
+    #[test]
+    fn typst_markup_contains_report_data() {
+        let typ = report_to_typst(&sample_report(), &MoneyFormat::default());
+
+        assert!(typ.contains("Profit and Loss"));
+        assert!(typ.contains("2026-01-01 to 2026-03-31"));
+        assert!(typ.contains("columns: (1fr, auto,)"));
+        assert!(typ.contains("table.cell(colspan: 2)[#v(0.4em)#strong[Income]]"));
+        assert!(typ.contains("4000 Services \\[consulting\\]"));
+        assert!(typ.contains("\\$2,500.00"));
+        assert!(typ.contains("(\\$12.50)"));
+    }
+
+    #[test]
+    fn amounts_follow_the_money_format() {
+        let euros = MoneyFormat::new(aequi_core::Locale::parse("de-DE").unwrap(), "EUR");
+        let typ = report_to_typst(&sample_report(), &euros);
+        assert!(typ.contains("2.500,00\u{a0}€"));
+        assert!(typ.contains("(12,50\u{a0}€)"));
+    }
+
+    #[test]
+    fn render_pdf_produces_bytes() {
+        let pdf = render_report_pdf(&sample_report(), &MoneyFormat::default())
+            .expect("PDF render failed");
+        assert_eq!(&pdf[0..5], b"%PDF-");
+    }
+}
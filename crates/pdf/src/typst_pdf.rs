@@ -121,7 +121,7 @@ fn invoice_to_typst(invoice: &Invoice, contact: &Contact) -> String {
 }
 
 /// Escape special Typst characters in user-provided text.
-fn escape(s: &str) -> String {
+pub(crate) fn escape(s: &str) -> String {
     s.replace('\\', "\\\\")
         .replace('#', "\\#")
         .replace('$', "\\$")
@@ -130,6 +130,8 @@ fn escape(s: &str) -> String {
         .replace('@', "\\@")
         .replace('<', "\\<")
         .replace('>', "\\>")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
 }
 
 /// Format a Money value safe for Typst (escape the $ sign).
@@ -141,8 +143,11 @@ fn money(m: aequi_core::Money) -> String {
 ///
 /// Returns `Ok(pdf_bytes)` on success, or an error string.
 pub fn render_invoice_pdf(invoice: &Invoice, contact: &Contact) -> Result<Vec<u8>, String> {
-    let typst_source = invoice_to_typst(invoice, contact);
+    compile_pdf(invoice_to_typst(invoice, contact))
+}
 
+/// Compile Typst markup to PDF bytes.
+pub(crate) fn compile_pdf(typst_source: String) -> Result<Vec<u8>, String> {
     let engine = typst_as_lib::TypstEngine::builder()
         .main_file(typst_source)
         .build();
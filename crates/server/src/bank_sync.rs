@@ -0,0 +1,263 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use aequi_import::plaid::PlaidClient;
+use aequi_import::simplefin::SimpleFinClient;
+use aequi_import::{BankAggregator, SyncBatch};
+use tokio::sync::watch;
+
+use crate::state::ServerState;
+
+const DEFAULT_SYNC_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+/// Each run re-requests this many days so late-posting transactions are
+/// picked up; provider ids keep the overlap from queueing duplicates.
+const LOOKBACK_DAYS: i64 = 30;
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SyncSummary {
+    pub queued: usize,
+    pub skipped: usize,
+    pub balances: usize,
+}
+
+/// Queue a batch as pending `imported_transactions` and refresh stored
+/// balances. Rows whose provider id was already imported are skipped.
+pub async fn apply_sync_batch(
+    db: &aequi_storage::DbPool,
+    batch: &SyncBatch,
+) -> Result<SyncSummary, sqlx::Error> {
+    let source_type = batch.provider.source_type();
+    let batch_id = format!(
+        "sync-{source_type}-{}",
+        chrono::Utc::now().format("%Y%m%d%H%M%S")
+    );
+    let mut summary = SyncSummary::default();
+
+    for t in &batch.transactions {
+        if aequi_storage::imported_transaction_exists(db, source_type, &t.provider_id).await? {
+            summary.skipped += 1;
+            continue;
+        }
+        let (debit_cents, credit_cents) = if t.amount_cents < 0 {
+            (Some(-t.amount_cents), None)
+        } else {
+            (None, Some(t.amount_cents))
+        };
+        let row = aequi_storage::ImportedTransaction {
+            id: 0,
+            source_type: source_type.to_string(),
+            source_id: Some(t.provider_id.clone()),
+            import_batch_id: batch_id.clone(),
+            date: t.date.to_string(),
+            description: t.description.clone(),
+            amount_cents: t.amount_cents,
+            debit_cents,
+            credit_cents,
+            memo: t.memo.clone(),
+            matched_transaction_id: None,
+            category_rule_id: None,
+            status: "pending".to_string(),
+            created_at: String::new(),
+        };
+        aequi_storage::insert_imported_transaction(db, &row).await?;
+        summary.queued += 1;
+    }
+
+    for b in &batch.balances {
+        let balance = aequi_storage::BankBalance {
+            id: 0,
+            provider: source_type.to_string(),
+            provider_account_id: b.account_id.clone(),
+            name: b.name.clone(),
+            currency: b.currency.clone(),
+            current_cents: b.current_cents,
+            available_cents: b.available_cents,
+            as_of: b.as_of.to_rfc3339(),
+        };
+        aequi_storage::upsert_bank_balance(db, &balance).await?;
+        summary.balances += 1;
+    }
+
+    Ok(summary)
+}
+
+/// Aggregators that are configured right now. Plaid is re-checked on every
+/// run because the access token only appears once the Link flow completes.
+async fn configured_aggregators(
+    state: &ServerState,
+    simplefin_access_url: Option<&str>,
+) -> Vec<BankAggregator> {
+    let mut aggregators = Vec::new();
+
+    if let Some(config) = state.plaid_config.clone() {
+        match aequi_storage::get_setting(&state.db, "plaid_access_token").await {
+            Ok(Some(access_token)) => aggregators.push(BankAggregator::Plaid {
+                client: PlaidClient::new(config),
+                access_token,
+            }),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("bank sync: failed to read Plaid access token: {e}"),
+        }
+    }
+
+    if let Some(url) = simplefin_access_url {
+        match SimpleFinClient::new(url) {
+            Ok(client) => aggregators.push(BankAggregator::SimpleFin(client)),
+            Err(e) => tracing::warn!("bank sync: {e}"),
+        }
+    }
+
+    aggregators
+}
+
+async fn run_once(state: &ServerState, simplefin_access_url: Option<&str>) {
+    let end = chrono::Utc::now().date_naive();
+    let start = end - chrono::Duration::days(LOOKBACK_DAYS);
+
+    for aggregator in configured_aggregators(state, simplefin_access_url).await {
+        let provider = aggregator.provider().source_type();
+        let batch = match aggregator.fetch(start, end).await {
+            Ok(batch) => batch,
+            Err(e) => {
+                tracing::warn!("bank sync: {provider} fetch failed: {e}");
+                continue;
+            }
+        };
+        match apply_sync_batch(&state.db, &batch).await {
+            Ok(s) => tracing::info!(
+                "bank sync: {provider} queued {} transactions ({} already imported), {} balances",
+                s.queued,
+                s.skipped,
+                s.balances
+            ),
+            Err(e) => tracing::warn!("bank sync: failed to store {provider} batch: {e}"),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Background task
+// ---------------------------------------------------------------------------
+
+/// Spawn the scheduled bank aggregator sync.
+///
+/// Runs immediately and then every `AEQUI_BANK_SYNC_INTERVAL_SECS` seconds
+/// (default six hours) against whichever of Plaid (`AEQUI_PLAID_CONFIG` plus a
+/// linked access token) and SimpleFIN (`AEQUI_SIMPLEFIN_ACCESS_URL`) are
+/// configured. The task shuts down when the `shutdown` receiver fires.
+pub fn spawn_bank_sync_task(
+    state: Arc<ServerState>,
+    shutdown: watch::Receiver<bool>,
+) -> tokio::task::JoinHandle<()> {
+    let interval = std::env::var("AEQUI_BANK_SYNC_INTERVAL_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .filter(|&secs| secs > 0)
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_SYNC_INTERVAL);
+    let simplefin_access_url = std::env::var("AEQUI_SIMPLEFIN_ACCESS_URL").ok();
+
+    tokio::spawn(async move {
+        let mut shutdown = shutdown;
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    run_once(&state, simplefin_access_url.as_deref()).await;
+                }
+                _ = shutdown.changed() => {
+                    tracing::info!("bank sync task shutting down");
+                    return;
+                }
+            }
+        }
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aequi_import::sync::{SyncedBalance, SyncedTransaction};
+    use aequi_import::SyncProvider;
+    use chrono::NaiveDate;
+
+    async fn test_pool() -> aequi_storage::DbPool {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        aequi_storage::migrate::run_migrations(&pool).await.unwrap();
+        pool
+    }
+
+    fn batch() -> SyncBatch {
+        SyncBatch {
+            provider: SyncProvider::SimpleFin,
+            transactions: vec![
+                SyncedTransaction {
+                    provider_id: "T1".to_string(),
+                    account_id: "ACT-1".to_string(),
+                    date: NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(),
+                    description: "OFFICE DEPOT".to_string(),
+                    amount_cents: -1234,
+                    memo: None,
+                },
+                SyncedTransaction {
+                    provider_id: "T2".to_string(),
+                    account_id: "ACT-1".to_string(),
+                    date: NaiveDate::from_ymd_opt(2024, 3, 2).unwrap(),
+                    description: "CLIENT CO".to_string(),
+                    amount_cents: 80_000,
+                    memo: None,
+                },
+            ],
+            balances: vec![SyncedBalance {
+                account_id: "ACT-1".to_string(),
+                name: "Checking".to_string(),
+                currency: Some("USD".to_string()),
+                current_cents: Some(204_510),
+                available_cents: None,
+                as_of: chrono::Utc::now(),
+            }],
+        }
+    }
+
+    #[tokio::test]
+    async fn batch_is_queued_with_provider_ids() {
+        let db = test_pool().await;
+        let summary = apply_sync_batch(&db, &batch()).await.unwrap();
+        assert_eq!(
+            summary,
+            SyncSummary {
+                queued: 2,
+                skipped: 0,
+                balances: 1
+            }
+        );
+        assert!(
+            aequi_storage::imported_transaction_exists(&db, "simplefin", "T1")
+                .await
+                .unwrap()
+        );
+        let balances = aequi_storage::get_bank_balances(&db).await.unwrap();
+        assert_eq!(balances[0].current_cents, Some(204_510));
+    }
+
+    #[tokio::test]
+    async fn resync_skips_already_imported() {
+        let db = test_pool().await;
+        apply_sync_batch(&db, &batch()).await.unwrap();
+        let summary = apply_sync_batch(&db, &batch()).await.unwrap();
+        assert_eq!(summary.queued, 0);
+        assert_eq!(summary.skipped, 2);
+        assert_eq!(
+            aequi_storage::get_bank_balances(&db).await.unwrap().len(),
+            1
+        );
+    }
+}
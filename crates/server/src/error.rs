@@ -41,3 +41,14 @@ impl From<aequi_core::LedgerError> for ApiError {
         ApiError::BadRequest(e.to_string())
     }
 }
+
+impl From<aequi_storage::receipt_status::ReceiptStatusError> for ApiError {
+    fn from(e: aequi_storage::receipt_status::ReceiptStatusError) -> Self {
+        use aequi_storage::receipt_status::ReceiptStatusError;
+        match e {
+            ReceiptStatusError::Database(e) => e.into(),
+            ReceiptStatusError::NotFound(_) => ApiError::NotFound(e.to_string()),
+            _ => ApiError::BadRequest(e.to_string()),
+        }
+    }
+}
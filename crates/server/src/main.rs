@@ -7,6 +7,8 @@ use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::EnvFilter;
 
+#[cfg(feature = "sync")]
+mod bank_sync;
 mod daimon;
 mod error;
 pub(crate) mod oidc;
@@ -95,7 +97,11 @@ async fn main() -> Result<()> {
     let (shutdown_tx, shutdown_rx) = watch::channel(false);
 
     // Spawn daimon integration (non-blocking — server starts regardless)
-    let daimon_handle = daimon::spawn_daimon_task(Arc::clone(&state), shutdown_rx);
+    let daimon_handle = daimon::spawn_daimon_task(Arc::clone(&state), shutdown_rx.clone());
+
+    // Scheduled bank aggregator sync
+    #[cfg(feature = "sync")]
+    let bank_sync_handle = bank_sync::spawn_bank_sync_task(Arc::clone(&state), shutdown_rx);
 
     let app = routes::router(state);
 
@@ -106,6 +112,8 @@ async fn main() -> Result<()> {
     // Server stopped — shut down background tasks
     let _ = shutdown_tx.send(true);
     let _ = daimon_handle.await;
+    #[cfg(feature = "sync")]
+    let _ = bank_sync_handle.await;
 
     Ok(())
 }
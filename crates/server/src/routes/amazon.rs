@@ -0,0 +1,96 @@
+use std::sync::Arc;
+
+use aequi_import::amazon::{
+    group_charges, match_charges, parse_items_report, split_charge, AmazonCategoryMap,
+    AmazonSplitLine,
+};
+use aequi_import::MatchableTransaction;
+use axum::extract::{Query, State};
+use axum::routing::post;
+use axum::{Json, Router};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ApiError;
+use crate::routes::rules::load_rule_engine;
+use crate::state::ServerState;
+
+#[derive(Deserialize)]
+struct AmazonQuery {
+    date_window_days: Option<i64>,
+    default_account: Option<String>,
+}
+
+#[derive(Serialize)]
+struct AmazonReconciliation {
+    order_id: String,
+    date: NaiveDate,
+    total_cents: i64,
+    /// Imported bank row that paid for this charge, if one was found.
+    imported_transaction_id: Option<i64>,
+    lines: Vec<AmazonSplitLine>,
+}
+
+/// Parse an Amazon order items report (CSV body), split each charge into
+/// categorized per-item lines, and pair charges with pending bank rows.
+async fn reconcile_amazon(
+    State(state): State<Arc<ServerState>>,
+    Query(q): Query<AmazonQuery>,
+    body: String,
+) -> Result<Json<Vec<AmazonReconciliation>>, ApiError> {
+    let items = parse_items_report(body.as_bytes())
+        .map_err(|e| ApiError::BadRequest(format!("Invalid Amazon report: {e}")))?;
+    let charges = group_charges(&items);
+    let window = q.date_window_days.unwrap_or(5).clamp(0, 30);
+
+    let categories = match q.default_account {
+        Some(code) => {
+            aequi_storage::get_account_by_code(&state.db, &code)
+                .await?
+                .ok_or_else(|| ApiError::BadRequest(format!("Unknown account code: {code}")))?;
+            AmazonCategoryMap::standard(code)
+        }
+        None => AmazonCategoryMap::default(),
+    };
+    let rules = load_rule_engine(&state.db).await?;
+
+    // Charges are sorted by date, so the first and last bound the search.
+    let (Some(first), Some(last)) = (charges.first(), charges.last()) else {
+        return Ok(Json(Vec::new()));
+    };
+    let span = chrono::Duration::days(window);
+    let bank: Vec<MatchableTransaction> = aequi_storage::get_open_imported_transactions(
+        &state.db,
+        &(first.date - span).to_string(),
+        &(last.date + span).to_string(),
+    )
+    .await?
+    .into_iter()
+    .filter_map(|t| {
+        Some(MatchableTransaction {
+            id: t.id,
+            date: NaiveDate::parse_from_str(&t.date, "%Y-%m-%d").ok()?,
+            description: t.description,
+            amount_cents: t.amount_cents,
+        })
+    })
+    .collect();
+
+    let matched = match_charges(&charges, &bank, window);
+    let out = charges
+        .iter()
+        .zip(matched)
+        .map(|(charge, imported_transaction_id)| AmazonReconciliation {
+            order_id: charge.order_id.clone(),
+            date: charge.date,
+            total_cents: charge.total_cents,
+            imported_transaction_id,
+            lines: split_charge(charge, &categories, &rules),
+        })
+        .collect();
+    Ok(Json(out))
+}
+
+pub fn routes() -> Router<Arc<ServerState>> {
+    Router::new().route("/import/amazon", post(reconcile_amazon))
+}
@@ -1,4 +1,5 @@
 mod accounts;
+mod amazon;
 mod health;
 mod invoices;
 mod plaid;
@@ -87,6 +88,7 @@ pub fn router(state: Arc<ServerState>) -> Router {
         .merge(reconciliation::routes())
         .merge(reports::routes())
         .merge(plaid::routes())
+        .merge(amazon::routes())
         .route_layer(middleware::from_fn_with_state(
             state.clone(),
             auth_middleware,
@@ -1,8 +1,11 @@
 use std::sync::Arc;
 
-use axum::extract::{Path, State};
+use aequi_import::{AutoMatchEngine, ReceiptLinkTarget, ReceiptMatchSuggestion};
+use aequi_storage::receipt_status::ReceiptStatus;
+use axum::extract::{Path, Query, State};
 use axum::routing::{get, post};
 use axum::{Json, Router};
+use serde::Deserialize;
 
 use crate::error::ApiError;
 use crate::state::ServerState;
@@ -18,7 +21,7 @@ async fn approve_receipt(
     State(state): State<Arc<ServerState>>,
     Path(id): Path<i64>,
 ) -> Result<Json<()>, ApiError> {
-    aequi_storage::update_receipt_status(&state.db, id, "approved").await?;
+    aequi_storage::update_receipt_status(&state.db, id, ReceiptStatus::Approved).await?;
     Ok(Json(()))
 }
 
@@ -26,7 +29,61 @@ async fn reject_receipt(
     State(state): State<Arc<ServerState>>,
     Path(id): Path<i64>,
 ) -> Result<Json<()>, ApiError> {
-    aequi_storage::update_receipt_status(&state.db, id, "rejected").await?;
+    aequi_storage::update_receipt_status(&state.db, id, ReceiptStatus::Rejected).await?;
+    Ok(Json(()))
+}
+
+#[derive(Deserialize)]
+struct MatchQuery {
+    date_window_days: Option<i32>,
+    tolerance_cents: Option<i64>,
+    min_confidence: Option<f32>,
+}
+
+async fn suggest_matches(
+    State(state): State<Arc<ServerState>>,
+    Query(q): Query<MatchQuery>,
+) -> Result<Json<Vec<ReceiptMatchSuggestion>>, ApiError> {
+    let engine = AutoMatchEngine::new(
+        q.date_window_days.unwrap_or(3).clamp(0, 30),
+        q.min_confidence.unwrap_or(0.5).clamp(0.0, 1.0),
+        q.tolerance_cents.unwrap_or(0).max(0),
+    );
+    let suggestions = aequi_storage::find_receipt_match_suggestions(&state.db, &engine).await?;
+    Ok(Json(suggestions))
+}
+
+async fn confirm_match(
+    State(state): State<Arc<ServerState>>,
+    Path(id): Path<i64>,
+    Json(target): Json<ReceiptLinkTarget>,
+) -> Result<Json<()>, ApiError> {
+    let receipt = aequi_storage::get_receipt_by_id(&state.db, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Receipt not found".to_string()))?;
+    if receipt.status == "rejected" {
+        return Err(ApiError::BadRequest(
+            "Rejected receipts cannot be linked".to_string(),
+        ));
+    }
+
+    let (sql, what) = match target {
+        ReceiptLinkTarget::Ledger(_) => ("SELECT id FROM transactions WHERE id = ?", "Transaction"),
+        ReceiptLinkTarget::Imported(_) => (
+            "SELECT id FROM imported_transactions WHERE id = ?",
+            "Imported transaction",
+        ),
+    };
+    let (ReceiptLinkTarget::Ledger(target_id) | ReceiptLinkTarget::Imported(target_id)) = target;
+    let exists: Option<(i64,)> = sqlx::query_as(sql)
+        .bind(target_id)
+        .fetch_optional(&state.db)
+        .await?;
+    if exists.is_none() {
+        return Err(ApiError::NotFound(format!("{what} not found")));
+    }
+
+    aequi_storage::confirm_receipt_match(&state.db, id, target).await?;
     Ok(Json(()))
 }
 
@@ -35,4 +92,6 @@ pub fn routes() -> Router<Arc<ServerState>> {
         .route("/receipts", get(list_receipts))
         .route("/receipts/{id}/approve", post(approve_receipt))
         .route("/receipts/{id}/reject", post(reject_receipt))
+        .route("/receipts/matches", get(suggest_matches))
+        .route("/receipts/{id}/match", post(confirm_match))
 }
@@ -3,8 +3,8 @@ use std::sync::Arc;
 use axum::extract::{Query, State};
 use axum::routing::get;
 use axum::{Json, Router};
+use chrono::Datelike;
 use serde::{Deserialize, Serialize};
-use sqlx::Row;
 
 use crate::error::ApiError;
 use crate::state::ServerState;
@@ -27,38 +27,30 @@ async fn profit_loss(
     Query(q): Query<DateRange>,
 ) -> Result<Json<Vec<ProfitLossEntry>>, ApiError> {
     let now = chrono::Utc::now().date_naive();
-    let start = q
-        .start_date
-        .unwrap_or_else(|| format!("{}-01-01", now.format("%Y")));
-    let end = q.end_date.unwrap_or_else(|| now.to_string());
-
-    let rows = sqlx::query(
-        r#"
-        SELECT a.code, a.name,
-            COALESCE(SUM(tl.credit_cents - tl.debit_cents), 0) as total_cents
-        FROM accounts a
-        LEFT JOIN transaction_lines tl ON a.id = tl.account_id
-        LEFT JOIN transactions t ON tl.transaction_id = t.id
-            AND t.date >= ? AND t.date <= ?
-        WHERE a.account_type IN ('Income', 'Expense')
-        GROUP BY a.id, a.code, a.name
-        ORDER BY a.account_type, a.code
-        "#,
+    let date = |d: Option<String>, default: chrono::NaiveDate| match d {
+        Some(d) => chrono::NaiveDate::parse_from_str(&d, "%Y-%m-%d")
+            .map_err(|e| ApiError::BadRequest(format!("Invalid date: {e}"))),
+        None => Ok(default),
+    };
+    let start = date(
+        q.start_date,
+        chrono::NaiveDate::from_ymd_opt(now.year(), 1, 1).unwrap(),
+    )?;
+    let end = date(q.end_date, now)?;
+
+    let totals = aequi_storage::account_balances::income_expense_totals(
+        &state.db,
+        aequi_core::DateRange::new(start, end),
     )
-    .bind(&start)
-    .bind(&end)
-    .fetch_all(&state.db)
     .await?;
 
     Ok(Json(
-        rows.into_iter()
-            .map(|r| {
-                let total_cents: i64 = r.get("total_cents");
-                ProfitLossEntry {
-                    account_code: r.get("code"),
-                    account_name: r.get("name"),
-                    total_cents,
-                }
+        totals
+            .into_iter()
+            .map(|n| ProfitLossEntry {
+                account_code: n.code,
+                account_name: n.name,
+                total_cents: -n.net_cents,
             })
             .collect(),
     ))
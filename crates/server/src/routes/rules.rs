@@ -1,7 +1,14 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
-use axum::extract::State;
-use axum::routing::get;
+use aequi_import::rule_sharing::{export_rule_set, import_rule_set, plan_rule_import};
+use aequi_import::{
+    BayesPrediction, CategorizableTransaction, CategorizedExample, CategoryRule,
+    CategoryRuleEngine, ConflictPolicy, NaiveBayesCategorizer, RuleSet, RuleSetFormat,
+    RuleSuggestion, SuggestOptions,
+};
+use axum::extract::{Query, State};
+use axum::routing::{get, post};
 use axum::{Json, Router};
 use serde::Deserialize;
 
@@ -35,12 +42,202 @@ async fn create_rule(
         match_pattern: input.match_pattern,
         match_type: input.match_type,
         account_id: input.account_id,
+        amount_min_cents: None,
+        amount_max_cents: None,
         created_at: String::new(),
     };
     let id = aequi_storage::save_categorization_rule(&state.db, &rule).await?;
     Ok(Json(id))
 }
 
+pub(super) async fn load_rule_engine(
+    db: &aequi_storage::DbPool,
+) -> Result<CategoryRuleEngine, ApiError> {
+    let rules = aequi_storage::get_category_rules(db)
+        .await?
+        .into_iter()
+        .map(|(_, r)| r)
+        .collect();
+    Ok(CategoryRuleEngine::new(rules))
+}
+
+async fn load_history(
+    db: &aequi_storage::DbPool,
+    limit: i64,
+) -> Result<Vec<CategorizedExample>, ApiError> {
+    Ok(aequi_storage::get_categorized_history(db, limit)
+        .await?
+        .into_iter()
+        .map(|row| CategorizedExample {
+            description: row.description,
+            amount_cents: row.amount_cents,
+            account_code: row.account_code,
+        })
+        .collect())
+}
+
+#[derive(Deserialize)]
+struct SuggestQuery {
+    min_support: Option<usize>,
+    limit: Option<i64>,
+}
+
+async fn suggest_rules(
+    State(state): State<Arc<ServerState>>,
+    Query(q): Query<SuggestQuery>,
+) -> Result<Json<Vec<RuleSuggestion>>, ApiError> {
+    let history = load_history(&state.db, q.limit.unwrap_or(2000)).await?;
+    let engine = load_rule_engine(&state.db).await?;
+    let mut options = SuggestOptions::default();
+    if let Some(min_support) = q.min_support {
+        options.min_support = min_support.max(1);
+    }
+    Ok(Json(aequi_import::suggest_rules(
+        &history, &engine, &options,
+    )))
+}
+
+#[derive(Deserialize)]
+struct PredictInput {
+    description: String,
+    amount_cents: i64,
+}
+
+#[derive(Deserialize)]
+struct PredictQuery {
+    min_confidence: Option<f64>,
+}
+
+#[derive(serde::Serialize)]
+struct PredictOutput {
+    index: usize,
+    #[serde(flatten)]
+    prediction: BayesPrediction,
+}
+
+/// Propose accounts for transactions no stored rule matches. Results are for
+/// the review queue only; nothing is posted.
+async fn predict_accounts(
+    State(state): State<Arc<ServerState>>,
+    Query(q): Query<PredictQuery>,
+    Json(input): Json<Vec<PredictInput>>,
+) -> Result<Json<Vec<PredictOutput>>, ApiError> {
+    let history = load_history(&state.db, 5000).await?;
+    let model = NaiveBayesCategorizer::train(&history);
+    let engine = load_rule_engine(&state.db).await?;
+    let txs: Vec<CategorizableTransaction> = input
+        .into_iter()
+        .map(|i| CategorizableTransaction {
+            date: chrono::Utc::now().date_naive(),
+            description: i.description,
+            amount_cents: i.amount_cents,
+            memo: None,
+        })
+        .collect();
+    let min_confidence = q.min_confidence.unwrap_or(0.6).clamp(0.0, 1.0);
+    let out = model
+        .suggest_unmatched(&engine, &txs, min_confidence)
+        .into_iter()
+        .map(|(index, prediction)| PredictOutput { index, prediction })
+        .collect();
+    Ok(Json(out))
+}
+
+#[derive(Deserialize)]
+struct ExportQuery {
+    format: Option<String>,
+}
+
+fn parse_format(format: Option<&str>) -> Result<RuleSetFormat, ApiError> {
+    format
+        .unwrap_or("toml")
+        .parse()
+        .map_err(ApiError::BadRequest)
+}
+
+async fn export_rules(
+    State(state): State<Arc<ServerState>>,
+    Query(q): Query<ExportQuery>,
+) -> Result<String, ApiError> {
+    let format = parse_format(q.format.as_deref())?;
+    let rules = aequi_storage::get_category_rules(&state.db)
+        .await?
+        .into_iter()
+        .map(|(_, r)| r)
+        .collect();
+    export_rule_set(&RuleSet::new(rules), format).map_err(|e| ApiError::Internal(e.to_string()))
+}
+
+#[derive(Deserialize)]
+struct ImportQuery {
+    format: Option<String>,
+    #[serde(default)]
+    policy: ConflictPolicy,
+}
+
+#[derive(serde::Serialize)]
+struct ImportSummary {
+    inserted: usize,
+    replaced: usize,
+    unchanged: usize,
+    skipped: usize,
+}
+
+async fn import_rules(
+    State(state): State<Arc<ServerState>>,
+    Query(q): Query<ImportQuery>,
+    body: String,
+) -> Result<Json<ImportSummary>, ApiError> {
+    let format = parse_format(q.format.as_deref())?;
+    let set = import_rule_set(&body, format).map_err(|e| ApiError::BadRequest(e.to_string()))?;
+
+    let account_ids: HashMap<String, i64> = aequi_storage::get_all_accounts(&state.db)
+        .await?
+        .into_iter()
+        .filter_map(|a| a.id.map(|id| (a.code, id.0)))
+        .collect();
+    if let Some(unknown) = set
+        .rules
+        .iter()
+        .find(|r| !account_ids.contains_key(&r.account_code))
+    {
+        return Err(ApiError::BadRequest(format!(
+            "rule '{}' references unknown account {}",
+            unknown.name, unknown.account_code
+        )));
+    }
+
+    let stored = aequi_storage::get_category_rules(&state.db).await?;
+    let existing: Vec<CategoryRule> = stored.iter().map(|(_, r)| r.clone()).collect();
+    let plan = plan_rule_import(&existing, set.rules, q.policy);
+
+    let to_row = |id: i64, rule: &CategoryRule| {
+        aequi_storage::CategorizationRule::from_category_rule(
+            id,
+            rule,
+            account_ids[&rule.account_code],
+        )
+    };
+    for rule in &plan.to_insert {
+        aequi_storage::save_categorization_rule(&state.db, &to_row(0, rule)).await?;
+    }
+    for (idx, rule) in &plan.to_replace {
+        aequi_storage::update_categorization_rule(&state.db, &to_row(stored[*idx].0, rule)).await?;
+    }
+
+    Ok(Json(ImportSummary {
+        inserted: plan.to_insert.len(),
+        replaced: plan.to_replace.len(),
+        unchanged: plan.unchanged.len(),
+        skipped: plan.skipped.len(),
+    }))
+}
+
 pub fn routes() -> Router<Arc<ServerState>> {
-    Router::new().route("/rules", get(list_rules).post(create_rule))
+    Router::new()
+        .route("/rules", get(list_rules).post(create_rule))
+        .route("/rules/suggestions", get(suggest_rules))
+        .route("/rules/predict", post(predict_accounts))
+        .route("/rules/export", get(export_rules))
+        .route("/rules/import", post(import_rules))
 }
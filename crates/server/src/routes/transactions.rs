@@ -5,7 +5,9 @@ use axum::routing::get;
 use axum::{Json, Router};
 use serde::{Deserialize, Serialize};
 
-use aequi_core::{Money, TransactionLine, UnvalidatedTransaction, ValidatedTransaction};
+use aequi_core::{
+    Deductibility, Money, TransactionLine, UnvalidatedTransaction, ValidatedTransaction,
+};
 
 use crate::error::ApiError;
 use crate::state::ServerState;
@@ -38,20 +40,16 @@ struct TransactionOut {
 async fn list_transactions(
     State(state): State<Arc<ServerState>>,
 ) -> Result<Json<Vec<TransactionOut>>, ApiError> {
-    let rows = sqlx::query_as::<_, (i64, String, String, Option<String>, i64)>(
-        "SELECT id, date, description, memo, balanced_total_cents FROM transactions ORDER BY date DESC, id DESC LIMIT 1000"
-    )
-    .fetch_all(&state.db)
-    .await?;
+    let rows = aequi_storage::get_transactions(&state.db, None, Some(1000)).await?;
 
     Ok(Json(
         rows.into_iter()
             .map(|r| TransactionOut {
-                id: r.0,
-                date: r.1,
-                description: r.2,
-                memo: r.3,
-                balanced_total_cents: r.4,
+                id: r.id,
+                date: r.date,
+                description: r.description,
+                memo: r.memo,
+                balanced_total_cents: r.balanced_total_cents,
             })
             .collect(),
     ))
@@ -109,39 +107,17 @@ async fn create_transaction(
 
     let mut db_tx = state.db.begin().await?;
 
-    let row = sqlx::query_as::<_, (i64,)>(
-        "INSERT INTO transactions (date, description, memo, balanced_total_cents) VALUES (?, ?, ?, ?) RETURNING id"
-    )
-    .bind(validated.date.to_string())
-    .bind(&validated.description)
-    .bind(&validated.memo)
-    .bind(validated.balanced_total.to_cents())
-    .fetch_one(&mut *db_tx)
-    .await?;
-
-    let id = row.0;
-
-    for line in &validated.lines {
-        sqlx::query(
-            "INSERT INTO transaction_lines (transaction_id, account_id, debit_cents, credit_cents, memo) VALUES (?, ?, ?, ?, ?)"
-        )
-        .bind(id)
-        .bind(line.account_id.0)
-        .bind(line.debit.to_cents())
-        .bind(line.credit.to_cents())
-        .bind(&line.memo)
-        .execute(&mut *db_tx)
-        .await?;
-    }
+    let record =
+        aequi_storage::insert_transaction(&mut db_tx, &validated, Deductibility::default()).await?;
 
     db_tx.commit().await?;
 
     Ok(Json(TransactionOut {
-        id,
-        date: validated.date.to_string(),
-        description: validated.description,
-        balanced_total_cents: validated.balanced_total.to_cents(),
-        memo: validated.memo,
+        id: record.id,
+        date: record.date,
+        description: record.description,
+        balanced_total_cents: record.balanced_total_cents,
+        memo: record.memo,
     }))
 }
 
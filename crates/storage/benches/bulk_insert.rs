@@ -0,0 +1,147 @@
+//! Posting an import batch: a statement and commit per transaction against
+//! `insert_transactions_bulk`. Each run starts from a new database file with
+//! the app's journal settings, so earlier runs don't slow later ones.
+//!
+//! Run with `cargo bench -p aequi-storage --bench bulk_insert`.
+
+use std::path::Path;
+
+use aequi_core::{AccountId, Money, TransactionLine, UnvalidatedTransaction, ValidatedTransaction};
+use aequi_storage::{get_account_by_code, insert_transactions_bulk, seed_default_accounts, DbPool};
+use chrono::NaiveDate;
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous};
+use tempfile::TempDir;
+use tokio::runtime::Runtime;
+
+const BATCH_SIZES: [usize; 2] = [100, 500];
+
+/// A new database file, migrated and seeded. One connection, so the
+/// migrations run in order on it.
+async fn bench_db(path: &Path) -> DbPool {
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal)
+        .synchronous(SqliteSynchronous::Normal)
+        .foreign_keys(true);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(options)
+        .await
+        .unwrap();
+    aequi_storage::migrate::run_migrations(&pool).await.unwrap();
+    seed_default_accounts(&pool).await.unwrap();
+    pool
+}
+
+fn fresh_db(rt: &Runtime) -> (TempDir, DbPool) {
+    let dir = tempfile::tempdir().unwrap();
+    let pool = rt.block_on(bench_db(&dir.path().join("bench.db")));
+    (dir, pool)
+}
+
+/// `count` card purchases out of the bank account.
+fn batch(expense: AccountId, bank: AccountId, count: usize) -> Vec<ValidatedTransaction> {
+    (0..count)
+        .map(|n| {
+            let amount = Money::from_cents(1_000 + n as i64);
+            ValidatedTransaction::validate(UnvalidatedTransaction {
+                date: NaiveDate::from_ymd_opt(2026, 3, 1).unwrap(),
+                description: format!("CARD PURCHASE {n}"),
+                lines: vec![
+                    TransactionLine {
+                        account_id: expense,
+                        debit: amount,
+                        credit: Money::zero(),
+                        memo: None,
+                    },
+                    TransactionLine {
+                        account_id: bank,
+                        debit: Money::zero(),
+                        credit: amount,
+                        memo: None,
+                    },
+                ],
+                memo: None,
+            })
+            .unwrap()
+        })
+        .collect()
+}
+
+/// Each transaction in its own statements and commit.
+async fn insert_per_row(pool: &DbPool, transactions: &[ValidatedTransaction]) {
+    for t in transactions {
+        let mut tx = pool.begin().await.unwrap();
+        let id: i64 = sqlx::query_scalar(
+            "INSERT INTO transactions (date, description, memo, balanced_total_cents) VALUES (?, ?, ?, ?) RETURNING id",
+        )
+        .bind(t.date.to_string())
+        .bind(&t.description)
+        .bind(&t.memo)
+        .bind(t.balanced_total.to_cents())
+        .fetch_one(&mut *tx)
+        .await
+        .unwrap();
+        for line in &t.lines {
+            sqlx::query(
+                "INSERT INTO transaction_lines (transaction_id, account_id, debit_cents, credit_cents, memo) VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(id)
+            .bind(line.account_id.0)
+            .bind(line.debit.to_cents())
+            .bind(line.credit.to_cents())
+            .bind(&line.memo)
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        }
+        tx.commit().await.unwrap();
+    }
+}
+
+fn bench_bulk_insert(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    // Every new database seeds the same accounts, so their ids hold for all.
+    let (expense, bank) = {
+        let (_dir, pool) = fresh_db(&rt);
+        let id = |code| {
+            rt.block_on(get_account_by_code(&pool, code))
+                .unwrap()
+                .and_then(|a| a.id)
+                .unwrap()
+        };
+        (id("5100"), id("1000"))
+    };
+
+    let mut group = c.benchmark_group("post_import_batch");
+    group.sample_size(20);
+    for size in BATCH_SIZES {
+        let transactions = batch(expense, bank, size);
+        group.bench_with_input(BenchmarkId::new("per_row", size), &transactions, |b, t| {
+            b.iter_batched(
+                || fresh_db(&rt),
+                |db| {
+                    rt.block_on(insert_per_row(&db.1, t));
+                    db
+                },
+                BatchSize::PerIteration,
+            );
+        });
+        group.bench_with_input(BenchmarkId::new("bulk", size), &transactions, |b, t| {
+            b.iter_batched(
+                || fresh_db(&rt),
+                |db| {
+                    rt.block_on(insert_transactions_bulk(&db.1, t)).unwrap();
+                    db
+                },
+                BatchSize::PerIteration,
+            );
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_bulk_insert);
+criterion_main!(benches);
@@ -0,0 +1,327 @@
+//! Debits and credits per account per month, so totals over long periods
+//! don't have to add up every line.
+//!
+//! Triggers keep `account_balances` in step with the ledger as lines and
+//! transactions are written, changed and removed (see the V026 migration).
+//! [`net_debits`] takes whole months in a range from it and reads lines only
+//! for the part months at either end. [`rebuild_account_balances`] recomputes
+//! the table from the lines, should it ever disagree with them.
+
+use std::collections::HashMap;
+
+use aequi_core::DateRange;
+use chrono::{Datelike, Days, NaiveDate};
+use serde::Serialize;
+
+use crate::db::DbPool;
+
+/// An account's debits less credits over a period.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, sqlx::FromRow)]
+pub struct AccountNet {
+    pub account_id: i64,
+    pub code: String,
+    pub name: String,
+    pub account_type: String,
+    pub net_cents: i64,
+}
+
+/// How a date range divides between the rollup and the lines.
+#[derive(Debug, PartialEq, Eq)]
+struct Split {
+    /// First and last whole month (`YYYY-MM`); the first is empty when the
+    /// range has no start.
+    months: Option<(String, String)>,
+    /// Dates before the first whole month, or the whole range if it has
+    /// none. The start is `None` when the range has no start.
+    head: Option<(Option<NaiveDate>, NaiveDate)>,
+    /// Dates after the last whole month.
+    tail: Option<(NaiveDate, NaiveDate)>,
+}
+
+fn month_key(date: NaiveDate) -> String {
+    date.format("%Y-%m").to_string()
+}
+
+fn first_of_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap()
+}
+
+fn split(start: Option<NaiveDate>, end: NaiveDate) -> Split {
+    let first_whole = match start {
+        Some(s) if s.day() == 1 => Some(s),
+        Some(s) => first_of_month(s).checked_add_months(chrono::Months::new(1)),
+        None => None,
+    };
+    let last_whole_end = if end.succ_opt().is_some_and(|next| next.day() == 1) {
+        end
+    } else {
+        first_of_month(end) - Days::new(1)
+    };
+    let has_whole_month = match first_whole {
+        Some(first) => first <= last_whole_end,
+        None => start.is_none(),
+    };
+    if !has_whole_month {
+        return Split {
+            months: None,
+            head: Some((start, end)),
+            tail: None,
+        };
+    }
+    Split {
+        months: Some((
+            first_whole.map(month_key).unwrap_or_default(),
+            month_key(last_whole_end),
+        )),
+        head: start
+            .zip(first_whole)
+            .filter(|(s, first)| s < first)
+            .map(|(s, first)| (Some(s), first - Days::new(1))),
+        tail: (last_whole_end < end).then(|| (last_whole_end + Days::new(1), end)),
+    }
+}
+
+/// Net debits per account from transactions dated `start` (if given)
+/// through `end`, leaving out accounts with none. `business_only` leaves
+/// out personal transactions.
+pub async fn net_debits(
+    pool: &DbPool,
+    start: Option<NaiveDate>,
+    end: NaiveDate,
+    business_only: bool,
+) -> Result<Vec<AccountNet>, sqlx::Error> {
+    let Split { months, head, tail } = split(start, end);
+    let (first_month, last_month) = months.unzip();
+    let (head_start, head_end) = head.unzip();
+    let (tail_start, tail_end) = tail.unzip();
+    sqlx::query_as::<_, AccountNet>(
+        r#"SELECT a.id AS account_id, a.code, a.name, a.account_type,
+                  SUM(n.net) AS net_cents
+           FROM (
+               SELECT account_id, debit_cents - credit_cents AS net
+               FROM account_balances
+               WHERE month >= ?1 AND month <= ?2 AND (?7 = 0 OR is_personal = 0)
+               UNION ALL
+               SELECT tl.account_id, tl.debit_cents - tl.credit_cents
+               FROM transaction_lines tl
+               JOIN transactions t ON t.id = tl.transaction_id
+               WHERE ((t.date >= COALESCE(?3, '') AND t.date <= ?4)
+                      OR (t.date >= ?5 AND t.date <= ?6))
+                 AND (?7 = 0 OR t.is_personal = 0)
+           ) n
+           JOIN accounts a ON a.id = n.account_id
+           GROUP BY a.id
+           HAVING net_cents != 0
+           ORDER BY a.code"#,
+    )
+    .bind(first_month)
+    .bind(last_month)
+    .bind(head_start.flatten().map(|d| d.to_string()))
+    .bind(head_end.map(|d| d.to_string()))
+    .bind(tail_start.map(|d| d.to_string()))
+    .bind(tail_end.map(|d| d.to_string()))
+    .bind(business_only)
+    .fetch_all(pool)
+    .await
+}
+
+/// Every income and expense account, by type and code, with its net debits
+/// from business transactions over `period` (zero when it has none).
+pub async fn income_expense_totals(
+    pool: &DbPool,
+    period: DateRange,
+) -> Result<Vec<AccountNet>, sqlx::Error> {
+    let mut net: HashMap<i64, i64> = net_debits(pool, Some(period.start), period.end, true)
+        .await?
+        .into_iter()
+        .map(|n| (n.account_id, n.net_cents))
+        .collect();
+    let mut accounts = sqlx::query_as::<_, AccountNet>(
+        r#"SELECT id AS account_id, code, name, account_type, 0 AS net_cents
+           FROM accounts
+           WHERE account_type IN ('Income', 'Expense')
+           ORDER BY account_type, code"#,
+    )
+    .fetch_all(pool)
+    .await?;
+    for account in &mut accounts {
+        account.net_cents = net.remove(&account.account_id).unwrap_or(0);
+    }
+    Ok(accounts)
+}
+
+/// Recompute the rollup from the lines. Returns how many account-months it
+/// now holds.
+pub async fn rebuild_account_balances(pool: &DbPool) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM account_balances")
+        .execute(&mut *tx)
+        .await?;
+    let rows = sqlx::query(
+        r#"INSERT INTO account_balances (account_id, month, is_personal, debit_cents, credit_cents)
+           SELECT tl.account_id, substr(t.date, 1, 7), t.is_personal,
+                  SUM(tl.debit_cents), SUM(tl.credit_cents)
+           FROM transaction_lines tl
+           JOIN transactions t ON t.id = tl.transaction_id
+           GROUP BY tl.account_id, substr(t.date, 1, 7), t.is_personal"#,
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    tx.commit().await?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    /// Post `cents` from `credit` to `debit` on `date`.
+    async fn post(pool: &DbPool, date: &str, debit: &str, credit: &str, cents: i64) -> i64 {
+        let id: i64 = sqlx::query_scalar(
+            "INSERT INTO transactions (date, description, balanced_total_cents) VALUES (?, 'Entry', ?) RETURNING id",
+        )
+        .bind(date)
+        .bind(cents)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        for (code, d, c) in [(debit, cents, 0), (credit, 0, cents)] {
+            sqlx::query(
+                r#"INSERT INTO transaction_lines (transaction_id, account_id, debit_cents, credit_cents)
+                   VALUES (?, (SELECT id FROM accounts WHERE code = ?), ?, ?)"#,
+            )
+            .bind(id)
+            .bind(code)
+            .bind(d)
+            .bind(c)
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+        id
+    }
+
+    /// The rollup as it stands, against what the lines add up to.
+    async fn rollup_and_lines(
+        pool: &DbPool,
+    ) -> (Vec<(i64, String, i64, i64)>, Vec<(i64, String, i64, i64)>) {
+        let rollup = sqlx::query_as(
+            r#"SELECT account_id, month, debit_cents, credit_cents FROM account_balances
+               WHERE debit_cents != 0 OR credit_cents != 0
+               ORDER BY account_id, month"#,
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap();
+        let lines = sqlx::query_as(
+            r#"SELECT tl.account_id, substr(t.date, 1, 7), SUM(tl.debit_cents), SUM(tl.credit_cents)
+               FROM transaction_lines tl JOIN transactions t ON t.id = tl.transaction_id
+               GROUP BY tl.account_id, substr(t.date, 1, 7)
+               HAVING SUM(tl.debit_cents) != 0 OR SUM(tl.credit_cents) != 0
+               ORDER BY tl.account_id, substr(t.date, 1, 7)"#,
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap();
+        (rollup, lines)
+    }
+
+    #[test]
+    fn ranges_split_into_whole_and_part_months() {
+        assert_eq!(
+            split(Some(date("2026-01-15")), date("2026-04-10")),
+            Split {
+                months: Some(("2026-02".into(), "2026-03".into())),
+                head: Some((Some(date("2026-01-15")), date("2026-01-31"))),
+                tail: Some((date("2026-04-01"), date("2026-04-10"))),
+            }
+        );
+        assert_eq!(
+            split(Some(date("2026-01-01")), date("2026-12-31")),
+            Split {
+                months: Some(("2026-01".into(), "2026-12".into())),
+                head: None,
+                tail: None,
+            }
+        );
+        assert_eq!(
+            split(None, date("2026-02-28")).months,
+            Some((String::new(), "2026-02".into()))
+        );
+        // Inside one month there is nothing whole to take.
+        assert_eq!(
+            split(Some(date("2026-03-02")), date("2026-03-30")),
+            Split {
+                months: None,
+                head: Some((Some(date("2026-03-02")), date("2026-03-30"))),
+                tail: None,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn rollup_follows_the_ledger() {
+        let pool = test_pool().await;
+        let rent = post(&pool, "2026-01-05", "5100", "1000", 120_000).await;
+        post(&pool, "2026-01-20", "1000", "4000", 300_000).await;
+        let sale = post(&pool, "2026-02-10", "1000", "4000", 50_000).await;
+        post(&pool, "2026-03-31", "5100", "2000", 2_500).await;
+        let (rollup, lines) = rollup_and_lines(&pool).await;
+        assert_eq!(rollup, lines);
+
+        // Edits, moves and deletes all carry through.
+        sqlx::query("UPDATE transaction_lines SET debit_cents = 125000 WHERE transaction_id = ? AND debit_cents > 0")
+            .bind(rent)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE transactions SET date = '2026-03-01', is_personal = 1 WHERE id = ?")
+            .bind(sale)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM transactions WHERE id = ?")
+            .bind(rent)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let (rollup, lines) = rollup_and_lines(&pool).await;
+        assert_eq!(rollup, lines);
+
+        // Mid-month to mid-month takes February whole and the ends from
+        // the lines; business only leaves out the sale now personal.
+        let net = net_debits(&pool, Some(date("2026-01-15")), date("2026-03-31"), true)
+            .await
+            .unwrap();
+        let by_code: Vec<(&str, i64)> =
+            net.iter().map(|n| (n.code.as_str(), n.net_cents)).collect();
+        assert_eq!(
+            by_code,
+            [
+                ("1000", 300_000),
+                ("2000", -2_500),
+                ("4000", -300_000),
+                ("5100", 2_500)
+            ]
+        );
+        let all = net_debits(&pool, None, date("2026-03-15"), false)
+            .await
+            .unwrap();
+        let cash = all.iter().find(|n| n.code == "1000").unwrap();
+        assert_eq!(cash.net_cents, 350_000);
+
+        sqlx::query("DELETE FROM account_balances")
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(rebuild_account_balances(&pool).await.unwrap(), 6);
+        let (rollup, lines) = rollup_and_lines(&pool).await;
+        assert_eq!(rollup, lines);
+    }
+}
@@ -0,0 +1,339 @@
+//! Receivable and payable aging: what customers owe on invoices and what is
+//! owed to vendors on bills, by how long past due it is.
+//!
+//! Both are worked out as of a date from the documents issued by then and
+//! the payments recorded by then, so an aging for a past month-end shows what
+//! was open at the time whatever has been paid since.
+
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use serde::Serialize;
+
+use crate::db::{get_invoice_lines, get_invoice_tax_lines, DbPool, InvoiceRecord};
+use crate::error::StorageError;
+use crate::payables::Bill;
+use crate::receivables::records_to_invoice;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgingBucket {
+    /// Not yet due.
+    Current,
+    #[serde(rename = "days_1_30")]
+    Days1To30,
+    #[serde(rename = "days_31_60")]
+    Days31To60,
+    #[serde(rename = "days_61_90")]
+    Days61To90,
+    #[serde(rename = "over_90")]
+    Over90,
+}
+
+impl AgingBucket {
+    pub fn for_days_overdue(days: i64) -> Self {
+        match days {
+            ..=0 => AgingBucket::Current,
+            1..=30 => AgingBucket::Days1To30,
+            31..=60 => AgingBucket::Days31To60,
+            61..=90 => AgingBucket::Days61To90,
+            _ => AgingBucket::Over90,
+        }
+    }
+}
+
+/// Amounts open in each bucket.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AgingTotals {
+    pub current_cents: i64,
+    pub days_1_30_cents: i64,
+    pub days_31_60_cents: i64,
+    pub days_61_90_cents: i64,
+    pub over_90_cents: i64,
+    pub total_cents: i64,
+}
+
+impl AgingTotals {
+    fn add(&mut self, bucket: AgingBucket, cents: i64) {
+        *match bucket {
+            AgingBucket::Current => &mut self.current_cents,
+            AgingBucket::Days1To30 => &mut self.days_1_30_cents,
+            AgingBucket::Days31To60 => &mut self.days_31_60_cents,
+            AgingBucket::Days61To90 => &mut self.days_61_90_cents,
+            AgingBucket::Over90 => &mut self.over_90_cents,
+        } += cents;
+        self.total_cents += cents;
+    }
+}
+
+/// An invoice or bill with something still open.
+#[derive(Debug, Clone, Serialize)]
+pub struct AgingItem {
+    /// The invoice's or bill's id.
+    pub id: i64,
+    /// Invoice number, or the vendor's bill number.
+    pub number: Option<String>,
+    pub contact_id: i64,
+    pub contact_name: String,
+    pub issue_date: String,
+    pub due_date: String,
+    pub total_cents: i64,
+    pub open_cents: i64,
+    /// Negative while not yet due.
+    pub days_overdue: i64,
+    pub bucket: AgingBucket,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AgingContact {
+    pub contact_id: i64,
+    pub contact_name: String,
+    pub totals: AgingTotals,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AgingReport {
+    pub as_of: NaiveDate,
+    /// Most overdue first.
+    pub items: Vec<AgingItem>,
+    /// Per customer or vendor, largest balance first.
+    pub contacts: Vec<AgingContact>,
+    pub totals: AgingTotals,
+}
+
+impl AgingReport {
+    fn new(as_of: NaiveDate, mut items: Vec<AgingItem>) -> Self {
+        items.sort_by(|a, b| {
+            b.days_overdue
+                .cmp(&a.days_overdue)
+                .then_with(|| a.contact_name.cmp(&b.contact_name))
+                .then(a.id.cmp(&b.id))
+        });
+        let mut totals = AgingTotals::default();
+        let mut contacts: BTreeMap<i64, AgingContact> = BTreeMap::new();
+        for item in &items {
+            totals.add(item.bucket, item.open_cents);
+            contacts
+                .entry(item.contact_id)
+                .or_insert_with(|| AgingContact {
+                    contact_id: item.contact_id,
+                    contact_name: item.contact_name.clone(),
+                    totals: AgingTotals::default(),
+                })
+                .totals
+                .add(item.bucket, item.open_cents);
+        }
+        let mut contacts: Vec<_> = contacts.into_values().collect();
+        contacts.sort_by(|a, b| {
+            b.totals
+                .total_cents
+                .cmp(&a.totals.total_cents)
+                .then_with(|| a.contact_name.cmp(&b.contact_name))
+        });
+        AgingReport {
+            as_of,
+            items,
+            contacts,
+            totals,
+        }
+    }
+}
+
+/// Days past due as of the date, negative while not yet due.
+fn days_overdue(as_of: NaiveDate, due_date: &str) -> i64 {
+    let due = NaiveDate::parse_from_str(due_date, "%Y-%m-%d").unwrap_or(as_of);
+    (as_of - due).num_days()
+}
+
+/// What customers owed on invoices sent by `as_of`. Drafts and void invoices
+/// are left out.
+pub async fn ar_aging(pool: &DbPool, as_of: NaiveDate) -> Result<AgingReport, StorageError> {
+    let as_of_str = as_of.to_string();
+    let invoices = sqlx::query_as::<_, InvoiceRecord>(
+        r#"SELECT * FROM invoices
+           WHERE status_type NOT IN ('Draft', 'Void') AND issue_date <= ?
+           ORDER BY due_date"#,
+    )
+    .bind(&as_of_str)
+    .fetch_all(pool)
+    .await?;
+
+    let mut items = Vec::new();
+    for rec in invoices {
+        let lines = get_invoice_lines(pool, rec.id).await?;
+        let tax_lines = get_invoice_tax_lines(pool, rec.id).await?;
+        let total_cents = records_to_invoice(&rec, &lines, &tax_lines)?
+            .total()
+            .to_cents();
+        let paid_cents: i64 = sqlx::query_scalar(
+            "SELECT COALESCE(SUM(amount_cents), 0) FROM payments WHERE invoice_id = ? AND date <= ?",
+        )
+        .bind(rec.id)
+        .bind(&as_of_str)
+        .fetch_one(pool)
+        .await?;
+        let contact_name: String = sqlx::query_scalar("SELECT name FROM contacts WHERE id = ?")
+            .bind(rec.contact_id)
+            .fetch_optional(pool)
+            .await?
+            .unwrap_or_default();
+        if total_cents <= paid_cents {
+            continue;
+        }
+        let days_overdue = days_overdue(as_of, &rec.due_date);
+        items.push(AgingItem {
+            id: rec.id,
+            number: Some(rec.invoice_number),
+            contact_id: rec.contact_id,
+            contact_name,
+            issue_date: rec.issue_date,
+            due_date: rec.due_date,
+            total_cents,
+            open_cents: total_cents - paid_cents,
+            days_overdue,
+            bucket: AgingBucket::for_days_overdue(days_overdue),
+        });
+    }
+    Ok(AgingReport::new(as_of, items))
+}
+
+/// What was owed to vendors on bills dated by `as_of`. Void bills are left
+/// out.
+pub async fn ap_aging(pool: &DbPool, as_of: NaiveDate) -> Result<AgingReport, sqlx::Error> {
+    let bills = sqlx::query_as::<_, Bill>(
+        r#"SELECT b.*, c.name AS contact_name,
+                  COALESCE((SELECT SUM(amount_cents) FROM bill_payments
+                            WHERE bill_id = b.id AND date <= ?1), 0) AS paid_cents
+           FROM bills b JOIN contacts c ON c.id = b.contact_id
+           WHERE b.is_void = 0 AND b.bill_date <= ?1"#,
+    )
+    .bind(as_of.to_string())
+    .fetch_all(pool)
+    .await?;
+
+    let items = bills
+        .into_iter()
+        .filter(|bill| bill.open_cents() > 0)
+        .map(|bill| {
+            let days_overdue = days_overdue(as_of, &bill.due_date);
+            AgingItem {
+                id: bill.id,
+                open_cents: bill.open_cents(),
+                number: bill.bill_number,
+                contact_id: bill.contact_id,
+                contact_name: bill.contact_name,
+                issue_date: bill.bill_date,
+                due_date: bill.due_date,
+                total_cents: bill.amount_cents,
+                days_overdue,
+                bucket: AgingBucket::for_days_overdue(days_overdue),
+            }
+        })
+        .collect();
+    Ok(AgingReport::new(as_of, items))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    fn date(m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, m, d).unwrap()
+    }
+
+    #[test]
+    fn buckets_by_days_overdue() {
+        assert_eq!(AgingBucket::for_days_overdue(-5), AgingBucket::Current);
+        assert_eq!(AgingBucket::for_days_overdue(0), AgingBucket::Current);
+        assert_eq!(AgingBucket::for_days_overdue(30), AgingBucket::Days1To30);
+        assert_eq!(AgingBucket::for_days_overdue(31), AgingBucket::Days31To60);
+        assert_eq!(AgingBucket::for_days_overdue(90), AgingBucket::Days61To90);
+        assert_eq!(AgingBucket::for_days_overdue(91), AgingBucket::Over90);
+    }
+
+    #[tokio::test]
+    async fn ar_and_ap_aging_as_of_a_date() {
+        let pool = test_pool().await;
+        sqlx::query("INSERT INTO contacts (id, name) VALUES (1, 'Acme'), (2, 'Globex')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        // (number, contact, status, issue, due, cents)
+        for (number, contact, status, issue, due, cents) in [
+            ("INV-1", 1, "Sent", "2026-01-01", "2026-01-31", 100_000),
+            (
+                "INV-2",
+                2,
+                "PartiallyPaid",
+                "2026-03-01",
+                "2026-03-31",
+                50_000,
+            ),
+            ("INV-3", 1, "Sent", "2026-04-20", "2026-05-20", 20_000),
+            ("INV-4", 1, "Draft", "2026-04-01", "2026-04-30", 9_900),
+        ] {
+            let id: i64 = sqlx::query_scalar(
+                r#"INSERT INTO invoices (invoice_number, contact_id, status_type, issue_date, due_date)
+                   VALUES (?, ?, ?, ?, ?) RETURNING id"#,
+            )
+            .bind(number)
+            .bind(contact)
+            .bind(status)
+            .bind(issue)
+            .bind(due)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+            sqlx::query(
+                r#"INSERT INTO invoice_lines (invoice_id, description, quantity_hundredths, unit_rate_cents)
+                   VALUES (?, 'Work', 100, ?)"#,
+            )
+            .bind(id)
+            .bind(cents)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        // INV-2 part paid before the as-of date; a later payment is ignored.
+        sqlx::query(
+            r#"INSERT INTO payments (invoice_id, amount_cents, date)
+               VALUES (2, 20000, '2026-04-10'), (2, 30000, '2026-05-10')"#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let ar = ar_aging(&pool, date(4, 30)).await.unwrap();
+        let open: Vec<_> = ar
+            .items
+            .iter()
+            .map(|i| (i.number.as_deref().unwrap(), i.open_cents, i.bucket))
+            .collect();
+        assert_eq!(
+            open,
+            [
+                ("INV-1", 100_000, AgingBucket::Days61To90),
+                ("INV-2", 30_000, AgingBucket::Days1To30),
+                ("INV-3", 20_000, AgingBucket::Current),
+            ]
+        );
+        assert_eq!(ar.totals.total_cents, 150_000);
+        assert_eq!(ar.contacts[0].contact_name, "Acme");
+        assert_eq!(ar.contacts[0].totals.total_cents, 120_000);
+
+        sqlx::query(
+            r#"INSERT INTO bills (contact_id, bill_date, due_date, amount_cents, account_id)
+               VALUES (2, '2026-01-01', '2026-01-15', 40000, 1)"#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let ap = ap_aging(&pool, date(4, 30)).await.unwrap();
+        assert_eq!(ap.items.len(), 1);
+        assert_eq!(ap.items[0].bucket, AgingBucket::Over90);
+        assert_eq!(ap.totals.over_90_cents, 40_000);
+        let before = NaiveDate::from_ymd_opt(2025, 12, 31).unwrap();
+        assert!(ap_aging(&pool, before).await.unwrap().items.is_empty());
+    }
+}
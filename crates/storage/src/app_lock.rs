@@ -0,0 +1,377 @@
+//! Passphrase lock for the app.
+//!
+//! With a passphrase (or PIN) set, the app starts locked and locks again
+//! after a stretch of inactivity. Only a salted PBKDF2 hash of the
+//! passphrase is stored. Wrong guesses are counted across restarts; after a
+//! few, each further one shuts out unlocking for longer.
+
+use std::num::NonZeroU32;
+
+use chrono::NaiveDateTime;
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+
+use crate::db::{get_setting, set_setting, DbPool};
+
+/// Setting holding the [`AppLockSettings`], as JSON.
+pub const APP_LOCK_SETTING: &str = "app_lock";
+
+/// Shortest passphrase accepted; a four-digit PIN is the minimum.
+pub const MIN_PASSPHRASE_LEN: usize = 4;
+
+/// PBKDF2-HMAC-SHA256 rounds for new passphrase hashes. Tests use far
+/// fewer, since unoptimised builds are slow at it.
+#[cfg(not(test))]
+const KDF_ITERATIONS: u32 = 600_000;
+#[cfg(test)]
+const KDF_ITERATIONS: u32 = 1_000;
+
+/// Wrong guesses allowed before backoff starts.
+const FREE_ATTEMPTS: u32 = 3;
+/// Lockout after the first guess past the free ones; doubles each time.
+const BACKOFF_BASE_SECS: i64 = 30;
+const MAX_BACKOFF_SECS: i64 = 15 * 60;
+
+const SALT_LEN: usize = 16;
+const HASH_LEN: usize = 32;
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PassphraseHash {
+    pub iterations: u32,
+    /// Hex.
+    pub salt: String,
+    /// Hex.
+    pub hash: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppLockSettings {
+    /// `None` when no passphrase is set and the app never locks.
+    pub passphrase: Option<PassphraseHash>,
+    /// Lock after this many minutes without activity; 0 only locks on
+    /// launch and on request.
+    pub idle_timeout_minutes: u32,
+    /// Wrong guesses since the last successful unlock.
+    pub failed_attempts: u32,
+    /// No guesses are checked before this time (UTC).
+    pub retry_at: Option<String>,
+}
+
+impl Default for AppLockSettings {
+    fn default() -> Self {
+        AppLockSettings {
+            passphrase: None,
+            idle_timeout_minutes: 15,
+            failed_attempts: 0,
+            retry_at: None,
+        }
+    }
+}
+
+impl AppLockSettings {
+    pub fn enabled(&self) -> bool {
+        self.passphrase.is_some()
+    }
+
+    /// Seconds until another guess is allowed, if unlocking is shut out.
+    pub fn retry_after_secs(&self, now: NaiveDateTime) -> Option<i64> {
+        let retry_at =
+            NaiveDateTime::parse_from_str(self.retry_at.as_deref()?, TIMESTAMP_FORMAT).ok()?;
+        let secs = (retry_at - now).num_seconds();
+        (secs > 0).then_some(secs)
+    }
+}
+
+pub async fn get_app_lock_settings(pool: &DbPool) -> Result<AppLockSettings, sqlx::Error> {
+    match get_setting(pool, APP_LOCK_SETTING).await? {
+        Some(json) if !json.trim().is_empty() => {
+            serde_json::from_str(&json).map_err(|e| sqlx::Error::Decode(Box::new(e)))
+        }
+        _ => Ok(AppLockSettings::default()),
+    }
+}
+
+async fn save(pool: &DbPool, settings: &AppLockSettings) -> Result<(), sqlx::Error> {
+    let json = serde_json::to_string(settings).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    set_setting(pool, APP_LOCK_SETTING, &json).await
+}
+
+/// Check a passphrase. Wrong guesses count towards the backoff, and none
+/// are checked while it lasts. Succeeds trivially when no passphrase is set.
+pub async fn verify_passphrase(
+    pool: &DbPool,
+    attempt: &str,
+    now: NaiveDateTime,
+) -> Result<(), AppLockError> {
+    let mut settings = get_app_lock_settings(pool).await?;
+    let Some(stored) = &settings.passphrase else {
+        return Ok(());
+    };
+    if let Some(secs) = settings.retry_after_secs(now) {
+        return Err(AppLockError::TooManyAttempts {
+            retry_after_secs: secs,
+        });
+    }
+
+    if matches_hash(stored, attempt) {
+        if settings.failed_attempts > 0 || settings.retry_at.is_some() {
+            settings.failed_attempts = 0;
+            settings.retry_at = None;
+            save(pool, &settings).await?;
+        }
+        return Ok(());
+    }
+
+    settings.failed_attempts += 1;
+    let retry_after_secs = backoff_secs(settings.failed_attempts);
+    settings.retry_at = retry_after_secs.map(|secs| {
+        (now + chrono::Duration::seconds(secs))
+            .format(TIMESTAMP_FORMAT)
+            .to_string()
+    });
+    save(pool, &settings).await?;
+    Err(AppLockError::WrongPassphrase {
+        failed_attempts: settings.failed_attempts,
+        retry_after_secs,
+    })
+}
+
+/// Lockout after `failed` wrong guesses in a row, if any.
+fn backoff_secs(failed: u32) -> Option<i64> {
+    let past_free = failed.checked_sub(FREE_ATTEMPTS).filter(|n| *n > 0)?;
+    let secs = BACKOFF_BASE_SECS.saturating_mul(1i64 << (past_free - 1).min(16));
+    Some(secs.min(MAX_BACKOFF_SECS))
+}
+
+/// Set or change the passphrase. Changing one needs the current one.
+pub async fn set_passphrase(
+    pool: &DbPool,
+    new_passphrase: &str,
+    current: Option<&str>,
+    now: NaiveDateTime,
+) -> Result<(), AppLockError> {
+    if new_passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(AppLockError::Invalid(format!(
+            "Passphrase must be at least {MIN_PASSPHRASE_LEN} characters"
+        )));
+    }
+    check_current(pool, current, now).await?;
+    let mut settings = get_app_lock_settings(pool).await?;
+    settings.passphrase = Some(hash_passphrase(new_passphrase, KDF_ITERATIONS)?);
+    settings.failed_attempts = 0;
+    settings.retry_at = None;
+    save(pool, &settings).await?;
+    Ok(())
+}
+
+/// Turn the lock off. Needs the current passphrase.
+pub async fn remove_passphrase(
+    pool: &DbPool,
+    current: &str,
+    now: NaiveDateTime,
+) -> Result<(), AppLockError> {
+    check_current(pool, Some(current), now).await?;
+    let mut settings = get_app_lock_settings(pool).await?;
+    settings.passphrase = None;
+    save(pool, &settings).await?;
+    Ok(())
+}
+
+pub async fn set_idle_timeout(pool: &DbPool, minutes: u32) -> Result<(), sqlx::Error> {
+    let mut settings = get_app_lock_settings(pool).await?;
+    settings.idle_timeout_minutes = minutes;
+    save(pool, &settings).await
+}
+
+async fn check_current(
+    pool: &DbPool,
+    current: Option<&str>,
+    now: NaiveDateTime,
+) -> Result<(), AppLockError> {
+    if !get_app_lock_settings(pool).await?.enabled() {
+        return Ok(());
+    }
+    let current = current
+        .ok_or_else(|| AppLockError::Invalid("The current passphrase is required".into()))?;
+    verify_passphrase(pool, current, now).await
+}
+
+fn hash_passphrase(passphrase: &str, iterations: u32) -> Result<PassphraseHash, AppLockError> {
+    let mut salt = [0u8; SALT_LEN];
+    SystemRandom::new()
+        .fill(&mut salt)
+        .map_err(|_| AppLockError::Invalid("no randomness available".into()))?;
+    let rounds = NonZeroU32::new(iterations)
+        .ok_or_else(|| AppLockError::Invalid("zero key derivation rounds".into()))?;
+    let mut hash = [0u8; HASH_LEN];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        rounds,
+        &salt,
+        passphrase.as_bytes(),
+        &mut hash,
+    );
+    Ok(PassphraseHash {
+        iterations,
+        salt: hex(&salt),
+        hash: hex(&hash),
+    })
+}
+
+/// Constant-time check of `attempt` against a stored hash. A malformed
+/// hash matches nothing.
+fn matches_hash(stored: &PassphraseHash, attempt: &str) -> bool {
+    let (Some(rounds), Some(salt), Some(hash)) = (
+        NonZeroU32::new(stored.iterations),
+        unhex(&stored.salt),
+        unhex(&stored.hash),
+    ) else {
+        return false;
+    };
+    pbkdf2::verify(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        rounds,
+        &salt,
+        attempt.as_bytes(),
+        &hash,
+    )
+    .is_ok()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn unhex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AppLockError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("{0}")]
+    Invalid(String),
+    #[error("Wrong passphrase")]
+    WrongPassphrase {
+        failed_attempts: u32,
+        /// Set once the backoff has started.
+        retry_after_secs: Option<i64>,
+    },
+    #[error("Too many wrong passphrases; try again in {retry_after_secs} seconds")]
+    TooManyAttempts { retry_after_secs: i64 },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, TIMESTAMP_FORMAT).unwrap()
+    }
+
+    #[test]
+    fn hashes_verify_only_their_passphrase() {
+        let stored = hash_passphrase("1234", 1_000).unwrap();
+        assert_eq!(stored.salt.len(), SALT_LEN * 2);
+        assert!(matches_hash(&stored, "1234"));
+        assert!(!matches_hash(&stored, "12345"));
+        assert_ne!(hash_passphrase("1234", 1_000).unwrap().salt, stored.salt);
+        let broken = PassphraseHash {
+            salt: "zz".into(),
+            ..stored
+        };
+        assert!(!matches_hash(&broken, "1234"));
+    }
+
+    #[test]
+    fn backoff_doubles_after_free_attempts() {
+        assert_eq!(backoff_secs(1), None);
+        assert_eq!(backoff_secs(3), None);
+        assert_eq!(backoff_secs(4), Some(30));
+        assert_eq!(backoff_secs(5), Some(60));
+        assert_eq!(backoff_secs(9), Some(MAX_BACKOFF_SECS));
+        assert_eq!(backoff_secs(60), Some(MAX_BACKOFF_SECS));
+    }
+
+    #[tokio::test]
+    async fn wrong_guesses_back_off_until_unlocked() {
+        let pool = test_pool().await;
+        let now = at("2026-05-01 09:00:00");
+        // No passphrase: anything unlocks.
+        verify_passphrase(&pool, "", now).await.unwrap();
+        assert!(matches!(
+            set_passphrase(&pool, "123", None, now).await,
+            Err(AppLockError::Invalid(_))
+        ));
+        set_passphrase(&pool, "2468", None, now).await.unwrap();
+        assert!(get_app_lock_settings(&pool).await.unwrap().enabled());
+
+        for n in 1..=3 {
+            match verify_passphrase(&pool, "0000", now).await {
+                Err(AppLockError::WrongPassphrase {
+                    failed_attempts,
+                    retry_after_secs: None,
+                }) => assert_eq!(failed_attempts, n),
+                other => panic!("unexpected {other:?}"),
+            }
+        }
+        assert!(matches!(
+            verify_passphrase(&pool, "0000", now).await,
+            Err(AppLockError::WrongPassphrase {
+                failed_attempts: 4,
+                retry_after_secs: Some(30),
+            })
+        ));
+        // Even the right passphrase waits out the backoff.
+        assert!(matches!(
+            verify_passphrase(&pool, "2468", at("2026-05-01 09:00:10")).await,
+            Err(AppLockError::TooManyAttempts {
+                retry_after_secs: 20
+            })
+        ));
+        verify_passphrase(&pool, "2468", at("2026-05-01 09:00:30"))
+            .await
+            .unwrap();
+        let settings = get_app_lock_settings(&pool).await.unwrap();
+        assert_eq!(settings.failed_attempts, 0);
+        assert!(settings.retry_at.is_none());
+    }
+
+    #[tokio::test]
+    async fn changing_or_removing_needs_current_passphrase() {
+        let pool = test_pool().await;
+        let now = at("2026-05-01 09:00:00");
+        set_passphrase(&pool, "first pass", None, now)
+            .await
+            .unwrap();
+        assert!(matches!(
+            set_passphrase(&pool, "second pass", None, now).await,
+            Err(AppLockError::Invalid(_))
+        ));
+        assert!(matches!(
+            set_passphrase(&pool, "second pass", Some("nope"), now).await,
+            Err(AppLockError::WrongPassphrase { .. })
+        ));
+        set_passphrase(&pool, "second pass", Some("first pass"), now)
+            .await
+            .unwrap();
+        set_idle_timeout(&pool, 5).await.unwrap();
+
+        assert!(remove_passphrase(&pool, "first pass", now).await.is_err());
+        remove_passphrase(&pool, "second pass", now).await.unwrap();
+        let settings = get_app_lock_settings(&pool).await.unwrap();
+        assert!(!settings.enabled());
+        assert_eq!(settings.idle_timeout_minutes, 5);
+    }
+}
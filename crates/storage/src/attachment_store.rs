@@ -0,0 +1,330 @@
+//! Where the attachment store lives, and moving it.
+//!
+//! The store defaults to `attachments` under the app data directory; the
+//! `attachments_dir` setting points it elsewhere, such as an external drive
+//! or a NAS share. Rows record their file's path relative to the store, so
+//! the ledger doesn't care where it is; [`resolve_path`] finds the file.
+//! Absolute paths left from before that are still read as they are.
+//!
+//! [`move_attachment_store`] copies the files over and then, in one
+//! transaction, repoints any absolute rows at the copies and saves the new
+//! location, so the ledger never refers to a file that isn't there. It can
+//! be run again over the same pair of directories to pick up files stored
+//! while it ran. The originals are only deleted by [`remove_moved_files`],
+//! once their copies are in use.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf, MAIN_SEPARATOR};
+
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::db::DbPool;
+
+/// Absolute path of the attachment store; the default location when unset.
+pub const ATTACHMENTS_DIR_SETTING: &str = "attachments_dir";
+/// Longest side, in pixels, photos are scaled down to before they're
+/// stored; unset keeps originals as they are.
+pub const MAX_SIDE_SETTING: &str = "attachment_max_side";
+/// JPEG quality stored photos are recompressed at.
+pub const JPEG_QUALITY_SETTING: &str = "attachment_jpeg_quality";
+
+/// Tables whose rows point into the store.
+const PATH_TABLES: [&str; 3] = ["receipts", "attachments", "documents"];
+
+#[derive(Debug, Error)]
+pub enum StoreMoveError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Cannot copy attachments: {0}")]
+    Io(#[from] io::Error),
+    #[error("{0}")]
+    Invalid(String),
+}
+
+/// What a pass of [`move_attachment_store`] did.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct StoreMove {
+    pub files_copied: u64,
+    pub bytes_copied: u64,
+    /// Receipt, attachment and document rows repointed at `to`.
+    pub rows_updated: u64,
+}
+
+/// Copy the store at `from` into `to`, creating it if needed, then point
+/// the absolute rows that referred to files under `from` at their copies
+/// and save `to` as the store's location. Files already in `to` with the same size
+/// aren't copied again. `from` is left as it was.
+pub async fn move_attachment_store(
+    pool: &DbPool,
+    from: &Path,
+    to: &Path,
+) -> Result<StoreMove, StoreMoveError> {
+    let (src, dest) = (from.to_path_buf(), to.to_path_buf());
+    let (files_copied, bytes_copied) =
+        tokio::task::spawn_blocking(move || -> Result<(u64, u64), StoreMoveError> {
+            check_destination(&src, &dest)?;
+            copy_tree(&src, &dest)
+        })
+        .await
+        .map_err(|e| StoreMoveError::Io(io::Error::other(e)))??;
+
+    // Rows hold the paths as they were written, so match on `from` as given
+    // rather than its canonical form.
+    let old_prefix = with_separator(from);
+    let new_prefix = with_separator(to);
+    let mut tx = pool.begin().await?;
+    let mut rows_updated = 0;
+    for table in PATH_TABLES {
+        // `substr` counts characters, not bytes.
+        let result = sqlx::query(&format!(
+            "UPDATE {table} SET attachment_path = ? || substr(attachment_path, ?)
+             WHERE substr(attachment_path, 1, ?) = ?"
+        ))
+        .bind(&new_prefix)
+        .bind(old_prefix.chars().count() as i64 + 1)
+        .bind(old_prefix.chars().count() as i64)
+        .bind(&old_prefix)
+        .execute(&mut *tx)
+        .await?;
+        rows_updated += result.rows_affected();
+    }
+    sqlx::query(
+        "INSERT INTO settings (key, value) VALUES (?, ?)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+    )
+    .bind(ATTACHMENTS_DIR_SETTING)
+    .bind(to.to_string_lossy())
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(StoreMove {
+        files_copied,
+        bytes_copied,
+        rows_updated,
+    })
+}
+
+/// Where a path recorded in the ledger is on disk: under `root` when it's
+/// relative, which it is unless recorded before paths were.
+pub fn resolve_path(root: &Path, stored: &str) -> PathBuf {
+    let path = Path::new(stored);
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+    stored
+        .split('/')
+        .fold(root.to_path_buf(), |dir, part| dir.join(part))
+}
+
+/// Delete the files under `from` that have a copy of the same size under
+/// `to`, and the folders that leaves empty. `from` itself is kept. Returns
+/// how many files were deleted; a file without a copy is never touched.
+pub fn remove_moved_files(from: &Path, to: &Path) -> io::Result<u64> {
+    let mut removed = 0;
+    for rel in list_files(from)? {
+        let (old, new) = (from.join(&rel), to.join(&rel));
+        let copied = match (fs::metadata(&old), fs::metadata(&new)) {
+            (Ok(old_meta), Ok(new_meta)) => old_meta.len() == new_meta.len(),
+            _ => false,
+        };
+        if copied {
+            fs::remove_file(&old)?;
+            removed += 1;
+            // Fails harmlessly while the folder still holds something.
+            if let Some(parent) = old.parent().filter(|p| *p != from) {
+                let _ = fs::remove_dir(parent);
+            }
+        }
+    }
+    Ok(removed)
+}
+
+fn check_destination(from: &Path, to: &Path) -> Result<(), StoreMoveError> {
+    if !to.is_absolute() {
+        return Err(StoreMoveError::Invalid(
+            "The new attachment folder must be an absolute path".to_string(),
+        ));
+    }
+    fs::create_dir_all(to)?;
+    let (from, to) = (fs::canonicalize(from)?, fs::canonicalize(to)?);
+    if from == to {
+        return Err(StoreMoveError::Invalid(
+            "Attachments are already stored there".to_string(),
+        ));
+    }
+    if to.starts_with(&from) || from.starts_with(&to) {
+        return Err(StoreMoveError::Invalid(
+            "The new attachment folder can't be inside the current one, or contain it".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Copy each file not already in `to`, through a temporary name so an
+/// interrupted copy is never taken for a finished one.
+fn copy_tree(from: &Path, to: &Path) -> Result<(u64, u64), StoreMoveError> {
+    let (mut files, mut bytes) = (0, 0);
+    for rel in list_files(from)? {
+        let (src, dest) = (from.join(&rel), to.join(&rel));
+        let len = fs::metadata(&src)?.len();
+        if fs::metadata(&dest).is_ok_and(|meta| meta.len() == len) {
+            continue;
+        }
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut partial = dest.clone().into_os_string();
+        partial.push(".partial");
+        let partial = PathBuf::from(partial);
+        fs::copy(&src, &partial)?;
+        fs::File::open(&partial)?.sync_all()?;
+        fs::rename(&partial, &dest)?;
+        files += 1;
+        bytes += len;
+    }
+    Ok((files, bytes))
+}
+
+/// Paths of the files under `root`, relative to it.
+fn list_files(root: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![PathBuf::new()];
+    while let Some(rel) = pending.pop() {
+        for entry in fs::read_dir(root.join(&rel))? {
+            let entry = entry?;
+            let path = rel.join(entry.file_name());
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                pending.push(path);
+            } else if file_type.is_file() && path.extension().is_none_or(|e| e != "partial") {
+                files.push(path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn with_separator(dir: &Path) -> String {
+    let mut prefix = dir.to_string_lossy().into_owned();
+    if !prefix.ends_with(MAIN_SEPARATOR) {
+        prefix.push(MAIN_SEPARATOR);
+    }
+    prefix
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    async fn insert_receipt(pool: &DbPool, hash: &str, path: &str) {
+        crate::insert_receipt(
+            pool, hash, "jpg", path, None, None, None, None, None, None, None, None, 0.0,
+        )
+        .await
+        .unwrap();
+    }
+
+    #[test]
+    fn test_resolve_path() {
+        let root = Path::new("/mnt/nas/attachments");
+        assert_eq!(
+            resolve_path(root, "ab/abcd.jpg"),
+            root.join("ab").join("abcd.jpg")
+        );
+        assert_eq!(
+            resolve_path(root, "/home/old/attachments/ab/abcd.jpg"),
+            Path::new("/home/old/attachments/ab/abcd.jpg")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_move_attachment_store() {
+        let pool = test_pool().await;
+        let dir = tempfile::tempdir().unwrap();
+        let (from, to) = (dir.path().join("attachments"), dir.path().join("nas"));
+        fs::create_dir_all(from.join("ab")).unwrap();
+        fs::create_dir_all(from.join("98")).unwrap();
+        fs::write(from.join("ab/abcd.jpg"), b"receipt").unwrap();
+        fs::write(from.join("98/9876.jpg"), b"older").unwrap();
+        insert_receipt(&pool, "abcd", "ab/abcd.jpg").await;
+        // Recorded before paths were relative.
+        let legacy = from.join("98/9876.jpg").to_string_lossy().into_owned();
+        insert_receipt(&pool, "9876", &legacy).await;
+        // Elsewhere on disk; left alone.
+        insert_receipt(&pool, "ef01", "/elsewhere/ef/ef01.jpg").await;
+
+        let moved = move_attachment_store(&pool, &from, &to).await.unwrap();
+        assert_eq!(
+            moved,
+            StoreMove {
+                files_copied: 2,
+                bytes_copied: 12,
+                rows_updated: 1,
+            }
+        );
+        let paths: Vec<String> =
+            sqlx::query_scalar("SELECT attachment_path FROM receipts ORDER BY id")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(
+            paths,
+            [
+                "ab/abcd.jpg".to_string(),
+                to.join("98/9876.jpg").to_string_lossy().into_owned(),
+                "/elsewhere/ef/ef01.jpg".to_string(),
+            ]
+        );
+        assert_eq!(fs::read(resolve_path(&to, &paths[0])).unwrap(), b"receipt");
+        assert_eq!(
+            crate::get_setting(&pool, ATTACHMENTS_DIR_SETTING)
+                .await
+                .unwrap()
+                .as_deref(),
+            Some(&*to.to_string_lossy())
+        );
+
+        // A receipt stored in the old place while the first pass ran is
+        // picked up by a second; the rest isn't copied again.
+        fs::create_dir_all(from.join("12")).unwrap();
+        fs::write(from.join("12/1234.jpg"), b"late").unwrap();
+        insert_receipt(&pool, "1234", "12/1234.jpg").await;
+        let again = move_attachment_store(&pool, &from, &to).await.unwrap();
+        assert_eq!((again.files_copied, again.rows_updated), (1, 0));
+
+        // Only files with a copy are deleted.
+        fs::write(from.join("12/orphan.jpg"), b"not copied").unwrap();
+        assert_eq!(remove_moved_files(&from, &to).unwrap(), 3);
+        assert!(!from.join("ab").exists());
+        assert!(from.join("12/orphan.jpg").exists());
+    }
+
+    #[tokio::test]
+    async fn test_move_attachment_store_refuses_nested_folders() {
+        let pool = test_pool().await;
+        let dir = tempfile::tempdir().unwrap();
+        let from = dir.path().join("attachments");
+        fs::create_dir_all(&from).unwrap();
+
+        for to in [from.clone(), from.join("inner"), dir.path().to_path_buf()] {
+            assert!(matches!(
+                move_attachment_store(&pool, &from, &to).await,
+                Err(StoreMoveError::Invalid(_))
+            ));
+        }
+        assert!(matches!(
+            move_attachment_store(&pool, &from, Path::new("relative/dir")).await,
+            Err(StoreMoveError::Invalid(_))
+        ));
+        assert_eq!(
+            crate::get_setting(&pool, ATTACHMENTS_DIR_SETTING)
+                .await
+                .unwrap(),
+            None
+        );
+    }
+}
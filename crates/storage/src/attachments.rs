@@ -0,0 +1,222 @@
+//! Documents attached to ledger transactions and accounts: contracts,
+//! warranties, statements.
+//!
+//! The files themselves sit in the content-addressed attachment store with
+//! the receipts; these rows say what each is attached to. Attaching the same
+//! file to the same thing again returns the existing attachment.
+
+use serde::{Deserialize, Serialize};
+
+use crate::db::DbPool;
+
+/// What a document is attached to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "id", rename_all = "snake_case")]
+pub enum AttachmentEntity {
+    Transaction(i64),
+    Account(i64),
+}
+
+impl AttachmentEntity {
+    pub fn entity_type(&self) -> &'static str {
+        match self {
+            AttachmentEntity::Transaction(_) => "transaction",
+            AttachmentEntity::Account(_) => "account",
+        }
+    }
+
+    pub fn id(&self) -> i64 {
+        match self {
+            AttachmentEntity::Transaction(id) | AttachmentEntity::Account(id) => *id,
+        }
+    }
+
+    /// Whether the transaction or account exists.
+    pub async fn exists(&self, pool: &DbPool) -> Result<bool, sqlx::Error> {
+        let sql = match self {
+            AttachmentEntity::Transaction(_) => "SELECT 1 FROM transactions WHERE id = ?",
+            AttachmentEntity::Account(_) => "SELECT 1 FROM accounts WHERE id = ?",
+        };
+        let row: Option<(i64,)> = sqlx::query_as(sql)
+            .bind(self.id())
+            .fetch_optional(pool)
+            .await?;
+        Ok(row.is_some())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Attachment {
+    pub id: i64,
+    pub entity_type: String,
+    pub entity_id: i64,
+    pub file_hash: String,
+    pub file_ext: String,
+    /// Name of the file as it was attached, for display.
+    pub file_name: Option<String>,
+    pub size_bytes: i64,
+    pub attachment_path: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct NewAttachment<'a> {
+    pub entity: AttachmentEntity,
+    pub file_hash: &'a str,
+    pub file_ext: &'a str,
+    pub file_name: Option<&'a str>,
+    pub size_bytes: i64,
+    pub attachment_path: &'a str,
+}
+
+/// Record an attachment, or find the one already made for this file and
+/// entity. Returns its id.
+pub async fn insert_attachment(
+    pool: &DbPool,
+    attachment: &NewAttachment<'_>,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query(
+        r#"INSERT INTO attachments (entity_type, entity_id, file_hash, file_ext, file_name,
+               size_bytes, attachment_path)
+           VALUES (?, ?, ?, ?, ?, ?, ?)
+           ON CONFLICT(entity_type, entity_id, file_hash) DO NOTHING"#,
+    )
+    .bind(attachment.entity.entity_type())
+    .bind(attachment.entity.id())
+    .bind(attachment.file_hash)
+    .bind(attachment.file_ext)
+    .bind(attachment.file_name)
+    .bind(attachment.size_bytes)
+    .bind(attachment.attachment_path)
+    .execute(pool)
+    .await?;
+    sqlx::query_scalar(
+        "SELECT id FROM attachments WHERE entity_type = ? AND entity_id = ? AND file_hash = ?",
+    )
+    .bind(attachment.entity.entity_type())
+    .bind(attachment.entity.id())
+    .bind(attachment.file_hash)
+    .fetch_one(pool)
+    .await
+}
+
+pub async fn get_attachment(pool: &DbPool, id: i64) -> Result<Option<Attachment>, sqlx::Error> {
+    sqlx::query_as::<_, Attachment>("SELECT * FROM attachments WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Attachments on a transaction or account, oldest first.
+pub async fn get_attachments(
+    pool: &DbPool,
+    entity: AttachmentEntity,
+) -> Result<Vec<Attachment>, sqlx::Error> {
+    sqlx::query_as::<_, Attachment>(
+        "SELECT * FROM attachments WHERE entity_type = ? AND entity_id = ? ORDER BY created_at, id",
+    )
+    .bind(entity.entity_type())
+    .bind(entity.id())
+    .fetch_all(pool)
+    .await
+}
+
+/// Remove an attachment. Returns it, with whether its file is still in use
+/// elsewhere (and so must stay in the store).
+pub async fn delete_attachment(
+    pool: &DbPool,
+    id: i64,
+) -> Result<Option<(Attachment, bool)>, sqlx::Error> {
+    let Some(attachment) = get_attachment(pool, id).await? else {
+        return Ok(None);
+    };
+    sqlx::query("DELETE FROM attachments WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    let in_use = file_in_use(pool, &attachment.attachment_path).await?;
+    Ok(Some((attachment, in_use)))
+}
+
+/// Whether an attachment, document or receipt still refers to the stored
+/// file at `attachment_path`.
+pub async fn file_in_use(pool: &DbPool, attachment_path: &str) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"SELECT EXISTS (SELECT 1 FROM attachments WHERE attachment_path = ?1)
+               OR EXISTS (SELECT 1 FROM documents WHERE attachment_path = ?1)
+               OR EXISTS (SELECT 1 FROM receipts WHERE attachment_path = ?1)"#,
+    )
+    .bind(attachment_path)
+    .fetch_one(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    fn contract(entity: AttachmentEntity) -> NewAttachment<'static> {
+        NewAttachment {
+            entity,
+            file_hash: "ab12",
+            file_ext: "pdf",
+            file_name: Some("contract.pdf"),
+            size_bytes: 2048,
+            attachment_path: "/store/ab/ab12.pdf",
+        }
+    }
+
+    #[tokio::test]
+    async fn attachments_dedup_and_follow_their_entity() {
+        let pool = test_pool().await;
+        let tx: i64 = sqlx::query_scalar(
+            "INSERT INTO transactions (date, description, balanced_total_cents) VALUES ('2026-02-01', 'Laptop', 150000) RETURNING id",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let transaction = AttachmentEntity::Transaction(tx);
+        let account = AttachmentEntity::Account(1);
+        assert!(transaction.exists(&pool).await.unwrap());
+        assert!(!AttachmentEntity::Transaction(tx + 1)
+            .exists(&pool)
+            .await
+            .unwrap());
+
+        let first = insert_attachment(&pool, &contract(transaction))
+            .await
+            .unwrap();
+        let again = insert_attachment(&pool, &contract(transaction))
+            .await
+            .unwrap();
+        assert_eq!(first, again);
+        let on_account = insert_attachment(&pool, &contract(account)).await.unwrap();
+        assert_eq!(get_attachments(&pool, transaction).await.unwrap().len(), 1);
+
+        // The file stays while the account still has it.
+        let (removed, in_use) = delete_attachment(&pool, first).await.unwrap().unwrap();
+        assert_eq!(removed.file_name.as_deref(), Some("contract.pdf"));
+        assert!(in_use);
+        let (_, in_use) = delete_attachment(&pool, on_account).await.unwrap().unwrap();
+        assert!(!in_use);
+        assert!(delete_attachment(&pool, on_account)
+            .await
+            .unwrap()
+            .is_none());
+
+        // Deleting the transaction takes its attachments with it.
+        insert_attachment(&pool, &contract(transaction))
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM transactions WHERE id = ?")
+            .bind(tx)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert!(get_attachments(&pool, transaction)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
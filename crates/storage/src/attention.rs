@@ -0,0 +1,251 @@
+//! One to-do list across the places work piles up: invoices past due,
+//! reconciliation differences still open, imported bank rows not yet
+//! settled and receipts waiting for review.
+//!
+//! Items come in that order, since money owed and books that don't agree
+//! matter more than paperwork. Within a kind, the most pressing come first:
+//! the longest overdue, the oldest difference, the largest bank row and the
+//! oldest receipt.
+
+use chrono::NaiveDate;
+use serde::Serialize;
+
+use crate::db::DbPool;
+use crate::error::StorageError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttentionKind {
+    OverdueInvoice,
+    ReconciliationItem,
+    ImportedTransaction,
+    PendingReceipt,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AttentionItem {
+    pub kind: AttentionKind,
+    /// Id of the invoice, reconciliation item, imported row or receipt.
+    pub id: i64,
+    pub title: String,
+    pub detail: Option<String>,
+    pub date: Option<String>,
+    pub amount_cents: Option<i64>,
+    /// The import batch or reconciliation session the item belongs to.
+    pub reference: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AttentionCounts {
+    pub overdue_invoices: usize,
+    pub reconciliation_items: usize,
+    pub imported_transactions: usize,
+    pub pending_receipts: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AttentionFeed {
+    pub items: Vec<AttentionItem>,
+    pub counts: AttentionCounts,
+}
+
+#[derive(sqlx::FromRow)]
+struct OpenDifference {
+    id: i64,
+    session_id: i64,
+    account_name: String,
+    match_type: String,
+    difference_cents: i64,
+    date: Option<String>,
+    description: Option<String>,
+}
+
+/// Everything needing attention as of `today`.
+pub async fn needs_attention(
+    pool: &DbPool,
+    today: NaiveDate,
+) -> Result<AttentionFeed, StorageError> {
+    let mut items = Vec::new();
+    let mut counts = AttentionCounts::default();
+
+    let mut overdue: Vec<_> = crate::aging::ar_aging(pool, today)
+        .await?
+        .items
+        .into_iter()
+        .filter(|i| i.days_overdue > 0)
+        .collect();
+    overdue.sort_by(|a, b| b.days_overdue.cmp(&a.days_overdue).then(a.id.cmp(&b.id)));
+    counts.overdue_invoices = overdue.len();
+    items.extend(overdue.into_iter().map(|i| AttentionItem {
+        kind: AttentionKind::OverdueInvoice,
+        id: i.id,
+        title: format!(
+            "Invoice {} to {}",
+            i.number.as_deref().unwrap_or("(unnumbered)"),
+            i.contact_name
+        ),
+        detail: Some(format!(
+            "{} day{} overdue",
+            i.days_overdue,
+            if i.days_overdue == 1 { "" } else { "s" }
+        )),
+        date: Some(i.due_date),
+        amount_cents: Some(i.open_cents),
+        reference: None,
+    }));
+
+    let differences = sqlx::query_as::<_, OpenDifference>(
+        r#"SELECT ri.id, ri.session_id, a.name AS account_name, ri.match_type,
+                  ri.difference_cents, COALESCE(t.date, it.date) AS date,
+                  COALESCE(t.description, it.description) AS description
+           FROM reconciliation_items ri
+           JOIN reconciliation_sessions s ON s.id = ri.session_id
+           JOIN accounts a ON a.id = s.account_id
+           LEFT JOIN transactions t ON t.id = ri.transaction_id
+           LEFT JOIN imported_transactions it ON it.id = ri.imported_transaction_id
+           WHERE ri.is_resolved = 0
+           ORDER BY ri.created_at, ri.id"#,
+    )
+    .fetch_all(pool)
+    .await?;
+    counts.reconciliation_items = differences.len();
+    items.extend(differences.into_iter().map(|d| AttentionItem {
+        kind: AttentionKind::ReconciliationItem,
+        id: d.id,
+        title: format!("Reconciliation difference in {}", d.account_name),
+        detail: Some(match d.description {
+            Some(description) => format!("{description} ({})", d.match_type),
+            None => d.match_type,
+        }),
+        date: d.date,
+        amount_cents: Some(d.difference_cents),
+        reference: Some(d.session_id.to_string()),
+    }));
+
+    let imported: Vec<(i64, String, String, String, i64, bool)> = sqlx::query_as(
+        r#"SELECT id, import_batch_id, date, description, amount_cents,
+                  matched_transaction_id IS NOT NULL
+           FROM imported_transactions
+           WHERE status IN ('pending', 'categorized')
+           ORDER BY ABS(amount_cents) DESC, date, id"#,
+    )
+    .fetch_all(pool)
+    .await?;
+    counts.imported_transactions = imported.len();
+    items.extend(imported.into_iter().map(
+        |(id, batch_id, date, description, amount, suggested)| {
+            AttentionItem {
+                kind: AttentionKind::ImportedTransaction,
+                id,
+                title: description,
+                detail: Some(
+                    if suggested {
+                        "Suggested match to confirm"
+                    } else {
+                        "Not yet in the ledger"
+                    }
+                    .to_string(),
+                ),
+                date: Some(date),
+                amount_cents: Some(amount),
+                reference: Some(batch_id),
+            }
+        },
+    ));
+
+    let mut receipts = crate::db::get_receipts_pending_review(pool).await?;
+    receipts.reverse();
+    counts.pending_receipts = receipts.len();
+    items.extend(receipts.into_iter().map(|r| AttentionItem {
+        kind: AttentionKind::PendingReceipt,
+        id: r.id,
+        title: r.vendor.unwrap_or_else(|| "Receipt".to_string()),
+        detail: Some("Waiting for review".to_string()),
+        date: r.receipt_date.or(Some(r.created_at)),
+        amount_cents: r.total_cents,
+        reference: None,
+    }));
+
+    Ok(AttentionFeed { items, counts })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    #[tokio::test]
+    async fn feed_orders_by_kind_then_urgency() {
+        let pool = test_pool().await;
+        sqlx::query("INSERT INTO contacts (id, name) VALUES (1, 'Acme')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            r#"INSERT INTO invoices (id, invoice_number, contact_id, status_type, issue_date, due_date)
+               VALUES (1, 'INV-1', 1, 'Sent', '2026-05-01', '2026-05-31'),
+                      (2, 'INV-2', 1, 'Sent', '2026-04-01', '2026-04-30'),
+                      (3, 'INV-3', 1, 'Sent', '2026-06-01', '2026-07-01')"#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            r#"INSERT INTO invoice_lines (invoice_id, description, quantity_hundredths, unit_rate_cents)
+               VALUES (1, 'Work', 100, 10000), (2, 'Work', 100, 20000), (3, 'Work', 100, 30000)"#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            r#"INSERT INTO imported_transactions
+                   (source_type, import_batch_id, date, description, amount_cents, status)
+               VALUES ('csv', 'b1', '2026-06-02', 'COFFEE', -450, 'pending'),
+                      ('csv', 'b1', '2026-06-03', 'LAPTOP', -150000, 'categorized'),
+                      ('csv', 'b1', '2026-06-04', 'DONE', -100, 'matched')"#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            r#"INSERT INTO reconciliation_sessions (id, account_id, start_date, end_date, statement_balance_cents)
+               VALUES (1, (SELECT id FROM accounts WHERE code = '1000'), '2026-05-01', '2026-05-31', 0)"#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO reconciliation_items (session_id, match_type, difference_cents) VALUES (1, 'missing', 1500)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            r#"INSERT INTO receipts (file_hash, file_ext, vendor, total_cents, status, attachment_path)
+               VALUES ('h1', 'jpg', 'Cafe', 450, 'pending_review', 'a/h1.jpg')"#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let today = NaiveDate::from_ymd_opt(2026, 6, 15).unwrap();
+        let feed = needs_attention(&pool, today).await.unwrap();
+        let kinds: Vec<(AttentionKind, i64)> = feed.items.iter().map(|i| (i.kind, i.id)).collect();
+        assert_eq!(
+            kinds,
+            [
+                (AttentionKind::OverdueInvoice, 2),
+                (AttentionKind::OverdueInvoice, 1),
+                (AttentionKind::ReconciliationItem, 1),
+                (AttentionKind::ImportedTransaction, 2),
+                (AttentionKind::ImportedTransaction, 1),
+                (AttentionKind::PendingReceipt, 1),
+            ]
+        );
+        assert_eq!(feed.items[0].detail.as_deref(), Some("46 days overdue"));
+        assert_eq!(feed.items[0].amount_cents, Some(20_000));
+        assert_eq!(feed.items[3].reference.as_deref(), Some("b1"));
+        assert_eq!(feed.counts.imported_transactions, 2);
+        assert_eq!(feed.counts.pending_receipts, 1);
+    }
+}
@@ -10,6 +10,9 @@ use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
 
+/// Setting holding when the last backup was made (RFC 3339).
+pub const LAST_BACKUP_SETTING: &str = "last_backup_at";
+
 /// Metadata written into the backup archive as `manifest.json`.
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
 pub struct BackupManifest {
@@ -119,6 +122,10 @@ pub async fn create_backup(
     // Clean up snapshot
     let _ = fs::remove_file(&snapshot_path);
 
+    // Only shown in diagnostics, so failing to record it doesn't fail the
+    // backup.
+    let _ = crate::db::set_setting(pool, LAST_BACKUP_SETTING, &manifest.created_at).await;
+
     Ok(manifest)
 }
 
@@ -0,0 +1,219 @@
+//! Balance assertions: what a bank statement says an account held on a day,
+//! recorded by hand or taken from an OFX closing balance.
+//!
+//! [`check_balance_assertions`] compares each one with the ledger balance at
+//! the end of its day. A difference (drift) means something was entered,
+//! edited or deleted since the statement was checked.
+
+use chrono::NaiveDate;
+use serde::Serialize;
+
+use crate::db::DbPool;
+
+/// Source of an assertion typed in from a statement.
+pub const SOURCE_STATEMENT: &str = "statement";
+/// Source of an assertion read from an OFX file's `<LEDGERBAL>`.
+pub const SOURCE_OFX: &str = "ofx";
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct BalanceAssertion {
+    pub id: i64,
+    pub account_id: i64,
+    pub account_code: String,
+    pub account_name: String,
+    pub date: String,
+    /// In the account's normal sign: what a card or loan owes is positive.
+    pub balance_cents: i64,
+    pub source: String,
+    pub created_at: String,
+}
+
+/// An assertion with the ledger's balance on the same day.
+#[derive(Debug, Clone, Serialize)]
+pub struct AssertionCheck {
+    #[serde(flatten)]
+    pub assertion: BalanceAssertion,
+    pub ledger_balance_cents: i64,
+    /// Ledger balance less the asserted balance; zero when they agree.
+    pub drift_cents: i64,
+}
+
+impl AssertionCheck {
+    pub fn holds(&self) -> bool {
+        self.drift_cents == 0
+    }
+}
+
+/// Record that an account held `balance_cents` at the end of `date`,
+/// replacing any assertion already made for that account and day.
+pub async fn record_balance_assertion(
+    pool: &DbPool,
+    account_id: i64,
+    date: NaiveDate,
+    balance_cents: i64,
+    source: &str,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"INSERT INTO balance_assertions (account_id, date, balance_cents, source)
+           VALUES (?, ?, ?, ?)
+           ON CONFLICT(account_id, date) DO UPDATE SET
+               balance_cents = excluded.balance_cents,
+               source = excluded.source,
+               created_at = datetime('now')
+           RETURNING id"#,
+    )
+    .bind(account_id)
+    .bind(date.to_string())
+    .bind(balance_cents)
+    .bind(source)
+    .fetch_one(pool)
+    .await
+}
+
+pub async fn delete_balance_assertion(pool: &DbPool, id: i64) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM balance_assertions WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Assertions, by account and then date. `account_id` limits them to one
+/// account.
+pub async fn get_balance_assertions(
+    pool: &DbPool,
+    account_id: Option<i64>,
+) -> Result<Vec<BalanceAssertion>, sqlx::Error> {
+    sqlx::query_as::<_, BalanceAssertion>(
+        r#"SELECT b.id, b.account_id, a.code AS account_code, a.name AS account_name,
+                  b.date, b.balance_cents, b.source, b.created_at
+           FROM balance_assertions b JOIN accounts a ON a.id = b.account_id
+           WHERE ?1 IS NULL OR b.account_id = ?1
+           ORDER BY a.code, b.date"#,
+    )
+    .bind(account_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Check assertions against the ledger, optionally for one account.
+pub async fn check_balance_assertions(
+    pool: &DbPool,
+    account_id: Option<i64>,
+) -> Result<Vec<AssertionCheck>, sqlx::Error> {
+    let assertions = get_balance_assertions(pool, account_id).await?;
+    let mut checks = Vec::with_capacity(assertions.len());
+    for assertion in assertions {
+        let ledger_balance_cents: i64 = sqlx::query_scalar(
+            r#"SELECT COALESCE(SUM(CASE WHEN a.account_type IN ('Asset', 'Expense')
+                                        THEN tl.debit_cents - tl.credit_cents
+                                        ELSE tl.credit_cents - tl.debit_cents END), 0)
+               FROM transaction_lines tl
+               JOIN transactions t ON t.id = tl.transaction_id
+               JOIN accounts a ON a.id = tl.account_id
+               WHERE tl.account_id = ? AND t.date <= ?"#,
+        )
+        .bind(assertion.account_id)
+        .bind(&assertion.date)
+        .fetch_one(pool)
+        .await?;
+        checks.push(AssertionCheck {
+            drift_cents: ledger_balance_cents - assertion.balance_cents,
+            ledger_balance_cents,
+            assertion,
+        });
+    }
+    Ok(checks)
+}
+
+/// Assertions the ledger no longer agrees with.
+pub async fn balance_drift(pool: &DbPool) -> Result<Vec<AssertionCheck>, sqlx::Error> {
+    Ok(check_balance_assertions(pool, None)
+        .await?
+        .into_iter()
+        .filter(|c| !c.holds())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    /// Post `cents` from `credit` to `debit` on `date`.
+    async fn post(pool: &DbPool, date: &str, debit: &str, credit: &str, cents: i64) {
+        let id: i64 = sqlx::query_scalar(
+            "INSERT INTO transactions (date, description, balanced_total_cents) VALUES (?, 'Entry', ?) RETURNING id",
+        )
+        .bind(date)
+        .bind(cents)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        for (code, d, c) in [(debit, cents, 0), (credit, 0, cents)] {
+            sqlx::query(
+                r#"INSERT INTO transaction_lines (transaction_id, account_id, debit_cents, credit_cents)
+                   VALUES (?, (SELECT id FROM accounts WHERE code = ?), ?, ?)"#,
+            )
+            .bind(id)
+            .bind(code)
+            .bind(d)
+            .bind(c)
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+    }
+
+    async fn account_id(pool: &DbPool, code: &str) -> i64 {
+        sqlx::query_scalar("SELECT id FROM accounts WHERE code = ?")
+            .bind(code)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn assertions_flag_drift() {
+        let pool = test_pool().await;
+        let checking = account_id(&pool, "1000").await;
+        let card = account_id(&pool, "2000").await;
+        let date = |d| NaiveDate::from_ymd_opt(2026, 5, d).unwrap();
+
+        post(&pool, "2026-05-01", "1000", "4000", 100_000).await;
+        post(&pool, "2026-05-10", "5100", "2000", 4_500).await;
+        record_balance_assertion(&pool, checking, date(5), 100_000, SOURCE_STATEMENT)
+            .await
+            .unwrap();
+        record_balance_assertion(&pool, card, date(31), 4_500, SOURCE_OFX)
+            .await
+            .unwrap();
+        assert!(balance_drift(&pool).await.unwrap().is_empty());
+
+        // Something back-dated into a checked period shows as drift.
+        post(&pool, "2026-05-03", "5100", "1000", 2_000).await;
+        let drift = balance_drift(&pool).await.unwrap();
+        assert_eq!(drift.len(), 1);
+        assert_eq!(drift[0].assertion.account_code, "1000");
+        assert_eq!(drift[0].ledger_balance_cents, 98_000);
+        assert_eq!(drift[0].drift_cents, -2_000);
+
+        // A corrected statement replaces the day's assertion.
+        let id = record_balance_assertion(&pool, checking, date(5), 98_000, SOURCE_STATEMENT)
+            .await
+            .unwrap();
+        assert!(balance_drift(&pool).await.unwrap().is_empty());
+        assert_eq!(
+            get_balance_assertions(&pool, Some(checking))
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(delete_balance_assertion(&pool, id).await.unwrap());
+        assert_eq!(
+            check_balance_assertions(&pool, None).await.unwrap().len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,189 @@
+//! Monthly budgets for expense accounts, and how this month's spending is
+//! tracking against them.
+//!
+//! Spending is the account's business activity (debits less credits) from
+//! the first of the month through the given day. Alerts for accounts past a
+//! share of their budget are raised with the other reminders.
+
+use chrono::{Datelike, NaiveDate};
+use serde::Serialize;
+
+use crate::db::DbPool;
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Budget {
+    pub account_id: i64,
+    pub account_code: String,
+    pub account_name: String,
+    pub monthly_cents: i64,
+}
+
+/// One budgeted account's spending so far this month.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BudgetVariance {
+    pub account_code: String,
+    pub account_name: String,
+    pub budget_cents: i64,
+    pub actual_cents: i64,
+    /// Budget less spending; negative once the account is over budget.
+    pub variance_cents: i64,
+    /// Spending as a percentage of the budget, rounded down.
+    pub percent_used: i64,
+}
+
+impl BudgetVariance {
+    /// Whether spending is past `percent` of the budget.
+    pub fn exceeds(&self, percent: u32) -> bool {
+        self.actual_cents * 100 > self.budget_cents * percent as i64
+    }
+}
+
+/// Budgets, by account code.
+pub async fn get_budgets(pool: &DbPool) -> Result<Vec<Budget>, sqlx::Error> {
+    sqlx::query_as::<_, Budget>(
+        r#"SELECT b.account_id, a.code AS account_code, a.name AS account_name, b.monthly_cents
+           FROM budgets b JOIN accounts a ON a.id = b.account_id
+           ORDER BY a.code"#,
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Set an account's monthly budget, or remove it with `None`.
+pub async fn set_budget(
+    pool: &DbPool,
+    account_id: i64,
+    monthly_cents: Option<i64>,
+) -> Result<(), sqlx::Error> {
+    match monthly_cents {
+        Some(cents) => {
+            sqlx::query(
+                r#"INSERT INTO budgets (account_id, monthly_cents) VALUES (?, ?)
+                   ON CONFLICT(account_id) DO UPDATE SET
+                       monthly_cents = excluded.monthly_cents,
+                       updated_at = datetime('now')"#,
+            )
+            .bind(account_id)
+            .bind(cents)
+            .execute(pool)
+            .await?;
+        }
+        None => {
+            sqlx::query("DELETE FROM budgets WHERE account_id = ?")
+                .bind(account_id)
+                .execute(pool)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Month-to-date spending against each budget, as of `today`, most over
+/// budget first.
+pub async fn month_to_date_variance(
+    pool: &DbPool,
+    today: NaiveDate,
+) -> Result<Vec<BudgetVariance>, sqlx::Error> {
+    let month_start = today.with_day(1).unwrap_or(today);
+    let rows: Vec<(String, String, i64, i64)> = sqlx::query_as(
+        r#"SELECT a.code, a.name, b.monthly_cents,
+                  COALESCE((SELECT SUM(tl.debit_cents - tl.credit_cents)
+                            FROM transaction_lines tl
+                            JOIN transactions t ON t.id = tl.transaction_id
+                            WHERE tl.account_id = b.account_id AND t.is_personal = 0
+                              AND t.date >= ? AND t.date <= ?), 0)
+           FROM budgets b JOIN accounts a ON a.id = b.account_id
+           WHERE a.is_archived = 0"#,
+    )
+    .bind(month_start.to_string())
+    .bind(today.to_string())
+    .fetch_all(pool)
+    .await?;
+
+    let mut variances: Vec<BudgetVariance> = rows
+        .into_iter()
+        .map(|(code, name, budget, actual)| BudgetVariance {
+            account_code: code,
+            account_name: name,
+            budget_cents: budget,
+            actual_cents: actual,
+            variance_cents: budget - actual,
+            percent_used: actual * 100 / budget,
+        })
+        .collect();
+    variances.sort_by(|a, b| {
+        b.percent_used
+            .cmp(&a.percent_used)
+            .then_with(|| a.account_code.cmp(&b.account_code))
+    });
+    Ok(variances)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    async fn spend(pool: &DbPool, date: &str, code: &str, cents: i64) {
+        let tx: i64 = sqlx::query_scalar(
+            "INSERT INTO transactions (date, description, balanced_total_cents) VALUES (?, 'Spend', ?) RETURNING id",
+        )
+        .bind(date)
+        .bind(cents)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        for (account, debit, credit) in [(code, cents, 0), ("1000", 0, cents)] {
+            sqlx::query(
+                r#"INSERT INTO transaction_lines (transaction_id, account_id, debit_cents, credit_cents)
+                   VALUES (?, (SELECT id FROM accounts WHERE code = ?), ?, ?)"#,
+            )
+            .bind(tx)
+            .bind(account)
+            .bind(debit)
+            .bind(credit)
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+    }
+
+    async fn account_id(pool: &DbPool, code: &str) -> i64 {
+        sqlx::query_scalar("SELECT id FROM accounts WHERE code = ?")
+            .bind(code)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn variance_counts_only_this_month() {
+        let pool = test_pool().await;
+        let supplies = account_id(&pool, "5100").await;
+        let travel = account_id(&pool, "5120").await;
+        set_budget(&pool, supplies, Some(10_000)).await.unwrap();
+        set_budget(&pool, travel, Some(50_000)).await.unwrap();
+        set_budget(&pool, travel, Some(40_000)).await.unwrap();
+        assert_eq!(get_budgets(&pool).await.unwrap().len(), 2);
+
+        spend(&pool, "2026-02-27", "5100", 9_000).await;
+        spend(&pool, "2026-03-02", "5100", 6_000).await;
+        spend(&pool, "2026-03-09", "5100", 6_000).await;
+        spend(&pool, "2026-03-20", "5100", 6_000).await;
+        spend(&pool, "2026-03-05", "5120", 10_000).await;
+
+        let today = NaiveDate::from_ymd_opt(2026, 3, 15).unwrap();
+        let variances = month_to_date_variance(&pool, today).await.unwrap();
+        assert_eq!(variances.len(), 2);
+        assert_eq!(variances[0].account_code, "5100");
+        assert_eq!(variances[0].actual_cents, 12_000);
+        assert_eq!(variances[0].variance_cents, -2_000);
+        assert_eq!(variances[0].percent_used, 120);
+        assert!(variances[0].exceeds(100));
+        assert_eq!(variances[1].percent_used, 25);
+        assert!(!variances[1].exceeds(80));
+
+        set_budget(&pool, travel, None).await.unwrap();
+        assert_eq!(get_budgets(&pool).await.unwrap().len(), 1);
+    }
+}
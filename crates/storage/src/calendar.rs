@@ -0,0 +1,472 @@
+//! An iCalendar (`.ics`) feed of upcoming financial deadlines: estimated
+//! tax payments, invoice due dates and the next date of transactions that
+//! recur in the ledger.
+//!
+//! [`deadline_events`] gathers the events and [`write_ics`] renders them as
+//! all-day events. The reminder check rewrites the feed file each time it
+//! runs, so a calendar app subscribed to it stays current.
+
+use std::collections::HashMap;
+
+use aequi_core::Money;
+use chrono::{DateTime, Months, NaiveDate, Utc};
+use serde::Serialize;
+
+use crate::db::DbPool;
+use crate::reminders::{TaxDeadline, TAX_OVERDUE_DAYS};
+
+/// How far back the ledger is searched for recurring transactions.
+const RECURRING_LOOKBACK_DAYS: i64 = 2 * 366;
+
+/// A recurring transaction whose next date is further in the past than this
+/// is taken to have stopped.
+const RECURRING_GRACE_DAYS: i64 = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CalendarEventKind {
+    TaxDeadline,
+    InvoiceDue,
+    Recurring,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CalendarEvent {
+    /// Stable across refreshes, so calendar apps update events in place.
+    pub uid: String,
+    pub kind: CalendarEventKind,
+    pub date: NaiveDate,
+    pub summary: String,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Cadence {
+    Weekly,
+    Biweekly,
+    Monthly,
+    Quarterly,
+    Yearly,
+}
+
+impl Cadence {
+    /// The cadence a gap of `days` between occurrences fits, if any.
+    fn from_gap(days: i64) -> Option<Self> {
+        match days {
+            6..=8 => Some(Cadence::Weekly),
+            13..=15 => Some(Cadence::Biweekly),
+            27..=33 => Some(Cadence::Monthly),
+            85..=97 => Some(Cadence::Quarterly),
+            355..=375 => Some(Cadence::Yearly),
+            _ => None,
+        }
+    }
+
+    fn next(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Cadence::Weekly => date + chrono::Duration::days(7),
+            Cadence::Biweekly => date + chrono::Duration::days(14),
+            Cadence::Monthly => date + Months::new(1),
+            Cadence::Quarterly => date + Months::new(3),
+            Cadence::Yearly => date + Months::new(12),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Cadence::Weekly => "weekly",
+            Cadence::Biweekly => "every two weeks",
+            Cadence::Monthly => "monthly",
+            Cadence::Quarterly => "quarterly",
+            Cadence::Yearly => "yearly",
+        }
+    }
+}
+
+/// A transaction that has repeated at a steady cadence.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecurringTransaction {
+    pub description: String,
+    pub cadence: Cadence,
+    pub last_date: NaiveDate,
+    pub next_date: NaiveDate,
+    /// Total of the most recent occurrence.
+    pub amount_cents: i64,
+    pub occurrences: usize,
+}
+
+/// Transactions that have recurred with the same description at a steady
+/// cadence, and are still due: at least three occurrences (two for yearly
+/// ones), every gap between them fitting the same cadence.
+pub async fn recurring_transactions(
+    pool: &DbPool,
+    today: NaiveDate,
+) -> Result<Vec<RecurringTransaction>, sqlx::Error> {
+    let since = today - chrono::Duration::days(RECURRING_LOOKBACK_DAYS);
+    let rows: Vec<(String, String, i64)> = sqlx::query_as(
+        r#"SELECT description, date, balanced_total_cents FROM transactions
+           WHERE date >= ? AND date <= ?
+           ORDER BY date, id"#,
+    )
+    .bind(since.to_string())
+    .bind(today.to_string())
+    .fetch_all(pool)
+    .await?;
+
+    let mut groups: HashMap<String, Vec<(NaiveDate, String, i64)>> = HashMap::new();
+    for (description, date, amount) in rows {
+        let Ok(date) = NaiveDate::parse_from_str(&date, "%Y-%m-%d") else {
+            continue;
+        };
+        let key = description
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase();
+        if key.is_empty() {
+            continue;
+        }
+        let group = groups.entry(key).or_default();
+        // One occurrence per day: a split payment isn't a second one.
+        if group.last().is_some_and(|(d, _, _)| *d == date) {
+            continue;
+        }
+        group.push((date, description, amount));
+    }
+
+    let mut recurring = Vec::new();
+    for occurrences in groups.into_values() {
+        let Some(cadence) = occurrences
+            .get(1)
+            .and_then(|(d, _, _)| Cadence::from_gap((*d - occurrences[0].0).num_days()))
+        else {
+            continue;
+        };
+        let min = if cadence == Cadence::Yearly { 2 } else { 3 };
+        if occurrences.len() < min
+            || occurrences
+                .windows(2)
+                .any(|w| Cadence::from_gap((w[1].0 - w[0].0).num_days()) != Some(cadence))
+        {
+            continue;
+        }
+        let (last_date, description, amount_cents) = occurrences.last().cloned().unwrap();
+        let next_date = cadence.next(last_date);
+        if (today - next_date).num_days() > RECURRING_GRACE_DAYS {
+            continue;
+        }
+        recurring.push(RecurringTransaction {
+            description: description.trim().to_string(),
+            cadence,
+            last_date,
+            next_date,
+            amount_cents,
+            occurrences: occurrences.len(),
+        });
+    }
+    recurring.sort_by(|a, b| {
+        a.next_date
+            .cmp(&b.next_date)
+            .then_with(|| a.description.cmp(&b.description))
+    });
+    Ok(recurring)
+}
+
+/// Lowercase letters and digits of `s`, runs of anything else as one `-`.
+fn slug(s: &str) -> String {
+    let mut out = String::new();
+    for c in s.chars() {
+        if c.is_ascii_alphanumeric() {
+            out.push(c.to_ascii_lowercase());
+        } else if !out.is_empty() && !out.ends_with('-') {
+            out.push('-');
+        }
+    }
+    out.trim_end_matches('-').to_string()
+}
+
+/// Every deadline from `deadlines` that hasn't passed (or passed within the
+/// last month while still unpaid), every open invoice's due date, and the
+/// next date of each recurring transaction.
+pub async fn deadline_events(
+    pool: &DbPool,
+    deadlines: &[TaxDeadline],
+    today: NaiveDate,
+) -> Result<Vec<CalendarEvent>, sqlx::Error> {
+    let mut events = Vec::new();
+
+    for deadline in deadlines {
+        let quarter = format!("Q{}", deadline.quarter);
+        let paid: Option<i64> = sqlx::query_scalar(
+            "SELECT payment_recorded_cents FROM tax_periods WHERE year = ? AND quarter = ?",
+        )
+        .bind(deadline.year as i64)
+        .bind(deadline.quarter as i64)
+        .fetch_optional(pool)
+        .await?;
+        let paid = paid.is_some_and(|p| p > 0);
+        if deadline.due_date < today
+            && (paid || (today - deadline.due_date).num_days() > TAX_OVERDUE_DAYS)
+        {
+            continue;
+        }
+        events.push(CalendarEvent {
+            uid: format!("tax-{}-{quarter}", deadline.year),
+            kind: CalendarEventKind::TaxDeadline,
+            date: deadline.due_date,
+            summary: format!("Estimated tax payment due ({} {quarter})", deadline.year),
+            description: if paid {
+                format!(
+                    "The {} {quarter} estimated payment is recorded as paid.",
+                    deadline.year
+                )
+            } else {
+                format!(
+                    "The {} {quarter} estimated tax payment is due.",
+                    deadline.year
+                )
+            },
+        });
+    }
+
+    let invoices: Vec<(i64, String, String, Option<String>)> = sqlx::query_as(
+        r#"SELECT i.id, i.invoice_number, i.due_date, c.name
+           FROM invoices i
+           LEFT JOIN contacts c ON c.id = i.contact_id
+           WHERE i.status_type IN ('Sent', 'Viewed', 'PartiallyPaid')
+           ORDER BY i.due_date"#,
+    )
+    .fetch_all(pool)
+    .await?;
+    for (id, number, due_date, contact) in invoices {
+        let Ok(date) = NaiveDate::parse_from_str(&due_date, "%Y-%m-%d") else {
+            continue;
+        };
+        let client = contact.map(|c| format!(" ({c})")).unwrap_or_default();
+        events.push(CalendarEvent {
+            uid: format!("invoice-{id}"),
+            kind: CalendarEventKind::InvoiceDue,
+            date,
+            summary: format!("Invoice {number} due{client}"),
+            description: format!("Payment of invoice {number} is due."),
+        });
+    }
+
+    for recurring in recurring_transactions(pool, today).await? {
+        events.push(CalendarEvent {
+            uid: format!(
+                "recurring-{}-{}",
+                slug(&recurring.description),
+                recurring.next_date.format("%Y%m%d")
+            ),
+            kind: CalendarEventKind::Recurring,
+            date: recurring.next_date,
+            summary: format!(
+                "{} ({})",
+                recurring.description,
+                Money::from_cents(recurring.amount_cents)
+            ),
+            description: format!(
+                "Recurs {}; last on {}.",
+                recurring.cadence.as_str(),
+                recurring.last_date
+            ),
+        });
+    }
+
+    events.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.uid.cmp(&b.uid)));
+    Ok(events)
+}
+
+/// Escape a TEXT value (RFC 5545 §3.3.11).
+fn ics_text(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Append a content line, folded so no line is longer than 75 octets.
+fn push_line(out: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+/// Render events as an iCalendar file of all-day events. `stamp` is when
+/// the feed was generated.
+pub fn write_ics(events: &[CalendarEvent], stamp: DateTime<Utc>) -> String {
+    let mut out = String::new();
+    let stamp = stamp.format("%Y%m%dT%H%M%SZ").to_string();
+    for line in [
+        "BEGIN:VCALENDAR",
+        "VERSION:2.0",
+        "PRODID:-//aequi//Financial deadlines//EN",
+        "CALSCALE:GREGORIAN",
+        "METHOD:PUBLISH",
+        "X-WR-CALNAME:aequi deadlines",
+    ] {
+        push_line(&mut out, line);
+    }
+    for event in events {
+        let end = event.date.succ_opt().unwrap_or(event.date);
+        push_line(&mut out, "BEGIN:VEVENT");
+        push_line(&mut out, &format!("UID:{}@aequi", event.uid));
+        push_line(&mut out, &format!("DTSTAMP:{stamp}"));
+        push_line(
+            &mut out,
+            &format!("DTSTART;VALUE=DATE:{}", event.date.format("%Y%m%d")),
+        );
+        push_line(
+            &mut out,
+            &format!("DTEND;VALUE=DATE:{}", end.format("%Y%m%d")),
+        );
+        push_line(&mut out, &format!("SUMMARY:{}", ics_text(&event.summary)));
+        push_line(
+            &mut out,
+            &format!("DESCRIPTION:{}", ics_text(&event.description)),
+        );
+        push_line(&mut out, "TRANSP:TRANSPARENT");
+        push_line(&mut out, "END:VEVENT");
+    }
+    push_line(&mut out, "END:VCALENDAR");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    async fn add_transaction(pool: &DbPool, date: &str, description: &str, cents: i64) {
+        sqlx::query(
+            "INSERT INTO transactions (date, description, balanced_total_cents) VALUES (?, ?, ?)",
+        )
+        .bind(date)
+        .bind(description)
+        .bind(cents)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn monthly_transactions_are_projected() {
+        let pool = test_pool().await;
+        for d in ["2026-01-03", "2026-02-03", "2026-03-03"] {
+            add_transaction(&pool, d, "Adobe  Creative Cloud", 5299).await;
+        }
+        // Irregular, and too few.
+        for d in ["2026-01-10", "2026-01-12", "2026-03-01"] {
+            add_transaction(&pool, d, "Hardware store", 2000).await;
+        }
+        add_transaction(&pool, "2026-02-01", "Rent", 150000).await;
+        add_transaction(&pool, "2026-03-01", "Rent", 150000).await;
+
+        let recurring = recurring_transactions(&pool, date("2026-03-20"))
+            .await
+            .unwrap();
+        assert_eq!(recurring.len(), 1);
+        let adobe = &recurring[0];
+        assert_eq!(adobe.description, "Adobe  Creative Cloud");
+        assert_eq!(adobe.cadence, Cadence::Monthly);
+        assert_eq!(adobe.next_date, date("2026-04-03"));
+        assert_eq!(adobe.amount_cents, 5299);
+
+        // Long overdue: it has stopped.
+        assert!(recurring_transactions(&pool, date("2026-06-01"))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn events_cover_taxes_invoices_and_recurring() {
+        let pool = test_pool().await;
+        for d in ["2026-01-15", "2026-02-15", "2026-03-15"] {
+            add_transaction(&pool, d, "Phone bill", 8000).await;
+        }
+        let contact: i64 =
+            sqlx::query_scalar("INSERT INTO contacts (name) VALUES ('Acme') RETURNING id")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        for (number, status) in [("INV-1", "Sent"), ("INV-2", "Paid")] {
+            sqlx::query(
+                "INSERT INTO invoices (invoice_number, contact_id, status_type, issue_date, due_date) VALUES (?, ?, ?, '2026-03-01', '2026-03-31')",
+            )
+            .bind(number)
+            .bind(contact)
+            .bind(status)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        let deadlines = [
+            TaxDeadline {
+                year: 2025,
+                quarter: 4,
+                due_date: date("2026-01-15"),
+            },
+            TaxDeadline {
+                year: 2026,
+                quarter: 1,
+                due_date: date("2026-04-15"),
+            },
+        ];
+
+        let events = deadline_events(&pool, &deadlines, date("2026-03-20"))
+            .await
+            .unwrap();
+        let summaries: Vec<&str> = events.iter().map(|e| e.summary.as_str()).collect();
+        assert_eq!(
+            summaries,
+            [
+                "Invoice INV-1 due (Acme)",
+                "Phone bill ($80.00)",
+                "Estimated tax payment due (2026 Q1)",
+            ]
+        );
+        assert_eq!(events[1].uid, "recurring-phone-bill-20260415");
+    }
+
+    #[test]
+    fn ics_escapes_and_folds() {
+        let event = CalendarEvent {
+            uid: "invoice-1".to_string(),
+            kind: CalendarEventKind::InvoiceDue,
+            date: date("2026-03-31"),
+            summary: "Invoice INV-1 due (Smith, Jones; Partners)".to_string(),
+            description: "x".repeat(100),
+        };
+        let stamp = DateTime::parse_from_rfc3339("2026-03-20T08:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let ics = write_ics(&[event], stamp);
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert!(ics.contains("UID:invoice-1@aequi\r\nDTSTAMP:20260320T080000Z\r\n"));
+        assert!(ics.contains("DTSTART;VALUE=DATE:20260331\r\nDTEND;VALUE=DATE:20260401\r\n"));
+        assert!(ics.contains("SUMMARY:Invoice INV-1 due (Smith\\, Jones\\; Partners)\r\n"));
+        assert!(ics.lines().all(|l| l.trim_end_matches('\r').len() <= 75));
+        assert!(ics.contains(&format!(
+            "DESCRIPTION:{}\r\n {}\r\n",
+            "x".repeat(63),
+            "x".repeat(37)
+        )));
+    }
+}
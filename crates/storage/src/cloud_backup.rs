@@ -0,0 +1,1227 @@
+//! Encrypted backups to a remote target.
+//!
+//! A snapshot is an ordinary backup archive (see [`crate::backup`]) encrypted
+//! on this machine with AES-256-GCM under a key derived from the user's
+//! passphrase, then uploaded to S3 (or any S3-compatible store), a WebDAV
+//! server, Dropbox or a plain directory such as a mounted NAS share. The
+//! passphrase is never stored or sent; without it a snapshot can't be read.
+//!
+//! Snapshots are named `aequi-<UTC timestamp>.tar.gz.aenc`, so listing the
+//! target is enough to find them, and a [`RetentionPolicy`] decides which
+//! old ones are deleted after each upload.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM};
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::{digest, hmac, pbkdf2};
+use serde::{Deserialize, Serialize};
+
+use crate::backup::{BackupManifest, RestoreResult};
+use crate::db::DbPool;
+
+/// Setting holding [`CloudBackupSettings`], as JSON.
+pub const CLOUD_BACKUP_SETTING: &str = "cloud_backup";
+
+const SNAPSHOT_PREFIX: &str = "aequi-";
+const SNAPSHOT_SUFFIX: &str = ".tar.gz.aenc";
+const SNAPSHOT_TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+/// Header of an encrypted snapshot: magic, PBKDF2 rounds (big-endian u32),
+/// salt and nonce, followed by the ciphertext and tag.
+const MAGIC: &[u8; 8] = b"AEQUIENC";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = MAGIC.len() + 4 + SALT_LEN + NONCE_LEN;
+
+/// PBKDF2-HMAC-SHA256 rounds for new snapshots.
+const KDF_ITERATIONS: u32 = 600_000;
+/// Snapshots claiming more rounds than this are rejected rather than tying
+/// up the machine.
+const MAX_KDF_ITERATIONS: u32 = 10_000_000;
+
+// ── Settings ──────────────────────────────────────────────────────────────────
+
+/// Where snapshots go. Credentials are kept with the other settings; the
+/// encryption passphrase is not.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RemoteTarget {
+    /// An S3 bucket, addressed path-style so S3-compatible stores (MinIO,
+    /// Backblaze B2, Wasabi) work too.
+    S3 {
+        /// e.g. `https://s3.us-east-1.amazonaws.com`
+        endpoint: String,
+        region: String,
+        bucket: String,
+        /// Key prefix, e.g. `aequi/`.
+        #[serde(default)]
+        prefix: String,
+        access_key_id: String,
+        secret_access_key: String,
+    },
+    /// A WebDAV collection, e.g. a Nextcloud folder.
+    WebDav {
+        url: String,
+        username: String,
+        password: String,
+    },
+    Dropbox {
+        access_token: String,
+        /// Folder path such as `/Apps/aequi`; empty for the app folder root.
+        #[serde(default)]
+        folder: String,
+    },
+    /// A local or mounted directory.
+    Directory { path: PathBuf },
+}
+
+// Redact credentials in Debug output
+impl std::fmt::Debug for RemoteTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RemoteTarget::S3 {
+                endpoint,
+                region,
+                bucket,
+                prefix,
+                ..
+            } => f
+                .debug_struct("S3")
+                .field("endpoint", endpoint)
+                .field("region", region)
+                .field("bucket", bucket)
+                .field("prefix", prefix)
+                .field("access_key_id", &"[REDACTED]")
+                .field("secret_access_key", &"[REDACTED]")
+                .finish(),
+            RemoteTarget::WebDav { url, username, .. } => f
+                .debug_struct("WebDav")
+                .field("url", url)
+                .field("username", username)
+                .field("password", &"[REDACTED]")
+                .finish(),
+            RemoteTarget::Dropbox { folder, .. } => f
+                .debug_struct("Dropbox")
+                .field("folder", folder)
+                .field("access_token", &"[REDACTED]")
+                .finish(),
+            RemoteTarget::Directory { path } => {
+                f.debug_struct("Directory").field("path", path).finish()
+            }
+        }
+    }
+}
+
+impl RemoteTarget {
+    pub fn validate(&self) -> Result<(), String> {
+        let required = |value: &str, what: &str| {
+            if value.trim().is_empty() {
+                Err(format!("{what} is required"))
+            } else {
+                Ok(())
+            }
+        };
+        match self {
+            RemoteTarget::S3 {
+                endpoint,
+                region,
+                bucket,
+                access_key_id,
+                secret_access_key,
+                ..
+            } => {
+                check_http_url(endpoint)?;
+                required(region, "Region")?;
+                required(bucket, "Bucket")?;
+                required(access_key_id, "Access key id")?;
+                required(secret_access_key, "Secret access key")
+            }
+            RemoteTarget::WebDav { url, .. } => check_http_url(url),
+            RemoteTarget::Dropbox {
+                access_token,
+                folder,
+            } => {
+                required(access_token, "Access token")?;
+                if !folder.is_empty() && !folder.starts_with('/') {
+                    return Err("Dropbox folder must start with '/'".into());
+                }
+                Ok(())
+            }
+            RemoteTarget::Directory { path } => {
+                if path.as_os_str().is_empty() {
+                    Err("Directory is required".into())
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
+fn check_http_url(url: &str) -> Result<(), String> {
+    match reqwest::Url::parse(url) {
+        Ok(u) if matches!(u.scheme(), "https" | "http") && u.host().is_some() => Ok(()),
+        _ => Err(format!("Not an http(s) URL: {url}")),
+    }
+}
+
+/// Which snapshots survive pruning. A snapshot is kept if any rule keeps it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    /// The most recent snapshots.
+    pub keep_last: usize,
+    /// The newest snapshot of each of the most recent days that have one.
+    pub keep_daily: usize,
+    /// The newest snapshot of each of the most recent months that have one.
+    pub keep_monthly: usize,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        RetentionPolicy {
+            keep_last: 3,
+            keep_daily: 7,
+            keep_monthly: 12,
+        }
+    }
+}
+
+impl RetentionPolicy {
+    /// The snapshots to delete. At least one snapshot is always kept.
+    pub fn expired<'a>(&self, snapshots: &'a [RemoteSnapshot]) -> Vec<&'a RemoteSnapshot> {
+        let mut newest_first: Vec<&RemoteSnapshot> = snapshots.iter().collect();
+        newest_first.sort_by_key(|s| std::cmp::Reverse(s.created_at));
+
+        let mut keep: HashSet<&str> = newest_first
+            .iter()
+            .take(self.keep_last.max(1))
+            .map(|s| s.name.as_str())
+            .collect();
+        for (format, count) in [("%Y-%m-%d", self.keep_daily), ("%Y-%m", self.keep_monthly)] {
+            let mut periods = HashSet::new();
+            for s in &newest_first {
+                if periods.len() == count {
+                    break;
+                }
+                if periods.insert(s.created_at.format(format).to_string()) {
+                    keep.insert(&s.name);
+                }
+            }
+        }
+        newest_first
+            .into_iter()
+            .filter(|s| !keep.contains(s.name.as_str()))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CloudBackupSettings {
+    pub target: Option<RemoteTarget>,
+    pub retention: RetentionPolicy,
+}
+
+pub async fn get_cloud_backup_settings(pool: &DbPool) -> Result<CloudBackupSettings, sqlx::Error> {
+    match crate::db::get_setting(pool, CLOUD_BACKUP_SETTING).await? {
+        Some(json) if !json.trim().is_empty() => {
+            serde_json::from_str(&json).map_err(|e| sqlx::Error::Decode(Box::new(e)))
+        }
+        _ => Ok(CloudBackupSettings::default()),
+    }
+}
+
+pub async fn set_cloud_backup_settings(
+    pool: &DbPool,
+    settings: &CloudBackupSettings,
+) -> Result<(), sqlx::Error> {
+    let json = serde_json::to_string(settings).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    crate::db::set_setting(pool, CLOUD_BACKUP_SETTING, &json).await
+}
+
+// ── Snapshots ─────────────────────────────────────────────────────────────────
+
+/// A snapshot found on the target.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RemoteSnapshot {
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub size_bytes: Option<u64>,
+}
+
+impl RemoteSnapshot {
+    /// Recognise a snapshot by its file name; anything else on the target is
+    /// ignored.
+    pub fn from_name(name: &str, size_bytes: Option<u64>) -> Option<Self> {
+        let stamp = name
+            .strip_prefix(SNAPSHOT_PREFIX)?
+            .strip_suffix(SNAPSHOT_SUFFIX)?;
+        let created_at = NaiveDateTime::parse_from_str(stamp, SNAPSHOT_TIME_FORMAT)
+            .ok()?
+            .and_utc();
+        Some(RemoteSnapshot {
+            name: name.to_string(),
+            created_at,
+            size_bytes,
+        })
+    }
+
+    fn name_for(time: DateTime<Utc>) -> String {
+        format!(
+            "{SNAPSHOT_PREFIX}{}{SNAPSHOT_SUFFIX}",
+            time.format(SNAPSHOT_TIME_FORMAT)
+        )
+    }
+}
+
+/// What one cloud backup run did.
+#[derive(Debug, Serialize)]
+pub struct CloudBackupOutcome {
+    pub snapshot: RemoteSnapshot,
+    pub manifest: BackupManifest,
+    /// Old snapshots deleted under the retention policy.
+    pub pruned: Vec<String>,
+}
+
+/// Back up the database and attachments, encrypt the archive with
+/// `passphrase`, upload it, then prune old snapshots.
+pub async fn upload_backup(
+    pool: &DbPool,
+    db_path: &Path,
+    attachments_dir: &Path,
+    settings: &CloudBackupSettings,
+    passphrase: &str,
+    app_version: &str,
+) -> Result<CloudBackupOutcome, CloudBackupError> {
+    let target = settings
+        .target
+        .as_ref()
+        .ok_or(CloudBackupError::NotConfigured)?;
+    check_passphrase(passphrase)?;
+    let store = RemoteStore::new(target.clone())?;
+
+    let now = Utc::now();
+    let archive_path = temp_path(&format!("{}.tar.gz", now.format(SNAPSHOT_TIME_FORMAT)));
+    let manifest =
+        crate::backup::create_backup(pool, db_path, attachments_dir, &archive_path, app_version)
+            .await;
+    let archive = manifest.and_then(|m| {
+        std::fs::read(&archive_path)
+            .map(|data| (m, data))
+            .map_err(|e| crate::backup::BackupError::Io(e.to_string()))
+    });
+    let _ = std::fs::remove_file(&archive_path);
+    let (manifest, archive) = archive?;
+
+    let sealed = encrypt_snapshot(&archive, passphrase)?;
+    let name = RemoteSnapshot::name_for(now);
+    let size = sealed.len() as u64;
+    store.put(&name, sealed).await?;
+
+    let snapshots = store.list().await?;
+    let mut pruned = Vec::new();
+    for old in settings.retention.expired(&snapshots) {
+        if old.name == name {
+            continue;
+        }
+        store.delete(&old.name).await?;
+        pruned.push(old.name.clone());
+    }
+
+    Ok(CloudBackupOutcome {
+        snapshot: RemoteSnapshot {
+            name,
+            created_at: now,
+            size_bytes: Some(size),
+        },
+        manifest,
+        pruned,
+    })
+}
+
+/// Snapshots on the target, newest first.
+pub async fn list_snapshots(
+    target: &RemoteTarget,
+) -> Result<Vec<RemoteSnapshot>, CloudBackupError> {
+    RemoteStore::new(target.clone())?.list().await
+}
+
+/// Download a snapshot, decrypt it and restore it into `target_dir` as
+/// [`crate::backup::restore_backup`] would.
+pub async fn restore_snapshot(
+    target: &RemoteTarget,
+    name: &str,
+    passphrase: &str,
+    target_dir: &Path,
+) -> Result<RestoreResult, CloudBackupError> {
+    if RemoteSnapshot::from_name(name, None).is_none() {
+        return Err(CloudBackupError::Invalid(format!(
+            "{name} is not a backup snapshot"
+        )));
+    }
+    let sealed = RemoteStore::new(target.clone())?.get(name).await?;
+    let archive = decrypt_snapshot(&sealed, passphrase)?;
+
+    let archive_path = temp_path(name.trim_end_matches(".aenc"));
+    std::fs::write(&archive_path, archive).map_err(|e| CloudBackupError::Io(e.to_string()))?;
+    let result = crate::backup::restore_backup(&archive_path, target_dir);
+    let _ = std::fs::remove_file(&archive_path);
+    Ok(result?)
+}
+
+fn temp_path(file_name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("aequi-cloud-{}-{file_name}", std::process::id()))
+}
+
+fn check_passphrase(passphrase: &str) -> Result<(), CloudBackupError> {
+    if passphrase.chars().count() < 8 {
+        return Err(CloudBackupError::Invalid(
+            "Passphrase must be at least 8 characters".into(),
+        ));
+    }
+    Ok(())
+}
+
+// ── Encryption ────────────────────────────────────────────────────────────────
+
+/// Encrypt a backup archive under a key derived from `passphrase`.
+pub fn encrypt_snapshot(plain: &[u8], passphrase: &str) -> Result<Vec<u8>, CloudBackupError> {
+    seal(plain, passphrase, KDF_ITERATIONS)
+}
+
+fn seal(plain: &[u8], passphrase: &str, iterations: u32) -> Result<Vec<u8>, CloudBackupError> {
+    let rng = SystemRandom::new();
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill(&mut salt)
+        .and_then(|_| rng.fill(&mut nonce))
+        .map_err(|_| CloudBackupError::Crypto("no randomness available".into()))?;
+
+    let mut out = Vec::with_capacity(HEADER_LEN + plain.len() + AES_256_GCM.tag_len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&iterations.to_be_bytes());
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+
+    let mut body = plain.to_vec();
+    derive_key(passphrase, &salt, iterations)?
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(&out[..HEADER_LEN]),
+            &mut body,
+        )
+        .map_err(|_| CloudBackupError::Crypto("encryption failed".into()))?;
+    out.extend_from_slice(&body);
+    Ok(out)
+}
+
+/// Decrypt a snapshot made by [`encrypt_snapshot`]. A wrong passphrase and a
+/// tampered file look the same: both fail authentication.
+pub fn decrypt_snapshot(sealed: &[u8], passphrase: &str) -> Result<Vec<u8>, CloudBackupError> {
+    if sealed.len() < HEADER_LEN + AES_256_GCM.tag_len() || &sealed[..MAGIC.len()] != MAGIC {
+        return Err(CloudBackupError::Invalid(
+            "not an encrypted aequi backup".into(),
+        ));
+    }
+    let (header, body) = sealed.split_at(HEADER_LEN);
+    let iterations = u32::from_be_bytes(header[8..12].try_into().expect("4 bytes"));
+    if iterations == 0 || iterations > MAX_KDF_ITERATIONS {
+        return Err(CloudBackupError::Invalid(
+            "unsupported key derivation settings".into(),
+        ));
+    }
+    let salt = &header[12..12 + SALT_LEN];
+    let nonce: [u8; NONCE_LEN] = header[12 + SALT_LEN..].try_into().expect("nonce length");
+
+    let mut body = body.to_vec();
+    let plain_len = derive_key(passphrase, salt, iterations)?
+        .open_in_place(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(header),
+            &mut body,
+        )
+        .map_err(|_| CloudBackupError::Crypto("wrong passphrase or damaged snapshot".into()))?
+        .len();
+    body.truncate(plain_len);
+    Ok(body)
+}
+
+fn derive_key(
+    passphrase: &str,
+    salt: &[u8],
+    iterations: u32,
+) -> Result<LessSafeKey, CloudBackupError> {
+    let mut key = [0u8; 32];
+    let rounds = std::num::NonZeroU32::new(iterations)
+        .ok_or_else(|| CloudBackupError::Crypto("zero key derivation rounds".into()))?;
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        rounds,
+        salt,
+        passphrase.as_bytes(),
+        &mut key,
+    );
+    let key = UnboundKey::new(&AES_256_GCM, &key)
+        .map_err(|_| CloudBackupError::Crypto("bad key".into()))?;
+    Ok(LessSafeKey::new(key))
+}
+
+// ── Targets ───────────────────────────────────────────────────────────────────
+
+const DROPBOX_API: &str = "https://api.dropboxapi.com/2";
+const DROPBOX_CONTENT: &str = "https://content.dropboxapi.com/2";
+
+/// A configured target with an HTTP client.
+pub struct RemoteStore {
+    http: reqwest::Client,
+    target: RemoteTarget,
+}
+
+impl RemoteStore {
+    pub fn new(target: RemoteTarget) -> Result<Self, CloudBackupError> {
+        target.validate().map_err(CloudBackupError::Invalid)?;
+        let http = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(600))
+            .build()
+            .expect("failed to build HTTP client");
+        Ok(RemoteStore { http, target })
+    }
+
+    pub async fn put(&self, name: &str, data: Vec<u8>) -> Result<(), CloudBackupError> {
+        match &self.target {
+            RemoteTarget::S3 { .. } => {
+                let url = self.s3_object_url(name)?;
+                let resp = self.s3_request(reqwest::Method::PUT, url, data).await?;
+                check_status(resp).await.map(drop)
+            }
+            RemoteTarget::WebDav {
+                username, password, ..
+            } => {
+                let url = self.webdav_url(name)?;
+                let send = |body: Vec<u8>| {
+                    self.http
+                        .put(url.clone())
+                        .basic_auth(username, Some(password))
+                        .body(body)
+                        .send()
+                };
+                let mut resp = send(data.clone()).await?;
+                // The folder doesn't exist yet: create it and try again.
+                if matches!(resp.status().as_u16(), 404 | 409) {
+                    let mkcol = reqwest::Method::from_bytes(b"MKCOL").expect("valid method");
+                    check_status(
+                        self.http
+                            .request(mkcol, self.webdav_url("")?)
+                            .basic_auth(username, Some(password))
+                            .send()
+                            .await?,
+                    )
+                    .await?;
+                    resp = send(data).await?;
+                }
+                check_status(resp).await.map(drop)
+            }
+            RemoteTarget::Dropbox { access_token, .. } => {
+                let arg = serde_json::json!({
+                    "path": self.dropbox_path(name),
+                    "mode": "overwrite",
+                });
+                let resp = self
+                    .http
+                    .post(format!("{DROPBOX_CONTENT}/files/upload"))
+                    .bearer_auth(access_token)
+                    .header("Dropbox-API-Arg", arg.to_string())
+                    .header("Content-Type", "application/octet-stream")
+                    .body(data)
+                    .send()
+                    .await?;
+                check_status(resp).await.map(drop)
+            }
+            RemoteTarget::Directory { path } => {
+                tokio::fs::create_dir_all(path).await?;
+                // Write under a temporary name so a partial upload never
+                // looks like a snapshot.
+                let partial = path.join(format!("{name}.partial"));
+                tokio::fs::write(&partial, data).await?;
+                tokio::fs::rename(&partial, path.join(name)).await?;
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn get(&self, name: &str) -> Result<Vec<u8>, CloudBackupError> {
+        let resp = match &self.target {
+            RemoteTarget::S3 { .. } => {
+                let url = self.s3_object_url(name)?;
+                self.s3_request(reqwest::Method::GET, url, Vec::new())
+                    .await?
+            }
+            RemoteTarget::WebDav {
+                username, password, ..
+            } => {
+                self.http
+                    .get(self.webdav_url(name)?)
+                    .basic_auth(username, Some(password))
+                    .send()
+                    .await?
+            }
+            RemoteTarget::Dropbox { access_token, .. } => {
+                let arg = serde_json::json!({ "path": self.dropbox_path(name) });
+                self.http
+                    .post(format!("{DROPBOX_CONTENT}/files/download"))
+                    .bearer_auth(access_token)
+                    .header("Dropbox-API-Arg", arg.to_string())
+                    .send()
+                    .await?
+            }
+            RemoteTarget::Directory { path } => {
+                return Ok(tokio::fs::read(path.join(name)).await?);
+            }
+        };
+        Ok(check_status(resp).await?.bytes().await?.to_vec())
+    }
+
+    pub async fn delete(&self, name: &str) -> Result<(), CloudBackupError> {
+        let resp = match &self.target {
+            RemoteTarget::S3 { .. } => {
+                let url = self.s3_object_url(name)?;
+                self.s3_request(reqwest::Method::DELETE, url, Vec::new())
+                    .await?
+            }
+            RemoteTarget::WebDav {
+                username, password, ..
+            } => {
+                self.http
+                    .delete(self.webdav_url(name)?)
+                    .basic_auth(username, Some(password))
+                    .send()
+                    .await?
+            }
+            RemoteTarget::Dropbox { access_token, .. } => {
+                self.http
+                    .post(format!("{DROPBOX_API}/files/delete_v2"))
+                    .bearer_auth(access_token)
+                    .json(&serde_json::json!({ "path": self.dropbox_path(name) }))
+                    .send()
+                    .await?
+            }
+            RemoteTarget::Directory { path } => {
+                tokio::fs::remove_file(path.join(name)).await?;
+                return Ok(());
+            }
+        };
+        check_status(resp).await.map(drop)
+    }
+
+    /// Snapshots on the target, newest first.
+    pub async fn list(&self) -> Result<Vec<RemoteSnapshot>, CloudBackupError> {
+        let files = match &self.target {
+            RemoteTarget::S3 { .. } => self.s3_list().await?,
+            RemoteTarget::WebDav { .. } => self.webdav_list().await?,
+            RemoteTarget::Dropbox { .. } => self.dropbox_list().await?,
+            RemoteTarget::Directory { path } => {
+                let mut files = Vec::new();
+                let mut entries = match tokio::fs::read_dir(path).await {
+                    Ok(entries) => entries,
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+                    Err(e) => return Err(e.into()),
+                };
+                while let Some(entry) = entries.next_entry().await? {
+                    let size = entry.metadata().await.ok().map(|m| m.len());
+                    files.push((entry.file_name().to_string_lossy().into_owned(), size));
+                }
+                files
+            }
+        };
+        let mut snapshots: Vec<RemoteSnapshot> = files
+            .into_iter()
+            .filter_map(|(name, size)| RemoteSnapshot::from_name(&name, size))
+            .collect();
+        snapshots.sort_by_key(|s| std::cmp::Reverse(s.created_at));
+        Ok(snapshots)
+    }
+
+    // ── S3 ──
+
+    fn s3_object_url(&self, name: &str) -> Result<reqwest::Url, CloudBackupError> {
+        let RemoteTarget::S3 {
+            endpoint,
+            bucket,
+            prefix,
+            ..
+        } = &self.target
+        else {
+            unreachable!("S3 request for a non-S3 target");
+        };
+        let key = format!("{prefix}{name}");
+        parse_url(&format!(
+            "{}/{}/{}",
+            endpoint.trim_end_matches('/'),
+            uri_encode(bucket, true),
+            uri_encode(&key, false)
+        ))
+    }
+
+    async fn s3_list(&self) -> Result<Vec<(String, Option<u64>)>, CloudBackupError> {
+        let RemoteTarget::S3 {
+            endpoint,
+            bucket,
+            prefix,
+            ..
+        } = &self.target
+        else {
+            unreachable!("S3 request for a non-S3 target");
+        };
+        let mut files = Vec::new();
+        let mut continuation: Option<String> = None;
+        loop {
+            let mut query = vec![
+                ("list-type".to_string(), "2".to_string()),
+                ("prefix".to_string(), prefix.clone()),
+            ];
+            if let Some(token) = continuation.take() {
+                query.push(("continuation-token".to_string(), token));
+            }
+            query.sort();
+            let query_string = query
+                .iter()
+                .map(|(k, v)| format!("{}={}", uri_encode(k, true), uri_encode(v, true)))
+                .collect::<Vec<_>>()
+                .join("&");
+            let url = parse_url(&format!(
+                "{}/{}?{query_string}",
+                endpoint.trim_end_matches('/'),
+                uri_encode(bucket, true),
+            ))?;
+            let resp = self
+                .s3_request(reqwest::Method::GET, url, Vec::new())
+                .await?;
+            let xml = check_status(resp).await?.text().await?;
+            for item in xml_elements(&xml, "Contents") {
+                if let Some(key) = xml_elements(item, "Key").first() {
+                    let key = xml_unescape(key);
+                    let name = key.strip_prefix(prefix.as_str()).unwrap_or(&key);
+                    let size = xml_elements(item, "Size")
+                        .first()
+                        .and_then(|s| s.trim().parse().ok());
+                    files.push((name.to_string(), size));
+                }
+            }
+            let truncated = xml_elements(&xml, "IsTruncated")
+                .first()
+                .is_some_and(|t| t.trim() == "true");
+            continuation = xml_elements(&xml, "NextContinuationToken")
+                .first()
+                .map(|t| xml_unescape(t));
+            if !truncated || continuation.is_none() {
+                return Ok(files);
+            }
+        }
+    }
+
+    /// Send a request signed with AWS Signature Version 4.
+    async fn s3_request(
+        &self,
+        method: reqwest::Method,
+        url: reqwest::Url,
+        body: Vec<u8>,
+    ) -> Result<reqwest::Response, CloudBackupError> {
+        let RemoteTarget::S3 {
+            region,
+            access_key_id,
+            secret_access_key,
+            ..
+        } = &self.target
+        else {
+            unreachable!("S3 request for a non-S3 target");
+        };
+        let headers = sigv4_headers(
+            method.as_str(),
+            &url,
+            &body,
+            region,
+            access_key_id,
+            secret_access_key,
+            Utc::now(),
+        );
+        let mut request = self.http.request(method, url).body(body);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        Ok(request.send().await?)
+    }
+
+    // ── WebDAV ──
+
+    fn webdav_url(&self, name: &str) -> Result<reqwest::Url, CloudBackupError> {
+        let RemoteTarget::WebDav { url, .. } = &self.target else {
+            unreachable!("WebDAV request for a non-WebDAV target");
+        };
+        parse_url(&format!(
+            "{}/{}",
+            url.trim_end_matches('/'),
+            uri_encode(name, true)
+        ))
+    }
+
+    async fn webdav_list(&self) -> Result<Vec<(String, Option<u64>)>, CloudBackupError> {
+        let RemoteTarget::WebDav {
+            username, password, ..
+        } = &self.target
+        else {
+            unreachable!("WebDAV request for a non-WebDAV target");
+        };
+        let propfind = reqwest::Method::from_bytes(b"PROPFIND").expect("valid method");
+        let resp = self
+            .http
+            .request(propfind, self.webdav_url("")?)
+            .basic_auth(username, Some(password))
+            .header("Depth", "1")
+            .header("Content-Type", "application/xml")
+            .body(r#"<?xml version="1.0"?><d:propfind xmlns:d="DAV:"><d:prop><d:getcontentlength/></d:prop></d:propfind>"#)
+            .send()
+            .await?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(Vec::new());
+        }
+        let xml = check_status(resp).await?.text().await?;
+        Ok(xml_elements(&xml, "response")
+            .into_iter()
+            .filter_map(|item| {
+                let href = xml_unescape(xml_elements(item, "href").first()?);
+                // Snapshot names need no percent-decoding.
+                let name = href.trim_end_matches('/').rsplit('/').next()?.to_string();
+                let size = xml_elements(item, "getcontentlength")
+                    .first()
+                    .and_then(|s| s.trim().parse().ok());
+                Some((name, size))
+            })
+            .collect())
+    }
+
+    // ── Dropbox ──
+
+    fn dropbox_path(&self, name: &str) -> String {
+        let RemoteTarget::Dropbox { folder, .. } = &self.target else {
+            unreachable!("Dropbox request for a non-Dropbox target");
+        };
+        format!("{}/{name}", folder.trim_end_matches('/'))
+    }
+
+    async fn dropbox_list(&self) -> Result<Vec<(String, Option<u64>)>, CloudBackupError> {
+        let RemoteTarget::Dropbox {
+            access_token,
+            folder,
+        } = &self.target
+        else {
+            unreachable!("Dropbox request for a non-Dropbox target");
+        };
+        #[derive(Deserialize)]
+        struct Entry {
+            #[serde(rename = ".tag")]
+            tag: String,
+            name: String,
+            size: Option<u64>,
+        }
+        #[derive(Deserialize)]
+        struct Page {
+            entries: Vec<Entry>,
+            cursor: String,
+            has_more: bool,
+        }
+
+        let mut files = Vec::new();
+        let mut resp = self
+            .http
+            .post(format!("{DROPBOX_API}/files/list_folder"))
+            .bearer_auth(access_token)
+            .json(&serde_json::json!({ "path": folder.trim_end_matches('/') }))
+            .send()
+            .await?;
+        // Nothing has been uploaded yet.
+        if resp.status() == reqwest::StatusCode::CONFLICT {
+            return Ok(files);
+        }
+        loop {
+            let page: Page = check_status(resp).await?.json().await?;
+            files.extend(
+                page.entries
+                    .into_iter()
+                    .filter(|e| e.tag == "file")
+                    .map(|e| (e.name, e.size)),
+            );
+            if !page.has_more {
+                return Ok(files);
+            }
+            resp = self
+                .http
+                .post(format!("{DROPBOX_API}/files/list_folder/continue"))
+                .bearer_auth(access_token)
+                .json(&serde_json::json!({ "cursor": page.cursor }))
+                .send()
+                .await?;
+        }
+    }
+}
+
+fn parse_url(url: &str) -> Result<reqwest::Url, CloudBackupError> {
+    reqwest::Url::parse(url).map_err(|e| CloudBackupError::Invalid(format!("{url}: {e}")))
+}
+
+async fn check_status(resp: reqwest::Response) -> Result<reqwest::Response, CloudBackupError> {
+    let status = resp.status();
+    if status.is_success() {
+        return Ok(resp);
+    }
+    let body = resp.text().await.unwrap_or_default();
+    let detail: String = body.chars().take(300).collect();
+    Err(CloudBackupError::Remote {
+        status: status.as_u16(),
+        detail,
+    })
+}
+
+// ── AWS Signature Version 4 ──
+
+/// Percent-encode everything but unreserved characters, as SigV4 requires.
+/// Slashes are kept when encoding an object key.
+fn uri_encode(s: &str, encode_slash: bool) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(b as char)
+            }
+            b'/' if !encode_slash => out.push('/'),
+            _ => out.push_str(&format!("%{b:02X}")),
+        }
+    }
+    out
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, key), data.as_bytes())
+        .as_ref()
+        .to_vec()
+}
+
+fn sigv4_signing_key(secret: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let k_date = hmac_sha256(format!("AWS4{secret}").as_bytes(), date);
+    let k_region = hmac_sha256(&k_date, region);
+    let k_service = hmac_sha256(&k_region, service);
+    hmac_sha256(&k_service, "aws4_request")
+}
+
+/// The headers that sign an S3 request. `url` must already be encoded the
+/// way SigV4 expects (see [`uri_encode`]).
+fn sigv4_headers(
+    method: &str,
+    url: &reqwest::Url,
+    body: &[u8],
+    region: &str,
+    access_key_id: &str,
+    secret_access_key: &str,
+    now: DateTime<Utc>,
+) -> Vec<(&'static str, String)> {
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+    let payload_hash = hex(digest::digest(&digest::SHA256, body).as_ref());
+    let host = match url.port() {
+        Some(port) => format!("{}:{port}", url.host_str().unwrap_or_default()),
+        None => url.host_str().unwrap_or_default().to_string(),
+    };
+
+    let canonical_request = format!(
+        "{method}\n{}\n{}\nhost:{host}\nx-amz-content-sha256:{payload_hash}\nx-amz-date:{amz_date}\n\nhost;x-amz-content-sha256;x-amz-date\n{payload_hash}",
+        url.path(),
+        url.query().unwrap_or_default(),
+    );
+    let scope = format!("{date}/{region}/s3/aws4_request");
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+        hex(digest::digest(&digest::SHA256, canonical_request.as_bytes()).as_ref())
+    );
+    let signature = hex(&hmac_sha256(
+        &sigv4_signing_key(secret_access_key, &date, region, "s3"),
+        &string_to_sign,
+    ));
+
+    vec![
+        ("x-amz-date", amz_date),
+        ("x-amz-content-sha256", payload_hash),
+        (
+            "authorization",
+            format!(
+                "AWS4-HMAC-SHA256 Credential={access_key_id}/{scope}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={signature}"
+            ),
+        ),
+    ]
+}
+
+// ── XML ──
+
+/// The contents of every `<name>` element, with or without a namespace
+/// prefix. Enough for S3 listings and WebDAV multistatus replies.
+fn xml_elements<'a>(xml: &'a str, name: &str) -> Vec<&'a str> {
+    let mut found = Vec::new();
+    let mut rest = xml;
+    while let Some(open) = rest.find('<') {
+        rest = &rest[open + 1..];
+        let Some(end) = rest.find('>') else { break };
+        let tag = &rest[..end];
+        let qualified = tag.split_whitespace().next().unwrap_or_default();
+        let local = qualified.rsplit(':').next().unwrap_or_default();
+        if local != name || tag.starts_with('/') || tag.ends_with('/') {
+            continue;
+        }
+        let body = &rest[end + 1..];
+        let close = format!("</{qualified}>");
+        if let Some(stop) = body.find(&close) {
+            found.push(&body[..stop]);
+            rest = &body[stop + close.len()..];
+        }
+    }
+    found
+}
+
+fn xml_unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CloudBackupError {
+    #[error("No backup target is configured")]
+    NotConfigured,
+    #[error("{0}")]
+    Invalid(String),
+    #[error("Encryption error: {0}")]
+    Crypto(String),
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("Backup target returned HTTP {status}: {detail}")]
+    Remote { status: u16, detail: String },
+    #[error("IO error: {0}")]
+    Io(String),
+    #[error(transparent)]
+    Backup(#[from] crate::backup::BackupError),
+}
+
+impl From<std::io::Error> for CloudBackupError {
+    fn from(e: std::io::Error) -> Self {
+        CloudBackupError::Io(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    fn snapshot(y: i32, m: u32, d: u32, h: u32) -> RemoteSnapshot {
+        let time = Utc.with_ymd_and_hms(y, m, d, h, 0, 0).unwrap();
+        RemoteSnapshot::from_name(&RemoteSnapshot::name_for(time), None).unwrap()
+    }
+
+    #[test]
+    fn encryption_round_trips_and_authenticates() {
+        let plain = b"ledger snapshot bytes".repeat(100);
+        let sealed = seal(&plain, "correct horse battery", 1_000).unwrap();
+        assert_ne!(&sealed[HEADER_LEN..HEADER_LEN + 20], &plain[..20]);
+        assert_eq!(
+            decrypt_snapshot(&sealed, "correct horse battery").unwrap(),
+            plain
+        );
+
+        assert!(matches!(
+            decrypt_snapshot(&sealed, "wrong passphrase"),
+            Err(CloudBackupError::Crypto(_))
+        ));
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(decrypt_snapshot(&tampered, "correct horse battery").is_err());
+        // The header is authenticated too.
+        let mut tampered = sealed;
+        tampered[20] ^= 1;
+        assert!(decrypt_snapshot(&tampered, "correct horse battery").is_err());
+        assert!(matches!(
+            decrypt_snapshot(b"plain tarball", "correct horse battery"),
+            Err(CloudBackupError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn snapshot_names() {
+        let s = snapshot(2026, 3, 15, 9);
+        assert_eq!(s.name, "aequi-20260315T090000Z.tar.gz.aenc");
+        assert!(RemoteSnapshot::from_name("aequi-notadate.tar.gz.aenc", None).is_none());
+        assert!(RemoteSnapshot::from_name("notes.txt", None).is_none());
+        assert!(RemoteSnapshot::from_name("../aequi-20260315T090000Z.tar.gz.aenc", None).is_none());
+    }
+
+    #[test]
+    fn retention_keeps_recent_daily_and_monthly() {
+        let snapshots = vec![
+            snapshot(2026, 3, 15, 18),
+            snapshot(2026, 3, 15, 9),
+            snapshot(2026, 3, 14, 18),
+            snapshot(2026, 3, 14, 9),
+            snapshot(2026, 3, 13, 9),
+            snapshot(2026, 3, 1, 9),
+            snapshot(2026, 2, 20, 9),
+            snapshot(2026, 2, 10, 9),
+            snapshot(2026, 1, 5, 9),
+        ];
+        let policy = RetentionPolicy {
+            keep_last: 1,
+            keep_daily: 2,
+            keep_monthly: 2,
+        };
+        let expired: Vec<&str> = policy
+            .expired(&snapshots)
+            .into_iter()
+            .map(|s| s.name.as_str())
+            .collect();
+        // Kept: the latest, the newest of the 15th and 14th, and the newest
+        // of March and February.
+        assert_eq!(
+            expired,
+            vec![
+                snapshots[1].name.as_str(),
+                snapshots[3].name.as_str(),
+                snapshots[4].name.as_str(),
+                snapshots[5].name.as_str(),
+                snapshots[7].name.as_str(),
+                snapshots[8].name.as_str(),
+            ]
+        );
+
+        let keep_nothing = RetentionPolicy {
+            keep_last: 0,
+            keep_daily: 0,
+            keep_monthly: 0,
+        };
+        assert_eq!(keep_nothing.expired(&snapshots).len(), snapshots.len() - 1);
+    }
+
+    #[test]
+    fn sigv4_signing_key_matches_aws_example() {
+        // From the AWS "Deriving the signing key" example.
+        let key = sigv4_signing_key(
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "20120215",
+            "us-east-1",
+            "iam",
+        );
+        assert_eq!(
+            hex(&key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+        assert_eq!(uri_encode("a b/c+d", false), "a%20b/c%2Bd");
+        assert_eq!(uri_encode("a/b", true), "a%2Fb");
+    }
+
+    #[test]
+    fn parses_listing_xml() {
+        let s3 = r#"<?xml version="1.0"?><ListBucketResult><IsTruncated>false</IsTruncated>
+            <Contents><Key>aequi/aequi-20260315T090000Z.tar.gz.aenc</Key><Size>1024</Size></Contents>
+            <Contents><Key>aequi/notes &amp; things.txt</Key><Size>3</Size></Contents>
+            </ListBucketResult>"#;
+        let contents = xml_elements(s3, "Contents");
+        assert_eq!(contents.len(), 2);
+        assert_eq!(
+            xml_elements(contents[1], "Key")[0],
+            "aequi/notes &amp; things.txt"
+        );
+        assert_eq!(xml_unescape("notes &amp; things"), "notes & things");
+
+        let dav = r#"<d:multistatus xmlns:d="DAV:">
+            <d:response><d:href>/dav/aequi/</d:href><d:propstat><d:prop><d:getcontentlength/></d:prop></d:propstat></d:response>
+            <d:response><d:href>/dav/aequi/aequi-20260315T090000Z.tar.gz.aenc</d:href>
+              <d:propstat><d:prop><d:getcontentlength>2048</d:getcontentlength></d:prop></d:propstat></d:response>
+            </d:multistatus>"#;
+        let responses = xml_elements(dav, "response");
+        assert_eq!(responses.len(), 2);
+        assert!(xml_elements(responses[0], "getcontentlength").is_empty());
+        assert_eq!(xml_elements(responses[1], "getcontentlength"), vec!["2048"]);
+    }
+
+    #[tokio::test]
+    async fn directory_target_backup_list_and_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("ledger.db");
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect(&format!("sqlite:{}?mode=rwc", db_path.display()))
+            .await
+            .unwrap();
+        crate::migrate::run_migrations(&pool).await.unwrap();
+        crate::db::seed_default_accounts(&pool).await.unwrap();
+
+        let settings = CloudBackupSettings {
+            target: Some(RemoteTarget::Directory {
+                path: dir.path().join("remote"),
+            }),
+            retention: RetentionPolicy::default(),
+        };
+        assert!(matches!(
+            upload_backup(
+                &pool,
+                &db_path,
+                &dir.path().join("none"),
+                &settings,
+                "short",
+                "test"
+            )
+            .await,
+            Err(CloudBackupError::Invalid(_))
+        ));
+        let outcome = upload_backup(
+            &pool,
+            &db_path,
+            &dir.path().join("none"),
+            &settings,
+            "a long passphrase",
+            "test",
+        )
+        .await
+        .unwrap();
+        assert!(outcome.pruned.is_empty());
+
+        let target = settings.target.as_ref().unwrap();
+        let listed = list_snapshots(target).await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].name, outcome.snapshot.name);
+        let stored = std::fs::read(dir.path().join("remote").join(&listed[0].name)).unwrap();
+        assert_eq!(&stored[..MAGIC.len()], MAGIC);
+
+        let restored_dir = dir.path().join("restored");
+        assert!(
+            restore_snapshot(target, &listed[0].name, "not the passphrase", &restored_dir)
+                .await
+                .is_err()
+        );
+        let restored =
+            restore_snapshot(target, &listed[0].name, "a long passphrase", &restored_dir)
+                .await
+                .unwrap();
+        assert!(restored.db_path.exists());
+        assert!(
+            restore_snapshot(target, "../ledger.db", "a long passphrase", &restored_dir)
+                .await
+                .is_err()
+        );
+    }
+}
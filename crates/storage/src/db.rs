@@ -1,38 +1,50 @@
+use aequi_core::import::{
+    AutoMatchEngine, CategoryRule, CsvColumnMapping, CsvImportProfile, MatchableReceipt,
+    MatchableTransaction, ReceiptLinkTarget, ReceiptMatchCandidate, ReceiptMatchSuggestion,
+};
 use aequi_core::{
-    Account, AccountId, AccountType, FiscalYear, LedgerSnapshot, Money, ScheduleCLine,
+    Account, AccountId, AccountType, DateRange, Deductibility, FiscalYear, LedgerSnapshot, Locale,
+    Money, MoneyFormat, PaymentAccountMap, ScheduleCLine, TransactionLine, ValidatedTransaction,
     DEFAULT_ACCOUNTS,
 };
-use sqlx::{sqlite::SqlitePoolOptions, Pool, Sqlite};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous};
+use sqlx::{Pool, QueryBuilder, Sqlite};
 use std::collections::BTreeMap;
 use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::receipt_status::{receipt_state, ReceiptStatus, ReceiptStatusError};
 
 pub type DbPool = Pool<Sqlite>;
 
 pub async fn create_db(path: &Path) -> Result<DbPool, sqlx::Error> {
+    // Set on every connection the pool opens, not just the first.
+    let options = SqliteConnectOptions::from_str(&format!("sqlite:{}?mode=rwc", path.display()))?
+        .journal_mode(SqliteJournalMode::Wal)
+        .foreign_keys(true)
+        .synchronous(SqliteSynchronous::Normal)
+        .busy_timeout(Duration::from_secs(5))
+        .pragma("cache_size", "-32000");
+
     // WAL mode supports concurrent readers + one writer.
     // 4 connections allows parallel reads without serializing everything.
     let pool = SqlitePoolOptions::new()
         .max_connections(4)
-        .connect(&format!("sqlite:{}?mode=rwc", path.display()))
+        .connect_with(options.clone())
         .await?;
 
-    sqlx::query("PRAGMA journal_mode = WAL")
-        .execute(&pool)
-        .await?;
-    sqlx::query("PRAGMA foreign_keys = ON")
-        .execute(&pool)
-        .await?;
-    sqlx::query("PRAGMA synchronous = NORMAL")
-        .execute(&pool)
-        .await?;
-    sqlx::query("PRAGMA busy_timeout = 5000")
-        .execute(&pool)
-        .await?;
-    sqlx::query("PRAGMA cache_size = -32000")
-        .execute(&pool)
+    // Migrations go through a connection of their own, so each statement
+    // sees the schema the one before it left.
+    let migrator = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(options)
         .await?;
-
-    crate::migrate::run_migrations(&pool).await?;
+    let migrated = crate::migrate::run_migrations(&migrator).await;
+    migrator.close().await;
+    migrated?;
 
     Ok(pool)
 }
@@ -61,50 +73,74 @@ pub async fn seed_default_accounts(pool: &DbPool) -> Result<(), sqlx::Error> {
     Ok(())
 }
 
-type AccountRow = (i64, String, String, String, i64, i64, Option<String>);
+/// Columns read into [`AccountRow`].
+const ACCOUNT_COLUMNS: &str =
+    "id, code, name, account_type, is_archetype, is_archived, schedule_c_line, deductible_pct";
 
-fn row_to_account(r: AccountRow) -> Account {
-    let account_type = match r.3.as_str() {
-        "Asset" => AccountType::Asset,
-        "Liability" => AccountType::Liability,
-        "Equity" => AccountType::Equity,
-        "Income" => AccountType::Income,
-        "Expense" => AccountType::Expense,
-        _ => AccountType::Asset,
-    };
-    Account {
-        id: Some(AccountId(r.0)),
-        code: r.1,
-        name: r.2,
-        account_type,
-        is_archetype: r.4 != 0,
-        is_archived: r.5 != 0,
-        schedule_c_line: r.6,
+#[derive(sqlx::FromRow)]
+struct AccountRow {
+    id: i64,
+    code: String,
+    name: String,
+    account_type: String,
+    is_archetype: bool,
+    is_archived: bool,
+    schedule_c_line: Option<String>,
+    deductible_pct: u8,
+}
+
+impl From<AccountRow> for Account {
+    fn from(r: AccountRow) -> Self {
+        let account_type = match r.account_type.as_str() {
+            "Asset" => AccountType::Asset,
+            "Liability" => AccountType::Liability,
+            "Equity" => AccountType::Equity,
+            "Income" => AccountType::Income,
+            "Expense" => AccountType::Expense,
+            _ => AccountType::Asset,
+        };
+        Account {
+            id: Some(AccountId(r.id)),
+            code: r.code,
+            name: r.name,
+            account_type,
+            is_archetype: r.is_archetype,
+            is_archived: r.is_archived,
+            schedule_c_line: r.schedule_c_line,
+            deductible_pct: r.deductible_pct,
+        }
     }
 }
 
 pub async fn get_all_accounts(pool: &DbPool) -> Result<Vec<Account>, sqlx::Error> {
-    let rows = sqlx::query_as::<_, AccountRow>(
-        "SELECT id, code, name, account_type, is_archetype, is_archived, schedule_c_line FROM accounts WHERE is_archived = 0 ORDER BY code"
-    )
+    let rows = sqlx::query_as::<_, AccountRow>(&format!(
+        "SELECT {ACCOUNT_COLUMNS} FROM accounts WHERE is_archived = 0 ORDER BY code"
+    ))
     .fetch_all(pool)
     .await?;
 
-    Ok(rows.into_iter().map(row_to_account).collect())
+    Ok(rows.into_iter().map(Account::from).collect())
 }
 
 pub async fn get_account_by_code(
     pool: &DbPool,
     code: &str,
 ) -> Result<Option<Account>, sqlx::Error> {
-    let row = sqlx::query_as::<_, AccountRow>(
-        "SELECT id, code, name, account_type, is_archetype, is_archived, schedule_c_line FROM accounts WHERE code = ?"
-    )
+    let row = sqlx::query_as::<_, AccountRow>(&format!(
+        "SELECT {ACCOUNT_COLUMNS} FROM accounts WHERE code = ?"
+    ))
     .bind(code)
     .fetch_optional(pool)
     .await?;
 
-    Ok(row.map(row_to_account))
+    Ok(row.map(Account::from))
+}
+
+/// Accounts not archived.
+pub async fn count_active_accounts(pool: &DbPool) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar("SELECT COUNT(*) FROM accounts WHERE is_archived = 0")
+        .fetch_one(pool)
+        .await
 }
 
 #[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
@@ -123,6 +159,69 @@ pub struct ImportProfile {
     pub created_at: String,
 }
 
+/// A stored profile whose column indices cannot drive a CSV import.
+#[derive(Debug, thiserror::Error)]
+#[error("import profile '{profile}': {column} has invalid index {value}")]
+pub struct ProfileConversionError {
+    pub profile: String,
+    pub column: &'static str,
+    pub value: i64,
+}
+
+impl TryFrom<ImportProfile> for CsvImportProfile {
+    type Error = ProfileConversionError;
+
+    fn try_from(p: ImportProfile) -> Result<Self, Self::Error> {
+        let column = |column: &'static str, value: Option<i64>| {
+            value
+                .map(|v| {
+                    usize::try_from(v).map_err(|_| ProfileConversionError {
+                        profile: p.name.clone(),
+                        column,
+                        value: v,
+                    })
+                })
+                .transpose()
+        };
+        let mapping = CsvColumnMapping {
+            date_column: column("date_column", p.date_column)?,
+            description_column: column("description_column", p.description_column)?,
+            amount_column: column("amount_column", p.amount_column)?,
+            debit_column: column("debit_column", p.debit_column)?,
+            credit_column: column("credit_column", p.credit_column)?,
+            memo_column: column("memo_column", p.memo_column)?,
+            date_format: p.date_format,
+        };
+        Ok(CsvImportProfile {
+            id: Some(p.id),
+            name: p.name,
+            mapping,
+            has_header: p.has_header,
+            delimiter: p.delimiter,
+        })
+    }
+}
+
+impl From<&CsvImportProfile> for ImportProfile {
+    fn from(p: &CsvImportProfile) -> Self {
+        let column = |c: Option<usize>| c.map(|v| v as i64);
+        ImportProfile {
+            id: p.id.unwrap_or(0),
+            name: p.name.clone(),
+            has_header: p.has_header,
+            delimiter: p.delimiter.clone(),
+            date_column: column(p.mapping.date_column),
+            description_column: column(p.mapping.description_column),
+            amount_column: column(p.mapping.amount_column),
+            debit_column: column(p.mapping.debit_column),
+            credit_column: column(p.mapping.credit_column),
+            memo_column: column(p.mapping.memo_column),
+            date_format: p.mapping.date_format.clone(),
+            created_at: String::new(),
+        }
+    }
+}
+
 pub async fn save_import_profile(
     pool: &DbPool,
     profile: &ImportProfile,
@@ -149,6 +248,35 @@ pub async fn save_import_profile(
     Ok(result.last_insert_rowid())
 }
 
+/// Overwrite a saved profile. Returns false if there is no profile `profile.id`.
+pub async fn update_import_profile(
+    pool: &DbPool,
+    profile: &ImportProfile,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"UPDATE import_profiles
+           SET name = ?, has_header = ?, delimiter = ?, date_column = ?, description_column = ?,
+               amount_column = ?, debit_column = ?, credit_column = ?, memo_column = ?,
+               date_format = ?
+           WHERE id = ?"#,
+    )
+    .bind(&profile.name)
+    .bind(profile.has_header)
+    .bind(&profile.delimiter)
+    .bind(profile.date_column)
+    .bind(profile.description_column)
+    .bind(profile.amount_column)
+    .bind(profile.debit_column)
+    .bind(profile.credit_column)
+    .bind(profile.memo_column)
+    .bind(&profile.date_format)
+    .bind(profile.id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
 pub async fn get_import_profiles(pool: &DbPool) -> Result<Vec<ImportProfile>, sqlx::Error> {
     let rows = sqlx::query_as::<_, ImportProfile>("SELECT * FROM import_profiles ORDER BY name")
         .fetch_all(pool)
@@ -157,6 +285,27 @@ pub async fn get_import_profiles(pool: &DbPool) -> Result<Vec<ImportProfile>, sq
     Ok(rows)
 }
 
+/// Save a profile in the form the CSV importer consumes. Returns the new row id.
+pub async fn save_csv_import_profile(
+    pool: &DbPool,
+    profile: &CsvImportProfile,
+) -> Result<i64, sqlx::Error> {
+    save_import_profile(pool, &ImportProfile::from(profile)).await
+}
+
+/// Saved profiles ready to pass to the import crate's `import_csv`. Rows with
+/// corrupt column indices are reported individually rather than failing the
+/// whole list.
+pub async fn get_csv_import_profiles(
+    pool: &DbPool,
+) -> Result<Vec<Result<CsvImportProfile, ProfileConversionError>>, sqlx::Error> {
+    Ok(get_import_profiles(pool)
+        .await?
+        .into_iter()
+        .map(CsvImportProfile::try_from)
+        .collect())
+}
+
 pub async fn delete_import_profile(pool: &DbPool, id: i64) -> Result<(), sqlx::Error> {
     sqlx::query("DELETE FROM import_profiles WHERE id = ?")
         .bind(id)
@@ -174,33 +323,93 @@ pub struct CategorizationRule {
     pub match_pattern: String,
     pub match_type: String,
     pub account_id: i64,
+    pub amount_min_cents: Option<i64>,
+    pub amount_max_cents: Option<i64>,
     pub created_at: String,
 }
 
+impl CategorizationRule {
+    /// The row for an engine rule booking to `account_id`.
+    pub fn from_category_rule(id: i64, rule: &CategoryRule, account_id: i64) -> Self {
+        Self {
+            id,
+            name: rule.name.clone(),
+            priority: rule.priority,
+            match_pattern: rule.pattern.clone(),
+            match_type: rule.match_type.to_string(),
+            account_id,
+            amount_min_cents: rule.amount_min_cents,
+            amount_max_cents: rule.amount_max_cents,
+            created_at: String::new(),
+        }
+    }
+
+    /// The engine rule for this row, given the code of its account. Fails if
+    /// the stored match type can't be read.
+    pub fn to_category_rule(&self, account_code: &str) -> Result<CategoryRule, String> {
+        Ok(CategoryRule {
+            name: self.name.clone(),
+            priority: self.priority,
+            pattern: self.match_pattern.clone(),
+            match_type: self.match_type.parse()?,
+            account_code: account_code.to_string(),
+            amount_min_cents: self.amount_min_cents,
+            amount_max_cents: self.amount_max_cents,
+        })
+    }
+}
+
 pub async fn save_categorization_rule(
     pool: &DbPool,
     rule: &CategorizationRule,
 ) -> Result<i64, sqlx::Error> {
     let result = sqlx::query(
-        r#"INSERT INTO categorization_rules (name, priority, match_pattern, match_type, account_id)
-           VALUES (?, ?, ?, ?, ?)"#,
+        r#"INSERT INTO categorization_rules
+               (name, priority, match_pattern, match_type, account_id, amount_min_cents, amount_max_cents)
+           VALUES (?, ?, ?, ?, ?, ?, ?)"#,
     )
     .bind(&rule.name)
     .bind(rule.priority)
     .bind(&rule.match_pattern)
     .bind(&rule.match_type)
     .bind(rule.account_id)
+    .bind(rule.amount_min_cents)
+    .bind(rule.amount_max_cents)
     .execute(pool)
     .await?;
 
     Ok(result.last_insert_rowid())
 }
 
+pub async fn update_categorization_rule(
+    pool: &DbPool,
+    rule: &CategorizationRule,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"UPDATE categorization_rules
+           SET name = ?, priority = ?, match_pattern = ?, match_type = ?, account_id = ?,
+               amount_min_cents = ?, amount_max_cents = ?
+           WHERE id = ?"#,
+    )
+    .bind(&rule.name)
+    .bind(rule.priority)
+    .bind(&rule.match_pattern)
+    .bind(&rule.match_type)
+    .bind(rule.account_id)
+    .bind(rule.amount_min_cents)
+    .bind(rule.amount_max_cents)
+    .bind(rule.id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
 pub async fn get_categorization_rules(
     pool: &DbPool,
 ) -> Result<Vec<CategorizationRule>, sqlx::Error> {
     let rows = sqlx::query_as::<_, CategorizationRule>(
-        "SELECT * FROM categorization_rules ORDER BY priority DESC",
+        "SELECT * FROM categorization_rules ORDER BY priority DESC, id",
     )
     .fetch_all(pool)
     .await?;
@@ -208,15 +417,200 @@ pub async fn get_categorization_rules(
     Ok(rows)
 }
 
+/// Stored rules as engine rules, highest priority first, paired with their
+/// row ids. Rules whose account no longer exists or whose match type is
+/// unreadable are left out.
+pub async fn get_category_rules(pool: &DbPool) -> Result<Vec<(i64, CategoryRule)>, sqlx::Error> {
+    let codes: std::collections::HashMap<i64, String> = get_all_accounts(pool)
+        .await?
+        .into_iter()
+        .filter_map(|a| a.id.map(|id| (id.0, a.code)))
+        .collect();
+    Ok(get_categorization_rules(pool)
+        .await?
+        .into_iter()
+        .filter_map(|r| {
+            let rule = r.to_category_rule(codes.get(&r.account_id)?).ok()?;
+            Some((r.id, rule))
+        })
+        .collect())
+}
+
+/// Give the listed rules descending priorities in the order given, so the
+/// first id is tried first. Rules not listed keep their priority.
+pub async fn reorder_categorization_rules(pool: &DbPool, ids: &[i64]) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    for (i, id) in ids.iter().enumerate() {
+        let priority = ((ids.len() - i) * 10) as i32;
+        sqlx::query("UPDATE categorization_rules SET priority = ? WHERE id = ?")
+            .bind(priority)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Delete a rule. Imported rows it categorized keep any account picked by
+/// hand; open rows that relied on the rule alone go back to pending.
 pub async fn delete_categorization_rule(pool: &DbPool, id: i64) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        r#"UPDATE imported_transactions SET status = 'pending'
+           WHERE category_rule_id = ? AND status = 'categorized' AND account_id IS NULL"#,
+    )
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        "UPDATE imported_transactions SET category_rule_id = NULL WHERE category_rule_id = ?",
+    )
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
     sqlx::query("DELETE FROM categorization_rules WHERE id = ?")
         .bind(id)
-        .execute(pool)
+        .execute(&mut *tx)
         .await?;
+    tx.commit().await?;
 
     Ok(())
 }
 
+/// A posted transaction paired with the income or expense account it was filed under.
+#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
+pub struct CategorizedHistoryRow {
+    pub transaction_id: i64,
+    pub date: String,
+    pub description: String,
+    pub amount_cents: i64,
+    pub account_code: String,
+}
+
+/// Most recent categorizations, used to learn new rules from the user's own
+/// choices. Transfers between balance-sheet accounts are excluded.
+pub async fn get_categorized_history(
+    pool: &DbPool,
+    limit: i64,
+) -> Result<Vec<CategorizedHistoryRow>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, CategorizedHistoryRow>(
+        r#"SELECT t.id AS transaction_id, t.date, t.description,
+                  ABS(tl.debit_cents - tl.credit_cents) AS amount_cents,
+                  a.code AS account_code
+           FROM transactions t
+           JOIN transaction_lines tl ON tl.transaction_id = t.id
+           JOIN accounts a ON a.id = tl.account_id
+           WHERE a.account_type IN ('Income', 'Expense')
+           ORDER BY t.date DESC, t.id DESC
+           LIMIT ?"#,
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// A payee the user has booked before, for quick-entry autocompletion.
+#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
+pub struct PayeeSuggestion {
+    pub payee: String,
+    pub uses: i64,
+    pub last_used: String,
+}
+
+/// Payees whose name contains `query`, names starting with it first, then
+/// by how often and how recently they were used. Descriptions differing
+/// only in case count as one payee, under their latest spelling.
+pub async fn get_payee_suggestions(
+    pool: &DbPool,
+    query: &str,
+    limit: i64,
+) -> Result<Vec<PayeeSuggestion>, sqlx::Error> {
+    let escaped = query
+        .trim()
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    sqlx::query_as::<_, PayeeSuggestion>(
+        r#"SELECT description AS payee, COUNT(*) AS uses, MAX(date) AS last_used
+           FROM transactions
+           WHERE description LIKE '%' || ? || '%' ESCAPE '\'
+           GROUP BY lower(description)
+           ORDER BY MAX(description LIKE ? || '%' ESCAPE '\') DESC, uses DESC, last_used DESC
+           LIMIT ?"#,
+    )
+    .bind(&escaped)
+    .bind(&escaped)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// An account booked against a payee, and how often.
+#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
+pub struct PayeeAccountUsage {
+    pub account_id: i64,
+    pub account_code: String,
+    pub account_name: String,
+    pub account_type: String,
+    /// Number of the payee's transactions with a line on this account.
+    pub uses: i64,
+    pub last_used: String,
+}
+
+/// The accounts the payee's transactions were booked to, most used first.
+/// Archived accounts are left out.
+pub async fn get_payee_accounts(
+    pool: &DbPool,
+    payee: &str,
+    limit: i64,
+) -> Result<Vec<PayeeAccountUsage>, sqlx::Error> {
+    sqlx::query_as::<_, PayeeAccountUsage>(
+        r#"SELECT a.id AS account_id, a.code AS account_code, a.name AS account_name,
+                  a.account_type, COUNT(DISTINCT t.id) AS uses, MAX(t.date) AS last_used
+           FROM transactions t
+           JOIN transaction_lines tl ON tl.transaction_id = t.id
+           JOIN accounts a ON a.id = tl.account_id
+           WHERE lower(t.description) = lower(?) AND a.is_archived = 0
+           GROUP BY a.id
+           ORDER BY uses DESC, last_used DESC, a.code
+           LIMIT ?"#,
+    )
+    .bind(payee.trim())
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// A past amount paid to (or received from) a payee.
+#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
+pub struct PayeeAmount {
+    pub transaction_id: i64,
+    pub date: String,
+    pub amount_cents: i64,
+}
+
+/// The payee's most recent transaction totals, newest first.
+pub async fn get_payee_amounts(
+    pool: &DbPool,
+    payee: &str,
+    limit: i64,
+) -> Result<Vec<PayeeAmount>, sqlx::Error> {
+    sqlx::query_as::<_, PayeeAmount>(
+        r#"SELECT id AS transaction_id, date, balanced_total_cents AS amount_cents
+           FROM transactions
+           WHERE lower(description) = lower(?)
+           ORDER BY date DESC, id DESC
+           LIMIT ?"#,
+    )
+    .bind(payee.trim())
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
 #[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
 pub struct ImportedTransaction {
     pub id: i64,
@@ -261,6 +655,24 @@ pub async fn insert_imported_transaction(
     Ok(result.last_insert_rowid())
 }
 
+/// True if a transaction with this provider id was already imported, so
+/// re-dropped or re-synced files do not queue duplicates.
+pub async fn imported_transaction_exists(
+    pool: &DbPool,
+    source_type: &str,
+    source_id: &str,
+) -> Result<bool, sqlx::Error> {
+    let row: Option<(i64,)> = sqlx::query_as(
+        "SELECT id FROM imported_transactions WHERE source_type = ? AND source_id = ? LIMIT 1",
+    )
+    .bind(source_type)
+    .bind(source_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.is_some())
+}
+
 pub async fn get_pending_imported_transactions(
     pool: &DbPool,
     batch_id: &str,
@@ -275,31 +687,56 @@ pub async fn get_pending_imported_transactions(
     Ok(rows)
 }
 
+/// Mark an imported row as matched to a ledger transaction. Receipts that
+/// were linked to the imported row follow it onto the ledger transaction.
 pub async fn mark_imported_transaction_matched(
     pool: &DbPool,
     id: i64,
     transaction_id: i64,
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    settle_imported_transaction(&mut tx, id, transaction_id).await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// [`mark_imported_transaction_matched`] on an open connection, so a
+/// transaction posted from the row can be written in the same commit.
+pub async fn settle_imported_transaction(
+    conn: &mut sqlx::SqliteConnection,
+    id: i64,
+    transaction_id: i64,
 ) -> Result<(), sqlx::Error> {
     sqlx::query(
         "UPDATE imported_transactions SET matched_transaction_id = ?, status = 'matched' WHERE id = ?"
     )
     .bind(transaction_id)
     .bind(id)
-    .execute(pool)
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query(
+        "UPDATE receipts SET transaction_id = ? WHERE imported_transaction_id = ? AND transaction_id IS NULL",
+    )
+    .bind(transaction_id)
+    .bind(id)
+    .execute(&mut *conn)
     .await?;
 
     Ok(())
 }
 
-pub async fn mark_imported_transaction_categorized(
+/// Propose a ledger transaction for an open imported row without settling
+/// it; the row stays in review until the match is accepted or rejected.
+pub async fn suggest_imported_match(
     pool: &DbPool,
     id: i64,
-    rule_id: i64,
+    transaction_id: i64,
 ) -> Result<(), sqlx::Error> {
     sqlx::query(
-        "UPDATE imported_transactions SET category_rule_id = ?, status = 'categorized' WHERE id = ?"
+        "UPDATE imported_transactions SET matched_transaction_id = ? WHERE id = ? AND status IN ('pending', 'categorized')",
     )
-    .bind(rule_id)
+    .bind(transaction_id)
     .bind(id)
     .execute(pool)
     .await?;
@@ -307,85 +744,449 @@ pub async fn mark_imported_transaction_categorized(
     Ok(())
 }
 
-pub async fn get_imported_transactions_for_review(
-    pool: &DbPool,
-    batch_id: &str,
-) -> Result<Vec<ImportedTransaction>, sqlx::Error> {
-    let rows = sqlx::query_as::<_, ImportedTransaction>(
-        "SELECT * FROM imported_transactions WHERE import_batch_id = ? AND status IN ('pending', 'categorized') ORDER BY date"
+/// Accept the suggested match on an open imported row. Returns false if the
+/// row is not open or has no suggestion.
+pub async fn accept_imported_match(pool: &DbPool, id: i64) -> Result<bool, sqlx::Error> {
+    let suggested: Option<(i64,)> = sqlx::query_as(
+        r#"SELECT matched_transaction_id FROM imported_transactions
+           WHERE id = ? AND status IN ('pending', 'categorized')
+             AND matched_transaction_id IS NOT NULL"#,
     )
-    .bind(batch_id)
-    .fetch_all(pool)
+    .bind(id)
+    .fetch_optional(pool)
     .await?;
-
-    Ok(rows)
+    let Some((transaction_id,)) = suggested else {
+        return Ok(false);
+    };
+    mark_imported_transaction_matched(pool, id, transaction_id).await?;
+    Ok(true)
 }
 
-#[derive(Debug, Clone, sqlx::FromRow)]
-pub struct ReconciliationSession {
-    pub id: i64,
-    pub account_id: i64,
-    pub start_date: String,
-    pub end_date: String,
-    pub statement_balance_cents: i64,
-    pub is_completed: bool,
-    pub created_at: String,
+/// Drop the suggested match on an open imported row. Returns false if the
+/// row is not open or has no suggestion.
+pub async fn reject_imported_match(pool: &DbPool, id: i64) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"UPDATE imported_transactions SET matched_transaction_id = NULL
+           WHERE id = ? AND status IN ('pending', 'categorized')
+             AND matched_transaction_id IS NOT NULL"#,
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
 }
 
-pub async fn create_reconciliation_session(
+/// Book an open imported row to `account_id` by hand, overriding any rule.
+/// Returns false if the row is not open.
+pub async fn set_imported_transaction_account(
     pool: &DbPool,
+    id: i64,
     account_id: i64,
-    start_date: &str,
-    end_date: &str,
-    statement_balance_cents: i64,
-) -> Result<i64, sqlx::Error> {
+) -> Result<bool, sqlx::Error> {
     let result = sqlx::query(
-        r#"INSERT INTO reconciliation_sessions 
-           (account_id, start_date, end_date, statement_balance_cents)
-           VALUES (?, ?, ?, ?)"#,
+        r#"UPDATE imported_transactions SET account_id = ?, status = 'categorized'
+           WHERE id = ? AND status IN ('pending', 'categorized')"#,
     )
     .bind(account_id)
-    .bind(start_date)
-    .bind(end_date)
-    .bind(statement_balance_cents)
+    .bind(id)
     .execute(pool)
     .await?;
 
-    Ok(result.last_insert_rowid())
+    Ok(result.rows_affected() > 0)
 }
 
-pub async fn get_reconciliation_sessions(
+pub async fn mark_imported_transaction_categorized(
     pool: &DbPool,
-    account_id: i64,
-) -> Result<Vec<ReconciliationSession>, sqlx::Error> {
-    let rows = sqlx::query_as::<_, ReconciliationSession>(
-        "SELECT * FROM reconciliation_sessions WHERE account_id = ? ORDER BY created_at DESC",
+    id: i64,
+    rule_id: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE imported_transactions SET category_rule_id = ?, status = 'categorized' WHERE id = ?"
     )
-    .bind(account_id)
-    .fetch_all(pool)
+    .bind(rule_id)
+    .bind(id)
+    .execute(pool)
     .await?;
 
-    Ok(rows)
+    Ok(())
 }
 
-pub async fn complete_reconciliation_session(
+pub async fn get_imported_transactions_for_review(
     pool: &DbPool,
-    session_id: i64,
-) -> Result<(), sqlx::Error> {
-    sqlx::query("UPDATE reconciliation_sessions SET is_completed = 1 WHERE id = ?")
-        .bind(session_id)
-        .execute(pool)
-        .await?;
+    batch_id: &str,
+) -> Result<Vec<ImportedTransaction>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, ImportedTransaction>(
+        "SELECT * FROM imported_transactions WHERE import_batch_id = ? AND status IN ('pending', 'categorized') ORDER BY date"
+    )
+    .bind(batch_id)
+    .fetch_all(pool)
+    .await?;
 
-    Ok(())
+    Ok(rows)
 }
 
+/// An imported row as shown in the review screen, with the account it will
+/// be booked to and the ledger transaction suggested for it.
 #[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
-pub struct ReconciliationItem {
+pub struct ImportReviewRow {
     pub id: i64,
-    pub session_id: i64,
-    pub imported_transaction_id: Option<i64>,
-    pub transaction_id: Option<i64>,
+    pub import_batch_id: String,
+    pub date: String,
+    pub description: String,
+    pub amount_cents: i64,
+    pub memo: Option<String>,
+    pub status: String,
+    /// Chosen by hand, or else the account of the rule that categorized it.
+    pub account_id: Option<i64>,
+    pub account_code: Option<String>,
+    pub rule_name: Option<String>,
+    pub matched_transaction_id: Option<i64>,
+    pub matched_date: Option<String>,
+    pub matched_description: Option<String>,
+}
+
+const IMPORT_REVIEW_SELECT: &str = r#"SELECT i.id, i.import_batch_id, i.date, i.description, i.amount_cents, i.memo, i.status,
+              COALESCE(i.account_id, r.account_id) AS account_id,
+              a.code AS account_code,
+              r.name AS rule_name,
+              i.matched_transaction_id,
+              t.date AS matched_date,
+              t.description AS matched_description
+       FROM imported_transactions i
+       LEFT JOIN categorization_rules r ON r.id = i.category_rule_id
+       LEFT JOIN accounts a ON a.id = COALESCE(i.account_id, r.account_id)
+       LEFT JOIN transactions t ON t.id = i.matched_transaction_id"#;
+
+/// Every row of an import batch, oldest first.
+pub async fn get_import_review(
+    pool: &DbPool,
+    batch_id: &str,
+) -> Result<Vec<ImportReviewRow>, sqlx::Error> {
+    sqlx::query_as::<_, ImportReviewRow>(&format!(
+        "{IMPORT_REVIEW_SELECT} WHERE i.import_batch_id = ? ORDER BY i.date, i.id"
+    ))
+    .bind(batch_id)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn get_import_review_row(
+    pool: &DbPool,
+    id: i64,
+) -> Result<Option<ImportReviewRow>, sqlx::Error> {
+    sqlx::query_as::<_, ImportReviewRow>(&format!("{IMPORT_REVIEW_SELECT} WHERE i.id = ?"))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Rows of a batch ready to post: categorized, with an account, and with no
+/// suggested match still waiting on a decision.
+pub async fn get_postable_imported_transactions(
+    pool: &DbPool,
+    batch_id: &str,
+) -> Result<Vec<ImportReviewRow>, sqlx::Error> {
+    sqlx::query_as::<_, ImportReviewRow>(&format!(
+        r#"{IMPORT_REVIEW_SELECT}
+           WHERE i.import_batch_id = ? AND i.status = 'categorized'
+             AND i.matched_transaction_id IS NULL
+             AND COALESCE(i.account_id, r.account_id) IS NOT NULL
+           ORDER BY i.date, i.id"#
+    ))
+    .bind(batch_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Open imported rows across every batch that no rule categorized and no
+/// account was chosen for, largest amount (in or out) first.
+pub async fn get_uncategorized_imports(
+    pool: &DbPool,
+    limit: i64,
+) -> Result<Vec<ImportReviewRow>, sqlx::Error> {
+    sqlx::query_as::<_, ImportReviewRow>(&format!(
+        r#"{IMPORT_REVIEW_SELECT}
+           WHERE i.status = 'pending' AND i.account_id IS NULL
+             AND i.category_rule_id IS NULL
+           ORDER BY ABS(i.amount_cents) DESC, i.date, i.id
+           LIMIT ?"#
+    ))
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// Book open imported rows to `account_id` by hand. With `similar`, other
+/// uncategorized rows whose description matches one of theirs (ignoring
+/// case and spacing) are booked too. Returns the ids of the rows booked.
+pub async fn categorize_imported_transactions(
+    pool: &DbPool,
+    ids: &[i64],
+    account_id: i64,
+    similar: bool,
+) -> Result<Vec<i64>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let mut targets: Vec<i64> = Vec::new();
+    let mut descriptions = std::collections::HashSet::new();
+    for &id in ids {
+        let description: Option<String> = sqlx::query_scalar(
+            "SELECT description FROM imported_transactions WHERE id = ? AND status IN ('pending', 'categorized')",
+        )
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        if let Some(description) = description {
+            descriptions.insert(normalize_payee(&description));
+            targets.push(id);
+        }
+    }
+    if similar && !descriptions.is_empty() {
+        let open: Vec<(i64, String)> = sqlx::query_as(
+            r#"SELECT id, description FROM imported_transactions
+               WHERE status = 'pending' AND account_id IS NULL AND category_rule_id IS NULL"#,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        for (id, description) in open {
+            if !targets.contains(&id) && descriptions.contains(&normalize_payee(&description)) {
+                targets.push(id);
+            }
+        }
+    }
+    for &id in &targets {
+        sqlx::query(
+            "UPDATE imported_transactions SET account_id = ?, status = 'categorized' WHERE id = ?",
+        )
+        .bind(account_id)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(targets)
+}
+
+/// Imported rows dated within `[start, end]` that are still awaiting review
+/// (not yet matched to the ledger or ignored).
+pub async fn get_open_imported_transactions(
+    pool: &DbPool,
+    start: &str,
+    end: &str,
+) -> Result<Vec<ImportedTransaction>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, ImportedTransaction>(
+        "SELECT * FROM imported_transactions WHERE date BETWEEN ? AND ? AND status IN ('pending', 'categorized') ORDER BY date",
+    )
+    .bind(start)
+    .bind(end)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Ledger transactions dated within `[start, end]` that no imported row has
+/// been matched to yet. Amounts are the transaction's balanced total.
+pub async fn get_unmatched_ledger_transactions(
+    pool: &DbPool,
+    start: &str,
+    end: &str,
+) -> Result<Vec<MatchableTransaction>, sqlx::Error> {
+    let rows: Vec<(i64, String, String, i64)> = sqlx::query_as(
+        r#"SELECT t.id, t.date, t.description, t.balanced_total_cents
+           FROM transactions t
+           WHERE t.date BETWEEN ? AND ?
+             AND NOT EXISTS (
+               SELECT 1 FROM imported_transactions i WHERE i.matched_transaction_id = t.id
+             )
+           ORDER BY t.date, t.id"#,
+    )
+    .bind(start)
+    .bind(end)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|(id, date, description, amount_cents)| {
+            Some(MatchableTransaction {
+                id,
+                date: NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok()?,
+                description,
+                amount_cents,
+            })
+        })
+        .collect())
+}
+
+/// Row counts for one import batch, by review status.
+#[derive(Debug, Clone, Default, serde::Serialize, sqlx::FromRow)]
+pub struct ImportBatchSummary {
+    pub batch_id: String,
+    pub total: i64,
+    pub pending: i64,
+    pub categorized: i64,
+    pub matched: i64,
+    pub ignored: i64,
+    /// Open rows with a suggested ledger match awaiting a decision.
+    pub suggested_matches: i64,
+    pub first_date: Option<String>,
+    pub last_date: Option<String>,
+}
+
+/// Summarize an import batch. `None` if no rows carry the batch id.
+pub async fn get_import_batch_summary(
+    pool: &DbPool,
+    batch_id: &str,
+) -> Result<Option<ImportBatchSummary>, sqlx::Error> {
+    let summary = sqlx::query_as::<_, ImportBatchSummary>(
+        r#"SELECT import_batch_id AS batch_id,
+                  COUNT(*) AS total,
+                  SUM(status = 'pending') AS pending,
+                  SUM(status = 'categorized') AS categorized,
+                  SUM(status = 'matched') AS matched,
+                  SUM(status = 'ignored') AS ignored,
+                  SUM(status IN ('pending', 'categorized')
+                      AND matched_transaction_id IS NOT NULL) AS suggested_matches,
+                  MIN(date) AS first_date,
+                  MAX(date) AS last_date
+           FROM imported_transactions
+           WHERE import_batch_id = ?
+           GROUP BY import_batch_id"#,
+    )
+    .bind(batch_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(summary)
+}
+
+/// Discard an import batch's rows that are still open, e.g. when the import
+/// was cancelled part way. Rows already matched or ignored are kept.
+pub async fn delete_import_batch(pool: &DbPool, batch_id: &str) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "DELETE FROM imported_transactions WHERE import_batch_id = ? AND status IN ('pending', 'categorized')",
+    )
+    .bind(batch_id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Latest balance reported by a bank aggregator for one linked account.
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct BankBalance {
+    pub id: i64,
+    pub provider: String,
+    pub provider_account_id: String,
+    pub name: String,
+    pub currency: Option<String>,
+    pub current_cents: Option<i64>,
+    pub available_cents: Option<i64>,
+    pub as_of: String,
+}
+
+/// Insert or refresh the balance for `(provider, provider_account_id)`.
+pub async fn upsert_bank_balance(pool: &DbPool, balance: &BankBalance) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"INSERT INTO bank_balances
+           (provider, provider_account_id, name, currency, current_cents, available_cents, as_of)
+           VALUES (?, ?, ?, ?, ?, ?, ?)
+           ON CONFLICT(provider, provider_account_id) DO UPDATE SET
+             name = excluded.name,
+             currency = excluded.currency,
+             current_cents = excluded.current_cents,
+             available_cents = excluded.available_cents,
+             as_of = excluded.as_of
+        "#,
+    )
+    .bind(&balance.provider)
+    .bind(&balance.provider_account_id)
+    .bind(&balance.name)
+    .bind(&balance.currency)
+    .bind(balance.current_cents)
+    .bind(balance.available_cents)
+    .bind(&balance.as_of)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_bank_balances(pool: &DbPool) -> Result<Vec<BankBalance>, sqlx::Error> {
+    let rows =
+        sqlx::query_as::<_, BankBalance>("SELECT * FROM bank_balances ORDER BY provider, name")
+            .fetch_all(pool)
+            .await?;
+
+    Ok(rows)
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ReconciliationSession {
+    pub id: i64,
+    pub account_id: i64,
+    pub start_date: String,
+    pub end_date: String,
+    pub statement_balance_cents: i64,
+    pub is_completed: bool,
+    pub created_at: String,
+}
+
+pub async fn create_reconciliation_session(
+    pool: &DbPool,
+    account_id: i64,
+    start_date: &str,
+    end_date: &str,
+    statement_balance_cents: i64,
+) -> Result<i64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"INSERT INTO reconciliation_sessions 
+           (account_id, start_date, end_date, statement_balance_cents)
+           VALUES (?, ?, ?, ?)"#,
+    )
+    .bind(account_id)
+    .bind(start_date)
+    .bind(end_date)
+    .bind(statement_balance_cents)
+    .execute(pool)
+    .await?;
+
+    Ok(result.last_insert_rowid())
+}
+
+pub async fn get_reconciliation_sessions(
+    pool: &DbPool,
+    account_id: i64,
+) -> Result<Vec<ReconciliationSession>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, ReconciliationSession>(
+        "SELECT * FROM reconciliation_sessions WHERE account_id = ? ORDER BY created_at DESC",
+    )
+    .bind(account_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+pub async fn complete_reconciliation_session(
+    pool: &DbPool,
+    session_id: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE reconciliation_sessions SET is_completed = 1 WHERE id = ?")
+        .bind(session_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
+pub struct ReconciliationItem {
+    pub id: i64,
+    pub session_id: i64,
+    pub imported_transaction_id: Option<i64>,
+    pub transaction_id: Option<i64>,
     pub match_type: String,
     pub difference_cents: i64,
     pub is_resolved: bool,
@@ -461,6 +1262,163 @@ pub async fn get_unresolved_reconciliation_items(
     Ok(rows)
 }
 
+// ── Transaction storage ───────────────────────────────────────────────────────
+
+/// Columns read into [`TransactionRecord`].
+const TRANSACTION_COLUMNS: &str =
+    "id, date, description, memo, balanced_total_cents, created_at, is_personal, deductible_percent";
+
+/// A ledger transaction without its lines.
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct TransactionRecord {
+    pub id: i64,
+    pub date: String,
+    pub description: String,
+    pub memo: Option<String>,
+    pub balanced_total_cents: i64,
+    pub created_at: String,
+    pub is_personal: bool,
+    pub deductible_percent: u8,
+}
+
+/// Write a validated transaction and its lines within the caller's
+/// database transaction.
+pub async fn insert_transaction(
+    conn: &mut sqlx::SqliteConnection,
+    transaction: &ValidatedTransaction,
+    deductibility: Deductibility,
+) -> Result<TransactionRecord, sqlx::Error> {
+    let record = sqlx::query_as::<_, TransactionRecord>(&format!(
+        r#"INSERT INTO transactions (date, description, memo, balanced_total_cents,
+               is_personal, deductible_percent)
+           VALUES (?, ?, ?, ?, ?, ?)
+           RETURNING {TRANSACTION_COLUMNS}"#
+    ))
+    .bind(transaction.date.to_string())
+    .bind(&transaction.description)
+    .bind(&transaction.memo)
+    .bind(transaction.balanced_total.to_cents())
+    .bind(deductibility.is_personal)
+    .bind(deductibility.deductible_percent)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    for line in &transaction.lines {
+        sqlx::query(
+            "INSERT INTO transaction_lines (transaction_id, account_id, debit_cents, credit_cents, memo) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(record.id)
+        .bind(line.account_id.0)
+        .bind(line.debit.to_cents())
+        .bind(line.credit.to_cents())
+        .bind(&line.memo)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(record)
+}
+
+/// Transactions, newest first, dated within `range` if given. `limit`
+/// caps how many.
+pub async fn get_transactions(
+    pool: &DbPool,
+    range: Option<DateRange>,
+    limit: Option<i64>,
+) -> Result<Vec<TransactionRecord>, sqlx::Error> {
+    sqlx::query_as::<_, TransactionRecord>(&format!(
+        r#"SELECT {TRANSACTION_COLUMNS} FROM transactions
+           WHERE (?1 IS NULL OR date >= ?1) AND (?2 IS NULL OR date <= ?2)
+           ORDER BY date DESC, id DESC
+           LIMIT COALESCE(?3, -1)"#
+    ))
+    .bind(range.map(|r| r.start.to_string()))
+    .bind(range.map(|r| r.end.to_string()))
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn transaction_exists(pool: &DbPool, id: i64) -> Result<bool, sqlx::Error> {
+    let row: Option<(i64,)> = sqlx::query_as("SELECT id FROM transactions WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.is_some())
+}
+
+pub async fn count_transactions(pool: &DbPool) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar("SELECT COUNT(*) FROM transactions")
+        .fetch_one(pool)
+        .await
+}
+
+/// Transactions per multi-row `INSERT`, at 4 parameters each.
+const BULK_TRANSACTION_ROWS: usize = 500;
+/// Lines per multi-row `INSERT`, at 5 parameters each.
+const BULK_LINE_ROWS: usize = 1_000;
+
+/// Write many validated transactions in one database transaction, with
+/// multi-row statements instead of one per row. Returns their ids in the
+/// order given. They are business transactions, fully deductible.
+pub async fn insert_transactions_bulk(
+    pool: &DbPool,
+    transactions: &[ValidatedTransaction],
+) -> Result<Vec<i64>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let ids = insert_transactions_bulk_tx(&mut tx, transactions).await?;
+    tx.commit().await?;
+    Ok(ids)
+}
+
+/// [`insert_transactions_bulk`] within a database transaction the caller
+/// already holds, so more can be written alongside.
+pub async fn insert_transactions_bulk_tx(
+    conn: &mut sqlx::SqliteConnection,
+    transactions: &[ValidatedTransaction],
+) -> Result<Vec<i64>, sqlx::Error> {
+    let mut ids = Vec::with_capacity(transactions.len());
+    for chunk in transactions.chunks(BULK_TRANSACTION_ROWS) {
+        let mut insert = QueryBuilder::<Sqlite>::new(
+            "INSERT INTO transactions (date, description, memo, balanced_total_cents, sync_uid) ",
+        );
+        // Giving each its sync id here spares the insert trigger an UPDATE
+        // (and the change log a second entry) per transaction.
+        insert.push_values(chunk, |mut row, t| {
+            row.push_bind(t.date.to_string())
+                .push_bind(&t.description)
+                .push_bind(&t.memo)
+                .push_bind(t.balanced_total.to_cents())
+                .push("lower(hex(randomblob(16)))");
+        });
+        insert.push(" RETURNING id");
+        // RETURNING gives no order, but AUTOINCREMENT ids rise in the order
+        // the rows were inserted.
+        let mut chunk_ids: Vec<i64> = insert.build_query_scalar().fetch_all(&mut *conn).await?;
+        chunk_ids.sort_unstable();
+        ids.extend(chunk_ids);
+    }
+
+    let lines: Vec<(i64, &TransactionLine)> = ids
+        .iter()
+        .zip(transactions)
+        .flat_map(|(&id, t)| t.lines.iter().map(move |line| (id, line)))
+        .collect();
+    for chunk in lines.chunks(BULK_LINE_ROWS) {
+        let mut insert = QueryBuilder::<Sqlite>::new(
+            "INSERT INTO transaction_lines (transaction_id, account_id, debit_cents, credit_cents, memo) ",
+        );
+        insert.push_values(chunk, |mut row, (id, line)| {
+            row.push_bind(*id)
+                .push_bind(line.account_id.0)
+                .push_bind(line.debit.to_cents())
+                .push_bind(line.credit.to_cents())
+                .push_bind(&line.memo);
+        });
+        insert.build().execute(&mut *conn).await?;
+    }
+    Ok(ids)
+}
+
 // ── Receipt storage ───────────────────────────────────────────────────────────
 
 #[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
@@ -481,8 +1439,56 @@ pub struct ReceiptRecord {
     pub attachment_path: String,
     pub created_at: String,
     pub reviewed_at: Option<String>,
+    pub imported_transaction_id: Option<i64>,
+    /// Photo quality, 0.0–1.0; `None` for PDFs and unscored receipts.
+    pub quality_score: Option<f64>,
+    /// Comma-separated quality issue codes (e.g. `blurry,too_dark`).
+    pub quality_issues: Option<String>,
+    /// Invoice, receipt or order number printed on the receipt.
+    pub reference: Option<String>,
+    /// Personal purchase; carried onto the transaction posted from it.
+    pub is_personal: bool,
+    /// Business share, 0–100, carried onto the transaction like `is_personal`.
+    pub deductible_percent: i64,
+    /// For a `duplicate`, the receipt it looks like a second copy of.
+    pub duplicate_of: Option<i64>,
+    /// Hash of the photo this receipt was cut out of, when one photo held
+    /// several; shared by every receipt from it.
+    pub source_hash: Option<String>,
+    /// Where that photo is stored, relative to the attachments directory.
+    pub source_path: Option<String>,
+}
+
+/// What [`insert_receipt`] did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum ReceiptInsert {
+    /// A new receipt, pending review.
+    Inserted { id: i64 },
+    /// A new receipt with the same vendor, date and total as one already
+    /// on file, stored with status `duplicate` for the user to confirm.
+    PossibleDuplicate { id: i64, duplicate_of: i64 },
+    /// The same file was stored before as `id`; nothing new was stored.
+    AlreadyStored { id: i64 },
+}
+
+impl ReceiptInsert {
+    pub fn id(self) -> i64 {
+        match self {
+            ReceiptInsert::Inserted { id }
+            | ReceiptInsert::PossibleDuplicate { id, .. }
+            | ReceiptInsert::AlreadyStored { id } => id,
+        }
+    }
+
+    /// Whether a receipt was stored, rather than found.
+    pub fn is_new(self) -> bool {
+        !matches!(self, ReceiptInsert::AlreadyStored { .. })
+    }
 }
 
+/// Store a receipt. A file stored before isn't stored again, and a receipt
+/// that looks like a second copy of one on file is marked `duplicate`.
 #[allow(clippy::too_many_arguments)]
 pub async fn insert_receipt(
     pool: &DbPool,
@@ -496,14 +1502,14 @@ pub async fn insert_receipt(
     subtotal_cents: Option<i64>,
     tax_cents: Option<i64>,
     payment_method: Option<&str>,
+    reference: Option<&str>,
     confidence: f64,
-) -> Result<i64, sqlx::Error> {
-    // Silently ignore exact duplicates (same file imported twice).
+) -> Result<ReceiptInsert, sqlx::Error> {
     let result = sqlx::query(
         r#"INSERT OR IGNORE INTO receipts
            (file_hash, file_ext, attachment_path, ocr_text, vendor, receipt_date,
-            total_cents, subtotal_cents, tax_cents, payment_method, confidence)
-           VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"#,
+            total_cents, subtotal_cents, tax_cents, payment_method, reference, confidence)
+           VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"#,
     )
     .bind(file_hash)
     .bind(file_ext)
@@ -515,1137 +1521,3955 @@ pub async fn insert_receipt(
     .bind(subtotal_cents)
     .bind(tax_cents)
     .bind(payment_method)
+    .bind(reference)
     .bind(confidence)
     .execute(pool)
     .await?;
 
     if result.rows_affected() == 0 {
-        // Duplicate — return the existing id.
         let row = sqlx::query_as::<_, (i64,)>("SELECT id FROM receipts WHERE file_hash = ?")
             .bind(file_hash)
             .fetch_one(pool)
             .await?;
-        return Ok(row.0);
+        return Ok(ReceiptInsert::AlreadyStored { id: row.0 });
     }
 
-    Ok(result.last_insert_rowid())
+    let id = result.last_insert_rowid();
+    let Some(&original) = find_possible_duplicate_receipts(pool, id).await?.first() else {
+        return Ok(ReceiptInsert::Inserted { id });
+    };
+    sqlx::query("UPDATE receipts SET status = 'duplicate', duplicate_of = ? WHERE id = ?")
+        .bind(original)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(ReceiptInsert::PossibleDuplicate {
+        id,
+        duplicate_of: original,
+    })
 }
 
-pub async fn get_receipt_by_id(
+/// Record the photo a receipt was cut out of, along with others. Receipts
+/// from one photo are separate purchases, never copies of each other, so a
+/// duplicate mark against one of them is reconsidered. Returns the receipt
+/// this one is marked a duplicate of afterwards, if any.
+pub async fn set_receipt_source(
     pool: &DbPool,
     id: i64,
-) -> Result<Option<ReceiptRecord>, sqlx::Error> {
-    let row = sqlx::query_as::<_, ReceiptRecord>("SELECT * FROM receipts WHERE id = ?")
+    source_hash: &str,
+    source_path: &str,
+) -> Result<Option<i64>, sqlx::Error> {
+    sqlx::query("UPDATE receipts SET source_hash = ?, source_path = ? WHERE id = ?")
+        .bind(source_hash)
+        .bind(source_path)
         .bind(id)
-        .fetch_optional(pool)
+        .execute(pool)
         .await?;
-    Ok(row)
+    let marked: Option<(Option<i64>,)> =
+        sqlx::query_as("SELECT duplicate_of FROM receipts WHERE id = ? AND status = 'duplicate'")
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+    let Some((marked,)) = marked else {
+        return Ok(None);
+    };
+    let original = find_possible_duplicate_receipts(pool, id)
+        .await?
+        .first()
+        .copied();
+    if original != marked {
+        sqlx::query(
+            r#"UPDATE receipts
+               SET status = CASE WHEN ?1 IS NULL THEN 'pending_review' ELSE 'duplicate' END,
+                   duplicate_of = ?1
+               WHERE id = ?2"#,
+        )
+        .bind(original)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    }
+    Ok(original)
 }
 
-pub async fn get_receipts_pending_review(pool: &DbPool) -> Result<Vec<ReceiptRecord>, sqlx::Error> {
-    let rows = sqlx::query_as::<_, ReceiptRecord>(
-        "SELECT * FROM receipts WHERE status = 'pending_review' ORDER BY created_at DESC",
-    )
-    .fetch_all(pool)
-    .await?;
-    Ok(rows)
+/// Every receipt cut out of the photo with this hash, in the order they
+/// were stored.
+pub async fn get_receipts_from_source(
+    pool: &DbPool,
+    source_hash: &str,
+) -> Result<Vec<ReceiptRecord>, sqlx::Error> {
+    sqlx::query_as::<_, ReceiptRecord>("SELECT * FROM receipts WHERE source_hash = ? ORDER BY id")
+        .bind(source_hash)
+        .fetch_all(pool)
+        .await
 }
 
-pub async fn update_receipt_status(
+/// Record the photo quality assessed during preprocessing.
+pub async fn set_receipt_quality(
     pool: &DbPool,
     id: i64,
-    status: &str,
+    score: f64,
+    issues: &[&str],
 ) -> Result<(), sqlx::Error> {
-    sqlx::query("UPDATE receipts SET status = ?, reviewed_at = datetime('now') WHERE id = ?")
-        .bind(status)
+    let issues = (!issues.is_empty()).then(|| issues.join(","));
+    sqlx::query("UPDATE receipts SET quality_score = ?, quality_issues = ? WHERE id = ?")
+        .bind(score)
+        .bind(issues)
         .bind(id)
         .execute(pool)
         .await?;
     Ok(())
 }
 
-pub async fn link_receipt_to_transaction(
+/// An item printed on a receipt, as extracted.
+#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
+pub struct ReceiptLineItemRecord {
+    pub id: i64,
+    pub receipt_id: i64,
+    pub description: String,
+    pub amount_cents: Option<i64>,
+    pub quantity: Option<f64>,
+}
+
+pub async fn insert_receipt_line_item(
     pool: &DbPool,
     receipt_id: i64,
-    transaction_id: i64,
-) -> Result<(), sqlx::Error> {
-    sqlx::query(
-        "UPDATE receipts SET transaction_id = ?, status = 'approved', reviewed_at = datetime('now') WHERE id = ?",
+    description: &str,
+    amount_cents: Option<i64>,
+    quantity: Option<f64>,
+) -> Result<i64, sqlx::Error> {
+    let row: (i64,) = sqlx::query_as(
+        "INSERT INTO receipt_line_items (receipt_id, description, amount_cents, quantity) VALUES (?, ?, ?, ?) RETURNING id",
     )
-    .bind(transaction_id)
     .bind(receipt_id)
-    .execute(pool)
+    .bind(description)
+    .bind(amount_cents)
+    .bind(quantity)
+    .fetch_one(pool)
     .await?;
-    Ok(())
+    Ok(row.0)
 }
 
-pub async fn check_receipt_duplicate(
+/// A receipt's line items, in the order they were printed.
+pub async fn get_receipt_line_items(
     pool: &DbPool,
-    file_hash: &str,
-) -> Result<Option<i64>, sqlx::Error> {
-    let row = sqlx::query_as::<_, (i64,)>("SELECT id FROM receipts WHERE file_hash = ?")
-        .bind(file_hash)
-        .fetch_optional(pool)
-        .await?;
-    Ok(row.map(|r| r.0))
+    receipt_id: i64,
+) -> Result<Vec<ReceiptLineItemRecord>, sqlx::Error> {
+    sqlx::query_as::<_, ReceiptLineItemRecord>(
+        "SELECT * FROM receipt_line_items WHERE receipt_id = ? ORDER BY id",
+    )
+    .bind(receipt_id)
+    .fetch_all(pool)
+    .await
 }
 
-// ── Tax engine storage ───────────────────────────────────────────────────────
-
-/// Build a LedgerSnapshot for a given fiscal year by aggregating transaction_lines
-/// grouped by the account's schedule_c_line tag.
-pub async fn build_ledger_snapshot(
+pub async fn get_receipt_by_id(
     pool: &DbPool,
-    year: FiscalYear,
-    prior_year_tax: Option<Money>,
-) -> Result<LedgerSnapshot, sqlx::Error> {
-    let start = year.start_date().to_string();
-    let end = year.end_date().to_string();
+    id: i64,
+) -> Result<Option<ReceiptRecord>, sqlx::Error> {
+    let row = sqlx::query_as::<_, ReceiptRecord>("SELECT * FROM receipts WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row)
+}
 
-    // Income: credit_cents - debit_cents (net credit = revenue)
-    // Expenses: debit_cents - credit_cents (net debit = cost)
-    let rows = sqlx::query_as::<_, (String, String, i64, i64)>(
-        r#"
-        SELECT a.schedule_c_line, a.account_type,
-            COALESCE(SUM(tl.debit_cents), 0) as total_debit,
-            COALESCE(SUM(tl.credit_cents), 0) as total_credit
-        FROM accounts a
-        JOIN transaction_lines tl ON a.id = tl.account_id
-        JOIN transactions t ON tl.transaction_id = t.id
-        WHERE a.schedule_c_line IS NOT NULL
-            AND a.schedule_c_line != ''
-            AND t.date >= ? AND t.date <= ?
-        GROUP BY a.schedule_c_line, a.account_type
-        "#,
+pub async fn get_receipts_pending_review(pool: &DbPool) -> Result<Vec<ReceiptRecord>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, ReceiptRecord>(
+        "SELECT * FROM receipts WHERE status = 'pending_review' ORDER BY created_at DESC",
     )
-    .bind(&start)
-    .bind(&end)
     .fetch_all(pool)
     .await?;
+    Ok(rows)
+}
 
-    let mut line_totals = BTreeMap::new();
-    for (tag, account_type, total_debit, total_credit) in rows {
-        if let Some(line) = ScheduleCLine::from_tag(&tag) {
-            let amount = match account_type.as_str() {
-                "Income" => Money::from_cents(total_credit - total_debit),
-                "Expense" => Money::from_cents(total_debit - total_credit),
-                _ => Money::zero(),
-            };
-            if !amount.is_zero() {
-                let entry = line_totals.entry(line).or_insert(Money::zero());
-                *entry = *entry + amount;
-            }
+/// Move a receipt to `status` if the [state machine](crate::receipt_status)
+/// allows it. A receipt put back in review is no longer a duplicate.
+pub async fn update_receipt_status(
+    pool: &DbPool,
+    id: i64,
+    status: ReceiptStatus,
+) -> Result<(), ReceiptStatusError> {
+    loop {
+        let state = receipt_state(pool, id).await?;
+        state.check(status)?;
+        // Only while the receipt is as checked; if it changed in between,
+        // check again.
+        let done = sqlx::query(
+            r#"UPDATE receipts
+               SET status = ?1, reviewed_at = datetime('now'),
+                   duplicate_of = CASE WHEN ?1 = 'pending_review' THEN NULL ELSE duplicate_of END
+               WHERE id = ?2 AND status = ?3
+                 AND (?1 != 'rejected' OR (transaction_id IS NULL AND imported_transaction_id IS NULL))"#,
+        )
+        .bind(status.as_str())
+        .bind(id)
+        .bind(state.status.as_str())
+        .execute(pool)
+        .await?
+        .rows_affected();
+        if done > 0 {
+            return Ok(());
         }
     }
-
-    Ok(LedgerSnapshot {
-        year,
-        line_totals,
-        prior_year_tax,
-    })
 }
 
-#[derive(Debug, Clone, sqlx::FromRow)]
-pub struct TaxPeriodRecord {
-    pub id: i64,
-    pub year: i64,
-    pub quarter: i64,
-    pub estimated_tax_cents: i64,
-    pub se_tax_cents: i64,
-    pub income_tax_cents: i64,
-    pub net_profit_cents: i64,
-    pub payment_recorded_cents: i64,
-    pub payment_date: Option<String>,
-    pub due_date: String,
-    pub rules_year: i64,
-    pub created_at: String,
-    pub updated_at: String,
+/// Receipts marked `duplicate` when they were stored, newest first.
+pub async fn get_duplicate_receipts(pool: &DbPool) -> Result<Vec<ReceiptRecord>, sqlx::Error> {
+    sqlx::query_as::<_, ReceiptRecord>(
+        "SELECT * FROM receipts WHERE status = 'duplicate' ORDER BY created_at DESC, id DESC",
+    )
+    .fetch_all(pool)
+    .await
 }
 
-#[allow(clippy::too_many_arguments)]
-pub async fn upsert_tax_period(
-    pool: &DbPool,
-    year: u16,
-    quarter: u8,
-    estimated_tax_cents: i64,
-    se_tax_cents: i64,
-    income_tax_cents: i64,
-    net_profit_cents: i64,
-    due_date: &str,
-    rules_year: u16,
-) -> Result<i64, sqlx::Error> {
+/// The user says a receipt marked `duplicate` is a purchase of its own:
+/// put it back in the review queue. Returns false if it wasn't marked.
+pub async fn clear_receipt_duplicate(pool: &DbPool, id: i64) -> Result<bool, sqlx::Error> {
     let result = sqlx::query(
-        r#"INSERT INTO tax_periods
-           (year, quarter, estimated_tax_cents, se_tax_cents, income_tax_cents,
-            net_profit_cents, due_date, rules_year)
-           VALUES (?, ?, ?, ?, ?, ?, ?, ?)
-           ON CONFLICT(year, quarter) DO UPDATE SET
-             estimated_tax_cents = excluded.estimated_tax_cents,
-             se_tax_cents = excluded.se_tax_cents,
-             income_tax_cents = excluded.income_tax_cents,
-             net_profit_cents = excluded.net_profit_cents,
-             due_date = excluded.due_date,
-             rules_year = excluded.rules_year,
-             updated_at = datetime('now')
-        "#,
+        "UPDATE receipts SET status = 'pending_review', duplicate_of = NULL
+         WHERE id = ? AND status = 'duplicate'",
     )
-    .bind(year as i64)
-    .bind(quarter as i64)
-    .bind(estimated_tax_cents)
-    .bind(se_tax_cents)
-    .bind(income_tax_cents)
-    .bind(net_profit_cents)
-    .bind(due_date)
-    .bind(rules_year as i64)
+    .bind(id)
     .execute(pool)
     .await?;
+    Ok(result.rows_affected() > 0)
+}
 
-    Ok(result.last_insert_rowid())
+/// Move each receipt in `ids` that is still awaiting review to `status`, all
+/// in one transaction. Returns the ids that changed; receipts that are
+/// missing or already reviewed are left as they are.
+pub async fn set_pending_receipts_status(
+    pool: &DbPool,
+    ids: &[i64],
+    status: ReceiptStatus,
+) -> Result<Vec<i64>, ReceiptStatusError> {
+    if !ReceiptStatus::PendingReview.can_become(status) {
+        return Err(ReceiptStatusError::Invalid {
+            from: ReceiptStatus::PendingReview,
+            to: status,
+        });
+    }
+    let mut tx = pool.begin().await?;
+    let mut updated = Vec::new();
+    for &id in ids {
+        let done = sqlx::query(
+            "UPDATE receipts SET status = ?, reviewed_at = datetime('now') WHERE id = ? AND status = 'pending_review'",
+        )
+        .bind(status.as_str())
+        .bind(id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if done > 0 {
+            updated.push(id);
+        }
+    }
+    tx.commit().await?;
+    Ok(updated)
 }
 
-pub async fn record_tax_payment(
+/// Link a receipt to a transaction and approve it. Rejected receipts and
+/// duplicates can't be linked.
+pub async fn link_receipt_to_transaction(
     pool: &DbPool,
-    year: u16,
-    quarter: u8,
-    payment_cents: i64,
-    payment_date: &str,
+    receipt_id: i64,
+    transaction_id: i64,
+) -> Result<(), ReceiptStatusError> {
+    loop {
+        receipt_state(pool, receipt_id).await?.check_link()?;
+        let done = sqlx::query(
+            r#"UPDATE receipts SET transaction_id = ?, status = 'approved', reviewed_at = datetime('now')
+               WHERE id = ? AND status IN ('pending_review', 'approved')"#,
+        )
+        .bind(transaction_id)
+        .bind(receipt_id)
+        .execute(pool)
+        .await?
+        .rows_affected();
+        if done > 0 {
+            return Ok(());
+        }
+    }
+}
+
+/// Link a receipt to the transaction made from it and approve it, within
+/// the caller's database transaction. Keeps the time it was first reviewed.
+pub async fn mark_receipt_posted(
+    conn: &mut sqlx::SqliteConnection,
+    receipt_id: i64,
+    transaction_id: i64,
 ) -> Result<(), sqlx::Error> {
     sqlx::query(
-        r#"UPDATE tax_periods
-           SET payment_recorded_cents = ?,
-               payment_date = ?,
-               updated_at = datetime('now')
-           WHERE year = ? AND quarter = ?"#,
+        "UPDATE receipts SET transaction_id = ?, status = 'approved', reviewed_at = COALESCE(reviewed_at, datetime('now')) WHERE id = ?",
     )
-    .bind(payment_cents)
-    .bind(payment_date)
-    .bind(year as i64)
-    .bind(quarter as i64)
-    .execute(pool)
+    .bind(transaction_id)
+    .bind(receipt_id)
+    .execute(conn)
     .await?;
     Ok(())
 }
 
-pub async fn get_tax_periods(
-    pool: &DbPool,
-    year: u16,
-) -> Result<Vec<TaxPeriodRecord>, sqlx::Error> {
-    let rows = sqlx::query_as::<_, TaxPeriodRecord>(
-        "SELECT * FROM tax_periods WHERE year = ? ORDER BY quarter",
-    )
-    .bind(year as i64)
-    .fetch_all(pool)
-    .await?;
-    Ok(rows)
-}
-
-pub async fn get_prior_year_total_tax(
+/// The receipt stored for a file, or the first one cut out of it when it
+/// was a photo of several.
+pub async fn check_receipt_duplicate(
     pool: &DbPool,
-    year: u16,
+    file_hash: &str,
 ) -> Result<Option<i64>, sqlx::Error> {
     let row = sqlx::query_as::<_, (i64,)>(
-        "SELECT COALESCE(SUM(estimated_tax_cents), 0) FROM tax_periods WHERE year = ?",
+        "SELECT id FROM receipts WHERE file_hash = ?1 OR source_hash = ?1 ORDER BY id LIMIT 1",
     )
-    .bind((year - 1) as i64)
+    .bind(file_hash)
     .fetch_optional(pool)
     .await?;
-
-    match row {
-        Some((total,)) if total > 0 => Ok(Some(total)),
-        _ => Ok(None),
-    }
+    Ok(row.map(|r| r.0))
 }
 
-// ── Contact storage ──────────────────────────────────────────────────────────
-
+/// One reviewed field of a receipt, with the OCR text it was extracted from.
 #[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
-pub struct ContactRecord {
+pub struct ReceiptCorrectionRecord {
     pub id: i64,
-    pub name: String,
-    pub email: Option<String>,
-    pub phone: Option<String>,
-    pub address: Option<String>,
-    pub contact_type: String,
-    pub is_contractor: bool,
-    pub tax_id: Option<String>,
-    pub notes: Option<String>,
-    pub created_at: String,
+    pub receipt_id: i64,
+    /// `vendor`, `date` (YYYY-MM-DD) or `total` (cents).
+    pub field: String,
+    /// What extraction produced, before any correction.
+    pub extracted_value: Option<String>,
+    pub corrected_value: Option<String>,
+    /// The receipt's overall extraction confidence at the time.
+    pub confidence: f64,
+    pub ocr_text: Option<String>,
+    pub updated_at: String,
 }
 
-#[allow(clippy::too_many_arguments)]
-pub async fn insert_contact(
+#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
+pub struct FieldAccuracyRecord {
+    pub field: String,
+    pub reviewed: i64,
+    /// Reviews that left the extracted value unchanged.
+    pub correct: i64,
+}
+
+/// Vendor, date, total and confidence as extraction left them.
+/// Save the user's values for a receipt's vendor, date and total, recording
+/// each reviewed field against what extraction originally produced. `None`
+/// leaves a field unreviewed. Returns `false` if the receipt doesn't exist.
+///
+/// Correcting the same field again keeps the original extracted value, so
+/// accuracy is always measured against the extractor's output.
+pub async fn correct_receipt_fields(
     pool: &DbPool,
-    name: &str,
-    email: Option<&str>,
-    phone: Option<&str>,
-    address: Option<&str>,
-    contact_type: &str,
-    is_contractor: bool,
-    tax_id: Option<&str>,
-    notes: Option<&str>,
-) -> Result<i64, sqlx::Error> {
-    let result = sqlx::query(
-        r#"INSERT INTO contacts (name, email, phone, address, contact_type, is_contractor, tax_id, notes)
-           VALUES (?, ?, ?, ?, ?, ?, ?, ?)"#,
-    )
-    .bind(name)
-    .bind(email)
-    .bind(phone)
-    .bind(address)
-    .bind(contact_type)
-    .bind(is_contractor)
-    .bind(tax_id)
-    .bind(notes)
-    .execute(pool)
-    .await?;
+    receipt_id: i64,
+    vendor: Option<&str>,
+    receipt_date: Option<&str>,
+    total_cents: Option<i64>,
+) -> Result<bool, sqlx::Error> {
+    let update = ReceiptUpdate {
+        vendor: vendor.map(str::to_string),
+        receipt_date: receipt_date.map(str::to_string),
+        total_cents,
+        ..Default::default()
+    };
+    update_receipt(pool, receipt_id, &update).await
+}
 
-    Ok(result.last_insert_rowid())
+/// A line item as entered by the user.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ReceiptLineItemInput {
+    pub description: String,
+    pub amount_cents: Option<i64>,
+    pub quantity: Option<f64>,
 }
 
-#[allow(clippy::too_many_arguments)]
-pub async fn update_contact(
-    pool: &DbPool,
-    id: i64,
-    name: &str,
-    email: Option<&str>,
-    phone: Option<&str>,
-    address: Option<&str>,
-    contact_type: &str,
-    is_contractor: bool,
-    tax_id: Option<&str>,
-    notes: Option<&str>,
-) -> Result<(), sqlx::Error> {
+/// Fields to change on a receipt during review. `None` leaves a field as it
+/// is; an empty payment method or reference clears it.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct ReceiptUpdate {
+    pub vendor: Option<String>,
+    pub receipt_date: Option<String>,
+    pub total_cents: Option<i64>,
+    pub subtotal_cents: Option<i64>,
+    pub tax_cents: Option<i64>,
+    pub payment_method: Option<String>,
+    pub reference: Option<String>,
+    /// Replaces all of the receipt's line items when set.
+    pub line_items: Option<Vec<ReceiptLineItemInput>>,
+    pub is_personal: Option<bool>,
+    pub deductible_percent: Option<u8>,
+}
+
+/// Apply the user's edits to a receipt, recording each field passed as
+/// reviewed like [`correct_receipt_fields`] does. Returns `false` if the
+/// receipt doesn't exist.
+pub async fn update_receipt(
+    pool: &DbPool,
+    receipt_id: i64,
+    update: &ReceiptUpdate,
+) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let current = sqlx::query_as::<_, ReceiptRecord>("SELECT * FROM receipts WHERE id = ?")
+        .bind(receipt_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+    let Some(current) = current else {
+        return Ok(false);
+    };
+
+    // Field names match those checked by `apply_reprocessed_receipt`, so
+    // re-processing leaves the user's values alone.
+    let text = |v: Option<i64>| v.map(|c| c.to_string());
+    let reviewed = [
+        ("vendor", update.vendor.clone(), current.vendor),
+        ("date", update.receipt_date.clone(), current.receipt_date),
+        ("total", text(update.total_cents), text(current.total_cents)),
+        (
+            "subtotal",
+            text(update.subtotal_cents),
+            text(current.subtotal_cents),
+        ),
+        ("tax", text(update.tax_cents), text(current.tax_cents)),
+        (
+            "payment_method",
+            update.payment_method.clone(),
+            current.payment_method,
+        ),
+        ("reference", update.reference.clone(), current.reference),
+    ];
+    for (field, corrected, extracted) in reviewed {
+        let Some(corrected) = corrected else { continue };
+        sqlx::query(
+            r#"INSERT INTO receipt_corrections
+                 (receipt_id, field, extracted_value, corrected_value, confidence)
+               VALUES (?, ?, ?, ?, ?)
+               ON CONFLICT(receipt_id, field) DO UPDATE SET
+                 corrected_value = excluded.corrected_value,
+                 updated_at = datetime('now')"#,
+        )
+        .bind(receipt_id)
+        .bind(field)
+        .bind(extracted)
+        .bind(corrected)
+        .bind(current.confidence)
+        .execute(&mut *tx)
+        .await?;
+    }
+
     sqlx::query(
-        r#"UPDATE contacts SET name=?, email=?, phone=?, address=?, contact_type=?,
-           is_contractor=?, tax_id=?, notes=? WHERE id=?"#,
+        r#"UPDATE receipts SET
+             vendor = COALESCE(?, vendor),
+             receipt_date = COALESCE(?, receipt_date),
+             total_cents = COALESCE(?, total_cents),
+             subtotal_cents = COALESCE(?, subtotal_cents),
+             tax_cents = COALESCE(?, tax_cents),
+             payment_method = NULLIF(COALESCE(?, payment_method), ''),
+             reference = NULLIF(COALESCE(?, reference), ''),
+             is_personal = COALESCE(?, is_personal),
+             deductible_percent = COALESCE(?, deductible_percent)
+           WHERE id = ?"#,
     )
-    .bind(name)
-    .bind(email)
-    .bind(phone)
-    .bind(address)
-    .bind(contact_type)
-    .bind(is_contractor)
-    .bind(tax_id)
-    .bind(notes)
-    .bind(id)
-    .execute(pool)
+    .bind(&update.vendor)
+    .bind(&update.receipt_date)
+    .bind(update.total_cents)
+    .bind(update.subtotal_cents)
+    .bind(update.tax_cents)
+    .bind(&update.payment_method)
+    .bind(&update.reference)
+    .bind(update.is_personal)
+    .bind(update.deductible_percent)
+    .bind(receipt_id)
+    .execute(&mut *tx)
     .await?;
-    Ok(())
-}
 
-pub async fn get_all_contacts(pool: &DbPool) -> Result<Vec<ContactRecord>, sqlx::Error> {
-    sqlx::query_as::<_, ContactRecord>("SELECT * FROM contacts ORDER BY name LIMIT 5000")
-        .fetch_all(pool)
-        .await
+    if let Some(items) = &update.line_items {
+        sqlx::query("DELETE FROM receipt_line_items WHERE receipt_id = ?")
+            .bind(receipt_id)
+            .execute(&mut *tx)
+            .await?;
+        for item in items {
+            sqlx::query(
+                "INSERT INTO receipt_line_items (receipt_id, description, amount_cents, quantity) VALUES (?, ?, ?, ?)",
+            )
+            .bind(receipt_id)
+            .bind(&item.description)
+            .bind(item.amount_cents)
+            .bind(item.quantity)
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+    tx.commit().await?;
+    Ok(true)
 }
 
-pub async fn get_contact_by_id(
+/// Other live receipts for the same vendor, date and total as this one:
+/// most likely the same purchase photographed or emailed twice. Receipts
+/// cut out of the same photo are never counted.
+pub async fn find_possible_duplicate_receipts(
     pool: &DbPool,
-    id: i64,
-) -> Result<Option<ContactRecord>, sqlx::Error> {
-    sqlx::query_as::<_, ContactRecord>("SELECT * FROM contacts WHERE id = ?")
-        .bind(id)
-        .fetch_optional(pool)
-        .await
+    receipt_id: i64,
+) -> Result<Vec<i64>, sqlx::Error> {
+    let rows: Vec<(i64,)> = sqlx::query_as(
+        r#"SELECT o.id FROM receipts r
+           JOIN receipts o ON o.id != r.id
+             AND o.receipt_date = r.receipt_date
+             AND o.total_cents = r.total_cents
+             AND LOWER(TRIM(o.vendor)) = LOWER(TRIM(r.vendor))
+             AND (r.source_hash IS NULL OR o.source_hash IS NOT r.source_hash)
+           WHERE r.id = ? AND o.status NOT IN ('rejected', 'duplicate')
+           ORDER BY o.id"#,
+    )
+    .bind(receipt_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(|r| r.0).collect())
 }
 
-pub async fn get_contractors(pool: &DbPool) -> Result<Vec<ContactRecord>, sqlx::Error> {
-    sqlx::query_as::<_, ContactRecord>(
-        "SELECT * FROM contacts WHERE is_contractor = 1 ORDER BY name",
+/// Every reviewed field, oldest first.
+pub async fn get_receipt_corrections(
+    pool: &DbPool,
+) -> Result<Vec<ReceiptCorrectionRecord>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, ReceiptCorrectionRecord>(
+        r#"SELECT c.id, c.receipt_id, c.field, c.extracted_value, c.corrected_value,
+                  c.confidence, r.ocr_text, c.updated_at
+           FROM receipt_corrections c
+           JOIN receipts r ON r.id = c.receipt_id
+           ORDER BY c.id"#,
     )
     .fetch_all(pool)
-    .await
+    .await?;
+    Ok(rows)
 }
 
-// ── Invoice storage ──────────────────────────────────────────────────────────
-
-#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
-pub struct InvoiceRecord {
-    pub id: i64,
-    pub invoice_number: String,
-    pub contact_id: i64,
-    pub status_type: String,
-    pub status_data: Option<String>,
-    pub issue_date: String,
-    pub due_date: String,
-    pub discount_type: Option<String>,
-    pub discount_value: Option<i64>,
-    pub notes: Option<String>,
-    pub terms: Option<String>,
-    pub created_at: String,
-    pub updated_at: String,
+/// How often extraction got each field right, across all reviews.
+pub async fn get_extraction_accuracy(
+    pool: &DbPool,
+) -> Result<Vec<FieldAccuracyRecord>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, FieldAccuracyRecord>(
+        r#"SELECT field,
+                  COUNT(*) AS reviewed,
+                  SUM(extracted_value IS corrected_value) AS correct
+           FROM receipt_corrections
+           GROUP BY field
+           ORDER BY field"#,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
 }
 
-#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
-pub struct InvoiceLineRecord {
-    pub id: i64,
-    pub invoice_id: i64,
-    pub description: String,
-    pub quantity_hundredths: i64,
-    pub unit_rate_cents: i64,
-    pub taxable: bool,
-    pub sort_order: i64,
+/// Extraction confidence bands, as shown in the review queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfidenceBand {
+    /// Below 0.7: needs review.
+    Low,
+    Medium,
+    /// 0.9 and up.
+    High,
 }
 
-#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
-pub struct InvoiceTaxLineRecord {
-    pub id: i64,
-    pub invoice_id: i64,
-    pub label: String,
-    pub rate_bps: i64,
+impl ConfidenceBand {
+    /// `[min, max)` confidence of the band.
+    fn bounds(self) -> (f64, f64) {
+        match self {
+            ConfidenceBand::Low => (f64::MIN, 0.7),
+            ConfidenceBand::Medium => (0.7, 0.9),
+            ConfidenceBand::High => (0.9, f64::MAX),
+        }
+    }
 }
 
-#[allow(clippy::too_many_arguments)]
-pub async fn insert_invoice(
-    pool: &DbPool,
-    invoice_number: &str,
-    contact_id: i64,
-    status_type: &str,
-    status_data: Option<&str>,
-    issue_date: &str,
-    due_date: &str,
-    discount_type: Option<&str>,
-    discount_value: Option<i64>,
-    notes: Option<&str>,
-    terms: Option<&str>,
-) -> Result<i64, sqlx::Error> {
-    let result = sqlx::query(
-        r#"INSERT INTO invoices (invoice_number, contact_id, status_type, status_data,
-           issue_date, due_date, discount_type, discount_value, notes, terms)
-           VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"#,
-    )
-    .bind(invoice_number)
-    .bind(contact_id)
-    .bind(status_type)
-    .bind(status_data)
-    .bind(issue_date)
-    .bind(due_date)
-    .bind(discount_type)
-    .bind(discount_value)
-    .bind(notes)
-    .bind(terms)
-    .execute(pool)
-    .await?;
+/// Receipt browser filter. Unset fields don't filter.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct ReceiptQuery {
+    /// `pending_review`, `approved`, `rejected` or `duplicate`.
+    pub status: Option<String>,
+    /// Case-insensitive substring of the vendor name.
+    pub vendor: Option<String>,
+    /// Receipt date range (YYYY-MM-DD), inclusive.
+    pub date_from: Option<String>,
+    pub date_to: Option<String>,
+    /// Total range in cents, inclusive.
+    pub min_total_cents: Option<i64>,
+    pub max_total_cents: Option<i64>,
+    /// Linked to a ledger transaction or an imported bank row, or not.
+    pub linked: Option<bool>,
+    pub confidence: Option<ConfidenceBand>,
+    /// Page size; defaults to 50, at most 500.
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
 
-    Ok(result.last_insert_rowid())
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ReceiptPage {
+    pub receipts: Vec<ReceiptRecord>,
+    /// Receipts matching the filter across all pages.
+    pub total: i64,
 }
 
-pub async fn update_invoice_status(
-    pool: &DbPool,
-    id: i64,
-    status_type: &str,
-    status_data: Option<&str>,
-) -> Result<(), sqlx::Error> {
-    sqlx::query(
-        "UPDATE invoices SET status_type=?, status_data=?, updated_at=datetime('now') WHERE id=?",
-    )
-    .bind(status_type)
-    .bind(status_data)
-    .bind(id)
-    .execute(pool)
-    .await?;
-    Ok(())
+const RECEIPT_QUERY_WHERE: &str = r#"WHERE (? IS NULL OR status = ?)
+             AND (? IS NULL OR instr(LOWER(vendor), LOWER(?)) > 0)
+             AND (? IS NULL OR receipt_date >= ?)
+             AND (? IS NULL OR receipt_date <= ?)
+             AND (? IS NULL OR total_cents >= ?)
+             AND (? IS NULL OR total_cents <= ?)
+             AND (? IS NULL OR (transaction_id IS NOT NULL OR imported_transaction_id IS NOT NULL) = ?)
+             AND (? IS NULL OR (confidence >= ? AND confidence < ?))"#;
+
+fn bind_receipt_query<'q, O>(
+    query: sqlx::query::QueryAs<'q, Sqlite, O, sqlx::sqlite::SqliteArguments<'q>>,
+    q: &'q ReceiptQuery,
+) -> sqlx::query::QueryAs<'q, Sqlite, O, sqlx::sqlite::SqliteArguments<'q>> {
+    let band = q.confidence.map(ConfidenceBand::bounds);
+    query
+        .bind(&q.status)
+        .bind(&q.status)
+        .bind(&q.vendor)
+        .bind(&q.vendor)
+        .bind(&q.date_from)
+        .bind(&q.date_from)
+        .bind(&q.date_to)
+        .bind(&q.date_to)
+        .bind(q.min_total_cents)
+        .bind(q.min_total_cents)
+        .bind(q.max_total_cents)
+        .bind(q.max_total_cents)
+        .bind(q.linked)
+        .bind(q.linked)
+        .bind(band.map(|b| b.0))
+        .bind(band.map(|b| b.0))
+        .bind(band.map(|b| b.1))
 }
 
-pub async fn get_all_invoices(pool: &DbPool) -> Result<Vec<InvoiceRecord>, sqlx::Error> {
-    sqlx::query_as::<_, InvoiceRecord>("SELECT * FROM invoices ORDER BY created_at DESC LIMIT 5000")
+/// One page of receipts matching `q`, newest receipt date first.
+pub async fn query_receipts(pool: &DbPool, q: &ReceiptQuery) -> Result<ReceiptPage, sqlx::Error> {
+    let sql = format!(
+        "SELECT * FROM receipts {RECEIPT_QUERY_WHERE}
+           ORDER BY receipt_date IS NULL, receipt_date DESC, id DESC
+           LIMIT ? OFFSET ?"
+    );
+    let receipts = bind_receipt_query(sqlx::query_as::<_, ReceiptRecord>(&sql), q)
+        .bind(q.limit.unwrap_or(50).clamp(1, 500))
+        .bind(q.offset.unwrap_or(0).max(0))
         .fetch_all(pool)
-        .await
+        .await?;
+
+    let sql = format!("SELECT COUNT(*) FROM receipts {RECEIPT_QUERY_WHERE}");
+    let (total,): (i64,) = bind_receipt_query(sqlx::query_as(&sql), q)
+        .fetch_one(pool)
+        .await?;
+    Ok(ReceiptPage { receipts, total })
 }
 
-pub async fn get_invoice_by_id(
-    pool: &DbPool,
-    id: i64,
-) -> Result<Option<InvoiceRecord>, sqlx::Error> {
-    sqlx::query_as::<_, InvoiceRecord>("SELECT * FROM invoices WHERE id = ?")
-        .bind(id)
-        .fetch_optional(pool)
-        .await
+/// Setting holding the expense amount, in cents, above which a transaction
+/// is expected to have a receipt.
+pub const RECEIPT_THRESHOLD_SETTING: &str = "receipt_required_threshold_cents";
+
+/// The IRS asks for receipts for expenses of $75 and up.
+pub const DEFAULT_RECEIPT_THRESHOLD_CENTS: i64 = 7_500;
+
+pub async fn get_receipt_threshold_cents(pool: &DbPool) -> Result<i64, sqlx::Error> {
+    Ok(get_setting(pool, RECEIPT_THRESHOLD_SETTING)
+        .await?
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_RECEIPT_THRESHOLD_CENTS))
 }
 
-pub async fn get_invoices_by_status(
-    pool: &DbPool,
-    status_type: &str,
-) -> Result<Vec<InvoiceRecord>, sqlx::Error> {
-    sqlx::query_as::<_, InvoiceRecord>(
-        "SELECT * FROM invoices WHERE status_type = ? ORDER BY due_date",
-    )
-    .bind(status_type)
-    .fetch_all(pool)
-    .await
+/// An expense transaction with no receipt attached.
+#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
+pub struct UnreceiptedExpense {
+    pub transaction_id: i64,
+    pub date: String,
+    pub description: String,
+    /// Net amount booked to expense accounts.
+    pub expense_cents: i64,
 }
 
-pub async fn insert_invoice_line(
+/// Expense transactions dated `start_date` to `end_date` of at least
+/// `min_cents` that no receipt is linked to, largest first. Personal
+/// transactions don't need one.
+pub async fn get_unreceipted_expenses(
     pool: &DbPool,
-    invoice_id: i64,
-    description: &str,
-    quantity_hundredths: i64,
-    unit_rate_cents: i64,
-    taxable: bool,
-    sort_order: i64,
-) -> Result<i64, sqlx::Error> {
-    let result = sqlx::query(
-        r#"INSERT INTO invoice_lines (invoice_id, description, quantity_hundredths,
-           unit_rate_cents, taxable, sort_order) VALUES (?, ?, ?, ?, ?, ?)"#,
+    start_date: &str,
+    end_date: &str,
+    min_cents: i64,
+) -> Result<Vec<UnreceiptedExpense>, sqlx::Error> {
+    sqlx::query_as::<_, UnreceiptedExpense>(
+        r#"SELECT t.id AS transaction_id, t.date, t.description,
+                  SUM(tl.debit_cents - tl.credit_cents) AS expense_cents
+           FROM transactions t
+           JOIN transaction_lines tl ON tl.transaction_id = t.id
+           JOIN accounts a ON a.id = tl.account_id
+           WHERE a.account_type = 'Expense'
+             AND t.is_personal = 0
+             AND t.date >= ? AND t.date <= ?
+             AND NOT EXISTS (SELECT 1 FROM receipts r WHERE r.transaction_id = t.id)
+           GROUP BY t.id
+           HAVING expense_cents >= ?
+           ORDER BY expense_cents DESC, t.date"#,
     )
-    .bind(invoice_id)
-    .bind(description)
-    .bind(quantity_hundredths)
-    .bind(unit_rate_cents)
-    .bind(taxable)
-    .bind(sort_order)
-    .execute(pool)
-    .await?;
-    Ok(result.last_insert_rowid())
+    .bind(start_date)
+    .bind(end_date)
+    .bind(min_cents)
+    .fetch_all(pool)
+    .await
 }
 
-pub async fn get_invoice_lines(
+/// Approved receipts dated `start_date` to `end_date` that back no ledger
+/// transaction and aren't waiting on a bank row either.
+pub async fn get_unlinked_approved_receipts(
     pool: &DbPool,
-    invoice_id: i64,
-) -> Result<Vec<InvoiceLineRecord>, sqlx::Error> {
-    sqlx::query_as::<_, InvoiceLineRecord>(
-        "SELECT * FROM invoice_lines WHERE invoice_id = ? ORDER BY sort_order",
+    start_date: &str,
+    end_date: &str,
+) -> Result<Vec<ReceiptRecord>, sqlx::Error> {
+    sqlx::query_as::<_, ReceiptRecord>(
+        r#"SELECT * FROM receipts
+           WHERE status = 'approved'
+             AND transaction_id IS NULL
+             AND imported_transaction_id IS NULL
+             AND receipt_date >= ? AND receipt_date <= ?
+           ORDER BY receipt_date, id"#,
     )
-    .bind(invoice_id)
+    .bind(start_date)
+    .bind(end_date)
     .fetch_all(pool)
     .await
 }
 
-pub async fn insert_invoice_tax_line(
-    pool: &DbPool,
-    invoice_id: i64,
-    label: &str,
-    rate_bps: i64,
-) -> Result<i64, sqlx::Error> {
-    let result =
-        sqlx::query("INSERT INTO invoice_tax_lines (invoice_id, label, rate_bps) VALUES (?, ?, ?)")
-            .bind(invoice_id)
-            .bind(label)
-            .bind(rate_bps)
-            .execute(pool)
-            .await?;
-    Ok(result.last_insert_rowid())
+// ── Receipt re-processing ─────────────────────────────────────────────────────
+
+/// Which stored receipts to re-run extraction over. Unset fields don't
+/// filter; rejected receipts are never included.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+pub struct ReprocessFilter {
+    /// `pending_review`, `approved` or `duplicate`.
+    pub status: Option<String>,
+    /// Ingested on or after this date (YYYY-MM-DD).
+    pub since: Option<String>,
+    /// Only receipts whose extraction confidence is below this.
+    pub max_confidence: Option<f64>,
+    pub limit: Option<i64>,
 }
 
-pub async fn get_invoice_tax_lines(
-    pool: &DbPool,
-    invoice_id: i64,
-) -> Result<Vec<InvoiceTaxLineRecord>, sqlx::Error> {
-    sqlx::query_as::<_, InvoiceTaxLineRecord>(
-        "SELECT * FROM invoice_tax_lines WHERE invoice_id = ?",
-    )
-    .bind(invoice_id)
-    .fetch_all(pool)
-    .await
+/// Fields from re-running OCR and extraction over a stored receipt.
+#[derive(Debug, Clone, Default)]
+pub struct ReextractedReceipt {
+    pub ocr_text: String,
+    pub vendor: Option<String>,
+    pub receipt_date: Option<String>,
+    pub total_cents: Option<i64>,
+    pub subtotal_cents: Option<i64>,
+    pub tax_cents: Option<i64>,
+    pub payment_method: Option<String>,
+    pub reference: Option<String>,
+    pub confidence: f64,
 }
 
-// ── Payment storage ──────────────────────────────────────────────────────────
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReprocessOutcome {
+    /// Extraction came out the same.
+    Unchanged,
+    /// Unreviewed receipt, overwritten with the new fields.
+    Updated,
+    /// Reviewed receipt; the differences wait in `receipt_reprocess_diffs`.
+    Queued,
+}
 
 #[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
-pub struct PaymentRecord {
+pub struct ReceiptReprocessDiff {
     pub id: i64,
-    pub invoice_id: i64,
-    pub amount_cents: i64,
-    pub date: String,
-    pub method: Option<String>,
-    pub transaction_id: Option<i64>,
+    pub receipt_id: i64,
+    /// The `receipts` column that would change.
+    pub field: String,
+    pub current_value: Option<String>,
+    pub proposed_value: String,
+    pub status: String,
     pub created_at: String,
 }
 
-pub async fn insert_payment(
+pub async fn get_receipts_for_reprocess(
     pool: &DbPool,
-    invoice_id: i64,
-    amount_cents: i64,
-    date: &str,
-    method: Option<&str>,
-    transaction_id: Option<i64>,
-) -> Result<i64, sqlx::Error> {
-    let result = sqlx::query(
-        r#"INSERT INTO payments (invoice_id, amount_cents, date, method, transaction_id)
-           VALUES (?, ?, ?, ?, ?)"#,
+    filter: &ReprocessFilter,
+) -> Result<Vec<ReceiptRecord>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, ReceiptRecord>(
+        r#"SELECT * FROM receipts
+           WHERE status != 'rejected'
+             AND (? IS NULL OR status = ?)
+             AND (? IS NULL OR date(created_at) >= ?)
+             AND (? IS NULL OR confidence < ?)
+           ORDER BY id
+           LIMIT ?"#,
     )
-    .bind(invoice_id)
-    .bind(amount_cents)
-    .bind(date)
-    .bind(method)
-    .bind(transaction_id)
-    .execute(pool)
+    .bind(&filter.status)
+    .bind(&filter.status)
+    .bind(&filter.since)
+    .bind(&filter.since)
+    .bind(filter.max_confidence)
+    .bind(filter.max_confidence)
+    .bind(filter.limit.unwrap_or(-1))
+    .fetch_all(pool)
     .await?;
-    Ok(result.last_insert_rowid())
+    Ok(rows)
 }
 
-pub async fn get_payments_for_invoice(
+/// Compare a re-extraction with what is stored. Fields the new run left
+/// empty, and fields the user corrected by hand, are never touched.
+///
+/// Receipts still pending review are updated in place. Anything already
+/// reviewed keeps its values and gets one pending diff per changed field,
+/// replacing diffs from an earlier run.
+pub async fn apply_reprocessed_receipt(
     pool: &DbPool,
-    invoice_id: i64,
-) -> Result<Vec<PaymentRecord>, sqlx::Error> {
-    sqlx::query_as::<_, PaymentRecord>("SELECT * FROM payments WHERE invoice_id = ? ORDER BY date")
-        .bind(invoice_id)
-        .fetch_all(pool)
-        .await
+    receipt_id: i64,
+    new: &ReextractedReceipt,
+) -> Result<ReprocessOutcome, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let current = sqlx::query_as::<_, ReceiptRecord>("SELECT * FROM receipts WHERE id = ?")
+        .bind(receipt_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    let corrected: Vec<(String,)> =
+        sqlx::query_as("SELECT field FROM receipt_corrections WHERE receipt_id = ?")
+            .bind(receipt_id)
+            .fetch_all(&mut *tx)
+            .await?;
+    let corrected = |field: &str| corrected.iter().any(|(f,)| f == field);
+
+    let text = |v: Option<i64>| v.map(|c| c.to_string());
+    let fields = [
+        ("vendor", "vendor", current.vendor, new.vendor.clone()),
+        (
+            "receipt_date",
+            "date",
+            current.receipt_date,
+            new.receipt_date.clone(),
+        ),
+        (
+            "total_cents",
+            "total",
+            text(current.total_cents),
+            text(new.total_cents),
+        ),
+        (
+            "subtotal_cents",
+            "subtotal",
+            text(current.subtotal_cents),
+            text(new.subtotal_cents),
+        ),
+        (
+            "tax_cents",
+            "tax",
+            text(current.tax_cents),
+            text(new.tax_cents),
+        ),
+        (
+            "payment_method",
+            "payment_method",
+            current.payment_method,
+            new.payment_method.clone(),
+        ),
+        (
+            "reference",
+            "reference",
+            current.reference,
+            new.reference.clone(),
+        ),
+    ];
+    let changes: Vec<(&str, Option<String>, String)> = fields
+        .into_iter()
+        .filter(|(_, correction_field, _, _)| !corrected(correction_field))
+        .filter_map(|(column, _, old, new)| {
+            let new = new?;
+            (old.as_deref() != Some(new.as_str())).then_some((column, old, new))
+        })
+        .collect();
+
+    let outcome = if current.status == "pending_review" {
+        // Column names come from the fixed list above, never from input.
+        for (column, _, value) in &changes {
+            sqlx::query(&format!("UPDATE receipts SET {column} = ? WHERE id = ?"))
+                .bind(value)
+                .bind(receipt_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        sqlx::query("UPDATE receipts SET ocr_text = ?, confidence = ? WHERE id = ?")
+            .bind(&new.ocr_text)
+            .bind(new.confidence)
+            .bind(receipt_id)
+            .execute(&mut *tx)
+            .await?;
+        if changes.is_empty() {
+            ReprocessOutcome::Unchanged
+        } else {
+            ReprocessOutcome::Updated
+        }
+    } else {
+        sqlx::query(
+            "DELETE FROM receipt_reprocess_diffs WHERE receipt_id = ? AND status = 'pending'",
+        )
+        .bind(receipt_id)
+        .execute(&mut *tx)
+        .await?;
+        for (column, old, value) in &changes {
+            sqlx::query(
+                r#"INSERT INTO receipt_reprocess_diffs
+                     (receipt_id, field, current_value, proposed_value)
+                   VALUES (?, ?, ?, ?)"#,
+            )
+            .bind(receipt_id)
+            .bind(column)
+            .bind(old)
+            .bind(value)
+            .execute(&mut *tx)
+            .await?;
+        }
+        if changes.is_empty() {
+            ReprocessOutcome::Unchanged
+        } else {
+            ReprocessOutcome::Queued
+        }
+    };
+    tx.commit().await?;
+    Ok(outcome)
 }
 
-pub async fn get_ytd_payments_to_contact(
+pub async fn get_pending_reprocess_diffs(
     pool: &DbPool,
-    contact_id: i64,
-    year: u16,
-) -> Result<i64, sqlx::Error> {
-    let start = format!("{year}-01-01");
-    let end = format!("{year}-12-31");
-    let row = sqlx::query_as::<_, (i64,)>(
-        r#"SELECT COALESCE(SUM(p.amount_cents), 0)
-           FROM payments p
-           JOIN invoices i ON p.invoice_id = i.id
-           WHERE i.contact_id = ? AND p.date >= ? AND p.date <= ?"#,
+) -> Result<Vec<ReceiptReprocessDiff>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, ReceiptReprocessDiff>(
+        r#"SELECT id, receipt_id, field, current_value, proposed_value, status, created_at
+           FROM receipt_reprocess_diffs
+           WHERE status = 'pending'
+           ORDER BY receipt_id, id"#,
     )
-    .bind(contact_id)
-    .bind(&start)
-    .bind(&end)
-    .fetch_one(pool)
+    .fetch_all(pool)
     .await?;
-    Ok(row.0)
+    Ok(rows)
 }
 
-/// Get YTD payments for all contractors in a single query (avoids N+1).
-pub async fn get_contractor_ytd_payments(
+/// Accept a pending diff, writing the proposed value to the receipt, or
+/// dismiss it. Returns `false` if there is no such pending diff.
+pub async fn resolve_reprocess_diff(
     pool: &DbPool,
-    year: u16,
-) -> Result<Vec<(i64, String, i64)>, sqlx::Error> {
-    let start = format!("{year}-01-01");
-    let end = format!("{year}-12-31");
-    sqlx::query_as::<_, (i64, String, i64)>(
-        r#"SELECT c.id, c.name, COALESCE(SUM(p.amount_cents), 0) as ytd
-           FROM contacts c
-           LEFT JOIN invoices i ON i.contact_id = c.id
-           LEFT JOIN payments p ON p.invoice_id = i.id AND p.date >= ? AND p.date <= ?
-           WHERE c.is_contractor = 1
-           GROUP BY c.id, c.name
-           ORDER BY c.name"#,
+    diff_id: i64,
+    accept: bool,
+) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let diff: Option<(i64, String, String)> = sqlx::query_as(
+        "SELECT receipt_id, field, proposed_value FROM receipt_reprocess_diffs WHERE id = ? AND status = 'pending'",
+    )
+    .bind(diff_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some((receipt_id, field, value)) = diff else {
+        return Ok(false);
+    };
+
+    if accept {
+        // `field` is limited to receipt columns by the table's CHECK constraint.
+        sqlx::query(&format!("UPDATE receipts SET {field} = ? WHERE id = ?"))
+            .bind(value)
+            .bind(receipt_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    sqlx::query(
+        "UPDATE receipt_reprocess_diffs SET status = ?, resolved_at = datetime('now') WHERE id = ?",
+    )
+    .bind(if accept { "accepted" } else { "dismissed" })
+    .bind(diff_id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(true)
+}
+
+type UnmatchedReceiptRow = (
+    i64,
+    String,
+    Option<String>,
+    i64,
+    Option<String>,
+    Option<String>,
+);
+
+/// Approved receipts with a total and date that are not yet linked to any
+/// ledger or imported transaction.
+pub async fn get_unmatched_receipts(pool: &DbPool) -> Result<Vec<MatchableReceipt>, sqlx::Error> {
+    let payment_accounts = get_payment_account_map(pool).await?;
+    let rows: Vec<UnmatchedReceiptRow> = sqlx::query_as(
+        r#"SELECT id, receipt_date, vendor, total_cents, reference, payment_method FROM receipts
+           WHERE status = 'approved'
+             AND transaction_id IS NULL
+             AND imported_transaction_id IS NULL
+             AND receipt_date IS NOT NULL
+             AND total_cents IS NOT NULL
+           ORDER BY receipt_date"#,
     )
-    .bind(&start)
-    .bind(&end)
     .fetch_all(pool)
-    .await
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|(id, date, vendor, total_cents, reference, method)| {
+            Some(MatchableReceipt {
+                id,
+                date: NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok()?,
+                vendor: vendor.unwrap_or_default(),
+                total_cents,
+                reference,
+                payment_account: method
+                    .and_then(|m| payment_accounts.mapped(&m).map(str::to_string)),
+            })
+        })
+        .collect())
 }
 
-/// Invoice aging report: returns invoices bucketed by days overdue.
-pub async fn get_invoice_aging(pool: &DbPool) -> Result<Vec<InvoiceRecord>, sqlx::Error> {
-    sqlx::query_as::<_, InvoiceRecord>(
-        r#"SELECT * FROM invoices
-           WHERE status_type IN ('Sent', 'Viewed', 'PartiallyPaid')
-           ORDER BY due_date"#,
+/// Ledger transactions and still-pending imported rows dated within
+/// `[start, end]` that no receipt is linked to yet.
+pub async fn get_receipt_match_candidates(
+    pool: &DbPool,
+    start: &str,
+    end: &str,
+) -> Result<Vec<ReceiptMatchCandidate>, sqlx::Error> {
+    let rows: Vec<(String, i64, String, String, i64, Option<String>)> = sqlx::query_as(
+        r#"SELECT 'ledger', t.id, t.date, t.description, t.balanced_total_cents,
+                  (SELECT GROUP_CONCAT(a.code) FROM transaction_lines l
+                   JOIN accounts a ON a.id = l.account_id
+                   WHERE l.transaction_id = t.id)
+           FROM transactions t
+           WHERE t.date BETWEEN ? AND ?
+             AND NOT EXISTS (SELECT 1 FROM receipts r WHERE r.transaction_id = t.id)
+           UNION ALL
+           SELECT 'imported', i.id, i.date, i.description, i.amount_cents, NULL
+           FROM imported_transactions i
+           WHERE i.date BETWEEN ? AND ?
+             AND i.status IN ('pending', 'categorized')
+             AND NOT EXISTS (SELECT 1 FROM receipts r WHERE r.imported_transaction_id = i.id)"#,
     )
+    .bind(start)
+    .bind(end)
+    .bind(start)
+    .bind(end)
     .fetch_all(pool)
-    .await
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|(source, id, date, description, amount_cents, codes)| {
+            let target = if source == "ledger" {
+                ReceiptLinkTarget::Ledger(id)
+            } else {
+                ReceiptLinkTarget::Imported(id)
+            };
+            Some(ReceiptMatchCandidate {
+                target,
+                date: NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok()?,
+                description,
+                amount_cents,
+                account_codes: codes
+                    .map(|c| c.split(',').map(str::to_string).collect())
+                    .unwrap_or_default(),
+            })
+        })
+        .collect())
 }
 
-// ── Audit log ────────────────────────────────────────────────────────────────
+/// Suggest links for every unmatched approved receipt, searching candidates
+/// within the engine's date window of the receipts' dates.
+pub async fn find_receipt_match_suggestions(
+    pool: &DbPool,
+    engine: &AutoMatchEngine,
+) -> Result<Vec<ReceiptMatchSuggestion>, sqlx::Error> {
+    let receipts = get_unmatched_receipts(pool).await?;
+    let (Some(first), Some(last)) = (receipts.first(), receipts.last()) else {
+        return Ok(Vec::new());
+    };
+    let window = chrono::Duration::days(engine.date_window_days.max(0) as i64);
+    let candidates = get_receipt_match_candidates(
+        pool,
+        &(first.date - window).to_string(),
+        &(last.date + window).to_string(),
+    )
+    .await?;
+    Ok(aequi_core::import::suggest_receipt_matches(
+        engine,
+        &receipts,
+        &candidates,
+    ))
+}
 
-#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
-pub struct AuditLogRecord {
-    pub id: i64,
-    pub timestamp: String,
-    pub tool_name: String,
-    pub input_hash: Option<String>,
-    pub outcome: String,
-    pub details: Option<String>,
+/// Whether the ledger transaction or imported row a receipt would link to
+/// exists.
+pub async fn receipt_link_target_exists(
+    pool: &DbPool,
+    target: ReceiptLinkTarget,
+) -> Result<bool, sqlx::Error> {
+    let (sql, id) = match target {
+        ReceiptLinkTarget::Ledger(id) => ("SELECT id FROM transactions WHERE id = ?", id),
+        ReceiptLinkTarget::Imported(id) => {
+            ("SELECT id FROM imported_transactions WHERE id = ?", id)
+        }
+    };
+    let row: Option<(i64,)> = sqlx::query_as(sql).bind(id).fetch_optional(pool).await?;
+    Ok(row.is_some())
 }
 
-pub async fn insert_audit_log(
+/// Link a receipt to a suggested match. Ledger targets attach the receipt to
+/// the transaction directly; imported targets attach to the ledger
+/// transaction the row was already matched to, or otherwise hold the link on
+/// the imported row until it is matched.
+pub async fn confirm_receipt_match(
     pool: &DbPool,
-    tool_name: &str,
-    input_hash: Option<&str>,
-    outcome: &str,
-    details: Option<&str>,
-) -> Result<i64, sqlx::Error> {
-    let result = sqlx::query(
-        "INSERT INTO audit_log (tool_name, input_hash, outcome, details) VALUES (?, ?, ?, ?)",
+    receipt_id: i64,
+    target: ReceiptLinkTarget,
+) -> Result<(), ReceiptStatusError> {
+    match target {
+        ReceiptLinkTarget::Ledger(transaction_id) => {
+            link_receipt_to_transaction(pool, receipt_id, transaction_id).await
+        }
+        ReceiptLinkTarget::Imported(imported_id) => {
+            let (matched,): (Option<i64>,) = sqlx::query_as(
+                "SELECT matched_transaction_id FROM imported_transactions WHERE id = ?",
+            )
+            .bind(imported_id)
+            .fetch_one(pool)
+            .await?;
+            loop {
+                receipt_state(pool, receipt_id).await?.check_link()?;
+                let done = sqlx::query(
+                    r#"UPDATE receipts
+                       SET imported_transaction_id = ?, transaction_id = ?,
+                           status = 'approved', reviewed_at = datetime('now')
+                       WHERE id = ? AND status IN ('pending_review', 'approved')"#,
+                )
+                .bind(imported_id)
+                .bind(matched)
+                .bind(receipt_id)
+                .execute(pool)
+                .await?
+                .rows_affected();
+                if done > 0 {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+// ── Receipt auto-approval ─────────────────────────────────────────────────────
+
+/// Setting holding [`AutoApproveSettings`], as JSON.
+pub const AUTO_APPROVE_SETTING: &str = "receipt_auto_approve";
+
+/// When a receipt may be approved without review. Off unless the user turns
+/// it on.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct AutoApproveSettings {
+    pub enabled: bool,
+    /// Lowest extraction confidence, 0.0–1.0, a receipt needs.
+    pub min_confidence: f64,
+    /// Lowest confidence, 0.0–1.0, of its best bank-transaction match.
+    pub min_match_confidence: f32,
+}
+
+impl Default for AutoApproveSettings {
+    fn default() -> Self {
+        AutoApproveSettings {
+            enabled: false,
+            min_confidence: 0.9,
+            min_match_confidence: 0.9,
+        }
+    }
+}
+
+pub async fn get_auto_approve_settings(pool: &DbPool) -> Result<AutoApproveSettings, sqlx::Error> {
+    match get_setting(pool, AUTO_APPROVE_SETTING).await? {
+        Some(json) if !json.trim().is_empty() => {
+            serde_json::from_str(&json).map_err(|e| sqlx::Error::Decode(Box::new(e)))
+        }
+        _ => Ok(AutoApproveSettings::default()),
+    }
+}
+
+pub async fn set_auto_approve_settings(
+    pool: &DbPool,
+    settings: &AutoApproveSettings,
+) -> Result<(), sqlx::Error> {
+    let json = serde_json::to_string(settings).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    set_setting(pool, AUTO_APPROVE_SETTING, &json).await
+}
+
+/// The best match for one receipt among the transactions no receipt is
+/// linked to yet. `None` if the receipt has no date or total, or nothing
+/// scores above the engine's threshold.
+pub async fn find_receipt_match(
+    pool: &DbPool,
+    engine: &AutoMatchEngine,
+    receipt: &ReceiptRecord,
+) -> Result<Option<ReceiptMatchSuggestion>, sqlx::Error> {
+    let (Some(date), Some(total_cents)) = (
+        receipt
+            .receipt_date
+            .as_deref()
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok()),
+        receipt.total_cents,
+    ) else {
+        return Ok(None);
+    };
+    let payment_accounts = get_payment_account_map(pool).await?;
+    let matchable = MatchableReceipt {
+        id: receipt.id,
+        date,
+        vendor: receipt.vendor.clone().unwrap_or_default(),
+        total_cents,
+        reference: receipt.reference.clone(),
+        payment_account: receipt
+            .payment_method
+            .as_deref()
+            .and_then(|m| payment_accounts.mapped(m).map(str::to_string)),
+    };
+    let window = chrono::Duration::days(engine.date_window_days.max(0) as i64);
+    let candidates = get_receipt_match_candidates(
+        pool,
+        &(date - window).to_string(),
+        &(date + window).to_string(),
     )
-    .bind(tool_name)
-    .bind(input_hash)
-    .bind(outcome)
-    .bind(details)
-    .execute(pool)
     .await?;
-    Ok(result.last_insert_rowid())
+    Ok(
+        aequi_core::import::suggest_receipt_matches(engine, &[matchable], &candidates)
+            .into_iter()
+            .max_by(|a, b| a.confidence.total_cmp(&b.confidence)),
+    )
 }
 
-pub async fn get_audit_log(pool: &DbPool, limit: i64) -> Result<Vec<AuditLogRecord>, sqlx::Error> {
-    sqlx::query_as::<_, AuditLogRecord>("SELECT * FROM audit_log ORDER BY timestamp DESC LIMIT ?")
-        .bind(limit)
-        .fetch_all(pool)
-        .await
+/// A receipt about to be approved without review.
+#[derive(Debug, Clone)]
+pub struct NewAutoApproval {
+    pub receipt_id: i64,
+    /// The ledger transaction the receipt supports.
+    pub transaction_id: i64,
+    /// The bank row it matched, when the match was an imported transaction.
+    pub imported_transaction_id: Option<i64>,
+    /// `transaction_id` was posted from the receipt rather than already in
+    /// the ledger.
+    pub posted: bool,
+    pub receipt_confidence: f64,
+    pub match_confidence: f32,
 }
 
-// ── Settings storage ─────────────────────────────────────────────────────────
+/// Approve a pending receipt, link it (and its bank row, if any) to the
+/// transaction, and log the approval. Runs on the caller's connection so a
+/// transaction posted for it commits or rolls back together with the link.
+/// Returns `None` if the receipt was no longer pending review.
+pub async fn record_auto_approval(
+    conn: &mut sqlx::SqliteConnection,
+    approval: &NewAutoApproval,
+) -> Result<Option<i64>, sqlx::Error> {
+    let updated = sqlx::query(
+        r#"UPDATE receipts
+           SET status = 'approved', transaction_id = ?, imported_transaction_id = ?,
+               reviewed_at = datetime('now')
+           WHERE id = ? AND status = 'pending_review'"#,
+    )
+    .bind(approval.transaction_id)
+    .bind(approval.imported_transaction_id)
+    .bind(approval.receipt_id)
+    .execute(&mut *conn)
+    .await?;
+    if updated.rows_affected() == 0 {
+        return Ok(None);
+    }
+    if let Some(imported_id) = approval.imported_transaction_id {
+        sqlx::query(
+            "UPDATE imported_transactions SET matched_transaction_id = ?, status = 'matched' WHERE id = ?",
+        )
+        .bind(approval.transaction_id)
+        .bind(imported_id)
+        .execute(&mut *conn)
+        .await?;
+    }
+    let id = sqlx::query(
+        r#"INSERT INTO receipt_auto_approvals
+           (receipt_id, transaction_id, imported_transaction_id, posted,
+            receipt_confidence, match_confidence)
+           VALUES (?, ?, ?, ?, ?, ?)"#,
+    )
+    .bind(approval.receipt_id)
+    .bind(approval.transaction_id)
+    .bind(approval.imported_transaction_id)
+    .bind(approval.posted)
+    .bind(approval.receipt_confidence)
+    .bind(approval.match_confidence)
+    .execute(&mut *conn)
+    .await?
+    .last_insert_rowid();
+    Ok(Some(id))
+}
 
-pub async fn get_setting(pool: &DbPool, key: &str) -> Result<Option<String>, sqlx::Error> {
-    let row = sqlx::query_as::<_, (String,)>("SELECT value FROM settings WHERE key = ?")
-        .bind(key)
-        .fetch_optional(pool)
+#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
+pub struct AutoApprovalRecord {
+    pub id: i64,
+    pub receipt_id: i64,
+    pub vendor: Option<String>,
+    pub receipt_date: Option<String>,
+    pub total_cents: Option<i64>,
+    pub transaction_id: Option<i64>,
+    pub transaction_description: Option<String>,
+    pub imported_transaction_id: Option<i64>,
+    pub posted: bool,
+    pub receipt_confidence: f64,
+    pub match_confidence: f64,
+    pub created_at: String,
+    pub undone_at: Option<String>,
+}
+
+/// Auto-approvals made at or after `since` (`YYYY-MM-DD HH:MM:SS`, UTC),
+/// newest first, including ones since undone.
+pub async fn get_auto_approvals(
+    pool: &DbPool,
+    since: &str,
+) -> Result<Vec<AutoApprovalRecord>, sqlx::Error> {
+    sqlx::query_as::<_, AutoApprovalRecord>(
+        r#"SELECT a.id, a.receipt_id, r.vendor, r.receipt_date, r.total_cents,
+                  a.transaction_id, t.description AS transaction_description,
+                  a.imported_transaction_id, a.posted, a.receipt_confidence,
+                  a.match_confidence, a.created_at, a.undone_at
+           FROM receipt_auto_approvals a
+           JOIN receipts r ON r.id = a.receipt_id
+           LEFT JOIN transactions t ON t.id = a.transaction_id
+           WHERE a.created_at >= ?
+           ORDER BY a.created_at DESC, a.id DESC"#,
+    )
+    .bind(since)
+    .fetch_all(pool)
+    .await
+}
+
+/// Put an auto-approved receipt back in the review queue. A transaction
+/// that was posted from the receipt is deleted and its bank row goes back
+/// to categorized; a transaction that was already in the ledger is only
+/// unlinked. Returns false if the approval doesn't exist or was already
+/// undone.
+pub async fn undo_auto_approval(pool: &DbPool, id: i64) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let Some((receipt_id, transaction_id, imported_id, posted)) =
+        sqlx::query_as::<_, (i64, Option<i64>, Option<i64>, bool)>(
+            r#"SELECT receipt_id, transaction_id, imported_transaction_id, posted
+               FROM receipt_auto_approvals WHERE id = ? AND undone_at IS NULL"#,
+        )
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?
+    else {
+        return Ok(false);
+    };
+
+    sqlx::query(
+        r#"UPDATE receipts
+           SET status = 'pending_review', transaction_id = NULL,
+               imported_transaction_id = NULL, reviewed_at = NULL
+           WHERE id = ?"#,
+    )
+    .bind(receipt_id)
+    .execute(&mut *tx)
+    .await?;
+    // Only categorized bank rows are posted from, so that is where they go back to.
+    if let Some(imported_id) = imported_id {
+        sqlx::query(
+            "UPDATE imported_transactions SET matched_transaction_id = NULL, status = 'categorized' WHERE id = ?",
+        )
+        .bind(imported_id)
+        .execute(&mut *tx)
         .await?;
-    Ok(row.map(|r| r.0))
+    }
+    if let (true, Some(transaction_id)) = (posted, transaction_id) {
+        sqlx::query("UPDATE receipts SET transaction_id = NULL WHERE transaction_id = ?")
+            .bind(transaction_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM transaction_lines WHERE transaction_id = ?")
+            .bind(transaction_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM transactions WHERE id = ?")
+            .bind(transaction_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    sqlx::query("UPDATE receipt_auto_approvals SET undone_at = datetime('now') WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(true)
 }
 
-pub async fn set_setting(pool: &DbPool, key: &str, value: &str) -> Result<(), sqlx::Error> {
+// ── Vendor profiles ───────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
+pub struct VendorProfileRecord {
+    pub id: i64,
+    pub name: String,
+    /// Mean tax rate (tax / subtotal) over `tax_samples` approved receipts.
+    pub tax_rate: Option<f64>,
+    pub tax_samples: i64,
+    pub total_label: Option<String>,
+    pub approvals: i64,
+    pub updated_at: String,
+}
+
+/// Fold an approved receipt into its vendor's profile, creating it on first
+/// approval. The tax rate is averaged over every receipt that had one; a new
+/// total label replaces the old one.
+pub async fn record_vendor_approval(
+    pool: &DbPool,
+    name: &str,
+    tax_rate: Option<f64>,
+    total_label: Option<&str>,
+) -> Result<(), sqlx::Error> {
     sqlx::query(
-        "INSERT INTO settings (key, value) VALUES (?, ?) ON CONFLICT(key) DO UPDATE SET value=excluded.value",
+        r#"INSERT INTO vendor_profiles (name, tax_rate, tax_samples, total_label, approvals)
+           VALUES (?, ?, ?, ?, 1)
+           ON CONFLICT(name) DO UPDATE SET
+             tax_rate = CASE
+               WHEN excluded.tax_rate IS NULL THEN tax_rate
+               ELSE (COALESCE(tax_rate, 0) * tax_samples + excluded.tax_rate) / (tax_samples + 1)
+             END,
+             tax_samples = tax_samples + excluded.tax_samples,
+             total_label = COALESCE(excluded.total_label, total_label),
+             approvals = approvals + 1,
+             updated_at = datetime('now')
+        "#,
     )
-    .bind(key)
-    .bind(value)
+    .bind(name)
+    .bind(tax_rate)
+    .bind(i64::from(tax_rate.is_some()))
+    .bind(total_label)
     .execute(pool)
     .await?;
     Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use sqlx::sqlite::SqlitePoolOptions;
+pub async fn get_vendor_profiles(pool: &DbPool) -> Result<Vec<VendorProfileRecord>, sqlx::Error> {
+    let rows =
+        sqlx::query_as::<_, VendorProfileRecord>("SELECT * FROM vendor_profiles ORDER BY name")
+            .fetch_all(pool)
+            .await?;
+    Ok(rows)
+}
 
-    async fn test_pool() -> DbPool {
-        let pool = SqlitePoolOptions::new()
-            .max_connections(1)
-            .connect("sqlite::memory:")
-            .await
-            .unwrap();
-        sqlx::query("PRAGMA foreign_keys = ON")
-            .execute(&pool)
-            .await
-            .unwrap();
-        crate::migrate::run_migrations(&pool).await.unwrap();
-        seed_default_accounts(&pool).await.unwrap();
-        pool
-    }
+// ── Tax engine storage ───────────────────────────────────────────────────────
 
-    // Helper: insert a contact and return its id.
-    async fn insert_test_contact(pool: &DbPool, name: &str, is_contractor: bool) -> i64 {
-        insert_contact(
-            pool,
-            name,
-            Some("test@example.com"),
-            Some("555-1234"),
-            Some("123 Main St"),
-            "vendor",
+/// Build a LedgerSnapshot for a given fiscal year by aggregating transaction_lines
+/// grouped by the account's schedule_c_line tag.
+pub async fn build_ledger_snapshot(
+    pool: &DbPool,
+    year: FiscalYear,
+    prior_year_tax: Option<Money>,
+) -> Result<LedgerSnapshot, sqlx::Error> {
+    let start = year.start_date().to_string();
+    let end = year.end_date().to_string();
+
+    // Income: credit_cents - debit_cents (net credit = revenue)
+    // Expenses: debit_cents - credit_cents (net debit = cost), scaled by
+    // each transaction's deductible share and the account's. Personal
+    // transactions are left out.
+    let rows = sqlx::query_as::<_, (String, String, u8, u8, i64, i64)>(
+        r#"
+        SELECT a.schedule_c_line, a.account_type, t.deductible_percent, a.deductible_pct,
+            COALESCE(SUM(tl.debit_cents), 0) as total_debit,
+            COALESCE(SUM(tl.credit_cents), 0) as total_credit
+        FROM accounts a
+        JOIN transaction_lines tl ON a.id = tl.account_id
+        JOIN transactions t ON tl.transaction_id = t.id
+        WHERE a.schedule_c_line IS NOT NULL
+            AND a.schedule_c_line != ''
+            AND t.is_personal = 0
+            AND t.date >= ? AND t.date <= ?
+        GROUP BY a.schedule_c_line, a.account_type, t.deductible_percent, a.deductible_pct
+        "#,
+    )
+    .bind(&start)
+    .bind(&end)
+    .fetch_all(pool)
+    .await?;
+
+    let mut line_totals = BTreeMap::new();
+    let mut book_totals = BTreeMap::new();
+    for (tag, account_type, percent, account_percent, total_debit, total_credit) in rows {
+        if let Some(line) = ScheduleCLine::from_tag(&tag) {
+            let (book, amount) = match account_type.as_str() {
+                "Income" => {
+                    let income = Money::from_cents(total_credit - total_debit);
+                    (income, income)
+                }
+                "Expense" => {
+                    // Both shares at once, so the amount is rounded only once.
+                    let book = Money::from_cents(total_debit - total_credit);
+                    let share = Decimal::from(percent.min(100))
+                        * Decimal::from(account_percent.min(100))
+                        / Decimal::from(10_000);
+                    (book, book * share)
+                }
+                _ => continue,
+            };
+            if !book.is_zero() {
+                let entry = book_totals.entry(line).or_insert(Money::zero());
+                *entry = *entry + book;
+            }
+            if !amount.is_zero() {
+                let entry = line_totals.entry(line).or_insert(Money::zero());
+                *entry = *entry + amount;
+            }
+        }
+    }
+
+    Ok(LedgerSnapshot {
+        year,
+        line_totals,
+        book_totals,
+        prior_year_tax,
+    })
+}
+
+/// Set how much of an account's spending is deductible, 0–100. Returns
+/// `false` if the account doesn't exist.
+pub async fn set_account_deductible_pct(
+    pool: &DbPool,
+    code: &str,
+    deductible_pct: u8,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("UPDATE accounts SET deductible_pct = ? WHERE code = ?")
+        .bind(deductible_pct)
+        .bind(code)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Mark a transaction personal or set its deductible share. Returns `false`
+/// if the transaction doesn't exist.
+pub async fn set_transaction_deductibility(
+    pool: &DbPool,
+    transaction_id: i64,
+    deductibility: Deductibility,
+) -> Result<bool, sqlx::Error> {
+    let result =
+        sqlx::query("UPDATE transactions SET is_personal = ?, deductible_percent = ? WHERE id = ?")
+            .bind(deductibility.is_personal)
+            .bind(deductibility.deductible_percent)
+            .bind(transaction_id)
+            .execute(pool)
+            .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Tags are matched without regard to case or surrounding space, so
+/// `Client A` and ` client a` are one tag.
+pub fn normalize_tag(s: &str) -> String {
+    normalize_payee(s)
+}
+
+pub async fn get_transaction_tags(
+    pool: &DbPool,
+    transaction_id: i64,
+) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar("SELECT tag FROM transaction_tags WHERE transaction_id = ? ORDER BY tag")
+        .bind(transaction_id)
+        .fetch_all(pool)
+        .await
+}
+
+/// Every tag in use, alphabetically.
+pub async fn get_all_tags(pool: &DbPool) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar("SELECT DISTINCT tag FROM transaction_tags ORDER BY tag")
+        .fetch_all(pool)
+        .await
+}
+
+/// Replace a transaction's tags, such as the client or project it was for.
+/// Returns `false` if the transaction doesn't exist.
+pub async fn set_transaction_tags(
+    pool: &DbPool,
+    transaction_id: i64,
+    tags: &[String],
+) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM transactions WHERE id = ?)")
+        .bind(transaction_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    if !exists {
+        return Ok(false);
+    }
+    sqlx::query("DELETE FROM transaction_tags WHERE transaction_id = ?")
+        .bind(transaction_id)
+        .execute(&mut *tx)
+        .await?;
+    for tag in tags.iter().map(|t| normalize_tag(t)) {
+        if tag.is_empty() {
+            continue;
+        }
+        sqlx::query("INSERT OR IGNORE INTO transaction_tags (transaction_id, tag) VALUES (?, ?)")
+            .bind(transaction_id)
+            .bind(tag)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+    Ok(true)
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct TaxPeriodRecord {
+    pub id: i64,
+    pub year: i64,
+    pub quarter: i64,
+    pub estimated_tax_cents: i64,
+    pub se_tax_cents: i64,
+    pub income_tax_cents: i64,
+    pub net_profit_cents: i64,
+    pub payment_recorded_cents: i64,
+    pub payment_date: Option<String>,
+    pub due_date: String,
+    pub rules_year: i64,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn upsert_tax_period(
+    pool: &DbPool,
+    year: u16,
+    quarter: u8,
+    estimated_tax_cents: i64,
+    se_tax_cents: i64,
+    income_tax_cents: i64,
+    net_profit_cents: i64,
+    due_date: &str,
+    rules_year: u16,
+) -> Result<i64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"INSERT INTO tax_periods
+           (year, quarter, estimated_tax_cents, se_tax_cents, income_tax_cents,
+            net_profit_cents, due_date, rules_year)
+           VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+           ON CONFLICT(year, quarter) DO UPDATE SET
+             estimated_tax_cents = excluded.estimated_tax_cents,
+             se_tax_cents = excluded.se_tax_cents,
+             income_tax_cents = excluded.income_tax_cents,
+             net_profit_cents = excluded.net_profit_cents,
+             due_date = excluded.due_date,
+             rules_year = excluded.rules_year,
+             updated_at = datetime('now')
+        "#,
+    )
+    .bind(year as i64)
+    .bind(quarter as i64)
+    .bind(estimated_tax_cents)
+    .bind(se_tax_cents)
+    .bind(income_tax_cents)
+    .bind(net_profit_cents)
+    .bind(due_date)
+    .bind(rules_year as i64)
+    .execute(pool)
+    .await?;
+
+    Ok(result.last_insert_rowid())
+}
+
+pub async fn record_tax_payment(
+    pool: &DbPool,
+    year: u16,
+    quarter: u8,
+    payment_cents: i64,
+    payment_date: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"UPDATE tax_periods
+           SET payment_recorded_cents = ?,
+               payment_date = ?,
+               updated_at = datetime('now')
+           WHERE year = ? AND quarter = ?"#,
+    )
+    .bind(payment_cents)
+    .bind(payment_date)
+    .bind(year as i64)
+    .bind(quarter as i64)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn get_tax_periods(
+    pool: &DbPool,
+    year: u16,
+) -> Result<Vec<TaxPeriodRecord>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, TaxPeriodRecord>(
+        "SELECT * FROM tax_periods WHERE year = ? ORDER BY quarter",
+    )
+    .bind(year as i64)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn get_prior_year_total_tax(
+    pool: &DbPool,
+    year: u16,
+) -> Result<Option<i64>, sqlx::Error> {
+    let row = sqlx::query_as::<_, (i64,)>(
+        "SELECT COALESCE(SUM(estimated_tax_cents), 0) FROM tax_periods WHERE year = ?",
+    )
+    .bind((year - 1) as i64)
+    .fetch_optional(pool)
+    .await?;
+
+    match row {
+        Some((total,)) if total > 0 => Ok(Some(total)),
+        _ => Ok(None),
+    }
+}
+
+// ── Contact storage ──────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
+pub struct ContactRecord {
+    pub id: i64,
+    pub name: String,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub address: Option<String>,
+    pub contact_type: String,
+    pub is_contractor: bool,
+    pub tax_id: Option<String>,
+    pub notes: Option<String>,
+    pub created_at: String,
+    /// Account their transactions are booked to when nothing else says.
+    pub default_account_id: Option<i64>,
+    /// Days they are given to pay an invoice.
+    pub payment_terms_days: Option<i64>,
+    pub is_archived: bool,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn insert_contact(
+    pool: &DbPool,
+    name: &str,
+    email: Option<&str>,
+    phone: Option<&str>,
+    address: Option<&str>,
+    contact_type: &str,
+    is_contractor: bool,
+    tax_id: Option<&str>,
+    notes: Option<&str>,
+) -> Result<i64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"INSERT INTO contacts (name, email, phone, address, contact_type, is_contractor, tax_id, notes)
+           VALUES (?, ?, ?, ?, ?, ?, ?, ?)"#,
+    )
+    .bind(name)
+    .bind(email)
+    .bind(phone)
+    .bind(address)
+    .bind(contact_type)
+    .bind(is_contractor)
+    .bind(tax_id)
+    .bind(notes)
+    .execute(pool)
+    .await?;
+
+    Ok(result.last_insert_rowid())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn update_contact(
+    pool: &DbPool,
+    id: i64,
+    name: &str,
+    email: Option<&str>,
+    phone: Option<&str>,
+    address: Option<&str>,
+    contact_type: &str,
+    is_contractor: bool,
+    tax_id: Option<&str>,
+    notes: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"UPDATE contacts SET name=?, email=?, phone=?, address=?, contact_type=?,
+           is_contractor=?, tax_id=?, notes=? WHERE id=?"#,
+    )
+    .bind(name)
+    .bind(email)
+    .bind(phone)
+    .bind(address)
+    .bind(contact_type)
+    .bind(is_contractor)
+    .bind(tax_id)
+    .bind(notes)
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn get_all_contacts(pool: &DbPool) -> Result<Vec<ContactRecord>, sqlx::Error> {
+    sqlx::query_as::<_, ContactRecord>("SELECT * FROM contacts ORDER BY name LIMIT 5000")
+        .fetch_all(pool)
+        .await
+}
+
+pub async fn get_contact_by_id(
+    pool: &DbPool,
+    id: i64,
+) -> Result<Option<ContactRecord>, sqlx::Error> {
+    sqlx::query_as::<_, ContactRecord>("SELECT * FROM contacts WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+pub async fn get_contractors(pool: &DbPool) -> Result<Vec<ContactRecord>, sqlx::Error> {
+    sqlx::query_as::<_, ContactRecord>(
+        "SELECT * FROM contacts WHERE is_contractor = 1 ORDER BY name",
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn set_contact_defaults(
+    pool: &DbPool,
+    id: i64,
+    default_account_id: Option<i64>,
+    payment_terms_days: Option<i64>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE contacts SET default_account_id = ?, payment_terms_days = ? WHERE id = ?")
+        .bind(default_account_id)
+        .bind(payment_terms_days)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Archive or restore a contact. Returns whether it exists.
+pub async fn set_contact_archived(
+    pool: &DbPool,
+    id: i64,
+    archived: bool,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("UPDATE contacts SET is_archived = ? WHERE id = ?")
+        .bind(archived)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Delete a contact nothing refers to. Returns whether it was deleted;
+/// contacts with invoices, bills or time entries should be archived instead.
+pub async fn delete_contact(pool: &DbPool, id: i64) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"DELETE FROM contacts WHERE id = ?1
+           AND NOT EXISTS (SELECT 1 FROM invoices WHERE contact_id = ?1)
+           AND NOT EXISTS (SELECT 1 FROM time_entries WHERE contact_id = ?1)
+           AND NOT EXISTS (SELECT 1 FROM bills WHERE contact_id = ?1)"#,
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// A payee or alias as compared: lowercase, single-spaced.
+pub fn normalize_payee(s: &str) -> String {
+    s.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+pub async fn get_contact_aliases(pool: &DbPool, id: i64) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar("SELECT alias FROM contact_aliases WHERE contact_id = ? ORDER BY alias")
+        .bind(id)
+        .fetch_all(pool)
+        .await
+}
+
+/// The contact an alias belongs to.
+pub async fn get_alias_contact(pool: &DbPool, alias: &str) -> Result<Option<i64>, sqlx::Error> {
+    sqlx::query_scalar("SELECT contact_id FROM contact_aliases WHERE alias = ?")
+        .bind(normalize_payee(alias))
+        .fetch_optional(pool)
+        .await
+}
+
+/// Replace a contact's aliases.
+pub async fn set_contact_aliases(
+    pool: &DbPool,
+    id: i64,
+    aliases: &[String],
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM contact_aliases WHERE contact_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    for alias in aliases.iter().map(|a| normalize_payee(a)) {
+        if alias.is_empty() {
+            continue;
+        }
+        sqlx::query("INSERT OR IGNORE INTO contact_aliases (alias, contact_id) VALUES (?, ?)")
+            .bind(alias)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await
+}
+
+/// The active contact a payee refers to: one named the same, or with the
+/// payee as an alias, or else the one whose longest alias appears in it (so
+/// `AMZN MKTP US*2K4` finds the contact with alias `amzn mktp`).
+pub async fn find_contact_by_payee(
+    pool: &DbPool,
+    payee: &str,
+) -> Result<Option<ContactRecord>, sqlx::Error> {
+    let payee = normalize_payee(payee);
+    if payee.is_empty() {
+        return Ok(None);
+    }
+    sqlx::query_as::<_, ContactRecord>(
+        r#"SELECT c.* FROM contacts c
+           LEFT JOIN contact_aliases a ON a.contact_id = c.id
+           WHERE c.is_archived = 0
+             AND (lower(c.name) = ?1 OR a.alias = ?1 OR instr(?1, a.alias) > 0)
+           ORDER BY lower(c.name) = ?1 DESC, a.alias = ?1 DESC, length(a.alias) DESC, c.id
+           LIMIT 1"#,
+    )
+    .bind(&payee)
+    .fetch_optional(pool)
+    .await
+}
+
+// ── Invoice storage ──────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
+pub struct InvoiceRecord {
+    pub id: i64,
+    pub invoice_number: String,
+    pub contact_id: i64,
+    pub status_type: String,
+    pub status_data: Option<String>,
+    pub issue_date: String,
+    pub due_date: String,
+    pub discount_type: Option<String>,
+    pub discount_value: Option<i64>,
+    pub notes: Option<String>,
+    pub terms: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
+pub struct InvoiceLineRecord {
+    pub id: i64,
+    pub invoice_id: i64,
+    pub description: String,
+    pub quantity_hundredths: i64,
+    pub unit_rate_cents: i64,
+    pub taxable: bool,
+    pub sort_order: i64,
+}
+
+#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
+pub struct InvoiceTaxLineRecord {
+    pub id: i64,
+    pub invoice_id: i64,
+    pub label: String,
+    pub rate_bps: i64,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn insert_invoice(
+    pool: &DbPool,
+    invoice_number: &str,
+    contact_id: i64,
+    status_type: &str,
+    status_data: Option<&str>,
+    issue_date: &str,
+    due_date: &str,
+    discount_type: Option<&str>,
+    discount_value: Option<i64>,
+    notes: Option<&str>,
+    terms: Option<&str>,
+) -> Result<i64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"INSERT INTO invoices (invoice_number, contact_id, status_type, status_data,
+           issue_date, due_date, discount_type, discount_value, notes, terms)
+           VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"#,
+    )
+    .bind(invoice_number)
+    .bind(contact_id)
+    .bind(status_type)
+    .bind(status_data)
+    .bind(issue_date)
+    .bind(due_date)
+    .bind(discount_type)
+    .bind(discount_value)
+    .bind(notes)
+    .bind(terms)
+    .execute(pool)
+    .await?;
+
+    Ok(result.last_insert_rowid())
+}
+
+pub async fn update_invoice_status(
+    pool: &DbPool,
+    id: i64,
+    status_type: &str,
+    status_data: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE invoices SET status_type=?, status_data=?, updated_at=datetime('now') WHERE id=?",
+    )
+    .bind(status_type)
+    .bind(status_data)
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn get_all_invoices(pool: &DbPool) -> Result<Vec<InvoiceRecord>, sqlx::Error> {
+    sqlx::query_as::<_, InvoiceRecord>("SELECT * FROM invoices ORDER BY created_at DESC LIMIT 5000")
+        .fetch_all(pool)
+        .await
+}
+
+pub async fn get_invoice_by_id(
+    pool: &DbPool,
+    id: i64,
+) -> Result<Option<InvoiceRecord>, sqlx::Error> {
+    sqlx::query_as::<_, InvoiceRecord>("SELECT * FROM invoices WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+pub async fn get_invoices_by_status(
+    pool: &DbPool,
+    status_type: &str,
+) -> Result<Vec<InvoiceRecord>, sqlx::Error> {
+    sqlx::query_as::<_, InvoiceRecord>(
+        "SELECT * FROM invoices WHERE status_type = ? ORDER BY due_date",
+    )
+    .bind(status_type)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn insert_invoice_line(
+    pool: &DbPool,
+    invoice_id: i64,
+    description: &str,
+    quantity_hundredths: i64,
+    unit_rate_cents: i64,
+    taxable: bool,
+    sort_order: i64,
+) -> Result<i64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"INSERT INTO invoice_lines (invoice_id, description, quantity_hundredths,
+           unit_rate_cents, taxable, sort_order) VALUES (?, ?, ?, ?, ?, ?)"#,
+    )
+    .bind(invoice_id)
+    .bind(description)
+    .bind(quantity_hundredths)
+    .bind(unit_rate_cents)
+    .bind(taxable)
+    .bind(sort_order)
+    .execute(pool)
+    .await?;
+    Ok(result.last_insert_rowid())
+}
+
+pub async fn get_invoice_lines(
+    pool: &DbPool,
+    invoice_id: i64,
+) -> Result<Vec<InvoiceLineRecord>, sqlx::Error> {
+    sqlx::query_as::<_, InvoiceLineRecord>(
+        "SELECT * FROM invoice_lines WHERE invoice_id = ? ORDER BY sort_order",
+    )
+    .bind(invoice_id)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn insert_invoice_tax_line(
+    pool: &DbPool,
+    invoice_id: i64,
+    label: &str,
+    rate_bps: i64,
+) -> Result<i64, sqlx::Error> {
+    let result =
+        sqlx::query("INSERT INTO invoice_tax_lines (invoice_id, label, rate_bps) VALUES (?, ?, ?)")
+            .bind(invoice_id)
+            .bind(label)
+            .bind(rate_bps)
+            .execute(pool)
+            .await?;
+    Ok(result.last_insert_rowid())
+}
+
+pub async fn get_invoice_tax_lines(
+    pool: &DbPool,
+    invoice_id: i64,
+) -> Result<Vec<InvoiceTaxLineRecord>, sqlx::Error> {
+    sqlx::query_as::<_, InvoiceTaxLineRecord>(
+        "SELECT * FROM invoice_tax_lines WHERE invoice_id = ?",
+    )
+    .bind(invoice_id)
+    .fetch_all(pool)
+    .await
+}
+
+// ── Payment storage ──────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
+pub struct PaymentRecord {
+    pub id: i64,
+    pub invoice_id: i64,
+    pub amount_cents: i64,
+    pub date: String,
+    pub method: Option<String>,
+    pub transaction_id: Option<i64>,
+    pub created_at: String,
+    /// Overpayment held as customer credit, or (negative) credit spent.
+    pub credit_cents: i64,
+}
+
+pub async fn insert_payment(
+    pool: &DbPool,
+    invoice_id: i64,
+    amount_cents: i64,
+    date: &str,
+    method: Option<&str>,
+    transaction_id: Option<i64>,
+) -> Result<i64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"INSERT INTO payments (invoice_id, amount_cents, date, method, transaction_id)
+           VALUES (?, ?, ?, ?, ?)"#,
+    )
+    .bind(invoice_id)
+    .bind(amount_cents)
+    .bind(date)
+    .bind(method)
+    .bind(transaction_id)
+    .execute(pool)
+    .await?;
+    Ok(result.last_insert_rowid())
+}
+
+pub async fn get_payments_for_invoice(
+    pool: &DbPool,
+    invoice_id: i64,
+) -> Result<Vec<PaymentRecord>, sqlx::Error> {
+    sqlx::query_as::<_, PaymentRecord>("SELECT * FROM payments WHERE invoice_id = ? ORDER BY date")
+        .bind(invoice_id)
+        .fetch_all(pool)
+        .await
+}
+
+pub async fn get_ytd_payments_to_contact(
+    pool: &DbPool,
+    contact_id: i64,
+    year: u16,
+) -> Result<i64, sqlx::Error> {
+    let start = format!("{year}-01-01");
+    let end = format!("{year}-12-31");
+    let row = sqlx::query_as::<_, (i64,)>(
+        r#"SELECT COALESCE(SUM(p.amount_cents), 0)
+           FROM payments p
+           JOIN invoices i ON p.invoice_id = i.id
+           WHERE i.contact_id = ? AND p.date >= ? AND p.date <= ?"#,
+    )
+    .bind(contact_id)
+    .bind(&start)
+    .bind(&end)
+    .fetch_one(pool)
+    .await?;
+    Ok(row.0)
+}
+
+/// Get YTD payments for all contractors in a single query (avoids N+1).
+pub async fn get_contractor_ytd_payments(
+    pool: &DbPool,
+    year: u16,
+) -> Result<Vec<(i64, String, i64)>, sqlx::Error> {
+    let start = format!("{year}-01-01");
+    let end = format!("{year}-12-31");
+    sqlx::query_as::<_, (i64, String, i64)>(
+        r#"SELECT c.id, c.name, COALESCE(SUM(p.amount_cents), 0) as ytd
+           FROM contacts c
+           LEFT JOIN invoices i ON i.contact_id = c.id
+           LEFT JOIN payments p ON p.invoice_id = i.id AND p.date >= ? AND p.date <= ?
+           WHERE c.is_contractor = 1
+           GROUP BY c.id, c.name
+           ORDER BY c.name"#,
+    )
+    .bind(&start)
+    .bind(&end)
+    .fetch_all(pool)
+    .await
+}
+
+/// Invoice aging report: returns invoices bucketed by days overdue.
+pub async fn get_invoice_aging(pool: &DbPool) -> Result<Vec<InvoiceRecord>, sqlx::Error> {
+    sqlx::query_as::<_, InvoiceRecord>(
+        r#"SELECT * FROM invoices
+           WHERE status_type IN ('Sent', 'Viewed', 'PartiallyPaid')
+           ORDER BY due_date"#,
+    )
+    .fetch_all(pool)
+    .await
+}
+
+// ── Audit log ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
+pub struct AuditLogRecord {
+    pub id: i64,
+    pub timestamp: String,
+    pub tool_name: String,
+    pub input_hash: Option<String>,
+    pub outcome: String,
+    pub details: Option<String>,
+}
+
+pub async fn insert_audit_log(
+    pool: &DbPool,
+    tool_name: &str,
+    input_hash: Option<&str>,
+    outcome: &str,
+    details: Option<&str>,
+) -> Result<i64, sqlx::Error> {
+    let result = sqlx::query(
+        "INSERT INTO audit_log (tool_name, input_hash, outcome, details) VALUES (?, ?, ?, ?)",
+    )
+    .bind(tool_name)
+    .bind(input_hash)
+    .bind(outcome)
+    .bind(details)
+    .execute(pool)
+    .await?;
+    Ok(result.last_insert_rowid())
+}
+
+pub async fn get_audit_log(pool: &DbPool, limit: i64) -> Result<Vec<AuditLogRecord>, sqlx::Error> {
+    sqlx::query_as::<_, AuditLogRecord>("SELECT * FROM audit_log ORDER BY timestamp DESC LIMIT ?")
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+}
+
+// ── Settings storage ─────────────────────────────────────────────────────────
+
+pub async fn get_setting(pool: &DbPool, key: &str) -> Result<Option<String>, sqlx::Error> {
+    let row = sqlx::query_as::<_, (String,)>("SELECT value FROM settings WHERE key = ?")
+        .bind(key)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.map(|r| r.0))
+}
+
+pub async fn set_setting(pool: &DbPool, key: &str, value: &str) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO settings (key, value) VALUES (?, ?) ON CONFLICT(key) DO UPDATE SET value=excluded.value",
+    )
+    .bind(key)
+    .bind(value)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+// ── Preferences ──────────────────────────────────────────────────────────────
+
+pub const DEFAULT_CURRENCY_SETTING: &str = "default_currency";
+pub const FISCAL_YEAR_START_SETTING: &str = "fiscal_year_start_month";
+pub const OCR_BACKEND_SETTING: &str = "ocr_backend";
+pub const REVIEW_THRESHOLD_SETTING: &str = "review_confidence_threshold";
+pub const DATE_FORMAT_SETTING: &str = "date_format";
+pub const NUMBER_LOCALE_SETTING: &str = "number_locale";
+
+/// Receipts read with less confidence than this are flagged for review.
+pub const DEFAULT_REVIEW_THRESHOLD: f64 = 0.7;
+
+/// App-wide preferences. Each is its own row in `settings`, so they can be
+/// changed one at a time with [`set_setting`] after [`Preferences::set`] has
+/// checked the value.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Preferences {
+    /// ISO 4217 code, e.g. `USD`.
+    pub default_currency: String,
+    /// Month the fiscal year starts in, 1–12.
+    pub fiscal_year_start_month: u32,
+    /// OCR backend name, e.g. `tesseract`. Checked against the backends the
+    /// app was built with when it is set.
+    pub ocr_backend: String,
+    /// Extraction confidence, 0.0–1.0, below which receipts need review.
+    pub review_threshold: f64,
+    /// strftime-style format dates are shown in, e.g. `%m/%d/%Y`.
+    pub date_format: String,
+    /// Locale amounts are shown in, e.g. `de-DE` for `1.234,56 €`.
+    pub number_locale: String,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Preferences {
+            default_currency: "USD".to_string(),
+            fiscal_year_start_month: 1,
+            ocr_backend: "tesseract".to_string(),
+            review_threshold: DEFAULT_REVIEW_THRESHOLD,
+            date_format: "%Y-%m-%d".to_string(),
+            number_locale: Locale::default().to_string(),
+        }
+    }
+}
+
+impl Preferences {
+    pub const KEYS: [&'static str; 6] = [
+        DEFAULT_CURRENCY_SETTING,
+        FISCAL_YEAR_START_SETTING,
+        OCR_BACKEND_SETTING,
+        REVIEW_THRESHOLD_SETTING,
+        DATE_FORMAT_SETTING,
+        NUMBER_LOCALE_SETTING,
+    ];
+
+    /// Set one preference from its stored string form. Returns false if
+    /// `key` isn't a preference, and an error if `value` isn't valid for it.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<bool, String> {
+        let value = value.trim();
+        match key {
+            DEFAULT_CURRENCY_SETTING => {
+                if value.len() != 3 || !value.chars().all(|c| c.is_ascii_alphabetic()) {
+                    return Err(format!("Invalid currency code: {value}"));
+                }
+                self.default_currency = value.to_ascii_uppercase();
+            }
+            FISCAL_YEAR_START_SETTING => {
+                self.fiscal_year_start_month = value
+                    .parse()
+                    .ok()
+                    .filter(|m| (1..=12).contains(m))
+                    .ok_or_else(|| format!("Fiscal year start must be a month 1-12: {value}"))?;
+            }
+            OCR_BACKEND_SETTING => {
+                if value.is_empty() {
+                    return Err("OCR backend is required".to_string());
+                }
+                self.ocr_backend = value.to_ascii_lowercase();
+            }
+            REVIEW_THRESHOLD_SETTING => {
+                self.review_threshold = value
+                    .parse()
+                    .ok()
+                    .filter(|t| (0.0..=1.0).contains(t))
+                    .ok_or_else(|| format!("Review threshold must be between 0 and 1: {value}"))?;
+            }
+            DATE_FORMAT_SETTING => {
+                let valid = !value.is_empty()
+                    && chrono::format::StrftimeItems::new(value)
+                        .all(|item| !matches!(item, chrono::format::Item::Error));
+                if !valid {
+                    return Err(format!("Invalid date format: {value}"));
+                }
+                self.date_format = value.to_string();
+            }
+            NUMBER_LOCALE_SETTING => {
+                let locale =
+                    Locale::parse(value).ok_or_else(|| format!("Invalid locale: {value}"))?;
+                self.number_locale = locale.to_string();
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// How amounts are shown: the number locale with the default currency.
+    pub fn money_format(&self) -> MoneyFormat {
+        let locale = Locale::parse(&self.number_locale).unwrap_or_default();
+        MoneyFormat::new(locale, &self.default_currency)
+    }
+}
+
+/// The saved preferences, with defaults for any not set. Values that no
+/// longer parse fall back to their default.
+pub async fn get_preferences(pool: &DbPool) -> Result<Preferences, sqlx::Error> {
+    let mut prefs = Preferences::default();
+    for key in Preferences::KEYS {
+        if let Some(value) = get_setting(pool, key).await? {
+            let _ = prefs.set(key, &value);
+        }
+    }
+    Ok(prefs)
+}
+
+/// Setting holding [`LocalApiSettings`], as JSON.
+pub const LOCAL_API_SETTING: &str = "local_api";
+
+/// Port the local HTTP API listens on unless another is chosen.
+pub const DEFAULT_LOCAL_API_PORT: u16 = 8062;
+
+/// The localhost HTTP API for scripts and automations. Off unless the user
+/// turns it on.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct LocalApiSettings {
+    pub enabled: bool,
+    pub port: u16,
+    /// Bearer token every request must carry. Generated when the API is
+    /// first turned on.
+    pub token: Option<String>,
+}
+
+impl Default for LocalApiSettings {
+    fn default() -> Self {
+        LocalApiSettings {
+            enabled: false,
+            port: DEFAULT_LOCAL_API_PORT,
+            token: None,
+        }
+    }
+}
+
+pub async fn get_local_api_settings(pool: &DbPool) -> Result<LocalApiSettings, sqlx::Error> {
+    match get_setting(pool, LOCAL_API_SETTING).await? {
+        Some(json) if !json.trim().is_empty() => {
+            serde_json::from_str(&json).map_err(|e| sqlx::Error::Decode(Box::new(e)))
+        }
+        _ => Ok(LocalApiSettings::default()),
+    }
+}
+
+pub async fn set_local_api_settings(
+    pool: &DbPool,
+    settings: &LocalApiSettings,
+) -> Result<(), sqlx::Error> {
+    let json = serde_json::to_string(settings).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    set_setting(pool, LOCAL_API_SETTING, &json).await
+}
+
+/// Setting holding the payment method to account mapping, as JSON.
+pub const PAYMENT_ACCOUNTS_SETTING: &str = "payment_method_accounts";
+
+/// The payment method to account mapping, or the built-in one if the user
+/// hasn't saved their own.
+pub async fn get_payment_account_map(pool: &DbPool) -> Result<PaymentAccountMap, sqlx::Error> {
+    match get_setting(pool, PAYMENT_ACCOUNTS_SETTING).await? {
+        Some(json) if !json.trim().is_empty() => {
+            serde_json::from_str(&json).map_err(|e| sqlx::Error::Decode(Box::new(e)))
+        }
+        _ => Ok(PaymentAccountMap::default()),
+    }
+}
+
+pub async fn set_payment_account_map(
+    pool: &DbPool,
+    map: &PaymentAccountMap,
+) -> Result<(), sqlx::Error> {
+    let json = serde_json::to_string(map).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    set_setting(pool, PAYMENT_ACCOUNTS_SETTING, &json).await
+}
+
+/// An in-memory database with the schema and default accounts, enforcing
+/// foreign keys as [`create_db`] does.
+#[cfg(test)]
+pub(crate) async fn test_pool() -> DbPool {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    sqlx::query("PRAGMA foreign_keys = ON")
+        .execute(&pool)
+        .await
+        .unwrap();
+    crate::migrate::run_migrations(&pool).await.unwrap();
+    seed_default_accounts(&pool).await.unwrap();
+    pool
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Helper: insert a contact and return its id.
+    async fn insert_test_contact(pool: &DbPool, name: &str, is_contractor: bool) -> i64 {
+        insert_contact(
+            pool,
+            name,
+            Some("test@example.com"),
+            Some("555-1234"),
+            Some("123 Main St"),
+            "vendor",
             is_contractor,
             Some("12-3456789"),
             Some("test notes"),
         )
         .await
-        .unwrap()
+        .unwrap()
+    }
+
+    // Helper: insert an invoice for a contact and return its id.
+    async fn insert_test_invoice(pool: &DbPool, contact_id: i64, number: &str) -> i64 {
+        insert_invoice(
+            pool,
+            number,
+            contact_id,
+            "Draft",
+            None,
+            "2026-01-15",
+            "2026-02-15",
+            None,
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap()
+    }
+
+    // ── 1. Accounts ──────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_get_all_accounts_returns_default_accounts() {
+        let pool = test_pool().await;
+        let accounts = get_all_accounts(&pool).await.unwrap();
+        assert!(!accounts.is_empty(), "should have default accounts");
+        // All should be non-archived
+        for a in &accounts {
+            assert!(!a.is_archived);
+        }
+        // Should be sorted by code
+        let codes: Vec<&str> = accounts.iter().map(|a| a.code.as_str()).collect();
+        let mut sorted = codes.clone();
+        sorted.sort();
+        assert_eq!(codes, sorted);
+    }
+
+    #[tokio::test]
+    async fn test_get_account_by_code_found_and_not_found() {
+        let pool = test_pool().await;
+        // Pick first default account code
+        let accounts = get_all_accounts(&pool).await.unwrap();
+        let first = &accounts[0];
+        let found = get_account_by_code(&pool, &first.code).await.unwrap();
+        assert!(found.is_some());
+        assert_eq!(found.unwrap().code, first.code);
+
+        let missing = get_account_by_code(&pool, "NONEXISTENT-999").await.unwrap();
+        assert!(missing.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_get_all_accounts_excludes_archived() {
+        let pool = test_pool().await;
+        // Archive one account directly
+        let accounts = get_all_accounts(&pool).await.unwrap();
+        let first_id = accounts[0].id.unwrap().0;
+        sqlx::query("UPDATE accounts SET is_archived = 1 WHERE id = ?")
+            .bind(first_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let after = get_all_accounts(&pool).await.unwrap();
+        assert_eq!(after.len(), accounts.len() - 1);
+        assert!(after.iter().all(|a| a.id.unwrap().0 != first_id));
+    }
+
+    // ── 2. Contacts CRUD ─────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_contact_crud() {
+        let pool = test_pool().await;
+
+        // Insert
+        let id = insert_test_contact(&pool, "Alice Corp", false).await;
+        assert!(id > 0);
+
+        // Get by id
+        let c = get_contact_by_id(&pool, id).await.unwrap().unwrap();
+        assert_eq!(c.name, "Alice Corp");
+        assert_eq!(c.email.as_deref(), Some("test@example.com"));
+        assert!(!c.is_contractor);
+
+        // Update
+        update_contact(
+            &pool,
+            id,
+            "Alice Corp Updated",
+            Some("new@example.com"),
+            None,
+            None,
+            "customer",
+            true,
+            None,
+            Some("updated notes"),
+        )
+        .await
+        .unwrap();
+        let updated = get_contact_by_id(&pool, id).await.unwrap().unwrap();
+        assert_eq!(updated.name, "Alice Corp Updated");
+        assert_eq!(updated.email.as_deref(), Some("new@example.com"));
+        assert!(updated.is_contractor);
+
+        // Get all
+        let all = get_all_contacts(&pool).await.unwrap();
+        assert_eq!(all.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_contact_details_and_deletion() {
+        let pool = test_pool().await;
+        let id = insert_test_contact(&pool, "Amazon", false).await;
+        let supplies: i64 = sqlx::query_scalar("SELECT id FROM accounts WHERE code = '5100'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+        set_contact_defaults(&pool, id, Some(supplies), Some(30))
+            .await
+            .unwrap();
+        let c = get_contact_by_id(&pool, id).await.unwrap().unwrap();
+        assert_eq!(c.default_account_id, Some(supplies));
+        assert_eq!(c.payment_terms_days, Some(30));
+
+        // Deletable until something refers to it; archiving always works.
+        let unused = insert_test_contact(&pool, "Unused", false).await;
+        assert!(delete_contact(&pool, unused).await.unwrap());
+        insert_test_invoice(&pool, id, "INV-1").await;
+        assert!(!delete_contact(&pool, id).await.unwrap());
+        assert!(set_contact_archived(&pool, id, true).await.unwrap());
+        assert!(
+            get_contact_by_id(&pool, id)
+                .await
+                .unwrap()
+                .unwrap()
+                .is_archived
+        );
+    }
+
+    #[tokio::test]
+    async fn test_find_contact_by_payee() {
+        let pool = test_pool().await;
+        let amazon = insert_test_contact(&pool, "Amazon", false).await;
+        let aws = insert_test_contact(&pool, "Amazon Web Services", false).await;
+        set_contact_aliases(&pool, amazon, &["AMZN  Mktp".into(), " ".into()])
+            .await
+            .unwrap();
+        set_contact_aliases(&pool, aws, &["amzn mktp aws".into()])
+            .await
+            .unwrap();
+        assert_eq!(
+            get_contact_aliases(&pool, amazon).await.unwrap(),
+            ["amzn mktp"]
+        );
+        assert_eq!(
+            get_alias_contact(&pool, "AMZN MKTP").await.unwrap(),
+            Some(amazon)
+        );
+
+        let find = |payee: &'static str| {
+            let pool = pool.clone();
+            async move {
+                find_contact_by_payee(&pool, payee)
+                    .await
+                    .unwrap()
+                    .map(|c| c.id)
+            }
+        };
+        assert_eq!(find("amazon").await, Some(amazon));
+        assert_eq!(find("AMZN MKTP US*2K4").await, Some(amazon));
+        assert_eq!(find("AMZN MKTP AWS 8812").await, Some(aws));
+        assert_eq!(find("Staples").await, None);
+
+        set_contact_archived(&pool, amazon, true).await.unwrap();
+        assert_eq!(find("amazon").await, None);
+    }
+
+    #[tokio::test]
+    async fn test_get_contractors() {
+        let pool = test_pool().await;
+        insert_test_contact(&pool, "Contractor A", true).await;
+        insert_test_contact(&pool, "Non-contractor B", false).await;
+        insert_test_contact(&pool, "Contractor C", true).await;
+
+        let contractors = get_contractors(&pool).await.unwrap();
+        assert_eq!(contractors.len(), 2);
+        // Should be sorted by name
+        assert_eq!(contractors[0].name, "Contractor A");
+        assert_eq!(contractors[1].name, "Contractor C");
+    }
+
+    // ── 3. Invoice lifecycle ─────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_invoice_lifecycle() {
+        let pool = test_pool().await;
+        let contact_id = insert_test_contact(&pool, "Client X", false).await;
+
+        // Insert invoice
+        let inv_id = insert_test_invoice(&pool, contact_id, "INV-001").await;
+        assert!(inv_id > 0);
+
+        // Get by id
+        let inv = get_invoice_by_id(&pool, inv_id).await.unwrap().unwrap();
+        assert_eq!(inv.invoice_number, "INV-001");
+        assert_eq!(inv.status_type, "Draft");
+
+        // Update status
+        update_invoice_status(&pool, inv_id, "Sent", Some("2026-01-16"))
+            .await
+            .unwrap();
+        let updated = get_invoice_by_id(&pool, inv_id).await.unwrap().unwrap();
+        assert_eq!(updated.status_type, "Sent");
+        assert_eq!(updated.status_data.as_deref(), Some("2026-01-16"));
+
+        // Get all invoices
+        let all = get_all_invoices(&pool).await.unwrap();
+        assert_eq!(all.len(), 1);
+
+        // Get by status
+        let sent = get_invoices_by_status(&pool, "Sent").await.unwrap();
+        assert_eq!(sent.len(), 1);
+        let drafts = get_invoices_by_status(&pool, "Draft").await.unwrap();
+        assert_eq!(drafts.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_invoice_aging() {
+        let pool = test_pool().await;
+        let contact_id = insert_test_contact(&pool, "Client", false).await;
+
+        // Draft invoice should not appear in aging
+        insert_test_invoice(&pool, contact_id, "INV-D").await;
+
+        // Sent invoice should appear
+        let sent_id = insert_invoice(
+            &pool,
+            "INV-S",
+            contact_id,
+            "Sent",
+            None,
+            "2026-01-01",
+            "2026-01-15",
+            None,
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let aging = get_invoice_aging(&pool).await.unwrap();
+        assert_eq!(aging.len(), 1);
+        assert_eq!(aging[0].id, sent_id);
+    }
+
+    // ── 4. Invoice lines ─────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_invoice_lines() {
+        let pool = test_pool().await;
+        let contact_id = insert_test_contact(&pool, "Client", false).await;
+        let inv_id = insert_test_invoice(&pool, contact_id, "INV-001").await;
+
+        // Insert lines
+        let l1 = insert_invoice_line(&pool, inv_id, "Web dev", 10000, 15000, true, 1)
+            .await
+            .unwrap();
+        let l2 = insert_invoice_line(&pool, inv_id, "Hosting", 100, 5000, false, 2)
+            .await
+            .unwrap();
+        assert!(l1 > 0);
+        assert!(l2 > l1);
+
+        // Get lines
+        let lines = get_invoice_lines(&pool, inv_id).await.unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].description, "Web dev");
+        assert_eq!(lines[0].quantity_hundredths, 10000);
+        assert_eq!(lines[0].unit_rate_cents, 15000);
+        assert!(lines[0].taxable);
+        assert_eq!(lines[1].description, "Hosting");
+        assert!(!lines[1].taxable);
+    }
+
+    // ── 5. Payments ──────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_payments_and_ytd() {
+        let pool = test_pool().await;
+        let contact_id = insert_test_contact(&pool, "Client", false).await;
+        let inv_id = insert_test_invoice(&pool, contact_id, "INV-001").await;
+
+        // Insert payments
+        let p1 = insert_payment(&pool, inv_id, 50000, "2026-03-01", Some("check"), None)
+            .await
+            .unwrap();
+        let p2 = insert_payment(&pool, inv_id, 25000, "2026-04-01", Some("wire"), None)
+            .await
+            .unwrap();
+        assert!(p1 > 0);
+        assert!(p2 > p1);
+
+        // Get payments for invoice
+        let payments = get_payments_for_invoice(&pool, inv_id).await.unwrap();
+        assert_eq!(payments.len(), 2);
+        assert_eq!(payments[0].amount_cents, 50000);
+        assert_eq!(payments[1].amount_cents, 25000);
+
+        // YTD payments to contact
+        let ytd = get_ytd_payments_to_contact(&pool, contact_id, 2026)
+            .await
+            .unwrap();
+        assert_eq!(ytd, 75000);
+
+        // Different year returns 0
+        let ytd_other = get_ytd_payments_to_contact(&pool, contact_id, 2025)
+            .await
+            .unwrap();
+        assert_eq!(ytd_other, 0);
+    }
+
+    #[tokio::test]
+    async fn test_contractor_ytd_payments() {
+        let pool = test_pool().await;
+        let c1 = insert_test_contact(&pool, "Contractor A", true).await;
+        let c2 = insert_test_contact(&pool, "Contractor B", true).await;
+        let _non = insert_test_contact(&pool, "Regular Vendor", false).await;
+
+        let inv1 = insert_test_invoice(&pool, c1, "INV-C1").await;
+        let inv2 = insert_test_invoice(&pool, c2, "INV-C2").await;
+
+        insert_payment(&pool, inv1, 100000, "2026-06-01", None, None)
+            .await
+            .unwrap();
+        insert_payment(&pool, inv2, 200000, "2026-07-01", None, None)
+            .await
+            .unwrap();
+
+        let results = get_contractor_ytd_payments(&pool, 2026).await.unwrap();
+        assert_eq!(results.len(), 2);
+        // Sorted by name
+        assert_eq!(results[0].1, "Contractor A");
+        assert_eq!(results[0].2, 100000);
+        assert_eq!(results[1].1, "Contractor B");
+        assert_eq!(results[1].2, 200000);
+    }
+
+    // ── 6. Receipts ──────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_receipt_lifecycle() {
+        let pool = test_pool().await;
+
+        // Insert receipt
+        let id = insert_receipt(
+            &pool,
+            "abc123hash",
+            "jpg",
+            "/receipts/abc123.jpg",
+            Some("ACME Store $42.50"),
+            Some("ACME Store"),
+            Some("2026-03-01"),
+            Some(4250),
+            Some(3900),
+            Some(350),
+            Some("credit_card"),
+            Some("INV-1042"),
+            0.85,
+        )
+        .await
+        .unwrap()
+        .id();
+        assert!(id > 0);
+
+        // Get by id
+        let r = get_receipt_by_id(&pool, id).await.unwrap().unwrap();
+        assert_eq!(r.file_hash, "abc123hash");
+        assert_eq!(r.vendor.as_deref(), Some("ACME Store"));
+        assert_eq!(r.reference.as_deref(), Some("INV-1042"));
+        assert_eq!(r.total_cents, Some(4250));
+        assert_eq!(r.status, "pending_review");
+        assert_eq!(r.quality_score, None);
+
+        // Photo quality
+        set_receipt_quality(&pool, id, 0.2, &["blurry", "too_dark"])
+            .await
+            .unwrap();
+        let r = get_receipt_by_id(&pool, id).await.unwrap().unwrap();
+        assert_eq!(r.quality_score, Some(0.2));
+        assert_eq!(r.quality_issues.as_deref(), Some("blurry,too_dark"));
+
+        // Pending review
+        let pending = get_receipts_pending_review(&pool).await.unwrap();
+        assert_eq!(pending.len(), 1);
+
+        // Update status
+        update_receipt_status(&pool, id, ReceiptStatus::Approved)
+            .await
+            .unwrap();
+        let updated = get_receipt_by_id(&pool, id).await.unwrap().unwrap();
+        assert_eq!(updated.status, "approved");
+        assert!(updated.reviewed_at.is_some());
+
+        // No longer pending
+        let pending2 = get_receipts_pending_review(&pool).await.unwrap();
+        assert_eq!(pending2.len(), 0);
+
+        // Line items
+        insert_receipt_line_item(&pool, id, "Paper", Some(2500), Some(1.0))
+            .await
+            .unwrap();
+        insert_receipt_line_item(&pool, id, "Toner", Some(1400), None)
+            .await
+            .unwrap();
+        let items = get_receipt_line_items(&pool, id).await.unwrap();
+        assert_eq!(
+            items
+                .iter()
+                .map(|i| i.description.as_str())
+                .collect::<Vec<_>>(),
+            ["Paper", "Toner"]
+        );
+        assert_eq!(items[1].amount_cents, Some(1400));
+    }
+
+    #[tokio::test]
+    async fn test_query_receipts() {
+        let pool = test_pool().await;
+        let rows = [
+            ("q_a", "Shell", "2026-01-10", 4500, 0.95),
+            ("q_b", "Shell Oil", "2026-02-10", 6000, 0.8),
+            ("q_c", "Starbucks", "2026-02-11", 550, 0.5),
+            ("q_d", "Office Depot", "2026-03-01", 12000, 0.92),
+        ];
+        let mut ids = Vec::new();
+        for (hash, vendor, date, total, confidence) in rows {
+            let id = insert_receipt(
+                &pool,
+                hash,
+                "jpg",
+                "/r/q.jpg",
+                None,
+                Some(vendor),
+                Some(date),
+                Some(total),
+                None,
+                None,
+                None,
+                None,
+                confidence,
+            )
+            .await
+            .unwrap()
+            .id();
+            ids.push(id);
+        }
+        let tx_id = sqlx::query("INSERT INTO transactions (date, description, balanced_total_cents) VALUES ('2026-03-01', 'Supplies', 12000)")
+            .execute(&pool)
+            .await
+            .unwrap()
+            .last_insert_rowid();
+        link_receipt_to_transaction(&pool, ids[3], tx_id)
+            .await
+            .unwrap();
+
+        let found = |q: ReceiptQuery| {
+            let pool = pool.clone();
+            async move {
+                let page = query_receipts(&pool, &q).await.unwrap();
+                (
+                    page.receipts.iter().map(|r| r.id).collect::<Vec<_>>(),
+                    page.total,
+                )
+            }
+        };
+
+        // Newest first.
+        let (all, total) = found(ReceiptQuery::default()).await;
+        assert_eq!(all, [ids[3], ids[2], ids[1], ids[0]]);
+        assert_eq!(total, 4);
+
+        let (shell, _) = found(ReceiptQuery {
+            vendor: Some("shell".into()),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(shell, [ids[1], ids[0]]);
+
+        let (feb, _) = found(ReceiptQuery {
+            date_from: Some("2026-02-01".into()),
+            date_to: Some("2026-02-28".into()),
+            min_total_cents: Some(1000),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(feb, [ids[1]]);
+
+        let (linked, _) = found(ReceiptQuery {
+            linked: Some(true),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(linked, [ids[3]]);
+        let (unlinked_high, _) = found(ReceiptQuery {
+            linked: Some(false),
+            confidence: Some(ConfidenceBand::High),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(unlinked_high, [ids[0]]);
+
+        let (pending, _) = found(ReceiptQuery {
+            status: Some("pending_review".into()),
+            confidence: Some(ConfidenceBand::Medium),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(pending, [ids[1]]);
+
+        // Second page of two.
+        let (page, total) = found(ReceiptQuery {
+            limit: Some(2),
+            offset: Some(2),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(page, [ids[1], ids[0]]);
+        assert_eq!(total, 4);
+    }
+
+    #[tokio::test]
+    async fn test_bulk_receipt_review() {
+        let pool = test_pool().await;
+        let mut ids = Vec::new();
+        for hash in ["bulk_a", "bulk_b", "bulk_c"] {
+            let id = insert_receipt(
+                &pool,
+                hash,
+                "jpg",
+                "/r/bulk.jpg",
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                0.95,
+            )
+            .await
+            .unwrap()
+            .id();
+            ids.push(id);
+        }
+        update_receipt_status(&pool, ids[2], ReceiptStatus::Rejected)
+            .await
+            .unwrap();
+
+        let approved = set_pending_receipts_status(
+            &pool,
+            &[ids[0], ids[1], ids[2], 9999],
+            ReceiptStatus::Approved,
+        )
+        .await
+        .unwrap();
+        assert_eq!(approved, [ids[0], ids[1]]);
+        let r = get_receipt_by_id(&pool, ids[2]).await.unwrap().unwrap();
+        assert_eq!(r.status, "rejected");
+        assert!(get_receipts_pending_review(&pool).await.unwrap().is_empty());
+
+        // Already reviewed: nothing left to reject.
+        assert!(
+            set_pending_receipts_status(&pool, &ids, ReceiptStatus::Rejected)
+                .await
+                .unwrap()
+                .is_empty()
+        );
     }
 
-    // Helper: insert an invoice for a contact and return its id.
-    async fn insert_test_invoice(pool: &DbPool, contact_id: i64, number: &str) -> i64 {
-        insert_invoice(
-            pool,
-            number,
-            contact_id,
-            "Draft",
+    #[tokio::test]
+    async fn test_receipt_duplicate_detection() {
+        let pool = test_pool().await;
+        let id1 = insert_receipt(
+            &pool,
+            "dup_hash",
+            "png",
+            "/receipts/dup.png",
+            None,
             None,
-            "2026-01-15",
-            "2026-02-15",
             None,
             None,
             None,
             None,
+            None,
+            None,
+            0.0,
         )
         .await
         .unwrap()
-    }
+        .id();
 
-    // ── 1. Accounts ──────────────────────────────────────────────────────────
-
-    #[tokio::test]
-    async fn test_get_all_accounts_returns_default_accounts() {
-        let pool = test_pool().await;
-        let accounts = get_all_accounts(&pool).await.unwrap();
-        assert!(!accounts.is_empty(), "should have default accounts");
-        // All should be non-archived
-        for a in &accounts {
-            assert!(!a.is_archived);
-        }
-        // Should be sorted by code
-        let codes: Vec<&str> = accounts.iter().map(|a| a.code.as_str()).collect();
-        let mut sorted = codes.clone();
-        sorted.sort();
-        assert_eq!(codes, sorted);
-    }
+        // Insert same hash again - nothing new is stored
+        let second = insert_receipt(
+            &pool,
+            "dup_hash",
+            "png",
+            "/receipts/dup2.png",
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            0.0,
+        )
+        .await
+        .unwrap();
+        assert_eq!(second, ReceiptInsert::AlreadyStored { id: id1 });
+        assert!(!second.is_new());
 
-    #[tokio::test]
-    async fn test_get_account_by_code_found_and_not_found() {
-        let pool = test_pool().await;
-        // Pick first default account code
-        let accounts = get_all_accounts(&pool).await.unwrap();
-        let first = &accounts[0];
-        let found = get_account_by_code(&pool, &first.code).await.unwrap();
-        assert!(found.is_some());
-        assert_eq!(found.unwrap().code, first.code);
+        // check_receipt_duplicate
+        let dup = check_receipt_duplicate(&pool, "dup_hash").await.unwrap();
+        assert_eq!(dup, Some(id1));
 
-        let missing = get_account_by_code(&pool, "NONEXISTENT-999").await.unwrap();
-        assert!(missing.is_none());
+        let no_dup = check_receipt_duplicate(&pool, "other_hash").await.unwrap();
+        assert!(no_dup.is_none());
     }
 
     #[tokio::test]
-    async fn test_get_all_accounts_excludes_archived() {
+    async fn test_second_copy_of_receipt_marked_duplicate() {
         let pool = test_pool().await;
-        // Archive one account directly
-        let accounts = get_all_accounts(&pool).await.unwrap();
-        let first_id = accounts[0].id.unwrap().0;
-        sqlx::query("UPDATE accounts SET is_archived = 1 WHERE id = ?")
-            .bind(first_id)
-            .execute(&pool)
+        let mut outcomes = Vec::new();
+        for (hash, vendor) in [("copy_a", "Home Depot"), ("copy_b", " home depot ")] {
+            let outcome = insert_receipt(
+                &pool,
+                hash,
+                "jpg",
+                "/r/copy.jpg",
+                None,
+                Some(vendor),
+                Some("2026-04-02"),
+                Some(8999),
+                None,
+                None,
+                None,
+                None,
+                0.8,
+            )
             .await
             .unwrap();
+            outcomes.push(outcome);
+        }
+        let original = outcomes[0].id();
+        assert_eq!(outcomes[0], ReceiptInsert::Inserted { id: original });
+        let copy = outcomes[1].id();
+        assert_eq!(
+            outcomes[1],
+            ReceiptInsert::PossibleDuplicate {
+                id: copy,
+                duplicate_of: original
+            }
+        );
 
-        let after = get_all_accounts(&pool).await.unwrap();
-        assert_eq!(after.len(), accounts.len() - 1);
-        assert!(after.iter().all(|a| a.id.unwrap().0 != first_id));
+        let duplicates = get_duplicate_receipts(&pool).await.unwrap();
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].status, "duplicate");
+        assert_eq!(duplicates[0].duplicate_of, Some(original));
+        // Only the original waits for review.
+        let pending = get_receipts_pending_review(&pool).await.unwrap();
+        assert_eq!(pending.iter().map(|r| r.id).collect::<Vec<_>>(), [original]);
+
+        // The user says it's a separate purchase after all.
+        assert!(clear_receipt_duplicate(&pool, copy).await.unwrap());
+        assert!(!clear_receipt_duplicate(&pool, copy).await.unwrap());
+        let cleared = get_receipt_by_id(&pool, copy).await.unwrap().unwrap();
+        assert_eq!(cleared.status, "pending_review");
+        assert_eq!(cleared.duplicate_of, None);
+        assert!(get_duplicate_receipts(&pool).await.unwrap().is_empty());
     }
 
-    // ── 2. Contacts CRUD ─────────────────────────────────────────────────────
-
     #[tokio::test]
-    async fn test_contact_crud() {
+    async fn test_receipts_from_one_photo_are_not_duplicates() {
         let pool = test_pool().await;
+        // Two identical coffees, both stubs in one photo.
+        let mut ids = Vec::new();
+        let mut marked = Vec::new();
+        for hash in ["stub_a", "stub_b"] {
+            let id = insert_receipt(
+                &pool,
+                hash,
+                "jpg",
+                &format!("{hash}.jpg"),
+                None,
+                Some("Corner Cafe"),
+                Some("2026-05-11"),
+                Some(450),
+                None,
+                None,
+                None,
+                None,
+                0.8,
+            )
+            .await
+            .unwrap()
+            .id();
+            marked.push(
+                set_receipt_source(&pool, id, "photo_hash", "ph/photo_hash.png")
+                    .await
+                    .unwrap(),
+            );
+            ids.push(id);
+        }
+        assert_eq!(marked, [None, None]);
+        let second = get_receipt_by_id(&pool, ids[1]).await.unwrap().unwrap();
+        assert_eq!(second.status, "pending_review");
+        assert_eq!(second.duplicate_of, None);
+        assert_eq!(second.source_path.as_deref(), Some("ph/photo_hash.png"));
+
+        let siblings = get_receipts_from_source(&pool, "photo_hash").await.unwrap();
+        assert_eq!(siblings.iter().map(|r| r.id).collect::<Vec<_>>(), ids);
+        // The photo itself counts as ingested.
+        assert_eq!(
+            check_receipt_duplicate(&pool, "photo_hash").await.unwrap(),
+            Some(ids[0])
+        );
 
-        // Insert
-        let id = insert_test_contact(&pool, "Alice Corp", false).await;
-        assert!(id > 0);
-
-        // Get by id
-        let c = get_contact_by_id(&pool, id).await.unwrap().unwrap();
-        assert_eq!(c.name, "Alice Corp");
-        assert_eq!(c.email.as_deref(), Some("test@example.com"));
-        assert!(!c.is_contractor);
-
-        // Update
-        update_contact(
+        // A copy of the same coffee from elsewhere still is a duplicate.
+        let copy = insert_receipt(
             &pool,
-            id,
-            "Alice Corp Updated",
-            Some("new@example.com"),
+            "emailed",
+            "pdf",
+            "emailed.pdf",
             None,
+            Some("Corner Cafe"),
+            Some("2026-05-11"),
+            Some(450),
             None,
-            "customer",
-            true,
             None,
-            Some("updated notes"),
+            None,
+            None,
+            0.8,
         )
         .await
         .unwrap();
-        let updated = get_contact_by_id(&pool, id).await.unwrap().unwrap();
-        assert_eq!(updated.name, "Alice Corp Updated");
-        assert_eq!(updated.email.as_deref(), Some("new@example.com"));
-        assert!(updated.is_contractor);
-
-        // Get all
-        let all = get_all_contacts(&pool).await.unwrap();
-        assert_eq!(all.len(), 1);
+        assert_eq!(
+            copy,
+            ReceiptInsert::PossibleDuplicate {
+                id: copy.id(),
+                duplicate_of: ids[0]
+            }
+        );
     }
 
     #[tokio::test]
-    async fn test_get_contractors() {
+    async fn test_link_receipt_to_transaction() {
         let pool = test_pool().await;
-        insert_test_contact(&pool, "Contractor A", true).await;
-        insert_test_contact(&pool, "Non-contractor B", false).await;
-        insert_test_contact(&pool, "Contractor C", true).await;
+        let receipt_id = insert_receipt(
+            &pool,
+            "link_hash",
+            "jpg",
+            "/r/link.jpg",
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            0.5,
+        )
+        .await
+        .unwrap()
+        .id();
 
-        let contractors = get_contractors(&pool).await.unwrap();
-        assert_eq!(contractors.len(), 2);
-        // Should be sorted by name
-        assert_eq!(contractors[0].name, "Contractor A");
-        assert_eq!(contractors[1].name, "Contractor C");
-    }
+        // We need a real transaction_id. Insert one directly.
+        sqlx::query("INSERT INTO transactions (date, description, balanced_total_cents) VALUES ('2026-03-01', 'Test tx', 1000)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let tx_id: (i64,) = sqlx::query_as("SELECT last_insert_rowid()")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
 
-    // ── 3. Invoice lifecycle ─────────────────────────────────────────────────
+        link_receipt_to_transaction(&pool, receipt_id, tx_id.0)
+            .await
+            .unwrap();
+
+        let r = get_receipt_by_id(&pool, receipt_id).await.unwrap().unwrap();
+        assert_eq!(r.transaction_id, Some(tx_id.0));
+        assert_eq!(r.status, "approved");
+        assert!(r.reviewed_at.is_some());
+    }
 
     #[tokio::test]
-    async fn test_invoice_lifecycle() {
+    async fn test_receipt_match_workflow() {
         let pool = test_pool().await;
-        let contact_id = insert_test_contact(&pool, "Client X", false).await;
+        let receipt_id = insert_receipt(
+            &pool,
+            "match_hash",
+            "jpg",
+            "/r/match.jpg",
+            None,
+            Some("Office Depot"),
+            Some("2026-03-05"),
+            Some(4599),
+            None,
+            None,
+            None,
+            None,
+            0.9,
+        )
+        .await
+        .unwrap()
+        .id();
+        // Still pending review — not offered for matching yet.
+        assert!(get_unmatched_receipts(&pool).await.unwrap().is_empty());
+        update_receipt_status(&pool, receipt_id, ReceiptStatus::Approved)
+            .await
+            .unwrap();
+        let receipts = get_unmatched_receipts(&pool).await.unwrap();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].total_cents, 4599);
 
-        // Insert invoice
-        let inv_id = insert_test_invoice(&pool, contact_id, "INV-001").await;
-        assert!(inv_id > 0);
+        sqlx::query("INSERT INTO transactions (date, description, balanced_total_cents) VALUES ('2026-03-05', 'Supplies', 4599)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let imported = ImportedTransaction {
+            id: 0,
+            source_type: "ofx".to_string(),
+            source_id: Some("F1".to_string()),
+            import_batch_id: "b1".to_string(),
+            date: "2026-03-06".to_string(),
+            description: "OFFICE DEPOT #12".to_string(),
+            amount_cents: -4599,
+            debit_cents: Some(4599),
+            credit_cents: None,
+            memo: None,
+            matched_transaction_id: None,
+            category_rule_id: None,
+            status: "pending".to_string(),
+            created_at: String::new(),
+        };
+        let imported_id = insert_imported_transaction(&pool, &imported).await.unwrap();
 
-        // Get by id
-        let inv = get_invoice_by_id(&pool, inv_id).await.unwrap().unwrap();
-        assert_eq!(inv.invoice_number, "INV-001");
-        assert_eq!(inv.status_type, "Draft");
+        let candidates = get_receipt_match_candidates(&pool, "2026-03-01", "2026-03-31")
+            .await
+            .unwrap();
+        assert_eq!(candidates.len(), 2);
 
-        // Update status
-        update_invoice_status(&pool, inv_id, "Sent", Some("2026-01-16"))
+        let suggestions = find_receipt_match_suggestions(&pool, &AutoMatchEngine::new(3, 0.5, 0))
             .await
             .unwrap();
-        let updated = get_invoice_by_id(&pool, inv_id).await.unwrap().unwrap();
-        assert_eq!(updated.status_type, "Sent");
-        assert_eq!(updated.status_data.as_deref(), Some("2026-01-16"));
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].receipt_id, receipt_id);
 
-        // Get all invoices
-        let all = get_all_invoices(&pool).await.unwrap();
-        assert_eq!(all.len(), 1);
+        // Link to the pending bank row, then post it: the receipt follows.
+        confirm_receipt_match(&pool, receipt_id, ReceiptLinkTarget::Imported(imported_id))
+            .await
+            .unwrap();
+        let r = get_receipt_by_id(&pool, receipt_id).await.unwrap().unwrap();
+        assert_eq!(r.imported_transaction_id, Some(imported_id));
+        assert!(r.transaction_id.is_none());
+        assert!(get_unmatched_receipts(&pool).await.unwrap().is_empty());
+        let candidates = get_receipt_match_candidates(&pool, "2026-03-01", "2026-03-31")
+            .await
+            .unwrap();
+        assert_eq!(candidates.len(), 1);
 
-        // Get by status
-        let sent = get_invoices_by_status(&pool, "Sent").await.unwrap();
-        assert_eq!(sent.len(), 1);
-        let drafts = get_invoices_by_status(&pool, "Draft").await.unwrap();
-        assert_eq!(drafts.len(), 0);
+        let (tx_id,): (i64,) = sqlx::query_as("SELECT id FROM transactions LIMIT 1")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        mark_imported_transaction_matched(&pool, imported_id, tx_id)
+            .await
+            .unwrap();
+        let r = get_receipt_by_id(&pool, receipt_id).await.unwrap().unwrap();
+        assert_eq!(r.transaction_id, Some(tx_id));
+        assert!(
+            get_receipt_match_candidates(&pool, "2026-03-01", "2026-03-31")
+                .await
+                .unwrap()
+                .is_empty()
+        );
     }
 
     #[tokio::test]
-    async fn test_invoice_aging() {
+    async fn test_receipt_match_respects_payment_account() {
         let pool = test_pool().await;
-        let contact_id = insert_test_contact(&pool, "Client", false).await;
-
-        // Draft invoice should not appear in aging
-        insert_test_invoice(&pool, contact_id, "INV-D").await;
-
-        // Sent invoice should appear
-        let sent_id = insert_invoice(
-            &pool,
-            "INV-S",
-            contact_id,
-            "Sent",
-            None,
-            "2026-01-01",
-            "2026-01-15",
+        let receipt_id = insert_receipt(
+            &pool,
+            "visa_hash",
+            "jpg",
+            "/r/visa.jpg",
             None,
+            Some("Staples"),
+            Some("2026-03-05"),
+            Some(2500),
             None,
             None,
+            Some("Visa"),
             None,
+            0.9,
         )
         .await
-        .unwrap();
-
-        let aging = get_invoice_aging(&pool).await.unwrap();
-        assert_eq!(aging.len(), 1);
-        assert_eq!(aging[0].id, sent_id);
-    }
-
-    // ── 4. Invoice lines ─────────────────────────────────────────────────────
-
-    #[tokio::test]
-    async fn test_invoice_lines() {
-        let pool = test_pool().await;
-        let contact_id = insert_test_contact(&pool, "Client", false).await;
-        let inv_id = insert_test_invoice(&pool, contact_id, "INV-001").await;
-
-        // Insert lines
-        let l1 = insert_invoice_line(&pool, inv_id, "Web dev", 10000, 15000, true, 1)
+        .unwrap()
+        .id();
+        update_receipt_status(&pool, receipt_id, ReceiptStatus::Approved)
             .await
             .unwrap();
-        let l2 = insert_invoice_line(&pool, inv_id, "Hosting", 100, 5000, false, 2)
+        assert_eq!(
+            get_unmatched_receipts(&pool).await.unwrap()[0]
+                .payment_account
+                .as_deref(),
+            Some("2000")
+        );
+
+        // Paid out of checking, per the ledger.
+        let checking = get_account_by_code(&pool, "1000").await.unwrap().unwrap();
+        let supplies = get_account_by_code(&pool, "5000").await.unwrap().unwrap();
+        let tx_id = sqlx::query("INSERT INTO transactions (date, description, balanced_total_cents) VALUES ('2026-03-05', 'Staples', 2500)")
+            .execute(&pool)
+            .await
+            .unwrap()
+            .last_insert_rowid();
+        for (account, debit, credit) in [
+            (supplies.id.unwrap().0, 2500, 0),
+            (checking.id.unwrap().0, 0, 2500),
+        ] {
+            sqlx::query("INSERT INTO transaction_lines (transaction_id, account_id, debit_cents, credit_cents) VALUES (?, ?, ?, ?)")
+                .bind(tx_id)
+                .bind(account)
+                .bind(debit)
+                .bind(credit)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        let candidates = get_receipt_match_candidates(&pool, "2026-03-01", "2026-03-31")
             .await
             .unwrap();
-        assert!(l1 > 0);
-        assert!(l2 > l1);
+        assert_eq!(candidates[0].account_codes, ["5000", "1000"]);
 
-        // Get lines
-        let lines = get_invoice_lines(&pool, inv_id).await.unwrap();
-        assert_eq!(lines.len(), 2);
-        assert_eq!(lines[0].description, "Web dev");
-        assert_eq!(lines[0].quantity_hundredths, 10000);
-        assert_eq!(lines[0].unit_rate_cents, 15000);
-        assert!(lines[0].taxable);
-        assert_eq!(lines[1].description, "Hosting");
-        assert!(!lines[1].taxable);
+        let engine = AutoMatchEngine::new(3, 0.5, 0);
+        assert!(find_receipt_match_suggestions(&pool, &engine)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // The user pays with a Visa debit card drawn on checking.
+        let mut map = get_payment_account_map(&pool).await.unwrap();
+        map.methods.insert("Visa".to_string(), "1000".to_string());
+        set_payment_account_map(&pool, &map).await.unwrap();
+        assert_eq!(get_payment_account_map(&pool).await.unwrap(), map);
+        let suggestions = find_receipt_match_suggestions(&pool, &engine)
+            .await
+            .unwrap();
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].target, ReceiptLinkTarget::Ledger(tx_id));
     }
 
-    // ── 5. Payments ──────────────────────────────────────────────────────────
-
     #[tokio::test]
-    async fn test_payments_and_ytd() {
+    async fn test_auto_approval_and_undo() {
         let pool = test_pool().await;
-        let contact_id = insert_test_contact(&pool, "Client", false).await;
-        let inv_id = insert_test_invoice(&pool, contact_id, "INV-001").await;
+        assert!(!get_auto_approve_settings(&pool).await.unwrap().enabled);
+        let settings = AutoApproveSettings {
+            enabled: true,
+            ..Default::default()
+        };
+        set_auto_approve_settings(&pool, &settings).await.unwrap();
+        assert_eq!(get_auto_approve_settings(&pool).await.unwrap(), settings);
 
-        // Insert payments
-        let p1 = insert_payment(&pool, inv_id, 50000, "2026-03-01", Some("check"), None)
+        let receipt_id = insert_receipt(
+            &pool,
+            "auto_hash",
+            "jpg",
+            "/r/auto.jpg",
+            None,
+            Some("Office Depot"),
+            Some("2026-03-05"),
+            Some(4599),
+            None,
+            None,
+            None,
+            None,
+            0.97,
+        )
+        .await
+        .unwrap()
+        .id();
+        let imported = ImportedTransaction {
+            id: 0,
+            source_type: "ofx".to_string(),
+            source_id: Some("F2".to_string()),
+            import_batch_id: "b2".to_string(),
+            date: "2026-03-05".to_string(),
+            description: "OFFICE DEPOT #12".to_string(),
+            amount_cents: -4599,
+            debit_cents: Some(4599),
+            credit_cents: None,
+            memo: None,
+            matched_transaction_id: None,
+            category_rule_id: None,
+            status: "categorized".to_string(),
+            created_at: String::new(),
+        };
+        let imported_id = insert_imported_transaction(&pool, &imported).await.unwrap();
+
+        // Pending receipts are matched one at a time.
+        let receipt = get_receipt_by_id(&pool, receipt_id).await.unwrap().unwrap();
+        let found = find_receipt_match(&pool, &AutoMatchEngine::new(3, 0.5, 0), &receipt)
             .await
+            .unwrap()
             .unwrap();
-        let p2 = insert_payment(&pool, inv_id, 25000, "2026-04-01", Some("wire"), None)
+        assert_eq!(found.target, ReceiptLinkTarget::Imported(imported_id));
+
+        let tx_id = sqlx::query("INSERT INTO transactions (date, description, balanced_total_cents) VALUES ('2026-03-05', 'Office Depot', 4599)")
+            .execute(&pool)
+            .await
+            .unwrap()
+            .last_insert_rowid();
+        let approval = NewAutoApproval {
+            receipt_id,
+            transaction_id: tx_id,
+            imported_transaction_id: Some(imported_id),
+            posted: true,
+            receipt_confidence: receipt.confidence,
+            match_confidence: found.confidence,
+        };
+        let mut conn = pool.acquire().await.unwrap();
+        let approval_id = record_auto_approval(&mut conn, &approval)
             .await
+            .unwrap()
             .unwrap();
-        assert!(p1 > 0);
-        assert!(p2 > p1);
-
-        // Get payments for invoice
-        let payments = get_payments_for_invoice(&pool, inv_id).await.unwrap();
-        assert_eq!(payments.len(), 2);
-        assert_eq!(payments[0].amount_cents, 50000);
-        assert_eq!(payments[1].amount_cents, 25000);
+        // Already approved: a second attempt does nothing.
+        assert!(record_auto_approval(&mut conn, &approval)
+            .await
+            .unwrap()
+            .is_none());
+        drop(conn);
 
-        // YTD payments to contact
-        let ytd = get_ytd_payments_to_contact(&pool, contact_id, 2026)
+        let r = get_receipt_by_id(&pool, receipt_id).await.unwrap().unwrap();
+        assert_eq!(r.status, "approved");
+        assert_eq!(r.transaction_id, Some(tx_id));
+        let approvals = get_auto_approvals(&pool, "2000-01-01 00:00:00")
             .await
             .unwrap();
-        assert_eq!(ytd, 75000);
+        assert_eq!(approvals.len(), 1);
+        assert_eq!(approvals[0].vendor.as_deref(), Some("Office Depot"));
+        assert!(approvals[0].posted);
 
-        // Different year returns 0
-        let ytd_other = get_ytd_payments_to_contact(&pool, contact_id, 2025)
+        assert!(undo_auto_approval(&pool, approval_id).await.unwrap());
+        assert!(!undo_auto_approval(&pool, approval_id).await.unwrap());
+        let r = get_receipt_by_id(&pool, receipt_id).await.unwrap().unwrap();
+        assert_eq!(r.status, "pending_review");
+        assert!(r.transaction_id.is_none() && r.imported_transaction_id.is_none());
+        let (status, matched): (String, Option<i64>) = sqlx::query_as(
+            "SELECT status, matched_transaction_id FROM imported_transactions WHERE id = ?",
+        )
+        .bind(imported_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!((status.as_str(), matched), ("categorized", None));
+        let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM transactions")
+            .fetch_one(&pool)
             .await
             .unwrap();
-        assert_eq!(ytd_other, 0);
+        assert_eq!(count, 0);
+        let approvals = get_auto_approvals(&pool, "2000-01-01 00:00:00")
+            .await
+            .unwrap();
+        assert!(approvals[0].undone_at.is_some());
     }
 
     #[tokio::test]
-    async fn test_contractor_ytd_payments() {
+    async fn test_vendor_profile_learning() {
         let pool = test_pool().await;
-        let c1 = insert_test_contact(&pool, "Contractor A", true).await;
-        let c2 = insert_test_contact(&pool, "Contractor B", true).await;
-        let _non = insert_test_contact(&pool, "Regular Vendor", false).await;
-
-        let inv1 = insert_test_invoice(&pool, c1, "INV-C1").await;
-        let inv2 = insert_test_invoice(&pool, c2, "INV-C2").await;
-
-        insert_payment(&pool, inv1, 100000, "2026-06-01", None, None)
+        record_vendor_approval(&pool, "Joe's Hardware", Some(0.08), Some("AMOUNT TENDERED"))
             .await
             .unwrap();
-        insert_payment(&pool, inv2, 200000, "2026-07-01", None, None)
+        record_vendor_approval(&pool, "JOE'S HARDWARE", None, None)
+            .await
+            .unwrap();
+        record_vendor_approval(&pool, "Joe's Hardware", Some(0.09), None)
             .await
             .unwrap();
 
-        let results = get_contractor_ytd_payments(&pool, 2026).await.unwrap();
-        assert_eq!(results.len(), 2);
-        // Sorted by name
-        assert_eq!(results[0].1, "Contractor A");
-        assert_eq!(results[0].2, 100000);
-        assert_eq!(results[1].1, "Contractor B");
-        assert_eq!(results[1].2, 200000);
+        let profiles = get_vendor_profiles(&pool).await.unwrap();
+        assert_eq!(profiles.len(), 1);
+        let p = &profiles[0];
+        assert_eq!(p.name, "Joe's Hardware");
+        assert_eq!(p.approvals, 3);
+        assert_eq!(p.tax_samples, 2);
+        assert!((p.tax_rate.unwrap() - 0.085).abs() < 1e-9);
+        assert_eq!(p.total_label.as_deref(), Some("AMOUNT TENDERED"));
     }
 
-    // ── 6. Receipts ──────────────────────────────────────────────────────────
-
     #[tokio::test]
-    async fn test_receipt_lifecycle() {
+    async fn test_receipt_corrections_measure_accuracy() {
         let pool = test_pool().await;
-
-        // Insert receipt
         let id = insert_receipt(
             &pool,
-            "abc123hash",
+            "corr_hash",
             "jpg",
-            "/receipts/abc123.jpg",
-            Some("ACME Store $42.50"),
-            Some("ACME Store"),
-            Some("2026-03-01"),
-            Some(4250),
-            Some(3900),
-            Some(350),
-            Some("credit_card"),
-            0.85,
+            "/r/corr.jpg",
+            Some("SHELL\n03/05/2026\nTOTAL 45.99"),
+            Some("SHELL"),
+            Some("2026-05-03"),
+            Some(4599),
+            None,
+            None,
+            None,
+            None,
+            0.8,
         )
         .await
-        .unwrap();
-        assert!(id > 0);
-
-        // Get by id
-        let r = get_receipt_by_id(&pool, id).await.unwrap().unwrap();
-        assert_eq!(r.file_hash, "abc123hash");
-        assert_eq!(r.vendor.as_deref(), Some("ACME Store"));
-        assert_eq!(r.total_cents, Some(4250));
-        assert_eq!(r.status, "pending_review");
+        .unwrap()
+        .id();
 
-        // Pending review
-        let pending = get_receipts_pending_review(&pool).await.unwrap();
-        assert_eq!(pending.len(), 1);
+        // Vendor confirmed, date fixed, total not reviewed.
+        assert!(
+            correct_receipt_fields(&pool, id, Some("SHELL"), Some("2026-03-05"), None)
+                .await
+                .unwrap()
+        );
+        // A second correction keeps the original extracted date.
+        correct_receipt_fields(&pool, id, None, Some("2026-03-06"), None)
+            .await
+            .unwrap();
+        assert!(!correct_receipt_fields(&pool, 9999, Some("X"), None, None)
+            .await
+            .unwrap());
 
-        // Update status
-        update_receipt_status(&pool, id, "approved").await.unwrap();
-        let updated = get_receipt_by_id(&pool, id).await.unwrap().unwrap();
-        assert_eq!(updated.status, "approved");
-        assert!(updated.reviewed_at.is_some());
+        let r = get_receipt_by_id(&pool, id).await.unwrap().unwrap();
+        assert_eq!(r.receipt_date.as_deref(), Some("2026-03-06"));
+        assert_eq!(r.total_cents, Some(4599));
+
+        let corrections = get_receipt_corrections(&pool).await.unwrap();
+        assert_eq!(corrections.len(), 2);
+        let date = corrections.iter().find(|c| c.field == "date").unwrap();
+        assert_eq!(date.extracted_value.as_deref(), Some("2026-05-03"));
+        assert_eq!(date.corrected_value.as_deref(), Some("2026-03-06"));
+        assert!(date.ocr_text.as_deref().unwrap().contains("SHELL"));
+
+        let accuracy = get_extraction_accuracy(&pool).await.unwrap();
+        let by_field: Vec<(&str, i64, i64)> = accuracy
+            .iter()
+            .map(|a| (a.field.as_str(), a.reviewed, a.correct))
+            .collect();
+        assert_eq!(by_field, [("date", 1, 0), ("vendor", 1, 1)]);
+    }
 
-        // No longer pending
-        let pending2 = get_receipts_pending_review(&pool).await.unwrap();
-        assert_eq!(pending2.len(), 0);
+    #[tokio::test]
+    async fn test_update_receipt_during_review() {
+        let pool = test_pool().await;
+        let mut ids = Vec::new();
+        for (hash, vendor, total) in [("up_a", "Home Depot", 8999), ("up_b", "HOME DEPOT", 8990)] {
+            let id = insert_receipt(
+                &pool,
+                hash,
+                "jpg",
+                "/r/up.jpg",
+                None,
+                Some(vendor),
+                Some("2026-04-02"),
+                Some(total),
+                None,
+                None,
+                Some("Visa"),
+                Some("R-1"),
+                0.6,
+            )
+            .await
+            .unwrap()
+            .id();
+            insert_receipt_line_item(&pool, id, "Lumber", Some(total), None)
+                .await
+                .unwrap();
+            ids.push(id);
+        }
+        assert!(find_possible_duplicate_receipts(&pool, ids[0])
+            .await
+            .unwrap()
+            .is_empty());
+
+        // The second photo misread the total.
+        let update = ReceiptUpdate {
+            total_cents: Some(8999),
+            tax_cents: Some(666),
+            payment_method: Some("Cash".to_string()),
+            reference: Some(String::new()),
+            line_items: Some(vec![
+                ReceiptLineItemInput {
+                    description: "Lumber".to_string(),
+                    amount_cents: Some(8333),
+                    quantity: Some(3.0),
+                },
+                ReceiptLineItemInput {
+                    description: "Screws".to_string(),
+                    amount_cents: None,
+                    quantity: None,
+                },
+            ]),
+            deductible_percent: Some(60),
+            ..Default::default()
+        };
+        assert!(update_receipt(&pool, ids[1], &update).await.unwrap());
+        assert!(!update_receipt(&pool, 9999, &update).await.unwrap());
+
+        let r = get_receipt_by_id(&pool, ids[1]).await.unwrap().unwrap();
+        assert_eq!(r.vendor.as_deref(), Some("HOME DEPOT"));
+        assert_eq!(r.total_cents, Some(8999));
+        assert_eq!(r.tax_cents, Some(666));
+        assert_eq!(r.payment_method.as_deref(), Some("Cash"));
+        assert_eq!(r.reference, None);
+        assert!(!r.is_personal);
+        assert_eq!(r.deductible_percent, 60);
+        let items = get_receipt_line_items(&pool, ids[1]).await.unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].quantity, Some(3.0));
+
+        let mut fields: Vec<String> = get_receipt_corrections(&pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|c| c.field)
+            .collect();
+        fields.sort();
+        assert_eq!(fields, ["payment_method", "reference", "tax", "total"]);
+
+        // Same vendor, date and total now: flagged both ways.
+        assert_eq!(
+            find_possible_duplicate_receipts(&pool, ids[0])
+                .await
+                .unwrap(),
+            [ids[1]]
+        );
+        update_receipt_status(&pool, ids[1], ReceiptStatus::Rejected)
+            .await
+            .unwrap();
+        assert!(find_possible_duplicate_receipts(&pool, ids[0])
+            .await
+            .unwrap()
+            .is_empty());
     }
 
     #[tokio::test]
-    async fn test_receipt_duplicate_detection() {
+    async fn test_documentation_gaps() {
         let pool = test_pool().await;
-        let id1 = insert_receipt(
-            &pool,
-            "dup_hash",
-            "png",
-            "/receipts/dup.png",
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            0.0,
-        )
-        .await
-        .unwrap();
+        let checking = get_account_by_code(&pool, "1000").await.unwrap().unwrap();
+        let supplies = get_account_by_code(&pool, "5040").await.unwrap().unwrap();
+        assert_eq!(
+            get_receipt_threshold_cents(&pool).await.unwrap(),
+            DEFAULT_RECEIPT_THRESHOLD_CENTS
+        );
+        set_setting(&pool, RECEIPT_THRESHOLD_SETTING, "5000")
+            .await
+            .unwrap();
+        let threshold = get_receipt_threshold_cents(&pool).await.unwrap();
+        assert_eq!(threshold, 5000);
+
+        let mut tx_ids = Vec::new();
+        for (description, cents) in [
+            ("Monitor", 32_000),
+            ("Cables", 1_200),
+            ("Desk", 45_000),
+            ("Chair", 20_000),
+        ] {
+            let tx_id = sqlx::query("INSERT INTO transactions (date, description, balanced_total_cents) VALUES ('2026-05-02', ?, ?)")
+                .bind(description)
+                .bind(cents)
+                .execute(&pool)
+                .await
+                .unwrap()
+                .last_insert_rowid();
+            for (account, debit, credit) in [
+                (supplies.id.unwrap().0, cents, 0),
+                (checking.id.unwrap().0, 0, cents),
+            ] {
+                sqlx::query("INSERT INTO transaction_lines (transaction_id, account_id, debit_cents, credit_cents) VALUES (?, ?, ?, ?)")
+                    .bind(tx_id)
+                    .bind(account)
+                    .bind(debit)
+                    .bind(credit)
+                    .execute(&pool)
+                    .await
+                    .unwrap();
+            }
+            tx_ids.push(tx_id);
+        }
+        set_transaction_deductibility(&pool, tx_ids[3], Deductibility::personal())
+            .await
+            .unwrap();
 
-        // Insert same hash again - should return existing id
-        let id2 = insert_receipt(
-            &pool,
-            "dup_hash",
-            "png",
-            "/receipts/dup2.png",
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            0.0,
-        )
-        .await
-        .unwrap();
-        assert_eq!(id1, id2);
+        let mut receipt_ids = Vec::new();
+        for (hash, date) in [("gap_a", "2026-05-02"), ("gap_b", "2026-05-03")] {
+            let id = insert_receipt(
+                &pool,
+                hash,
+                "jpg",
+                "/r/gap.jpg",
+                None,
+                Some("Best Buy"),
+                Some(date),
+                Some(32_000),
+                None,
+                None,
+                None,
+                None,
+                0.9,
+            )
+            .await
+            .unwrap()
+            .id();
+            update_receipt_status(&pool, id, ReceiptStatus::Approved)
+                .await
+                .unwrap();
+            receipt_ids.push(id);
+        }
+        link_receipt_to_transaction(&pool, receipt_ids[0], tx_ids[0])
+            .await
+            .unwrap();
 
-        // check_receipt_duplicate
-        let dup = check_receipt_duplicate(&pool, "dup_hash").await.unwrap();
-        assert_eq!(dup, Some(id1));
+        let missing = get_unreceipted_expenses(&pool, "2026-01-01", "2026-12-31", threshold)
+            .await
+            .unwrap();
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].transaction_id, tx_ids[2]);
+        assert_eq!(missing[0].expense_cents, 45_000);
 
-        let no_dup = check_receipt_duplicate(&pool, "other_hash").await.unwrap();
-        assert!(no_dup.is_none());
+        let unlinked = get_unlinked_approved_receipts(&pool, "2026-01-01", "2026-12-31")
+            .await
+            .unwrap();
+        assert_eq!(unlinked.len(), 1);
+        assert_eq!(unlinked[0].id, receipt_ids[1]);
     }
 
     #[tokio::test]
-    async fn test_link_receipt_to_transaction() {
+    async fn test_reprocess_updates_unreviewed_and_queues_reviewed() {
         let pool = test_pool().await;
-        let receipt_id = insert_receipt(
-            &pool,
-            "link_hash",
-            "jpg",
-            "/r/link.jpg",
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            0.5,
-        )
-        .await
-        .unwrap();
+        let mut ids = Vec::new();
+        for (hash, date) in [("re_a", "2026-03-04"), ("re_b", "2026-03-05")] {
+            let id = insert_receipt(
+                &pool,
+                hash,
+                "jpg",
+                "/r/re.jpg",
+                Some("SHELL TOTAL 4.99"),
+                Some("SHELL"),
+                Some(date),
+                Some(499),
+                None,
+                None,
+                None,
+                None,
+                0.4,
+            )
+            .await
+            .unwrap()
+            .id();
+            ids.push(id);
+        }
+        let (pending, approved) = (ids[0], ids[1]);
+        update_receipt_status(&pool, approved, ReceiptStatus::Approved)
+            .await
+            .unwrap();
+        // The user fixed this vendor by hand; re-processing must leave it.
+        correct_receipt_fields(&pool, approved, Some("Shell Oil"), None, None)
+            .await
+            .unwrap();
 
-        // We need a real transaction_id. Insert one directly.
-        sqlx::query("INSERT INTO transactions (date, description, balanced_total_cents) VALUES ('2026-03-01', 'Test tx', 1000)")
-            .execute(&pool)
+        let filter = ReprocessFilter {
+            max_confidence: Some(0.5),
+            ..Default::default()
+        };
+        assert_eq!(
+            get_receipts_for_reprocess(&pool, &filter)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+
+        let new = ReextractedReceipt {
+            ocr_text: "SHELL STATION TOTAL 49.99".to_string(),
+            vendor: Some("Shell Station".to_string()),
+            receipt_date: Some("2026-03-05".to_string()),
+            total_cents: Some(4999),
+            confidence: 0.9,
+            ..Default::default()
+        };
+        let outcome = apply_reprocessed_receipt(&pool, pending, &new)
             .await
             .unwrap();
-        let tx_id: (i64,) = sqlx::query_as("SELECT last_insert_rowid()")
-            .fetch_one(&pool)
+        assert_eq!(outcome, ReprocessOutcome::Updated);
+        let r = get_receipt_by_id(&pool, pending).await.unwrap().unwrap();
+        assert_eq!(r.vendor.as_deref(), Some("Shell Station"));
+        assert_eq!(r.total_cents, Some(4999));
+        assert_eq!(r.confidence, 0.9);
+
+        let outcome = apply_reprocessed_receipt(&pool, approved, &new)
             .await
             .unwrap();
+        assert_eq!(outcome, ReprocessOutcome::Queued);
+        let r = get_receipt_by_id(&pool, approved).await.unwrap().unwrap();
+        assert_eq!(r.vendor.as_deref(), Some("Shell Oil"));
+        assert_eq!(r.total_cents, Some(499));
 
-        link_receipt_to_transaction(&pool, receipt_id, tx_id.0)
+        // Running again replaces, rather than duplicates, the pending diffs.
+        apply_reprocessed_receipt(&pool, approved, &new)
             .await
             .unwrap();
+        let diffs = get_pending_reprocess_diffs(&pool).await.unwrap();
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].field, "total_cents");
+        assert_eq!(diffs[0].current_value.as_deref(), Some("499"));
+        assert_eq!(diffs[0].proposed_value, "4999");
 
-        let r = get_receipt_by_id(&pool, receipt_id).await.unwrap().unwrap();
-        assert_eq!(r.transaction_id, Some(tx_id.0));
-        assert_eq!(r.status, "approved");
-        assert!(r.reviewed_at.is_some());
+        assert!(resolve_reprocess_diff(&pool, diffs[0].id, true)
+            .await
+            .unwrap());
+        assert!(!resolve_reprocess_diff(&pool, diffs[0].id, false)
+            .await
+            .unwrap());
+        let r = get_receipt_by_id(&pool, approved).await.unwrap().unwrap();
+        assert_eq!(r.total_cents, Some(4999));
+        assert!(get_pending_reprocess_diffs(&pool).await.unwrap().is_empty());
+        assert_eq!(
+            apply_reprocessed_receipt(&pool, approved, &new)
+                .await
+                .unwrap(),
+            ReprocessOutcome::Unchanged
+        );
     }
 
     // ── 7. Audit log ─────────────────────────────────────────────────────────
@@ -1713,6 +5537,60 @@ mod tests {
         assert_eq!(val.as_deref(), Some("light"));
     }
 
+    #[tokio::test]
+    async fn test_preferences_defaults_and_validation() {
+        let pool = test_pool().await;
+        assert_eq!(
+            get_preferences(&pool).await.unwrap(),
+            Preferences::default()
+        );
+
+        set_setting(&pool, DEFAULT_CURRENCY_SETTING, "eur")
+            .await
+            .unwrap();
+        set_setting(&pool, FISCAL_YEAR_START_SETTING, "7")
+            .await
+            .unwrap();
+        set_setting(&pool, REVIEW_THRESHOLD_SETTING, "not a number")
+            .await
+            .unwrap();
+        let prefs = get_preferences(&pool).await.unwrap();
+        assert_eq!(prefs.default_currency, "EUR");
+        assert_eq!(prefs.fiscal_year_start_month, 7);
+        assert_eq!(prefs.review_threshold, DEFAULT_REVIEW_THRESHOLD);
+
+        let mut prefs = Preferences::default();
+        assert!(prefs.set(FISCAL_YEAR_START_SETTING, "13").is_err());
+        assert!(prefs.set(REVIEW_THRESHOLD_SETTING, "1.5").is_err());
+        assert!(prefs.set(DATE_FORMAT_SETTING, "%Q").is_err());
+        assert!(prefs.set(DATE_FORMAT_SETTING, "%d.%m.%Y").unwrap());
+        assert!(prefs.set(NUMBER_LOCALE_SETTING, "en-US-x").is_err());
+        assert!(prefs.set(NUMBER_LOCALE_SETTING, "de_de").unwrap());
+        assert_eq!(prefs.number_locale, "de-DE");
+        prefs.set(DEFAULT_CURRENCY_SETTING, "EUR").unwrap();
+        assert_eq!(
+            prefs.money_format().format_cents(123_456),
+            "1.234,56\u{a0}€"
+        );
+        assert!(!prefs.set("theme", "dark").unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_local_api_settings_roundtrip() {
+        let pool = test_pool().await;
+        let settings = get_local_api_settings(&pool).await.unwrap();
+        assert!(!settings.enabled);
+        assert_eq!(settings.port, DEFAULT_LOCAL_API_PORT);
+
+        let settings = LocalApiSettings {
+            enabled: true,
+            port: 9000,
+            token: Some("secret".to_string()),
+        };
+        set_local_api_settings(&pool, &settings).await.unwrap();
+        assert_eq!(get_local_api_settings(&pool).await.unwrap(), settings);
+    }
+
     // ── 9. Tax periods ───────────────────────────────────────────────────────
 
     #[tokio::test]
@@ -1823,6 +5701,78 @@ mod tests {
         assert!(snap.prior_year_tax.is_none());
     }
 
+    #[tokio::test]
+    async fn test_build_ledger_snapshot_deductibility() {
+        let pool = test_pool().await;
+        let checking = get_account_by_code(&pool, "1000").await.unwrap().unwrap();
+        let phone = get_account_by_code(&pool, "5070").await.unwrap().unwrap();
+
+        let mut tx_ids = Vec::new();
+        for (description, cents) in [
+            ("Phone bill", 10_000),
+            ("Family plan", 5_000),
+            ("Router", 2_000),
+        ] {
+            let tx_id = sqlx::query("INSERT INTO transactions (date, description, balanced_total_cents) VALUES ('2026-04-01', ?, ?)")
+                .bind(description)
+                .bind(cents)
+                .execute(&pool)
+                .await
+                .unwrap()
+                .last_insert_rowid();
+            for (account, debit, credit) in [
+                (phone.id.unwrap().0, cents, 0),
+                (checking.id.unwrap().0, 0, cents),
+            ] {
+                sqlx::query("INSERT INTO transaction_lines (transaction_id, account_id, debit_cents, credit_cents) VALUES (?, ?, ?, ?)")
+                    .bind(tx_id)
+                    .bind(account)
+                    .bind(debit)
+                    .bind(credit)
+                    .execute(&pool)
+                    .await
+                    .unwrap();
+            }
+            tx_ids.push(tx_id);
+        }
+        let partial = Deductibility::partial(60).unwrap();
+        assert!(set_transaction_deductibility(&pool, tx_ids[0], partial)
+            .await
+            .unwrap());
+        assert!(
+            set_transaction_deductibility(&pool, tx_ids[1], Deductibility::personal())
+                .await
+                .unwrap()
+        );
+        assert!(!set_transaction_deductibility(&pool, 9999, partial)
+            .await
+            .unwrap());
+
+        let snap = build_ledger_snapshot(&pool, FiscalYear::new(2026), None)
+            .await
+            .unwrap();
+        let line = ScheduleCLine::from_tag("line_18").unwrap();
+        assert_eq!(snap.line_totals[&line].to_cents(), 8_000);
+        assert_eq!(snap.book_totals[&line].to_cents(), 12_000);
+
+        // Half the account's spending on top of each transaction's share.
+        assert!(set_account_deductible_pct(&pool, "5070", 50).await.unwrap());
+        assert!(!set_account_deductible_pct(&pool, "9999", 50).await.unwrap());
+        assert_eq!(
+            get_account_by_code(&pool, "5070")
+                .await
+                .unwrap()
+                .unwrap()
+                .deductible_pct,
+            50
+        );
+        let snap = build_ledger_snapshot(&pool, FiscalYear::new(2026), None)
+            .await
+            .unwrap();
+        assert_eq!(snap.line_totals[&line].to_cents(), 4_000);
+        assert_eq!(snap.book_totals[&line].to_cents(), 12_000);
+    }
+
     // ── 11. Import profiles ──────────────────────────────────────────────────
 
     #[tokio::test]
@@ -1853,11 +5803,81 @@ mod tests {
         assert!(profiles[0].has_header);
         assert_eq!(profiles[0].date_column, Some(0));
 
+        let mut updated = profiles[0].clone();
+        updated.delimiter = ";".to_string();
+        updated.amount_column = None;
+        updated.debit_column = Some(2);
+        updated.credit_column = Some(3);
+        assert!(update_import_profile(&pool, &updated).await.unwrap());
+        let profiles = get_import_profiles(&pool).await.unwrap();
+        assert_eq!(profiles[0].delimiter, ";");
+        assert_eq!(profiles[0].credit_column, Some(3));
+        updated.id = id + 100;
+        assert!(!update_import_profile(&pool, &updated).await.unwrap());
+
         delete_import_profile(&pool, id).await.unwrap();
         let profiles = get_import_profiles(&pool).await.unwrap();
         assert_eq!(profiles.len(), 0);
     }
 
+    #[tokio::test]
+    async fn test_csv_import_profile_roundtrip() {
+        let pool = test_pool().await;
+        let profile = CsvImportProfile {
+            id: None,
+            name: "Chase Checking".to_string(),
+            mapping: CsvColumnMapping {
+                date_column: Some(0),
+                description_column: Some(2),
+                amount_column: None,
+                debit_column: Some(3),
+                credit_column: Some(4),
+                memo_column: Some(5),
+                date_format: "%m/%d/%Y".to_string(),
+            },
+            has_header: false,
+            delimiter: ";".to_string(),
+        };
+
+        let id = save_csv_import_profile(&pool, &profile).await.unwrap();
+        let loaded = get_csv_import_profiles(&pool).await.unwrap();
+        assert_eq!(loaded.len(), 1);
+        let loaded = loaded.into_iter().next().unwrap().unwrap();
+        assert_eq!(loaded.id, Some(id));
+        assert_eq!(loaded.name, profile.name);
+        assert_eq!(loaded.mapping.description_column, Some(2));
+        assert_eq!(loaded.mapping.debit_column, Some(3));
+        assert_eq!(loaded.mapping.credit_column, Some(4));
+        assert_eq!(loaded.mapping.amount_column, None);
+        assert_eq!(loaded.mapping.date_format, "%m/%d/%Y");
+        assert!(!loaded.has_header);
+        assert_eq!(loaded.delimiter, ";");
+
+        // The loaded profile is one the importer accepts.
+        assert!(loaded.validate().is_ok());
+    }
+
+    #[test]
+    fn test_import_profile_negative_column_rejected() {
+        let row = ImportProfile {
+            id: 1,
+            name: "Broken".to_string(),
+            has_header: true,
+            delimiter: ",".to_string(),
+            date_column: Some(0),
+            description_column: Some(-1),
+            amount_column: Some(2),
+            debit_column: None,
+            credit_column: None,
+            memo_column: None,
+            date_format: "%Y-%m-%d".to_string(),
+            created_at: String::new(),
+        };
+        let err = CsvImportProfile::try_from(row).unwrap_err();
+        assert_eq!(err.column, "description_column");
+        assert_eq!(err.value, -1);
+    }
+
     // ── 12. Categorization rules ─────────────────────────────────────────────
 
     #[tokio::test]
@@ -1873,6 +5893,8 @@ mod tests {
             match_pattern: "(?i)starbucks|coffee".to_string(),
             match_type: "regex".to_string(),
             account_id: acc_id,
+            amount_min_cents: None,
+            amount_max_cents: None,
             created_at: String::new(),
         };
 
@@ -1884,11 +5906,135 @@ mod tests {
         assert_eq!(rules[0].name, "Coffee shops");
         assert_eq!(rules[0].priority, 10);
 
+        let mut updated = rules[0].clone();
+        updated.priority = 20;
+        updated.match_pattern = "(?i)peets".to_string();
+        update_categorization_rule(&pool, &updated).await.unwrap();
+        let rules = get_categorization_rules(&pool).await.unwrap();
+        assert_eq!(rules[0].priority, 20);
+        assert_eq!(rules[0].match_pattern, "(?i)peets");
+
         delete_categorization_rule(&pool, id).await.unwrap();
         let rules = get_categorization_rules(&pool).await.unwrap();
         assert_eq!(rules.len(), 0);
     }
 
+    #[tokio::test]
+    async fn test_category_rules_reorder_and_delete() {
+        let pool = test_pool().await;
+        let meals = get_account_by_code(&pool, "5020").await.unwrap().unwrap();
+        let meals_id = meals.id.unwrap().0;
+
+        let coffee = CategoryRule {
+            name: "Coffee".to_string(),
+            priority: 5,
+            pattern: "coffee".to_string(),
+            match_type: aequi_core::import::RuleMatchType::Fuzzy { threshold: 0.8 },
+            account_code: "5020".to_string(),
+            amount_min_cents: Some(-5000),
+            amount_max_cents: Some(0),
+        };
+        let lunch = CategoryRule {
+            name: "Lunch".to_string(),
+            match_type: aequi_core::import::RuleMatchType::Contains,
+            pattern: "lunch".to_string(),
+            amount_min_cents: None,
+            amount_max_cents: None,
+            ..coffee.clone()
+        };
+        let coffee_id = save_categorization_rule(
+            &pool,
+            &CategorizationRule::from_category_rule(0, &coffee, meals_id),
+        )
+        .await
+        .unwrap();
+        let lunch_id = save_categorization_rule(
+            &pool,
+            &CategorizationRule::from_category_rule(0, &lunch, meals_id),
+        )
+        .await
+        .unwrap();
+
+        let rules = get_category_rules(&pool).await.unwrap();
+        assert_eq!(rules.len(), 2);
+        let (_, stored) = rules.iter().find(|(id, _)| *id == coffee_id).unwrap();
+        assert_eq!(stored.match_type, coffee.match_type);
+        assert_eq!(stored.account_code, "5020");
+        assert_eq!(stored.amount_min_cents, Some(-5000));
+
+        reorder_categorization_rules(&pool, &[lunch_id, coffee_id])
+            .await
+            .unwrap();
+        let ids: Vec<i64> = get_category_rules(&pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        assert_eq!(ids, vec![lunch_id, coffee_id]);
+
+        // Deleting a rule in use sends rows it categorized back to review.
+        let row = ImportedTransaction {
+            id: 0,
+            source_type: "csv".to_string(),
+            source_id: None,
+            import_batch_id: "b1".to_string(),
+            date: "2026-03-01".to_string(),
+            description: "LUNCH SPOT".to_string(),
+            amount_cents: -1200,
+            debit_cents: None,
+            credit_cents: None,
+            memo: None,
+            matched_transaction_id: None,
+            category_rule_id: None,
+            status: "pending".to_string(),
+            created_at: String::new(),
+        };
+        let row_id = insert_imported_transaction(&pool, &row).await.unwrap();
+        mark_imported_transaction_categorized(&pool, row_id, lunch_id)
+            .await
+            .unwrap();
+        delete_categorization_rule(&pool, lunch_id).await.unwrap();
+        let open = get_imported_transactions_for_review(&pool, "b1")
+            .await
+            .unwrap();
+        assert_eq!(open[0].status, "pending");
+        assert_eq!(open[0].category_rule_id, None);
+        assert_eq!(get_category_rules(&pool).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_categorized_history_excludes_balance_sheet_lines() {
+        let pool = test_pool().await;
+        let checking = get_account_by_code(&pool, "1000").await.unwrap().unwrap();
+        let meals = get_account_by_code(&pool, "5020").await.unwrap().unwrap();
+
+        let tx_id = sqlx::query("INSERT INTO transactions (date, description, balanced_total_cents) VALUES ('2026-03-01', 'STARBUCKS 123', 650)")
+            .execute(&pool)
+            .await
+            .unwrap()
+            .last_insert_rowid();
+        for (account, debit, credit) in [
+            (meals.id.unwrap().0, 650, 0),
+            (checking.id.unwrap().0, 0, 650),
+        ] {
+            sqlx::query("INSERT INTO transaction_lines (transaction_id, account_id, debit_cents, credit_cents) VALUES (?, ?, ?, ?)")
+                .bind(tx_id)
+                .bind(account)
+                .bind(debit)
+                .bind(credit)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        let history = get_categorized_history(&pool, 100).await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].description, "STARBUCKS 123");
+        assert_eq!(history[0].account_code, "5020");
+        assert_eq!(history[0].amount_cents, 650);
+    }
+
     // ── 13. Reconciliation ───────────────────────────────────────────────────
 
     #[tokio::test]
@@ -1973,6 +6119,24 @@ mod tests {
         let id = insert_imported_transaction(&pool, &tx).await.unwrap();
         assert!(id > 0);
 
+        assert!(imported_transaction_exists(&pool, "csv", "row-1")
+            .await
+            .unwrap());
+        assert!(!imported_transaction_exists(&pool, "ofx", "row-1")
+            .await
+            .unwrap());
+
+        let open = get_open_imported_transactions(&pool, "2026-01-01", "2026-12-31")
+            .await
+            .unwrap();
+        assert_eq!(open.len(), 1);
+        assert!(
+            get_open_imported_transactions(&pool, "2025-01-01", "2025-12-31")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
         // Pending for batch
         let pending = get_pending_imported_transactions(&pool, "batch-abc")
             .await
@@ -1995,6 +6159,8 @@ mod tests {
             match_pattern: "ACME".to_string(),
             match_type: "contains".to_string(),
             account_id: accounts[0].id.unwrap().0,
+            amount_min_cents: None,
+            amount_max_cents: None,
             created_at: String::new(),
         };
         let rule_id = save_categorization_rule(&pool, &rule).await.unwrap();
@@ -2014,6 +6180,172 @@ mod tests {
             .unwrap();
         assert_eq!(review_after.len(), 1);
         assert_eq!(review_after[0].status, "categorized");
+
+        let summary = get_import_batch_summary(&pool, "batch-abc")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(summary.total, 1);
+        assert_eq!(summary.categorized, 1);
+        assert_eq!(summary.pending, 0);
+        assert_eq!(summary.first_date.as_deref(), Some("2026-03-01"));
+        assert!(get_import_batch_summary(&pool, "batch-none")
+            .await
+            .unwrap()
+            .is_none());
+
+        // Ledger entries already matched to a bank row aren't offered again
+        let tx_id = sqlx::query("INSERT INTO transactions (date, description, balanced_total_cents) VALUES ('2026-03-01', 'ACME', 5000)")
+            .execute(&pool)
+            .await
+            .unwrap()
+            .last_insert_rowid();
+        let unmatched = get_unmatched_ledger_transactions(&pool, "2026-01-01", "2026-12-31")
+            .await
+            .unwrap();
+        assert_eq!(unmatched.len(), 1);
+        assert_eq!(unmatched[0].amount_cents, 5000);
+        mark_imported_transaction_matched(&pool, id, tx_id)
+            .await
+            .unwrap();
+        assert!(
+            get_unmatched_ledger_transactions(&pool, "2026-01-01", "2026-12-31")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_import_review_loop() {
+        let pool = test_pool().await;
+        let supplies = get_account_by_code(&pool, "5040").await.unwrap().unwrap();
+        let mut ids = Vec::new();
+        for (source_id, description) in [("r-1", "OFFICE DEPOT"), ("r-2", "STAPLES")] {
+            let row = ImportedTransaction {
+                id: 0,
+                source_type: "ofx".to_string(),
+                source_id: Some(source_id.to_string()),
+                import_batch_id: "batch-review".to_string(),
+                date: "2026-04-02".to_string(),
+                description: description.to_string(),
+                amount_cents: -2500,
+                debit_cents: None,
+                credit_cents: None,
+                memo: None,
+                matched_transaction_id: None,
+                category_rule_id: None,
+                status: "pending".to_string(),
+                created_at: String::new(),
+            };
+            ids.push(insert_imported_transaction(&pool, &row).await.unwrap());
+        }
+        let tx_id = sqlx::query("INSERT INTO transactions (date, description, balanced_total_cents) VALUES ('2026-04-02', 'Office Depot', 2500)")
+            .execute(&pool)
+            .await
+            .unwrap()
+            .last_insert_rowid();
+
+        // A suggestion can be rejected and made again, then accepted
+        suggest_imported_match(&pool, ids[0], tx_id).await.unwrap();
+        let review = get_import_review(&pool, "batch-review").await.unwrap();
+        assert_eq!(
+            review[0].matched_description.as_deref(),
+            Some("Office Depot")
+        );
+        assert!(reject_imported_match(&pool, ids[0]).await.unwrap());
+        assert!(!reject_imported_match(&pool, ids[0]).await.unwrap());
+        suggest_imported_match(&pool, ids[0], tx_id).await.unwrap();
+        let summary = get_import_batch_summary(&pool, "batch-review")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(summary.suggested_matches, 1);
+        assert!(accept_imported_match(&pool, ids[0]).await.unwrap());
+        assert!(!accept_imported_match(&pool, ids[0]).await.unwrap());
+
+        // Manual categorization makes the second row postable
+        assert!(get_postable_imported_transactions(&pool, "batch-review")
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(
+            set_imported_transaction_account(&pool, ids[1], supplies.id.unwrap().0)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !set_imported_transaction_account(&pool, ids[0], supplies.id.unwrap().0)
+                .await
+                .unwrap()
+        );
+        let postable = get_postable_imported_transactions(&pool, "batch-review")
+            .await
+            .unwrap();
+        assert_eq!(postable.len(), 1);
+        assert_eq!(postable[0].id, ids[1]);
+        assert_eq!(postable[0].account_code.as_deref(), Some("5040"));
+
+        let summary = get_import_batch_summary(&pool, "batch-review")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(summary.matched, 1);
+        assert_eq!(summary.categorized, 1);
+        assert_eq!(summary.suggested_matches, 0);
+    }
+
+    #[tokio::test]
+    async fn test_uncategorized_queue() {
+        let pool = test_pool().await;
+        let supplies = get_account_by_code(&pool, "5100").await.unwrap().unwrap();
+        let mut ids = Vec::new();
+        for (batch, description, amount) in [
+            ("batch-a", "Staples  #12", -2_500),
+            ("batch-a", "CLIENT DEPOSIT", 90_000),
+            ("batch-b", "STAPLES #12", -400),
+            ("batch-b", "Coffee", -450),
+        ] {
+            let row = ImportedTransaction {
+                id: 0,
+                source_type: "csv".to_string(),
+                source_id: None,
+                import_batch_id: batch.to_string(),
+                date: "2026-04-02".to_string(),
+                description: description.to_string(),
+                amount_cents: amount,
+                debit_cents: None,
+                credit_cents: None,
+                memo: None,
+                matched_transaction_id: None,
+                category_rule_id: None,
+                status: "pending".to_string(),
+                created_at: String::new(),
+            };
+            ids.push(insert_imported_transaction(&pool, &row).await.unwrap());
+        }
+
+        let queue = get_uncategorized_imports(&pool, 10).await.unwrap();
+        let order: Vec<i64> = queue.iter().map(|r| r.id).collect();
+        assert_eq!(order, [ids[1], ids[0], ids[3], ids[2]]);
+        assert_eq!(queue[3].import_batch_id, "batch-b");
+
+        let booked =
+            categorize_imported_transactions(&pool, &[ids[0]], supplies.id.unwrap().0, true)
+                .await
+                .unwrap();
+        assert_eq!(booked, [ids[0], ids[2]]);
+        let queue = get_uncategorized_imports(&pool, 1).await.unwrap();
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue[0].id, ids[1]);
+        assert_eq!(
+            get_postable_imported_transactions(&pool, "batch-b")
+                .await
+                .unwrap()[0]
+                .account_code
+                .as_deref(),
+            Some("5100")
+        );
     }
 
     // ── 15. Invoice tax lines ────────────────────────────────────────────────
@@ -2038,4 +6370,268 @@ mod tests {
         assert_eq!(lines[0].label, "State Sales Tax");
         assert_eq!(lines[0].rate_bps, 825);
     }
+
+    // ── 16. Quick entry ──────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_payee_suggestions_accounts_and_amounts() {
+        let pool = test_pool().await;
+        let checking = get_account_by_code(&pool, "1000").await.unwrap().unwrap();
+        let visa = get_account_by_code(&pool, "2000").await.unwrap().unwrap();
+        let supplies = get_account_by_code(&pool, "5110").await.unwrap().unwrap();
+        let meals = get_account_by_code(&pool, "5020").await.unwrap().unwrap();
+        for (date, description, cents, expense, funding) in [
+            ("2026-03-01", "Staples", 2_500, &supplies, &checking),
+            ("2026-03-08", "STAPLES", 1_800, &supplies, &visa),
+            ("2026-03-15", "Staples", 4_200, &supplies, &visa),
+            ("2026-03-10", "Star Diner", 3_100, &meals, &visa),
+            ("2026-03-12", "100% Natural_Foods", 900, &meals, &checking),
+        ] {
+            let tx_id = sqlx::query("INSERT INTO transactions (date, description, balanced_total_cents) VALUES (?, ?, ?)")
+                .bind(date)
+                .bind(description)
+                .bind(cents)
+                .execute(&pool)
+                .await
+                .unwrap()
+                .last_insert_rowid();
+            for (account, debit, credit) in [
+                (expense.id.unwrap().0, cents, 0),
+                (funding.id.unwrap().0, 0, cents),
+            ] {
+                sqlx::query("INSERT INTO transaction_lines (transaction_id, account_id, debit_cents, credit_cents) VALUES (?, ?, ?, ?)")
+                    .bind(tx_id)
+                    .bind(account)
+                    .bind(debit)
+                    .bind(credit)
+                    .execute(&pool)
+                    .await
+                    .unwrap();
+            }
+        }
+
+        let suggestions = get_payee_suggestions(&pool, "sta", 10).await.unwrap();
+        let payees: Vec<&str> = suggestions.iter().map(|s| s.payee.as_str()).collect();
+        assert_eq!(payees, ["Staples", "Star Diner"]);
+        assert_eq!(suggestions[0].uses, 3);
+        assert_eq!(suggestions[0].last_used, "2026-03-15");
+        // Substring matches come after prefix matches.
+        let payees: Vec<String> = get_payee_suggestions(&pool, "ta", 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|s| s.payee)
+            .collect();
+        assert_eq!(payees, ["Staples", "Star Diner"]);
+        // LIKE wildcards in the query are matched literally.
+        let natural = get_payee_suggestions(&pool, "0% natural_", 10)
+            .await
+            .unwrap();
+        assert_eq!(natural.len(), 1);
+        assert!(get_payee_suggestions(&pool, "_", 10)
+            .await
+            .unwrap()
+            .iter()
+            .all(|s| s.payee.contains('_')));
+
+        let accounts = get_payee_accounts(&pool, "staples", 5).await.unwrap();
+        let codes: Vec<&str> = accounts.iter().map(|a| a.account_code.as_str()).collect();
+        assert_eq!(codes, ["5110", "2000", "1000"]);
+        assert_eq!(accounts[0].uses, 3);
+        assert_eq!(accounts[0].account_type, "Expense");
+
+        let amounts = get_payee_amounts(&pool, "Staples", 2).await.unwrap();
+        let cents: Vec<i64> = amounts.iter().map(|a| a.amount_cents).collect();
+        assert_eq!(cents, [4_200, 1_800]);
+        assert!(get_payee_amounts(&pool, "Nobody", 5)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    // ── 17. Bank balances ────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_bank_balance_upsert() {
+        let pool = test_pool().await;
+        let mut balance = BankBalance {
+            id: 0,
+            provider: "simplefin".to_string(),
+            provider_account_id: "ACT-1".to_string(),
+            name: "Checking".to_string(),
+            currency: Some("USD".to_string()),
+            current_cents: Some(125_000),
+            available_cents: None,
+            as_of: "2024-03-01T00:00:00Z".to_string(),
+        };
+        upsert_bank_balance(&pool, &balance).await.unwrap();
+
+        balance.current_cents = Some(98_050);
+        balance.available_cents = Some(90_000);
+        balance.as_of = "2024-03-02T00:00:00Z".to_string();
+        upsert_bank_balance(&pool, &balance).await.unwrap();
+
+        let rows = get_bank_balances(&pool).await.unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].current_cents, Some(98_050));
+        assert_eq!(rows[0].available_cents, Some(90_000));
+        assert_eq!(rows[0].as_of, "2024-03-02T00:00:00Z");
+
+        balance.provider = "plaid".to_string();
+        upsert_bank_balance(&pool, &balance).await.unwrap();
+        assert_eq!(get_bank_balances(&pool).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_delete_import_batch() {
+        let pool = test_pool().await;
+        sqlx::query(
+            r#"INSERT INTO imported_transactions (source_type, import_batch_id, date, description, amount_cents, status)
+               VALUES ('csv', 'b1', '2026-03-01', 'A', -100, 'pending'),
+                      ('csv', 'b1', '2026-03-02', 'B', -200, 'categorized'),
+                      ('csv', 'b1', '2026-03-03', 'C', -300, 'ignored'),
+                      ('csv', 'b2', '2026-03-04', 'D', -400, 'pending')"#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        assert_eq!(delete_import_batch(&pool, "b1").await.unwrap(), 2);
+        let summary = get_import_batch_summary(&pool, "b1")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(summary.total, 1);
+        assert_eq!(summary.ignored, 1);
+        assert!(get_import_batch_summary(&pool, "b2")
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    /// `count` balanced office supply purchases on the card, the nth for
+    /// n + 1 cents.
+    fn supply_purchases(
+        expense: AccountId,
+        card: AccountId,
+        count: usize,
+    ) -> Vec<ValidatedTransaction> {
+        (0..count)
+            .map(|n| {
+                let amount = Money::from_cents(n as i64 + 1);
+                ValidatedTransaction::validate(aequi_core::UnvalidatedTransaction {
+                    date: NaiveDate::from_ymd_opt(2026, 3, 1).unwrap(),
+                    description: format!("Purchase {n}"),
+                    lines: vec![
+                        TransactionLine {
+                            account_id: expense,
+                            debit: amount,
+                            credit: Money::zero(),
+                            memo: None,
+                        },
+                        TransactionLine {
+                            account_id: card,
+                            debit: Money::zero(),
+                            credit: amount,
+                            memo: Some("card".to_string()),
+                        },
+                    ],
+                    memo: None,
+                })
+                .unwrap()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_insert_transactions_bulk() {
+        let pool = test_pool().await;
+        let expense = get_account_by_code(&pool, "5100")
+            .await
+            .unwrap()
+            .unwrap()
+            .id;
+        let card = get_account_by_code(&pool, "2000")
+            .await
+            .unwrap()
+            .unwrap()
+            .id;
+        // Enough to span several statements of each kind.
+        let transactions = supply_purchases(expense.unwrap(), card.unwrap(), 1_203);
+        let ids = insert_transactions_bulk(&pool, &transactions)
+            .await
+            .unwrap();
+        assert_eq!(ids.len(), 1_203);
+
+        for (n, id) in [(0, ids[0]), (700, ids[700]), (1_202, ids[1_202])] {
+            let (description, total): (String, i64) = sqlx::query_as(
+                "SELECT description, balanced_total_cents FROM transactions WHERE id = ?",
+            )
+            .bind(id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+            assert_eq!(description, format!("Purchase {n}"));
+            assert_eq!(total, n as i64 + 1);
+        }
+        let (lines, debits, credits): (i64, i64, i64) = sqlx::query_as(
+            "SELECT COUNT(*), SUM(debit_cents), SUM(credit_cents) FROM transaction_lines",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(lines, 2_406);
+        assert_eq!(debits, 1_203 * 1_204 / 2);
+        assert_eq!(debits, credits);
+
+        assert!(insert_transactions_bulk(&pool, &[])
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_insert_and_list_transactions() {
+        let pool = test_pool().await;
+        let expense = get_account_by_code(&pool, "5100")
+            .await
+            .unwrap()
+            .unwrap()
+            .id;
+        let card = get_account_by_code(&pool, "2000")
+            .await
+            .unwrap()
+            .unwrap()
+            .id;
+        let mut purchases = supply_purchases(expense.unwrap(), card.unwrap(), 3);
+        purchases[2].date = NaiveDate::from_ymd_opt(2026, 4, 2).unwrap();
+
+        let mut conn = pool.acquire().await.unwrap();
+        let personal = Deductibility {
+            is_personal: true,
+            deductible_percent: 0,
+        };
+        let record = insert_transaction(&mut conn, &purchases[0], personal)
+            .await
+            .unwrap();
+        assert!(record.is_personal);
+        assert_eq!(record.balanced_total_cents, 1);
+        for t in &purchases[1..] {
+            insert_transaction(&mut conn, t, Deductibility::default())
+                .await
+                .unwrap();
+        }
+        drop(conn);
+
+        assert!(transaction_exists(&pool, record.id).await.unwrap());
+        assert_eq!(count_transactions(&pool).await.unwrap(), 3);
+        let newest = get_transactions(&pool, None, Some(1)).await.unwrap();
+        assert_eq!(newest[0].description, "Purchase 2");
+        let march = DateRange::new(
+            NaiveDate::from_ymd_opt(2026, 3, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2026, 3, 31).unwrap(),
+        );
+        let in_march = get_transactions(&pool, Some(march), None).await.unwrap();
+        let ids: Vec<i64> = in_march.iter().map(|t| t.id).collect();
+        assert_eq!(ids, [record.id + 1, record.id]);
+    }
 }
@@ -0,0 +1,160 @@
+//! Database and storage statistics for support and health checks.
+
+use std::fs;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::db::DbPool;
+use crate::migrate::PendingMigration;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TableCount {
+    pub table: String,
+    pub rows: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageDiagnostics {
+    pub db_path: String,
+    pub db_size_bytes: u64,
+    /// Size of the write-ahead log next to the database; 0 when there is
+    /// none.
+    pub wal_size_bytes: u64,
+    pub journal_mode: String,
+    pub sqlite_version: String,
+    pub schema_version: i64,
+    pub pending_migrations: Vec<PendingMigration>,
+    pub tables: Vec<TableCount>,
+    pub attachments_dir: String,
+    pub attachment_count: u64,
+    pub attachments_size_bytes: u64,
+    /// When the last local or cloud backup was made, if ever.
+    pub last_backup_at: Option<String>,
+    /// Statement balances the ledger no longer agrees with.
+    pub balance_drift: Vec<crate::balance_assertions::AssertionCheck>,
+}
+
+/// Gather statistics about the database at `db_path` and the attachment
+/// store. Missing files count as empty rather than failing.
+pub async fn collect(
+    pool: &DbPool,
+    db_path: &Path,
+    attachments_dir: &Path,
+) -> Result<StorageDiagnostics, sqlx::Error> {
+    let tables: Vec<(String,)> = sqlx::query_as(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name",
+    )
+    .fetch_all(pool)
+    .await?;
+    let mut counts = Vec::with_capacity(tables.len());
+    for (table,) in tables {
+        let sql = format!("SELECT COUNT(*) FROM \"{}\"", table.replace('"', "\"\""));
+        let (rows,): (i64,) = sqlx::query_as(&sql).fetch_one(pool).await?;
+        counts.push(TableCount { table, rows });
+    }
+
+    let (journal_mode,): (String,) = sqlx::query_as("PRAGMA journal_mode")
+        .fetch_one(pool)
+        .await?;
+    let (sqlite_version,): (String,) = sqlx::query_as("SELECT sqlite_version()")
+        .fetch_one(pool)
+        .await?;
+    let mut wal_path = db_path.as_os_str().to_owned();
+    wal_path.push("-wal");
+    let (attachment_count, attachments_size_bytes) = dir_usage(attachments_dir);
+
+    Ok(StorageDiagnostics {
+        db_path: db_path.to_string_lossy().to_string(),
+        db_size_bytes: file_size(db_path),
+        wal_size_bytes: file_size(Path::new(&wal_path)),
+        journal_mode,
+        sqlite_version,
+        schema_version: crate::migrate::current_version(pool).await?,
+        pending_migrations: crate::migrate::pending_migrations(pool).await?,
+        tables: counts,
+        attachments_dir: attachments_dir.to_string_lossy().to_string(),
+        attachment_count,
+        attachments_size_bytes,
+        last_backup_at: crate::db::get_setting(pool, crate::backup::LAST_BACKUP_SETTING).await?,
+        balance_drift: crate::balance_assertions::balance_drift(pool).await?,
+    })
+}
+
+fn file_size(path: &Path) -> u64 {
+    fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+/// Number of files under `dir` and their total size.
+fn dir_usage(dir: &Path) -> (u64, u64) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return (0, 0);
+    };
+    let mut usage = (0, 0);
+    for entry in entries.flatten() {
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        if meta.is_dir() {
+            let (files, bytes) = dir_usage(&entry.path());
+            usage.0 += files;
+            usage.1 += bytes;
+        } else if meta.is_file() {
+            usage.0 += 1;
+            usage.1 += meta.len();
+        }
+    }
+    usage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn reports_sizes_counts_and_last_backup() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("ledger.db");
+        let attachments = tmp.path().join("attachments");
+        fs::create_dir_all(attachments.join("2026")).unwrap();
+        fs::write(attachments.join("a.jpg"), [0u8; 10]).unwrap();
+        fs::write(attachments.join("2026/b.pdf"), [0u8; 5]).unwrap();
+
+        let pool = crate::db::create_db(&db_path).await.unwrap();
+        crate::db::seed_default_accounts(&pool).await.unwrap();
+
+        let report = collect(&pool, &db_path, &attachments).await.unwrap();
+        assert!(report.db_size_bytes > 0);
+        assert_eq!(report.journal_mode, "wal");
+        assert!(report.pending_migrations.is_empty());
+        assert!(report.schema_version > 0);
+        let accounts = report
+            .tables
+            .iter()
+            .find(|t| t.table == "accounts")
+            .unwrap();
+        assert_eq!(accounts.rows, aequi_core::DEFAULT_ACCOUNTS.len() as i64);
+        assert!(report
+            .tables
+            .iter()
+            .all(|t| !t.table.starts_with("sqlite_")));
+        assert_eq!(report.attachment_count, 2);
+        assert_eq!(report.attachments_size_bytes, 15);
+        assert_eq!(report.last_backup_at, None);
+
+        crate::backup::create_backup(
+            &pool,
+            &db_path,
+            &attachments,
+            &tmp.path().join("backup.tar.gz"),
+            "test",
+        )
+        .await
+        .unwrap();
+        let report = collect(&pool, &db_path, &tmp.path().join("missing"))
+            .await
+            .unwrap();
+        assert!(report.last_backup_at.is_some());
+        assert_eq!(report.attachment_count, 0);
+    }
+}
@@ -0,0 +1,595 @@
+//! Filed documents: contracts, warranties, statements and tax forms kept in
+//! the attachment store with a type, the period they cover, who they are
+//! from or with, and optionally the transaction or account they belong to.
+//!
+//! [`archive_fiscal_year`] bundles a finished year for safekeeping: its
+//! documents, the attachments and receipts of its transactions and the
+//! year-end reports, in one zip.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::{Seek, Write};
+use std::path::Path;
+
+use aequi_core::DateRange;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use zip::write::SimpleFileOptions;
+use zip::CompressionMethod;
+
+use crate::attachment_store::resolve_path;
+use crate::attachments::AttachmentEntity;
+use crate::db::DbPool;
+use crate::receipt_export::{csv_field, slug, ExportError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentType {
+    Contract,
+    Warranty,
+    Statement,
+    TaxForm,
+    Insurance,
+    Other,
+}
+
+impl DocumentType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DocumentType::Contract => "contract",
+            DocumentType::Warranty => "warranty",
+            DocumentType::Statement => "statement",
+            DocumentType::TaxForm => "tax_form",
+            DocumentType::Insurance => "insurance",
+            DocumentType::Other => "other",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Document {
+    pub id: i64,
+    pub doc_type: String,
+    pub title: String,
+    pub period_start: Option<String>,
+    pub period_end: Option<String>,
+    pub contact_id: Option<i64>,
+    pub contact_name: Option<String>,
+    /// `transaction` or `account`, with `entity_id`, when the document is
+    /// linked to one.
+    pub entity_type: Option<String>,
+    pub entity_id: Option<i64>,
+    pub notes: Option<String>,
+    pub file_hash: String,
+    pub file_ext: String,
+    pub file_name: Option<String>,
+    pub size_bytes: i64,
+    pub attachment_path: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct NewDocument<'a> {
+    pub doc_type: DocumentType,
+    pub title: &'a str,
+    pub period_start: Option<NaiveDate>,
+    pub period_end: Option<NaiveDate>,
+    pub contact_id: Option<i64>,
+    pub entity: Option<AttachmentEntity>,
+    pub notes: Option<&'a str>,
+    pub file_hash: &'a str,
+    pub file_ext: &'a str,
+    pub file_name: Option<&'a str>,
+    pub size_bytes: i64,
+    pub attachment_path: &'a str,
+}
+
+/// Document search. Unset fields don't filter.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct DocumentSearch {
+    /// Case-insensitive substring of the title, notes, file name or
+    /// counterparty.
+    pub text: Option<String>,
+    pub doc_type: Option<DocumentType>,
+    pub contact_id: Option<i64>,
+    pub entity: Option<AttachmentEntity>,
+    /// Documents whose period overlaps this range. Documents without a
+    /// period count as covering the day they were filed.
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+const DOCUMENT_SELECT: &str = r#"SELECT d.id, d.doc_type, d.title, d.period_start, d.period_end,
+           d.contact_id, c.name AS contact_name, d.entity_type, d.entity_id, d.notes,
+           d.file_hash, d.file_ext, d.file_name, d.size_bytes, d.attachment_path, d.created_at
+       FROM documents d LEFT JOIN contacts c ON c.id = d.contact_id"#;
+
+/// First and last day a document covers.
+const PERIOD_FROM: &str = "COALESCE(d.period_start, d.period_end, date(d.created_at))";
+const PERIOD_TO: &str = "COALESCE(d.period_end, d.period_start, date(d.created_at))";
+
+pub async fn insert_document(pool: &DbPool, doc: &NewDocument<'_>) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"INSERT INTO documents (doc_type, title, period_start, period_end, contact_id,
+               entity_type, entity_id, notes, file_hash, file_ext, file_name, size_bytes,
+               attachment_path)
+           VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+           RETURNING id"#,
+    )
+    .bind(doc.doc_type.as_str())
+    .bind(doc.title)
+    .bind(doc.period_start.map(|d| d.to_string()))
+    .bind(doc.period_end.map(|d| d.to_string()))
+    .bind(doc.contact_id)
+    .bind(doc.entity.map(|e| e.entity_type()))
+    .bind(doc.entity.map(|e| e.id()))
+    .bind(doc.notes)
+    .bind(doc.file_hash)
+    .bind(doc.file_ext)
+    .bind(doc.file_name)
+    .bind(doc.size_bytes)
+    .bind(doc.attachment_path)
+    .fetch_one(pool)
+    .await
+}
+
+pub async fn get_document(pool: &DbPool, id: i64) -> Result<Option<Document>, sqlx::Error> {
+    sqlx::query_as::<_, Document>(&format!("{DOCUMENT_SELECT} WHERE d.id = ?"))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Documents matching `q`, most recent period first.
+pub async fn search_documents(
+    pool: &DbPool,
+    q: &DocumentSearch,
+) -> Result<Vec<Document>, sqlx::Error> {
+    let text = q.text.as_deref().map(str::trim).filter(|t| !t.is_empty());
+    let sql = format!(
+        r#"{DOCUMENT_SELECT}
+           WHERE (?1 IS NULL OR instr(LOWER(d.title || ' ' || COALESCE(d.notes, '') || ' '
+                                      || COALESCE(d.file_name, '') || ' '
+                                      || COALESCE(c.name, '')), LOWER(?1)) > 0)
+             AND (?2 IS NULL OR d.doc_type = ?2)
+             AND (?3 IS NULL OR d.contact_id = ?3)
+             AND (?4 IS NULL OR (d.entity_type = ?4 AND d.entity_id = ?5))
+             AND (?6 IS NULL OR {PERIOD_TO} >= ?6)
+             AND (?7 IS NULL OR {PERIOD_FROM} <= ?7)
+           ORDER BY {PERIOD_TO} DESC, d.id DESC"#
+    );
+    sqlx::query_as::<_, Document>(&sql)
+        .bind(text)
+        .bind(q.doc_type.map(|t| t.as_str()))
+        .bind(q.contact_id)
+        .bind(q.entity.map(|e| e.entity_type()))
+        .bind(q.entity.map(|e| e.id()))
+        .bind(q.from.map(|d| d.to_string()))
+        .bind(q.to.map(|d| d.to_string()))
+        .fetch_all(pool)
+        .await
+}
+
+/// Remove a document. Returns it, with whether its file is still in use
+/// elsewhere (and so must stay in the store).
+pub async fn delete_document(
+    pool: &DbPool,
+    id: i64,
+) -> Result<Option<(Document, bool)>, sqlx::Error> {
+    let Some(document) = get_document(pool, id).await? else {
+        return Ok(None);
+    };
+    sqlx::query("DELETE FROM documents WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    let in_use = crate::attachments::file_in_use(pool, &document.attachment_path).await?;
+    Ok(Some((document, in_use)))
+}
+
+/// What went into a year's archive.
+#[derive(Debug, Clone, Serialize)]
+pub struct YearArchiveSummary {
+    pub document_count: u64,
+    pub attachment_count: u64,
+    pub receipt_count: u64,
+    pub report_count: u64,
+    /// Stored files that could not be read, by archive folder and id.
+    pub missing_files: Vec<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct ArchivedAttachment {
+    id: i64,
+    date: String,
+    description: String,
+    file_ext: String,
+    file_name: Option<String>,
+    attachment_path: String,
+}
+
+const DOCUMENT_INDEX_HEADER: &str =
+    "file,document_id,type,title,period_start,period_end,counterparty,linked_to,notes";
+
+/// Write everything kept for `period` to a zip at `output_path`, reading
+/// stored files from the attachment store at `attachments_dir`:
+///
+/// - `documents/`: documents whose period overlaps the year, with an index
+/// - `attachments/`: files attached to the year's transactions
+/// - `receipts/`: the year's receipts, as [`crate::receipt_export`] writes them
+/// - `reports/`: profit and loss and the year-end balance sheet, as CSV and
+///   JSON, plus any `extra_reports` the caller rendered itself
+pub async fn archive_fiscal_year(
+    pool: &DbPool,
+    attachments_dir: &Path,
+    period: DateRange,
+    extra_reports: Vec<(String, Vec<u8>)>,
+    output_path: &Path,
+) -> Result<YearArchiveSummary, ExportError> {
+    let db_err = |e: sqlx::Error| ExportError::Database(e.to_string());
+    let documents = search_documents(
+        pool,
+        &DocumentSearch {
+            from: Some(period.start),
+            to: Some(period.end),
+            ..Default::default()
+        },
+    )
+    .await
+    .map_err(db_err)?;
+    let attachments = sqlx::query_as::<_, ArchivedAttachment>(
+        r#"SELECT a.id, t.date, t.description, a.file_ext, a.file_name, a.attachment_path
+           FROM attachments a JOIN transactions t ON t.id = a.entity_id
+           WHERE a.entity_type = 'transaction' AND t.date >= ? AND t.date <= ?
+           ORDER BY t.date, a.id"#,
+    )
+    .bind(period.start.to_string())
+    .bind(period.end.to_string())
+    .fetch_all(pool)
+    .await
+    .map_err(db_err)?;
+
+    let profit_loss = crate::reports::profit_loss(pool, period)
+        .await
+        .map_err(db_err)?;
+    let balance_sheet = crate::reports::balance_sheet(pool, period.end)
+        .await
+        .map_err(db_err)?;
+    let json = |value: serde_json::Result<Vec<u8>>| {
+        value.map_err(|e| ExportError::Io(format!("Failed to write report: {e}")))
+    };
+    let mut reports = vec![
+        (
+            "profit_and_loss.csv".to_string(),
+            profit_loss.table().to_csv().into_bytes(),
+        ),
+        (
+            "profit_and_loss.json".to_string(),
+            json(serde_json::to_vec_pretty(&profit_loss))?,
+        ),
+        (
+            "balance_sheet.csv".to_string(),
+            balance_sheet.table().to_csv().into_bytes(),
+        ),
+        (
+            "balance_sheet.json".to_string(),
+            json(serde_json::to_vec_pretty(&balance_sheet))?,
+        ),
+    ];
+    reports.extend(extra_reports);
+
+    let output_file = fs::File::create(output_path)
+        .map_err(|e| ExportError::Io(format!("Failed to create archive: {e}")))?;
+    let mut archive = zip::ZipWriter::new(output_file);
+    let mut missing_files = Vec::new();
+
+    let mut index = String::from(DOCUMENT_INDEX_HEADER);
+    index.push('\n');
+    for doc in &documents {
+        let name = format!(
+            "{}/{}_{}.{}",
+            doc.doc_type,
+            doc.id,
+            file_stem(doc.file_name.as_deref(), &doc.title),
+            doc.file_ext
+        );
+        let file = if add_stored_file(
+            &mut archive,
+            "documents/",
+            &name,
+            &resolve_path(attachments_dir, &doc.attachment_path),
+        )? {
+            name
+        } else {
+            missing_files.push(format!("documents/{}", doc.id));
+            String::new()
+        };
+        let linked_to = match (&doc.entity_type, doc.entity_id) {
+            (Some(kind), Some(id)) => format!("{kind} {id}"),
+            _ => String::new(),
+        };
+        let fields = [
+            file,
+            doc.id.to_string(),
+            doc.doc_type.clone(),
+            doc.title.clone(),
+            doc.period_start.clone().unwrap_or_default(),
+            doc.period_end.clone().unwrap_or_default(),
+            doc.contact_name.clone().unwrap_or_default(),
+            linked_to,
+            doc.notes.clone().unwrap_or_default(),
+        ];
+        let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        index.push_str(&line.join(","));
+        index.push('\n');
+    }
+    add_file(&mut archive, "documents/index.csv", index.as_bytes(), true)?;
+
+    let mut names = HashSet::new();
+    for a in &attachments {
+        let mut name = format!(
+            "{}_{}.{}",
+            a.date,
+            file_stem(a.file_name.as_deref(), &a.description),
+            a.file_ext
+        );
+        if !names.insert(name.clone()) {
+            name = format!("{}_{}_{}", a.date, a.id, &name[a.date.len() + 1..]);
+        }
+        if !add_stored_file(
+            &mut archive,
+            "attachments/",
+            &name,
+            &resolve_path(attachments_dir, &a.attachment_path),
+        )? {
+            missing_files.push(format!("attachments/{}", a.id));
+        }
+    }
+
+    let receipts = crate::receipt_export::write_receipts(
+        pool,
+        attachments_dir,
+        &period.start.to_string(),
+        &period.end.to_string(),
+        &mut archive,
+        "receipts/",
+    )
+    .await?;
+    missing_files.extend(
+        receipts
+            .missing_files
+            .iter()
+            .map(|id| format!("receipts/{id}")),
+    );
+
+    for (name, data) in &reports {
+        add_file(&mut archive, &format!("reports/{name}"), data, true)?;
+    }
+    archive
+        .finish()
+        .map_err(|e| ExportError::Io(format!("Failed to finalize archive: {e}")))?;
+
+    Ok(YearArchiveSummary {
+        document_count: documents.len() as u64,
+        attachment_count: attachments.len() as u64,
+        receipt_count: receipts.receipt_count,
+        report_count: reports.len() as u64,
+        missing_files,
+    })
+}
+
+/// The original file name without its extension, or the title, made safe
+/// for a file name.
+fn file_stem(file_name: Option<&str>, title: &str) -> String {
+    let stem = file_name
+        .map(|n| n.rsplit_once('.').map_or(n, |(stem, _)| stem))
+        .map(slug)
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| slug(title));
+    if stem.is_empty() {
+        "document".to_string()
+    } else {
+        stem
+    }
+}
+
+/// Copy a stored file into the archive. Returns false if it can't be read.
+fn add_stored_file<W: Write + Seek>(
+    archive: &mut zip::ZipWriter<W>,
+    folder: &str,
+    name: &str,
+    path: &Path,
+) -> Result<bool, ExportError> {
+    match fs::read(path) {
+        // PDFs and photos are already compressed.
+        Ok(data) => add_file(archive, &format!("{folder}{name}"), &data, false).map(|()| true),
+        Err(_) => Ok(false),
+    }
+}
+
+fn add_file<W: Write + Seek>(
+    archive: &mut zip::ZipWriter<W>,
+    name: &str,
+    data: &[u8],
+    compress: bool,
+) -> Result<(), ExportError> {
+    let options = if compress {
+        SimpleFileOptions::default()
+    } else {
+        SimpleFileOptions::default().compression_method(CompressionMethod::Stored)
+    };
+    archive
+        .start_file(name, options)
+        .and_then(|()| archive.write_all(data).map_err(Into::into))
+        .map_err(|e| ExportError::Io(format!("Failed to add {name}: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+    use std::io::Read;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn contract(path: &str) -> NewDocument<'_> {
+        NewDocument {
+            doc_type: DocumentType::Contract,
+            title: "Office lease",
+            period_start: Some(date(2024, 7, 1)),
+            period_end: Some(date(2025, 6, 30)),
+            contact_id: Some(1),
+            entity: None,
+            notes: None,
+            file_hash: path,
+            file_ext: "pdf",
+            file_name: Some("Lease 2024.pdf"),
+            size_bytes: 10,
+            attachment_path: path,
+        }
+    }
+
+    #[tokio::test]
+    async fn search_and_archive_a_year() {
+        let pool = test_pool().await;
+        let tmp = tempfile::tempdir().unwrap();
+        sqlx::query("INSERT INTO contacts (id, name) VALUES (1, 'Acme Leasing')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let tx: i64 = sqlx::query_scalar(
+            "INSERT INTO transactions (date, description, balanced_total_cents) VALUES ('2025-03-02', 'Laptop', 150000) RETURNING id",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+        let lease = tmp.path().join("lease.pdf");
+        fs::write(&lease, b"%PDF lease").unwrap();
+        let warranty = tmp.path().join("warranty.pdf");
+        fs::write(&warranty, b"%PDF warranty").unwrap();
+        let lease_path = lease.to_string_lossy().into_owned();
+        let warranty_path = warranty.to_string_lossy().into_owned();
+        let lease_id = insert_document(&pool, &contract(&lease_path))
+            .await
+            .unwrap();
+        let warranty_id = insert_document(
+            &pool,
+            &NewDocument {
+                doc_type: DocumentType::Warranty,
+                title: "Laptop warranty",
+                period_start: Some(date(2026, 3, 2)),
+                period_end: Some(date(2028, 3, 1)),
+                contact_id: None,
+                entity: Some(AttachmentEntity::Transaction(tx)),
+                notes: Some("Three years, on site"),
+                file_name: None,
+                ..contract(&warranty_path)
+            },
+        )
+        .await
+        .unwrap();
+        crate::attachments::insert_attachment(
+            &pool,
+            &crate::attachments::NewAttachment {
+                entity: AttachmentEntity::Transaction(tx),
+                file_hash: "w",
+                file_ext: "pdf",
+                file_name: Some("invoice.pdf"),
+                size_bytes: 13,
+                attachment_path: &warranty_path,
+            },
+        )
+        .await
+        .unwrap();
+
+        let search = |q: DocumentSearch| {
+            let pool = pool.clone();
+            async move {
+                search_documents(&pool, &q)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|d| d.id)
+                    .collect::<Vec<_>>()
+            }
+        };
+        assert_eq!(
+            search(DocumentSearch::default()).await,
+            [warranty_id, lease_id]
+        );
+        let text = |t: &str| DocumentSearch {
+            text: Some(t.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(search(text("acme")).await, [lease_id]);
+        assert_eq!(search(text("ON SITE")).await, [warranty_id]);
+        assert_eq!(
+            search(DocumentSearch {
+                entity: Some(AttachmentEntity::Transaction(tx)),
+                ..Default::default()
+            })
+            .await,
+            [warranty_id]
+        );
+        let year_2025 = DocumentSearch {
+            from: Some(date(2025, 1, 1)),
+            to: Some(date(2025, 12, 31)),
+            ..Default::default()
+        };
+        assert_eq!(search(year_2025).await, [lease_id]);
+
+        let output = tmp.path().join("fy2025.zip");
+        let summary = archive_fiscal_year(
+            &pool,
+            tmp.path(),
+            DateRange::new(date(2025, 1, 1), date(2025, 12, 31)),
+            vec![("schedule_c.csv".to_string(), b"line,amount\n".to_vec())],
+            &output,
+        )
+        .await
+        .unwrap();
+        assert_eq!(summary.document_count, 1);
+        assert_eq!(summary.attachment_count, 1);
+        assert_eq!(summary.report_count, 5);
+        assert!(summary.missing_files.is_empty());
+
+        let mut zip = zip::ZipArchive::new(fs::File::open(&output).unwrap()).unwrap();
+        let mut lease_copy = String::new();
+        zip.by_name(&format!("documents/contract/{lease_id}_Lease-2024.pdf"))
+            .unwrap()
+            .read_to_string(&mut lease_copy)
+            .unwrap();
+        assert_eq!(lease_copy, "%PDF lease");
+        let mut index = String::new();
+        zip.by_name("documents/index.csv")
+            .unwrap()
+            .read_to_string(&mut index)
+            .unwrap();
+        assert!(index.contains(",Office lease,2024-07-01,2025-06-30,Acme Leasing,,"));
+        for name in [
+            "attachments/2025-03-02_invoice.pdf",
+            "receipts/index.csv",
+            "reports/profit_and_loss.csv",
+            "reports/balance_sheet.json",
+            "reports/schedule_c.csv",
+        ] {
+            assert!(zip.by_name(name).is_ok(), "{name} missing");
+        }
+
+        // Deleting the transaction unlinks the warranty rather than removing
+        // it, and takes the attachment sharing its file.
+        sqlx::query("DELETE FROM transactions WHERE id = ?")
+            .bind(tx)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let doc = get_document(&pool, warranty_id).await.unwrap().unwrap();
+        assert_eq!(doc.entity_id, None);
+        let (_, in_use) = delete_document(&pool, warranty_id).await.unwrap().unwrap();
+        assert!(!in_use);
+    }
+}
@@ -0,0 +1,240 @@
+//! Database errors sorted by what the caller can do about them, and a retry
+//! policy for the ones that clear up on their own.
+//!
+//! `busy_timeout` makes SQLite wait for a lock, but not in every case: a
+//! deferred transaction that read before another connection committed gets
+//! `SQLITE_BUSY` straight away when it tries to write, since waiting can't
+//! help it. Running the whole transaction again can, so write paths that
+//! race each other go through [`with_retry`].
+
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+// SQLite primary result codes. Extended codes carry these in the low byte.
+const SQLITE_BUSY: i64 = 5;
+const SQLITE_LOCKED: i64 = 6;
+const SQLITE_CORRUPT: i64 = 11;
+const SQLITE_CONSTRAINT: i64 = 19;
+const SQLITE_NOTADB: i64 = 26;
+
+#[derive(Debug, thiserror::Error)]
+pub enum StorageError {
+    #[error("Record not found")]
+    NotFound,
+    /// A unique, foreign key or check constraint refused the write.
+    #[error("Conflicts with existing data: {0}")]
+    Conflict(String),
+    /// Another connection held the lock for longer than we waited.
+    #[error("Database is busy")]
+    Busy,
+    /// The file isn't a database or its pages don't add up.
+    #[error("Database file is damaged: {0}")]
+    Corruption(String),
+    /// A saved value can't be read back, like a malformed date.
+    #[error("Saved data can't be read: {0}")]
+    InvalidData(String),
+    #[error("Database error: {0}")]
+    Database(sqlx::Error),
+}
+
+impl StorageError {
+    /// Whether trying again later may succeed.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, StorageError::Busy)
+    }
+}
+
+impl From<sqlx::Error> for StorageError {
+    fn from(e: sqlx::Error) -> Self {
+        match &e {
+            sqlx::Error::RowNotFound => return StorageError::NotFound,
+            sqlx::Error::PoolTimedOut => return StorageError::Busy,
+            sqlx::Error::Database(db_err) => {
+                let primary = db_err
+                    .code()
+                    .and_then(|code| code.parse::<i64>().ok())
+                    .map(|code| code & 0xff);
+                match primary {
+                    Some(SQLITE_BUSY | SQLITE_LOCKED) => return StorageError::Busy,
+                    Some(SQLITE_CONSTRAINT) => {
+                        return StorageError::Conflict(db_err.message().to_string())
+                    }
+                    Some(SQLITE_CORRUPT | SQLITE_NOTADB) => {
+                        return StorageError::Corruption(db_err.message().to_string())
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+        StorageError::Database(e)
+    }
+}
+
+/// How often and how patiently to rerun an operation that hit a busy
+/// database.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Attempts in all, counting the first.
+    pub max_attempts: u32,
+    /// Upper bound of the wait before the first retry; it doubles after each.
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(20),
+            max_delay: Duration::from_millis(500),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A random wait of up to the backoff for `retry` (counting from 0), so
+    /// writers that collided don't all come back at the same moment.
+    fn delay(&self, retry: u32) -> Duration {
+        let ceiling = self
+            .base_delay
+            .saturating_mul(1 << retry.min(16))
+            .min(self.max_delay);
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u32(retry);
+        let fraction = (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64;
+        ceiling.mul_f64(fraction)
+    }
+}
+
+/// Run `op` until it succeeds, fails with something other than a busy
+/// database, or `policy` runs out of attempts. `op` should be a whole SQL
+/// transaction, begun and committed inside it, so a retry starts clean.
+pub async fn with_retry<T, F, Fut>(policy: &RetryPolicy, mut op: F) -> Result<T, StorageError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, sqlx::Error>>,
+{
+    let mut retry = 0;
+    loop {
+        match op().await.map_err(StorageError::from) {
+            Err(e) if e.is_transient() && retry + 1 < policy.max_attempts => {
+                tokio::time::sleep(policy.delay(retry)).await;
+                retry += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::DbPool;
+    use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+    use sqlx::Connection;
+    use std::path::Path;
+
+    async fn file_pool(path: &Path) -> DbPool {
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            .busy_timeout(Duration::ZERO);
+        SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_classifies_sqlite_errors() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::query("CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT UNIQUE)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO t (name) VALUES ('a')")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let duplicate = sqlx::query("INSERT INTO t (name) VALUES ('a')")
+            .execute(&pool)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            StorageError::from(duplicate),
+            StorageError::Conflict(_)
+        ));
+
+        let missing = sqlx::query_scalar::<_, i64>("SELECT id FROM t WHERE name = 'b'")
+            .fetch_one(&pool)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            StorageError::from(missing),
+            StorageError::NotFound
+        ));
+
+        let syntax = sqlx::query("SELEC 1").execute(&pool).await.unwrap_err();
+        assert!(matches!(
+            StorageError::from(syntax),
+            StorageError::Database(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_retry_waits_out_a_busy_writer() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("busy.db");
+        let pool = file_pool(&path).await;
+        sqlx::query("CREATE TABLE t (id INTEGER PRIMARY KEY)")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // A second connection takes the write lock and keeps it for a while.
+        let mut holder =
+            sqlx::SqliteConnection::connect_with(&SqliteConnectOptions::new().filename(&path))
+                .await
+                .unwrap();
+        sqlx::query("BEGIN IMMEDIATE")
+            .execute(&mut holder)
+            .await
+            .unwrap();
+        let released = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            sqlx::query("COMMIT").execute(&mut holder).await.unwrap();
+        });
+
+        let insert = || async {
+            sqlx::query("INSERT INTO t DEFAULT VALUES")
+                .execute(&pool)
+                .await
+        };
+        let impatient = RetryPolicy {
+            max_attempts: 1,
+            ..RetryPolicy::default()
+        };
+        assert!(matches!(
+            with_retry(&impatient, insert).await,
+            Err(StorageError::Busy)
+        ));
+
+        let patient = RetryPolicy {
+            max_attempts: 20,
+            base_delay: Duration::from_millis(20),
+            max_delay: Duration::from_millis(50),
+        };
+        with_retry(&patient, insert).await.unwrap();
+        released.await.unwrap();
+    }
+}
@@ -0,0 +1,325 @@
+//! Equipment capitalized as fixed assets.
+//!
+//! Equipment is booked to the Equipment expense account like any other
+//! purchase. One costing at least the capitalize threshold can instead be
+//! capitalized: its equipment lines move to the fixed asset account and an
+//! asset record keeps its cost and recovery period, from which the
+//! depreciation schedule is worked out. Depreciation itself isn't posted.
+
+use aequi_core::tax::{straight_line_schedule, DepreciationYear};
+use aequi_core::{AccountType, EQUIPMENT_EXPENSE_CODE};
+use serde::{Deserialize, Serialize};
+
+use crate::db::{get_account_by_code, get_setting, DbPool};
+
+pub const CAPITALIZE_THRESHOLD_SETTING: &str = "capitalize_threshold_cents";
+
+/// The de minimis safe harbor lets items up to $2,500 be expensed outright.
+pub const DEFAULT_CAPITALIZE_THRESHOLD_CENTS: i64 = 250_000;
+
+/// The asset account capitalized equipment moves to, created when first
+/// needed.
+pub const FIXED_ASSET_ACCOUNT_CODE: &str = "1500";
+const FIXED_ASSET_ACCOUNT_NAME: &str = "Equipment (Fixed Assets)";
+
+/// Computers and office equipment are five-year property.
+pub const DEFAULT_RECOVERY_YEARS: u8 = 5;
+
+#[derive(Debug, thiserror::Error)]
+pub enum FixedAssetError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Invalid(String),
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct FixedAsset {
+    pub id: i64,
+    pub transaction_id: i64,
+    pub description: String,
+    /// The purchase date.
+    pub placed_in_service: String,
+    pub cost_cents: i64,
+    pub recovery_years: u8,
+    pub account_code: String,
+    pub created_at: String,
+    #[sqlx(skip)]
+    pub depreciation: Vec<DepreciationYear>,
+}
+
+impl FixedAsset {
+    fn with_depreciation(mut self) -> Self {
+        let year = self
+            .placed_in_service
+            .get(..4)
+            .and_then(|y| y.parse().ok())
+            .unwrap_or_default();
+        self.depreciation = straight_line_schedule(self.cost_cents, self.recovery_years, year);
+        self
+    }
+}
+
+/// Overrides for [`capitalize_equipment`].
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CapitalizeInput {
+    /// The transaction's description unless given.
+    pub description: Option<String>,
+    /// [`DEFAULT_RECOVERY_YEARS`] unless given.
+    pub recovery_years: Option<u8>,
+}
+
+const ASSET_SELECT: &str = r#"SELECT f.id, f.transaction_id, f.description, f.placed_in_service,
+       f.cost_cents, f.recovery_years, a.code AS account_code, f.created_at
+    FROM fixed_assets f JOIN accounts a ON a.id = f.account_id"#;
+
+pub async fn get_capitalize_threshold_cents(pool: &DbPool) -> Result<i64, sqlx::Error> {
+    Ok(get_setting(pool, CAPITALIZE_THRESHOLD_SETTING)
+        .await?
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_CAPITALIZE_THRESHOLD_CENTS))
+}
+
+/// Net debits a transaction booked to the Equipment account.
+async fn equipment_cents(pool: &DbPool, transaction_id: i64) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"SELECT COALESCE(SUM(tl.debit_cents - tl.credit_cents), 0)
+           FROM transaction_lines tl JOIN accounts a ON a.id = tl.account_id
+           WHERE tl.transaction_id = ? AND a.code = ?"#,
+    )
+    .bind(transaction_id)
+    .bind(EQUIPMENT_EXPENSE_CODE)
+    .fetch_one(pool)
+    .await
+}
+
+/// Whether a business transaction booked at least the capitalize threshold
+/// to Equipment, and so might be capitalized instead.
+pub async fn should_offer_capitalize(
+    pool: &DbPool,
+    transaction_id: i64,
+) -> Result<bool, sqlx::Error> {
+    let is_personal: Option<bool> =
+        sqlx::query_scalar("SELECT is_personal FROM transactions WHERE id = ?")
+            .bind(transaction_id)
+            .fetch_optional(pool)
+            .await?;
+    if is_personal != Some(false) {
+        return Ok(false);
+    }
+    Ok(
+        equipment_cents(pool, transaction_id).await?
+            >= get_capitalize_threshold_cents(pool).await?,
+    )
+}
+
+/// Capitalize what a transaction booked to Equipment: move those lines to
+/// the fixed asset account and record the asset.
+pub async fn capitalize_equipment(
+    pool: &DbPool,
+    transaction_id: i64,
+    input: &CapitalizeInput,
+) -> Result<FixedAsset, FixedAssetError> {
+    let (date, description, is_personal): (String, String, bool) =
+        sqlx::query_as("SELECT date, description, is_personal FROM transactions WHERE id = ?")
+            .bind(transaction_id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| {
+                FixedAssetError::NotFound(format!("Transaction {transaction_id} not found"))
+            })?;
+    if is_personal {
+        return Err(FixedAssetError::Invalid(
+            "Personal purchases can't be capitalized".into(),
+        ));
+    }
+    let recovery_years = input.recovery_years.unwrap_or(DEFAULT_RECOVERY_YEARS);
+    if recovery_years == 0 {
+        return Err(FixedAssetError::Invalid(
+            "Recovery period must be at least a year".into(),
+        ));
+    }
+    let cost_cents = equipment_cents(pool, transaction_id).await?;
+    if cost_cents <= 0 {
+        return Err(FixedAssetError::Invalid(
+            "Nothing in this transaction is booked to Equipment".into(),
+        ));
+    }
+    let equipment = get_account_by_code(pool, EQUIPMENT_EXPENSE_CODE)
+        .await?
+        .and_then(|a| a.id)
+        .ok_or_else(|| {
+            FixedAssetError::NotFound(format!("Account {EQUIPMENT_EXPENSE_CODE} not found"))
+        })?;
+    let asset_account = get_account_by_code(pool, FIXED_ASSET_ACCOUNT_CODE).await?;
+    if let Some(account) = &asset_account {
+        if account.account_type != AccountType::Asset || account.is_archived {
+            return Err(FixedAssetError::Invalid(format!(
+                "Account {FIXED_ASSET_ACCOUNT_CODE} {} must be an active asset account",
+                account.name
+            )));
+        }
+    }
+    let description = input
+        .description
+        .as_deref()
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .unwrap_or(&description)
+        .to_string();
+
+    let mut tx = pool.begin().await?;
+    let asset_account_id: i64 = match asset_account.and_then(|a| a.id) {
+        Some(id) => id.0,
+        None => {
+            sqlx::query_scalar(
+                r#"INSERT INTO accounts (code, name, account_type, is_archetype)
+                   VALUES (?, ?, 'Asset', 0) RETURNING id"#,
+            )
+            .bind(FIXED_ASSET_ACCOUNT_CODE)
+            .bind(FIXED_ASSET_ACCOUNT_NAME)
+            .fetch_one(&mut *tx)
+            .await?
+        }
+    };
+    sqlx::query(
+        "UPDATE transaction_lines SET account_id = ? WHERE transaction_id = ? AND account_id = ?",
+    )
+    .bind(asset_account_id)
+    .bind(transaction_id)
+    .bind(equipment.0)
+    .execute(&mut *tx)
+    .await?;
+    let id: i64 = sqlx::query_scalar(
+        r#"INSERT INTO fixed_assets (transaction_id, description, placed_in_service,
+           cost_cents, recovery_years, account_id) VALUES (?, ?, ?, ?, ?, ?) RETURNING id"#,
+    )
+    .bind(transaction_id)
+    .bind(&description)
+    .bind(&date)
+    .bind(cost_cents)
+    .bind(recovery_years)
+    .bind(asset_account_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    get_fixed_asset(pool, id)
+        .await?
+        .ok_or_else(|| FixedAssetError::NotFound(format!("Fixed asset {id} not found")))
+}
+
+pub async fn get_fixed_asset(pool: &DbPool, id: i64) -> Result<Option<FixedAsset>, sqlx::Error> {
+    Ok(
+        sqlx::query_as::<_, FixedAsset>(&format!("{ASSET_SELECT} WHERE f.id = ?"))
+            .bind(id)
+            .fetch_optional(pool)
+            .await?
+            .map(FixedAsset::with_depreciation),
+    )
+}
+
+/// Fixed assets, oldest first, each with its depreciation schedule.
+pub async fn get_fixed_assets(pool: &DbPool) -> Result<Vec<FixedAsset>, sqlx::Error> {
+    Ok(sqlx::query_as::<_, FixedAsset>(&format!(
+        "{ASSET_SELECT} ORDER BY f.placed_in_service, f.id"
+    ))
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(FixedAsset::with_depreciation)
+    .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::set_setting;
+    use crate::db::test_pool;
+
+    async fn buy(pool: &DbPool, description: &str, cents: i64) -> i64 {
+        let id: i64 = sqlx::query_scalar(
+            r#"INSERT INTO transactions (date, description, balanced_total_cents)
+               VALUES ('2026-04-10', ?, ?) RETURNING id"#,
+        )
+        .bind(description)
+        .bind(cents)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        for (code, debit, credit) in [("5040", cents, 0), ("2000", 0, cents)] {
+            sqlx::query(
+                r#"INSERT INTO transaction_lines (transaction_id, account_id, debit_cents,
+                   credit_cents) SELECT ?, id, ?, ? FROM accounts WHERE code = ?"#,
+            )
+            .bind(id)
+            .bind(debit)
+            .bind(credit)
+            .bind(code)
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+        id
+    }
+
+    #[tokio::test]
+    async fn offers_to_capitalize_over_the_threshold() {
+        let pool = test_pool().await;
+        let mouse = buy(&pool, "Mouse", 4_000).await;
+        let laptop = buy(&pool, "Laptop", 300_000).await;
+        assert!(!should_offer_capitalize(&pool, mouse).await.unwrap());
+        assert!(should_offer_capitalize(&pool, laptop).await.unwrap());
+
+        set_setting(&pool, CAPITALIZE_THRESHOLD_SETTING, "2500")
+            .await
+            .unwrap();
+        assert!(should_offer_capitalize(&pool, mouse).await.unwrap());
+        assert!(!should_offer_capitalize(&pool, 999).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn capitalizing_moves_the_expense_to_an_asset() {
+        let pool = test_pool().await;
+        let laptop = buy(&pool, "Laptop", 300_000).await;
+        let asset = capitalize_equipment(
+            &pool,
+            laptop,
+            &CapitalizeInput {
+                description: Some("MacBook Pro".into()),
+                recovery_years: None,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(asset.description, "MacBook Pro");
+        assert_eq!(asset.cost_cents, 300_000);
+        assert_eq!(asset.account_code, FIXED_ASSET_ACCOUNT_CODE);
+        assert_eq!(asset.depreciation.len(), 6);
+        assert_eq!(asset.depreciation[0].year, 2026);
+        assert_eq!(asset.depreciation[0].amount_cents, 30_000);
+
+        let moved: Vec<String> = sqlx::query_scalar(
+            r#"SELECT a.code FROM transaction_lines tl JOIN accounts a ON a.id = tl.account_id
+               WHERE tl.transaction_id = ? ORDER BY a.code"#,
+        )
+        .bind(laptop)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(moved, ["1500", "2000"]);
+        assert!(!should_offer_capitalize(&pool, laptop).await.unwrap());
+
+        assert!(matches!(
+            capitalize_equipment(&pool, laptop, &CapitalizeInput::default()).await,
+            Err(FixedAssetError::Invalid(_))
+        ));
+        let chair = buy(&pool, "Chair", 260_000).await;
+        capitalize_equipment(&pool, chair, &CapitalizeInput::default())
+            .await
+            .unwrap();
+        assert_eq!(get_fixed_assets(&pool).await.unwrap().len(), 2);
+    }
+}
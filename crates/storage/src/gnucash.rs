@@ -0,0 +1,619 @@
+//! Importing GnuCash books.
+//!
+//! [`read_sqlite_book`] reads a book saved in GnuCash's SQLite format into
+//! the same [`GnucashBook`] the XML reader produces, and [`import_book`]
+//! brings either into the ledger: GnuCash accounts are matched to existing
+//! ones or created, and transactions are posted in the base currency.
+//! Imported transactions are remembered by their GnuCash guid, so a book
+//! can be imported again after more work was done in GnuCash.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use aequi_core::import::gnucash::{
+    account_type, parse_date, rational_cents, Commodity, GnucashAccount, GnucashBook, GnucashError,
+    GnucashSplit, GnucashTransaction,
+};
+use aequi_core::{
+    Account, AccountId, AccountType, Money, TransactionLine, UnvalidatedTransaction,
+    ValidatedTransaction,
+};
+use serde::Serialize;
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{ConnectOptions, Connection};
+
+use crate::db::{get_all_accounts, DbPool};
+
+/// `source` of GnuCash rows in `external_transaction_ids`.
+pub const GNUCASH_SOURCE: &str = "gnucash";
+
+#[derive(Debug, thiserror::Error)]
+pub enum GnucashImportError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error(transparent)]
+    Gnucash(#[from] GnucashError),
+}
+
+/// What importing a book did.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GnucashImportSummary {
+    pub accounts_created: usize,
+    pub accounts_matched: usize,
+    pub transactions_imported: usize,
+    /// Transactions brought in by an earlier import of the same book.
+    pub already_imported: usize,
+    /// Transactions and accounts that couldn't be brought in, and why.
+    pub skipped: Vec<String>,
+}
+
+/// Read a book saved in GnuCash's SQLite format. Scheduled transaction
+/// templates are left out.
+pub async fn read_sqlite_book(path: &Path) -> Result<GnucashBook, sqlx::Error> {
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .read_only(true)
+        .disable_statement_logging();
+    let mut conn = sqlx::SqliteConnection::connect_with(&options).await?;
+
+    let template_root: Option<(Option<String>,)> =
+        sqlx::query_as("SELECT root_template_guid FROM books LIMIT 1")
+            .fetch_optional(&mut conn)
+            .await?;
+    let template_root = template_root.and_then(|(guid,)| guid);
+
+    let commodities: HashMap<String, Commodity> = sqlx::query_as::<_, (String, String, String)>(
+        "SELECT guid, namespace, mnemonic FROM commodities",
+    )
+    .fetch_all(&mut conn)
+    .await?
+    .into_iter()
+    .map(|(guid, space, id)| (guid, Commodity { space, id }))
+    .collect();
+    let commodity = |guid: &Option<String>| {
+        guid.as_ref()
+            .and_then(|g| commodities.get(g))
+            .cloned()
+            .unwrap_or_else(|| Commodity {
+                space: "CURRENCY".to_string(),
+                id: String::new(),
+            })
+    };
+
+    let rows = sqlx::query_as::<
+        _,
+        (
+            String,
+            String,
+            String,
+            Option<String>,
+            Option<String>,
+            Option<String>,
+            Option<String>,
+            Option<i64>,
+        ),
+    >(
+        r#"SELECT guid, name, account_type, commodity_guid, parent_guid, code, description,
+                  placeholder
+           FROM accounts"#,
+    )
+    .fetch_all(&mut conn)
+    .await?;
+    let mut accounts: Vec<GnucashAccount> = rows
+        .into_iter()
+        .map(
+            |(guid, name, account_type, commodity_guid, parent, code, description, placeholder)| {
+                GnucashAccount {
+                    guid,
+                    name,
+                    account_type,
+                    parent,
+                    commodity: commodity(&commodity_guid),
+                    code: code.filter(|c| !c.trim().is_empty()),
+                    description: description.filter(|d| !d.trim().is_empty()),
+                    placeholder: placeholder.unwrap_or(0) != 0,
+                }
+            },
+        )
+        .collect();
+
+    // Drop the template tree: the template root and everything below it.
+    if let Some(template_root) = template_root {
+        let mut templates: HashSet<String> = HashSet::from([template_root]);
+        loop {
+            let before = templates.len();
+            for account in &accounts {
+                if account
+                    .parent
+                    .as_ref()
+                    .is_some_and(|p| templates.contains(p))
+                {
+                    templates.insert(account.guid.clone());
+                }
+            }
+            if templates.len() == before {
+                break;
+            }
+        }
+        accounts.retain(|a| !templates.contains(&a.guid));
+    }
+    let known: HashSet<&str> = accounts.iter().map(|a| a.guid.as_str()).collect();
+
+    let notes: HashMap<String, String> = sqlx::query_as::<_, (String, Option<String>)>(
+        "SELECT obj_guid, string_val FROM slots WHERE name = 'notes'",
+    )
+    .fetch_all(&mut conn)
+    .await?
+    .into_iter()
+    .filter_map(|(guid, note)| note.filter(|n| !n.trim().is_empty()).map(|n| (guid, n)))
+    .collect();
+
+    let mut splits: HashMap<String, Vec<GnucashSplit>> = HashMap::new();
+    let split_rows = sqlx::query_as::<_, (String, String, Option<String>, i64, i64, i64, i64)>(
+        r#"SELECT tx_guid, account_guid, memo, value_num, value_denom,
+                  quantity_num, quantity_denom
+           FROM splits"#,
+    )
+    .fetch_all(&mut conn)
+    .await?;
+    let mut template_transactions = HashSet::new();
+    for (tx_guid, account, memo, value_num, value_denom, quantity_num, quantity_denom) in split_rows
+    {
+        if !known.contains(account.as_str()) {
+            template_transactions.insert(tx_guid);
+            continue;
+        }
+        let cents = |num: i64, denom: i64| {
+            rational_cents(&format!("{num}/{denom}")).map_err(|e| sqlx::Error::Decode(Box::new(e)))
+        };
+        splits.entry(tx_guid).or_default().push(GnucashSplit {
+            account,
+            value_cents: cents(value_num, value_denom)?,
+            quantity_cents: cents(quantity_num, quantity_denom)?,
+            memo: memo.filter(|m| !m.trim().is_empty()),
+        });
+    }
+
+    let tx_rows = sqlx::query_as::<_, (String, Option<String>, Option<String>, Option<String>, Option<String>)>(
+        "SELECT guid, currency_guid, num, post_date, description FROM transactions ORDER BY post_date",
+    )
+    .fetch_all(&mut conn)
+    .await?;
+    let mut transactions = Vec::new();
+    for (guid, currency_guid, num, post_date, description) in tx_rows {
+        if template_transactions.contains(&guid) {
+            continue;
+        }
+        let date = parse_date(post_date.as_deref().unwrap_or_default())
+            .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+        transactions.push(GnucashTransaction {
+            date,
+            description: description.unwrap_or_default(),
+            num: num.filter(|n| !n.trim().is_empty()),
+            notes: notes.get(&guid).cloned(),
+            currency: commodity(&currency_guid),
+            splits: splits.remove(&guid).unwrap_or_default(),
+            guid,
+        });
+    }
+    conn.close().await?;
+
+    Ok(GnucashBook {
+        accounts,
+        transactions,
+    })
+}
+
+/// First code of each account type's range, e.g. 5000 for expenses.
+fn code_base(account_type: AccountType) -> u32 {
+    match account_type {
+        AccountType::Asset => 1000,
+        AccountType::Liability => 2000,
+        AccountType::Equity => 3000,
+        AccountType::Income => 4000,
+        AccountType::Expense => 5000,
+    }
+}
+
+/// An unused code in the account type's range, on a multiple of ten if one
+/// is free.
+fn next_code(account_type: AccountType, taken: &HashSet<String>) -> Option<String> {
+    let base = code_base(account_type);
+    (base..base + 1000)
+        .step_by(10)
+        .chain(base..base + 1000)
+        .map(|c| c.to_string())
+        .find(|c| !taken.contains(c))
+}
+
+/// Bring a GnuCash book into the ledger, converting amounts to
+/// `base_currency`.
+///
+/// Each GnuCash account is matched to an existing account of the same type
+/// by code, then by name; otherwise one is created. Placeholders and parent
+/// accounts without postings of their own are left out: the hierarchy is
+/// kept in the names of accounts that need it, not as accounts. Transactions already
+/// imported are skipped, as are ones that can't be converted.
+pub async fn import_book(
+    pool: &DbPool,
+    book: &GnucashBook,
+    base_currency: &str,
+) -> Result<GnucashImportSummary, GnucashImportError> {
+    let mut summary = GnucashImportSummary::default();
+    let existing = get_all_accounts(pool).await?;
+    let mut taken: HashSet<String> = sqlx::query_as::<_, (String,)>("SELECT code FROM accounts")
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|(code,)| code)
+        .collect();
+    let mut names: HashSet<String> = sqlx::query_as::<_, (String,)>("SELECT name FROM accounts")
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|(name,)| name.to_lowercase())
+        .collect();
+    let used: HashSet<&str> = book
+        .transactions
+        .iter()
+        .flat_map(|t| t.splits.iter().map(|s| s.account.as_str()))
+        .collect();
+    let parents: HashSet<&str> = book
+        .accounts
+        .iter()
+        .filter_map(|a| a.parent.as_deref())
+        .collect();
+
+    let mut db_tx = pool.begin().await?;
+    let mut ids: HashMap<&str, AccountId> = HashMap::new();
+    for account in &book.accounts {
+        let Some(kind) = account_type(&account.account_type) else {
+            if account.account_type != "ROOT" && used.contains(account.guid.as_str()) {
+                summary.skipped.push(format!(
+                    "Account {}: GnuCash type {} has no equivalent",
+                    book.account_path(&account.guid),
+                    account.account_type
+                ));
+            }
+            continue;
+        };
+        if (account.placeholder || parents.contains(account.guid.as_str()))
+            && !used.contains(account.guid.as_str())
+        {
+            continue;
+        }
+        let path = book.account_path(&account.guid);
+        if let Some(id) = matching_account(&existing, account, &path, kind) {
+            ids.insert(&account.guid, id);
+            summary.accounts_matched += 1;
+            continue;
+        }
+
+        let code = match account.code.as_deref() {
+            Some(code) if !taken.contains(code) => Some(code.to_string()),
+            _ => next_code(kind, &taken),
+        };
+        let Some(code) = code else {
+            summary
+                .skipped
+                .push(format!("Account {path}: no free {kind} account codes"));
+            continue;
+        };
+        let name = if names.contains(&account.name.to_lowercase()) {
+            path
+        } else {
+            account.name.clone()
+        };
+        let id = sqlx::query(
+            "INSERT INTO accounts (code, name, account_type, is_archetype) VALUES (?, ?, ?, 0)",
+        )
+        .bind(&code)
+        .bind(&name)
+        .bind(kind.to_string())
+        .execute(&mut *db_tx)
+        .await?
+        .last_insert_rowid();
+        taken.insert(code);
+        names.insert(name.to_lowercase());
+        ids.insert(&account.guid, AccountId(id));
+        summary.accounts_created += 1;
+    }
+
+    for gnc_tx in &book.transactions {
+        let seen: Option<(i64,)> = sqlx::query_as(
+            "SELECT transaction_id FROM external_transaction_ids WHERE source = ? AND external_id = ?",
+        )
+        .bind(GNUCASH_SOURCE)
+        .bind(&gnc_tx.guid)
+        .fetch_optional(&mut *db_tx)
+        .await?;
+        if seen.is_some() {
+            summary.already_imported += 1;
+            continue;
+        }
+
+        let validated = match to_transaction(book, gnc_tx, &ids, base_currency) {
+            Ok(Some(validated)) => validated,
+            Ok(None) => continue,
+            Err(reason) => {
+                summary.skipped.push(reason);
+                continue;
+            }
+        };
+        let id = sqlx::query(
+            "INSERT INTO transactions (date, description, memo, balanced_total_cents) VALUES (?, ?, ?, ?)",
+        )
+        .bind(validated.date.to_string())
+        .bind(&validated.description)
+        .bind(&validated.memo)
+        .bind(validated.balanced_total.to_cents())
+        .execute(&mut *db_tx)
+        .await?
+        .last_insert_rowid();
+        for line in &validated.lines {
+            sqlx::query(
+                "INSERT INTO transaction_lines (transaction_id, account_id, debit_cents, credit_cents, memo) VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(id)
+            .bind(line.account_id.0)
+            .bind(line.debit.to_cents())
+            .bind(line.credit.to_cents())
+            .bind(&line.memo)
+            .execute(&mut *db_tx)
+            .await?;
+        }
+        sqlx::query(
+            "INSERT INTO external_transaction_ids (source, external_id, transaction_id) VALUES (?, ?, ?)",
+        )
+        .bind(GNUCASH_SOURCE)
+        .bind(&gnc_tx.guid)
+        .bind(id)
+        .execute(&mut *db_tx)
+        .await?;
+        summary.transactions_imported += 1;
+    }
+    db_tx.commit().await?;
+    Ok(summary)
+}
+
+/// An existing account of the same type with the GnuCash account's code,
+/// or with its name or full path (which accounts created from a clashing
+/// name get).
+fn matching_account(
+    existing: &[Account],
+    account: &GnucashAccount,
+    path: &str,
+    kind: AccountType,
+) -> Option<AccountId> {
+    let same_type = || existing.iter().filter(move |a| a.account_type == kind);
+    account
+        .code
+        .as_deref()
+        .and_then(|code| same_type().find(|a| a.code == code))
+        .or_else(|| {
+            same_type().find(|a| {
+                a.name.eq_ignore_ascii_case(account.name.trim())
+                    || a.name.eq_ignore_ascii_case(path)
+            })
+        })
+        .and_then(|a| a.id)
+}
+
+/// The ledger transaction for a GnuCash one: `Ok(None)` when it has no
+/// amounts at all, `Err` with the reason when it can't be brought in.
+fn to_transaction(
+    book: &GnucashBook,
+    gnc_tx: &GnucashTransaction,
+    ids: &HashMap<&str, AccountId>,
+    base_currency: &str,
+) -> Result<Option<ValidatedTransaction>, String> {
+    let amounts = book.base_amounts(gnc_tx, base_currency)?;
+    let mut lines = Vec::new();
+    for (split, cents) in gnc_tx.splits.iter().zip(amounts) {
+        if cents == 0 {
+            continue;
+        }
+        let account_id = *ids.get(split.account.as_str()).ok_or_else(|| {
+            format!(
+                "{} ({}): account {} wasn't imported",
+                gnc_tx.description,
+                gnc_tx.date,
+                book.account_path(&split.account)
+            )
+        })?;
+        lines.push(TransactionLine {
+            account_id,
+            debit: Money::from_cents(cents.max(0)),
+            credit: Money::from_cents((-cents).max(0)),
+            memo: split.memo.clone(),
+        });
+    }
+    if lines.is_empty() {
+        return Ok(None);
+    }
+    let description = match (&gnc_tx.num, gnc_tx.description.trim()) {
+        (Some(num), "") => format!("#{num}"),
+        (_, "") => "GnuCash transaction".to_string(),
+        (_, description) => description.to_string(),
+    };
+    ValidatedTransaction::validate(UnvalidatedTransaction {
+        date: gnc_tx.date,
+        description,
+        lines,
+        memo: gnc_tx.notes.clone(),
+    })
+    .map(Some)
+    .map_err(|e| format!("{} ({}): {e}", gnc_tx.description, gnc_tx.date))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    fn usd() -> Commodity {
+        Commodity {
+            space: "CURRENCY".to_string(),
+            id: "USD".to_string(),
+        }
+    }
+
+    fn account(guid: &str, name: &str, kind: &str, parent: Option<&str>) -> GnucashAccount {
+        GnucashAccount {
+            guid: guid.to_string(),
+            name: name.to_string(),
+            account_type: kind.to_string(),
+            parent: parent.map(str::to_string),
+            commodity: usd(),
+            code: None,
+            description: None,
+            placeholder: false,
+        }
+    }
+
+    fn split(account: &str, cents: i64) -> GnucashSplit {
+        GnucashSplit {
+            account: account.to_string(),
+            value_cents: cents,
+            quantity_cents: cents,
+            memo: None,
+        }
+    }
+
+    fn transaction(guid: &str, description: &str, splits: Vec<GnucashSplit>) -> GnucashTransaction {
+        GnucashTransaction {
+            guid: guid.to_string(),
+            date: chrono::NaiveDate::from_ymd_opt(2026, 2, 3).unwrap(),
+            description: description.to_string(),
+            num: None,
+            notes: None,
+            currency: usd(),
+            splits,
+        }
+    }
+
+    fn sample_book() -> GnucashBook {
+        let mut assets = account("assets", "Assets", "ASSET", Some("root"));
+        assets.placeholder = true;
+        let mut checking = account("checking", "Checking", "BANK", Some("assets"));
+        checking.code = Some("1000".to_string());
+        GnucashBook {
+            accounts: vec![
+                account("root", "Root Account", "ROOT", None),
+                assets,
+                checking,
+                account("expenses", "Expenses", "EXPENSE", Some("root")),
+                account("fuel", "Fuel", "EXPENSE", Some("expenses")),
+                account("auto", "Checking", "EXPENSE", Some("expenses")),
+                account("shares", "Shares", "STOCK", Some("assets")),
+            ],
+            transactions: vec![
+                transaction(
+                    "t1",
+                    "Gas",
+                    vec![split("fuel", 4000), split("checking", -4000)],
+                ),
+                transaction(
+                    "t2",
+                    "Oil change",
+                    vec![split("auto", 6000), split("checking", -6000)],
+                ),
+                transaction(
+                    "t3",
+                    "Broken",
+                    vec![split("fuel", 100), split("checking", -50)],
+                ),
+            ],
+        }
+    }
+
+    #[tokio::test]
+    async fn imports_accounts_and_transactions() {
+        let pool = test_pool().await;
+        let summary = import_book(&pool, &sample_book(), "USD").await.unwrap();
+
+        // Checking matched by code; the Assets and Expenses parents left
+        // out; Fuel, Shares and the second "Checking" created.
+        assert_eq!(summary.accounts_matched, 1);
+        assert_eq!(summary.accounts_created, 3);
+        assert_eq!(summary.transactions_imported, 2);
+        assert_eq!(summary.skipped.len(), 1);
+        assert!(summary.skipped[0].starts_with("Broken"));
+
+        let accounts = get_all_accounts(&pool).await.unwrap();
+        let fuel = accounts.iter().find(|a| a.name == "Fuel").unwrap();
+        assert_eq!(fuel.account_type, AccountType::Expense);
+        assert!(fuel.code.starts_with('5'));
+        assert!(accounts.iter().any(|a| a.name == "Expenses:Checking"));
+
+        let (debits,): (i64,) = sqlx::query_as(
+            "SELECT SUM(debit_cents) FROM transaction_lines tl JOIN accounts a ON a.id = tl.account_id WHERE a.id = ?",
+        )
+        .bind(fuel.id.unwrap().0)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(debits, 4000);
+    }
+
+    #[tokio::test]
+    async fn reimport_skips_known_transactions() {
+        let pool = test_pool().await;
+        import_book(&pool, &sample_book(), "USD").await.unwrap();
+        let again = import_book(&pool, &sample_book(), "USD").await.unwrap();
+        assert_eq!(again.accounts_created, 0);
+        assert_eq!(again.transactions_imported, 0);
+        assert_eq!(again.already_imported, 2);
+
+        let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM transactions")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+    }
+
+    #[tokio::test]
+    async fn reads_sqlite_books() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("book.gnucash");
+        let options = SqliteConnectOptions::new()
+            .filename(&path)
+            .create_if_missing(true);
+        let mut conn = sqlx::SqliteConnection::connect_with(&options)
+            .await
+            .unwrap();
+        for sql in [
+            "CREATE TABLE books (guid TEXT, root_account_guid TEXT, root_template_guid TEXT)",
+            "CREATE TABLE commodities (guid TEXT, namespace TEXT, mnemonic TEXT)",
+            "CREATE TABLE accounts (guid TEXT, name TEXT, account_type TEXT, commodity_guid TEXT, parent_guid TEXT, code TEXT, description TEXT, placeholder INTEGER)",
+            "CREATE TABLE transactions (guid TEXT, currency_guid TEXT, num TEXT, post_date TEXT, description TEXT)",
+            "CREATE TABLE splits (guid TEXT, tx_guid TEXT, account_guid TEXT, memo TEXT, value_num INTEGER, value_denom INTEGER, quantity_num INTEGER, quantity_denom INTEGER)",
+            "CREATE TABLE slots (obj_guid TEXT, name TEXT, string_val TEXT)",
+            "INSERT INTO books VALUES ('b', 'root', 'troot')",
+            "INSERT INTO commodities VALUES ('usd', 'CURRENCY', 'USD')",
+            "INSERT INTO accounts VALUES ('root', 'Root Account', 'ROOT', NULL, NULL, '', '', 0)",
+            "INSERT INTO accounts VALUES ('troot', 'Template Root', 'ROOT', NULL, NULL, '', '', 0)",
+            "INSERT INTO accounts VALUES ('tacct', 'x', 'BANK', 'usd', 'troot', '', '', 0)",
+            "INSERT INTO accounts VALUES ('checking', 'Checking', 'BANK', 'usd', 'root', '1000', '', 0)",
+            "INSERT INTO accounts VALUES ('fees', 'Fees', 'EXPENSE', 'usd', 'root', '', 'Bank fees', 0)",
+            "INSERT INTO transactions VALUES ('t1', 'usd', '', '2026-02-03 10:59:00', 'Monthly fee')",
+            "INSERT INTO transactions VALUES ('t2', 'usd', '', '2026-02-04 10:59:00', 'Scheduled')",
+            "INSERT INTO splits VALUES ('s1', 't1', 'fees', '', 1500, 100, 1500, 100)",
+            "INSERT INTO splits VALUES ('s2', 't1', 'checking', '', -15, 1, -15, 1)",
+            "INSERT INTO splits VALUES ('s3', 't2', 'tacct', '', 100, 100, 100, 100)",
+            "INSERT INTO slots VALUES ('t1', 'notes', 'Waived next month')",
+        ] {
+            sqlx::query(sql).execute(&mut conn).await.unwrap();
+        }
+        conn.close().await.unwrap();
+
+        let book = read_sqlite_book(&path).await.unwrap();
+        assert_eq!(book.accounts.len(), 3);
+        assert_eq!(book.transactions.len(), 1);
+        let tx = &book.transactions[0];
+        assert_eq!(tx.notes.as_deref(), Some("Waived next month"));
+        assert_eq!(tx.splits[1].value_cents, -1500);
+        assert_eq!(book.base_amounts(tx, "USD").unwrap(), vec![1500, -1500]);
+        assert_eq!(book.account("fees").unwrap().code, None);
+    }
+}
@@ -0,0 +1,282 @@
+//! Checks on the ledger file before it is opened, and the local snapshots it
+//! can be put back from when they fail.
+//!
+//! Snapshots are plain copies of the database made with `VACUUM INTO`,
+//! named `ledger-<UTC timestamp>.db`, at most one a day. Unlike backup
+//! archives they leave the attachments out: a damaged ledger doesn't touch
+//! the files it points at.
+
+use crate::db::DbPool;
+use crate::error::StorageError;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Serialize;
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{Connection, SqliteConnection};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const SNAPSHOT_PREFIX: &str = "ledger-";
+const SNAPSHOT_SUFFIX: &str = ".db";
+const SNAPSHOT_TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+/// What the checks made of the database file.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum DbHealth {
+    Healthy,
+    /// `PRAGMA integrity_check` found problems, or the file isn't a database.
+    Corrupt {
+        problems: Vec<String>,
+    },
+    /// Written by a newer version of the app; this one doesn't know its schema.
+    TooNew {
+        version: i64,
+        supported: i64,
+    },
+}
+
+/// Run `PRAGMA integrity_check` on the file at `path` and compare its schema
+/// version with the migrations this build has. A file that doesn't exist yet
+/// is healthy. Errors other than corruption, such as the file being
+/// unreadable, are returned as they are.
+pub async fn check_database(path: &Path) -> Result<DbHealth, StorageError> {
+    if !path.exists() {
+        return Ok(DbHealth::Healthy);
+    }
+    match run_checks(path).await.map_err(StorageError::from) {
+        Err(StorageError::Corruption(problem)) => Ok(DbHealth::Corrupt {
+            problems: vec![problem],
+        }),
+        result => result,
+    }
+}
+
+async fn run_checks(path: &Path) -> Result<DbHealth, sqlx::Error> {
+    // Read-only, so closing it can't checkpoint a leftover WAL into a
+    // damaged file.
+    let options = SqliteConnectOptions::new().filename(path).read_only(true);
+    let mut conn = SqliteConnection::connect_with(&options).await?;
+
+    let problems: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check")
+        .fetch_all(&mut conn)
+        .await?;
+    if problems != ["ok"] {
+        conn.close().await?;
+        return Ok(DbHealth::Corrupt { problems });
+    }
+
+    // Databases from before the migration system have no versions table;
+    // the migrations bring them up to date.
+    let has_versions: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'schema_versions')",
+    )
+    .fetch_one(&mut conn)
+    .await?;
+    let version: Option<i64> = if has_versions {
+        sqlx::query_scalar("SELECT MAX(version) FROM schema_versions")
+            .fetch_one(&mut conn)
+            .await?
+    } else {
+        None
+    };
+    conn.close().await?;
+
+    let supported = crate::migrate::latest_version();
+    Ok(match version {
+        Some(version) if version > supported => DbHealth::TooNew { version, supported },
+        _ => DbHealth::Healthy,
+    })
+}
+
+/// A snapshot in the snapshot directory.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Snapshot {
+    pub path: PathBuf,
+    pub created_at: DateTime<Utc>,
+    pub size_bytes: u64,
+}
+
+impl Snapshot {
+    fn from_path(path: PathBuf) -> Option<Self> {
+        let stamp = path
+            .file_name()?
+            .to_str()?
+            .strip_prefix(SNAPSHOT_PREFIX)?
+            .strip_suffix(SNAPSHOT_SUFFIX)?;
+        let created_at = NaiveDateTime::parse_from_str(stamp, SNAPSHOT_TIME_FORMAT)
+            .ok()?
+            .and_utc();
+        let size_bytes = fs::metadata(&path).ok()?.len();
+        Some(Snapshot {
+            path,
+            created_at,
+            size_bytes,
+        })
+    }
+}
+
+/// Snapshots in `dir`, newest first. A missing directory has none.
+pub fn list_snapshots(dir: &Path) -> Vec<Snapshot> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut snapshots: Vec<Snapshot> = entries
+        .flatten()
+        .filter_map(|entry| Snapshot::from_path(entry.path()))
+        .collect();
+    snapshots.sort_by_key(|s| std::cmp::Reverse(s.created_at));
+    snapshots
+}
+
+/// Copy the database into `dir` unless a snapshot was already taken today,
+/// then delete all but the newest `keep`. Returns the new snapshot, if one
+/// was made.
+pub async fn take_daily_snapshot(
+    pool: &DbPool,
+    dir: &Path,
+    keep: usize,
+) -> Result<Option<Snapshot>, StorageError> {
+    let now = Utc::now();
+    let existing = list_snapshots(dir);
+    if existing
+        .first()
+        .is_some_and(|latest| latest.created_at.date_naive() == now.date_naive())
+    {
+        return Ok(None);
+    }
+
+    fs::create_dir_all(dir).map_err(|e| StorageError::Database(sqlx::Error::Io(e)))?;
+    let path = dir.join(format!(
+        "{SNAPSHOT_PREFIX}{}{SNAPSHOT_SUFFIX}",
+        now.format(SNAPSHOT_TIME_FORMAT)
+    ));
+    sqlx::query("VACUUM INTO ?")
+        .bind(path.to_string_lossy())
+        .execute(pool)
+        .await?;
+
+    for old in existing.iter().skip(keep.saturating_sub(1)) {
+        let _ = fs::remove_file(&old.path);
+    }
+    Ok(Snapshot::from_path(path))
+}
+
+/// Put `snapshot` in place of the database at `db_path`. The damaged file
+/// and its WAL and shared-memory files are kept beside it with a
+/// `.damaged-<timestamp>` suffix rather than deleted; the path of the
+/// damaged database is returned. Nothing may have the database open.
+pub fn restore_snapshot(snapshot: &Path, db_path: &Path) -> io::Result<PathBuf> {
+    let suffix = format!(".damaged-{}", Utc::now().format(SNAPSHOT_TIME_FORMAT));
+    let aside = |path: &Path| {
+        let mut name = path.as_os_str().to_owned();
+        name.push(&suffix);
+        PathBuf::from(name)
+    };
+
+    let damaged = aside(db_path);
+    // A WAL left from the damaged file would be replayed over the snapshot.
+    for extra in ["-wal", "-shm"] {
+        let mut name = db_path.as_os_str().to_owned();
+        name.push(extra);
+        let path = PathBuf::from(name);
+        if path.exists() {
+            fs::rename(&path, aside(&path))?;
+        }
+    }
+    if db_path.exists() {
+        fs::rename(db_path, &damaged)?;
+    }
+    fs::copy(snapshot, db_path)?;
+    Ok(damaged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_check_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ledger.db");
+        assert_eq!(check_database(&path).await.unwrap(), DbHealth::Healthy);
+
+        let pool = crate::db::create_db(&path).await.unwrap();
+        assert_eq!(check_database(&path).await.unwrap(), DbHealth::Healthy);
+
+        // As if a newer app had migrated it further.
+        let newer = crate::migrate::latest_version() + 1;
+        sqlx::query(
+            "INSERT INTO schema_versions (version, name, checksum) VALUES (?, 'future', '')",
+        )
+        .bind(newer)
+        .execute(&pool)
+        .await
+        .unwrap();
+        pool.close().await;
+        assert_eq!(
+            check_database(&path).await.unwrap(),
+            DbHealth::TooNew {
+                version: newer,
+                supported: crate::migrate::latest_version(),
+            }
+        );
+
+        let garbage = dir.path().join("garbage.db");
+        fs::write(&garbage, vec![0x5a; 8192]).unwrap();
+        assert!(matches!(
+            check_database(&garbage).await.unwrap(),
+            DbHealth::Corrupt { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_and_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("ledger.db");
+        let snapshots = dir.path().join("snapshots");
+        let pool = crate::db::create_db(&db_path).await.unwrap();
+        crate::db::seed_default_accounts(&pool).await.unwrap();
+
+        // Older snapshots beyond the two kept are pruned.
+        fs::create_dir_all(&snapshots).unwrap();
+        for stamp in ["20240101T000000Z", "20240102T000000Z"] {
+            fs::write(snapshots.join(format!("ledger-{stamp}.db")), b"old").unwrap();
+        }
+        let taken = take_daily_snapshot(&pool, &snapshots, 2)
+            .await
+            .unwrap()
+            .unwrap();
+        let listed = list_snapshots(&snapshots);
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0], taken);
+        assert!(listed[1].path.ends_with("ledger-20240102T000000Z.db"));
+        // One a day.
+        assert!(take_daily_snapshot(&pool, &snapshots, 2)
+            .await
+            .unwrap()
+            .is_none());
+
+        // A damaged ledger with a WAL beside it.
+        let damaged_path = dir.path().join("damaged.db");
+        fs::write(&damaged_path, vec![0x5a; 8192]).unwrap();
+        fs::write(dir.path().join("damaged.db-wal"), b"stale").unwrap();
+        assert!(matches!(
+            check_database(&damaged_path).await.unwrap(),
+            DbHealth::Corrupt { .. }
+        ));
+
+        let damaged = restore_snapshot(&taken.path, &damaged_path).unwrap();
+        assert_eq!(fs::read(&damaged).unwrap(), vec![0x5a; 8192]);
+        assert!(!dir.path().join("damaged.db-wal").exists());
+        assert_eq!(
+            check_database(&damaged_path).await.unwrap(),
+            DbHealth::Healthy
+        );
+        let restored = crate::db::create_db(&damaged_path).await.unwrap();
+        assert_eq!(
+            crate::db::count_active_accounts(&restored).await.unwrap(),
+            crate::db::count_active_accounts(&pool).await.unwrap()
+        );
+    }
+}
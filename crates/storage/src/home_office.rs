@@ -0,0 +1,453 @@
+//! Home office deduction worksheets, one per tax year.
+//!
+//! A worksheet keeps the inputs and the line 30 figure as they were worked
+//! out, for audit support. Home costs can be entered by hand or pulled from
+//! an account: what was booked to it as personal over the calendar year, as
+//! the business share already reaches Schedule C on its own line. Posting
+//! the worksheet books the deduction to the line 30 account on December 31,
+//! after which it can't be changed until that entry is deleted.
+
+use aequi_core::tax::{
+    compute_home_office, HomeExpense, HomeOfficeDeduction, HomeOfficeInput, HomeOfficeMethod,
+};
+use aequi_core::{AccountType, FiscalYear, TaxRules};
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::account_balances::net_debits;
+use crate::db::{build_ledger_snapshot, get_account_by_code, DbPool};
+
+/// Home costs a new worksheet starts with, and the account each is pulled
+/// from by default.
+const DEFAULT_EXPENSES: &[(&str, Option<&str>)] = &[
+    ("Rent", None),
+    ("Mortgage interest", None),
+    ("Real estate taxes", None),
+    ("Homeowners or renters insurance", None),
+    ("Utilities", Some("5130")),
+    ("Repairs and maintenance", None),
+];
+
+/// Where the deduction is credited unless another account is given: home
+/// costs are usually paid personally, so it goes to owner's equity.
+pub const DEFAULT_CREDIT_CODE: &str = "3000";
+
+#[derive(Debug, thiserror::Error)]
+pub enum HomeOfficeError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Invalid(String),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HomeOfficeWorksheet {
+    pub year: u16,
+    #[serde(flatten)]
+    pub input: HomeOfficeInput,
+    /// Schedule C line 29 when the worksheet was saved.
+    pub tentative_profit_cents: i64,
+    #[serde(flatten)]
+    pub deduction: HomeOfficeDeduction,
+    /// The entry posting the deduction, once posted.
+    pub transaction_id: Option<i64>,
+    pub updated_at: String,
+}
+
+#[derive(sqlx::FromRow)]
+struct WorksheetRow {
+    year: i64,
+    method: String,
+    office_sqft: i64,
+    home_sqft: i64,
+    expenses: String,
+    prior_carryover_cents: i64,
+    tentative_profit_cents: i64,
+    allowable_cents: i64,
+    deduction_cents: i64,
+    carryover_cents: i64,
+    transaction_id: Option<i64>,
+    updated_at: String,
+}
+
+impl TryFrom<WorksheetRow> for HomeOfficeWorksheet {
+    type Error = sqlx::Error;
+
+    fn try_from(row: WorksheetRow) -> Result<Self, Self::Error> {
+        let method = HomeOfficeMethod::parse(&row.method).ok_or_else(|| {
+            sqlx::Error::Decode(format!("unknown home office method {}", row.method).into())
+        })?;
+        let expenses: Vec<HomeExpense> =
+            serde_json::from_str(&row.expenses).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+        let input = HomeOfficeInput {
+            method,
+            office_sqft: row.office_sqft as u32,
+            home_sqft: row.home_sqft as u32,
+            expenses,
+            prior_carryover_cents: row.prior_carryover_cents,
+        };
+        let deduction = HomeOfficeDeduction {
+            business_percent: input.business_percent(),
+            expenses_cents: input.expenses_cents(),
+            allowable_cents: row.allowable_cents,
+            deduction_cents: row.deduction_cents,
+            carryover_cents: row.carryover_cents,
+        };
+        Ok(HomeOfficeWorksheet {
+            year: row.year as u16,
+            input,
+            tentative_profit_cents: row.tentative_profit_cents,
+            deduction,
+            transaction_id: row.transaction_id,
+            updated_at: row.updated_at,
+        })
+    }
+}
+
+pub async fn get_home_office_worksheet(
+    pool: &DbPool,
+    year: u16,
+) -> Result<Option<HomeOfficeWorksheet>, sqlx::Error> {
+    let row = sqlx::query_as::<_, WorksheetRow>(
+        r#"SELECT year, method, office_sqft, home_sqft, expenses, prior_carryover_cents,
+           tentative_profit_cents, allowable_cents, deduction_cents, carryover_cents,
+           transaction_id, updated_at
+           FROM home_office_worksheets WHERE year = ?"#,
+    )
+    .bind(year as i64)
+    .fetch_optional(pool)
+    .await?;
+    row.map(HomeOfficeWorksheet::try_from).transpose()
+}
+
+/// Inputs to start the year's worksheet from: the saved ones if any, else
+/// last year's areas and home costs with last year's carryover, else the
+/// default home costs. Amounts for costs pulled from an account are
+/// brought up to date; last year's hand-entered amounts are cleared.
+pub async fn draft_home_office_input(
+    pool: &DbPool,
+    year: u16,
+) -> Result<HomeOfficeInput, sqlx::Error> {
+    let mut input = if let Some(saved) = get_home_office_worksheet(pool, year).await? {
+        saved.input
+    } else if let Some(prior) = get_home_office_worksheet(pool, year - 1).await? {
+        let mut input = prior.input;
+        for expense in &mut input.expenses {
+            expense.amount_cents = 0;
+        }
+        input.prior_carryover_cents = prior.deduction.carryover_cents;
+        input
+    } else {
+        HomeOfficeInput {
+            method: HomeOfficeMethod::Simplified,
+            office_sqft: 0,
+            home_sqft: 0,
+            expenses: DEFAULT_EXPENSES
+                .iter()
+                .map(|(label, code)| HomeExpense {
+                    label: label.to_string(),
+                    account_code: code.map(str::to_string),
+                    amount_cents: 0,
+                })
+                .collect(),
+            prior_carryover_cents: 0,
+        }
+    };
+    pull_account_amounts(pool, year, &mut input.expenses).await?;
+    Ok(input)
+}
+
+/// Set each account-backed cost to what was booked to the account as
+/// personal during `year`.
+async fn pull_account_amounts(
+    pool: &DbPool,
+    year: u16,
+    expenses: &mut [HomeExpense],
+) -> Result<(), sqlx::Error> {
+    if expenses.iter().all(|e| e.account_code.is_none()) {
+        return Ok(());
+    }
+    let fy = FiscalYear::new(year);
+    let (start, end) = (Some(fy.start_date()), fy.end_date());
+    let mut personal: HashMap<String, i64> = HashMap::new();
+    for net in net_debits(pool, start, end, false).await? {
+        *personal.entry(net.code).or_default() += net.net_cents;
+    }
+    for net in net_debits(pool, start, end, true).await? {
+        *personal.entry(net.code).or_default() -= net.net_cents;
+    }
+    for expense in expenses {
+        if let Some(code) = &expense.account_code {
+            expense.amount_cents = personal.get(code).copied().unwrap_or(0).max(0);
+        }
+    }
+    Ok(())
+}
+
+/// Work out and save the year's worksheet against the ledger as it stands.
+pub async fn save_home_office_worksheet(
+    pool: &DbPool,
+    year: u16,
+    mut input: HomeOfficeInput,
+    rules: &TaxRules,
+) -> Result<HomeOfficeWorksheet, HomeOfficeError> {
+    if let Some(saved) = get_home_office_worksheet(pool, year).await? {
+        if saved.transaction_id.is_some() {
+            return Err(HomeOfficeError::Invalid(format!(
+                "The {year} worksheet has been posted; delete its entry to change it"
+            )));
+        }
+    }
+    for code in input
+        .expenses
+        .iter()
+        .filter_map(|e| e.account_code.as_deref())
+    {
+        if get_account_by_code(pool, code).await?.is_none() {
+            return Err(HomeOfficeError::NotFound(format!(
+                "Account {code} not found"
+            )));
+        }
+    }
+    pull_account_amounts(pool, year, &mut input.expenses).await?;
+
+    let snapshot = build_ledger_snapshot(pool, FiscalYear::new(year), None).await?;
+    let tentative_profit = snapshot.tentative_profit(rules);
+    let deduction = compute_home_office(&rules.home_office_simplified, &input, tentative_profit)
+        .map_err(|e| HomeOfficeError::Invalid(e.to_string()))?;
+    let expenses =
+        serde_json::to_string(&input.expenses).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+
+    sqlx::query(
+        r#"INSERT INTO home_office_worksheets (year, method, office_sqft, home_sqft, expenses,
+           prior_carryover_cents, tentative_profit_cents, allowable_cents, deduction_cents,
+           carryover_cents) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+           ON CONFLICT(year) DO UPDATE SET method = excluded.method,
+           office_sqft = excluded.office_sqft, home_sqft = excluded.home_sqft,
+           expenses = excluded.expenses,
+           prior_carryover_cents = excluded.prior_carryover_cents,
+           tentative_profit_cents = excluded.tentative_profit_cents,
+           allowable_cents = excluded.allowable_cents,
+           deduction_cents = excluded.deduction_cents,
+           carryover_cents = excluded.carryover_cents, updated_at = datetime('now')"#,
+    )
+    .bind(year as i64)
+    .bind(input.method.as_str())
+    .bind(input.office_sqft as i64)
+    .bind(input.home_sqft as i64)
+    .bind(expenses)
+    .bind(input.prior_carryover_cents)
+    .bind(tentative_profit.to_cents())
+    .bind(deduction.allowable_cents)
+    .bind(deduction.deduction_cents)
+    .bind(deduction.carryover_cents)
+    .execute(pool)
+    .await?;
+
+    get_home_office_worksheet(pool, year)
+        .await?
+        .ok_or_else(|| HomeOfficeError::NotFound(format!("No {year} worksheet")))
+}
+
+/// Book the saved worksheet's deduction on December 31: a debit to the
+/// line 30 account and a credit to `credit_code`, or
+/// [`DEFAULT_CREDIT_CODE`] if not given. Returns the entry's id.
+pub async fn post_home_office_entry(
+    pool: &DbPool,
+    year: u16,
+    credit_code: Option<&str>,
+) -> Result<i64, HomeOfficeError> {
+    let credit_code = credit_code.unwrap_or(DEFAULT_CREDIT_CODE);
+    let worksheet = get_home_office_worksheet(pool, year)
+        .await?
+        .ok_or_else(|| HomeOfficeError::NotFound(format!("No {year} worksheet saved")))?;
+    if worksheet.transaction_id.is_some() {
+        return Err(HomeOfficeError::Invalid(format!(
+            "The {year} worksheet has already been posted"
+        )));
+    }
+    let amount_cents = worksheet.deduction.deduction_cents;
+    if amount_cents <= 0 {
+        return Err(HomeOfficeError::Invalid(
+            "There is no deduction to post".into(),
+        ));
+    }
+
+    let home_office_id: i64 = sqlx::query_scalar(
+        r#"SELECT id FROM accounts WHERE schedule_c_line = 'line_30' AND is_archived = 0
+           ORDER BY code LIMIT 1"#,
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| HomeOfficeError::NotFound("No active line 30 account".into()))?;
+    let credit = get_account_by_code(pool, credit_code)
+        .await?
+        .ok_or_else(|| HomeOfficeError::NotFound(format!("Account {credit_code} not found")))?;
+    if !matches!(
+        credit.account_type,
+        AccountType::Asset | AccountType::Liability | AccountType::Equity
+    ) || credit.is_archived
+    {
+        return Err(HomeOfficeError::Invalid(format!(
+            "The deduction must be credited to an active balance sheet account, not {} {}",
+            credit.code, credit.name
+        )));
+    }
+
+    let mut tx = pool.begin().await?;
+    let transaction_id: i64 = sqlx::query_scalar(
+        r#"INSERT INTO transactions (date, description, balanced_total_cents)
+           VALUES (?, ?, ?) RETURNING id"#,
+    )
+    .bind(FiscalYear::new(year).end_date().to_string())
+    .bind(format!("Home office deduction {year}"))
+    .bind(amount_cents)
+    .fetch_one(&mut *tx)
+    .await?;
+    for (account_id, debit, credit) in [
+        (Some(home_office_id), amount_cents, 0),
+        (credit.id.map(|id| id.0), 0, amount_cents),
+    ] {
+        sqlx::query(
+            r#"INSERT INTO transaction_lines (transaction_id, account_id, debit_cents,
+               credit_cents) VALUES (?, ?, ?, ?)"#,
+        )
+        .bind(transaction_id)
+        .bind(account_id)
+        .bind(debit)
+        .bind(credit)
+        .execute(&mut *tx)
+        .await?;
+    }
+    sqlx::query("UPDATE home_office_worksheets SET transaction_id = ? WHERE year = ?")
+        .bind(transaction_id)
+        .bind(year as i64)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(transaction_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    fn load_rules() -> TaxRules {
+        TaxRules::from_toml(include_str!("../../core/test_data/tax_rules_2026.toml")).unwrap()
+    }
+
+    async fn post(
+        pool: &DbPool,
+        date: &str,
+        debit: &str,
+        credit: &str,
+        cents: i64,
+        personal: bool,
+    ) {
+        let id: i64 = sqlx::query_scalar(
+            r#"INSERT INTO transactions (date, description, balanced_total_cents, is_personal)
+               VALUES (?, 'test', ?, ?) RETURNING id"#,
+        )
+        .bind(date)
+        .bind(cents)
+        .bind(personal)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        for (code, debit_cents, credit_cents) in [(debit, cents, 0), (credit, 0, cents)] {
+            sqlx::query(
+                r#"INSERT INTO transaction_lines (transaction_id, account_id, debit_cents,
+                   credit_cents) SELECT ?, id, ?, ? FROM accounts WHERE code = ?"#,
+            )
+            .bind(id)
+            .bind(debit_cents)
+            .bind(credit_cents)
+            .bind(code)
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn regular_worksheet_pulls_personal_utilities_and_posts() {
+        let pool = test_pool().await;
+        post(&pool, "2026-02-01", "1000", "4000", 1_000_000, false).await;
+        // Business utilities stay on line 18; the household's are the
+        // home's cost.
+        post(&pool, "2026-03-01", "5130", "1000", 50_000, false).await;
+        post(&pool, "2026-03-05", "5130", "3100", 240_000, true).await;
+
+        let mut input = draft_home_office_input(&pool, 2026).await.unwrap();
+        let utilities = input
+            .expenses
+            .iter()
+            .find(|e| e.account_code.as_deref() == Some("5130"))
+            .unwrap();
+        assert_eq!(utilities.amount_cents, 240_000);
+
+        input.method = HomeOfficeMethod::Regular;
+        input.office_sqft = 150;
+        input.home_sqft = 1_200;
+        input.expenses[0].amount_cents = 2_400_000;
+        let worksheet = save_home_office_worksheet(&pool, 2026, input, &load_rules())
+            .await
+            .unwrap();
+        assert_eq!(worksheet.tentative_profit_cents, 950_000);
+        assert_eq!(worksheet.deduction.expenses_cents, 2_640_000);
+        assert_eq!(worksheet.deduction.deduction_cents, 330_000);
+
+        assert!(matches!(
+            post_home_office_entry(&pool, 2026, Some("4000")).await,
+            Err(HomeOfficeError::Invalid(_))
+        ));
+        post_home_office_entry(&pool, 2026, None).await.unwrap();
+        let snapshot = build_ledger_snapshot(&pool, FiscalYear::new(2026), None)
+            .await
+            .unwrap();
+        assert_eq!(
+            snapshot.line_totals[&aequi_core::ScheduleCLine::Line30].to_cents(),
+            330_000
+        );
+
+        let saved = get_home_office_worksheet(&pool, 2026)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(saved.transaction_id.is_some());
+        assert!(matches!(
+            save_home_office_worksheet(&pool, 2026, saved.input, &load_rules()).await,
+            Err(HomeOfficeError::Invalid(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn next_year_starts_from_this_years_worksheet() {
+        let pool = test_pool().await;
+        post(&pool, "2026-02-01", "1000", "4000", 100_000, false).await;
+        let input = HomeOfficeInput {
+            method: HomeOfficeMethod::Regular,
+            office_sqft: 200,
+            home_sqft: 1_000,
+            expenses: vec![HomeExpense {
+                label: "Rent".into(),
+                account_code: None,
+                amount_cents: 1_800_000,
+            }],
+            prior_carryover_cents: 0,
+        };
+        let worksheet = save_home_office_worksheet(&pool, 2026, input, &load_rules())
+            .await
+            .unwrap();
+        assert_eq!(worksheet.deduction.deduction_cents, 100_000);
+        assert_eq!(worksheet.deduction.carryover_cents, 260_000);
+
+        let draft = draft_home_office_input(&pool, 2027).await.unwrap();
+        assert_eq!(draft.office_sqft, 200);
+        assert_eq!(draft.expenses[0].amount_cents, 0);
+        assert_eq!(draft.prior_carryover_cents, 260_000);
+    }
+}
@@ -0,0 +1,488 @@
+//! Hooks: local scripts or HTTP endpoints notified when something happens in
+//! the books, for wiring aequi into Home Assistant, Slack, an archive, ...
+//!
+//! Each [`Hook`] subscribes to some [`HookEventKind`]s. When one occurs the
+//! app delivers a [`HookPayload`] to every enabled hook subscribed to it: as
+//! the body of a JSON `POST`, or on a script's standard input. Payloads look
+//! like
+//!
+//! ```json
+//! {
+//!   "event": "transaction_posted",
+//!   "occurred_at": "2026-03-15T09:30:00Z",
+//!   "data": { "transaction_id": 42, ... }
+//! }
+//! ```
+//!
+//! where `data` is a [`ReceiptApproved`], [`TransactionPosted`] or
+//! [`ImportCompleted`].
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::db::DbPool;
+
+/// Setting holding [`HookSettings`], as JSON.
+pub const HOOK_SETTINGS: &str = "hooks";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEventKind {
+    ReceiptApproved,
+    TransactionPosted,
+    ImportCompleted,
+}
+
+impl HookEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HookEventKind::ReceiptApproved => "receipt_approved",
+            HookEventKind::TransactionPosted => "transaction_posted",
+            HookEventKind::ImportCompleted => "import_completed",
+        }
+    }
+}
+
+/// Where a hook delivers its payloads.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HookTarget {
+    /// Run a program with the payload on standard input and the event name
+    /// in `AEQUI_EVENT`.
+    Script {
+        path: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+    /// `POST` the payload as JSON.
+    Http {
+        url: String,
+        /// Extra request headers, e.g. an `Authorization` token.
+        #[serde(default)]
+        headers: BTreeMap<String, String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hook {
+    pub name: String,
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+    pub events: Vec<HookEventKind>,
+    pub target: HookTarget,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+impl Hook {
+    /// Why the hook can't be saved, if it can't.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Hook name is required".to_string());
+        }
+        if self.events.is_empty() {
+            return Err(format!("Hook {} has no events", self.name));
+        }
+        match &self.target {
+            HookTarget::Script { path, .. } if path.trim().is_empty() => {
+                Err(format!("Hook {} has no script", self.name))
+            }
+            HookTarget::Http { url, .. }
+                if !(url.starts_with("http://") || url.starts_with("https://")) =>
+            {
+                Err(format!(
+                    "Hook {} needs an http:// or https:// URL",
+                    self.name
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HookSettings {
+    pub hooks: Vec<Hook>,
+}
+
+impl HookSettings {
+    /// The enabled hooks subscribed to `kind`.
+    pub fn subscribed(&self, kind: HookEventKind) -> Vec<&Hook> {
+        self.hooks
+            .iter()
+            .filter(|h| h.enabled && h.events.contains(&kind))
+            .collect()
+    }
+}
+
+pub async fn get_hook_settings(pool: &DbPool) -> Result<HookSettings, sqlx::Error> {
+    match crate::db::get_setting(pool, HOOK_SETTINGS).await? {
+        Some(json) if !json.trim().is_empty() => {
+            serde_json::from_str(&json).map_err(|e| sqlx::Error::Decode(Box::new(e)))
+        }
+        _ => Ok(HookSettings::default()),
+    }
+}
+
+pub async fn set_hook_settings(pool: &DbPool, settings: &HookSettings) -> Result<(), sqlx::Error> {
+    let json = serde_json::to_string(settings).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    crate::db::set_setting(pool, HOOK_SETTINGS, &json).await
+}
+
+// ---------------------------------------------------------------------------
+// Payloads
+// ---------------------------------------------------------------------------
+
+/// A receipt was approved, by hand or automatically.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReceiptApproved {
+    pub receipt_id: i64,
+    /// The transaction it documents, if linked.
+    pub transaction_id: Option<i64>,
+    pub vendor: Option<String>,
+    /// `YYYY-MM-DD`.
+    pub receipt_date: Option<String>,
+    pub total_cents: Option<i64>,
+    pub payment_method: Option<String>,
+    /// Approved by the auto-approval rules rather than the user.
+    pub automatic: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionSource {
+    /// Entered by hand or through the local API.
+    Manual,
+    /// Created from a receipt.
+    Receipt,
+    /// Posted from an import batch.
+    Import,
+    /// Posted when a receipt was auto-approved against a bank row.
+    AutoApproval,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PostedLine {
+    pub account_code: String,
+    pub account_name: String,
+    pub debit_cents: i64,
+    pub credit_cents: i64,
+    pub memo: Option<String>,
+}
+
+/// A transaction was recorded in the ledger.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransactionPosted {
+    pub transaction_id: i64,
+    /// `YYYY-MM-DD`.
+    pub date: String,
+    pub description: String,
+    pub memo: Option<String>,
+    /// Sum of the debits.
+    pub total_cents: i64,
+    pub source: TransactionSource,
+    pub lines: Vec<PostedLine>,
+}
+
+/// A bank file or another app's books finished importing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportCompleted {
+    /// `csv`, `ofx`, `qif`, `mint`, `ynab`, `quicken` or `gnucash`.
+    pub source: String,
+    pub file_name: String,
+    /// Review batches the rows were queued in; empty for imports posted
+    /// straight to the ledger.
+    pub batch_ids: Vec<String>,
+    /// Rows or transactions brought in.
+    pub imported: usize,
+    /// Ones skipped because they were imported before.
+    pub duplicates: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum HookEvent {
+    ReceiptApproved(ReceiptApproved),
+    TransactionPosted(TransactionPosted),
+    ImportCompleted(ImportCompleted),
+}
+
+impl HookEvent {
+    pub fn kind(&self) -> HookEventKind {
+        match self {
+            HookEvent::ReceiptApproved(_) => HookEventKind::ReceiptApproved,
+            HookEvent::TransactionPosted(_) => HookEventKind::TransactionPosted,
+            HookEvent::ImportCompleted(_) => HookEventKind::ImportCompleted,
+        }
+    }
+
+    /// A made-up event of `kind`, for trying a hook out.
+    pub fn sample(kind: HookEventKind) -> Self {
+        match kind {
+            HookEventKind::ReceiptApproved => HookEvent::ReceiptApproved(ReceiptApproved {
+                receipt_id: 1,
+                transaction_id: Some(1),
+                vendor: Some("Office Depot".to_string()),
+                receipt_date: Some("2026-03-15".to_string()),
+                total_cents: Some(4599),
+                payment_method: Some("Visa".to_string()),
+                automatic: false,
+            }),
+            HookEventKind::TransactionPosted => HookEvent::TransactionPosted(TransactionPosted {
+                transaction_id: 1,
+                date: "2026-03-15".to_string(),
+                description: "Office Depot".to_string(),
+                memo: None,
+                total_cents: 4599,
+                source: TransactionSource::Receipt,
+                lines: vec![
+                    PostedLine {
+                        account_code: "5100".to_string(),
+                        account_name: "Office Supplies".to_string(),
+                        debit_cents: 4599,
+                        credit_cents: 0,
+                        memo: None,
+                    },
+                    PostedLine {
+                        account_code: "2000".to_string(),
+                        account_name: "Credit Card".to_string(),
+                        debit_cents: 0,
+                        credit_cents: 4599,
+                        memo: Some("Visa".to_string()),
+                    },
+                ],
+            }),
+            HookEventKind::ImportCompleted => HookEvent::ImportCompleted(ImportCompleted {
+                source: "ofx".to_string(),
+                file_name: "checking-march.ofx".to_string(),
+                batch_ids: vec!["import-20260315093000-checking-march".to_string()],
+                imported: 42,
+                duplicates: 3,
+            }),
+        }
+    }
+}
+
+/// What a hook receives.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HookPayload {
+    #[serde(flatten)]
+    pub event: HookEvent,
+    /// RFC 3339, UTC.
+    pub occurred_at: String,
+}
+
+impl HookPayload {
+    pub fn now(event: HookEvent) -> Self {
+        HookPayload {
+            event,
+            occurred_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        }
+    }
+}
+
+/// The [`ReceiptApproved`] event for a receipt, or `None` if it is gone.
+pub async fn receipt_approved(
+    pool: &DbPool,
+    receipt_id: i64,
+    automatic: bool,
+) -> Result<Option<HookEvent>, sqlx::Error> {
+    let Some(receipt) = crate::db::get_receipt_by_id(pool, receipt_id).await? else {
+        return Ok(None);
+    };
+    Ok(Some(HookEvent::ReceiptApproved(ReceiptApproved {
+        receipt_id,
+        transaction_id: receipt.transaction_id,
+        vendor: receipt.vendor,
+        receipt_date: receipt.receipt_date,
+        total_cents: receipt.total_cents,
+        payment_method: receipt.payment_method,
+        automatic,
+    })))
+}
+
+/// The [`TransactionPosted`] event for a transaction, or `None` if it is
+/// gone.
+pub async fn transaction_posted(
+    pool: &DbPool,
+    transaction_id: i64,
+    source: TransactionSource,
+) -> Result<Option<HookEvent>, sqlx::Error> {
+    let Some((date, description, memo, total_cents)) =
+        sqlx::query_as::<_, (String, String, Option<String>, i64)>(
+            "SELECT date, description, memo, balanced_total_cents FROM transactions WHERE id = ?",
+        )
+        .bind(transaction_id)
+        .fetch_optional(pool)
+        .await?
+    else {
+        return Ok(None);
+    };
+    let lines = sqlx::query_as::<_, (String, String, i64, i64, Option<String>)>(
+        r#"SELECT a.code, a.name, l.debit_cents, l.credit_cents, l.memo
+           FROM transaction_lines l
+           JOIN accounts a ON a.id = l.account_id
+           WHERE l.transaction_id = ?
+           ORDER BY l.id"#,
+    )
+    .bind(transaction_id)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(
+        |(account_code, account_name, debit_cents, credit_cents, memo)| PostedLine {
+            account_code,
+            account_name,
+            debit_cents,
+            credit_cents,
+            memo,
+        },
+    )
+    .collect();
+    Ok(Some(HookEvent::TransactionPosted(TransactionPosted {
+        transaction_id,
+        date,
+        description,
+        memo,
+        total_cents,
+        source,
+        lines,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    fn hook(name: &str, events: Vec<HookEventKind>) -> Hook {
+        Hook {
+            name: name.to_string(),
+            enabled: true,
+            events,
+            target: HookTarget::Http {
+                url: "https://example.com/hook".to_string(),
+                headers: BTreeMap::new(),
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn settings_round_trip() {
+        let pool = test_pool().await;
+        assert_eq!(
+            get_hook_settings(&pool).await.unwrap(),
+            HookSettings::default()
+        );
+
+        let mut archive = hook("Archive", vec![HookEventKind::ReceiptApproved]);
+        archive.target = HookTarget::Script {
+            path: "/usr/local/bin/archive-receipt".to_string(),
+            args: vec!["--quiet".to_string()],
+        };
+        let settings = HookSettings {
+            hooks: vec![archive, hook("Slack", vec![HookEventKind::ImportCompleted])],
+        };
+        set_hook_settings(&pool, &settings).await.unwrap();
+        assert_eq!(get_hook_settings(&pool).await.unwrap(), settings);
+    }
+
+    #[test]
+    fn subscribed_skips_disabled_hooks() {
+        let mut off = hook("Off", vec![HookEventKind::TransactionPosted]);
+        off.enabled = false;
+        let settings = HookSettings {
+            hooks: vec![
+                hook("On", vec![HookEventKind::TransactionPosted]),
+                off,
+                hook("Other", vec![HookEventKind::ImportCompleted]),
+            ],
+        };
+        let names: Vec<&str> = settings
+            .subscribed(HookEventKind::TransactionPosted)
+            .iter()
+            .map(|h| h.name.as_str())
+            .collect();
+        assert_eq!(names, ["On"]);
+    }
+
+    #[test]
+    fn validate_rejects_incomplete_hooks() {
+        assert!(hook("Ok", vec![HookEventKind::ImportCompleted])
+            .validate()
+            .is_ok());
+        assert!(hook(" ", vec![HookEventKind::ImportCompleted])
+            .validate()
+            .is_err());
+        assert!(hook("No events", vec![]).validate().is_err());
+        let mut ftp = hook("Ftp", vec![HookEventKind::ImportCompleted]);
+        ftp.target = HookTarget::Http {
+            url: "ftp://example.com".to_string(),
+            headers: BTreeMap::new(),
+        };
+        assert!(ftp.validate().is_err());
+    }
+
+    #[test]
+    fn payload_names_the_event_and_nests_its_data() {
+        let payload = HookPayload {
+            event: HookEvent::sample(HookEventKind::ImportCompleted),
+            occurred_at: "2026-03-15T09:30:00Z".to_string(),
+        };
+        let json = serde_json::to_value(&payload).unwrap();
+        assert_eq!(json["event"], "import_completed");
+        assert_eq!(json["occurred_at"], "2026-03-15T09:30:00Z");
+        assert_eq!(json["data"]["imported"], 42);
+        assert_eq!(
+            serde_json::from_value::<HookPayload>(json).unwrap(),
+            payload
+        );
+    }
+
+    #[tokio::test]
+    async fn transaction_posted_lists_lines_by_account() {
+        let pool = test_pool().await;
+        let tx_id: i64 = sqlx::query_scalar(
+            "INSERT INTO transactions (date, description, balanced_total_cents) VALUES ('2026-03-15', 'Paper', 1250) RETURNING id",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        for (code, debit, credit) in [("5100", 1250, 0), ("1000", 0, 1250)] {
+            sqlx::query(
+                "INSERT INTO transaction_lines (transaction_id, account_id, debit_cents, credit_cents) SELECT ?, id, ?, ? FROM accounts WHERE code = ?",
+            )
+            .bind(tx_id)
+            .bind(debit)
+            .bind(credit)
+            .bind(code)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let Some(HookEvent::TransactionPosted(posted)) =
+            transaction_posted(&pool, tx_id, TransactionSource::Manual)
+                .await
+                .unwrap()
+        else {
+            panic!("expected a transaction_posted event");
+        };
+        assert_eq!(posted.total_cents, 1250);
+        assert_eq!(posted.lines.len(), 2);
+        assert_eq!(posted.lines[0].account_code, "5100");
+        assert_eq!(posted.lines[0].account_name, "Office Supplies");
+        assert_eq!(posted.lines[1].credit_cents, 1250);
+
+        assert!(
+            transaction_posted(&pool, tx_id + 1, TransactionSource::Manual)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
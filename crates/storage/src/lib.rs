@@ -1,24 +1,90 @@
+pub mod account_balances;
+pub mod aging;
+pub mod app_lock;
+pub mod attachment_store;
+pub mod attachments;
+pub mod attention;
 pub mod backup;
+pub mod balance_assertions;
+pub mod budgets;
+pub mod calendar;
+pub mod cloud_backup;
 pub mod db;
+pub mod diagnostics;
+pub mod documents;
+pub mod error;
+pub mod fixed_assets;
+pub mod gnucash;
+pub mod health;
+pub mod home_office;
+pub mod hooks;
 pub mod migrate;
+pub mod onboarding;
+pub mod payables;
+pub mod receipt_export;
+pub mod receipt_jobs;
+pub mod receipt_status;
+pub mod receivables;
+pub mod reminders;
+pub mod reports;
+pub mod saved_reports;
+pub mod sync;
+pub mod time_tracking;
 
 pub use db::{
-    build_ledger_snapshot, check_receipt_duplicate, complete_reconciliation_session, create_db,
-    create_reconciliation_session, delete_categorization_rule, delete_import_profile,
-    get_account_by_code, get_all_accounts, get_all_contacts, get_all_invoices, get_audit_log,
-    get_categorization_rules, get_contact_by_id, get_contractor_ytd_payments, get_contractors,
-    get_import_profiles, get_imported_transactions_for_review, get_invoice_aging,
+    accept_imported_match, apply_reprocessed_receipt, build_ledger_snapshot,
+    categorize_imported_transactions, check_receipt_duplicate, clear_receipt_duplicate,
+    complete_reconciliation_session, confirm_receipt_match, correct_receipt_fields,
+    count_active_accounts, count_transactions, create_db, create_reconciliation_session,
+    delete_categorization_rule, delete_contact, delete_import_batch, delete_import_profile,
+    find_contact_by_payee, find_possible_duplicate_receipts, find_receipt_match,
+    find_receipt_match_suggestions, get_account_by_code, get_alias_contact, get_all_accounts,
+    get_all_contacts, get_all_invoices, get_all_tags, get_audit_log, get_auto_approvals,
+    get_auto_approve_settings, get_bank_balances, get_categorization_rules,
+    get_categorized_history, get_category_rules, get_contact_aliases, get_contact_by_id,
+    get_contractor_ytd_payments, get_contractors, get_csv_import_profiles, get_duplicate_receipts,
+    get_extraction_accuracy, get_import_batch_summary, get_import_profiles, get_import_review,
+    get_import_review_row, get_imported_transactions_for_review, get_invoice_aging,
     get_invoice_by_id, get_invoice_lines, get_invoice_tax_lines, get_invoices_by_status,
-    get_payments_for_invoice, get_pending_imported_transactions, get_prior_year_total_tax,
-    get_receipt_by_id, get_receipts_pending_review, get_reconciliation_items,
-    get_reconciliation_sessions, get_setting, get_tax_periods, get_unresolved_reconciliation_items,
-    get_ytd_payments_to_contact, insert_audit_log, insert_contact, insert_imported_transaction,
+    get_local_api_settings, get_open_imported_transactions, get_payee_accounts, get_payee_amounts,
+    get_payee_suggestions, get_payment_account_map, get_payments_for_invoice,
+    get_pending_imported_transactions, get_pending_reprocess_diffs,
+    get_postable_imported_transactions, get_preferences, get_prior_year_total_tax,
+    get_receipt_by_id, get_receipt_corrections, get_receipt_line_items,
+    get_receipt_match_candidates, get_receipt_threshold_cents, get_receipts_for_reprocess,
+    get_receipts_from_source, get_receipts_pending_review, get_reconciliation_items,
+    get_reconciliation_sessions, get_setting, get_tax_periods, get_transaction_tags,
+    get_transactions, get_uncategorized_imports, get_unlinked_approved_receipts,
+    get_unmatched_ledger_transactions, get_unmatched_receipts, get_unreceipted_expenses,
+    get_unresolved_reconciliation_items, get_vendor_profiles, get_ytd_payments_to_contact,
+    imported_transaction_exists, insert_audit_log, insert_contact, insert_imported_transaction,
     insert_invoice, insert_invoice_line, insert_invoice_tax_line, insert_payment, insert_receipt,
-    link_receipt_to_transaction, mark_imported_transaction_categorized,
-    mark_imported_transaction_matched, record_tax_payment, resolve_reconciliation_item,
-    save_categorization_rule, save_import_profile, seed_default_accounts, set_setting,
-    update_contact, update_invoice_status, update_receipt_status, upsert_tax_period,
-    AuditLogRecord, CategorizationRule, ContactRecord, DbPool, ImportProfile, ImportedTransaction,
-    InvoiceLineRecord, InvoiceRecord, InvoiceTaxLineRecord, PaymentRecord, ReceiptRecord,
-    ReconciliationItem, ReconciliationSession, TaxPeriodRecord,
+    insert_receipt_line_item, insert_transaction, insert_transactions_bulk,
+    insert_transactions_bulk_tx, link_receipt_to_transaction,
+    mark_imported_transaction_categorized, mark_imported_transaction_matched, mark_receipt_posted,
+    normalize_payee, normalize_tag, query_receipts, receipt_link_target_exists,
+    record_auto_approval, record_tax_payment, record_vendor_approval, reject_imported_match,
+    reorder_categorization_rules, resolve_reconciliation_item, resolve_reprocess_diff,
+    save_categorization_rule, save_csv_import_profile, save_import_profile, seed_default_accounts,
+    set_account_deductible_pct, set_auto_approve_settings, set_contact_aliases,
+    set_contact_archived, set_contact_defaults, set_imported_transaction_account,
+    set_local_api_settings, set_payment_account_map, set_pending_receipts_status,
+    set_receipt_quality, set_receipt_source, set_setting, set_transaction_deductibility,
+    set_transaction_tags, settle_imported_transaction, suggest_imported_match, transaction_exists,
+    undo_auto_approval, update_categorization_rule, update_contact, update_import_profile,
+    update_invoice_status, update_receipt, update_receipt_status, upsert_bank_balance,
+    upsert_tax_period, AuditLogRecord, AutoApprovalRecord, AutoApproveSettings, BankBalance,
+    CategorizationRule, CategorizedHistoryRow, ConfidenceBand, ContactRecord, DbPool,
+    FieldAccuracyRecord, ImportBatchSummary, ImportProfile, ImportReviewRow, ImportedTransaction,
+    InvoiceLineRecord, InvoiceRecord, InvoiceTaxLineRecord, LocalApiSettings, NewAutoApproval,
+    PayeeAccountUsage, PayeeAmount, PayeeSuggestion, PaymentRecord, Preferences,
+    ProfileConversionError, ReceiptCorrectionRecord, ReceiptInsert, ReceiptLineItemInput,
+    ReceiptLineItemRecord, ReceiptPage, ReceiptQuery, ReceiptRecord, ReceiptReprocessDiff,
+    ReceiptUpdate, ReconciliationItem, ReconciliationSession, ReextractedReceipt, ReprocessFilter,
+    ReprocessOutcome, TaxPeriodRecord, TransactionRecord, UnreceiptedExpense, VendorProfileRecord,
+    AUTO_APPROVE_SETTING, DATE_FORMAT_SETTING, DEFAULT_CURRENCY_SETTING, DEFAULT_LOCAL_API_PORT,
+    DEFAULT_RECEIPT_THRESHOLD_CENTS, DEFAULT_REVIEW_THRESHOLD, FISCAL_YEAR_START_SETTING,
+    LOCAL_API_SETTING, NUMBER_LOCALE_SETTING, OCR_BACKEND_SETTING, PAYMENT_ACCOUNTS_SETTING,
+    RECEIPT_THRESHOLD_SETTING,
 };
+pub use error::{with_retry, RetryPolicy, StorageError};
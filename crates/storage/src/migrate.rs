@@ -24,6 +24,13 @@ pub struct SchemaVersion {
     pub checksum: String,
 }
 
+/// A known migration not yet applied to the database.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PendingMigration {
+    pub version: i64,
+    pub name: String,
+}
+
 /// All known migrations, ordered by version.
 fn all_migrations() -> Vec<Migration> {
     vec![
@@ -39,6 +46,198 @@ fn all_migrations() -> Vec<Migration> {
             up_sql: include_str!("migrations/V002__indexes_and_constraints.sql"),
             down_sql: include_str!("migrations/V002__indexes_and_constraints.down.sql"),
         },
+        Migration {
+            version: 3,
+            name: "bank_sync",
+            up_sql: include_str!("migrations/V003__bank_sync.sql"),
+            down_sql: include_str!("migrations/V003__bank_sync.down.sql"),
+        },
+        Migration {
+            version: 4,
+            name: "receipt_bank_links",
+            up_sql: include_str!("migrations/V004__receipt_bank_links.sql"),
+            down_sql: include_str!("migrations/V004__receipt_bank_links.down.sql"),
+        },
+        Migration {
+            version: 5,
+            name: "receipt_image_quality",
+            up_sql: include_str!("migrations/V005__receipt_image_quality.sql"),
+            down_sql: include_str!("migrations/V005__receipt_image_quality.down.sql"),
+        },
+        Migration {
+            version: 6,
+            name: "vendor_profiles",
+            up_sql: include_str!("migrations/V006__vendor_profiles.sql"),
+            down_sql: include_str!("migrations/V006__vendor_profiles.down.sql"),
+        },
+        Migration {
+            version: 7,
+            name: "receipt_reference",
+            up_sql: include_str!("migrations/V007__receipt_reference.sql"),
+            down_sql: include_str!("migrations/V007__receipt_reference.down.sql"),
+        },
+        Migration {
+            version: 8,
+            name: "receipt_corrections",
+            up_sql: include_str!("migrations/V008__receipt_corrections.sql"),
+            down_sql: include_str!("migrations/V008__receipt_corrections.down.sql"),
+        },
+        Migration {
+            version: 9,
+            name: "receipt_reprocess_diffs",
+            up_sql: include_str!("migrations/V009__receipt_reprocess_diffs.sql"),
+            down_sql: include_str!("migrations/V009__receipt_reprocess_diffs.down.sql"),
+        },
+        Migration {
+            version: 10,
+            name: "receipt_correction_fields",
+            up_sql: include_str!("migrations/V010__receipt_correction_fields.sql"),
+            down_sql: include_str!("migrations/V010__receipt_correction_fields.down.sql"),
+        },
+        Migration {
+            version: 11,
+            name: "deductibility",
+            up_sql: include_str!("migrations/V011__deductibility.sql"),
+            down_sql: include_str!("migrations/V011__deductibility.down.sql"),
+        },
+        Migration {
+            version: 12,
+            name: "receipt_auto_approvals",
+            up_sql: include_str!("migrations/V012__receipt_auto_approvals.sql"),
+            down_sql: include_str!("migrations/V012__receipt_auto_approvals.down.sql"),
+        },
+        Migration {
+            version: 13,
+            name: "imported_transaction_accounts",
+            up_sql: include_str!("migrations/V013__imported_transaction_accounts.sql"),
+            down_sql: include_str!("migrations/V013__imported_transaction_accounts.down.sql"),
+        },
+        Migration {
+            version: 14,
+            name: "rule_amount_range",
+            up_sql: include_str!("migrations/V014__rule_amount_range.sql"),
+            down_sql: include_str!("migrations/V014__rule_amount_range.down.sql"),
+        },
+        Migration {
+            version: 15,
+            name: "sync_log",
+            up_sql: include_str!("migrations/V015__sync_log.sql"),
+            down_sql: include_str!("migrations/V015__sync_log.down.sql"),
+        },
+        Migration {
+            version: 16,
+            name: "notifications",
+            up_sql: include_str!("migrations/V016__notifications.sql"),
+            down_sql: include_str!("migrations/V016__notifications.down.sql"),
+        },
+        Migration {
+            version: 17,
+            name: "external_transaction_ids",
+            up_sql: include_str!("migrations/V017__external_transaction_ids.sql"),
+            down_sql: include_str!("migrations/V017__external_transaction_ids.down.sql"),
+        },
+        Migration {
+            version: 18,
+            name: "time_entries",
+            up_sql: include_str!("migrations/V018__time_entries.sql"),
+            down_sql: include_str!("migrations/V018__time_entries.down.sql"),
+        },
+        Migration {
+            version: 19,
+            name: "contact_details",
+            up_sql: include_str!("migrations/V019__contact_details.sql"),
+            down_sql: include_str!("migrations/V019__contact_details.down.sql"),
+        },
+        Migration {
+            version: 20,
+            name: "payment_credits",
+            up_sql: include_str!("migrations/V020__payment_credits.sql"),
+            down_sql: include_str!("migrations/V020__payment_credits.down.sql"),
+        },
+        Migration {
+            version: 21,
+            name: "bills",
+            up_sql: include_str!("migrations/V021__bills.sql"),
+            down_sql: include_str!("migrations/V021__bills.down.sql"),
+        },
+        Migration {
+            version: 22,
+            name: "budgets",
+            up_sql: include_str!("migrations/V022__budgets.sql"),
+            down_sql: include_str!("migrations/V022__budgets.down.sql"),
+        },
+        Migration {
+            version: 23,
+            name: "balance_assertions",
+            up_sql: include_str!("migrations/V023__balance_assertions.sql"),
+            down_sql: include_str!("migrations/V023__balance_assertions.down.sql"),
+        },
+        Migration {
+            version: 24,
+            name: "attachments",
+            up_sql: include_str!("migrations/V024__attachments.sql"),
+            down_sql: include_str!("migrations/V024__attachments.down.sql"),
+        },
+        Migration {
+            version: 25,
+            name: "documents",
+            up_sql: include_str!("migrations/V025__documents.sql"),
+            down_sql: include_str!("migrations/V025__documents.down.sql"),
+        },
+        Migration {
+            version: 26,
+            name: "account_balances",
+            up_sql: include_str!("migrations/V026__account_balances.sql"),
+            down_sql: include_str!("migrations/V026__account_balances.down.sql"),
+        },
+        Migration {
+            version: 27,
+            name: "receipt_jobs",
+            up_sql: include_str!("migrations/V027__receipt_jobs.sql"),
+            down_sql: include_str!("migrations/V027__receipt_jobs.down.sql"),
+        },
+        Migration {
+            version: 28,
+            name: "relative_attachment_paths",
+            up_sql: include_str!("migrations/V028__relative_attachment_paths.sql"),
+            down_sql: include_str!("migrations/V028__relative_attachment_paths.down.sql"),
+        },
+        Migration {
+            version: 29,
+            name: "receipt_duplicates",
+            up_sql: include_str!("migrations/V029__receipt_duplicates.sql"),
+            down_sql: include_str!("migrations/V029__receipt_duplicates.down.sql"),
+        },
+        Migration {
+            version: 30,
+            name: "receipt_sources",
+            up_sql: include_str!("migrations/V030__receipt_sources.sql"),
+            down_sql: include_str!("migrations/V030__receipt_sources.down.sql"),
+        },
+        Migration {
+            version: 31,
+            name: "account_deductible_pct",
+            up_sql: include_str!("migrations/V031__account_deductible_pct.sql"),
+            down_sql: include_str!("migrations/V031__account_deductible_pct.down.sql"),
+        },
+        Migration {
+            version: 32,
+            name: "home_office_worksheets",
+            up_sql: include_str!("migrations/V032__home_office_worksheets.sql"),
+            down_sql: include_str!("migrations/V032__home_office_worksheets.down.sql"),
+        },
+        Migration {
+            version: 33,
+            name: "fixed_assets",
+            up_sql: include_str!("migrations/V033__fixed_assets.sql"),
+            down_sql: include_str!("migrations/V033__fixed_assets.down.sql"),
+        },
+        Migration {
+            version: 34,
+            name: "saved_reports",
+            up_sql: include_str!("migrations/V034__saved_reports.sql"),
+            down_sql: include_str!("migrations/V034__saved_reports.down.sql"),
+        },
     ]
 }
 
@@ -208,6 +407,23 @@ pub async fn get_schema_versions(pool: &DbPool) -> Result<Vec<SchemaVersion>, sq
     .await
 }
 
+/// Migrations this build knows about that the database hasn't had.
+pub async fn pending_migrations(pool: &DbPool) -> Result<Vec<PendingMigration>, sqlx::Error> {
+    let applied: std::collections::HashSet<i64> = get_schema_versions(pool)
+        .await?
+        .into_iter()
+        .map(|v| v.version)
+        .collect();
+    Ok(all_migrations()
+        .into_iter()
+        .filter(|m| !applied.contains(&m.version))
+        .map(|m| PendingMigration {
+            version: m.version,
+            name: m.name.to_string(),
+        })
+        .collect())
+}
+
 /// Get the current schema version number, or 0 if no migrations applied.
 pub async fn current_version(pool: &DbPool) -> Result<i64, sqlx::Error> {
     ensure_version_table(pool).await?;
@@ -217,14 +433,22 @@ pub async fn current_version(pool: &DbPool) -> Result<i64, sqlx::Error> {
     Ok(row.map(|r| r.0).unwrap_or(0))
 }
 
+/// The newest schema version this build knows how to use.
+pub fn latest_version() -> i64 {
+    all_migrations().last().map_or(0, |m| m.version)
+}
+
 /// Split SQL text into individual statements on semicolons.
-/// Handles comments and avoids splitting inside string literals.
+/// Handles comments, avoids splitting inside string literals, and keeps
+/// `CREATE TRIGGER ... BEGIN ... END` bodies together.
 fn split_statements(sql: &str) -> Vec<&str> {
     let mut statements = Vec::new();
     let mut start = 0;
     let bytes = sql.as_bytes();
     let mut i = 0;
     let len = bytes.len();
+    // Open BEGIN/CASE blocks inside the current trigger statement.
+    let mut depth = 0usize;
 
     while i < len {
         match bytes[i] {
@@ -250,6 +474,23 @@ fn split_statements(sql: &str) -> Vec<&str> {
                     i += 1;
                 }
             }
+            b if b.is_ascii_alphabetic() && (i == 0 || !is_word_byte(bytes[i - 1])) => {
+                let word_start = i;
+                while i < len && is_word_byte(bytes[i]) {
+                    i += 1;
+                }
+                let word = &sql[word_start..i];
+                if is_trigger(&sql[start..word_start]) {
+                    if word.eq_ignore_ascii_case("BEGIN") || word.eq_ignore_ascii_case("CASE") {
+                        depth += 1;
+                    } else if word.eq_ignore_ascii_case("END") {
+                        depth = depth.saturating_sub(1);
+                    }
+                }
+            }
+            b';' if depth > 0 => {
+                i += 1;
+            }
             b';' => {
                 let stmt = &sql[start..i];
                 if !stmt.trim().is_empty() {
@@ -273,6 +514,25 @@ fn split_statements(sql: &str) -> Vec<&str> {
     statements
 }
 
+fn is_word_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+/// Whether the statement text so far is a `CREATE TRIGGER`, ignoring
+/// leading comments.
+fn is_trigger(stmt: &str) -> bool {
+    let code = stmt
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty() && !l.starts_with("--"))
+        .unwrap_or("");
+    let mut words = code.split_whitespace();
+    matches!(
+        (words.next(), words.next()),
+        (Some(a), Some(b)) if a.eq_ignore_ascii_case("CREATE") && b.eq_ignore_ascii_case("TRIGGER")
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -344,6 +604,10 @@ mod tests {
 
         let ver = current_version(&pool).await.unwrap();
         assert_eq!(ver, total - 1);
+
+        let pending = pending_migrations(&pool).await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].version, total);
     }
 
     #[tokio::test]
@@ -367,6 +631,57 @@ mod tests {
         assert_eq!(rolled, None);
     }
 
+    #[tokio::test]
+    async fn attachment_paths_become_relative() {
+        let pool = test_pool().await;
+        run_migrations(&pool).await.unwrap();
+        // Back to before V028.
+        while current_version(&pool).await.unwrap() >= 28 {
+            rollback_last(&pool).await.unwrap();
+        }
+
+        let (a, b, c) = ("ab".repeat(32), "cd".repeat(32), "ef".repeat(32));
+        let rows = [
+            (
+                &a,
+                "jpg",
+                format!("/home/sam/.local/share/aequi/attachments/ab/{a}.jpg"),
+            ),
+            (
+                &b,
+                "PDF",
+                format!(r"C:\Users\Sam\AppData\aequi\attachments\cd\{b}.pdf"),
+            ),
+            (&c, "png", "/tmp/scan.png".to_string()),
+        ];
+        for (hash, ext, path) in &rows {
+            sqlx::query(
+                "INSERT INTO receipts (file_hash, file_ext, attachment_path) VALUES (?, ?, ?)",
+            )
+            .bind(hash)
+            .bind(ext)
+            .bind(path)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        run_migrations(&pool).await.unwrap();
+
+        let paths: Vec<String> =
+            sqlx::query_scalar("SELECT attachment_path FROM receipts ORDER BY id")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(
+            paths,
+            [
+                format!("ab/{a}.jpg"),
+                format!("cd/{b}.pdf"),
+                "/tmp/scan.png".to_string(),
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn all_17_tables_created() {
         let pool = test_pool().await;
@@ -399,11 +714,32 @@ mod tests {
         assert!(names.contains(&"payments"));
         assert!(names.contains(&"audit_log"));
         assert!(names.contains(&"tax_periods"));
-        // 19 domain tables + sqlite_sequence (from AUTOINCREMENT)
+        assert!(names.contains(&"bank_balances"));
+        assert!(names.contains(&"vendor_profiles"));
+        assert!(names.contains(&"receipt_corrections"));
+        assert!(names.contains(&"receipt_reprocess_diffs"));
+        assert!(names.contains(&"sync_log"));
+        assert!(names.contains(&"sync_peers"));
+        assert!(names.contains(&"notifications"));
+        assert!(names.contains(&"external_transaction_ids"));
+        assert!(names.contains(&"time_entries"));
+        assert!(names.contains(&"contact_aliases"));
+        assert!(names.contains(&"bills"));
+        assert!(names.contains(&"bill_payments"));
+        assert!(names.contains(&"budgets"));
+        assert!(names.contains(&"balance_assertions"));
+        assert!(names.contains(&"attachments"));
+        assert!(names.contains(&"documents"));
+        assert!(names.contains(&"account_balances"));
+        assert!(names.contains(&"receipt_jobs"));
+        assert!(names.contains(&"home_office_worksheets"));
+        assert!(names.contains(&"fixed_assets"));
+        assert!(names.contains(&"transaction_tags"));
+        assert!(names.contains(&"saved_reports"));
         assert_eq!(
             names.len(),
-            20,
-            "Should have 20 tables (19 domain + sqlite_sequence)"
+            43,
+            "Should have 43 tables (42 domain + sqlite_sequence)"
         );
     }
 
@@ -429,6 +765,20 @@ mod tests {
         assert!(stmts[0].contains("hello; world"));
     }
 
+    #[test]
+    fn split_statements_keeps_trigger_bodies() {
+        let sql = "CREATE TABLE a (id INT);\n\
+                   CREATE TRIGGER t AFTER INSERT ON a BEGIN\n\
+                       UPDATE a SET id = CASE WHEN id > 0 THEN id ELSE 0 END;\n\
+                       DELETE FROM a WHERE id < 0;\n\
+                   END;\n\
+                   CREATE INDEX idx ON a(id);";
+        let stmts = split_statements(sql);
+        assert_eq!(stmts.len(), 3);
+        assert!(stmts[1].contains("DELETE FROM a"));
+        assert!(stmts[1].trim_end().ends_with("END"));
+    }
+
     #[test]
     fn split_statements_trailing_no_semicolon() {
         let sql = "CREATE TABLE a (id INT)";
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_imported_tx_source;
+DROP TABLE IF EXISTS bank_balances;
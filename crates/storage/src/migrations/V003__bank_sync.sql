@@ -0,0 +1,16 @@
+-- V003: Balances pulled from bank aggregators, and fast lookup of imported
+-- rows by provider transaction id for sync dedup
+
+CREATE TABLE IF NOT EXISTS bank_balances (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    provider TEXT NOT NULL,
+    provider_account_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    currency TEXT,
+    current_cents INTEGER,
+    available_cents INTEGER,
+    as_of TEXT NOT NULL,
+    UNIQUE (provider, provider_account_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_imported_tx_source ON imported_transactions(source_type, source_id);
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_receipts_imported_tx;
+ALTER TABLE receipts DROP COLUMN imported_transaction_id;
@@ -0,0 +1,6 @@
+-- V004: Let a receipt point at a bank row still in the import review queue;
+-- the link moves to the ledger transaction once that row is matched
+
+ALTER TABLE receipts ADD COLUMN imported_transaction_id INTEGER;
+
+CREATE INDEX IF NOT EXISTS idx_receipts_imported_tx ON receipts(imported_transaction_id);
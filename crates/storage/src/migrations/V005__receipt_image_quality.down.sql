@@ -0,0 +1,2 @@
+ALTER TABLE receipts DROP COLUMN quality_issues;
+ALTER TABLE receipts DROP COLUMN quality_score;
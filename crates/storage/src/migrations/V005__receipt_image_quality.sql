@@ -0,0 +1,5 @@
+-- V005: Photo quality score for captured receipts, so the review UI can ask
+-- for a retake. NULL for PDFs and receipts ingested before scoring existed
+
+ALTER TABLE receipts ADD COLUMN quality_score REAL;
+ALTER TABLE receipts ADD COLUMN quality_issues TEXT;
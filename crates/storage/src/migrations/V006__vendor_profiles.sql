@@ -0,0 +1,12 @@
+-- V006: Vendors learned from approved receipts, consulted by receipt
+-- extraction alongside its built-in list of chains
+
+CREATE TABLE IF NOT EXISTS vendor_profiles (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    tax_rate REAL,
+    tax_samples INTEGER NOT NULL DEFAULT 0,
+    total_label TEXT,
+    approvals INTEGER NOT NULL DEFAULT 0,
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
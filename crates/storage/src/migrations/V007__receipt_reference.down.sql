@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_receipts_reference;
+ALTER TABLE receipts DROP COLUMN reference;
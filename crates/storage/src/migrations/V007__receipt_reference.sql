@@ -0,0 +1,5 @@
+-- V007: Invoice, receipt or order number printed on a receipt, used to tie it
+-- to vendor bills and online orders.
+
+ALTER TABLE receipts ADD COLUMN reference TEXT;
+CREATE INDEX IF NOT EXISTS idx_receipts_reference ON receipts(reference);
@@ -0,0 +1,15 @@
+-- V008: Fields the user reviewed on a receipt, with the value extraction
+-- originally produced. A row whose values match was confirmed correct; the
+-- table doubles as a test set for changes to the extractor
+
+CREATE TABLE IF NOT EXISTS receipt_corrections (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    receipt_id INTEGER NOT NULL REFERENCES receipts(id) ON DELETE CASCADE,
+    field TEXT NOT NULL CHECK(field IN ('vendor', 'date', 'total')),
+    extracted_value TEXT,
+    corrected_value TEXT,
+    confidence REAL NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+    UNIQUE(receipt_id, field)
+);
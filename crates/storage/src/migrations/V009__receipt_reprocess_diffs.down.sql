@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_receipt_reprocess_diffs_status;
+DROP TABLE IF EXISTS receipt_reprocess_diffs;
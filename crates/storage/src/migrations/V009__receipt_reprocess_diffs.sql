@@ -0,0 +1,20 @@
+-- V009: Changes proposed by re-running extraction over receipts that were
+-- already reviewed. They wait here until the user accepts or dismisses them
+
+CREATE TABLE IF NOT EXISTS receipt_reprocess_diffs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    receipt_id INTEGER NOT NULL REFERENCES receipts(id) ON DELETE CASCADE,
+    field TEXT NOT NULL CHECK(field IN (
+        'vendor', 'receipt_date', 'total_cents', 'subtotal_cents',
+        'tax_cents', 'payment_method', 'reference'
+    )),
+    current_value TEXT,
+    proposed_value TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending'
+        CHECK(status IN ('pending', 'accepted', 'dismissed')),
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    resolved_at TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_receipt_reprocess_diffs_status
+    ON receipt_reprocess_diffs(status, receipt_id);
@@ -0,0 +1,18 @@
+CREATE TABLE receipt_corrections_old (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    receipt_id INTEGER NOT NULL REFERENCES receipts(id) ON DELETE CASCADE,
+    field TEXT NOT NULL CHECK(field IN ('vendor', 'date', 'total')),
+    extracted_value TEXT,
+    corrected_value TEXT,
+    confidence REAL NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+    UNIQUE(receipt_id, field)
+);
+INSERT INTO receipt_corrections_old
+    (id, receipt_id, field, extracted_value, corrected_value, confidence, created_at, updated_at)
+SELECT id, receipt_id, field, extracted_value, corrected_value, confidence, created_at, updated_at
+FROM receipt_corrections
+WHERE field IN ('vendor', 'date', 'total');
+DROP TABLE receipt_corrections;
+ALTER TABLE receipt_corrections_old RENAME TO receipt_corrections;
@@ -0,0 +1,26 @@
+-- V010: Corrections cover every field the user can edit during review, so
+-- re-processing leaves hand-entered subtotals, tax, payment methods and
+-- references alone too. SQLite can't alter a CHECK, so the table is rebuilt
+
+CREATE TABLE receipt_corrections_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    receipt_id INTEGER NOT NULL REFERENCES receipts(id) ON DELETE CASCADE,
+    field TEXT NOT NULL CHECK(field IN (
+        'vendor', 'date', 'total', 'subtotal', 'tax', 'payment_method', 'reference'
+    )),
+    extracted_value TEXT,
+    corrected_value TEXT,
+    confidence REAL NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+    UNIQUE(receipt_id, field)
+);
+
+INSERT INTO receipt_corrections_new
+    (id, receipt_id, field, extracted_value, corrected_value, confidence, created_at, updated_at)
+SELECT id, receipt_id, field, extracted_value, corrected_value, confidence, created_at, updated_at
+FROM receipt_corrections;
+
+DROP TABLE receipt_corrections;
+
+ALTER TABLE receipt_corrections_new RENAME TO receipt_corrections;
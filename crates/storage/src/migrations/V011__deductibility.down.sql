@@ -0,0 +1,4 @@
+ALTER TABLE receipts DROP COLUMN deductible_percent;
+ALTER TABLE receipts DROP COLUMN is_personal;
+ALTER TABLE transactions DROP COLUMN deductible_percent;
+ALTER TABLE transactions DROP COLUMN is_personal;
@@ -0,0 +1,11 @@
+-- V011: Personal spending on a shared card and partly-deductible expenses.
+-- Personal transactions stay in the ledger but are left out of profit and
+-- loss and tax; deductible_percent scales the rest for tax. Receipts carry
+-- the same flags onto the transactions posted from them.
+
+ALTER TABLE transactions ADD COLUMN is_personal INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE transactions ADD COLUMN deductible_percent INTEGER NOT NULL DEFAULT 100
+    CHECK(deductible_percent BETWEEN 0 AND 100);
+ALTER TABLE receipts ADD COLUMN is_personal INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE receipts ADD COLUMN deductible_percent INTEGER NOT NULL DEFAULT 100
+    CHECK(deductible_percent BETWEEN 0 AND 100);
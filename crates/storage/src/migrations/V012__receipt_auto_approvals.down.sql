@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_receipt_auto_approvals_created;
+DROP TABLE IF EXISTS receipt_auto_approvals;
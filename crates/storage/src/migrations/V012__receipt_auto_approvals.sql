@@ -0,0 +1,18 @@
+-- V012: Receipts approved without review because they were read with high
+-- confidence and matched a bank transaction. Kept for the daily digest and
+-- so each one can be undone; `posted` marks a transaction created from the
+-- receipt rather than an existing one it was linked to.
+
+CREATE TABLE IF NOT EXISTS receipt_auto_approvals (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    receipt_id INTEGER NOT NULL REFERENCES receipts(id) ON DELETE CASCADE,
+    transaction_id INTEGER REFERENCES transactions(id) ON DELETE SET NULL,
+    imported_transaction_id INTEGER REFERENCES imported_transactions(id) ON DELETE SET NULL,
+    posted INTEGER NOT NULL DEFAULT 0,
+    receipt_confidence REAL NOT NULL,
+    match_confidence REAL NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    undone_at TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_receipt_auto_approvals_created ON receipt_auto_approvals(created_at);
@@ -0,0 +1 @@
+ALTER TABLE imported_transactions DROP COLUMN account_id;
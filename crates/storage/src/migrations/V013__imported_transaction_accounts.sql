@@ -0,0 +1,4 @@
+-- V013: Account chosen by hand for an imported bank row during review.
+-- Overrides the account of the rule that categorized it, if any.
+
+ALTER TABLE imported_transactions ADD COLUMN account_id INTEGER REFERENCES accounts(id);
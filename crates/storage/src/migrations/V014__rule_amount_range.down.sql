@@ -0,0 +1,2 @@
+ALTER TABLE categorization_rules DROP COLUMN amount_max_cents;
+ALTER TABLE categorization_rules DROP COLUMN amount_min_cents;
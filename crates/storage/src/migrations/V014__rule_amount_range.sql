@@ -0,0 +1,5 @@
+-- V014: Optional amount range on categorization rules, so a rule can match
+-- only rows whose amount falls between the bounds (in cents, inclusive).
+
+ALTER TABLE categorization_rules ADD COLUMN amount_min_cents INTEGER;
+ALTER TABLE categorization_rules ADD COLUMN amount_max_cents INTEGER;
@@ -0,0 +1,13 @@
+DROP TRIGGER IF EXISTS sync_accounts_update;
+DROP TRIGGER IF EXISTS sync_accounts_insert;
+DROP TRIGGER IF EXISTS sync_lines_delete;
+DROP TRIGGER IF EXISTS sync_lines_update;
+DROP TRIGGER IF EXISTS sync_lines_insert;
+DROP TRIGGER IF EXISTS sync_transactions_delete;
+DROP TRIGGER IF EXISTS sync_transactions_update;
+DROP TRIGGER IF EXISTS sync_transactions_insert;
+DROP TABLE IF EXISTS sync_peers;
+DROP INDEX IF EXISTS idx_sync_log_entity;
+DROP TABLE IF EXISTS sync_log;
+DROP INDEX IF EXISTS idx_transactions_sync_uid;
+ALTER TABLE transactions DROP COLUMN sync_uid;
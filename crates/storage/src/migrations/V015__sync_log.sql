@@ -0,0 +1,83 @@
+-- V015: Change feed for device-to-device sync.
+-- Transactions get an id that is the same on every device, and triggers
+-- record each change to accounts, transactions and their lines in sync_log.
+
+ALTER TABLE transactions ADD COLUMN sync_uid TEXT;
+UPDATE transactions SET sync_uid = lower(hex(randomblob(16))) WHERE sync_uid IS NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_transactions_sync_uid ON transactions(sync_uid);
+
+CREATE TABLE IF NOT EXISTS sync_log (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    -- 'account' (keyed by code) or 'transaction' (keyed by sync_uid)
+    entity TEXT NOT NULL,
+    entity_key TEXT NOT NULL,
+    changed_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+    -- Device a merged change came from; NULL for changes made here.
+    origin TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_sync_log_entity ON sync_log(entity, entity_key);
+
+-- What is already in the ledger counts as changed, so the first exchange
+-- with a device carries it.
+INSERT INTO sync_log (entity, entity_key) SELECT 'account', code FROM accounts;
+INSERT INTO sync_log (entity, entity_key) SELECT 'transaction', sync_uid FROM transactions;
+
+-- What has been exchanged with each other device.
+CREATE TABLE IF NOT EXISTS sync_peers (
+    device_id TEXT PRIMARY KEY,
+    last_sent_seq INTEGER NOT NULL DEFAULT 0,
+    last_received_seq INTEGER NOT NULL DEFAULT 0,
+    last_synced_at TEXT
+);
+
+CREATE TRIGGER IF NOT EXISTS sync_transactions_insert AFTER INSERT ON transactions
+BEGIN
+    UPDATE transactions SET sync_uid = lower(hex(randomblob(16)))
+        WHERE id = NEW.id AND sync_uid IS NULL;
+    INSERT INTO sync_log (entity, entity_key)
+        SELECT 'transaction', sync_uid FROM transactions WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS sync_transactions_update AFTER UPDATE ON transactions
+    WHEN NEW.sync_uid IS NOT NULL
+BEGIN
+    INSERT INTO sync_log (entity, entity_key) VALUES ('transaction', NEW.sync_uid);
+END;
+
+CREATE TRIGGER IF NOT EXISTS sync_transactions_delete AFTER DELETE ON transactions
+    WHEN OLD.sync_uid IS NOT NULL
+BEGIN
+    INSERT INTO sync_log (entity, entity_key) VALUES ('transaction', OLD.sync_uid);
+END;
+
+CREATE TRIGGER IF NOT EXISTS sync_lines_insert AFTER INSERT ON transaction_lines
+BEGIN
+    INSERT INTO sync_log (entity, entity_key)
+        SELECT 'transaction', sync_uid FROM transactions
+        WHERE id = NEW.transaction_id AND sync_uid IS NOT NULL;
+END;
+
+CREATE TRIGGER IF NOT EXISTS sync_lines_update AFTER UPDATE ON transaction_lines
+BEGIN
+    INSERT INTO sync_log (entity, entity_key)
+        SELECT 'transaction', sync_uid FROM transactions
+        WHERE id = NEW.transaction_id AND sync_uid IS NOT NULL;
+END;
+
+CREATE TRIGGER IF NOT EXISTS sync_lines_delete AFTER DELETE ON transaction_lines
+BEGIN
+    INSERT INTO sync_log (entity, entity_key)
+        SELECT 'transaction', sync_uid FROM transactions
+        WHERE id = OLD.transaction_id AND sync_uid IS NOT NULL;
+END;
+
+CREATE TRIGGER IF NOT EXISTS sync_accounts_insert AFTER INSERT ON accounts
+BEGIN
+    INSERT INTO sync_log (entity, entity_key) VALUES ('account', NEW.code);
+END;
+
+CREATE TRIGGER IF NOT EXISTS sync_accounts_update AFTER UPDATE ON accounts
+BEGIN
+    INSERT INTO sync_log (entity, entity_key) VALUES ('account', NEW.code);
+END;
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_notifications_open;
+DROP TABLE IF EXISTS notifications;
@@ -0,0 +1,20 @@
+-- V016: Reminders raised by the notification engine.
+-- One row per condition (an overdue invoice, an upcoming tax deadline, ...),
+-- identified by `key`. A row is resolved when its condition clears and
+-- reopened if it comes back.
+
+CREATE TABLE IF NOT EXISTS notifications (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    key TEXT NOT NULL UNIQUE,
+    kind TEXT NOT NULL,
+    title TEXT NOT NULL,
+    body TEXT NOT NULL,
+    due_date TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+    read_at TEXT,
+    dismissed_at TEXT,
+    resolved_at TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_notifications_open ON notifications(resolved_at, dismissed_at);
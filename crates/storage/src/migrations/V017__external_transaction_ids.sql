@@ -0,0 +1,9 @@
+-- V017: Transactions brought in from other bookkeeping programs, keyed by
+-- the id they had there, so importing the same book again skips them.
+
+CREATE TABLE IF NOT EXISTS external_transaction_ids (
+    source TEXT NOT NULL,
+    external_id TEXT NOT NULL,
+    transaction_id INTEGER NOT NULL REFERENCES transactions(id) ON DELETE CASCADE,
+    PRIMARY KEY (source, external_id)
+);
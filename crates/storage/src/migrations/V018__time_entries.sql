@@ -0,0 +1,21 @@
+-- V018: Time worked for clients, billed by rolling it into invoices.
+-- A timer entry has `started_at` set and `ended_at` empty while it runs;
+-- `minutes` is filled in when it stops. Manual entries only have `minutes`.
+-- `invoice_id` is set once the time has been billed.
+
+CREATE TABLE IF NOT EXISTS time_entries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    contact_id INTEGER NOT NULL REFERENCES contacts(id),
+    project TEXT,
+    description TEXT NOT NULL DEFAULT '',
+    date TEXT NOT NULL,
+    minutes INTEGER NOT NULL DEFAULT 0,
+    started_at TEXT,
+    ended_at TEXT,
+    hourly_rate_cents INTEGER NOT NULL,
+    billable INTEGER NOT NULL DEFAULT 1,
+    invoice_id INTEGER REFERENCES invoices(id) ON DELETE SET NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_time_entries_contact ON time_entries(contact_id, invoice_id);
@@ -0,0 +1,4 @@
+DROP TABLE IF EXISTS contact_aliases;
+ALTER TABLE contacts DROP COLUMN is_archived;
+ALTER TABLE contacts DROP COLUMN payment_terms_days;
+ALTER TABLE contacts DROP COLUMN default_account_id;
@@ -0,0 +1,16 @@
+-- V019: What invoicing, bills and payee matching need to know about a
+-- contact: the account their transactions usually go to, how many days they
+-- are given to pay, and the other names they show up under on statements
+-- (stored lowercase and single-spaced). Contacts no longer worked with are
+-- archived rather than deleted, so their history stays intact.
+
+ALTER TABLE contacts ADD COLUMN default_account_id INTEGER REFERENCES accounts(id);
+ALTER TABLE contacts ADD COLUMN payment_terms_days INTEGER;
+ALTER TABLE contacts ADD COLUMN is_archived INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS contact_aliases (
+    alias TEXT PRIMARY KEY,
+    contact_id INTEGER NOT NULL REFERENCES contacts(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_contact_aliases_contact ON contact_aliases(contact_id);
@@ -0,0 +1,5 @@
+-- V020: The part of a payment beyond what the invoice was owed, held for the
+-- customer as a credit. Positive when an overpayment adds to the customer's
+-- credit balance, negative when credit is spent paying an invoice.
+
+ALTER TABLE payments ADD COLUMN credit_cents INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,28 @@
+-- V021: Bills from vendors, owed until paid. A bill is kept off the ledger
+-- until it is paid: each payment posts the expense against the account it
+-- was paid from, so a partly paid bill has one entry per payment.
+
+CREATE TABLE IF NOT EXISTS bills (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    contact_id INTEGER NOT NULL REFERENCES contacts(id),
+    bill_number TEXT,
+    bill_date TEXT NOT NULL,
+    due_date TEXT NOT NULL,
+    amount_cents INTEGER NOT NULL,
+    account_id INTEGER NOT NULL REFERENCES accounts(id),
+    notes TEXT,
+    is_void INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE TABLE IF NOT EXISTS bill_payments (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    bill_id INTEGER NOT NULL REFERENCES bills(id) ON DELETE CASCADE,
+    amount_cents INTEGER NOT NULL,
+    date TEXT NOT NULL,
+    transaction_id INTEGER REFERENCES transactions(id),
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_bills_contact ON bills(contact_id);
+CREATE INDEX IF NOT EXISTS idx_bill_payments_bill ON bill_payments(bill_id);
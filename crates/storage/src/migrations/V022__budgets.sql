@@ -0,0 +1,8 @@
+-- V022: Monthly budgets for expense accounts. An account has at most one
+-- budget, the same amount every month.
+
+CREATE TABLE IF NOT EXISTS budgets (
+    account_id INTEGER PRIMARY KEY REFERENCES accounts(id) ON DELETE CASCADE,
+    monthly_cents INTEGER NOT NULL CHECK (monthly_cents > 0),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
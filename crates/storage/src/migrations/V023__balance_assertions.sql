@@ -0,0 +1,13 @@
+-- V023: Balances read off bank statements ("1000 held $X on D"), kept to
+-- check the ledger against. Amounts are in the account's normal sign, like
+-- reconciliation statement balances; one assertion per account and day.
+
+CREATE TABLE IF NOT EXISTS balance_assertions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    account_id INTEGER NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    date TEXT NOT NULL,
+    balance_cents INTEGER NOT NULL,
+    source TEXT NOT NULL DEFAULT 'statement',
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    UNIQUE (account_id, date)
+);
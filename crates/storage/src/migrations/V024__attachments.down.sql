@@ -0,0 +1,3 @@
+DROP TRIGGER IF EXISTS attachments_account_deleted;
+DROP TRIGGER IF EXISTS attachments_transaction_deleted;
+DROP TABLE IF EXISTS attachments;
@@ -0,0 +1,31 @@
+-- V024: Documents (contracts, warranties, statements) attached to ledger
+-- transactions and accounts. Files live in the content-addressed
+-- attachment store next to receipts, so the same file is stored once however
+-- often it is attached. Attachments go when what they are attached to does.
+
+CREATE TABLE IF NOT EXISTS attachments (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    entity_type TEXT NOT NULL CHECK (entity_type IN ('transaction', 'account')),
+    entity_id INTEGER NOT NULL,
+    file_hash TEXT NOT NULL,
+    file_ext TEXT NOT NULL,
+    file_name TEXT,
+    size_bytes INTEGER NOT NULL,
+    attachment_path TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    UNIQUE (entity_type, entity_id, file_hash)
+);
+
+CREATE INDEX IF NOT EXISTS idx_attachments_hash ON attachments(file_hash);
+
+CREATE TRIGGER IF NOT EXISTS attachments_transaction_deleted
+AFTER DELETE ON transactions
+BEGIN
+    DELETE FROM attachments WHERE entity_type = 'transaction' AND entity_id = OLD.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS attachments_account_deleted
+AFTER DELETE ON accounts
+BEGIN
+    DELETE FROM attachments WHERE entity_type = 'account' AND entity_id = OLD.id;
+END;
@@ -0,0 +1,3 @@
+DROP TRIGGER IF EXISTS documents_account_deleted;
+DROP TRIGGER IF EXISTS documents_transaction_deleted;
+DROP TABLE IF EXISTS documents;
@@ -0,0 +1,41 @@
+-- V025: A filing cabinet over the attachment store. Each document has a type,
+-- the period it covers, who it is from or with, and optionally the
+-- transaction or account it belongs to. When that transaction or account is
+-- deleted the document stays, unlinked.
+
+CREATE TABLE IF NOT EXISTS documents (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    doc_type TEXT NOT NULL,
+    title TEXT NOT NULL,
+    period_start TEXT,
+    period_end TEXT,
+    contact_id INTEGER REFERENCES contacts(id) ON DELETE SET NULL,
+    entity_type TEXT CHECK (entity_type IN ('transaction', 'account')),
+    entity_id INTEGER,
+    notes TEXT,
+    file_hash TEXT NOT NULL,
+    file_ext TEXT NOT NULL,
+    file_name TEXT,
+    size_bytes INTEGER NOT NULL,
+    attachment_path TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    CHECK ((entity_type IS NULL) = (entity_id IS NULL))
+);
+
+CREATE INDEX IF NOT EXISTS idx_documents_type ON documents(doc_type);
+CREATE INDEX IF NOT EXISTS idx_documents_period ON documents(period_start, period_end);
+CREATE INDEX IF NOT EXISTS idx_documents_hash ON documents(file_hash);
+
+CREATE TRIGGER IF NOT EXISTS documents_transaction_deleted
+AFTER DELETE ON transactions
+BEGIN
+    UPDATE documents SET entity_type = NULL, entity_id = NULL
+    WHERE entity_type = 'transaction' AND entity_id = OLD.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS documents_account_deleted
+AFTER DELETE ON accounts
+BEGIN
+    UPDATE documents SET entity_type = NULL, entity_id = NULL
+    WHERE entity_type = 'account' AND entity_id = OLD.id;
+END;
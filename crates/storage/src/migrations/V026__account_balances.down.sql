@@ -0,0 +1,6 @@
+DROP TRIGGER IF EXISTS account_balances_transaction_delete;
+DROP TRIGGER IF EXISTS account_balances_transaction_update;
+DROP TRIGGER IF EXISTS account_balances_line_update;
+DROP TRIGGER IF EXISTS account_balances_line_delete;
+DROP TRIGGER IF EXISTS account_balances_line_insert;
+DROP TABLE IF EXISTS account_balances;
@@ -0,0 +1,104 @@
+-- V026: Debits and credits per account per month, kept up to date by
+-- triggers as lines and transactions change, so reports over long periods
+-- add up a row per month instead of every line. Personal and business
+-- postings are kept apart so business-only reports can use it too.
+
+CREATE TABLE IF NOT EXISTS account_balances (
+    account_id INTEGER NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    -- YYYY-MM of the transaction date
+    month TEXT NOT NULL,
+    is_personal INTEGER NOT NULL,
+    debit_cents INTEGER NOT NULL DEFAULT 0,
+    credit_cents INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (account_id, month, is_personal)
+) WITHOUT ROWID;
+
+INSERT INTO account_balances (account_id, month, is_personal, debit_cents, credit_cents)
+SELECT tl.account_id, substr(t.date, 1, 7), t.is_personal,
+       SUM(tl.debit_cents), SUM(tl.credit_cents)
+FROM transaction_lines tl
+JOIN transactions t ON t.id = tl.transaction_id
+GROUP BY tl.account_id, substr(t.date, 1, 7), t.is_personal;
+
+CREATE TRIGGER IF NOT EXISTS account_balances_line_insert
+AFTER INSERT ON transaction_lines
+BEGIN
+    INSERT INTO account_balances (account_id, month, is_personal, debit_cents, credit_cents)
+        SELECT NEW.account_id, substr(t.date, 1, 7), t.is_personal,
+               NEW.debit_cents, NEW.credit_cents
+        FROM transactions t WHERE t.id = NEW.transaction_id
+        ON CONFLICT (account_id, month, is_personal) DO UPDATE SET
+            debit_cents = debit_cents + excluded.debit_cents,
+            credit_cents = credit_cents + excluded.credit_cents;
+END;
+
+-- A line removed along with its transaction was counted out beforehand by
+-- account_balances_transaction_delete, and finds no transaction here.
+CREATE TRIGGER IF NOT EXISTS account_balances_line_delete
+AFTER DELETE ON transaction_lines
+BEGIN
+    INSERT INTO account_balances (account_id, month, is_personal, debit_cents, credit_cents)
+        SELECT OLD.account_id, substr(t.date, 1, 7), t.is_personal,
+               -OLD.debit_cents, -OLD.credit_cents
+        FROM transactions t WHERE t.id = OLD.transaction_id
+        ON CONFLICT (account_id, month, is_personal) DO UPDATE SET
+            debit_cents = debit_cents + excluded.debit_cents,
+            credit_cents = credit_cents + excluded.credit_cents;
+END;
+
+CREATE TRIGGER IF NOT EXISTS account_balances_line_update
+AFTER UPDATE OF transaction_id, account_id, debit_cents, credit_cents ON transaction_lines
+BEGIN
+    INSERT INTO account_balances (account_id, month, is_personal, debit_cents, credit_cents)
+        SELECT OLD.account_id, substr(t.date, 1, 7), t.is_personal,
+               -OLD.debit_cents, -OLD.credit_cents
+        FROM transactions t WHERE t.id = OLD.transaction_id
+        ON CONFLICT (account_id, month, is_personal) DO UPDATE SET
+            debit_cents = debit_cents + excluded.debit_cents,
+            credit_cents = credit_cents + excluded.credit_cents;
+    INSERT INTO account_balances (account_id, month, is_personal, debit_cents, credit_cents)
+        SELECT NEW.account_id, substr(t.date, 1, 7), t.is_personal,
+               NEW.debit_cents, NEW.credit_cents
+        FROM transactions t WHERE t.id = NEW.transaction_id
+        ON CONFLICT (account_id, month, is_personal) DO UPDATE SET
+            debit_cents = debit_cents + excluded.debit_cents,
+            credit_cents = credit_cents + excluded.credit_cents;
+END;
+
+-- Moving a transaction to another month, or between business and
+-- personal, moves its lines' amounts with it.
+CREATE TRIGGER IF NOT EXISTS account_balances_transaction_update
+AFTER UPDATE OF date, is_personal ON transactions
+    WHEN substr(OLD.date, 1, 7) != substr(NEW.date, 1, 7)
+      OR OLD.is_personal != NEW.is_personal
+BEGIN
+    INSERT INTO account_balances (account_id, month, is_personal, debit_cents, credit_cents)
+        SELECT account_id, substr(OLD.date, 1, 7), OLD.is_personal,
+               -SUM(debit_cents), -SUM(credit_cents)
+        FROM transaction_lines WHERE transaction_id = OLD.id
+        GROUP BY account_id
+        ON CONFLICT (account_id, month, is_personal) DO UPDATE SET
+            debit_cents = debit_cents + excluded.debit_cents,
+            credit_cents = credit_cents + excluded.credit_cents;
+    INSERT INTO account_balances (account_id, month, is_personal, debit_cents, credit_cents)
+        SELECT account_id, substr(NEW.date, 1, 7), NEW.is_personal,
+               SUM(debit_cents), SUM(credit_cents)
+        FROM transaction_lines WHERE transaction_id = NEW.id
+        GROUP BY account_id
+        ON CONFLICT (account_id, month, is_personal) DO UPDATE SET
+            debit_cents = debit_cents + excluded.debit_cents,
+            credit_cents = credit_cents + excluded.credit_cents;
+END;
+
+CREATE TRIGGER IF NOT EXISTS account_balances_transaction_delete
+BEFORE DELETE ON transactions
+BEGIN
+    INSERT INTO account_balances (account_id, month, is_personal, debit_cents, credit_cents)
+        SELECT account_id, substr(OLD.date, 1, 7), OLD.is_personal,
+               -SUM(debit_cents), -SUM(credit_cents)
+        FROM transaction_lines WHERE transaction_id = OLD.id
+        GROUP BY account_id
+        ON CONFLICT (account_id, month, is_personal) DO UPDATE SET
+            debit_cents = debit_cents + excluded.debit_cents,
+            credit_cents = credit_cents + excluded.credit_cents;
+END;
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_receipt_jobs_status;
+DROP INDEX IF EXISTS idx_receipt_jobs_active_path;
+DROP TABLE IF EXISTS receipt_jobs;
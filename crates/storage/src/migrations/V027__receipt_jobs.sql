@@ -0,0 +1,25 @@
+-- V027: The receipt intake queue. Each file picked up by the intake folder
+-- watcher is a job here before it is processed, so files waiting or in
+-- progress when the app exits are picked up again on the next launch.
+-- A file is queued at most once at a time; a job that keeps failing ends
+-- up 'dead' for the user to look at.
+
+CREATE TABLE IF NOT EXISTS receipt_jobs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    path TEXT NOT NULL,
+    source TEXT NOT NULL DEFAULT 'watcher',
+    status TEXT NOT NULL DEFAULT 'queued'
+        CHECK (status IN ('queued', 'processing', 'done', 'dead')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    -- Not tried again before this time, after a failure.
+    available_at TEXT NOT NULL DEFAULT (datetime('now')),
+    enqueued_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_receipt_jobs_active_path
+    ON receipt_jobs(path) WHERE status IN ('queued', 'processing');
+
+CREATE INDEX IF NOT EXISTS idx_receipt_jobs_status
+    ON receipt_jobs(status, available_at);
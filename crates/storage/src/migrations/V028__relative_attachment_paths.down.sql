@@ -0,0 +1,5 @@
+-- V028 rollback: Relative paths can't be made absolute again without the
+-- store's location, which the database doesn't hold. They are left as they
+-- are.
+
+SELECT 1;
@@ -0,0 +1,29 @@
+-- V028: Record stored files by their path inside the attachment store,
+-- `<first two hex chars>/<hash>.<ext>`, rather than by absolute path, so the
+-- ledger survives the data directory moving, the user being renamed, or a
+-- restore on another machine. Rows whose path doesn't end in that layout
+-- are left absolute; they are still read as they are.
+
+UPDATE receipts
+SET attachment_path = replace(
+        substr(attachment_path, -(length(file_hash) + length(file_ext) + 4)), '\', '/')
+WHERE length(file_hash) > 2
+  AND lower(substr(attachment_path, -(length(file_hash) + length(file_ext) + 5))) IN (
+      lower('/' || substr(file_hash, 1, 2) || '/' || file_hash || '.' || file_ext),
+      lower('\' || substr(file_hash, 1, 2) || '\' || file_hash || '.' || file_ext));
+
+UPDATE attachments
+SET attachment_path = replace(
+        substr(attachment_path, -(length(file_hash) + length(file_ext) + 4)), '\', '/')
+WHERE length(file_hash) > 2
+  AND lower(substr(attachment_path, -(length(file_hash) + length(file_ext) + 5))) IN (
+      lower('/' || substr(file_hash, 1, 2) || '/' || file_hash || '.' || file_ext),
+      lower('\' || substr(file_hash, 1, 2) || '\' || file_hash || '.' || file_ext));
+
+UPDATE documents
+SET attachment_path = replace(
+        substr(attachment_path, -(length(file_hash) + length(file_ext) + 4)), '\', '/')
+WHERE length(file_hash) > 2
+  AND lower(substr(attachment_path, -(length(file_hash) + length(file_ext) + 5))) IN (
+      lower('/' || substr(file_hash, 1, 2) || '/' || file_hash || '.' || file_ext),
+      lower('\' || substr(file_hash, 1, 2) || '\' || file_hash || '.' || file_ext));
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_receipts_duplicate_of;
+ALTER TABLE receipts DROP COLUMN duplicate_of;
@@ -0,0 +1,7 @@
+-- V029: The receipt a receipt marked `duplicate` looks like a second copy
+-- of: same vendor, date and total. Set when the receipt is stored; cleared
+-- if the user says it's a purchase of its own.
+
+ALTER TABLE receipts ADD COLUMN duplicate_of INTEGER;
+
+CREATE INDEX IF NOT EXISTS idx_receipts_duplicate_of ON receipts(duplicate_of);
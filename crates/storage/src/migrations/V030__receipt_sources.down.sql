@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_receipts_source_hash;
+ALTER TABLE receipts DROP COLUMN source_path;
+ALTER TABLE receipts DROP COLUMN source_hash;
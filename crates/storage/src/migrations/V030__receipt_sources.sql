@@ -0,0 +1,8 @@
+-- V030: The photo a receipt was cut out of, when one photo held several.
+-- Receipts from the same photo share its hash and stored path; the photo
+-- itself has no receipt of its own.
+
+ALTER TABLE receipts ADD COLUMN source_hash TEXT;
+ALTER TABLE receipts ADD COLUMN source_path TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_receipts_source_hash ON receipts(source_hash);
@@ -0,0 +1,6 @@
+-- V031: How much of an account's spending is deductible, for accounts
+-- whose rule used to live only in their name. Applied on top of each
+-- transaction's own deductible_percent in the tax reports.
+
+ALTER TABLE accounts ADD COLUMN deductible_pct INTEGER NOT NULL DEFAULT 100
+    CHECK(deductible_pct BETWEEN 0 AND 100);
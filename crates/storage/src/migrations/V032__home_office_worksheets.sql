@@ -0,0 +1,19 @@
+-- V032: Home office deduction worksheets, one per tax year, kept as worked
+-- out so the line 30 figure can be backed up later. `expenses` is a JSON
+-- array of the home's costs; `transaction_id` is the entry posting the
+-- deduction, once posted.
+
+CREATE TABLE IF NOT EXISTS home_office_worksheets (
+    year INTEGER PRIMARY KEY,
+    method TEXT NOT NULL CHECK (method IN ('simplified', 'regular')),
+    office_sqft INTEGER NOT NULL CHECK (office_sqft > 0),
+    home_sqft INTEGER NOT NULL CHECK (home_sqft >= 0),
+    expenses TEXT NOT NULL DEFAULT '[]',
+    prior_carryover_cents INTEGER NOT NULL DEFAULT 0,
+    tentative_profit_cents INTEGER NOT NULL,
+    allowable_cents INTEGER NOT NULL,
+    deduction_cents INTEGER NOT NULL,
+    carryover_cents INTEGER NOT NULL DEFAULT 0,
+    transaction_id INTEGER REFERENCES transactions(id) ON DELETE SET NULL,
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
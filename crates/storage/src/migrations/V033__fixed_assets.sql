@@ -0,0 +1,13 @@
+-- V033: Equipment capitalized as fixed assets. Each comes from one
+-- transaction whose equipment expense was moved to an asset account.
+
+CREATE TABLE IF NOT EXISTS fixed_assets (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    transaction_id INTEGER NOT NULL UNIQUE REFERENCES transactions(id) ON DELETE CASCADE,
+    description TEXT NOT NULL,
+    placed_in_service TEXT NOT NULL,
+    cost_cents INTEGER NOT NULL CHECK (cost_cents > 0),
+    recovery_years INTEGER NOT NULL CHECK (recovery_years > 0),
+    account_id INTEGER NOT NULL REFERENCES accounts(id),
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS saved_reports;
+DROP INDEX IF EXISTS idx_transaction_tags_tag;
+DROP TABLE IF EXISTS transaction_tags;
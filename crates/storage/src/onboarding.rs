@@ -0,0 +1,589 @@
+//! First-run setup.
+//!
+//! The setup wizard walks through the business profile, fiscal year and
+//! currency, a starting chart of accounts, opening balances and the receipt
+//! intake folder. Each step is saved as soon as it is done, so the wizard
+//! can be left and resumed; [`complete_onboarding`] marks the whole thing
+//! finished.
+
+use std::collections::HashSet;
+
+use aequi_core::{
+    Account, AccountType, ChartTemplate, Money, TransactionLine, UnvalidatedTransaction,
+    ValidatedTransaction, DEFAULT_ACCOUNTS, OPENING_BALANCE_EQUITY_CODE,
+};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::db::{get_account_by_code, get_preferences, get_setting, set_setting, DbPool};
+
+/// Setting holding the [`BusinessProfile`], as JSON.
+pub const BUSINESS_PROFILE_SETTING: &str = "business_profile";
+/// Setting holding the [`OnboardingState`], as JSON.
+pub const ONBOARDING_SETTING: &str = "onboarding";
+/// Setting holding the receipt intake folder chosen during setup.
+pub const INTAKE_FOLDER_SETTING: &str = "receipt_intake_folder";
+
+const OPENING_BALANCE_DESCRIPTION: &str = "Opening balances";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BusinessType {
+    #[default]
+    SoleProprietorship,
+    SingleMemberLlc,
+    Partnership,
+    Corporation,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BusinessProfile {
+    pub name: String,
+    pub business_type: BusinessType,
+}
+
+/// How far the setup wizard has got.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OnboardingState {
+    pub chart_template: Option<ChartTemplate>,
+    /// The transaction holding the opening balances, replaced whenever
+    /// they are entered again.
+    pub opening_balance_transaction_id: Option<i64>,
+    pub completed_at: Option<String>,
+}
+
+/// One account's balance when the books start. Positive amounts are in the
+/// account's normal direction: money held for assets, money owed for
+/// liabilities.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpeningBalance {
+    pub account_code: String,
+    pub amount_cents: i64,
+}
+
+/// Everything the setup wizard has saved so far.
+#[derive(Debug, Clone, Serialize)]
+pub struct OnboardingStatus {
+    pub completed: bool,
+    pub completed_at: Option<String>,
+    pub business_profile: Option<BusinessProfile>,
+    pub base_currency: String,
+    pub fiscal_year_start_month: u32,
+    pub chart_template: Option<ChartTemplate>,
+    pub opening_balance_date: Option<String>,
+    pub opening_balances: Vec<OpeningBalance>,
+    pub intake_folder: Option<String>,
+}
+
+/// What applying a chart template changed.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ChartTemplateOutcome {
+    /// Template accounts created or brought back from the archive.
+    pub added: Vec<String>,
+    /// Default accounts outside the template that were archived.
+    pub archived: Vec<String>,
+    /// Default accounts outside the template kept because they have
+    /// transactions.
+    pub kept: Vec<String>,
+}
+
+pub async fn get_business_profile(pool: &DbPool) -> Result<Option<BusinessProfile>, sqlx::Error> {
+    match get_setting(pool, BUSINESS_PROFILE_SETTING).await? {
+        Some(json) if !json.trim().is_empty() => serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|e| sqlx::Error::Decode(Box::new(e))),
+        _ => Ok(None),
+    }
+}
+
+pub async fn set_business_profile(
+    pool: &DbPool,
+    profile: &BusinessProfile,
+) -> Result<(), sqlx::Error> {
+    let json = serde_json::to_string(profile).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    set_setting(pool, BUSINESS_PROFILE_SETTING, &json).await
+}
+
+pub async fn get_onboarding_state(pool: &DbPool) -> Result<OnboardingState, sqlx::Error> {
+    match get_setting(pool, ONBOARDING_SETTING).await? {
+        Some(json) if !json.trim().is_empty() => {
+            serde_json::from_str(&json).map_err(|e| sqlx::Error::Decode(Box::new(e)))
+        }
+        _ => Ok(OnboardingState::default()),
+    }
+}
+
+async fn set_onboarding_state(pool: &DbPool, state: &OnboardingState) -> Result<(), sqlx::Error> {
+    let json = serde_json::to_string(state).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    set_setting(pool, ONBOARDING_SETTING, &json).await
+}
+
+pub async fn get_onboarding_status(pool: &DbPool) -> Result<OnboardingStatus, sqlx::Error> {
+    let state = get_onboarding_state(pool).await?;
+    let prefs = get_preferences(pool).await?;
+    let (opening_balance_date, opening_balances) = match state.opening_balance_transaction_id {
+        Some(id) => get_opening_balances(pool, id).await?,
+        None => (None, Vec::new()),
+    };
+    Ok(OnboardingStatus {
+        completed: state.completed_at.is_some(),
+        completed_at: state.completed_at,
+        business_profile: get_business_profile(pool).await?,
+        base_currency: prefs.default_currency,
+        fiscal_year_start_month: prefs.fiscal_year_start_month,
+        chart_template: state.chart_template,
+        opening_balance_date,
+        opening_balances,
+        intake_folder: get_setting(pool, INTAKE_FOLDER_SETTING).await?,
+    })
+}
+
+/// Read the opening balances back from their transaction.
+async fn get_opening_balances(
+    pool: &DbPool,
+    transaction_id: i64,
+) -> Result<(Option<String>, Vec<OpeningBalance>), sqlx::Error> {
+    let date: Option<(String,)> = sqlx::query_as("SELECT date FROM transactions WHERE id = ?")
+        .bind(transaction_id)
+        .fetch_optional(pool)
+        .await?;
+    let Some((date,)) = date else {
+        return Ok((None, Vec::new()));
+    };
+    let rows: Vec<(String, String, i64, i64)> = sqlx::query_as(
+        r#"SELECT a.code, a.account_type, tl.debit_cents, tl.credit_cents
+           FROM transaction_lines tl JOIN accounts a ON a.id = tl.account_id
+           WHERE tl.transaction_id = ? AND a.code != ?
+           ORDER BY a.code"#,
+    )
+    .bind(transaction_id)
+    .bind(OPENING_BALANCE_EQUITY_CODE)
+    .fetch_all(pool)
+    .await?;
+    let balances = rows
+        .into_iter()
+        .map(|(code, account_type, debit, credit)| OpeningBalance {
+            account_code: code,
+            amount_cents: if account_type == "Asset" {
+                debit - credit
+            } else {
+                credit - debit
+            },
+        })
+        .collect();
+    Ok((Some(date), balances))
+}
+
+/// Make the chart of accounts match `template`: its accounts are created
+/// (or unarchived) and default accounts outside it are archived, unless
+/// they already have transactions. Accounts the user added are left alone.
+pub async fn apply_chart_template(
+    pool: &DbPool,
+    template: ChartTemplate,
+) -> Result<ChartTemplateOutcome, sqlx::Error> {
+    let mut outcome = ChartTemplateOutcome::default();
+    let mut tx = pool.begin().await?;
+    for (code, name, account_type, schedule_c_line) in DEFAULT_ACCOUNTS {
+        if template.includes(code) {
+            let inserted = sqlx::query(
+                "INSERT OR IGNORE INTO accounts (code, name, account_type, is_archetype, schedule_c_line) VALUES (?, ?, ?, 1, ?)",
+            )
+            .bind(code)
+            .bind(name)
+            .bind(account_type.to_string())
+            .bind(if schedule_c_line.is_empty() { None } else { Some(*schedule_c_line) })
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            let restored = sqlx::query(
+                "UPDATE accounts SET is_archived = 0 WHERE code = ? AND is_archived = 1",
+            )
+            .bind(code)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            if inserted + restored > 0 {
+                outcome.added.push(code.to_string());
+            }
+            continue;
+        }
+
+        let used: Option<(i64,)> = sqlx::query_as(
+            r#"SELECT 1 FROM transaction_lines tl JOIN accounts a ON a.id = tl.account_id
+               WHERE a.code = ? LIMIT 1"#,
+        )
+        .bind(code)
+        .fetch_optional(&mut *tx)
+        .await?;
+        if used.is_some() {
+            outcome.kept.push(code.to_string());
+            continue;
+        }
+        let archived =
+            sqlx::query("UPDATE accounts SET is_archived = 1 WHERE code = ? AND is_archived = 0")
+                .bind(code)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+        if archived > 0 {
+            outcome.archived.push(code.to_string());
+        }
+    }
+    tx.commit().await?;
+
+    let mut state = get_onboarding_state(pool).await?;
+    state.chart_template = Some(template);
+    set_onboarding_state(pool, &state).await?;
+    Ok(outcome)
+}
+
+/// Record the balances the books start with as of `as_of`, offset against
+/// owner's equity. Entering them again replaces the earlier entry; an empty
+/// list removes it. Returns the transaction id.
+pub async fn set_opening_balances(
+    pool: &DbPool,
+    as_of: NaiveDate,
+    balances: &[OpeningBalance],
+) -> Result<Option<i64>, OnboardingError> {
+    let mut seen = HashSet::new();
+    let mut lines = Vec::new();
+    let mut equity_cents = 0i64;
+    for balance in balances.iter().filter(|b| b.amount_cents != 0) {
+        if !seen.insert(balance.account_code.as_str()) {
+            return Err(OnboardingError::Invalid(format!(
+                "Account {} is listed twice",
+                balance.account_code
+            )));
+        }
+        let account = get_account_by_code(pool, &balance.account_code)
+            .await?
+            .filter(|a| !a.is_archived)
+            .ok_or_else(|| {
+                OnboardingError::Invalid(format!("Unknown account {}", balance.account_code))
+            })?;
+        // Debit-positive amount for this line.
+        let signed = match account.account_type {
+            AccountType::Asset => balance.amount_cents,
+            AccountType::Liability => -balance.amount_cents,
+            _ => {
+                return Err(OnboardingError::Invalid(format!(
+                    "Opening balances are for asset and liability accounts; {} is {}",
+                    account.code, account.account_type
+                )))
+            }
+        };
+        equity_cents -= signed;
+        lines.push(line(&account, signed)?);
+    }
+
+    if equity_cents != 0 {
+        let equity = get_account_by_code(pool, OPENING_BALANCE_EQUITY_CODE)
+            .await?
+            .ok_or_else(|| {
+                OnboardingError::Invalid(format!(
+                    "Equity account {OPENING_BALANCE_EQUITY_CODE} is missing"
+                ))
+            })?;
+        lines.push(line(&equity, equity_cents)?);
+    }
+    let validated = if lines.is_empty() {
+        None
+    } else {
+        Some(
+            ValidatedTransaction::validate(UnvalidatedTransaction {
+                date: as_of,
+                description: OPENING_BALANCE_DESCRIPTION.to_string(),
+                lines,
+                memo: None,
+            })
+            .map_err(|e| OnboardingError::Invalid(e.to_string()))?,
+        )
+    };
+
+    let mut state = get_onboarding_state(pool).await?;
+    let mut tx = pool.begin().await?;
+    if let Some(id) = state.opening_balance_transaction_id {
+        sqlx::query("DELETE FROM transaction_lines WHERE transaction_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    let Some(validated) = validated else {
+        if let Some(id) = state.opening_balance_transaction_id.take() {
+            sqlx::query(
+                "UPDATE reconciliation_items SET transaction_id = NULL WHERE transaction_id = ?",
+            )
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query("DELETE FROM transactions WHERE id = ?")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        set_onboarding_state(pool, &state).await?;
+        return Ok(None);
+    };
+
+    let existing = match state.opening_balance_transaction_id {
+        Some(id) => {
+            sqlx::query("UPDATE transactions SET date = ?, balanced_total_cents = ? WHERE id = ?")
+                .bind(as_of.to_string())
+                .bind(validated.balanced_total.to_cents())
+                .bind(id)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected()
+                > 0
+        }
+        None => false,
+    };
+    let id = match state.opening_balance_transaction_id {
+        Some(id) if existing => id,
+        _ => sqlx::query(
+            "INSERT INTO transactions (date, description, balanced_total_cents) VALUES (?, ?, ?)",
+        )
+        .bind(as_of.to_string())
+        .bind(&validated.description)
+        .bind(validated.balanced_total.to_cents())
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid(),
+    };
+    for line in &validated.lines {
+        sqlx::query(
+            "INSERT INTO transaction_lines (transaction_id, account_id, debit_cents, credit_cents) VALUES (?, ?, ?, ?)",
+        )
+        .bind(id)
+        .bind(line.account_id.0)
+        .bind(line.debit.to_cents())
+        .bind(line.credit.to_cents())
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+
+    state.opening_balance_transaction_id = Some(id);
+    set_onboarding_state(pool, &state).await?;
+    Ok(Some(id))
+}
+
+/// A line for `signed_cents`, debit when positive and credit when negative.
+fn line(account: &Account, signed_cents: i64) -> Result<TransactionLine, OnboardingError> {
+    Ok(TransactionLine {
+        account_id: account.id.ok_or_else(|| {
+            OnboardingError::Invalid(format!("Account {} has no id", account.code))
+        })?,
+        debit: Money::from_cents(signed_cents.max(0)),
+        credit: Money::from_cents((-signed_cents).max(0)),
+        memo: None,
+    })
+}
+
+/// Mark setup finished. The wizard isn't shown again once this is set.
+pub async fn complete_onboarding(pool: &DbPool) -> Result<OnboardingState, sqlx::Error> {
+    let mut state = get_onboarding_state(pool).await?;
+    if state.completed_at.is_none() {
+        state.completed_at = Some(
+            chrono::Utc::now()
+                .naive_utc()
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string(),
+        );
+        set_onboarding_state(pool, &state).await?;
+    }
+    Ok(state)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum OnboardingError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("{0}")]
+    Invalid(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn balance(code: &str, cents: i64) -> OpeningBalance {
+        OpeningBalance {
+            account_code: code.to_string(),
+            amount_cents: cents,
+        }
+    }
+
+    /// Sum of (debit, credit) per account code on a transaction.
+    async fn lines(pool: &DbPool, id: i64) -> Vec<(String, i64, i64)> {
+        sqlx::query_as(
+            r#"SELECT a.code, tl.debit_cents, tl.credit_cents
+               FROM transaction_lines tl JOIN accounts a ON a.id = tl.account_id
+               WHERE tl.transaction_id = ? ORDER BY a.code"#,
+        )
+        .bind(id)
+        .fetch_all(pool)
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn chart_template_archives_unused_defaults() {
+        let pool = test_pool().await;
+        // Equipment has activity, so it survives the minimal template.
+        set_opening_balances(&pool, date("2025-12-31"), &[balance("1000", 100)])
+            .await
+            .unwrap();
+        sqlx::query(
+            r#"INSERT INTO transaction_lines (transaction_id, account_id, debit_cents, credit_cents)
+               VALUES (1, (SELECT id FROM accounts WHERE code = '5040'), 0, 0)"#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let outcome = apply_chart_template(&pool, ChartTemplate::Minimal)
+            .await
+            .unwrap();
+        assert!(outcome.added.is_empty());
+        assert!(outcome.archived.contains(&"4010".to_string()));
+        assert_eq!(outcome.kept, ["5040"]);
+        let codes: Vec<_> = crate::db::get_all_accounts(&pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|a| a.code)
+            .collect();
+        assert!(codes.contains(&"5040".to_string()));
+        assert!(!codes.contains(&"4010".to_string()));
+        assert_eq!(
+            get_onboarding_state(&pool).await.unwrap().chart_template,
+            Some(ChartTemplate::Minimal)
+        );
+
+        // Archived accounts stay archived when defaults are seeded again.
+        crate::db::seed_default_accounts(&pool).await.unwrap();
+        let outcome = apply_chart_template(&pool, ChartTemplate::Services)
+            .await
+            .unwrap();
+        assert!(outcome.added.contains(&"5120".to_string()));
+        assert_eq!(outcome.archived, Vec::<String>::new());
+        assert!(!outcome.added.contains(&"4010".to_string()));
+    }
+
+    #[tokio::test]
+    async fn opening_balances_post_against_equity_and_replace() {
+        let pool = test_pool().await;
+        let id = set_opening_balances(
+            &pool,
+            date("2025-12-31"),
+            &[
+                balance("1000", 500_000),
+                balance("2000", 120_000),
+                balance("1010", 0),
+            ],
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            lines(&pool, id).await,
+            [
+                ("1000".to_string(), 500_000, 0),
+                ("2000".to_string(), 0, 120_000),
+                ("3000".to_string(), 0, 380_000),
+            ]
+        );
+
+        let status = get_onboarding_status(&pool).await.unwrap();
+        assert_eq!(status.opening_balance_date.as_deref(), Some("2025-12-31"));
+        assert_eq!(
+            status.opening_balances,
+            [balance("1000", 500_000), balance("2000", 120_000)]
+        );
+
+        // Entered again: same transaction, new lines.
+        let again = set_opening_balances(&pool, date("2026-01-01"), &[balance("1000", -2_500)])
+            .await
+            .unwrap();
+        assert_eq!(again, Some(id));
+        assert_eq!(
+            lines(&pool, id).await,
+            [
+                ("1000".to_string(), 0, 2_500),
+                ("3000".to_string(), 2_500, 0)
+            ]
+        );
+
+        for bad in [
+            vec![balance("4000", 100)],
+            vec![balance("9999", 100)],
+            vec![balance("1000", 100), balance("1000", 200)],
+        ] {
+            assert!(matches!(
+                set_opening_balances(&pool, date("2026-01-01"), &bad).await,
+                Err(OnboardingError::Invalid(_))
+            ));
+        }
+        assert_eq!(lines(&pool, id).await.len(), 2);
+
+        assert_eq!(
+            set_opening_balances(&pool, date("2026-01-01"), &[])
+                .await
+                .unwrap(),
+            None
+        );
+        let remaining: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM transactions")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(remaining.0, 0);
+        assert!(get_onboarding_status(&pool)
+            .await
+            .unwrap()
+            .opening_balances
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn status_reflects_saved_steps() {
+        let pool = test_pool().await;
+        let status = get_onboarding_status(&pool).await.unwrap();
+        assert!(!status.completed);
+        assert!(status.business_profile.is_none());
+        assert_eq!(status.base_currency, "USD");
+
+        let profile = BusinessProfile {
+            name: "Hill Design".to_string(),
+            business_type: BusinessType::SingleMemberLlc,
+        };
+        set_business_profile(&pool, &profile).await.unwrap();
+        set_setting(&pool, crate::db::FISCAL_YEAR_START_SETTING, "7")
+            .await
+            .unwrap();
+        set_setting(&pool, INTAKE_FOLDER_SETTING, "/tmp/receipts")
+            .await
+            .unwrap();
+        let completed_at = complete_onboarding(&pool).await.unwrap().completed_at;
+        assert!(completed_at.is_some());
+        // Completing twice keeps the first time.
+        assert_eq!(
+            complete_onboarding(&pool).await.unwrap().completed_at,
+            completed_at
+        );
+
+        let status = get_onboarding_status(&pool).await.unwrap();
+        assert!(status.completed);
+        assert_eq!(status.business_profile, Some(profile));
+        assert_eq!(status.fiscal_year_start_month, 7);
+        assert_eq!(status.intake_folder.as_deref(), Some("/tmp/receipts"));
+    }
+}
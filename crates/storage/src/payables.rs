@@ -0,0 +1,266 @@
+//! Bills from vendors and paying them.
+//!
+//! Bills are kept on a cash basis: nothing is posted when a bill is entered,
+//! and each payment posts the bill's expense against the account it was paid
+//! from. What is still owed is the bill's amount less its payments.
+
+use aequi_core::AccountType;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::db::{get_account_by_code, DbPool};
+use crate::receivables::PaymentError;
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Bill {
+    pub id: i64,
+    pub contact_id: i64,
+    pub contact_name: String,
+    /// The vendor's number for the bill.
+    pub bill_number: Option<String>,
+    pub bill_date: String,
+    pub due_date: String,
+    pub amount_cents: i64,
+    /// The expense account payments are booked to.
+    pub account_id: i64,
+    pub notes: Option<String>,
+    pub is_void: bool,
+    pub created_at: String,
+    pub paid_cents: i64,
+}
+
+impl Bill {
+    pub fn open_cents(&self) -> i64 {
+        if self.is_void {
+            0
+        } else {
+            (self.amount_cents - self.paid_cents).max(0)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewBill {
+    pub contact_id: i64,
+    pub bill_number: Option<String>,
+    pub bill_date: NaiveDate,
+    pub due_date: NaiveDate,
+    pub amount_cents: i64,
+    pub account_id: i64,
+    pub notes: Option<String>,
+}
+
+/// A payment made on a bill.
+#[derive(Debug, Clone, Serialize)]
+pub struct BillPayment {
+    pub payment_id: i64,
+    /// The entry posting the expense.
+    pub transaction_id: i64,
+    /// What is still owed after the payment.
+    pub open_cents: i64,
+}
+
+const BILL_SELECT: &str = r#"SELECT b.id, b.contact_id, c.name AS contact_name, b.bill_number,
+       b.bill_date, b.due_date, b.amount_cents, b.account_id, b.notes, b.is_void,
+       b.created_at,
+       COALESCE((SELECT SUM(amount_cents) FROM bill_payments WHERE bill_id = b.id), 0)
+           AS paid_cents
+    FROM bills b JOIN contacts c ON c.id = b.contact_id"#;
+
+pub async fn insert_bill(pool: &DbPool, bill: &NewBill) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"INSERT INTO bills (contact_id, bill_number, bill_date, due_date, amount_cents,
+           account_id, notes) VALUES (?, ?, ?, ?, ?, ?, ?) RETURNING id"#,
+    )
+    .bind(bill.contact_id)
+    .bind(&bill.bill_number)
+    .bind(bill.bill_date.to_string())
+    .bind(bill.due_date.to_string())
+    .bind(bill.amount_cents)
+    .bind(bill.account_id)
+    .bind(&bill.notes)
+    .fetch_one(pool)
+    .await
+}
+
+pub async fn get_bill(pool: &DbPool, id: i64) -> Result<Option<Bill>, sqlx::Error> {
+    sqlx::query_as::<_, Bill>(&format!("{BILL_SELECT} WHERE b.id = ?"))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Bills, earliest due first. With `open_only`, just those not void and not
+/// paid in full.
+pub async fn get_bills(pool: &DbPool, open_only: bool) -> Result<Vec<Bill>, sqlx::Error> {
+    let bills = sqlx::query_as::<_, Bill>(&format!("{BILL_SELECT} ORDER BY b.due_date, b.id"))
+        .fetch_all(pool)
+        .await?;
+    Ok(bills
+        .into_iter()
+        .filter(|b| !open_only || b.open_cents() > 0)
+        .collect())
+}
+
+/// Void a bill with no payments. Returns whether it was voided.
+pub async fn void_bill(pool: &DbPool, id: i64) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"UPDATE bills SET is_void = 1 WHERE id = ?1
+           AND NOT EXISTS (SELECT 1 FROM bill_payments WHERE bill_id = ?1)"#,
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Pay some or all of what is owed on a bill from a bank, cash or card
+/// account, posting the expense.
+pub async fn record_bill_payment(
+    pool: &DbPool,
+    bill_id: i64,
+    amount_cents: i64,
+    date: NaiveDate,
+    paid_from_code: &str,
+) -> Result<BillPayment, PaymentError> {
+    let bill = get_bill(pool, bill_id)
+        .await?
+        .ok_or_else(|| PaymentError::NotFound(format!("Bill {bill_id} not found")))?;
+    if bill.is_void {
+        return Err(PaymentError::Invalid("Bill is void".into()));
+    }
+    if amount_cents <= 0 {
+        return Err(PaymentError::Invalid(
+            "Payment amount must be positive".into(),
+        ));
+    }
+    if amount_cents > bill.open_cents() {
+        return Err(PaymentError::Invalid(format!(
+            "Payment exceeds the {} still owed",
+            aequi_core::Money::from_cents(bill.open_cents())
+        )));
+    }
+    let paid_from = get_account_by_code(pool, paid_from_code)
+        .await?
+        .ok_or_else(|| PaymentError::NotFound(format!("Account {paid_from_code} not found")))?;
+    if !matches!(
+        paid_from.account_type,
+        AccountType::Asset | AccountType::Liability
+    ) || paid_from.is_archived
+    {
+        return Err(PaymentError::Invalid(format!(
+            "Bills must be paid from an active bank, cash or card account, not {} {}",
+            paid_from.code, paid_from.name
+        )));
+    }
+
+    let description = match &bill.bill_number {
+        Some(number) => format!("{} bill {number}", bill.contact_name),
+        None => format!("{} bill", bill.contact_name),
+    };
+    let mut tx = pool.begin().await?;
+    let transaction_id: i64 = sqlx::query_scalar(
+        r#"INSERT INTO transactions (date, description, balanced_total_cents)
+           VALUES (?, ?, ?) RETURNING id"#,
+    )
+    .bind(date.to_string())
+    .bind(description)
+    .bind(amount_cents)
+    .fetch_one(&mut *tx)
+    .await?;
+    for (account_id, debit, credit) in [
+        (Some(bill.account_id), amount_cents, 0),
+        (paid_from.id.map(|id| id.0), 0, amount_cents),
+    ] {
+        sqlx::query(
+            r#"INSERT INTO transaction_lines (transaction_id, account_id, debit_cents,
+               credit_cents) VALUES (?, ?, ?, ?)"#,
+        )
+        .bind(transaction_id)
+        .bind(account_id)
+        .bind(debit)
+        .bind(credit)
+        .execute(&mut *tx)
+        .await?;
+    }
+    let payment_id: i64 = sqlx::query_scalar(
+        r#"INSERT INTO bill_payments (bill_id, amount_cents, date, transaction_id)
+           VALUES (?, ?, ?, ?) RETURNING id"#,
+    )
+    .bind(bill_id)
+    .bind(amount_cents)
+    .bind(date.to_string())
+    .bind(transaction_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(BillPayment {
+        payment_id,
+        transaction_id,
+        open_cents: bill.open_cents() - amount_cents,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    #[tokio::test]
+    async fn bill_paid_in_parts_posts_the_expense() {
+        let pool = test_pool().await;
+        sqlx::query(
+            "INSERT INTO contacts (id, name, contact_type) VALUES (1, 'Landlord', 'Vendor')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let rent = get_account_by_code(&pool, "5900").await.unwrap().unwrap();
+        let date = |d| NaiveDate::from_ymd_opt(2026, 3, d).unwrap();
+        let id = insert_bill(
+            &pool,
+            &NewBill {
+                contact_id: 1,
+                bill_number: Some("R-3".into()),
+                bill_date: date(1),
+                due_date: date(15),
+                amount_cents: 120_000,
+                account_id: rent.id.unwrap().0,
+                notes: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        record_bill_payment(&pool, id, 50_000, date(10), "1000")
+            .await
+            .unwrap();
+        assert!(matches!(
+            record_bill_payment(&pool, id, 80_000, date(12), "1000").await,
+            Err(PaymentError::Invalid(_))
+        ));
+        assert!(matches!(
+            record_bill_payment(&pool, id, 100, date(12), "4000").await,
+            Err(PaymentError::Invalid(_))
+        ));
+        assert_eq!(
+            get_bills(&pool, true).await.unwrap()[0].open_cents(),
+            70_000
+        );
+
+        record_bill_payment(&pool, id, 70_000, date(14), "2000")
+            .await
+            .unwrap();
+        assert!(get_bills(&pool, true).await.unwrap().is_empty());
+        assert!(!void_bill(&pool, id).await.unwrap());
+
+        let expensed: i64 = sqlx::query_scalar(
+            "SELECT SUM(debit_cents) FROM transaction_lines WHERE account_id = ?",
+        )
+        .bind(rent.id.unwrap().0)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(expensed, 120_000);
+    }
+}
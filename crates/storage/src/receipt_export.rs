@@ -0,0 +1,315 @@
+//! Receipt bundles for the accountant.
+//!
+//! A zip of every receipt dated within a range, each renamed
+//! `YYYY-MM-DD_vendor_total.ext`, plus an `index.csv` tying each file to the
+//! ledger transaction it supports.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::{Seek, Write};
+use std::path::Path;
+
+use zip::write::SimpleFileOptions;
+use zip::CompressionMethod;
+
+/// What went into an export.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ReceiptExportSummary {
+    pub receipt_count: u64,
+    /// Receipts listed in the index whose file could not be read.
+    pub missing_files: Vec<i64>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct ExportRow {
+    id: i64,
+    file_ext: String,
+    attachment_path: String,
+    vendor: Option<String>,
+    date: Option<String>,
+    total_cents: Option<i64>,
+    payment_method: Option<String>,
+    reference: Option<String>,
+    status: String,
+    is_personal: bool,
+    transaction_id: Option<i64>,
+    transaction_date: Option<String>,
+    transaction_description: Option<String>,
+}
+
+const INDEX_HEADER: &str = "file,receipt_id,date,vendor,total,payment_method,reference,status,personal,transaction_id,transaction_date,transaction_description";
+
+/// Write the receipts dated `start_date` to `end_date` (inclusive,
+/// `YYYY-MM-DD`) to a zip at `output_path`, reading their files from the
+/// attachment store at `attachments_dir`. Receipts without a date of their
+/// own use the date of their transaction; rejected receipts and duplicates
+/// are left out.
+pub async fn export_receipts(
+    pool: &crate::db::DbPool,
+    attachments_dir: &Path,
+    start_date: &str,
+    end_date: &str,
+    output_path: &Path,
+) -> Result<ReceiptExportSummary, ExportError> {
+    let output_file = fs::File::create(output_path)
+        .map_err(|e| ExportError::Io(format!("Failed to create export file: {e}")))?;
+    let mut archive = zip::ZipWriter::new(output_file);
+    let summary = write_receipts(
+        pool,
+        attachments_dir,
+        start_date,
+        end_date,
+        &mut archive,
+        "",
+    )
+    .await?;
+    archive
+        .finish()
+        .map_err(|e| ExportError::Io(format!("Failed to finalize archive: {e}")))?;
+    Ok(summary)
+}
+
+/// Add the receipts and their index to an open archive, under `folder`
+/// (empty, or ending in `/`).
+pub(crate) async fn write_receipts<W: Write + Seek>(
+    pool: &crate::db::DbPool,
+    attachments_dir: &Path,
+    start_date: &str,
+    end_date: &str,
+    archive: &mut zip::ZipWriter<W>,
+    folder: &str,
+) -> Result<ReceiptExportSummary, ExportError> {
+    let rows = sqlx::query_as::<_, ExportRow>(
+        r#"SELECT r.id, r.file_ext, r.attachment_path, r.vendor,
+                  COALESCE(r.receipt_date, t.date) AS date,
+                  r.total_cents, r.payment_method, r.reference, r.status, r.is_personal,
+                  r.transaction_id, t.date AS transaction_date,
+                  t.description AS transaction_description
+           FROM receipts r
+           LEFT JOIN transactions t ON t.id = r.transaction_id
+           WHERE r.status NOT IN ('rejected', 'duplicate')
+             AND COALESCE(r.receipt_date, t.date) >= ?
+             AND COALESCE(r.receipt_date, t.date) <= ?
+           ORDER BY date, r.id"#,
+    )
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| ExportError::Database(e.to_string()))?;
+
+    // Photos and PDFs are already compressed.
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+
+    let mut index = String::from(INDEX_HEADER);
+    index.push('\n');
+    let mut names = HashSet::new();
+    let mut missing_files = Vec::new();
+    for row in &rows {
+        let path = crate::attachment_store::resolve_path(attachments_dir, &row.attachment_path);
+        let file = match fs::read(path) {
+            Ok(data) => {
+                let mut name = export_file_name(row);
+                if !names.insert(name.clone()) {
+                    name = with_receipt_id(&name, row.id);
+                    names.insert(name.clone());
+                }
+                archive
+                    .start_file(format!("{folder}{name}"), stored)
+                    .and_then(|()| archive.write_all(&data).map_err(Into::into))
+                    .map_err(|e| ExportError::Io(format!("Failed to add {name}: {e}")))?;
+                name
+            }
+            Err(_) => {
+                missing_files.push(row.id);
+                String::new()
+            }
+        };
+
+        let fields = [
+            file,
+            row.id.to_string(),
+            row.date.clone().unwrap_or_default(),
+            row.vendor.clone().unwrap_or_default(),
+            row.total_cents.map(format_cents).unwrap_or_default(),
+            row.payment_method.clone().unwrap_or_default(),
+            row.reference.clone().unwrap_or_default(),
+            row.status.clone(),
+            if row.is_personal { "yes" } else { "no" }.to_string(),
+            row.transaction_id
+                .map(|id| id.to_string())
+                .unwrap_or_default(),
+            row.transaction_date.clone().unwrap_or_default(),
+            row.transaction_description.clone().unwrap_or_default(),
+        ];
+        let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        index.push_str(&line.join(","));
+        index.push('\n');
+    }
+
+    archive
+        .start_file(format!("{folder}index.csv"), SimpleFileOptions::default())
+        .and_then(|()| archive.write_all(index.as_bytes()).map_err(Into::into))
+        .map_err(|e| ExportError::Io(format!("Failed to add index: {e}")))?;
+
+    Ok(ReceiptExportSummary {
+        receipt_count: rows.len() as u64,
+        missing_files,
+    })
+}
+
+/// `2026-03-05_Home-Depot_89.99.jpg`
+fn export_file_name(row: &ExportRow) -> String {
+    let date = row.date.as_deref().unwrap_or("undated");
+    let vendor = row
+        .vendor
+        .as_deref()
+        .map(slug)
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| "unknown".to_string());
+    let total = row
+        .total_cents
+        .map(format_cents)
+        .unwrap_or_else(|| "no-total".to_string());
+    let ext = row.file_ext.to_ascii_lowercase();
+    format!("{date}_{vendor}_{total}.{ext}")
+}
+
+/// Second receipt with the same name: tell them apart by id.
+fn with_receipt_id(name: &str, id: i64) -> String {
+    match name.rsplit_once('.') {
+        Some((stem, ext)) => format!("{stem}_{id}.{ext}"),
+        None => format!("{name}_{id}"),
+    }
+}
+
+/// Vendor name reduced to letters, digits and single dashes, so it is
+/// safe in a file name on any system.
+pub(crate) fn slug(vendor: &str) -> String {
+    let mut out = String::new();
+    for c in vendor.chars() {
+        if c.is_alphanumeric() {
+            out.push(c);
+        } else if !out.is_empty() && !out.ends_with('-') {
+            out.push('-');
+        }
+        if out.chars().count() >= 40 {
+            break;
+        }
+    }
+    out.trim_end_matches('-').to_string()
+}
+
+pub(crate) fn format_cents(cents: i64) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    format!("{sign}{}.{:02}", cents.abs() / 100, cents.abs() % 100)
+}
+
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    #[error("Database error: {0}")]
+    Database(String),
+    #[error("IO error: {0}")]
+    Io(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn file_names() {
+        assert_eq!(slug("Home Depot #4021"), "Home-Depot-4021");
+        assert_eq!(slug("  Café & Co. "), "Café-Co");
+        assert_eq!(format_cents(8999), "89.99");
+        assert_eq!(format_cents(-1250), "-12.50");
+        assert_eq!(with_receipt_id("a_b_1.00.jpg", 7), "a_b_1.00_7.jpg");
+        assert_eq!(csv_field("Lunch, client"), "\"Lunch, client\"");
+        assert_eq!(csv_field("6\" pipe"), "\"6\"\" pipe\"");
+    }
+
+    #[tokio::test]
+    async fn exports_receipts_in_range_with_index() {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        crate::migrate::run_migrations(&pool).await.unwrap();
+        let tmp = tempfile::tempdir().unwrap();
+
+        let mut ids = Vec::new();
+        for (hash, vendor, date, total) in [
+            ("ex_a", "Home Depot", "2026-03-05", 8999),
+            ("ex_b", "Home Depot", "2026-03-05", 4250),
+            ("ex_c", "Blue Bottle", "2025-12-30", 525),
+        ] {
+            let path = format!("{hash}.jpg");
+            fs::write(tmp.path().join(&path), hash.as_bytes()).unwrap();
+            let id = crate::db::insert_receipt(
+                &pool,
+                hash,
+                "jpg",
+                &path,
+                None,
+                Some(vendor),
+                Some(date),
+                Some(total),
+                None,
+                None,
+                None,
+                None,
+                0.9,
+            )
+            .await
+            .unwrap()
+            .id();
+            ids.push(id);
+        }
+        let tx_id = sqlx::query("INSERT INTO transactions (date, description, balanced_total_cents) VALUES ('2026-03-06', 'Lumber, screws', 8999)")
+            .execute(&pool)
+            .await
+            .unwrap()
+            .last_insert_rowid();
+        crate::db::link_receipt_to_transaction(&pool, ids[0], tx_id)
+            .await
+            .unwrap();
+        fs::remove_file(tmp.path().join("ex_b.jpg")).unwrap();
+
+        let output = tmp.path().join("receipts.zip");
+        let summary = export_receipts(&pool, tmp.path(), "2026-01-01", "2026-12-31", &output)
+            .await
+            .unwrap();
+        assert_eq!(summary.receipt_count, 2);
+        assert_eq!(summary.missing_files, [ids[1]]);
+
+        let mut zip = zip::ZipArchive::new(fs::File::open(&output).unwrap()).unwrap();
+        let mut photo = String::new();
+        zip.by_name("2026-03-05_Home-Depot_89.99.jpg")
+            .unwrap()
+            .read_to_string(&mut photo)
+            .unwrap();
+        assert_eq!(photo, "ex_a");
+
+        let mut index = String::new();
+        zip.by_name("index.csv")
+            .unwrap()
+            .read_to_string(&mut index)
+            .unwrap();
+        let lines: Vec<&str> = index.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], INDEX_HEADER);
+        assert!(lines[1].starts_with("2026-03-05_Home-Depot_89.99.jpg,"));
+        assert!(lines[1].ends_with(&format!(",{tx_id},2026-03-06,\"Lumber, screws\"")));
+        assert!(lines[2].starts_with(&format!(",{},", ids[1])));
+    }
+}
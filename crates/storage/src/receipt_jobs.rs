@@ -0,0 +1,337 @@
+//! The receipt intake queue.
+//!
+//! Files found by the intake folder watcher, or shared to the app, are
+//! queued here with [`enqueue_receipt_job`] before anything is done with
+//! them. Workers take them in order with [`claim_next_receipt_job`] and
+//! report back with [`finish_receipt_job`]: a failure is tried again after
+//! a pause, up to [`MAX_ATTEMPTS`] in all, then the job is left `dead` until
+//! the user retries it. A file that can't be handled at all is marked dead
+//! at once with [`fail_receipt_job`]. Jobs still `processing` when the app
+//! stopped are put back by [`requeue_interrupted_receipt_jobs`] at the next
+//! launch.
+//!
+//! A file is in the queue at most once at a time. If the app stops after a
+//! receipt is stored but before its job is finished, the next run finds the
+//! receipt by its hash and stores nothing new.
+
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::db::DbPool;
+
+/// Attempts at a job, counting one cut short by the app stopping, before it
+/// is given up on.
+pub const MAX_ATTEMPTS: i64 = 3;
+
+/// Wait before the first retry; it doubles with each one after.
+const RETRY_DELAY_SECS: i64 = 60;
+
+/// Where a queued file came from.
+pub const SOURCE_WATCHER: &str = "watcher";
+/// Opened with the app or dropped on its window.
+pub const SOURCE_SHARED: &str = "shared";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiptJobStatus {
+    Queued,
+    Processing,
+    Done,
+    /// Failed [`MAX_ATTEMPTS`] times; waits for the user.
+    Dead,
+}
+
+impl ReceiptJobStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReceiptJobStatus::Queued => "queued",
+            ReceiptJobStatus::Processing => "processing",
+            ReceiptJobStatus::Done => "done",
+            ReceiptJobStatus::Dead => "dead",
+        }
+    }
+}
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct ReceiptJob {
+    pub id: i64,
+    pub path: String,
+    pub source: String,
+    pub status: String,
+    pub attempts: i64,
+    pub last_error: Option<String>,
+    pub available_at: String,
+    pub enqueued_at: String,
+    pub updated_at: String,
+}
+
+const RECEIPT_JOB_COLUMNS: &str =
+    "id, path, source, status, attempts, last_error, available_at, enqueued_at, updated_at";
+
+/// Queue `path`. Returns the new job's id, or `None` if the file is already
+/// queued or being processed.
+pub async fn enqueue_receipt_job(
+    pool: &DbPool,
+    path: &str,
+    source: &str,
+) -> Result<Option<i64>, sqlx::Error> {
+    sqlx::query_scalar(
+        "INSERT INTO receipt_jobs (path, source) VALUES (?, ?)
+         ON CONFLICT (path) WHERE status IN ('queued', 'processing') DO NOTHING
+         RETURNING id",
+    )
+    .bind(path)
+    .bind(source)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Mark the oldest job that is due as `processing` and return it.
+pub async fn claim_next_receipt_job(pool: &DbPool) -> Result<Option<ReceiptJob>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "UPDATE receipt_jobs
+         SET status = 'processing', attempts = attempts + 1, updated_at = datetime('now')
+         WHERE id = (
+             SELECT id FROM receipt_jobs
+             WHERE status = 'queued' AND available_at <= datetime('now')
+             ORDER BY id LIMIT 1
+         )
+         RETURNING {RECEIPT_JOB_COLUMNS}"
+    ))
+    .fetch_optional(pool)
+    .await
+}
+
+/// Record how processing `path` went. A failure is queued again unless the
+/// job has had all its attempts. Returns the job's new status, or `None` if
+/// no job for `path` was being processed.
+pub async fn finish_receipt_job(
+    pool: &DbPool,
+    path: &str,
+    outcome: Result<(), &str>,
+) -> Result<Option<ReceiptJobStatus>, sqlx::Error> {
+    let Some((id, attempts)): Option<(i64, i64)> = sqlx::query_as(
+        "SELECT id, attempts FROM receipt_jobs WHERE path = ? AND status = 'processing'",
+    )
+    .bind(path)
+    .fetch_optional(pool)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let error = outcome.err();
+    let status = match error {
+        None => ReceiptJobStatus::Done,
+        Some(_) if attempts >= MAX_ATTEMPTS => ReceiptJobStatus::Dead,
+        Some(_) => ReceiptJobStatus::Queued,
+    };
+    let available_at = Utc::now() + retry_delay(attempts);
+    sqlx::query(
+        "UPDATE receipt_jobs
+         SET status = ?, last_error = ?, available_at = ?, updated_at = datetime('now')
+         WHERE id = ?",
+    )
+    .bind(status.as_str())
+    .bind(error)
+    .bind(available_at.format("%Y-%m-%d %H:%M:%S").to_string())
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(Some(status))
+}
+
+/// Give up on the job for `path` straight away, for a file no retry would
+/// help with, such as one of a type intake doesn't handle. Returns whether a
+/// job was being processed.
+pub async fn fail_receipt_job(pool: &DbPool, path: &str, error: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE receipt_jobs
+         SET status = 'dead', last_error = ?, updated_at = datetime('now')
+         WHERE path = ? AND status = 'processing'",
+    )
+    .bind(error)
+    .bind(path)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+fn retry_delay(attempts: i64) -> Duration {
+    Duration::seconds(RETRY_DELAY_SECS << (attempts - 1).clamp(0, 10))
+}
+
+/// Put jobs left `processing` by an app that stopped back in the queue, or
+/// mark them dead if that was their last attempt. Call before any worker
+/// starts. Returns how many were requeued.
+pub async fn requeue_interrupted_receipt_jobs(pool: &DbPool) -> Result<u64, sqlx::Error> {
+    // Counting the interrupted attempt keeps a file that takes the app down
+    // with it from doing so on every launch.
+    let result = sqlx::query(
+        "UPDATE receipt_jobs
+         SET status = CASE WHEN attempts >= ? THEN 'dead' ELSE 'queued' END,
+             last_error = COALESCE(last_error, 'Interrupted when the app stopped'),
+             updated_at = datetime('now')
+         WHERE status = 'processing'",
+    )
+    .bind(MAX_ATTEMPTS)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Jobs with `status`, or all that aren't done, oldest first.
+pub async fn get_receipt_jobs(
+    pool: &DbPool,
+    status: Option<ReceiptJobStatus>,
+) -> Result<Vec<ReceiptJob>, sqlx::Error> {
+    let sql = match status {
+        Some(_) => {
+            format!("SELECT {RECEIPT_JOB_COLUMNS} FROM receipt_jobs WHERE status = ? ORDER BY id")
+        }
+        None => format!(
+            "SELECT {RECEIPT_JOB_COLUMNS} FROM receipt_jobs WHERE status != 'done' ORDER BY id"
+        ),
+    };
+    sqlx::query_as(&sql)
+        .bind(status.map(ReceiptJobStatus::as_str))
+        .fetch_all(pool)
+        .await
+}
+
+/// Give a dead job a fresh set of attempts, starting now. Returns whether it
+/// was requeued; it isn't if it wasn't dead or the file has been queued
+/// again since.
+pub async fn retry_receipt_job(pool: &DbPool, id: i64) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE OR IGNORE receipt_jobs
+         SET status = 'queued', attempts = 0, last_error = NULL,
+             available_at = datetime('now'), updated_at = datetime('now')
+         WHERE id = ? AND status = 'dead'",
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    #[tokio::test]
+    async fn test_receipt_job_lifecycle() {
+        let pool = test_pool().await;
+        let first = enqueue_receipt_job(&pool, "/intake/a.jpg", SOURCE_WATCHER)
+            .await
+            .unwrap();
+        assert!(first.is_some());
+        // The watcher reporting the same file again doesn't queue it twice.
+        assert_eq!(
+            enqueue_receipt_job(&pool, "/intake/a.jpg", SOURCE_WATCHER)
+                .await
+                .unwrap(),
+            None
+        );
+        enqueue_receipt_job(&pool, "/intake/b.jpg", SOURCE_WATCHER)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let job = claim_next_receipt_job(&pool).await.unwrap().unwrap();
+        assert_eq!((job.id, job.attempts), (first.unwrap(), 1));
+        assert_eq!(job.status, "processing");
+        assert_eq!(
+            enqueue_receipt_job(&pool, "/intake/a.jpg", SOURCE_WATCHER)
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            finish_receipt_job(&pool, "/intake/a.jpg", Ok(()))
+                .await
+                .unwrap(),
+            Some(ReceiptJobStatus::Done)
+        );
+        // Once done, the same path may be queued again.
+        assert!(enqueue_receipt_job(&pool, "/intake/a.jpg", SOURCE_WATCHER)
+            .await
+            .unwrap()
+            .is_some());
+
+        // A failure waits before it is tried again.
+        let job = claim_next_receipt_job(&pool).await.unwrap().unwrap();
+        assert_eq!(job.path, "/intake/b.jpg");
+        assert_eq!(
+            finish_receipt_job(&pool, "/intake/b.jpg", Err("unreadable"))
+                .await
+                .unwrap(),
+            Some(ReceiptJobStatus::Queued)
+        );
+        let job = claim_next_receipt_job(&pool).await.unwrap().unwrap();
+        assert_eq!(job.path, "/intake/a.jpg");
+        assert!(claim_next_receipt_job(&pool).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_receipt_jobs_survive_restart_and_die() {
+        let pool = test_pool().await;
+        let id = enqueue_receipt_job(&pool, "/intake/c.pdf", SOURCE_WATCHER)
+            .await
+            .unwrap()
+            .unwrap();
+
+        // Each launch that stops mid-processing uses up an attempt.
+        for attempt in 1..=MAX_ATTEMPTS {
+            let job = claim_next_receipt_job(&pool).await.unwrap().unwrap();
+            assert_eq!((job.id, job.attempts), (id, attempt));
+            assert_eq!(requeue_interrupted_receipt_jobs(&pool).await.unwrap(), 1);
+        }
+        let dead = get_receipt_jobs(&pool, Some(ReceiptJobStatus::Dead))
+            .await
+            .unwrap();
+        assert_eq!(dead.len(), 1);
+        assert_eq!(
+            dead[0].last_error.as_deref(),
+            Some("Interrupted when the app stopped")
+        );
+        assert!(claim_next_receipt_job(&pool).await.unwrap().is_none());
+
+        assert!(retry_receipt_job(&pool, id).await.unwrap());
+        let job = claim_next_receipt_job(&pool).await.unwrap().unwrap();
+        assert_eq!((job.id, job.attempts), (id, 1));
+        assert_eq!(
+            finish_receipt_job(&pool, "/intake/c.pdf", Err("still unreadable"))
+                .await
+                .unwrap(),
+            Some(ReceiptJobStatus::Queued)
+        );
+        assert_eq!(get_receipt_jobs(&pool, None).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_file_fails_without_retry() {
+        let pool = test_pool().await;
+        let id = enqueue_receipt_job(&pool, "/intake/notes.txt", SOURCE_WATCHER)
+            .await
+            .unwrap()
+            .unwrap();
+        // Not processing yet.
+        assert!(!fail_receipt_job(&pool, "/intake/notes.txt", "unsupported")
+            .await
+            .unwrap());
+
+        claim_next_receipt_job(&pool).await.unwrap().unwrap();
+        assert!(fail_receipt_job(&pool, "/intake/notes.txt", "unsupported")
+            .await
+            .unwrap());
+        let dead = get_receipt_jobs(&pool, Some(ReceiptJobStatus::Dead))
+            .await
+            .unwrap();
+        assert_eq!((dead[0].id, dead[0].attempts), (id, 1));
+        assert_eq!(dead[0].last_error.as_deref(), Some("unsupported"));
+        assert!(claim_next_receipt_job(&pool).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,264 @@
+//! The review states a receipt moves through, and which moves are allowed.
+//!
+//! ```text
+//! pending_review ──► approved ──► rejected (once unlinked)
+//!    │    ▲
+//!    │    └─────── duplicate ──► rejected
+//!    ├──► duplicate
+//!    └──► rejected
+//! ```
+//!
+//! Rejected is final. An approved receipt never goes back to review; the
+//! one exception is undoing an auto-approval, which unlinks it in the same
+//! step. A receipt linked to a transaction or bank row has to be unlinked
+//! with [`unlink_receipt`] before it can be rejected, so no rejected
+//! receipt is left backing a ledger entry. Only receipts that are pending
+//! review or approved can be linked.
+
+use serde::{Deserialize, Serialize};
+
+use crate::db::DbPool;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiptStatus {
+    PendingReview,
+    Approved,
+    Rejected,
+    /// Looks like a second copy of a receipt already on file.
+    Duplicate,
+}
+
+impl ReceiptStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReceiptStatus::PendingReview => "pending_review",
+            ReceiptStatus::Approved => "approved",
+            ReceiptStatus::Rejected => "rejected",
+            ReceiptStatus::Duplicate => "duplicate",
+        }
+    }
+
+    /// Whether a receipt in this state may move to `to`, leaving links
+    /// aside.
+    pub fn can_become(self, to: ReceiptStatus) -> bool {
+        use ReceiptStatus::*;
+        matches!(
+            (self, to),
+            (PendingReview, Approved | Rejected | Duplicate)
+                | (Duplicate, PendingReview | Rejected)
+                | (Approved, Rejected)
+        )
+    }
+
+    /// Whether a receipt in this state may be linked to a transaction,
+    /// which approves it.
+    pub fn can_link(self) -> bool {
+        matches!(self, ReceiptStatus::PendingReview | ReceiptStatus::Approved)
+    }
+}
+
+impl std::fmt::Display for ReceiptStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for ReceiptStatus {
+    type Err = ReceiptStatusError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending_review" => Ok(ReceiptStatus::PendingReview),
+            "approved" => Ok(ReceiptStatus::Approved),
+            "rejected" => Ok(ReceiptStatus::Rejected),
+            "duplicate" => Ok(ReceiptStatus::Duplicate),
+            other => Err(ReceiptStatusError::Unknown(other.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReceiptStatusError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Receipt {0} not found")]
+    NotFound(i64),
+    #[error("Unknown receipt status: '{0}'")]
+    Unknown(String),
+    #[error("Receipt is {from} and can't be made {to}")]
+    Invalid {
+        from: ReceiptStatus,
+        to: ReceiptStatus,
+    },
+    #[error("Receipt is linked to a transaction; unlink it before rejecting it")]
+    Linked,
+}
+
+/// A receipt's status and whether it is linked to anything.
+pub(crate) struct ReceiptState {
+    pub status: ReceiptStatus,
+    pub linked: bool,
+}
+
+impl ReceiptState {
+    /// Check a move to `to`, refusing to reject a linked receipt.
+    pub fn check(&self, to: ReceiptStatus) -> Result<(), ReceiptStatusError> {
+        if !self.status.can_become(to) {
+            return Err(ReceiptStatusError::Invalid {
+                from: self.status,
+                to,
+            });
+        }
+        if to == ReceiptStatus::Rejected && self.linked {
+            return Err(ReceiptStatusError::Linked);
+        }
+        Ok(())
+    }
+
+    /// Check that the receipt may be linked.
+    pub fn check_link(&self) -> Result<(), ReceiptStatusError> {
+        if self.status.can_link() {
+            Ok(())
+        } else {
+            Err(ReceiptStatusError::Invalid {
+                from: self.status,
+                to: ReceiptStatus::Approved,
+            })
+        }
+    }
+}
+
+pub(crate) async fn receipt_state<'e, E>(
+    executor: E,
+    id: i64,
+) -> Result<ReceiptState, ReceiptStatusError>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    let (status, linked): (String, bool) = sqlx::query_as(
+        "SELECT status, transaction_id IS NOT NULL OR imported_transaction_id IS NOT NULL
+         FROM receipts WHERE id = ?",
+    )
+    .bind(id)
+    .fetch_optional(executor)
+    .await?
+    .ok_or(ReceiptStatusError::NotFound(id))?;
+    Ok(ReceiptState {
+        status: status.parse()?,
+        linked,
+    })
+}
+
+/// Take the links off an approved receipt, leaving the transaction and bank
+/// row themselves as they are. The receipt stays approved.
+pub async fn unlink_receipt(pool: &DbPool, id: i64) -> Result<(), ReceiptStatusError> {
+    let state = receipt_state(pool, id).await?;
+    if state.status != ReceiptStatus::Approved {
+        return Err(ReceiptStatusError::Invalid {
+            from: state.status,
+            to: ReceiptStatus::Approved,
+        });
+    }
+    sqlx::query(
+        "UPDATE receipts SET transaction_id = NULL, imported_transaction_id = NULL WHERE id = ?",
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ReceiptStatus::*;
+
+    #[test]
+    fn test_transitions() {
+        let all = [PendingReview, Approved, Rejected, Duplicate];
+        let allowed: Vec<(ReceiptStatus, ReceiptStatus)> = all
+            .iter()
+            .flat_map(|&from| all.iter().map(move |&to| (from, to)))
+            .filter(|&(from, to)| from.can_become(to))
+            .collect();
+        assert_eq!(
+            allowed,
+            [
+                (PendingReview, Approved),
+                (PendingReview, Rejected),
+                (PendingReview, Duplicate),
+                (Approved, Rejected),
+                (Duplicate, PendingReview),
+                (Duplicate, Rejected),
+            ]
+        );
+        for status in all {
+            assert_eq!(status.as_str().parse::<ReceiptStatus>().unwrap(), status);
+        }
+        assert!(matches!(
+            "posted".parse::<ReceiptStatus>(),
+            Err(ReceiptStatusError::Unknown(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_linked_receipt_unlinked_before_rejecting() {
+        use crate::db::{get_receipt_by_id, link_receipt_to_transaction, update_receipt_status};
+
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        crate::migrate::run_migrations(&pool).await.unwrap();
+        let id = crate::db::insert_receipt(
+            &pool, "sm_hash", "jpg", "sm.jpg", None, None, None, None, None, None, None, None, 0.9,
+        )
+        .await
+        .unwrap()
+        .id();
+        let tx_id = sqlx::query(
+            "INSERT INTO transactions (date, description, balanced_total_cents) VALUES ('2026-03-06', 'Lumber', 8999)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+
+        link_receipt_to_transaction(&pool, id, tx_id).await.unwrap();
+        assert!(matches!(
+            update_receipt_status(&pool, id, PendingReview).await,
+            Err(ReceiptStatusError::Invalid {
+                from: Approved,
+                to: PendingReview
+            })
+        ));
+        assert!(matches!(
+            update_receipt_status(&pool, id, Rejected).await,
+            Err(ReceiptStatusError::Linked)
+        ));
+
+        unlink_receipt(&pool, id).await.unwrap();
+        let receipt = get_receipt_by_id(&pool, id).await.unwrap().unwrap();
+        assert_eq!(
+            (receipt.status.as_str(), receipt.transaction_id),
+            ("approved", None)
+        );
+        update_receipt_status(&pool, id, Rejected).await.unwrap();
+
+        // Rejected is final.
+        assert!(matches!(
+            link_receipt_to_transaction(&pool, id, tx_id).await,
+            Err(ReceiptStatusError::Invalid { from: Rejected, .. })
+        ));
+        assert!(matches!(
+            unlink_receipt(&pool, id).await,
+            Err(ReceiptStatusError::Invalid { from: Rejected, .. })
+        ));
+        assert!(matches!(
+            update_receipt_status(&pool, 9999, Approved).await,
+            Err(ReceiptStatusError::NotFound(9999))
+        ));
+    }
+}
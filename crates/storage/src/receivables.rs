@@ -0,0 +1,758 @@
+//! Getting paid: payments against invoices and customer credit.
+//!
+//! Invoices are kept on a cash basis like bills: nothing is posted when an
+//! invoice is sent, and each payment posts into the deposit account as
+//! revenue, with the tax it carries going to taxes payable. A payment is
+//! applied to what the invoice still owes; anything beyond that is held in
+//! the customer credit liability account until [`apply_customer_credit`]
+//! spends it on another invoice, booking it as revenue then.
+
+use aequi_core::{
+    Account, AccountType, ContactId, Discount, Invoice, InvoiceId, InvoiceLine, InvoiceStatus,
+    Money, TaxLine,
+};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::db::{
+    get_account_by_code, DbPool, InvoiceLineRecord, InvoiceRecord, InvoiceTaxLineRecord,
+};
+use crate::error::StorageError;
+
+/// Accounts receivable, which payments can't be deposited to.
+pub const RECEIVABLE_ACCOUNT_CODE: &str = "1020";
+
+/// Where invoice income is booked.
+pub const REVENUE_ACCOUNT_CODE: &str = "4000";
+
+/// Where the tax collected on invoices is booked.
+pub const TAXES_PAYABLE_ACCOUNT_CODE: &str = "2010";
+
+/// The liability account overpayments are held in, created when first
+/// needed.
+pub const CUSTOMER_CREDIT_ACCOUNT_CODE: &str = "2020";
+const CUSTOMER_CREDIT_ACCOUNT_NAME: &str = "Customer Credits";
+
+/// `method` of payments made from customer credit.
+pub const CREDIT_METHOD: &str = "Customer credit";
+
+#[derive(Debug, thiserror::Error)]
+pub enum PaymentError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Invalid(String),
+}
+
+/// What an invoice comes to and what is still owed on it.
+#[derive(Debug, Clone, Serialize)]
+pub struct InvoiceBalance {
+    pub invoice_id: i64,
+    pub invoice_number: String,
+    pub contact_id: i64,
+    pub status_type: String,
+    pub total_cents: i64,
+    /// The tax included in the total.
+    pub tax_cents: i64,
+    pub paid_cents: i64,
+    pub due_cents: i64,
+}
+
+/// A payment received for an invoice.
+#[derive(Debug, Clone)]
+pub struct NewPayment<'a> {
+    pub invoice_id: i64,
+    pub amount_cents: i64,
+    pub date: NaiveDate,
+    /// The bank or cash account the money went into.
+    pub deposit_account_code: &'a str,
+    pub method: Option<&'a str>,
+}
+
+/// What recording a payment did.
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordedPayment {
+    pub payment_id: i64,
+    /// The entry booking the payment.
+    pub transaction_id: i64,
+    /// How much went towards the invoice.
+    pub applied_cents: i64,
+    /// How much was left over and held as customer credit.
+    pub credit_cents: i64,
+    pub balance: InvoiceBalance,
+}
+
+/// Reconstruct the domain invoice from its records. Fails if a date on it
+/// can't be read.
+pub fn records_to_invoice(
+    rec: &InvoiceRecord,
+    lines: &[InvoiceLineRecord],
+    tax_lines: &[InvoiceTaxLineRecord],
+) -> Result<Invoice, StorageError> {
+    let discount = match (rec.discount_type.as_deref(), rec.discount_value) {
+        (Some("Percentage"), Some(bps)) => Some(Discount::Percentage(Decimal::new(bps, 2))),
+        (Some("Flat"), Some(cents)) => Some(Discount::Flat(Money::from_cents(cents))),
+        _ => None,
+    };
+    let date = |field: &str, s: &str| {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|_| {
+            StorageError::InvalidData(format!("invoice {} has {field} {s:?}", rec.invoice_number))
+        })
+    };
+
+    Ok(Invoice {
+        id: Some(InvoiceId(rec.id)),
+        invoice_number: rec.invoice_number.clone(),
+        contact_id: ContactId(rec.contact_id),
+        status: InvoiceStatus::Draft,
+        issue_date: date("issue date", &rec.issue_date)?,
+        due_date: date("due date", &rec.due_date)?,
+        lines: lines
+            .iter()
+            .map(|l| InvoiceLine {
+                description: l.description.clone(),
+                quantity: Decimal::new(l.quantity_hundredths, 2),
+                unit_rate: Money::from_cents(l.unit_rate_cents),
+                taxable: l.taxable,
+            })
+            .collect(),
+        discount,
+        tax_lines: tax_lines
+            .iter()
+            .map(|t| TaxLine {
+                label: t.label.clone(),
+                rate: Decimal::new(t.rate_bps, 4),
+            })
+            .collect(),
+        notes: rec.notes.clone(),
+        terms: rec.terms.clone(),
+    })
+}
+
+pub async fn invoice_balance(
+    pool: &DbPool,
+    invoice_id: i64,
+) -> Result<Option<InvoiceBalance>, StorageError> {
+    let mut conn = pool.acquire().await?;
+    balance_on(&mut conn, invoice_id).await
+}
+
+async fn balance_on(
+    conn: &mut sqlx::SqliteConnection,
+    invoice_id: i64,
+) -> Result<Option<InvoiceBalance>, StorageError> {
+    let Some(rec) = sqlx::query_as::<_, InvoiceRecord>("SELECT * FROM invoices WHERE id = ?")
+        .bind(invoice_id)
+        .fetch_optional(&mut *conn)
+        .await?
+    else {
+        return Ok(None);
+    };
+    let lines = sqlx::query_as::<_, InvoiceLineRecord>(
+        "SELECT * FROM invoice_lines WHERE invoice_id = ? ORDER BY sort_order",
+    )
+    .bind(invoice_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    let tax_lines = sqlx::query_as::<_, InvoiceTaxLineRecord>(
+        "SELECT * FROM invoice_tax_lines WHERE invoice_id = ?",
+    )
+    .bind(invoice_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    let invoice = records_to_invoice(&rec, &lines, &tax_lines)?;
+    let total_cents = invoice.total().to_cents();
+    let paid_cents: i64 = sqlx::query_scalar(
+        "SELECT COALESCE(SUM(amount_cents), 0) FROM payments WHERE invoice_id = ?",
+    )
+    .bind(invoice_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    Ok(Some(InvoiceBalance {
+        invoice_id,
+        invoice_number: rec.invoice_number,
+        contact_id: rec.contact_id,
+        status_type: rec.status_type,
+        total_cents,
+        tax_cents: invoice.tax_amount().to_cents(),
+        paid_cents,
+        due_cents: (total_cents - paid_cents).max(0),
+    }))
+}
+
+/// Credit the customer has from overpayments and not yet spent.
+pub async fn customer_credit_cents<'e, E>(executor: E, contact_id: i64) -> Result<i64, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    sqlx::query_scalar(
+        r#"SELECT COALESCE(SUM(p.credit_cents), 0) FROM payments p
+           JOIN invoices i ON i.id = p.invoice_id
+           WHERE i.contact_id = ?"#,
+    )
+    .bind(contact_id)
+    .fetch_one(executor)
+    .await
+}
+
+/// The invoice's balance, if it can take a payment. Read inside the
+/// transaction that records the payment, so the check and the payment can't
+/// be split by another one.
+async fn payable_balance(
+    conn: &mut sqlx::SqliteConnection,
+    invoice_id: i64,
+) -> Result<InvoiceBalance, PaymentError> {
+    let balance = balance_on(conn, invoice_id)
+        .await?
+        .ok_or_else(|| PaymentError::NotFound(format!("Invoice {invoice_id} not found")))?;
+    match balance.status_type.as_str() {
+        "Draft" => Err(PaymentError::Invalid(format!(
+            "Invoice {} hasn't been sent yet",
+            balance.invoice_number
+        ))),
+        "Void" => Err(PaymentError::Invalid(format!(
+            "Invoice {} is void",
+            balance.invoice_number
+        ))),
+        "Paid" => Err(PaymentError::Invalid(format!(
+            "Invoice {} is already paid",
+            balance.invoice_number
+        ))),
+        _ if balance.due_cents == 0 => Err(PaymentError::Invalid(format!(
+            "Invoice {} has nothing owing",
+            balance.invoice_number
+        ))),
+        _ => Ok(balance),
+    }
+}
+
+/// The share of the invoice's tax in the first `paid_cents` paid on it.
+fn tax_share(balance: &InvoiceBalance, paid_cents: i64) -> i64 {
+    if balance.total_cents <= 0 {
+        return 0;
+    }
+    (i128::from(paid_cents.min(balance.total_cents)) * i128::from(balance.tax_cents)
+        / i128::from(balance.total_cents)) as i64
+}
+
+/// The accounts a payment's income goes to.
+struct IncomeAccounts {
+    revenue: i64,
+    taxes_payable: i64,
+}
+
+impl IncomeAccounts {
+    async fn load(pool: &DbPool) -> Result<Self, PaymentError> {
+        let account = |code: &'static str| async move {
+            get_account_by_code(pool, code)
+                .await?
+                .and_then(|a| a.id)
+                .map(|id| id.0)
+                .ok_or_else(|| PaymentError::NotFound(format!("Account {code} not found")))
+        };
+        Ok(IncomeAccounts {
+            revenue: account(REVENUE_ACCOUNT_CODE).await?,
+            taxes_payable: account(TAXES_PAYABLE_ACCOUNT_CODE).await?,
+        })
+    }
+
+    /// Revenue, and taxes payable for the tax the payment carries. Worked
+    /// out from the running total so partial payments add up to the
+    /// invoice's tax exactly.
+    fn lines(&self, balance: &InvoiceBalance, applied_cents: i64) -> [EntryLine; 2] {
+        let tax_cents = tax_share(balance, balance.paid_cents + applied_cents)
+            - tax_share(balance, balance.paid_cents);
+        [
+            EntryLine {
+                account_id: Some(self.revenue),
+                debit_cents: 0,
+                credit_cents: applied_cents - tax_cents,
+                memo: None,
+            },
+            EntryLine {
+                account_id: Some(self.taxes_payable),
+                debit_cents: 0,
+                credit_cents: tax_cents,
+                memo: None,
+            },
+        ]
+    }
+}
+
+/// The customer credit account's id, created if `existing` is `None`.
+async fn customer_credit_account(
+    conn: &mut sqlx::SqliteConnection,
+    existing: Option<Account>,
+) -> Result<i64, PaymentError> {
+    let Some(account) = existing else {
+        return Ok(sqlx::query_scalar(
+            r#"INSERT INTO accounts (code, name, account_type, is_archetype)
+               VALUES (?, ?, 'Liability', 0) RETURNING id"#,
+        )
+        .bind(CUSTOMER_CREDIT_ACCOUNT_CODE)
+        .bind(CUSTOMER_CREDIT_ACCOUNT_NAME)
+        .fetch_one(&mut *conn)
+        .await?);
+    };
+    match account.id {
+        Some(id) if account.account_type == AccountType::Liability && !account.is_archived => {
+            Ok(id.0)
+        }
+        _ => Err(PaymentError::Invalid(format!(
+            "Account {CUSTOMER_CREDIT_ACCOUNT_CODE} {} must be an active liability account",
+            account.name
+        ))),
+    }
+}
+
+struct EntryLine {
+    account_id: Option<i64>,
+    debit_cents: i64,
+    credit_cents: i64,
+    memo: Option<&'static str>,
+}
+
+/// Post a payment's entry, skipping empty lines.
+async fn post_entry(
+    conn: &mut sqlx::SqliteConnection,
+    date: &str,
+    description: &str,
+    total_cents: i64,
+    lines: &[EntryLine],
+) -> Result<i64, sqlx::Error> {
+    let transaction_id: i64 = sqlx::query_scalar(
+        r#"INSERT INTO transactions (date, description, balanced_total_cents)
+           VALUES (?, ?, ?) RETURNING id"#,
+    )
+    .bind(date)
+    .bind(description)
+    .bind(total_cents)
+    .fetch_one(&mut *conn)
+    .await?;
+    for line in lines {
+        if line.debit_cents == 0 && line.credit_cents == 0 {
+            continue;
+        }
+        sqlx::query(
+            r#"INSERT INTO transaction_lines (transaction_id, account_id, debit_cents,
+               credit_cents, memo) VALUES (?, ?, ?, ?, ?)"#,
+        )
+        .bind(transaction_id)
+        .bind(line.account_id)
+        .bind(line.debit_cents)
+        .bind(line.credit_cents)
+        .bind(line.memo)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(transaction_id)
+}
+
+/// Record a payment: post it into the deposit account as revenue, apply it
+/// to the invoice and mark the invoice partially or fully paid. Any amount
+/// beyond what was owed is held as customer credit.
+pub async fn record_invoice_payment(
+    pool: &DbPool,
+    payment: &NewPayment<'_>,
+) -> Result<RecordedPayment, PaymentError> {
+    if payment.amount_cents <= 0 {
+        return Err(PaymentError::Invalid(
+            "Payment amount must be positive".into(),
+        ));
+    }
+    let deposit = get_account_by_code(pool, payment.deposit_account_code)
+        .await?
+        .ok_or_else(|| {
+            PaymentError::NotFound(format!(
+                "Account {} not found",
+                payment.deposit_account_code
+            ))
+        })?;
+    if deposit.account_type != AccountType::Asset
+        || deposit.is_archived
+        || deposit.code == RECEIVABLE_ACCOUNT_CODE
+    {
+        return Err(PaymentError::Invalid(format!(
+            "Payments must be deposited to an active asset account, not {} {}",
+            deposit.code, deposit.name
+        )));
+    }
+    let income = IncomeAccounts::load(pool).await?;
+    let credit_account = get_account_by_code(pool, CUSTOMER_CREDIT_ACCOUNT_CODE).await?;
+    let date = payment.date.to_string();
+
+    // Taking the write lock up front keeps a second payment from passing the
+    // balance check before this one is in.
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
+    let mut balance = payable_balance(&mut tx, payment.invoice_id).await?;
+    let applied_cents = payment.amount_cents.min(balance.due_cents);
+    let credit_cents = payment.amount_cents - applied_cents;
+    let credit_account_id = if credit_cents > 0 {
+        Some(customer_credit_account(&mut tx, credit_account).await?)
+    } else {
+        None
+    };
+    let [revenue, tax] = income.lines(&balance, applied_cents);
+    let lines = [
+        EntryLine {
+            account_id: deposit.id.map(|id| id.0),
+            debit_cents: payment.amount_cents,
+            credit_cents: 0,
+            memo: None,
+        },
+        revenue,
+        tax,
+        EntryLine {
+            account_id: credit_account_id,
+            debit_cents: 0,
+            credit_cents,
+            memo: Some("Overpayment held as customer credit"),
+        },
+    ];
+    let transaction_id = post_entry(
+        &mut tx,
+        &date,
+        &format!("Payment for invoice {}", balance.invoice_number),
+        payment.amount_cents,
+        &lines,
+    )
+    .await?;
+    let payment_id = insert_applied_payment(
+        &mut tx,
+        &mut balance,
+        applied_cents,
+        credit_cents,
+        &date,
+        payment.method,
+        transaction_id,
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(RecordedPayment {
+        payment_id,
+        transaction_id,
+        applied_cents,
+        credit_cents,
+        balance,
+    })
+}
+
+/// Pay as much of the invoice as the customer's credit covers, moving it
+/// out of customer credit into revenue.
+pub async fn apply_customer_credit(
+    pool: &DbPool,
+    invoice_id: i64,
+    date: NaiveDate,
+) -> Result<RecordedPayment, PaymentError> {
+    let income = IncomeAccounts::load(pool).await?;
+    let credit_account = get_account_by_code(pool, CUSTOMER_CREDIT_ACCOUNT_CODE).await?;
+    let date = date.to_string();
+
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
+    let mut balance = payable_balance(&mut tx, invoice_id).await?;
+    let available = customer_credit_cents(&mut *tx, balance.contact_id).await?;
+    if available <= 0 {
+        return Err(PaymentError::Invalid(
+            "The customer has no credit to apply".into(),
+        ));
+    }
+    let credit_account_id = customer_credit_account(&mut tx, credit_account).await?;
+    let applied_cents = available.min(balance.due_cents);
+    let [revenue, tax] = income.lines(&balance, applied_cents);
+    let lines = [
+        EntryLine {
+            account_id: Some(credit_account_id),
+            debit_cents: applied_cents,
+            credit_cents: 0,
+            memo: Some("Customer credit applied"),
+        },
+        revenue,
+        tax,
+    ];
+    let transaction_id = post_entry(
+        &mut tx,
+        &date,
+        &format!("Credit applied to invoice {}", balance.invoice_number),
+        applied_cents,
+        &lines,
+    )
+    .await?;
+    let payment_id = insert_applied_payment(
+        &mut tx,
+        &mut balance,
+        applied_cents,
+        -applied_cents,
+        &date,
+        Some(CREDIT_METHOD),
+        transaction_id,
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(RecordedPayment {
+        payment_id,
+        transaction_id,
+        applied_cents,
+        credit_cents: -applied_cents,
+        balance,
+    })
+}
+
+/// Insert the payment and bring the invoice's status and `balance` up to
+/// date with it.
+async fn insert_applied_payment(
+    conn: &mut sqlx::SqliteConnection,
+    balance: &mut InvoiceBalance,
+    applied_cents: i64,
+    credit_cents: i64,
+    date: &str,
+    method: Option<&str>,
+    transaction_id: i64,
+) -> Result<i64, sqlx::Error> {
+    let payment_id: i64 = sqlx::query_scalar(
+        r#"INSERT INTO payments (invoice_id, amount_cents, date, method, transaction_id,
+           credit_cents) VALUES (?, ?, ?, ?, ?, ?) RETURNING id"#,
+    )
+    .bind(balance.invoice_id)
+    .bind(applied_cents)
+    .bind(date)
+    .bind(method)
+    .bind(transaction_id)
+    .bind(credit_cents)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    balance.paid_cents += applied_cents;
+    balance.due_cents = (balance.total_cents - balance.paid_cents).max(0);
+    let (status_type, status_data) = if balance.due_cents == 0 {
+        ("Paid", serde_json::json!({ "paid_at": date }))
+    } else {
+        (
+            "PartiallyPaid",
+            serde_json::json!({
+                "paid_amount_cents": balance.paid_cents,
+                "last_payment_at": date,
+            }),
+        )
+    };
+    sqlx::query(
+        "UPDATE invoices SET status_type = ?, status_data = ?, updated_at = datetime('now') WHERE id = ?",
+    )
+    .bind(status_type)
+    .bind(status_data.to_string())
+    .bind(balance.invoice_id)
+    .execute(&mut *conn)
+    .await?;
+    balance.status_type = status_type.to_string();
+    Ok(payment_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{get_invoice_by_id, test_pool};
+
+    /// A sent invoice for `cents`, to the first contact.
+    async fn sent_invoice(pool: &DbPool, number: &str, cents: i64) -> i64 {
+        sqlx::query("INSERT OR IGNORE INTO contacts (id, name) VALUES (1, 'Acme')")
+            .execute(pool)
+            .await
+            .unwrap();
+        let id: i64 = sqlx::query_scalar(
+            r#"INSERT INTO invoices (invoice_number, contact_id, status_type, issue_date, due_date)
+               VALUES (?, 1, 'Sent', '2026-03-01', '2026-03-31') RETURNING id"#,
+        )
+        .bind(number)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            r#"INSERT INTO invoice_lines (invoice_id, description, quantity_hundredths, unit_rate_cents)
+               VALUES (?, 'Consulting', 100, ?)"#,
+        )
+        .bind(id)
+        .bind(cents)
+        .execute(pool)
+        .await
+        .unwrap();
+        id
+    }
+
+    fn payment(invoice_id: i64, amount_cents: i64, account: &str) -> NewPayment<'_> {
+        NewPayment {
+            invoice_id,
+            amount_cents,
+            date: NaiveDate::from_ymd_opt(2026, 3, 20).unwrap(),
+            deposit_account_code: account,
+            method: Some("ACH"),
+        }
+    }
+
+    async fn net_credits(pool: &DbPool, code: &str) -> i64 {
+        sqlx::query_scalar(
+            r#"SELECT COALESCE(SUM(l.credit_cents - l.debit_cents), 0) FROM transaction_lines l
+               JOIN accounts a ON a.id = l.account_id WHERE a.code = ?"#,
+        )
+        .bind(code)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn partial_payment_then_overpayment_becomes_credit() {
+        let pool = test_pool().await;
+        let id = sent_invoice(&pool, "INV-1", 100_000).await;
+
+        let first = record_invoice_payment(&pool, &payment(id, 40_000, "1000"))
+            .await
+            .unwrap();
+        assert_eq!(first.applied_cents, 40_000);
+        assert_eq!(first.credit_cents, 0);
+        assert_eq!(first.balance.status_type, "PartiallyPaid");
+        assert_eq!(first.balance.due_cents, 60_000);
+
+        let second = record_invoice_payment(&pool, &payment(id, 70_000, "1000"))
+            .await
+            .unwrap();
+        assert_eq!(second.applied_cents, 60_000);
+        assert_eq!(second.credit_cents, 10_000);
+        assert_eq!(second.balance.status_type, "Paid");
+        assert_eq!(net_credits(&pool, REVENUE_ACCOUNT_CODE).await, 100_000);
+        assert_eq!(
+            net_credits(&pool, CUSTOMER_CREDIT_ACCOUNT_CODE).await,
+            10_000
+        );
+        assert_eq!(net_credits(&pool, RECEIVABLE_ACCOUNT_CODE).await, 0);
+        assert_eq!(customer_credit_cents(&pool, 1).await.unwrap(), 10_000);
+
+        let inv = get_invoice_by_id(&pool, id).await.unwrap().unwrap();
+        assert_eq!(inv.status_type, "Paid");
+        assert!(matches!(
+            record_invoice_payment(&pool, &payment(id, 100, "1000")).await,
+            Err(PaymentError::Invalid(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn credit_pays_a_later_invoice() {
+        let pool = test_pool().await;
+        let first = sent_invoice(&pool, "INV-1", 5_000).await;
+        record_invoice_payment(&pool, &payment(first, 20_000, "1000"))
+            .await
+            .unwrap();
+
+        let second = sent_invoice(&pool, "INV-2", 8_000).await;
+        let date = NaiveDate::from_ymd_opt(2026, 4, 1).unwrap();
+        let applied = apply_customer_credit(&pool, second, date).await.unwrap();
+        assert_eq!(applied.applied_cents, 8_000);
+        assert_eq!(applied.balance.status_type, "Paid");
+        assert_eq!(customer_credit_cents(&pool, 1).await.unwrap(), 7_000);
+
+        // Spending credit moves it out of the liability into revenue.
+        assert_eq!(
+            net_credits(&pool, CUSTOMER_CREDIT_ACCOUNT_CODE).await,
+            7_000
+        );
+        assert_eq!(net_credits(&pool, REVENUE_ACCOUNT_CODE).await, 13_000);
+    }
+
+    #[tokio::test]
+    async fn tax_goes_to_taxes_payable_across_partial_payments() {
+        let pool = test_pool().await;
+        let id = sent_invoice(&pool, "INV-1", 10_000).await;
+        sqlx::query("UPDATE invoice_lines SET taxable = 1 WHERE invoice_id = ?")
+            .bind(id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO invoice_tax_lines (invoice_id, label, rate_bps) VALUES (?, 'Sales tax', 800)",
+        )
+        .bind(id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        for cents in [3_333, 3_333, 4_134] {
+            record_invoice_payment(&pool, &payment(id, cents, "1000"))
+                .await
+                .unwrap();
+        }
+        assert_eq!(net_credits(&pool, TAXES_PAYABLE_ACCOUNT_CODE).await, 800);
+        assert_eq!(net_credits(&pool, REVENUE_ACCOUNT_CODE).await, 10_000);
+    }
+
+    #[tokio::test]
+    async fn rejects_draft_invoice() {
+        let pool = test_pool().await;
+        let id = sent_invoice(&pool, "INV-1", 5_000).await;
+        sqlx::query("UPDATE invoices SET status_type = 'Draft' WHERE id = ?")
+            .bind(id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert!(matches!(
+            record_invoice_payment(&pool, &payment(id, 5_000, "1000")).await,
+            Err(PaymentError::Invalid(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn concurrent_payments_cannot_both_pay_the_balance() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = crate::db::create_db(&dir.path().join("ledger.db"))
+            .await
+            .unwrap();
+        crate::db::seed_default_accounts(&pool).await.unwrap();
+        let id = sent_invoice(&pool, "INV-1", 5_000).await;
+
+        let full = payment(id, 5_000, "1000");
+        let (a, b) = tokio::join!(
+            record_invoice_payment(&pool, &full),
+            record_invoice_payment(&pool, &full),
+        );
+        assert_eq!([&a, &b].iter().filter(|r| r.is_ok()).count(), 1);
+        assert!(matches!(a.or(b), Ok(p) if p.applied_cents == 5_000));
+        assert_eq!(net_credits(&pool, REVENUE_ACCOUNT_CODE).await, 5_000);
+    }
+
+    #[tokio::test]
+    async fn unreadable_invoice_date_is_an_error() {
+        let pool = test_pool().await;
+        let id = sent_invoice(&pool, "INV-1", 5_000).await;
+        sqlx::query("UPDATE invoices SET due_date = 'soon' WHERE id = ?")
+            .bind(id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert!(matches!(
+            invoice_balance(&pool, id).await,
+            Err(StorageError::InvalidData(_))
+        ));
+        assert!(matches!(
+            record_invoice_payment(&pool, &payment(id, 5_000, "1000")).await,
+            Err(PaymentError::Storage(StorageError::InvalidData(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn rejects_non_asset_deposit_account() {
+        let pool = test_pool().await;
+        let id = sent_invoice(&pool, "INV-1", 5_000).await;
+        assert!(matches!(
+            record_invoice_payment(&pool, &payment(id, 5_000, "4000")).await,
+            Err(PaymentError::Invalid(_))
+        ));
+        assert!(matches!(
+            record_invoice_payment(&pool, &payment(id + 1, 5_000, "1000")).await,
+            Err(PaymentError::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,706 @@
+//! Reminders: upcoming estimated-tax deadlines, overdue invoices, accounts
+//! not reconciled recently, a low checking balance and spending running
+//! past its monthly budget.
+//!
+//! [`evaluate_reminders`] works out which conditions hold today, and
+//! [`refresh_notifications`] stores them in `notifications`, keyed so each
+//! condition appears once. Notifications whose condition has cleared are
+//! resolved; a dismissed notification stays dismissed while its condition
+//! holds.
+
+use std::collections::HashSet;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::db::DbPool;
+
+/// Setting holding [`ReminderSettings`], as JSON.
+pub const REMINDER_SETTINGS: &str = "reminders";
+
+/// How long after its due date an unpaid estimated tax payment is still
+/// flagged.
+pub(crate) const TAX_OVERDUE_DAYS: i64 = 30;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReminderSettings {
+    pub enabled: bool,
+    /// Days before an estimated tax payment is due to start reminding.
+    pub tax_deadline_lead_days: i64,
+    /// Remind when an account's last completed reconciliation is older
+    /// than this.
+    pub reconciliation_interval_days: i64,
+    /// Codes of the accounts that should be reconciled regularly.
+    pub reconciliation_accounts: Vec<String>,
+    /// Account watched for a low balance.
+    pub low_balance_account: String,
+    /// Remind when that account's balance drops below this; `None` turns
+    /// the check off.
+    pub low_balance_threshold_cents: Option<i64>,
+    /// Alert when an account's spending this month passes this percentage
+    /// of its budget; `None` turns the alerts off.
+    pub budget_alert_percent: Option<u32>,
+    /// Where to keep an iCalendar file of upcoming deadlines, rewritten on
+    /// each check; `None` turns the feed off.
+    pub calendar_path: Option<String>,
+}
+
+impl Default for ReminderSettings {
+    fn default() -> Self {
+        ReminderSettings {
+            enabled: true,
+            tax_deadline_lead_days: 14,
+            reconciliation_interval_days: 45,
+            reconciliation_accounts: vec!["1000".to_string()],
+            low_balance_account: "1000".to_string(),
+            low_balance_threshold_cents: None,
+            budget_alert_percent: Some(90),
+            calendar_path: None,
+        }
+    }
+}
+
+pub async fn get_reminder_settings(pool: &DbPool) -> Result<ReminderSettings, sqlx::Error> {
+    match crate::db::get_setting(pool, REMINDER_SETTINGS).await? {
+        Some(json) if !json.trim().is_empty() => {
+            serde_json::from_str(&json).map_err(|e| sqlx::Error::Decode(Box::new(e)))
+        }
+        _ => Ok(ReminderSettings::default()),
+    }
+}
+
+pub async fn set_reminder_settings(
+    pool: &DbPool,
+    settings: &ReminderSettings,
+) -> Result<(), sqlx::Error> {
+    let json = serde_json::to_string(settings).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    crate::db::set_setting(pool, REMINDER_SETTINGS, &json).await
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReminderKind {
+    TaxDeadline,
+    InvoiceOverdue,
+    ReconciliationDue,
+    LowBalance,
+    BudgetAlert,
+}
+
+impl ReminderKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReminderKind::TaxDeadline => "tax_deadline",
+            ReminderKind::InvoiceOverdue => "invoice_overdue",
+            ReminderKind::ReconciliationDue => "reconciliation_due",
+            ReminderKind::LowBalance => "low_balance",
+            ReminderKind::BudgetAlert => "budget_alert",
+        }
+    }
+}
+
+/// A condition that currently needs the user's attention.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reminder {
+    /// Identifies the condition, e.g. `invoice_overdue:12`.
+    pub key: String,
+    pub kind: ReminderKind,
+    pub title: String,
+    pub body: String,
+    pub due_date: Option<NaiveDate>,
+}
+
+/// An estimated tax payment deadline, from the year's tax rules.
+#[derive(Debug, Clone, Copy)]
+pub struct TaxDeadline {
+    pub year: u16,
+    /// 1 to 4.
+    pub quarter: u8,
+    pub due_date: NaiveDate,
+}
+
+/// Everything that needs a reminder as of `today`.
+pub async fn evaluate_reminders(
+    pool: &DbPool,
+    settings: &ReminderSettings,
+    deadlines: &[TaxDeadline],
+    today: NaiveDate,
+) -> Result<Vec<Reminder>, sqlx::Error> {
+    if !settings.enabled {
+        return Ok(Vec::new());
+    }
+    let money = crate::db::get_preferences(pool).await?.money_format();
+    let mut reminders = Vec::new();
+
+    for deadline in deadlines {
+        let days_left = (deadline.due_date - today).num_days();
+        if days_left > settings.tax_deadline_lead_days || days_left < -TAX_OVERDUE_DAYS {
+            continue;
+        }
+        let quarter = format!("Q{}", deadline.quarter);
+        let period: Option<(i64, i64)> = sqlx::query_as(
+            "SELECT estimated_tax_cents, payment_recorded_cents FROM tax_periods WHERE year = ? AND quarter = ?",
+        )
+        .bind(deadline.year as i64)
+        .bind(deadline.quarter as i64)
+        .fetch_optional(pool)
+        .await?;
+        if period.is_some_and(|(_, paid)| paid > 0) {
+            continue;
+        }
+        let amount = period
+            .map(|(estimate, _)| estimate)
+            .filter(|e| *e > 0)
+            .map(|e| format!(" ({})", money.format_cents(e)))
+            .unwrap_or_default();
+        let due = deadline.due_date.format("%b %-d, %Y");
+        let (title, body) = if days_left < 0 {
+            (
+                "Estimated tax payment overdue",
+                format!(
+                    "The {} {quarter} estimated payment{amount} was due {due}.",
+                    deadline.year
+                ),
+            )
+        } else {
+            (
+                "Estimated tax payment due",
+                format!(
+                    "The {} {quarter} estimated payment{amount} is due {due}.",
+                    deadline.year
+                ),
+            )
+        };
+        reminders.push(Reminder {
+            key: format!("tax_deadline:{}-{quarter}", deadline.year),
+            kind: ReminderKind::TaxDeadline,
+            title: title.to_string(),
+            body,
+            due_date: Some(deadline.due_date),
+        });
+    }
+
+    for invoice in crate::db::get_invoice_aging(pool).await? {
+        let Ok(due) = NaiveDate::parse_from_str(&invoice.due_date, "%Y-%m-%d") else {
+            continue;
+        };
+        let days = (today - due).num_days();
+        if days <= 0 {
+            continue;
+        }
+        reminders.push(Reminder {
+            key: format!("invoice_overdue:{}", invoice.id),
+            kind: ReminderKind::InvoiceOverdue,
+            title: "Invoice overdue".to_string(),
+            body: format!(
+                "Invoice {} was due {} ({days} day{} ago).",
+                invoice.invoice_number,
+                invoice.due_date,
+                if days == 1 { "" } else { "s" }
+            ),
+            due_date: Some(due),
+        });
+    }
+
+    for code in &settings.reconciliation_accounts {
+        let row: Option<(String, Option<String>, Option<String>)> = sqlx::query_as(
+            r#"SELECT a.name,
+                      (SELECT MAX(end_date) FROM reconciliation_sessions
+                       WHERE account_id = a.id AND is_completed = 1),
+                      (SELECT MIN(t.date) FROM transaction_lines tl
+                       JOIN transactions t ON t.id = tl.transaction_id
+                       WHERE tl.account_id = a.id)
+               FROM accounts a WHERE a.code = ? AND a.is_archived = 0"#,
+        )
+        .bind(code)
+        .fetch_optional(pool)
+        .await?;
+        let Some((name, last_reconciled, first_activity)) = row else {
+            continue;
+        };
+        let since = last_reconciled
+            .as_deref()
+            .or(first_activity.as_deref())
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
+        let Some(since) = since else { continue };
+        if (today - since).num_days() <= settings.reconciliation_interval_days {
+            continue;
+        }
+        let body = match &last_reconciled {
+            Some(date) => format!("{name} was last reconciled through {date}."),
+            None => format!("{name} has never been reconciled."),
+        };
+        reminders.push(Reminder {
+            key: format!("reconciliation_due:{code}"),
+            kind: ReminderKind::ReconciliationDue,
+            title: "Reconciliation due".to_string(),
+            body,
+            due_date: None,
+        });
+    }
+
+    if let Some(threshold) = settings.low_balance_threshold_cents {
+        let row: Option<(String, i64)> = sqlx::query_as(
+            r#"SELECT a.name, COALESCE(SUM(tl.debit_cents - tl.credit_cents), 0)
+               FROM accounts a
+               LEFT JOIN transaction_lines tl ON tl.account_id = a.id
+               WHERE a.code = ?
+               GROUP BY a.id"#,
+        )
+        .bind(&settings.low_balance_account)
+        .fetch_optional(pool)
+        .await?;
+        if let Some((name, balance)) = row.filter(|(_, balance)| *balance < threshold) {
+            reminders.push(Reminder {
+                key: format!("low_balance:{}", settings.low_balance_account),
+                kind: ReminderKind::LowBalance,
+                title: "Low balance".to_string(),
+                body: format!(
+                    "{name} is at {}, below your {} threshold.",
+                    money.format_cents(balance),
+                    money.format_cents(threshold)
+                ),
+                due_date: None,
+            });
+        }
+    }
+
+    if let Some(percent) = settings.budget_alert_percent {
+        let month = today.format("%Y-%m");
+        for variance in crate::budgets::month_to_date_variance(pool, today).await? {
+            if !variance.exceeds(percent) {
+                continue;
+            }
+            let (title, left) = if variance.variance_cents < 0 {
+                (
+                    "Over budget",
+                    format!("{} over", money.format_cents(-variance.variance_cents)),
+                )
+            } else {
+                (
+                    "Nearing budget",
+                    format!("{} left", money.format_cents(variance.variance_cents)),
+                )
+            };
+            reminders.push(Reminder {
+                // Keyed by month, so a new month's overspend is raised afresh.
+                key: format!("budget_alert:{}:{month}", variance.account_code),
+                kind: ReminderKind::BudgetAlert,
+                title: title.to_string(),
+                body: format!(
+                    "{} has spent {} of its {} budget this month ({}%, {left}).",
+                    variance.account_name,
+                    money.format_cents(variance.actual_cents),
+                    money.format_cents(variance.budget_cents),
+                    variance.percent_used
+                ),
+                due_date: None,
+            });
+        }
+    }
+
+    Ok(reminders)
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct NotificationRecord {
+    pub id: i64,
+    pub key: String,
+    pub kind: String,
+    pub title: String,
+    pub body: String,
+    pub due_date: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub read_at: Option<String>,
+    pub dismissed_at: Option<String>,
+    pub resolved_at: Option<String>,
+}
+
+/// Store the current reminders: new conditions (and ones that had cleared
+/// and came back) are added, existing ones get their text refreshed, and
+/// open notifications not in `reminders` are resolved. Returns the
+/// notifications raised by this call, to be shown to the user.
+pub async fn refresh_notifications(
+    pool: &DbPool,
+    reminders: &[Reminder],
+) -> Result<Vec<NotificationRecord>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let mut raised = Vec::new();
+    for r in reminders {
+        let due_date = r.due_date.map(|d| d.to_string());
+        let existing: Option<(i64, Option<String>)> =
+            sqlx::query_as("SELECT id, resolved_at FROM notifications WHERE key = ?")
+                .bind(&r.key)
+                .fetch_optional(&mut *tx)
+                .await?;
+        let id = match existing {
+            None => {
+                let id = sqlx::query(
+                    "INSERT INTO notifications (key, kind, title, body, due_date) VALUES (?, ?, ?, ?, ?)",
+                )
+                .bind(&r.key)
+                .bind(r.kind.as_str())
+                .bind(&r.title)
+                .bind(&r.body)
+                .bind(&due_date)
+                .execute(&mut *tx)
+                .await?
+                .last_insert_rowid();
+                Some(id)
+            }
+            Some((id, resolved_at)) => {
+                sqlx::query(
+                    "UPDATE notifications SET title = ?, body = ?, due_date = ?, updated_at = datetime('now') WHERE id = ?",
+                )
+                .bind(&r.title)
+                .bind(&r.body)
+                .bind(&due_date)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+                // The condition had cleared and is back: raise it afresh.
+                let reopen = resolved_at.is_some();
+                if reopen {
+                    sqlx::query(
+                        r#"UPDATE notifications
+                           SET created_at = datetime('now'), read_at = NULL,
+                               dismissed_at = NULL, resolved_at = NULL
+                           WHERE id = ?"#,
+                    )
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                reopen.then_some(id)
+            }
+        };
+        if let Some(id) = id {
+            raised.push(id);
+        }
+    }
+
+    let active: HashSet<&str> = reminders.iter().map(|r| r.key.as_str()).collect();
+    let open: Vec<(i64, String)> =
+        sqlx::query_as("SELECT id, key FROM notifications WHERE resolved_at IS NULL")
+            .fetch_all(&mut *tx)
+            .await?;
+    for (id, key) in open {
+        if !active.contains(key.as_str()) {
+            sqlx::query("UPDATE notifications SET resolved_at = datetime('now') WHERE id = ?")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+    }
+
+    let mut records = Vec::with_capacity(raised.len());
+    for id in raised {
+        records.push(
+            sqlx::query_as::<_, NotificationRecord>("SELECT * FROM notifications WHERE id = ?")
+                .bind(id)
+                .fetch_one(&mut *tx)
+                .await?,
+        );
+    }
+    tx.commit().await?;
+    Ok(records)
+}
+
+/// Open notifications, newest first. Dismissed ones are included only if
+/// asked for.
+pub async fn get_notifications(
+    pool: &DbPool,
+    include_dismissed: bool,
+) -> Result<Vec<NotificationRecord>, sqlx::Error> {
+    sqlx::query_as::<_, NotificationRecord>(
+        r#"SELECT * FROM notifications
+           WHERE resolved_at IS NULL AND (? OR dismissed_at IS NULL)
+           ORDER BY created_at DESC, id DESC"#,
+    )
+    .bind(include_dismissed)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn mark_notification_read(pool: &DbPool, id: i64) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE notifications SET read_at = COALESCE(read_at, datetime('now')) WHERE id = ?",
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Hide a notification until its condition clears and comes back.
+pub async fn dismiss_notification(pool: &DbPool, id: i64) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"UPDATE notifications
+           SET dismissed_at = COALESCE(dismissed_at, datetime('now')),
+               read_at = COALESCE(read_at, datetime('now'))
+           WHERE id = ?"#,
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    /// Deposit `cents` into checking (1000) from sales (4000) on `day`.
+    async fn deposit(pool: &DbPool, day: &str, cents: i64) {
+        let id = sqlx::query(
+            "INSERT INTO transactions (date, description, balanced_total_cents) VALUES (?, 'Deposit', ?)",
+        )
+        .bind(day)
+        .bind(cents)
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+        for (code, debit, credit) in [("1000", cents, 0), ("4000", 0, cents)] {
+            sqlx::query(
+                r#"INSERT INTO transaction_lines (transaction_id, account_id, debit_cents, credit_cents)
+                   VALUES (?, (SELECT id FROM accounts WHERE code = ?), ?, ?)"#,
+            )
+            .bind(id)
+            .bind(code)
+            .bind(debit)
+            .bind(credit)
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+    }
+
+    fn keys(reminders: &[Reminder]) -> Vec<&str> {
+        reminders.iter().map(|r| r.key.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn evaluates_each_reminder_kind() {
+        let pool = test_pool().await;
+        let today = date("2026-04-05");
+        let deadlines = [
+            TaxDeadline {
+                year: 2026,
+                quarter: 1,
+                due_date: date("2026-04-15"),
+            },
+            TaxDeadline {
+                year: 2026,
+                quarter: 2,
+                due_date: date("2026-06-15"),
+            },
+        ];
+        let mut settings = ReminderSettings::default();
+
+        // Nothing booked yet: only the upcoming tax payment.
+        let reminders = evaluate_reminders(&pool, &settings, &deadlines, today)
+            .await
+            .unwrap();
+        assert_eq!(keys(&reminders), ["tax_deadline:2026-Q1"]);
+        assert_eq!(
+            reminders[0].body,
+            "The 2026 Q1 estimated payment is due Apr 15, 2026."
+        );
+
+        deposit(&pool, "2026-01-10", 5_000).await;
+        sqlx::query("INSERT INTO contacts (name) VALUES ('Acme')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            r#"INSERT INTO invoices (invoice_number, contact_id, status_type, issue_date, due_date)
+               VALUES ('INV-7', 1, 'Sent', '2026-03-01', '2026-03-31'),
+                      ('INV-8', 1, 'Sent', '2026-04-01', '2026-05-01'),
+                      ('INV-9', 1, 'Draft', '2026-01-01', '2026-01-31')"#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        crate::db::upsert_tax_period(&pool, 2026, 1, 120_000, 0, 0, 0, "2026-04-15", 2026)
+            .await
+            .unwrap();
+        settings.low_balance_threshold_cents = Some(10_000);
+
+        let reminders = evaluate_reminders(&pool, &settings, &deadlines, today)
+            .await
+            .unwrap();
+        assert_eq!(
+            keys(&reminders),
+            [
+                "tax_deadline:2026-Q1",
+                "invoice_overdue:1",
+                "reconciliation_due:1000",
+                "low_balance:1000",
+            ]
+        );
+        assert!(reminders[0].body.contains("($1,200.00)"));
+        assert_eq!(reminders[2].body, "Checking has never been reconciled.");
+
+        // Paying the estimate, reconciling and topping up clear them.
+        crate::db::record_tax_payment(&pool, 2026, 1, 120_000, "2026-04-06")
+            .await
+            .unwrap();
+        let checking = crate::db::get_account_by_code(&pool, "1000")
+            .await
+            .unwrap()
+            .unwrap();
+        let session = crate::db::create_reconciliation_session(
+            &pool,
+            checking.id.unwrap().0,
+            "2026-03-01",
+            "2026-03-31",
+            5_000,
+        )
+        .await
+        .unwrap();
+        crate::db::complete_reconciliation_session(&pool, session)
+            .await
+            .unwrap();
+        deposit(&pool, "2026-04-02", 10_000).await;
+        let reminders = evaluate_reminders(&pool, &settings, &deadlines, today)
+            .await
+            .unwrap();
+        assert_eq!(keys(&reminders), ["invoice_overdue:1"]);
+
+        settings.enabled = false;
+        assert!(evaluate_reminders(&pool, &settings, &deadlines, today)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn budget_alert_past_threshold() {
+        let pool = test_pool().await;
+        let today = date("2026-04-20");
+        let mut settings = ReminderSettings {
+            reconciliation_accounts: Vec::new(),
+            ..ReminderSettings::default()
+        };
+        let supplies = crate::db::get_account_by_code(&pool, "5100")
+            .await
+            .unwrap()
+            .unwrap();
+        crate::budgets::set_budget(&pool, supplies.id.unwrap().0, Some(20_000))
+            .await
+            .unwrap();
+        let id = sqlx::query(
+            "INSERT INTO transactions (date, description, balanced_total_cents) VALUES ('2026-04-08', 'Paper', 19000)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+        for (code, debit, credit) in [("5100", 19_000, 0), ("2000", 0, 19_000)] {
+            sqlx::query(
+                r#"INSERT INTO transaction_lines (transaction_id, account_id, debit_cents, credit_cents)
+                   VALUES (?, (SELECT id FROM accounts WHERE code = ?), ?, ?)"#,
+            )
+            .bind(id)
+            .bind(code)
+            .bind(debit)
+            .bind(credit)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let reminders = evaluate_reminders(&pool, &settings, &[], today)
+            .await
+            .unwrap();
+        assert_eq!(keys(&reminders), ["budget_alert:5100:2026-04"]);
+        assert_eq!(reminders[0].title, "Nearing budget");
+        assert!(reminders[0].body.contains("(95%, $10.00 left)"));
+
+        settings.budget_alert_percent = Some(100);
+        assert!(evaluate_reminders(&pool, &settings, &[], today)
+            .await
+            .unwrap()
+            .is_empty());
+        // A new month starts from nothing.
+        settings.budget_alert_percent = Some(90);
+        assert!(
+            evaluate_reminders(&pool, &settings, &[], date("2026-05-02"))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn notifications_raise_resolve_and_reopen() {
+        let pool = test_pool().await;
+        let overdue = Reminder {
+            key: "invoice_overdue:1".to_string(),
+            kind: ReminderKind::InvoiceOverdue,
+            title: "Invoice overdue".to_string(),
+            body: "Invoice INV-7 was due 2026-03-31 (5 days ago).".to_string(),
+            due_date: Some(date("2026-03-31")),
+        };
+        let low = Reminder {
+            key: "low_balance:1000".to_string(),
+            kind: ReminderKind::LowBalance,
+            title: "Low balance".to_string(),
+            body: "Checking is at $50.00, below your $100.00 threshold.".to_string(),
+            due_date: None,
+        };
+
+        let raised = refresh_notifications(&pool, &[overdue.clone(), low.clone()])
+            .await
+            .unwrap();
+        assert_eq!(raised.len(), 2);
+        assert_eq!(raised[0].kind, "invoice_overdue");
+        assert_eq!(raised[0].due_date.as_deref(), Some("2026-03-31"));
+
+        // Seen again: updated, not raised again.
+        let mut later = overdue.clone();
+        later.body = "Invoice INV-7 was due 2026-03-31 (6 days ago).".to_string();
+        assert!(refresh_notifications(&pool, &[later, low.clone()])
+            .await
+            .unwrap()
+            .is_empty());
+        let open = get_notifications(&pool, false).await.unwrap();
+        assert_eq!(open.len(), 2);
+        let invoice = open.iter().find(|n| n.key == overdue.key).unwrap();
+        assert!(invoice.body.contains("6 days"));
+
+        // Dismissed stays hidden while the condition holds.
+        assert!(mark_notification_read(&pool, invoice.id).await.unwrap());
+        assert!(dismiss_notification(&pool, invoice.id).await.unwrap());
+        assert!(!dismiss_notification(&pool, 999).await.unwrap());
+        refresh_notifications(&pool, &[overdue.clone(), low.clone()])
+            .await
+            .unwrap();
+        assert_eq!(get_notifications(&pool, false).await.unwrap().len(), 1);
+        assert_eq!(get_notifications(&pool, true).await.unwrap().len(), 2);
+
+        // Cleared conditions resolve; one that comes back is raised afresh.
+        refresh_notifications(&pool, std::slice::from_ref(&overdue))
+            .await
+            .unwrap();
+        let open = get_notifications(&pool, true).await.unwrap();
+        assert_eq!(keys_of(&open), [overdue.key.as_str()]);
+        let raised = refresh_notifications(&pool, &[overdue.clone(), low.clone()])
+            .await
+            .unwrap();
+        assert_eq!(keys_of(&raised), [low.key.as_str()]);
+        assert!(raised[0].read_at.is_none());
+    }
+
+    fn keys_of(records: &[NotificationRecord]) -> Vec<&str> {
+        records.iter().map(|r| r.key.as_str()).collect()
+    }
+}
@@ -0,0 +1,1448 @@
+//! Financial reports for export: profit and loss, balance sheet, an
+//! account register, a reconciliation summary, spending by vendor and the
+//! owner's equity roll-forward.
+//!
+//! Each report is a plain struct, serialized as-is for JSON exports, with a
+//! `table()` laying it out as a [`ReportTable`] for CSV and PDF. The raw
+//! transactions for a period, with the balances carried into it, are here
+//! too for plain-text journal exports.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use aequi_core::export::{LedgerOpening, ReportCell, ReportColumn, ReportRow, ReportTable};
+use aequi_core::tax::engine::ScheduleCPreview;
+use aequi_core::{
+    AccountId, DateRange, Money, ScheduleCLine, TransactionLine, ValidatedTransaction,
+    OWNER_DRAW_CODE,
+};
+use chrono::{Datelike, Days, Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::account_balances::{net_debits, AccountNet};
+use crate::balance_assertions::{check_balance_assertions, AssertionCheck};
+use crate::db::{normalize_payee, DbPool};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportLine {
+    pub account_code: String,
+    pub account_name: String,
+    pub amount_cents: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfitLossReport {
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub income: Vec<ReportLine>,
+    pub expenses: Vec<ReportLine>,
+    pub total_income_cents: i64,
+    pub total_expenses_cents: i64,
+    pub net_income_cents: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BalanceSheetReport {
+    pub as_of: NaiveDate,
+    pub assets: Vec<ReportLine>,
+    pub liabilities: Vec<ReportLine>,
+    pub equity: Vec<ReportLine>,
+    /// Income less expenses through `as_of`, not yet closed to equity.
+    pub net_income_cents: i64,
+    pub total_assets_cents: i64,
+    pub total_liabilities_cents: i64,
+    /// Equity accounts plus net income.
+    pub total_equity_cents: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RegisterEntry {
+    pub transaction_id: i64,
+    pub date: String,
+    pub description: String,
+    pub memo: Option<String>,
+    pub debit_cents: i64,
+    pub credit_cents: i64,
+    /// Running balance after this entry, positive in the account's normal
+    /// direction.
+    pub balance_cents: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountRegister {
+    pub account_code: String,
+    pub account_name: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub opening_balance_cents: i64,
+    pub entries: Vec<RegisterEntry>,
+    pub closing_balance_cents: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReconciliationReportItem {
+    pub date: Option<String>,
+    pub description: Option<String>,
+    pub match_type: String,
+    pub difference_cents: i64,
+    pub is_resolved: bool,
+    pub resolution_notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReconciliationReport {
+    pub session_id: i64,
+    pub account_code: String,
+    pub account_name: String,
+    pub start_date: String,
+    pub end_date: String,
+    pub is_completed: bool,
+    pub statement_balance_cents: i64,
+    /// The account's ledger balance on `end_date`.
+    pub ledger_balance_cents: i64,
+    /// Statement balance less ledger balance.
+    pub difference_cents: i64,
+    pub items: Vec<ReconciliationReportItem>,
+    /// Balance assertions on the account dated within the session.
+    pub assertions: Vec<AssertionCheck>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VendorSpending {
+    /// The contact's name when the payee is a known contact, otherwise the
+    /// payee as most often written.
+    pub payee: String,
+    pub contact_id: Option<i64>,
+    pub transaction_count: i64,
+    pub total_cents: i64,
+    /// Spending in each of the report's `months`.
+    pub monthly_cents: Vec<i64>,
+    /// The last month's spending less the month before's.
+    pub last_month_change_cents: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VendorSpendingReport {
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    /// `YYYY-MM`, oldest first.
+    pub months: Vec<String>,
+    /// Largest total first.
+    pub vendors: Vec<VendorSpending>,
+    /// Spending with vendors beyond the ones listed.
+    pub other_cents: i64,
+    /// Spending with all vendors in each month.
+    pub monthly_total_cents: Vec<i64>,
+    pub total_cents: i64,
+}
+
+/// How a report's period is divided into columns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportInterval {
+    Month,
+    Quarter,
+    Year,
+}
+
+impl ReportInterval {
+    /// `range` cut at the start of each month, quarter or year, with a label
+    /// for each part.
+    pub fn split(self, range: DateRange) -> Vec<(String, DateRange)> {
+        let mut parts = Vec::new();
+        let mut start = range.start;
+        while start <= range.end {
+            let first = match self {
+                ReportInterval::Month => start.with_day(1),
+                ReportInterval::Quarter => {
+                    NaiveDate::from_ymd_opt(start.year(), (start.month0() / 3) * 3 + 1, 1)
+                }
+                ReportInterval::Year => NaiveDate::from_ymd_opt(start.year(), 1, 1),
+            }
+            .unwrap_or(start);
+            let (months, label) = match self {
+                ReportInterval::Month => (1, start.format("%Y-%m").to_string()),
+                ReportInterval::Quarter => {
+                    (3, format!("{} Q{}", start.year(), start.month0() / 3 + 1))
+                }
+                ReportInterval::Year => (12, start.year().to_string()),
+            };
+            let next = first + Months::new(months);
+            let end = (next - Days::new(1)).min(range.end);
+            parts.push((label, DateRange::new(start, end)));
+            start = next;
+        }
+        parts
+    }
+}
+
+/// How owner's equity moved over one part of the report.
+#[derive(Debug, Clone, Serialize)]
+pub struct EquityPeriod {
+    pub label: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub opening_cents: i64,
+    /// Net credits to equity accounts other than the draw account.
+    pub contributions_cents: i64,
+    /// Net debits to the draw account: what the owner took out.
+    pub draws_cents: i64,
+    /// Business income less expenses, as on the profit and loss.
+    pub net_profit_cents: i64,
+    /// Income less expenses on personal transactions; usually negative.
+    pub personal_cents: i64,
+    pub closing_cents: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OwnersEquityReport {
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub interval: ReportInterval,
+    pub periods: Vec<EquityPeriod>,
+    pub opening_cents: i64,
+    pub contributions_cents: i64,
+    pub draws_cents: i64,
+    pub net_profit_cents: i64,
+    pub personal_cents: i64,
+    /// Matches total equity on the balance sheet at `end_date`.
+    pub closing_cents: i64,
+}
+
+/// Whether balances of this account type grow with debits.
+fn debit_normal(account_type: &str) -> bool {
+    matches!(account_type, "Asset" | "Expense")
+}
+
+fn sum(lines: &[ReportLine]) -> i64 {
+    lines.iter().map(|l| l.amount_cents).sum()
+}
+
+/// Income and expenses by account over `period`, leaving out personal
+/// transactions.
+pub async fn profit_loss(
+    pool: &DbPool,
+    period: DateRange,
+) -> Result<ProfitLossReport, sqlx::Error> {
+    let mut income = Vec::new();
+    let mut expenses = Vec::new();
+    for net in net_debits(pool, Some(period.start), period.end, true).await? {
+        let (lines, amount_cents) = match net.account_type.as_str() {
+            "Income" => (&mut income, -net.net_cents),
+            "Expense" => (&mut expenses, net.net_cents),
+            _ => continue,
+        };
+        lines.push(ReportLine {
+            account_code: net.code,
+            account_name: net.name,
+            amount_cents,
+        });
+    }
+    let total_income_cents = sum(&income);
+    let total_expenses_cents = sum(&expenses);
+    Ok(ProfitLossReport {
+        start_date: period.start,
+        end_date: period.end,
+        income,
+        expenses,
+        total_income_cents,
+        total_expenses_cents,
+        net_income_cents: total_income_cents - total_expenses_cents,
+    })
+}
+
+/// Asset, liability and equity balances at the end of `as_of`.
+pub async fn balance_sheet(
+    pool: &DbPool,
+    as_of: NaiveDate,
+) -> Result<BalanceSheetReport, sqlx::Error> {
+    let mut assets = Vec::new();
+    let mut liabilities = Vec::new();
+    let mut equity = Vec::new();
+    let mut net_income_cents = 0;
+    for net in net_debits(pool, None, as_of, false).await? {
+        let (lines, amount_cents) = match net.account_type.as_str() {
+            "Asset" => (&mut assets, net.net_cents),
+            "Liability" => (&mut liabilities, -net.net_cents),
+            "Equity" => (&mut equity, -net.net_cents),
+            _ => {
+                net_income_cents -= net.net_cents;
+                continue;
+            }
+        };
+        lines.push(ReportLine {
+            account_code: net.code,
+            account_name: net.name,
+            amount_cents,
+        });
+    }
+    Ok(BalanceSheetReport {
+        as_of,
+        total_assets_cents: sum(&assets),
+        total_liabilities_cents: sum(&liabilities),
+        total_equity_cents: sum(&equity) + net_income_cents,
+        assets,
+        liabilities,
+        equity,
+        net_income_cents,
+    })
+}
+
+/// Every posting to the account over `period` with a running balance, or
+/// `None` if there is no account with that code.
+pub async fn account_register(
+    pool: &DbPool,
+    account_code: &str,
+    period: DateRange,
+) -> Result<Option<AccountRegister>, sqlx::Error> {
+    let Some((account_id, account_name, account_type)) =
+        sqlx::query_as::<_, (i64, String, String)>(
+            "SELECT id, name, account_type FROM accounts WHERE code = ?",
+        )
+        .bind(account_code)
+        .fetch_optional(pool)
+        .await?
+    else {
+        return Ok(None);
+    };
+    let sign = if debit_normal(&account_type) { 1 } else { -1 };
+
+    let opening: i64 = sqlx::query_scalar(
+        r#"SELECT COALESCE(SUM(tl.debit_cents - tl.credit_cents), 0)
+           FROM transaction_lines tl
+           JOIN transactions t ON t.id = tl.transaction_id
+           WHERE tl.account_id = ? AND t.date < ?"#,
+    )
+    .bind(account_id)
+    .bind(period.start.to_string())
+    .fetch_one(pool)
+    .await?;
+    let opening_balance_cents = sign * opening;
+
+    let rows = sqlx::query_as::<_, (i64, String, String, Option<String>, i64, i64)>(
+        r#"SELECT t.id, t.date, t.description, COALESCE(tl.memo, t.memo),
+                  tl.debit_cents, tl.credit_cents
+           FROM transaction_lines tl
+           JOIN transactions t ON t.id = tl.transaction_id
+           WHERE tl.account_id = ? AND t.date >= ? AND t.date <= ?
+           ORDER BY t.date, t.id, tl.id"#,
+    )
+    .bind(account_id)
+    .bind(period.start.to_string())
+    .bind(period.end.to_string())
+    .fetch_all(pool)
+    .await?;
+
+    let mut balance = opening_balance_cents;
+    let entries = rows
+        .into_iter()
+        .map(
+            |(transaction_id, date, description, memo, debit_cents, credit_cents)| {
+                balance += sign * (debit_cents - credit_cents);
+                RegisterEntry {
+                    transaction_id,
+                    date,
+                    description,
+                    memo,
+                    debit_cents,
+                    credit_cents,
+                    balance_cents: balance,
+                }
+            },
+        )
+        .collect();
+
+    Ok(Some(AccountRegister {
+        account_code: account_code.to_string(),
+        account_name,
+        start_date: period.start,
+        end_date: period.end,
+        opening_balance_cents,
+        entries,
+        closing_balance_cents: balance,
+    }))
+}
+
+/// A reconciliation session against the ledger, with its items, or `None`
+/// if there is no such session.
+pub async fn reconciliation(
+    pool: &DbPool,
+    session_id: i64,
+) -> Result<Option<ReconciliationReport>, sqlx::Error> {
+    let Some(session) =
+        sqlx::query_as::<_, (i64, String, String, String, String, String, i64, bool)>(
+            r#"SELECT a.id, a.code, a.name, a.account_type, s.start_date, s.end_date,
+                  s.statement_balance_cents, s.is_completed
+           FROM reconciliation_sessions s
+           JOIN accounts a ON a.id = s.account_id
+           WHERE s.id = ?"#,
+        )
+        .bind(session_id)
+        .fetch_optional(pool)
+        .await?
+    else {
+        return Ok(None);
+    };
+    let (
+        account_id,
+        account_code,
+        account_name,
+        account_type,
+        start_date,
+        end_date,
+        statement,
+        is_completed,
+    ) = session;
+
+    let net: i64 = sqlx::query_scalar(
+        r#"SELECT COALESCE(SUM(tl.debit_cents - tl.credit_cents), 0)
+           FROM transaction_lines tl
+           JOIN transactions t ON t.id = tl.transaction_id
+           WHERE tl.account_id = ? AND t.date <= ?"#,
+    )
+    .bind(account_id)
+    .bind(&end_date)
+    .fetch_one(pool)
+    .await?;
+    let ledger_balance_cents = if debit_normal(&account_type) {
+        net
+    } else {
+        -net
+    };
+
+    let items = sqlx::query_as::<
+        _,
+        (
+            Option<String>,
+            Option<String>,
+            String,
+            i64,
+            bool,
+            Option<String>,
+        ),
+    >(
+        r#"SELECT COALESCE(t.date, it.date), COALESCE(t.description, it.description),
+                  ri.match_type, ri.difference_cents, ri.is_resolved, ri.resolution_notes
+           FROM reconciliation_items ri
+           LEFT JOIN transactions t ON t.id = ri.transaction_id
+           LEFT JOIN imported_transactions it ON it.id = ri.imported_transaction_id
+           WHERE ri.session_id = ?
+           ORDER BY COALESCE(t.date, it.date), ri.id"#,
+    )
+    .bind(session_id)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(
+        |(date, description, match_type, difference_cents, is_resolved, resolution_notes)| {
+            ReconciliationReportItem {
+                date,
+                description,
+                match_type,
+                difference_cents,
+                is_resolved,
+                resolution_notes,
+            }
+        },
+    )
+    .collect();
+
+    let assertions = check_balance_assertions(pool, Some(account_id))
+        .await?
+        .into_iter()
+        .filter(|c| c.assertion.date >= start_date && c.assertion.date <= end_date)
+        .collect();
+
+    Ok(Some(ReconciliationReport {
+        session_id,
+        account_code,
+        account_name,
+        start_date,
+        end_date,
+        is_completed,
+        statement_balance_cents: statement,
+        ledger_balance_cents,
+        difference_cents: statement - ledger_balance_cents,
+        items,
+        assertions,
+    }))
+}
+
+/// Every transaction dated within `period`, with its lines, oldest first.
+pub async fn period_transactions(
+    pool: &DbPool,
+    period: DateRange,
+) -> Result<Vec<ValidatedTransaction>, sqlx::Error> {
+    let (start, end) = (period.start.to_string(), period.end.to_string());
+    let headers = sqlx::query_as::<_, (i64, String, String, Option<String>, i64)>(
+        r#"SELECT id, date, description, memo, balanced_total_cents
+           FROM transactions
+           WHERE date >= ? AND date <= ?
+           ORDER BY date, id"#,
+    )
+    .bind(&start)
+    .bind(&end)
+    .fetch_all(pool)
+    .await?;
+    let lines = sqlx::query_as::<_, (i64, i64, i64, i64, Option<String>)>(
+        r#"SELECT tl.transaction_id, tl.account_id, tl.debit_cents, tl.credit_cents, tl.memo
+           FROM transaction_lines tl
+           JOIN transactions t ON t.id = tl.transaction_id
+           WHERE t.date >= ? AND t.date <= ?
+           ORDER BY tl.id"#,
+    )
+    .bind(&start)
+    .bind(&end)
+    .fetch_all(pool)
+    .await?;
+
+    let mut by_transaction: HashMap<i64, Vec<TransactionLine>> = HashMap::new();
+    for (transaction_id, account_id, debit, credit, memo) in lines {
+        by_transaction
+            .entry(transaction_id)
+            .or_default()
+            .push(TransactionLine {
+                account_id: AccountId(account_id),
+                debit: Money::from_cents(debit),
+                credit: Money::from_cents(credit),
+                memo,
+            });
+    }
+
+    headers
+        .into_iter()
+        .map(|(id, date, description, memo, total)| {
+            let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+            Ok(ValidatedTransaction {
+                id: Some(id),
+                date,
+                description,
+                lines: by_transaction.remove(&id).unwrap_or_default(),
+                memo,
+                balanced_total: Money::from_cents(total),
+                created_at: None,
+            })
+        })
+        .collect()
+}
+
+/// Net debits of the asset, liability and equity accounts from everything
+/// dated before `date`, to open a journal that starts then.
+pub async fn opening_balances(
+    pool: &DbPool,
+    date: NaiveDate,
+) -> Result<LedgerOpening, sqlx::Error> {
+    let balances = sqlx::query_as::<_, (i64, i64)>(
+        r#"SELECT a.id, SUM(tl.debit_cents - tl.credit_cents) AS net
+           FROM accounts a
+           JOIN transaction_lines tl ON tl.account_id = a.id
+           JOIN transactions t ON t.id = tl.transaction_id
+           WHERE t.date < ? AND a.account_type IN ('Asset', 'Liability', 'Equity')
+           GROUP BY a.id
+           HAVING net != 0
+           ORDER BY a.code"#,
+    )
+    .bind(date.to_string())
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|(id, net)| (AccountId(id), Money::from_cents(net)))
+    .collect();
+    Ok(LedgerOpening { date, balances })
+}
+
+/// Business expenses by payee over `period`, the `limit` largest vendors
+/// with their spending month by month. Payees are grouped the way
+/// [`find_contact_by_payee`](crate::db::find_contact_by_payee) matches them
+/// to contacts, and otherwise by their normalized description.
+pub async fn vendor_spending(
+    pool: &DbPool,
+    period: DateRange,
+    limit: usize,
+) -> Result<VendorSpendingReport, sqlx::Error> {
+    let mut months = Vec::new();
+    let mut month = period.start.with_day(1).unwrap_or(period.start);
+    while month <= period.end {
+        months.push(month.format("%Y-%m").to_string());
+        month = month + Months::new(1);
+    }
+
+    // (name, alias) of active contacts; the name itself matches too.
+    let contacts = sqlx::query_as::<_, (i64, String, Option<String>)>(
+        r#"SELECT c.id, c.name, a.alias FROM contacts c
+           LEFT JOIN contact_aliases a ON a.contact_id = c.id
+           WHERE c.is_archived = 0"#,
+    )
+    .fetch_all(pool)
+    .await?;
+    let contact_for = |payee: &str| {
+        let mut best: Option<(usize, i64, &str)> = None;
+        for (id, name, alias) in &contacts {
+            let name_key = normalize_payee(name);
+            if name_key == payee || alias.as_deref() == Some(payee) {
+                return Some((*id, name.as_str()));
+            }
+            if let Some(alias) = alias.as_deref().filter(|a| payee.contains(*a)) {
+                if best.is_none_or(|(len, ..)| alias.len() > len) {
+                    best = Some((alias.len(), *id, name.as_str()));
+                }
+            }
+        }
+        best.map(|(_, id, name)| (id, name))
+    };
+
+    let spending = sqlx::query_as::<_, (String, String, i64)>(
+        r#"SELECT t.description, t.date, SUM(tl.debit_cents - tl.credit_cents)
+           FROM transactions t
+           JOIN transaction_lines tl ON tl.transaction_id = t.id
+           JOIN accounts a ON a.id = tl.account_id
+           WHERE a.account_type = 'Expense' AND t.is_personal = 0
+             AND t.date >= ? AND t.date <= ?
+           GROUP BY t.id"#,
+    )
+    .bind(period.start.to_string())
+    .bind(period.end.to_string())
+    .fetch_all(pool)
+    .await?;
+
+    // Spellings seen for each payee, to name it by the commonest.
+    let mut vendors: HashMap<String, (VendorSpending, HashMap<String, usize>)> = HashMap::new();
+    for (description, date, cents) in spending {
+        let key = normalize_payee(&description);
+        let contact = contact_for(&key);
+        let key = contact.map_or(key, |(id, _)| format!("contact:{id}"));
+        let (vendor, spellings) = vendors.entry(key).or_insert_with(|| {
+            (
+                VendorSpending {
+                    payee: contact
+                        .map(|(_, name)| name.to_string())
+                        .unwrap_or_default(),
+                    contact_id: contact.map(|(id, _)| id),
+                    transaction_count: 0,
+                    total_cents: 0,
+                    monthly_cents: vec![0; months.len()],
+                    last_month_change_cents: 0,
+                },
+                HashMap::new(),
+            )
+        });
+        vendor.transaction_count += 1;
+        vendor.total_cents += cents;
+        if let Some(i) = months.iter().position(|m| date.starts_with(m.as_str())) {
+            vendor.monthly_cents[i] += cents;
+        }
+        *spellings.entry(description.trim().to_string()).or_default() += 1;
+    }
+
+    let mut vendors: Vec<VendorSpending> = vendors
+        .into_values()
+        .filter(|(v, _)| v.total_cents != 0)
+        .map(|(mut vendor, spellings)| {
+            if vendor.contact_id.is_none() {
+                vendor.payee = spellings
+                    .into_iter()
+                    .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+                    .map(|(spelling, _)| spelling)
+                    .unwrap_or_default();
+            }
+            if let [.., previous, last] = vendor.monthly_cents[..] {
+                vendor.last_month_change_cents = last - previous;
+            }
+            vendor
+        })
+        .collect();
+    vendors.sort_by(|a, b| {
+        b.total_cents
+            .cmp(&a.total_cents)
+            .then_with(|| a.payee.cmp(&b.payee))
+    });
+    let mut monthly_total_cents = vec![0; months.len()];
+    for vendor in &vendors {
+        for (total, cents) in monthly_total_cents.iter_mut().zip(&vendor.monthly_cents) {
+            *total += cents;
+        }
+    }
+    let total_cents = vendors.iter().map(|v| v.total_cents).sum();
+    let other_cents = vendors.iter().skip(limit).map(|v| v.total_cents).sum();
+    vendors.truncate(limit);
+    Ok(VendorSpendingReport {
+        start_date: period.start,
+        end_date: period.end,
+        months,
+        vendors,
+        other_cents,
+        monthly_total_cents,
+        total_cents,
+    })
+}
+
+/// Net credits to accounts of the given types.
+fn credits(nets: &[AccountNet], account_types: &[&str]) -> i64 {
+    -nets
+        .iter()
+        .filter(|n| account_types.contains(&n.account_type.as_str()))
+        .map(|n| n.net_cents)
+        .sum::<i64>()
+}
+
+/// Owner's equity from the start of `period` to its end, part by part:
+/// what the owner put in and took out, and the profit the business made.
+pub async fn owners_equity(
+    pool: &DbPool,
+    period: DateRange,
+    interval: ReportInterval,
+) -> Result<OwnersEquityReport, sqlx::Error> {
+    // Everything not an asset or liability is equity, with income and
+    // expenses not yet closed to it.
+    let opening_cents = match period.start.pred_opt() {
+        Some(before) => credits(
+            &net_debits(pool, None, before, false).await?,
+            &["Equity", "Income", "Expense"],
+        ),
+        None => 0,
+    };
+
+    let mut periods = Vec::new();
+    let mut balance = opening_cents;
+    for (label, part) in interval.split(period) {
+        let all = net_debits(pool, Some(part.start), part.end, false).await?;
+        let business = net_debits(pool, Some(part.start), part.end, true).await?;
+        let equity = all.iter().filter(|n| n.account_type == "Equity");
+        let draws_cents: i64 = equity
+            .clone()
+            .filter(|n| n.code == OWNER_DRAW_CODE)
+            .map(|n| n.net_cents)
+            .sum();
+        let contributions_cents: i64 = -equity
+            .filter(|n| n.code != OWNER_DRAW_CODE)
+            .map(|n| n.net_cents)
+            .sum::<i64>();
+        let net_profit_cents = credits(&business, &["Income", "Expense"]);
+        let personal_cents = credits(&all, &["Income", "Expense"]) - net_profit_cents;
+        let opening = balance;
+        balance += contributions_cents - draws_cents + net_profit_cents + personal_cents;
+        periods.push(EquityPeriod {
+            label,
+            start_date: part.start,
+            end_date: part.end,
+            opening_cents: opening,
+            contributions_cents,
+            draws_cents,
+            net_profit_cents,
+            personal_cents,
+            closing_cents: balance,
+        });
+    }
+
+    let total = |f: fn(&EquityPeriod) -> i64| periods.iter().map(f).sum();
+    Ok(OwnersEquityReport {
+        start_date: period.start,
+        end_date: period.end,
+        interval,
+        opening_cents,
+        contributions_cents: total(|p| p.contributions_cents),
+        draws_cents: total(|p| p.draws_cents),
+        net_profit_cents: total(|p| p.net_profit_cents),
+        personal_cents: total(|p| p.personal_cents),
+        closing_cents: balance,
+        periods,
+    })
+}
+
+fn amount(cents: i64) -> ReportCell {
+    ReportCell::Amount(Money::from_cents(cents))
+}
+
+fn account_rows(table: &mut ReportTable, lines: &[ReportLine]) {
+    for line in lines {
+        table.push(ReportRow::detail(vec![
+            ReportCell::text(format!("{} {}", line.account_code, line.account_name)),
+            amount(line.amount_cents),
+        ]));
+    }
+}
+
+fn labelled(label: &str, cents: i64) -> Vec<ReportCell> {
+    vec![ReportCell::text(label), amount(cents)]
+}
+
+impl ProfitLossReport {
+    pub fn table(&self) -> ReportTable {
+        let mut table = ReportTable::new(
+            "Profit and Loss",
+            DateRange::new(self.start_date, self.end_date).to_string(),
+        )
+        .columns(vec![
+            ReportColumn::text("Account"),
+            ReportColumn::amount("Amount"),
+        ]);
+        table.push(ReportRow::heading("Income"));
+        account_rows(&mut table, &self.income);
+        table.push(ReportRow::subtotal(labelled(
+            "Total income",
+            self.total_income_cents,
+        )));
+        table.push(ReportRow::heading("Expenses"));
+        account_rows(&mut table, &self.expenses);
+        table.push(ReportRow::subtotal(labelled(
+            "Total expenses",
+            self.total_expenses_cents,
+        )));
+        table.push(ReportRow::total(labelled(
+            "Net income",
+            self.net_income_cents,
+        )));
+        table
+    }
+}
+
+impl BalanceSheetReport {
+    pub fn table(&self) -> ReportTable {
+        let mut table =
+            ReportTable::new("Balance Sheet", format!("As of {}", self.as_of)).columns(vec![
+                ReportColumn::text("Account"),
+                ReportColumn::amount("Balance"),
+            ]);
+        table.push(ReportRow::heading("Assets"));
+        account_rows(&mut table, &self.assets);
+        table.push(ReportRow::total(labelled(
+            "Total assets",
+            self.total_assets_cents,
+        )));
+        table.push(ReportRow::heading("Liabilities"));
+        account_rows(&mut table, &self.liabilities);
+        table.push(ReportRow::subtotal(labelled(
+            "Total liabilities",
+            self.total_liabilities_cents,
+        )));
+        table.push(ReportRow::heading("Equity"));
+        account_rows(&mut table, &self.equity);
+        table.push(ReportRow::detail(labelled(
+            "Net income to date",
+            self.net_income_cents,
+        )));
+        table.push(ReportRow::subtotal(labelled(
+            "Total equity",
+            self.total_equity_cents,
+        )));
+        table.push(ReportRow::total(labelled(
+            "Total liabilities and equity",
+            self.total_liabilities_cents + self.total_equity_cents,
+        )));
+        table
+    }
+}
+
+impl AccountRegister {
+    pub fn table(&self) -> ReportTable {
+        let mut table = ReportTable::new(
+            format!("Register: {} {}", self.account_code, self.account_name),
+            DateRange::new(self.start_date, self.end_date).to_string(),
+        )
+        .columns(vec![
+            ReportColumn::text("Date"),
+            ReportColumn::text("Description"),
+            ReportColumn::amount("Debit"),
+            ReportColumn::amount("Credit"),
+            ReportColumn::amount("Balance"),
+        ]);
+        table.push(ReportRow::detail(vec![
+            ReportCell::text(self.start_date.to_string()),
+            ReportCell::text("Opening balance"),
+            ReportCell::Empty,
+            ReportCell::Empty,
+            amount(self.opening_balance_cents),
+        ]));
+        let nonzero = |cents: i64| match cents {
+            0 => ReportCell::Empty,
+            c => amount(c),
+        };
+        for entry in &self.entries {
+            let description = match &entry.memo {
+                Some(memo) if !memo.is_empty() => format!("{} — {memo}", entry.description),
+                _ => entry.description.clone(),
+            };
+            table.push(ReportRow::detail(vec![
+                ReportCell::text(&entry.date),
+                ReportCell::text(description),
+                nonzero(entry.debit_cents),
+                nonzero(entry.credit_cents),
+                amount(entry.balance_cents),
+            ]));
+        }
+        table.push(ReportRow::total(vec![
+            ReportCell::text(self.end_date.to_string()),
+            ReportCell::text("Closing balance"),
+            amount(self.entries.iter().map(|e| e.debit_cents).sum()),
+            amount(self.entries.iter().map(|e| e.credit_cents).sum()),
+            amount(self.closing_balance_cents),
+        ]));
+        table
+    }
+}
+
+impl ReconciliationReport {
+    pub fn table(&self) -> ReportTable {
+        let status = if self.is_completed {
+            "completed"
+        } else {
+            "in progress"
+        };
+        let mut table = ReportTable::new(
+            format!(
+                "Reconciliation: {} {}",
+                self.account_code, self.account_name
+            ),
+            format!("{} to {} ({status})", self.start_date, self.end_date),
+        )
+        .columns(vec![
+            ReportColumn::text("Date"),
+            ReportColumn::text("Description"),
+            ReportColumn::text("Match"),
+            ReportColumn::amount("Difference"),
+            ReportColumn::text("Status"),
+        ]);
+        let summary = |label: &str, cents: i64| {
+            vec![
+                ReportCell::Empty,
+                ReportCell::text(label),
+                ReportCell::Empty,
+                amount(cents),
+            ]
+        };
+        table.push(ReportRow::detail(summary(
+            "Statement balance",
+            self.statement_balance_cents,
+        )));
+        table.push(ReportRow::detail(summary(
+            "Ledger balance",
+            self.ledger_balance_cents,
+        )));
+        table.push(ReportRow::subtotal(summary(
+            "Difference",
+            self.difference_cents,
+        )));
+        table.push(ReportRow::heading("Items"));
+        for item in &self.items {
+            let status = match (&item.resolution_notes, item.is_resolved) {
+                (Some(notes), true) if !notes.is_empty() => format!("Resolved: {notes}"),
+                (_, true) => "Resolved".to_string(),
+                (_, false) => "Open".to_string(),
+            };
+            table.push(ReportRow::detail(vec![
+                ReportCell::text(item.date.clone().unwrap_or_default()),
+                ReportCell::text(item.description.clone().unwrap_or_default()),
+                ReportCell::text(&item.match_type),
+                amount(item.difference_cents),
+                ReportCell::text(status),
+            ]));
+        }
+        if !self.assertions.is_empty() {
+            table.push(ReportRow::heading("Balance assertions"));
+            for check in &self.assertions {
+                let status = if check.holds() {
+                    "Agrees".to_string()
+                } else {
+                    format!(
+                        "Ledger shows {}",
+                        Money::from_cents(check.ledger_balance_cents)
+                    )
+                };
+                table.push(ReportRow::detail(vec![
+                    ReportCell::text(&check.assertion.date),
+                    ReportCell::text(format!("Statement balance ({})", check.assertion.source)),
+                    ReportCell::Empty,
+                    amount(check.drift_cents),
+                    ReportCell::text(status),
+                ]));
+            }
+        }
+        table
+    }
+}
+
+impl VendorSpendingReport {
+    pub fn table(&self) -> ReportTable {
+        let mut columns = vec![ReportColumn::text("Vendor")];
+        columns.extend(self.months.iter().map(ReportColumn::amount));
+        columns.push(ReportColumn::amount("Total"));
+        let mut table = ReportTable::new(
+            "Vendor Spending",
+            DateRange::new(self.start_date, self.end_date).to_string(),
+        )
+        .columns(columns);
+        let row = |label: &str, monthly: &[i64], total: i64| {
+            let mut cells = vec![ReportCell::text(label)];
+            cells.extend(monthly.iter().map(|&c| amount(c)));
+            cells.push(amount(total));
+            cells
+        };
+        for vendor in &self.vendors {
+            table.push(ReportRow::detail(row(
+                &vendor.payee,
+                &vendor.monthly_cents,
+                vendor.total_cents,
+            )));
+        }
+        if self.other_cents != 0 {
+            let listed =
+                |i: usize| -> i64 { self.vendors.iter().map(|v| v.monthly_cents[i]).sum() };
+            let other: Vec<i64> = (0..self.months.len())
+                .map(|i| self.monthly_total_cents[i] - listed(i))
+                .collect();
+            table.push(ReportRow::detail(row(
+                "All other vendors",
+                &other,
+                self.other_cents,
+            )));
+        }
+        table.push(ReportRow::total(row(
+            "Total",
+            &self.monthly_total_cents,
+            self.total_cents,
+        )));
+        table
+    }
+}
+
+impl OwnersEquityReport {
+    pub fn table(&self) -> ReportTable {
+        let mut columns = vec![ReportColumn::text("")];
+        columns.extend(self.periods.iter().map(|p| ReportColumn::amount(&p.label)));
+        columns.push(ReportColumn::amount("Total"));
+        let mut table = ReportTable::new(
+            "Owner's Equity",
+            DateRange::new(self.start_date, self.end_date).to_string(),
+        )
+        .columns(columns);
+        let row = |label: &str, f: &dyn Fn(&EquityPeriod) -> i64, total: i64| {
+            let mut cells = vec![ReportCell::text(label)];
+            cells.extend(self.periods.iter().map(|p| amount(f(p))));
+            cells.push(amount(total));
+            cells
+        };
+        table.push(ReportRow::detail(row(
+            "Opening equity",
+            &|p| p.opening_cents,
+            self.opening_cents,
+        )));
+        table.push(ReportRow::detail(row(
+            "Owner contributions",
+            &|p| p.contributions_cents,
+            self.contributions_cents,
+        )));
+        // Negative, so each column adds up to its closing balance.
+        table.push(ReportRow::detail(row(
+            "Owner draws",
+            &|p| -p.draws_cents,
+            -self.draws_cents,
+        )));
+        table.push(ReportRow::detail(row(
+            "Net profit",
+            &|p| p.net_profit_cents,
+            self.net_profit_cents,
+        )));
+        if self.periods.iter().any(|p| p.personal_cents != 0) {
+            table.push(ReportRow::detail(row(
+                "Personal transactions",
+                &|p| p.personal_cents,
+                self.personal_cents,
+            )));
+        }
+        table.push(ReportRow::total(row(
+            "Closing equity",
+            &|p| p.closing_cents,
+            self.closing_cents,
+        )));
+        table
+    }
+}
+
+/// Lay out a Schedule C preview line by line, income then expenses.
+pub fn schedule_c_table(preview: &ScheduleCPreview) -> ReportTable {
+    let mut table =
+        ReportTable::new("Schedule C", format!("Tax year {}", preview.year)).columns(vec![
+            ReportColumn::text("Line"),
+            ReportColumn::amount("Book"),
+            ReportColumn::amount("Deductible"),
+        ]);
+    // A line can be booked and yet deduct nothing, so take both sides' lines.
+    let all: BTreeSet<ScheduleCLine> = preview
+        .lines
+        .keys()
+        .chain(preview.book_lines.keys())
+        .copied()
+        .collect();
+    let amount = |amounts: &BTreeMap<ScheduleCLine, Money>, line| {
+        ReportCell::Amount(amounts.get(line).copied().unwrap_or_else(Money::zero))
+    };
+    let lines = |income: bool| {
+        all.iter()
+            .filter(move |line| line.is_income() == income)
+            .map(|line| {
+                ReportRow::detail(vec![
+                    ReportCell::text(line.label()),
+                    amount(&preview.book_lines, line),
+                    amount(&preview.lines, line),
+                ])
+            })
+    };
+    table.push(ReportRow::heading("Income"));
+    for row in lines(true) {
+        table.push(row);
+    }
+    table.push(ReportRow::subtotal(vec![
+        ReportCell::text("Gross income"),
+        ReportCell::Amount(preview.gross_income),
+        ReportCell::Amount(preview.gross_income),
+    ]));
+    table.push(ReportRow::heading("Expenses"));
+    for row in lines(false) {
+        table.push(row);
+    }
+    table.push(ReportRow::subtotal(vec![
+        ReportCell::text("Total expenses"),
+        ReportCell::Amount(preview.book_total_expenses),
+        ReportCell::Amount(preview.total_expenses),
+    ]));
+    table.push(ReportRow::total(vec![
+        ReportCell::text("Net profit"),
+        ReportCell::Amount(preview.gross_income - preview.book_total_expenses),
+        ReportCell::Amount(preview.net_profit),
+    ]));
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    /// Debit `debit` and credit `credit` for `cents` on `day`.
+    async fn post(
+        pool: &DbPool,
+        day: &str,
+        description: &str,
+        debit: &str,
+        credit: &str,
+        cents: i64,
+    ) -> i64 {
+        let id = sqlx::query(
+            "INSERT INTO transactions (date, description, balanced_total_cents) VALUES (?, ?, ?)",
+        )
+        .bind(day)
+        .bind(description)
+        .bind(cents)
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+        for (code, debit_cents, credit_cents) in [(debit, cents, 0), (credit, 0, cents)] {
+            sqlx::query(
+                r#"INSERT INTO transaction_lines (transaction_id, account_id, debit_cents, credit_cents)
+                   VALUES (?, (SELECT id FROM accounts WHERE code = ?), ?, ?)"#,
+            )
+            .bind(id)
+            .bind(code)
+            .bind(debit_cents)
+            .bind(credit_cents)
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+        id
+    }
+
+    async fn sample_books(pool: &DbPool) {
+        post(
+            pool,
+            "2025-12-31",
+            "Owner investment",
+            "1000",
+            "3000",
+            100_000,
+        )
+        .await;
+        post(
+            pool,
+            "2026-01-10",
+            "Client payment",
+            "1000",
+            "4000",
+            250_000,
+        )
+        .await;
+        post(pool, "2026-01-15", "Laptop", "5040", "2000", 120_000).await;
+        post(pool, "2026-02-01", "Team lunch", "5020", "1000", 8_000).await;
+        let personal = post(pool, "2026-02-02", "Groceries", "5020", "1000", 5_000).await;
+        sqlx::query("UPDATE transactions SET is_personal = 1 WHERE id = ?")
+            .bind(personal)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn profit_loss_and_balance_sheet() {
+        let pool = test_pool().await;
+        sample_books(&pool).await;
+
+        let period = DateRange::new(date("2026-01-01"), date("2026-03-31"));
+        let pl = profit_loss(&pool, period).await.unwrap();
+        assert_eq!(pl.total_income_cents, 250_000);
+        assert_eq!(pl.total_expenses_cents, 128_000);
+        assert_eq!(pl.net_income_cents, 122_000);
+        assert_eq!(pl.income[0].account_code, "4000");
+
+        let csv = pl.table().to_csv();
+        assert!(csv.starts_with("Account,Amount\nIncome,\n4000 "));
+        assert!(csv.ends_with("Net income,1220.00\n"));
+
+        let bs = balance_sheet(&pool, date("2026-03-31")).await.unwrap();
+        assert_eq!(bs.total_assets_cents, 100_000 + 250_000 - 8_000 - 5_000);
+        assert_eq!(bs.total_liabilities_cents, 120_000);
+        // Personal spending still left the bank, so it counts here.
+        assert_eq!(bs.net_income_cents, 250_000 - 120_000 - 13_000);
+        assert_eq!(
+            bs.total_assets_cents,
+            bs.total_liabilities_cents + bs.total_equity_cents
+        );
+
+        let before = balance_sheet(&pool, date("2025-12-31")).await.unwrap();
+        assert_eq!(before.total_assets_cents, 100_000);
+        assert_eq!(before.equity[0].amount_cents, 100_000);
+    }
+
+    #[test]
+    fn report_interval_splits_on_calendar_boundaries() {
+        let range = DateRange::new(date("2026-02-15"), date("2026-08-10"));
+        let quarters = ReportInterval::Quarter.split(range);
+        let labels: Vec<&str> = quarters.iter().map(|(l, _)| l.as_str()).collect();
+        assert_eq!(labels, ["2026 Q1", "2026 Q2", "2026 Q3"]);
+        assert_eq!(
+            quarters[0].1,
+            DateRange::new(date("2026-02-15"), date("2026-03-31"))
+        );
+        assert_eq!(
+            quarters[2].1,
+            DateRange::new(date("2026-07-01"), date("2026-08-10"))
+        );
+        assert_eq!(ReportInterval::Month.split(range).len(), 7);
+        assert_eq!(ReportInterval::Year.split(range).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn owners_equity_rolls_forward_to_the_balance_sheet() {
+        let pool = test_pool().await;
+        sample_books(&pool).await;
+        post(&pool, "2026-02-20", "Owner draw", "3100", "1000", 30_000).await;
+        post(&pool, "2026-03-05", "Added capital", "1000", "3000", 20_000).await;
+
+        let period = DateRange::new(date("2026-01-01"), date("2026-03-31"));
+        let report = owners_equity(&pool, period, ReportInterval::Month)
+            .await
+            .unwrap();
+        assert_eq!(report.opening_cents, 100_000);
+        assert_eq!(report.periods.len(), 3);
+        assert_eq!(report.periods[0].net_profit_cents, 130_000);
+        assert_eq!(report.periods[1].draws_cents, 30_000);
+        assert_eq!(report.periods[1].personal_cents, -5_000);
+        assert_eq!(report.periods[2].contributions_cents, 20_000);
+        assert_eq!(
+            report.periods[1].opening_cents,
+            report.periods[0].closing_cents
+        );
+        assert_eq!(report.net_profit_cents, 122_000);
+        assert_eq!(
+            report.closing_cents,
+            100_000 + 20_000 - 30_000 + 122_000 - 5_000
+        );
+
+        let bs = balance_sheet(&pool, period.end).await.unwrap();
+        assert_eq!(report.closing_cents, bs.total_equity_cents);
+
+        let csv = report.table().to_csv();
+        assert!(csv.starts_with(",2026-01,2026-02,2026-03,Total\nOpening equity,1000.00,"));
+        assert!(csv.contains("Owner draws,0.00,-300.00,0.00,-300.00\n"));
+        assert!(csv.ends_with("Closing equity,2300.00,1870.00,2070.00,2070.00\n"));
+    }
+
+    #[tokio::test]
+    async fn register_runs_a_balance_from_the_opening() {
+        let pool = test_pool().await;
+        sample_books(&pool).await;
+
+        let period = DateRange::new(date("2026-01-01"), date("2026-01-31"));
+        let register = account_register(&pool, "1000", period)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(register.opening_balance_cents, 100_000);
+        assert_eq!(register.entries.len(), 1);
+        assert_eq!(register.closing_balance_cents, 350_000);
+
+        let card = account_register(&pool, "2000", period)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(card.entries[0].credit_cents, 120_000);
+        assert_eq!(card.closing_balance_cents, 120_000);
+
+        assert!(account_register(&pool, "9999", period)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn period_transactions_with_opening_balances() {
+        let pool = test_pool().await;
+        sample_books(&pool).await;
+
+        let period = DateRange::new(date("2026-01-01"), date("2026-01-31"));
+        let txs = period_transactions(&pool, period).await.unwrap();
+        assert_eq!(txs.len(), 2);
+        assert_eq!(txs[0].description, "Client payment");
+        assert_eq!(txs[1].lines.len(), 2);
+        assert_eq!(txs[1].lines[0].debit, Money::from_cents(120_000));
+
+        let opening = opening_balances(&pool, period.start).await.unwrap();
+        assert_eq!(opening.balances.len(), 2);
+        assert_eq!(opening.balances[0].1, Money::from_cents(100_000));
+        assert_eq!(opening.balances[1].1, Money::from_cents(-100_000));
+
+        let journal = aequi_core::export::export_ledger(
+            &crate::db::get_all_accounts(&pool).await.unwrap(),
+            Some(&opening),
+            &txs,
+        );
+        assert!(journal.contains("    Assets:Checking  $1000.00\n"));
+        assert!(journal.contains("    Equity:Owner's Equity  $-1000.00\n"));
+        assert!(journal.contains("    Liabilities:Credit Card  $-1200.00\n"));
+    }
+
+    #[tokio::test]
+    async fn reconciliation_compares_statement_to_ledger() {
+        let pool = test_pool().await;
+        sample_books(&pool).await;
+        let checking = crate::db::get_account_by_code(&pool, "1000")
+            .await
+            .unwrap()
+            .unwrap()
+            .id
+            .unwrap()
+            .0;
+        let session = crate::db::create_reconciliation_session(
+            &pool,
+            checking,
+            "2026-01-01",
+            "2026-01-31",
+            345_000,
+        )
+        .await
+        .unwrap();
+        crate::db::add_reconciliation_item(&pool, session, None, None, "missing", -5_000)
+            .await
+            .unwrap();
+        crate::balance_assertions::record_balance_assertion(
+            &pool,
+            checking,
+            date("2026-01-31"),
+            345_000,
+            crate::balance_assertions::SOURCE_OFX,
+        )
+        .await
+        .unwrap();
+
+        let report = reconciliation(&pool, session).await.unwrap().unwrap();
+        assert_eq!(report.ledger_balance_cents, 350_000);
+        assert_eq!(report.difference_cents, -5_000);
+        assert_eq!(report.items.len(), 1);
+        assert!(!report.items[0].is_resolved);
+        assert!(report.table().to_csv().contains(",Difference,,-50.00,\n"));
+        assert_eq!(report.assertions.len(), 1);
+        assert_eq!(report.assertions[0].drift_cents, 5_000);
+
+        assert!(reconciliation(&pool, session + 1).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn vendor_spending_groups_payees_by_month() {
+        let pool = test_pool().await;
+        let adobe = sqlx::query_scalar::<_, i64>(
+            "INSERT INTO contacts (name) VALUES ('Adobe') RETURNING id",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        crate::db::set_contact_aliases(&pool, adobe, &["adobe *creative".into()])
+            .await
+            .unwrap();
+        post(
+            &pool,
+            "2026-01-05",
+            "ADOBE *CREATIVE CLD",
+            "5110",
+            "2000",
+            5_499,
+        )
+        .await;
+        post(&pool, "2026-02-05", "Adobe", "5110", "2000", 5_999).await;
+        post(&pool, "2026-02-09", "Staples", "5100", "1000", 2_000).await;
+        post(&pool, "2026-02-20", "staples ", "5100", "1000", 1_000).await;
+        post(&pool, "2026-02-21", "STAPLES", "5100", "1000", 500).await;
+        post(&pool, "2026-01-10", "Corner Cafe", "5020", "1000", 1_200).await;
+        // Not an expense.
+        post(&pool, "2026-01-15", "Client A", "1000", "4000", 90_000).await;
+
+        let period = DateRange::new(date("2026-01-01"), date("2026-02-28"));
+        let report = vendor_spending(&pool, period, 2).await.unwrap();
+        assert_eq!(report.months, ["2026-01", "2026-02"]);
+        let adobe_row = &report.vendors[0];
+        assert_eq!(adobe_row.payee, "Adobe");
+        assert_eq!(adobe_row.contact_id, Some(adobe));
+        assert_eq!(adobe_row.monthly_cents, [5_499, 5_999]);
+        assert_eq!(adobe_row.last_month_change_cents, 500);
+        let staples = &report.vendors[1];
+        assert_eq!(staples.transaction_count, 3);
+        assert_eq!(staples.total_cents, 3_500);
+        assert_eq!(report.other_cents, 1_200);
+        assert_eq!(report.monthly_total_cents, [6_699, 9_499]);
+        assert_eq!(report.total_cents, 16_198);
+        assert!(report
+            .table()
+            .to_csv()
+            .contains("All other vendors,12.00,0.00,12.00\n"));
+    }
+
+    #[test]
+    fn schedule_c_table_shows_book_and_deductible() {
+        let line = |tag| ScheduleCLine::from_tag(tag).unwrap();
+        let cents = Money::from_cents;
+        let preview = ScheduleCPreview {
+            year: 2026,
+            gross_income: cents(100_000),
+            total_expenses: cents(3_000),
+            net_profit: cents(97_000),
+            lines: BTreeMap::from([
+                (line("line_1"), cents(100_000)),
+                (line("line_24b"), cents(3_000)),
+            ]),
+            book_total_expenses: cents(7_000),
+            book_lines: BTreeMap::from([
+                (line("line_1"), cents(100_000)),
+                (line("line_18"), cents(1_000)),
+                (line("line_24b"), cents(6_000)),
+            ]),
+        };
+        let csv = schedule_c_table(&preview).to_csv();
+        assert!(csv.contains("Line 18 — Office expense,10.00,0.00\n"));
+        assert!(csv.contains("Line 24b — Deductible meals,60.00,30.00\n"));
+        assert!(csv.contains("Total expenses,70.00,30.00\n"));
+        assert!(csv.contains("Net profit,930.00,970.00\n"));
+    }
+}
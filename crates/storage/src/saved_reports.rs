@@ -0,0 +1,766 @@
+//! Report definitions saved by name, for reports the built-in set doesn't
+//! cover.
+//!
+//! A definition picks the accounts to report on, a period worked out from
+//! the day it is run, how rows are grouped, whether the period is broken
+//! into months, quarters or years, a period to compare against, and the
+//! tags a transaction must carry to count. Definitions are stored as JSON.
+//! Running one gives a [`SavedReportOutput`], which like the built-in
+//! reports is serialized as-is for JSON and laid out by `table()` for CSV
+//! and PDF.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use aequi_core::export::{ReportCell, ReportColumn, ReportRow, ReportTable};
+use aequi_core::{AccountType, DateRange, Money};
+use chrono::{Datelike, Days, Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::db::{get_account_by_code, normalize_tag, DbPool};
+use crate::reports::ReportInterval;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SavedReportError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Invalid(String),
+}
+
+/// The period a report covers, relative to the day it is run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReportPeriod {
+    MonthToDate,
+    QuarterToDate,
+    YearToDate,
+    LastMonth,
+    LastQuarter,
+    LastYear,
+    Custom { start: NaiveDate, end: NaiveDate },
+}
+
+fn quarter_start(date: NaiveDate) -> NaiveDate {
+    NaiveDate::from_ymd_opt(date.year(), date.month0() / 3 * 3 + 1, 1).unwrap_or(date)
+}
+
+impl ReportPeriod {
+    /// The calendar dates covered when run on `today`.
+    pub fn resolve(self, today: NaiveDate) -> DateRange {
+        let month = today.with_day(1).unwrap_or(today);
+        let quarter = quarter_start(today);
+        let year = today.with_ordinal(1).unwrap_or(today);
+        match self {
+            ReportPeriod::MonthToDate => DateRange::new(month, today),
+            ReportPeriod::QuarterToDate => DateRange::new(quarter, today),
+            ReportPeriod::YearToDate => DateRange::new(year, today),
+            ReportPeriod::LastMonth => DateRange::new(month - Months::new(1), month - Days::new(1)),
+            ReportPeriod::LastQuarter => {
+                DateRange::new(quarter - Months::new(3), quarter - Days::new(1))
+            }
+            ReportPeriod::LastYear => DateRange::new(year - Months::new(12), year - Days::new(1)),
+            ReportPeriod::Custom { start, end } => DateRange::new(start, end),
+        }
+    }
+}
+
+/// A second period each row is compared against, with the change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportComparison {
+    /// The period just before. One starting on the first of a month is
+    /// moved back by whole months, so this month to date compares with
+    /// the same days last month.
+    PreviousPeriod,
+    /// The same dates a year earlier.
+    PriorYear,
+}
+
+impl ReportComparison {
+    pub fn label(self) -> &'static str {
+        match self {
+            ReportComparison::PreviousPeriod => "Previous period",
+            ReportComparison::PriorYear => "Prior year",
+        }
+    }
+
+    pub fn range(self, period: DateRange) -> DateRange {
+        match self {
+            ReportComparison::PriorYear => {
+                DateRange::new(period.start - Months::new(12), period.end - Months::new(12))
+            }
+            ReportComparison::PreviousPeriod if period.start.day() == 1 => {
+                let months = (period.end.year() - period.start.year()) * 12
+                    + period.end.month() as i32
+                    - period.start.month() as i32
+                    + 1;
+                let back = Months::new(months as u32);
+                DateRange::new(
+                    period.start - back,
+                    (period.end - back).min(period.start - Days::new(1)),
+                )
+            }
+            ReportComparison::PreviousPeriod => {
+                let days = Days::new((period.end - period.start).num_days() as u64 + 1);
+                DateRange::new(period.start - days, period.end - days)
+            }
+        }
+    }
+}
+
+/// What each row of a report adds up.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportGrouping {
+    #[default]
+    Account,
+    AccountType,
+    /// One row per tag. A transaction with several tags counts in each of
+    /// their rows, but once in the totals.
+    Tag,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReportDefinition {
+    /// Accounts by code. When empty, every account of `account_types`.
+    #[serde(default)]
+    pub account_codes: Vec<String>,
+    /// Income and expense when empty.
+    #[serde(default)]
+    pub account_types: Vec<AccountType>,
+    #[serde(default)]
+    pub group_by: ReportGrouping,
+    pub period: ReportPeriod,
+    /// Columns for each month, quarter or year of the period, then a total.
+    #[serde(default)]
+    pub interval: Option<ReportInterval>,
+    #[serde(default)]
+    pub compare: Option<ReportComparison>,
+    /// Only transactions with at least one of these tags, such as a client
+    /// or project. Every transaction when empty.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub include_personal: bool,
+}
+
+impl ReportDefinition {
+    fn account_types(&self) -> Vec<AccountType> {
+        if self.account_types.is_empty() {
+            vec![AccountType::Income, AccountType::Expense]
+        } else {
+            self.account_types.clone()
+        }
+    }
+
+    fn tags(&self) -> BTreeSet<String> {
+        self.tags
+            .iter()
+            .map(|t| normalize_tag(t))
+            .filter(|t| !t.is_empty())
+            .collect()
+    }
+
+    async fn validate(&self, pool: &DbPool) -> Result<(), SavedReportError> {
+        if let ReportPeriod::Custom { start, end } = self.period {
+            if start > end {
+                return Err(SavedReportError::Invalid(
+                    "Start date is after end date".to_string(),
+                ));
+            }
+        }
+        for code in &self.account_codes {
+            if get_account_by_code(pool, code).await?.is_none() {
+                return Err(SavedReportError::NotFound(format!(
+                    "Account {code} not found"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SavedReport {
+    pub id: i64,
+    pub name: String,
+    pub definition: ReportDefinition,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+type SavedReportRow = (i64, String, String, String, String);
+
+fn from_row(
+    (id, name, definition, created_at, updated_at): SavedReportRow,
+) -> Result<SavedReport, sqlx::Error> {
+    Ok(SavedReport {
+        id,
+        name,
+        definition: serde_json::from_str(&definition)
+            .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+        created_at,
+        updated_at,
+    })
+}
+
+const REPORT_SELECT: &str =
+    "SELECT id, name, definition, created_at, updated_at FROM saved_reports";
+
+/// Saved reports by name.
+pub async fn get_saved_reports(pool: &DbPool) -> Result<Vec<SavedReport>, sqlx::Error> {
+    sqlx::query_as::<_, SavedReportRow>(&format!("{REPORT_SELECT} ORDER BY name"))
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(from_row)
+        .collect()
+}
+
+pub async fn get_saved_report(pool: &DbPool, id: i64) -> Result<Option<SavedReport>, sqlx::Error> {
+    sqlx::query_as::<_, SavedReportRow>(&format!("{REPORT_SELECT} WHERE id = ?"))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .map(from_row)
+        .transpose()
+}
+
+/// Save a definition under `name`, replacing report `id` when given.
+/// Returns the report's id.
+pub async fn save_saved_report(
+    pool: &DbPool,
+    id: Option<i64>,
+    name: &str,
+    definition: &ReportDefinition,
+) -> Result<i64, SavedReportError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(SavedReportError::Invalid(
+            "Report name is required".to_string(),
+        ));
+    }
+    definition.validate(pool).await?;
+    let taken: Option<i64> = sqlx::query_scalar("SELECT id FROM saved_reports WHERE name = ?")
+        .bind(name)
+        .fetch_optional(pool)
+        .await?;
+    if taken.is_some_and(|other| Some(other) != id) {
+        return Err(SavedReportError::Invalid(format!(
+            "A report named {name} already exists"
+        )));
+    }
+    let definition = ReportDefinition {
+        tags: definition.tags().into_iter().collect(),
+        ..definition.clone()
+    };
+    let json = serde_json::to_string(&definition).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+
+    match id {
+        Some(id) => {
+            let result = sqlx::query(
+                "UPDATE saved_reports SET name = ?, definition = ?, updated_at = datetime('now') WHERE id = ?",
+            )
+            .bind(name)
+            .bind(json)
+            .bind(id)
+            .execute(pool)
+            .await?;
+            if result.rows_affected() == 0 {
+                return Err(SavedReportError::NotFound(format!(
+                    "Saved report {id} not found"
+                )));
+            }
+            Ok(id)
+        }
+        None => Ok(sqlx::query_scalar(
+            "INSERT INTO saved_reports (name, definition) VALUES (?, ?) RETURNING id",
+        )
+        .bind(name)
+        .bind(json)
+        .fetch_one(pool)
+        .await?),
+    }
+}
+
+pub async fn delete_saved_report(pool: &DbPool, id: i64) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM saved_reports WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SavedReportLine {
+    pub label: String,
+    /// One amount per column, positive in the accounts' normal direction.
+    pub amounts_cents: Vec<i64>,
+}
+
+/// The rows for one type of account.
+#[derive(Debug, Clone, Serialize)]
+pub struct SavedReportSection {
+    pub account_type: AccountType,
+    /// Empty when grouped by account type.
+    pub rows: Vec<SavedReportLine>,
+    pub totals_cents: Vec<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SavedReportOutput {
+    pub name: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub group_by: ReportGrouping,
+    pub tags: Vec<String>,
+    /// Labels of the amount columns.
+    pub columns: Vec<String>,
+    pub sections: Vec<SavedReportSection>,
+    /// Income less expenses per column, when both are in the report.
+    pub net_income_cents: Option<Vec<i64>>,
+}
+
+impl SavedReportOutput {
+    pub fn table(&self) -> ReportTable {
+        let mut subtitle = format!("{} to {}", self.start_date, self.end_date);
+        if !self.tags.is_empty() {
+            subtitle.push_str(&format!(", tagged {}", self.tags.join(", ")));
+        }
+        let first = match self.group_by {
+            ReportGrouping::Account => "Account",
+            ReportGrouping::AccountType => "Account type",
+            ReportGrouping::Tag => "Tag",
+        };
+        let mut columns = vec![ReportColumn::text(first)];
+        columns.extend(self.columns.iter().map(ReportColumn::amount));
+        let mut table = ReportTable::new(&self.name, subtitle).columns(columns);
+        let cells = |label: String, amounts: &[i64]| {
+            let mut cells = vec![ReportCell::text(label)];
+            cells.extend(
+                amounts
+                    .iter()
+                    .map(|&cents| ReportCell::Amount(Money::from_cents(cents))),
+            );
+            cells
+        };
+        for section in &self.sections {
+            if self.group_by == ReportGrouping::AccountType {
+                table.push(ReportRow::detail(cells(
+                    section.account_type.to_string(),
+                    &section.totals_cents,
+                )));
+                continue;
+            }
+            table.push(ReportRow::heading(section.account_type.to_string()));
+            for row in &section.rows {
+                table.push(ReportRow::detail(cells(
+                    row.label.clone(),
+                    &row.amounts_cents,
+                )));
+            }
+            table.push(ReportRow::subtotal(cells(
+                format!("Total {}", section.account_type),
+                &section.totals_cents,
+            )));
+        }
+        if let Some(net) = &self.net_income_cents {
+            table.push(ReportRow::total(cells("Net income".to_string(), net)));
+        }
+        table
+    }
+}
+
+const TYPE_ORDER: [AccountType; 5] = [
+    AccountType::Asset,
+    AccountType::Liability,
+    AccountType::Equity,
+    AccountType::Income,
+    AccountType::Expense,
+];
+
+/// Debit-normal accounts report net debits, the rest net credits.
+fn normal_sign(account_type: AccountType) -> i64 {
+    match account_type {
+        AccountType::Asset | AccountType::Expense => 1,
+        _ => -1,
+    }
+}
+
+const UNTAGGED: &str = "Untagged";
+
+/// A section's rows, keyed for sorting with their label alongside, and its
+/// totals.
+type SectionSums = (BTreeMap<String, (String, Vec<i64>)>, Vec<i64>);
+
+/// Run a saved report as of `today`.
+pub async fn run_saved_report(
+    pool: &DbPool,
+    id: i64,
+    today: NaiveDate,
+) -> Result<SavedReportOutput, SavedReportError> {
+    let report = get_saved_report(pool, id)
+        .await?
+        .ok_or_else(|| SavedReportError::NotFound(format!("Saved report {id} not found")))?;
+    run_report_definition(pool, &report.name, &report.definition, today).await
+}
+
+/// Run a definition as of `today` without saving it, to preview it while
+/// it is being built.
+pub async fn run_report_definition(
+    pool: &DbPool,
+    name: &str,
+    definition: &ReportDefinition,
+    today: NaiveDate,
+) -> Result<SavedReportOutput, SavedReportError> {
+    definition.validate(pool).await?;
+    let period = definition.period.resolve(today);
+    let parts = match definition.interval {
+        Some(interval) => interval.split(period),
+        None => vec![("Amount".to_string(), period)],
+    };
+    let compare = definition.compare.map(|c| (c, c.range(period)));
+
+    // Column layout: each part, a total when there are several, then the
+    // comparison and the change.
+    let total_col = (parts.len() > 1).then_some(parts.len());
+    let compare_col = compare.map(|_| parts.len() + total_col.map_or(0, |_| 1));
+    let mut columns: Vec<String> = parts.iter().map(|(label, _)| label.clone()).collect();
+    if total_col.is_some() {
+        columns.push("Total".to_string());
+    }
+    if let Some((comparison, _)) = compare {
+        columns.push(comparison.label().to_string());
+        columns.push("Change".to_string());
+    }
+
+    let query_start = compare.map_or(period.start, |(_, r)| r.start.min(period.start));
+    let query_end = compare.map_or(period.end, |(_, r)| r.end.max(period.end));
+    let lines = sqlx::query_as::<_, (i64, NaiveDate, String, String, String, i64)>(
+        r#"SELECT t.id, t.date, a.code, a.name, a.account_type,
+                  tl.debit_cents - tl.credit_cents
+           FROM transaction_lines tl
+           JOIN transactions t ON t.id = tl.transaction_id
+           JOIN accounts a ON a.id = tl.account_id
+           WHERE t.date >= ? AND t.date <= ? AND (? OR t.is_personal = 0)"#,
+    )
+    .bind(query_start.to_string())
+    .bind(query_end.to_string())
+    .bind(definition.include_personal)
+    .fetch_all(pool)
+    .await?;
+
+    let mut tags_of: HashMap<i64, Vec<String>> = HashMap::new();
+    for (transaction_id, tag) in sqlx::query_as::<_, (i64, String)>(
+        r#"SELECT tt.transaction_id, tt.tag FROM transaction_tags tt
+           JOIN transactions t ON t.id = tt.transaction_id
+           WHERE t.date >= ? AND t.date <= ?"#,
+    )
+    .bind(query_start.to_string())
+    .bind(query_end.to_string())
+    .fetch_all(pool)
+    .await?
+    {
+        tags_of.entry(transaction_id).or_default().push(tag);
+    }
+
+    let wanted_tags = definition.tags();
+    let codes: HashSet<&str> = definition
+        .account_codes
+        .iter()
+        .map(String::as_str)
+        .collect();
+    let types = definition.account_types();
+    let width = columns.len();
+    let mut sections: BTreeMap<usize, SectionSums> = BTreeMap::new();
+
+    for (transaction_id, date, code, account_name, account_type, net) in lines {
+        let Some(type_index) = TYPE_ORDER
+            .iter()
+            .position(|t| t.to_string() == account_type)
+        else {
+            continue;
+        };
+        let kind = TYPE_ORDER[type_index];
+        let selected = if codes.is_empty() {
+            types.contains(&kind)
+        } else {
+            codes.contains(code.as_str())
+        };
+        if !selected {
+            continue;
+        }
+        let tags = tags_of
+            .get(&transaction_id)
+            .map(Vec::as_slice)
+            .unwrap_or(&[]);
+        if !wanted_tags.is_empty() && !tags.iter().any(|t| wanted_tags.contains(t)) {
+            continue;
+        }
+
+        let amount = net * normal_sign(kind);
+        let mut hits = Vec::new();
+        if let Some(i) = parts.iter().position(|(_, r)| r.contains(date)) {
+            hits.push(i);
+            hits.extend(total_col);
+        }
+        if let (Some(col), Some((_, range))) = (compare_col, compare) {
+            if range.contains(date) {
+                hits.push(col);
+            }
+        }
+        if hits.is_empty() {
+            continue;
+        }
+
+        let keys: Vec<(String, String)> = match definition.group_by {
+            ReportGrouping::Account => vec![(code.clone(), format!("{code} {account_name}"))],
+            ReportGrouping::AccountType => Vec::new(),
+            ReportGrouping::Tag => {
+                let mut keys: Vec<(String, String)> = tags
+                    .iter()
+                    .filter(|t| wanted_tags.is_empty() || wanted_tags.contains(*t))
+                    .map(|t| (t.clone(), t.clone()))
+                    .collect();
+                if keys.is_empty() {
+                    // Sorts after any tag.
+                    keys.push(("\u{10FFFF}".to_string(), UNTAGGED.to_string()));
+                }
+                keys
+            }
+        };
+        let (rows, totals) = sections
+            .entry(type_index)
+            .or_insert_with(|| (BTreeMap::new(), vec![0; width]));
+        for &col in &hits {
+            totals[col] += amount;
+        }
+        for (key, label) in keys {
+            let (_, amounts) = rows.entry(key).or_insert_with(|| (label, vec![0; width]));
+            for &col in &hits {
+                amounts[col] += amount;
+            }
+        }
+    }
+
+    // The change is the current total less the comparison.
+    let current_col = total_col.unwrap_or(0);
+    let with_change = |mut amounts: Vec<i64>| {
+        if let Some(col) = compare_col {
+            amounts[col + 1] = amounts[current_col] - amounts[col];
+        }
+        amounts
+    };
+    let sections: Vec<SavedReportSection> = sections
+        .into_iter()
+        .map(|(type_index, (rows, totals))| SavedReportSection {
+            account_type: TYPE_ORDER[type_index],
+            rows: rows
+                .into_values()
+                .filter(|(_, amounts)| amounts.iter().any(|&c| c != 0))
+                .map(|(label, amounts)| SavedReportLine {
+                    label,
+                    amounts_cents: with_change(amounts),
+                })
+                .collect(),
+            totals_cents: with_change(totals),
+        })
+        .collect();
+
+    let totals_for = |kind: AccountType| {
+        sections
+            .iter()
+            .find(|s| s.account_type == kind)
+            .map(|s| s.totals_cents.clone())
+    };
+    let net_income_cents = match (
+        totals_for(AccountType::Income),
+        totals_for(AccountType::Expense),
+    ) {
+        (Some(income), Some(expenses)) => {
+            Some(income.iter().zip(&expenses).map(|(i, e)| i - e).collect())
+        }
+        _ => None,
+    };
+
+    Ok(SavedReportOutput {
+        name: name.to_string(),
+        start_date: period.start,
+        end_date: period.end,
+        group_by: definition.group_by,
+        tags: wanted_tags.into_iter().collect(),
+        columns,
+        sections,
+        net_income_cents,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::set_transaction_tags;
+    use crate::db::test_pool;
+
+    async fn post(pool: &DbPool, day: &str, debit: &str, credit: &str, cents: i64) -> i64 {
+        let id = sqlx::query(
+            "INSERT INTO transactions (date, description, balanced_total_cents) VALUES (?, 'Test', ?)",
+        )
+        .bind(day)
+        .bind(cents)
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+        for (code, debit_cents, credit_cents) in [(debit, cents, 0), (credit, 0, cents)] {
+            sqlx::query(
+                r#"INSERT INTO transaction_lines (transaction_id, account_id, debit_cents, credit_cents)
+                   VALUES (?, (SELECT id FROM accounts WHERE code = ?), ?, ?)"#,
+            )
+            .bind(id)
+            .bind(code)
+            .bind(debit_cents)
+            .bind(credit_cents)
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+        id
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn periods_resolve_from_today() {
+        let today = date("2026-05-17");
+        let range = |p: ReportPeriod| {
+            let r = p.resolve(today);
+            (r.start.to_string(), r.end.to_string())
+        };
+        assert_eq!(range(ReportPeriod::QuarterToDate).0, "2026-04-01");
+        assert_eq!(
+            range(ReportPeriod::LastQuarter),
+            ("2026-01-01".into(), "2026-03-31".into())
+        );
+        assert_eq!(
+            range(ReportPeriod::LastMonth),
+            ("2026-04-01".into(), "2026-04-30".into())
+        );
+        assert_eq!(
+            range(ReportPeriod::LastYear),
+            ("2025-01-01".into(), "2025-12-31".into())
+        );
+
+        let mtd = ReportPeriod::MonthToDate.resolve(today);
+        let previous = ReportComparison::PreviousPeriod.range(mtd);
+        assert_eq!(
+            previous,
+            DateRange::new(date("2026-04-01"), date("2026-04-17"))
+        );
+        let odd = DateRange::new(date("2026-05-10"), date("2026-05-19"));
+        assert_eq!(
+            ReportComparison::PreviousPeriod.range(odd),
+            DateRange::new(date("2026-04-30"), date("2026-05-09"))
+        );
+        assert_eq!(
+            ReportComparison::PriorYear.range(mtd),
+            DateRange::new(date("2025-05-01"), date("2025-05-17"))
+        );
+    }
+
+    #[tokio::test]
+    async fn saved_report_runs_by_month_with_a_comparison() {
+        let pool = test_pool().await;
+        let a = post(&pool, "2026-01-10", "1000", "4000", 100_000).await;
+        let b = post(&pool, "2026-02-10", "1000", "4000", 50_000).await;
+        post(&pool, "2026-02-12", "1000", "4000", 7_000).await;
+        let c = post(&pool, "2026-02-15", "5100", "1000", 2_000).await;
+        let d = post(&pool, "2025-01-20", "1000", "4000", 80_000).await;
+        set_transaction_tags(&pool, a, &["Acme".into()])
+            .await
+            .unwrap();
+        set_transaction_tags(&pool, b, &["acme ".into(), "website".into()])
+            .await
+            .unwrap();
+        set_transaction_tags(&pool, c, &["website".into()])
+            .await
+            .unwrap();
+        set_transaction_tags(&pool, d, &["acme".into()])
+            .await
+            .unwrap();
+
+        let definition = ReportDefinition {
+            account_codes: Vec::new(),
+            account_types: Vec::new(),
+            group_by: ReportGrouping::Account,
+            period: ReportPeriod::Custom {
+                start: date("2026-01-01"),
+                end: date("2026-02-28"),
+            },
+            interval: Some(ReportInterval::Month),
+            compare: Some(ReportComparison::PriorYear),
+            tags: vec!["ACME".into()],
+            include_personal: false,
+        };
+        let id = save_saved_report(&pool, None, "Acme by month", &definition)
+            .await
+            .unwrap();
+        assert!(matches!(
+            save_saved_report(&pool, None, "Acme by month", &definition).await,
+            Err(SavedReportError::Invalid(_))
+        ));
+        assert_eq!(
+            get_saved_reports(&pool).await.unwrap()[0].definition.tags,
+            vec!["acme".to_string()]
+        );
+
+        let report = run_saved_report(&pool, id, date("2026-10-17"))
+            .await
+            .unwrap();
+        assert_eq!(
+            report.columns,
+            ["2026-01", "2026-02", "Total", "Prior year", "Change"]
+        );
+        // Only the Acme income; the untagged sale and the website expense
+        // are left out.
+        assert_eq!(report.sections.len(), 1);
+        let income = &report.sections[0];
+        assert_eq!(income.account_type, AccountType::Income);
+        assert_eq!(income.rows[0].label, "4000 Services Revenue");
+        assert_eq!(
+            income.totals_cents,
+            [100_000, 50_000, 150_000, 80_000, 70_000]
+        );
+        assert!(report.net_income_cents.is_none());
+
+        let by_tag = ReportDefinition {
+            account_types: vec![AccountType::Income, AccountType::Expense],
+            group_by: ReportGrouping::Tag,
+            interval: None,
+            compare: None,
+            tags: Vec::new(),
+            ..definition
+        };
+        let report = run_report_definition(&pool, "By tag", &by_tag, date("2026-10-17"))
+            .await
+            .unwrap();
+        let income = &report.sections[0];
+        let labels: Vec<&str> = income.rows.iter().map(|r| r.label.as_str()).collect();
+        assert_eq!(labels, ["acme", "website", "Untagged"]);
+        // The sale tagged both counts in each row but once in the total.
+        assert_eq!(income.totals_cents, [157_000]);
+        assert_eq!(report.net_income_cents, Some(vec![155_000]));
+        let csv = report.table().to_csv();
+        assert!(csv.contains("Untagged,70.00\n"), "{csv}");
+        assert!(csv.ends_with("Net income,1550.00\n"), "{csv}");
+
+        assert!(delete_saved_report(&pool, id).await.unwrap());
+        assert!(matches!(
+            run_saved_report(&pool, id, date("2026-10-17")).await,
+            Err(SavedReportError::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,788 @@
+//! Device-to-device sync of the ledger.
+//!
+//! Triggers record every change to accounts, transactions and transaction
+//! lines in `sync_log`. [`export_changes`] turns the log since the last
+//! exchange with a device into a [`ChangeSet`] carrying the current state of
+//! each changed entity; [`apply_changes`] merges a change set from another
+//! device. No server is involved: change sets are plain JSON that can travel
+//! over a shared folder, a USB stick or anything else.
+//!
+//! Accounts are matched across devices by code and transactions by their
+//! `sync_uid`. When both devices changed the same entity since they last
+//! synced, the later change wins (ties go to the higher device id), and the
+//! overwritten side is reported as a conflict. This assumes the devices'
+//! clocks are roughly right.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, Sqlite, Transaction};
+
+use crate::db::DbPool;
+
+/// Version of the change set format written by [`export_changes`].
+pub const SYNC_FORMAT_VERSION: u32 = 1;
+
+/// Setting holding this device's sync id.
+pub const DEVICE_ID_SETTING: &str = "sync_device_id";
+
+/// Changes made on one device, ready to be merged on another.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeSet {
+    pub format_version: u32,
+    pub device_id: String,
+    pub created_at: String,
+    /// Last `sync_log` entry covered, in the sender's numbering.
+    pub up_to_seq: i64,
+    /// How far the sender had merged the receiver's own changes, in the
+    /// receiver's numbering. Receiver changes after this are concurrent.
+    pub acknowledged_seq: i64,
+    pub changes: Vec<Change>,
+}
+
+/// The state of one entity after its latest change.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "entity", rename_all = "snake_case")]
+pub enum Change {
+    Account {
+        code: String,
+        changed_at: String,
+        account: SyncAccount,
+    },
+    Transaction {
+        uid: String,
+        changed_at: String,
+        /// `None` when the transaction was deleted.
+        transaction: Option<SyncTransaction>,
+    },
+}
+
+impl Change {
+    fn entity(&self) -> &'static str {
+        match self {
+            Change::Account { .. } => "account",
+            Change::Transaction { .. } => "transaction",
+        }
+    }
+
+    fn key(&self) -> &str {
+        match self {
+            Change::Account { code, .. } => code,
+            Change::Transaction { uid, .. } => uid,
+        }
+    }
+
+    fn changed_at(&self) -> &str {
+        match self {
+            Change::Account { changed_at, .. } | Change::Transaction { changed_at, .. } => {
+                changed_at
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncAccount {
+    pub name: String,
+    pub account_type: String,
+    pub is_archetype: bool,
+    pub is_archived: bool,
+    pub schedule_c_line: Option<String>,
+    /// Absent from peers that predate it; they treat every account as
+    /// fully deductible.
+    #[serde(default = "fully_deductible")]
+    pub deductible_pct: u8,
+}
+
+fn fully_deductible() -> u8 {
+    100
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncTransaction {
+    pub date: String,
+    pub description: String,
+    pub memo: Option<String>,
+    pub is_personal: bool,
+    pub deductible_percent: i64,
+    pub lines: Vec<SyncLine>,
+}
+
+/// A transaction line, with its account given by code.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncLine {
+    pub account_code: String,
+    pub debit_cents: i64,
+    pub credit_cents: i64,
+    pub memo: Option<String>,
+}
+
+/// Which side's version was kept when both devices changed an entity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictResolution {
+    KeptLocal,
+    KeptRemote,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncConflict {
+    pub entity: String,
+    pub key: String,
+    pub resolution: ConflictResolution,
+}
+
+/// What merging a change set did.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncReport {
+    pub device_id: String,
+    pub applied: usize,
+    /// Changes that matched what this device already had.
+    pub unchanged: usize,
+    pub conflicts: Vec<SyncConflict>,
+}
+
+/// Another device this one has exchanged changes with.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct SyncPeer {
+    pub device_id: String,
+    pub last_sent_seq: i64,
+    pub last_received_seq: i64,
+    pub last_synced_at: Option<String>,
+}
+
+/// This device's sync id, created on first use.
+pub async fn get_device_id(pool: &DbPool) -> Result<String, sqlx::Error> {
+    if let Some(id) = crate::db::get_setting(pool, DEVICE_ID_SETTING).await? {
+        return Ok(id);
+    }
+    let (id,): (String,) = sqlx::query_as("SELECT lower(hex(randomblob(16)))")
+        .fetch_one(pool)
+        .await?;
+    sqlx::query("INSERT OR IGNORE INTO settings (key, value) VALUES (?, ?)")
+        .bind(DEVICE_ID_SETTING)
+        .bind(&id)
+        .execute(pool)
+        .await?;
+    // Another caller may have got there first.
+    Ok(crate::db::get_setting(pool, DEVICE_ID_SETTING)
+        .await?
+        .unwrap_or(id))
+}
+
+pub async fn get_sync_peers(pool: &DbPool) -> Result<Vec<SyncPeer>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT device_id, last_sent_seq, last_received_seq, last_synced_at FROM sync_peers ORDER BY device_id",
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Build the change set for `peer`: everything changed since the last
+/// export to it, leaving out changes that came from it. With no peer, or a
+/// device not synced with before, every entity ever changed is included.
+pub async fn export_changes(pool: &DbPool, peer: Option<&str>) -> Result<ChangeSet, SyncError> {
+    let device_id = get_device_id(pool).await?;
+    if peer == Some(device_id.as_str()) {
+        return Err(SyncError::Invalid(
+            "cannot sync a device with itself".into(),
+        ));
+    }
+    let mut tx = pool.begin().await?;
+    let known = match peer {
+        Some(peer) => {
+            sqlx::query_as::<_, (i64, i64)>(
+                "SELECT last_sent_seq, last_received_seq FROM sync_peers WHERE device_id = ?",
+            )
+            .bind(peer)
+            .fetch_optional(&mut *tx)
+            .await?
+        }
+        None => None,
+    };
+    let (since, acknowledged_seq) = known.unwrap_or((0, 0));
+    let (up_to_seq,): (i64,) = sqlx::query_as("SELECT COALESCE(MAX(seq), 0) FROM sync_log")
+        .fetch_one(&mut *tx)
+        .await?;
+
+    // The latest entry for each entity, unless that came from the peer
+    // itself, in which case the peer already has this state.
+    let latest: Vec<(String, String, String)> = sqlx::query_as(
+        r#"SELECT l.entity, l.entity_key, l.changed_at
+           FROM sync_log l
+           JOIN (SELECT entity, entity_key, MAX(seq) AS seq FROM sync_log
+                 WHERE seq > ? AND seq <= ? GROUP BY entity, entity_key) m
+             ON m.seq = l.seq
+           WHERE l.origin IS NULL OR l.origin != ?
+           ORDER BY l.seq"#,
+    )
+    .bind(since)
+    .bind(up_to_seq)
+    .bind(peer.unwrap_or(""))
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut changes = Vec::with_capacity(latest.len());
+    for (entity, key, changed_at) in latest {
+        match entity.as_str() {
+            "account" => {
+                // Accounts are never deleted, only archived.
+                if let Some(account) = load_account(&mut tx, &key).await? {
+                    changes.push(Change::Account {
+                        code: key,
+                        changed_at,
+                        account,
+                    });
+                }
+            }
+            "transaction" => {
+                let transaction = load_transaction(&mut tx, &key).await?;
+                changes.push(Change::Transaction {
+                    uid: key,
+                    changed_at,
+                    transaction,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(peer) = peer {
+        sqlx::query(
+            r#"INSERT INTO sync_peers (device_id, last_sent_seq) VALUES (?, ?)
+               ON CONFLICT(device_id) DO UPDATE SET last_sent_seq = excluded.last_sent_seq"#,
+        )
+        .bind(peer)
+        .bind(up_to_seq)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+
+    Ok(ChangeSet {
+        format_version: SYNC_FORMAT_VERSION,
+        device_id,
+        created_at: chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        up_to_seq,
+        acknowledged_seq,
+        changes,
+    })
+}
+
+/// Merge a change set from another device. Everything is applied in one
+/// database transaction, so a change set that fails leaves nothing behind.
+pub async fn apply_changes(pool: &DbPool, set: &ChangeSet) -> Result<SyncReport, SyncError> {
+    if set.format_version > SYNC_FORMAT_VERSION {
+        return Err(SyncError::Invalid(format!(
+            "change set format {} is newer than this version of aequi supports",
+            set.format_version
+        )));
+    }
+    let device_id = get_device_id(pool).await?;
+    if set.device_id == device_id {
+        return Err(SyncError::Invalid(
+            "this change set was exported from this device".into(),
+        ));
+    }
+
+    let mut report = SyncReport {
+        device_id: set.device_id.clone(),
+        ..SyncReport::default()
+    };
+    let mut tx = pool.begin().await?;
+
+    // Accounts first so transaction lines can find them.
+    let mut changes: Vec<&Change> = set.changes.iter().collect();
+    changes.sort_by_key(|c| matches!(c, Change::Transaction { .. }));
+
+    for change in changes {
+        let same = match change {
+            Change::Account { code, account, .. } => {
+                load_account(&mut tx, code).await?.as_ref() == Some(account)
+            }
+            Change::Transaction {
+                uid, transaction, ..
+            } => load_transaction(&mut tx, uid).await? == *transaction,
+        };
+        if same {
+            report.unchanged += 1;
+            continue;
+        }
+
+        // A local change the sender hadn't seen when it exported is
+        // concurrent with this one; otherwise this one is simply newer.
+        let local: Option<(i64, String, Option<String>)> = sqlx::query_as(
+            r#"SELECT seq, changed_at, origin FROM sync_log
+               WHERE entity = ? AND entity_key = ? ORDER BY seq DESC LIMIT 1"#,
+        )
+        .bind(change.entity())
+        .bind(change.key())
+        .fetch_optional(&mut *tx)
+        .await?;
+        if let Some((seq, changed_at, origin)) = local {
+            let concurrent =
+                seq > set.acknowledged_seq && origin.as_deref() != Some(set.device_id.as_str());
+            if concurrent {
+                let local_wins = (changed_at.as_str(), device_id.as_str())
+                    > (change.changed_at(), set.device_id.as_str());
+                report.conflicts.push(SyncConflict {
+                    entity: change.entity().to_string(),
+                    key: change.key().to_string(),
+                    resolution: if local_wins {
+                        ConflictResolution::KeptLocal
+                    } else {
+                        ConflictResolution::KeptRemote
+                    },
+                });
+                if local_wins {
+                    continue;
+                }
+            }
+        }
+
+        let (before,): (i64,) = sqlx::query_as("SELECT COALESCE(MAX(seq), 0) FROM sync_log")
+            .fetch_one(&mut *tx)
+            .await?;
+        match change {
+            Change::Account { code, account, .. } => store_account(&mut tx, code, account).await?,
+            Change::Transaction {
+                uid,
+                transaction: Some(t),
+                ..
+            } => store_transaction(&mut tx, uid, t).await?,
+            Change::Transaction {
+                uid,
+                transaction: None,
+                ..
+            } => delete_transaction(&mut tx, uid).await?,
+        }
+        // Tag what the triggers just logged with where it came from, so it
+        // isn't sent back and keeps the original change time.
+        sqlx::query("UPDATE sync_log SET origin = ?, changed_at = ? WHERE seq > ?")
+            .bind(&set.device_id)
+            .bind(change.changed_at())
+            .bind(before)
+            .execute(&mut *tx)
+            .await?;
+        report.applied += 1;
+    }
+
+    sqlx::query(
+        r#"INSERT INTO sync_peers (device_id, last_received_seq, last_synced_at)
+           VALUES (?, ?, datetime('now'))
+           ON CONFLICT(device_id) DO UPDATE SET
+               last_received_seq = MAX(last_received_seq, excluded.last_received_seq),
+               last_synced_at = excluded.last_synced_at"#,
+    )
+    .bind(&set.device_id)
+    .bind(set.up_to_seq)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(report)
+}
+
+async fn load_account(
+    tx: &mut Transaction<'_, Sqlite>,
+    code: &str,
+) -> Result<Option<SyncAccount>, sqlx::Error> {
+    let row = sqlx::query(
+        "SELECT name, account_type, is_archetype, is_archived, schedule_c_line, deductible_pct FROM accounts WHERE code = ?",
+    )
+    .bind(code)
+    .fetch_optional(&mut **tx)
+    .await?;
+    Ok(row.map(|r| SyncAccount {
+        name: r.get("name"),
+        account_type: r.get("account_type"),
+        is_archetype: r.get("is_archetype"),
+        is_archived: r.get("is_archived"),
+        schedule_c_line: r.get("schedule_c_line"),
+        deductible_pct: r.get("deductible_pct"),
+    }))
+}
+
+async fn load_transaction(
+    tx: &mut Transaction<'_, Sqlite>,
+    uid: &str,
+) -> Result<Option<SyncTransaction>, sqlx::Error> {
+    let Some(row) = sqlx::query(
+        "SELECT id, date, description, memo, is_personal, deductible_percent FROM transactions WHERE sync_uid = ?",
+    )
+    .bind(uid)
+    .fetch_optional(&mut **tx)
+    .await?
+    else {
+        return Ok(None);
+    };
+    let id: i64 = row.get("id");
+    let lines = sqlx::query_as::<_, (String, i64, i64, Option<String>)>(
+        r#"SELECT a.code, l.debit_cents, l.credit_cents, l.memo
+           FROM transaction_lines l JOIN accounts a ON a.id = l.account_id
+           WHERE l.transaction_id = ? ORDER BY l.id"#,
+    )
+    .bind(id)
+    .fetch_all(&mut **tx)
+    .await?
+    .into_iter()
+    .map(|(account_code, debit_cents, credit_cents, memo)| SyncLine {
+        account_code,
+        debit_cents,
+        credit_cents,
+        memo,
+    })
+    .collect();
+    Ok(Some(SyncTransaction {
+        date: row.get("date"),
+        description: row.get("description"),
+        memo: row.get("memo"),
+        is_personal: row.get("is_personal"),
+        deductible_percent: row.get("deductible_percent"),
+        lines,
+    }))
+}
+
+async fn store_account(
+    tx: &mut Transaction<'_, Sqlite>,
+    code: &str,
+    account: &SyncAccount,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"INSERT INTO accounts (code, name, account_type, is_archetype, is_archived, schedule_c_line, deductible_pct)
+           VALUES (?, ?, ?, ?, ?, ?, ?)
+           ON CONFLICT(code) DO UPDATE SET
+               name = excluded.name,
+               account_type = excluded.account_type,
+               is_archetype = excluded.is_archetype,
+               is_archived = excluded.is_archived,
+               schedule_c_line = excluded.schedule_c_line,
+               deductible_pct = excluded.deductible_pct"#,
+    )
+    .bind(code)
+    .bind(&account.name)
+    .bind(&account.account_type)
+    .bind(account.is_archetype)
+    .bind(account.is_archived)
+    .bind(&account.schedule_c_line)
+    .bind(account.deductible_pct.min(100))
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+async fn store_transaction(
+    tx: &mut Transaction<'_, Sqlite>,
+    uid: &str,
+    t: &SyncTransaction,
+) -> Result<(), SyncError> {
+    let mut account_ids = HashMap::new();
+    for line in &t.lines {
+        if account_ids.contains_key(&line.account_code) {
+            continue;
+        }
+        let id: Option<(i64,)> = sqlx::query_as("SELECT id FROM accounts WHERE code = ?")
+            .bind(&line.account_code)
+            .fetch_optional(&mut **tx)
+            .await?;
+        let (id,) = id.ok_or_else(|| {
+            SyncError::Invalid(format!(
+                "transaction {uid} uses unknown account {}",
+                line.account_code
+            ))
+        })?;
+        account_ids.insert(line.account_code.clone(), id);
+    }
+    let total: i64 = t.lines.iter().map(|l| l.debit_cents).sum();
+
+    let existing: Option<(i64,)> = sqlx::query_as("SELECT id FROM transactions WHERE sync_uid = ?")
+        .bind(uid)
+        .fetch_optional(&mut **tx)
+        .await?;
+    let id = match existing {
+        Some((id,)) => {
+            sqlx::query(
+                r#"UPDATE transactions SET date = ?, description = ?, memo = ?,
+                       balanced_total_cents = ?, is_personal = ?, deductible_percent = ?
+                   WHERE id = ?"#,
+            )
+            .bind(&t.date)
+            .bind(&t.description)
+            .bind(&t.memo)
+            .bind(total)
+            .bind(t.is_personal)
+            .bind(t.deductible_percent)
+            .bind(id)
+            .execute(&mut **tx)
+            .await?;
+            sqlx::query("DELETE FROM transaction_lines WHERE transaction_id = ?")
+                .bind(id)
+                .execute(&mut **tx)
+                .await?;
+            id
+        }
+        None => {
+            sqlx::query(
+                r#"INSERT INTO transactions
+                       (date, description, memo, balanced_total_cents, is_personal, deductible_percent, sync_uid)
+                   VALUES (?, ?, ?, ?, ?, ?, ?)"#,
+            )
+            .bind(&t.date)
+            .bind(&t.description)
+            .bind(&t.memo)
+            .bind(total)
+            .bind(t.is_personal)
+            .bind(t.deductible_percent)
+            .bind(uid)
+            .execute(&mut **tx)
+            .await?
+            .last_insert_rowid()
+        }
+    };
+    for line in &t.lines {
+        sqlx::query(
+            "INSERT INTO transaction_lines (transaction_id, account_id, debit_cents, credit_cents, memo) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(id)
+        .bind(account_ids[&line.account_code])
+        .bind(line.debit_cents)
+        .bind(line.credit_cents)
+        .bind(&line.memo)
+        .execute(&mut **tx)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Delete a transaction removed on another device. Receipts, bank rows,
+/// payments and reconciliation items linked to it are unlinked, not deleted.
+async fn delete_transaction(
+    tx: &mut Transaction<'_, Sqlite>,
+    uid: &str,
+) -> Result<(), sqlx::Error> {
+    let Some((id,)) = sqlx::query_as::<_, (i64,)>("SELECT id FROM transactions WHERE sync_uid = ?")
+        .bind(uid)
+        .fetch_optional(&mut **tx)
+        .await?
+    else {
+        return Ok(());
+    };
+    for sql in [
+        "UPDATE receipts SET transaction_id = NULL WHERE transaction_id = ?",
+        r#"UPDATE imported_transactions SET matched_transaction_id = NULL,
+               status = CASE WHEN account_id IS NOT NULL OR category_rule_id IS NOT NULL
+                             THEN 'categorized' ELSE 'pending' END
+           WHERE matched_transaction_id = ?"#,
+        "UPDATE payments SET transaction_id = NULL WHERE transaction_id = ?",
+        "UPDATE reconciliation_items SET transaction_id = NULL WHERE transaction_id = ?",
+        "DELETE FROM transaction_lines WHERE transaction_id = ?",
+        "DELETE FROM transactions WHERE id = ?",
+    ] {
+        sqlx::query(sql).bind(id).execute(&mut **tx).await?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SyncError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Invalid change set: {0}")]
+    Invalid(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    /// Book `cents` from checking (1000) to office supplies (5110).
+    async fn insert_expense(pool: &DbPool, description: &str, cents: i64) -> String {
+        let id = sqlx::query(
+            "INSERT INTO transactions (date, description, balanced_total_cents) VALUES ('2026-03-01', ?, ?)",
+        )
+        .bind(description)
+        .bind(cents)
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+        for (code, debit, credit) in [("5110", cents, 0), ("1000", 0, cents)] {
+            sqlx::query(
+                r#"INSERT INTO transaction_lines (transaction_id, account_id, debit_cents, credit_cents)
+                   VALUES (?, (SELECT id FROM accounts WHERE code = ?), ?, ?)"#,
+            )
+            .bind(id)
+            .bind(code)
+            .bind(debit)
+            .bind(credit)
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+        let (uid,): (String,) = sqlx::query_as("SELECT sync_uid FROM transactions WHERE id = ?")
+            .bind(id)
+            .fetch_one(pool)
+            .await
+            .unwrap();
+        uid
+    }
+
+    async fn description(pool: &DbPool, uid: &str) -> Option<String> {
+        sqlx::query_as::<_, (String,)>("SELECT description FROM transactions WHERE sync_uid = ?")
+            .bind(uid)
+            .fetch_optional(pool)
+            .await
+            .unwrap()
+            .map(|r| r.0)
+    }
+
+    /// Send `from`'s changes to `to`, as a device-to-device exchange would.
+    async fn sync(from: &DbPool, to: &DbPool) -> SyncReport {
+        let to_id = get_device_id(to).await.unwrap();
+        let set = export_changes(from, Some(&to_id)).await.unwrap();
+        apply_changes(to, &set).await.unwrap()
+    }
+
+    fn transaction_changes(set: &ChangeSet) -> usize {
+        set.changes
+            .iter()
+            .filter(|c| matches!(c, Change::Transaction { .. }))
+            .count()
+    }
+
+    #[tokio::test]
+    async fn device_id_is_stable() {
+        let pool = test_pool().await;
+        let id = get_device_id(&pool).await.unwrap();
+        assert_eq!(id.len(), 32);
+        assert_eq!(get_device_id(&pool).await.unwrap(), id);
+    }
+
+    #[tokio::test]
+    async fn first_export_includes_data_from_before_sync() {
+        let pool = test_pool().await;
+        insert_expense(&pool, "Paper", 1250).await;
+        // Take the database back to before sync existed, then forward again.
+        while crate::migrate::current_version(&pool).await.unwrap() >= 15 {
+            crate::migrate::rollback_last(&pool).await.unwrap();
+        }
+        crate::migrate::run_migrations(&pool).await.unwrap();
+
+        let set = export_changes(&pool, None).await.unwrap();
+        assert_eq!(transaction_changes(&set), 1);
+        let accounts = set
+            .changes
+            .iter()
+            .filter(|c| matches!(c, Change::Account { .. }))
+            .count();
+        assert_eq!(accounts, aequi_core::DEFAULT_ACCOUNTS.len());
+    }
+
+    #[tokio::test]
+    async fn changes_round_trip_without_echo() {
+        let laptop = test_pool().await;
+        let desktop = test_pool().await;
+        let uid = insert_expense(&laptop, "Paper", 1250).await;
+
+        let report = sync(&laptop, &desktop).await;
+        assert_eq!(report.applied, 1, "only the transaction is new");
+        assert!(report.conflicts.is_empty());
+        assert_eq!(description(&desktop, &uid).await.as_deref(), Some("Paper"));
+        let lines: Vec<(String, i64, i64)> = sqlx::query_as(
+            r#"SELECT a.code, l.debit_cents, l.credit_cents FROM transaction_lines l
+               JOIN accounts a ON a.id = l.account_id
+               JOIN transactions t ON t.id = l.transaction_id
+               WHERE t.sync_uid = ? ORDER BY l.id"#,
+        )
+        .bind(&uid)
+        .fetch_all(&desktop)
+        .await
+        .unwrap();
+        assert_eq!(
+            lines,
+            vec![("5110".into(), 1250, 0), ("1000".into(), 0, 1250)]
+        );
+
+        // The desktop doesn't send the laptop's transaction back, and the
+        // laptop has nothing new to send.
+        let laptop_id = get_device_id(&laptop).await.unwrap();
+        let back = export_changes(&desktop, Some(&laptop_id)).await.unwrap();
+        assert_eq!(transaction_changes(&back), 0);
+        let desktop_id = get_device_id(&desktop).await.unwrap();
+        let again = export_changes(&laptop, Some(&desktop_id)).await.unwrap();
+        assert!(again.changes.is_empty());
+
+        let set = export_changes(&laptop, None).await.unwrap();
+        assert!(
+            apply_changes(&laptop, &set).await.is_err(),
+            "own change set"
+        );
+    }
+
+    #[tokio::test]
+    async fn edits_and_deletes_merge_last_writer_wins() {
+        let laptop = test_pool().await;
+        let desktop = test_pool().await;
+        let uid = insert_expense(&laptop, "Paper", 1250).await;
+        sync(&laptop, &desktop).await;
+
+        // A later edit on the desktop is not a conflict.
+        sqlx::query("UPDATE transactions SET description = 'Printer paper' WHERE sync_uid = ?")
+            .bind(&uid)
+            .execute(&desktop)
+            .await
+            .unwrap();
+        let report = sync(&desktop, &laptop).await;
+        assert!(report.conflicts.is_empty());
+        assert_eq!(
+            description(&laptop, &uid).await.as_deref(),
+            Some("Printer paper")
+        );
+        assert_eq!(sync(&laptop, &desktop).await.applied, 0);
+
+        // Both change it before syncing: the later change wins on both.
+        sqlx::query("UPDATE transactions SET description = 'Toner' WHERE sync_uid = ?")
+            .bind(&uid)
+            .execute(&laptop)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM transactions WHERE sync_uid = ?")
+            .bind(&uid)
+            .execute(&desktop)
+            .await
+            .unwrap();
+        for (pool, at) in [
+            (&laptop, "2026-03-02 09:00:00.000"),
+            (&desktop, "2026-03-02 10:00:00.000"),
+        ] {
+            sqlx::query(
+                "UPDATE sync_log SET changed_at = ? WHERE origin IS NULL AND entity_key = ?",
+            )
+            .bind(at)
+            .bind(&uid)
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+        let laptop_id = get_device_id(&laptop).await.unwrap();
+        let desktop_id = get_device_id(&desktop).await.unwrap();
+        let from_laptop = export_changes(&laptop, Some(&desktop_id)).await.unwrap();
+        let from_desktop = export_changes(&desktop, Some(&laptop_id)).await.unwrap();
+
+        let report = apply_changes(&desktop, &from_laptop).await.unwrap();
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(
+            report.conflicts[0].resolution,
+            ConflictResolution::KeptLocal
+        );
+        assert_eq!(description(&desktop, &uid).await, None);
+
+        let report = apply_changes(&laptop, &from_desktop).await.unwrap();
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(
+            report.conflicts[0].resolution,
+            ConflictResolution::KeptRemote
+        );
+        assert_eq!(description(&laptop, &uid).await, None);
+    }
+}
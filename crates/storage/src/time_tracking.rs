@@ -0,0 +1,460 @@
+//! Time tracking: hours worked for a client, entered by hand or with a
+//! start/stop timer, and billed by rolling the unbilled entries into a draft
+//! invoice.
+//!
+//! Only one timer runs at a time. [`invoice_unbilled_time`] adds one invoice
+//! line per project and hourly rate, and marks the entries it billed so they
+//! aren't billed twice.
+
+use std::collections::BTreeMap;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::db::DbPool;
+
+/// Format of `started_at` and `ended_at`, UTC, as SQLite's `datetime()`.
+pub const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Invoice line description for time not logged against a project.
+const NO_PROJECT: &str = "Professional services";
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct TimeEntry {
+    pub id: i64,
+    pub contact_id: i64,
+    pub project: Option<String>,
+    pub description: String,
+    /// `YYYY-MM-DD`, the day the work was done.
+    pub date: String,
+    pub minutes: i64,
+    pub started_at: Option<String>,
+    /// `None` with `started_at` set while the timer runs.
+    pub ended_at: Option<String>,
+    pub hourly_rate_cents: i64,
+    pub billable: bool,
+    /// The invoice the time was billed on.
+    pub invoice_id: Option<i64>,
+    pub created_at: String,
+}
+
+impl TimeEntry {
+    pub fn is_running(&self) -> bool {
+        self.started_at.is_some() && self.ended_at.is_none()
+    }
+}
+
+/// What was worked on, for whom and at what rate.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TimeEntryInput {
+    pub contact_id: i64,
+    pub project: Option<String>,
+    #[serde(default)]
+    pub description: String,
+    pub hourly_rate_cents: i64,
+    #[serde(default = "billable_by_default")]
+    pub billable: bool,
+}
+
+fn billable_by_default() -> bool {
+    true
+}
+
+impl TimeEntryInput {
+    fn project(&self) -> Option<&str> {
+        self.project
+            .as_deref()
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+    }
+}
+
+/// Record time worked. Returns the entry's id.
+pub async fn insert_time_entry(
+    pool: &DbPool,
+    input: &TimeEntryInput,
+    date: NaiveDate,
+    minutes: i64,
+) -> Result<i64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"INSERT INTO time_entries (contact_id, project, description, date, minutes,
+           hourly_rate_cents, billable) VALUES (?, ?, ?, ?, ?, ?, ?)"#,
+    )
+    .bind(input.contact_id)
+    .bind(input.project())
+    .bind(input.description.trim())
+    .bind(date.to_string())
+    .bind(minutes)
+    .bind(input.hourly_rate_cents)
+    .bind(input.billable)
+    .execute(pool)
+    .await?;
+    Ok(result.last_insert_rowid())
+}
+
+/// Change an entry that hasn't been billed and isn't running. Returns
+/// whether it was changed.
+pub async fn update_time_entry(
+    pool: &DbPool,
+    id: i64,
+    input: &TimeEntryInput,
+    date: NaiveDate,
+    minutes: i64,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"UPDATE time_entries
+           SET contact_id = ?, project = ?, description = ?, date = ?, minutes = ?,
+               hourly_rate_cents = ?, billable = ?
+           WHERE id = ? AND invoice_id IS NULL
+             AND NOT (started_at IS NOT NULL AND ended_at IS NULL)"#,
+    )
+    .bind(input.contact_id)
+    .bind(input.project())
+    .bind(input.description.trim())
+    .bind(date.to_string())
+    .bind(minutes)
+    .bind(input.hourly_rate_cents)
+    .bind(input.billable)
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Delete an entry that hasn't been billed. Returns whether it was deleted.
+pub async fn delete_time_entry(pool: &DbPool, id: i64) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM time_entries WHERE id = ? AND invoice_id IS NULL")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn get_time_entry(pool: &DbPool, id: i64) -> Result<Option<TimeEntry>, sqlx::Error> {
+    sqlx::query_as::<_, TimeEntry>("SELECT * FROM time_entries WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Entries newest first, for one client if given, and only those not yet
+/// billed if `unbilled_only`.
+pub async fn get_time_entries(
+    pool: &DbPool,
+    contact_id: Option<i64>,
+    unbilled_only: bool,
+) -> Result<Vec<TimeEntry>, sqlx::Error> {
+    sqlx::query_as::<_, TimeEntry>(
+        r#"SELECT * FROM time_entries
+           WHERE (?1 IS NULL OR contact_id = ?1)
+             AND (?2 = 0 OR invoice_id IS NULL)
+           ORDER BY date DESC, id DESC"#,
+    )
+    .bind(contact_id)
+    .bind(unbilled_only)
+    .fetch_all(pool)
+    .await
+}
+
+/// The running timer, if any.
+pub async fn running_timer(pool: &DbPool) -> Result<Option<TimeEntry>, sqlx::Error> {
+    sqlx::query_as::<_, TimeEntry>(
+        "SELECT * FROM time_entries WHERE started_at IS NOT NULL AND ended_at IS NULL LIMIT 1",
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Start a timer at `started_at` (UTC) for work done on `date`. Returns the
+/// new entry's id, or `None` if a timer is already running.
+pub async fn start_timer(
+    pool: &DbPool,
+    input: &TimeEntryInput,
+    date: NaiveDate,
+    started_at: NaiveDateTime,
+) -> Result<Option<i64>, sqlx::Error> {
+    let result = sqlx::query(
+        r#"INSERT INTO time_entries (contact_id, project, description, date, started_at,
+           hourly_rate_cents, billable)
+           SELECT ?, ?, ?, ?, ?, ?, ?
+           WHERE NOT EXISTS (SELECT 1 FROM time_entries
+                             WHERE started_at IS NOT NULL AND ended_at IS NULL)"#,
+    )
+    .bind(input.contact_id)
+    .bind(input.project())
+    .bind(input.description.trim())
+    .bind(date.to_string())
+    .bind(started_at.format(TIMESTAMP_FORMAT).to_string())
+    .bind(input.hourly_rate_cents)
+    .bind(input.billable)
+    .execute(pool)
+    .await?;
+    Ok((result.rows_affected() > 0).then(|| result.last_insert_rowid()))
+}
+
+/// Stop the running timer at `ended_at` (UTC), rounding the time worked up
+/// to the minute. Returns the stopped entry, or `None` if none was running.
+pub async fn stop_timer(
+    pool: &DbPool,
+    ended_at: NaiveDateTime,
+) -> Result<Option<TimeEntry>, sqlx::Error> {
+    let Some(entry) = running_timer(pool).await? else {
+        return Ok(None);
+    };
+    let started = entry
+        .started_at
+        .as_deref()
+        .and_then(|s| NaiveDateTime::parse_from_str(s, TIMESTAMP_FORMAT).ok())
+        .unwrap_or(ended_at);
+    let seconds = (ended_at - started).num_seconds().max(0);
+    let minutes = (seconds + 59) / 60;
+    sqlx::query("UPDATE time_entries SET ended_at = ?, minutes = ? WHERE id = ?")
+        .bind(ended_at.format(TIMESTAMP_FORMAT).to_string())
+        .bind(minutes)
+        .bind(entry.id)
+        .execute(pool)
+        .await?;
+    get_time_entry(pool, entry.id).await
+}
+
+/// The invoice unbilled time is rolled into.
+#[derive(Debug, Clone)]
+pub struct DraftInvoice {
+    pub invoice_number: String,
+    pub issue_date: NaiveDate,
+    pub due_date: NaiveDate,
+    pub notes: Option<String>,
+    pub terms: Option<String>,
+}
+
+/// Create a draft invoice for a client's billable time not yet invoiced,
+/// worked up to `through`: one line per project and hourly rate, in hours.
+/// Returns the invoice id, or `None` if there was no time to bill.
+pub async fn invoice_unbilled_time(
+    pool: &DbPool,
+    contact_id: i64,
+    through: NaiveDate,
+    invoice: &DraftInvoice,
+) -> Result<Option<i64>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let entries = sqlx::query_as::<_, TimeEntry>(
+        r#"SELECT * FROM time_entries
+           WHERE contact_id = ? AND invoice_id IS NULL AND billable = 1
+             AND minutes > 0 AND date <= ?
+             AND NOT (started_at IS NOT NULL AND ended_at IS NULL)
+           ORDER BY date, id"#,
+    )
+    .bind(contact_id)
+    .bind(through.to_string())
+    .fetch_all(&mut *tx)
+    .await?;
+    if entries.is_empty() {
+        return Ok(None);
+    }
+
+    // (project, rate) -> (minutes, first date, last date)
+    let mut lines: BTreeMap<(String, i64), (i64, String, String)> = BTreeMap::new();
+    for entry in &entries {
+        let project = entry.project.clone().unwrap_or_else(|| NO_PROJECT.into());
+        let line = lines
+            .entry((project, entry.hourly_rate_cents))
+            .or_insert_with(|| (0, entry.date.clone(), entry.date.clone()));
+        line.0 += entry.minutes;
+        line.2 = entry.date.clone();
+    }
+
+    let invoice_id: i64 = sqlx::query_scalar(
+        r#"INSERT INTO invoices (invoice_number, contact_id, status_type, issue_date,
+           due_date, notes, terms) VALUES (?, ?, 'Draft', ?, ?, ?, ?) RETURNING id"#,
+    )
+    .bind(&invoice.invoice_number)
+    .bind(contact_id)
+    .bind(invoice.issue_date.to_string())
+    .bind(invoice.due_date.to_string())
+    .bind(&invoice.notes)
+    .bind(&invoice.terms)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    for (i, ((project, rate), (minutes, first, last))) in lines.into_iter().enumerate() {
+        let period = if first == last {
+            first
+        } else {
+            format!("{first} to {last}")
+        };
+        sqlx::query(
+            r#"INSERT INTO invoice_lines (invoice_id, description, quantity_hundredths,
+               unit_rate_cents, taxable, sort_order) VALUES (?, ?, ?, ?, 0, ?)"#,
+        )
+        .bind(invoice_id)
+        .bind(format!("{project} ({period})"))
+        .bind((minutes * 100 + 30) / 60)
+        .bind(rate)
+        .bind(i as i64)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    for entry in &entries {
+        sqlx::query("UPDATE time_entries SET invoice_id = ? WHERE id = ?")
+            .bind(invoice_id)
+            .bind(entry.id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+    Ok(Some(invoice_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    async fn client(pool: &DbPool, name: &str) -> i64 {
+        sqlx::query_scalar("INSERT INTO contacts (name) VALUES (?) RETURNING id")
+            .bind(name)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    fn input(contact_id: i64, project: Option<&str>, rate: i64) -> TimeEntryInput {
+        TimeEntryInput {
+            contact_id,
+            project: project.map(str::to_string),
+            description: "Work".to_string(),
+            hourly_rate_cents: rate,
+            billable: true,
+        }
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, TIMESTAMP_FORMAT).unwrap()
+    }
+
+    fn draft(number: &str) -> DraftInvoice {
+        DraftInvoice {
+            invoice_number: number.to_string(),
+            issue_date: date("2026-04-01"),
+            due_date: date("2026-05-01"),
+            notes: None,
+            terms: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn one_timer_runs_at_a_time() {
+        let pool = test_pool().await;
+        let acme = client(&pool, "Acme").await;
+        let entry = input(acme, Some("Site"), 10000);
+
+        let id = start_timer(&pool, &entry, date("2026-03-02"), at("2026-03-02 09:00:00"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(
+            start_timer(&pool, &entry, date("2026-03-02"), at("2026-03-02 09:05:00"))
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(running_timer(&pool).await.unwrap().unwrap().id, id);
+
+        let stopped = stop_timer(&pool, at("2026-03-02 10:30:01"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stopped.minutes, 91);
+        assert!(!stopped.is_running());
+        assert!(stop_timer(&pool, at("2026-03-02 11:00:00"))
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn unbilled_time_becomes_invoice_lines() {
+        let pool = test_pool().await;
+        let acme = client(&pool, "Acme").await;
+        let other = client(&pool, "Other").await;
+
+        for (d, minutes) in [("2026-03-02", 90), ("2026-03-09", 45)] {
+            insert_time_entry(&pool, &input(acme, Some("Site"), 10000), date(d), minutes)
+                .await
+                .unwrap();
+        }
+        insert_time_entry(&pool, &input(acme, None, 15000), date("2026-03-10"), 20)
+            .await
+            .unwrap();
+        let mut free = input(acme, Some("Site"), 10000);
+        free.billable = false;
+        insert_time_entry(&pool, &free, date("2026-03-11"), 60)
+            .await
+            .unwrap();
+        // Worked after the cut-off, and for someone else.
+        insert_time_entry(
+            &pool,
+            &input(acme, Some("Site"), 10000),
+            date("2026-04-02"),
+            60,
+        )
+        .await
+        .unwrap();
+        insert_time_entry(
+            &pool,
+            &input(other, Some("Site"), 10000),
+            date("2026-03-02"),
+            60,
+        )
+        .await
+        .unwrap();
+
+        let invoice_id = invoice_unbilled_time(&pool, acme, date("2026-03-31"), &draft("INV-7"))
+            .await
+            .unwrap()
+            .unwrap();
+        let lines = crate::db::get_invoice_lines(&pool, invoice_id)
+            .await
+            .unwrap();
+        let got: Vec<(&str, i64, i64)> = lines
+            .iter()
+            .map(|l| {
+                (
+                    l.description.as_str(),
+                    l.quantity_hundredths,
+                    l.unit_rate_cents,
+                )
+            })
+            .collect();
+        assert_eq!(
+            got,
+            [
+                ("Professional services (2026-03-10)", 33, 15000),
+                ("Site (2026-03-02 to 2026-03-09)", 225, 10000),
+            ]
+        );
+
+        let unbilled = get_time_entries(&pool, Some(acme), true).await.unwrap();
+        assert_eq!(unbilled.len(), 2);
+        assert!(
+            invoice_unbilled_time(&pool, acme, date("2026-03-31"), &draft("INV-8"))
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        // Billed time is locked.
+        let billed = get_time_entries(&pool, Some(acme), false)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|e| e.invoice_id == Some(invoice_id))
+            .unwrap();
+        assert!(!delete_time_entry(&pool, billed.id).await.unwrap());
+    }
+}
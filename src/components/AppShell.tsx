@@ -1,6 +1,7 @@
 import { NavLink, Outlet } from "react-router-dom";
 import { useKeyboardShortcuts } from "../lib/keyboard";
 import { ShortcutsOverlay } from "./ShortcutsOverlay";
+import { DatabaseRecoveryBanner } from "./DatabaseRecoveryBanner";
 
 const links = [
   { to: "/", label: "Home", icon: "M3 12l2-2m0 0l7-7 7 7M5 10v10a1 1 0 001 1h3m10-11l2 2m-2-2v10a1 1 0 01-1 1h-3m-4 0a1 1 0 01-1-1v-4a1 1 0 011-1h2a1 1 0 011 1v4a1 1 0 01-1 1", shortcut: "0" },
@@ -58,6 +59,8 @@ export function AppShell() {
         </nav>
       </header>
 
+      <DatabaseRecoveryBanner />
+
       <main id="main-content" className="flex-1 p-4 md:p-6 max-w-6xl w-full mx-auto pb-20 md:pb-6" role="main">
         <Outlet />
       </main>
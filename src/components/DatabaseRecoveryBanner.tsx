@@ -0,0 +1,60 @@
+import { useEffect, useState } from "react";
+import { getDatabaseHealth, restoreDatabaseSnapshot, type DatabaseHealth } from "../lib/api";
+
+function describe(health: DatabaseHealth): string {
+  switch (health.status) {
+    case "corrupt":
+      return "Your ledger file is damaged and couldn't be opened.";
+    case "too_new":
+      return `Your ledger was saved by a newer version of Aequi (schema ${health.version}; this version reads up to ${health.supported}). Update Aequi to open it.`;
+    case "healthy":
+      return `Your ledger couldn't be opened: ${health.error ?? "unknown error"}`;
+  }
+}
+
+/** Shown instead of the ledger when it failed its checks at launch. */
+export function DatabaseRecoveryBanner() {
+  const [health, setHealth] = useState<DatabaseHealth | null>(null);
+  const [restoring, setRestoring] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    getDatabaseHealth().then(setHealth).catch(() => {});
+  }, []);
+
+  if (!health?.recovery_mode) return null;
+
+  const snapshot = health.latest_snapshot;
+  const restore = async () => {
+    setRestoring(true);
+    setError(null);
+    try {
+      await restoreDatabaseSnapshot();
+    } catch (e) {
+      setError(String((e as { message?: string }).message ?? e));
+      setRestoring(false);
+    }
+  };
+
+  return (
+    <div role="alert" className="bg-danger/10 border-b border-danger px-4 py-3 text-sm">
+      <p className="font-medium text-danger">{describe(health)}</p>
+      <p className="text-text-muted mt-1">
+        Nothing you enter now will be saved.
+        {snapshot
+          ? ` A copy from ${new Date(snapshot.created_at).toLocaleString()} can be restored; the damaged file is kept.`
+          : " No automatic copy was found; restore one of your backups instead."}
+      </p>
+      {snapshot && health.status !== "too_new" && (
+        <button
+          onClick={restore}
+          disabled={restoring}
+          className="mt-2 px-3 py-1.5 text-sm bg-primary text-white rounded-md hover:bg-primary-hover disabled:opacity-50"
+        >
+          {restoring ? "Restoring…" : "Restore and restart"}
+        </button>
+      )}
+      {error && <p className="text-danger mt-1">{error}</p>}
+    </div>
+  );
+}
@@ -1,9 +1,12 @@
 import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
 
 export interface Account {
   code: string;
   name: string;
   account_type: string;
+  // Share of the account's spending that is deductible, 0–100.
+  deductible_pct: number;
 }
 
 export interface TransactionLineInput {
@@ -18,21 +21,38 @@ export interface TransactionInput {
   description: string;
   lines: TransactionLineInput[];
   memo?: string;
+  is_personal?: boolean;
+  deductible_percent?: number;
 }
 
 export interface TransactionOutput {
   id: number;
   date: string;
   description: string;
+  balanced_total_cents: number;
+  // balanced_total_cents as the user's locale writes it.
   balanced_total: string;
   memo: string | null;
   created_at: string;
+  is_personal: boolean;
+  deductible_percent: number;
+  // Set on a newly posted transaction that booked at least the capitalize
+  // threshold (capitalize_threshold_cents setting, $2,500 by default) to
+  // Equipment; offer capitalizeEquipment.
+  capitalize_prompt: boolean;
+}
+
+export interface Deductibility {
+  is_personal?: boolean;
+  deductible_percent?: number;
 }
 
 export interface ProfitLossEntry {
   account_code: string;
   account_name: string;
   total_cents: number;
+  // total_cents as the user's locale writes it.
+  total: string;
 }
 
 export interface ReceiptOutput {
@@ -44,12 +64,26 @@ export interface ReceiptOutput {
   subtotal_cents: number | null;
   tax_cents: number | null;
   payment_method: string | null;
+  reference: string | null;
   confidence: number;
   status: string;
   transaction_id: number | null;
   attachment_path: string;
   needs_review: boolean;
   created_at: string;
+  quality_score: number | null;
+  quality_issues: string[];
+  retake_message: string | null;
+  payment_account_code: string | null;
+  content_type: string;
+  is_personal: boolean;
+  deductible_percent: number;
+  duplicate_of: number | null;
+  already_stored: boolean;
+  /** The photo this receipt was cut out of, when it held several. */
+  source_path: string | null;
+  /** Set by ingest when the photo held several receipts: all of them, this one first. */
+  split_into: number[];
 }
 
 export function getAccounts(): Promise<Account[]> {
@@ -69,253 +103,2283 @@ export function getTransactions(
   return invoke("get_transactions", { startDate, endDate });
 }
 
-export function getProfitLoss(
-  startDate?: string,
-  endDate?: string,
-): Promise<ProfitLossEntry[]> {
-  return invoke("get_profit_loss", { startDate, endDate });
+export function setAccountDeductiblePct(
+  accountCode: string,
+  deductiblePct: number,
+): Promise<void> {
+  return invoke("set_account_deductible_pct", { accountCode, deductiblePct });
 }
 
-export function ingestReceipt(filePath: string): Promise<ReceiptOutput> {
-  return invoke("ingest_receipt", { filePath });
+export interface DepreciationYear {
+  year: number;
+  amount_cents: number;
 }
 
-export function getPendingReceipts(): Promise<ReceiptOutput[]> {
-  return invoke("get_pending_receipts");
+export interface FixedAsset {
+  id: number;
+  transaction_id: number;
+  description: string;
+  placed_in_service: string;
+  cost_cents: number;
+  recovery_years: number;
+  account_code: string;
+  created_at: string;
+  depreciation: DepreciationYear[];
 }
 
-export function approveReceipt(
-  receiptId: number,
-  transactionId?: number,
+export function capitalizeEquipment(
+  transactionId: number,
+  input?: { description?: string; recovery_years?: number },
+): Promise<FixedAsset> {
+  return invoke("capitalize_equipment", { transactionId, input });
+}
+
+export function getFixedAssets(): Promise<FixedAsset[]> {
+  return invoke("get_fixed_assets");
+}
+
+export function setTransactionDeductibility(
+  transactionId: number,
+  deductibility: Deductibility,
 ): Promise<void> {
-  return invoke("approve_receipt", { receiptId, transactionId });
+  return invoke("set_transaction_deductibility", { transactionId, deductibility });
 }
 
-export function rejectReceipt(receiptId: number): Promise<void> {
-  return invoke("reject_receipt", { receiptId });
+export function getTransactionTags(transactionId: number): Promise<string[]> {
+  return invoke("get_transaction_tags", { transactionId });
 }
 
-// ── Tax commands ─────────────────────────────────────────────────────────────
+// Tags are stored lowercased and trimmed.
+export function setTransactionTags(transactionId: number, tags: string[]): Promise<void> {
+  return invoke("set_transaction_tags", { transactionId, tags });
+}
 
-export interface ScheduleCLineOutput {
-  line: string;
-  label: string;
-  amount_cents: number;
-  is_income: boolean;
+export function getTags(): Promise<string[]> {
+  return invoke("get_tags");
 }
 
-export interface QuarterlyEstimateOutput {
-  year: number;
-  quarter: string;
-  ytd_gross_income_cents: number;
-  ytd_total_expenses_cents: number;
-  ytd_net_profit_cents: number;
-  se_tax_cents: number;
-  se_tax_deduction_cents: number;
-  income_tax_cents: number;
-  total_tax_cents: number;
-  safe_harbor_cents: number;
-  quarterly_payment_cents: number;
-  payment_due_date: string;
-  schedule_c_lines: ScheduleCLineOutput[];
+export function getProfitLoss(
+  startDate?: string,
+  endDate?: string,
+): Promise<ProfitLossEntry[]> {
+  return invoke("get_profit_loss", { startDate, endDate });
 }
 
-export interface ScheduleCPreviewOutput {
-  year: number;
-  gross_income_cents: number;
-  total_expenses_cents: number;
+export interface VendorSpending {
+  payee: string;
+  contact_id: number | null;
+  transaction_count: number;
+  total_cents: number;
+  monthly_cents: number[];
+  last_month_change_cents: number;
+}
+
+export interface VendorSpendingReport {
+  start_date: string;
+  end_date: string;
+  months: string[];
+  vendors: VendorSpending[];
+  other_cents: number;
+  monthly_total_cents: number[];
+  total_cents: number;
+}
+
+export function getVendorSpending(
+  period: { start: string; end: string },
+  limit?: number,
+): Promise<VendorSpendingReport> {
+  return invoke("get_vendor_spending", { period, limit });
+}
+
+export type ReportInterval = "month" | "quarter" | "year";
+
+export interface EquityPeriod {
+  label: string;
+  start_date: string;
+  end_date: string;
+  opening_cents: number;
+  contributions_cents: number;
+  // What the owner took out, as a positive amount.
+  draws_cents: number;
   net_profit_cents: number;
-  lines: ScheduleCLineOutput[];
+  // Income less expenses on personal transactions; usually negative.
+  personal_cents: number;
+  closing_cents: number;
 }
 
-export function estimateQuarterlyTax(
-  year?: number,
-  quarter?: number,
-): Promise<QuarterlyEstimateOutput> {
-  return invoke("estimate_quarterly_tax", { year, quarter });
+export interface OwnersEquityReport {
+  start_date: string;
+  end_date: string;
+  interval: ReportInterval;
+  periods: EquityPeriod[];
+  opening_cents: number;
+  contributions_cents: number;
+  draws_cents: number;
+  net_profit_cents: number;
+  personal_cents: number;
+  closing_cents: number;
 }
 
-export function getScheduleCPreview(
-  year?: number,
-): Promise<ScheduleCPreviewOutput> {
-  return invoke("get_schedule_c_preview", { year });
+export function getOwnersEquity(
+  period: { start: string; end: string },
+  interval?: ReportInterval,
+): Promise<OwnersEquityReport> {
+  return invoke("get_owners_equity", { period, interval });
 }
 
-// ── Contact commands ─────────────────────────────────────────────────────────
+export type AccountType = "Asset" | "Liability" | "Equity" | "Income" | "Expense";
 
-export interface ContactRecord {
+export type ReportPeriod =
+  | { type: "month_to_date" }
+  | { type: "quarter_to_date" }
+  | { type: "year_to_date" }
+  | { type: "last_month" }
+  | { type: "last_quarter" }
+  | { type: "last_year" }
+  | { type: "custom"; start: string; end: string };
+
+export type ReportGrouping = "account" | "account_type" | "tag";
+
+export type ReportComparison = "previous_period" | "prior_year";
+
+export interface ReportDefinition {
+  // By code; every account of account_types when empty.
+  account_codes?: string[];
+  // Income and expense when empty.
+  account_types?: AccountType[];
+  group_by?: ReportGrouping;
+  period: ReportPeriod;
+  interval?: ReportInterval | null;
+  compare?: ReportComparison | null;
+  // Only transactions with at least one of these tags.
+  tags?: string[];
+  include_personal?: boolean;
+}
+
+export interface SavedReport {
   id: number;
   name: string;
-  email: string | null;
-  phone: string | null;
-  address: string | null;
-  contact_type: string;
-  is_contractor: boolean;
-  tax_id: string | null;
-  notes: string | null;
+  definition: ReportDefinition;
   created_at: string;
+  updated_at: string;
 }
 
-export interface ContactInput {
+export interface SavedReportLine {
+  label: string;
+  // One per column, positive in the accounts' normal direction.
+  amounts_cents: number[];
+}
+
+export interface SavedReportSection {
+  account_type: AccountType;
+  // Empty when grouped by account type.
+  rows: SavedReportLine[];
+  totals_cents: number[];
+}
+
+export interface SavedReportOutput {
   name: string;
-  email?: string;
-  phone?: string;
-  address?: string;
-  contact_type: string;
-  is_contractor: boolean;
-  tax_id?: string;
-  notes?: string;
+  start_date: string;
+  end_date: string;
+  group_by: ReportGrouping;
+  tags: string[];
+  columns: string[];
+  sections: SavedReportSection[];
+  // Present when both income and expenses are in the report.
+  net_income_cents: number[] | null;
 }
 
-export function getContacts(): Promise<ContactRecord[]> {
-  return invoke("get_contacts");
+export function getSavedReports(): Promise<SavedReport[]> {
+  return invoke("get_saved_reports");
 }
 
-export function createContact(input: ContactInput): Promise<ContactRecord> {
-  return invoke("create_contact", { input });
+export function saveSavedReport(
+  name: string,
+  definition: ReportDefinition,
+  id?: number,
+): Promise<SavedReport> {
+  return invoke("save_saved_report", { id, name, definition });
 }
 
-// ── Invoice commands ─────────────────────────────────────────────────────────
+export function deleteSavedReport(id: number): Promise<void> {
+  return invoke("delete_saved_report", { id });
+}
 
-export interface InvoiceRecord {
+export function runSavedReport(id: number): Promise<SavedReportOutput> {
+  return invoke("run_saved_report", { id });
+}
+
+export function previewReportDefinition(
+  definition: ReportDefinition,
+): Promise<SavedReportOutput> {
+  return invoke("preview_report_definition", { definition });
+}
+
+export function ingestReceipt(filePath: string): Promise<ReceiptOutput> {
+  return invoke("ingest_receipt", { filePath });
+}
+
+export function ingestReceiptBytes(data: Uint8Array, ext: string): Promise<ReceiptOutput> {
+  return invoke("ingest_receipt_bytes", { data: Array.from(data), ext });
+}
+
+/**
+ * Queue a photo or PDF shared to the app. Resolves to the path the
+ * `receipt:*` progress events report it under.
+ */
+export function ingestSharedFile(path: string): Promise<string> {
+  return invoke("ingest_shared_file", { path });
+}
+
+// Returns an object URL; revoke it with URL.revokeObjectURL when done.
+export async function getReceiptImageUrl(
+  receipt: ReceiptOutput,
+  thumbnail = false,
+): Promise<string> {
+  const data = await invoke<ArrayBuffer>("get_receipt_image", {
+    receiptId: receipt.id,
+    thumbnail,
+  });
+  const type = thumbnail ? "image/jpeg" : receipt.content_type;
+  return URL.createObjectURL(new Blob([data], { type }));
+}
+
+export interface UnreceiptedExpense {
+  transaction_id: number;
+  date: string;
+  description: string;
+  expense_cents: number;
+}
+
+export interface DocumentationGaps {
+  threshold_cents: number;
+  unreceipted_transactions: UnreceiptedExpense[];
+  unlinked_receipts: ReceiptOutput[];
+}
+
+// The threshold defaults to the receipt_required_threshold_cents setting.
+export function getDocumentationGaps(
+  startDate?: string,
+  endDate?: string,
+  thresholdCents?: number,
+): Promise<DocumentationGaps> {
+  return invoke("get_documentation_gaps", { startDate, endDate, thresholdCents });
+}
+
+export type ReceiptJobStatus = "queued" | "processing" | "done" | "dead";
+
+export interface ReceiptJob {
   id: number;
-  invoice_number: string;
-  contact_id: number;
-  status_type: string;
-  status_data: string | null;
-  issue_date: string;
-  due_date: string;
-  discount_type: string | null;
-  discount_value: number | null;
-  notes: string | null;
-  terms: string | null;
-  created_at: string;
+  path: string;
+  source: string;
+  status: ReceiptJobStatus;
+  attempts: number;
+  last_error: string | null;
+  available_at: string;
+  enqueued_at: string;
   updated_at: string;
 }
 
-export interface InvoiceInput {
-  invoice_number: string;
-  contact_id: number;
-  issue_date: string;
-  due_date: string;
-  notes?: string;
-  terms?: string;
+/** Intake queue jobs with `status`, or every job not yet done. */
+export function getReceiptJobs(status?: ReceiptJobStatus): Promise<ReceiptJob[]> {
+  return invoke("get_receipt_jobs", { status });
 }
 
-export interface PaymentInput {
-  invoice_id: number;
-  amount_cents: number;
-  date: string;
-  method?: string;
+export function retryReceiptJob(id: number): Promise<void> {
+  return invoke("retry_receipt_job", { id });
 }
 
-export interface NecSummaryEntry {
-  contact_id: number;
-  contact_name: string;
-  ytd_cents: number;
-  over_threshold: boolean;
+/** Where a file dropped into the receipt intake folder is sent. */
+export type IntakeRoute = "image" | "pdf" | "email" | "bank_statement" | "reject";
+
+/** Setting (JSON object of extension → IntakeRoute) overriding the default routes. */
+export const INTAKE_ROUTES_SETTING = "receipt_intake_routes";
+
+export function getPendingReceipts(): Promise<ReceiptOutput[]> {
+  return invoke("get_pending_receipts");
 }
 
-export function getInvoices(): Promise<InvoiceRecord[]> {
-  return invoke("get_invoices");
+export function getDuplicateReceipts(): Promise<ReceiptOutput[]> {
+  return invoke("get_duplicate_receipts");
 }
 
-export function createInvoice(input: InvoiceInput): Promise<InvoiceRecord> {
-  return invoke("create_invoice", { input });
+export function clearReceiptDuplicate(receiptId: number): Promise<void> {
+  return invoke("clear_receipt_duplicate", { receiptId });
 }
 
-export function getInvoiceAging(): Promise<InvoiceRecord[]> {
-  return invoke("get_invoice_aging");
+export function getReceiptsFromSamePhoto(receiptId: number): Promise<ReceiptOutput[]> {
+  return invoke("get_receipts_from_same_photo", { receiptId });
 }
 
-export function recordInvoicePayment(input: PaymentInput): Promise<number> {
-  return invoke("record_invoice_payment", { input });
+export interface ReceiptQuery {
+  status?: "pending_review" | "approved" | "rejected" | "duplicate";
+  vendor?: string;
+  date_from?: string;
+  date_to?: string;
+  min_total_cents?: number;
+  max_total_cents?: number;
+  linked?: boolean;
+  confidence?: "low" | "medium" | "high";
+  limit?: number;
+  offset?: number;
 }
 
-export function get1099Summary(year?: number): Promise<NecSummaryEntry[]> {
-  return invoke("get_1099_summary", { year });
+export interface ReceiptPage {
+  receipts: ReceiptOutput[];
+  total: number;
 }
 
-export interface SendInvoiceInput {
-  invoice_id: number;
-  subject?: string;
+export function queryReceipts(filter: ReceiptQuery): Promise<ReceiptPage> {
+  return invoke("query_receipts", { filter });
 }
 
-export interface DeliveryResult {
-  recipient: string;
-  invoice_number: string;
-  backend: string;
+export function approveReceipt(
+  receiptId: number,
+  transactionId?: number,
+): Promise<void> {
+  return invoke("approve_receipt", { receiptId, transactionId });
 }
 
-export function sendInvoice(input: SendInvoiceInput): Promise<DeliveryResult> {
-  return invoke("send_invoice", { input });
+export interface ReceiptLineItem {
+  id: number;
+  receipt_id: number;
+  description: string;
+  amount_cents: number | null;
+  quantity: number | null;
 }
 
-// ── Export commands ──────────────────────────────────────────────────────────
+export interface ReceiptSplit {
+  line_item_id: number;
+  account_code: string;
+}
 
-export function exportBeancount(): Promise<string> {
-  return invoke("export_beancount");
+export function getReceiptLineItems(
+  receiptId: number,
+): Promise<ReceiptLineItem[]> {
+  return invoke("get_receipt_line_items", { receiptId });
 }
 
-export function exportQif(): Promise<string> {
-  return invoke("export_qif");
+export function createTransactionFromReceipt(
+  receiptId: number,
+  accountCode: string,
+  options: {
+    creditAccountCode?: string;
+    splitLineItems?: boolean;
+    splits?: ReceiptSplit[];
+  } = {},
+): Promise<TransactionOutput> {
+  return invoke("create_transaction_from_receipt", {
+    receiptId,
+    accountCode,
+    ...options,
+  });
 }
 
-// ── Settings commands ───────────────────────────────────────────────────────
+export function rejectReceipt(receiptId: number): Promise<void> {
+  return invoke("reject_receipt", { receiptId });
+}
 
-export function getSetting(key: string): Promise<string | null> {
-  return invoke("get_setting", { key });
+export function unlinkReceipt(receiptId: number): Promise<void> {
+  return invoke("unlink_receipt", { receiptId });
 }
 
-export function setSetting(key: string, value: string): Promise<void> {
-  return invoke("set_setting", { key, value });
+export interface BulkReviewResult {
+  updated: number[];
+  skipped: number[];
 }
 
-// ── Audit log ───────────────────────────────────────────────────────────────
+export function bulkApproveReceipts(ids: number[]): Promise<BulkReviewResult> {
+  return invoke("bulk_approve_receipts", { ids });
+}
 
-export interface AuditLogRecord {
-  id: number;
-  timestamp: string;
-  tool_name: string;
-  input_hash: string | null;
-  outcome: string;
-  details: string | null;
+export function bulkRejectReceipts(ids: number[]): Promise<BulkReviewResult> {
+  return invoke("bulk_reject_receipts", { ids });
 }
 
-export function getAuditLog(limit?: number): Promise<AuditLogRecord[]> {
-  return invoke("get_audit_log", { limit });
+export function correctReceiptFields(
+  receiptId: number,
+  fields: { vendor?: string; receiptDate?: string; totalCents?: number },
+): Promise<ReceiptOutput> {
+  return invoke("correct_receipt_fields", { receiptId, ...fields });
 }
 
-// ── Update commands ────────────────────────────────────────────────────────
+export interface ReceiptLineItemInput {
+  description: string;
+  amount_cents?: number | null;
+  quantity?: number | null;
+}
 
-export interface UpdateStatus {
-  update_available: boolean;
-  current_version: string;
-  latest_version: string | null;
+export interface ReceiptUpdate {
+  vendor?: string;
+  receipt_date?: string;
+  total_cents?: number;
+  subtotal_cents?: number;
+  tax_cents?: number;
+  // An empty string clears the payment method or reference.
+  payment_method?: string;
+  reference?: string;
+  // Replaces all line items when given.
+  line_items?: ReceiptLineItemInput[];
+  is_personal?: boolean;
+  deductible_percent?: number;
 }
 
-export function checkForUpdates(): Promise<UpdateStatus> {
-  return invoke("check_for_updates");
+export interface UpdatedReceipt {
+  receipt: ReceiptOutput;
+  possible_duplicates: number[];
 }
 
-// ── Dashboard commands ──────────────────────────────────────────────────────
+export function updateReceipt(
+  receiptId: number,
+  fields: ReceiptUpdate,
+): Promise<UpdatedReceipt> {
+  return invoke("update_receipt", { receiptId, fields });
+}
 
-export interface DashboardSummary {
-  ytd_income_cents: number;
-  ytd_expenses_cents: number;
-  ytd_net_profit_cents: number;
-  outstanding_invoices: number;
-  overdue_invoices: number;
-  pending_receipts: number;
-  total_accounts: number;
-  total_transactions: number;
-  recent_transactions: TransactionOutput[];
-  quarterly_tax_due_cents: number | null;
-  next_tax_due_date: string | null;
+export interface FieldAccuracy {
+  field: "vendor" | "date" | "total";
+  reviewed: number;
+  correct: number;
+  current_correct: number;
 }
 
-export function getDashboardSummary(): Promise<DashboardSummary> {
-  return invoke("get_dashboard_summary");
+export function getExtractionAccuracy(): Promise<FieldAccuracy[]> {
+  return invoke("get_extraction_accuracy");
+}
+
+export type ReceiptProgressEvent =
+  | "receipt:queued"
+  | "receipt:processing"
+  | "receipt:done"
+  | "receipt:retrying"
+  | "receipt:failed";
+
+export interface ReceiptProgress {
+  path: string;
+  receipt_ids: number[];
+  error: string | null;
+}
+
+/** Follow intake-folder receipts through the pipeline. */
+export function onReceiptProgress(
+  event: ReceiptProgressEvent,
+  handler: (progress: ReceiptProgress) => void,
+): Promise<UnlistenFn> {
+  return listen<ReceiptProgress>(event, (e) => handler(e.payload));
+}
+
+export interface ReprocessFilter {
+  status?: "pending_review" | "approved" | "duplicate";
+  since?: string;
+  max_confidence?: number;
+  limit?: number;
+}
+
+export interface ReprocessSummary {
+  processed: number;
+  updated: number;
+  queued: number;
+  unchanged: number;
+  failed: number;
+}
+
+export function reprocessReceipts(
+  filter?: ReprocessFilter,
+): Promise<ReprocessSummary> {
+  return invoke("reprocess_receipts", { filter });
+}
+
+export interface ReprocessDiff {
+  id: number;
+  receipt_id: number;
+  field: string;
+  current_value: string | null;
+  proposed_value: string;
+  status: "pending" | "accepted" | "dismissed";
+  created_at: string;
+}
+
+export function getReprocessDiffs(): Promise<ReprocessDiff[]> {
+  return invoke("get_reprocess_diffs");
+}
+
+export function resolveReprocessDiff(
+  diffId: number,
+  accept: boolean,
+): Promise<void> {
+  return invoke("resolve_reprocess_diff", { diffId, accept });
+}
+
+export type ReceiptLinkTarget =
+  | { kind: "ledger"; id: number }
+  | { kind: "imported"; id: number };
+
+export interface ReceiptMatchSuggestion {
+  receipt_id: number;
+  target: ReceiptLinkTarget;
+  confidence: number;
+  difference_cents: number;
+}
+
+export function suggestReceiptMatches(
+  dateWindowDays?: number,
+  toleranceCents?: number,
+): Promise<ReceiptMatchSuggestion[]> {
+  return invoke("suggest_receipt_matches", { dateWindowDays, toleranceCents });
+}
+
+export function confirmReceiptMatch(
+  receiptId: number,
+  target: ReceiptLinkTarget,
+): Promise<void> {
+  return invoke("confirm_receipt_match", { receiptId, target });
+}
+
+export interface AutoApproveSettings {
+  enabled: boolean;
+  min_confidence: number;
+  min_match_confidence: number;
+}
+
+export function getAutoApproveSettings(): Promise<AutoApproveSettings> {
+  return invoke("get_auto_approve_settings");
+}
+
+// Returns how many waiting receipts were approved right away.
+export function setAutoApproveSettings(settings: AutoApproveSettings): Promise<number> {
+  return invoke("set_auto_approve_settings", { settings });
+}
+
+export interface AutoApprovalRecord {
+  id: number;
+  receipt_id: number;
+  vendor: string | null;
+  receipt_date: string | null;
+  total_cents: number | null;
+  transaction_id: number | null;
+  transaction_description: string | null;
+  imported_transaction_id: number | null;
+  posted: boolean;
+  receipt_confidence: number;
+  match_confidence: number;
+  created_at: string;
+  undone_at: string | null;
+}
+
+export function getAutoApprovals(hours?: number): Promise<AutoApprovalRecord[]> {
+  return invoke("get_auto_approvals", { hours });
+}
+
+export function undoAutoApproval(id: number): Promise<void> {
+  return invoke("undo_auto_approval", { id });
+}
+
+export type AttachmentEntity =
+  | { kind: "transaction"; id: number }
+  | { kind: "account"; id: number };
+
+export interface Attachment {
+  id: number;
+  entity_type: "transaction" | "account";
+  entity_id: number;
+  file_hash: string;
+  file_ext: string;
+  file_name: string | null;
+  size_bytes: number;
+  attachment_path: string;
+  created_at: string;
+}
+
+export function attachDocument(
+  entity: AttachmentEntity,
+  data: Uint8Array,
+  fileName?: string,
+): Promise<Attachment> {
+  return invoke("attach_document", { entity, data: Array.from(data), fileName });
+}
+
+export function getAttachments(entity: AttachmentEntity): Promise<Attachment[]> {
+  return invoke("get_attachments", { entity });
+}
+
+// Returns an object URL; revoke it with URL.revokeObjectURL when done.
+export async function getAttachmentUrl(attachment: Attachment): Promise<string> {
+  const data = await invoke<ArrayBuffer>("get_attachment_file", { id: attachment.id });
+  return URL.createObjectURL(new Blob([data], { type: storedFileType(attachment.file_ext) }));
+}
+
+function storedFileType(ext: string): string {
+  return ext === "pdf" ? "application/pdf" : `image/${ext === "jpg" ? "jpeg" : ext}`;
+}
+
+export function deleteAttachment(id: number): Promise<void> {
+  return invoke("delete_attachment", { id });
+}
+
+export interface AttachmentStoreSettings {
+  dir: string;
+  /** Longest side photos are scaled down to; null keeps originals. */
+  max_side: number | null;
+  jpeg_quality: number;
+}
+
+export interface StoreMove {
+  files_copied: number;
+  bytes_copied: number;
+  rows_updated: number;
+}
+
+export function getAttachmentStoreSettings(): Promise<AttachmentStoreSettings> {
+  return invoke("get_attachment_store_settings");
+}
+
+export function setAttachmentImageLimits(
+  maxSide: number | null,
+  jpegQuality?: number,
+): Promise<void> {
+  return invoke("set_attachment_image_limits", { maxSide, jpegQuality });
+}
+
+export function moveAttachmentStore(dir: string): Promise<StoreMove> {
+  return invoke("move_attachment_store", { dir });
+}
+
+export type DocumentType =
+  | "contract"
+  | "warranty"
+  | "statement"
+  | "tax_form"
+  | "insurance"
+  | "other";
+
+export interface DocumentRecord {
+  id: number;
+  doc_type: DocumentType;
+  title: string;
+  period_start: string | null;
+  period_end: string | null;
+  contact_id: number | null;
+  contact_name: string | null;
+  entity_type: "transaction" | "account" | null;
+  entity_id: number | null;
+  notes: string | null;
+  file_hash: string;
+  file_ext: string;
+  file_name: string | null;
+  size_bytes: number;
+  attachment_path: string;
+  created_at: string;
+}
+
+export interface DocumentInput {
+  doc_type: DocumentType;
+  title: string;
+  period_start?: string;
+  period_end?: string;
+  contact_id?: number;
+  entity?: AttachmentEntity;
+  notes?: string;
+  file_name?: string;
+}
+
+export interface DocumentSearch {
+  text?: string;
+  doc_type?: DocumentType;
+  contact_id?: number;
+  entity?: AttachmentEntity;
+  from?: string;
+  to?: string;
+}
+
+export function fileDocument(input: DocumentInput, data: Uint8Array): Promise<DocumentRecord> {
+  return invoke("file_document", { input, data: Array.from(data) });
+}
+
+export function searchDocuments(query: DocumentSearch = {}): Promise<DocumentRecord[]> {
+  return invoke("search_documents", { query });
+}
+
+// Returns an object URL; revoke it with URL.revokeObjectURL when done.
+export async function getDocumentUrl(document: DocumentRecord): Promise<string> {
+  const data = await invoke<ArrayBuffer>("get_document_file", { id: document.id });
+  return URL.createObjectURL(new Blob([data], { type: storedFileType(document.file_ext) }));
+}
+
+export function deleteDocument(id: number): Promise<void> {
+  return invoke("delete_document", { id });
+}
+
+export interface YearArchiveSummary {
+  document_count: number;
+  attachment_count: number;
+  receipt_count: number;
+  report_count: number;
+  missing_files: string[];
+}
+
+export function archiveFiscalYear(year: number, outputPath: string): Promise<YearArchiveSummary> {
+  return invoke("archive_fiscal_year", { year, outputPath });
+}
+
+export type OcrBackendKind = "mock" | "tesseract";
+
+export interface OcrHealth {
+  requested: OcrBackendKind;
+  active: OcrBackendKind;
+  real_ocr: boolean;
+  tesseract_compiled: boolean;
+  tessdata_dir: string | null;
+  language: string;
+  language_installed: boolean | null;
+  page_segmentation: number;
+  char_whitelist: string | null;
+  detail: string | null;
+}
+
+export function getOcrHealth(): Promise<OcrHealth> {
+  return invoke("get_ocr_health");
+}
+
+export function configureOcr(
+  backend: OcrBackendKind,
+  language?: string,
+  tessdataDir?: string,
+  pageSegmentation?: number,
+  charWhitelist?: string,
+): Promise<OcrHealth> {
+  return invoke("configure_ocr", {
+    backend,
+    language,
+    tessdataDir,
+    pageSegmentation,
+    charWhitelist,
+  });
+}
+
+export interface PaymentAccountMap {
+  methods: Record<string, string>;
+  default_account: string | null;
+}
+
+export function getPaymentAccounts(): Promise<PaymentAccountMap> {
+  return invoke("get_payment_accounts");
+}
+
+export function setPaymentAccounts(map: PaymentAccountMap): Promise<void> {
+  return invoke("set_payment_accounts", { map });
+}
+
+export function configureReceiptLlm(
+  endpoint: string,
+  model: string,
+  apiKey: string | undefined,
+  sendImage: boolean,
+): Promise<boolean> {
+  return invoke("configure_receipt_llm", { endpoint, model, apiKey, sendImage });
+}
+
+export function configureReceiptLocale(locale: string): Promise<OcrHealth> {
+  return invoke("configure_receipt_locale", { locale });
+}
+
+export interface ImapConfig {
+  host: string;
+  port?: number;
+  username: string;
+  password: string;
+  folder?: string;
+}
+
+export function configureReceiptInbox(
+  config: ImapConfig | null,
+): Promise<number> {
+  return invoke("configure_receipt_inbox", { config });
+}
+
+export function pollReceiptInbox(): Promise<number> {
+  return invoke("poll_receipt_inbox");
+}
+
+export function downloadOcrLanguage(language?: string): Promise<OcrHealth> {
+  return invoke("download_ocr_language", { language });
+}
+
+// ── Quick entry ─────────────────────────────────────────────────────────────
+
+export interface PayeeSuggestion {
+  payee: string;
+  uses: number;
+  last_used: string;
+}
+
+export interface PayeeAccountUsage {
+  account_id: number;
+  account_code: string;
+  account_name: string;
+  account_type: string;
+  uses: number;
+  last_used: string;
+}
+
+export interface PayeeAmount {
+  transaction_id: number;
+  date: string;
+  amount_cents: number;
+}
+
+export interface QuickEntryPrefill {
+  payee: string;
+  category_account: PayeeAccountUsage | null;
+  payment_account: PayeeAccountUsage | null;
+  amount_cents: number | null;
+  contact_id: number | null;
+}
+
+export function suggestPayees(query: string, limit?: number): Promise<PayeeSuggestion[]> {
+  return invoke("suggest_payees", { query, limit });
+}
+
+export function getPayeeAccounts(payee: string, limit?: number): Promise<PayeeAccountUsage[]> {
+  return invoke("get_payee_accounts", { payee, limit });
+}
+
+export function getPayeeAmounts(payee: string, limit?: number): Promise<PayeeAmount[]> {
+  return invoke("get_payee_amounts", { payee, limit });
+}
+
+export function getQuickEntryPrefill(payee: string): Promise<QuickEntryPrefill> {
+  return invoke("get_quick_entry_prefill", { payee });
+}
+
+// ── Import commands ──────────────────────────────────────────────────────────
+
+export interface CsvColumnMapping {
+  date_column: number | null;
+  description_column: number | null;
+  amount_column: number | null;
+  debit_column: number | null;
+  credit_column: number | null;
+  memo_column: number | null;
+  date_format: string;
+  number_locale: string;
+}
+
+export interface CsvImportProfile {
+  id: number | null;
+  name: string;
+  mapping: CsvColumnMapping;
+  has_header: boolean;
+  delimiter: string;
+}
+
+export interface ImportPreview {
+  kind: "csv" | "ofx" | "qif";
+  columns: string[];
+  sample_rows: string[][];
+  suggested_profile: CsvImportProfile | null;
+  profile_matched: boolean;
+  transaction_count: number;
+  parse_error: string | null;
+}
+
+export interface ImportBatchSummary {
+  batch_id: string;
+  total: number;
+  pending: number;
+  categorized: number;
+  matched: number;
+  ignored: number;
+  suggested_matches: number;
+  first_date: string | null;
+  last_date: string | null;
+}
+
+export interface CommitImportOutput {
+  batch_id: string;
+  duplicates: number;
+  summary: ImportBatchSummary;
+  balance_assertion: AssertionCheck | null;
+}
+
+export function previewImportFile(filePath: string): Promise<ImportPreview> {
+  return invoke("preview_import_file", { filePath });
+}
+
+// Pass the profile from the mapping step to override the saved ones, and
+// the account the statement is for to check an OFX closing balance.
+export function commitImport(
+  filePath: string,
+  profile?: CsvImportProfile,
+  saveProfile?: boolean,
+  accountCode?: string,
+): Promise<CommitImportOutput> {
+  return invoke("commit_import", { filePath, profile, saveProfile, accountCode });
+}
+
+export interface ImportProgress {
+  job_id: string;
+  rows: number;
+  bytes: number;
+  total_bytes: number;
+}
+
+/** Import a large CSV in chunks; follow it with `onImportProgress`. */
+export function importCsvStreaming(
+  filePath: string,
+  profile: CsvImportProfile,
+  jobId: string,
+): Promise<CommitImportOutput> {
+  return invoke("import_csv_streaming", { filePath, profile, jobId });
+}
+
+export function cancelImport(jobId: string): Promise<void> {
+  return invoke("cancel_import", { jobId });
+}
+
+export function onImportProgress(
+  handler: (progress: ImportProgress) => void,
+): Promise<UnlistenFn> {
+  return listen<ImportProgress>("import:progress", (e) => handler(e.payload));
+}
+
+export function getImportSummary(batchId: string): Promise<ImportBatchSummary> {
+  return invoke("get_import_summary", { batchId });
+}
+
+export interface ImportReviewRow {
+  id: number;
+  import_batch_id: string;
+  date: string;
+  description: string;
+  amount_cents: number;
+  memo: string | null;
+  status: string;
+  account_id: number | null;
+  account_code: string | null;
+  rule_name: string | null;
+  matched_transaction_id: number | null;
+  matched_date: string | null;
+  matched_description: string | null;
+}
+
+export interface PostBatchOutput {
+  transaction_ids: number[];
+  left_for_review: number;
+}
+
+export function getImportReview(batchId: string): Promise<ImportReviewRow[]> {
+  return invoke("get_import_review", { batchId });
+}
+
+export function setImportedCategory(
+  id: number,
+  accountCode: string,
+): Promise<void> {
+  return invoke("set_imported_category", { id, accountCode });
+}
+
+export function getUncategorizedQueue(limit?: number): Promise<ImportReviewRow[]> {
+  return invoke("get_uncategorized_queue", { limit });
+}
+
+// With `similar`, rows with the same description are booked as well.
+export function quickCategorize(
+  ids: number[],
+  accountCode: string,
+  similar?: boolean,
+): Promise<number[]> {
+  return invoke("quick_categorize", { ids, accountCode, similar });
+}
+
+export function acceptMatch(id: number): Promise<void> {
+  return invoke("accept_match", { id });
+}
+
+export function rejectMatch(id: number): Promise<void> {
+  return invoke("reject_match", { id });
+}
+
+// bankAccountCode is the account the statement was downloaded from.
+export function postBatch(
+  batchId: string,
+  bankAccountCode: string,
+): Promise<PostBatchOutput> {
+  return invoke("post_batch", { batchId, bankAccountCode });
+}
+
+export type BudgetApp = "mint" | "ynab" | "quicken";
+
+export interface BudgetAppBatch {
+  account: string;
+  batch_id: string;
+  duplicates: number;
+  summary: ImportBatchSummary;
+}
+
+export interface BudgetAppImportOutput {
+  app: BudgetApp;
+  batches: BudgetAppBatch[];
+}
+
+export function importBudgetApp(
+  filePath: string,
+  app?: BudgetApp,
+): Promise<BudgetAppImportOutput> {
+  return invoke("import_budget_app", { filePath, app });
+}
+
+export interface GnucashImportSummary {
+  accounts_created: number;
+  accounts_matched: number;
+  transactions_imported: number;
+  already_imported: number;
+  skipped: string[];
+}
+
+export function importGnucash(path: string): Promise<GnucashImportSummary> {
+  return invoke("import_gnucash", { path });
+}
+
+// ── Balance assertion commands ──────────────────────────────────────────────
+
+export interface AssertionCheck {
+  id: number;
+  account_id: number;
+  account_code: string;
+  account_name: string;
+  date: string;
+  balance_cents: number;
+  source: "statement" | "ofx";
+  created_at: string;
+  ledger_balance_cents: number;
+  drift_cents: number;
+}
+
+export function addBalanceAssertion(
+  accountCode: string,
+  date: string,
+  balanceCents: number,
+): Promise<AssertionCheck> {
+  return invoke("add_balance_assertion", { accountCode, date, balanceCents });
+}
+
+export function getBalanceAssertions(accountCode?: string): Promise<AssertionCheck[]> {
+  return invoke("get_balance_assertions", { accountCode });
+}
+
+export function deleteBalanceAssertion(id: number): Promise<void> {
+  return invoke("delete_balance_assertion", { id });
+}
+
+// ── Categorization rule commands ────────────────────────────────────────────
+
+export type RuleMatchType =
+  | "Contains"
+  | "Exact"
+  | "Regex"
+  | { Fuzzy: { threshold: number } };
+
+export interface CategoryRule {
+  name: string;
+  priority: number;
+  pattern: string;
+  match_type: RuleMatchType;
+  account_code: string;
+  amount_min_cents: number | null;
+  amount_max_cents: number | null;
+}
+
+export interface CategorizationRuleOutput extends CategoryRule {
+  id: number;
+}
+
+export function getCategorizationRules(): Promise<CategorizationRuleOutput[]> {
+  return invoke("get_categorization_rules");
+}
+
+export function createCategorizationRule(rule: CategoryRule): Promise<number> {
+  return invoke("create_categorization_rule", { rule });
+}
+
+export function updateCategorizationRule(
+  id: number,
+  rule: CategoryRule,
+): Promise<void> {
+  return invoke("update_categorization_rule", { id, rule });
+}
+
+export function deleteCategorizationRule(id: number): Promise<void> {
+  return invoke("delete_categorization_rule", { id });
+}
+
+// ids in the order the rules should be tried.
+export function reorderCategorizationRules(ids: number[]): Promise<void> {
+  return invoke("reorder_categorization_rules", { ids });
+}
+
+// ── Import profile commands ─────────────────────────────────────────────────
+
+export function getImportProfiles(): Promise<CsvImportProfile[]> {
+  return invoke("get_import_profiles");
+}
+
+export function saveImportProfile(profile: CsvImportProfile): Promise<number> {
+  return invoke("save_import_profile", { profile });
+}
+
+export function updateImportProfile(
+  id: number,
+  profile: CsvImportProfile,
+): Promise<void> {
+  return invoke("update_import_profile", { id, profile });
+}
+
+export function deleteImportProfile(id: number): Promise<void> {
+  return invoke("delete_import_profile", { id });
+}
+
+// Preview a sample file read with an unsaved profile.
+export function testImportProfile(
+  filePath: string,
+  profile: CsvImportProfile,
+): Promise<ImportPreview> {
+  return invoke("test_import_profile", { filePath, profile });
+}
+
+// ── Tax commands ─────────────────────────────────────────────────────────────
+
+export interface ScheduleCLineOutput {
+  line: string;
+  label: string;
+  // Deductible amount; book_amount_cents is the amount as booked.
+  amount_cents: number;
+  book_amount_cents: number;
+  is_income: boolean;
+}
+
+export interface QuarterlyEstimateOutput {
+  year: number;
+  quarter: string;
+  ytd_gross_income_cents: number;
+  ytd_total_expenses_cents: number;
+  ytd_net_profit_cents: number;
+  se_tax_cents: number;
+  se_tax_deduction_cents: number;
+  income_tax_cents: number;
+  total_tax_cents: number;
+  safe_harbor_cents: number;
+  quarterly_payment_cents: number;
+  payment_due_date: string;
+  schedule_c_lines: ScheduleCLineOutput[];
+}
+
+export interface ScheduleCPreviewOutput {
+  year: number;
+  gross_income_cents: number;
+  total_expenses_cents: number;
+  book_total_expenses_cents: number;
+  net_profit_cents: number;
+  lines: ScheduleCLineOutput[];
+}
+
+export function estimateQuarterlyTax(
+  year?: number,
+  quarter?: number,
+): Promise<QuarterlyEstimateOutput> {
+  return invoke("estimate_quarterly_tax", { year, quarter });
+}
+
+export function getScheduleCPreview(
+  year?: number,
+): Promise<ScheduleCPreviewOutput> {
+  return invoke("get_schedule_c_preview", { year });
+}
+
+// ── Home office deduction ────────────────────────────────────────────────────
+
+export type HomeOfficeMethod = "simplified" | "regular";
+
+export interface HomeExpense {
+  label: string;
+  // Account the amount is pulled from (its personal bookings); null when
+  // entered by hand.
+  account_code: string | null;
+  amount_cents: number;
+}
+
+export interface HomeOfficeInput {
+  method: HomeOfficeMethod;
+  office_sqft: number;
+  home_sqft: number;
+  expenses: HomeExpense[];
+  prior_carryover_cents: number;
+}
+
+export interface HomeOfficeWorksheet extends HomeOfficeInput {
+  year: number;
+  tentative_profit_cents: number;
+  // Decimal string, e.g. "12.50".
+  business_percent: string;
+  expenses_cents: number;
+  allowable_cents: number;
+  // Schedule C line 30.
+  deduction_cents: number;
+  carryover_cents: number;
+  transaction_id: number | null;
+  updated_at: string;
+}
+
+export interface HomeOfficeWorksheetOutput {
+  year: number;
+  draft: HomeOfficeInput;
+  saved: HomeOfficeWorksheet | null;
+  simplified_rate_per_sqft: string;
+  simplified_max_sqft: number;
+}
+
+export function getHomeOfficeWorksheet(
+  year?: number,
+): Promise<HomeOfficeWorksheetOutput> {
+  return invoke("get_home_office_worksheet", { year });
+}
+
+export function saveHomeOfficeWorksheet(
+  year: number,
+  input: HomeOfficeInput,
+): Promise<HomeOfficeWorksheet> {
+  return invoke("save_home_office_worksheet", { year, input });
+}
+
+export function postHomeOfficeEntry(
+  year: number,
+  creditAccountCode?: string,
+): Promise<HomeOfficeWorksheet> {
+  return invoke("post_home_office_entry", { year, creditAccountCode });
+}
+
+// ── Contact commands ─────────────────────────────────────────────────────────
+
+export interface ContactRecord {
+  id: number;
+  name: string;
+  email: string | null;
+  phone: string | null;
+  address: string | null;
+  contact_type: string;
+  is_contractor: boolean;
+  tax_id: string | null;
+  notes: string | null;
+  created_at: string;
+  default_account_id: number | null;
+  payment_terms_days: number | null;
+  is_archived: boolean;
+}
+
+export interface ContactDetail extends ContactRecord {
+  default_account_code: string | null;
+  aliases: string[];
+}
+
+export interface ContactInput {
+  name: string;
+  email?: string;
+  phone?: string;
+  address?: string;
+  contact_type: string;
+  is_contractor: boolean;
+  tax_id?: string;
+  notes?: string;
+  default_account_code?: string;
+  payment_terms_days?: number;
+  aliases?: string[];
+}
+
+export function getContacts(includeArchived?: boolean): Promise<ContactRecord[]> {
+  return invoke("get_contacts", { includeArchived });
+}
+
+export function getContact(id: number): Promise<ContactDetail> {
+  return invoke("get_contact", { id });
+}
+
+export function createContact(input: ContactInput): Promise<ContactRecord> {
+  return invoke("create_contact", { input });
+}
+
+export function archiveContact(id: number, archived: boolean): Promise<void> {
+  return invoke("archive_contact", { id, archived });
+}
+
+export function deleteContact(id: number): Promise<void> {
+  return invoke("delete_contact", { id });
+}
+
+// ── Invoice commands ─────────────────────────────────────────────────────────
+
+export interface InvoiceRecord {
+  id: number;
+  invoice_number: string;
+  contact_id: number;
+  status_type: string;
+  status_data: string | null;
+  issue_date: string;
+  due_date: string;
+  discount_type: string | null;
+  discount_value: number | null;
+  notes: string | null;
+  terms: string | null;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface InvoiceInput {
+  invoice_number: string;
+  contact_id: number;
+  issue_date: string;
+  due_date: string;
+  notes?: string;
+  terms?: string;
+}
+
+export interface PaymentInput {
+  invoice_id: number;
+  amount_cents: number;
+  date: string;
+  method?: string;
+  account_code?: string;
+}
+
+export interface InvoiceBalance {
+  invoice_id: number;
+  invoice_number: string;
+  contact_id: number;
+  status_type: string;
+  total_cents: number;
+  tax_cents: number;
+  paid_cents: number;
+  due_cents: number;
+}
+
+export interface RecordedPayment {
+  payment_id: number;
+  transaction_id: number;
+  applied_cents: number;
+  credit_cents: number;
+  balance: InvoiceBalance;
+}
+
+export interface PaymentRecord {
+  id: number;
+  invoice_id: number;
+  amount_cents: number;
+  date: string;
+  method: string | null;
+  transaction_id: number | null;
+  created_at: string;
+  credit_cents: number;
+}
+
+export interface InvoicePayments {
+  balance: InvoiceBalance;
+  payments: PaymentRecord[];
+  customer_credit_cents: number;
+}
+
+export interface NecSummaryEntry {
+  contact_id: number;
+  contact_name: string;
+  ytd_cents: number;
+  over_threshold: boolean;
+}
+
+export function getInvoices(): Promise<InvoiceRecord[]> {
+  return invoke("get_invoices");
+}
+
+export function createInvoice(input: InvoiceInput): Promise<InvoiceRecord> {
+  return invoke("create_invoice", { input });
+}
+
+export function getInvoiceAging(): Promise<InvoiceRecord[]> {
+  return invoke("get_invoice_aging");
+}
+
+export type AgingBucket = "current" | "days_1_30" | "days_31_60" | "days_61_90" | "over_90";
+
+export interface AgingTotals {
+  current_cents: number;
+  days_1_30_cents: number;
+  days_31_60_cents: number;
+  days_61_90_cents: number;
+  over_90_cents: number;
+  total_cents: number;
+}
+
+export interface AgingItem {
+  id: number;
+  number: string | null;
+  contact_id: number;
+  contact_name: string;
+  issue_date: string;
+  due_date: string;
+  total_cents: number;
+  open_cents: number;
+  days_overdue: number;
+  bucket: AgingBucket;
+}
+
+export interface AgingReport {
+  as_of: string;
+  items: AgingItem[];
+  contacts: { contact_id: number; contact_name: string; totals: AgingTotals }[];
+  totals: AgingTotals;
+}
+
+export function getArAging(asOf?: string): Promise<AgingReport> {
+  return invoke("get_ar_aging", { asOf });
+}
+
+export function getApAging(asOf?: string): Promise<AgingReport> {
+  return invoke("get_ap_aging", { asOf });
+}
+
+// ── Bill commands ────────────────────────────────────────────────────────────
+
+export interface Bill {
+  id: number;
+  contact_id: number;
+  contact_name: string;
+  bill_number: string | null;
+  bill_date: string;
+  due_date: string;
+  amount_cents: number;
+  account_id: number;
+  notes: string | null;
+  is_void: boolean;
+  created_at: string;
+  paid_cents: number;
+}
+
+export interface BillInput {
+  contact_id: number;
+  bill_number?: string;
+  bill_date: string;
+  due_date: string;
+  amount_cents: number;
+  account_code: string;
+  notes?: string;
+}
+
+export interface BillPaymentInput {
+  bill_id: number;
+  amount_cents: number;
+  date: string;
+  account_code: string;
+}
+
+export interface BillPayment {
+  payment_id: number;
+  transaction_id: number;
+  open_cents: number;
+}
+
+export function createBill(input: BillInput): Promise<Bill> {
+  return invoke("create_bill", { input });
+}
+
+export function getBills(openOnly?: boolean): Promise<Bill[]> {
+  return invoke("get_bills", { openOnly });
+}
+
+export function voidBill(id: number): Promise<void> {
+  return invoke("void_bill", { id });
+}
+
+export function recordBillPayment(input: BillPaymentInput): Promise<BillPayment> {
+  return invoke("record_bill_payment", { input });
+}
+
+export function recordInvoicePayment(input: PaymentInput): Promise<RecordedPayment> {
+  return invoke("record_invoice_payment", { input });
+}
+
+export function applyCustomerCredit(invoiceId: number, date: string): Promise<RecordedPayment> {
+  return invoke("apply_customer_credit", { invoiceId, date });
+}
+
+export function getInvoicePayments(invoiceId: number): Promise<InvoicePayments> {
+  return invoke("get_invoice_payments", { invoiceId });
+}
+
+export function get1099Summary(year?: number): Promise<NecSummaryEntry[]> {
+  return invoke("get_1099_summary", { year });
+}
+
+// ── Time tracking commands ──────────────────────────────────────────────────
+
+export interface TimeEntry {
+  id: number;
+  contact_id: number;
+  project: string | null;
+  description: string;
+  date: string;
+  minutes: number;
+  started_at: string | null;
+  ended_at: string | null;
+  hourly_rate_cents: number;
+  billable: boolean;
+  invoice_id: number | null;
+  created_at: string;
+}
+
+export interface TimeEntryInput {
+  contact_id: number;
+  project?: string;
+  description?: string;
+  hourly_rate_cents: number;
+  billable?: boolean;
+}
+
+export function getTimeEntries(
+  contactId?: number,
+  unbilledOnly?: boolean,
+): Promise<TimeEntry[]> {
+  return invoke("get_time_entries", { contactId, unbilledOnly });
+}
+
+export function createTimeEntry(
+  entry: TimeEntryInput,
+  date: string,
+  minutes: number,
+): Promise<TimeEntry> {
+  return invoke("create_time_entry", { entry, date, minutes });
+}
+
+export function updateTimeEntry(
+  id: number,
+  entry: TimeEntryInput,
+  date: string,
+  minutes: number,
+): Promise<TimeEntry> {
+  return invoke("update_time_entry", { id, entry, date, minutes });
+}
+
+export function deleteTimeEntry(id: number): Promise<void> {
+  return invoke("delete_time_entry", { id });
+}
+
+export function getRunningTimer(): Promise<TimeEntry | null> {
+  return invoke("get_running_timer");
+}
+
+export function startTimer(entry: TimeEntryInput): Promise<TimeEntry> {
+  return invoke("start_timer", { entry });
+}
+
+export function stopTimer(): Promise<TimeEntry> {
+  return invoke("stop_timer");
+}
+
+export function invoiceUnbilledTime(
+  input: InvoiceInput,
+  throughDate?: string,
+): Promise<InvoiceRecord> {
+  return invoke("invoice_unbilled_time", { input, throughDate });
+}
+
+export interface SendInvoiceInput {
+  invoice_id: number;
+  subject?: string;
+}
+
+export interface DeliveryResult {
+  recipient: string;
+  invoice_number: string;
+  backend: string;
+}
+
+export type EmailConfig =
+  | {
+      backend: "smtp";
+      from_name: string;
+      from_email: string;
+      smtp: {
+        host: string;
+        port: number;
+        username: string;
+        password: string;
+        starttls?: boolean;
+      };
+    }
+  | {
+      backend: "resend";
+      from_name: string;
+      from_email: string;
+      api_key: string;
+    };
+
+// Pass null to stop sending invoices by email.
+export function configureEmail(config: EmailConfig | null): Promise<void> {
+  return invoke("configure_email", { config });
+}
+
+export function sendInvoice(input: SendInvoiceInput): Promise<DeliveryResult> {
+  return invoke("send_invoice", { input });
+}
+
+// ── Export commands ──────────────────────────────────────────────────────────
+
+export function exportBeancount(): Promise<string> {
+  return invoke("export_beancount");
+}
+
+export function exportQif(): Promise<string> {
+  return invoke("export_qif");
+}
+
+export interface ReceiptExportSummary {
+  receipt_count: number;
+  // Receipts listed in the index whose file was missing.
+  missing_files: number[];
+}
+
+export function exportReceipts(
+  startDate: string,
+  endDate: string,
+  outputPath: string,
+): Promise<ReceiptExportSummary> {
+  return invoke("export_receipts", { startDate, endDate, outputPath });
+}
+
+export type ReportKind =
+  | { type: "profit_loss" }
+  | { type: "balance_sheet" }
+  | { type: "schedule_c" }
+  | { type: "register"; account_code: string }
+  | { type: "reconciliation"; session_id: number }
+  | { type: "vendor_spending"; limit?: number }
+  | { type: "owners_equity"; interval?: ReportInterval }
+  | { type: "saved"; id: number };
+
+export type ReportFormat = "csv" | "json" | "pdf";
+
+export function exportReport(
+  kind: ReportKind,
+  period: { start: string; end: string },
+  format: ReportFormat,
+  path: string,
+): Promise<void> {
+  return invoke("export_report", { kind, period, format, path });
+}
+
+/** Writes a ledger-cli/hledger journal; resolves to the number of transactions. */
+export function exportLedgerFormat(
+  path: string,
+  period?: { start: string; end: string },
+): Promise<number> {
+  return invoke("export_ledger_format", { path, period });
+}
+
+export type QuickbooksFormat = "iif" | "journal_csv";
+
+export function exportQuickbooks(
+  path: string,
+  period: { start: string; end: string },
+  format: QuickbooksFormat,
+): Promise<number> {
+  return invoke("export_quickbooks", { path, period, format });
+}
+
+export function exportOfx(
+  path: string,
+  accountCode: string,
+  period: { start: string; end: string },
+): Promise<number> {
+  return invoke("export_ofx", { path, accountCode, period });
+}
+
+export function exportGnucash(path: string): Promise<number> {
+  return invoke("export_gnucash", { path });
+}
+
+export function exportCalendar(path: string): Promise<number> {
+  return invoke("export_calendar", { path });
+}
+
+// ── Setup ───────────────────────────────────────────────────────────────────
+
+export type BusinessType =
+  | "sole_proprietorship"
+  | "single_member_llc"
+  | "partnership"
+  | "corporation";
+
+export type ChartTemplate = "standard" | "services" | "minimal";
+
+export interface BusinessProfile {
+  name: string;
+  business_type: BusinessType;
+}
+
+export interface OpeningBalance {
+  account_code: string;
+  amount_cents: number;
+}
+
+export interface OnboardingStatus {
+  completed: boolean;
+  completed_at: string | null;
+  business_profile: BusinessProfile | null;
+  base_currency: string;
+  fiscal_year_start_month: number;
+  chart_template: ChartTemplate | null;
+  opening_balance_date: string | null;
+  opening_balances: OpeningBalance[];
+  intake_folder: string | null;
+}
+
+export interface ChartTemplateOption {
+  template: ChartTemplate;
+  accounts: { code: string; name: string; account_type: string }[];
+}
+
+export interface ChartTemplateOutcome {
+  added: string[];
+  archived: string[];
+  kept: string[];
+}
+
+export function getOnboardingStatus(): Promise<OnboardingStatus> {
+  return invoke("get_onboarding_status");
+}
+
+export function getChartTemplates(): Promise<ChartTemplateOption[]> {
+  return invoke("get_chart_templates");
+}
+
+export function saveBusinessProfile(profile: BusinessProfile): Promise<void> {
+  return invoke("save_business_profile", { profile });
+}
+
+export function saveFiscalSettings(
+  fiscalYearStartMonth: number,
+  baseCurrency: string,
+): Promise<void> {
+  return invoke("save_fiscal_settings", { fiscalYearStartMonth, baseCurrency });
+}
+
+export function applyChartTemplate(template: ChartTemplate): Promise<ChartTemplateOutcome> {
+  return invoke("apply_chart_template", { template });
+}
+
+export function setOpeningBalances(
+  asOf: string,
+  balances: OpeningBalance[],
+): Promise<number | null> {
+  return invoke("set_opening_balances", { asOf, balances });
+}
+
+export function setIntakeFolder(path: string): Promise<void> {
+  return invoke("set_intake_folder", { path });
+}
+
+export function completeOnboarding(): Promise<OnboardingStatus> {
+  return invoke("complete_onboarding");
+}
+
+// ── Settings commands ───────────────────────────────────────────────────────
+
+export interface Preferences {
+  default_currency: string;
+  fiscal_year_start_month: number;
+  ocr_backend: string;
+  review_threshold: number;
+  date_format: string;
+}
+
+// Keys set_setting accepts for the typed preferences.
+export const PREFERENCE_KEYS = {
+  defaultCurrency: "default_currency",
+  fiscalYearStartMonth: "fiscal_year_start_month",
+  ocrBackend: "ocr_backend",
+  reviewThreshold: "review_confidence_threshold",
+  dateFormat: "date_format",
+  numberLocale: "number_locale",
+} as const;
+
+export function getSettings(): Promise<Preferences> {
+  return invoke("get_settings");
+}
+
+// Only the preferences and a few general settings (business name and EIN,
+// MCP switches, receipt and capitalize thresholds, receipt intake workers,
+// recursion and file moving) can be read or written by key; the rest have
+// their own commands.
+export function getSetting(key: string): Promise<string | null> {
+  return invoke("get_setting", { key });
+}
+
+export function setSetting(key: string, value: string): Promise<void> {
+  return invoke("set_setting", { key, value });
+}
+
+// ── Local API commands ──────────────────────────────────────────────────────
+
+export interface LocalApiStatus {
+  available: boolean;
+  enabled: boolean;
+  running: boolean;
+  port: number;
+  token: string | null;
+  url: string | null;
+}
+
+export function getLocalApiStatus(): Promise<LocalApiStatus> {
+  return invoke("get_local_api_status");
+}
+
+export function configureLocalApi(
+  enabled: boolean,
+  port?: number,
+  regenerateToken?: boolean,
+): Promise<LocalApiStatus> {
+  return invoke("configure_local_api", { enabled, port, regenerateToken });
+}
+
+// ── App lock ────────────────────────────────────────────────────────────────
+
+/** Emitted when the app locks itself after the idle timeout or on request. */
+export const APP_LOCKED_EVENT = "app:locked";
+
+export interface AppLockStatus {
+  enabled: boolean;
+  locked: boolean;
+  idle_timeout_minutes: number;
+  failed_attempts: number;
+  retry_after_secs: number | null;
+}
+
+export function getAppLockStatus(): Promise<AppLockStatus> {
+  return invoke("get_app_lock_status");
+}
+
+export function lockApp(): Promise<void> {
+  return invoke("lock_app");
+}
+
+export function unlockApp(passphrase: string): Promise<AppLockStatus> {
+  return invoke("unlock_app", { passphrase });
+}
+
+export function setAppLockPassphrase(
+  passphrase: string,
+  currentPassphrase?: string,
+): Promise<AppLockStatus> {
+  return invoke("set_app_lock_passphrase", { passphrase, currentPassphrase });
+}
+
+export function removeAppLock(currentPassphrase: string): Promise<AppLockStatus> {
+  return invoke("remove_app_lock", { currentPassphrase });
+}
+
+export function setAppLockTimeout(minutes: number): Promise<AppLockStatus> {
+  return invoke("set_app_lock_timeout", { minutes });
+}
+
+// ── Sync ────────────────────────────────────────────────────────────────────
+
+export interface SyncPeer {
+  device_id: string;
+  last_sent_seq: number;
+  last_received_seq: number;
+  last_synced_at: string | null;
+}
+
+export interface SyncStatus {
+  device_id: string;
+  peers: SyncPeer[];
+}
+
+export interface SyncExportSummary {
+  device_id: string;
+  changes: number;
+  up_to_seq: number;
+}
+
+export interface SyncConflict {
+  entity: "account" | "transaction";
+  key: string;
+  resolution: "kept_local" | "kept_remote";
+}
+
+export interface SyncReport {
+  device_id: string;
+  applied: number;
+  unchanged: number;
+  conflicts: SyncConflict[];
+}
+
+export function getSyncStatus(): Promise<SyncStatus> {
+  return invoke("get_sync_status");
+}
+
+export function exportSyncChanges(
+  outputPath: string,
+  peerDeviceId?: string,
+): Promise<SyncExportSummary> {
+  return invoke("export_sync_changes", { outputPath, peerDeviceId });
+}
+
+export function importSyncChanges(inputPath: string): Promise<SyncReport> {
+  return invoke("import_sync_changes", { inputPath });
+}
+
+// ── Cloud backup ────────────────────────────────────────────────────────────
+
+export type RemoteTarget =
+  | {
+      kind: "s3";
+      endpoint: string;
+      region: string;
+      bucket: string;
+      prefix: string;
+      access_key_id: string;
+      secret_access_key: string;
+    }
+  | { kind: "web_dav"; url: string; username: string; password: string }
+  | { kind: "dropbox"; access_token: string; folder: string }
+  | { kind: "directory"; path: string };
+
+export interface RetentionPolicy {
+  keep_last: number;
+  keep_daily: number;
+  keep_monthly: number;
+}
+
+export interface CloudBackupSettings {
+  target: RemoteTarget | null;
+  retention: RetentionPolicy;
+}
+
+export interface RemoteSnapshot {
+  name: string;
+  created_at: string;
+  size_bytes: number | null;
+}
+
+export interface CloudBackupOutcome {
+  snapshot: RemoteSnapshot;
+  manifest: {
+    version: string;
+    created_at: string;
+    schema_version: number;
+    db_size_bytes: number;
+    attachment_count: number;
+  };
+  pruned: string[];
+}
+
+export function getCloudBackupSettings(): Promise<CloudBackupSettings> {
+  return invoke("get_cloud_backup_settings");
+}
+
+export function saveCloudBackupSettings(settings: CloudBackupSettings): Promise<void> {
+  return invoke("save_cloud_backup_settings", { settings });
+}
+
+export function runCloudBackup(passphrase: string): Promise<CloudBackupOutcome> {
+  return invoke("run_cloud_backup", { passphrase });
+}
+
+export function listCloudBackups(): Promise<RemoteSnapshot[]> {
+  return invoke("list_cloud_backups");
+}
+
+export function restoreCloudBackup(
+  name: string,
+  passphrase: string,
+  targetDir: string,
+): Promise<string> {
+  return invoke("restore_cloud_backup", { name, passphrase, targetDir });
+}
+
+export interface DatabaseSnapshot {
+  path: string;
+  created_at: string;
+  size_bytes: number;
+}
+
+export type DatabaseHealth = (
+  | { status: "healthy" }
+  | { status: "corrupt"; problems: string[] }
+  | { status: "too_new"; version: number; supported: number }
+) & {
+  error: string | null;
+  recovery_mode: boolean;
+  latest_snapshot: DatabaseSnapshot | null;
+};
+
+export function getDatabaseHealth(): Promise<DatabaseHealth> {
+  return invoke("get_database_health");
+}
+
+/** Restores the latest daily snapshot and restarts the app. */
+export function restoreDatabaseSnapshot(): Promise<void> {
+  return invoke("restore_database_snapshot");
+}
+
+// ── Audit log ───────────────────────────────────────────────────────────────
+
+export interface AuditLogRecord {
+  id: number;
+  timestamp: string;
+  tool_name: string;
+  input_hash: string | null;
+  outcome: string;
+  details: string | null;
+}
+
+export function getAuditLog(limit?: number): Promise<AuditLogRecord[]> {
+  return invoke("get_audit_log", { limit });
+}
+
+// ── Diagnostics ─────────────────────────────────────────────────────────────
+
+export interface StorageDiagnostics {
+  db_path: string;
+  db_size_bytes: number;
+  wal_size_bytes: number;
+  journal_mode: string;
+  sqlite_version: string;
+  schema_version: number;
+  pending_migrations: { version: number; name: string }[];
+  tables: { table: string; rows: number }[];
+  attachments_dir: string;
+  attachment_count: number;
+  attachments_size_bytes: number;
+  last_backup_at: string | null;
+  balance_drift: AssertionCheck[];
+}
+
+export interface Diagnostics {
+  app_version: string;
+  os: string;
+  arch: string;
+  storage: StorageDiagnostics;
+  ocr: OcrHealth;
+  local_api_running: boolean;
+}
+
+export function getDiagnostics(): Promise<Diagnostics> {
+  return invoke("get_diagnostics");
+}
+
+export type LogLevel = "error" | "warn" | "info" | "debug" | "trace";
+
+export interface LogEntry {
+  timestamp: string;
+  level: string;
+  target: string;
+  message: string;
+  correlation_id: string | null;
+  fields: Record<string, unknown>;
+}
+
+export function getRecentLogs(
+  level?: LogLevel,
+  limit?: number,
+  correlationId?: string,
+): Promise<LogEntry[]> {
+  return invoke("get_recent_logs", { level, limit, correlationId });
+}
+
+/** Recompute the monthly account balance rollup; returns its row count. */
+export function rebuildAccountBalances(): Promise<number> {
+  return invoke("rebuild_account_balances");
+}
+
+// ── Update commands ────────────────────────────────────────────────────────
+
+export interface UpdateStatus {
+  update_available: boolean;
+  current_version: string;
+  latest_version: string | null;
+}
+
+export function checkForUpdates(): Promise<UpdateStatus> {
+  return invoke("check_for_updates");
+}
+
+// ── Reminders ──────────────────────────────────────────────────────────────
+
+export interface NotificationRecord {
+  id: number;
+  key: string;
+  kind:
+    | "tax_deadline"
+    | "invoice_overdue"
+    | "reconciliation_due"
+    | "low_balance"
+    | "budget_alert";
+  title: string;
+  body: string;
+  due_date: string | null;
+  created_at: string;
+  updated_at: string;
+  read_at: string | null;
+  dismissed_at: string | null;
+  resolved_at: string | null;
+}
+
+export interface ReminderSettings {
+  enabled: boolean;
+  tax_deadline_lead_days: number;
+  reconciliation_interval_days: number;
+  reconciliation_accounts: string[];
+  low_balance_account: string;
+  low_balance_threshold_cents: number | null;
+  budget_alert_percent: number | null;
+  calendar_path: string | null;
+}
+
+export function getNotifications(includeDismissed?: boolean): Promise<NotificationRecord[]> {
+  return invoke("get_notifications", { includeDismissed });
+}
+
+export function refreshNotifications(): Promise<NotificationRecord[]> {
+  return invoke("refresh_notifications");
+}
+
+export function markNotificationRead(id: number): Promise<void> {
+  return invoke("mark_notification_read", { id });
+}
+
+export function dismissNotification(id: number): Promise<void> {
+  return invoke("dismiss_notification", { id });
+}
+
+export function getReminderSettings(): Promise<ReminderSettings> {
+  return invoke("get_reminder_settings");
+}
+
+export function setReminderSettings(settings: ReminderSettings): Promise<void> {
+  return invoke("set_reminder_settings", { settings });
+}
+
+// ── Budgets ────────────────────────────────────────────────────────────────
+
+export interface Budget {
+  account_id: number;
+  account_code: string;
+  account_name: string;
+  monthly_cents: number;
+}
+
+export interface BudgetVariance {
+  account_code: string;
+  account_name: string;
+  budget_cents: number;
+  actual_cents: number;
+  variance_cents: number;
+  percent_used: number;
+}
+
+export function getBudgets(): Promise<Budget[]> {
+  return invoke("get_budgets");
+}
+
+export function setBudget(accountCode: string, monthlyCents: number | null): Promise<void> {
+  return invoke("set_budget", { accountCode, monthlyCents });
+}
+
+export function getBudgetVariance(asOf?: string): Promise<BudgetVariance[]> {
+  return invoke("get_budget_variance", { asOf });
+}
+
+// ── Hook commands ───────────────────────────────────────────────────────────
+
+export type HookEventKind =
+  | "receipt_approved"
+  | "transaction_posted"
+  | "import_completed";
+
+export type HookTarget =
+  | { type: "script"; path: string; args: string[] }
+  | { type: "http"; url: string; headers: Record<string, string> };
+
+export interface Hook {
+  name: string;
+  enabled: boolean;
+  events: HookEventKind[];
+  target: HookTarget;
+}
+
+export interface HookSettings {
+  hooks: Hook[];
+}
+
+export function getHookSettings(): Promise<HookSettings> {
+  return invoke("get_hook_settings");
+}
+
+export function setHookSettings(settings: HookSettings): Promise<void> {
+  return invoke("set_hook_settings", { settings });
+}
+
+export function testHook(hook: Hook, event?: HookEventKind): Promise<void> {
+  return invoke("test_hook", { hook, event });
+}
+
+// ── Dashboard commands ──────────────────────────────────────────────────────
+
+export interface DashboardSummary {
+  ytd_income_cents: number;
+  ytd_expenses_cents: number;
+  ytd_net_profit_cents: number;
+  outstanding_invoices: number;
+  overdue_invoices: number;
+  pending_receipts: number;
+  total_accounts: number;
+  total_transactions: number;
+  recent_transactions: TransactionOutput[];
+  quarterly_tax_due_cents: number | null;
+  next_tax_due_date: string | null;
+  budget_variances: BudgetVariance[];
+}
+
+export function getDashboardSummary(): Promise<DashboardSummary> {
+  return invoke("get_dashboard_summary");
+}
+
+export type AttentionKind =
+  | "overdue_invoice"
+  | "reconciliation_item"
+  | "imported_transaction"
+  | "pending_receipt";
+
+export interface AttentionItem {
+  kind: AttentionKind;
+  id: number;
+  title: string;
+  detail: string | null;
+  date: string | null;
+  amount_cents: number | null;
+  // Import batch id or reconciliation session id.
+  reference: string | null;
+}
+
+export interface AttentionFeed {
+  items: AttentionItem[];
+  counts: {
+    overdue_invoices: number;
+    reconciliation_items: number;
+    imported_transactions: number;
+    pending_receipts: number;
+  };
+}
+
+export function getAttentionFeed(limit?: number): Promise<AttentionFeed> {
+  return invoke("get_attention_feed", { limit });
 }
 
 // ── Update contact command ──────────────────────────────────────────────────
@@ -330,6 +2394,9 @@ export interface UpdateContactInput {
   is_contractor: boolean;
   tax_id?: string;
   notes?: string;
+  default_account_code?: string;
+  payment_terms_days?: number;
+  aliases?: string[];
 }
 
 export function updateContact(
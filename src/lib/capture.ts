@@ -1,29 +1,16 @@
-import { writeFile, mkdir, BaseDirectory } from "@tauri-apps/plugin-fs";
-import { appDataDir } from "@tauri-apps/api/path";
-
 /**
- * Write a captured camera File to the app's intake directory so the
- * backend receipt pipeline picks it up. Returns the absolute file path.
+ * Read a captured camera File for `ingestReceiptBytes`. The backend checks
+ * the bytes against the reported type, so the MIME type is preferred over
+ * the (often generic) file name.
  */
 const MAX_FILE_SIZE = 50 * 1024 * 1024; // 50 MB
 
-export async function writeCapturedFile(file: File): Promise<string> {
+export async function readCapturedFile(file: File): Promise<{ data: Uint8Array; ext: string }> {
   if (file.size > MAX_FILE_SIZE) {
     throw new Error(`File too large (${(file.size / 1048576).toFixed(1)} MB, max 50 MB)`);
   }
 
-  const dir = "intake";
-  await mkdir(dir, { baseDir: BaseDirectory.AppData, recursive: true });
-
-  const rawExt = (file.name.split(".").pop() ?? "jpg").toLowerCase();
-  const allowedExts = ["jpg", "jpeg", "png", "gif", "webp", "tiff", "tif", "bmp", "pdf"];
-  const ext = allowedExts.includes(rawExt) ? rawExt : "jpg";
-  const name = `capture_${Date.now()}.${ext}`;
-  const path = `${dir}/${name}`;
-
-  const buffer = new Uint8Array(await file.arrayBuffer());
-  await writeFile(path, buffer, { baseDir: BaseDirectory.AppData });
-
-  const base = await appDataDir();
-  return `${base}${path}`;
+  const ext = file.type || (file.name.split(".").pop() ?? "jpg").toLowerCase();
+  const data = new Uint8Array(await file.arrayBuffer());
+  return { data, ext };
 }
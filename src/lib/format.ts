@@ -1,10 +1,25 @@
+// Amounts arrive as cents; this is how they're shown. Set from the
+// number_locale and default_currency preferences at startup.
+let money = moneyFormat("en-US", "USD");
+
+function moneyFormat(locale: string, currency: string): Intl.NumberFormat {
+  return new Intl.NumberFormat(locale, {
+    style: "currency",
+    currency,
+    currencySign: "accounting",
+  });
+}
+
+export function setMoneyFormat(locale: string, currency: string) {
+  try {
+    money = moneyFormat(locale, currency);
+  } catch {
+    // Keep the previous format if the locale or currency isn't supported.
+  }
+}
+
 export function formatCents(cents: number): string {
-  const negative = cents < 0;
-  const abs = Math.abs(cents);
-  const dollars = Math.floor(abs / 100);
-  const remainder = abs % 100;
-  const formatted = `$${dollars.toLocaleString()}.${String(remainder).padStart(2, "0")}`;
-  return negative ? `(${formatted})` : formatted;
+  return money.format(cents / 100);
 }
 
 export function formatDate(iso: string): string {
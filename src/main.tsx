@@ -13,27 +13,37 @@ import { TaxPage } from "./pages/TaxPage";
 import { InvoicesPage } from "./pages/InvoicesPage";
 import { ContactsPage } from "./pages/ContactsPage";
 import { SettingsPage } from "./pages/SettingsPage";
+import { getSettings } from "./lib/api";
+import { setMoneyFormat } from "./lib/format";
 
-createRoot(document.getElementById("root")!).render(
-  <StrictMode>
-    <ErrorBoundary>
-      <ToastProvider>
-        <BrowserRouter>
-          <Routes>
-            <Route element={<AppShell />}>
-              <Route path="/" element={<DashboardPage />} />
-              <Route path="/accounts" element={<AccountsPage />} />
-              <Route path="/transactions" element={<TransactionsPage />} />
-              <Route path="/receipts" element={<ReceiptsPage />} />
-              <Route path="/tax" element={<TaxPage />} />
-              <Route path="/invoices" element={<InvoicesPage />} />
-              <Route path="/contacts" element={<ContactsPage />} />
-              <Route path="/settings" element={<SettingsPage />} />
-              <Route path="*" element={<Navigate to="/" replace />} />
-            </Route>
-          </Routes>
-        </BrowserRouter>
-      </ToastProvider>
-    </ErrorBoundary>
-  </StrictMode>
-);
+function render() {
+  createRoot(document.getElementById("root")!).render(
+    <StrictMode>
+      <ErrorBoundary>
+        <ToastProvider>
+          <BrowserRouter>
+            <Routes>
+              <Route element={<AppShell />}>
+                <Route path="/" element={<DashboardPage />} />
+                <Route path="/accounts" element={<AccountsPage />} />
+                <Route path="/transactions" element={<TransactionsPage />} />
+                <Route path="/receipts" element={<ReceiptsPage />} />
+                <Route path="/tax" element={<TaxPage />} />
+                <Route path="/invoices" element={<InvoicesPage />} />
+                <Route path="/contacts" element={<ContactsPage />} />
+                <Route path="/settings" element={<SettingsPage />} />
+                <Route path="*" element={<Navigate to="/" replace />} />
+              </Route>
+            </Routes>
+          </BrowserRouter>
+        </ToastProvider>
+      </ErrorBoundary>
+    </StrictMode>
+  );
+}
+
+// Amounts are formatted with the saved locale, so load it before the first render.
+getSettings()
+  .then((prefs) => setMoneyFormat(prefs.number_locale, prefs.default_currency))
+  .catch(() => {})
+  .finally(render);
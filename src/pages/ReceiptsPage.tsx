@@ -4,13 +4,16 @@ import {
   getTransactions,
   approveReceipt,
   rejectReceipt,
-  ingestReceipt,
+  bulkApproveReceipts,
+  ingestReceiptBytes,
+  getReceiptImageUrl,
+  onReceiptProgress,
   type ReceiptOutput,
   type TransactionOutput,
 } from "../lib/api";
 import { formatCents, formatDate, confidenceLabel, confidenceColor } from "../lib/format";
 import { CameraCapture } from "../components/CameraCapture";
-import { writeCapturedFile } from "../lib/capture";
+import { readCapturedFile } from "../lib/capture";
 import { useToast } from "../components/Toast";
 
 export function ReceiptsPage() {
@@ -34,6 +37,33 @@ export function ReceiptsPage() {
     refresh();
   }, [refresh]);
 
+  // Intake-folder files still in the pipeline, by path.
+  const [inFlight, setInFlight] = useState<Set<string>>(new Set());
+  useEffect(() => {
+    const settle = (path: string) =>
+      setInFlight((prev) => {
+        const next = new Set(prev);
+        next.delete(path);
+        return next;
+      });
+    const unlisten = Promise.all([
+      onReceiptProgress("receipt:queued", ({ path }) =>
+        setInFlight((prev) => new Set(prev).add(path)),
+      ),
+      onReceiptProgress("receipt:done", ({ path }) => {
+        settle(path);
+        refresh();
+      }),
+      onReceiptProgress("receipt:failed", ({ path, error }) => {
+        settle(path);
+        toastRef.current("error", `${path.split("/").pop()}: ${error}`);
+      }),
+    ]);
+    return () => {
+      unlisten.then((fns) => fns.forEach((fn) => fn()));
+    };
+  }, [refresh]);
+
   async function handleApprove(id: number, transactionId?: number) {
     try {
       await approveReceipt(id, transactionId);
@@ -56,12 +86,29 @@ export function ReceiptsPage() {
     }
   }
 
+  const confident = receipts.filter((r) => !r.needs_review);
+
+  async function handleApproveConfident() {
+    try {
+      const { updated } = await bulkApproveReceipts(confident.map((r) => r.id));
+      setSelected(null);
+      refresh();
+      toast("success", `Approved ${updated.length} receipt${updated.length === 1 ? "" : "s"}`);
+    } catch (e) {
+      toast("error", String(e));
+    }
+  }
+
   async function handleCapture(file: File) {
     try {
-      const filePath = await writeCapturedFile(file);
-      await ingestReceipt(filePath);
+      const { data, ext } = await readCapturedFile(file);
+      const receipt = await ingestReceiptBytes(data, ext);
       refresh();
-      toast("success", "Receipt captured");
+      if (receipt.retake_message) {
+        toast("error", receipt.retake_message);
+      } else {
+        toast("success", "Receipt captured");
+      }
     } catch (e) {
       toast("error", String(e));
     }
@@ -71,7 +118,22 @@ export function ReceiptsPage() {
     <div className="space-y-4">
       <div className="flex items-center justify-between">
         <h2 className="text-xl font-semibold">Receipt Review Queue</h2>
-        <CameraCapture onCapture={handleCapture} />
+        <div className="flex items-center gap-3">
+          {inFlight.size > 0 && (
+            <span className="text-sm text-text-muted">
+              Processing {inFlight.size} receipt{inFlight.size === 1 ? "" : "s"}…
+            </span>
+          )}
+          {confident.length > 0 && (
+            <button
+              onClick={handleApproveConfident}
+              className="px-3 py-1.5 rounded-md text-sm font-medium text-white bg-success hover:bg-success/90 transition-colors"
+            >
+              Approve {confident.length} high-confidence
+            </button>
+          )}
+          <CameraCapture onCapture={handleCapture} />
+        </div>
       </div>
 
       {receipts.length === 0 ? (
@@ -149,6 +211,30 @@ function ReceiptDetail({
     setShowPicker(false);
   }, [receipt.id]);
 
+  const isImage = receipt.content_type.startsWith("image/");
+  const [imageUrl, setImageUrl] = useState<string | null>(null);
+
+  useEffect(() => {
+    if (!isImage) return;
+    let url: string | null = null;
+    let cancelled = false;
+    getReceiptImageUrl(receipt)
+      .then((u) => {
+        if (cancelled) {
+          URL.revokeObjectURL(u);
+          return;
+        }
+        url = u;
+        setImageUrl(u);
+      })
+      .catch(() => setImageUrl(null));
+    return () => {
+      cancelled = true;
+      setImageUrl(null);
+      if (url) URL.revokeObjectURL(url);
+    };
+  }, [receipt.id, isImage]);
+
   const filtered = useMemo(() => {
     if (!search) return transactions;
     const q = search.toLowerCase();
@@ -164,15 +250,13 @@ function ReceiptDetail({
     ? transactions.find((tx) => tx.id === linkedTxId)
     : undefined;
 
-  const isImage = receipt.attachment_path.match(/\.(png|jpg|jpeg|gif|webp)$/i);
-
   return (
     <div className="bg-surface rounded-lg border border-border overflow-hidden">
       {/* Attachment viewer */}
       <div className="bg-bg border-b border-border p-4 flex items-center justify-center min-h-[200px] md:min-h-[300px]">
-        {isImage ? (
+        {imageUrl ? (
           <img
-            src={`asset://localhost/${receipt.attachment_path}`}
+            src={imageUrl}
             alt="Receipt"
             className="max-h-[400px] object-contain rounded"
           />
@@ -192,6 +276,7 @@ function ReceiptDetail({
           <Field label="Subtotal" value={receipt.subtotal_cents != null ? formatCents(receipt.subtotal_cents) : null} />
           <Field label="Tax" value={receipt.tax_cents != null ? formatCents(receipt.tax_cents) : null} />
           <Field label="Payment" value={receipt.payment_method} />
+          <Field label="Reference" value={receipt.reference} />
         </div>
 
         <div className="flex items-center gap-2 text-sm">
@@ -1,7 +1,13 @@
 import { useEffect, useState } from "react";
 import {
   estimateQuarterlyTax,
+  getHomeOfficeWorksheet,
   getScheduleCPreview,
+  postHomeOfficeEntry,
+  saveHomeOfficeWorksheet,
+  type HomeOfficeInput,
+  type HomeOfficeWorksheet,
+  type HomeOfficeWorksheetOutput,
   type QuarterlyEstimateOutput,
   type ScheduleCPreviewOutput,
 } from "../lib/api";
@@ -12,7 +18,9 @@ export function TaxPage() {
   const [preview, setPreview] = useState<ScheduleCPreviewOutput | null>(null);
   const [error, setError] = useState<string | null>(null);
   const [loading, setLoading] = useState(true);
-  const [activeTab, setActiveTab] = useState<"estimate" | "schedule_c">("estimate");
+  const [activeTab, setActiveTab] = useState<"estimate" | "schedule_c" | "home_office">(
+    "estimate",
+  );
 
   useEffect(() => {
     Promise.all([estimateQuarterlyTax(), getScheduleCPreview()])
@@ -58,6 +66,16 @@ export function TaxPage() {
         >
           Schedule C Preview
         </button>
+        <button
+          onClick={() => setActiveTab("home_office")}
+          className={`px-4 py-2 text-sm font-medium border-b-2 transition-colors ${
+            activeTab === "home_office"
+              ? "border-primary text-primary"
+              : "border-transparent text-text-muted hover:text-foreground"
+          }`}
+        >
+          Home Office
+        </button>
       </div>
 
       {activeTab === "estimate" && estimate && (
@@ -67,6 +85,8 @@ export function TaxPage() {
       {activeTab === "schedule_c" && preview && (
         <ScheduleCCard preview={preview} />
       )}
+
+      {activeTab === "home_office" && <HomeOfficeCard />}
     </div>
   );
 }
@@ -150,7 +170,196 @@ function ScheduleCCard({ preview }: { preview: ScheduleCPreviewOutput }) {
       )}
 
       <p className="text-xs text-text-muted">
-        Meals (Line 24b) shown at 50% deductible amount. This is a preview — not a filed return.
+        Deductible amounts apply each account's and transaction's deductible share, and the meals
+        cap on Line 24b. This is a preview — not a filed return.
+      </p>
+    </div>
+  );
+}
+
+const inputClass =
+  "px-2 py-1.5 text-sm border border-border rounded-md bg-bg focus:outline-none focus:border-primary";
+
+function HomeOfficeCard() {
+  const [data, setData] = useState<HomeOfficeWorksheetOutput | null>(null);
+  const [input, setInput] = useState<HomeOfficeInput | null>(null);
+  const [saved, setSaved] = useState<HomeOfficeWorksheet | null>(null);
+  const [error, setError] = useState<string | null>(null);
+  const [busy, setBusy] = useState(false);
+
+  useEffect(() => {
+    getHomeOfficeWorksheet()
+      .then((out) => {
+        setData(out);
+        setInput(out.draft);
+        setSaved(out.saved);
+      })
+      .catch((e) => setError(String(e)));
+  }, []);
+
+  if (error && !data) {
+    return <p className="text-danger">{error}</p>;
+  }
+  if (!data || !input) {
+    return <p className="text-text-muted">Loading worksheet...</p>;
+  }
+
+  const posted = saved?.transaction_id != null;
+  const regular = input.method === "regular";
+
+  function run(action: () => Promise<HomeOfficeWorksheet>) {
+    setBusy(true);
+    setError(null);
+    action()
+      .then((worksheet) => {
+        setSaved(worksheet);
+        setInput(worksheet);
+      })
+      .catch((e) => setError(String(e)))
+      .finally(() => setBusy(false));
+  }
+
+  function setExpense(idx: number, cents: number) {
+    setInput({
+      ...input!,
+      expenses: input!.expenses.map((e, i) => (i === idx ? { ...e, amount_cents: cents } : e)),
+    });
+  }
+
+  return (
+    <div className="space-y-4">
+      <h3 className="text-lg font-medium">{data.year} Home Office Deduction</h3>
+
+      <div className="flex flex-wrap items-end gap-3">
+        <label className="text-sm">
+          <span className="block text-xs text-text-muted mb-1">Method</span>
+          <select
+            value={input.method}
+            disabled={posted}
+            onChange={(e) =>
+              setInput({ ...input, method: e.target.value as HomeOfficeInput["method"] })
+            }
+            className={inputClass}
+          >
+            <option value="simplified">
+              Simplified ({formatCents(Number(data.simplified_rate_per_sqft) * 100)}/sq ft, up to{" "}
+              {data.simplified_max_sqft} sq ft)
+            </option>
+            <option value="regular">Regular (share of home costs)</option>
+          </select>
+        </label>
+        <label className="text-sm">
+          <span className="block text-xs text-text-muted mb-1">Office sq ft</span>
+          <input
+            type="number"
+            min="0"
+            value={input.office_sqft || ""}
+            disabled={posted}
+            onChange={(e) => setInput({ ...input, office_sqft: Number(e.target.value) || 0 })}
+            className={`w-28 ${inputClass}`}
+          />
+        </label>
+        {regular && (
+          <label className="text-sm">
+            <span className="block text-xs text-text-muted mb-1">Home sq ft</span>
+            <input
+              type="number"
+              min="0"
+              value={input.home_sqft || ""}
+              disabled={posted}
+              onChange={(e) => setInput({ ...input, home_sqft: Number(e.target.value) || 0 })}
+              className={`w-28 ${inputClass}`}
+            />
+          </label>
+        )}
+      </div>
+
+      {regular && (
+        <div className="border border-border rounded-lg overflow-hidden">
+          <table className="w-full text-sm">
+            <thead>
+              <tr className="border-b border-border text-xs text-text-muted">
+                <th className="px-3 py-1.5 text-left font-normal">Home cost</th>
+                <th className="px-3 py-1.5 text-right font-normal">Year total</th>
+              </tr>
+            </thead>
+            <tbody>
+              {input.expenses.map((expense, idx) => (
+                <tr key={expense.label} className="border-b border-border last:border-0">
+                  <td className="px-3 py-2">
+                    {expense.label}
+                    {expense.account_code && (
+                      <span className="ml-2 text-xs text-text-muted">
+                        from account {expense.account_code} (personal)
+                      </span>
+                    )}
+                  </td>
+                  <td className="px-3 py-2 text-right">
+                    {expense.account_code ? (
+                      <span className="font-mono">{formatCents(expense.amount_cents)}</span>
+                    ) : (
+                      <input
+                        type="number"
+                        step="0.01"
+                        min="0"
+                        aria-label={`${expense.label} for the year`}
+                        value={expense.amount_cents ? (expense.amount_cents / 100).toFixed(2) : ""}
+                        disabled={posted}
+                        onChange={(e) => {
+                          const v = Number(e.target.value);
+                          if (!isNaN(v)) setExpense(idx, Math.round(v * 100));
+                        }}
+                        className={`w-32 text-right ${inputClass}`}
+                      />
+                    )}
+                  </td>
+                </tr>
+              ))}
+            </tbody>
+          </table>
+        </div>
+      )}
+
+      {regular && input.prior_carryover_cents > 0 && (
+        <p className="text-sm text-text-muted">
+          Carried over from last year: {formatCents(input.prior_carryover_cents)}
+        </p>
+      )}
+
+      {saved && (
+        <div className="grid grid-cols-2 md:grid-cols-4 gap-3">
+          <SummaryCard label="Tentative Profit (Line 29)" cents={saved.tentative_profit_cents} />
+          <SummaryCard label="Allowable" cents={saved.allowable_cents} />
+          <SummaryCard label="Line 30 Deduction" cents={saved.deduction_cents} />
+          <SummaryCard label="Carryover" cents={saved.carryover_cents} />
+        </div>
+      )}
+
+      {error && <p className="text-sm text-danger">{error}</p>}
+
+      <div className="flex gap-2">
+        <button
+          onClick={() => run(() => saveHomeOfficeWorksheet(data.year, input))}
+          disabled={busy || posted}
+          className="px-4 py-2 text-sm font-medium bg-primary text-white rounded-md hover:bg-primary-hover transition-colors disabled:opacity-40"
+        >
+          Calculate &amp; Save
+        </button>
+        {saved && !posted && saved.deduction_cents > 0 && (
+          <button
+            onClick={() => run(() => postHomeOfficeEntry(data.year))}
+            disabled={busy}
+            className="px-4 py-2 text-sm font-medium rounded-md border border-border disabled:opacity-40"
+          >
+            Post Journal Entry
+          </button>
+        )}
+      </div>
+
+      <p className="text-xs text-text-muted">
+        {posted
+          ? "The deduction is posted to the Home Office account on December 31. Delete that entry to change the worksheet."
+          : "The worksheet is kept for your records. Posting books the deduction to the Home Office account against owner's equity, since home costs are usually paid personally."}
       </p>
     </div>
   );
@@ -170,17 +379,27 @@ function LineTable({
   lines,
 }: {
   title: string;
-  lines: { label: string; amount_cents: number }[];
+  lines: { label: string; amount_cents: number; book_amount_cents: number }[];
 }) {
   return (
     <div>
       <h4 className="text-sm font-medium text-text-muted mb-2">{title}</h4>
       <div className="border border-border rounded-lg overflow-hidden">
         <table className="w-full text-sm">
+          <thead>
+            <tr className="border-b border-border text-xs text-text-muted">
+              <th className="px-3 py-1.5 text-left font-normal">Line</th>
+              <th className="px-3 py-1.5 text-right font-normal">Book</th>
+              <th className="px-3 py-1.5 text-right font-normal">Deductible</th>
+            </tr>
+          </thead>
           <tbody>
             {lines.map((line) => (
               <tr key={line.label} className="border-b border-border last:border-0">
                 <td className="px-3 py-2">{line.label}</td>
+                <td className="px-3 py-2 text-right font-mono text-text-muted">
+                  {formatCents(line.book_amount_cents)}
+                </td>
                 <td className="px-3 py-2 text-right font-mono">
                   {formatCents(line.amount_cents)}
                 </td>
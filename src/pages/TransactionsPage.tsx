@@ -2,6 +2,7 @@ import { useEffect, useState, useMemo } from "react";
 import {
   getTransactions,
   getAccounts,
+  capitalizeEquipment,
   createTransaction,
   type TransactionOutput,
   type Account,
@@ -224,7 +225,7 @@ function TransactionForm({
 
     setSubmitting(true);
     try {
-      await createTransaction({
+      const created = await createTransaction({
         date,
         description: description.trim(),
         lines: filledLines.map((l) => ({
@@ -233,6 +234,14 @@ function TransactionForm({
           credit_cents: l.credit_cents,
         })),
       });
+      if (
+        created.capitalize_prompt &&
+        window.confirm(
+          "This equipment purchase is over the capitalize threshold. Capitalize it as a fixed asset and depreciate it instead of expensing it now?",
+        )
+      ) {
+        await capitalizeEquipment(created.id);
+      }
       onCreated();
     } catch (err) {
       onError(String(err));